@@ -317,6 +317,7 @@ export default function WorkspaceTemplatesPage() {
         shared_network: sharedNetwork,
         tailscale_mode: tailscaleMode,
         config_profile: configProfile ?? undefined,
+        env_schema: selectedTemplate?.env_schema,
       });
       baselineRef.current = snapshot;
       setDirty(false);
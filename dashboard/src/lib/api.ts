@@ -619,8 +619,19 @@ export function streamControl(
 export type McpStatus = "connected" | "connecting" | "disconnected" | "error" | "disabled";
 export type McpScope = "global" | "workspace";
 
+export type McpAuth =
+  | { type: "bearer"; token: string }
+  | {
+      type: "oauth_client_credentials";
+      token_url: string;
+      client_id: string;
+      client_secret: string;
+      scopes?: string[];
+      audience?: string;
+    };
+
 export interface McpTransport {
-  http?: { endpoint: string; headers: Record<string, string> };
+  http?: { endpoint: string; headers: Record<string, string>; auth?: McpAuth };
   stdio?: { command: string; args: string[]; env: Record<string, string> };
 }
 
@@ -643,6 +654,11 @@ export interface McpServerState extends McpServerConfig {
   error: string | null;
   tool_calls: number;
   tool_errors: number;
+  pending_start: boolean;
+  restarts: number;
+  consecutive_failures: number;
+  last_health_check: string | null;
+  next_retry_at: string | null;
 }
 
 export interface ToolInfo {
@@ -1001,6 +1017,20 @@ export interface LibraryStatus {
   ahead: number;
   behind: number;
   modified_files: string[];
+  shallow?: boolean;
+  sparse?: boolean;
+}
+
+export interface LibraryCloneProgress {
+  remote: string;
+  phase: string;
+  percent?: number;
+  current?: number;
+  total?: number;
+  done: boolean;
+  error?: string;
+  started_at: string;
+  updated_at: string;
 }
 
 // MCP Server definition (OpenCode-aligned format)
@@ -1012,6 +1042,7 @@ export interface McpServerDef {
   // Remote (HTTP) server fields
   url?: string;
   headers?: Record<string, string>;
+  auth?: McpAuth;
   // Common
   enabled?: boolean;
 }
@@ -1158,6 +1189,122 @@ export async function pushLibrary(): Promise<void> {
   return libPost("/api/library/push", undefined, "Failed to push library");
 }
 
+// Fetch history missing from a shallow clone (omit commits for full history)
+export async function deepenLibrary(commits?: number): Promise<LibraryStatus> {
+  return libPost("/api/library/deepen", { commits }, "Failed to deepen library history");
+}
+
+// Progress of the most recent library clone (null if none has run)
+export async function getLibraryCloneProgress(): Promise<LibraryCloneProgress | null> {
+  return apiGet("/api/library/clone-progress", "Failed to fetch library clone progress");
+}
+
+export type LibraryItemType = "skill" | "command" | "agent" | "tool" | "workspace-template";
+
+export interface LibraryHistoryEntry {
+  commit: string;
+  short_commit: string;
+  author_name: string;
+  author_email: string;
+  date: string;
+  message: string;
+}
+
+export interface LibraryItemDiff {
+  path: string;
+  from: string;
+  to: string;
+  diff: string;
+}
+
+export interface LibraryRestoreResult {
+  path: string;
+  revision: string;
+  commit?: string;
+}
+
+export interface SkillTestRequest {
+  template?: string;
+  workspace_id?: string;
+  prompt?: string;
+  model?: string;
+  expect?: string;
+  timeout_secs?: number;
+}
+
+export interface SkillTestStep {
+  command: string;
+  exit_code?: number;
+  skipped: boolean;
+  output: string;
+}
+
+export interface SkillTestReport {
+  skill: string;
+  target?: string;
+  passed: boolean;
+  setup: SkillTestStep[];
+  smoke_test?: {
+    model: string;
+    prompt: string;
+    response?: string;
+    passed: boolean;
+    error?: string;
+  };
+  errors: string[];
+  duration_ms: number;
+}
+
+// Dry-run a skill's setup commands (and optional smoke-test prompt) in a throwaway container
+export async function testSkill(name: string, req: SkillTestRequest): Promise<SkillTestReport> {
+  return libPost(
+    `/api/library/skills/${encodeURIComponent(name)}/test`,
+    req,
+    "Failed to test skill"
+  );
+}
+
+// Commits that touched a library item, newest first
+export async function getLibraryItemHistory(
+  itemType: LibraryItemType,
+  name: string,
+  limit?: number
+): Promise<LibraryHistoryEntry[]> {
+  const query = limit ? `?limit=${limit}` : "";
+  return libGet(
+    `/api/library/history/${itemType}/${encodeURIComponent(name)}${query}`,
+    "Failed to fetch item history"
+  );
+}
+
+// Diff a library item between two revisions (`to` defaults to HEAD)
+export async function getLibraryItemDiff(
+  itemType: LibraryItemType,
+  name: string,
+  from: string,
+  to?: string
+): Promise<LibraryItemDiff> {
+  const params = new URLSearchParams({ from });
+  if (to) params.set("to", to);
+  return libGet(
+    `/api/library/diff/${itemType}/${encodeURIComponent(name)}?${params}`,
+    "Failed to fetch item diff"
+  );
+}
+
+// Restore a library item to a prior revision (recorded as a new commit)
+export async function restoreLibraryItem(
+  itemType: LibraryItemType,
+  name: string,
+  revision: string
+): Promise<LibraryRestoreResult> {
+  return libPost(
+    `/api/library/restore/${itemType}/${encodeURIComponent(name)}`,
+    { revision },
+    "Failed to restore item"
+  );
+}
+
 // Get MCP servers
 export async function getLibraryMcps(): Promise<Record<string, McpServerDef>> {
   return libGet("/api/library/mcps", "Failed to fetch MCPs");
@@ -1374,6 +1521,13 @@ export interface WorkspaceTemplateSummary {
 
 export type TailscaleMode = "exit_node" | "tailnet_only";
 
+export interface EgressPolicy {
+  mode: "open" | "allowlist" | "deny_all";
+  allow_domains?: string[];
+  allow_cidrs?: string[];
+  proxy?: string | null;
+}
+
 export interface WorkspaceTemplate {
   name: string;
   description?: string;
@@ -1386,7 +1540,30 @@ export interface WorkspaceTemplate {
   init_script: string;
   shared_network?: boolean | null;
   tailscale_mode?: TailscaleMode | null;
+  egress_policy?: EgressPolicy | null;
   config_profile?: string;
+  env_schema?: TemplateEnvVar[];
+}
+
+export interface TemplateEnvVar {
+  name: string;
+  description?: string | null;
+  required: boolean;
+  default?: string | null;
+  pattern?: string | null;
+}
+
+export interface EnvVarIssue {
+  name: string;
+  kind: "missing" | "invalid";
+  description?: string | null;
+  message: string;
+}
+
+export interface CheckTemplateEnvResponse {
+  valid: boolean;
+  issues: EnvVarIssue[];
+  env_schema: TemplateEnvVar[];
 }
 
 export async function listWorkspaceTemplates(): Promise<WorkspaceTemplateSummary[]> {
@@ -1409,12 +1586,25 @@ export async function saveWorkspaceTemplate(
     init_script?: string;
     shared_network?: boolean | null;
     tailscale_mode?: TailscaleMode | null;
+    egress_policy?: EgressPolicy | null;
     config_profile?: string;
+    env_schema?: TemplateEnvVar[];
   }
 ): Promise<void> {
   return libPut(`/api/library/workspace-template/${encodeURIComponent(name)}`, data, "Failed to save workspace template");
 }
 
+export async function checkWorkspaceTemplateEnv(
+  name: string,
+  envVars: Record<string, string> = {}
+): Promise<CheckTemplateEnvResponse> {
+  return libPost(
+    `/api/library/workspace-template/${encodeURIComponent(name)}/check-env`,
+    { env_vars: envVars },
+    "Failed to check template environment"
+  );
+}
+
 export async function deleteWorkspaceTemplate(name: string): Promise<void> {
   return libDel(`/api/library/workspace-template/${encodeURIComponent(name)}`, "Failed to delete workspace template");
 }
@@ -1433,7 +1623,9 @@ export async function renameWorkspaceTemplate(oldName: string, newName: string):
     init_script: template.init_script,
     shared_network: template.shared_network,
     tailscale_mode: template.tailscale_mode,
+    egress_policy: template.egress_policy,
     config_profile: template.config_profile,
+    env_schema: template.env_schema,
   });
   // Delete old template
   await deleteWorkspaceTemplate(oldName);
@@ -1443,10 +1635,28 @@ export async function renameWorkspaceTemplate(oldName: string, newName: string):
 // Init Scripts
 // ─────────────────────────────────────────────────────────────────────────────
 
+export interface InitScriptMeta {
+  after?: string[];
+  provides?: string[];
+  idempotent: boolean;
+  parallel: boolean;
+}
+
 export interface InitScriptSummary {
   name: string;
   description?: string | null;
   path: string;
+  meta?: InitScriptMeta;
+}
+
+export interface InitScriptStep {
+  fragments: string[];
+  parallel: boolean;
+}
+
+export interface InitScriptPlan {
+  steps: InitScriptStep[];
+  warnings?: string[];
 }
 
 export interface InitScript extends InitScriptSummary {
@@ -1469,6 +1679,10 @@ export async function deleteInitScript(name: string): Promise<void> {
   return libDel(`/api/library/init-script/${encodeURIComponent(name)}`, "Failed to delete init script");
 }
 
+export async function planInitScripts(fragments: string[]): Promise<InitScriptPlan> {
+  return libPost("/api/library/init-script-plan", { fragments }, "Failed to plan init scripts");
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Library Rename
 // ─────────────────────────────────────────────────────────────────────────────
@@ -2362,6 +2576,38 @@ export async function uninstallSystemComponent(
   );
 }
 
+// ============================================
+// Shared Package Caches API
+// ============================================
+
+export type PackageCacheKind = 'cargo' | 'npm' | 'pip';
+
+export interface PackageCacheStats {
+  kind: PackageCacheKind;
+  path: string;
+  enabled: boolean;
+  size_bytes: number;
+  files: number;
+  last_modified: string | null;
+}
+
+export interface PackageCachePurgeResult {
+  kind: PackageCacheKind;
+  freed_bytes: number;
+}
+
+// Get size and usage of the shared package manager caches
+export async function getPackageCaches(): Promise<PackageCacheStats[]> {
+  return apiGet('/api/system/package-caches', 'Failed to get package caches');
+}
+
+// Purge one shared package cache, or all of them
+export async function purgePackageCache(
+  kind: PackageCacheKind | 'all'
+): Promise<PackageCachePurgeResult[]> {
+  return apiDel(`/api/system/package-caches/${kind}`, 'Failed to purge package cache');
+}
+
 // ============================================
 // Global Settings API
 // ============================================
@@ -39,6 +39,11 @@ export {
   type StoredEvent,
   type CreateMissionOptions,
   type RunningMissionInfo,
+  type CompactMissionOptions,
+  type CompactMissionResponse,
+  type MissionMessageResponse,
+  type MissionBrief,
+  type MissionDraft,
   listMissions,
   getMission,
   getMissionEvents,
@@ -52,6 +57,10 @@ export {
   deleteMission,
   cleanupEmptyMissions,
   resumeMission,
+  compactMission,
+  sendMissionMessage,
+  draftMission,
+  acceptMissionDraft,
 } from "./missions";
 
 // Workspaces
@@ -63,6 +72,12 @@ export {
   CONTAINER_DISTROS,
   type WorkspaceDebugInfo,
   type InitLogResponse,
+  type InitFragmentStatus,
+  type InitFragmentResult,
+  type InitScriptReport,
+  type BuildLogResponse,
+  type WorkspaceMcpStatus,
+  type McpStatusResponse,
   listWorkspaces,
   getWorkspace,
   createWorkspace,
@@ -72,6 +87,8 @@ export {
   buildWorkspace,
   getWorkspaceDebug,
   getWorkspaceInitLog,
+  getWorkspaceBuildLog,
+  getWorkspaceMcpStatus,
 } from "./workspaces";
 
 // Providers
@@ -68,6 +68,50 @@ export interface CreateMissionOptions {
   backend?: string;
 }
 
+export interface MissionBrief {
+  title: string;
+  goal: string;
+  constraints: string[];
+  acceptance_criteria: string[];
+}
+
+export interface MissionDraft {
+  brief: MissionBrief;
+  /** First message of the mission, rendered from the brief */
+  prompt: string;
+  workspace?: { id: string; name: string };
+  agent?: { name: string; description?: string };
+  method: "model" | "fallback";
+  warning?: string;
+}
+
+/** Turn a rough prompt into a mission brief with workspace/agent suggestions. */
+export async function draftMission(
+  prompt: string,
+  model?: string
+): Promise<MissionDraft> {
+  return apiPost(
+    "/api/control/missions/draft",
+    { prompt, model },
+    "Failed to draft mission"
+  );
+}
+
+/** Create the mission described by a draft and send its brief as the first message. */
+export async function acceptMissionDraft(
+  draft: MissionDraft,
+  options?: CreateMissionOptions
+): Promise<Mission> {
+  const mission = await createMission({
+    title: draft.brief.title,
+    workspaceId: draft.workspace?.id,
+    agent: draft.agent?.name,
+    ...options,
+  });
+  await sendMissionMessage(mission.id, draft.prompt);
+  return mission;
+}
+
 export interface RunningMissionInfo {
   mission_id: string;
   state: "queued" | "running" | "waiting_for_tool" | "finished";
@@ -225,6 +269,59 @@ export async function resumeMission(
   return res.json();
 }
 
+export interface MissionMessageResponse {
+  id: string;
+  mission_id: string;
+  /** True when the message waits behind a running turn */
+  queued: boolean;
+}
+
+/** Send a follow-up message to a mission, reviving it if it already finished. */
+export async function sendMissionMessage(
+  id: string,
+  content: string,
+  agent?: string
+): Promise<MissionMessageResponse> {
+  return apiPost(
+    `/api/control/missions/${id}/messages`,
+    { content, agent },
+    "Failed to send mission message"
+  );
+}
+
+export interface CompactMissionOptions {
+  /** Most recent history entries kept verbatim (default 6) */
+  keep_recent?: number;
+  /** Model or chain that writes the summary (default builtin/cheap) */
+  model?: string;
+  /** Summarize without calling a model */
+  extractive?: boolean;
+}
+
+export interface CompactMissionResponse {
+  mission_id: string;
+  compacted_entries: number;
+  kept_entries: number;
+  chars_before: number;
+  chars_after: number;
+  method: "model" | "extractive";
+  summary: string;
+  warning?: string;
+  history: MissionHistoryEntry[];
+}
+
+/** Fold older history into a summary entry to shrink future prompts. */
+export async function compactMission(
+  id: string,
+  options: CompactMissionOptions = {}
+): Promise<CompactMissionResponse> {
+  return apiPost(
+    `/api/control/missions/${id}/compact`,
+    options,
+    "Failed to compact mission"
+  );
+}
+
 // ---------------------------------------------------------------------------
 // Title management
 // ---------------------------------------------------------------------------
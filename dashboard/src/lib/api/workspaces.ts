@@ -12,6 +12,13 @@ export type WorkspaceType = "host" | "container";
 export type WorkspaceStatus = "pending" | "building" | "ready" | "error";
 export type TailscaleMode = "exit_node" | "tailnet_only";
 
+export interface EgressPolicy {
+  mode: "open" | "allowlist" | "deny_all";
+  allow_domains?: string[];
+  allow_cidrs?: string[];
+  proxy?: string | null;
+}
+
 export interface Workspace {
   id: string;
   name: string;
@@ -28,7 +35,9 @@ export interface Workspace {
   init_script?: string | null;
   shared_network?: boolean | null;
   tailscale_mode?: TailscaleMode | null;
+  egress_policy?: EgressPolicy | null;
   config_profile?: string | null;
+  init_report?: InitScriptReport | null;
 }
 
 export type ContainerDistro =
@@ -66,6 +75,53 @@ export interface InitLogResponse {
   log_path: string;
 }
 
+export type InitFragmentStatus = "succeeded" | "failed" | "interrupted" | "skipped";
+
+export interface InitFragmentResult {
+  kind: "fragment" | "skill-setup" | "custom";
+  name: string;
+  status: InitFragmentStatus;
+  exit_code?: number | null;
+  duration_ms: number;
+  output: string;
+}
+
+export interface InitScriptReport {
+  started_at: string;
+  finished_at: string;
+  exit_code?: number | null;
+  fragments: InitFragmentResult[];
+}
+
+export interface BuildLogResponse {
+  status: WorkspaceStatus;
+  error_message: string | null;
+  init_report: InitScriptReport | null;
+  failed_fragments: InitFragmentResult[];
+  summary: string | null;
+}
+
+export interface WorkspaceMcpStatus {
+  id: string;
+  name: string;
+  transport: "stdio" | "http";
+  status: "connected" | "disconnected" | "error" | "disabled";
+  error: string | null;
+  version: string | null;
+  tool_count: number;
+  pending_start: boolean;
+  restarts: number;
+  consecutive_failures: number;
+  last_connected_at: string | null;
+  last_health_check: string | null;
+  next_retry_at: string | null;
+}
+
+export interface McpStatusResponse {
+  servers: WorkspaceMcpStatus[];
+  missing: string[];
+}
+
 // ---------------------------------------------------------------------------
 // API Functions
 // ---------------------------------------------------------------------------
@@ -90,6 +146,7 @@ export async function createWorkspace(data: {
   init_script?: string;
   shared_network?: boolean | null;
   tailscale_mode?: TailscaleMode | null;
+  egress_policy?: EgressPolicy | null;
   config_profile?: string | null;
 }): Promise<Workspace> {
   return apiPost("/api/workspaces", data, "Failed to create workspace");
@@ -107,6 +164,8 @@ export async function updateWorkspace(
     init_script?: string | null;
     shared_network?: boolean | null;
     tailscale_mode?: TailscaleMode | null;
+    egress_policy?: EgressPolicy | null;
+  egress_policy?: EgressPolicy | null;
     config_profile?: string | null;
   }
 ): Promise<Workspace> {
@@ -155,3 +214,11 @@ export async function getWorkspaceInitLog(id: string): Promise<InitLogResponse>
   }
   return res.json();
 }
+
+export async function getWorkspaceBuildLog(id: string): Promise<BuildLogResponse> {
+  return apiGet(`/api/workspaces/${id}/build-log`, "Failed to get build log");
+}
+
+export async function getWorkspaceMcpStatus(id: string): Promise<McpStatusResponse> {
+  return apiGet(`/api/workspaces/${id}/mcp-status`, "Failed to get MCP status");
+}
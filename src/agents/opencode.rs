@@ -118,6 +118,7 @@ impl OpenCodeAgent {
             OpenCodeEvent::MessageComplete { .. } => return, // Don't forward completion marker
             OpenCodeEvent::TurnSummary { .. } => return,     // Summary is handled elsewhere
             OpenCodeEvent::Usage { .. } => return,           // Usage tracked at runner level
+            OpenCodeEvent::KilledProcesses { .. } => return, // OpenCode runs no local processes
         };
 
         match events_tx.send(agent_event) {
@@ -725,6 +726,7 @@ impl Agent for OpenCodeAgent {
                 "session_id": session.id,
             })),
             terminal_reason: Some(TerminalReason::Completed),
+            chat_options: None,
         }
     }
 }
@@ -812,6 +814,7 @@ impl OpenCodeAgent {
                 "session_id": session_id,
             })),
             terminal_reason: Some(TerminalReason::Completed),
+            chat_options: None,
         }
     }
 }
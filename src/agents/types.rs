@@ -56,7 +56,9 @@ impl AgentType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, schemars::JsonSchema,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum CostSource {
     Actual,
@@ -95,6 +97,10 @@ pub struct AgentResult {
 
     /// Reason why execution terminated (if not successful completion)
     pub terminal_reason: Option<TerminalReason>,
+
+    /// Effective sampling options used for this turn (if any were configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_options: Option<crate::chat_options::ChatOptions>,
 }
 
 impl AgentResult {
@@ -109,6 +115,7 @@ impl AgentResult {
             model_used: None,
             data: None,
             terminal_reason: None,
+            chat_options: None,
         }
     }
 
@@ -123,6 +130,7 @@ impl AgentResult {
             model_used: None,
             data: None,
             terminal_reason: None,
+            chat_options: None,
         }
     }
 
@@ -155,6 +163,12 @@ impl AgentResult {
         self.terminal_reason = Some(reason);
         self
     }
+
+    /// Record the effective sampling options for the turn.
+    pub fn with_chat_options(mut self, options: crate::chat_options::ChatOptions) -> Self {
+        self.chat_options = (!options.is_empty()).then_some(options);
+        self
+    }
 }
 
 /// Reason why agent execution terminated.
@@ -106,6 +106,7 @@ pub enum ProviderType {
     ZaiCodingPlan,
     Minimax,
     Amp,
+    Ollama,
     Custom,
 }
 
@@ -131,6 +132,7 @@ impl ProviderType {
             Self::ZaiCodingPlan => "Z.AI Coding Plan",
             Self::Minimax => "Minimax",
             Self::Amp => "Amp",
+            Self::Ollama => "Ollama",
             Self::Custom => "Custom",
         }
     }
@@ -156,6 +158,7 @@ impl ProviderType {
             Self::ZaiCodingPlan => "zai-coding-plan",
             Self::Minimax => "minimax",
             Self::Amp => "amp",
+            Self::Ollama => "ollama",
             Self::Custom => "custom",
         }
     }
@@ -182,6 +185,7 @@ impl ProviderType {
             "zai-coding-plan" => Some(Self::ZaiCodingPlan),
             "minimax" => Some(Self::Minimax),
             "amp" => Some(Self::Amp),
+            "ollama" => Some(Self::Ollama),
             "custom" => Some(Self::Custom),
             _ => None,
         }
@@ -208,6 +212,7 @@ impl ProviderType {
             Self::ZaiCodingPlan => Some("ZHIPU_API_KEY"),
             Self::Minimax => Some("MINIMAX_API_KEY"),
             Self::Amp => Some("AMP_API_KEY"),
+            Self::Ollama => None,
             Self::Custom => None,
         }
     }
@@ -384,11 +389,12 @@ impl AIProvider {
     }
 
     /// Check if this provider has valid credentials configured.
-    /// Custom providers may not require credentials.
+    /// Custom providers may not require credentials; local Ollama servers never do.
     pub fn has_credentials(&self) -> bool {
         self.api_key.is_some()
             || self.oauth.is_some()
             || (self.provider_type == ProviderType::Custom && self.base_url.is_some())
+            || self.provider_type == ProviderType::Ollama
     }
 
     /// Check if this provider has OAuth credentials.
@@ -0,0 +1,282 @@
+//! Approval queue for guarded tool calls.
+//!
+//! Tools that must not run on the model's say-so alone (policy rules with
+//! `require_approval`, `terraform_plan` applies, `git_push` under a confirm
+//! push policy) post
+//! the call to `POST /api/approvals/:mission_id` (the mission's token, see
+//! [`crate::tools::guard::request_approval`]). The request is held until an
+//! admin decides with `POST /api/control/approvals/:id/{approve,deny}`, or
+//! [`APPROVAL_TIMEOUT`] passes. Pending requests are listed at
+//! `GET /api/control/approvals` and announced on the spending alerts webhook
+//! when one is configured.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::mission_guard::authorize;
+use super::mission_store::now_string;
+use super::notifier::{self, mission_link, Notification, NotificationLink};
+use crate::tools::guard::ApprovalOutcome;
+
+/// How long a request waits for a decision.
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// A tool call waiting for approval.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: Uuid,
+    pub mission_id: Uuid,
+    pub tool: String,
+    pub args: Value,
+    /// Why the call needs approval, shown to the approver
+    pub reason: String,
+    pub requested_at: String,
+}
+
+struct Pending {
+    request: ApprovalRequest,
+    decide: oneshot::Sender<ApprovalOutcome>,
+}
+
+static PENDING: LazyLock<Mutex<HashMap<Uuid, Pending>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn with_pending<T>(f: impl FnOnce(&mut HashMap<Uuid, Pending>) -> T) -> T {
+    f(&mut PENDING.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalCall {
+    pub tool: String,
+    #[serde(default)]
+    pub args: Value,
+    #[serde(default)]
+    pub reason: String,
+}
+
+async fn announce(request: &ApprovalRequest) {
+    let settings = crate::settings::spending_alert_settings_cached();
+    let Some(url) = settings.webhook_url.as_deref() else {
+        return;
+    };
+    let notification = Notification {
+        kind: "tool_approval_needed".to_string(),
+        title: format!("{} needs approval", request.tool),
+        text: format!(
+            "A mission wants to call `{}`.\nReason: {}",
+            request.tool, request.reason
+        ),
+        links: vec![
+            NotificationLink {
+                label: "Open mission".to_string(),
+                url: mission_link(settings.dashboard_url.as_deref(), request.mission_id),
+            },
+            NotificationLink {
+                label: "Approve".to_string(),
+                url: format!("/api/control/approvals/{}/approve", request.id),
+            },
+        ],
+        data: json!({ "request": request }),
+    };
+    if let Err(e) = notifier::send(url, &notification).await {
+        tracing::warn!(request_id = %request.id, error = %e, "Failed to announce approval request");
+    }
+}
+
+/// Hold `request` until it is decided or expires.
+async fn wait_for_decision(request: ApprovalRequest, timeout: Duration) -> ApprovalOutcome {
+    let id = request.id;
+    let (decide, decision) = oneshot::channel();
+    with_pending(|pending| {
+        pending.insert(
+            id,
+            Pending {
+                request: request.clone(),
+                decide,
+            },
+        )
+    });
+    announce(&request).await;
+    let outcome = tokio::time::timeout(timeout, decision).await;
+    with_pending(|pending| pending.remove(&id));
+    let outcome = outcome
+        .ok()
+        .and_then(Result::ok)
+        .unwrap_or(ApprovalOutcome::Expired);
+    tracing::info!(
+        mission_id = %request.mission_id,
+        request_id = %id,
+        tool = %request.tool,
+        outcome = ?outcome,
+        "Tool approval request decided"
+    );
+    outcome
+}
+
+/// POST /api/approvals/:mission_id - a guarded tool call from workspace-mcp.
+/// Answers once the call was decided.
+pub async fn post_approval(
+    Path(mission_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(call): Json<ApprovalCall>,
+) -> Result<Json<ApprovalOutcome>, StatusCode> {
+    authorize(mission_id, &headers)?;
+    let request = ApprovalRequest {
+        id: Uuid::new_v4(),
+        mission_id,
+        tool: call.tool,
+        args: call.args,
+        reason: call.reason,
+        requested_at: now_string(),
+    };
+    tracing::warn!(
+        mission_id = %mission_id,
+        request_id = %request.id,
+        tool = %request.tool,
+        "Tool call waiting for approval"
+    );
+    Ok(Json(wait_for_decision(request, APPROVAL_TIMEOUT).await))
+}
+
+/// The oldest call of a mission that is waiting for approval.
+pub fn oldest_pending(mission_id: Uuid) -> Option<ApprovalRequest> {
+    with_pending(|pending| {
+        pending
+            .values()
+            .map(|p| &p.request)
+            .filter(|r| r.mission_id == mission_id)
+            .min_by(|a, b| a.requested_at.cmp(&b.requested_at))
+            .cloned()
+    })
+}
+
+/// GET /api/control/approvals - Calls waiting for approval.
+pub async fn list_pending(Extension(_user): Extension<AuthUser>) -> Json<Vec<ApprovalRequest>> {
+    let mut requests: Vec<ApprovalRequest> =
+        with_pending(|pending| pending.values().map(|p| p.request.clone()).collect());
+    requests.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+    Json(requests)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DenyRequest {
+    pub reason: Option<String>,
+}
+
+fn decide(id: Uuid, outcome: ApprovalOutcome) -> Result<StatusCode, (StatusCode, String)> {
+    let pending = with_pending(|pending| pending.remove(&id)).ok_or((
+        StatusCode::NOT_FOUND,
+        "No pending approval request with this ID".to_string(),
+    ))?;
+    pending.decide.send(outcome).map_err(|_| {
+        (
+            StatusCode::GONE,
+            "The request was abandoned by the mission".to_string(),
+        )
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/control/approvals/:id/approve - Let a pending call run (admin).
+pub async fn approve_request(
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    decide(
+        id,
+        ApprovalOutcome::Approved {
+            actor: user.username,
+        },
+    )
+}
+
+/// POST /api/control/approvals/:id/deny - Refuse a pending call (admin).
+pub async fn deny_request(
+    Extension(_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    body: Option<Json<DenyRequest>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    decide(
+        id,
+        ApprovalOutcome::Denied {
+            reason: body.and_then(|Json(b)| b.reason),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(tool: &str) -> ApprovalRequest {
+        ApprovalRequest {
+            id: Uuid::new_v4(),
+            mission_id: Uuid::new_v4(),
+            tool: tool.to_string(),
+            args: json!({ "path": "infra" }),
+            reason: "apply changes".to_string(),
+            requested_at: now_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn decisions_reach_the_waiting_call() {
+        let pending = request("terraform_plan");
+        let id = pending.id;
+        let waiting = tokio::spawn(wait_for_decision(pending, Duration::from_secs(5)));
+        while !with_pending(|p| p.contains_key(&id)) {
+            tokio::task::yield_now().await;
+        }
+        let Json(listed) = list_pending(Extension(AuthUser {
+            id: "admin".to_string(),
+            username: "admin".to_string(),
+            role: crate::config::Role::Admin,
+        }))
+        .await;
+        assert!(listed.iter().any(|r| r.id == id));
+        let mission_id = listed.iter().find(|r| r.id == id).unwrap().mission_id;
+        assert_eq!(oldest_pending(mission_id).map(|r| r.id), Some(id));
+        assert!(oldest_pending(Uuid::new_v4()).is_none());
+
+        decide(
+            id,
+            ApprovalOutcome::Approved {
+                actor: "admin".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            waiting.await.unwrap(),
+            ApprovalOutcome::Approved {
+                actor: "admin".to_string()
+            }
+        );
+        assert_eq!(
+            decide(id, ApprovalOutcome::Denied { reason: None })
+                .unwrap_err()
+                .0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn undecided_requests_expire() {
+        let pending = request("git_push");
+        let id = pending.id;
+        let outcome = wait_for_decision(pending, Duration::from_millis(20)).await;
+        assert_eq!(outcome, ApprovalOutcome::Expired);
+        assert!(!with_pending(|p| p.contains_key(&id)));
+    }
+}
@@ -3,6 +3,9 @@
 //! - Dashboard submits a password to `/api/auth/login`
 //! - Server returns a JWT valid for ~30 days
 //! - When `DEV_MODE=false`, all API endpoints require `Authorization: Bearer <jwt>`
+//! - With `OIDC_*` configured, the dashboard can instead sign in through corporate
+//!   SSO (see [`super::oidc`]), receiving a short-lived session JWT plus a refresh token
+//! - OIDC users carry a [`Role`]; password logins are always `admin`
 //!
 //! # Security notes
 //! - This is intentionally minimal; it is NOT multi-tenant and does not implement RLS.
@@ -21,7 +24,7 @@ use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
 
 use super::routes::AppState;
 use super::types::{LoginRequest, LoginResponse};
-use crate::config::{AuthMode, Config, UserAccount};
+use crate::config::{AuthMode, Config, Role, UserAccount};
 use crate::util::internal_error;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -35,12 +38,19 @@ struct Claims {
     iat: i64,
     /// Expiration unix seconds
     exp: i64,
+    /// Role granted to the user (absent for password logins, which are admins)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<Role>,
+    /// OIDC session ID (only set for SSO logins)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sid: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub id: String,
     pub username: String,
+    pub role: Role,
 }
 
 pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
@@ -113,13 +123,34 @@ pub fn verify_password_hash(password: &str, stored: &str) -> bool {
 }
 
 fn issue_jwt(secret: &str, ttl_days: i64, user: &AuthUser) -> anyhow::Result<(String, i64)> {
+    encode_jwt(secret, Duration::days(ttl_days.max(1)), user, None)
+}
+
+/// Issue a short-lived session JWT for an OIDC login.
+pub(crate) fn issue_session_jwt(
+    secret: &str,
+    ttl: Duration,
+    user: &AuthUser,
+    session_id: &str,
+) -> anyhow::Result<(String, i64)> {
+    encode_jwt(secret, ttl, user, Some(session_id.to_string()))
+}
+
+fn encode_jwt(
+    secret: &str,
+    ttl: Duration,
+    user: &AuthUser,
+    sid: Option<String>,
+) -> anyhow::Result<(String, i64)> {
     let now = Utc::now();
-    let exp = now + Duration::days(ttl_days.max(1));
+    let exp = now + ttl;
     let claims = Claims {
         sub: user.id.clone(),
         usr: user.username.clone(),
         iat: now.timestamp(),
         exp: exp.timestamp(),
+        role: sid.as_ref().map(|_| user.role),
+        sid,
     };
     let token = jsonwebtoken::encode(
         &Header::default(),
@@ -142,8 +173,8 @@ fn verify_jwt(token: &str, secret: &str) -> anyhow::Result<Claims> {
 /// Verify a JWT against the server config.
 /// Returns true iff:
 /// - auth is not required (dev mode), OR
-/// - auth is required and the token is valid.
-pub fn verify_token_for_config(token: &str, config: &Config) -> bool {
+/// - auth is required, the token is valid and its role is at least `min_role`.
+pub fn verify_token_for_config(token: &str, config: &Config, min_role: Role) -> bool {
     if !config.auth.auth_required(config.dev_mode) {
         return true;
     }
@@ -154,6 +185,9 @@ pub fn verify_token_for_config(token: &str, config: &Config) -> bool {
     let Ok(claims) = verify_jwt(token, secret) else {
         return false;
     };
+    if claims.sid.is_some() {
+        return config.auth.oidc.is_some() && claims.role.unwrap_or(Role::Viewer) >= min_role;
+    }
     match config.auth.auth_mode(config.dev_mode) {
         AuthMode::MultiUser => user_for_claims(&claims, &config.auth.users).is_some(),
         AuthMode::SingleTenant => true,
@@ -208,6 +242,7 @@ pub async fn login(
             AuthUser {
                 id: effective_id,
                 username: account.username.clone(),
+                role: Role::Admin,
             }
         }
         AuthMode::SingleTenant | AuthMode::Disabled => {
@@ -245,6 +280,7 @@ pub async fn login(
             AuthUser {
                 id: "default".to_string(),
                 username: "default".to_string(),
+                role: Role::Admin,
             }
         }
     };
@@ -259,7 +295,11 @@ pub async fn login(
     let (token, exp) =
         issue_jwt(secret, state.config.auth.jwt_ttl_days, &user).map_err(internal_error)?;
 
-    Ok(Json(LoginResponse { token, exp }))
+    Ok(Json(LoginResponse {
+        token,
+        exp,
+        refresh_token: None,
+    }))
 }
 
 pub async fn require_auth(
@@ -272,6 +312,7 @@ pub async fn require_auth(
         req.extensions_mut().insert(AuthUser {
             id: "dev".to_string(),
             username: "dev".to_string(),
+            role: Role::Admin,
         });
         return next.run(req).await;
     }
@@ -305,22 +346,49 @@ pub async fn require_auth(
 
     match verify_jwt(token, secret) {
         Ok(claims) => {
-            let user = match state.config.auth.auth_mode(state.config.dev_mode) {
-                AuthMode::MultiUser => match user_for_claims(&claims, &state.config.auth.users) {
-                    Some(u) => u,
-                    None => {
-                        return (StatusCode::UNAUTHORIZED, "Invalid user").into_response();
-                    }
-                },
-                AuthMode::SingleTenant => AuthUser {
+            let user = if claims.sid.is_some() {
+                // OIDC session tokens are self-contained: the identity provider
+                // vouched for the user when the session was created or refreshed.
+                if state.config.auth.oidc.is_none() {
+                    return (StatusCode::UNAUTHORIZED, "SSO is not configured").into_response();
+                }
+                AuthUser {
                     id: claims.sub,
                     username: claims.usr,
-                },
-                AuthMode::Disabled => AuthUser {
-                    id: "default".to_string(),
-                    username: "default".to_string(),
-                },
+                    role: claims.role.unwrap_or(Role::Viewer),
+                }
+            } else {
+                match state.config.auth.auth_mode(state.config.dev_mode) {
+                    AuthMode::MultiUser => {
+                        match user_for_claims(&claims, &state.config.auth.users) {
+                            Some(u) => u,
+                            None => {
+                                return (StatusCode::UNAUTHORIZED, "Invalid user").into_response();
+                            }
+                        }
+                    }
+                    AuthMode::SingleTenant => AuthUser {
+                        id: claims.sub,
+                        username: claims.usr,
+                        role: Role::Admin,
+                    },
+                    AuthMode::Disabled => AuthUser {
+                        id: "default".to_string(),
+                        username: "default".to_string(),
+                        role: Role::Admin,
+                    },
+                }
             };
+            if user.role < required_role(req.method(), req.uri().path()) {
+                return (
+                    StatusCode::FORBIDDEN,
+                    format!(
+                        "Role '{}' is not allowed to perform this action",
+                        user.role.as_str()
+                    ),
+                )
+                    .into_response();
+            }
             req.extensions_mut().insert(user);
             next.run(req).await
         }
@@ -328,6 +396,40 @@ pub async fn require_auth(
     }
 }
 
+/// Path prefixes whose mutating endpoints are restricted to admins.
+const ADMIN_PATH_PREFIXES: &[&str] = &[
+    "/api/settings",
+    "/api/secrets",
+    "/api/system",
+    "/api/proxy-keys",
+    "/api/backends",
+    "/api/auth/change-password",
+    "/api/control/host-exec",
+    "/api/control/approvals",
+];
+
+/// Path prefixes whose endpoints return credentials or server configuration,
+/// restricted to admins for reads as well.
+const ADMIN_READ_PATH_PREFIXES: &[&str] = &["/api/settings", "/api/secrets", "/api/proxy-keys"];
+
+/// Minimum role needed for a request: reads are open to viewers except on
+/// credential endpoints, writes need an operator, and writes to server
+/// administration endpoints need an admin.
+fn required_role(method: &axum::http::Method, path: &str) -> Role {
+    use axum::http::Method;
+    if ADMIN_READ_PATH_PREFIXES.iter().any(|p| path.starts_with(p)) {
+        return Role::Admin;
+    }
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Role::Viewer;
+    }
+    if ADMIN_PATH_PREFIXES.iter().any(|p| path.starts_with(p)) {
+        Role::Admin
+    } else {
+        Role::Operator
+    }
+}
+
 /// Returns the effective user ID (id if non-empty, otherwise username).
 fn effective_user_id(user: &UserAccount) -> String {
     if user.id.is_empty() {
@@ -344,6 +446,7 @@ fn user_for_claims(claims: &Claims, users: &[UserAccount]) -> Option<AuthUser> {
         .map(|u| AuthUser {
             id: effective_user_id(u),
             username: u.username.clone(),
+            role: Role::Admin,
         })
 }
 
@@ -355,6 +458,7 @@ pub struct AuthStatusResponse {
     pub password_source: String, // "dashboard", "environment", "none"
     pub password_changed_at: Option<String>,
     pub dev_mode: bool,
+    pub oidc_enabled: bool,
 }
 
 pub async fn auth_status(
@@ -388,6 +492,7 @@ pub async fn auth_status(
         password_source: password_source.to_string(),
         password_changed_at,
         dev_mode: state.config.dev_mode,
+        oidc_enabled: state.config.auth.oidc.is_some(),
     })
 }
 
@@ -495,3 +600,34 @@ pub async fn change_password(
         "password_changed_at": now
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Method;
+
+    #[test]
+    fn viewers_cannot_read_secrets_or_settings() {
+        for path in [
+            "/api/secrets/encryption/key",
+            "/api/secrets/registries/default",
+            "/api/settings",
+        ] {
+            assert!(Role::Viewer < required_role(&Method::GET, path), "{path}");
+            assert_eq!(required_role(&Method::GET, path), Role::Admin);
+        }
+        assert_eq!(
+            required_role(&Method::GET, "/api/control/missions"),
+            Role::Viewer
+        );
+        assert_eq!(required_role(&Method::GET, "/api/backends"), Role::Viewer);
+        assert_eq!(
+            required_role(&Method::POST, "/api/backends/x/config"),
+            Role::Admin
+        );
+        assert_eq!(
+            required_role(&Method::POST, "/api/control/message"),
+            Role::Operator
+        );
+    }
+}
@@ -9,7 +9,7 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::backend::registry::BackendInfo;
+use crate::backend::{BackendCapabilities, BackendInfo, BackendRegistry};
 
 use super::auth::AuthUser;
 use super::routes::AppState;
@@ -19,6 +19,9 @@ use super::routes::AppState;
 pub struct BackendResponse {
     pub id: String,
     pub name: String,
+    /// False for backends plugged in at startup
+    pub builtin: bool,
+    pub capabilities: BackendCapabilities,
 }
 
 impl From<BackendInfo> for BackendResponse {
@@ -26,6 +29,8 @@ impl From<BackendInfo> for BackendResponse {
         Self {
             id: info.id,
             name: info.name,
+            builtin: info.builtin,
+            capabilities: info.capabilities,
         }
     }
 }
@@ -55,10 +60,7 @@ pub async fn get_backend(
 ) -> Result<Json<BackendResponse>, (StatusCode, String)> {
     let registry = state.backend_registry.read().await;
     match registry.get(&id) {
-        Some(backend) => Ok(Json(BackendResponse {
-            id: backend.id().to_string(),
-            name: backend.name().to_string(),
-        })),
+        Some(backend) => Ok(Json(BackendRegistry::info(backend.as_ref()).into())),
         None => Err((StatusCode::NOT_FOUND, format!("Backend {} not found", id))),
     }
 }
@@ -0,0 +1,286 @@
+//! Budget buckets: cost attribution and monthly caps for shared servers.
+//!
+//! Buckets are configured in settings (`budget_buckets`). Each mission's cost
+//! is attributed to one bucket: the first bucket listing one of the mission's
+//! tags, otherwise the first bucket listing its workspace. Missions matching
+//! no bucket are reported as [`UNATTRIBUTED`].
+//!
+//! Usage is the cost of assistant messages logged in a calendar month (UTC),
+//! so a mission spanning two months is charged to both. When a bucket with a
+//! `monthly_cap_cents` has reached its cap, new missions attributed to it are
+//! refused with `402 Payment Required`; running missions are left alone.
+//!
+//! `GET /api/control/budget/buckets?month=YYYY-MM` reports per-bucket usage
+//! (default: the current month).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::control_for_user;
+use super::mission_store::{MissionCost, MissionStore};
+use super::routes::AppState;
+use crate::settings::BudgetBucket;
+
+/// Name reported for cost that matches no bucket.
+pub const UNATTRIBUTED: &str = "unattributed";
+
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LEN: usize = 64;
+
+/// Trim, lowercase and de-duplicate mission tags, rejecting invalid ones.
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        let valid = tag.len() <= MAX_TAG_LEN
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '/'));
+        if !valid {
+            return Err(format!(
+                "Invalid tag '{}' (letters, digits and - _ . : / only, at most {} characters)",
+                tag, MAX_TAG_LEN
+            ));
+        }
+        if !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    if out.len() > MAX_TAGS {
+        return Err(format!("At most {} tags are allowed", MAX_TAGS));
+    }
+    Ok(out)
+}
+
+/// The bucket a mission with `workspace_id` and `tags` is attributed to.
+pub fn attribute<'a>(
+    buckets: &'a [BudgetBucket],
+    workspace_id: Uuid,
+    tags: &[String],
+) -> Option<&'a BudgetBucket> {
+    buckets
+        .iter()
+        .find(|b| b.tags.iter().any(|t| tags.contains(t)))
+        .or_else(|| {
+            buckets
+                .iter()
+                .find(|b| b.workspaces.contains(&workspace_id))
+        })
+}
+
+/// `[start, end)` of the month `month` (`YYYY-MM`), or of the month containing `now`.
+fn month_range(
+    month: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(String, DateTime<Utc>, DateTime<Utc>), String> {
+    let first = match month {
+        Some(raw) => NaiveDate::parse_from_str(&format!("{}-01", raw.trim()), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid month '{}' (expected YYYY-MM)", raw))?,
+        None => NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap(),
+    };
+    let next = first
+        .checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| "Month out of range".to_string())?;
+    let start = Utc.from_utc_datetime(&first.and_hms_opt(0, 0, 0).unwrap());
+    let end = Utc.from_utc_datetime(&next.and_hms_opt(0, 0, 0).unwrap());
+    Ok((first.format("%Y-%m").to_string(), start, end))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BucketUsage {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_cap_cents: Option<u64>,
+    pub spent_cents: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_cents: Option<u64>,
+    pub over_cap: bool,
+    /// Missions with cost in the month.
+    pub missions: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetStatus {
+    /// Month reported (`YYYY-MM`, UTC).
+    pub month: String,
+    /// Configured buckets in order, followed by [`UNATTRIBUTED`].
+    pub buckets: Vec<BucketUsage>,
+}
+
+/// Sum `costs` per bucket.
+fn summarize(buckets: &[BudgetBucket], costs: &[MissionCost]) -> Vec<BucketUsage> {
+    let mut totals: HashMap<&str, (u64, usize)> = HashMap::new();
+    for cost in costs {
+        let name = attribute(buckets, cost.workspace_id, &cost.tags)
+            .map(|b| b.name.as_str())
+            .unwrap_or(UNATTRIBUTED);
+        let entry = totals.entry(name).or_default();
+        entry.0 = entry.0.saturating_add(cost.cost_cents);
+        entry.1 += 1;
+    }
+    let usage = |name: &str, cap: Option<u64>| {
+        let (spent_cents, missions) = totals.get(name).copied().unwrap_or_default();
+        BucketUsage {
+            name: name.to_string(),
+            monthly_cap_cents: cap,
+            spent_cents,
+            remaining_cents: cap.map(|cap| cap.saturating_sub(spent_cents)),
+            over_cap: cap.is_some_and(|cap| spent_cents >= cap),
+            missions,
+        }
+    };
+    buckets
+        .iter()
+        .map(|b| usage(&b.name, b.monthly_cap_cents))
+        .chain(std::iter::once(usage(UNATTRIBUTED, None)))
+        .collect()
+}
+
+async fn budget_status(
+    store: &Arc<dyn MissionStore>,
+    buckets: &[BudgetBucket],
+    month: Option<&str>,
+) -> Result<BudgetStatus, (StatusCode, String)> {
+    let (month, start, end) =
+        month_range(month, Utc::now()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let costs = store
+        .get_mission_costs_between(&start.to_rfc3339(), &end.to_rfc3339())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(BudgetStatus {
+        month,
+        buckets: summarize(buckets, &costs),
+    })
+}
+
+/// Refuse a new mission whose bucket has reached its monthly cap.
+pub(crate) async fn check_cap(
+    store: &Arc<dyn MissionStore>,
+    workspace_id: Option<Uuid>,
+    tags: &[String],
+) -> Result<(), (StatusCode, String)> {
+    let buckets = crate::settings::budget_buckets_cached();
+    let workspace_id = workspace_id.unwrap_or(crate::workspace::DEFAULT_WORKSPACE_ID);
+    let Some(bucket) = attribute(&buckets, workspace_id, tags) else {
+        return Ok(());
+    };
+    if bucket.monthly_cap_cents.is_none() {
+        return Ok(());
+    }
+    let status = budget_status(store, &buckets, None).await?;
+    match status.buckets.iter().find(|u| u.name == bucket.name) {
+        Some(usage) if usage.over_cap => Err((
+            StatusCode::PAYMENT_REQUIRED,
+            format!(
+                "Budget bucket '{}' has reached its monthly cap ({} of {} cents spent in {})",
+                usage.name,
+                usage.spent_cents,
+                usage.monthly_cap_cents.unwrap_or_default(),
+                status.month
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BudgetStatusQuery {
+    /// Month to report (`YYYY-MM`); defaults to the current month.
+    pub month: Option<String>,
+}
+
+/// GET /api/control/budget/buckets - usage per budget bucket for a month.
+pub async fn get_budget_status(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<BudgetStatusQuery>,
+) -> Result<Json<BudgetStatus>, (StatusCode, String)> {
+    let store = control_for_user(&state, &user).await.mission_store;
+    let buckets = crate::settings::budget_buckets_cached();
+    budget_status(&store, &buckets, query.month.as_deref())
+        .await
+        .map(Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(name: &str, cap: Option<u64>, tags: &[&str], workspaces: &[Uuid]) -> BudgetBucket {
+        BudgetBucket {
+            name: name.to_string(),
+            monthly_cap_cents: cap,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            workspaces: workspaces.to_vec(),
+        }
+    }
+
+    #[test]
+    fn normalizes_tags() {
+        let tags = vec![" Team-A ".to_string(), "team-a".to_string(), "".to_string()];
+        assert_eq!(normalize_tags(&tags).unwrap(), vec!["team-a".to_string()]);
+        assert!(normalize_tags(&["bad tag".to_string()]).is_err());
+    }
+
+    #[test]
+    fn tags_take_precedence_over_workspaces() {
+        let ws = Uuid::new_v4();
+        let buckets = vec![
+            bucket("infra", None, &[], &[ws]),
+            bucket("team-a", Some(100), &["team-a"], &[]),
+        ];
+        let tagged = vec!["team-a".to_string()];
+        assert_eq!(attribute(&buckets, ws, &tagged).unwrap().name, "team-a");
+        assert_eq!(attribute(&buckets, ws, &[]).unwrap().name, "infra");
+        assert!(attribute(&buckets, Uuid::new_v4(), &[]).is_none());
+
+        let cost = |tags: &[String], workspace_id, cost_cents| MissionCost {
+            mission_id: Uuid::new_v4(),
+            workspace_id,
+            tags: tags.to_vec(),
+            cost_cents,
+        };
+        let usage = summarize(
+            &buckets,
+            &[
+                cost(&tagged, ws, 60),
+                cost(&tagged, Uuid::new_v4(), 50),
+                cost(&[], ws, 7),
+                cost(&[], Uuid::new_v4(), 3),
+            ],
+        );
+        let names: Vec<&str> = usage.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["infra", "team-a", UNATTRIBUTED]);
+        assert_eq!(usage[0].spent_cents, 7);
+        assert_eq!(usage[1].spent_cents, 110);
+        assert_eq!(usage[1].missions, 2);
+        assert_eq!(usage[1].remaining_cents, Some(0));
+        assert!(usage[1].over_cap);
+        assert_eq!(usage[2].spent_cents, 3);
+        assert!(!usage[2].over_cap);
+    }
+
+    #[test]
+    fn month_ranges() {
+        let now = Utc.with_ymd_and_hms(2026, 12, 16, 5, 0, 0).unwrap();
+        let (month, start, end) = month_range(None, now).unwrap();
+        assert_eq!(month, "2026-12");
+        assert_eq!(start.to_rfc3339(), "2026-12-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2027-01-01T00:00:00+00:00");
+        assert_eq!(month_range(Some("2026-02"), now).unwrap().0, "2026-02");
+        assert!(month_range(Some("2026-13"), now).is_err());
+    }
+}
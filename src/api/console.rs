@@ -28,12 +28,18 @@ use uuid::Uuid;
 
 use super::auth;
 use super::routes::AppState;
+use super::workspace_terminal::AttachRecorder;
+use crate::bwrap::ContainerBackend;
+use crate::config::Role;
 use crate::nspawn;
-use crate::workspace::{use_nspawn_for_workspace, WorkspaceType};
+use crate::workspace::{use_container_rootfs, workspace_container_backend, WorkspaceType};
 
 /// How long to keep a session alive after disconnect before cleanup.
 const SESSION_POOL_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// How long an attached workspace terminal survives without connections.
+const ATTACH_DETACH_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
 /// How often to run the cleanup task.
 const CLEANUP_INTERVAL: Duration = Duration::from_secs(10);
 
@@ -58,6 +64,8 @@ struct PooledSession {
     child_killer: Arc<Mutex<Option<Box<dyn portable_pty::Child + Send>>>>,
     /// Broadcast channel for PTY output (fan-out to all websocket clients).
     from_pty_tx: broadcast::Sender<String>,
+    /// How long the session survives without connections.
+    detach_timeout: Duration,
 }
 
 /// Global session pool, keyed by a session identifier.
@@ -94,7 +102,7 @@ impl SessionPool {
                 if let Ok(s) = session.try_lock() {
                     if s.connection_count == 0 {
                         if let Some(disconnected_at) = s.disconnected_at {
-                            if now.duration_since(disconnected_at) > SESSION_POOL_TIMEOUT {
+                            if now.duration_since(disconnected_at) > s.detach_timeout {
                                 return Some(key.clone());
                             }
                         }
@@ -120,6 +128,20 @@ impl SessionPool {
     }
 }
 
+impl SessionPool {
+    /// Kill a session and remove it from the pool. Returns false if it was not found.
+    pub(crate) async fn end_session(&self, key: &str) -> bool {
+        let Some(session) = self.sessions.write().await.remove(key) else {
+            return false;
+        };
+        let s = session.lock().await;
+        if let Some(mut child) = s.child_killer.lock().await.take() {
+            let _ = child.kill();
+        }
+        true
+    }
+}
+
 impl Default for SessionPool {
     fn default() -> Self {
         Self::new()
@@ -152,7 +174,7 @@ pub async fn console_ws(
             Some(t) => t,
             None => return (StatusCode::UNAUTHORIZED, "Missing websocket JWT").into_response(),
         };
-        if !auth::verify_token_for_config(&token, &state.config) {
+        if !auth::verify_token_for_config(&token, &state.config, Role::Operator) {
             return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response();
         }
         // Use token hash as session key for authenticated users
@@ -400,6 +422,7 @@ async fn handle_new_session(mut socket: WebSocket, state: Arc<AppState>, session
         disconnected_at: None,
         connection_count: 1,
         child_killer: child_killer.clone(),
+        detach_timeout: SESSION_POOL_TIMEOUT,
     }));
 
     // Store in pool
@@ -512,7 +535,7 @@ pub async fn workspace_shell_ws(
             Some(t) => t,
             None => return (StatusCode::UNAUTHORIZED, "Missing websocket JWT").into_response(),
         };
-        if !auth::verify_token_for_config(&token, &state.config) {
+        if !auth::verify_token_for_config(&token, &state.config, Role::Operator) {
             return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response();
         }
         format!("workspace:{}:{:x}", workspace_id, md5::compute(&token))
@@ -551,8 +574,9 @@ pub async fn workspace_shell_ws(
             .into_response();
     }
 
-    ws.protocols(["sandboxed"])
-        .on_upgrade(move |socket| handle_workspace_shell(socket, state, workspace_id, session_key))
+    ws.protocols(["sandboxed"]).on_upgrade(move |socket| {
+        handle_workspace_shell(socket, state, workspace_id, session_key, None)
+    })
 }
 
 fn runtime_display_path() -> Option<PathBuf> {
@@ -670,11 +694,16 @@ async fn terminate_stale_container(machine_name: &str) {
     }
 }
 
-async fn handle_workspace_shell(
+/// Attach to the pooled workspace shell `session_key`, starting it if needed.
+///
+/// With a recorder, everything typed and printed in a newly started session
+/// is passed to it, and the session stays alive longer while detached.
+pub(crate) async fn handle_workspace_shell(
     socket: WebSocket,
     state: Arc<AppState>,
     workspace_id: Uuid,
     session_key: String,
+    recorder: Option<Arc<AttachRecorder>>,
 ) {
     tracing::info!(
         session_key = %session_key,
@@ -711,7 +740,7 @@ async fn handle_workspace_shell(
     }
 
     tracing::debug!("Creating new workspace shell session: {}", session_key);
-    handle_new_workspace_shell(socket, state, workspace_id, session_key).await;
+    handle_new_workspace_shell(socket, state, workspace_id, session_key, recorder).await;
 }
 
 async fn handle_new_workspace_shell(
@@ -719,6 +748,7 @@ async fn handle_new_workspace_shell(
     state: Arc<AppState>,
     workspace_id: Uuid,
     session_key: String,
+    recorder: Option<Arc<AttachRecorder>>,
 ) {
     // Get workspace info
     let workspace = match state.workspaces.get(workspace_id).await {
@@ -754,7 +784,53 @@ async fn handle_new_workspace_shell(
 
     // Build command based on workspace type
     let mut cmd = match workspace.workspace_type {
-        WorkspaceType::Container if use_nspawn_for_workspace(&workspace) => {
+        WorkspaceType::Container
+            if matches!(
+                workspace_container_backend(&workspace),
+                Some(ContainerBackend::Bwrap | ContainerBackend::MicroVm)
+            ) =>
+        {
+            let mut env = workspace.env_vars.clone();
+            if let Some(gpu) = crate::workspace::workspace_gpu_passthrough(&workspace) {
+                gpu.apply_env(&mut env);
+            }
+            env.insert("TERM".to_string(), "xterm-256color".to_string());
+            env.insert("WORKSPACE_ID".to_string(), workspace_id.to_string());
+            env.insert("WORKSPACE_NAME".to_string(), workspace.name.clone());
+            if let Some(display) = read_runtime_display() {
+                env.insert("DISPLAY".to_string(), display);
+            }
+            let shell = if workspace.path.join("bin/bash").exists() {
+                "/bin/bash"
+            } else {
+                "/bin/sh"
+            };
+            let args = if shell == "/bin/bash" {
+                vec!["--login".to_string(), "-i".to_string()]
+            } else {
+                vec!["-i".to_string()]
+            };
+            let exec = crate::workspace_exec::WorkspaceExec::new(workspace.clone());
+            let argv = if workspace_container_backend(&workspace) == Some(ContainerBackend::MicroVm)
+            {
+                match exec.microvm_argv(&workspace.path, shell, &args, &env).await {
+                    Ok(argv) => argv,
+                    Err(e) => {
+                        let _ = socket
+                            .send(Message::Text(format!("Failed to start microVM: {}", e)))
+                            .await;
+                        let _ = socket.close().await;
+                        return;
+                    }
+                }
+            } else {
+                exec.bwrap_argv(&workspace.path, shell, &args, &env)
+            };
+            let mut cmd = CommandBuilder::new(&argv[0]);
+            cmd.args(&argv[1..]);
+            cmd
+        }
+        WorkspaceType::Container if use_container_rootfs(&workspace) => {
             // For container workspaces, use systemd-nspawn to enter the isolated environment
             // First, terminate any stale container that might be holding the directory lock
             terminate_stale_container(&workspace.name).await;
@@ -769,6 +845,13 @@ async fn handle_new_workspace_shell(
             for arg in nspawn::tailscale_nspawn_extra_args(&workspace.env_vars) {
                 cmd.arg(arg);
             }
+            let mut env_vars = workspace.env_vars.clone();
+            if let Some(gpu) = crate::workspace::workspace_gpu_passthrough(&workspace) {
+                for arg in gpu.nspawn_args() {
+                    cmd.arg(arg);
+                }
+                gpu.apply_env(&mut env_vars);
+            }
 
             if let Some(display) = read_runtime_display() {
                 if std::path::Path::new("/tmp/.X11-unix").exists() {
@@ -780,7 +863,7 @@ async fn handle_new_workspace_shell(
             cmd.arg("--setenv=TERM=xterm-256color");
             cmd.arg(format!("--setenv=WORKSPACE_ID={}", workspace_id));
             cmd.arg(format!("--setenv=WORKSPACE_NAME={}", workspace.name));
-            for (key, value) in &workspace.env_vars {
+            for (key, value) in &env_vars {
                 if key.trim().is_empty() {
                     continue;
                 }
@@ -886,11 +969,15 @@ async fn handle_new_workspace_shell(
 
     let writer_task = {
         let master = master_for_writer;
+        let recorder = recorder.clone();
         tokio::task::spawn_blocking(move || {
             use std::io::Write;
             while let Some(msg) = to_pty_rx.blocking_recv() {
                 match msg {
                     ClientMsg::Input { d } => {
+                        if let Some(recorder) = &recorder {
+                            recorder.input(&d);
+                        }
                         let _ = writer.write_all(d.as_bytes());
                         let _ = writer.flush();
                     }
@@ -908,6 +995,7 @@ async fn handle_new_workspace_shell(
     };
 
     let from_pty_tx_reader = from_pty_tx.clone();
+    let output_recorder = recorder.clone();
     let reader_task = tokio::task::spawn_blocking(move || {
         use std::io::Read;
         let mut buf = [0u8; 8192];
@@ -916,11 +1004,18 @@ async fn handle_new_workspace_shell(
                 Ok(0) => break,
                 Ok(n) => {
                     let s = String::from_utf8_lossy(&buf[..n]).to_string();
+                    if let Some(recorder) = &output_recorder {
+                        recorder.output(&s);
+                    }
                     let _ = from_pty_tx_reader.send(s);
                 }
                 Err(_) => break,
             }
         }
+        // The shell exited or was killed.
+        if let Some(recorder) = &output_recorder {
+            recorder.finish();
+        }
     });
 
     // Create pooled session
@@ -930,6 +1025,11 @@ async fn handle_new_workspace_shell(
         disconnected_at: None,
         connection_count: 1,
         child_killer: child_killer.clone(),
+        detach_timeout: if recorder.is_some() {
+            ATTACH_DETACH_TIMEOUT
+        } else {
+            SESSION_POOL_TIMEOUT
+        },
     }));
 
     // Store in pool
@@ -0,0 +1,200 @@
+//! Recovery from context-length-exceeded errors in the model-routing proxy.
+//!
+//! When every provider in a chain rejects a request because the prompt does
+//! not fit the context window, the proxy retries it with older tool results
+//! cut down ([`TRIM_STAGES`]): first to a short excerpt, then replaced by a
+//! placeholder. If the request still overflows and a `long_context_model`
+//! chain is configured in settings, the original request is retried on that
+//! chain. Each step is reported to the mission as a `context_recovery` event.
+
+use axum::response::Response;
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::control::{AgentEvent, ControlHub};
+
+/// Trim passes as `(recent tool results kept intact, excerpt length)`.
+/// An excerpt length of 0 replaces the result with a placeholder.
+pub const TRIM_STAGES: [(usize, usize); 2] = [(2, 400), (1, 0)];
+
+/// Error messages providers use for prompts that exceed the context window.
+const OVERFLOW_MARKERS: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "context window",
+    "context length",
+    "prompt is too long",
+    "input is too long",
+    "too many tokens",
+    "exceeds the maximum number of tokens",
+    "input token count",
+];
+
+/// Response extension marking a chain that failed only on context overflow.
+#[derive(Debug, Clone, Copy)]
+pub struct ContextOverflow;
+
+/// Whether an upstream error body reports a context-length overflow.
+pub fn is_context_overflow(body: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(body).to_lowercase();
+    OVERFLOW_MARKERS.iter().any(|marker| text.contains(marker))
+}
+
+/// Whether a proxy response is a context overflow the proxy can retry.
+pub fn is_overflow_response(response: &Response) -> bool {
+    response.extensions().get::<ContextOverflow>().is_some()
+}
+
+/// What a trim pass changed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrimReport {
+    /// Tool results shortened or dropped
+    pub trimmed: usize,
+    /// Characters removed across those results
+    pub removed_chars: usize,
+}
+
+/// Shorten a tool result to `excerpt_chars`, returning the removed length.
+fn trim_text(text: &mut String, excerpt_chars: usize) -> usize {
+    let total = text.chars().count();
+    if total <= excerpt_chars {
+        return 0;
+    }
+    let removed = total - excerpt_chars;
+    *text = if excerpt_chars == 0 {
+        format!(
+            "[tool result omitted to fit the context window: {} characters]",
+            total
+        )
+    } else {
+        let excerpt: String = text.chars().take(excerpt_chars).collect();
+        format!(
+            "{}\n[... {} characters omitted to fit the context window]",
+            excerpt, removed
+        )
+    };
+    removed
+}
+
+/// Trim the tool results of an OpenAI chat request, oldest first, keeping
+/// the `keep_recent` most recent ones intact.
+pub fn trim_tool_results(
+    request: &mut Value,
+    keep_recent: usize,
+    excerpt_chars: usize,
+) -> TrimReport {
+    let mut report = TrimReport::default();
+    let Some(messages) = request.get_mut("messages").and_then(Value::as_array_mut) else {
+        return report;
+    };
+    let tool_results: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.get("role").and_then(Value::as_str) == Some("tool"))
+        .map(|(idx, _)| idx)
+        .collect();
+    let older = tool_results.len().saturating_sub(keep_recent);
+    for idx in &tool_results[..older] {
+        let removed = match messages[*idx].get_mut("content") {
+            Some(Value::String(text)) => trim_text(text, excerpt_chars),
+            Some(Value::Array(parts)) => parts
+                .iter_mut()
+                .filter_map(|part| match part.get_mut("text") {
+                    Some(Value::String(text)) => Some(trim_text(text, excerpt_chars)),
+                    _ => None,
+                })
+                .sum(),
+            _ => 0,
+        };
+        if removed > 0 {
+            report.trimmed += 1;
+            report.removed_chars += removed;
+        }
+    }
+    report
+}
+
+/// Report a recovery step to the session running `mission_id`.
+pub async fn emit(
+    control: &ControlHub,
+    mission_id: Uuid,
+    action: &str,
+    message: String,
+    details: Value,
+) {
+    for session in control.all_sessions().await {
+        let running = session
+            .running_missions
+            .read()
+            .await
+            .iter()
+            .any(|m| m.mission_id == mission_id);
+        if running || *session.current_mission.read().await == Some(mission_id) {
+            let _ = session.events_tx.send(AgentEvent::ContextRecovery {
+                action: action.to_string(),
+                message,
+                details,
+                mission_id,
+            });
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_provider_overflow_errors() {
+        assert!(is_context_overflow(
+            br#"{"error":{"code":"context_length_exceeded","message":"This model's maximum context length is 128000 tokens."}}"#
+        ));
+        assert!(is_context_overflow(
+            br#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#
+        ));
+        assert!(!is_context_overflow(
+            br#"{"error":{"message":"model not found"}}"#
+        ));
+    }
+
+    #[test]
+    fn trims_oldest_tool_results_first() {
+        let long = "x".repeat(1000);
+        let mut request = serde_json::json!({
+            "messages": [
+                {"role": "user", "content": long},
+                {"role": "tool", "tool_call_id": "a", "content": long},
+                {"role": "tool", "tool_call_id": "b", "content": [{"type": "text", "text": long}]},
+                {"role": "tool", "tool_call_id": "c", "content": long},
+            ]
+        });
+
+        let report = trim_tool_results(&mut request, 1, 100);
+        assert_eq!(
+            report,
+            TrimReport {
+                trimmed: 2,
+                removed_chars: 1800
+            }
+        );
+        let messages = request["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["content"], long);
+        assert!(messages[1]["content"]
+            .as_str()
+            .unwrap()
+            .ends_with("[... 900 characters omitted to fit the context window]"));
+        assert!(messages[2]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with(&"x".repeat(100)));
+        assert_eq!(messages[3]["content"], long);
+
+        let report = trim_tool_results(&mut request, 1, 0);
+        assert_eq!(report.trimmed, 2);
+        assert!(request["messages"][1]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("[tool result omitted"));
+    }
+}
@@ -15,24 +15,31 @@ use axum::{
     body::Bytes,
     extract::{Extension, Path, State},
     http::{HeaderMap, StatusCode},
-    response::sse::{Event, Sse},
+    response::{
+        sse::{Event, Sse},
+        Response,
+    },
     Json,
 };
 use futures::stream::Stream;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, mpsc, oneshot, Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::agents::{AgentContext, AgentRef, TerminalReason};
+use crate::backend::BackendRegistry;
 use crate::config::Config;
 use crate::mcp::McpRegistry;
+use crate::schedule_windows::{active_maintenance, in_quiet_hours};
 use crate::secrets::SecretsStore;
 use crate::util::{build_history_context, internal_error};
 use crate::workspace;
 
 use super::auth::AuthUser;
 use super::desktop;
+use super::event_schema::Versioned;
 use super::library::SharedLibrary;
 use super::mission_store::{
     self, create_mission_store, now_string, Mission, MissionHistoryEntry, MissionStore,
@@ -205,7 +212,7 @@ fn require_mission_id(
 }
 
 /// Query the control actor for the list of currently running missions.
-async fn get_running_missions(
+pub(crate) async fn get_running_missions(
     control: &ControlState,
 ) -> Result<Vec<super::mission_runner::RunningMissionInfo>, (StatusCode, String)> {
     let (tx, rx) = oneshot::channel();
@@ -397,6 +404,102 @@ pub(crate) async fn resolve_claudecode_default_model(
     }
 }
 
+/// Resolve the effective sampling options for a turn: config profile defaults,
+/// overridden by the workspace's, overridden by the library agent's frontmatter.
+///
+/// Returns the merged options and the agent's own, which some backends apply
+/// natively.
+pub(crate) async fn resolve_chat_options(
+    library: &SharedLibrary,
+    config_profile: Option<&str>,
+    workspace_options: &crate::chat_options::ChatOptions,
+    agent: Option<&str>,
+) -> (
+    crate::chat_options::ChatOptions,
+    crate::chat_options::ChatOptions,
+) {
+    let Some(lib) = library.read().await.clone() else {
+        return (workspace_options.clone(), Default::default());
+    };
+
+    let profile = config_profile.unwrap_or("default");
+    let profile_options = match lib.get_sandboxed_config_for_profile(profile).await {
+        Ok(config) => config.chat_options,
+        Err(err) => {
+            tracing::warn!(
+                "Failed to load sandboxed config from library (profile: {}): {}",
+                profile,
+                err
+            );
+            Default::default()
+        }
+    };
+    let agent_options = match agent {
+        Some(name) => lib
+            .get_library_agent(name)
+            .await
+            .map(|a| a.chat_options)
+            .unwrap_or_default(),
+        None => Default::default(),
+    };
+    let merged = profile_options
+        .merged_with(workspace_options)
+        .merged_with(&agent_options);
+    (merged, agent_options)
+}
+
+/// Mirror a `set_plan`/`update_plan` call onto the mission's stored plan and
+/// announce the new plan. Calls the workspace MCP rejects are ignored here too.
+async fn apply_plan_call(
+    mission_store: &Arc<dyn MissionStore>,
+    events_tx: &broadcast::Sender<AgentEvent>,
+    mission_id: Uuid,
+    tool: &str,
+    args: &serde_json::Value,
+) {
+    let Ok(Some(mission)) = mission_store.get_mission(mission_id).await else {
+        return;
+    };
+    let plan = match crate::tools::plan::apply(mission.plan.as_ref(), tool, args) {
+        Ok(plan) => plan,
+        Err(e) => {
+            tracing::debug!(mission_id = %mission_id, error = %e, "Ignoring invalid plan update");
+            return;
+        }
+    };
+    if let Err(e) = mission_store.update_mission_plan(mission_id, &plan).await {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to store mission plan");
+        return;
+    }
+    let _ = events_tx.send(AgentEvent::PlanUpdated {
+        done: plan.done(),
+        total: plan.steps.len(),
+        plan,
+        mission_id,
+    });
+}
+
+/// Store the report of a `submit_audit_report` call on the audit mission.
+async fn store_audit_report(
+    mission_store: &Arc<dyn MissionStore>,
+    mission_id: Uuid,
+    args: &serde_json::Value,
+) {
+    let report = match crate::tools::audit::AuditReport::from_args(args) {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::debug!(mission_id = %mission_id, error = %e, "Ignoring invalid audit report");
+            return;
+        }
+    };
+    if let Err(e) = mission_store
+        .update_mission_audit_report(mission_id, &report)
+        .await
+    {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to store audit report");
+    }
+}
+
 async fn close_mission_desktop_sessions(
     mission_store: &Arc<dyn MissionStore>,
     mission_id: Uuid,
@@ -445,7 +548,7 @@ async fn close_mission_desktop_sessions(
 }
 
 /// Message posted by a user to the control session.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ControlMessageRequest {
     pub content: String,
     /// Optional agent override for this specific message (e.g., from @agent mention)
@@ -457,7 +560,7 @@ pub struct ControlMessageRequest {
     pub mission_id: Option<Uuid>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ControlMessageResponse {
     pub id: Uuid,
     pub queued: bool,
@@ -481,7 +584,7 @@ pub struct ControlToolResultRequest {
     pub result: serde_json::Value,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ControlRunState {
     #[default]
@@ -491,7 +594,7 @@ pub enum ControlRunState {
 }
 
 /// A file shared by the agent (images render inline, other files show as download links).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SharedFile {
     /// Display name for the file
     pub name: String,
@@ -507,7 +610,7 @@ pub struct SharedFile {
 }
 
 /// Kind of shared file (determines how it renders in the UI).
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SharedFileKind {
     /// Images (PNG, JPEG, GIF, WebP, SVG) - rendered inline
@@ -703,7 +806,10 @@ async fn validate_rich_tags(
 }
 
 /// A structured event emitted by the control session.
-#[derive(Debug, Clone, Serialize)]
+///
+/// Evolves additively within an [`EVENT_SCHEMA_VERSION`](super::event_schema::EVENT_SCHEMA_VERSION):
+/// fields added later must be optional or carry `#[serde(default)]`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AgentEvent {
     Status {
@@ -734,6 +840,9 @@ pub enum AgentEvent {
         model: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         model_normalized: Option<String>,
+        /// Effective sampling options (temperature, max_tokens, ...) for this turn
+        #[serde(skip_serializing_if = "Option::is_none")]
+        chat_options: Option<crate::chat_options::ChatOptions>,
         /// Mission this message belongs to (for parallel execution)
         #[serde(skip_serializing_if = "Option::is_none")]
         mission_id: Option<Uuid>,
@@ -846,10 +955,88 @@ pub enum AgentEvent {
         #[serde(skip_serializing_if = "Option::is_none")]
         mission_id: Option<Uuid>,
     },
+    /// Dependency the agent added to a manifest, with license and provenance
+    DependencyAdded {
+        #[serde(flatten)]
+        dependency: crate::dependency_audit::Dependency,
+        license: Option<String>,
+        /// Registry page the license was read from
+        registry_url: Option<String>,
+        /// License policy violation, if any
+        violation: Option<String>,
+        mission_id: Uuid,
+    },
+    /// Security-relevant event observed while a mission ran (e.g. blocked egress)
+    SecurityEvent {
+        /// Machine-readable category, e.g. "egress_blocked"
+        kind: String,
+        message: String,
+        #[serde(default)]
+        details: serde_json::Value,
+        mission_id: Uuid,
+    },
+    /// Mission configuration that cannot take effect as written, reported
+    /// when the mission starts (e.g. a profile permission the backend ignores)
+    ConfigWarning { message: String, mission_id: Uuid },
+    /// Backend subprocesses terminated because the mission was cancelled
+    KilledProcesses {
+        processes: Vec<crate::process_group::KilledProcess>,
+        /// Processes still running after SIGKILL
+        #[serde(default)]
+        survivors: Vec<crate::process_group::KilledProcess>,
+        mission_id: Uuid,
+    },
+    /// Older conversation history was replaced by a summary entry
+    HistoryCompacted {
+        summary: String,
+        /// Number of history entries folded into the summary
+        compacted: usize,
+        /// Number of recent entries kept verbatim after the summary
+        kept: usize,
+        mission_id: Uuid,
+    },
+    /// The model-routing proxy recovered from a context-length-exceeded error
+    ContextRecovery {
+        /// What was done: "trimmed_tool_results" or "long_context_fallback"
+        action: String,
+        message: String,
+        #[serde(default)]
+        details: serde_json::Value,
+        mission_id: Uuid,
+    },
+    /// A mission passed its SLA deadline (see `mission_sla`)
+    SlaBreached {
+        deadline: String,
+        message: String,
+        /// Escalations that ran, e.g. "switched to model ..."
+        #[serde(default)]
+        escalations: Vec<String>,
+        mission_id: Uuid,
+    },
+    /// The agent recorded or updated its plan (see `tools::plan`)
+    PlanUpdated {
+        plan: crate::tools::plan::MissionPlan,
+        /// Steps completed or skipped
+        done: usize,
+        total: usize,
+        mission_id: Uuid,
+    },
+    /// A person worked in the mission's workspace through an attached terminal
+    ManualIntervention {
+        session_id: Uuid,
+        /// "started", "command" or "ended"
+        action: String,
+        user: String,
+        /// Summary, or the command line for "command"
+        content: String,
+        #[serde(default)]
+        details: serde_json::Value,
+        mission_id: Uuid,
+    },
 }
 
 /// A node in the agent tree (for visualization)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AgentTreeNode {
     pub id: String,
     #[serde(rename = "type")]
@@ -926,7 +1113,16 @@ impl AgentEvent {
             AgentEvent::Progress { .. } => "progress",
             AgentEvent::SessionIdUpdate { .. } => "session_id_update",
             AgentEvent::MissionActivity { .. } => "mission_activity",
+            AgentEvent::DependencyAdded { .. } => "dependency_added",
+            AgentEvent::SecurityEvent { .. } => "security_event",
+            AgentEvent::KilledProcesses { .. } => "killed_processes",
+            AgentEvent::HistoryCompacted { .. } => "history_compacted",
+            AgentEvent::ContextRecovery { .. } => "context_recovery",
+            AgentEvent::ConfigWarning { .. } => "config_warning",
             AgentEvent::MissionTitleChanged { .. } => "mission_title_changed",
+            AgentEvent::ManualIntervention { .. } => "manual_intervention",
+            AgentEvent::SlaBreached { .. } => "sla_breached",
+            AgentEvent::PlanUpdated { .. } => "plan_updated",
         }
     }
 
@@ -946,7 +1142,16 @@ impl AgentEvent {
             AgentEvent::Progress { mission_id, .. } => *mission_id,
             AgentEvent::SessionIdUpdate { mission_id, .. } => Some(*mission_id),
             AgentEvent::MissionActivity { mission_id, .. } => *mission_id,
+            AgentEvent::DependencyAdded { mission_id, .. } => Some(*mission_id),
+            AgentEvent::SecurityEvent { mission_id, .. } => Some(*mission_id),
+            AgentEvent::KilledProcesses { mission_id, .. } => Some(*mission_id),
+            AgentEvent::HistoryCompacted { mission_id, .. } => Some(*mission_id),
+            AgentEvent::ContextRecovery { mission_id, .. } => Some(*mission_id),
+            AgentEvent::ConfigWarning { mission_id, .. } => Some(*mission_id),
             AgentEvent::MissionTitleChanged { mission_id, .. } => Some(*mission_id),
+            AgentEvent::ManualIntervention { mission_id, .. } => Some(*mission_id),
+            AgentEvent::SlaBreached { mission_id, .. } => Some(*mission_id),
+            AgentEvent::PlanUpdated { mission_id, .. } => Some(*mission_id),
         }
     }
 }
@@ -1014,6 +1219,13 @@ pub enum ControlCommand {
         mission_id: Uuid,
         respond: oneshot::Sender<Result<(), String>>,
     },
+    /// Checkpoint and interrupt a running parallel mission to free its slot.
+    /// Responds with the prompt to resume it with.
+    PreemptMission {
+        mission_id: Uuid,
+        reason: String,
+        respond: oneshot::Sender<Result<String, String>>,
+    },
     /// List currently running missions
     ListRunning {
         respond: oneshot::Sender<Vec<super::mission_runner::RunningMissionInfo>>,
@@ -1044,12 +1256,32 @@ pub enum ControlCommand {
     ClearQueue {
         respond: oneshot::Sender<usize>, // number of messages cleared
     },
+    /// Switch a mission's later turns to another model (SLA escalation)
+    SetMissionModel {
+        mission_id: Uuid,
+        model: String,
+        respond: oneshot::Sender<Result<(), String>>,
+    },
+    /// Move a mission's queued messages to the front of the queue
+    PrioritizeMission {
+        mission_id: Uuid,
+        respond: oneshot::Sender<usize>, // number of messages moved
+    },
+    /// Replace older history with a summary, keeping `kept` (the current
+    /// tail of the history) verbatim. Fails if the mission is running or its
+    /// history no longer ends with `kept`.
+    CompactHistory {
+        mission_id: Uuid,
+        summary: String,
+        kept: Vec<MissionHistoryEntry>,
+        respond: oneshot::Sender<Result<Vec<MissionHistoryEntry>, String>>,
+    },
 }
 
 // ==================== Mission Types ====================
 
 /// Mission status.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum MissionStatus {
     /// Mission created but hasn't received any messages yet
@@ -1106,7 +1338,7 @@ pub struct DesktopSessionInfo {
 }
 
 /// Request to set mission status.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SetMissionStatusRequest {
     pub status: MissionStatus,
 }
@@ -1117,6 +1349,12 @@ pub struct SetMissionTitleRequest {
     pub title: String,
 }
 
+/// Request to replace a mission's tags.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetMissionTagsRequest {
+    pub tags: Vec<String>,
+}
+
 // MissionStore trait and implementations are in mission_store module
 
 /// Shared tool hub used to await frontend tool results.
@@ -1206,6 +1444,10 @@ pub struct ControlState {
     pub running_missions: Arc<RwLock<Vec<super::mission_runner::RunningMissionInfo>>>,
     /// Max parallel missions allowed
     pub max_parallel: usize,
+    /// User the session belongs to (the fair-share tenant)
+    pub user_id: String,
+    /// Cached mission details for status polling
+    pub mission_cache: Arc<super::mission_cache::MissionCache>,
     /// Mission persistence (SQLite-backed)
     pub mission_store: Arc<dyn MissionStore>,
 }
@@ -1220,6 +1462,7 @@ pub struct ControlHub {
     workspaces: workspace::SharedWorkspaceStore,
     library: SharedLibrary,
     secrets: Option<Arc<SecretsStore>>,
+    backends: Arc<RwLock<BackendRegistry>>,
 }
 
 impl ControlHub {
@@ -1230,6 +1473,7 @@ impl ControlHub {
         workspaces: workspace::SharedWorkspaceStore,
         library: SharedLibrary,
         secrets: Option<Arc<SecretsStore>>,
+        backends: Arc<RwLock<BackendRegistry>>,
     ) -> Self {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
@@ -1239,6 +1483,7 @@ impl ControlHub {
             workspaces,
             library,
             secrets,
+            backends,
         }
     }
 
@@ -1282,6 +1527,8 @@ impl ControlHub {
             Arc::clone(&self.library),
             mission_store,
             self.secrets.clone(),
+            Arc::clone(&self.backends),
+            user.id.clone(),
         );
         sessions.insert(user.id.clone(), state.clone());
         state
@@ -1363,7 +1610,7 @@ async fn set_and_emit_status(
     });
 }
 
-async fn control_for_user(state: &Arc<AppState>, user: &AuthUser) -> ControlState {
+pub(crate) async fn control_for_user(state: &Arc<AppState>, user: &AuthUser) -> ControlState {
     state.control.get_or_spawn(user).await
 }
 
@@ -1561,28 +1808,57 @@ pub async fn get_mission(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Mission>, (StatusCode, String)> {
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
     let control = control_for_user(&state, &user).await;
-    match control
+    if let Some(cached) = control.mission_cache.get(id) {
+        return Ok(cached.into_response(&headers));
+    }
+    let loaded_at = std::time::Instant::now();
+    let Some(mut mission) = control
         .mission_store
         .get_mission(id)
         .await
         .map_err(internal_error)?
-    {
-        Some(mut mission) => {
-            // Populate workspace_name
-            if let Some(workspace) = state.workspaces.get(mission.workspace_id).await {
-                mission.workspace_name = Some(workspace.name);
-            }
-            Ok(Json(mission))
-        }
-        None => Err((StatusCode::NOT_FOUND, format!("Mission {} not found", id))),
+    else {
+        return Err((StatusCode::NOT_FOUND, format!("Mission {} not found", id)));
+    };
+    // Populate workspace_name
+    if let Some(workspace) = state.workspaces.get(mission.workspace_id).await {
+        mission.workspace_name = Some(workspace.name);
     }
+    let timing = super::mission_timing::mission_timing(&control.mission_store, &mission).await;
+    let results = control
+        .mission_store
+        .get_mission_results(id)
+        .await
+        .map_err(internal_error)?;
+    let body = serde_json::to_vec(&MissionDetail {
+        mission,
+        timing,
+        results,
+    })
+    .map_err(internal_error)?;
+    let entry = super::mission_cache::CachedMission::new(body);
+    control.mission_cache.insert(id, entry.clone(), loaded_at);
+    Ok(entry.into_response(&headers))
+}
+
+/// A mission with its per-turn timing breakdown and post-processing results.
+#[derive(Debug, Serialize)]
+pub struct MissionDetail {
+    #[serde(flatten)]
+    pub mission: Mission,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<super::mission_timing::MissionTiming>,
+    /// Output of the post-processors run after the mission finished.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<super::mission_store::MissionResult>,
 }
 
 /// Create a new mission and switch to it.
 /// Request body for creating a mission
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CreateMissionRequest {
     pub title: Option<String>,
     /// Workspace ID to run the mission in (defaults to host workspace)
@@ -1591,19 +1867,51 @@ pub struct CreateMissionRequest {
     pub agent: Option<String>,
     /// Optional model override (provider/model) - deprecated, use config_profile instead
     pub model_override: Option<String>,
-    /// Optional model effort override (supports: low, medium, high)
+    /// Optional model effort override (supports: off, low, medium, high, or a token budget)
     pub model_effort: Option<String>,
+    /// Reasoning / thinking budget: "off", "low", "medium", "high", or a token
+    /// budget number. Takes precedence over `model_effort`.
+    pub reasoning: Option<serde_json::Value>,
     /// Config profile to use for this mission (overrides workspace's default profile)
     pub config_profile: Option<String>,
     /// Backend to use for this mission ("opencode" or "claudecode")
     pub backend: Option<String>,
+    /// Locale and time zone for this mission (overrides the global settings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<crate::locale::LocaleSettings>,
+    /// Tags for budget attribution (see `budget`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Soft deadline and escalation policy (see `mission_sla`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sla: Option<super::mission_sla::SlaRequest>,
+    /// Per-mission feature flag overrides (see `feature_flags`)
+    #[serde(
+        default,
+        skip_serializing_if = "crate::feature_flags::FlagSet::is_empty"
+    )]
+    pub feature_flags: crate::feature_flags::FlagSet,
+    /// Turn and tool call limits (see `step_budget`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_budget: Option<super::step_budget::StepBudget>,
+    /// Scheduling priority, higher first (see `mission_priority`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// "audit" for a read-only verification mission (see `mission_audit`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<super::mission_audit::MissionMode>,
 }
 
 fn normalize_model_effort(raw: &str) -> Option<String> {
-    match raw.trim().to_ascii_lowercase().as_str() {
-        "low" => Some("low".to_string()),
-        "medium" => Some("medium".to_string()),
-        "high" => Some("high".to_string()),
+    crate::chat_options::normalize_reasoning_effort(raw)
+}
+
+/// Flatten a `reasoning` request value (string or number) to the stored setting.
+fn reasoning_setting_from_json(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(false) => Some("off".to_string()),
         _ => None,
     }
 }
@@ -1621,13 +1929,68 @@ fn normalize_model_override_for_backend(backend: Option<&str>, raw_model: &str)
     Some(trimmed.to_string())
 }
 
-pub async fn create_mission(
-    State(state): State<Arc<AppState>>,
-    Extension(user): Extension<AuthUser>,
-    body: Option<Json<CreateMissionRequest>>,
-) -> Result<Json<Mission>, (StatusCode, String)> {
-    let (tx, rx) = oneshot::channel();
+/// Refuse a mission model the organization's model policy forbids (see
+/// `model_policy`). A chain passes when at least one of its models is allowed;
+/// the proxy skips the others.
+async fn check_model_policy(state: &AppState, backend: &str, model: &str) -> Result<(), String> {
+    let policy = crate::settings::model_policy_cached();
+    if policy.is_empty() {
+        return Ok(());
+    }
+    let chain = match state.chain_store.get(model).await {
+        Some(chain) => Some(chain),
+        None => state.chain_store.get(&format!("builtin/{}", model)).await,
+    };
+    if let Some(chain) = chain {
+        let refusals: Vec<String> = chain
+            .entries
+            .iter()
+            .filter_map(|e| policy.check(&e.provider_id, &e.model_id).err())
+            .collect();
+        if refusals.len() < chain.entries.len() {
+            return Ok(());
+        }
+        return Err(format!(
+            "No model in chain '{}' is allowed by the model policy: {}",
+            chain.id,
+            refusals.join("; ")
+        ));
+    }
+    if backend == "opencode" {
+        if let Some((provider, model_id)) = model.split_once('/') {
+            return policy.check(provider, model_id);
+        }
+    }
+    match crate::model_policy::backend_provider(backend) {
+        Some(provider) => policy.check(provider, model),
+        None => Ok(()),
+    }
+}
 
+/// A create-mission request after normalization and validation.
+pub(crate) struct PreparedMission {
+    pub title: Option<String>,
+    pub workspace_id: Option<Uuid>,
+    pub agent: Option<String>,
+    pub model_override: Option<String>,
+    pub model_effort: Option<String>,
+    pub backend: Option<String>,
+    pub config_profile: Option<String>,
+    pub locale: Option<crate::locale::LocaleSettings>,
+    pub tags: Vec<String>,
+    pub sla: Option<super::mission_sla::MissionSla>,
+    pub feature_flags: crate::feature_flags::FlagSet,
+    pub step_budget: Option<super::step_budget::StepBudget>,
+    pub priority: i32,
+    pub mode: super::mission_audit::MissionMode,
+}
+
+/// Normalize and validate a create-mission request: resolves the backend,
+/// config profile and default model, and checks agent/backend/model exist.
+pub(crate) async fn prepare_mission(
+    state: &Arc<AppState>,
+    body: Option<&CreateMissionRequest>,
+) -> Result<PreparedMission, (StatusCode, String)> {
     let (title, workspace_id, agent, model_override, model_effort, config_profile, mut backend) =
         body.map(|b| {
             (
@@ -1635,7 +1998,10 @@ pub async fn create_mission(
                 b.workspace_id,
                 b.agent.clone(),
                 b.model_override.clone(),
-                b.model_effort.clone(),
+                b.reasoning
+                    .as_ref()
+                    .map(|r| reasoning_setting_from_json(r).unwrap_or_else(|| r.to_string()))
+                    .or_else(|| b.model_effort.clone()),
                 b.config_profile.clone(),
                 b.backend.clone(),
             )
@@ -1662,12 +2028,38 @@ pub async fn create_mission(
             if model_effort.is_none() {
                 return Err((
                     StatusCode::BAD_REQUEST,
-                    "Invalid model_effort. Supported values: low, medium, high".to_string(),
+                    format!(
+                        "Invalid reasoning/model_effort. Supported values: off, low, medium, high, or a token budget between {} and {}",
+                        crate::chat_options::MIN_THINKING_BUDGET,
+                        crate::chat_options::MAX_THINKING_BUDGET
+                    ),
                 ));
             }
         }
     }
 
+    let locale = body
+        .and_then(|b| b.locale.clone())
+        .filter(|l| !l.is_empty());
+    if let Some(ref locale) = locale {
+        locale
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("locale: {}", e)))?;
+    }
+    let tags = super::budget::normalize_tags(body.map(|b| b.tags.as_slice()).unwrap_or_default())
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("tags: {}", e)))?;
+    let feature_flags = body.map(|b| b.feature_flags.clone()).unwrap_or_default();
+    crate::feature_flags::validate_overrides(&feature_flags)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("feature_flags: {}", e)))?;
+    let step_budget = body
+        .and_then(|b| b.step_budget)
+        .filter(|budget| !budget.is_empty());
+    if let Some(budget) = step_budget {
+        budget
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("step_budget: {}", e)))?;
+    }
+
     // If no backend specified, use the default from registry
     // This needs to happen BEFORE agent validation so we validate against the correct backend
     if backend.is_none() {
@@ -1675,8 +2067,9 @@ pub async fn create_mission(
         backend = Some(registry.default_id().to_string());
     }
 
-    // Model effort is currently supported for Codex missions.
-    if backend.as_deref() != Some("codex") {
+    // Reasoning effort maps to Codex `reasoning.effort` and Claude Code's
+    // extended-thinking budget; other backends take it from agent config.
+    if !matches!(backend.as_deref(), Some("codex" | "claudecode")) {
         model_effort = None;
     }
 
@@ -1709,7 +2102,7 @@ pub async fn create_mission(
         let skip_validation = matches!(backend_id, Some("claudecode" | "amp" | "codex"));
         if !skip_validation {
             super::library::validate_agent_exists(
-                &state,
+                state,
                 agent_name,
                 effective_config_profile.as_deref(),
             )
@@ -1732,7 +2125,7 @@ pub async fn create_mission(
     // Validate model override if provided
     if let Some(ref model) = model_override {
         let backend_id = backend.as_deref().unwrap_or("claudecode");
-        if let Err(e) = super::providers::validate_model_override(&state, backend_id, model).await {
+        if let Err(e) = super::providers::validate_model_override(state, backend_id, model).await {
             return Err((StatusCode::BAD_REQUEST, e));
         }
     }
@@ -1747,7 +2140,86 @@ pub async fn create_mission(
         }
     }
 
+    if let Some(ref model) = model_override {
+        check_model_policy(state, backend.as_deref().unwrap_or("claudecode"), model)
+            .await
+            .map_err(|e| (StatusCode::FORBIDDEN, e))?;
+    }
+
+    let sla = match body.and_then(|b| b.sla.as_ref()) {
+        Some(request) => {
+            let mut sla = request
+                .resolve(chrono::Utc::now())
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("sla: {}", e)))?;
+            if let Some(raw_model) = sla.escalation_model.take() {
+                let backend_id = backend.as_deref().unwrap_or("claudecode");
+                let model = normalize_model_override_for_backend(backend.as_deref(), &raw_model)
+                    .unwrap_or(raw_model);
+                super::providers::validate_model_override(state, backend_id, &model)
+                    .await
+                    .map_err(|e| {
+                        (
+                            StatusCode::BAD_REQUEST,
+                            format!("sla.escalation_model: {}", e),
+                        )
+                    })?;
+                check_model_policy(state, backend_id, &model)
+                    .await
+                    .map_err(|e| (StatusCode::FORBIDDEN, e))?;
+                sla.escalation_model = Some(model);
+            }
+            Some(sla)
+        }
+        None => None,
+    };
+
+    let mode = body.and_then(|b| b.mode).unwrap_or_default();
+    super::mission_audit::check_backend(mode, backend.as_deref().unwrap_or("claudecode"))
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok(PreparedMission {
+        title,
+        workspace_id,
+        agent,
+        model_override,
+        model_effort,
+        backend,
+        config_profile: effective_config_profile,
+        locale,
+        tags,
+        sla,
+        feature_flags,
+        step_budget,
+        priority: body.and_then(|b| b.priority).unwrap_or_default(),
+        mode,
+    })
+}
+
+pub async fn create_mission(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    body: Option<Json<CreateMissionRequest>>,
+) -> Result<Json<Mission>, (StatusCode, String)> {
+    let PreparedMission {
+        title,
+        workspace_id,
+        agent,
+        model_override,
+        model_effort,
+        backend,
+        config_profile,
+        locale,
+        tags,
+        sla,
+        feature_flags,
+        step_budget,
+        priority,
+        mode,
+    } = prepare_mission(&state, body.as_deref()).await?;
+    let (tx, rx) = oneshot::channel();
+
     let control = control_for_user(&state, &user).await;
+    super::budget::check_cap(&control.mission_store, workspace_id, &tags).await?;
     control
         .cmd_tx
         .send(ControlCommand::CreateMission {
@@ -1757,16 +2229,70 @@ pub async fn create_mission(
             model_override,
             model_effort,
             backend,
-            config_profile: effective_config_profile,
+            config_profile,
             respond: tx,
         })
         .await
         .map_err(session_unavailable)?;
 
-    rx.await
-        .map_err(recv_failed)?
-        .map(Json)
-        .map_err(internal_error)
+    let mut mission = rx.await.map_err(recv_failed)?.map_err(internal_error)?;
+    if let Some(locale) = locale {
+        control
+            .mission_store
+            .update_mission_locale(mission.id, Some(&locale))
+            .await
+            .map_err(internal_error)?;
+        mission.locale = Some(locale);
+    }
+    if !tags.is_empty() {
+        control
+            .mission_store
+            .update_mission_tags(mission.id, &tags)
+            .await
+            .map_err(internal_error)?;
+        mission.tags = tags;
+    }
+    if let Some(sla) = sla {
+        control
+            .mission_store
+            .update_mission_sla(mission.id, Some(&sla))
+            .await
+            .map_err(internal_error)?;
+        mission.sla = Some(sla);
+    }
+    if !feature_flags.is_empty() {
+        mission.feature_flags.extend(feature_flags);
+        control
+            .mission_store
+            .update_mission_feature_flags(mission.id, &mission.feature_flags)
+            .await
+            .map_err(internal_error)?;
+    }
+    if let Some(budget) = step_budget {
+        control
+            .mission_store
+            .update_mission_step_budget(mission.id, Some(budget))
+            .await
+            .map_err(internal_error)?;
+        mission.step_budget = Some(budget);
+    }
+    if priority != 0 {
+        control
+            .mission_store
+            .update_mission_priority(mission.id, priority)
+            .await
+            .map_err(internal_error)?;
+        mission.priority = priority;
+    }
+    if mode != super::mission_audit::MissionMode::Standard {
+        control
+            .mission_store
+            .update_mission_mode(mission.id, mode)
+            .await
+            .map_err(internal_error)?;
+        mission.mode = mode;
+    }
+    Ok(Json(mission))
 }
 
 /// Load/switch to a mission.
@@ -1846,6 +2372,58 @@ pub async fn set_mission_title(
         .map_err(internal_error)
 }
 
+/// Set or clear a mission's locale and time zone (`{}` clears).
+pub async fn set_mission_locale(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(locale): Json<crate::locale::LocaleSettings>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    locale
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("locale: {}", e)))?;
+    let store = control_for_user(&state, &user).await.mission_store;
+    if store
+        .get_mission(id)
+        .await
+        .map_err(internal_error)?
+        .is_none()
+    {
+        return Err((StatusCode::NOT_FOUND, format!("Mission {} not found", id)));
+    }
+    let locale = Some(locale).filter(|l| !l.is_empty());
+    store
+        .update_mission_locale(id, locale.as_ref())
+        .await
+        .map(|_| ok_json())
+        .map_err(internal_error)
+}
+
+/// Replace a mission's budget tags.
+pub async fn set_mission_tags(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SetMissionTagsRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let tags = super::budget::normalize_tags(&req.tags)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("tags: {}", e)))?;
+    let store = control_for_user(&state, &user).await.mission_store;
+    if store
+        .get_mission(id)
+        .await
+        .map_err(internal_error)?
+        .is_none()
+    {
+        return Err((StatusCode::NOT_FOUND, format!("Mission {} not found", id)));
+    }
+    store
+        .update_mission_tags(id, &tags)
+        .await
+        .map(|_| ok_json())
+        .map_err(internal_error)
+}
+
 /// Get the current mission (if any).
 pub async fn get_current_mission(
     State(state): State<Arc<AppState>>,
@@ -1974,6 +2552,202 @@ pub async fn get_mission_events(
     Ok(Json(events))
 }
 
+/// Query params for the mission diff endpoint.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GetMissionDiffQuery {
+    /// Include the unified patch in the JSON response
+    #[serde(default)]
+    pub include_patch: bool,
+    /// Set to "patch" to download the raw unified diff instead of JSON
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// Get the cumulative diff of a mission's workspace since the mission started.
+///
+/// The diff is computed against the baseline snapshot recorded when the mission
+/// workspace was first prepared, so it includes uncommitted work.
+pub async fn get_mission_diff(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<GetMissionDiffQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let control = control_for_user(&state, &user).await;
+    let mission = control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Mission not found".to_string()))?;
+
+    let workspace_root = workspace::resolve_workspace_root(
+        &state.workspaces,
+        &state.config,
+        Some(mission.workspace_id),
+    )
+    .await;
+    let mission_dir = workspace::mission_workspace_dir_for_root(&workspace_root, mission_id);
+
+    let as_patch = query.format.as_deref() == Some("patch");
+    let diff =
+        crate::workspace_snapshot::mission_diff(&mission_dir, as_patch || query.include_patch)
+            .await
+            .map_err(internal_error)?;
+
+    if as_patch {
+        let filename = format!("mission-{}.patch", &mission_id.to_string()[..8]);
+        let headers = [
+            (
+                axum::http::header::CONTENT_TYPE,
+                "text/x-diff; charset=utf-8".to_string(),
+            ),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ];
+        return Ok((headers, diff.patch.unwrap_or_default()).into_response());
+    }
+
+    Ok(Json(diff).into_response())
+}
+
+/// Mission directory of a mission the user can see.
+async fn visible_mission_dir(
+    state: &Arc<AppState>,
+    user: &AuthUser,
+    mission_id: Uuid,
+) -> Result<std::path::PathBuf, (StatusCode, String)> {
+    let control = control_for_user(state, user).await;
+    let mission = control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Mission not found".to_string()))?;
+    let workspace_root = workspace::resolve_workspace_root(
+        &state.workspaces,
+        &state.config,
+        Some(mission.workspace_id),
+    )
+    .await;
+    Ok(workspace::mission_workspace_dir_for_root(
+        &workspace_root,
+        mission_id,
+    ))
+}
+
+/// GET /api/control/missions/:id/plan - The agent's step-level plan.
+pub async fn get_mission_plan(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let mission = control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Mission not found".to_string()))?;
+    let plan = mission
+        .plan
+        .ok_or((StatusCode::NOT_FOUND, "Mission has no plan".to_string()))?;
+    Ok(Json(serde_json::json!({
+        "steps": plan.steps,
+        "done": plan.done(),
+        "total": plan.steps.len(),
+    })))
+}
+
+/// GET /api/control/missions/:id/checkpoints - Workspace states files can be read at.
+pub async fn get_mission_checkpoints(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+) -> Result<Json<Vec<crate::workspace_snapshot::Checkpoint>>, (StatusCode, String)> {
+    let mission_dir = visible_mission_dir(&state, &user, mission_id).await?;
+    crate::workspace_snapshot::list_checkpoints(&mission_dir)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileAtQuery {
+    /// Path relative to the mission directory
+    pub path: String,
+    /// Checkpoint ID (defaults to the baseline)
+    #[serde(default)]
+    pub checkpoint: Option<String>,
+}
+
+/// GET /api/control/missions/:id/files-at?path=&checkpoint= - A workspace file
+/// as it was at a checkpoint, without touching the current workspace.
+pub async fn get_mission_file_at(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<FileAtQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    use axum::response::IntoResponse;
+
+    let mission_dir = visible_mission_dir(&state, &user, mission_id).await?;
+    let checkpoint = query
+        .checkpoint
+        .as_deref()
+        .unwrap_or(crate::workspace_snapshot::BASE_CHECKPOINT);
+    let content = crate::workspace_snapshot::read_file_at(&mission_dir, &query.path, checkpoint)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("{} did not exist at checkpoint {}", query.path, checkpoint),
+        ))?;
+    let content_type = if std::str::from_utf8(&content).is_ok() {
+        "text/plain; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    };
+    Ok(([(axum::http::header::CONTENT_TYPE, content_type)], content).into_response())
+}
+
+/// GET /api/control/missions/:id/web-access - URLs the mission fetched through the web proxy.
+pub async fn get_mission_web_access(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+) -> Result<Json<Vec<crate::web_proxy::WebAccessRecord>>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Mission not found".to_string()))?;
+
+    Ok(Json(crate::web_proxy::read_mission_log(
+        &state.config.working_dir,
+        mission_id,
+    )))
+}
+
+/// GET /api/control/missions/:id/tool-usage - Usage of metered tools against their quotas.
+pub async fn get_mission_tool_usage(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let mission_dir = visible_mission_dir(&state, &user, mission_id).await?;
+    Ok(Json(serde_json::json!({
+        "limits": crate::tool_quotas::read_limits(&mission_dir).unwrap_or_default(),
+        "usage": crate::tool_quotas::read_usage(&mission_dir),
+    })))
+}
+
 // ==================== Diagnostic Endpoints ====================
 
 /// Response for OpenCode diagnostic endpoint.
@@ -2021,7 +2795,7 @@ pub async fn list_running_missions(
 }
 
 /// Request body for starting a mission in parallel.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct StartParallelRequest {
     pub content: String,
 }
@@ -2077,7 +2851,7 @@ pub async fn cancel_mission(
 }
 
 /// Request body for resuming a mission
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ResumeMissionRequest {
     /// If true, clean the mission's work directory before resuming
     #[serde(default)]
@@ -2134,13 +2908,77 @@ pub async fn get_parallel_config(
     })))
 }
 
+/// Get tool schema pruning totals (prompt-size savings since startup).
+pub async fn get_tool_pruning_stats(
+    Extension(_user): Extension<AuthUser>,
+) -> Json<crate::tool_pruning::PruningTotals> {
+    Json(crate::tool_pruning::totals())
+}
+
+/// List executor feature flags with their rollout and the outcomes of recent
+/// missions with and without each flag.
+pub async fn get_feature_flags(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<crate::feature_flags::FlagSummary>>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let missions = control
+        .mission_store
+        .list_missions(1000, 0)
+        .await
+        .map_err(internal_error)?;
+    let statuses: Vec<String> = missions.iter().map(|m| m.status.to_string()).collect();
+    Ok(Json(crate::feature_flags::summarize(
+        &crate::settings::feature_flag_rollouts_cached(),
+        missions
+            .iter()
+            .zip(&statuses)
+            .map(|(m, status)| (&m.feature_flags, status.as_str())),
+    )))
+}
+
+/// Look up which mission authored a commit (full hash or a prefix of at least
+/// seven characters).
+pub async fn get_commit_provenance(
+    State(state): State<Arc<AppState>>,
+    Extension(_user): Extension<AuthUser>,
+    Path(hash): Path<String>,
+) -> Result<Json<Vec<crate::provenance::CommitRecord>>, (StatusCode, String)> {
+    if hash.trim().len() < crate::provenance::MIN_HASH_PREFIX
+        || !hash.trim().chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Commit hash must be at least {} hex characters",
+                crate::provenance::MIN_HASH_PREFIX
+            ),
+        ));
+    }
+    let records = crate::provenance::lookup(&state.config.working_dir, &hash);
+    if records.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No agent commit recorded for {}", hash.trim()),
+        ));
+    }
+    Ok(Json(records))
+}
+
+/// Response of `DELETE /api/control/missions/:id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteMissionResponse {
+    pub ok: bool,
+    pub deleted: Uuid,
+}
+
 /// Delete a mission by ID.
 /// Only allows deleting missions that are not currently running.
 pub async fn delete_mission(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthUser>,
     Path(mission_id): Path<Uuid>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<DeleteMissionResponse>, (StatusCode, String)> {
     let control = control_for_user(&state, &user).await;
     let running = get_running_missions(&control).await?;
 
@@ -2156,12 +2994,13 @@ pub async fn delete_mission(
         .delete_mission(mission_id)
         .await
         .map_err(internal_error)?;
+    control.mission_cache.invalidate(mission_id);
 
     if deleted {
-        Ok(Json(serde_json::json!({
-            "ok": true,
-            "deleted": mission_id
-        })))
+        Ok(Json(DeleteMissionResponse {
+            ok: true,
+            deleted: mission_id,
+        }))
     } else {
         Err((StatusCode::NOT_FOUND, "Mission not found".to_string()))
     }
@@ -2233,11 +3072,15 @@ pub async fn stream(
 
     let stream = async_stream::stream! {
         let _guard = drop_guard;
-        match Event::default().event("status").json_data(AgentEvent::Status {
+        let initial_status = AgentEvent::Status {
             state: initial.state,
             queue_len: initial.queue_len,
             mission_id: initial.mission_id,
-        }) {
+        };
+        match Event::default()
+            .event("status")
+            .json_data(Versioned::new(initial_status))
+        {
             Ok(init_ev) => yield Ok(init_ev),
             Err(e) => {
                 tracing::error!("Failed to serialize initial SSE status event: {e}");
@@ -2272,7 +3115,10 @@ pub async fn stream(
                                     );
                                 }
                             }
-                            match Event::default().event(ev.event_name()).json_data(&ev) {
+                            match Event::default()
+                                .event(ev.event_name())
+                                .json_data(Versioned::new(&ev))
+                            {
                                 Ok(sse) => yield Ok(sse),
                                 Err(e) => {
                                     tracing::error!(
@@ -2291,13 +3137,13 @@ pub async fn stream(
                             );
                             match Event::default()
                                 .event("error")
-                                .json_data(AgentEvent::Error {
+                                .json_data(Versioned::new(AgentEvent::Error {
                                     message:
                                         "event stream lagged; some events were dropped"
                                             .to_string(),
                                     mission_id: None,
                                     resumable: false,
-                                }) {
+                                })) {
                                 Ok(sse) => yield Ok(sse),
                                 Err(e) => {
                                     tracing::error!(
@@ -2328,6 +3174,7 @@ pub async fn stream(
 }
 
 /// Spawn the global control session actor.
+#[allow(clippy::too_many_arguments)]
 fn spawn_control_session(
     config: Config,
     root_agent: AgentRef,
@@ -2336,6 +3183,8 @@ fn spawn_control_session(
     library: SharedLibrary,
     mission_store: Arc<dyn MissionStore>,
     secrets: Option<Arc<SecretsStore>>,
+    backends: Arc<RwLock<BackendRegistry>>,
+    user_id: String,
 ) -> ControlState {
     let (cmd_tx, cmd_rx) = mpsc::channel::<ControlCommand>(256);
     let (events_tx, events_rx) = broadcast::channel::<AgentEvent>(1024);
@@ -2367,8 +3216,16 @@ fn spawn_control_session(
         progress: Arc::clone(&progress),
         running_missions: Arc::clone(&running_missions),
         max_parallel,
+        user_id: user_id.clone(),
+        mission_cache: Arc::new(super::mission_cache::MissionCache::new(
+            std::time::Duration::from_secs(config.mission_cache_ttl_secs),
+        )),
         mission_store: Arc::clone(&mission_store),
     };
+    tokio::spawn(super::mission_cache::invalidation_loop(
+        Arc::clone(&state.mission_cache),
+        events_tx.subscribe(),
+    ));
 
     // Spawn the main control actor
     tokio::spawn(control_actor_loop(
@@ -2389,6 +3246,8 @@ fn spawn_control_session(
         progress,
         mission_store,
         secrets,
+        backends,
+        user_id,
     ));
 
     // Recover orphaned missions from previous run.
@@ -2480,6 +3339,33 @@ fn spawn_control_session(
         });
     }
 
+    // Spawn mission title worker (model-written titles for untitled missions)
+    if super::mission_titles::auto_title_enabled() {
+        super::mission_titles::spawn_title_worker(
+            config.clone(),
+            Arc::clone(&state.mission_store),
+            events_tx.clone(),
+        );
+    }
+
+    // Spawn mission post-process worker (summary, artifacts, diff, user processors)
+    if super::mission_postprocess::postprocess_enabled() {
+        super::mission_postprocess::spawn_postprocess_worker(
+            config.clone(),
+            Arc::clone(&state.mission_store),
+            workspaces.clone(),
+            &events_tx,
+        );
+    }
+
+    // Spawn mission credentials worker (revokes credentials of stopped missions)
+    super::mission_credentials::spawn_revoke_worker(
+        &events_tx,
+        config
+            .working_dir
+            .join(".sandboxed-sh/mission_credentials.json"),
+    );
+
     // Spawn automation scheduler task
     if state.mission_store.is_persistent() && config.automations_enabled {
         tokio::spawn(automation_scheduler_loop(
@@ -2488,10 +3374,61 @@ fn spawn_control_session(
             state.cmd_tx.clone(),
             workspaces.clone(),
         ));
-    } else if state.mission_store.is_persistent() {
-        tracing::info!("Automation scheduler disabled by config");
+    } else if state.mission_store.is_persistent() {
+        tracing::info!("Automation scheduler disabled by config");
+    }
+
+    // Spawn daemon mission supervisor (trigger subscriptions of daemon missions)
+    if state.mission_store.is_persistent() && config.automations_enabled {
+        tokio::spawn(super::mission_daemon::daemon_supervisor_loop(
+            Arc::clone(&state.mission_store),
+            state.cmd_tx.clone(),
+            events_tx.clone(),
+            workspaces.clone(),
+        ));
+    }
+
+    // Spawn nightly maintenance scheduler (runs only while enabled in settings)
+    if state.mission_store.is_persistent() && config.automations_enabled {
+        tokio::spawn(super::maintenance::maintenance_scheduler_loop(
+            super::maintenance::MaintenanceContext {
+                config: config.clone(),
+                store: Arc::clone(&state.mission_store),
+                library: library.clone(),
+                workspaces: workspaces.clone(),
+                user_id: state.user_id.clone(),
+            },
+        ));
+    }
+
+    // Spawn spending alerts (run only while enabled in settings)
+    if state.mission_store.is_persistent() {
+        tokio::spawn(super::spending_alerts::spending_alert_loop(Arc::clone(
+            &state.mission_store,
+        )));
     }
 
+    // Spawn SLA monitor (missions past their soft deadline)
+    tokio::spawn(super::mission_sla::sla_monitor_loop(
+        Arc::clone(&state.mission_store),
+        state.cmd_tx.clone(),
+        events_tx.clone(),
+    ));
+
+    // Spawn step budget supervisor (missions over their turn/tool call limits)
+    tokio::spawn(super::step_budget::step_budget_loop(
+        Arc::clone(&state.mission_store),
+        state.cmd_tx.clone(),
+        events_tx.clone(),
+    ));
+
+    // Spawn tool loop monitor (missions repeating a failing tool call)
+    tokio::spawn(super::tool_loops::repeat_monitor_loop(
+        Arc::clone(&state.mission_store),
+        state.cmd_tx.clone(),
+        events_tx.clone(),
+    ));
+
     state
 }
 
@@ -2638,10 +3575,35 @@ async fn automation_scheduler_loop(
     tracing::info!("Automation scheduler task started");
 
     let mut logged_unsupported = false;
+    let mut paused_for_maintenance = false;
 
     loop {
         tokio::time::sleep(check_interval).await;
 
+        // Maintenance windows pause the scheduler entirely; running missions
+        // are left alone and nothing new is started until the window closes.
+        let (quiet_hours, maintenance_windows) = crate::settings::schedule_windows_cached();
+        let now = chrono::Utc::now();
+        if let Some(window) = active_maintenance(&maintenance_windows, now) {
+            if !paused_for_maintenance {
+                tracing::info!(
+                    "Automation scheduler paused for maintenance until {}{}",
+                    window.ends_at,
+                    window
+                        .reason
+                        .as_deref()
+                        .map(|r| format!(" ({})", r))
+                        .unwrap_or_default()
+                );
+                paused_for_maintenance = true;
+            }
+            continue;
+        } else if paused_for_maintenance {
+            tracing::info!("Maintenance window ended, automation scheduler resumed");
+            paused_for_maintenance = false;
+        }
+        let globally_quiet = in_quiet_hours(&quiet_hours, now);
+
         let automations = match mission_store.list_active_automations().await {
             Ok(automations) => automations,
             Err(e) => {
@@ -2653,6 +3615,18 @@ async fn automation_scheduler_loop(
             }
         };
 
+        if !globally_quiet {
+            dispatch_deferred_webhooks(
+                &mission_store,
+                &library,
+                &cmd_tx,
+                &workspaces,
+                &automations,
+                now,
+            )
+            .await;
+        }
+
         for automation in automations {
             // Only trigger interval-based automations (webhooks are triggered via HTTP endpoint)
             let interval_seconds = match &automation.trigger {
@@ -2736,6 +3710,18 @@ async fn automation_scheduler_loop(
                 continue;
             }
 
+            // Get workspace for reading local files
+            let workspace = workspaces.get(mission.workspace_id).await;
+
+            // During quiet hours the automation stays due and fires once the window ends.
+            if globally_quiet
+                || workspace
+                    .as_ref()
+                    .is_some_and(|ws| in_quiet_hours(&workspace::workspace_quiet_hours(ws), now))
+            {
+                continue;
+            }
+
             // Check if the mission is currently busy (has a running task or queued messages)
             let is_busy = {
                 let (tx, rx) = tokio::sync::oneshot::channel();
@@ -2770,9 +3756,6 @@ async fn automation_scheduler_loop(
                 continue;
             }
 
-            // Get workspace for reading local files
-            let workspace = workspaces.get(mission.workspace_id).await;
-
             // Fetch the command content based on the command source
             let command_content = match &automation.command_source {
                 CommandSource::Library { name } => {
@@ -2982,6 +3965,153 @@ async fn automation_scheduler_loop(
     }
 }
 
+/// Dispatch webhook executions that were deferred during quiet hours or a
+/// maintenance window, oldest first.
+async fn dispatch_deferred_webhooks(
+    mission_store: &Arc<dyn MissionStore>,
+    library: &SharedLibrary,
+    cmd_tx: &mpsc::Sender<ControlCommand>,
+    workspaces: &workspace::SharedWorkspaceStore,
+    automations: &[mission_store::Automation],
+    now: chrono::DateTime<chrono::Utc>,
+) {
+    use super::automation_variables::{substitute_variables, SubstitutionContext};
+    use super::mission_store::{CommandSource, ExecutionStatus, TriggerType};
+
+    for automation in automations {
+        if !matches!(automation.trigger, TriggerType::Webhook { .. }) {
+            continue;
+        }
+
+        let mut deferred: Vec<_> = match mission_store
+            .get_automation_executions(automation.id, Some(100))
+            .await
+        {
+            Ok(executions) => executions
+                .into_iter()
+                .filter(|e| {
+                    e.status == ExecutionStatus::Pending
+                        && e.trigger_source == "webhook"
+                        && e.webhook_payload.is_some()
+                })
+                .collect(),
+            Err(_) => continue,
+        };
+        if deferred.is_empty() {
+            continue;
+        }
+        deferred.sort_by(|a, b| a.triggered_at.cmp(&b.triggered_at));
+
+        let Ok(Some(mission)) = mission_store.get_mission(automation.mission_id).await else {
+            continue;
+        };
+        let workspace = workspaces.get(mission.workspace_id).await;
+        if workspace
+            .as_ref()
+            .is_some_and(|ws| in_quiet_hours(&workspace::workspace_quiet_hours(ws), now))
+        {
+            continue;
+        }
+
+        let command_content = match &automation.command_source {
+            CommandSource::Library { name } => {
+                let lib = library.read().await;
+                let Some(lib) = lib.as_ref() else {
+                    continue;
+                };
+                match lib.get_command(name).await {
+                    Ok(command) => automation_library_command_body(&command.content),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to fetch command '{}' for deferred webhook {}: {}",
+                            name,
+                            automation.id,
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            CommandSource::LocalFile { path } => {
+                let Some(ws) = workspace.as_ref() else {
+                    continue;
+                };
+                match tokio::fs::read_to_string(ws.path.join(path)).await {
+                    Ok(content) => content,
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to read file '{}' for deferred webhook {}: {}",
+                            path,
+                            automation.id,
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            CommandSource::Inline { content } => content.clone(),
+        };
+
+        for mut execution in deferred {
+            let mut context = SubstitutionContext::new(mission.id);
+            if let Some(ref title) = mission.title {
+                context = context.with_mission_name(title.clone());
+            }
+            if let Some(ws) = workspace.as_ref() {
+                context = context.with_working_directory(ws.path.to_string_lossy().to_string());
+            }
+            if let Some(payload) = execution.webhook_payload.clone() {
+                context = context.with_webhook_payload(payload);
+            }
+            context = context.with_custom_variables(execution.variables_used.clone());
+            let content = substitute_variables(&command_content, &context);
+
+            tracing::info!(
+                "Dispatching deferred webhook execution {} for automation {} (received {})",
+                execution.id,
+                automation.id,
+                execution.triggered_at
+            );
+
+            let (respond_tx, _respond_rx) = tokio::sync::oneshot::channel();
+            let send_result = cmd_tx
+                .send(ControlCommand::UserMessage {
+                    id: Uuid::new_v4(),
+                    content,
+                    agent: None,
+                    target_mission_id: Some(mission.id),
+                    respond: respond_tx,
+                })
+                .await;
+
+            match send_result {
+                Ok(_) => {
+                    execution.status = ExecutionStatus::Running;
+                    if let Err(e) = mission_store
+                        .update_automation_last_triggered(automation.id)
+                        .await
+                    {
+                        tracing::warn!("Failed to update automation last triggered time: {}", e);
+                    }
+                }
+                Err(e) => {
+                    execution.status = ExecutionStatus::Failed;
+                    execution.completed_at = Some(mission_store::now_string());
+                    execution.error = Some(format!("Failed to send message: {}", e));
+                }
+            }
+            let execution_id = execution.id;
+            if let Err(e) = mission_store.update_automation_execution(execution).await {
+                tracing::warn!(
+                    "Failed to update deferred execution {}: {}",
+                    execution_id,
+                    e
+                );
+            }
+        }
+    }
+}
+
 /// Keep automation library command execution consistent with `/command` usage:
 /// frontmatter is metadata and should never be injected into model prompts.
 fn automation_library_command_body(command_content: &str) -> String {
@@ -3257,6 +4387,8 @@ async fn control_actor_loop(
     progress: Arc<RwLock<ExecutionProgress>>,
     mission_store: Arc<dyn MissionStore>,
     secrets: Option<Arc<SecretsStore>>,
+    backends: Arc<RwLock<BackendRegistry>>,
+    user_id: String,
 ) {
     // Queue stores (id, content, agent, target_mission_id) for the current/primary mission
     // The target_mission_id tracks which mission each queued message is intended for
@@ -3638,6 +4770,11 @@ async fn control_actor_loop(
                                     });
                                     // Try to start if not already running
                                     if !runner.is_running() {
+                                        if let Ok(Some(m)) = mission_store.get_mission(tid).await {
+                                            runner.locale = m.locale;
+                                            runner.feature_flags = m.feature_flags;
+                                            runner.mode = m.mode;
+                                        }
                                         runner.start_next(
                                             config.clone(),
                                             Arc::clone(&root_agent),
@@ -3650,6 +4787,7 @@ async fn control_actor_loop(
                                             mission_cmd_tx.clone(),
                                             Arc::new(RwLock::new(Some(tid))),
                                             secrets.clone(),
+                                            Arc::clone(&backends),
                                         );
                                     }
                                     let _ = respond.send(was_running);
@@ -3682,6 +4820,15 @@ async fn control_actor_loop(
                                     });
                                     let _ = respond.send(false);
                                     continue;
+                                } else if let Err(e) = super::fair_share::admit(&user_id, tid) {
+                                    tracing::warn!("Cannot start parallel mission {}: {}", tid, e);
+                                    let _ = events_tx.send(AgentEvent::Error {
+                                        message: format!("Cannot start mission {}: {}", tid, e),
+                                        mission_id: Some(tid),
+                                        resumable: true,
+                                    });
+                                    let _ = respond.send(false);
+                                    continue;
                                 } else {
                                     // Load mission and start in parallel
                                     match load_mission_record(&mission_store, tid).await {
@@ -3719,6 +4866,9 @@ async fn control_actor_loop(
                                                 mission.model_override.clone(),
                                                 mission.model_effort.clone(),
                                             );
+                                            runner.locale = mission.locale.clone();
+                                            runner.feature_flags = mission.feature_flags.clone();
+                                            runner.mode = mission.mode;
                                             // Load existing history
                                             for entry in &mission.history {
                                                 runner.history.push((entry.role.clone(), entry.content.clone()));
@@ -3745,6 +4895,7 @@ async fn control_actor_loop(
                                                 mission_cmd_tx.clone(),
                                                 Arc::new(RwLock::new(Some(tid))),
                                                 secrets.clone(),
+                                                Arc::clone(&backends),
                                             );
                                             tracing::info!("Auto-started mission {} in parallel", tid);
                                             parallel_runners.insert(tid, runner);
@@ -3752,6 +4903,7 @@ async fn control_actor_loop(
                                             continue;
                                         }
                                         Err(e) => {
+                                            super::fair_share::release(tid);
                                             tracing::error!(
                                                 "Failed to load mission {} for parallel: {}. \
                                                  Dropping targeted message to avoid sending to wrong mission.",
@@ -3951,6 +5103,7 @@ async fn control_actor_loop(
                                 let mcp_ref = Arc::clone(&mcp);
                                 let workspaces_ref = Arc::clone(&workspaces);
                                 let library_ref = Arc::clone(&library);
+                                let backends_ref = Arc::clone(&backends);
                                 let events = events_tx.clone();
                                 let tools_hub = Arc::clone(&tool_hub);
                                 let status_ref = Arc::clone(&status);
@@ -3965,7 +5118,7 @@ async fn control_actor_loop(
                                 // Use the mission ID that was captured when message was queued
                                 // This prevents race conditions where current_mission changes between queueing and execution
                                 let mission_id = msg_target_mid;
-                                let (workspace_id, model_override, model_effort, mission_agent, backend_id, session_id, mission_config_profile) = if let Some(mid) = mission_id {
+                                let (workspace_id, model_override, model_effort, mission_agent, backend_id, session_id, mission_config_profile, mission_locale) = if let Some(mid) = mission_id {
                                     match mission_store.get_mission(mid).await {
                                         Ok(Some(mission)) => {
                                             // Activate mission: if pending, interrupted, blocked, completed, or failed, update status to active
@@ -4000,6 +5153,7 @@ async fn control_actor_loop(
                                                 Some(mission.backend.clone()),
                                                 mission.session_id.clone(),
                                                 mission.config_profile.clone(),
+                                                mission.locale.clone(),
                                             )
                                         }
                                         Ok(None) => {
@@ -4007,7 +5161,7 @@ async fn control_actor_loop(
                                                 "Mission {} not found while resolving workspace",
                                                 mid
                                             );
-                                            (None, None, None, None, None, None, None)
+                                            (None, None, None, None, None, None, None, None)
                                         }
                                         Err(e) => {
                                             tracing::warn!(
@@ -4015,11 +5169,11 @@ async fn control_actor_loop(
                                                 mid,
                                                 e
                                             );
-                                            (None, None, None, None, None, None, None)
+                                            (None, None, None, None, None, None, None, None)
                                         }
                                     }
                                 } else {
-                                    (None, None, None, None, None, None, None)
+                                    (None, None, None, None, None, None, None, None)
                                 };
                                 // Per-message agent overrides mission agent
                                 let agent_override = per_msg_agent.or(mission_agent);
@@ -4054,6 +5208,8 @@ async fn control_actor_loop(
                                         session_id,
                                         false, // force_session_resume: regular message, not a resume
                                         mission_config_profile,
+                                        mission_locale,
+                                        backends_ref,
                                     )
                                     .await;
                                     (mid, msg, result)
@@ -4232,6 +5388,10 @@ async fn control_actor_loop(
                                     continue;
                                 }
                             };
+                            if let Err(e) = super::fair_share::admit(&user_id, mission_id) {
+                                let _ = respond.send(Err(e));
+                                continue;
+                            }
 
                             // Create a new MissionRunner
                             let mut runner = super::mission_runner::MissionRunner::new(
@@ -4244,6 +5404,9 @@ async fn control_actor_loop(
                                 mission.model_override.clone(),
                                 mission.model_effort.clone(),
                             );
+                            runner.locale = mission.locale.clone();
+                            runner.feature_flags = mission.feature_flags.clone();
+                            runner.mode = mission.mode;
 
                             // Load existing history into runner to preserve conversation context
                             for entry in &mission.history {
@@ -4266,6 +5429,7 @@ async fn control_actor_loop(
                                 mission_cmd_tx.clone(),
                                 Arc::new(RwLock::new(Some(mission_id))), // Each runner tracks its own mission
                                 secrets.clone(),
+                                Arc::clone(&backends),
                             );
 
                             if started {
@@ -4273,6 +5437,7 @@ async fn control_actor_loop(
                                 entry.insert(runner);
                                 let _ = respond.send(Ok(()));
                             } else {
+                                super::fair_share::release(mission_id);
                                 let _ = respond.send(Err("Failed to start mission execution".to_string()));
                             }
                         } else {
@@ -4308,6 +5473,7 @@ async fn control_actor_loop(
                                 summary: None,
                             });
                             parallel_runners.remove(&mission_id);
+                            super::fair_share::release(mission_id);
                             close_mission_desktop_sessions(
                                 &mission_store,
                                 mission_id,
@@ -4341,6 +5507,62 @@ async fn control_actor_loop(
                             }
                         }
                     }
+                    ControlCommand::PreemptMission { mission_id, reason, respond } => {
+                        let Some(runner) = parallel_runners.get_mut(&mission_id) else {
+                            let _ = respond.send(Err(format!(
+                                "Mission {} is not running in parallel",
+                                mission_id
+                            )));
+                            continue;
+                        };
+                        runner.cancel();
+                        parallel_runners.remove(&mission_id);
+                        super::fair_share::release(mission_id);
+                        close_mission_desktop_sessions(&mission_store, mission_id, &config.working_dir)
+                            .await;
+
+                        // Checkpoint the workspace so the resumed run can be
+                        // compared with (or rolled back to) where it stopped.
+                        if let Ok(mission) = load_mission_record(&mission_store, mission_id).await {
+                            let workspace_root = workspace::resolve_workspace_root(
+                                &workspaces,
+                                &config,
+                                Some(mission.workspace_id),
+                            )
+                            .await;
+                            let mission_dir =
+                                workspace::mission_workspace_dir_for_root(&workspace_root, mission_id);
+                            if let Err(e) = crate::workspace_snapshot::record_checkpoint(&mission_dir).await {
+                                tracing::warn!(mission_id = %mission_id, "Failed to checkpoint preempted mission: {}", e);
+                            }
+                        }
+
+                        if let Err(e) = mission_store
+                            .update_mission_status_with_reason(
+                                mission_id,
+                                MissionStatus::Interrupted,
+                                Some(super::mission_priority::PREEMPTED_REASON),
+                            )
+                            .await
+                        {
+                            tracing::warn!("Failed to update preempted mission status: {}", e);
+                        }
+                        let _ = events_tx.send(AgentEvent::MissionStatusChanged {
+                            mission_id,
+                            status: MissionStatus::Interrupted,
+                            summary: Some(reason),
+                        });
+                        let result = resume_mission_impl(
+                            &mission_store,
+                            &config,
+                            &workspaces,
+                            mission_id,
+                            false,
+                        )
+                        .await
+                        .map(|(_, resume_prompt)| resume_prompt);
+                        let _ = respond.send(result);
+                    }
                     ControlCommand::ListRunning { respond } => {
                         // Return info about currently running missions
                         let mut running_list = Vec::new();
@@ -4455,6 +5677,7 @@ async fn control_actor_loop(
                                         let mcp_ref = Arc::clone(&mcp);
                                         let workspaces_ref = Arc::clone(&workspaces);
                                         let library_ref = Arc::clone(&library);
+                                        let backends_ref = Arc::clone(&backends);
                                         let events = events_tx.clone();
                                         let tools_hub = Arc::clone(&tool_hub);
                                         let status_ref = Arc::clone(&status);
@@ -4474,6 +5697,7 @@ async fn control_actor_loop(
                                         let agent_override = mission.agent.clone();
                                         let session_id = mission.session_id.clone();
                                         let mission_config_profile = mission.config_profile.clone();
+                                        let mission_locale = mission.locale.clone();
                                         running_cancel = Some(cancel.clone());
                                         // Capture which mission this task is working on (the resumed mission)
                                         running_mission_id = Some(mission_id);
@@ -4506,6 +5730,8 @@ async fn control_actor_loop(
                                                 session_id,
                                                 true, // force_session_resume: this is a resume operation
                                                 mission_config_profile,
+                                                mission_locale,
+                                                backends_ref,
                                             )
                                             .await;
                                             (mid, msg, result)
@@ -4688,6 +5914,102 @@ async fn control_actor_loop(
                         tracing::info!("Cleared {} total queued messages (main + parallel)", cleared);
                         let _ = respond.send(cleared);
                     }
+                    ControlCommand::SetMissionModel { mission_id, model, respond } => {
+                        let result = mission_store
+                            .update_mission_model_override(mission_id, Some(&model))
+                            .await;
+                        if result.is_ok() {
+                            if let Some(runner) = parallel_runners.get_mut(&mission_id) {
+                                runner.model_override = Some(model.clone());
+                            }
+                            tracing::info!(mission_id = %mission_id, model = %model, "Mission model switched");
+                        }
+                        let _ = respond.send(result);
+                    }
+                    ControlCommand::PrioritizeMission { mission_id, respond } => {
+                        let (mut prioritized, rest): (VecDeque<_>, VecDeque<_>) = queue
+                            .drain(..)
+                            .partition(|(_, _, _, target)| *target == Some(mission_id));
+                        let moved = prioritized.len();
+                        prioritized.extend(rest);
+                        queue = prioritized;
+                        if moved > 0 {
+                            tracing::info!(mission_id = %mission_id, moved, "Moved queued messages to the front");
+                        }
+                        let _ = respond.send(moved);
+                    }
+                    ControlCommand::CompactHistory { mission_id, summary, kept, respond } => {
+                        let main_busy = running.is_some() && running_mission_id == Some(mission_id);
+                        let parallel_busy = parallel_runners
+                            .get(&mission_id)
+                            .is_some_and(|r| r.is_running());
+                        if main_busy || parallel_busy {
+                            let _ = respond.send(Err(
+                                "Mission is running; compact it once the current turn finishes"
+                                    .to_string(),
+                            ));
+                            continue;
+                        }
+                        let mission = match load_mission_record(&mission_store, mission_id).await {
+                            Ok(m) => m,
+                            Err(e) => {
+                                let _ = respond.send(Err(e));
+                                continue;
+                            }
+                        };
+                        let is_current = *current_mission.read().await == Some(mission_id);
+                        let source: Vec<(String, String)> = if let Some(runner) = parallel_runners.get(&mission_id) {
+                            runner.history.clone()
+                        } else if is_current && !history.is_empty() {
+                            history.clone()
+                        } else {
+                            mission
+                                .history
+                                .iter()
+                                .map(|e| (e.role.clone(), e.content.clone()))
+                                .collect()
+                        };
+                        let Some(compacted) = super::mission_compact::apply(&source, &summary, &kept) else {
+                            let _ = respond.send(Err(
+                                "Mission history changed since it was read; try again".to_string(),
+                            ));
+                            continue;
+                        };
+
+                        if let Some(runner) = parallel_runners.get_mut(&mission_id) {
+                            runner.history = compacted.clone();
+                        }
+                        if is_current {
+                            history = compacted.clone();
+                        }
+                        persist_mission_history_to(&mission_store, Some(mission_id), &compacted).await;
+
+                        // Claude Code keeps its own transcript per session; start a
+                        // fresh one so the next turn only sees the compacted context.
+                        if mission.backend == "claudecode" {
+                            let session_id = Uuid::new_v4().to_string();
+                            if let Err(e) = mission_store
+                                .update_mission_session_id(mission_id, &session_id)
+                                .await
+                            {
+                                tracing::warn!("Failed to reset session for compacted mission {}: {}", mission_id, e);
+                            }
+                            if let Some(runner) = parallel_runners.get_mut(&mission_id) {
+                                runner.session_id = Some(session_id);
+                            }
+                        }
+
+                        let _ = events_tx.send(AgentEvent::HistoryCompacted {
+                            summary,
+                            compacted: source.len() - kept.len(),
+                            kept: kept.len(),
+                            mission_id,
+                        });
+                        let _ = respond.send(Ok(compacted
+                            .into_iter()
+                            .map(|(role, content)| MissionHistoryEntry { role, content })
+                            .collect()));
+                    }
                 }
             }
             // Handle agent-initiated mission status changes (from complete_mission tool)
@@ -4994,6 +6316,7 @@ async fn control_actor_loop(
                                 model_normalized: model_used
                                     .as_deref()
                                     .map(crate::cost::normalized_model),
+                                chat_options: agent_result.chat_options.clone(),
                                 mission_id: completed_mission_id,
                                 shared_files,
                                 resumable,
@@ -5123,6 +6446,7 @@ async fn control_actor_loop(
                     let mcp_ref = Arc::clone(&mcp);
                     let workspaces_ref = Arc::clone(&workspaces);
                     let library_ref = Arc::clone(&library);
+                    let backends_ref = Arc::clone(&backends);
                     let events = events_tx.clone();
                     let tools_hub = Arc::clone(&tool_hub);
                     let status_ref = Arc::clone(&status);
@@ -5138,7 +6462,7 @@ async fn control_actor_loop(
                     // Use the mission ID that was captured when message was queued
                     // This prevents race conditions where current_mission changes between queueing and execution
                     let mission_id = msg_target_mid;
-                    let (workspace_id, model_override, model_effort, mission_agent, backend_id, session_id, mission_config_profile) = if let Some(mid) = mission_id {
+                    let (workspace_id, model_override, model_effort, mission_agent, backend_id, session_id, mission_config_profile, mission_locale) = if let Some(mid) = mission_id {
                         match mission_store.get_mission(mid).await {
                             Ok(Some(mission)) => (
                                 Some(mission.workspace_id),
@@ -5148,13 +6472,14 @@ async fn control_actor_loop(
                                 Some(mission.backend.clone()),
                                 mission.session_id.clone(),
                                 mission.config_profile.clone(),
+                                mission.locale.clone(),
                             ),
                             Ok(None) => {
                                 tracing::warn!(
                                     "Mission {} not found while resolving workspace",
                                     mid
                                 );
-                                (None, None, None, None, None, None, None)
+                                (None, None, None, None, None, None, None, None)
                             }
                             Err(e) => {
                                 tracing::warn!(
@@ -5162,11 +6487,11 @@ async fn control_actor_loop(
                                     mid,
                                     e
                                 );
-                                (None, None, None, None, None, None, None)
+                                (None, None, None, None, None, None, None, None)
                             }
                         }
                     } else {
-                        (None, None, None, None, None, None, None)
+                        (None, None, None, None, None, None, None, None)
                     };
                     // Per-message agent overrides mission agent
                     let agent_override = per_msg_agent.or(mission_agent);
@@ -5200,6 +6525,8 @@ async fn control_actor_loop(
                             session_id,
                             false, // force_session_resume: continuation turn, not a resume
                             mission_config_profile,
+                            mission_locale,
+                            backends_ref,
                         )
                         .await;
                         (mid, msg, result)
@@ -5267,6 +6594,7 @@ async fn control_actor_loop(
                                     .model_used
                                     .as_deref()
                                     .map(crate::cost::normalized_model),
+                                chat_options: result.chat_options.clone(),
                                 mission_id: Some(*mission_id),
                                 shared_files,
                                 resumable,
@@ -5341,6 +6669,10 @@ async fn control_actor_loop(
                                 // SessionIdUpdate event hasn't been processed yet
                                 // (race between the events_rx and sleep poll arms).
                                 if let Ok(Some(m)) = mission_store.get_mission(*mission_id).await {
+                                    // Pick up locale changes made while the runner was idle.
+                                    runner.locale = m.locale.clone();
+                                    runner.feature_flags = m.feature_flags.clone();
+                                    runner.mode = m.mode;
                                     if m.session_id != runner.session_id {
                                         tracing::debug!(
                                             mission_id = %mission_id,
@@ -5363,6 +6695,7 @@ async fn control_actor_loop(
                                     mission_cmd_tx.clone(),
                                     Arc::new(RwLock::new(Some(*mission_id))),
                                     secrets.clone(),
+                                    Arc::clone(&backends),
                                 );
 
                                 // If no queued messages, update status and mark for cleanup
@@ -5415,6 +6748,7 @@ async fn control_actor_loop(
                 // Remove completed runners and clean up their desktop sessions
                 for mid in completed_missions {
                     parallel_runners.remove(&mid);
+                    super::fair_share::release(mid);
                     close_mission_desktop_sessions(
                         &mission_store,
                         mid,
@@ -5441,6 +6775,8 @@ async fn control_actor_loop(
                         AgentEvent::AgentTree { mission_id, .. } => *mission_id,
                         AgentEvent::Progress { mission_id, .. } => *mission_id,
                         AgentEvent::MissionActivity { mission_id, .. } => *mission_id,
+                        AgentEvent::DependencyAdded { mission_id, .. } => Some(*mission_id),
+                        AgentEvent::SecurityEvent { mission_id, .. } => Some(*mission_id),
                         AgentEvent::SessionIdUpdate { mission_id, .. } => Some(*mission_id),
                         _ => None,
                     };
@@ -5511,6 +6847,14 @@ async fn control_actor_loop(
                                     }
                                 }
 
+                                if let Some(tool) = crate::tools::plan::plan_tool(name) {
+                                    apply_plan_call(&mission_store, &events_tx, *mid, tool, args)
+                                        .await;
+                                }
+                                if crate::tools::audit::is_report_tool(name) {
+                                    store_audit_report(&mission_store, *mid, args).await;
+                                }
+
                                 // Desktop session detection from ToolCall.
                                 // Claude Code and Amp don't emit ToolResult for MCP tools,
                                 // so we detect the session start from the ToolCall and
@@ -5800,6 +7144,8 @@ async fn run_single_control_turn(
     session_id: Option<String>,
     force_session_resume: bool,
     mission_config_profile: Option<String>,
+    mission_locale: Option<crate::locale::LocaleSettings>,
+    backends: Arc<RwLock<BackendRegistry>>,
 ) -> crate::agents::AgentResult {
     let is_claudecode = backend_id.as_deref() == Some("claudecode");
     // Get config profile: mission's config_profile takes priority over workspace's
@@ -5831,7 +7177,8 @@ async fn run_single_control_turn(
     }
     // Ensure a workspace directory for this mission (if applicable).
     let (working_dir_path, runtime_workspace) = if let Some(mid) = mission_id {
-        let ws = workspace::resolve_workspace(&workspaces, &config, workspace_id).await;
+        let mut ws = workspace::resolve_workspace(&workspaces, &config, workspace_id).await;
+        let server_settings = super::mission_guard::prepare_turn(mid, &mut ws);
         if let Err(e) =
             workspace::sync_workspace_mcp_binaries_for_workspace(&config.working_dir, &ws).await
         {
@@ -5855,7 +7202,20 @@ async fn run_single_control_turn(
         ))
         .await
         {
-            Ok(dir) => dir,
+            Ok(dir) => {
+                if ws.workspace_type == workspace::WorkspaceType::Host {
+                    let tool_user = server_settings
+                        .get(crate::tools::host_access::TOOL_USER_SETTING)
+                        .map(String::as_str);
+                    if let Err(e) = crate::tools::host_access::grant_mission_dir(&dir, tool_user) {
+                        tracing::warn!(
+                            "Failed to hand the mission directory to the tool user: {}",
+                            e
+                        );
+                    }
+                }
+                dir
+            }
             Err(e) => {
                 tracing::warn!("Failed to prepare mission workspace: {}", e);
                 ws.path.clone()
@@ -5899,6 +7259,12 @@ async fn run_single_control_turn(
     convo.push_str("User:\n");
     convo.push_str(&user_message);
     convo.push_str("\n\nInstructions:\n- Continue the conversation helpfully.\n- Use available tools as needed.\n- For large data processing tasks (>10KB), prefer executing scripts rather than inline processing.\n");
+    if let Some(locale) = crate::locale::LocaleSettings::resolve(mission_locale.as_ref())
+        .prompt_instructions(chrono::Utc::now())
+    {
+        convo.push_str(&locale);
+        convo.push('\n');
+    }
     let _task = match crate::task::Task::new(convo.clone(), Some(1000)) {
         Ok(t) => t,
         Err(e) => {
@@ -5936,10 +7302,25 @@ async fn run_single_control_turn(
             // where the session exists but history may not have assistant messages yet).
             let is_continuation =
                 force_session_resume || history.iter().any(|(role, _)| role == "assistant");
+            // A fresh session after compaction starts from the compacted history.
+            let fresh_session = !matches!(
+                session_id.as_deref(),
+                Some(sid) if super::mission_runner::claude_session_initiated(&ctx.working_dir, sid)
+            );
+            let first_message = fresh_session
+                .then(|| {
+                    super::mission_compact::fresh_session_message(
+                        &history,
+                        &user_message,
+                        config.context.max_history_total_chars,
+                    )
+                })
+                .flatten()
+                .unwrap_or_else(|| user_message.clone());
             let mut result = Box::pin(super::mission_runner::run_claudecode_turn(
                 exec_workspace,
                 &ctx.working_dir,
-                &user_message,
+                &first_message,
                 config.default_model.as_deref(),
                 config.opencode_agent.as_deref(),
                 mid,
@@ -6064,6 +7445,7 @@ async fn run_single_control_turn(
                 exec_workspace,
                 &ctx.working_dir,
                 &convo,
+                &user_message,
                 requested_model
                     .as_deref()
                     .or(config.default_model.as_deref()),
@@ -6079,13 +7461,40 @@ async fn run_single_control_turn(
             .await
         }
         Some(backend) if backend != "opencode" => {
-            let _ = events_tx.send(AgentEvent::Error {
-                message: format!("Unsupported backend: {}", backend),
-                mission_id,
-                resumable: mission_id.is_some(),
-            });
-            crate::agents::AgentResult::failure(format!("Unsupported backend: {}", backend), 0)
-                .with_terminal_reason(TerminalReason::LlmError)
+            let plugin = backends.read().await.plugin(backend);
+            match plugin {
+                Some(plugin) => {
+                    let mid = match require_mission_id(mission_id, plugin.name(), &events_tx) {
+                        Ok(id) => id,
+                        Err(r) => return r,
+                    };
+                    Box::pin(super::mission_runner::run_plugin_turn(
+                        plugin,
+                        &ctx.working_dir,
+                        &convo,
+                        &user_message,
+                        requested_model.as_deref(),
+                        config.opencode_agent.as_deref(),
+                        mid,
+                        events_tx.clone(),
+                        cancel,
+                        session_id.as_deref(),
+                    ))
+                    .await
+                }
+                None => {
+                    let _ = events_tx.send(AgentEvent::Error {
+                        message: format!("Unsupported backend: {}", backend),
+                        mission_id,
+                        resumable: mission_id.is_some(),
+                    });
+                    crate::agents::AgentResult::failure(
+                        format!("Unsupported backend: {}", backend),
+                        0,
+                    )
+                    .with_terminal_reason(TerminalReason::LlmError)
+                }
+            }
         }
         _ => {
             // Default to opencode using per-workspace CLI execution
@@ -6093,6 +7502,7 @@ async fn run_single_control_turn(
             Box::pin(super::mission_runner::run_opencode_turn(
                 exec_workspace,
                 &ctx.working_dir,
+                &convo,
                 &user_message,
                 config.default_model.as_deref(),
                 requested_model_effort.as_deref(),
@@ -6101,6 +7511,7 @@ async fn run_single_control_turn(
                 events_tx.clone(),
                 cancel,
                 &config.working_dir,
+                session_id.as_deref(),
             ))
             .await
         }
@@ -6110,7 +7521,7 @@ async fn run_single_control_turn(
 
 // === Automation API handlers ===
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateAutomationRequest {
     pub command_source: mission_store::CommandSource,
     pub trigger: mission_store::TriggerType,
@@ -6679,6 +8090,42 @@ pub async fn webhook_receiver(
     merged_vars.extend(direct_vars);
     context = context.with_custom_variables(merged_vars.clone());
 
+    // During quiet hours or a maintenance window, store the payload as a pending
+    // execution; the automation scheduler dispatches it once the window ends.
+    let (quiet_hours, maintenance_windows) = crate::settings::schedule_windows_cached();
+    let now = chrono::Utc::now();
+    let deferred = active_maintenance(&maintenance_windows, now).is_some()
+        || in_quiet_hours(&quiet_hours, now)
+        || workspace
+            .as_ref()
+            .is_some_and(|ws| in_quiet_hours(&workspace::workspace_quiet_hours(ws), now));
+    if deferred {
+        let execution = AutomationExecution {
+            id: Uuid::new_v4(),
+            automation_id: automation.id,
+            mission_id: mission.id,
+            triggered_at: mission_store::now_string(),
+            trigger_source: "webhook".to_string(),
+            status: ExecutionStatus::Pending,
+            webhook_payload: Some(payload),
+            variables_used: merged_vars,
+            completed_at: None,
+            error: None,
+            retry_count: 0,
+        };
+        control
+            .mission_store
+            .create_automation_execution(execution)
+            .await
+            .map_err(internal_error)?;
+        tracing::info!(
+            "Webhook {} for automation {} deferred (quiet hours or maintenance window)",
+            webhook_id,
+            automation.id
+        );
+        return Ok(StatusCode::ACCEPTED);
+    }
+
     // Apply variable substitution
     let substituted_content = substitute_variables(&command_content, &context);
 
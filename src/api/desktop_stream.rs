@@ -21,6 +21,7 @@ use tokio::sync::mpsc;
 
 use super::auth;
 use super::routes::AppState;
+use crate::config::Role;
 
 /// Query parameters for the desktop stream endpoint
 #[derive(Debug, Deserialize)]
@@ -62,7 +63,7 @@ pub async fn desktop_stream_ws(
             Some(t) => t,
             None => return (StatusCode::UNAUTHORIZED, "Missing websocket JWT").into_response(),
         };
-        if !auth::verify_token_for_config(&token, &state.config) {
+        if !auth::verify_token_for_config(&token, &state.config, Role::Operator) {
             return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response();
         }
     }
@@ -0,0 +1,404 @@
+//! Bulk export of mission events to object storage.
+//!
+//! The `export_events` maintenance step uploads events older than
+//! `event_export.export_after_days` to an S3-compatible bucket (see
+//! `object_storage`), partitioned Hive-style so DuckDB, Athena or Spark can
+//! read the prefix as one table:
+//!
+//! ```text
+//! {prefix}/events/tenant={user}/date=2026-01-15/part-000000001201-000000004388.jsonl
+//! ```
+//!
+//! Objects are JSON Lines (one `StoredEvent` per line) or Parquet with one
+//! column per `StoredEvent` field (`metadata` as a JSON string). Every upload
+//! is recorded in a per-user manifest under the working directory, served at
+//! `GET /api/control/events/exports`; the highest exported event ID is the
+//! cursor of the next run, so each event is exported once. Pruning
+//! (`prune_events`) only runs after a successful export.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::auth::AuthUser;
+use super::mission_store::{now_string, MissionStore, StoredEvent};
+use super::routes::AppState;
+use crate::object_storage::{Bucket, Credentials};
+use crate::settings::{EventExportFormat, EventExportSettings};
+
+/// Events read from the store per page.
+const PAGE_SIZE: usize = 5_000;
+
+/// Manifest directory, relative to the server working directory.
+const MANIFEST_DIR: &str = ".sandboxed-sh/event_exports";
+
+/// One uploaded object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportRecord {
+    pub key: String,
+    /// `s3://bucket/key`
+    pub uri: String,
+    pub tenant: String,
+    /// Event date (UTC) of the partition.
+    pub date: String,
+    pub format: EventExportFormat,
+    pub events: usize,
+    pub first_event_id: i64,
+    pub last_event_id: i64,
+    pub bytes: usize,
+    pub exported_at: String,
+}
+
+fn manifest_path(root: &Path, tenant: &str) -> PathBuf {
+    let name: String = tenant
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    root.join(MANIFEST_DIR).join(format!("{}.jsonl", name))
+}
+
+/// Objects exported so far for a user, oldest first.
+pub fn read_manifest(root: &Path, tenant: &str) -> Vec<ExportRecord> {
+    std::fs::read_to_string(manifest_path(root, tenant))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn append_manifest(root: &Path, record: &ExportRecord) -> Result<(), String> {
+    let path = manifest_path(root, &record.tenant);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+fn object_key(
+    prefix: &str,
+    tenant: &str,
+    date: &str,
+    events: &[&StoredEvent],
+    format: EventExportFormat,
+) -> String {
+    let ext = match format {
+        EventExportFormat::Jsonl => "jsonl",
+        EventExportFormat::Parquet => "parquet",
+    };
+    let prefix = prefix.trim_matches('/');
+    let path = format!(
+        "events/tenant={}/date={}/part-{:012}-{:012}.{}",
+        tenant,
+        date,
+        events.first().map_or(0, |e| e.id),
+        events.last().map_or(0, |e| e.id),
+        ext
+    );
+    if prefix.is_empty() {
+        path
+    } else {
+        format!("{}/{}", prefix, path)
+    }
+}
+
+/// Group events by the UTC date of their timestamp.
+fn partition(events: &[StoredEvent]) -> BTreeMap<String, Vec<&StoredEvent>> {
+    let mut partitions: BTreeMap<String, Vec<&StoredEvent>> = BTreeMap::new();
+    for event in events {
+        let date = chrono::DateTime::parse_from_rfc3339(&event.timestamp)
+            .map(|t| t.with_timezone(&Utc).format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        partitions.entry(date).or_default().push(event);
+    }
+    partitions
+}
+
+fn encode_jsonl(events: &[&StoredEvent]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut out, event).map_err(|e| e.to_string())?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+/// Values of one Parquet column; text columns carry definition levels when
+/// optional.
+enum Column {
+    Int(Vec<i64>),
+    Text(Vec<parquet::data_type::ByteArray>, Option<Vec<i16>>),
+}
+
+fn required(events: &[&StoredEvent], value: impl Fn(&StoredEvent) -> String) -> Column {
+    Column::Text(
+        events
+            .iter()
+            .map(|e| value(e).into_bytes().into())
+            .collect(),
+        None,
+    )
+}
+
+fn optional(events: &[&StoredEvent], value: impl Fn(&StoredEvent) -> Option<&str>) -> Column {
+    let values = events.iter().map(|e| value(e));
+    Column::Text(
+        values
+            .clone()
+            .flatten()
+            .map(|v| v.as_bytes().to_vec().into())
+            .collect(),
+        Some(values.map(|v| i16::from(v.is_some())).collect()),
+    )
+}
+
+fn encode_parquet(events: &[&StoredEvent]) -> Result<Vec<u8>, String> {
+    use parquet::data_type::{ByteArrayType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    let schema = parse_message_type(
+        "message event {
+            REQUIRED INT64 id;
+            REQUIRED BYTE_ARRAY mission_id (UTF8);
+            REQUIRED INT64 sequence;
+            REQUIRED BYTE_ARRAY event_type (UTF8);
+            REQUIRED BYTE_ARRAY timestamp (UTF8);
+            OPTIONAL BYTE_ARRAY event_id (UTF8);
+            OPTIONAL BYTE_ARRAY tool_call_id (UTF8);
+            OPTIONAL BYTE_ARRAY tool_name (UTF8);
+            REQUIRED BYTE_ARRAY content (UTF8);
+            REQUIRED BYTE_ARRAY metadata (UTF8);
+        }",
+    )
+    .map_err(|e| e.to_string())?;
+    let columns = [
+        Column::Int(events.iter().map(|e| e.id).collect()),
+        required(events, |e| e.mission_id.to_string()),
+        Column::Int(events.iter().map(|e| e.sequence).collect()),
+        required(events, |e| e.event_type.clone()),
+        required(events, |e| e.timestamp.clone()),
+        optional(events, |e| e.event_id.as_deref()),
+        optional(events, |e| e.tool_call_id.as_deref()),
+        optional(events, |e| e.tool_name.as_deref()),
+        required(events, |e| e.content.clone()),
+        required(events, |e| e.metadata.to_string()),
+    ];
+
+    let props = WriterProperties::builder()
+        .set_compression(parquet::basic::Compression::SNAPPY)
+        .build();
+    let mut out = Vec::new();
+    let mut writer = SerializedFileWriter::new(&mut out, Arc::new(schema), Arc::new(props))
+        .map_err(|e| e.to_string())?;
+    let mut group = writer.next_row_group().map_err(|e| e.to_string())?;
+    for values in &columns {
+        let mut column = group
+            .next_column()
+            .map_err(|e| e.to_string())?
+            .ok_or("schema has fewer columns than the export")?;
+        match values {
+            Column::Int(values) => column.typed::<Int64Type>().write_batch(values, None, None),
+            Column::Text(values, levels) => {
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(values, levels.as_deref(), None)
+            }
+        }
+        .map_err(|e| e.to_string())?;
+        column.close().map_err(|e| e.to_string())?;
+    }
+    group.close().map_err(|e| e.to_string())?;
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// Upload events older than `export_after_days` that were not exported yet.
+/// Returns a summary for the maintenance report.
+pub async fn export_events(
+    store: &Arc<dyn MissionStore>,
+    root: &Path,
+    tenant: &str,
+    settings: &EventExportSettings,
+) -> Result<String, String> {
+    settings.validate()?;
+    let bucket = Bucket {
+        endpoint: settings.endpoint.clone().unwrap_or_default(),
+        name: settings.bucket.clone().unwrap_or_default(),
+        region: settings.region.trim().to_string(),
+        credentials: Credentials::from_env()?,
+    };
+    let client = reqwest::Client::new();
+    let cutoff =
+        (Utc::now() - chrono::Duration::days(settings.export_after_days as i64)).to_rfc3339();
+    let mut cursor = read_manifest(root, tenant)
+        .iter()
+        .map(|r| r.last_event_id)
+        .max()
+        .unwrap_or(0);
+    let (mut exported, mut objects) = (0, 0);
+    loop {
+        let page = store.get_events_before(&cutoff, cursor, PAGE_SIZE).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        let page_end = last.id;
+        for (date, events) in partition(&page) {
+            let body = match settings.format {
+                EventExportFormat::Jsonl => encode_jsonl(&events)?,
+                EventExportFormat::Parquet => encode_parquet(&events)?,
+            };
+            let key = object_key(&settings.prefix, tenant, &date, &events, settings.format);
+            let content_type = match settings.format {
+                EventExportFormat::Jsonl => "application/x-ndjson",
+                EventExportFormat::Parquet => "application/vnd.apache.parquet",
+            };
+            let bytes = body.len();
+            bucket.put_object(&client, &key, body, content_type).await?;
+            append_manifest(
+                root,
+                &ExportRecord {
+                    uri: bucket.uri(&key),
+                    key,
+                    tenant: tenant.to_string(),
+                    date,
+                    format: settings.format,
+                    events: events.len(),
+                    first_event_id: events.first().map_or(0, |e| e.id),
+                    last_event_id: events.last().map_or(0, |e| e.id),
+                    bytes,
+                    exported_at: now_string(),
+                },
+            )?;
+            exported += events.len();
+            objects += 1;
+        }
+        cursor = page_end;
+    }
+    Ok(format!(
+        "exported {} events older than {} days in {} objects",
+        exported, settings.export_after_days, objects
+    ))
+}
+
+/// GET /api/control/events/exports - Objects exported for the current user.
+pub async fn get_event_exports(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<ExportRecord>>, (StatusCode, String)> {
+    Ok(Json(read_manifest(&state.config.working_dir, &user.id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn event(id: i64, timestamp: &str, tool_name: Option<&str>) -> StoredEvent {
+        StoredEvent {
+            id,
+            mission_id: Uuid::nil(),
+            sequence: id,
+            event_type: "tool_call".to_string(),
+            timestamp: timestamp.to_string(),
+            event_id: None,
+            tool_call_id: None,
+            tool_name: tool_name.map(str::to_string),
+            content: format!("event {}", id),
+            metadata: serde_json::json!({"n": id}),
+        }
+    }
+
+    #[test]
+    fn partitions_by_utc_date_and_names_objects() {
+        let events = vec![
+            event(1, "2026-01-15T23:30:00-02:00", None),
+            event(2, "2026-01-15T10:00:00+00:00", None),
+            event(3, "2026-01-16T01:00:00+00:00", None),
+        ];
+        let partitions = partition(&events);
+        assert_eq!(
+            partitions.keys().collect::<Vec<_>>(),
+            ["2026-01-15", "2026-01-16"]
+        );
+        assert_eq!(partitions["2026-01-16"].len(), 2);
+        let key = object_key(
+            "/exports/",
+            "default",
+            "2026-01-16",
+            &partitions["2026-01-16"],
+            EventExportFormat::Jsonl,
+        );
+        assert_eq!(
+            key,
+            "exports/events/tenant=default/date=2026-01-16/part-000000000001-000000000003.jsonl"
+        );
+        let jsonl = String::from_utf8(encode_jsonl(&partitions["2026-01-15"]).unwrap()).unwrap();
+        assert_eq!(jsonl.lines().count(), 1);
+        assert!(jsonl.contains("\"content\":\"event 2\""));
+    }
+
+    #[test]
+    fn parquet_round_trips() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let events = [
+            event(1, "2026-01-15T10:00:00+00:00", Some("bash")),
+            event(2, "2026-01-15T10:00:01+00:00", None),
+        ];
+        let refs: Vec<&StoredEvent> = events.iter().collect();
+        let data = encode_parquet(&refs).unwrap();
+        let reader = SerializedFileReader::new(bytes::Bytes::from(data)).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        let rows: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert!(rows[0].contains("tool_name: \"bash\""), "{}", rows[0]);
+        assert!(rows[1].contains("tool_name: null"), "{}", rows[1]);
+        assert!(rows[1].contains(r#"metadata: "{"n":2}""#), "{}", rows[1]);
+    }
+
+    #[test]
+    fn manifest_tracks_exports_per_tenant() {
+        let root = tempfile::tempdir().unwrap();
+        let record = ExportRecord {
+            key: "k".to_string(),
+            uri: "s3://b/k".to_string(),
+            tenant: "user/1".to_string(),
+            date: "2026-01-15".to_string(),
+            format: EventExportFormat::Parquet,
+            events: 2,
+            first_event_id: 1,
+            last_event_id: 2,
+            bytes: 10,
+            exported_at: "t".to_string(),
+        };
+        append_manifest(root.path(), &record).unwrap();
+        assert_eq!(read_manifest(root.path(), "user/1"), vec![record]);
+        assert!(read_manifest(root.path(), "other").is_empty());
+        assert!(manifest_path(root.path(), "user/1").ends_with("user_1.jsonl"));
+    }
+}
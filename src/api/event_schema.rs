@@ -0,0 +1,119 @@
+//! Versioning and JSON Schema for streamed mission events.
+//!
+//! Every [`AgentEvent`] sent over SSE is wrapped in [`Versioned`], which adds a
+//! top-level `schema_version`. Within a version, [`AgentEvent`] and
+//! [`ExecutionEvent`] only change additively: new variants and new fields may
+//! appear, and new fields are optional or `#[serde(default)]` so payloads
+//! produced before the change still parse. Removing, renaming or retyping a
+//! field bumps [`EVENT_SCHEMA_VERSION`]. Consumers should ignore unknown event
+//! types and fields.
+//!
+//! `GET /api/schema/events` serves the JSON Schema for both event types.
+
+use axum::Json;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use super::control::AgentEvent;
+use crate::backend::events::ExecutionEvent;
+
+/// Current version of the event wire format.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// An event tagged with the schema version it was produced under.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Versioned<T> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub event: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(event: T) -> Self {
+        Self {
+            schema_version: EVENT_SCHEMA_VERSION,
+            event,
+        }
+    }
+}
+
+/// GET /api/schema/events - JSON Schema for streamed events.
+pub async fn get_event_schemas() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "schema_version": EVENT_SCHEMA_VERSION,
+        "agent_event": schema_for!(Versioned<AgentEvent>),
+        "execution_event": schema_for!(ExecutionEvent),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn versioned_events_round_trip() {
+        let mission_id = Uuid::new_v4();
+        let event = AgentEvent::ToolCall {
+            tool_call_id: "t1".to_string(),
+            name: "bash".to_string(),
+            args: serde_json::json!({ "command": "ls" }),
+            mission_id: Some(mission_id),
+        };
+        let json = serde_json::to_value(Versioned::new(&event)).unwrap();
+        assert_eq!(json["schema_version"], EVENT_SCHEMA_VERSION);
+        assert_eq!(json["type"], "tool_call");
+
+        let parsed: Versioned<AgentEvent> = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            parsed.event,
+            AgentEvent::ToolCall { mission_id: Some(id), .. } if id == mission_id
+        ));
+    }
+
+    #[test]
+    fn older_and_newer_payloads_parse() {
+        // Written before `resumable` existed, with a field added after it.
+        let event: AgentEvent = serde_json::from_value(serde_json::json!({
+            "type": "error",
+            "message": "boom",
+            "added_later": true,
+        }))
+        .unwrap();
+        assert!(matches!(
+            event,
+            AgentEvent::Error {
+                resumable: false,
+                ..
+            }
+        ));
+
+        let event: ExecutionEvent = serde_json::from_value(serde_json::json!({
+            "type": "killed_processes",
+            "report": { "killed": [{ "pid": 7, "command": "codex" }] },
+        }))
+        .unwrap();
+        assert!(
+            matches!(event, ExecutionEvent::KilledProcesses { report } if report.survivors.is_empty())
+        );
+    }
+
+    #[test]
+    fn schema_covers_every_event_type() {
+        let schema = serde_json::to_string(&schema_for!(Versioned<AgentEvent>)).unwrap();
+        for name in [
+            "assistant_message",
+            "killed_processes",
+            "history_compacted",
+            "context_recovery",
+            "manual_intervention",
+            "sla_breached",
+            "plan_updated",
+        ] {
+            assert!(schema.contains(&format!("\"{name}\"")), "missing {name}");
+        }
+        assert!(schema.contains("schema_version"));
+        let schema = serde_json::to_string(&schema_for!(ExecutionEvent)).unwrap();
+        assert!(schema.contains("\"message_complete\""));
+    }
+}
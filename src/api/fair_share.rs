@@ -0,0 +1,420 @@
+//! Fair-share scheduling of parallel missions across users.
+//!
+//! Every user (tenant) has its own control session, so without coordination
+//! one user's burst of batch missions can take every worker. With
+//! `fair_share` enabled in the settings, parallel mission starts go through
+//! [`admit`], which refuses a start when:
+//!
+//! - the user is at its `max_concurrency` ceiling,
+//! - `max_workers` missions are already running across all users,
+//! - the last free workers are reserved for waiting users below their
+//!   `min_concurrency` floor, or
+//! - another waiting user has less recent usage per unit of `weight`.
+//!
+//! Recent usage is mission run time in worker-seconds, decayed with
+//! `usage_half_life_minutes`. A refused user counts as waiting for
+//! [`WAITER_TTL`]; mission dispatchers retry refused starts, so waiting users
+//! are admitted in usage order as workers free up. Running missions are
+//! tracked even while fair-share is disabled, so enabling it takes effect
+//! with accurate counts. Current shares are served at
+//! `GET /api/control/parallel/shares`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Extension, State},
+    Json,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::routes::AppState;
+use crate::settings::FairShareSettings;
+
+/// How long a refused user keeps its place among the waiting users.
+pub const WAITER_TTL: Duration = Duration::from_secs(30);
+
+/// Prefix of refusals, so dispatchers can tell them from failures.
+pub const REFUSAL_PREFIX: &str = "Fair share:";
+
+static SCHEDULER: LazyLock<Mutex<Scheduler>> = LazyLock::new(|| Mutex::new(Scheduler::default()));
+
+#[derive(Debug)]
+struct TenantState {
+    /// Running missions and when they started.
+    running: HashMap<Uuid, Instant>,
+    /// Decayed worker-seconds of finished runs, as of `updated`.
+    usage: f64,
+    updated: Instant,
+    waiting_since: Option<Instant>,
+}
+
+impl TenantState {
+    fn new(now: Instant) -> Self {
+        Self {
+            running: HashMap::new(),
+            usage: 0.0,
+            updated: now,
+            waiting_since: None,
+        }
+    }
+
+    fn decay(&mut self, now: Instant, half_life: Duration) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.usage *= 0.5f64.powf(elapsed / half_life.as_secs_f64());
+        self.updated = now;
+    }
+
+    /// Recent usage including the time spent by running missions so far.
+    fn recent_usage(&self, now: Instant) -> f64 {
+        self.usage
+            + self
+                .running
+                .values()
+                .map(|start| now.saturating_duration_since(*start).as_secs_f64())
+                .sum::<f64>()
+    }
+
+    fn is_waiting(&self, now: Instant) -> bool {
+        self.waiting_since
+            .is_some_and(|since| now.saturating_duration_since(since) < WAITER_TTL)
+    }
+}
+
+/// A user's current share, as reported by the shares endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TenantShare {
+    pub tenant: String,
+    pub weight: f64,
+    pub min_concurrency: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    pub running: usize,
+    pub waiting: bool,
+    /// Decayed worker-seconds.
+    pub recent_usage_seconds: u64,
+    /// Fraction of the running missions that are this user's.
+    pub share: f64,
+    /// Fraction this user is entitled to among active users, by weight.
+    pub target_share: f64,
+}
+
+#[derive(Debug, Default)]
+struct Scheduler {
+    tenants: HashMap<String, TenantState>,
+}
+
+impl Scheduler {
+    fn total_running(&self) -> usize {
+        self.tenants.values().map(|t| t.running.len()).sum()
+    }
+
+    fn score(&self, settings: &FairShareSettings, tenant: &str, now: Instant) -> f64 {
+        let usage = self
+            .tenants
+            .get(tenant)
+            .map(|t| t.recent_usage(now))
+            .unwrap_or(0.0);
+        usage / settings.limits(tenant).weight
+    }
+
+    fn admit(
+        &mut self,
+        settings: &FairShareSettings,
+        tenant: &str,
+        mission_id: Uuid,
+        now: Instant,
+    ) -> Result<(), String> {
+        self.decay(settings, now);
+        let state = self
+            .tenants
+            .entry(tenant.to_string())
+            .or_insert_with(|| TenantState::new(now));
+        if state.running.contains_key(&mission_id) {
+            return Ok(());
+        }
+        if settings.enabled {
+            if let Err(reason) = self.check(settings, tenant, now) {
+                let state = self.tenants.get_mut(tenant).expect("tenant inserted above");
+                if !state.is_waiting(now) {
+                    state.waiting_since = Some(now);
+                }
+                return Err(format!("{} {}", REFUSAL_PREFIX, reason));
+            }
+        }
+        let state = self.tenants.get_mut(tenant).expect("tenant inserted above");
+        state.running.insert(mission_id, now);
+        state.waiting_since = None;
+        Ok(())
+    }
+
+    fn check(
+        &self,
+        settings: &FairShareSettings,
+        tenant: &str,
+        now: Instant,
+    ) -> Result<(), String> {
+        let limits = settings.limits(tenant);
+        let running = self.tenants.get(tenant).map_or(0, |t| t.running.len());
+        if let Some(max) = limits.max_concurrency.filter(|max| running >= *max) {
+            return Err(format!(
+                "user {} is at its ceiling of {} parallel missions",
+                tenant, max
+            ));
+        }
+        let Some(max_workers) = settings.max_workers else {
+            return Ok(());
+        };
+        let total = self.total_running();
+        if total >= max_workers {
+            return Err(format!("all {} workers are busy", max_workers));
+        }
+        let others_waiting: Vec<(&String, &TenantState)> = self
+            .tenants
+            .iter()
+            .filter(|(name, state)| name.as_str() != tenant && state.is_waiting(now))
+            .filter(|(name, state)| {
+                settings
+                    .limits(name)
+                    .max_concurrency
+                    .is_none_or(|max| state.running.len() < max)
+            })
+            .collect();
+        let reserved: usize = others_waiting
+            .iter()
+            .map(|(name, state)| {
+                settings
+                    .limits(name)
+                    .min_concurrency
+                    .saturating_sub(state.running.len())
+            })
+            .sum();
+        if running < limits.min_concurrency {
+            return Ok(());
+        }
+        if max_workers - total <= reserved {
+            return Err("the free workers are reserved for users below their floor".to_string());
+        }
+        let own_score = self.score(settings, tenant, now);
+        if let Some((name, _)) = others_waiting
+            .iter()
+            .find(|(name, _)| self.score(settings, name, now) < own_score)
+        {
+            return Err(format!(
+                "user {} has used less of its share recently and goes first",
+                name
+            ));
+        }
+        Ok(())
+    }
+
+    fn decay(&mut self, settings: &FairShareSettings, now: Instant) {
+        let half_life = Duration::from_secs(settings.usage_half_life_minutes.max(1) * 60);
+        for state in self.tenants.values_mut() {
+            state.decay(now, half_life);
+        }
+    }
+
+    fn release(&mut self, settings: &FairShareSettings, mission_id: Uuid, now: Instant) {
+        self.decay(settings, now);
+        for state in self.tenants.values_mut() {
+            if let Some(start) = state.running.remove(&mission_id) {
+                state.usage += now.saturating_duration_since(start).as_secs_f64();
+                return;
+            }
+        }
+    }
+
+    fn shares(&self, settings: &FairShareSettings, now: Instant) -> Vec<TenantShare> {
+        let total = self.total_running();
+        let active: Vec<&String> = self
+            .tenants
+            .iter()
+            .filter(|(_, state)| !state.running.is_empty() || state.is_waiting(now))
+            .map(|(name, _)| name)
+            .collect();
+        let active_weight: f64 = active.iter().map(|name| settings.limits(name).weight).sum();
+        let mut shares: Vec<TenantShare> = self
+            .tenants
+            .iter()
+            .map(|(name, state)| {
+                let limits = settings.limits(name);
+                let running = state.running.len();
+                TenantShare {
+                    tenant: name.clone(),
+                    weight: limits.weight,
+                    min_concurrency: limits.min_concurrency,
+                    max_concurrency: limits.max_concurrency,
+                    running,
+                    waiting: state.is_waiting(now),
+                    recent_usage_seconds: state.recent_usage(now) as u64,
+                    share: if total == 0 {
+                        0.0
+                    } else {
+                        running as f64 / total as f64
+                    },
+                    target_share: if active.contains(&name) && active_weight > 0.0 {
+                        limits.weight / active_weight
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+        shares.sort_by(|a, b| a.tenant.cmp(&b.tenant));
+        shares
+    }
+}
+
+/// Reserve a worker for a parallel mission of `tenant`. The error explains a
+/// refusal and starts with [`REFUSAL_PREFIX`].
+pub fn admit(tenant: &str, mission_id: Uuid) -> Result<(), String> {
+    let settings = crate::settings::fair_share_settings_cached();
+    let mut scheduler = SCHEDULER.lock().unwrap_or_else(|e| e.into_inner());
+    scheduler.admit(&settings, tenant, mission_id, Instant::now())
+}
+
+/// Free the worker of a mission that stopped running.
+pub fn release(mission_id: Uuid) {
+    let settings = crate::settings::fair_share_settings_cached();
+    let mut scheduler = SCHEDULER.lock().unwrap_or_else(|e| e.into_inner());
+    scheduler.release(&settings, mission_id, Instant::now());
+}
+
+#[derive(Debug, Serialize)]
+pub struct SharesResponse {
+    pub enabled: bool,
+    pub max_workers: Option<usize>,
+    pub running: usize,
+    pub tenants: Vec<TenantShare>,
+}
+
+/// GET /api/control/parallel/shares
+pub async fn get_shares(
+    State(_state): State<Arc<AppState>>,
+    Extension(_user): Extension<AuthUser>,
+) -> Json<SharesResponse> {
+    let settings = crate::settings::fair_share_settings_cached();
+    let scheduler = SCHEDULER.lock().unwrap_or_else(|e| e.into_inner());
+    Json(SharesResponse {
+        enabled: settings.enabled,
+        max_workers: settings.max_workers,
+        running: scheduler.total_running(),
+        tenants: scheduler.shares(&settings, Instant::now()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::TenantLimits;
+
+    fn settings(max_workers: usize) -> FairShareSettings {
+        FairShareSettings {
+            enabled: true,
+            max_workers: Some(max_workers),
+            ..FairShareSettings::default()
+        }
+    }
+
+    #[test]
+    fn waiting_user_with_less_usage_goes_first() {
+        let settings = settings(2);
+        let mut scheduler = Scheduler::default();
+        let t0 = Instant::now();
+        let (a1, a2, a3, b1) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        scheduler.admit(&settings, "alice", a1, t0).unwrap();
+        scheduler.admit(&settings, "alice", a2, t0).unwrap();
+        let err = scheduler.admit(&settings, "bob", b1, t0).unwrap_err();
+        assert!(err.starts_with(REFUSAL_PREFIX), "{}", err);
+
+        // A worker frees up: alice retries first but bob is waiting with no usage.
+        let t1 = t0 + Duration::from_secs(10);
+        scheduler.release(&settings, a1, t1);
+        let err = scheduler.admit(&settings, "alice", a3, t1).unwrap_err();
+        assert!(err.contains("bob"), "{}", err);
+        scheduler.admit(&settings, "bob", b1, t1).unwrap();
+
+        let shares = scheduler.shares(&settings, t1);
+        let alice = shares.iter().find(|s| s.tenant == "alice").unwrap();
+        assert_eq!(alice.running, 1);
+        assert!(alice.waiting);
+        assert_eq!(alice.recent_usage_seconds, 20);
+        assert_eq!(alice.target_share, 0.5);
+    }
+
+    #[test]
+    fn floors_and_ceilings() {
+        let mut settings = settings(3);
+        settings.tenants.insert(
+            "bob".to_string(),
+            TenantLimits {
+                min_concurrency: 2,
+                ..TenantLimits::default()
+            },
+        );
+        settings.default_limits.max_concurrency = Some(2);
+        let mut scheduler = Scheduler::default();
+        let t0 = Instant::now();
+        scheduler
+            .admit(&settings, "alice", Uuid::new_v4(), t0)
+            .unwrap();
+        let err = scheduler
+            .admit(&settings, "bob", Uuid::new_v4(), t0)
+            .and_then(|_| scheduler.admit(&settings, "bob", Uuid::new_v4(), t0))
+            .and_then(|_| scheduler.admit(&settings, "bob", Uuid::new_v4(), t0))
+            .unwrap_err();
+        assert!(err.contains("busy"), "{}", err);
+
+        // Bob waits below his floor: the next free worker is reserved for him.
+        let mut scheduler = Scheduler::default();
+        let carol = Uuid::new_v4();
+        scheduler
+            .admit(&settings, "alice", Uuid::new_v4(), t0)
+            .unwrap();
+        scheduler
+            .admit(&settings, "alice", Uuid::new_v4(), t0)
+            .unwrap();
+        scheduler.admit(&settings, "carol", carol, t0).unwrap();
+        scheduler
+            .admit(&settings, "bob", Uuid::new_v4(), t0)
+            .unwrap_err();
+        scheduler.release(&settings, carol, t0);
+        let err = scheduler
+            .admit(&settings, "alice", Uuid::new_v4(), t0)
+            .unwrap_err();
+        assert!(err.contains("ceiling of 2"), "{}", err);
+        let err = scheduler
+            .admit(&settings, "carol", Uuid::new_v4(), t0)
+            .unwrap_err();
+        assert!(err.contains("reserved"), "{}", err);
+        assert!(scheduler
+            .admit(&settings, "bob", Uuid::new_v4(), t0)
+            .is_ok());
+    }
+
+    #[test]
+    fn disabled_tracks_without_refusing() {
+        let settings = FairShareSettings {
+            max_workers: Some(1),
+            ..FairShareSettings::default()
+        };
+        let mut scheduler = Scheduler::default();
+        let t0 = Instant::now();
+        scheduler
+            .admit(&settings, "alice", Uuid::new_v4(), t0)
+            .unwrap();
+        scheduler
+            .admit(&settings, "alice", Uuid::new_v4(), t0)
+            .unwrap();
+        assert_eq!(scheduler.total_running(), 2);
+    }
+}
@@ -0,0 +1,588 @@
+//! Host exec: approved, audited commands on the server host.
+//!
+//! Workspace tools are confined to their mission (see `tools::host_access`).
+//! For the rare task that needs the host itself, an admin enables host exec
+//! per mission with `PUT /api/control/host-exec/missions/:id`, which offers
+//! the mission the `host_exec` tool from its next turn.
+//!
+//! Each call is posted by the workspace MCP to `POST /api/host-exec/:mission_id`
+//! (the mission's own token, see `api::mission_guard`) and held until an admin approves or denies it with
+//! `POST /api/control/host-exec/requests/:id/{approve,deny}`, or
+//! [`APPROVAL_TIMEOUT`] passes. Pending requests are listed at
+//! `GET /api/control/host-exec/requests` and announced on the spending alerts
+//! webhook when one is configured. Approved commands run on the server as the
+//! server's user, in its working directory.
+//!
+//! Enabling, disabling, every request, decision and result are appended to the
+//! mission's audit log under `.sandboxed-sh/host-exec/`, returned by
+//! `GET /api/control/host-exec/missions/:id`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::control_for_user;
+use super::mission_store::now_string;
+use super::notifier::{self, mission_link, Notification, NotificationLink};
+use super::routes::AppState;
+use crate::tools::host_access::{HostExecOutcome, MAX_HOST_EXEC_TIMEOUT_SECS};
+use crate::tools::safe_truncate_index;
+
+const DIR: &str = ".sandboxed-sh/host-exec";
+/// How long a request waits for a decision.
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+/// Output kept per stream, for the model and the audit log.
+const MAX_OUTPUT_CHARS: usize = 50_000;
+/// Command text kept in audit records and notifications.
+const MAX_COMMAND_CHARS: usize = 4_000;
+
+/// One entry of a mission's audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub at: String,
+    /// `enabled`, `disabled`, `requested`, `approved`, `denied`, `expired`,
+    /// `completed` or `failed`
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<Uuid>,
+    /// Admin who enabled or decided; unset for events of the mission itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+impl AuditRecord {
+    fn new(event: &str, request_id: Option<Uuid>) -> Self {
+        Self {
+            at: now_string(),
+            event: event.to_string(),
+            request_id,
+            actor: None,
+            command: None,
+            detail: None,
+            exit_code: None,
+        }
+    }
+}
+
+fn enabled_path(root: &Path, mission_id: Uuid) -> PathBuf {
+    root.join(DIR).join(format!("{}.enabled", mission_id))
+}
+
+fn audit_path(root: &Path, mission_id: Uuid) -> PathBuf {
+    root.join(DIR).join(format!("{}.jsonl", mission_id))
+}
+
+/// Whether an admin enabled host exec for the mission.
+pub fn is_enabled(root: &Path, mission_id: Uuid) -> bool {
+    enabled_path(root, mission_id).exists()
+}
+
+fn set_enabled(root: &Path, mission_id: Uuid, enabled: bool) -> std::io::Result<()> {
+    let path = enabled_path(root, mission_id);
+    if enabled {
+        std::fs::create_dir_all(root.join(DIR))?;
+        return std::fs::write(path, now_string());
+    }
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn audit(root: &Path, mission_id: Uuid, record: &AuditRecord) {
+    use std::io::Write;
+    let write = || -> std::io::Result<()> {
+        std::fs::create_dir_all(root.join(DIR))?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(audit_path(root, mission_id))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)
+    };
+    if let Err(e) = write() {
+        tracing::error!(mission_id = %mission_id, error = %e, "Failed to write host exec audit record");
+    }
+}
+
+pub fn read_audit(root: &Path, mission_id: Uuid) -> Vec<AuditRecord> {
+    std::fs::read_to_string(audit_path(root, mission_id))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn truncated(text: &str, max: usize) -> String {
+    let end = safe_truncate_index(text, max);
+    if end < text.len() {
+        format!("{}\n[truncated]", &text[..end])
+    } else {
+        text.to_string()
+    }
+}
+
+/// A call waiting for approval.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingRequest {
+    pub id: Uuid,
+    pub mission_id: Uuid,
+    pub command: String,
+    pub reason: String,
+    pub timeout_secs: u64,
+    pub requested_at: String,
+}
+
+enum Decision {
+    Approve {
+        actor: String,
+    },
+    Deny {
+        actor: String,
+        reason: Option<String>,
+    },
+}
+
+struct Pending {
+    request: PendingRequest,
+    decide: oneshot::Sender<Decision>,
+}
+
+static PENDING: Mutex<Option<HashMap<Uuid, Pending>>> = Mutex::new(None);
+
+fn with_pending<T>(f: impl FnOnce(&mut HashMap<Uuid, Pending>) -> T) -> T {
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    f(pending.get_or_insert_with(HashMap::new))
+}
+
+/// Run an approved command on the host.
+async fn run(command: &str, cwd: &Path, timeout: Duration) -> HostExecOutcome {
+    let mut cmd = tokio::process::Command::new("/bin/sh");
+    cmd.arg("-c")
+        .arg(command)
+        .current_dir(cwd)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(Ok(output)) => HostExecOutcome::Completed {
+            exit_code: output.status.code(),
+            stdout: truncated(&String::from_utf8_lossy(&output.stdout), MAX_OUTPUT_CHARS),
+            stderr: truncated(&String::from_utf8_lossy(&output.stderr), MAX_OUTPUT_CHARS),
+        },
+        Ok(Err(e)) => HostExecOutcome::Failed {
+            error: e.to_string(),
+        },
+        Err(_) => HostExecOutcome::Failed {
+            error: format!("timed out after {} seconds", timeout.as_secs()),
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HostExecCall {
+    pub command: String,
+    #[serde(default)]
+    pub reason: String,
+    pub timeout_secs: Option<u64>,
+}
+
+async fn announce(request: &PendingRequest) {
+    let settings = crate::settings::spending_alert_settings_cached();
+    let Some(url) = settings.webhook_url.as_deref() else {
+        return;
+    };
+    let notification = Notification {
+        kind: "host_exec_approval_needed".to_string(),
+        title: "Host command needs approval".to_string(),
+        text: format!(
+            "A mission wants to run on the host:\n```\n{}\n```\nReason: {}",
+            truncated(&request.command, MAX_COMMAND_CHARS),
+            request.reason
+        ),
+        links: vec![
+            NotificationLink {
+                label: "Open mission".to_string(),
+                url: mission_link(settings.dashboard_url.as_deref(), request.mission_id),
+            },
+            NotificationLink {
+                label: "Approve".to_string(),
+                url: format!("/api/control/host-exec/requests/{}/approve", request.id),
+            },
+        ],
+        data: json!({ "request": request }),
+    };
+    if let Err(e) = notifier::send(url, &notification).await {
+        tracing::warn!(request_id = %request.id, error = %e, "Failed to announce host exec request");
+    }
+}
+
+/// POST /api/host-exec/:mission_id - a `host_exec` call from a workspace tool.
+/// Answers once the call was decided and, if approved, has run.
+pub async fn post_host_exec(
+    State(state): State<Arc<AppState>>,
+    AxumPath(mission_id): AxumPath<Uuid>,
+    headers: HeaderMap,
+    Json(call): Json<HostExecCall>,
+) -> Result<Json<HostExecOutcome>, StatusCode> {
+    super::mission_guard::authorize(mission_id, &headers)?;
+    let root = &state.config.working_dir;
+    if !is_enabled(root, mission_id) {
+        return Ok(Json(HostExecOutcome::Disabled));
+    }
+
+    let request = PendingRequest {
+        id: Uuid::new_v4(),
+        mission_id,
+        command: call.command,
+        reason: call.reason,
+        timeout_secs: call
+            .timeout_secs
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+            .clamp(1, MAX_HOST_EXEC_TIMEOUT_SECS),
+        requested_at: now_string(),
+    };
+    audit(
+        root,
+        mission_id,
+        &AuditRecord {
+            command: Some(truncated(&request.command, MAX_COMMAND_CHARS)),
+            detail: Some(request.reason.clone()),
+            ..AuditRecord::new("requested", Some(request.id))
+        },
+    );
+    tracing::warn!(mission_id = %mission_id, request_id = %request.id, "Host exec request waiting for approval");
+    let (decide, decision) = oneshot::channel();
+    with_pending(|pending| {
+        pending.insert(
+            request.id,
+            Pending {
+                request: request.clone(),
+                decide,
+            },
+        )
+    });
+    announce(&request).await;
+
+    let decision = tokio::time::timeout(APPROVAL_TIMEOUT, decision).await;
+    with_pending(|pending| pending.remove(&request.id));
+    let actor = match decision {
+        Ok(Ok(Decision::Approve { actor })) => actor,
+        Ok(Ok(Decision::Deny { actor, reason })) => {
+            audit(
+                root,
+                mission_id,
+                &AuditRecord {
+                    actor: Some(actor),
+                    detail: reason.clone(),
+                    ..AuditRecord::new("denied", Some(request.id))
+                },
+            );
+            return Ok(Json(HostExecOutcome::Denied { reason }));
+        }
+        Ok(Err(_)) | Err(_) => {
+            audit(
+                root,
+                mission_id,
+                &AuditRecord::new("expired", Some(request.id)),
+            );
+            return Ok(Json(HostExecOutcome::Expired));
+        }
+    };
+    audit(
+        root,
+        mission_id,
+        &AuditRecord {
+            actor: Some(actor),
+            ..AuditRecord::new("approved", Some(request.id))
+        },
+    );
+
+    let outcome = run(
+        &request.command,
+        root,
+        Duration::from_secs(request.timeout_secs),
+    )
+    .await;
+    let record = match &outcome {
+        HostExecOutcome::Completed {
+            exit_code,
+            stdout,
+            stderr,
+        } => AuditRecord {
+            exit_code: *exit_code,
+            detail: Some(format!(
+                "{} bytes stdout, {} bytes stderr",
+                stdout.len(),
+                stderr.len()
+            )),
+            ..AuditRecord::new("completed", Some(request.id))
+        },
+        HostExecOutcome::Failed { error } => AuditRecord {
+            detail: Some(error.clone()),
+            ..AuditRecord::new("failed", Some(request.id))
+        },
+        _ => AuditRecord::new("failed", Some(request.id)),
+    };
+    audit(root, mission_id, &record);
+    Ok(Json(outcome))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetHostExecRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HostExecStatus {
+    pub mission_id: Uuid,
+    pub enabled: bool,
+    pub audit: Vec<AuditRecord>,
+}
+
+async fn ensure_mission(
+    state: &Arc<AppState>,
+    user: &AuthUser,
+    mission_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let control = control_for_user(state, user).await;
+    control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or((StatusCode::NOT_FOUND, "Mission not found".to_string()))?;
+    Ok(())
+}
+
+/// GET /api/control/host-exec/missions/:id - Whether host exec is enabled, with the audit log.
+pub async fn get_mission_host_exec(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    AxumPath(mission_id): AxumPath<Uuid>,
+) -> Result<Json<HostExecStatus>, (StatusCode, String)> {
+    ensure_mission(&state, &user, mission_id).await?;
+    let root = &state.config.working_dir;
+    Ok(Json(HostExecStatus {
+        mission_id,
+        enabled: is_enabled(root, mission_id),
+        audit: read_audit(root, mission_id),
+    }))
+}
+
+/// PUT /api/control/host-exec/missions/:id - Enable or disable host exec (admin).
+pub async fn set_mission_host_exec(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    AxumPath(mission_id): AxumPath<Uuid>,
+    Json(req): Json<SetHostExecRequest>,
+) -> Result<Json<HostExecStatus>, (StatusCode, String)> {
+    ensure_mission(&state, &user, mission_id).await?;
+    let root = &state.config.working_dir;
+    if is_enabled(root, mission_id) != req.enabled {
+        set_enabled(root, mission_id, req.enabled)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        let event = if req.enabled { "enabled" } else { "disabled" };
+        audit(
+            root,
+            mission_id,
+            &AuditRecord {
+                actor: Some(user.username.clone()),
+                ..AuditRecord::new(event, None)
+            },
+        );
+    }
+    Ok(Json(HostExecStatus {
+        mission_id,
+        enabled: req.enabled,
+        audit: read_audit(root, mission_id),
+    }))
+}
+
+/// GET /api/control/host-exec/requests - Calls waiting for approval.
+pub async fn list_pending(Extension(_user): Extension<AuthUser>) -> Json<Vec<PendingRequest>> {
+    let mut requests: Vec<PendingRequest> =
+        with_pending(|pending| pending.values().map(|p| p.request.clone()).collect());
+    requests.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+    Json(requests)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DenyRequest {
+    pub reason: Option<String>,
+}
+
+fn decide(id: Uuid, decision: Decision) -> Result<StatusCode, (StatusCode, String)> {
+    let pending = with_pending(|pending| pending.remove(&id)).ok_or((
+        StatusCode::NOT_FOUND,
+        "No pending host exec request with this ID".to_string(),
+    ))?;
+    pending.decide.send(decision).map_err(|_| {
+        (
+            StatusCode::GONE,
+            "The request was abandoned by the mission".to_string(),
+        )
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/control/host-exec/requests/:id/approve - Run a pending call (admin).
+pub async fn approve_request(
+    Extension(user): Extension<AuthUser>,
+    AxumPath(id): AxumPath<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    decide(
+        id,
+        Decision::Approve {
+            actor: user.username,
+        },
+    )
+}
+
+/// POST /api/control/host-exec/requests/:id/deny - Refuse a pending call (admin).
+pub async fn deny_request(
+    Extension(user): Extension<AuthUser>,
+    AxumPath(id): AxumPath<Uuid>,
+    body: Option<Json<DenyRequest>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    decide(
+        id,
+        Decision::Deny {
+            actor: user.username,
+            reason: body.and_then(|Json(b)| b.reason),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enablement_and_audit_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let mission_id = Uuid::new_v4();
+        assert!(!is_enabled(dir.path(), mission_id));
+        set_enabled(dir.path(), mission_id, true).unwrap();
+        assert!(is_enabled(dir.path(), mission_id));
+        set_enabled(dir.path(), mission_id, false).unwrap();
+        set_enabled(dir.path(), mission_id, false).unwrap();
+        assert!(!is_enabled(dir.path(), mission_id));
+
+        let request_id = Some(Uuid::new_v4());
+        audit(
+            dir.path(),
+            mission_id,
+            &AuditRecord::new("requested", request_id),
+        );
+        audit(
+            dir.path(),
+            mission_id,
+            &AuditRecord {
+                actor: Some("admin".to_string()),
+                ..AuditRecord::new("approved", request_id)
+            },
+        );
+        let events: Vec<String> = read_audit(dir.path(), mission_id)
+            .into_iter()
+            .map(|r| r.event)
+            .collect();
+        assert_eq!(events, vec!["requested", "approved"]);
+        assert!(read_audit(dir.path(), Uuid::new_v4()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn decisions_reach_the_waiting_call() {
+        let (tx, rx) = oneshot::channel();
+        let id = Uuid::new_v4();
+        with_pending(|pending| {
+            pending.insert(
+                id,
+                Pending {
+                    request: PendingRequest {
+                        id,
+                        mission_id: Uuid::new_v4(),
+                        command: "uptime".to_string(),
+                        reason: "check load".to_string(),
+                        timeout_secs: 5,
+                        requested_at: now_string(),
+                    },
+                    decide: tx,
+                },
+            )
+        });
+        let Json(listed) = list_pending(Extension(AuthUser {
+            id: "admin".to_string(),
+            username: "admin".to_string(),
+            role: crate::config::Role::Admin,
+        }))
+        .await;
+        assert!(listed.iter().any(|r| r.id == id));
+
+        let status = decide(
+            id,
+            Decision::Deny {
+                actor: "admin".to_string(),
+                reason: Some("not now".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(matches!(
+            rx.await.unwrap(),
+            Decision::Deny { reason: Some(r), .. } if r == "not now"
+        ));
+        assert_eq!(
+            decide(
+                id,
+                Decision::Approve {
+                    actor: "admin".to_string()
+                }
+            )
+            .unwrap_err()
+            .0,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn runs_commands_with_a_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let outcome = run(
+            "echo out; echo err >&2; exit 3",
+            dir.path(),
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(
+            outcome,
+            HostExecOutcome::Completed {
+                exit_code: Some(3),
+                stdout: "out\n".to_string(),
+                stderr: "err\n".to_string(),
+            }
+        );
+        let outcome = run("sleep 5", dir.path(), Duration::from_millis(100)).await;
+        assert!(matches!(outcome, HostExecOutcome::Failed { .. }));
+    }
+}
@@ -1,7 +1,8 @@
 //! Library management API endpoints.
 //!
 //! Provides endpoints for managing the configuration library:
-//! - Git operations (status, sync, commit, push)
+//! - Git operations (status, sync, commit, push, deepen, clone progress)
+//! - Per-item history, diff and restore
 //! - MCP server CRUD
 //! - Skills CRUD
 //! - Commands CRUD
@@ -14,19 +15,24 @@ use axum::{
     extract::{Multipart, Path, Query, State},
     http::{HeaderMap, StatusCode},
     routing::{delete, get, post, put},
-    Json, Router,
+    Extension, Json, Router,
 };
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use super::auth::AuthUser;
 use crate::library::{
+    clone_progress, env_schema,
+    history::DEFAULT_HISTORY_LIMIT,
     rename::{ItemType, RenameResult},
-    AmpCodeConfig, ClaudeCodeConfig, Command, CommandSummary, ConfigProfile, ConfigProfileSummary,
-    GitAuthor, InitScript, InitScriptSummary, LibraryAgent, LibraryAgentSummary, LibraryStatus,
-    LibraryStore, McpServer, MigrationReport, SandboxedConfig, Skill, SkillSummary,
-    WorkspaceTemplate, WorkspaceTemplateSummary,
+    AmpCodeConfig, ClaudeCodeConfig, CloneProgress, Command, CommandSummary, ConfigProfile,
+    ConfigProfileSummary, EnvVarIssue, GitAuthor, HistoryEntry, InitScript, InitScriptPlan,
+    InitScriptSummary, ItemDiff, LibraryAgent, LibraryAgentSummary, LibraryStatus, LibraryStore,
+    McpServer, MigrationReport, MissionTemplate, MissionTemplateSummary, RestoreResult,
+    SandboxedConfig, Skill, SkillSummary, TemplateEnvVar, WorkspaceTemplate,
+    WorkspaceTemplateSummary,
 };
 use crate::nspawn::NspawnDistro;
 use crate::util::{internal_error, not_found_or_internal, sanitize_skill_list};
@@ -143,6 +149,7 @@ async fn ensure_library(
     match LibraryStore::new(state.config.library_path.clone(), &remote).await {
         Ok(store) => {
             let store = Arc::new(store);
+            store.start_background_fetch();
             *library_guard = Some(Arc::clone(&store));
             drop(library_guard);
             sync_all_workspaces(state, store.as_ref()).await;
@@ -165,6 +172,8 @@ pub fn routes() -> Router<Arc<super::routes::AppState>> {
         .route("/force-push", post(force_push_library))
         .route("/commit", post(commit_library))
         .route("/push", post(push_library))
+        .route("/deepen", post(deepen_library))
+        .route("/clone-progress", get(get_clone_progress))
         // MCP servers
         .route("/mcps", get(get_mcps))
         .route("/mcps", put(save_mcps))
@@ -177,12 +186,16 @@ pub fn routes() -> Router<Arc<super::routes::AppState>> {
         .route("/skill/:name/files/*path", get(get_skill_reference))
         .route("/skill/:name/files/*path", put(save_skill_reference))
         .route("/skill/:name/files/*path", delete(delete_skill_reference))
+        .route("/skill/:name/history", get(get_skill_history))
+        .route("/skill/:name/test", post(test_skill))
         // Legacy skills routes (dashboard still calls /skills)
         .route("/skills", get(list_skills))
         .route("/skills/import", post(import_skill))
         .route("/skills/:name", get(get_skill))
         .route("/skills/:name", put(save_skill))
         .route("/skills/:name", delete(delete_skill))
+        .route("/skills/:name/history", get(get_skill_history))
+        .route("/skills/:name/test", post(test_skill))
         .route("/skills/:name/references/*path", get(get_skill_reference))
         .route("/skills/:name/references/*path", put(save_skill_reference))
         .route(
@@ -214,15 +227,29 @@ pub fn routes() -> Router<Arc<super::routes::AppState>> {
             "/workspace-template/:name",
             delete(delete_workspace_template),
         )
+        .route(
+            "/workspace-template/:name/check-env",
+            post(check_workspace_template_env),
+        )
+        // Mission Templates
+        .route("/mission-template", get(list_mission_templates))
+        .route("/mission-template/:name", get(get_mission_template))
+        .route("/mission-template/:name", put(save_mission_template))
+        .route("/mission-template/:name", delete(delete_mission_template))
         // Init Scripts
         .route("/init-script", get(list_init_scripts))
         .route("/init-script/:name", get(get_init_script))
         .route("/init-script/:name", put(save_init_script))
         .route("/init-script/:name", delete(delete_init_script))
+        .route("/init-script-plan", post(plan_init_scripts))
         // Migration
         .route("/migrate", post(migrate_library))
         // Rename (works for all item types)
         .route("/rename/:item_type/:name", post(rename_item))
+        // History and rollback (any item type)
+        .route("/history/:item_type/:name", get(get_item_history))
+        .route("/diff/:item_type/:name", get(get_item_diff))
+        .route("/restore/:item_type/:name", post(restore_item))
         // OpenCode Settings (oh-my-opencode.json)
         .route("/opencode/settings", get(get_opencode_settings))
         .route("/opencode/settings", put(save_opencode_settings))
@@ -314,6 +341,32 @@ pub struct CommitRequest {
     message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    from: String,
+    /// Defaults to `HEAD`.
+    #[serde(default)]
+    to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreRequest {
+    revision: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeepenRequest {
+    /// Number of additional commits to fetch; omit for the full history.
+    #[serde(default)]
+    commits: Option<u32>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SaveContentRequest {
     content: String,
@@ -359,12 +412,35 @@ pub struct SaveWorkspaceTemplateRequest {
     pub shared_network: Option<bool>,
     /// Tailscale networking mode (only relevant when shared_network is false).
     pub tailscale_mode: Option<crate::workspace::TailscaleMode>,
+    /// Outbound network policy for workspaces created from this template.
+    pub egress_policy: Option<crate::egress::EgressPolicy>,
+    /// microVM isolation and sizing for workspaces created from this template.
+    pub microvm: Option<crate::microvm::MicroVmSpec>,
+    /// GPUs to pass through to workspaces created from this template.
+    pub gpu: Option<crate::gpu::GpuRequest>,
     /// MCP server names to enable for workspaces created from this template.
     #[serde(default)]
     pub mcps: Option<Vec<String>>,
     /// Config profile to use for workspaces created from this template.
     #[serde(default)]
     pub config_profile: Option<String>,
+    /// Declared environment variables (required/optional, defaults, validation).
+    #[serde(default)]
+    pub env_schema: Option<Vec<TemplateEnvVar>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckTemplateEnvRequest {
+    /// Values the workspace would be created with, on top of the template's own.
+    #[serde(default)]
+    pub env_vars: HashMap<String, String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct CheckTemplateEnvResponse {
+    pub valid: bool,
+    pub issues: Vec<EnvVarIssue>,
+    pub env_schema: Vec<TemplateEnvVar>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -524,6 +600,28 @@ async fn push_library(
         .map_err(internal_error)
 }
 
+/// POST /api/library/deepen - Fetch history missing from a shallow clone.
+///
+/// Library clones are shallow by default; flows that need older commits call
+/// this first. Returns the updated status.
+async fn deepen_library(
+    State(state): State<Arc<super::routes::AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<DeepenRequest>,
+) -> Result<Json<LibraryStatus>, (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    library.deepen(req.commits).await.map_err(internal_error)?;
+    library.status().await.map(Json).map_err(internal_error)
+}
+
+/// GET /api/library/clone-progress - Progress of the most recent library clone.
+///
+/// Does not require the library to be initialized, so it can be polled while
+/// the initial clone is still running.
+async fn get_clone_progress() -> Json<Option<CloneProgress>> {
+    Json(clone_progress())
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // MCP Servers
 // ─────────────────────────────────────────────────────────────────────────────
@@ -1158,15 +1256,17 @@ async fn list_workspace_templates(
 /// GET /api/library/workspace-template/:name - Get workspace template.
 async fn get_workspace_template(
     State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
     Path(name): Path<String>,
     headers: HeaderMap,
 ) -> Result<Json<WorkspaceTemplate>, (StatusCode, String)> {
     let library = ensure_library(&state, &headers).await?;
-    library
+    let mut template = library
         .get_workspace_template(&name)
         .await
-        .map(Json)
-        .map_err(not_found_or_internal)
+        .map_err(not_found_or_internal)?;
+    super::workspaces::mask_env_values(&mut template.env_vars, user.role);
+    Ok(Json(template))
 }
 
 /// PUT /api/library/workspace-template/:name - Save workspace template.
@@ -1188,22 +1288,58 @@ async fn save_workspace_template(
             ));
         }
     }
+    if let Some(policy) = req.egress_policy.as_ref() {
+        policy
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if let Some(spec) = req.microvm.as_ref() {
+        spec.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if let Some(gpu) = req.gpu.as_ref() {
+        gpu.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        if req.microvm.is_some() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "gpu cannot be combined with microvm".to_string(),
+            ));
+        }
+    }
+    let env_schema = req.env_schema.clone().unwrap_or_default();
+    env_schema::validate_schema(&env_schema)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
 
     let library = ensure_library(&state, &headers).await?;
+    let mut env_vars = req.env_vars.unwrap_or_default();
+    if env_vars
+        .values()
+        .any(|v| v == super::workspaces::MASKED_ENV_VALUE)
+    {
+        let stored = library
+            .get_workspace_template(&name)
+            .await
+            .map(|t| t.env_vars)
+            .unwrap_or_default();
+        env_vars = super::workspaces::restore_masked_env_values(env_vars, &stored);
+    }
     let template = WorkspaceTemplate {
         name: name.clone(),
         description: req.description.clone(),
         path: format!("workspace-template/{}.json", name),
         distro: req.distro.clone(),
         skills: sanitize_skill_list(req.skills.unwrap_or_default()),
-        env_vars: req.env_vars.unwrap_or_default(),
+        env_vars,
         encrypted_keys: req.encrypted_keys.unwrap_or_default(),
         init_scripts: req.init_scripts.unwrap_or_default(),
         init_script: req.init_script.unwrap_or_default(),
         shared_network: req.shared_network,
         tailscale_mode: req.tailscale_mode,
+        egress_policy: req.egress_policy.clone(),
+        microvm: req.microvm.clone(),
+        gpu: req.gpu.clone(),
         mcps: req.mcps.unwrap_or_default(),
         config_profile: req.config_profile.clone(),
+        env_schema,
     };
 
     library
@@ -1218,6 +1354,32 @@ async fn save_workspace_template(
         .map_err(internal_error)
 }
 
+/// POST /api/library/workspace-template/:name/check-env - Check environment variables
+/// against the template's declared schema before creating a workspace from it.
+async fn check_workspace_template_env(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<CheckTemplateEnvRequest>,
+) -> Result<Json<CheckTemplateEnvResponse>, (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    let template = library
+        .get_workspace_template(&name)
+        .await
+        .map_err(not_found_or_internal)?;
+
+    let mut env_vars = template.env_vars;
+    env_vars.extend(req.env_vars);
+    env_schema::apply_defaults(&template.env_schema, &mut env_vars);
+    let issues = env_schema::check(&template.env_schema, &env_vars);
+
+    Ok(Json(CheckTemplateEnvResponse {
+        valid: issues.is_empty(),
+        issues,
+        env_schema: template.env_schema,
+    }))
+}
+
 /// DELETE /api/library/workspace-template/:name - Delete workspace template.
 async fn delete_workspace_template(
     State(state): State<Arc<super::routes::AppState>>,
@@ -1237,6 +1399,80 @@ async fn delete_workspace_template(
         .map_err(internal_error)
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Mission Templates
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// GET /api/library/mission-template - List mission templates.
+async fn list_mission_templates(
+    State(state): State<Arc<super::routes::AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<MissionTemplateSummary>>, (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    library
+        .list_mission_templates()
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// GET /api/library/mission-template/:name - Get mission template.
+async fn get_mission_template(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<MissionTemplate>, (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    library
+        .get_mission_template(&name)
+        .await
+        .map(Json)
+        .map_err(not_found_or_internal)
+}
+
+/// PUT /api/library/mission-template/:name - Save mission template.
+async fn save_mission_template(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(template): Json<MissionTemplate>,
+) -> Result<(StatusCode, String), (StatusCode, String)> {
+    template
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let library = ensure_library(&state, &headers).await?;
+    library
+        .save_mission_template(&name, &template)
+        .await
+        .map(|_| {
+            (
+                StatusCode::OK,
+                "Mission template saved successfully".to_string(),
+            )
+        })
+        .map_err(internal_error)
+}
+
+/// DELETE /api/library/mission-template/:name - Delete mission template.
+async fn delete_mission_template(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, String), (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    library
+        .delete_mission_template(&name)
+        .await
+        .map(|_| {
+            (
+                StatusCode::OK,
+                "Mission template deleted successfully".to_string(),
+            )
+        })
+        .map_err(internal_error)
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Init Scripts
 // ─────────────────────────────────────────────────────────────────────────────
@@ -1283,6 +1519,22 @@ async fn save_init_script(
         .map_err(internal_error)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct InitScriptPlanRequest {
+    pub fragments: Vec<String>,
+}
+
+/// POST /api/library/init-script-plan - Preview the execution order of init script
+/// fragments, including parallel groups and dependency warnings.
+async fn plan_init_scripts(
+    State(state): State<Arc<super::routes::AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<InitScriptPlanRequest>,
+) -> Result<Json<InitScriptPlan>, (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    Ok(Json(library.plan_init_scripts(&req.fragments).await))
+}
+
 /// DELETE /api/library/init-script/:name - Delete an init script fragment.
 async fn delete_init_script(
     State(state): State<Arc<super::routes::AppState>>,
@@ -1656,23 +1908,7 @@ async fn rename_item(
     headers: HeaderMap,
     Json(req): Json<RenameRequest>,
 ) -> Result<Json<RenameResult>, (StatusCode, String)> {
-    // Parse item type
-    let item_type = match item_type_str.as_str() {
-        "skill" => ItemType::Skill,
-        "command" => ItemType::Command,
-        "agent" => ItemType::Agent,
-        "tool" => ItemType::Tool,
-        "workspace-template" => ItemType::WorkspaceTemplate,
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                format!(
-                    "Invalid item type '{}'. Valid types: skill, command, agent, tool, workspace-template",
-                    item_type_str
-                ),
-            ))
-        }
-    };
+    let item_type = parse_item_type(&item_type_str)?;
 
     let library = ensure_library(&state, &headers).await?;
 
@@ -2339,6 +2575,129 @@ async fn copy_dir_recursive(
 // Unit Tests
 // ─────────────────────────────────────────────────────────────────────────────
 
+// ─────────────────────────────────────────────────────────────────────────────
+// History and Rollback
+// ─────────────────────────────────────────────────────────────────────────────
+
+fn parse_item_type(item_type: &str) -> Result<ItemType, (StatusCode, String)> {
+    ItemType::parse(item_type).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Invalid item type '{}'. Valid types: skill, command, agent, tool, workspace-template",
+                item_type
+            ),
+        )
+    })
+}
+
+/// Map history errors: bad revisions are 400, missing revisions/items 404.
+fn history_error(e: anyhow::Error) -> (StatusCode, String) {
+    let msg = e.to_string();
+    if msg.starts_with("Invalid revision") || msg.starts_with("Name ") {
+        (StatusCode::BAD_REQUEST, msg)
+    } else {
+        not_found_or_internal(msg)
+    }
+}
+
+/// POST /api/library/skills/:name/test - Dry-run a skill in a throwaway container.
+///
+/// Runs the skill's setup commands against an ephemeral snapshot of a workspace
+/// (or a scratch container built from a template) and optionally a smoke-test
+/// prompt, and reports per-command output and failures.
+async fn test_skill(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<super::skill_test::SkillTestRequest>,
+) -> Result<Json<super::skill_test::SkillTestReport>, (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    super::skill_test::run(&state, library.as_ref(), &name, req)
+        .await
+        .map(Json)
+}
+
+/// GET /api/library/skills/:name/history - Commits that touched a skill.
+async fn get_skill_history(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<HistoryQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<HistoryEntry>>, (StatusCode, String)> {
+    let library = ensure_library(&state, &headers).await?;
+    library
+        .item_history(
+            ItemType::Skill,
+            &name,
+            query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+        )
+        .await
+        .map(Json)
+        .map_err(history_error)
+}
+
+/// GET /api/library/history/:item_type/:name - Commits that touched an item.
+async fn get_item_history(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path((item_type, name)): Path<(String, String)>,
+    Query(query): Query<HistoryQuery>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<HistoryEntry>>, (StatusCode, String)> {
+    let item_type = parse_item_type(&item_type)?;
+    let library = ensure_library(&state, &headers).await?;
+    library
+        .item_history(
+            item_type,
+            &name,
+            query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+        )
+        .await
+        .map(Json)
+        .map_err(history_error)
+}
+
+/// GET /api/library/diff/:item_type/:name?from=<rev>&to=<rev> - Diff an item between revisions.
+async fn get_item_diff(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path((item_type, name)): Path<(String, String)>,
+    Query(query): Query<DiffQuery>,
+    headers: HeaderMap,
+) -> Result<Json<ItemDiff>, (StatusCode, String)> {
+    let item_type = parse_item_type(&item_type)?;
+    let library = ensure_library(&state, &headers).await?;
+    library
+        .item_diff(item_type, &name, &query.from, query.to.as_deref())
+        .await
+        .map(Json)
+        .map_err(history_error)
+}
+
+/// POST /api/library/restore/:item_type/:name - Restore an item to a prior revision.
+///
+/// The restore is recorded as a new commit containing only this item, so it
+/// can itself be reverted. Skills are re-synced to workspaces afterwards.
+async fn restore_item(
+    State(state): State<Arc<super::routes::AppState>>,
+    Path((item_type, name)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<RestoreResult>, (StatusCode, String)> {
+    let item_type = parse_item_type(&item_type)?;
+    let library = ensure_library(&state, &headers).await?;
+    let author = extract_git_author(&headers);
+    let result = library
+        .restore_item(item_type, &name, &req.revision, author.as_ref())
+        .await
+        .map_err(history_error)?;
+
+    if item_type == ItemType::Skill && result.commit.is_some() {
+        sync_skill_to_workspaces(&state, library.as_ref(), &name).await;
+    }
+
+    Ok(Json(result))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
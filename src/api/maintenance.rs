@@ -0,0 +1,490 @@
+//! Nightly self-maintenance.
+//!
+//! A built-in job that keeps a long-running instance tidy. It runs these steps
+//! in order, each reported as `ok`, `skipped` or `failed`:
+//! - `sync_library` - pull the configuration library from its remote
+//! - `cleanup_opencode_sessions` - delete stale OpenCode sessions
+//! - `export_events` - upload old events to object storage (see
+//!   `event_export`)
+//! - `prune_events` - delete old tool/streaming events of finished missions,
+//!   skipped when the export failed
+//! - `gc_workspaces` - delete directories of long-finished missions
+//!
+//! Steps that delete data are off by default and enabled individually in the
+//! `maintenance` settings (`PUT /api/settings`). Each run is recorded as a
+//! completed (or, if a step failed, failed) mission titled
+//! "Maintenance YYYY-MM-DD" whose final message is the report, so runs show up
+//! next to regular missions.
+//!
+//! When `maintenance.enabled` is set, the scheduler runs the job once a day
+//! after `run_at_hour` (UTC). `POST /api/control/maintenance/run` runs it now.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, MissionStatus};
+use super::library::SharedLibrary;
+use super::mission_store::{now_string, MissionHistoryEntry, MissionStore};
+use super::routes::AppState;
+use crate::config::Config;
+use crate::settings::MaintenanceSettings;
+use crate::workspace::{self, SharedWorkspaceStore};
+
+/// How often the scheduler checks whether the nightly run is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Terminal reason recorded on maintenance missions.
+pub const MAINTENANCE_REASON: &str = "maintenance";
+
+/// Page size used when scanning missions for workspace GC.
+const MISSION_PAGE_SIZE: usize = 500;
+
+/// Set while a run is in progress, so scheduled and manual runs never overlap.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Ok,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub step: &'static str,
+    pub status: StepStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    pub started_at: String,
+    pub finished_at: String,
+    pub steps: Vec<StepReport>,
+    /// Mission the report was recorded in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mission_id: Option<Uuid>,
+}
+
+impl MaintenanceReport {
+    pub fn failed(&self) -> bool {
+        self.steps.iter().any(|s| s.status == StepStatus::Failed)
+    }
+
+    /// Markdown summary recorded as the maintenance mission's final message.
+    pub fn summary(&self) -> String {
+        let failures = self
+            .steps
+            .iter()
+            .filter(|s| s.status == StepStatus::Failed)
+            .count();
+        let mut out = match failures {
+            0 => "Maintenance finished.".to_string(),
+            1 => "Maintenance finished with 1 failed step.".to_string(),
+            n => format!("Maintenance finished with {} failed steps.", n),
+        };
+        let locale = crate::locale::LocaleSettings::resolve(None);
+        out.push_str(&format!(
+            "\n\nStarted {}, finished {}.\n",
+            locale.format_timestamp(&self.started_at),
+            locale.format_timestamp(&self.finished_at)
+        ));
+        for step in &self.steps {
+            let status = match step.status {
+                StepStatus::Ok => "ok",
+                StepStatus::Skipped => "skipped",
+                StepStatus::Failed => "FAILED",
+            };
+            out.push_str(&format!("\n- `{}` {}: {}", step.step, status, step.detail));
+        }
+        out
+    }
+}
+
+/// What the job operates on.
+#[derive(Clone)]
+pub struct MaintenanceContext {
+    pub config: Config,
+    pub store: Arc<dyn MissionStore>,
+    pub library: SharedLibrary,
+    pub workspaces: SharedWorkspaceStore,
+    /// User whose store this is (the event export tenant)
+    pub user_id: String,
+}
+
+fn step(step: &'static str, result: Result<Option<String>, String>) -> StepReport {
+    match result {
+        Ok(Some(detail)) => StepReport {
+            step,
+            status: StepStatus::Ok,
+            detail,
+        },
+        Ok(None) => StepReport {
+            step,
+            status: StepStatus::Skipped,
+            detail: "disabled".to_string(),
+        },
+        Err(detail) => StepReport {
+            step,
+            status: StepStatus::Failed,
+            detail,
+        },
+    }
+}
+
+async fn sync_library(ctx: &MaintenanceContext) -> Result<Option<String>, String> {
+    let Some(library) = ctx.library.read().await.clone() else {
+        return Ok(Some("no library configured".to_string()));
+    };
+    library.sync().await.map_err(|e| e.to_string())?;
+    Ok(Some("pulled latest changes".to_string()))
+}
+
+async fn cleanup_opencode_sessions(
+    ctx: &MaintenanceContext,
+    max_age_hours: u64,
+) -> Result<Option<String>, String> {
+    let client =
+        crate::opencode::OpenCodeClient::new(ctx.config.opencode_base_url.clone(), None, false);
+    let deleted = client
+        .cleanup_old_sessions(Duration::from_secs(max_age_hours * 3600))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(format!(
+        "deleted {} sessions idle for more than {}h",
+        deleted, max_age_hours
+    )))
+}
+
+async fn prune_events(
+    ctx: &MaintenanceContext,
+    retention_days: u64,
+) -> Result<Option<String>, String> {
+    let cutoff = (Utc::now() - chrono::Duration::days(retention_days as i64)).to_rfc3339();
+    let deleted = ctx.store.prune_events_before(&cutoff).await?;
+    Ok(Some(format!(
+        "deleted {} events older than {} days",
+        deleted, retention_days
+    )))
+}
+
+/// Whether a mission's directory may be deleted.
+fn collectable(status: MissionStatus, updated_at: &str, cutoff: DateTime<Utc>) -> bool {
+    matches!(
+        status,
+        MissionStatus::Completed | MissionStatus::Failed | MissionStatus::NotFeasible
+    ) && DateTime::parse_from_rfc3339(updated_at).is_ok_and(|t| t < cutoff)
+}
+
+async fn gc_workspaces(
+    ctx: &MaintenanceContext,
+    retention_days: u64,
+) -> Result<Option<String>, String> {
+    let cutoff = Utc::now() - chrono::Duration::days(retention_days as i64);
+    let mut candidates = Vec::new();
+    // Mission directories are named after the first 8 characters of the ID;
+    // never delete one that another live mission could share.
+    let mut keep: HashSet<String> = HashSet::new();
+    let mut offset = 0;
+    loop {
+        let page = ctx.store.list_missions(MISSION_PAGE_SIZE, offset).await?;
+        let len = page.len();
+        for mission in page {
+            if collectable(mission.status, &mission.updated_at, cutoff) {
+                candidates.push(mission);
+            } else {
+                keep.insert(mission.id.to_string()[..8].to_string());
+            }
+        }
+        if len < MISSION_PAGE_SIZE {
+            break;
+        }
+        offset += len;
+    }
+
+    let mut removed = 0;
+    let mut errors = Vec::new();
+    for mission in candidates {
+        if keep.contains(&mission.id.to_string()[..8]) {
+            continue;
+        }
+        let root = workspace::resolve_workspace_root(
+            &ctx.workspaces,
+            &ctx.config,
+            Some(mission.workspace_id),
+        )
+        .await;
+        let dir = workspace::mission_workspace_dir_for_root(&root, mission.id);
+        if !dir.exists() {
+            continue;
+        }
+        match tokio::fs::remove_dir_all(&dir).await {
+            Ok(()) => {
+                removed += 1;
+                let snapshots = crate::workspace_snapshot::snapshot_dir(&dir);
+                let _ = tokio::fs::remove_dir_all(snapshots).await;
+            }
+            Err(e) => errors.push(format!("{}: {}", dir.display(), e)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format!(
+            "removed {} mission directories, failed on {}",
+            removed,
+            errors.join("; ")
+        ));
+    }
+    Ok(Some(format!(
+        "removed {} mission directories finished more than {} days ago",
+        removed, retention_days
+    )))
+}
+
+/// Run every maintenance step allowed by `settings`.
+pub async fn run_maintenance(
+    ctx: &MaintenanceContext,
+    settings: &MaintenanceSettings,
+) -> MaintenanceReport {
+    let started_at = now_string();
+    let mut steps = Vec::new();
+
+    steps.push(step(
+        "sync_library",
+        if settings.sync_library {
+            sync_library(ctx).await
+        } else {
+            Ok(None)
+        },
+    ));
+    steps.push(step(
+        "cleanup_opencode_sessions",
+        if settings.cleanup_opencode_sessions {
+            cleanup_opencode_sessions(ctx, settings.opencode_session_max_age_hours).await
+        } else {
+            Ok(None)
+        },
+    ));
+    let export = step(
+        "export_events",
+        if settings.export_events {
+            super::event_export::export_events(
+                &ctx.store,
+                &ctx.config.working_dir,
+                &ctx.user_id,
+                &settings.event_export,
+            )
+            .await
+            .map(Some)
+        } else {
+            Ok(None)
+        },
+    );
+    let export_failed = export.status == StepStatus::Failed;
+    steps.push(export);
+    steps.push(step(
+        "prune_events",
+        if export_failed && settings.prune_events {
+            Err("not run because export_events failed".to_string())
+        } else if settings.prune_events {
+            prune_events(ctx, settings.event_retention_days).await
+        } else {
+            Ok(None)
+        },
+    ));
+    steps.push(step(
+        "gc_workspaces",
+        if settings.gc_workspaces {
+            gc_workspaces(ctx, settings.workspace_retention_days).await
+        } else {
+            Ok(None)
+        },
+    ));
+
+    MaintenanceReport {
+        started_at,
+        finished_at: now_string(),
+        steps,
+        mission_id: None,
+    }
+}
+
+/// Record `report` as a finished maintenance mission.
+async fn record(store: &Arc<dyn MissionStore>, report: &MaintenanceReport) -> Result<Uuid, String> {
+    let title = format!("Maintenance {}", Utc::now().format("%Y-%m-%d"));
+    let mission = store
+        .create_mission(Some(&title), None, None, None, None, None, None)
+        .await?;
+    let history = [
+        MissionHistoryEntry {
+            role: "user".to_string(),
+            content: "Run scheduled maintenance".to_string(),
+        },
+        MissionHistoryEntry {
+            role: "assistant".to_string(),
+            content: report.summary(),
+        },
+    ];
+    store.update_mission_history(mission.id, &history).await?;
+    let status = if report.failed() {
+        MissionStatus::Failed
+    } else {
+        MissionStatus::Completed
+    };
+    store
+        .update_mission_status_with_reason(mission.id, status, Some(MAINTENANCE_REASON))
+        .await?;
+    Ok(mission.id)
+}
+
+/// Run the job and record it, unless a run is already in progress.
+pub async fn run_and_record(
+    ctx: &MaintenanceContext,
+    settings: &MaintenanceSettings,
+) -> Option<MaintenanceReport> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return None;
+    }
+    let mut report = run_maintenance(ctx, settings).await;
+    match record(&ctx.store, &report).await {
+        Ok(id) => report.mission_id = Some(id),
+        Err(e) => tracing::warn!(error = %e, "Failed to record maintenance mission"),
+    }
+    RUNNING.store(false, Ordering::SeqCst);
+    tracing::info!(
+        failed = report.failed(),
+        mission_id = ?report.mission_id,
+        "Maintenance run finished"
+    );
+    Some(report)
+}
+
+/// Whether the nightly run is due at `now`.
+fn is_due(now: DateTime<Utc>, run_at_hour: u8, last_run: Option<NaiveDate>) -> bool {
+    now.hour() >= u32::from(run_at_hour) && last_run != Some(now.date_naive())
+}
+
+/// Background task that runs the job nightly while `maintenance.enabled` is set.
+pub async fn maintenance_scheduler_loop(ctx: MaintenanceContext) {
+    // After a restart past today's slot, wait for tomorrow instead of running
+    // again right away.
+    let now = Utc::now();
+    let mut last_run = (now.hour()
+        >= u32::from(crate::settings::maintenance_settings_cached().run_at_hour))
+    .then(|| now.date_naive());
+
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        let settings = crate::settings::maintenance_settings_cached();
+        let now = Utc::now();
+        if !settings.enabled || !is_due(now, settings.run_at_hour, last_run) {
+            continue;
+        }
+        if run_and_record(&ctx, &settings).await.is_some() {
+            last_run = Some(now.date_naive());
+        }
+    }
+}
+
+/// POST /api/control/maintenance/run - Run the maintenance job now.
+pub async fn run_maintenance_now(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<MaintenanceReport>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let ctx = MaintenanceContext {
+        config: state.config.clone(),
+        store: Arc::clone(&control.mission_store),
+        library: state.library.clone(),
+        workspaces: state.workspaces.clone(),
+        user_id: user.id.clone(),
+    };
+    let settings = crate::settings::maintenance_settings_cached();
+    run_and_record(&ctx, &settings).await.map(Json).ok_or((
+        StatusCode::CONFLICT,
+        "A maintenance run is already in progress".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn nightly_run_is_due_once_per_day_after_the_hour() {
+        let at = |h| Utc.with_ymd_and_hms(2026, 3, 4, h, 30, 0).unwrap();
+        let today = at(0).date_naive();
+        let yesterday = today.pred_opt();
+        assert!(!is_due(at(2), 3, yesterday));
+        assert!(is_due(at(3), 3, yesterday));
+        assert!(is_due(at(23), 3, None));
+        assert!(!is_due(at(4), 3, Some(today)));
+    }
+
+    #[test]
+    fn only_long_finished_missions_are_collected() {
+        let cutoff = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let old = "2026-02-01T00:00:00+00:00";
+        assert!(collectable(MissionStatus::Completed, old, cutoff));
+        assert!(collectable(MissionStatus::Failed, old, cutoff));
+        assert!(!collectable(
+            MissionStatus::Completed,
+            "2026-03-02T00:00:00+00:00",
+            cutoff
+        ));
+        assert!(!collectable(MissionStatus::Interrupted, old, cutoff));
+        assert!(!collectable(MissionStatus::Blocked, old, cutoff));
+        assert!(!collectable(MissionStatus::Completed, "garbage", cutoff));
+    }
+
+    #[tokio::test]
+    async fn disabled_steps_are_skipped_and_run_is_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::new(dir.path().to_path_buf());
+        let store: Arc<dyn MissionStore> =
+            Arc::new(crate::api::mission_store::InMemoryMissionStore::new());
+        let ctx = MaintenanceContext {
+            workspaces: Arc::new(workspace::WorkspaceStore::new(dir.path().to_path_buf()).await),
+            config,
+            store: Arc::clone(&store),
+            library: Arc::new(tokio::sync::RwLock::new(None)),
+            user_id: "default".to_string(),
+        };
+
+        let report = run_and_record(&ctx, &MaintenanceSettings::default())
+            .await
+            .unwrap();
+        let statuses: Vec<(&str, StepStatus)> =
+            report.steps.iter().map(|s| (s.step, s.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("sync_library", StepStatus::Ok),
+                ("cleanup_opencode_sessions", StepStatus::Skipped),
+                ("export_events", StepStatus::Skipped),
+                ("prune_events", StepStatus::Skipped),
+                ("gc_workspaces", StepStatus::Skipped),
+            ]
+        );
+
+        let mission = store
+            .get_mission(report.mission_id.unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(mission.status, MissionStatus::Completed);
+        assert_eq!(mission.terminal_reason.as_deref(), Some(MAINTENANCE_REASON));
+        assert!(mission.history[1]
+            .content
+            .contains("`prune_events` skipped"));
+    }
+}
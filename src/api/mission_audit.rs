@@ -0,0 +1,205 @@
+//! Verification-only (audit) missions.
+//!
+//! A mission created with `"mode": "audit"` runs a read-only audit of its
+//! workspace: tests, lints, a security scan and an architecture summary. It
+//! cannot change anything: the workspace MCP registers only read-only tools
+//! (see [`crate::tools::audit`]), Claude Code runs with its editing and shell
+//! tools denied and Codex with a read-only sandbox. Backends whose built-in
+//! tools cannot be restricted refuse audit missions.
+//!
+//! The agent records a structured report with `submit_audit_report`; the
+//! control session stores it on the mission. `GET
+//! /api/control/missions/:id/audit-report` returns it along with its
+//! suggested follow-up missions as a ready-made
+//! `POST /api/control/missions/batch` request.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, CreateMissionRequest};
+use super::mission_batch::{BatchMissionSpec, CreateBatchRequest};
+use super::mission_store::Mission;
+use super::routes::AppState;
+use crate::tools::audit::AuditReport;
+
+/// What a mission may do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissionMode {
+    #[default]
+    Standard,
+    /// Read-only verification that produces an audit report
+    Audit,
+}
+
+impl MissionMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Audit => "audit",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "audit" => Self::Audit,
+            _ => Self::Standard,
+        }
+    }
+}
+
+/// Backends that can run audit missions without write access.
+pub const AUDIT_BACKENDS: &[&str] = &["claudecode", "codex"];
+
+/// Claude Code built-in tools denied to audit missions.
+pub const CLAUDECODE_DENIED_TOOLS: &[&str] =
+    &["Bash", "Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Refuse audit missions on backends that cannot be made read-only.
+pub fn check_backend(mode: MissionMode, backend: &str) -> Result<(), String> {
+    if mode == MissionMode::Audit && !AUDIT_BACKENDS.contains(&backend) {
+        return Err(format!(
+            "Audit missions need one of the {} backends; '{}' cannot restrict its built-in tools to read-only",
+            AUDIT_BACKENDS.join(", "),
+            backend
+        ));
+    }
+    Ok(())
+}
+
+/// Instructions added to every turn of an audit mission.
+pub const AUDIT_INSTRUCTIONS: &str = r#"
+
+**AUDIT MODE (read-only):**
+- This mission verifies the project without changing it. Do not modify files, commits, branches or external systems; tools that could are not available.
+- Run run_audit_check for tests, lints and security, and read the code to summarize its architecture.
+- Call submit_audit_report with the checks, findings (severity, location) and suggested follow-up missions with self-contained prompts, then call complete_mission."#;
+
+/// Suggested follow-up missions as a batch request that runs them in the
+/// audited mission's workspace, on its backend.
+pub fn follow_up_batch(mission: &Mission, report: &AuditReport) -> Option<CreateBatchRequest> {
+    if report.follow_ups.is_empty() {
+        return None;
+    }
+    let missions = report
+        .follow_ups
+        .iter()
+        .map(|follow_up| BatchMissionSpec {
+            mission: CreateMissionRequest {
+                title: Some(follow_up.title.clone()),
+                workspace_id: Some(mission.workspace_id),
+                backend: Some(mission.backend.clone()),
+                config_profile: mission.config_profile.clone(),
+                tags: mission.tags.clone(),
+                ..Default::default()
+            },
+            prompt: Some(follow_up.prompt.clone()),
+        })
+        .collect();
+    Some(CreateBatchRequest {
+        title: Some(format!(
+            "Follow-ups of {}",
+            mission.title.as_deref().unwrap_or("audit")
+        )),
+        missions,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditReportResponse {
+    pub mission_id: Uuid,
+    pub mode: MissionMode,
+    /// None until the agent submits its report
+    pub report: Option<AuditReport>,
+    /// Body for `POST /api/control/missions/batch` starting the follow-ups
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_up_batch: Option<CreateBatchRequest>,
+}
+
+/// GET /api/control/missions/:id/audit-report
+pub async fn get_audit_report(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+) -> Result<Json<AuditReportResponse>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let mission = control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or((StatusCode::NOT_FOUND, "Mission not found".to_string()))?;
+    if mission.mode != MissionMode::Audit {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Mission is not an audit mission".to_string(),
+        ));
+    }
+    let follow_up_batch = mission
+        .audit_report
+        .as_ref()
+        .and_then(|report| follow_up_batch(&mission, report));
+    Ok(Json(AuditReportResponse {
+        mission_id,
+        mode: mission.mode,
+        report: mission.audit_report,
+        follow_up_batch,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn audit_needs_a_restrictable_backend() {
+        assert!(check_backend(MissionMode::Audit, "claudecode").is_ok());
+        assert!(check_backend(MissionMode::Audit, "codex").is_ok());
+        assert!(check_backend(MissionMode::Audit, "amp")
+            .unwrap_err()
+            .contains("'amp'"));
+        assert!(check_backend(MissionMode::Standard, "opencode").is_ok());
+        assert_eq!(
+            MissionMode::parse(MissionMode::Audit.as_str()),
+            MissionMode::Audit
+        );
+        assert_eq!(MissionMode::parse("bogus"), MissionMode::Standard);
+    }
+
+    #[test]
+    fn follow_ups_become_a_batch_in_the_same_workspace() {
+        let mission: Mission = serde_json::from_value(json!({
+            "id": Uuid::new_v4(),
+            "status": "completed",
+            "title": "Audit api",
+            "workspace_id": Uuid::new_v4(),
+            "backend": "codex",
+            "history": [],
+            "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-01T00:00:00Z",
+            "mode": "audit"
+        }))
+        .unwrap();
+        let report = AuditReport::from_args(&json!({
+            "summary": "ok",
+            "follow_ups": [{ "title": "Fix flaky test", "prompt": "Stabilize test_x" }]
+        }))
+        .unwrap();
+        let batch = follow_up_batch(&mission, &report).unwrap();
+        assert_eq!(batch.title.as_deref(), Some("Follow-ups of Audit api"));
+        let spec = &batch.missions[0];
+        assert_eq!(spec.mission.workspace_id, Some(mission.workspace_id));
+        assert_eq!(spec.mission.backend.as_deref(), Some("codex"));
+        assert_eq!(spec.mission.mode, None);
+        assert_eq!(spec.prompt.as_deref(), Some("Stabilize test_x"));
+    }
+}
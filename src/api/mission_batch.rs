@@ -0,0 +1,520 @@
+//! Batch mission submission.
+//!
+//! `POST /api/control/missions/batch` creates up to [`MAX_BATCH_SIZE`] missions
+//! in one request (e.g. the same refactor across many workspaces). Every spec
+//! is validated before anything is created, and missions already created are
+//! deleted again if a later one fails, so a batch is created entirely or not
+//! at all. Specs with a `prompt` are started as parallel missions; when the
+//! parallel limit is reached the rest wait and start as slots free up.
+//!
+//! `GET /api/control/missions/batch/:id` aggregates progress, failures and
+//! total cost across the batch.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{
+    control_for_user, prepare_mission, AgentEvent, ControlCommand, ControlState,
+    CreateMissionRequest, MissionStatus, PreparedMission,
+};
+use super::mission_audit::MissionMode;
+use super::mission_priority;
+use super::mission_store::{now_string, Mission, MissionBatch, MissionStore, StoredEvent};
+use super::routes::AppState;
+
+/// Maximum number of missions in one batch.
+pub const MAX_BATCH_SIZE: usize = 50;
+
+/// How long the dispatcher waits for a parallel slot before retrying.
+const DISPATCH_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// One mission in a batch request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchMissionSpec {
+    #[serde(flatten)]
+    pub mission: CreateMissionRequest,
+    /// Initial message; when set the mission is started right away.
+    pub prompt: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateBatchRequest {
+    pub title: Option<String>,
+    pub missions: Vec<BatchMissionSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateBatchResponse {
+    pub batch: MissionBatch,
+    pub missions: Vec<Mission>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchMissionStatus {
+    pub mission_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal_reason: Option<String>,
+    pub cost_cents: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchStatusResponse {
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub created_at: String,
+    pub total: usize,
+    /// Missions that reached a terminal status.
+    pub finished: usize,
+    /// Mission counts keyed by status.
+    pub by_status: BTreeMap<String, usize>,
+    pub failures: Vec<BatchMissionStatus>,
+    pub total_cost_cents: u64,
+    pub missions: Vec<BatchMissionStatus>,
+}
+
+pub(crate) fn is_terminal(status: MissionStatus) -> bool {
+    !matches!(status, MissionStatus::Pending | MissionStatus::Active)
+}
+
+fn is_failure(status: MissionStatus) -> bool {
+    matches!(
+        status,
+        MissionStatus::Failed
+            | MissionStatus::Interrupted
+            | MissionStatus::Blocked
+            | MissionStatus::NotFeasible
+    )
+}
+
+/// Cost of an `assistant_message` event, accepting the legacy flat shape.
+pub(crate) fn event_cost_cents(event: &StoredEvent) -> u64 {
+    event
+        .metadata
+        .get("cost")
+        .and_then(|c| c.get("amount_cents"))
+        .or_else(|| event.metadata.get("cost_cents"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0)
+}
+
+pub(crate) async fn mission_cost_cents(store: &Arc<dyn MissionStore>, mission_id: Uuid) -> u64 {
+    store
+        .get_events(mission_id, Some(&["assistant_message"]), None, None)
+        .await
+        .map(|events| events.iter().map(event_cost_cents).sum())
+        .unwrap_or(0)
+}
+
+/// Create a batch of missions.
+pub async fn create_mission_batch(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<CreateBatchRequest>,
+) -> Result<Json<CreateBatchResponse>, (StatusCode, String)> {
+    if req.missions.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Batch must contain at least one mission".to_string(),
+        ));
+    }
+    if req.missions.len() > MAX_BATCH_SIZE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Batch contains {} missions (maximum {})",
+                req.missions.len(),
+                MAX_BATCH_SIZE
+            ),
+        ));
+    }
+
+    // Validate every spec before creating anything.
+    let mut prepared: Vec<(PreparedMission, Option<String>)> = Vec::new();
+    for (index, spec) in req.missions.iter().enumerate() {
+        let mission = prepare_mission(&state, Some(&spec.mission))
+            .await
+            .map_err(|(status, e)| (status, format!("missions[{}]: {}", index, e)))?;
+        let prompt = spec
+            .prompt
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string);
+        prepared.push((mission, prompt));
+    }
+
+    let control = control_for_user(&state, &user).await;
+    let store = control.mission_store.clone();
+    for (index, (mission, _)) in prepared.iter().enumerate() {
+        super::budget::check_cap(&store, mission.workspace_id, &mission.tags)
+            .await
+            .map_err(|(status, e)| (status, format!("missions[{}]: {}", index, e)))?;
+    }
+
+    let mut created: Vec<Mission> = Vec::new();
+    let mut starts: Vec<(Uuid, String)> = Vec::new();
+    for (mission, prompt) in prepared {
+        let result = store
+            .create_mission(
+                mission.title.as_deref(),
+                mission.workspace_id,
+                mission.agent.as_deref(),
+                mission.model_override.as_deref(),
+                mission.model_effort.as_deref(),
+                mission.backend.as_deref(),
+                mission.config_profile.as_deref(),
+            )
+            .await;
+        match result {
+            Ok(mut m) => {
+                m.locale = mission.locale;
+                m.tags = mission.tags;
+                m.sla = mission.sla;
+                m.step_budget = mission.step_budget;
+                m.priority = mission.priority;
+                m.mode = mission.mode;
+                let mut updated = Ok(());
+                if m.locale.is_some() {
+                    updated = store.update_mission_locale(m.id, m.locale.as_ref()).await;
+                }
+                if updated.is_ok() && !m.tags.is_empty() {
+                    updated = store.update_mission_tags(m.id, &m.tags).await;
+                }
+                if updated.is_ok() && m.sla.is_some() {
+                    updated = store.update_mission_sla(m.id, m.sla.as_ref()).await;
+                }
+                if updated.is_ok() && !mission.feature_flags.is_empty() {
+                    m.feature_flags.extend(mission.feature_flags);
+                    updated = store
+                        .update_mission_feature_flags(m.id, &m.feature_flags)
+                        .await;
+                }
+                if updated.is_ok() && m.step_budget.is_some() {
+                    updated = store.update_mission_step_budget(m.id, m.step_budget).await;
+                }
+                if updated.is_ok() && m.priority != 0 {
+                    updated = store.update_mission_priority(m.id, m.priority).await;
+                }
+                if updated.is_ok() && m.mode != MissionMode::Standard {
+                    updated = store.update_mission_mode(m.id, m.mode).await;
+                }
+                if let Err(e) = updated {
+                    created.push(m);
+                    rollback(&store, &created).await;
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to create batch mission: {}", e),
+                    ));
+                }
+                if let Some(prompt) = prompt {
+                    starts.push((m.id, prompt));
+                }
+                created.push(m);
+            }
+            Err(e) => {
+                rollback(&store, &created).await;
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to create batch mission: {}", e),
+                ));
+            }
+        }
+    }
+
+    let batch = MissionBatch {
+        id: Uuid::new_v4(),
+        title: req.title.clone(),
+        mission_ids: created.iter().map(|m| m.id).collect(),
+        created_at: now_string(),
+    };
+    let batch = match store.create_mission_batch(batch).await {
+        Ok(batch) => batch,
+        Err(e) => {
+            rollback(&store, &created).await;
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+        }
+    };
+
+    tracing::info!(
+        batch_id = %batch.id,
+        missions = created.len(),
+        to_start = starts.len(),
+        "Created mission batch"
+    );
+    if !starts.is_empty() {
+        tokio::spawn(dispatch_missions(
+            control,
+            format!("batch {}", batch.id),
+            starts,
+        ));
+    }
+
+    Ok(Json(CreateBatchResponse {
+        batch,
+        missions: created,
+    }))
+}
+
+async fn rollback(store: &Arc<dyn MissionStore>, created: &[Mission]) {
+    for mission in created {
+        if let Err(e) = store.delete_mission(mission.id).await {
+            tracing::warn!(mission_id = %mission.id, "Failed to roll back batch mission: {}", e);
+        }
+    }
+}
+
+/// Start pending missions as parallel slots become available.
+/// `source` identifies the submitter in logs (e.g. "batch <id>").
+///
+/// Waiting missions start in priority order across all of the user's
+/// dispatchers, and may preempt lower-priority ones (see
+/// [`mission_priority`](super::mission_priority)). Missions preempted
+/// earlier are dispatched here too, with their resume prompt.
+pub(crate) async fn dispatch_missions(
+    control: ControlState,
+    source: String,
+    starts: Vec<(Uuid, String)>,
+) {
+    let mut queue: Vec<(Uuid, String)> = starts;
+    loop {
+        // Refresh the waiting missions, skipping any deleted or started
+        // elsewhere since submission.
+        let mut waiting = Vec::with_capacity(queue.len());
+        for (mission_id, content) in queue.drain(..) {
+            match control.mission_store.get_mission(mission_id).await {
+                Ok(Some(m)) if m.status == MissionStatus::Pending || is_preempted(&m) => {
+                    mission_priority::wait(&control.user_id, mission_id, m.priority);
+                    waiting.push((m, content));
+                }
+                _ => mission_priority::stop_waiting(mission_id),
+            }
+        }
+        let Some(index) = waiting
+            .iter()
+            .position(|(m, _)| mission_priority::is_next(m.id))
+        else {
+            if waiting.is_empty() {
+                break;
+            }
+            queue = waiting.into_iter().map(|(m, c)| (m.id, c)).collect();
+            mission_priority::changed(DISPATCH_RETRY_INTERVAL).await;
+            continue;
+        };
+        let (mission, content) = waiting.remove(index);
+        queue = waiting.into_iter().map(|(m, c)| (m.id, c)).collect();
+        let mission_id = mission.id;
+
+        let (tx, rx) = oneshot::channel();
+        let sent = control
+            .cmd_tx
+            .send(ControlCommand::StartParallel {
+                mission_id,
+                content: content.clone(),
+                respond: tx,
+            })
+            .await;
+        if sent.is_err() {
+            tracing::warn!(source = %source, "Control session closed; stopping dispatch");
+            stop_all_waiting(mission_id, &queue);
+            return;
+        }
+
+        match rx.await {
+            Ok(Ok(())) => {
+                mission_priority::stop_waiting(mission_id);
+                if is_preempted(&mission) {
+                    mark_resumed(&control, mission_id).await;
+                }
+            }
+            Ok(Err(e))
+                if e.starts_with("Maximum parallel missions")
+                    || e.starts_with(super::fair_share::REFUSAL_PREFIX) =>
+            {
+                queue.insert(0, (mission_id, content));
+                if !mission_priority::preempt_for(&control, mission_id, mission.priority).await {
+                    mission_priority::changed(DISPATCH_RETRY_INTERVAL).await;
+                }
+            }
+            Ok(Err(e)) => {
+                mission_priority::stop_waiting(mission_id);
+                tracing::warn!(source = %source, mission_id = %mission_id, "Failed to start mission: {}", e);
+                let _ = control
+                    .mission_store
+                    .update_mission_status_with_reason(mission_id, MissionStatus::Failed, Some(&e))
+                    .await;
+            }
+            Err(_) => {
+                stop_all_waiting(mission_id, &queue);
+                return;
+            }
+        }
+    }
+    tracing::info!(source = %source, "All missions dispatched");
+}
+
+fn is_preempted(mission: &Mission) -> bool {
+    mission.status == MissionStatus::Interrupted
+        && mission.terminal_reason.as_deref() == Some(mission_priority::PREEMPTED_REASON)
+}
+
+fn stop_all_waiting(mission_id: Uuid, queue: &[(Uuid, String)]) {
+    mission_priority::stop_waiting(mission_id);
+    for (id, _) in queue {
+        mission_priority::stop_waiting(*id);
+    }
+}
+
+/// A preempted mission is running again.
+async fn mark_resumed(control: &ControlState, mission_id: Uuid) {
+    if let Err(e) = control
+        .mission_store
+        .update_mission_status(mission_id, MissionStatus::Active)
+        .await
+    {
+        tracing::warn!(mission_id = %mission_id, "Failed to mark preempted mission resumed: {}", e);
+        return;
+    }
+    let _ = control.events_tx.send(AgentEvent::MissionStatusChanged {
+        mission_id,
+        status: MissionStatus::Active,
+        summary: None,
+    });
+}
+
+/// Get aggregated status for a mission batch.
+pub async fn get_mission_batch(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(batch_id): Path<Uuid>,
+) -> Result<Json<BatchStatusResponse>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let store = control.mission_store.clone();
+    let batch = store
+        .get_mission_batch(batch_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Mission batch {} not found", batch_id),
+            )
+        })?;
+
+    let mut entries: Vec<(MissionStatus, BatchMissionStatus)> = Vec::new();
+    let mut by_status: BTreeMap<String, usize> = BTreeMap::new();
+    let mut finished = 0;
+    for mission_id in &batch.mission_ids {
+        let Some(mission) = store
+            .get_mission(*mission_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        else {
+            *by_status.entry("deleted".to_string()).or_default() += 1;
+            continue;
+        };
+        if is_terminal(mission.status) {
+            finished += 1;
+        }
+        *by_status.entry(mission.status.to_string()).or_default() += 1;
+        let cost_cents = mission_cost_cents(&store, mission.id).await;
+        entries.push((
+            mission.status,
+            BatchMissionStatus {
+                mission_id: mission.id,
+                title: mission.title,
+                status: mission.status.to_string(),
+                terminal_reason: mission.terminal_reason,
+                cost_cents,
+            },
+        ));
+    }
+
+    let failures = entries
+        .iter()
+        .filter(|(status, _)| is_failure(*status))
+        .map(|(_, m)| m.clone())
+        .collect();
+    let missions: Vec<BatchMissionStatus> = entries.into_iter().map(|(_, m)| m).collect();
+
+    Ok(Json(BatchStatusResponse {
+        id: batch.id,
+        title: batch.title,
+        created_at: batch.created_at,
+        total: batch.mission_ids.len(),
+        finished,
+        by_status,
+        failures,
+        total_cost_cents: missions.iter().map(|m| m.cost_cents).sum(),
+        missions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::mission_store::InMemoryMissionStore;
+
+    fn event(metadata: serde_json::Value) -> StoredEvent {
+        StoredEvent {
+            id: 1,
+            mission_id: Uuid::nil(),
+            sequence: 1,
+            event_type: "assistant_message".to_string(),
+            timestamp: now_string(),
+            event_id: None,
+            tool_call_id: None,
+            tool_name: None,
+            content: String::new(),
+            metadata,
+        }
+    }
+
+    #[test]
+    fn test_event_cost_accepts_both_shapes() {
+        let normalized = event(serde_json::json!({"cost": {"amount_cents": 12}, "cost_cents": 99}));
+        assert_eq!(event_cost_cents(&normalized), 12);
+        assert_eq!(
+            event_cost_cents(&event(serde_json::json!({"cost_cents": 7}))),
+            7
+        );
+        assert_eq!(event_cost_cents(&event(serde_json::json!({}))), 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_round_trip() {
+        let store = InMemoryMissionStore::new();
+        let mission = store
+            .create_mission(Some("a"), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let batch = MissionBatch {
+            id: Uuid::new_v4(),
+            title: Some("refactor".to_string()),
+            mission_ids: vec![mission.id],
+            created_at: now_string(),
+        };
+        store.create_mission_batch(batch.clone()).await.unwrap();
+        let loaded = store.get_mission_batch(batch.id).await.unwrap().unwrap();
+        assert_eq!(loaded.mission_ids, vec![mission.id]);
+        assert!(store
+            .get_mission_batch(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! In-memory cache for `GET /api/control/missions/:id`.
+//!
+//! Dashboards poll mission details every few seconds, and each poll costs
+//! several store queries (mission, timing, results). The cache keeps the
+//! serialized response per mission for `MISSION_CACHE_TTL_SECS` (default 5,
+//! `0` disables caching). Entries are dropped as soon as the mission's events
+//! show a change (status, title, messages, ...), so the TTL only bounds
+//! staleness for store updates that emit no event.
+//!
+//! Every response carries a strong `ETag` (a hash of the body) and
+//! `Cache-Control: no-cache`; a request whose `If-None-Match` matches gets
+//! `304 Not Modified` without a body, cached or not.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::control::AgentEvent;
+
+/// Missions kept at once; the oldest entry is dropped beyond this.
+const MAX_ENTRIES: usize = 1_000;
+/// How long invalidations are remembered to reject loads that raced them.
+const INVALIDATION_MEMORY: Duration = Duration::from_secs(60);
+
+/// A serialized mission detail response.
+#[derive(Debug, Clone)]
+pub struct CachedMission {
+    pub body: Bytes,
+    pub etag: String,
+    fetched: Instant,
+}
+
+impl CachedMission {
+    pub fn new(body: Vec<u8>) -> Self {
+        let digest = Sha256::digest(&body);
+        Self {
+            etag: format!("\"{}\"", hex::encode(&digest[..16])),
+            body: Bytes::from(body),
+            fetched: Instant::now(),
+        }
+    }
+
+    /// `304` when `If-None-Match` lists this entity's tag, otherwise the body.
+    pub fn into_response(self, request_headers: &HeaderMap) -> Response {
+        let etag = HeaderValue::from_str(&self.etag).expect("hex ETag is a valid header");
+        let cache_control = (header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        if if_none_match(request_headers, &self.etag) {
+            return (
+                StatusCode::NOT_MODIFIED,
+                [(header::ETAG, etag), cache_control],
+            )
+                .into_response();
+        }
+        (
+            [
+                (header::ETAG, etag),
+                cache_control,
+                (
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/json"),
+                ),
+            ],
+            self.body,
+        )
+            .into_response()
+    }
+}
+
+/// Whether `If-None-Match` matches `etag` (weak comparison, `*` matches).
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<Uuid, CachedMission>,
+    invalidated: HashMap<Uuid, Instant>,
+}
+
+/// Per-session cache of mission detail responses.
+#[derive(Debug)]
+pub struct MissionCache {
+    ttl: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl MissionCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A fresh entry for the mission, if any.
+    pub fn get(&self, id: Uuid) -> Option<CachedMission> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let inner = self.lock();
+        inner
+            .entries
+            .get(&id)
+            .filter(|entry| entry.fetched.elapsed() < self.ttl)
+            .cloned()
+    }
+
+    /// Cache a response loaded from the store. `loaded_at` is when the load
+    /// started: the entry is discarded if the mission changed since.
+    pub fn insert(&self, id: Uuid, entry: CachedMission, loaded_at: Instant) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut inner = self.lock();
+        inner
+            .invalidated
+            .retain(|_, at| at.elapsed() < INVALIDATION_MEMORY);
+        if inner
+            .invalidated
+            .get(&id)
+            .is_some_and(|at| *at >= loaded_at)
+        {
+            return;
+        }
+        if inner.entries.len() >= MAX_ENTRIES && !inner.entries.contains_key(&id) {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.fetched)
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.entries.insert(id, entry);
+    }
+
+    pub fn invalidate(&self, id: Uuid) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut inner = self.lock();
+        inner.entries.remove(&id);
+        inner.invalidated.insert(id, Instant::now());
+    }
+}
+
+/// Whether an event can change what `GET /missions/:id` returns. Streaming
+/// events (deltas, tool calls, progress) only touch the store at turn end,
+/// which is followed by a message or status event.
+fn invalidates(event: &AgentEvent) -> bool {
+    !matches!(
+        event,
+        AgentEvent::Status { .. }
+            | AgentEvent::Thinking { .. }
+            | AgentEvent::TextDelta { .. }
+            | AgentEvent::ToolCall { .. }
+            | AgentEvent::ToolResult { .. }
+            | AgentEvent::AgentPhase { .. }
+            | AgentEvent::AgentTree { .. }
+            | AgentEvent::Progress { .. }
+            | AgentEvent::MissionActivity { .. }
+    )
+}
+
+/// Drop cache entries as the session's events report mission changes.
+pub async fn invalidation_loop(
+    cache: std::sync::Arc<MissionCache>,
+    mut events_rx: broadcast::Receiver<AgentEvent>,
+) {
+    loop {
+        match events_rx.recv().await {
+            Ok(event) => {
+                if let Some(id) = event.mission_id().filter(|_| invalidates(&event)) {
+                    cache.invalidate(id);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!(skipped, "Mission cache lagged behind events; clearing");
+                cache.lock().entries.clear();
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etag_matching() {
+        let entry = CachedMission::new(b"{\"id\":1}".to_vec());
+        let mut headers = HeaderMap::new();
+        let response = entry.clone().into_response(&headers);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], entry.etag.as_str());
+
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", W/{}", entry.etag)).unwrap(),
+        );
+        let response = entry.clone().into_response(&headers);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        assert_eq!(entry.into_response(&headers).status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn invalidation_rejects_racing_loads() {
+        let cache = MissionCache::new(Duration::from_secs(60));
+        let id = Uuid::new_v4();
+        let loaded_at = Instant::now();
+        cache.invalidate(id);
+        cache.insert(id, CachedMission::new(b"old".to_vec()), loaded_at);
+        assert!(cache.get(id).is_none());
+
+        std::thread::sleep(Duration::from_millis(2));
+        cache.insert(id, CachedMission::new(b"new".to_vec()), Instant::now());
+        assert_eq!(cache.get(id).unwrap().body, Bytes::from_static(b"new"));
+        cache.invalidate(id);
+        assert!(cache.get(id).is_none());
+    }
+
+    #[test]
+    fn zero_ttl_disables_cache() {
+        let cache = MissionCache::new(Duration::ZERO);
+        let id = Uuid::new_v4();
+        cache.insert(id, CachedMission::new(b"x".to_vec()), Instant::now());
+        assert!(cache.get(id).is_none());
+        assert!(invalidates(&AgentEvent::MissionStatusChanged {
+            mission_id: id,
+            status: crate::api::control::MissionStatus::Completed,
+            summary: None,
+        }));
+    }
+}
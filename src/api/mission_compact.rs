@@ -0,0 +1,381 @@
+//! Mission history compaction.
+//!
+//! `POST /api/control/missions/:id/compact` folds the older part of a
+//! mission's conversation history into a single entry with role `summary`,
+//! keeping the most recent entries verbatim. Future prompts are built from the
+//! compacted history; the original messages stay in the mission's event log.
+//! Claude Code missions also start a fresh CLI session, which is seeded with
+//! the compacted history on its first turn.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, ControlCommand};
+use super::mission_store::MissionHistoryEntry;
+use super::routes::AppState;
+use crate::tools::safe_truncate_index;
+use crate::util::build_history_context;
+
+/// Role of the history entry holding the summary of compacted history.
+pub const SUMMARY_ROLE: &str = "summary";
+/// Recent history entries kept verbatim unless the request says otherwise.
+const DEFAULT_KEEP_RECENT: usize = 6;
+/// Model (or chain) that writes the summary unless the request names one.
+const DEFAULT_SUMMARY_MODEL: &str = "builtin/cheap";
+/// Transcript size sent to the summarizing model.
+const MAX_TRANSCRIPT_CHARS: usize = 60_000;
+/// Size cap of an extractive summary.
+const MAX_EXTRACTIVE_CHARS: usize = 4_000;
+/// Each message contributes at most this many characters to an extractive summary.
+const MAX_EXTRACT_LINE_CHARS: usize = 200;
+
+const SUMMARY_INSTRUCTIONS: &str = "You compact the history of a long conversation between a user and a coding agent. \
+Summarize the transcript so the agent can continue the work without it. Keep the user's goals and constraints, \
+decisions made, files and commands involved, results, and anything still open. Drop pleasantries and repeated \
+content. Reply with the summary only, as concise markdown under 400 words.";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CompactMissionRequest {
+    /// Most recent history entries kept verbatim (default: 6).
+    #[serde(default)]
+    pub keep_recent: Option<usize>,
+    /// Model or chain that writes the summary (default: `builtin/cheap`).
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Build the summary from the messages themselves without calling a model.
+    #[serde(default)]
+    pub extractive: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryMethod {
+    Model,
+    Extractive,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompactMissionResponse {
+    pub mission_id: Uuid,
+    /// History entries folded into the summary
+    pub compacted_entries: usize,
+    /// Recent entries kept verbatim
+    pub kept_entries: usize,
+    /// History size in characters before and after compaction
+    pub chars_before: usize,
+    pub chars_after: usize,
+    pub method: SummaryMethod,
+    pub summary: String,
+    /// Why the model summary was not used, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    pub history: Vec<MissionHistoryEntry>,
+}
+
+/// Split `history` into the entries to compact and the ones to keep. The kept
+/// part starts at a user message where possible so exchanges stay together.
+/// Returns `None` if there is nothing to compact.
+pub fn split(
+    history: &[MissionHistoryEntry],
+    keep_recent: usize,
+) -> Option<(&[MissionHistoryEntry], &[MissionHistoryEntry])> {
+    let mut at = history.len().saturating_sub(keep_recent);
+    while at > 0 && at < history.len() && history[at].role == "assistant" {
+        at -= 1;
+    }
+    let (older, kept) = history.split_at(at);
+    // A lone earlier summary has already been compacted.
+    if older.iter().all(|e| e.role == SUMMARY_ROLE) {
+        return None;
+    }
+    Some((older, kept))
+}
+
+fn first_line(content: &str) -> String {
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or_default();
+    let end = safe_truncate_index(line, MAX_EXTRACT_LINE_CHARS);
+    if end < line.len() {
+        format!("{}...", &line[..end])
+    } else {
+        line.to_string()
+    }
+}
+
+/// Summary built from the messages themselves: an earlier summary (if any)
+/// followed by the opening line of each message, dropping the oldest lines
+/// beyond the size cap.
+pub fn extractive_summary(older: &[MissionHistoryEntry]) -> String {
+    let mut previous = None;
+    let mut lines = Vec::new();
+    for entry in older {
+        match entry.role.as_str() {
+            SUMMARY_ROLE => previous = Some(entry.content.trim().to_string()),
+            "user" => lines.push(format!("- User: {}", first_line(&entry.content))),
+            _ => lines.push(format!("  - Agent: {}", first_line(&entry.content))),
+        }
+    }
+
+    let mut budget = MAX_EXTRACTIVE_CHARS.saturating_sub(previous.as_ref().map_or(0, String::len));
+    let mut kept = Vec::new();
+    for line in lines.iter().rev() {
+        if line.len() + 1 > budget {
+            break;
+        }
+        budget -= line.len() + 1;
+        kept.push(line.as_str());
+    }
+    kept.reverse();
+
+    let mut summary = String::new();
+    if let Some(previous) = previous {
+        summary.push_str(&previous);
+        summary.push_str("\n\n");
+    }
+    summary.push_str("Earlier exchanges:\n");
+    if kept.len() < lines.len() {
+        summary.push_str(&format!(
+            "- ({} earlier messages omitted)\n",
+            lines.len() - kept.len()
+        ));
+    }
+    for line in kept {
+        summary.push_str(line);
+        summary.push('\n');
+    }
+    summary
+}
+
+async fn model_summary(
+    state: &Arc<AppState>,
+    older: &[MissionHistoryEntry],
+    model: &str,
+) -> anyhow::Result<String> {
+    let pairs: Vec<(String, String)> = older
+        .iter()
+        .map(|e| (e.role.clone(), e.content.clone()))
+        .collect();
+    let transcript = build_history_context(&pairs, MAX_TRANSCRIPT_CHARS);
+    let summary = super::skill_test::proxy_completion(
+        state,
+        model,
+        SUMMARY_INSTRUCTIONS,
+        &transcript,
+        Duration::from_secs(180),
+    )
+    .await?;
+    if summary.trim().is_empty() {
+        anyhow::bail!("Model returned an empty summary");
+    }
+    Ok(summary.trim().to_string())
+}
+
+/// The compacted history: `summary` followed by `kept`, provided `history`
+/// still ends with `kept`.
+pub fn apply(
+    history: &[(String, String)],
+    summary: &str,
+    kept: &[MissionHistoryEntry],
+) -> Option<Vec<(String, String)>> {
+    if kept.len() > history.len() {
+        return None;
+    }
+    let tail = &history[history.len() - kept.len()..];
+    let matches = tail
+        .iter()
+        .zip(kept)
+        .all(|((role, content), e)| *role == e.role && *content == e.content);
+    if !matches || tail.len() == history.len() {
+        return None;
+    }
+    let mut compacted = Vec::with_capacity(kept.len() + 1);
+    compacted.push((SUMMARY_ROLE.to_string(), summary.to_string()));
+    compacted.extend(kept.iter().map(|e| (e.role.clone(), e.content.clone())));
+    Some(compacted)
+}
+
+/// Message for the first turn of a fresh session after compaction: the
+/// compacted history followed by `user_message`. `None` if the history has
+/// not been compacted.
+pub fn fresh_session_message(
+    history: &[(String, String)],
+    user_message: &str,
+    max_chars: usize,
+) -> Option<String> {
+    if history.first().map(|(role, _)| role.as_str()) != Some(SUMMARY_ROLE) {
+        return None;
+    }
+    let prior = match history.last() {
+        Some((role, content)) if role == "user" && content == user_message => {
+            &history[..history.len() - 1]
+        }
+        _ => history,
+    };
+    let context = build_history_context(prior, max_chars);
+    Some(format!(
+        "## Conversation so far (compacted)\n\n{context}## Current message\n\n{user_message}"
+    ))
+}
+
+fn history_chars(history: &[MissionHistoryEntry]) -> usize {
+    history.iter().map(|e| e.content.len()).sum()
+}
+
+/// Compact a mission's conversation history.
+pub async fn compact_mission(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    body: Option<Json<CompactMissionRequest>>,
+) -> Result<Json<CompactMissionResponse>, (StatusCode, String)> {
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    let control = control_for_user(&state, &user).await;
+    let mission = control
+        .mission_store
+        .get_mission(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Mission {} not found", id)))?;
+
+    let keep_recent = req.keep_recent.unwrap_or(DEFAULT_KEEP_RECENT);
+    let Some((older, kept)) = split(&mission.history, keep_recent) else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Nothing to compact: history is shorter than keep_recent".to_string(),
+        ));
+    };
+
+    let mut warning = None;
+    let (summary, method) = if req.extractive {
+        (extractive_summary(older), SummaryMethod::Extractive)
+    } else {
+        let model = req.model.as_deref().unwrap_or(DEFAULT_SUMMARY_MODEL);
+        match model_summary(&state, older, model).await {
+            Ok(summary) => (summary, SummaryMethod::Model),
+            Err(e) => {
+                tracing::warn!(mission_id = %id, error = %e, "Model summary failed; using extractive summary");
+                warning = Some(e.to_string());
+                (extractive_summary(older), SummaryMethod::Extractive)
+            }
+        }
+    };
+
+    let (tx, rx) = oneshot::channel();
+    control
+        .cmd_tx
+        .send(ControlCommand::CompactHistory {
+            mission_id: id,
+            summary: summary.clone(),
+            kept: kept.to_vec(),
+            respond: tx,
+        })
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "control session unavailable".to_string(),
+            )
+        })?;
+    let history = rx
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to receive response".to_string(),
+            )
+        })?
+        .map_err(|e| (StatusCode::CONFLICT, e))?;
+
+    Ok(Json(CompactMissionResponse {
+        mission_id: id,
+        compacted_entries: older.len(),
+        kept_entries: kept.len(),
+        chars_before: history_chars(&mission.history),
+        chars_after: history_chars(&history),
+        method,
+        summary,
+        warning,
+        history,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(role: &str, content: &str) -> MissionHistoryEntry {
+        MissionHistoryEntry {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn conversation(turns: usize) -> Vec<MissionHistoryEntry> {
+        (0..turns)
+            .flat_map(|i| {
+                [
+                    entry("user", &format!("request {}", i)),
+                    entry("assistant", &format!("done {}\ndetails", i)),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_at_user_messages() {
+        let history = conversation(4);
+        let (older, kept) = split(&history, 3).unwrap();
+        assert_eq!(older.len(), 4);
+        assert_eq!(kept[0].content, "request 2");
+
+        assert!(split(&history, 8).is_none());
+        let compacted = [entry(SUMMARY_ROLE, "s"), entry("user", "request 5")];
+        assert!(split(&compacted, 1).is_none());
+    }
+
+    #[test]
+    fn extractive_summary_folds_previous_summary() {
+        let mut history = vec![entry(SUMMARY_ROLE, "Set up the repo.")];
+        history.extend(conversation(2));
+        let summary = extractive_summary(&history);
+        assert!(summary.starts_with("Set up the repo.\n\nEarlier exchanges:\n"));
+        assert!(summary.contains("- User: request 1\n  - Agent: done 1\n"));
+        assert!(!summary.contains("details"));
+    }
+
+    #[test]
+    fn applies_only_to_unchanged_tail() {
+        let history: Vec<(String, String)> = conversation(3)
+            .into_iter()
+            .map(|e| (e.role, e.content))
+            .collect();
+        let kept = conversation(3)[4..].to_vec();
+        let compacted = apply(&history, "summary", &kept).unwrap();
+        assert_eq!(compacted.len(), 3);
+        assert_eq!(
+            compacted[0],
+            (SUMMARY_ROLE.to_string(), "summary".to_string())
+        );
+
+        assert!(apply(&history, "summary", &conversation(3)[2..4]).is_none());
+        assert!(apply(&history[4..], "summary", &kept).is_none());
+
+        let message = fresh_session_message(&compacted, "next", 10_000).unwrap();
+        assert!(message.starts_with("## Conversation so far (compacted)\n\nSUMMARY: summary"));
+        assert!(message.ends_with("## Current message\n\nnext"));
+        assert!(fresh_session_message(&history, "next", 10_000).is_none());
+    }
+}
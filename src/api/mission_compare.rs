@@ -0,0 +1,413 @@
+//! Side-by-side comparison of two missions.
+//!
+//! `GET /api/control/missions/compare?a=<id>&b=<id>` is meant for model
+//! evaluation: run the same prompt with different models or agents, then
+//! compare the outcomes. The response holds, per mission, cost, duration,
+//! tool-call counts and changed-file totals, plus:
+//! - `files` - every file changed by either mission, with the change on each
+//!   side and whether the final contents are identical
+//! - `results` - post-processor output per processor (test, coverage and
+//!   other verification processors), side by side
+//!
+//! The `diff` processor is left out of `results` since `files` covers it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, MissionStatus};
+use super::mission_batch::mission_cost_cents;
+use super::mission_store::{Mission, MissionResult, MissionStore};
+use super::routes::AppState;
+use crate::workspace;
+use crate::workspace_snapshot::{self, FileChange, FileChangeStatus, MissionDiff};
+
+/// Files larger than this are not compared byte for byte.
+const MAX_COMPARE_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    pub a: Uuid,
+    pub b: Uuid,
+}
+
+/// Metrics of one side of the comparison.
+#[derive(Debug, Clone, Serialize)]
+pub struct MissionSide {
+    pub mission_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub status: MissionStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal_reason: Option<String>,
+    pub backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_override: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_profile: Option<String>,
+    pub cost_cents: u64,
+    /// Total turn time (see `mission_timing`).
+    pub duration_ms: u64,
+    pub llm_ms: u64,
+    pub tool_ms: u64,
+    pub tool_calls: u32,
+    /// Calls per tool.
+    pub tools: BTreeMap<String, u32>,
+    pub files_changed: usize,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+/// Change to a file on one side.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileSide {
+    pub status: FileChangeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additions: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deletions: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileComparison {
+    pub path: String,
+    /// Unset when mission A did not change the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub a: Option<FileSide>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub b: Option<FileSide>,
+    /// Whether both missions left the file with the same contents. Unset when
+    /// only one side changed it or the file is too large to compare.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identical: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultComparison {
+    pub processor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub a: Option<MissionResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub b: Option<MissionResult>,
+    /// Both sides produced the same data and error.
+    pub same: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissionComparison {
+    pub a: MissionSide,
+    pub b: MissionSide,
+    /// Both missions started from the same first user message.
+    pub same_prompt: bool,
+    /// `b - a`
+    pub cost_delta_cents: i64,
+    /// `b - a`
+    pub duration_delta_ms: i64,
+    pub files: Vec<FileComparison>,
+    pub results: Vec<ResultComparison>,
+}
+
+fn first_prompt(mission: &Mission) -> Option<&str> {
+    mission
+        .history
+        .iter()
+        .find(|entry| entry.role == "user")
+        .map(|entry| entry.content.trim())
+}
+
+fn file_side(change: &FileChange) -> FileSide {
+    FileSide {
+        status: change.status,
+        additions: change.additions,
+        deletions: change.deletions,
+    }
+}
+
+/// Merge the changed files of both missions, sorted by path.
+fn compare_files(a: &[FileChange], b: &[FileChange]) -> Vec<FileComparison> {
+    let mut files: BTreeMap<&str, FileComparison> = BTreeMap::new();
+    for (change, is_a) in a
+        .iter()
+        .map(|c| (c, true))
+        .chain(b.iter().map(|c| (c, false)))
+    {
+        let entry = files
+            .entry(change.path.as_str())
+            .or_insert_with(|| FileComparison {
+                path: change.path.clone(),
+                a: None,
+                b: None,
+                identical: None,
+            });
+        if is_a {
+            entry.a = Some(file_side(change));
+        } else {
+            entry.b = Some(file_side(change));
+        }
+    }
+    files.into_values().collect()
+}
+
+/// Pair up post-processor results by processor name, keeping the latest
+/// result of missions that finished more than once.
+fn compare_results(a: Vec<MissionResult>, b: Vec<MissionResult>) -> Vec<ResultComparison> {
+    let mut a: BTreeMap<String, MissionResult> =
+        a.into_iter().map(|r| (r.processor.clone(), r)).collect();
+    let mut b: BTreeMap<String, MissionResult> =
+        b.into_iter().map(|r| (r.processor.clone(), r)).collect();
+    let names: BTreeSet<String> = a.keys().chain(b.keys()).cloned().collect();
+    names
+        .into_iter()
+        .filter(|name| name != "diff")
+        .map(|processor| {
+            let a = a.remove(&processor);
+            let b = b.remove(&processor);
+            let same = match (&a, &b) {
+                (Some(a), Some(b)) => a.data == b.data && a.error == b.error,
+                _ => false,
+            };
+            ResultComparison {
+                processor,
+                a,
+                b,
+                same,
+            }
+        })
+        .collect()
+}
+
+/// Whether `path` has the same contents under both directories. Files that
+/// were deleted on both sides count as identical.
+async fn same_contents(dir_a: &Path, dir_b: &Path, path: &str) -> Option<bool> {
+    let (a, b) = (dir_a.join(path), dir_b.join(path));
+    let (meta_a, meta_b) = (
+        tokio::fs::metadata(&a).await.ok(),
+        tokio::fs::metadata(&b).await.ok(),
+    );
+    match (meta_a, meta_b) {
+        (None, None) => Some(true),
+        (Some(meta_a), Some(meta_b)) => {
+            if meta_a.len() != meta_b.len() {
+                return Some(false);
+            }
+            if meta_a.len() > MAX_COMPARE_BYTES {
+                return None;
+            }
+            let a = tokio::fs::read(&a).await.ok()?;
+            let b = tokio::fs::read(&b).await.ok()?;
+            Some(a == b)
+        }
+        _ => Some(false),
+    }
+}
+
+struct Loaded {
+    side: MissionSide,
+    diff: MissionDiff,
+    dir: std::path::PathBuf,
+    results: Vec<MissionResult>,
+}
+
+async fn load(
+    state: &AppState,
+    store: &Arc<dyn MissionStore>,
+    mission: Mission,
+) -> Result<Loaded, (StatusCode, String)> {
+    let internal = |e: String| (StatusCode::INTERNAL_SERVER_ERROR, e);
+    let workspace_root = workspace::resolve_workspace_root(
+        &state.workspaces,
+        &state.config,
+        Some(mission.workspace_id),
+    )
+    .await;
+    let dir = workspace::mission_workspace_dir_for_root(&workspace_root, mission.id);
+    let diff = workspace_snapshot::mission_diff(&dir, false)
+        .await
+        .map_err(|e| internal(e.to_string()))?;
+    let results = store
+        .get_mission_results(mission.id)
+        .await
+        .map_err(internal)?;
+    let timing = super::mission_timing::mission_timing(store, &mission)
+        .await
+        .unwrap_or_default();
+    let side = MissionSide {
+        mission_id: mission.id,
+        title: mission.title,
+        status: mission.status,
+        terminal_reason: mission.terminal_reason,
+        backend: mission.backend,
+        agent: mission.agent,
+        model_override: mission.model_override,
+        config_profile: mission.config_profile,
+        cost_cents: mission_cost_cents(store, mission.id).await,
+        duration_ms: timing.total_ms,
+        llm_ms: timing.llm_ms,
+        tool_ms: timing.tool_ms,
+        tool_calls: timing.tools.values().map(|t| t.calls).sum(),
+        tools: timing
+            .tools
+            .into_iter()
+            .map(|(name, t)| (name, t.calls))
+            .collect(),
+        files_changed: diff.files.len(),
+        additions: diff.total_additions,
+        deletions: diff.total_deletions,
+    };
+    Ok(Loaded {
+        side,
+        diff,
+        dir,
+        results,
+    })
+}
+
+/// GET /api/control/missions/compare?a=&b= - Compare two missions.
+pub async fn compare_missions(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<CompareQuery>,
+) -> Result<Json<MissionComparison>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let store = &control.mission_store;
+    let mut missions = Vec::with_capacity(2);
+    for id in [query.a, query.b] {
+        let mission = store
+            .get_mission(id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .ok_or((StatusCode::NOT_FOUND, format!("Mission {} not found", id)))?;
+        missions.push(mission);
+    }
+    let mission_b = missions.pop().expect("two missions");
+    let mission_a = missions.pop().expect("two missions");
+    let same_prompt =
+        first_prompt(&mission_a).is_some() && first_prompt(&mission_a) == first_prompt(&mission_b);
+
+    let a = load(&state, store, mission_a).await?;
+    let b = load(&state, store, mission_b).await?;
+    let mut files = compare_files(&a.diff.files, &b.diff.files);
+    for file in files.iter_mut().filter(|f| f.a.is_some() && f.b.is_some()) {
+        file.identical = same_contents(&a.dir, &b.dir, &file.path).await;
+    }
+
+    Ok(Json(MissionComparison {
+        cost_delta_cents: b.side.cost_cents as i64 - a.side.cost_cents as i64,
+        duration_delta_ms: b.side.duration_ms as i64 - a.side.duration_ms as i64,
+        same_prompt,
+        files,
+        results: compare_results(a.results, b.results),
+        a: a.side,
+        b: b.side,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(path: &str, status: FileChangeStatus, additions: u64) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            status,
+            additions: Some(additions),
+            deletions: Some(0),
+            binary: false,
+        }
+    }
+
+    fn result(processor: &str, data: serde_json::Value) -> MissionResult {
+        MissionResult {
+            processor: processor.to_string(),
+            data: Some(data),
+            error: None,
+            created_at: "2026-10-16T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn merges_changed_files_by_path() {
+        let a = vec![
+            change("src/lib.rs", FileChangeStatus::Modified, 3),
+            change("README.md", FileChangeStatus::Modified, 1),
+        ];
+        let b = vec![
+            change("src/lib.rs", FileChangeStatus::Modified, 5),
+            change("src/new.rs", FileChangeStatus::Added, 10),
+        ];
+        let files = compare_files(&a, &b);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["README.md", "src/lib.rs", "src/new.rs"]);
+        assert!(files[0].b.is_none());
+        assert_eq!(files[1].a.as_ref().unwrap().additions, Some(3));
+        assert_eq!(files[1].b.as_ref().unwrap().additions, Some(5));
+        assert!(files[2].a.is_none());
+        assert_eq!(files[2].b.as_ref().unwrap().status, FileChangeStatus::Added);
+    }
+
+    #[test]
+    fn pairs_results_and_skips_diff() {
+        let a = vec![
+            result("tests", serde_json::json!({"passed": 10})),
+            result("diff", serde_json::json!([])),
+            result("coverage", serde_json::json!(81)),
+        ];
+        let b = vec![
+            result("tests", serde_json::json!({"passed": 10})),
+            result("coverage", serde_json::json!(77)),
+            result("lint", serde_json::json!("clean")),
+        ];
+        let results = compare_results(a, b);
+        let summary: Vec<(&str, bool, bool, bool)> = results
+            .iter()
+            .map(|r| (r.processor.as_str(), r.a.is_some(), r.b.is_some(), r.same))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("coverage", true, true, false),
+                ("lint", false, true, false),
+                ("tests", true, true, true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn compares_final_contents() {
+        let a = tempfile::tempdir().unwrap();
+        let b = tempfile::tempdir().unwrap();
+        std::fs::write(a.path().join("same.txt"), "x").unwrap();
+        std::fs::write(b.path().join("same.txt"), "x").unwrap();
+        std::fs::write(a.path().join("diff.txt"), "x").unwrap();
+        std::fs::write(b.path().join("diff.txt"), "y").unwrap();
+        std::fs::write(a.path().join("only_a.txt"), "x").unwrap();
+
+        assert_eq!(
+            same_contents(a.path(), b.path(), "same.txt").await,
+            Some(true)
+        );
+        assert_eq!(
+            same_contents(a.path(), b.path(), "diff.txt").await,
+            Some(false)
+        );
+        assert_eq!(
+            same_contents(a.path(), b.path(), "only_a.txt").await,
+            Some(false)
+        );
+        assert_eq!(
+            same_contents(a.path(), b.path(), "gone.txt").await,
+            Some(true)
+        );
+    }
+}
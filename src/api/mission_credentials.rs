@@ -0,0 +1,752 @@
+//! Short-lived credentials issued per mission.
+//!
+//! Instead of long-lived tokens in template env vars, the
+//! `mission_credentials` setting lists issuers that mint credentials when a
+//! mission starts a turn. The credentials are added to the workspace env for
+//! the mission (replacing variables of the same name), re-minted shortly
+//! before they expire, and revoked as soon as the mission stops running.
+//!
+//! ```json
+//! "mission_credentials": [
+//!   {
+//!     "name": "github",
+//!     "kind": "github_app",
+//!     "app_id": "123456",
+//!     "private_key_path": "/etc/sandboxed-sh/github-app.pem",
+//!     "permissions": { "contents": "write", "pull_requests": "write" },
+//!     "templates": ["rust-dev"]
+//!   },
+//!   {
+//!     "name": "aws",
+//!     "kind": "command",
+//!     "mint": "/etc/sandboxed-sh/assume-role.sh",
+//!     "revoke": "/etc/sandboxed-sh/revoke-role.sh"
+//!   }
+//! ]
+//! ```
+//!
+//! `github_app` issuers mint installation tokens scoped to `repositories`
+//! (`owner/name`), or to the workspace's `GITHUB_REPOSITORY` env var, and set
+//! `GH_TOKEN` and `GITHUB_TOKEN`; with neither, no token is minted. They are
+//! revoked through the GitHub API.
+//! `command` issuers run `mint` on the host with a JSON context on stdin
+//! (`mission_id`, `workspace`, `template`, `repositories`) and read
+//! `{ "env": { ... }, "expires_at": "<RFC 3339>" }` from stdout, which is
+//! useful for STS `assume-role` and other providers. `revoke` gets that output
+//! on stdin when the mission stops. Issuers apply to every mission unless
+//! limited to `workspaces` (names) or `templates`.
+//!
+//! Issued credentials are recorded in `.sandboxed-sh/mission_credentials.json`
+//! (owner-only) so that a restarted server revokes those the previous process
+//! left behind.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::control::{AgentEvent, MissionStatus};
+use crate::workspace::Workspace;
+
+/// Credentials expiring sooner than this are minted again before a turn.
+const REFRESH_MARGIN: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Time limit of a GitHub API call or an issuer command.
+const ISSUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Env vars set to a minted GitHub token.
+const GITHUB_TOKEN_VARS: &[&str] = &["GH_TOKEN", "GITHUB_TOKEN"];
+
+/// Where an issuer gets its credentials from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IssuerKind {
+    GithubApp {
+        app_id: String,
+        /// PEM private key of the app, read from the host
+        private_key_path: PathBuf,
+        /// Looked up from the first repository when unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        installation_id: Option<u64>,
+        /// `owner/name`; defaults to the workspace's `GITHUB_REPOSITORY`
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        repositories: Vec<String>,
+        /// Permissions of the token, e.g. `{"contents": "write"}`; all of the
+        /// app's permissions when empty
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        permissions: BTreeMap<String, String>,
+        /// For GitHub Enterprise Server
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        api_url: Option<String>,
+    },
+    Command {
+        mint: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        revoke: Option<String>,
+    },
+}
+
+/// One entry of the `mission_credentials` setting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialIssuer {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: IssuerKind,
+    /// Workspace names this issuer applies to (all when both lists are empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<String>,
+    /// Workspace templates this issuer applies to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub templates: Vec<String>,
+}
+
+impl CredentialIssuer {
+    pub fn applies_to(&self, workspace: &Workspace) -> bool {
+        if self.workspaces.is_empty() && self.templates.is_empty() {
+            return true;
+        }
+        self.workspaces.contains(&workspace.name)
+            || workspace
+                .template
+                .as_ref()
+                .is_some_and(|t| self.templates.contains(t))
+    }
+
+    /// Repositories the credentials are scoped to.
+    fn repositories(&self, workspace: &Workspace) -> Vec<String> {
+        match &self.kind {
+            IssuerKind::GithubApp { repositories, .. } if !repositories.is_empty() => {
+                repositories.clone()
+            }
+            _ => workspace
+                .env_vars
+                .get("GITHUB_REPOSITORY")
+                .map(|r| r.trim().to_string())
+                .filter(|r| !r.is_empty())
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// Validate the `mission_credentials` setting.
+pub fn validate_issuers(issuers: &[CredentialIssuer]) -> Result<(), String> {
+    for (i, issuer) in issuers.iter().enumerate() {
+        if issuer.name.trim().is_empty() {
+            return Err(format!("[{}]: name is required", i));
+        }
+        if issuers[..i].iter().any(|other| other.name == issuer.name) {
+            return Err(format!("[{}]: duplicate name '{}'", i, issuer.name));
+        }
+        match &issuer.kind {
+            IssuerKind::GithubApp {
+                app_id,
+                repositories,
+                ..
+            } => {
+                if app_id.trim().is_empty() {
+                    return Err(format!("{}: app_id is required", issuer.name));
+                }
+                if let Some(repo) = repositories.iter().find(|r| r.split('/').count() != 2) {
+                    return Err(format!(
+                        "{}: invalid repository '{}': expected owner/name",
+                        issuer.name, repo
+                    ));
+                }
+            }
+            IssuerKind::Command { mint, .. } => {
+                if mint.trim().is_empty() {
+                    return Err(format!("{}: mint command is required", issuer.name));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Revoke {
+    Nothing,
+    GithubToken { api_url: String, token: String },
+    Command { command: String, output: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Issued {
+    issuer: String,
+    env: HashMap<String, String>,
+    expires_at: Option<DateTime<Utc>>,
+    revoke: Revoke,
+}
+
+impl Issued {
+    fn fresh(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|at| at - REFRESH_MARGIN > now)
+    }
+}
+
+static ISSUED: LazyLock<Mutex<HashMap<Uuid, Vec<Issued>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// File the issued credentials are recorded in; unset until the first control
+/// session starts.
+static RECORDS_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+fn take_issued(mission_id: Uuid) -> Vec<Issued> {
+    let mut issued = ISSUED.lock().unwrap_or_else(|e| e.into_inner());
+    let taken = issued.remove(&mission_id).unwrap_or_default();
+    if !taken.is_empty() {
+        save_records(&issued);
+    }
+    taken
+}
+
+/// Record the issued credentials; called with the `ISSUED` lock held so
+/// writes land in order.
+fn save_records(issued: &HashMap<Uuid, Vec<Issued>>) {
+    let Some(path) = RECORDS_PATH.get() else {
+        return;
+    };
+    if let Err(e) = write_records(path, issued) {
+        tracing::warn!(
+            path = %path.display(),
+            "Failed to record mission credentials: {}",
+            e
+        );
+    }
+}
+
+fn write_records(path: &Path, issued: &HashMap<Uuid, Vec<Issued>>) -> std::io::Result<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&tmp_path)?
+        .write_all(&serde_json::to_vec(issued)?)?;
+    std::fs::rename(&tmp_path, path)
+}
+
+fn read_records(path: &Path) -> HashMap<Uuid, Vec<Issued>> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return HashMap::new(),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), "Failed to read mission credentials: {}", e);
+            return HashMap::new();
+        }
+    };
+    serde_json::from_slice(&contents).unwrap_or_else(|e| {
+        tracing::warn!(path = %path.display(), "Invalid mission credentials record: {}", e);
+        HashMap::new()
+    })
+}
+
+/// Credentials for a turn of `mission_id` in `workspace`, minting any that
+/// are missing or about to expire. Issuers that fail are logged and skipped,
+/// so the mission runs with whatever the workspace env provides.
+pub async fn env_for(mission_id: Uuid, workspace: &Workspace) -> HashMap<String, String> {
+    let issuers: Vec<CredentialIssuer> = crate::settings::mission_credentials_cached()
+        .into_iter()
+        .filter(|issuer| issuer.applies_to(workspace))
+        .collect();
+    let mut previous = take_issued(mission_id);
+    if issuers.is_empty() && previous.is_empty() {
+        return HashMap::new();
+    }
+
+    let now = Utc::now();
+    let mut issued = Vec::with_capacity(issuers.len());
+    for issuer in &issuers {
+        let existing = previous
+            .iter()
+            .position(|i| i.issuer == issuer.name)
+            .map(|index| previous.swap_remove(index));
+        match existing {
+            Some(credentials) if credentials.fresh(now) => issued.push(credentials),
+            existing => {
+                if let Some(stale) = existing {
+                    revoke(stale).await;
+                }
+                match mint(issuer, mission_id, workspace).await {
+                    Ok(credentials) => {
+                        tracing::info!(
+                            mission_id = %mission_id,
+                            issuer = %issuer.name,
+                            expires_at = ?credentials.expires_at,
+                            "Issued mission credentials"
+                        );
+                        issued.push(credentials);
+                    }
+                    Err(e) => tracing::warn!(
+                        mission_id = %mission_id,
+                        issuer = %issuer.name,
+                        "Failed to issue mission credentials: {}",
+                        e
+                    ),
+                }
+            }
+        }
+    }
+    // Issuers removed from the settings since the last turn.
+    for stale in previous {
+        revoke(stale).await;
+    }
+
+    let env = issued
+        .iter()
+        .flat_map(|i| i.env.iter().map(|(k, v)| (k.clone(), v.clone())))
+        .collect();
+    if !issued.is_empty() {
+        let mut all = ISSUED.lock().unwrap_or_else(|e| e.into_inner());
+        all.insert(mission_id, issued);
+        save_records(&all);
+    }
+    env
+}
+
+/// Revoke everything issued to `mission_id`.
+pub async fn revoke_mission(mission_id: Uuid) {
+    for issued in take_issued(mission_id) {
+        revoke(issued).await;
+    }
+}
+
+/// Revoke a mission's credentials whenever it stops running. Resumed
+/// missions get new ones on their next turn.
+///
+/// The first call also starts recording issued credentials in `records_path`
+/// and revokes those recorded there by a previous process: no turn has run
+/// yet, so none of them are in use.
+pub fn spawn_revoke_worker(events_tx: &broadcast::Sender<AgentEvent>, records_path: PathBuf) {
+    if RECORDS_PATH.set(records_path.clone()).is_ok() {
+        let leftover = read_records(&records_path);
+        if !leftover.is_empty() {
+            save_records(&HashMap::new());
+            tokio::spawn(async move {
+                for issued in leftover.into_values().flatten() {
+                    revoke(issued).await;
+                }
+            });
+        }
+    }
+
+    let mut rx = events_tx.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(AgentEvent::MissionStatusChanged {
+                    mission_id, status, ..
+                }) if !matches!(status, MissionStatus::Active | MissionStatus::Pending) => {
+                    tokio::spawn(revoke_mission(mission_id));
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Mission credentials worker lagged by {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+async fn mint(
+    issuer: &CredentialIssuer,
+    mission_id: Uuid,
+    workspace: &Workspace,
+) -> anyhow::Result<Issued> {
+    let repositories = issuer.repositories(workspace);
+    match &issuer.kind {
+        IssuerKind::GithubApp { .. } if repositories.is_empty() => {
+            anyhow::bail!(
+                "no repository to scope the token to; set repositories or the workspace's GITHUB_REPOSITORY"
+            )
+        }
+        IssuerKind::GithubApp {
+            app_id,
+            private_key_path,
+            installation_id,
+            permissions,
+            api_url,
+            ..
+        } => {
+            let api_url = api_url
+                .as_deref()
+                .unwrap_or(GITHUB_API_URL)
+                .trim_end_matches('/')
+                .to_string();
+            crate::offline::check_url(&api_url).map_err(anyhow::Error::msg)?;
+            let key = tokio::fs::read(private_key_path).await.map_err(|e| {
+                anyhow::anyhow!("cannot read {}: {}", private_key_path.display(), e)
+            })?;
+            let jwt = github_app_jwt(app_id, &key, Utc::now())?;
+            let client = reqwest::Client::builder().timeout(ISSUE_TIMEOUT).build()?;
+            let installation_id = match installation_id {
+                Some(id) => *id,
+                None => {
+                    let repo = &repositories[0];
+                    let installation: Value = github_request(
+                        client.get(format!("{}/repos/{}/installation", api_url, repo)),
+                        &jwt,
+                    )
+                    .await?;
+                    installation["id"]
+                        .as_u64()
+                        .ok_or_else(|| anyhow::anyhow!("no installation id for {}", repo))?
+                }
+            };
+            let token: Value = github_request(
+                client
+                    .post(format!(
+                        "{}/app/installations/{}/access_tokens",
+                        api_url, installation_id
+                    ))
+                    .json(&github_token_request(&repositories, permissions)),
+                &jwt,
+            )
+            .await?;
+            let value = token["token"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("GitHub returned no token"))?
+                .to_string();
+            Ok(Issued {
+                issuer: issuer.name.clone(),
+                env: GITHUB_TOKEN_VARS
+                    .iter()
+                    .map(|var| (var.to_string(), value.clone()))
+                    .collect(),
+                expires_at: token["expires_at"]
+                    .as_str()
+                    .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                    .map(|at| at.with_timezone(&Utc)),
+                revoke: Revoke::GithubToken {
+                    api_url,
+                    token: value,
+                },
+            })
+        }
+        IssuerKind::Command { mint, revoke } => {
+            let context = json!({
+                "mission_id": mission_id,
+                "workspace": workspace.name,
+                "template": workspace.template,
+                "repositories": repositories,
+            });
+            let output = run_command(mint, &context.to_string()).await?;
+            let parsed: CommandOutput = serde_json::from_str(&output)
+                .map_err(|e| anyhow::anyhow!("mint command printed invalid JSON: {}", e))?;
+            Ok(Issued {
+                issuer: issuer.name.clone(),
+                env: parsed.env,
+                expires_at: parsed.expires_at,
+                revoke: match revoke {
+                    Some(command) => Revoke::Command {
+                        command: command.clone(),
+                        output,
+                    },
+                    None => Revoke::Nothing,
+                },
+            })
+        }
+    }
+}
+
+async fn revoke(issued: Issued) {
+    let result = match &issued.revoke {
+        Revoke::Nothing => return,
+        Revoke::GithubToken { api_url, token } => {
+            async {
+                let client = reqwest::Client::builder().timeout(ISSUE_TIMEOUT).build()?;
+                let response = client
+                    .delete(format!("{}/installation/token", api_url))
+                    .bearer_auth(token)
+                    .header("Accept", "application/vnd.github+json")
+                    .header("User-Agent", "sandboxed-sh")
+                    .send()
+                    .await?;
+                if !response.status().is_success() {
+                    anyhow::bail!("GitHub returned {}", response.status());
+                }
+                Ok(())
+            }
+            .await
+        }
+        Revoke::Command { command, output } => run_command(command, output).await.map(|_| ()),
+    };
+    match result {
+        Ok(()) => tracing::info!(issuer = %issued.issuer, "Revoked mission credentials"),
+        Err(e) => tracing::warn!(
+            issuer = %issued.issuer,
+            "Failed to revoke mission credentials: {}",
+            e
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CommandOutput {
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Run an issuer command on the host with `input` on stdin; returns stdout.
+async fn run_command(command: &str, input: &str) -> anyhow::Result<String> {
+    let mut child = tokio::process::Command::new("/bin/sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Commands that exit without reading their input close the pipe
+        if let Err(e) = stdin.write_all(input.as_bytes()).await {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+    let output = tokio::time::timeout(ISSUE_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out after {} seconds", ISSUE_TIMEOUT.as_secs()))??;
+    if !output.status.success() {
+        anyhow::bail!(
+            "exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The JWT a GitHub App authenticates as (valid for 9 minutes, backdated a
+/// minute for clock drift).
+fn github_app_jwt(app_id: &str, private_key: &[u8], now: DateTime<Utc>) -> anyhow::Result<String> {
+    let claims = json!({
+        "iat": now.timestamp() - 60,
+        "exp": now.timestamp() + 540,
+        "iss": app_id,
+    });
+    let key = EncodingKey::from_rsa_pem(private_key)?;
+    Ok(jsonwebtoken::encode(
+        &Header::new(Algorithm::RS256),
+        &claims,
+        &key,
+    )?)
+}
+
+fn github_token_request(repositories: &[String], permissions: &BTreeMap<String, String>) -> Value {
+    let mut body = serde_json::Map::new();
+    let names: Vec<&str> = repositories
+        .iter()
+        .filter_map(|r| r.split_once('/').map(|(_, name)| name))
+        .collect();
+    if !names.is_empty() {
+        body.insert("repositories".to_string(), json!(names));
+    }
+    if !permissions.is_empty() {
+        body.insert("permissions".to_string(), json!(permissions));
+    }
+    Value::Object(body)
+}
+
+async fn github_request(request: reqwest::RequestBuilder, jwt: &str) -> anyhow::Result<Value> {
+    let response = request
+        .bearer_auth(jwt)
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "sandboxed-sh")
+        .send()
+        .await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        anyhow::bail!(
+            "GitHub returned {}: {}",
+            status,
+            body["message"].as_str().unwrap_or("no message")
+        );
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_issuer(name: &str, mint: &str, revoke: Option<&str>) -> CredentialIssuer {
+        CredentialIssuer {
+            name: name.to_string(),
+            kind: IssuerKind::Command {
+                mint: mint.to_string(),
+                revoke: revoke.map(str::to_string),
+            },
+            workspaces: Vec::new(),
+            templates: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn issuers_are_scoped_and_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut workspace = Workspace::new_container("api".to_string(), dir.path().to_path_buf());
+        workspace.template = Some("rust-dev".to_string());
+        workspace
+            .env_vars
+            .insert("GITHUB_REPOSITORY".to_string(), "acme/api".to_string());
+
+        let mut issuer = command_issuer("aws", "true", None);
+        assert!(issuer.applies_to(&workspace));
+        issuer.templates = vec!["node".to_string()];
+        assert!(!issuer.applies_to(&workspace));
+        issuer.workspaces = vec!["api".to_string()];
+        assert!(issuer.applies_to(&workspace));
+        assert_eq!(issuer.repositories(&workspace), vec!["acme/api"]);
+
+        let body = github_token_request(
+            &["acme/api".to_string()],
+            &BTreeMap::from([("contents".to_string(), "read".to_string())]),
+        );
+        assert_eq!(
+            body,
+            json!({ "repositories": ["api"], "permissions": { "contents": "read" } })
+        );
+
+        assert!(validate_issuers(&[issuer.clone()]).is_ok());
+        assert!(validate_issuers(&[issuer.clone(), issuer]).is_err());
+        let config: Vec<CredentialIssuer> = serde_json::from_value(json!([{
+            "name": "github",
+            "kind": "github_app",
+            "app_id": "1",
+            "private_key_path": "/tmp/key.pem",
+            "repositories": ["not-a-repo"]
+        }]))
+        .unwrap();
+        assert!(validate_issuers(&config)
+            .unwrap_err()
+            .contains("not-a-repo"));
+    }
+
+    #[tokio::test]
+    async fn github_tokens_need_a_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new_container("api".to_string(), dir.path().to_path_buf());
+        let issuer: CredentialIssuer = serde_json::from_value(json!({
+            "name": "github",
+            "kind": "github_app",
+            "app_id": "1",
+            "installation_id": 7,
+            "private_key_path": dir.path().join("key.pem")
+        }))
+        .unwrap();
+        let err = mint(&issuer, Uuid::new_v4(), &workspace)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no repository"), "{}", err);
+    }
+
+    #[test]
+    fn issued_credentials_are_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".sandboxed-sh/mission_credentials.json");
+        assert!(read_records(&path).is_empty());
+
+        let mission_id = Uuid::new_v4();
+        let issued = Issued {
+            issuer: "aws".to_string(),
+            env: HashMap::from([("TOKEN".to_string(), "t1".to_string())]),
+            expires_at: None,
+            revoke: Revoke::Command {
+                command: "revoke".to_string(),
+                output: "{}".to_string(),
+            },
+        };
+        write_records(&path, &HashMap::from([(mission_id, vec![issued])])).unwrap();
+        let records = read_records(&path);
+        assert_eq!(records[&mission_id][0].env["TOKEN"], "t1");
+        assert!(matches!(
+            records[&mission_id][0].revoke,
+            Revoke::Command { .. }
+        ));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn command_credentials_are_reused_refreshed_and_revoked() {
+        let dir = tempfile::tempdir().unwrap();
+        let revoked = dir.path().join("revoked");
+        let counter = dir.path().join("minted");
+        let workspace = Workspace::new_container("creds".to_string(), dir.path().to_path_buf());
+        let mission_id = Uuid::new_v4();
+        let mint = format!(
+            "echo x >> {counter}; n=$(wc -l < {counter}); \
+             echo '{{\"env\": {{\"TOKEN\": \"t'$n'\"}}, \"expires_at\": \"'$EXPIRES'\"}}'",
+            counter = counter.display()
+        );
+        let revoke = format!("cat >> {}", revoked.display());
+
+        // Long-lived credentials are minted once and reused.
+        let issuer = CredentialIssuer {
+            workspaces: vec!["creds".to_string()],
+            ..command_issuer(
+                "test",
+                &mint.replace("$EXPIRES", "2999-01-01T00:00:00Z"),
+                Some(&revoke),
+            )
+        };
+        crate::settings::set_mission_credentials_cached(vec![issuer]);
+        let env = env_for(mission_id, &workspace).await;
+        assert_eq!(env.get("TOKEN").map(String::as_str), Some("t1"));
+        assert_eq!(env_for(mission_id, &workspace).await, env);
+
+        // Credentials about to expire are revoked and minted again.
+        let soon = (Utc::now() + chrono::Duration::minutes(1)).to_rfc3339();
+        let issuer = CredentialIssuer {
+            workspaces: vec!["creds".to_string()],
+            ..command_issuer("test", &mint.replace("$EXPIRES", &soon), Some(&revoke))
+        };
+        crate::settings::set_mission_credentials_cached(vec![issuer]);
+        ISSUED.lock().unwrap().get_mut(&mission_id).unwrap()[0].expires_at =
+            Some(Utc::now() + chrono::Duration::minutes(1));
+        let env = env_for(mission_id, &workspace).await;
+        assert_eq!(env.get("TOKEN").map(String::as_str), Some("t2"));
+        assert!(std::fs::read_to_string(&revoked)
+            .unwrap()
+            .contains("\"t1\""));
+
+        revoke_mission(mission_id).await;
+        assert!(std::fs::read_to_string(&revoked)
+            .unwrap()
+            .contains("\"t2\""));
+        assert!(!ISSUED.lock().unwrap().contains_key(&mission_id));
+        crate::settings::set_mission_credentials_cached(Vec::new());
+    }
+}
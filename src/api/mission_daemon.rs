@@ -0,0 +1,1068 @@
+//! Daemon missions: long-running missions driven by trigger subscriptions.
+//!
+//! `PUT /api/control/missions/:id/daemon` turns a mission into a daemon that
+//! stays alive and runs one bounded turn per trigger it subscribes to:
+//!
+//! - `webhook`: `POST /api/daemon-webhooks/:mission_id/:webhook_id`, with an
+//!   optional HMAC-SHA256 secret checked like automation webhooks
+//! - `file_change`: changes under a path in the mission's workspace; changes
+//!   made while the daemon's own turn runs are ignored
+//! - `schedule`: every `interval_seconds`
+//! - `queue`: `POST /api/control/missions/:id/daemon/queue/:name`
+//!
+//! Triggers are queued per mission and dispatched one at a time while the
+//! mission is idle. Bursts of file changes and schedule ticks collapse into a
+//! single pending event. A turn running longer than `max_turn_seconds` is
+//! cancelled. The cost of every daemon turn is added up, and the daemon stops
+//! itself once `budget_cents` or `max_turns` is reached.
+//!
+//! `pause`, `resume` and `stop` supervise a daemon; a paused daemon keeps
+//! queueing triggers and handles them when resumed.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, AgentEvent, ControlCommand, ControlState};
+use super::mission_store::{now_string, DaemonState, DaemonTrigger, MissionDaemon, MissionStore};
+use super::routes::AppState;
+use crate::tools::safe_truncate_index;
+use crate::workspace;
+
+/// Default limit for a single triggered turn.
+const DEFAULT_MAX_TURN_SECONDS: u64 = 600;
+const MAX_TURN_SECONDS_LIMIT: u64 = 6 * 60 * 60;
+const MIN_SCHEDULE_SECONDS: u64 = 10;
+const MAX_TRIGGERS: usize = 20;
+/// Triggers waiting per daemon; the oldest is dropped beyond this.
+const MAX_PENDING: usize = 50;
+/// Payload size included in the turn prompt.
+const MAX_PAYLOAD_CHARS: usize = 8_000;
+/// Changed paths kept per coalesced file-change event.
+const MAX_CHANGED_PATHS: usize = 100;
+const TICK_INTERVAL: Duration = Duration::from_secs(2);
+/// A turn counts as finished once the mission is idle this long after dispatch,
+/// even if no assistant message was seen.
+const IDLE_GRACE: Duration = Duration::from_secs(30);
+
+/// A trigger occurrence waiting to be handled.
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonEvent {
+    /// `webhook:<id>`, `file_change:<path>`, `schedule:<seconds>s` or `queue:<name>`
+    pub source: String,
+    pub received_at: String,
+    pub payload: Value,
+}
+
+#[derive(Debug)]
+struct Turn {
+    source: String,
+    started: Instant,
+    cost_cents: u64,
+}
+
+/// In-process state of a daemon: queued triggers and the running turn.
+#[derive(Debug, Default)]
+struct Runtime {
+    pending: VecDeque<DaemonEvent>,
+    turn: Option<Turn>,
+    dropped: u64,
+}
+
+static RUNTIME: Mutex<BTreeMap<Uuid, Runtime>> = Mutex::new(BTreeMap::new());
+
+fn with_runtime<T>(mission_id: Uuid, f: impl FnOnce(&mut Runtime) -> T) -> T {
+    let mut runtime = RUNTIME.lock().unwrap_or_else(|e| e.into_inner());
+    f(runtime.entry(mission_id).or_default())
+}
+
+/// Queue a trigger for a daemon mission, merging it into a pending event of
+/// the same file-change or schedule trigger. Returns the number of pending
+/// events.
+pub fn enqueue(mission_id: Uuid, event: DaemonEvent) -> usize {
+    with_runtime(mission_id, |rt| {
+        // Changes made by the daemon's own turn must not trigger it again.
+        if event.source.starts_with("file_change:") && rt.turn.is_some() {
+            return rt.pending.len();
+        }
+        let coalesces =
+            event.source.starts_with("file_change:") || event.source.starts_with("schedule:");
+        if let Some(existing) = rt
+            .pending
+            .iter_mut()
+            .find(|e| coalesces && e.source == event.source)
+        {
+            merge_changes(&mut existing.payload, &event.payload);
+            return rt.pending.len();
+        }
+        rt.pending.push_back(event);
+        while rt.pending.len() > MAX_PENDING {
+            rt.pending.pop_front();
+            rt.dropped += 1;
+        }
+        rt.pending.len()
+    })
+}
+
+fn merge_changes(into: &mut Value, from: &Value) {
+    let (Some(into), Some(from)) = (
+        into.get_mut("changes").and_then(Value::as_object_mut),
+        from.get("changes").and_then(Value::as_object),
+    ) else {
+        return;
+    };
+    for (path, label) in from {
+        if into.len() >= MAX_CHANGED_PATHS && !into.contains_key(path) {
+            continue;
+        }
+        into.insert(path.clone(), label.clone());
+    }
+}
+
+/// Prompt for the turn handling `event`.
+fn turn_prompt(daemon: &MissionDaemon, event: &DaemonEvent, turn: u64) -> String {
+    let mut payload =
+        serde_json::to_string_pretty(&event.payload).unwrap_or_else(|_| event.payload.to_string());
+    if payload.len() > MAX_PAYLOAD_CHARS {
+        payload.truncate(safe_truncate_index(&payload, MAX_PAYLOAD_CHARS));
+        payload.push_str("\n... (truncated)");
+    }
+    let mut prompt = format!(
+        "Daemon trigger `{}` (received {}, turn {}).\n\n",
+        event.source, event.received_at, turn
+    );
+    if let Some(instructions) = daemon.instructions.as_deref().filter(|i| !i.is_empty()) {
+        prompt.push_str(instructions.trim());
+        prompt.push_str("\n\n");
+    }
+    prompt.push_str(&format!("Event:\n```json\n{}\n```\n\n", payload));
+    prompt.push_str(
+        "Handle this event in this turn, then stop. The mission keeps running for later \
+         events, so do not call complete_mission.",
+    );
+    prompt
+}
+
+/// Why a daemon must stop before its next turn, if it has reached a limit.
+fn limit_reached(daemon: &MissionDaemon) -> Option<String> {
+    if let Some(budget) = daemon.budget_cents {
+        if daemon.spent_cents >= budget {
+            return Some(format!(
+                "budget exhausted ({} of {} cents spent)",
+                daemon.spent_cents, budget
+            ));
+        }
+    }
+    if let Some(max_turns) = daemon.max_turns {
+        if daemon.turns >= max_turns {
+            return Some(format!("turn limit reached ({} turns)", daemon.turns));
+        }
+    }
+    None
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Supervisor
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// File watchers of one daemon, and the triggers they were created for.
+struct Watchers {
+    triggers: Vec<DaemonTrigger>,
+    _watchers: Vec<notify::RecommendedWatcher>,
+}
+
+struct Supervisor {
+    store: Arc<dyn MissionStore>,
+    cmd_tx: mpsc::Sender<ControlCommand>,
+    workspaces: workspace::SharedWorkspaceStore,
+    watchers: HashMap<Uuid, Watchers>,
+    schedule_fired: HashMap<(Uuid, usize), Instant>,
+}
+
+/// Background task that feeds triggers to the daemon missions of one control
+/// session and enforces their limits.
+pub async fn daemon_supervisor_loop(
+    store: Arc<dyn MissionStore>,
+    cmd_tx: mpsc::Sender<ControlCommand>,
+    events_tx: broadcast::Sender<AgentEvent>,
+    workspaces: workspace::SharedWorkspaceStore,
+) {
+    let mut supervisor = Supervisor {
+        store,
+        cmd_tx,
+        workspaces,
+        watchers: HashMap::new(),
+        schedule_fired: HashMap::new(),
+    };
+    let mut events = events_tx.subscribe();
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+    tracing::info!("Daemon mission supervisor started");
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => supervisor.observe(&event).await,
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = tick.tick() => supervisor.tick().await,
+        }
+    }
+}
+
+impl Supervisor {
+    /// Attribute cost to the running turn and finish it on its answer.
+    async fn observe(&mut self, event: &AgentEvent) {
+        let AgentEvent::AssistantMessage {
+            cost_cents,
+            mission_id: Some(mission_id),
+            ..
+        } = event
+        else {
+            return;
+        };
+        let finished = with_runtime(*mission_id, |rt| rt.turn.take());
+        if let Some(mut turn) = finished {
+            turn.cost_cents += cost_cents;
+            self.finish_turn(*mission_id, turn, None).await;
+        }
+    }
+
+    async fn finish_turn(&self, mission_id: Uuid, turn: Turn, note: Option<&str>) {
+        tracing::info!(
+            mission_id = %mission_id,
+            trigger = %turn.source,
+            cost_cents = turn.cost_cents,
+            seconds = turn.started.elapsed().as_secs(),
+            "Daemon turn finished{}",
+            note.map(|n| format!(" ({})", n)).unwrap_or_default()
+        );
+        let Ok(Some(mut daemon)) = self.store.get_mission_daemon(mission_id).await else {
+            return;
+        };
+        daemon.spent_cents += turn.cost_cents;
+        if daemon.state != DaemonState::Stopped {
+            if let Some(reason) = limit_reached(&daemon) {
+                daemon.state = DaemonState::Stopped;
+                daemon.stop_reason = Some(reason);
+            }
+        }
+        if let Err(e) = self.store.upsert_mission_daemon(&daemon).await {
+            tracing::warn!("Failed to update daemon {}: {}", mission_id, e);
+        }
+    }
+
+    async fn running_missions(&self) -> Option<Vec<super::mission_runner::RunningMissionInfo>> {
+        let (tx, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(ControlCommand::ListRunning { respond: tx })
+            .await
+            .ok()?;
+        rx.await.ok()
+    }
+
+    async fn tick(&mut self) {
+        let daemons = match self.store.list_mission_daemons().await {
+            Ok(daemons) => daemons,
+            Err(e) => {
+                tracing::debug!("Daemon supervisor could not list daemons: {}", e);
+                return;
+            }
+        };
+        let running = self.running_missions().await.unwrap_or_default();
+
+        let known: Vec<Uuid> = daemons
+            .iter()
+            .filter(|d| d.state != DaemonState::Stopped)
+            .map(|d| d.mission_id)
+            .collect();
+        self.watchers.retain(|id, _| known.contains(id));
+        self.schedule_fired.retain(|(id, _), _| known.contains(id));
+
+        for daemon in daemons {
+            let mission_id = daemon.mission_id;
+            if daemon.state == DaemonState::Stopped {
+                with_runtime(mission_id, |rt| rt.pending.clear());
+                continue;
+            }
+            self.sync_watchers(&daemon).await;
+            self.fire_schedules(&daemon);
+
+            let info = running.iter().find(|r| r.mission_id == mission_id);
+            let busy = info.is_some_and(|r| {
+                r.queue_len > 0 || matches!(r.state.as_str(), "running" | "waiting_for_tool")
+            });
+
+            let turn_state = with_runtime(mission_id, |rt| {
+                rt.turn.as_ref().map(|t| t.started.elapsed())
+            });
+            if let Some(elapsed) = turn_state {
+                if elapsed > Duration::from_secs(daemon.max_turn_seconds) {
+                    self.cancel_turn(mission_id).await;
+                } else if !busy && elapsed > IDLE_GRACE {
+                    if let Some(turn) = with_runtime(mission_id, |rt| rt.turn.take()) {
+                        self.finish_turn(mission_id, turn, Some("mission idle"))
+                            .await;
+                    }
+                }
+                continue;
+            }
+
+            if daemon.state == DaemonState::Paused || busy {
+                continue;
+            }
+            if with_runtime(mission_id, |rt| rt.pending.is_empty()) {
+                continue;
+            }
+            self.dispatch(daemon).await;
+        }
+    }
+
+    async fn cancel_turn(&self, mission_id: Uuid) {
+        let Some(turn) = with_runtime(mission_id, |rt| rt.turn.take()) else {
+            return;
+        };
+        tracing::warn!(
+            mission_id = %mission_id,
+            trigger = %turn.source,
+            "Daemon turn exceeded its time limit; cancelling"
+        );
+        let (tx, rx) = oneshot::channel();
+        if self
+            .cmd_tx
+            .send(ControlCommand::CancelMission {
+                mission_id,
+                respond: tx,
+            })
+            .await
+            .is_ok()
+        {
+            let _ = rx.await;
+        }
+        self.finish_turn(mission_id, turn, Some("cancelled after time limit"))
+            .await;
+    }
+
+    async fn dispatch(&self, mut daemon: MissionDaemon) {
+        let mission_id = daemon.mission_id;
+        if let Some(reason) = limit_reached(&daemon) {
+            tracing::info!("Stopping daemon mission {}: {}", mission_id, reason);
+            daemon.state = DaemonState::Stopped;
+            daemon.stop_reason = Some(reason);
+            if let Err(e) = self.store.upsert_mission_daemon(&daemon).await {
+                tracing::warn!("Failed to stop daemon {}: {}", mission_id, e);
+            }
+            return;
+        }
+        let Some(event) = with_runtime(mission_id, |rt| rt.pending.pop_front()) else {
+            return;
+        };
+
+        daemon.turns += 1;
+        daemon.last_triggered_at = Some(now_string());
+        let content = turn_prompt(&daemon, &event, daemon.turns);
+        let message_id = Uuid::new_v4();
+        with_runtime(mission_id, |rt| {
+            rt.turn = Some(Turn {
+                source: event.source.clone(),
+                started: Instant::now(),
+                cost_cents: 0,
+            })
+        });
+
+        tracing::info!(
+            mission_id = %mission_id,
+            trigger = %event.source,
+            turn = daemon.turns,
+            "Dispatching daemon turn"
+        );
+        let (tx, _rx) = oneshot::channel();
+        let sent = self
+            .cmd_tx
+            .send(ControlCommand::UserMessage {
+                id: message_id,
+                content,
+                agent: None,
+                target_mission_id: Some(mission_id),
+                respond: tx,
+            })
+            .await;
+        if sent.is_err() {
+            tracing::warn!(
+                "Control session closed; daemon turn for {} not sent",
+                mission_id
+            );
+            with_runtime(mission_id, |rt| {
+                rt.turn = None;
+                rt.pending.push_front(event);
+            });
+            return;
+        }
+        if let Err(e) = self.store.upsert_mission_daemon(&daemon).await {
+            tracing::warn!("Failed to update daemon {}: {}", mission_id, e);
+        }
+    }
+
+    fn fire_schedules(&mut self, daemon: &MissionDaemon) {
+        for (idx, trigger) in daemon.triggers.iter().enumerate() {
+            let DaemonTrigger::Schedule { interval_seconds } = trigger else {
+                continue;
+            };
+            let last = self
+                .schedule_fired
+                .entry((daemon.mission_id, idx))
+                .or_insert_with(Instant::now);
+            if last.elapsed() < Duration::from_secs(*interval_seconds) {
+                continue;
+            }
+            *last = Instant::now();
+            enqueue(
+                daemon.mission_id,
+                DaemonEvent {
+                    source: format!("schedule:{}s", interval_seconds),
+                    received_at: now_string(),
+                    payload: json!({ "interval_seconds": interval_seconds }),
+                },
+            );
+        }
+    }
+
+    /// (Re)create the file watchers when a daemon's triggers change.
+    async fn sync_watchers(&mut self, daemon: &MissionDaemon) {
+        if self
+            .watchers
+            .get(&daemon.mission_id)
+            .is_some_and(|w| w.triggers == daemon.triggers)
+        {
+            return;
+        }
+        let mut watchers = Vec::new();
+        let file_triggers = daemon.triggers.iter().filter_map(|t| match t {
+            DaemonTrigger::FileChange { path, pattern } => Some((path, pattern)),
+            _ => None,
+        });
+        let mut root = None;
+        for (path, pattern) in file_triggers {
+            if root.is_none() {
+                root = match self.store.get_mission(daemon.mission_id).await {
+                    Ok(Some(mission)) => self
+                        .workspaces
+                        .get(mission.workspace_id)
+                        .await
+                        .map(|ws| ws.path),
+                    _ => None,
+                };
+            }
+            let Some(root) = root.as_ref() else {
+                tracing::warn!(
+                    "No workspace for daemon mission {}; file triggers disabled",
+                    daemon.mission_id
+                );
+                break;
+            };
+            match watch_file_trigger(daemon.mission_id, root, path, pattern.clone()) {
+                Ok(watcher) => watchers.push(watcher),
+                Err(e) => tracing::warn!(
+                    "Failed to watch '{}' for daemon mission {}: {}",
+                    path,
+                    daemon.mission_id,
+                    e
+                ),
+            }
+        }
+        self.watchers.insert(
+            daemon.mission_id,
+            Watchers {
+                triggers: daemon.triggers.clone(),
+                _watchers: watchers,
+            },
+        );
+    }
+}
+
+fn watch_file_trigger(
+    mission_id: Uuid,
+    root: &std::path::Path,
+    path: &str,
+    pattern: Option<String>,
+) -> notify::Result<notify::RecommendedWatcher> {
+    let target = root.join(path);
+    let source = format!("file_change:{}", path);
+    let pattern = pattern.map(|p| p.to_lowercase());
+    let root = root.to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        let Some(label) = crate::tools::change_label(&event.kind) else {
+            return;
+        };
+        let mut changes = serde_json::Map::new();
+        for changed in &event.paths {
+            if let Some(pattern) = &pattern {
+                let name = changed
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                if !crate::tools::glob_match(pattern, &name) {
+                    continue;
+                }
+            }
+            let shown = changed.strip_prefix(&root).unwrap_or(changed);
+            changes.insert(shown.display().to_string(), Value::from(label));
+        }
+        if changes.is_empty() {
+            return;
+        }
+        enqueue(
+            mission_id,
+            DaemonEvent {
+                source: source.clone(),
+                received_at: now_string(),
+                payload: json!({ "changes": changes }),
+            },
+        );
+    })?;
+    let mode = if target.is_dir() {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher.watch(&target, mode)?;
+    Ok(watcher)
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// API
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct DaemonConfigRequest {
+    pub triggers: Vec<DaemonTrigger>,
+    #[serde(default)]
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub max_turn_seconds: Option<u64>,
+    #[serde(default)]
+    pub budget_cents: Option<u64>,
+    #[serde(default)]
+    pub max_turns: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DaemonStatus {
+    #[serde(flatten)]
+    pub daemon: MissionDaemon,
+    /// Triggers waiting to be handled
+    pub pending: Vec<DaemonEvent>,
+    /// Trigger of the turn running now
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_turn: Option<String>,
+    /// Triggers dropped because too many were pending
+    pub dropped: u64,
+}
+
+impl From<MissionDaemon> for DaemonStatus {
+    fn from(daemon: MissionDaemon) -> Self {
+        let (pending, current_turn, dropped) = with_runtime(daemon.mission_id, |rt| {
+            (
+                rt.pending.iter().cloned().collect(),
+                rt.turn.as_ref().map(|t| t.source.clone()),
+                rt.dropped,
+            )
+        });
+        Self {
+            daemon,
+            pending,
+            current_turn,
+            dropped,
+        }
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, message.into())
+}
+
+fn internal_error(e: String) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e)
+}
+
+/// Validate triggers, filling in missing webhook IDs.
+fn normalize_triggers(triggers: Vec<DaemonTrigger>) -> Result<Vec<DaemonTrigger>, String> {
+    if triggers.is_empty() {
+        return Err("A daemon needs at least one trigger".to_string());
+    }
+    if triggers.len() > MAX_TRIGGERS {
+        return Err(format!("At most {} triggers are allowed", MAX_TRIGGERS));
+    }
+    triggers
+        .into_iter()
+        .map(|trigger| match trigger {
+            DaemonTrigger::Webhook { webhook_id, secret } => {
+                let webhook_id = webhook_id.trim().to_string();
+                let webhook_id = if webhook_id.is_empty() {
+                    Uuid::new_v4().simple().to_string()
+                } else {
+                    webhook_id
+                };
+                Ok(DaemonTrigger::Webhook { webhook_id, secret })
+            }
+            DaemonTrigger::FileChange { path, pattern } => {
+                let relative = std::path::Path::new(&path);
+                if path.trim().is_empty()
+                    || relative.is_absolute()
+                    || relative
+                        .components()
+                        .any(|c| matches!(c, std::path::Component::ParentDir))
+                {
+                    return Err(format!(
+                        "file_change path '{}' must be relative to the workspace",
+                        path
+                    ));
+                }
+                Ok(DaemonTrigger::FileChange { path, pattern })
+            }
+            DaemonTrigger::Schedule { interval_seconds }
+                if interval_seconds < MIN_SCHEDULE_SECONDS =>
+            {
+                Err(format!(
+                    "schedule interval must be at least {} seconds",
+                    MIN_SCHEDULE_SECONDS
+                ))
+            }
+            DaemonTrigger::Queue { name } if name.trim().is_empty() => {
+                Err("queue name is required".to_string())
+            }
+            other => Ok(other),
+        })
+        .collect()
+}
+
+async fn require_daemon(
+    control: &ControlState,
+    mission_id: Uuid,
+) -> Result<MissionDaemon, (StatusCode, String)> {
+    control
+        .mission_store
+        .get_mission_daemon(mission_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Mission {} is not a daemon", mission_id),
+            )
+        })
+}
+
+/// `PUT /api/control/missions/:id/daemon` — create or reconfigure a daemon.
+/// Counters are kept when reconfiguring; a stopped daemon is started again.
+pub async fn put_daemon(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+    Json(req): Json<DaemonConfigRequest>,
+) -> Result<Json<DaemonStatus>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Mission {} not found", mission_id),
+            )
+        })?;
+
+    let triggers = normalize_triggers(req.triggers).map_err(bad_request)?;
+    let max_turn_seconds = req.max_turn_seconds.unwrap_or(DEFAULT_MAX_TURN_SECONDS);
+    if max_turn_seconds == 0 || max_turn_seconds > MAX_TURN_SECONDS_LIMIT {
+        return Err(bad_request(format!(
+            "max_turn_seconds must be between 1 and {}",
+            MAX_TURN_SECONDS_LIMIT
+        )));
+    }
+
+    let existing = control
+        .mission_store
+        .get_mission_daemon(mission_id)
+        .await
+        .map_err(internal_error)?;
+    let daemon = MissionDaemon {
+        mission_id,
+        state: DaemonState::Running,
+        triggers,
+        instructions: req.instructions.filter(|i| !i.trim().is_empty()),
+        max_turn_seconds,
+        budget_cents: req.budget_cents,
+        max_turns: req.max_turns,
+        turns: existing.as_ref().map_or(0, |d| d.turns),
+        spent_cents: existing.as_ref().map_or(0, |d| d.spent_cents),
+        created_at: existing
+            .as_ref()
+            .map_or_else(now_string, |d| d.created_at.clone()),
+        last_triggered_at: existing.and_then(|d| d.last_triggered_at),
+        stop_reason: None,
+    };
+    control
+        .mission_store
+        .upsert_mission_daemon(&daemon)
+        .await
+        .map_err(internal_error)?;
+    tracing::info!(
+        mission_id = %mission_id,
+        triggers = daemon.triggers.len(),
+        "Daemon mission configured"
+    );
+    Ok(Json(daemon.into()))
+}
+
+/// `GET /api/control/missions/:id/daemon`
+pub async fn get_daemon(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+) -> Result<Json<DaemonStatus>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    Ok(Json(require_daemon(&control, mission_id).await?.into()))
+}
+
+/// `GET /api/control/daemons`
+pub async fn list_daemons(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<DaemonStatus>>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let daemons = control
+        .mission_store
+        .list_mission_daemons()
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(daemons.into_iter().map(DaemonStatus::from).collect()))
+}
+
+/// `POST /api/control/missions/:id/daemon/:action` — `pause`, `resume` or `stop`.
+/// Stopping cancels the running turn and drops pending triggers.
+pub async fn control_daemon(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path((mission_id, action)): Path<(Uuid, String)>,
+) -> Result<Json<DaemonStatus>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let mut daemon = require_daemon(&control, mission_id).await?;
+
+    match action.as_str() {
+        "pause" | "resume" if daemon.state == DaemonState::Stopped => {
+            return Err((
+                StatusCode::CONFLICT,
+                "Daemon is stopped; configure it again to restart it".to_string(),
+            ));
+        }
+        "pause" => daemon.state = DaemonState::Paused,
+        "resume" => daemon.state = DaemonState::Running,
+        "stop" => {
+            daemon.state = DaemonState::Stopped;
+            daemon.stop_reason = Some("stopped by user".to_string());
+            let turn = with_runtime(mission_id, |rt| {
+                rt.pending.clear();
+                rt.turn.take()
+            });
+            if let Some(turn) = turn {
+                daemon.spent_cents += turn.cost_cents;
+                let (tx, rx) = oneshot::channel();
+                if control
+                    .cmd_tx
+                    .send(ControlCommand::CancelMission {
+                        mission_id,
+                        respond: tx,
+                    })
+                    .await
+                    .is_ok()
+                {
+                    let _ = rx.await;
+                }
+            }
+        }
+        other => {
+            return Err(bad_request(format!(
+                "Unknown daemon action '{}' (expected pause, resume or stop)",
+                other
+            )))
+        }
+    }
+    control
+        .mission_store
+        .upsert_mission_daemon(&daemon)
+        .await
+        .map_err(internal_error)?;
+    tracing::info!(mission_id = %mission_id, action = %action, "Daemon mission updated");
+    Ok(Json(daemon.into()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnqueueResponse {
+    pub mission_id: Uuid,
+    pub pending: usize,
+}
+
+fn accept_trigger(
+    daemon: &MissionDaemon,
+    source: String,
+    payload: Value,
+) -> Result<(StatusCode, Json<EnqueueResponse>), (StatusCode, String)> {
+    if daemon.state == DaemonState::Stopped {
+        return Err((StatusCode::CONFLICT, "Daemon is stopped".to_string()));
+    }
+    let pending = enqueue(
+        daemon.mission_id,
+        DaemonEvent {
+            source,
+            received_at: now_string(),
+            payload,
+        },
+    );
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(EnqueueResponse {
+            mission_id: daemon.mission_id,
+            pending,
+        }),
+    ))
+}
+
+/// `POST /api/control/missions/:id/daemon/queue/:name` — post a message to a
+/// queue the daemon subscribes to.
+pub async fn post_queue_message(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path((mission_id, name)): Path<(Uuid, String)>,
+    Json(payload): Json<Value>,
+) -> Result<(StatusCode, Json<EnqueueResponse>), (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    let daemon = require_daemon(&control, mission_id).await?;
+    let subscribed = daemon
+        .triggers
+        .iter()
+        .any(|t| matches!(t, DaemonTrigger::Queue { name: n } if *n == name));
+    if !subscribed {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Daemon does not subscribe to queue '{}'", name),
+        ));
+    }
+    accept_trigger(&daemon, format!("queue:{}", name), payload)
+}
+
+fn verify_signature(
+    secret: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), (StatusCode, String)> {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let unauthorized = |message: &str| (StatusCode::UNAUTHORIZED, message.to_string());
+    let signature = headers
+        .get("x-hub-signature-256")
+        .or_else(|| headers.get("x-webhook-signature"))
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| unauthorized("Missing webhook signature"))?
+        .trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let signature =
+        hex::decode(signature).map_err(|_| unauthorized("Invalid webhook signature"))?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|_| internal_error("Invalid webhook secret".to_string()))?;
+    mac.update(body);
+    mac.verify_slice(&signature)
+        .map_err(|_| unauthorized("Invalid webhook signature"))
+}
+
+/// `POST /api/daemon-webhooks/:mission_id/:webhook_id` — webhook trigger
+/// (no auth; validated with the trigger's secret when it has one).
+pub async fn daemon_webhook(
+    State(state): State<Arc<AppState>>,
+    Path((mission_id, webhook_id)): Path<(Uuid, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<EnqueueResponse>), (StatusCode, String)> {
+    let payload: Value = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| bad_request(format!("Invalid JSON payload: {}", e)))?
+    };
+
+    // Daemons are user-scoped, so look in every session's store.
+    let mut found = None;
+    for session in state.control.all_sessions().await {
+        if let Ok(Some(daemon)) = session.mission_store.get_mission_daemon(mission_id).await {
+            found = Some(daemon);
+            break;
+        }
+    }
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Webhook {} not found", webhook_id),
+        )
+    };
+    let daemon = found.ok_or_else(not_found)?;
+    let secret = daemon
+        .triggers
+        .iter()
+        .find_map(|t| match t {
+            DaemonTrigger::Webhook {
+                webhook_id: id,
+                secret,
+            } if *id == webhook_id => Some(secret.clone()),
+            _ => None,
+        })
+        .ok_or_else(not_found)?;
+    if let Some(secret) = secret {
+        verify_signature(&secret, &headers, &body)?;
+    }
+    accept_trigger(&daemon, format!("webhook:{}", webhook_id), payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(source: &str, payload: Value) -> DaemonEvent {
+        DaemonEvent {
+            source: source.to_string(),
+            received_at: "2026-01-01T00:00:00Z".to_string(),
+            payload,
+        }
+    }
+
+    fn daemon() -> MissionDaemon {
+        MissionDaemon {
+            mission_id: Uuid::new_v4(),
+            state: DaemonState::Running,
+            triggers: vec![DaemonTrigger::Queue {
+                name: "builds".to_string(),
+            }],
+            instructions: Some("Triage failed builds.".to_string()),
+            max_turn_seconds: 60,
+            budget_cents: Some(100),
+            max_turns: Some(3),
+            turns: 0,
+            spent_cents: 0,
+            created_at: now_string(),
+            last_triggered_at: None,
+            stop_reason: None,
+        }
+    }
+
+    #[test]
+    fn enqueue_coalesces_file_changes_and_bounds_queue() {
+        let id = Uuid::new_v4();
+        enqueue(
+            id,
+            event(
+                "file_change:src",
+                json!({"changes": {"src/a.rs": "modified"}}),
+            ),
+        );
+        enqueue(id, event("queue:builds", json!({"n": 1})));
+        let pending = enqueue(
+            id,
+            event(
+                "file_change:src",
+                json!({"changes": {"src/b.rs": "created"}}),
+            ),
+        );
+        assert_eq!(pending, 2);
+        let merged = with_runtime(id, |rt| rt.pending[0].payload.clone());
+        assert_eq!(
+            merged,
+            json!({"changes": {"src/a.rs": "modified", "src/b.rs": "created"}})
+        );
+
+        // File changes during the daemon's own turn are ignored
+        with_runtime(id, |rt| {
+            rt.pending.clear();
+            rt.turn = Some(Turn {
+                source: "queue:builds".to_string(),
+                started: Instant::now(),
+                cost_cents: 0,
+            });
+        });
+        assert_eq!(enqueue(id, event("file_change:src", json!({}))), 0);
+
+        for n in 0..MAX_PENDING + 5 {
+            enqueue(id, event("queue:builds", json!({ "n": n })));
+        }
+        let (len, dropped, first) = with_runtime(id, |rt| {
+            (rt.pending.len(), rt.dropped, rt.pending[0].payload.clone())
+        });
+        assert_eq!((len, dropped), (MAX_PENDING, 5));
+        assert_eq!(first, json!({"n": 5}));
+    }
+
+    #[test]
+    fn prompt_and_limits() {
+        let mut daemon = daemon();
+        let prompt = turn_prompt(&daemon, &event("queue:builds", json!({"build": 42})), 1);
+        assert!(prompt.starts_with("Daemon trigger `queue:builds`"));
+        assert!(prompt.contains("Triage failed builds."));
+        assert!(prompt.contains("\"build\": 42"));
+
+        assert_eq!(limit_reached(&daemon), None);
+        daemon.turns = 3;
+        assert_eq!(
+            limit_reached(&daemon).as_deref(),
+            Some("turn limit reached (3 turns)")
+        );
+        daemon.spent_cents = 150;
+        assert!(limit_reached(&daemon)
+            .unwrap()
+            .starts_with("budget exhausted"));
+    }
+
+    #[test]
+    fn normalizes_triggers() {
+        let triggers = normalize_triggers(vec![
+            DaemonTrigger::Webhook {
+                webhook_id: String::new(),
+                secret: None,
+            },
+            DaemonTrigger::Schedule {
+                interval_seconds: 60,
+            },
+        ])
+        .unwrap();
+        assert!(
+            matches!(&triggers[0], DaemonTrigger::Webhook { webhook_id, .. } if webhook_id.len() == 32)
+        );
+
+        assert!(normalize_triggers(vec![]).is_err());
+        assert!(normalize_triggers(vec![DaemonTrigger::FileChange {
+            path: "../etc".to_string(),
+            pattern: None,
+        }])
+        .is_err());
+        assert!(normalize_triggers(vec![DaemonTrigger::Schedule {
+            interval_seconds: 1,
+        }])
+        .is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! Mission drafts.
+//!
+//! `POST /api/control/missions/draft` turns a rough prompt into a structured
+//! mission brief (goal, constraints, acceptance criteria) written by a cheap
+//! model, and suggests the workspace and library agent that fit it. Nothing is
+//! created: the response carries a ready `POST /api/control/missions` body and
+//! the first message, which the client sends once the user accepts the draft.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::CreateMissionRequest;
+use super::routes::AppState;
+use crate::library::LibraryAgentSummary;
+use crate::workspace::{Workspace, DEFAULT_WORKSPACE_ID};
+
+/// Model (or chain) that writes the brief unless the request names one.
+const DEFAULT_DRAFT_MODEL: &str = "builtin/cheap";
+/// Maximum length of a title derived from the prompt.
+const MAX_TITLE_CHARS: usize = 80;
+
+const DRAFT_INSTRUCTIONS: &str =
+    "You turn a rough request for a coding agent into a clear mission brief. \
+Keep the user's intent; do not invent requirements. Reply with a JSON object only: \
+{\"title\": short title, \"goal\": one or two sentences, \"constraints\": [strings], \
+\"acceptance_criteria\": [checkable strings], \"workspace\": name from the list or null, \
+\"agent\": name from the list or null}. Only pick a workspace or agent the request clearly fits.";
+
+#[derive(Debug, Deserialize)]
+pub struct DraftMissionRequest {
+    /// The rough prompt to improve.
+    pub prompt: String,
+    /// Model or chain that writes the brief (default: `builtin/cheap`).
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Structured mission brief.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MissionBrief {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub goal: String,
+    #[serde(default)]
+    pub constraints: Vec<String>,
+    #[serde(default)]
+    pub acceptance_criteria: Vec<String>,
+}
+
+impl MissionBrief {
+    /// Markdown prompt sent as the mission's first message.
+    pub fn to_prompt(&self) -> String {
+        let mut out = format!("## Goal\n\n{}\n", self.goal.trim());
+        for (heading, items) in [
+            ("Constraints", &self.constraints),
+            ("Acceptance criteria", &self.acceptance_criteria),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n## {}\n\n", heading));
+            for item in items {
+                out.push_str(&format!("- {}\n", item.trim()));
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceSuggestion {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentSuggestion {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DraftMethod {
+    /// Brief and suggestions written by the model.
+    Model,
+    /// The model failed; the prompt is used as the goal and suggestions come
+    /// from names mentioned in the prompt.
+    Fallback,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MissionDraftResponse {
+    pub brief: MissionBrief,
+    /// First message of the mission, rendered from the brief.
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceSuggestion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<AgentSuggestion>,
+    /// Body for `POST /api/control/missions` that accepts the draft.
+    pub mission: CreateMissionRequest,
+    pub method: DraftMethod,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// What the model returns: the brief plus its workspace/agent picks.
+#[derive(Debug, Default, Deserialize)]
+struct ModelDraft {
+    #[serde(flatten)]
+    brief: MissionBrief,
+    #[serde(default)]
+    workspace: Option<String>,
+    #[serde(default)]
+    agent: Option<String>,
+}
+
+/// Parse the model reply, tolerating code fences and text around the object.
+fn parse_model_draft(raw: &str) -> Option<ModelDraft> {
+    let start = raw.find('{')?;
+    let end = raw.rfind('}')?;
+    let draft: ModelDraft = serde_json::from_str(raw.get(start..=end)?).ok()?;
+    if draft.brief.goal.trim().is_empty() {
+        return None;
+    }
+    Some(draft)
+}
+
+/// Whether `name` appears in `text` as a whole word, also with `-`/`_` read as spaces.
+fn mentions(text: &str, name: &str) -> bool {
+    let text = text.to_lowercase();
+    let name = name.to_lowercase();
+    let spaced = name.replace(['-', '_'], " ");
+    [name, spaced].iter().filter(|n| !n.is_empty()).any(|n| {
+        text.match_indices(n.as_str()).any(|(i, _)| {
+            let before = text[..i].chars().next_back();
+            let after = text[i + n.len()..].chars().next();
+            !matches!(before, Some(c) if c.is_alphanumeric())
+                && !matches!(after, Some(c) if c.is_alphanumeric())
+        })
+    })
+}
+
+fn find_workspace(workspaces: &[Workspace], name: &str) -> Option<WorkspaceSuggestion> {
+    workspaces
+        .iter()
+        .find(|w| w.name.eq_ignore_ascii_case(name.trim()))
+        .map(|w| WorkspaceSuggestion {
+            id: w.id,
+            name: w.name.clone(),
+        })
+}
+
+fn find_agent(agents: &[LibraryAgentSummary], name: &str) -> Option<AgentSuggestion> {
+    agents
+        .iter()
+        .find(|a| a.name.eq_ignore_ascii_case(name.trim()))
+        .map(|a| AgentSuggestion {
+            name: a.name.clone(),
+            description: a.description.clone(),
+        })
+}
+
+/// Workspace named in the prompt, longest name first. The host workspace is
+/// the default and is never suggested.
+fn detect_workspace(prompt: &str, workspaces: &[Workspace]) -> Option<WorkspaceSuggestion> {
+    let mut candidates: Vec<&Workspace> = workspaces
+        .iter()
+        .filter(|w| w.id != DEFAULT_WORKSPACE_ID && mentions(prompt, &w.name))
+        .collect();
+    candidates.sort_by_key(|w| std::cmp::Reverse(w.name.len()));
+    candidates
+        .first()
+        .and_then(|w| find_workspace(workspaces, &w.name))
+}
+
+/// Library agent named in the prompt, longest name first.
+fn detect_agent(prompt: &str, agents: &[LibraryAgentSummary]) -> Option<AgentSuggestion> {
+    let mut candidates: Vec<&LibraryAgentSummary> = agents
+        .iter()
+        .filter(|a| mentions(prompt, &a.name))
+        .collect();
+    candidates.sort_by_key(|a| std::cmp::Reverse(a.name.len()));
+    candidates.first().and_then(|a| find_agent(agents, &a.name))
+}
+
+/// Title from the first line of the prompt.
+fn fallback_title(prompt: &str) -> String {
+    let line = prompt.lines().next().unwrap_or_default().trim();
+    match line.char_indices().nth(MAX_TITLE_CHARS) {
+        Some((idx, _)) => format!("{}…", line[..idx].trim_end()),
+        None => line.to_string(),
+    }
+}
+
+async fn model_draft(
+    state: &Arc<AppState>,
+    prompt: &str,
+    model: &str,
+    workspaces: &[Workspace],
+    agents: &[LibraryAgentSummary],
+) -> anyhow::Result<ModelDraft> {
+    let workspace_list: Vec<&str> = workspaces
+        .iter()
+        .filter(|w| w.id != DEFAULT_WORKSPACE_ID)
+        .map(|w| w.name.as_str())
+        .collect();
+    let agent_list: Vec<String> = agents
+        .iter()
+        .map(|a| match &a.description {
+            Some(d) => format!("{}: {}", a.name, d),
+            None => a.name.clone(),
+        })
+        .collect();
+    let input = format!(
+        "Workspaces: {}\nAgents:\n{}\n\nRequest:\n{}",
+        if workspace_list.is_empty() {
+            "(none)".to_string()
+        } else {
+            workspace_list.join(", ")
+        },
+        if agent_list.is_empty() {
+            "(none)".to_string()
+        } else {
+            agent_list.join("\n")
+        },
+        prompt
+    );
+    let reply = super::skill_test::proxy_completion(
+        state,
+        model,
+        DRAFT_INSTRUCTIONS,
+        &input,
+        Duration::from_secs(120),
+    )
+    .await?;
+    parse_model_draft(&reply).ok_or_else(|| anyhow::anyhow!("Model reply is not a mission brief"))
+}
+
+/// Draft a mission brief from a rough prompt.
+pub async fn draft_mission(
+    State(state): State<Arc<AppState>>,
+    Extension(_user): Extension<AuthUser>,
+    Json(req): Json<DraftMissionRequest>,
+) -> Result<Json<MissionDraftResponse>, (StatusCode, String)> {
+    let prompt = req.prompt.trim();
+    if prompt.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "prompt is required".to_string()));
+    }
+
+    let workspaces = state.workspaces.list().await;
+    let library = state.library.read().await.clone();
+    let agents = match library {
+        Some(library) => library.list_library_agents().await.unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Failed to list library agents for draft");
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+
+    let model = req.model.as_deref().unwrap_or(DEFAULT_DRAFT_MODEL);
+    let (brief, workspace, agent, method, warning) =
+        match model_draft(&state, prompt, model, &workspaces, &agents).await {
+            Ok(draft) => {
+                let mut brief = draft.brief;
+                if brief.title.trim().is_empty() {
+                    brief.title = fallback_title(prompt);
+                }
+                (
+                    brief,
+                    draft
+                        .workspace
+                        .and_then(|name| find_workspace(&workspaces, &name))
+                        .filter(|w| w.id != DEFAULT_WORKSPACE_ID),
+                    draft.agent.and_then(|name| find_agent(&agents, &name)),
+                    DraftMethod::Model,
+                    None,
+                )
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Mission draft model failed; using prompt as is");
+                let brief = MissionBrief {
+                    title: fallback_title(prompt),
+                    goal: prompt.to_string(),
+                    ..Default::default()
+                };
+                (
+                    brief,
+                    detect_workspace(prompt, &workspaces),
+                    detect_agent(prompt, &agents),
+                    DraftMethod::Fallback,
+                    Some(e.to_string()),
+                )
+            }
+        };
+
+    let mission = CreateMissionRequest {
+        title: Some(brief.title.clone()),
+        workspace_id: workspace.as_ref().map(|w| w.id),
+        agent: agent.as_ref().map(|a| a.name.clone()),
+        ..Default::default()
+    };
+    Ok(Json(MissionDraftResponse {
+        prompt: brief.to_prompt(),
+        brief,
+        workspace,
+        agent,
+        mission,
+        method,
+        warning,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(name: &str) -> LibraryAgentSummary {
+        LibraryAgentSummary {
+            name: name.to_string(),
+            description: None,
+            path: format!("agent/{}.md", name),
+        }
+    }
+
+    #[test]
+    fn parses_fenced_model_reply() {
+        let raw = "```json\n{\"title\": \"Fix login\", \"goal\": \"Fix the login redirect.\", \
+                   \"constraints\": [\"No new deps\"], \"acceptance_criteria\": [\"Tests pass\"], \
+                   \"workspace\": \"web\", \"agent\": null}\n```";
+        let draft = parse_model_draft(raw).expect("draft");
+        assert_eq!(draft.brief.title, "Fix login");
+        assert_eq!(draft.brief.constraints, vec!["No new deps"]);
+        assert_eq!(draft.workspace.as_deref(), Some("web"));
+        assert!(draft.agent.is_none());
+        assert!(parse_model_draft("{\"title\": \"x\"}").is_none());
+        assert!(parse_model_draft("no json").is_none());
+    }
+
+    #[test]
+    fn detects_mentioned_agent_by_whole_name() {
+        let agents = vec![agent("reviewer"), agent("code-reviewer")];
+        let found = detect_agent("Ask the code reviewer to check PR 12", &agents).unwrap();
+        assert_eq!(found.name, "code-reviewer");
+        assert!(detect_agent("the reviewers are busy", &agents).is_none());
+    }
+
+    #[test]
+    fn prompt_lists_only_non_empty_sections() {
+        let brief = MissionBrief {
+            title: "t".to_string(),
+            goal: "Ship it".to_string(),
+            constraints: vec![],
+            acceptance_criteria: vec!["CI is green".to_string()],
+        };
+        assert_eq!(
+            brief.to_prompt(),
+            "## Goal\n\nShip it\n\n## Acceptance criteria\n\n- CI is green\n"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Guardrail state served to workspace-mcp.
+//!
+//! Anything in the mission directory can be rewritten by the agent it is
+//! meant to constrain, so guardrails are kept on the server and fetched by
+//! workspace-mcp (see [`crate::tools::guard`]) under
+//! `/api/mission-guard/:mission_id/...`:
+//!
+//! - `GET .../policies` - the library's guardrail policies (`policy/*.json`)
+//! - `GET .../hooks` - the library's lifecycle hooks (`hook/*`)
+//! - `GET .../settings` - tool settings kept out of the mission's workspace
+//!   env ([`SERVER_SETTINGS`]), see [`prepare_turn`]
+//!
+//! These endpoints, approvals and host exec are authenticated with the
+//! mission's own token ([`MISSION_TOKEN_ENV`]), so a call can only act for the
+//! mission it comes from.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use uuid::Uuid;
+
+use super::auth;
+use super::routes::AppState;
+use crate::library::types::LibraryHookFile;
+use crate::policy::PolicyFile;
+pub use crate::tools::guard::{MISSION_TOKEN_ENV, SERVER_SETTINGS};
+use crate::workspace::Workspace;
+
+struct MissionGuard {
+    token: String,
+    /// Server-side settings as of the mission's last turn
+    settings: HashMap<String, String>,
+}
+
+static MISSIONS: LazyLock<Mutex<HashMap<Uuid, MissionGuard>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn with_missions<T>(f: impl FnOnce(&mut HashMap<Uuid, MissionGuard>) -> T) -> T {
+    f(&mut MISSIONS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// [`SERVER_SETTINGS`] from `env_vars`, falling back to the server's
+/// environment. Empty values are treated as unset.
+fn server_settings(env_vars: &HashMap<String, String>) -> HashMap<String, String> {
+    SERVER_SETTINGS
+        .iter()
+        .filter_map(|name| {
+            let value = env_vars
+                .get(*name)
+                .cloned()
+                .or_else(|| std::env::var(name).ok())?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Get `workspace`'s env ready for a turn of `mission_id`, before it is
+/// written to the mission directory: the [`SERVER_SETTINGS`] are taken out
+/// and kept for `GET .../settings`, and the mission's token is added. Returns
+/// the server-side settings.
+pub fn prepare_turn(mission_id: Uuid, workspace: &mut Workspace) -> HashMap<String, String> {
+    let mut settings = server_settings(&workspace.env_vars);
+    // A workspace can't switch the server's offline mode off for its missions
+    if crate::offline::offline_mode() {
+        settings.insert(
+            crate::offline::OFFLINE_SETTING.to_string(),
+            "true".to_string(),
+        );
+    }
+    workspace
+        .env_vars
+        .retain(|name, _| !SERVER_SETTINGS.contains(&name.as_str()));
+    let token = with_missions(|missions| {
+        let guard = missions.entry(mission_id).or_insert_with(|| MissionGuard {
+            token: Uuid::new_v4().simple().to_string(),
+            settings: HashMap::new(),
+        });
+        guard.settings = settings.clone();
+        guard.token.clone()
+    });
+    workspace
+        .env_vars
+        .insert(MISSION_TOKEN_ENV.to_string(), token);
+    settings
+}
+
+/// Whether the request carries the token of `mission_id`.
+pub fn authorize(mission_id: Uuid, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let authorized = with_missions(|missions| {
+        missions
+            .get(&mission_id)
+            .zip(token)
+            .is_some_and(|(guard, token)| auth::constant_time_eq(token, &guard.token))
+    });
+    if authorized {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// GET /api/mission-guard/:mission_id/policies
+pub async fn get_policies(
+    State(state): State<Arc<AppState>>,
+    Path(mission_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PolicyFile>>, (StatusCode, String)> {
+    authorize(mission_id, &headers).map_err(|s| (s, "Unauthorized".to_string()))?;
+    let library = state.library.read().await;
+    let Some(library) = library.as_ref() else {
+        return Ok(Json(Vec::new()));
+    };
+    let policies = library
+        .list_policies()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(
+        policies
+            .into_iter()
+            .map(|p| PolicyFile {
+                name: p.name,
+                content: p.content,
+            })
+            .collect(),
+    ))
+}
+
+/// GET /api/mission-guard/:mission_id/hooks
+pub async fn get_hooks(
+    State(state): State<Arc<AppState>>,
+    Path(mission_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<LibraryHookFile>>, (StatusCode, String)> {
+    authorize(mission_id, &headers).map_err(|s| (s, "Unauthorized".to_string()))?;
+    let library = state.library.read().await;
+    let Some(library) = library.as_ref() else {
+        return Ok(Json(Vec::new()));
+    };
+    library
+        .list_hook_files()
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// GET /api/mission-guard/:mission_id/settings
+pub async fn get_settings(
+    Path(mission_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<HashMap<String, String>>, (StatusCode, String)> {
+    authorize(mission_id, &headers).map_err(|s| (s, "Unauthorized".to_string()))?;
+    let settings = with_missions(|missions| {
+        missions
+            .get(&mission_id)
+            .map(|guard| guard.settings.clone())
+    });
+    Ok(Json(settings.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn server_settings_leave_the_workspace_env() {
+        let mission_id = Uuid::new_v4();
+        let mut workspace = Workspace::default_host(std::path::PathBuf::from("/tmp"));
+        workspace.env_vars = HashMap::from([
+            (
+                "SANDBOXED_SH_GIT_PUSH_POLICY".to_string(),
+                "deny".to_string(),
+            ),
+            ("SANDBOXED_SH_GIT_TOKEN".to_string(), "  ".to_string()),
+            ("RUST_LOG".to_string(), "debug".to_string()),
+        ]);
+        let settings = prepare_turn(mission_id, &mut workspace);
+
+        let mut names: Vec<_> = workspace.env_vars.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["RUST_LOG", MISSION_TOKEN_ENV]);
+        assert_eq!(
+            settings
+                .get("SANDBOXED_SH_GIT_PUSH_POLICY")
+                .map(String::as_str),
+            Some("deny")
+        );
+        assert!(!settings.contains_key("SANDBOXED_SH_GIT_TOKEN"));
+    }
+
+    #[test]
+    fn tokens_only_work_for_their_mission() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut workspace = Workspace::default_host(std::path::PathBuf::from("/tmp"));
+        prepare_turn(first, &mut workspace);
+        let token = workspace.env_vars[MISSION_TOKEN_ENV].clone();
+        prepare_turn(second, &mut workspace.clone());
+
+        assert!(authorize(first, &bearer(&token)).is_ok());
+        assert!(authorize(second, &bearer(&token)).is_err());
+        assert!(authorize(Uuid::new_v4(), &bearer(&token)).is_err());
+        assert!(authorize(first, &HeaderMap::new()).is_err());
+
+        // Later turns keep the token the mission's tools already hold
+        prepare_turn(first, &mut workspace);
+        assert_eq!(workspace.env_vars[MISSION_TOKEN_ENV], token);
+    }
+}
@@ -0,0 +1,263 @@
+//! Mission follow-up turns.
+//!
+//! `POST /api/control/missions/:id/messages` sends a follow-up message to a
+//! specific mission, including one that already completed or failed. The
+//! message goes through the regular control routing: the mission is
+//! reactivated, runs on its own backend and keeps its CLI session. With
+//! `stream: true` the response is an SSE stream of that mission's events,
+//! closed once the assistant answers the message.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, Sse},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, oneshot};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, AgentEvent, ControlCommand};
+use super::event_schema::Versioned;
+use super::routes::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct MissionMessageRequest {
+    pub content: String,
+    /// Agent override for this turn.
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// Respond with an SSE stream of the turn's events instead of JSON.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissionMessageResponse {
+    pub id: Uuid,
+    pub mission_id: Uuid,
+    /// Whether the message waits behind a running turn of the mission.
+    pub queued: bool,
+}
+
+/// Follows the events of a mission until the turn answering `message_id` ends.
+struct TurnTracker {
+    mission_id: Uuid,
+    message_id: Uuid,
+    accepted: bool,
+    started: bool,
+}
+
+impl TurnTracker {
+    fn new(mission_id: Uuid, message_id: Uuid) -> Self {
+        Self {
+            mission_id,
+            message_id,
+            accepted: false,
+            started: false,
+        }
+    }
+
+    /// Whether the event belongs to the mission.
+    fn relevant(&self, event: &AgentEvent) -> bool {
+        event.mission_id() == Some(self.mission_id)
+    }
+
+    /// Record an event of the mission; returns true once the turn is over.
+    ///
+    /// The turn starts with the un-queued `user_message` of our message and
+    /// ends with the next `assistant_message`. An error before the message
+    /// was accepted means the control session dropped it.
+    fn observe(&mut self, event: &AgentEvent) -> bool {
+        match event {
+            AgentEvent::UserMessage { id, queued, .. } if *id == self.message_id => {
+                self.accepted = true;
+                self.started |= !*queued;
+                false
+            }
+            AgentEvent::AssistantMessage { .. } => self.started,
+            AgentEvent::Error { .. } => !self.accepted,
+            _ => false,
+        }
+    }
+}
+
+/// Send a follow-up message to a mission.
+pub async fn post_mission_message(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+    Json(req): Json<MissionMessageRequest>,
+) -> Result<Response, (StatusCode, String)> {
+    let content = req.content.trim().to_string();
+    if content.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "content is required".to_string()));
+    }
+
+    let control = control_for_user(&state, &user).await;
+    control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("Mission {} not found", mission_id),
+            )
+        })?;
+
+    // Subscribe before sending so the stream cannot miss the turn's first events.
+    let mut rx = control.events_tx.subscribe();
+    let id = Uuid::new_v4();
+    tracing::info!(
+        user_id = %user.id,
+        message_id = %id,
+        mission_id = %mission_id,
+        content_len = content.len(),
+        stream = req.stream,
+        "Received mission follow-up message"
+    );
+    let (tx, queued_rx) = oneshot::channel();
+    control
+        .cmd_tx
+        .send(ControlCommand::UserMessage {
+            id,
+            content,
+            agent: req.agent,
+            target_mission_id: Some(mission_id),
+            respond: tx,
+        })
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "control session unavailable".to_string(),
+            )
+        })?;
+    let queued = queued_rx.await.unwrap_or(false);
+    let response = MissionMessageResponse {
+        id,
+        mission_id,
+        queued,
+    };
+
+    if !req.stream {
+        return Ok(Json(response).into_response());
+    }
+
+    let mut tracker = TurnTracker::new(mission_id, id);
+    let stream = async_stream::stream! {
+        match Event::default().event("accepted").json_data(&response) {
+            Ok(ev) => yield Ok::<_, Infallible>(ev),
+            Err(e) => tracing::error!("Failed to serialize accepted event: {e}"),
+        }
+        loop {
+            match rx.recv().await {
+                Ok(ev) => {
+                    if !tracker.relevant(&ev) {
+                        continue;
+                    }
+                    let done = tracker.observe(&ev);
+                    match Event::default()
+                        .event(ev.event_name())
+                        .json_data(Versioned::new(&ev))
+                    {
+                        Ok(sse) => yield Ok(sse),
+                        Err(e) => tracing::error!(
+                            event = %ev.event_name(),
+                            error = %e,
+                            "Failed to serialize SSE event; dropping"
+                        ),
+                    }
+                    if done {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        mission_id = %mission_id,
+                        skipped,
+                        "Mission message stream lagged; events dropped"
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+
+    Ok(Sse::new(stream)
+        .keep_alive(
+            axum::response::sse::KeepAlive::new()
+                .interval(std::time::Duration::from_secs(15))
+                .text("keepalive"),
+        )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_message(id: Uuid, mission_id: Uuid, queued: bool) -> AgentEvent {
+        AgentEvent::UserMessage {
+            id,
+            content: "continue".to_string(),
+            queued,
+            mission_id: Some(mission_id),
+        }
+    }
+
+    fn error(mission_id: Uuid) -> AgentEvent {
+        AgentEvent::Error {
+            message: "boom".to_string(),
+            mission_id: Some(mission_id),
+            resumable: true,
+        }
+    }
+
+    fn assistant_message(mission_id: Uuid) -> AgentEvent {
+        AgentEvent::AssistantMessage {
+            id: Uuid::new_v4(),
+            content: "done".to_string(),
+            success: true,
+            cost_cents: 0,
+            cost_source: crate::agents::CostSource::Unknown,
+            usage: None,
+            model: None,
+            model_normalized: None,
+            chat_options: None,
+            mission_id: Some(mission_id),
+            shared_files: None,
+            resumable: false,
+        }
+    }
+
+    #[test]
+    fn queued_message_waits_for_its_own_turn() {
+        let (mission, message) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut tracker = TurnTracker::new(mission, message);
+        assert!(!tracker.observe(&user_message(message, mission, true)));
+        // The answer to the earlier message and errors do not end the stream.
+        assert!(!tracker.observe(&error(mission)));
+        assert!(!tracker.observe(&assistant_message(mission)));
+        assert!(!tracker.observe(&user_message(Uuid::new_v4(), mission, false)));
+        assert!(!tracker.observe(&user_message(message, mission, false)));
+        assert!(!tracker.observe(&error(mission)));
+        assert!(tracker.observe(&assistant_message(mission)));
+    }
+
+    #[test]
+    fn error_before_acceptance_ends_stream() {
+        let (mission, message) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut tracker = TurnTracker::new(mission, message);
+        assert!(tracker.relevant(&error(mission)));
+        assert!(!tracker.relevant(&error(Uuid::new_v4())));
+        assert!(tracker.observe(&error(mission)));
+    }
+}
@@ -0,0 +1,557 @@
+//! Post-processing of finished missions.
+//!
+//! A background task per control session watches for missions reaching a
+//! final status (completed, failed, blocked, not feasible) and runs a
+//! pipeline of processors over them. Each processor appends one
+//! [`MissionResult`] to the mission record, exposed as `results` on
+//! `GET /api/control/missions/:id`. Built-in processors run first:
+//! - `summary` - a short model-written summary of the outcome (falls back to
+//!   an excerpt of the final answer when the model is unavailable)
+//! - `artifacts` - files shared by the agent and deliverables named in the
+//!   request, with whether they exist in the mission directory
+//! - `diff` - per-file workspace changes (see `workspace_snapshot`)
+//!
+//! User processors are configured next to the hooks, in
+//! `hook/processors.json` (synced to [`HOOKS_DIR`]):
+//!
+//! ```json
+//! {
+//!   "processors": [
+//!     { "name": "coverage", "script": "coverage.sh", "timeout_secs": 120 },
+//!     { "name": "licenses", "wasm": "licenses.wasm" }
+//!   ]
+//! }
+//! ```
+//!
+//! They run in config order in the mission directory with a JSON context on
+//! stdin (`mission_id`, `status`, `title`, `terminal_reason`, and `results`
+//! holding the output of every earlier processor). Stdout is stored as JSON,
+//! or as a string if it is not valid JSON; a non-zero exit or timeout is
+//! recorded as the processor's error. WASM processors run like WASM plugin
+//! tools, with capabilities granted by `<name>.json` next to the module.
+//!
+//! Disable with `SANDBOXED_SH_POSTPROCESS=false`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::control::{AgentEvent, MissionStatus};
+use super::mission_store::{now_string, Mission, MissionResult, MissionStore};
+use super::skill_test::config_proxy_completion;
+use crate::config::Config;
+use crate::hooks::{valid_script_name, HOOKS_DIR};
+use crate::locale::LocaleSettings;
+use crate::tools::safe_truncate_index;
+use crate::tools::terminal::{run_workspace_shell_with_stdin, shell_quote};
+use crate::workspace::{self, SharedWorkspaceStore};
+
+/// User processor configuration file inside [`HOOKS_DIR`].
+pub const PROCESSORS_CONFIG_FILE: &str = "processors.json";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const SUMMARY_MODEL: &str = "builtin/cheap";
+/// Characters of each message shown to the summary model or kept as fallback.
+const MAX_EXCERPT_CHARS: usize = 2_000;
+/// Maximum stderr kept in a failed processor's error.
+const MAX_ERROR_CHARS: usize = 2_000;
+
+const SUMMARY_INSTRUCTIONS: &str = "You summarize finished tasks of a coding agent. From the \
+request, the outcome and the agent's final answer, write 2-4 plain sentences on what was done \
+and anything left open. No headings, no lists.";
+
+pub fn postprocess_enabled() -> bool {
+    crate::util::env_var_bool("SANDBOXED_SH_POSTPROCESS", true)
+}
+
+fn is_final(status: MissionStatus) -> bool {
+    matches!(
+        status,
+        MissionStatus::Completed
+            | MissionStatus::Failed
+            | MissionStatus::Blocked
+            | MissionStatus::NotFeasible
+    )
+}
+
+fn excerpt(text: &str, max: usize) -> &str {
+    let text = text.trim();
+    &text[..safe_truncate_index(text, max)]
+}
+
+/// What a processor gets to look at.
+struct ProcessorContext {
+    config: Config,
+    store: Arc<dyn MissionStore>,
+    mission: Mission,
+    mission_dir: PathBuf,
+    /// Output of the processors that already ran, by name.
+    results: serde_json::Map<String, Value>,
+}
+
+#[async_trait]
+trait PostProcessor: Send + Sync {
+    fn name(&self) -> &str;
+    async fn run(&self, ctx: &ProcessorContext) -> anyhow::Result<Value>;
+}
+
+struct SummaryProcessor;
+
+#[async_trait]
+impl PostProcessor for SummaryProcessor {
+    fn name(&self) -> &str {
+        "summary"
+    }
+
+    async fn run(&self, ctx: &ProcessorContext) -> anyhow::Result<Value> {
+        let history = &ctx.mission.history;
+        let request = history.iter().find(|e| e.role == "user");
+        let answer = history.iter().rev().find(|e| e.role == "assistant");
+        let fallback = answer.map(|a| excerpt(&a.content, MAX_EXCERPT_CHARS).to_string());
+        let locale = LocaleSettings::resolve(ctx.mission.locale.as_ref());
+
+        let generated = match (request, answer) {
+            (Some(request), Some(answer)) => {
+                let prompt = format!(
+                    "Request:\n{}\n\nOutcome: {}\n\nFinal answer:\n{}",
+                    excerpt(&request.content, MAX_EXCERPT_CHARS),
+                    ctx.mission.status,
+                    excerpt(&answer.content, MAX_EXCERPT_CHARS)
+                );
+                let instructions = match &locale.locale {
+                    Some(tag) => format!(
+                        "{} Write in the language of locale {}.",
+                        SUMMARY_INSTRUCTIONS, tag
+                    ),
+                    None => SUMMARY_INSTRUCTIONS.to_string(),
+                };
+                let secret = std::env::var("SANDBOXED_PROXY_SECRET").unwrap_or_default();
+                match config_proxy_completion(
+                    &ctx.config,
+                    &secret,
+                    SUMMARY_MODEL,
+                    &instructions,
+                    &prompt,
+                    Duration::from_secs(60),
+                )
+                .await
+                {
+                    Ok(reply) if !reply.trim().is_empty() => Some(reply.trim().to_string()),
+                    Ok(_) => None,
+                    Err(e) => {
+                        tracing::debug!(error = %e, "Mission summary generation failed");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        Ok(json!({
+            "status": ctx.mission.status,
+            "terminal_reason": ctx.mission.terminal_reason,
+            "finished_at": locale.format_timestamp(&ctx.mission.updated_at),
+            "turns": history.iter().filter(|e| e.role == "user").count(),
+            "generated": generated.is_some(),
+            "summary": generated.or(fallback),
+        }))
+    }
+}
+
+struct ArtifactsProcessor;
+
+#[async_trait]
+impl PostProcessor for ArtifactsProcessor {
+    fn name(&self) -> &str {
+        "artifacts"
+    }
+
+    async fn run(&self, ctx: &ProcessorContext) -> anyhow::Result<Value> {
+        let events = ctx
+            .store
+            .get_events(ctx.mission.id, Some(&["assistant_message"]), None, None)
+            .await
+            .map_err(anyhow::Error::msg)?;
+        let shared_files: Vec<Value> = events
+            .iter()
+            .filter_map(|e| e.metadata.get("shared_files").and_then(Value::as_array))
+            .flatten()
+            .cloned()
+            .collect();
+
+        let deliverables = ctx
+            .mission
+            .history
+            .iter()
+            .find(|e| e.role == "user")
+            .map(|request| crate::task::extract_deliverables(&request.content).deliverables)
+            .unwrap_or_default();
+        let deliverables: Vec<Value> = deliverables
+            .iter()
+            .filter_map(|d| d.path())
+            .map(|path| {
+                let resolved = ctx.mission_dir.join(path);
+                json!({
+                    "path": path,
+                    "exists": resolved.exists(),
+                    "size_bytes": std::fs::metadata(&resolved).ok().map(|m| m.len()),
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "shared_files": shared_files,
+            "deliverables": deliverables,
+        }))
+    }
+}
+
+struct DiffProcessor;
+
+#[async_trait]
+impl PostProcessor for DiffProcessor {
+    fn name(&self) -> &str {
+        "diff"
+    }
+
+    async fn run(&self, ctx: &ProcessorContext) -> anyhow::Result<Value> {
+        let diff = crate::workspace_snapshot::mission_diff(&ctx.mission_dir, false).await?;
+        Ok(serde_json::to_value(diff)?)
+    }
+}
+
+/// A user processor from [`PROCESSORS_CONFIG_FILE`].
+#[derive(Debug, Clone, Deserialize)]
+struct ProcessorSpec {
+    name: String,
+    /// Script in [`HOOKS_DIR`].
+    #[serde(default)]
+    script: Option<String>,
+    /// WASM module in [`HOOKS_DIR`].
+    #[serde(default)]
+    wasm: Option<String>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProcessorsConfig {
+    #[serde(default)]
+    processors: Vec<ProcessorSpec>,
+}
+
+fn load_config(working_dir: &Path) -> ProcessorsConfig {
+    let path = working_dir.join(HOOKS_DIR).join(PROCESSORS_CONFIG_FILE);
+    let Ok(raw) = std::fs::read_to_string(&path) else {
+        return ProcessorsConfig::default();
+    };
+    serde_json::from_str(&raw).unwrap_or_else(|e| {
+        tracing::warn!(path = %path.display(), error = %e, "Invalid processors config");
+        ProcessorsConfig::default()
+    })
+}
+
+/// Processor output: JSON if it parses, otherwise the trimmed text.
+fn parse_output(stdout: &str) -> Value {
+    let stdout = stdout.trim();
+    if stdout.is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_str(stdout).unwrap_or_else(|_| Value::String(stdout.to_string()))
+}
+
+struct UserProcessor(ProcessorSpec);
+
+impl UserProcessor {
+    fn input(ctx: &ProcessorContext) -> String {
+        json!({
+            "mission_id": ctx.mission.id,
+            "status": ctx.mission.status,
+            "title": ctx.mission.title,
+            "terminal_reason": ctx.mission.terminal_reason,
+            "results": ctx.results,
+        })
+        .to_string()
+    }
+
+    async fn run_script(&self, ctx: &ProcessorContext, script: &str) -> anyhow::Result<Value> {
+        let command = shell_quote(&format!("./{}/{}", HOOKS_DIR, script));
+        let mut env = HashMap::new();
+        env.insert("SANDBOXED_SH_PROCESSOR".to_string(), self.0.name.clone());
+        let timeout = Duration::from_secs(
+            self.0
+                .timeout_secs
+                .filter(|secs| *secs > 0)
+                .unwrap_or(DEFAULT_TIMEOUT_SECS),
+        );
+        let output = run_workspace_shell_with_stdin(
+            &ctx.mission_dir,
+            &command,
+            env,
+            Some(Self::input(ctx)),
+            timeout,
+        )
+        .await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "{} exited with {}: {}",
+                script,
+                output.status,
+                excerpt(&stderr, MAX_ERROR_CHARS)
+            );
+        }
+        Ok(parse_output(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+#[async_trait]
+impl PostProcessor for UserProcessor {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn run(&self, ctx: &ProcessorContext) -> anyhow::Result<Value> {
+        match (&self.0.script, &self.0.wasm) {
+            (Some(script), None) if valid_script_name(script) => self.run_script(ctx, script).await,
+            (None, Some(module)) if valid_script_name(module) => {
+                let file = format!("{}/{}", HOOKS_DIR, module);
+                let stdout =
+                    crate::tools::run_wasm_module(&ctx.mission_dir, &file, Self::input(ctx))
+                        .await?;
+                Ok(parse_output(&stdout))
+            }
+            (Some(_), Some(_)) | (None, None) => {
+                anyhow::bail!("processor needs exactly one of `script` or `wasm`")
+            }
+            (Some(file), None) | (None, Some(file)) => {
+                anyhow::bail!("invalid file name '{}'", file)
+            }
+        }
+    }
+}
+
+/// Built-in processors followed by the user processors of `mission_dir`.
+fn pipeline(mission_dir: &Path) -> Vec<Box<dyn PostProcessor>> {
+    let mut processors: Vec<Box<dyn PostProcessor>> = vec![
+        Box::new(SummaryProcessor),
+        Box::new(ArtifactsProcessor),
+        Box::new(DiffProcessor),
+    ];
+    let mut names: HashSet<String> = processors.iter().map(|p| p.name().to_string()).collect();
+    for spec in load_config(mission_dir).processors {
+        if spec.name.trim().is_empty() || !names.insert(spec.name.clone()) {
+            tracing::warn!(name = %spec.name, "Skipping processor with empty or duplicate name");
+            continue;
+        }
+        processors.push(Box::new(UserProcessor(spec)));
+    }
+    processors
+}
+
+/// Run every processor over `mission` and append their results.
+async fn process_mission(
+    config: Config,
+    store: Arc<dyn MissionStore>,
+    mission: Mission,
+    mission_dir: PathBuf,
+) {
+    let mission_id = mission.id;
+    let processors = pipeline(&mission_dir);
+    let mut ctx = ProcessorContext {
+        config,
+        store: Arc::clone(&store),
+        mission,
+        mission_dir,
+        results: serde_json::Map::new(),
+    };
+    for processor in processors {
+        let name = processor.name().to_string();
+        let (data, error) = match processor.run(&ctx).await {
+            Ok(data) => (Some(data), None),
+            Err(e) => {
+                tracing::warn!(
+                    mission_id = %mission_id,
+                    processor = %name,
+                    error = %e,
+                    "Mission post-processor failed"
+                );
+                (None, Some(e.to_string()))
+            }
+        };
+        ctx.results
+            .insert(name.clone(), data.clone().unwrap_or(Value::Null));
+        let result = MissionResult {
+            processor: name,
+            data,
+            error,
+            created_at: now_string(),
+        };
+        if let Err(e) = store.append_mission_result(mission_id, result).await {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to store mission result");
+            return;
+        }
+    }
+    tracing::info!(
+        mission_id = %mission_id,
+        processors = ctx.results.len(),
+        "Post-processed finished mission"
+    );
+}
+
+#[derive(Clone)]
+struct PostProcessWorker {
+    config: Config,
+    store: Arc<dyn MissionStore>,
+    workspaces: SharedWorkspaceStore,
+    /// Missions whose pipeline is running.
+    running: Arc<Mutex<HashSet<Uuid>>>,
+}
+
+/// Start the post-processing worker of a control session.
+pub fn spawn_postprocess_worker(
+    config: Config,
+    store: Arc<dyn MissionStore>,
+    workspaces: SharedWorkspaceStore,
+    events_tx: &broadcast::Sender<AgentEvent>,
+) {
+    let worker = PostProcessWorker {
+        config,
+        store,
+        workspaces,
+        running: Arc::new(Mutex::new(HashSet::new())),
+    };
+    let mut rx = events_tx.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(AgentEvent::MissionStatusChanged {
+                    mission_id, status, ..
+                }) if is_final(status) => {
+                    let inserted = worker
+                        .running
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(mission_id);
+                    if inserted {
+                        let worker = worker.clone();
+                        tokio::spawn(async move { worker.process(mission_id).await });
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Mission post-process worker lagged by {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+impl PostProcessWorker {
+    async fn process(&self, mission_id: Uuid) {
+        if let Ok(Some(mission)) = self.store.get_mission(mission_id).await {
+            let root = workspace::resolve_workspace_root(
+                &self.workspaces,
+                &self.config,
+                Some(mission.workspace_id),
+            )
+            .await;
+            let mission_dir = workspace::mission_workspace_dir_for_root(&root, mission_id);
+            process_mission(
+                self.config.clone(),
+                Arc::clone(&self.store),
+                mission,
+                mission_dir,
+            )
+            .await;
+        }
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&mission_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::mission_store::InMemoryMissionStore;
+
+    #[test]
+    fn parses_processor_output() {
+        assert_eq!(parse_output(" {\"lines\": 3}\n"), json!({ "lines": 3 }));
+        assert_eq!(parse_output("all good\n"), json!("all good"));
+        assert_eq!(parse_output("  "), Value::Null);
+    }
+
+    #[tokio::test]
+    async fn runs_builtin_and_user_processors() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join(HOOKS_DIR);
+        std::fs::create_dir_all(&hooks).unwrap();
+        std::fs::write(
+            hooks.join(PROCESSORS_CONFIG_FILE),
+            r#"{"processors": [
+                {"name": "echo", "script": "echo.sh"},
+                {"name": "broken", "script": "broken.sh"},
+                {"name": "bad", "script": "../x.sh"},
+                {"name": "diff", "script": "echo.sh"}
+            ]}"#,
+        )
+        .unwrap();
+        let scripts = [
+            ("echo.sh", "#!/bin/sh\ncat\n"),
+            ("broken.sh", "#!/bin/sh\necho nope >&2\nexit 3\n"),
+        ];
+        for (name, body) in scripts {
+            let path = hooks.join(name);
+            std::fs::write(&path, body).unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            }
+        }
+
+        let store: Arc<dyn MissionStore> = Arc::new(InMemoryMissionStore::new());
+        let mut mission = store
+            .create_mission(Some("Write report"), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        mission.status = MissionStatus::Completed;
+        let mission_id = mission.id;
+        process_mission(
+            Config::new(dir.path().to_path_buf()),
+            Arc::clone(&store),
+            mission,
+            dir.path().to_path_buf(),
+        )
+        .await;
+
+        let results = store.get_mission_results(mission_id).await.unwrap();
+        let names: Vec<&str> = results.iter().map(|r| r.processor.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["summary", "artifacts", "diff", "echo", "broken", "bad"]
+        );
+        let echo = results[3].data.as_ref().unwrap();
+        assert_eq!(echo["mission_id"], json!(mission_id));
+        assert_eq!(echo["status"], "completed");
+        assert!(echo["results"].get("artifacts").is_some());
+        let broken = results[4].error.as_deref().unwrap();
+        assert!(broken.contains("nope"), "{}", broken);
+        assert!(results[5]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("invalid file name"));
+    }
+}
@@ -0,0 +1,429 @@
+//! Mission priorities, boosts and preemption.
+//!
+//! Every mission has an integer `priority` (default 0, higher is more
+//! urgent), set when it is created or later with
+//! `POST /api/control/missions/:id/priority`. Batches, templates and the
+//! mission queue start missions through
+//! [`dispatch_missions`](super::mission_batch::dispatch_missions), which
+//! registers them here while they wait for a parallel slot. A user's waiting
+//! missions start in priority order, the longest waiting first on ties, so
+//! boosting a waiting mission moves it ahead at once.
+//! `GET /api/control/parallel/waiting` lists the waiting missions.
+//!
+//! With the `preemption` setting enabled, a waiting mission of at least
+//! `min_priority` that finds every slot taken preempts the running parallel
+//! mission with the lowest priority, if that is at least `min_gap` lower. The
+//! preempted mission's workspace is checkpointed, it is interrupted with the
+//! [`PREEMPTED_REASON`] reason and requeued with a resume prompt, so it
+//! continues by itself once a slot frees up. A mission is preempted at most
+//! `max_per_mission` times. `POST /api/control/missions/:id/preempt`
+//! preempts a running mission directly.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, Notify};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, ControlCommand, ControlState};
+use super::routes::AppState;
+
+/// Terminal reason of missions interrupted to make room for a more urgent one.
+pub const PREEMPTED_REASON: &str = "preempted";
+
+/// How long a waiting mission keeps its place without its dispatcher
+/// checking in, so a stopped dispatcher does not hold up the others.
+pub const WAITER_TTL: Duration = Duration::from_secs(60);
+
+/// Preemption of running missions (global settings). Off unless enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreemptionSettings {
+    pub enabled: bool,
+    /// Priority a waiting mission needs to preempt a running one
+    pub min_priority: i32,
+    /// How much lower the preempted mission's priority must be
+    pub min_gap: i32,
+    /// Times one mission may be preempted
+    pub max_per_mission: u32,
+}
+
+impl Default for PreemptionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_priority: 100,
+            min_gap: 1,
+            max_per_mission: 2,
+        }
+    }
+}
+
+impl PreemptionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_gap < 1 {
+            return Err("min_gap must be at least 1".to_string());
+        }
+        if self.max_per_mission < 1 {
+            return Err("max_per_mission must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Waiter {
+    tenant: String,
+    priority: i32,
+    since: Instant,
+    seen: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    waiting: HashMap<Uuid, Waiter>,
+    preemptions: HashMap<Uuid, u32>,
+}
+
+impl Registry {
+    fn wait(&mut self, tenant: &str, mission_id: Uuid, priority: i32, now: Instant) {
+        let waiter = self.waiting.entry(mission_id).or_insert_with(|| Waiter {
+            tenant: tenant.to_string(),
+            priority,
+            since: now,
+            seen: now,
+        });
+        waiter.priority = priority;
+        waiter.seen = now;
+    }
+
+    /// A user's waiting missions, next to start first.
+    fn ordered(&self, tenant: &str, now: Instant) -> Vec<(Uuid, &Waiter)> {
+        let mut waiting: Vec<(Uuid, &Waiter)> = self
+            .waiting
+            .iter()
+            .filter(|(_, w)| w.tenant == tenant && now.duration_since(w.seen) < WAITER_TTL)
+            .map(|(id, w)| (*id, w))
+            .collect();
+        waiting.sort_by_key(|(id, w)| (Reverse(w.priority), w.since, *id));
+        waiting
+    }
+
+    fn position(&self, mission_id: Uuid, now: Instant) -> Option<usize> {
+        let tenant = &self.waiting.get(&mission_id)?.tenant;
+        self.ordered(tenant, now)
+            .iter()
+            .position(|(id, _)| *id == mission_id)
+            .map(|index| index + 1)
+    }
+}
+
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(|| Mutex::new(Registry::default()));
+/// Wakes dispatchers when the waiting order may have changed.
+static CHANGED: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Register or refresh a mission waiting for a parallel slot.
+pub fn wait(tenant: &str, mission_id: Uuid, priority: i32) {
+    registry().wait(tenant, mission_id, priority, Instant::now());
+}
+
+/// A mission stopped waiting (started, failed or gone).
+pub fn stop_waiting(mission_id: Uuid) {
+    if registry().waiting.remove(&mission_id).is_some() {
+        CHANGED.notify_waiters();
+    }
+}
+
+/// Whether the mission is the next of its user's waiting missions to start.
+pub fn is_next(mission_id: Uuid) -> bool {
+    registry().position(mission_id, Instant::now()) == Some(1)
+}
+
+/// Change a waiting mission's priority. Returns its new place in the
+/// waiting order (1 = next), or None when it is not waiting.
+pub fn set_waiting_priority(mission_id: Uuid, priority: i32) -> Option<usize> {
+    let mut registry = registry();
+    registry.waiting.get_mut(&mission_id)?.priority = priority;
+    CHANGED.notify_waiters();
+    registry.position(mission_id, Instant::now())
+}
+
+/// Wait until the waiting order changes or `timeout` passes.
+pub async fn changed(timeout: Duration) {
+    let _ = tokio::time::timeout(timeout, CHANGED.notified()).await;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WaitingMission {
+    pub mission_id: Uuid,
+    pub priority: i32,
+    pub waiting_secs: u64,
+}
+
+/// A user's waiting missions, next to start first.
+pub fn waiting(tenant: &str) -> Vec<WaitingMission> {
+    let now = Instant::now();
+    registry()
+        .ordered(tenant, now)
+        .into_iter()
+        .map(|(mission_id, w)| WaitingMission {
+            mission_id,
+            priority: w.priority,
+            waiting_secs: now.duration_since(w.since).as_secs(),
+        })
+        .collect()
+}
+
+/// Running missions a waiting mission of `priority` may preempt, most
+/// preemptible (lowest priority) first.
+pub fn preemption_candidates(
+    settings: &PreemptionSettings,
+    priority: i32,
+    running: &[(Uuid, i32)],
+) -> Vec<Uuid> {
+    if !settings.enabled || priority < settings.min_priority {
+        return Vec::new();
+    }
+    let registry = registry();
+    let mut candidates: Vec<(Uuid, i32)> = running
+        .iter()
+        .filter(|(_, p)| priority.saturating_sub(*p) >= settings.min_gap)
+        .filter(|(id, _)| {
+            registry.preemptions.get(id).copied().unwrap_or(0) < settings.max_per_mission
+        })
+        .copied()
+        .collect();
+    candidates.sort_by_key(|(_, p)| *p);
+    candidates.into_iter().map(|(id, _)| id).collect()
+}
+
+/// Preempt a running parallel mission: the control session checkpoints and
+/// interrupts it, and it is requeued to resume when a slot frees up.
+pub(crate) async fn preempt(
+    control: &ControlState,
+    mission_id: Uuid,
+    reason: String,
+) -> Result<(), String> {
+    let (tx, rx) = oneshot::channel();
+    control
+        .cmd_tx
+        .send(ControlCommand::PreemptMission {
+            mission_id,
+            reason,
+            respond: tx,
+        })
+        .await
+        .map_err(|_| "control session unavailable".to_string())?;
+    let resume_prompt = rx
+        .await
+        .map_err(|_| "control session unavailable".to_string())??;
+    *registry().preemptions.entry(mission_id).or_default() += 1;
+    tracing::info!(mission_id = %mission_id, "Preempted mission; requeued to resume");
+    tokio::spawn(super::mission_batch::dispatch_missions(
+        control.clone(),
+        format!("preempted mission {}", mission_id),
+        vec![(mission_id, resume_prompt)],
+    ));
+    Ok(())
+}
+
+/// Make room for `mission_id`, waiting with `priority`, by preempting a
+/// lower-priority running mission if the preemption settings allow it.
+/// Returns whether a slot was freed.
+///
+/// Boxed because preempting requeues the preempted mission through
+/// `dispatch_missions`, which calls this in turn.
+pub(crate) fn preempt_for(
+    control: &ControlState,
+    mission_id: Uuid,
+    priority: i32,
+) -> BoxFuture<'_, bool> {
+    Box::pin(async move {
+        let settings = crate::settings::preemption_settings_cached();
+        if !settings.enabled || priority < settings.min_priority {
+            return false;
+        }
+        let (tx, rx) = oneshot::channel();
+        if control
+            .cmd_tx
+            .send(ControlCommand::ListRunning { respond: tx })
+            .await
+            .is_err()
+        {
+            return false;
+        }
+        let Ok(running) = rx.await else {
+            return false;
+        };
+        let mut priorities = Vec::new();
+        for info in running {
+            if let Ok(Some(mission)) = control.mission_store.get_mission(info.mission_id).await {
+                priorities.push((mission.id, mission.priority));
+            }
+        }
+        for victim in preemption_candidates(&settings, priority, &priorities) {
+            let reason = format!(
+                "Preempted by mission {} (priority {}); it resumes when a slot frees up",
+                mission_id, priority
+            );
+            match preempt(control, victim, reason).await {
+                Ok(()) => return true,
+                Err(e) => tracing::debug!(mission_id = %victim, "Cannot preempt mission: {}", e),
+            }
+        }
+        false
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPriorityRequest {
+    pub priority: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetPriorityResponse {
+    pub mission_id: Uuid,
+    pub priority: i32,
+    /// Place among the waiting missions (1 = next), when waiting for a slot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+/// POST /api/control/missions/:id/priority
+pub async fn set_mission_priority(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+    Json(req): Json<SetPriorityRequest>,
+) -> Result<Json<SetPriorityResponse>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    control
+        .mission_store
+        .get_mission(mission_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or((StatusCode::NOT_FOUND, "Mission not found".to_string()))?;
+    control
+        .mission_store
+        .update_mission_priority(mission_id, req.priority)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(SetPriorityResponse {
+        mission_id,
+        priority: req.priority,
+        position: set_waiting_priority(mission_id, req.priority),
+    }))
+}
+
+/// POST /api/control/missions/:id/preempt
+pub async fn preempt_mission(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(mission_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let control = control_for_user(&state, &user).await;
+    preempt(
+        &control,
+        mission_id,
+        "Preempted on request; it resumes when a slot frees up".to_string(),
+    )
+    .await
+    .map_err(|e| (StatusCode::CONFLICT, e))?;
+    Ok(Json(
+        serde_json::json!({ "ok": true, "preempted": mission_id }),
+    ))
+}
+
+/// GET /api/control/parallel/waiting
+pub async fn get_waiting(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Json<Vec<WaitingMission>> {
+    let control = control_for_user(&state, &user).await;
+    Json(waiting(&control.user_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waiting_missions_start_by_priority_then_age() {
+        let mut registry = Registry::default();
+        let t0 = Instant::now();
+        let (a, b, c, other) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        registry.wait("alice", a, 0, t0);
+        registry.wait("alice", b, 0, t0 + Duration::from_secs(1));
+        registry.wait("alice", c, 5, t0 + Duration::from_secs(2));
+        registry.wait("bob", other, 50, t0);
+        let now = t0 + Duration::from_secs(3);
+        assert_eq!(registry.position(c, now), Some(1));
+        assert_eq!(registry.position(a, now), Some(2));
+        assert_eq!(registry.position(other, now), Some(1));
+
+        // A boost moves b ahead; refreshing keeps the original wait time.
+        registry.wait("alice", b, 10, now);
+        registry.wait("alice", a, 10, now);
+        let order: Vec<Uuid> = registry
+            .ordered("alice", now)
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(order, vec![a, b, c]);
+
+        // Missions whose dispatcher stopped checking in lose their place.
+        let later = now + WAITER_TTL;
+        registry.wait("alice", c, 5, later);
+        assert_eq!(registry.position(c, later), Some(1));
+    }
+
+    #[test]
+    fn preemption_picks_lowest_priority_within_policy() {
+        let settings = PreemptionSettings {
+            enabled: true,
+            min_priority: 10,
+            min_gap: 5,
+            max_per_mission: 1,
+        };
+        let (low, mid, high, capped) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        registry().preemptions.insert(capped, 1);
+        let running = [(mid, 4), (high, 8), (low, -1), (capped, -5)];
+        assert_eq!(
+            preemption_candidates(&settings, 10, &running),
+            vec![low, mid]
+        );
+        assert!(preemption_candidates(&settings, 9, &running).is_empty());
+        let disabled = PreemptionSettings::default();
+        assert!(preemption_candidates(&disabled, 1000, &running).is_empty());
+        assert!(PreemptionSettings {
+            min_gap: 0,
+            ..settings
+        }
+        .validate()
+        .is_err());
+    }
+}
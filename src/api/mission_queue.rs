@@ -0,0 +1,671 @@
+//! Mission ingestion from a message queue (NATS or Redis streams).
+//!
+//! With `MISSION_QUEUE_URL` set (see [`MissionQueueConfig`]), the server
+//! consumes JSON requests from a NATS subject (as a queue group) or a Redis
+//! stream (as a consumer group) and publishes lifecycle events of the
+//! missions it created back to the events subject or stream. A request is
+//! one of:
+//!
+//! - a new mission: the `POST /api/control/missions` body plus an optional
+//!   `prompt` that starts it right away
+//! - a follow-up: `mission_id` and `prompt`
+//! - a daemon queue message: `mission_id`, `queue` and `payload` (see
+//!   `mission_daemon`)
+//!
+//! An optional `request_id` is echoed in every event about the request.
+//! Events have a `type` of `accepted`, `rejected`, `assistant_message`,
+//! `status_changed` or `finished`. NATS requests sent with a reply subject
+//! also get the `accepted`/`rejected` event as the reply. Redis entries carry
+//! the request JSON in a `payload` (or `data`) field and are acknowledged once
+//! handled; events are added with `type` and `payload` fields.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{prepare_mission, AgentEvent, ControlCommand, CreateMissionRequest};
+use super::mission_batch::{dispatch_missions, is_terminal};
+use super::mission_daemon::{enqueue, DaemonEvent};
+use super::mission_store::{now_string, DaemonTrigger};
+use super::routes::AppState;
+use crate::config::{MissionQueueConfig, Role};
+use crate::tools::safe_truncate_index;
+
+/// Assistant message text included in events.
+const MAX_EVENT_CONTENT_CHARS: usize = 4_000;
+/// Entries kept in the Redis events stream (approximate trim).
+const REDIS_EVENTS_MAXLEN: usize = 10_000;
+const REDIS_READ_BLOCK_MS: usize = 5_000;
+const RECONNECT_MIN: Duration = Duration::from_secs(2);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+/// A request read from the queue.
+#[derive(Debug, Deserialize)]
+pub struct QueueRequest {
+    /// Caller's correlation ID, echoed in events
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// Existing mission for follow-ups and daemon queue messages
+    #[serde(default)]
+    pub mission_id: Option<Uuid>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Daemon queue name (with `mission_id`)
+    #[serde(default)]
+    pub queue: Option<String>,
+    #[serde(default)]
+    pub payload: Option<Value>,
+    #[serde(flatten)]
+    pub mission: CreateMissionRequest,
+}
+
+/// Parse a request payload.
+pub fn parse_request(raw: &[u8]) -> Result<QueueRequest, String> {
+    serde_json::from_slice(raw).map_err(|e| format!("Invalid mission request: {}", e))
+}
+
+/// Where lifecycle events go.
+#[derive(Clone)]
+enum Publisher {
+    Nats(async_nats::Client),
+    Redis(redis::aio::MultiplexedConnection),
+}
+
+impl Publisher {
+    async fn publish(&self, config: &MissionQueueConfig, event: &Value) {
+        let kind = event["type"].as_str().unwrap_or("event");
+        let result = match self {
+            Publisher::Nats(client) => client
+                .publish(config.events_subject.clone(), event.to_string().into())
+                .await
+                .map_err(|e| e.to_string()),
+            Publisher::Redis(conn) => {
+                use redis::AsyncCommands;
+                let mut conn = conn.clone();
+                conn.xadd_maxlen::<_, _, _, _, ()>(
+                    &config.events_subject,
+                    redis::streams::StreamMaxlen::Approx(REDIS_EVENTS_MAXLEN),
+                    "*",
+                    &[("type", kind.to_string()), ("payload", event.to_string())],
+                )
+                .await
+                .map_err(|e| e.to_string())
+            }
+        };
+        if let Err(e) = result {
+            tracing::warn!(event = %kind, "Failed to publish mission queue event: {}", e);
+        }
+    }
+}
+
+fn event(kind: &str, request_id: Option<&str>, fields: Value) -> Value {
+    let mut event = json!({
+        "type": kind,
+        "timestamp": now_string(),
+    });
+    if let Some(request_id) = request_id {
+        event["request_id"] = Value::from(request_id);
+    }
+    if let (Some(event), Value::Object(fields)) = (event.as_object_mut(), fields) {
+        event.extend(fields);
+    }
+    event
+}
+
+/// Lifecycle event for an agent event of a mission created from the queue.
+/// Returns the event and whether the mission reached a terminal status.
+fn lifecycle_event(agent_event: &AgentEvent, request_id: Option<&str>) -> Option<(Value, bool)> {
+    match agent_event {
+        AgentEvent::AssistantMessage {
+            content,
+            success,
+            cost_cents,
+            mission_id: Some(mission_id),
+            ..
+        } => {
+            let mut content = content.clone();
+            content.truncate(safe_truncate_index(&content, MAX_EVENT_CONTENT_CHARS));
+            Some((
+                event(
+                    "assistant_message",
+                    request_id,
+                    json!({
+                        "mission_id": mission_id,
+                        "success": success,
+                        "cost_cents": cost_cents,
+                        "content": content,
+                    }),
+                ),
+                false,
+            ))
+        }
+        AgentEvent::MissionStatusChanged {
+            mission_id,
+            status,
+            summary,
+        } => {
+            let terminal = is_terminal(*status);
+            Some((
+                event(
+                    if terminal {
+                        "finished"
+                    } else {
+                        "status_changed"
+                    },
+                    request_id,
+                    json!({
+                        "mission_id": mission_id,
+                        "status": status,
+                        "summary": summary,
+                    }),
+                ),
+                terminal,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Missions created or addressed through the queue, with their request IDs.
+type Tracked = Arc<tokio::sync::Mutex<HashMap<Uuid, Option<String>>>>;
+
+/// Event forwarding task, stopped when the connection it publishes on goes away.
+struct Forwarder(tokio::task::JoinHandle<()>);
+
+impl Drop for Forwarder {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+struct Consumer {
+    state: Arc<AppState>,
+    config: MissionQueueConfig,
+    user: AuthUser,
+    publisher: Publisher,
+    tracked: Tracked,
+}
+
+/// Start consuming mission requests if `MISSION_QUEUE_URL` is configured.
+pub fn start(state: Arc<AppState>) {
+    let Some(config) = state.config.mission_queue.clone() else {
+        return;
+    };
+    let user = AuthUser {
+        id: config.user_id.clone(),
+        username: config.user_id.clone(),
+        role: Role::Operator,
+    };
+    tokio::spawn(async move {
+        let tracked: Tracked = Default::default();
+        let mut backoff = RECONNECT_MIN;
+        loop {
+            let result = if config.is_redis() {
+                consume_redis(&state, &config, &user, &tracked).await
+            } else {
+                consume_nats(&state, &config, &user, &tracked).await
+            };
+            let (error, connected) = result;
+            if connected {
+                backoff = RECONNECT_MIN;
+            }
+            tracing::warn!(
+                "Mission queue consumer stopped: {}; reconnecting in {}s",
+                error,
+                backoff.as_secs()
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_MAX);
+        }
+    });
+}
+
+impl Consumer {
+    /// Forward events of tracked missions while the consumer is connected.
+    fn spawn_event_forwarder(&self, mut events: broadcast::Receiver<AgentEvent>) -> Forwarder {
+        let publisher = self.publisher.clone();
+        let config = self.config.clone();
+        let tracked = Arc::clone(&self.tracked);
+        Forwarder(tokio::spawn(async move {
+            loop {
+                let agent_event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Mission queue event forwarder lagged by {} events", n);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(mission_id) = agent_event.mission_id() else {
+                    continue;
+                };
+                let request_id = match tracked.lock().await.get(&mission_id) {
+                    Some(request_id) => request_id.clone(),
+                    None => continue,
+                };
+                let Some((event, terminal)) = lifecycle_event(&agent_event, request_id.as_deref())
+                else {
+                    continue;
+                };
+                if terminal {
+                    tracked.lock().await.remove(&mission_id);
+                }
+                publisher.publish(&config, &event).await;
+            }
+        }))
+    }
+
+    /// Handle one request, returning the `accepted` or `rejected` event.
+    async fn handle(&self, raw: &[u8]) -> Value {
+        let request = match parse_request(raw) {
+            Ok(request) => request,
+            Err(e) => return event("rejected", None, json!({ "error": e })),
+        };
+        let request_id = request.request_id.clone();
+        match self.handle_request(request).await {
+            Ok(fields) => event("accepted", request_id.as_deref(), fields),
+            Err(e) => {
+                tracing::info!(request_id = ?request_id, "Rejected mission queue request: {}", e);
+                event("rejected", request_id.as_deref(), json!({ "error": e }))
+            }
+        }
+    }
+
+    async fn handle_request(&self, request: QueueRequest) -> Result<Value, String> {
+        let control = self.state.control.get_or_spawn(&self.user).await;
+        let store = control.mission_store.clone();
+        let prompt = request
+            .prompt
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string);
+
+        if let Some(mission_id) = request.mission_id {
+            store
+                .get_mission(mission_id)
+                .await?
+                .ok_or_else(|| format!("Mission {} not found", mission_id))?;
+
+            if let Some(queue) = request.queue {
+                let daemon = store
+                    .get_mission_daemon(mission_id)
+                    .await?
+                    .ok_or_else(|| format!("Mission {} is not a daemon", mission_id))?;
+                let subscribed = daemon
+                    .triggers
+                    .iter()
+                    .any(|t| matches!(t, DaemonTrigger::Queue { name } if *name == queue));
+                if !subscribed {
+                    return Err(format!("Daemon does not subscribe to queue '{}'", queue));
+                }
+                let pending = enqueue(
+                    mission_id,
+                    DaemonEvent {
+                        source: format!("queue:{}", queue),
+                        received_at: now_string(),
+                        payload: request.payload.unwrap_or(Value::Null),
+                    },
+                );
+                return Ok(json!({ "mission_id": mission_id, "pending": pending }));
+            }
+
+            let content = prompt.ok_or("prompt is required for a follow-up")?;
+            let message_id = Uuid::new_v4();
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            control
+                .cmd_tx
+                .send(ControlCommand::UserMessage {
+                    id: message_id,
+                    content,
+                    agent: request.mission.agent.clone(),
+                    target_mission_id: Some(mission_id),
+                    respond: tx,
+                })
+                .await
+                .map_err(|_| "control session unavailable".to_string())?;
+            let queued = rx.await.unwrap_or(false);
+            self.tracked
+                .lock()
+                .await
+                .insert(mission_id, request.request_id);
+            return Ok(json!({
+                "mission_id": mission_id,
+                "message_id": message_id,
+                "queued": queued,
+            }));
+        }
+
+        let prepared = prepare_mission(&self.state, Some(&request.mission))
+            .await
+            .map_err(|(_, e)| e)?;
+        super::budget::check_cap(&store, prepared.workspace_id, &prepared.tags)
+            .await
+            .map_err(|(_, e)| e)?;
+        let mission = store
+            .create_mission(
+                prepared.title.as_deref(),
+                prepared.workspace_id,
+                prepared.agent.as_deref(),
+                prepared.model_override.as_deref(),
+                prepared.model_effort.as_deref(),
+                prepared.backend.as_deref(),
+                prepared.config_profile.as_deref(),
+            )
+            .await?;
+        if prepared.locale.is_some() {
+            store
+                .update_mission_locale(mission.id, prepared.locale.as_ref())
+                .await?;
+        }
+        if !prepared.tags.is_empty() {
+            store
+                .update_mission_tags(mission.id, &prepared.tags)
+                .await?;
+        }
+        if prepared.sla.is_some() {
+            store
+                .update_mission_sla(mission.id, prepared.sla.as_ref())
+                .await?;
+        }
+        if !prepared.feature_flags.is_empty() {
+            let mut flags = mission.feature_flags.clone();
+            flags.extend(prepared.feature_flags);
+            store
+                .update_mission_feature_flags(mission.id, &flags)
+                .await?;
+        }
+        if prepared.step_budget.is_some() {
+            store
+                .update_mission_step_budget(mission.id, prepared.step_budget)
+                .await?;
+        }
+        if prepared.priority != 0 {
+            store
+                .update_mission_priority(mission.id, prepared.priority)
+                .await?;
+        }
+        if prepared.mode != super::mission_audit::MissionMode::Standard {
+            store.update_mission_mode(mission.id, prepared.mode).await?;
+        }
+        self.tracked
+            .lock()
+            .await
+            .insert(mission.id, request.request_id.clone());
+        tracing::info!(
+            mission_id = %mission.id,
+            request_id = ?request.request_id,
+            "Created mission from queue request"
+        );
+
+        let started = prompt.is_some();
+        if let Some(prompt) = prompt {
+            tokio::spawn(dispatch_missions(
+                control,
+                format!(
+                    "queue request {}",
+                    request.request_id.as_deref().unwrap_or("-")
+                ),
+                vec![(mission.id, prompt)],
+            ));
+        }
+        Ok(json!({
+            "mission_id": mission.id,
+            "workspace_id": mission.workspace_id,
+            "started": started,
+        }))
+    }
+}
+
+/// Consume until the connection fails. Returns the error and whether a
+/// subscription had been established.
+async fn consume_nats(
+    state: &Arc<AppState>,
+    config: &MissionQueueConfig,
+    user: &AuthUser,
+    tracked: &Tracked,
+) -> (String, bool) {
+    match subscribe_nats(config).await {
+        Ok((client, subscriber)) => (
+            run_nats(state, config, user, tracked, client, subscriber).await,
+            true,
+        ),
+        Err(e) => (e, false),
+    }
+}
+
+async fn subscribe_nats(
+    config: &MissionQueueConfig,
+) -> Result<(async_nats::Client, async_nats::Subscriber), String> {
+    let client = async_nats::connect(config.url.as_str())
+        .await
+        .map_err(|e| format!("NATS connect failed: {}", e))?;
+    let subscriber = client
+        .queue_subscribe(config.subject.clone(), config.group.clone())
+        .await
+        .map_err(|e| format!("NATS subscribe failed: {}", e))?;
+    Ok((client, subscriber))
+}
+
+async fn run_nats(
+    state: &Arc<AppState>,
+    config: &MissionQueueConfig,
+    user: &AuthUser,
+    tracked: &Tracked,
+    client: async_nats::Client,
+    mut subscriber: async_nats::Subscriber,
+) -> String {
+    tracing::info!(
+        subject = %config.subject,
+        group = %config.group,
+        "Consuming mission requests from NATS"
+    );
+
+    let consumer = Consumer {
+        state: Arc::clone(state),
+        config: config.clone(),
+        user: user.clone(),
+        publisher: Publisher::Nats(client.clone()),
+        tracked: Arc::clone(tracked),
+    };
+    let control = state.control.get_or_spawn(user).await;
+    let _forwarder = consumer.spawn_event_forwarder(control.events_tx.subscribe());
+
+    while let Some(message) = subscriber.next().await {
+        let response = consumer.handle(&message.payload).await;
+        if let Some(reply) = message.reply {
+            if let Err(e) = client.publish(reply, response.to_string().into()).await {
+                tracing::warn!("Failed to reply to mission queue request: {}", e);
+            }
+        }
+        consumer.publisher.publish(config, &response).await;
+    }
+    "NATS subscription closed".to_string()
+}
+
+/// Consume until the connection fails. Returns the error and whether the
+/// consumer group had been joined.
+async fn consume_redis(
+    state: &Arc<AppState>,
+    config: &MissionQueueConfig,
+    user: &AuthUser,
+    tracked: &Tracked,
+) -> (String, bool) {
+    match connect_redis(config).await {
+        Ok((reader, publisher)) => (
+            run_redis(state, config, user, tracked, reader, publisher).await,
+            true,
+        ),
+        Err(e) => (e, false),
+    }
+}
+
+async fn connect_redis(
+    config: &MissionQueueConfig,
+) -> Result<
+    (
+        redis::aio::MultiplexedConnection,
+        redis::aio::MultiplexedConnection,
+    ),
+    String,
+> {
+    use redis::AsyncCommands;
+
+    let client = redis::Client::open(config.url.as_str()).map_err(|e| e.to_string())?;
+    // Blocking reads get their own connection so event publishing never waits on them.
+    let mut reader = client
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| format!("Redis connect failed: {}", e))?;
+    let publisher = client
+        .get_multiplexed_async_connection()
+        .await
+        .map_err(|e| format!("Redis connect failed: {}", e))?;
+
+    let created: redis::RedisResult<()> = reader
+        .xgroup_create_mkstream(&config.subject, &config.group, "$")
+        .await;
+    if let Err(e) = created {
+        if e.code() != Some("BUSYGROUP") {
+            return Err(format!("Redis consumer group setup failed: {}", e));
+        }
+    }
+    Ok((reader, publisher))
+}
+
+async fn run_redis(
+    state: &Arc<AppState>,
+    config: &MissionQueueConfig,
+    user: &AuthUser,
+    tracked: &Tracked,
+    mut reader: redis::aio::MultiplexedConnection,
+    publisher: redis::aio::MultiplexedConnection,
+) -> String {
+    use redis::streams::{StreamReadOptions, StreamReadReply};
+    use redis::AsyncCommands;
+
+    let consumer_name = format!("{}-{}", config.group, std::process::id());
+    tracing::info!(
+        stream = %config.subject,
+        group = %config.group,
+        consumer = %consumer_name,
+        "Consuming mission requests from Redis"
+    );
+
+    let consumer = Consumer {
+        state: Arc::clone(state),
+        config: config.clone(),
+        user: user.clone(),
+        publisher: Publisher::Redis(publisher),
+        tracked: Arc::clone(tracked),
+    };
+    let control = state.control.get_or_spawn(user).await;
+    let _forwarder = consumer.spawn_event_forwarder(control.events_tx.subscribe());
+
+    let options = StreamReadOptions::default()
+        .group(&config.group, &consumer_name)
+        .block(REDIS_READ_BLOCK_MS)
+        .count(10);
+    loop {
+        let reply: StreamReadReply = match reader
+            .xread_options(&[&config.subject], &[">"], &options)
+            .await
+        {
+            Ok(reply) => reply,
+            Err(e) => return format!("Redis read failed: {}", e),
+        };
+        for entry in reply.keys.into_iter().flat_map(|key| key.ids) {
+            let raw: Option<String> = entry.get("payload").or_else(|| entry.get("data"));
+            let response = match raw {
+                Some(raw) => consumer.handle(raw.as_bytes()).await,
+                None => event(
+                    "rejected",
+                    None,
+                    json!({ "error": "Stream entry has no payload field", "entry_id": entry.id }),
+                ),
+            };
+            consumer.publisher.publish(config, &response).await;
+            let acked: redis::RedisResult<()> = reader
+                .xack(&config.subject, &config.group, &[&entry.id])
+                .await;
+            if let Err(e) = acked {
+                tracing::warn!(entry = %entry.id, "Failed to acknowledge mission request: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::control::MissionStatus;
+
+    #[test]
+    fn parses_request_kinds() {
+        let request = parse_request(
+            br#"{"request_id": "r1", "title": "Fix CI", "workspace_id": null, "prompt": "Fix the build", "tags": ["ci"]}"#,
+        )
+        .unwrap();
+        assert_eq!(request.request_id.as_deref(), Some("r1"));
+        assert_eq!(request.mission.title.as_deref(), Some("Fix CI"));
+        assert_eq!(request.mission.tags, vec!["ci"]);
+        assert!(request.mission_id.is_none());
+
+        let mission_id = Uuid::new_v4();
+        let raw = json!({"mission_id": mission_id, "queue": "builds", "payload": {"id": 7}});
+        let request = parse_request(raw.to_string().as_bytes()).unwrap();
+        assert_eq!(request.mission_id, Some(mission_id));
+        assert_eq!(request.payload, Some(json!({"id": 7})));
+
+        assert!(parse_request(b"not json")
+            .unwrap_err()
+            .starts_with("Invalid mission request"));
+    }
+
+    #[test]
+    fn maps_lifecycle_events() {
+        let mission_id = Uuid::new_v4();
+        let (event, terminal) = lifecycle_event(
+            &AgentEvent::MissionStatusChanged {
+                mission_id,
+                status: MissionStatus::Completed,
+                summary: Some("done".to_string()),
+            },
+            Some("r1"),
+        )
+        .unwrap();
+        assert!(terminal);
+        assert_eq!(event["type"], "finished");
+        assert_eq!(event["request_id"], "r1");
+        assert_eq!(event["status"], "completed");
+
+        let (event, terminal) = lifecycle_event(
+            &AgentEvent::MissionStatusChanged {
+                mission_id,
+                status: MissionStatus::Active,
+                summary: None,
+            },
+            None,
+        )
+        .unwrap();
+        assert!(!terminal);
+        assert_eq!(event["type"], "status_changed");
+        assert!(event.get("request_id").is_none());
+
+        assert!(lifecycle_event(
+            &AgentEvent::MissionTitleChanged {
+                mission_id,
+                title: "x".to_string()
+            },
+            None
+        )
+        .is_none());
+    }
+}
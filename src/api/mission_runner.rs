@@ -22,7 +22,10 @@ use uuid::Uuid;
 
 use crate::agents::{AgentRef, AgentResult, TerminalReason};
 use crate::backend::claudecode::client::{ClaudeEvent, ContentBlock, StreamEvent};
+use crate::backend::BackendRegistry;
 use crate::config::Config;
+use crate::hooks::{HookEvent, HookOutcome};
+use crate::library::ClaudeCodePermissions;
 use crate::mcp::McpRegistry;
 use crate::opencode::{extract_reasoning, extract_text};
 use crate::secrets::SecretsStore;
@@ -35,8 +38,8 @@ use crate::workspace_exec::WorkspaceExec;
 
 use super::automation_variables::substitute_custom_variables;
 use super::control::{
-    resolve_claudecode_default_model, safe_truncate_index, AgentEvent, AgentTreeNode,
-    ControlRunState, ControlStatus, ExecutionProgress, FrontendToolHub,
+    resolve_chat_options, resolve_claudecode_default_model, safe_truncate_index, AgentEvent,
+    AgentTreeNode, ControlRunState, ControlStatus, ExecutionProgress, FrontendToolHub,
 };
 use super::library::SharedLibrary;
 
@@ -716,7 +719,8 @@ fn parse_opencode_sse_event(
                         let args = if args_str.trim().is_empty() {
                             serde_json::json!({})
                         } else {
-                            serde_json::from_str(&args_str)
+                            crate::json_repair::parse(&args_str)
+                                .map(|repaired| repaired.value)
                                 .unwrap_or_else(|_| serde_json::json!({ "arguments": args_str }))
                         };
                         state.emitted_tool_calls.insert(call_id.clone(), ());
@@ -876,7 +880,7 @@ pub enum MissionRunState {
 const STALL_WARN_SECS: u64 = 120;
 const STALL_SEVERE_SECS: u64 = 300;
 
-#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum MissionStallSeverity {
     Warning,
@@ -884,7 +888,7 @@ pub enum MissionStallSeverity {
 }
 
 /// Health status of a mission.
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum MissionHealth {
     /// Mission is progressing normally
@@ -961,6 +965,15 @@ pub struct MissionRunner {
     /// Config profile from the mission (overrides workspace config_profile)
     pub config_profile: Option<String>,
 
+    /// Locale and time zone for this mission (falls back to the global settings)
+    pub locale: Option<crate::locale::LocaleSettings>,
+
+    /// Executor feature flags recorded on the mission (see `feature_flags`)
+    pub feature_flags: crate::feature_flags::FlagSet,
+
+    /// Standard or read-only audit (see `mission_audit`)
+    pub mode: super::mission_audit::MissionMode,
+
     /// Current state
     pub state: MissionRunState,
 
@@ -1026,6 +1039,9 @@ impl MissionRunner {
             backend_id: backend_id.unwrap_or_else(|| "opencode".to_string()),
             session_id,
             config_profile,
+            locale: None,
+            feature_flags: crate::feature_flags::FlagSet::new(),
+            mode: super::mission_audit::MissionMode::Standard,
             state: MissionRunState::Queued,
             agent_override,
             model_override,
@@ -1152,6 +1168,7 @@ impl MissionRunner {
         mission_cmd_tx: mpsc::Sender<crate::tools::mission::MissionControlCommand>,
         current_mission: Arc<RwLock<Option<Uuid>>>,
         secrets: Option<Arc<SecretsStore>>,
+        backends: Arc<RwLock<BackendRegistry>>,
     ) -> bool {
         // Don't start if already running
         if self.is_running() {
@@ -1180,6 +1197,9 @@ impl MissionRunner {
         let backend_id = self.backend_id.clone();
         let session_id = self.session_id.clone();
         let config_profile = self.config_profile.clone();
+        let locale = self.locale.clone();
+        let feature_flags = self.feature_flags.clone();
+        let mode = self.mode;
         let user_message = msg.content.clone();
         let msg_id = msg.id;
         tracing::info!(
@@ -1230,6 +1250,10 @@ impl MissionRunner {
                 secrets,
                 session_id,
                 config_profile,
+                locale,
+                feature_flags,
+                mode,
+                backends,
             )
             .await;
             (msg_id, user_message, result)
@@ -1425,6 +1449,154 @@ pub fn is_session_corruption_error(result: &AgentResult) -> bool {
     || out.contains("No conversation found with session ID")
 }
 
+/// Record dependencies the agent added this turn and emit an event for each.
+async fn report_added_dependencies(
+    mission_work_dir: &std::path::Path,
+    workspace: &Workspace,
+    mission_id: Uuid,
+    events_tx: &broadcast::Sender<AgentEvent>,
+) {
+    let allowlist = crate::dependency_audit::LicenseAllowlist::from_env_vars(&workspace.env_vars);
+    if let Err(e) = crate::dependency_audit::audit(mission_work_dir, &allowlist).await {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Dependency audit failed");
+        return;
+    }
+    for record in crate::dependency_audit::take_unreported(mission_work_dir) {
+        let _ = events_tx.send(AgentEvent::DependencyAdded {
+            dependency: record.dependency,
+            license: record.license,
+            registry_url: record.registry_url,
+            violation: record.violation,
+            mission_id,
+        });
+    }
+}
+
+/// Report connections the workspace egress policy rejected during the turn.
+fn report_egress_violations(
+    workspace: &Workspace,
+    mission_id: Uuid,
+    events_tx: &broadcast::Sender<AgentEvent>,
+) {
+    for violation in crate::egress::take_violations(&workspace.path) {
+        tracing::warn!(
+            mission_id = %mission_id,
+            destination = %violation.destination,
+            port = %violation.port,
+            "Egress policy blocked outbound connection"
+        );
+        let _ = events_tx.send(AgentEvent::SecurityEvent {
+            kind: "egress_blocked".to_string(),
+            message: format!(
+                "Blocked outbound connection to {}:{}",
+                violation.destination, violation.port
+            ),
+            details: serde_json::json!(violation),
+            mission_id,
+        });
+    }
+}
+
+/// Report tool results the workspace MCP masked PII in during the turn.
+fn report_masked_pii(
+    mission_work_dir: &std::path::Path,
+    mission_id: Uuid,
+    events_tx: &broadcast::Sender<AgentEvent>,
+) {
+    for report in crate::pii::take_unreported(mission_work_dir) {
+        let counts = report
+            .counts()
+            .iter()
+            .map(|(kind, n)| format!("{}: {}", kind, n))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = events_tx.send(AgentEvent::SecurityEvent {
+            kind: "pii_masked".to_string(),
+            message: format!(
+                "Masked {} PII span(s) in {} output ({})",
+                report.spans.len(),
+                report.tool,
+                counts
+            ),
+            details: serde_json::json!(report),
+            mission_id,
+        });
+    }
+}
+
+/// Move the commits the agent made this turn into the commit provenance index.
+fn index_agent_commits(
+    working_dir_root: &std::path::Path,
+    mission_work_dir: &std::path::Path,
+    mission_id: Uuid,
+    model_used: Option<&str>,
+) {
+    let mut commits = crate::provenance::take_pending(mission_work_dir);
+    if commits.is_empty() {
+        return;
+    }
+    for commit in &mut commits {
+        if commit.model.is_none() {
+            commit.model = model_used.map(str::to_string);
+        }
+    }
+    if let Err(e) = crate::provenance::index_commits(working_dir_root, &commits) {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to index agent commits");
+    }
+}
+
+/// Surface the notes lifecycle hooks attached to a mission event in the timeline.
+fn emit_hook_annotations(
+    events_tx: &broadcast::Sender<AgentEvent>,
+    mission_id: Uuid,
+    event: HookEvent,
+    outcome: &HookOutcome,
+) {
+    for annotation in &outcome.annotations {
+        let _ = events_tx.send(AgentEvent::AgentPhase {
+            phase: format!("hook:{}", event.as_str()),
+            detail: Some(annotation.text.clone()),
+            agent: Some(annotation.script.clone()),
+            mission_id: Some(mission_id),
+        });
+    }
+}
+
+/// Report the processes torn down after a cancelled backend run.
+fn emit_killed_processes(
+    report: crate::process_group::KillReport,
+    mission_id: Uuid,
+    events_tx: &broadcast::Sender<AgentEvent>,
+) {
+    if report.is_empty() {
+        return;
+    }
+    if !report.survivors.is_empty() {
+        tracing::warn!(
+            mission_id = %mission_id,
+            survivors = ?report.survivors,
+            "Backend processes still running after cancellation"
+        );
+    }
+    let _ = events_tx.send(AgentEvent::KilledProcesses {
+        processes: report.killed,
+        survivors: report.survivors,
+        mission_id,
+    });
+}
+
+/// Terminate the process tree of a cancelled backend CLI and report it.
+async fn kill_backend_processes(
+    pid: Option<u32>,
+    mission_id: Uuid,
+    events_tx: &broadcast::Sender<AgentEvent>,
+) {
+    if let Some(pid) = pid {
+        let report = crate::process_group::terminate(pid).await;
+        emit_killed_processes(report, mission_id, events_tx);
+    }
+}
+
 /// Execute a single turn for a mission.
 #[allow(clippy::too_many_arguments)]
 async fn run_mission_turn(
@@ -1451,6 +1623,10 @@ async fn run_mission_turn(
     secrets: Option<Arc<SecretsStore>>,
     session_id: Option<String>,
     mission_config_profile: Option<String>,
+    mission_locale: Option<crate::locale::LocaleSettings>,
+    feature_flags: crate::feature_flags::FlagSet,
+    mode: super::mission_audit::MissionMode,
+    backends: Arc<RwLock<BackendRegistry>>,
 ) -> AgentResult {
     let mut config = config;
     let effective_agent = agent_override.clone();
@@ -1461,11 +1637,17 @@ async fn run_mission_turn(
         config.default_model = Some(model.clone());
     }
     // Get config profile: mission's config_profile takes priority over workspace's
-    let workspace_config_profile = if let Some(ws_id) = workspace_id {
-        workspaces.get(ws_id).await.and_then(|ws| ws.config_profile)
-    } else {
-        None
+    let stored_workspace = match workspace_id {
+        Some(ws_id) => workspaces.get(ws_id).await,
+        None => None,
     };
+    let workspace_config_profile = stored_workspace
+        .as_ref()
+        .and_then(|ws| ws.config_profile.clone());
+    let workspace_chat_options = stored_workspace
+        .as_ref()
+        .map(workspace::workspace_chat_options)
+        .unwrap_or_default();
     tracing::info!(
         mission_id = %mission_id,
         mission_config_profile = ?mission_config_profile,
@@ -1492,6 +1674,32 @@ async fn run_mission_turn(
         // Codex.  Clear it so Codex uses its own CLI default.
         config.default_model = None;
     }
+    // Default sampling options from the config profile, workspace and library
+    // agent. An explicit mission effort still wins over the configured default.
+    let (mut chat_options, agent_chat_options) = resolve_chat_options(
+        &library,
+        effective_config_profile.as_deref(),
+        &workspace_chat_options,
+        effective_agent.as_deref(),
+    )
+    .await;
+    let model_effort = model_effort.or_else(|| chat_options.reasoning_effort.clone());
+    // Record the effective reasoning setting alongside usage for cost analysis.
+    chat_options.reasoning_effort = model_effort.clone();
+    // Only options the backend applies are recorded; the rest are reported once.
+    let ignored_options = chat_options.retain_applicable(&backend_id, &agent_chat_options);
+    if !ignored_options.is_empty() && !history.iter().any(|(role, _)| role == "assistant") {
+        let message = format!(
+            "The {} backend cannot apply chat options {}; they are ignored",
+            backend_id,
+            ignored_options.join(", ")
+        );
+        tracing::warn!(mission_id = %mission_id, "{}", message);
+        let _ = events_tx.send(AgentEvent::ConfigWarning {
+            message,
+            mission_id,
+        });
+    }
     tracing::info!(
         mission_id = %mission_id,
         workspace_id = ?workspace_id,
@@ -1543,6 +1751,7 @@ async fn run_mission_turn(
 - After each tool call, ask yourself: "Have I completed the FULL goal?"
 - DO NOT stop after just one step - keep working until ALL deliverables exist.
 - If you made progress but aren't done, continue in the same turn.
+- Record your plan with set_plan before starting, and mark each step with update_plan as you go.
 - Only call complete_mission when ALL requested outputs have been created."#
     } else {
         ""
@@ -1554,11 +1763,34 @@ async fn run_mission_turn(
     convo.push_str(&user_message);
     convo.push_str(&deliverable_reminder);
     convo.push_str("\n\nInstructions:\n- Continue the conversation helpfully.\n- Use available tools to gather information or make changes.\n- For large data processing tasks (>10KB), prefer executing scripts rather than inline processing.\n- USE information already provided in the message - do not ask for URLs, paths, or details that were already given.\n- When you have fully completed the user's goal or determined it cannot be completed, state that clearly in your final response.");
+    if let Some(locale) = crate::locale::LocaleSettings::resolve(mission_locale.as_ref())
+        .prompt_instructions(chrono::Utc::now())
+    {
+        convo.push('\n');
+        convo.push_str(&locale);
+    }
     convo.push_str(multi_step_instructions);
+    let audit = mode == super::mission_audit::MissionMode::Audit;
+    if audit {
+        convo.push_str(super::mission_audit::AUDIT_INSTRUCTIONS);
+    }
     convo.push('\n');
 
     // Ensure mission workspace exists and is configured for OpenCode.
-    let workspace = workspace::resolve_workspace(&workspaces, &config, workspace_id).await;
+    let mut workspace = workspace::resolve_workspace(&workspaces, &config, workspace_id).await;
+    if backend_id == "claudecode" {
+        // Claude Code reads output and thinking budgets from its environment;
+        // variables set explicitly on the workspace take precedence.
+        for (key, value) in chat_options.claudecode_env() {
+            workspace.env_vars.entry(key).or_insert(value);
+        }
+    }
+    // Short-lived credentials replace long-lived ones set on the workspace.
+    workspace
+        .env_vars
+        .extend(super::mission_credentials::env_for(mission_id, &workspace).await);
+    // Guarded tools read their settings from the server, not the mission directory.
+    let server_settings = super::mission_guard::prepare_turn(mission_id, &mut workspace);
     if let Err(e) =
         workspace::sync_workspace_mcp_binaries_for_workspace(&config.working_dir, &workspace).await
     {
@@ -1605,7 +1837,9 @@ async fn run_mission_turn(
         .iter()
         .filter(|(role, _)| role == "assistant")
         .count();
-    let should_rotate = turn_count > 0 && turn_count % SESSION_ROTATION_INTERVAL == 0;
+    let should_rotate = turn_count > 0
+        && turn_count % SESSION_ROTATION_INTERVAL == 0
+        && crate::feature_flags::is_enabled(&feature_flags, crate::feature_flags::SESSION_ROTATION);
 
     // Prepare user message and session ID (potentially with rotation)
     let (mut user_message, mut session_id) = (user_message, session_id);
@@ -1666,12 +1900,129 @@ async fn run_mission_turn(
     // Note: history may include the current user message before the turn runs,
     // so we check for assistant messages to determine if this is truly a continuation.
     let is_continuation = history.iter().any(|(role, _)| role == "assistant");
+    let tool_pruning = crate::tool_pruning::pruning_enabled_for(&workspace.env_vars)
+        || crate::feature_flags::is_enabled(&feature_flags, crate::feature_flags::TOOL_PRUNING);
+    if tool_pruning {
+        let agent_tools = resolve_agent_tool_patterns(&library, effective_agent.as_deref()).await;
+        let hints = crate::tool_pruning::TurnHints::new(&user_message, agent_tools);
+        if let Err(e) = crate::tool_pruning::write_turn_hints(&mission_work_dir, &hints) {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to write tool pruning hints");
+        }
+    } else {
+        crate::tool_pruning::clear_turn_hints(&mission_work_dir);
+    }
+    match crate::settings::tool_quotas_cached() {
+        Some(quotas) => {
+            if let Err(e) = crate::tool_quotas::write_limits(&mission_work_dir, &quotas) {
+                tracing::warn!(mission_id = %mission_id, error = %e, "Failed to write tool quotas");
+            }
+        }
+        None => crate::tool_quotas::clear_limits(&mission_work_dir),
+    }
+    let provenance = crate::provenance::TurnContext {
+        mission_id: mission_id.to_string(),
+        backend: backend_id.clone(),
+        model: config.default_model.clone(),
+    };
+    if let Err(e) = provenance.write(&mission_work_dir) {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to write commit provenance context");
+    }
+    let agent_bundles = resolve_agent_tool_bundles(&library, effective_agent.as_deref()).await;
+    if let Err(e) =
+        crate::tools::bundles::write_agent_bundles(&mission_work_dir, agent_bundles.as_deref())
+    {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to write agent tool bundles");
+    }
+    let agent_aliases = resolve_agent_tool_aliases(&library, effective_agent.as_deref()).await;
+    if let Err(e) = crate::tools::alias::write_agent_aliases(&mission_work_dir, &agent_aliases) {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to write agent tool aliases");
+    }
+    if let Err(e) = crate::tools::audit::write_audit_mode(&mission_work_dir, audit) {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to write audit mode marker");
+    }
+    let host_exec = super::host_exec::is_enabled(&config.working_dir, mission_id);
+    if let Err(e) = crate::tools::host_access::write_host_exec_enabled(&mission_work_dir, host_exec)
+    {
+        tracing::warn!(mission_id = %mission_id, error = %e, "Failed to write host exec marker");
+    }
+    if workspace.workspace_type == WorkspaceType::Host {
+        let tool_user = server_settings
+            .get(crate::tools::host_access::TOOL_USER_SETTING)
+            .map(String::as_str);
+        if let Err(e) = crate::tools::host_access::grant_mission_dir(&mission_work_dir, tool_user) {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to hand the mission directory to the tool user");
+        }
+    }
+    let dependency_audit = crate::dependency_audit::audit_enabled_for(&workspace.env_vars);
+    if dependency_audit {
+        if let Err(e) = crate::dependency_audit::record_baseline(&mission_work_dir) {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to record dependency baseline");
+        }
+    }
+    let egress_enforced = workspace.workspace_type == WorkspaceType::Container
+        && workspace
+            .egress_policy
+            .as_ref()
+            .is_some_and(|p| p.is_enforced());
+    if egress_enforced {
+        // Entries left over from earlier work in this workspace aren't ours to report.
+        let stale = crate::egress::take_violations(&workspace.path);
+        if !stale.is_empty() {
+            tracing::debug!(mission_id = %mission_id, count = stale.len(), "Discarded stale egress violations");
+        }
+    }
+    // Hooks run from the library, not the copy the agent can rewrite
+    let hooks = match library.read().await.clone() {
+        Some(lib) => match lib.list_hook_files().await {
+            Ok(files) => crate::hooks::Hooks::from_files(files),
+            Err(e) => {
+                return AgentResult::failure(format!("Failed to load hooks: {}", e), 0)
+                    .with_chat_options(chat_options);
+            }
+        },
+        None => crate::hooks::Hooks::default(),
+    };
+    let pre_hooks = crate::hooks::run_hooks(
+        &hooks,
+        &mission_work_dir,
+        HookEvent::PreMission,
+        None,
+        serde_json::json!({
+            "mission_id": mission_id,
+            "workspace_id": workspace_id,
+            "backend": backend_id,
+            "agent": effective_agent,
+            "turn": turn_count + 1,
+            "user_message": user_message,
+        }),
+    )
+    .await;
+    emit_hook_annotations(&events_tx, mission_id, HookEvent::PreMission, &pre_hooks);
+    if let Some(reason) = pre_hooks.blocked {
+        return AgentResult::failure(format!("Blocked by pre_mission hook: {}", reason), 0)
+            .with_chat_options(chat_options);
+    }
+
     let result = match backend_id.as_str() {
         "claudecode" => {
             // Track the effective message and session used for the most recent
             // attempt, so account rotation uses the right context (e.g. after
             // session corruption recovery rebuilds the message).
-            let mut effective_msg = user_message.clone();
+            // A fresh session after compaction starts from the compacted history.
+            let fresh_session = !matches!(
+                session_id.as_deref(),
+                Some(sid) if claude_session_initiated(&mission_work_dir, sid)
+            );
+            let mut effective_msg = fresh_session
+                .then(|| {
+                    super::mission_compact::fresh_session_message(
+                        &history,
+                        &user_message,
+                        config.context.max_history_total_chars,
+                    )
+                })
+                .flatten()
+                .unwrap_or_else(|| user_message.clone());
             let mut effective_sid = session_id.clone();
 
             let mut result = run_claudecode_turn(
@@ -1840,6 +2191,7 @@ async fn run_mission_turn(
                 &workspace,
                 &mission_work_dir,
                 &convo,
+                &user_message,
                 config.default_model.as_deref(),
                 model_effort.as_deref(),
                 effective_agent.as_deref(),
@@ -1847,6 +2199,7 @@ async fn run_mission_turn(
                 events_tx.clone(),
                 cancel,
                 &config.working_dir,
+                session_id.as_deref(),
             )
             .await
         }
@@ -1932,6 +2285,7 @@ async fn run_mission_turn(
                     &workspace,
                     &mission_work_dir,
                     &convo,
+                    &user_message,
                     config.default_model.as_deref(),
                     model_effort.as_deref(),
                     effective_agent.as_deref(),
@@ -1986,6 +2340,7 @@ async fn run_mission_turn(
                         &workspace,
                         &mission_work_dir,
                         &convo,
+                        &user_message,
                         config.default_model.as_deref(),
                         model_effort.as_deref(),
                         effective_agent.as_deref(),
@@ -2021,13 +2376,47 @@ async fn run_mission_turn(
                 }
             }
         }
-        _ => {
-            // Don't send Error event - the failure will be emitted as an AssistantMessage
-            // with success=false by the caller (control.rs), avoiding duplicate messages.
-            AgentResult::failure(format!("Unsupported backend: {}", backend_id), 0)
-                .with_terminal_reason(TerminalReason::LlmError)
+        other => {
+            let plugin = backends.read().await.plugin(other);
+            match plugin {
+                Some(backend) => {
+                    // The global default model belongs to the built-in backends.
+                    run_plugin_turn(
+                        backend,
+                        &mission_work_dir,
+                        &convo,
+                        &user_message,
+                        model_override.as_deref(),
+                        effective_agent.as_deref(),
+                        mission_id,
+                        events_tx.clone(),
+                        cancel.clone(),
+                        session_id.as_deref(),
+                    )
+                    .await
+                }
+                None => {
+                    // Don't send Error event - the failure will be emitted as an AssistantMessage
+                    // with success=false by the caller (control.rs), avoiding duplicate messages.
+                    AgentResult::failure(format!("Unsupported backend: {}", backend_id), 0)
+                        .with_terminal_reason(TerminalReason::LlmError)
+                }
+            }
         }
     };
+    let result = result.with_chat_options(chat_options);
+    if tool_pruning {
+        if let Some(stats) = crate::tool_pruning::take_stats(&mission_work_dir) {
+            tracing::info!(
+                mission_id = %mission_id,
+                total_tools = stats.total_tools,
+                selected_tools = stats.selected_tools,
+                saved_bytes = stats.saved_bytes(),
+                "Tool schemas pruned for turn"
+            );
+            crate::tool_pruning::record_turn_stats(&stats);
+        }
+    }
 
     tracing::info!(
         mission_id = %mission_id,
@@ -2038,6 +2427,40 @@ async fn run_mission_turn(
         "Mission turn finished"
     );
 
+    if dependency_audit {
+        report_added_dependencies(&mission_work_dir, &workspace, mission_id, &events_tx).await;
+    }
+    if egress_enforced {
+        report_egress_violations(&workspace, mission_id, &events_tx);
+    }
+    report_masked_pii(&mission_work_dir, mission_id, &events_tx);
+    index_agent_commits(
+        &config.working_dir,
+        &mission_work_dir,
+        mission_id,
+        result.model_used.as_deref(),
+    );
+
+    let post_hooks = crate::hooks::run_hooks(
+        &hooks,
+        &mission_work_dir,
+        HookEvent::PostMission,
+        None,
+        serde_json::json!({
+            "mission_id": mission_id,
+            "workspace_id": workspace_id,
+            "backend": backend_id,
+            "agent": effective_agent,
+            "turn": turn_count + 1,
+            "success": result.success,
+            "output": result.output,
+            "cost_cents": result.cost_cents,
+            "terminal_reason": result.terminal_reason,
+        }),
+    )
+    .await;
+    emit_hook_annotations(&events_tx, mission_id, HookEvent::PostMission, &post_hooks);
+
     // Clean up old debug files to prevent unbounded disk/memory growth
     // Keep last 20 debug files (each ~17KB) = ~340KB retained
     if let Err(e) = cleanup_old_debug_files(&mission_work_dir, 20) {
@@ -2051,6 +2474,50 @@ async fn run_mission_turn(
     result
 }
 
+/// Tool patterns from the library agent's frontmatter (`tools:`), if any.
+async fn resolve_agent_tool_patterns(
+    library: &SharedLibrary,
+    agent: Option<&str>,
+) -> HashMap<String, bool> {
+    let (Some(lib), Some(agent)) = (library.read().await.clone(), agent) else {
+        return HashMap::new();
+    };
+    lib.get_library_agent(agent)
+        .await
+        .map(|a| a.tools)
+        .unwrap_or_default()
+}
+
+/// The library agent's `tool_bundles`, or None when it doesn't restrict them.
+async fn resolve_agent_tool_bundles(
+    library: &SharedLibrary,
+    agent: Option<&str>,
+) -> Option<Vec<String>> {
+    let (Some(lib), Some(agent)) = (library.read().await.clone(), agent) else {
+        return None;
+    };
+    let bundles = lib.get_library_agent(agent).await.ok()?.tool_bundles;
+    let unknown = crate::tools::bundles::unknown_bundles(&bundles);
+    if !unknown.is_empty() {
+        tracing::warn!(agent = %agent, unknown = ?unknown, "Agent lists unknown tool bundles");
+    }
+    (!bundles.is_empty()).then_some(bundles)
+}
+
+/// The library agent's `tool_aliases`.
+async fn resolve_agent_tool_aliases(
+    library: &SharedLibrary,
+    agent: Option<&str>,
+) -> Vec<crate::tools::alias::ToolAlias> {
+    let (Some(lib), Some(agent)) = (library.read().await.clone(), agent) else {
+        return Vec::new();
+    };
+    lib.get_library_agent(agent)
+        .await
+        .map(|a| a.tool_aliases)
+        .unwrap_or_default()
+}
+
 fn read_backend_configs() -> Option<Vec<serde_json::Value>> {
     let home = std::env::var("HOME").ok()?;
 
@@ -2592,7 +3059,7 @@ pub fn run_claudecode_turn<'a>(
             }
         };
 
-        let workspace_exec = WorkspaceExec::new(workspace.clone());
+        let workspace_exec = WorkspaceExec::new(workspace.clone()).with_mission(mission_id);
         let cli_path =
             match ensure_claudecode_cli_available(&workspace_exec, work_dir, &cli_path).await {
                 Ok(path) => path,
@@ -2645,15 +3112,44 @@ pub fn run_claudecode_turn<'a>(
             "--include-partial-messages".to_string(),
         ];
 
-        // Add permission mode if a slash command triggered a special mode
-        if let Some(mode) = permission_mode {
-            args.push("--permission-mode".to_string());
-            args.push(mode.to_string());
+        // Permission mode and tool lists come from a slash command or the
+        // config profile. Without a profile mode all permission checks are
+        // skipped; IS_SANDBOX=1 is set in env vars below to allow
+        // --dangerously-skip-permissions even when running as root.
+        let mut permissions = read_claudecode_permissions(work_dir);
+        // Built-in web search runs inside the CLI, so an exhausted quota can
+        // only be enforced by denying the tool for the next turns.
+        if crate::tool_quotas::exhausted(work_dir, crate::tool_quotas::Quota::WebSearch) {
+            let permissions = permissions.get_or_insert_with(Default::default);
+            if !permissions.deny.iter().any(|tool| tool == "WebSearch") {
+                permissions.deny.push("WebSearch".to_string());
+            }
+        }
+        // Audit missions must not change the workspace; only the workspace
+        // MCP's read-only tools and the CLI's reading tools remain.
+        if crate::tools::audit::audit_mode(work_dir) {
+            let permissions = permissions.get_or_insert_with(Default::default);
+            for tool in super::mission_audit::CLAUDECODE_DENIED_TOOLS {
+                if !permissions.deny.iter().any(|t| t == tool) {
+                    permissions.deny.push(tool.to_string());
+                }
+            }
+        }
+        let (permission_args, permission_warnings) = claudecode_permission_args(
+            permissions.as_ref(),
+            permission_mode,
+            &claudecode_mcp_server_names(work_dir),
+        );
+        args.extend(permission_args);
+        if !is_continuation {
+            for message in permission_warnings {
+                tracing::warn!(mission_id = %mission_id, "{}", message);
+                let _ = events_tx.send(AgentEvent::ConfigWarning {
+                    message,
+                    mission_id,
+                });
+            }
         }
-
-        // Skip all permission checks. IS_SANDBOX=1 is set in env vars below
-        // to allow --dangerously-skip-permissions even when running as root.
-        args.push("--dangerously-skip-permissions".to_string());
 
         // Ensure per-workspace Claude settings are loaded (Claude CLI may not auto-load .claude in --print mode).
         //
@@ -2690,10 +3186,7 @@ pub fn run_claudecode_turn<'a>(
         // The marker file contains the session ID to prevent cross-mission interference
         // when workspaces are shared (e.g., fallback to workspace-wide directory).
         let session_marker = work_dir.join(".claude-session-initiated");
-        let session_was_initiated = session_marker.exists()
-            && std::fs::read_to_string(&session_marker)
-                .map(|content| content.trim() == session_id)
-                .unwrap_or(false);
+        let session_was_initiated = claude_session_initiated(work_dir, &session_id);
 
         // Determine if we should use --resume:
         // We can only resume if the session was actually initiated at THIS work_dir
@@ -3033,13 +3526,17 @@ pub fn run_claudecode_turn<'a>(
         );
         let startup_deadline = Instant::now() + startup_timeout;
         let mut idle_deadline = Instant::now() + idle_timeout;
+        // Time to the first model event, for streaming health metrics
+        let turn_start = Instant::now();
+        let mut first_event_ms: Option<u64> = None;
 
         // Process events until completion or cancellation
         loop {
             tokio::select! {
                 _ = cancel.cancelled() => {
                     tracing::info!(mission_id = %mission_id, "Claude Code execution cancelled, killing process");
-                    // Kill the process to stop consuming API resources
+                    // Kill the process tree to stop consuming API resources
+                    kill_backend_processes(pty.process_id(), mission_id, &events_tx).await;
                     pty.kill();
                     reader_handle.abort();
                     return AgentResult::failure("Cancelled".to_string(), 0)
@@ -3123,6 +3620,8 @@ pub fn run_claudecode_turn<'a>(
 
                     if !matches!(claude_event, ClaudeEvent::System(_)) {
                         saw_non_init_event = true;
+                        first_event_ms
+                            .get_or_insert_with(|| turn_start.elapsed().as_millis() as u64);
                     }
 
                             match claude_event {
@@ -3267,6 +3766,13 @@ pub fn run_claudecode_turn<'a>(
                                                     args: input.clone(),
                                                     mission_id: Some(mission_id),
                                                 });
+                                                if name == "WebSearch" {
+                                                    crate::tool_quotas::record(
+                                                        work_dir,
+                                                        crate::tool_quotas::Quota::WebSearch,
+                                                        1,
+                                                    );
+                                                }
 
                                                 if name == "question" || name == "AskUserQuestion" || name.starts_with("ui_") {
                                                     if let Some(ref hub) = tool_hub {
@@ -3482,6 +3988,19 @@ pub fn run_claudecode_turn<'a>(
         let model_for_cost = preferred_model_for_cost(model, observed_model.as_deref());
         let (cost_cents, cost_source) =
             resolve_cost_cents_and_source(actual_cost_cents, model_for_cost, &usage);
+        crate::stream_metrics::record(
+            "claudecode",
+            observed_model
+                .as_deref()
+                .or(model_for_cost)
+                .unwrap_or("default"),
+            crate::stream_metrics::StreamSample {
+                ttft_ms: first_event_ms,
+                output_tokens: total_output_tokens,
+                duration_ms: turn_start.elapsed().as_millis() as u64,
+                errored: had_error,
+            },
+        );
 
         // If no final result from Assistant or Result events, use accumulated text buffer
         // This handles plan mode and other cases where text is streamed incrementally
@@ -3580,7 +4099,7 @@ fn workspace_path_for_env(
     host_path: &std::path::Path,
 ) -> std::path::PathBuf {
     if workspace.workspace_type == workspace::WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(workspace)
+        && workspace::use_container_rootfs(workspace)
     {
         if let Ok(rel) = host_path.strip_prefix(&workspace.path) {
             return std::path::PathBuf::from("/").join(rel);
@@ -3589,7 +4108,7 @@ fn workspace_path_for_env(
     host_path.to_path_buf()
 }
 
-fn strip_ansi_codes(input: &str) -> Cow<'_, str> {
+pub(crate) fn strip_ansi_codes(input: &str) -> Cow<'_, str> {
     let bytes = input.as_bytes();
     if !bytes
         .iter()
@@ -3752,7 +4271,7 @@ fn install_opencode_serve_port_wrapper(
     // a fresh tmpfs over /tmp, hiding anything we write to the container rootfs.
     let (wrapper_dir_host, wrapper_dir_env) = if workspace.workspace_type
         == WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(workspace)
+        && workspace::use_container_rootfs(workspace)
     {
         (
             workspace.path.join("root").join(".sandboxed-sh-bin"),
@@ -3827,7 +4346,7 @@ exec "$REAL_OPENCODE" "$@"
 
 fn prepend_opencode_bin_to_path(env: &mut HashMap<String, String>, workspace: &Workspace) {
     let home = if workspace.workspace_type == WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(workspace)
+        && workspace::use_container_rootfs(workspace)
     {
         "/root".to_string()
     } else {
@@ -5191,6 +5710,26 @@ fn ensure_opencode_provider_for_model(opencode_config_dir: &std::path::Path, mod
     }
 }
 
+/// Attribute requests of the builtin (proxy) provider to `mission_id`, so the
+/// proxy can record them for the mission's debug bundle.
+fn set_builtin_provider_mission(opencode_config_dir: &std::path::Path, mission_id: Uuid) {
+    let (opencode_path, mut root) = load_opencode_json(opencode_config_dir);
+    let Some(options) = root
+        .pointer_mut("/provider/builtin/options")
+        .and_then(|o| o.as_object_mut())
+    else {
+        return;
+    };
+    let mission_header = serde_json::json!({
+        crate::web_proxy::MISSION_HEADER: mission_id.to_string()
+    });
+    if options.get("headers") == Some(&mission_header) {
+        return;
+    }
+    options.insert("headers".to_string(), mission_header);
+    save_json_warn(&opencode_path, &root, "OpenCode provider config");
+}
+
 /// Scan the oh-my-opencode config for all model references (top-level, agents,
 /// categories) and ensure each provider has a definition in `opencode.json`.
 fn ensure_opencode_providers_for_omo_config(opencode_config_dir: &std::path::Path) {
@@ -5250,7 +5789,7 @@ fn ensure_opencode_providers_for_omo_config(opencode_config_dir: &std::path::Pat
 
 fn workspace_abs_path(workspace: &Workspace, path: &std::path::Path) -> std::path::PathBuf {
     if workspace.workspace_type == WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(workspace)
+        && workspace::use_container_rootfs(workspace)
     {
         if let Ok(relative) = path.strip_prefix(std::path::Path::new("/")) {
             return workspace.path.join(relative);
@@ -5366,7 +5905,7 @@ fn patch_oh_my_opencode_port_override(workspace: &Workspace) -> bool {
 
 fn opencode_storage_roots(workspace: &Workspace) -> Vec<std::path::PathBuf> {
     if workspace.workspace_type == WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(workspace)
+        && workspace::use_container_rootfs(workspace)
     {
         let mut roots = Vec::new();
 
@@ -5480,7 +6019,7 @@ fn host_opencode_provider_auth_dir() -> Option<std::path::PathBuf> {
 
 fn workspace_opencode_auth_path(workspace: &Workspace) -> Option<std::path::PathBuf> {
     if workspace.workspace_type == WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(workspace)
+        && workspace::use_container_rootfs(workspace)
     {
         return Some(
             workspace
@@ -5497,7 +6036,7 @@ fn workspace_opencode_auth_path(workspace: &Workspace) -> Option<std::path::Path
 
 fn workspace_opencode_provider_auth_dir(workspace: &Workspace) -> Option<std::path::PathBuf> {
     if workspace.workspace_type == WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(workspace)
+        && workspace::use_container_rootfs(workspace)
     {
         return Some(workspace.path.join("root").join(".opencode").join("auth"));
     }
@@ -7082,7 +7621,7 @@ async fn opencode_binary_available(workspace_exec: &WorkspaceExec, cwd: &std::pa
         return true;
     }
     if workspace_exec.workspace.workspace_type == WorkspaceType::Container
-        && workspace::use_nspawn_for_workspace(&workspace_exec.workspace)
+        && workspace::use_container_rootfs(&workspace_exec.workspace)
     {
         if command_available(workspace_exec, cwd, "/root/.opencode/bin/opencode").await {
             return true;
@@ -7203,6 +7742,7 @@ pub async fn check_backend_prerequisites(
     workspace: &Workspace,
     backend_id: &str,
     cli_path: Option<&str>,
+    backends: &BackendRegistry,
 ) -> BackendPreflightResult {
     let workspace_exec = WorkspaceExec::new(workspace.clone());
     let cwd = &workspace.path;
@@ -7221,6 +7761,15 @@ pub async fn check_backend_prerequisites(
             let cli = cli_path.unwrap_or("amp");
             check_amp_prerequisites(&workspace_exec, cwd, cli).await
         }
+        // Custom backends manage their own dependencies.
+        id if backends.plugin(id).is_some() => BackendPreflightResult {
+            backend_id: backend_id.to_string(),
+            available: true,
+            cli_available: true,
+            auto_install_possible: false,
+            missing_dependencies: vec![],
+            message: None,
+        },
         _ => BackendPreflightResult {
             backend_id: backend_id.to_string(),
             available: false,
@@ -7424,18 +7973,24 @@ async fn check_amp_prerequisites(
 ///
 /// This uses the `oh-my-opencode run` CLI which creates an embedded OpenCode server,
 /// enabling per-workspace isolation without network issues.
+///
+/// Turns continue the mission's OpenCode session and send only `session_message`.
+/// `message` (with conversation context) primes a fresh session when there is
+/// none yet or the previous one was deleted or expired.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_opencode_turn(
     workspace: &Workspace,
     work_dir: &std::path::Path,
     message: &str,
+    session_message: &str,
     model: Option<&str>,
-    _model_effort: Option<&str>,
+    model_effort: Option<&str>,
     agent: Option<&str>,
     mission_id: Uuid,
     events_tx: broadcast::Sender<AgentEvent>,
     cancel: CancellationToken,
     app_working_dir: &std::path::Path,
+    mission_session_id: Option<&str>,
 ) -> AgentResult {
     use super::ai_providers::{
         ensure_anthropic_oauth_token_valid, ensure_google_oauth_token_valid,
@@ -7447,7 +8002,7 @@ pub async fn run_opencode_turn(
 
     // Determine CLI runner: prefer backend config, then env var, then try bunx/npx
     // We use 'bunx oh-my-opencode run' or 'npx oh-my-opencode run' for per-workspace execution.
-    let workspace_exec = WorkspaceExec::new(workspace.clone());
+    let workspace_exec = WorkspaceExec::new(workspace.clone()).with_mission(mission_id);
     if let Err(err) = ensure_opencode_cli_available(&workspace_exec, work_dir).await {
         tracing::error!("{}", err);
         return AgentResult::failure(err, 0).with_terminal_reason(TerminalReason::LlmError);
@@ -7668,6 +8223,7 @@ pub async fn run_opencode_turn(
         }
     }
     ensure_opencode_providers_for_omo_config(&opencode_config_dir_host);
+    set_builtin_provider_mission(&opencode_config_dir_host, mission_id);
     if needs_google {
         if let Some(project_id) = detect_google_project_id() {
             ensure_opencode_google_project_id(&opencode_config_dir_host, &project_id);
@@ -7714,6 +8270,28 @@ pub async fn run_opencode_turn(
     // For v3+ compiled binaries, the wrapper script handles port override instead.
     let _ = patch_oh_my_opencode_port_override(workspace);
 
+    // Continue the mission's OpenCode session while it is still usable.
+    let reused_session = backend_session_for(work_dir, OPENCODE_SESSION_MARKER, mission_session_id)
+        .filter(|sid| match opencode_session_unusable(workspace, sid) {
+            Some(reason) => {
+                tracing::info!(
+                    mission_id = %mission_id,
+                    opencode_session = %sid,
+                    reason,
+                    "OpenCode session cannot be continued; starting a fresh one"
+                );
+                let _ = std::fs::remove_file(work_dir.join(OPENCODE_SESSION_MARKER));
+                false
+            }
+            None => true,
+        });
+    let message_for_fresh_session = message;
+    let message = if reused_session.is_some() {
+        session_message
+    } else {
+        message
+    };
+
     // Build CLI arguments for oh-my-opencode run
     // The 'run' command takes a prompt and executes it with completion detection
     // Arguments: bunx oh-my-opencode run [--agent <agent>] [--directory <path>] <message>
@@ -7765,6 +8343,11 @@ pub async fn run_opencode_turn(
     shell_cmd.push_str(" --directory ");
     shell_cmd.push_str(&shell_escape(&work_dir_arg));
 
+    if let Some(sid) = reused_session.as_deref() {
+        shell_cmd.push_str(" --session-id ");
+        shell_cmd.push_str(&shell_escape(sid));
+    }
+
     // Read message from file via command substitution to guarantee a single argument
     shell_cmd.push_str(" \"$(cat ");
     shell_cmd.push_str(&shell_escape(&prompt_file_arg));
@@ -8057,12 +8640,16 @@ pub async fn run_opencode_turn(
                 }
 
                 loop {
-                    if sse_cancel.is_cancelled() {
-                        let _ = child.kill().await;
-                        return;
-                    }
                     line.clear();
-                    match reader.read_line(&mut line).await {
+                    // Select on cancellation so an idle stream doesn't keep curl alive.
+                    let read = tokio::select! {
+                        _ = sse_cancel.cancelled() => {
+                            let _ = child.kill().await;
+                            return;
+                        }
+                        read = reader.read_line(&mut line) => read,
+                    };
+                    match read {
                         Ok(0) => break,
                         Ok(_) => {
                             let trimmed = line.trim_end();
@@ -8434,6 +9021,7 @@ pub async fn run_opencode_turn(
         tokio::select! {
             _ = cancel.cancelled() => {
                 tracing::info!(mission_id = %mission_id, "OpenCode execution cancelled, killing process");
+                kill_backend_processes(child.id(), mission_id, &events_tx).await;
                 let _ = child.kill().await;
                 // Await background tasks so in-flight mutex writes complete
                 // before we return.  Use the same teardown discipline as the
@@ -8447,9 +9035,23 @@ pub async fn run_opencode_turn(
                     }
                 }
                 sse_cancel.cancel();
-                if let Some(handle) = sse_handle {
-                    handle.abort();
-                    let _ = handle.await;
+                if let Some(mut handle) = sse_handle {
+                    // Give the SSE task a moment to kill curl before aborting it.
+                    if tokio::time::timeout(std::time::Duration::from_secs(2), &mut handle)
+                        .await
+                        .is_err()
+                    {
+                        handle.abort();
+                        let _ = handle.await;
+                    }
+                }
+                // Keep the session so resuming the mission continues it.
+                if let Some(sid) = session_id_capture
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .as_deref()
+                {
+                    record_backend_session(work_dir, OPENCODE_SESSION_MARKER, mission_session_id, sid);
                 }
                 return AgentResult::failure("Cancelled".to_string(), 0)
                     .with_terminal_reason(TerminalReason::Cancelled);
@@ -8905,11 +9507,17 @@ pub async fn run_opencode_turn(
         };
 
     sse_cancel.cancel();
-    if let Some(handle) = sse_handle {
-        handle.abort();
-        // Await the abort so the SSE task finishes any in-flight writes to
+    if let Some(mut handle) = sse_handle {
+        // Let the SSE task kill curl on cancellation; abort it if it doesn't
+        // finish in time. Either way it has finished any in-flight writes to
         // sse_text_buffer before we read it in the fallback chain below.
-        let _ = handle.await;
+        if tokio::time::timeout(std::time::Duration::from_secs(2), &mut handle)
+            .await
+            .is_err()
+        {
+            handle.abort();
+            let _ = handle.await;
+        }
     }
 
     let sse_error = sse_error_message
@@ -8976,6 +9584,9 @@ pub async fn run_opencode_turn(
         .unwrap_or_else(|e| e.into_inner())
         .clone();
     let session_id = session_id.or_else(|| extract_opencode_session_id(&final_result));
+    if let Some(sid) = session_id.as_deref() {
+        record_backend_session(work_dir, OPENCODE_SESSION_MARKER, mission_session_id, sid);
+    }
     let stored_message = session_id
         .as_deref()
         .and_then(|id| load_latest_opencode_assistant_message(workspace, id));
@@ -9155,6 +9766,37 @@ pub async fn run_opencode_turn(
         "OpenCode CLI execution completed"
     );
 
+    // The session may have been deleted between the health check and the run;
+    // retry once on a fresh session primed with the conversation context.
+    if had_error
+        && reused_session.is_some()
+        && is_opencode_session_missing_error(&final_result)
+        && std::fs::remove_file(work_dir.join(OPENCODE_SESSION_MARKER)).is_ok()
+    {
+        tracing::warn!(
+            mission_id = %mission_id,
+            opencode_session = ?reused_session,
+            error = %final_result,
+            "OpenCode session could not be continued; retrying on a fresh session"
+        );
+        let _ = std::fs::remove_file(&prompt_file_host);
+        return Box::pin(run_opencode_turn(
+            workspace,
+            work_dir,
+            message_for_fresh_session,
+            session_message,
+            model,
+            model_effort,
+            agent,
+            mission_id,
+            events_tx,
+            cancel,
+            app_working_dir,
+            mission_session_id,
+        ))
+        .await;
+    }
+
     let mut result = if had_error {
         // Use RateLimited terminal reason when rate limit was detected
         let reason = if rate_limit_detected.load(std::sync::atomic::Ordering::SeqCst) {
@@ -9236,7 +9878,7 @@ pub async fn run_amp_turn(
     use std::collections::HashMap;
     use tokio::io::{AsyncBufReadExt, BufReader};
 
-    let workspace_exec = WorkspaceExec::new(workspace.clone());
+    let workspace_exec = WorkspaceExec::new(workspace.clone()).with_mission(mission_id);
 
     // Check if amp CLI is available
     if !command_available(&workspace_exec, work_dir, "amp").await {
@@ -9529,6 +10171,7 @@ pub async fn run_amp_turn(
         tokio::select! {
             _ = cancel.cancelled() => {
                 tracing::info!(mission_id = %mission_id, "Amp execution cancelled, killing process");
+                kill_backend_processes(child.id(), mission_id, &events_tx).await;
                 let _ = child.kill().await;
                 if let Some(handle) = stderr_handle {
                     handle.abort();
@@ -9946,7 +10589,7 @@ pub async fn run_amp_turn(
 }
 
 /// Compact info about a running mission (for API responses).
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RunningMissionInfo {
     pub mission_id: Uuid,
     pub state: String,
@@ -9992,6 +10635,7 @@ pub async fn run_codex_turn(
     workspace: &Workspace,
     mission_work_dir: &std::path::Path,
     user_message: &str,
+    thread_message: &str,
     model: Option<&str>,
     model_effort: Option<&str>,
     agent: Option<&str>,
@@ -9999,7 +10643,7 @@ pub async fn run_codex_turn(
     events_tx: broadcast::Sender<AgentEvent>,
     cancel: CancellationToken,
     app_working_dir: &std::path::Path,
-    _session_id: Option<&str>,
+    session_id: Option<&str>,
     override_api_key: Option<&str>,
 ) -> AgentResult {
     use crate::backend::codex::CodexBackend;
@@ -10007,7 +10651,10 @@ pub async fn run_codex_turn(
     use crate::backend::{Backend, SessionConfig};
 
     let model = model.map(str::trim).filter(|m| !m.is_empty());
-    let model_effort = model_effort.map(str::trim).filter(|m| !m.is_empty());
+    let model_effort = model_effort
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .and_then(crate::chat_options::codex_reasoning_effort);
     let resolved_model: Option<String> = model.map(|m| m.to_string());
 
     tracing::info!(
@@ -10044,7 +10691,7 @@ pub async fn run_codex_turn(
         .with_terminal_reason(TerminalReason::LlmError);
     }
 
-    let workspace_exec = WorkspaceExec::new(workspace.clone());
+    let workspace_exec = WorkspaceExec::new(workspace.clone()).with_mission(mission_id);
     let cli_path = get_backend_string_setting("codex", "cli_path")
         .or_else(|| std::env::var("CODEX_CLI_PATH").ok())
         .unwrap_or_else(|| "codex".to_string());
@@ -10069,32 +10716,49 @@ pub async fn run_codex_turn(
     let codex_config = crate::backend::codex::client::CodexConfig {
         cli_path,
         model_effort: model_effort.map(|s| s.to_string()),
+        read_only: crate::tools::audit::audit_mode(mission_work_dir),
         ..Default::default()
     };
 
     // Create Codex backend
     let backend = CodexBackend::with_config_and_workspace(codex_config, workspace_exec);
 
-    // Create session
-    let session = match backend
-        .create_session(SessionConfig {
-            directory: mission_work_dir.to_string_lossy().to_string(),
-            title: Some(format!("Mission {}", mission_id)),
-            model: resolved_model.clone(),
-            agent: agent.map(|s| s.to_string()),
-        })
-        .await
-    {
-        Ok(s) => s,
-        Err(e) => {
-            tracing::error!("Failed to create Codex session: {}", e);
-            return AgentResult::failure(format!("Failed to start Codex: {}", e), 0)
-                .with_terminal_reason(TerminalReason::LlmError);
+    // Continue the mission's Codex thread if an earlier turn started one;
+    // the thread already holds the conversation, so only the new message is sent.
+    let thread_id = backend_session_for(mission_work_dir, CODEX_THREAD_MARKER, session_id);
+    let session_config = SessionConfig {
+        directory: mission_work_dir.to_string_lossy().to_string(),
+        title: Some(format!("Mission {}", mission_id)),
+        model: resolved_model.clone(),
+        agent: agent.map(|s| s.to_string()),
+    };
+    let (session, message) = match thread_id.as_deref() {
+        Some(thread_id) => {
+            tracing::info!(
+                mission_id = %mission_id,
+                thread_id = %thread_id,
+                "Resuming existing Codex thread"
+            );
+            (
+                backend.resume_session(session_config, thread_id).await,
+                thread_message,
+            )
         }
+        None => match backend.create_session(session_config).await {
+            Ok(s) => (s, user_message),
+            Err(e) => {
+                tracing::error!("Failed to create Codex session: {}", e);
+                return AgentResult::failure(format!("Failed to start Codex: {}", e), 0)
+                    .with_terminal_reason(TerminalReason::LlmError);
+            }
+        },
     };
 
     // Send message streaming
-    let (mut event_rx, _handle) = match backend.send_message_streaming(&session, user_message).await
+    let backend_cancel = cancel.child_token();
+    let (mut event_rx, _handle) = match backend
+        .send_message_streaming(&session, message, backend_cancel.clone())
+        .await
     {
         Ok(result) => result,
         Err(e) => {
@@ -10105,6 +10769,8 @@ pub async fn run_codex_turn(
     };
 
     // Process events until completion or cancellation
+    let turn_start = Instant::now();
+    let mut first_event_ms: Option<u64> = None;
     let mut assistant_message = String::new();
     let mut success = false;
     let mut error_message: Option<String> = None;
@@ -10120,11 +10786,31 @@ pub async fn run_codex_turn(
         tokio::select! {
             _ = cancel.cancelled() => {
                 tracing::info!("Codex turn cancelled for mission {}", mission_id);
-                // Note: Codex process will be cleaned up automatically when the event stream task ends
+                // Keep the thread so resuming the mission continues it.
+                if let Some(thread_id) = backend.thread_id(&session.id).await {
+                    record_backend_session(mission_work_dir, CODEX_THREAD_MARKER, session_id, &thread_id);
+                }
+                // The backend kills the Codex process tree and reports it before closing the stream.
+                while let Ok(Some(event)) =
+                    tokio::time::timeout(Duration::from_secs(10), event_rx.recv()).await
+                {
+                    if let ExecutionEvent::KilledProcesses { report } = event {
+                        emit_killed_processes(report, mission_id, &events_tx);
+                        break;
+                    }
+                }
                 return AgentResult::failure("Mission cancelled".to_string(), 0)
                     .with_terminal_reason(TerminalReason::Cancelled);
             }
             Some(event) = event_rx.recv() => {
+                if matches!(
+                    event,
+                    ExecutionEvent::TextDelta { .. }
+                        | ExecutionEvent::Thinking { .. }
+                        | ExecutionEvent::ToolCall { .. }
+                ) {
+                    first_event_ms.get_or_insert_with(|| turn_start.elapsed().as_millis() as u64);
+                }
                 match event {
                     ExecutionEvent::TextDelta { content } => {
                         // For Codex backend, TextDelta is handled as the latest snapshot for
@@ -10179,6 +10865,9 @@ pub async fn run_codex_turn(
                         success = error_message.is_none();
                         break;
                     }
+                    ExecutionEvent::KilledProcesses { report } => {
+                        emit_killed_processes(report, mission_id, &events_tx);
+                    }
                 }
             }
             else => {
@@ -10188,6 +10877,59 @@ pub async fn run_codex_turn(
         }
     }
 
+    if let Some(thread_id) = backend.thread_id(&session.id).await {
+        record_backend_session(
+            mission_work_dir,
+            CODEX_THREAD_MARKER,
+            session_id,
+            &thread_id,
+        );
+    }
+
+    // A thread that can no longer be resumed (e.g. its rollout was deleted)
+    // is dropped and the turn retried once on a new thread with full context.
+    if thread_id.is_some()
+        && !success
+        && error_message
+            .as_deref()
+            .is_some_and(is_codex_thread_missing_error)
+        && std::fs::remove_file(mission_work_dir.join(CODEX_THREAD_MARKER)).is_ok()
+    {
+        tracing::warn!(
+            mission_id = %mission_id,
+            thread_id = ?thread_id,
+            error = ?error_message,
+            "Codex thread could not be resumed; retrying on a new thread"
+        );
+        return Box::pin(run_codex_turn(
+            workspace,
+            mission_work_dir,
+            user_message,
+            thread_message,
+            model,
+            model_effort,
+            agent,
+            mission_id,
+            events_tx,
+            cancel,
+            app_working_dir,
+            session_id,
+            override_api_key,
+        ))
+        .await;
+    }
+
+    crate::stream_metrics::record(
+        "codex",
+        resolved_model.as_deref().unwrap_or("default"),
+        crate::stream_metrics::StreamSample {
+            ttft_ms: first_event_ms,
+            output_tokens: total_output_tokens,
+            duration_ms: turn_start.elapsed().as_millis() as u64,
+            errored: !success,
+        },
+    );
+
     if !thinking_emitted {
         if let Some((thought, cleaned)) = extract_thought_line(&assistant_message) {
             let _ = events_tx.send(AgentEvent::Thinking {
@@ -10278,6 +11020,423 @@ Update it to the latest version (`npm install -g @openai/codex@latest`) and retr
     result
 }
 
+/// Marker file holding the session of a backend plugged in at startup.
+const PLUGIN_SESSION_MARKER: &str = ".backend-session";
+
+/// Run a turn on a backend plugged in at startup through the generic
+/// [`Backend`](crate::backend::Backend) interface. `TextDelta` events are
+/// appended to the reply. A backend that can resume sessions gets only the
+/// new message once its session exists; others get the full conversation.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_plugin_turn(
+    backend: Arc<dyn crate::backend::Backend>,
+    mission_work_dir: &std::path::Path,
+    full_conversation: &str,
+    new_message: &str,
+    model: Option<&str>,
+    agent: Option<&str>,
+    mission_id: Uuid,
+    events_tx: broadcast::Sender<AgentEvent>,
+    cancel: CancellationToken,
+    session_id: Option<&str>,
+) -> AgentResult {
+    use crate::backend::events::ExecutionEvent;
+    use crate::backend::{Session, SessionConfig};
+
+    let backend_id = backend.id().to_string();
+    let resume = backend.capabilities().session_resume;
+    let model = model.map(str::trim).filter(|m| !m.is_empty());
+    let session_config = SessionConfig {
+        directory: mission_work_dir.to_string_lossy().to_string(),
+        title: Some(format!("Mission {}", mission_id)),
+        model: model.map(|m| m.to_string()),
+        agent: agent.map(|s| s.to_string()),
+    };
+    let existing = resume
+        .then(|| backend_session_for(mission_work_dir, PLUGIN_SESSION_MARKER, session_id))
+        .flatten();
+    tracing::info!(
+        mission_id = %mission_id,
+        backend = %backend_id,
+        model = ?model,
+        resumed_session = ?existing,
+        "Starting turn on custom backend"
+    );
+    let (session, message) = match existing {
+        Some(id) => (
+            Session {
+                id,
+                directory: session_config.directory,
+                model: session_config.model,
+                agent: session_config.agent,
+            },
+            new_message,
+        ),
+        None => match backend.create_session(session_config).await {
+            Ok(session) => (session, full_conversation),
+            Err(e) => {
+                return AgentResult::failure(
+                    format!("Failed to start {}: {}", backend.name(), e),
+                    0,
+                )
+                .with_terminal_reason(TerminalReason::LlmError);
+            }
+        },
+    };
+    if resume {
+        record_backend_session(
+            mission_work_dir,
+            PLUGIN_SESSION_MARKER,
+            session_id,
+            &session.id,
+        );
+    }
+
+    let (mut event_rx, _handle) = match backend
+        .send_message_streaming(&session, message, cancel.child_token())
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            return AgentResult::failure(format!("{} execution failed: {}", backend.name(), e), 0)
+                .with_terminal_reason(TerminalReason::LlmError);
+        }
+    };
+
+    let turn_start = Instant::now();
+    let mut first_event_ms: Option<u64> = None;
+    let mut assistant_message = String::new();
+    let mut last_summary: Option<String> = None;
+    let mut error_message: Option<String> = None;
+    let mut completed = false;
+    let mut thinking_emitted = false;
+    let mut usage = crate::cost::TokenUsage::default();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                while let Ok(Some(event)) =
+                    tokio::time::timeout(Duration::from_secs(10), event_rx.recv()).await
+                {
+                    if let ExecutionEvent::KilledProcesses { report } = event {
+                        emit_killed_processes(report, mission_id, &events_tx);
+                        break;
+                    }
+                }
+                return AgentResult::failure("Mission cancelled".to_string(), 0)
+                    .with_terminal_reason(TerminalReason::Cancelled);
+            }
+            Some(event) = event_rx.recv() => {
+                if matches!(
+                    event,
+                    ExecutionEvent::TextDelta { .. }
+                        | ExecutionEvent::Thinking { .. }
+                        | ExecutionEvent::ToolCall { .. }
+                ) {
+                    first_event_ms.get_or_insert_with(|| turn_start.elapsed().as_millis() as u64);
+                }
+                match event {
+                    ExecutionEvent::TextDelta { content } => {
+                        assistant_message.push_str(&content);
+                        let _ = events_tx.send(AgentEvent::TextDelta {
+                            content: assistant_message.clone(),
+                            mission_id: Some(mission_id),
+                        });
+                    }
+                    ExecutionEvent::Thinking { content } => {
+                        thinking_emitted = true;
+                        let _ = events_tx.send(AgentEvent::Thinking {
+                            content,
+                            done: false,
+                            mission_id: Some(mission_id),
+                        });
+                    }
+                    ExecutionEvent::ToolCall { id, name, args } => {
+                        let _ = events_tx.send(AgentEvent::ToolCall {
+                            tool_call_id: id,
+                            name,
+                            args,
+                            mission_id: Some(mission_id),
+                        });
+                    }
+                    ExecutionEvent::ToolResult { id, name, result } => {
+                        let _ = events_tx.send(AgentEvent::ToolResult {
+                            tool_call_id: id,
+                            name,
+                            result,
+                            mission_id: Some(mission_id),
+                        });
+                    }
+                    ExecutionEvent::TurnSummary { content } => {
+                        if !content.trim().is_empty() {
+                            last_summary = Some(content);
+                        }
+                    }
+                    ExecutionEvent::Usage { input_tokens, output_tokens } => {
+                        usage.input_tokens = usage.input_tokens.saturating_add(input_tokens);
+                        usage.output_tokens = usage.output_tokens.saturating_add(output_tokens);
+                    }
+                    ExecutionEvent::Error { message } => {
+                        tracing::error!(backend = %backend_id, "Custom backend error: {}", message);
+                        error_message = Some(message);
+                    }
+                    ExecutionEvent::MessageComplete { .. } => {
+                        completed = true;
+                        break;
+                    }
+                    ExecutionEvent::KilledProcesses { report } => {
+                        emit_killed_processes(report, mission_id, &events_tx);
+                    }
+                }
+            }
+            else => break,
+        }
+    }
+
+    if thinking_emitted {
+        let _ = events_tx.send(AgentEvent::Thinking {
+            content: String::new(),
+            done: true,
+            mission_id: Some(mission_id),
+        });
+    }
+
+    let success = completed && error_message.is_none();
+    crate::stream_metrics::record(
+        &backend_id,
+        model.unwrap_or("default"),
+        crate::stream_metrics::StreamSample {
+            ttft_ms: first_event_ms,
+            output_tokens: usage.output_tokens,
+            duration_ms: turn_start.elapsed().as_millis() as u64,
+            errored: !success,
+        },
+    );
+
+    let final_message = error_message
+        .or_else(|| (!assistant_message.is_empty()).then_some(assistant_message))
+        .or(last_summary)
+        .unwrap_or_else(|| format!("No response from {}", backend.name()));
+    let (cost_cents, cost_source) = resolve_cost_cents_and_source(None, model, &usage);
+    let mut result = if success {
+        AgentResult::success(final_message, cost_cents)
+            .with_terminal_reason(TerminalReason::Completed)
+    } else {
+        let reason = if is_rate_limited_error(&final_message) {
+            TerminalReason::RateLimited
+        } else {
+            TerminalReason::LlmError
+        };
+        AgentResult::failure(final_message, cost_cents).with_terminal_reason(reason)
+    };
+    result = result.with_cost_source(cost_source);
+    if usage.has_usage() {
+        result = result.with_usage(usage);
+    }
+    if let Some(m) = model {
+        result = result.with_model(m.to_string());
+    }
+    result
+}
+
+/// Whether a Claude Code session with `session_id` was already started in
+/// `work_dir` (its marker file holds the session ID).
+pub(crate) fn claude_session_initiated(work_dir: &std::path::Path, session_id: &str) -> bool {
+    std::fs::read_to_string(work_dir.join(".claude-session-initiated"))
+        .map(|content| content.trim() == session_id)
+        .unwrap_or(false)
+}
+
+/// Permission modes accepted by `claude --permission-mode`.
+const CLAUDECODE_PERMISSION_MODES: &[&str] = &[
+    "default",
+    "acceptEdits",
+    "plan",
+    "dontAsk",
+    "bypassPermissions",
+];
+
+/// Profile permissions synced into `work_dir` for this mission, if any.
+fn read_claudecode_permissions(work_dir: &std::path::Path) -> Option<ClaudeCodePermissions> {
+    let path = work_dir.join(crate::workspace::CLAUDECODE_PERMISSIONS_PATH);
+    let content = std::fs::read_to_string(&path).ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| tracing::warn!(path = %path.display(), error = %e, "Invalid Claude Code permissions"))
+        .ok()
+}
+
+/// Names of the MCP servers in the mission's `--mcp-config` file.
+fn claudecode_mcp_server_names(work_dir: &std::path::Path) -> Vec<String> {
+    std::fs::read_to_string(work_dir.join(".claude").join("mcp.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|value| {
+            value
+                .get("mcpServers")
+                .and_then(|servers| servers.as_object())
+                .map(|servers| servers.keys().cloned().collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Claude CLI permission flags for a turn, plus warnings about profile
+/// settings that cannot take effect. A mode set by a slash command (e.g.
+/// `/plan`) wins over the profile's mode and keeps permission checks skipped.
+fn claudecode_permission_args(
+    permissions: Option<&ClaudeCodePermissions>,
+    command_mode: Option<&str>,
+    mcp_servers: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let default_permissions = ClaudeCodePermissions::default();
+    let permissions = permissions.unwrap_or(&default_permissions);
+    let mut args = Vec::new();
+    let mut warnings = Vec::new();
+
+    let profile_mode = match permissions.default_mode.as_deref().map(str::trim) {
+        Some(mode) if CLAUDECODE_PERMISSION_MODES.contains(&mode) => Some(mode),
+        Some(mode) if !mode.is_empty() => {
+            warnings.push(format!(
+                "Unknown Claude Code permission mode '{}' in config profile; permission checks are skipped",
+                mode
+            ));
+            None
+        }
+        _ => None,
+    };
+
+    let skip_checks = match (command_mode, profile_mode) {
+        (Some(mode), _) => {
+            args.extend(["--permission-mode".to_string(), mode.to_string()]);
+            true
+        }
+        (None, Some(mode)) if mode != "bypassPermissions" => {
+            args.extend(["--permission-mode".to_string(), mode.to_string()]);
+            if mode != "plan" && permissions.allow.is_empty() {
+                warnings.push(format!(
+                    "Claude Code permission mode '{}' has an empty allow list; missions run non-interactively, so tools that need approval will be denied",
+                    mode
+                ));
+            }
+            false
+        }
+        _ => true,
+    };
+    if skip_checks {
+        args.push("--dangerously-skip-permissions".to_string());
+        if !permissions.allow.is_empty() {
+            warnings.push(
+                "Claude Code allow list has no effect while permission checks are skipped; set a permission mode or use the deny list".to_string(),
+            );
+        }
+    }
+
+    for tool in &permissions.allow {
+        if permissions.deny.contains(tool) {
+            warnings.push(format!(
+                "Claude Code tool '{}' is both allowed and denied; deny takes precedence",
+                tool
+            ));
+        }
+    }
+    for tool in permissions.allow.iter().chain(&permissions.deny) {
+        let server = tool
+            .strip_prefix("mcp__")
+            .map(|rest| rest.split("__").next().unwrap_or(rest));
+        if let Some(server) = server.filter(|s| !s.contains('*')) {
+            if !mcp_servers.iter().any(|name| name == server) {
+                warnings.push(format!(
+                    "Claude Code permission '{}' refers to MCP server '{}', which is not enabled on this workspace",
+                    tool, server
+                ));
+            }
+        }
+    }
+
+    if !permissions.allow.is_empty() {
+        args.extend(["--allowedTools".to_string(), permissions.allow.join(",")]);
+    }
+    if !permissions.deny.is_empty() {
+        args.extend(["--disallowedTools".to_string(), permissions.deny.join(",")]);
+    }
+    (args, warnings)
+}
+
+/// Marker file holding the Codex thread of a mission's current session.
+const CODEX_THREAD_MARKER: &str = ".codex-thread";
+/// Marker file holding the OpenCode session of a mission's current session.
+const OPENCODE_SESSION_MARKER: &str = ".opencode-session";
+
+/// Backend session (Codex thread, OpenCode session) recorded in `work_dir`'s
+/// `marker` for the mission's `session_id`, if any. The marker records the
+/// mission session alongside the backend's, so a reset session (e.g. after
+/// compaction) starts a new backend session.
+fn backend_session_for(
+    work_dir: &std::path::Path,
+    marker: &str,
+    session_id: Option<&str>,
+) -> Option<String> {
+    let content = std::fs::read_to_string(work_dir.join(marker)).ok()?;
+    let (marker_session, backend_session) = content.split_once('\n')?;
+    let backend_session = backend_session.trim();
+    (marker_session.trim() == session_id.unwrap_or_default() && !backend_session.is_empty())
+        .then(|| backend_session.to_string())
+}
+
+fn record_backend_session(
+    work_dir: &std::path::Path,
+    marker: &str,
+    session_id: Option<&str>,
+    backend_session: &str,
+) {
+    let content = format!("{}\n{}\n", session_id.unwrap_or_default(), backend_session);
+    if let Err(e) = std::fs::write(work_dir.join(marker), content) {
+        tracing::warn!(marker = %marker, error = %e, "Failed to write backend session marker");
+    }
+}
+
+/// OpenCode sessions idle for longer than this are not continued.
+const OPENCODE_SESSION_MAX_IDLE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Why the stored OpenCode session `session_id` cannot be continued: its
+/// messages were deleted from storage, or it has been idle too long.
+fn opencode_session_unusable(workspace: &Workspace, session_id: &str) -> Option<&'static str> {
+    let Some(message_dir) = opencode_storage_roots(workspace)
+        .into_iter()
+        .map(|root| root.join("message").join(session_id))
+        .find(|dir| dir.is_dir())
+    else {
+        return Some("deleted");
+    };
+    let idle = std::fs::metadata(&message_dir)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    match idle {
+        Some(idle) if idle > OPENCODE_SESSION_MAX_IDLE => Some("expired"),
+        _ => None,
+    }
+}
+
+/// Whether OpenCode output says the session being continued does not exist.
+fn is_opencode_session_missing_error(output: &str) -> bool {
+    let lower = output.to_lowercase();
+    lower.contains("session")
+        && ["not found", "notfounderror", "does not exist", "no such"]
+            .iter()
+            .any(|w| lower.contains(w))
+}
+
+/// Whether a Codex error says the thread being resumed no longer exists.
+fn is_codex_thread_missing_error(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    ["thread", "session", "conversation", "rollout"]
+        .iter()
+        .any(|w| lower.contains(w))
+        && ["not found", "no such", "does not exist", "failed to resume"]
+            .iter()
+            .any(|w| lower.contains(w))
+}
+
 /// Generate a concise summary of recent conversation turns for session rotation.
 /// Summarizes the last N turns to preserve context when starting a new session.
 fn generate_session_summary(history: &[(String, String)], last_n_turns: usize) -> String {
@@ -10422,16 +11581,18 @@ fn cleanup_old_debug_files(
 #[cfg(test)]
 mod tests {
     use super::{
-        actual_cost_cents_from_total_cost_usd, bind_command_params, codex_key_fingerprint,
-        extract_model_from_message, extract_opencode_session_id, extract_part_text, extract_str,
-        extract_thought_line, is_capacity_limited_error, is_codex_node_wrapper,
-        is_rate_limited_error, is_session_corruption_error, is_tool_call_only_output,
-        opencode_output_needs_fallback, opencode_session_token_from_line,
+        actual_cost_cents_from_total_cost_usd, backend_session_for, bind_command_params,
+        claudecode_permission_args, codex_key_fingerprint, extract_model_from_message,
+        extract_opencode_session_id, extract_part_text, extract_str, extract_thought_line,
+        is_capacity_limited_error, is_codex_node_wrapper, is_codex_thread_missing_error,
+        is_opencode_session_missing_error, is_rate_limited_error, is_session_corruption_error,
+        is_tool_call_only_output, opencode_output_needs_fallback, opencode_session_token_from_line,
         parse_opencode_session_token, parse_opencode_sse_event, parse_opencode_stderr_text_part,
-        preferred_model_for_cost, resolve_cost_cents_and_source, running_health,
-        sanitized_opencode_stdout, stall_severity, strip_ansi_codes, strip_opencode_banner_lines,
-        strip_think_tags, summarize_recent_opencode_stderr, sync_opencode_agent_config,
-        MissionHealth, MissionRunState, MissionStallSeverity, OpencodeSseState, STALL_SEVERE_SECS,
+        preferred_model_for_cost, record_backend_session, resolve_cost_cents_and_source,
+        running_health, sanitized_opencode_stdout, stall_severity, strip_ansi_codes,
+        strip_opencode_banner_lines, strip_think_tags, summarize_recent_opencode_stderr,
+        sync_opencode_agent_config, MissionHealth, MissionRunState, MissionStallSeverity,
+        OpencodeSseState, CODEX_THREAD_MARKER, OPENCODE_SESSION_MARKER, STALL_SEVERE_SECS,
         STALL_WARN_SECS,
     };
     use crate::agents::{AgentResult, CostSource, TerminalReason};
@@ -10441,6 +11602,114 @@ mod tests {
     use std::fs;
     use uuid::Uuid;
 
+    #[test]
+    fn claudecode_permissions_map_to_cli_flags() {
+        let (args, warnings) = claudecode_permission_args(None, None, &[]);
+        assert_eq!(args, vec!["--dangerously-skip-permissions"]);
+        assert!(warnings.is_empty());
+
+        let permissions = crate::library::ClaudeCodePermissions {
+            default_mode: Some("acceptEdits".to_string()),
+            allow: vec!["Bash(git:*)".to_string(), "mcp__github__*".to_string()],
+            deny: vec!["WebFetch".to_string()],
+        };
+        let (args, warnings) =
+            claudecode_permission_args(Some(&permissions), None, &["github".to_string()]);
+        assert_eq!(
+            args,
+            vec![
+                "--permission-mode",
+                "acceptEdits",
+                "--allowedTools",
+                "Bash(git:*),mcp__github__*",
+                "--disallowedTools",
+                "WebFetch",
+            ]
+        );
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        // `/plan` wins over the profile mode and keeps checks skipped.
+        let (args, _) = claudecode_permission_args(Some(&permissions), Some("plan"), &[]);
+        assert_eq!(
+            &args[..3],
+            [
+                "--permission-mode",
+                "plan",
+                "--dangerously-skip-permissions"
+            ]
+        );
+    }
+
+    #[test]
+    fn claudecode_permission_mismatches_are_warned() {
+        let permissions = crate::library::ClaudeCodePermissions {
+            default_mode: Some("yolo".to_string()),
+            allow: vec!["mcp__jira__search".to_string(), "Bash".to_string()],
+            deny: vec!["Bash".to_string()],
+        };
+        let (args, warnings) =
+            claudecode_permission_args(Some(&permissions), None, &["github".to_string()]);
+        assert!(args.contains(&"--dangerously-skip-permissions".to_string()));
+        assert_eq!(warnings.len(), 4, "{:?}", warnings);
+        assert!(warnings[0].contains("Unknown Claude Code permission mode 'yolo'"));
+        assert!(warnings[1].contains("no effect"));
+        assert!(warnings[2].contains("both allowed and denied"));
+        assert!(warnings[3].contains("MCP server 'jira'"));
+    }
+
+    #[test]
+    fn backend_session_marker_is_scoped_to_mission_session() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let work_dir = temp_dir.path();
+        assert_eq!(
+            backend_session_for(work_dir, CODEX_THREAD_MARKER, Some("s1")),
+            None
+        );
+
+        record_backend_session(work_dir, CODEX_THREAD_MARKER, Some("s1"), "thread-1");
+        assert_eq!(
+            backend_session_for(work_dir, CODEX_THREAD_MARKER, Some("s1")).as_deref(),
+            Some("thread-1")
+        );
+        // A reset session starts a new thread.
+        assert_eq!(
+            backend_session_for(work_dir, CODEX_THREAD_MARKER, Some("s2")),
+            None
+        );
+
+        record_backend_session(work_dir, OPENCODE_SESSION_MARKER, None, "ses_abc123");
+        assert_eq!(
+            backend_session_for(work_dir, OPENCODE_SESSION_MARKER, None).as_deref(),
+            Some("ses_abc123")
+        );
+    }
+
+    #[test]
+    fn opencode_session_missing_error_detection() {
+        assert!(is_opencode_session_missing_error(
+            "Error: NotFoundError: Session not found: ses_abc123"
+        ));
+        assert!(is_opencode_session_missing_error(
+            "session ses_abc123 does not exist"
+        ));
+        assert!(!is_opencode_session_missing_error(
+            "OpenCode produced no assistant output"
+        ));
+        assert!(!is_opencode_session_missing_error("model not found"));
+    }
+
+    #[test]
+    fn codex_thread_missing_error_detection() {
+        assert!(is_codex_thread_missing_error(
+            "Error: thread 019c21ae not found"
+        ));
+        assert!(is_codex_thread_missing_error(
+            "No such session: 019c21ae-c46c-7a40-a5f5-36ab53521a27"
+        ));
+        assert!(!is_codex_thread_missing_error("rate limit exceeded"));
+        assert!(!is_codex_thread_missing_error("model not found"));
+    }
+
     #[test]
     fn sync_opencode_agent_config_removes_overrides_when_plugin_enabled() {
         let temp_dir = tempfile::tempdir().expect("temp dir");
@@ -0,0 +1,370 @@
+//! Mission SLA timers and escalation.
+//!
+//! A mission can declare a soft deadline when it is created:
+//!
+//! ```json
+//! "sla": {
+//!   "deadline_minutes": 90,
+//!   "escalate": ["stronger_model", "raise_priority", "page_approver"],
+//!   "escalation_model": "claude-opus-4-1",
+//!   "webhook_url": "https://hooks.slack.com/services/...",
+//!   "approver_webhook_url": "https://hooks.slack.com/services/..."
+//! }
+//! ```
+//!
+//! `deadline` (RFC 3339) can be given instead of `deadline_minutes`. A
+//! background task checks active missions every minute. When one passes its
+//! deadline the breach is recorded on the mission (so it is handled once), the
+//! listed escalations run, an `sla_breached` event is emitted and a
+//! notification is posted to `webhook_url`, or to the spending alerts webhook
+//! when the mission has none. Escalations:
+//! - `stronger_model`: later turns run on `escalation_model`
+//! - `raise_priority`: the mission's queued messages move to the front of the
+//!   message queue
+//! - `page_approver`: when the mission is waiting on a tool call in the
+//!   approval queue (`api::approvals`), the request is posted to
+//!   `approver_webhook_url`
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use super::approvals;
+use super::control::{AgentEvent, ControlCommand};
+use super::mission_store::{now_string, Mission, MissionStore};
+use super::notifier::{self, mission_link, Notification, NotificationLink};
+use crate::tools::safe_truncate_index;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pending approval text included in pages.
+const MAX_APPROVAL_CHARS: usize = 1_500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaEscalation {
+    StrongerModel,
+    RaisePriority,
+    PageApprover,
+}
+
+/// The `sla` field of a create-mission request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlaRequest {
+    /// Absolute soft deadline (RFC 3339)
+    #[serde(default)]
+    pub deadline: Option<String>,
+    /// Soft deadline relative to mission creation
+    #[serde(default)]
+    pub deadline_minutes: Option<u64>,
+    #[serde(default)]
+    pub escalate: Vec<SlaEscalation>,
+    /// Model used after a breach with `stronger_model`
+    #[serde(default)]
+    pub escalation_model: Option<String>,
+    /// Breach notifications (Slack incoming webhooks get Slack messages)
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Pages for pending approvals with `page_approver`
+    #[serde(default)]
+    pub approver_webhook_url: Option<String>,
+}
+
+/// A mission's SLA as stored on the mission.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissionSla {
+    pub deadline: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escalate: Vec<SlaEscalation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approver_webhook_url: Option<String>,
+    /// When the breach was handled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breached_at: Option<String>,
+}
+
+fn validate_url(field: &str, url: Option<&String>) -> Result<Option<String>, String> {
+    let Some(url) = url.map(|u| u.trim()).filter(|u| !u.is_empty()) else {
+        return Ok(None);
+    };
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(format!("{} must be an http(s) URL", field));
+    }
+    Ok(Some(url.to_string()))
+}
+
+impl SlaRequest {
+    /// Validate the request and resolve the deadline against `now`.
+    pub fn resolve(&self, now: DateTime<Utc>) -> Result<MissionSla, String> {
+        let deadline = match (self.deadline.as_deref(), self.deadline_minutes) {
+            (Some(_), Some(_)) => {
+                return Err("set either deadline or deadline_minutes, not both".to_string())
+            }
+            (None, None) => return Err("deadline or deadline_minutes is required".to_string()),
+            (None, Some(0)) => return Err("deadline_minutes must be positive".to_string()),
+            (None, Some(minutes)) => now + chrono::Duration::minutes(minutes as i64),
+            (Some(raw), None) => DateTime::parse_from_rfc3339(raw.trim())
+                .map_err(|e| format!("deadline: {}", e))?
+                .with_timezone(&Utc),
+        };
+        if deadline <= now {
+            return Err("deadline must be in the future".to_string());
+        }
+        let escalation_model = self
+            .escalation_model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(str::to_string);
+        if self.escalate.contains(&SlaEscalation::StrongerModel) && escalation_model.is_none() {
+            return Err("stronger_model escalation requires escalation_model".to_string());
+        }
+        let approver_webhook_url =
+            validate_url("approver_webhook_url", self.approver_webhook_url.as_ref())?;
+        if self.escalate.contains(&SlaEscalation::PageApprover) && approver_webhook_url.is_none() {
+            return Err("page_approver escalation requires approver_webhook_url".to_string());
+        }
+        let mut escalate = self.escalate.clone();
+        escalate.dedup();
+        Ok(MissionSla {
+            deadline: deadline.to_rfc3339(),
+            escalate,
+            escalation_model,
+            webhook_url: validate_url("webhook_url", self.webhook_url.as_ref())?,
+            approver_webhook_url,
+            breached_at: None,
+        })
+    }
+}
+
+impl MissionSla {
+    /// Past the deadline and not handled yet.
+    pub fn is_breached(&self, now: DateTime<Utc>) -> bool {
+        self.breached_at.is_none()
+            && DateTime::parse_from_rfc3339(&self.deadline).is_ok_and(|d| d <= now)
+    }
+}
+
+fn truncated(text: &str, max: usize) -> String {
+    let end = safe_truncate_index(text, max);
+    if end < text.len() {
+        format!("{}…", &text[..end])
+    } else {
+        text.to_string()
+    }
+}
+
+async fn send_command<T>(
+    cmd_tx: &mpsc::Sender<ControlCommand>,
+    command: ControlCommand,
+    rx: oneshot::Receiver<T>,
+) -> Option<T> {
+    cmd_tx.send(command).await.ok()?;
+    tokio::time::timeout(COMMAND_TIMEOUT, rx).await.ok()?.ok()
+}
+
+/// Run the escalations of a breached SLA; returns what was done.
+async fn escalate(
+    cmd_tx: &mpsc::Sender<ControlCommand>,
+    mission: &Mission,
+    sla: &MissionSla,
+    link: &str,
+) -> Vec<String> {
+    let mut actions = Vec::new();
+    for escalation in &sla.escalate {
+        match escalation {
+            SlaEscalation::StrongerModel => {
+                let Some(model) = sla.escalation_model.clone() else {
+                    continue;
+                };
+                let (tx, rx) = oneshot::channel();
+                let command = ControlCommand::SetMissionModel {
+                    mission_id: mission.id,
+                    model: model.clone(),
+                    respond: tx,
+                };
+                match send_command(cmd_tx, command, rx).await {
+                    Some(Ok(())) => actions.push(format!("switched to model {}", model)),
+                    Some(Err(e)) => actions.push(format!("model switch failed: {}", e)),
+                    None => actions.push("model switch failed: control session busy".to_string()),
+                }
+            }
+            SlaEscalation::RaisePriority => {
+                let (tx, rx) = oneshot::channel();
+                let command = ControlCommand::PrioritizeMission {
+                    mission_id: mission.id,
+                    respond: tx,
+                };
+                if let Some(moved) = send_command(cmd_tx, command, rx).await {
+                    actions.push(format!(
+                        "moved {} queued message(s) to the front of the queue",
+                        moved
+                    ));
+                }
+            }
+            SlaEscalation::PageApprover => {
+                let Some(url) = sla.approver_webhook_url.as_deref() else {
+                    continue;
+                };
+                let Some(pending) = approvals::oldest_pending(mission.id) else {
+                    actions.push("no pending approval to page for".to_string());
+                    continue;
+                };
+                let page = Notification {
+                    kind: "sla_approval_needed".to_string(),
+                    title: format!(
+                        "Approval needed: {}",
+                        mission.title.as_deref().unwrap_or("Untitled mission")
+                    ),
+                    text: format!(
+                        "The mission is past its SLA deadline and waiting for approval of `{}`:\n{}",
+                        pending.tool,
+                        truncated(&pending.reason, MAX_APPROVAL_CHARS)
+                    ),
+                    links: vec![NotificationLink {
+                        label: "Open mission".to_string(),
+                        url: link.to_string(),
+                    }],
+                    data: json!({
+                        "mission_id": mission.id,
+                        "request_id": pending.id,
+                        "tool": pending.tool,
+                        "requested_at": pending.requested_at,
+                    }),
+                };
+                match notifier::send(url, &page).await {
+                    Ok(()) => actions.push("paged the approver".to_string()),
+                    Err(e) => actions.push(format!("approver page failed: {}", e)),
+                }
+            }
+        }
+    }
+    actions
+}
+
+async fn handle_breach(
+    store: &Arc<dyn MissionStore>,
+    cmd_tx: &mpsc::Sender<ControlCommand>,
+    events_tx: &broadcast::Sender<AgentEvent>,
+    mission: &Mission,
+    mut sla: MissionSla,
+) {
+    sla.breached_at = Some(now_string());
+    if let Err(e) = store.update_mission_sla(mission.id, Some(&sla)).await {
+        tracing::warn!(mission_id = %mission.id, error = %e, "Failed to record SLA breach");
+        return;
+    }
+    tracing::warn!(mission_id = %mission.id, deadline = %sla.deadline, "Mission SLA breached");
+
+    let alert_settings = crate::settings::spending_alert_settings_cached();
+    let link = mission_link(alert_settings.dashboard_url.as_deref(), mission.id);
+    let actions = escalate(cmd_tx, mission, &sla, &link).await;
+    let title = mission.title.as_deref().unwrap_or("Untitled mission");
+    let mut message = format!("Mission '{}' missed its deadline ({})", title, sla.deadline);
+    if !actions.is_empty() {
+        message.push_str(&format!("; {}", actions.join("; ")));
+    }
+    let _ = events_tx.send(AgentEvent::SlaBreached {
+        deadline: sla.deadline.clone(),
+        message: message.clone(),
+        escalations: actions.clone(),
+        mission_id: mission.id,
+    });
+
+    let Some(url) = sla.webhook_url.clone().or(alert_settings.webhook_url) else {
+        return;
+    };
+    let notification = Notification {
+        kind: "sla_breach".to_string(),
+        title: "Mission SLA breached".to_string(),
+        text: message,
+        links: vec![NotificationLink {
+            label: title.to_string(),
+            url: link,
+        }],
+        data: json!({
+            "mission_id": mission.id,
+            "deadline": sla.deadline,
+            "escalations": actions,
+        }),
+    };
+    if let Err(e) = notifier::send(&url, &notification).await {
+        tracing::warn!(mission_id = %mission.id, error = %e, "Failed to send SLA breach notification");
+    }
+}
+
+/// Background task that handles missions past their SLA deadline.
+pub async fn sla_monitor_loop(
+    store: Arc<dyn MissionStore>,
+    cmd_tx: mpsc::Sender<ControlCommand>,
+    events_tx: broadcast::Sender<AgentEvent>,
+) {
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        let missions = match store.get_all_active_missions().await {
+            Ok(missions) => missions,
+            Err(e) => {
+                tracing::warn!(error = %e, "SLA check failed to list active missions");
+                continue;
+            }
+        };
+        let now = Utc::now();
+        for mission in &missions {
+            let Some(sla) = mission.sla.clone().filter(|s| s.is_breached(now)) else {
+                continue;
+            };
+            handle_breach(&store, &cmd_tx, &events_tx, mission, sla).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-15T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn resolves_and_validates_requests() {
+        let sla = SlaRequest {
+            deadline_minutes: Some(90),
+            escalate: vec![SlaEscalation::RaisePriority],
+            ..Default::default()
+        }
+        .resolve(now())
+        .unwrap();
+        assert_eq!(sla.deadline, "2026-01-15T11:30:00+00:00");
+        assert!(!sla.is_breached(now()));
+        assert!(sla.is_breached(now() + chrono::Duration::minutes(91)));
+
+        let past = SlaRequest {
+            deadline: Some("2026-01-15T09:00:00Z".to_string()),
+            ..Default::default()
+        };
+        assert!(past.resolve(now()).unwrap_err().contains("future"));
+        let no_model = SlaRequest {
+            deadline_minutes: Some(5),
+            escalate: vec![SlaEscalation::StrongerModel],
+            ..Default::default()
+        };
+        assert!(no_model.resolve(now()).is_err());
+        let bad_url = SlaRequest {
+            deadline_minutes: Some(5),
+            webhook_url: Some("hooks.slack.com/x".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_url.resolve(now()).is_err());
+    }
+}
@@ -4,6 +4,13 @@ use super::{
     now_string, sanitize_filename, Mission, MissionHistoryEntry, MissionStatus, MissionStore,
 };
 use crate::api::control::{AgentTreeNode, DesktopSessionInfo};
+use crate::api::mission_audit::MissionMode;
+use crate::api::mission_sla::MissionSla;
+use crate::api::step_budget::StepBudget;
+use crate::feature_flags::FlagSet;
+use crate::locale::LocaleSettings;
+use crate::tools::audit::AuditReport;
+use crate::tools::plan::MissionPlan;
 use async_trait::async_trait;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -107,8 +114,9 @@ impl MissionStore for FileMissionStore {
         config_profile: Option<&str>,
     ) -> Result<Mission, String> {
         let now = now_string();
+        let id = Uuid::new_v4();
         let mission = Mission {
-            id: Uuid::new_v4(),
+            id,
             status: MissionStatus::Pending,
             title: title.map(|s| s.to_string()),
             workspace_id: workspace_id.unwrap_or(crate::workspace::DEFAULT_WORKSPACE_ID),
@@ -126,6 +134,15 @@ impl MissionStore for FileMissionStore {
             desktop_sessions: Vec::new(),
             session_id: Some(Uuid::new_v4().to_string()),
             terminal_reason: None,
+            locale: None,
+            tags: Vec::new(),
+            sla: None,
+            feature_flags: crate::feature_flags::for_new_mission(id),
+            plan: None,
+            step_budget: None,
+            priority: 0,
+            mode: MissionMode::Standard,
+            audit_report: None,
         };
         self.missions
             .write()
@@ -210,6 +227,132 @@ impl MissionStore for FileMissionStore {
         self.persist().await
     }
 
+    async fn update_mission_locale(
+        &self,
+        id: Uuid,
+        locale: Option<&LocaleSettings>,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.locale = locale.cloned();
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_tags(&self, id: Uuid, tags: &[String]) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.tags = tags.to_vec();
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_sla(&self, id: Uuid, sla: Option<&MissionSla>) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.sla = sla.cloned();
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_feature_flags(&self, id: Uuid, flags: &FlagSet) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.feature_flags = flags.clone();
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_plan(&self, id: Uuid, plan: &MissionPlan) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.plan = Some(plan.clone());
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_step_budget(
+        &self,
+        id: Uuid,
+        budget: Option<StepBudget>,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.step_budget = budget;
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_priority(&self, id: Uuid, priority: i32) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.priority = priority;
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_mode(&self, id: Uuid, mode: MissionMode) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.mode = mode;
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_audit_report(
+        &self,
+        id: Uuid,
+        report: &AuditReport,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.audit_report = Some(report.clone());
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
+    async fn update_mission_model_override(
+        &self,
+        id: Uuid,
+        model_override: Option<&str>,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.model_override = model_override.map(str::to_string);
+        mission.updated_at = now_string();
+        drop(missions);
+        self.persist().await
+    }
+
     async fn update_mission_session_id(&self, id: Uuid, session_id: &str) -> Result<(), String> {
         let mut missions = self.missions.write().await;
         let mission = missions
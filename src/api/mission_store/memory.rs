@@ -1,7 +1,17 @@
 //! In-memory mission store (non-persistent).
 
-use super::{now_string, Mission, MissionHistoryEntry, MissionStatus, MissionStore};
+use super::{
+    now_string, Mission, MissionBatch, MissionHistoryEntry, MissionResult, MissionStatus,
+    MissionStore,
+};
 use crate::api::control::{AgentTreeNode, DesktopSessionInfo};
+use crate::api::mission_audit::MissionMode;
+use crate::api::mission_sla::MissionSla;
+use crate::api::step_budget::StepBudget;
+use crate::feature_flags::FlagSet;
+use crate::locale::LocaleSettings;
+use crate::tools::audit::AuditReport;
+use crate::tools::plan::MissionPlan;
 use async_trait::async_trait;
 use chrono::Utc;
 use std::collections::HashMap;
@@ -13,6 +23,8 @@ use uuid::Uuid;
 pub struct InMemoryMissionStore {
     missions: Arc<RwLock<HashMap<Uuid, Mission>>>,
     trees: Arc<RwLock<HashMap<Uuid, AgentTreeNode>>>,
+    batches: Arc<RwLock<HashMap<Uuid, MissionBatch>>>,
+    results: Arc<RwLock<HashMap<Uuid, Vec<MissionResult>>>>,
 }
 
 impl InMemoryMissionStore {
@@ -20,6 +32,8 @@ impl InMemoryMissionStore {
         Self {
             missions: Arc::new(RwLock::new(HashMap::new())),
             trees: Arc::new(RwLock::new(HashMap::new())),
+            batches: Arc::new(RwLock::new(HashMap::new())),
+            results: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
@@ -58,8 +72,9 @@ impl MissionStore for InMemoryMissionStore {
         config_profile: Option<&str>,
     ) -> Result<Mission, String> {
         let now = now_string();
+        let id = Uuid::new_v4();
         let mission = Mission {
-            id: Uuid::new_v4(),
+            id,
             status: MissionStatus::Pending,
             title: title.map(|s| s.to_string()),
             workspace_id: workspace_id.unwrap_or(crate::workspace::DEFAULT_WORKSPACE_ID),
@@ -77,6 +92,15 @@ impl MissionStore for InMemoryMissionStore {
             desktop_sessions: Vec::new(),
             session_id: Some(Uuid::new_v4().to_string()),
             terminal_reason: None,
+            locale: None,
+            tags: Vec::new(),
+            sla: None,
+            feature_flags: crate::feature_flags::for_new_mission(id),
+            plan: None,
+            step_budget: None,
+            priority: 0,
+            mode: MissionMode::Standard,
+            audit_report: None,
         };
         self.missions
             .write()
@@ -156,6 +180,122 @@ impl MissionStore for InMemoryMissionStore {
         Ok(())
     }
 
+    async fn update_mission_locale(
+        &self,
+        id: Uuid,
+        locale: Option<&LocaleSettings>,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.locale = locale.cloned();
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_tags(&self, id: Uuid, tags: &[String]) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.tags = tags.to_vec();
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_sla(&self, id: Uuid, sla: Option<&MissionSla>) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.sla = sla.cloned();
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_feature_flags(&self, id: Uuid, flags: &FlagSet) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.feature_flags = flags.clone();
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_plan(&self, id: Uuid, plan: &MissionPlan) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.plan = Some(plan.clone());
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_step_budget(
+        &self,
+        id: Uuid,
+        budget: Option<StepBudget>,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.step_budget = budget;
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_priority(&self, id: Uuid, priority: i32) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.priority = priority;
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_mode(&self, id: Uuid, mode: MissionMode) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.mode = mode;
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_audit_report(
+        &self,
+        id: Uuid,
+        report: &AuditReport,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.audit_report = Some(report.clone());
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
+    async fn update_mission_model_override(
+        &self,
+        id: Uuid,
+        model_override: Option<&str>,
+    ) -> Result<(), String> {
+        let mut missions = self.missions.write().await;
+        let mission = missions
+            .get_mut(&id)
+            .ok_or_else(|| format!("Mission {} not found", id))?;
+        mission.model_override = model_override.map(str::to_string);
+        mission.updated_at = now_string();
+        Ok(())
+    }
+
     async fn update_mission_session_id(&self, id: Uuid, session_id: &str) -> Result<(), String> {
         let mut missions = self.missions.write().await;
         let mission = missions
@@ -178,6 +318,7 @@ impl MissionStore for InMemoryMissionStore {
     async fn delete_mission(&self, id: Uuid) -> Result<bool, String> {
         let removed = self.missions.write().await.remove(&id).is_some();
         self.trees.write().await.remove(&id);
+        self.results.write().await.remove(&id);
         Ok(removed)
     }
 
@@ -257,4 +398,37 @@ impl MissionStore for InMemoryMissionStore {
     ) -> Result<(), String> {
         Ok(())
     }
+
+    async fn create_mission_batch(&self, batch: MissionBatch) -> Result<MissionBatch, String> {
+        self.batches.write().await.insert(batch.id, batch.clone());
+        Ok(batch)
+    }
+
+    async fn get_mission_batch(&self, id: Uuid) -> Result<Option<MissionBatch>, String> {
+        Ok(self.batches.read().await.get(&id).cloned())
+    }
+
+    async fn append_mission_result(
+        &self,
+        mission_id: Uuid,
+        result: MissionResult,
+    ) -> Result<(), String> {
+        self.results
+            .write()
+            .await
+            .entry(mission_id)
+            .or_default()
+            .push(result);
+        Ok(())
+    }
+
+    async fn get_mission_results(&self, mission_id: Uuid) -> Result<Vec<MissionResult>, String> {
+        Ok(self
+            .results
+            .read()
+            .await
+            .get(&mission_id)
+            .cloned()
+            .unwrap_or_default())
+    }
 }
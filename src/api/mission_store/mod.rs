@@ -14,6 +14,13 @@ pub use memory::InMemoryMissionStore;
 pub use sqlite::SqliteMissionStore;
 
 use crate::api::control::{AgentEvent, AgentTreeNode, DesktopSessionInfo, MissionStatus};
+use crate::api::mission_audit::MissionMode;
+use crate::api::mission_sla::MissionSla;
+use crate::api::step_budget::StepBudget;
+use crate::feature_flags::FlagSet;
+use crate::locale::LocaleSettings;
+use crate::tools::audit::AuditReport;
+use crate::tools::plan::MissionPlan;
 use async_trait::async_trait;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -66,6 +73,35 @@ pub struct Mission {
     /// Why the mission terminated (for failed/completed missions)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub terminal_reason: Option<String>,
+    /// Locale and time zone for this mission (falls back to the global settings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<LocaleSettings>,
+    /// Free-form labels, used to attribute the mission's cost to budget buckets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Soft deadline and escalation policy (see `mission_sla`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sla: Option<MissionSla>,
+    /// Executor feature flags resolved when the mission was created
+    /// (see `feature_flags`)
+    #[serde(default, skip_serializing_if = "FlagSet::is_empty")]
+    pub feature_flags: FlagSet,
+    /// Step-level plan the agent recorded with `set_plan` (see `tools::plan`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<MissionPlan>,
+    /// Turn and tool call limits over the settings' default (see `step_budget`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_budget: Option<StepBudget>,
+    /// Scheduling priority; higher starts first and may preempt lower ones
+    /// (see `mission_priority`)
+    #[serde(default)]
+    pub priority: i32,
+    /// Standard, or a read-only audit (see `mission_audit`)
+    #[serde(default)]
+    pub mode: MissionMode,
+    /// Report an audit mission submitted with `submit_audit_report`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_report: Option<AuditReport>,
 }
 
 fn default_backend() -> String {
@@ -98,6 +134,104 @@ pub struct StoredEvent {
     pub metadata: serde_json::Value,
 }
 
+/// A group of missions submitted together (see `POST /api/control/missions/batch`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionBatch {
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub mission_ids: Vec<Uuid>,
+    pub created_at: String,
+}
+
+/// Output of a post-processor run after a mission finished
+/// (see `api::mission_postprocess`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionResult {
+    /// Processor name (`summary`, `artifacts`, `diff` or a user processor)
+    pub processor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: String,
+}
+
+/// Event source a daemon mission subscribes to (see `api::mission_daemon`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonTrigger {
+    /// `POST /api/webhooks/daemon/:webhook_id`
+    Webhook {
+        webhook_id: String,
+        /// Optional secret for HMAC-SHA256 signature validation
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+    },
+    /// Changes under a path in the mission's workspace
+    FileChange {
+        path: String,
+        /// Only file names matching this glob (e.g. `*.rs`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pattern: Option<String>,
+    },
+    /// Fixed interval in seconds
+    Schedule { interval_seconds: u64 },
+    /// Messages posted to a named queue
+    Queue { name: String },
+}
+
+/// Lifecycle state of a daemon mission.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonState {
+    Running,
+    /// Triggers are queued but no turns run until resumed
+    Paused,
+    /// Triggers are ignored; terminal
+    Stopped,
+}
+
+/// A mission that stays alive and runs one bounded turn per trigger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionDaemon {
+    pub mission_id: Uuid,
+    pub state: DaemonState,
+    pub triggers: Vec<DaemonTrigger>,
+    /// Standing instructions included in every triggered turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    /// A turn running longer than this is cancelled
+    pub max_turn_seconds: u64,
+    /// Cumulative cost cap; the daemon stops once it is reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_cents: Option<u64>,
+    /// Maximum number of triggered turns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u64>,
+    /// Turns run so far
+    #[serde(default)]
+    pub turns: u64,
+    /// Mission cost since the daemon was created
+    #[serde(default)]
+    pub spent_cents: u64,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_triggered_at: Option<String>,
+    /// Why the daemon stopped (when `state` is `stopped`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+/// Cost of one mission over a period, with what budget attribution needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissionCost {
+    pub mission_id: Uuid,
+    pub workspace_id: Uuid,
+    pub tags: Vec<String>,
+    pub cost_cents: u64,
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Automation Types
 // ─────────────────────────────────────────────────────────────────────────────
@@ -355,6 +489,52 @@ pub trait MissionStore: Send + Sync {
     /// Update mission title.
     async fn update_mission_title(&self, id: Uuid, title: &str) -> Result<(), String>;
 
+    /// Set or clear the mission's locale and time zone.
+    async fn update_mission_locale(
+        &self,
+        id: Uuid,
+        locale: Option<&LocaleSettings>,
+    ) -> Result<(), String>;
+
+    /// Replace the mission's tags.
+    async fn update_mission_tags(&self, id: Uuid, tags: &[String]) -> Result<(), String>;
+
+    /// Set or clear the mission's SLA.
+    async fn update_mission_sla(&self, id: Uuid, sla: Option<&MissionSla>) -> Result<(), String>;
+
+    /// Replace the mission's feature flags (per-mission overrides).
+    async fn update_mission_feature_flags(&self, id: Uuid, flags: &FlagSet) -> Result<(), String>;
+
+    /// Replace the mission's plan.
+    async fn update_mission_plan(&self, id: Uuid, plan: &MissionPlan) -> Result<(), String>;
+
+    /// Set or clear the mission's step budget.
+    async fn update_mission_step_budget(
+        &self,
+        id: Uuid,
+        budget: Option<StepBudget>,
+    ) -> Result<(), String>;
+
+    /// Set the mission's scheduling priority.
+    async fn update_mission_priority(&self, id: Uuid, priority: i32) -> Result<(), String>;
+
+    /// Set the mission's mode.
+    async fn update_mission_mode(&self, id: Uuid, mode: MissionMode) -> Result<(), String>;
+
+    /// Replace the mission's audit report.
+    async fn update_mission_audit_report(
+        &self,
+        id: Uuid,
+        report: &AuditReport,
+    ) -> Result<(), String>;
+
+    /// Set or clear the mission's model override (used by later turns).
+    async fn update_mission_model_override(
+        &self,
+        id: Uuid,
+        model_override: Option<&str>,
+    ) -> Result<(), String>;
+
     /// Update mission session ID (for backends like Amp that generate their own IDs).
     async fn update_mission_session_id(&self, id: Uuid, session_id: &str) -> Result<(), String>;
 
@@ -408,6 +588,26 @@ pub trait MissionStore: Send + Sync {
         Ok(vec![])
     }
 
+    /// Delete events logged before `before` (ISO-8601) for missions that are no
+    /// longer running. User and assistant messages are kept, so conversations
+    /// and cost totals survive. Returns the number of events deleted.
+    async fn prune_events_before(&self, before: &str) -> Result<usize, String> {
+        let _ = before;
+        Ok(0)
+    }
+
+    /// Events of any mission logged before `before` (ISO-8601) with an ID
+    /// above `after_id`, in ID order (for bulk export).
+    async fn get_events_before(
+        &self,
+        before: &str,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<StoredEvent>, String> {
+        let _ = (before, after_id, limit);
+        Ok(vec![])
+    }
+
     /// Get total cost in cents across all missions.
     /// Aggregates assistant_message metadata cost across all events.
     async fn get_total_cost_cents(&self) -> Result<u64, String> {
@@ -430,6 +630,76 @@ pub trait MissionStore: Send + Sync {
         Ok((0, 0, 0))
     }
 
+    /// Cost in cents per mission for assistant messages logged in
+    /// `[since, until)` (ISO-8601), for budget attribution. Missions without
+    /// cost in the range are omitted.
+    async fn get_mission_costs_between(
+        &self,
+        since: &str,
+        until: &str,
+    ) -> Result<Vec<MissionCost>, String> {
+        let _ = (since, until);
+        Ok(vec![])
+    }
+
+    /// Input plus output tokens of assistant messages logged in
+    /// `[since, until)` (ISO-8601).
+    async fn get_token_usage_between(&self, since: &str, until: &str) -> Result<u64, String> {
+        let _ = (since, until);
+        Ok(0)
+    }
+
+    // === Mission batch methods ===
+
+    /// Record a batch of missions.
+    async fn create_mission_batch(&self, batch: MissionBatch) -> Result<MissionBatch, String> {
+        let _ = batch;
+        Err("Mission batches not supported by this store".to_string())
+    }
+
+    /// Get a mission batch by ID.
+    async fn get_mission_batch(&self, id: Uuid) -> Result<Option<MissionBatch>, String> {
+        let _ = id;
+        Ok(None)
+    }
+
+    // === Mission result methods ===
+
+    /// Append the output of a post-processor to a mission.
+    async fn append_mission_result(
+        &self,
+        mission_id: Uuid,
+        result: MissionResult,
+    ) -> Result<(), String> {
+        let _ = (mission_id, result);
+        Err("Mission results not supported by this store".to_string())
+    }
+
+    /// Post-processor outputs of a mission, oldest first.
+    async fn get_mission_results(&self, mission_id: Uuid) -> Result<Vec<MissionResult>, String> {
+        let _ = mission_id;
+        Ok(vec![])
+    }
+
+    // === Daemon mission methods ===
+
+    /// Create or replace the daemon configuration of a mission.
+    async fn upsert_mission_daemon(&self, daemon: &MissionDaemon) -> Result<(), String> {
+        let _ = daemon;
+        Err("Daemon missions not supported by this store".to_string())
+    }
+
+    /// Daemon configuration of a mission, if it is a daemon.
+    async fn get_mission_daemon(&self, mission_id: Uuid) -> Result<Option<MissionDaemon>, String> {
+        let _ = mission_id;
+        Ok(None)
+    }
+
+    /// All daemon missions, including stopped ones.
+    async fn list_mission_daemons(&self) -> Result<Vec<MissionDaemon>, String> {
+        Ok(vec![])
+    }
+
     // === Automation methods (default no-op for backward compatibility) ===
 
     /// Create an automation for a mission.
@@ -2,10 +2,18 @@
 
 use super::{
     now_string, sanitize_filename, Automation, AutomationExecution, CommandSource, ExecutionStatus,
-    FreshSession, Mission, MissionHistoryEntry, MissionStatus, MissionStore, RetryConfig,
-    StopPolicy, StoredEvent, TriggerType, WebhookConfig,
+    FreshSession, Mission, MissionBatch, MissionCost, MissionDaemon, MissionHistoryEntry,
+    MissionResult, MissionStatus, MissionStore, RetryConfig, StopPolicy, StoredEvent, TriggerType,
+    WebhookConfig,
 };
 use crate::api::control::{AgentEvent, AgentTreeNode, DesktopSessionInfo};
+use crate::api::mission_audit::MissionMode;
+use crate::api::mission_sla::MissionSla;
+use crate::api::step_budget::StepBudget;
+use crate::feature_flags::FlagSet;
+use crate::locale::LocaleSettings;
+use crate::tools::audit::AuditReport;
+use crate::tools::plan::MissionPlan;
 use async_trait::async_trait;
 use chrono::Utc;
 use rusqlite::{params, Connection, OptionalExtension};
@@ -37,6 +45,8 @@ struct AssistantMessageMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     model_normalized: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    chat_options: Option<crate::chat_options::ChatOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     shared_files: Option<Vec<crate::api::control::SharedFile>>,
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     resumable: bool,
@@ -49,6 +59,7 @@ struct AssistantMessageMetadataInput<'a> {
     usage: &'a Option<crate::cost::TokenUsage>,
     model: &'a Option<String>,
     model_normalized: &'a Option<String>,
+    chat_options: &'a Option<crate::chat_options::ChatOptions>,
     shared_files: &'a Option<Vec<crate::api::control::SharedFile>>,
     resumable: bool,
 }
@@ -65,6 +76,7 @@ fn assistant_message_metadata(input: AssistantMessageMetadataInput<'_>) -> serde
         usage: input.usage.clone(),
         model: input.model.clone(),
         model_normalized: input.model_normalized.clone(),
+        chat_options: input.chat_options.clone(),
         shared_files: input.shared_files.clone(),
         resumable: input.resumable,
     };
@@ -106,7 +118,16 @@ CREATE TABLE IF NOT EXISTS missions (
     interrupted_at TEXT,
     resumable INTEGER NOT NULL DEFAULT 0,
     desktop_sessions TEXT,
-    terminal_reason TEXT
+    terminal_reason TEXT,
+    locale TEXT,
+    tags TEXT,
+    sla TEXT,
+    feature_flags TEXT,
+    plan TEXT,
+    step_budget TEXT,
+    priority INTEGER NOT NULL DEFAULT 0,
+    mode TEXT NOT NULL DEFAULT 'standard',
+    audit_report TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_missions_updated_at ON missions(updated_at DESC);
@@ -193,6 +214,33 @@ CREATE TABLE IF NOT EXISTS automation_executions (
 CREATE INDEX IF NOT EXISTS idx_executions_automation ON automation_executions(automation_id, triggered_at DESC);
 CREATE INDEX IF NOT EXISTS idx_executions_mission ON automation_executions(mission_id, triggered_at DESC);
 CREATE INDEX IF NOT EXISTS idx_executions_status ON automation_executions(status);
+
+CREATE TABLE IF NOT EXISTS mission_batches (
+    id TEXT PRIMARY KEY NOT NULL,
+    title TEXT,
+    mission_ids TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS mission_results (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    mission_id TEXT NOT NULL,
+    processor TEXT NOT NULL,
+    data TEXT,
+    error TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (mission_id) REFERENCES missions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_mission_results_mission ON mission_results(mission_id, id);
+
+CREATE TABLE IF NOT EXISTS mission_daemons (
+    mission_id TEXT PRIMARY KEY NOT NULL,
+    state TEXT NOT NULL,
+    config TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (mission_id) REFERENCES missions(id) ON DELETE CASCADE
+);
 "#;
 
 /// Content size threshold for inline storage (64KB).
@@ -529,6 +577,120 @@ impl SqliteMissionStore {
                 .map_err(|e| format!("Failed to add model_effort column: {}", e))?;
         }
 
+        // Check if 'locale' column exists in missions table
+        let has_locale_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'locale'")
+            .map_err(|e| format!("Failed to check for locale column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_locale_column {
+            tracing::info!("Running migration: adding 'locale' column to missions table");
+            conn.execute("ALTER TABLE missions ADD COLUMN locale TEXT", [])
+                .map_err(|e| format!("Failed to add locale column: {}", e))?;
+        }
+
+        // Check if 'tags' column exists in missions table
+        let has_tags_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'tags'")
+            .map_err(|e| format!("Failed to check for tags column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_tags_column {
+            tracing::info!("Running migration: adding 'tags' column to missions table");
+            conn.execute("ALTER TABLE missions ADD COLUMN tags TEXT", [])
+                .map_err(|e| format!("Failed to add tags column: {}", e))?;
+        }
+
+        // Check if 'sla' column exists in missions table
+        let has_sla_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'sla'")
+            .map_err(|e| format!("Failed to check for sla column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_sla_column {
+            tracing::info!("Running migration: adding 'sla' column to missions table");
+            conn.execute("ALTER TABLE missions ADD COLUMN sla TEXT", [])
+                .map_err(|e| format!("Failed to add sla column: {}", e))?;
+        }
+
+        // Check if 'feature_flags' column exists in missions table
+        let has_feature_flags_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'feature_flags'")
+            .map_err(|e| format!("Failed to check for feature_flags column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_feature_flags_column {
+            tracing::info!("Running migration: adding 'feature_flags' column to missions table");
+            conn.execute("ALTER TABLE missions ADD COLUMN feature_flags TEXT", [])
+                .map_err(|e| format!("Failed to add feature_flags column: {}", e))?;
+        }
+
+        // Check if 'plan' column exists in missions table
+        let has_plan_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'plan'")
+            .map_err(|e| format!("Failed to check for plan column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_plan_column {
+            tracing::info!("Running migration: adding 'plan' column to missions table");
+            conn.execute("ALTER TABLE missions ADD COLUMN plan TEXT", [])
+                .map_err(|e| format!("Failed to add plan column: {}", e))?;
+        }
+
+        // Check if 'step_budget' column exists in missions table
+        let has_step_budget_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'step_budget'")
+            .map_err(|e| format!("Failed to check for step_budget column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_step_budget_column {
+            tracing::info!("Running migration: adding 'step_budget' column to missions table");
+            conn.execute("ALTER TABLE missions ADD COLUMN step_budget TEXT", [])
+                .map_err(|e| format!("Failed to add step_budget column: {}", e))?;
+        }
+
+        // Check if 'priority' column exists in missions table
+        let has_priority_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'priority'")
+            .map_err(|e| format!("Failed to check for priority column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_priority_column {
+            tracing::info!("Running migration: adding 'priority' column to missions table");
+            conn.execute(
+                "ALTER TABLE missions ADD COLUMN priority INTEGER NOT NULL DEFAULT 0",
+                [],
+            )
+            .map_err(|e| format!("Failed to add priority column: {}", e))?;
+        }
+
+        // Check if 'mode' column exists in missions table
+        let has_mode_column: bool = conn
+            .prepare("SELECT 1 FROM pragma_table_info('missions') WHERE name = 'mode'")
+            .map_err(|e| format!("Failed to check for mode column: {}", e))?
+            .exists([])
+            .map_err(|e| format!("Failed to query table info: {}", e))?;
+
+        if !has_mode_column {
+            tracing::info!(
+                "Running migration: adding 'mode' and 'audit_report' columns to missions table"
+            );
+            conn.execute(
+                "ALTER TABLE missions ADD COLUMN mode TEXT NOT NULL DEFAULT 'standard'",
+                [],
+            )
+            .map_err(|e| format!("Failed to add mode column: {}", e))?;
+            conn.execute("ALTER TABLE missions ADD COLUMN audit_report TEXT", [])
+                .map_err(|e| format!("Failed to add audit_report column: {}", e))?;
+        }
+
         // Migrate automations table to new schema
         Self::migrate_automations_table(conn)?;
         Self::ensure_automation_indexes(conn)?;
@@ -786,7 +948,8 @@ impl MissionStore for SqliteMissionStore {
                             model_effort,
                             created_at, updated_at, interrupted_at, resumable, desktop_sessions,
                             COALESCE(backend, 'opencode') as backend, session_id, terminal_reason,
-                            config_profile
+                            config_profile, locale, tags, sla, feature_flags, plan, step_budget,
+                            priority, mode, audit_report
                      FROM missions
                      ORDER BY updated_at DESC
                      LIMIT ?1 OFFSET ?2",
@@ -803,6 +966,14 @@ impl MissionStore for SqliteMissionStore {
                     let session_id: Option<String> = row.get(14)?;
                     let terminal_reason: Option<String> = row.get(15)?;
                     let config_profile: Option<String> = row.get(16)?;
+                    let locale_json: Option<String> = row.get(17)?;
+                    let tags_json: Option<String> = row.get(18)?;
+                    let sla_json: Option<String> = row.get(19)?;
+                    let feature_flags_json: Option<String> = row.get(20)?;
+                    let plan_json: Option<String> = row.get(21)?;
+                    let step_budget_json: Option<String> = row.get(22)?;
+                    let mode: String = row.get(24)?;
+                    let audit_report_json: Option<String> = row.get(25)?;
 
                     Ok(Mission {
                         id: parse_uuid_or_nil(&id_str),
@@ -826,6 +997,19 @@ impl MissionStore for SqliteMissionStore {
                             .unwrap_or_default(),
                         session_id,
                         terminal_reason,
+                        locale: locale_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        tags: tags_json
+                            .and_then(|s| serde_json::from_str(&s).ok())
+                            .unwrap_or_default(),
+                        sla: sla_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        feature_flags: feature_flags_json
+                            .and_then(|s| serde_json::from_str(&s).ok())
+                            .unwrap_or_default(),
+                        plan: plan_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        step_budget: step_budget_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        priority: row.get(23)?,
+                        mode: MissionMode::parse(&mode),
+                        audit_report: audit_report_json.and_then(|s| serde_json::from_str(&s).ok()),
                     })
                 })
                 .map_err(|e| e.to_string())?
@@ -852,7 +1036,8 @@ impl MissionStore for SqliteMissionStore {
                             model_effort,
                             created_at, updated_at, interrupted_at, resumable, desktop_sessions,
                             COALESCE(backend, 'opencode') as backend, session_id, terminal_reason,
-                            config_profile
+                            config_profile, locale, tags, sla, feature_flags, plan, step_budget,
+                            priority, mode, audit_report
                      FROM missions WHERE id = ?1",
                 )
                 .map_err(|e| e.to_string())?;
@@ -867,6 +1052,14 @@ impl MissionStore for SqliteMissionStore {
                     let session_id: Option<String> = row.get(14)?;
                     let terminal_reason: Option<String> = row.get(15)?;
                     let config_profile: Option<String> = row.get(16)?;
+                    let locale_json: Option<String> = row.get(17)?;
+                    let tags_json: Option<String> = row.get(18)?;
+                    let sla_json: Option<String> = row.get(19)?;
+                    let feature_flags_json: Option<String> = row.get(20)?;
+                    let plan_json: Option<String> = row.get(21)?;
+                    let step_budget_json: Option<String> = row.get(22)?;
+                    let mode: String = row.get(24)?;
+                    let audit_report_json: Option<String> = row.get(25)?;
 
                     Ok(Mission {
                         id: parse_uuid_or_nil(&id_str),
@@ -890,6 +1083,19 @@ impl MissionStore for SqliteMissionStore {
                             .unwrap_or_default(),
                         session_id,
                         terminal_reason,
+                        locale: locale_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        tags: tags_json
+                            .and_then(|s| serde_json::from_str(&s).ok())
+                            .unwrap_or_default(),
+                        sla: sla_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        feature_flags: feature_flags_json
+                            .and_then(|s| serde_json::from_str(&s).ok())
+                            .unwrap_or_default(),
+                        plan: plan_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        step_budget: step_budget_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        priority: row.get(23)?,
+                        mode: MissionMode::parse(&mode),
+                        audit_report: audit_report_json.and_then(|s| serde_json::from_str(&s).ok()),
                     })
                 })
                 .optional()
@@ -898,25 +1104,74 @@ impl MissionStore for SqliteMissionStore {
             // Load history from events (limited to last 200 messages for performance)
             // Full history can be retrieved via get_events() if needed
             if let Some(mut m) = mission {
+                // After a compaction, history is the summary followed by the
+                // messages kept verbatim and everything logged since.
+                let compaction: Option<(i64, Option<String>, Option<String>, String)> = conn
+                    .query_row(
+                        "SELECT sequence, content, content_file, metadata
+                         FROM mission_events
+                         WHERE mission_id = ?1 AND event_type = 'history_compacted'
+                         ORDER BY sequence DESC
+                         LIMIT 1",
+                        params![&id_str],
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                    )
+                    .optional()
+                    .map_err(|e| e.to_string())?;
+                let mut history = Vec::new();
+                let mut start_sequence = 0;
+                if let Some((sequence, content, content_file, metadata)) = compaction {
+                    let kept = serde_json::from_str::<serde_json::Value>(&metadata)
+                        .ok()
+                        .and_then(|v| v["kept"].as_u64())
+                        .unwrap_or(0);
+                    start_sequence = if kept == 0 {
+                        sequence
+                    } else {
+                        conn.query_row(
+                            "SELECT sequence FROM mission_events
+                             WHERE mission_id = ?1 AND sequence < ?2
+                               AND event_type IN ('user_message', 'assistant_message')
+                             ORDER BY sequence DESC
+                             LIMIT 1 OFFSET ?3",
+                            params![&id_str, sequence, kept as i64 - 1],
+                            |row| row.get(0),
+                        )
+                        .optional()
+                        .map_err(|e| e.to_string())?
+                        .unwrap_or(0)
+                    };
+                    history.push(MissionHistoryEntry {
+                        role: crate::api::mission_compact::SUMMARY_ROLE.to_string(),
+                        content: SqliteMissionStore::load_content(
+                            content.as_deref(),
+                            content_file.as_deref(),
+                        ),
+                    });
+                }
+
                 let mut history_stmt = conn
                     .prepare(
                         "SELECT event_type, content, content_file FROM (
                              SELECT event_type, content, content_file, sequence
                              FROM mission_events
-                             WHERE mission_id = ?1 AND event_type IN ('user_message', 'assistant_message')
+                             WHERE mission_id = ?1 AND sequence >= ?2
+                               AND event_type IN ('user_message', 'assistant_message')
                              ORDER BY sequence DESC
                              LIMIT 200
                          ) ORDER BY sequence ASC",
                     )
                     .map_err(|e| e.to_string())?;
 
-                let history: Vec<MissionHistoryEntry> = history_stmt
-                    .query_map(params![&id_str], |row| {
+                let messages = history_stmt
+                    .query_map(params![&id_str, start_sequence], |row| {
                         let event_type: String = row.get(0)?;
                         let content: Option<String> = row.get(1)?;
                         let content_file: Option<String> = row.get(2)?;
-                        let full_content =
-                            SqliteMissionStore::load_content(content.as_deref(), content_file.as_deref());
+                        let full_content = SqliteMissionStore::load_content(
+                            content.as_deref(),
+                            content_file.as_deref(),
+                        );
                         Ok(MissionHistoryEntry {
                             role: if event_type == "user_message" {
                                 "user".to_string()
@@ -929,6 +1184,7 @@ impl MissionStore for SqliteMissionStore {
                     .map_err(|e| e.to_string())?
                     .collect::<Result<Vec<_>, _>>()
                     .map_err(|e| e.to_string())?;
+                history.extend(messages);
 
                 m.history = history;
                 Ok(Some(m))
@@ -977,14 +1233,23 @@ impl MissionStore for SqliteMissionStore {
             desktop_sessions: Vec::new(),
             session_id: Some(session_id.clone()),
             terminal_reason: None,
+            locale: None,
+            tags: Vec::new(),
+            sla: None,
+            feature_flags: crate::feature_flags::for_new_mission(id),
+            plan: None,
+            step_budget: None,
+            priority: 0,
+            mode: MissionMode::Standard,
+            audit_report: None,
         };
 
         let m = mission.clone();
         tokio::task::spawn_blocking(move || {
             let conn = conn.blocking_lock();
             conn.execute(
-                "INSERT INTO missions (id, status, title, workspace_id, agent, model_override, model_effort, backend, config_profile, created_at, updated_at, resumable, session_id)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                "INSERT INTO missions (id, status, title, workspace_id, agent, model_override, model_effort, backend, config_profile, created_at, updated_at, resumable, session_id, feature_flags)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
                 params![
                     m.id.to_string(),
                     status_to_string(m.status),
@@ -999,6 +1264,7 @@ impl MissionStore for SqliteMissionStore {
                     m.updated_at,
                     0,
                     m.session_id,
+                    serde_json::to_string(&m.feature_flags).map_err(|e| e.to_string())?,
                 ],
             )
             .map_err(|e| e.to_string())?;
@@ -1123,6 +1389,213 @@ impl MissionStore for SqliteMissionStore {
         .map_err(|e| e.to_string())?
     }
 
+    async fn update_mission_locale(
+        &self,
+        id: Uuid,
+        locale: Option<&LocaleSettings>,
+    ) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let locale_json = locale
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET locale = ?1, updated_at = ?2 WHERE id = ?3",
+                params![locale_json, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_tags(&self, id: Uuid, tags: &[String]) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let tags_json = if tags.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(tags).map_err(|e| e.to_string())?)
+        };
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                params![tags_json, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_sla(&self, id: Uuid, sla: Option<&MissionSla>) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let sla_json = sla
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET sla = ?1, updated_at = ?2 WHERE id = ?3",
+                params![sla_json, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_feature_flags(&self, id: Uuid, flags: &FlagSet) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let flags_json = serde_json::to_string(flags).map_err(|e| e.to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET feature_flags = ?1, updated_at = ?2 WHERE id = ?3",
+                params![flags_json, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_plan(&self, id: Uuid, plan: &MissionPlan) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let plan_json = serde_json::to_string(plan).map_err(|e| e.to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET plan = ?1, updated_at = ?2 WHERE id = ?3",
+                params![plan_json, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_step_budget(
+        &self,
+        id: Uuid,
+        budget: Option<StepBudget>,
+    ) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let budget_json = budget
+            .map(|b| serde_json::to_string(&b))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET step_budget = ?1, updated_at = ?2 WHERE id = ?3",
+                params![budget_json, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_priority(&self, id: Uuid, priority: i32) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET priority = ?1, updated_at = ?2 WHERE id = ?3",
+                params![priority, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_mode(&self, id: Uuid, mode: MissionMode) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET mode = ?1, updated_at = ?2 WHERE id = ?3",
+                params![mode.as_str(), now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_audit_report(
+        &self,
+        id: Uuid,
+        report: &AuditReport,
+    ) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let report_json = serde_json::to_string(report).map_err(|e| e.to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET audit_report = ?1, updated_at = ?2 WHERE id = ?3",
+                params![report_json, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn update_mission_model_override(
+        &self,
+        id: Uuid,
+        model_override: Option<&str>,
+    ) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let now = now_string();
+        let model_override = model_override.map(str::to_string);
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "UPDATE missions SET model_override = ?1, updated_at = ?2 WHERE id = ?3",
+                params![model_override, now, id.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
     async fn update_mission_session_id(&self, id: Uuid, session_id: &str) -> Result<(), String> {
         let conn = self.conn.clone();
         let now = now_string();
@@ -1294,6 +1767,15 @@ impl MissionStore for SqliteMissionStore {
                             .unwrap_or_default(),
                         session_id: None, // Not needed for stale mission checks
                         terminal_reason: None,
+                        locale: None,
+                        tags: Vec::new(),
+                        sla: None,
+                        feature_flags: FlagSet::new(),
+                        plan: None,
+                        step_budget: None,
+                        priority: 0,
+                        mode: MissionMode::Standard,
+                        audit_report: None,
                     })
                 })
                 .map_err(|e| e.to_string())?
@@ -1315,7 +1797,8 @@ impl MissionStore for SqliteMissionStore {
                 .prepare(
                     "SELECT id, status, title, workspace_id, workspace_name, agent, model_override,
                             created_at, updated_at, interrupted_at, resumable, desktop_sessions,
-                            COALESCE(backend, 'opencode') as backend
+                            COALESCE(backend, 'opencode') as backend, sla, feature_flags, plan,
+                            step_budget, priority, mode
                      FROM missions
                      WHERE status = 'active'",
                 )
@@ -1328,6 +1811,10 @@ impl MissionStore for SqliteMissionStore {
                     let workspace_id_str: String = row.get(3)?;
                     let desktop_sessions_json: Option<String> = row.get(11)?;
                     let backend: String = row.get(12)?;
+                    let sla_json: Option<String> = row.get(13)?;
+                    let feature_flags_json: Option<String> = row.get(14)?;
+                    let plan_json: Option<String> = row.get(15)?;
+                    let step_budget_json: Option<String> = row.get(16)?;
 
                     Ok(Mission {
                         id: parse_uuid_or_nil(&id_str),
@@ -1351,6 +1838,17 @@ impl MissionStore for SqliteMissionStore {
                             .unwrap_or_default(),
                         session_id: None,
                         terminal_reason: None,
+                        locale: None,
+                        tags: Vec::new(),
+                        sla: sla_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        feature_flags: feature_flags_json
+                            .and_then(|s| serde_json::from_str(&s).ok())
+                            .unwrap_or_default(),
+                        plan: plan_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        step_budget: step_budget_json.and_then(|s| serde_json::from_str(&s).ok()),
+                        priority: row.get(17)?,
+                        mode: MissionMode::parse(&row.get::<_, String>(18)?),
+                        audit_report: None,
                     })
                 })
                 .map_err(|e| e.to_string())?
@@ -1427,6 +1925,7 @@ impl MissionStore for SqliteMissionStore {
                 usage,
                 model,
                 model_normalized,
+                chat_options,
                 shared_files,
                 resumable,
                 ..
@@ -1443,6 +1942,7 @@ impl MissionStore for SqliteMissionStore {
                     usage,
                     model,
                     model_normalized,
+                    chat_options,
                     shared_files,
                     resumable: *resumable,
                 }),
@@ -1509,36 +2009,183 @@ impl MissionStore for SqliteMissionStore {
                 summary.clone().unwrap_or_default(),
                 serde_json::json!({ "status": status.to_string() }),
             ),
-            // Skip events that are less important for debugging
-            AgentEvent::Status { .. }
-            | AgentEvent::AgentPhase { .. }
-            | AgentEvent::AgentTree { .. }
-            | AgentEvent::Progress { .. }
-            | AgentEvent::SessionIdUpdate { .. }
-            | AgentEvent::MissionActivity { .. }
-            | AgentEvent::MissionTitleChanged { .. } => return Ok(()),
-        };
-
-        let event_type = event_type.to_string();
-        let metadata_str = metadata.to_string();
-
-        tokio::task::spawn_blocking(move || {
+            AgentEvent::DependencyAdded {
+                dependency,
+                license,
+                registry_url,
+                violation,
+                ..
+            } => (
+                "dependency_added",
+                None,
+                None,
+                None,
+                dependency.name.clone(),
+                serde_json::json!({
+                    "dependency": dependency,
+                    "license": license,
+                    "registry_url": registry_url,
+                    "violation": violation,
+                }),
+            ),
+            AgentEvent::SecurityEvent {
+                kind,
+                message,
+                details,
+                ..
+            } => (
+                "security_event",
+                None,
+                None,
+                None,
+                message.clone(),
+                serde_json::json!({ "kind": kind, "details": details }),
+            ),
+            AgentEvent::HistoryCompacted {
+                summary,
+                compacted,
+                kept,
+                ..
+            } => (
+                "history_compacted",
+                None,
+                None,
+                None,
+                summary.clone(),
+                serde_json::json!({ "compacted": compacted, "kept": kept }),
+            ),
+            AgentEvent::ContextRecovery {
+                action,
+                message,
+                details,
+                ..
+            } => (
+                "context_recovery",
+                None,
+                None,
+                None,
+                message.clone(),
+                serde_json::json!({ "action": action, "details": details }),
+            ),
+            AgentEvent::KilledProcesses {
+                processes,
+                survivors,
+                ..
+            } => (
+                "killed_processes",
+                None,
+                None,
+                None,
+                format!(
+                    "Killed {} process(es), {} still running",
+                    processes.len(),
+                    survivors.len()
+                ),
+                serde_json::json!({ "processes": processes, "survivors": survivors }),
+            ),
+            AgentEvent::ConfigWarning { message, .. } => (
+                "config_warning",
+                None,
+                None,
+                None,
+                message.clone(),
+                serde_json::json!({}),
+            ),
+            AgentEvent::ManualIntervention {
+                session_id,
+                action,
+                user,
+                content,
+                details,
+                ..
+            } => (
+                "manual_intervention",
+                None,
+                None,
+                None,
+                content.clone(),
+                serde_json::json!({
+                    "session_id": session_id,
+                    "action": action,
+                    "user": user,
+                    "details": details,
+                }),
+            ),
+            AgentEvent::SlaBreached {
+                deadline,
+                message,
+                escalations,
+                ..
+            } => (
+                "sla_breached",
+                None,
+                None,
+                None,
+                message.clone(),
+                serde_json::json!({ "deadline": deadline, "escalations": escalations }),
+            ),
+            AgentEvent::PlanUpdated { plan, .. } => (
+                "plan_updated",
+                None,
+                None,
+                None,
+                plan.render(),
+                serde_json::to_value(plan).unwrap_or_default(),
+            ),
+            // Skip events that are less important for debugging
+            AgentEvent::Status { .. }
+            | AgentEvent::AgentPhase { .. }
+            | AgentEvent::AgentTree { .. }
+            | AgentEvent::Progress { .. }
+            | AgentEvent::SessionIdUpdate { .. }
+            | AgentEvent::MissionActivity { .. }
+            | AgentEvent::MissionTitleChanged { .. } => return Ok(()),
+        };
+
+        let event_type = event_type.to_string();
+        let metadata_str = metadata.to_string();
+
+        tokio::task::spawn_blocking(move || {
             let conn = conn.blocking_lock();
 
             // If this event has an event_id that already exists for this mission,
             // update the existing row's metadata instead of inserting a duplicate.
             // This happens when a queued UserMessage is re-emitted with queued: false.
             if let Some(ref eid) = event_id {
-                let existing: Option<i64> = conn
+                let existing: Option<(i64, String, Option<String>)> = conn
                     .query_row(
-                        "SELECT id FROM mission_events WHERE mission_id = ?1 AND event_id = ?2",
+                        "SELECT id, timestamp, metadata FROM mission_events WHERE mission_id = ?1 AND event_id = ?2",
                         params![&mid, eid],
-                        |row| row.get(0),
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
                     )
                     .optional()
                     .unwrap_or(None);
 
-                if let Some(row_id) = existing {
+                if let Some((row_id, old_timestamp, old_metadata)) = existing {
+                    // Keep when a queued message was queued so queue wait can
+                    // be measured after it is re-emitted on dequeue.
+                    let metadata_str = match old_metadata
+                        .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                    {
+                        Some(old) if event_type == "user_message" => {
+                            let queued_at = old
+                                .get("queued_at")
+                                .and_then(|v| v.as_str())
+                                .map(str::to_string)
+                                .or_else(|| {
+                                    (old.get("queued").and_then(|v| v.as_bool()) == Some(true))
+                                        .then_some(old_timestamp)
+                                });
+                            match (queued_at, serde_json::from_str::<serde_json::Value>(&metadata_str)) {
+                                (Some(queued_at), Ok(mut new)) => {
+                                    new["queued_at"] = serde_json::Value::String(queued_at);
+                                    new.to_string()
+                                }
+                                _ => metadata_str,
+                            }
+                        }
+                        _ => metadata_str,
+                    };
                     let (content_inline, content_file) = SqliteMissionStore::store_content(
                         &content_dir,
                         mission_id,
@@ -1680,6 +2327,92 @@ impl MissionStore for SqliteMissionStore {
         .map_err(|e| e.to_string())?
     }
 
+    async fn get_events_before(
+        &self,
+        before: &str,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<StoredEvent>, String> {
+        let conn = self.conn.clone();
+        let before = before.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, mission_id, sequence, event_type, timestamp, event_id, tool_call_id, tool_name, content, content_file, metadata
+                     FROM mission_events
+                     WHERE id > ?1 AND timestamp < ?2
+                     ORDER BY id ASC
+                     LIMIT ?3",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![after_id, &before, limit as i64], |row| {
+                    let content: Option<String> = row.get(8)?;
+                    let content_file: Option<String> = row.get(9)?;
+                    let metadata: Option<String> = row.get(10)?;
+                    let mission_id: String = row.get(1)?;
+                    Ok(StoredEvent {
+                        id: row.get(0)?,
+                        mission_id: parse_uuid_or_nil(&mission_id),
+                        sequence: row.get(2)?,
+                        event_type: row.get(3)?,
+                        timestamp: row.get(4)?,
+                        event_id: row.get(5)?,
+                        tool_call_id: row.get(6)?,
+                        tool_name: row.get(7)?,
+                        content: Self::load_content(content.as_deref(), content_file.as_deref()),
+                        metadata: metadata
+                            .and_then(|m| serde_json::from_str(&m).ok())
+                            .unwrap_or(serde_json::json!({})),
+                    })
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn prune_events_before(&self, before: &str) -> Result<usize, String> {
+        let conn = self.conn.clone();
+        let before = before.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            let filter = "timestamp < ?1
+                 AND event_type NOT IN ('user_message', 'assistant_message')
+                 AND mission_id IN (SELECT id FROM missions
+                     WHERE status IN ('completed', 'failed', 'blocked', 'not_feasible'))";
+            let files: Vec<String> = {
+                let mut stmt = conn
+                    .prepare(&format!(
+                        "SELECT content_file FROM mission_events
+                         WHERE content_file IS NOT NULL AND {filter}"
+                    ))
+                    .map_err(|e| e.to_string())?;
+                let rows = stmt
+                    .query_map([&before], |row| row.get(0))
+                    .map_err(|e| e.to_string())?;
+                rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+            };
+            let deleted = conn
+                .execute(
+                    &format!("DELETE FROM mission_events WHERE {filter}"),
+                    [&before],
+                )
+                .map_err(|e| e.to_string())?;
+            for file in files {
+                let _ = std::fs::remove_file(file);
+            }
+            Ok(deleted)
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
     async fn get_total_cost_cents(&self) -> Result<u64, String> {
         let conn = self.conn.lock().await;
 
@@ -1843,6 +2576,269 @@ impl MissionStore for SqliteMissionStore {
         Ok((actual, estimated, unknown))
     }
 
+    async fn get_mission_costs_between(
+        &self,
+        since: &str,
+        until: &str,
+    ) -> Result<Vec<MissionCost>, String> {
+        let conn = self.conn.lock().await;
+        let query = r#"
+            WITH assistant_costs AS (
+                SELECT
+                    mission_id,
+                    CAST(
+                        COALESCE(
+                            json_extract(metadata, '$.cost.amount_cents'),
+                            json_extract(metadata, '$.cost_cents'),
+                            0
+                        ) AS INTEGER
+                    ) AS raw_cost
+                FROM mission_events
+                WHERE event_type = 'assistant_message'
+                  AND timestamp >= ?1
+                  AND timestamp < ?2
+            )
+            SELECT
+                c.mission_id,
+                m.workspace_id,
+                m.tags,
+                SUM(CASE WHEN c.raw_cost > 0 THEN c.raw_cost ELSE 0 END) AS total
+            FROM assistant_costs c
+            JOIN missions m ON m.id = c.mission_id
+            GROUP BY c.mission_id
+            HAVING total > 0
+        "#;
+        let mut stmt = conn.prepare(query).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([since, until], |row| {
+                let mission_id: String = row.get(0)?;
+                let workspace_id: String = row.get(1)?;
+                let tags: Option<String> = row.get(2)?;
+                let total: i64 = row.get(3)?;
+                Ok(MissionCost {
+                    mission_id: parse_uuid_or_nil(&mission_id),
+                    workspace_id: Uuid::parse_str(&workspace_id)
+                        .unwrap_or(crate::workspace::DEFAULT_WORKSPACE_ID),
+                    tags: tags
+                        .and_then(|s| serde_json::from_str(&s).ok())
+                        .unwrap_or_default(),
+                    cost_cents: total.max(0) as u64,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_token_usage_between(&self, since: &str, until: &str) -> Result<u64, String> {
+        let conn = self.conn.lock().await;
+        let query = r#"
+            SELECT COALESCE(SUM(
+                COALESCE(CAST(json_extract(metadata, '$.usage.input_tokens') AS INTEGER), 0)
+                + COALESCE(CAST(json_extract(metadata, '$.usage.output_tokens') AS INTEGER), 0)
+            ), 0)
+            FROM mission_events
+            WHERE event_type = 'assistant_message'
+              AND timestamp >= ?1
+              AND timestamp < ?2
+        "#;
+        let total: i64 = conn
+            .query_row(query, [since, until], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        Ok(total.max(0) as u64)
+    }
+
+    async fn create_mission_batch(&self, batch: MissionBatch) -> Result<MissionBatch, String> {
+        let conn = self.conn.clone();
+        let mission_ids = serde_json::to_string(&batch.mission_ids).map_err(|e| e.to_string())?;
+        let b = batch.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "INSERT INTO mission_batches (id, title, mission_ids, created_at) VALUES (?, ?, ?, ?)",
+                params![b.id.to_string(), b.title, mission_ids, b.created_at],
+            )
+            .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+
+        Ok(batch)
+    }
+
+    async fn get_mission_batch(&self, id: Uuid) -> Result<Option<MissionBatch>, String> {
+        let conn = self.conn.clone();
+        let id_str = id.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            let row = conn
+                .query_row(
+                    "SELECT title, mission_ids, created_at FROM mission_batches WHERE id = ?",
+                    [id_str],
+                    |row| {
+                        Ok((
+                            row.get::<_, Option<String>>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    },
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+
+            row.map(|(title, mission_ids, created_at)| {
+                Ok(MissionBatch {
+                    id,
+                    title,
+                    mission_ids: serde_json::from_str(&mission_ids).map_err(|e| e.to_string())?,
+                    created_at,
+                })
+            })
+            .transpose()
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn append_mission_result(
+        &self,
+        mission_id: Uuid,
+        result: MissionResult,
+    ) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let data = result
+            .data
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "INSERT INTO mission_results (mission_id, processor, data, error, created_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![
+                    mission_id.to_string(),
+                    result.processor,
+                    data,
+                    result.error,
+                    result.created_at
+                ],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn get_mission_results(&self, mission_id: Uuid) -> Result<Vec<MissionResult>, String> {
+        let conn = self.conn.clone();
+        let id_str = mission_id.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            let mut stmt = conn
+                .prepare(
+                    "SELECT processor, data, error, created_at FROM mission_results
+                     WHERE mission_id = ? ORDER BY id",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([id_str], |row| {
+                    let data: Option<String> = row.get(1)?;
+                    Ok(MissionResult {
+                        processor: row.get(0)?,
+                        data: data.and_then(|d| serde_json::from_str(&d).ok()),
+                        error: row.get(2)?,
+                        created_at: row.get(3)?,
+                    })
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn upsert_mission_daemon(&self, daemon: &MissionDaemon) -> Result<(), String> {
+        let conn = self.conn.clone();
+        let config = serde_json::to_string(daemon).map_err(|e| e.to_string())?;
+        let state = serde_json::to_value(daemon.state)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let mission_id = daemon.mission_id.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            conn.execute(
+                "INSERT INTO mission_daemons (mission_id, state, config, updated_at)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT(mission_id) DO UPDATE SET
+                    state = excluded.state,
+                    config = excluded.config,
+                    updated_at = excluded.updated_at",
+                params![mission_id, state, config, now_string()],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn get_mission_daemon(&self, mission_id: Uuid) -> Result<Option<MissionDaemon>, String> {
+        let conn = self.conn.clone();
+        let id_str = mission_id.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            let config: Option<String> = conn
+                .query_row(
+                    "SELECT config FROM mission_daemons WHERE mission_id = ?",
+                    [id_str],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+            config
+                .map(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))
+                .transpose()
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn list_mission_daemons(&self) -> Result<Vec<MissionDaemon>, String> {
+        let conn = self.conn.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.blocking_lock();
+            let mut stmt = conn
+                .prepare("SELECT config FROM mission_daemons ORDER BY updated_at DESC")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(|e| e.to_string())?;
+            let mut daemons = Vec::new();
+            for config in rows {
+                let config = config.map_err(|e| e.to_string())?;
+                match serde_json::from_str(&config) {
+                    Ok(daemon) => daemons.push(daemon),
+                    Err(e) => tracing::warn!("Skipping unreadable mission daemon: {}", e),
+                }
+            }
+            Ok(daemons)
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
     async fn create_automation(&self, automation: Automation) -> Result<Automation, String> {
         let conn = self.conn.clone();
 
@@ -2359,10 +3355,12 @@ impl MissionStore for SqliteMissionStore {
 mod tests {
     use super::{assistant_message_metadata, AssistantMessageMetadataInput, SqliteMissionStore};
     use crate::agents::CostSource;
+    use crate::api::control::AgentEvent;
     use crate::api::mission_store::MissionStore;
     use crate::cost::TokenUsage;
     use rusqlite::params;
     use serde_json::json;
+    use uuid::Uuid;
 
     #[test]
     fn assistant_message_metadata_uses_normalized_cost_shape() {
@@ -2378,6 +3376,7 @@ mod tests {
             }),
             model: &Some("gpt-4o".to_string()),
             model_normalized: &Some("gpt-4o".to_string()),
+            chat_options: &None,
             shared_files: &None,
             resumable: false,
         });
@@ -2413,6 +3412,7 @@ mod tests {
             usage: &None,
             model: &None,
             model_normalized: &None,
+            chat_options: &None,
             shared_files: &None,
             resumable: false,
         });
@@ -2492,6 +3492,81 @@ mod tests {
         assert_eq!(total, 180);
     }
 
+    #[tokio::test]
+    async fn history_after_compaction_starts_with_summary() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let store = SqliteMissionStore::new(temp_dir.path().to_path_buf(), "test-user")
+            .await
+            .expect("sqlite store");
+        let mission = store
+            .create_mission(Some("Long mission"), None, None, None, None, None, None)
+            .await
+            .expect("mission");
+
+        let conn = store.conn.lock().await;
+        let messages = [
+            "user_message",
+            "assistant_message",
+            "user_message",
+            "tool_call",
+            "assistant_message",
+        ];
+        for (i, event_type) in messages.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO mission_events (mission_id, sequence, event_type, timestamp, content, metadata)
+                 VALUES (?1, ?2, ?3, '2026-01-01T00:00:00Z', ?4, '{}')",
+                params![mission.id.to_string(), i as i64 + 1, event_type, format!("m{}", i + 1)],
+            )
+            .expect("insert message");
+        }
+        drop(conn);
+
+        store
+            .log_event(
+                mission.id,
+                &AgentEvent::HistoryCompacted {
+                    summary: "earlier work".to_string(),
+                    compacted: 2,
+                    kept: 2,
+                    mission_id: mission.id,
+                },
+            )
+            .await
+            .expect("log compaction");
+        store
+            .log_event(
+                mission.id,
+                &AgentEvent::UserMessage {
+                    id: Uuid::new_v4(),
+                    content: "m7".to_string(),
+                    queued: false,
+                    mission_id: Some(mission.id),
+                },
+            )
+            .await
+            .expect("log message");
+
+        let history = store
+            .get_mission(mission.id)
+            .await
+            .expect("get mission")
+            .expect("mission exists")
+            .history;
+        let entries: Vec<_> = history
+            .iter()
+            .map(|e| (e.role.as_str(), e.content.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("summary", "earlier work"),
+                ("user", "m3"),
+                ("assistant", "m5"),
+                ("user", "m7")
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn get_total_cost_cents_clamps_negative_values_to_zero() {
         let temp_dir = tempfile::tempdir().expect("temp dir");
@@ -2735,4 +3810,138 @@ mod tests {
         assert_eq!(estimated, 15);
         assert_eq!(unknown, 0);
     }
+
+    #[tokio::test]
+    async fn prune_events_keeps_messages_and_running_missions() {
+        use crate::api::control::MissionStatus;
+
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let store = SqliteMissionStore::new(temp_dir.path().to_path_buf(), "test-user")
+            .await
+            .expect("sqlite store");
+        let done = store
+            .create_mission(Some("Done"), None, None, None, None, None, None)
+            .await
+            .expect("mission");
+        store
+            .update_mission_status(done.id, MissionStatus::Completed)
+            .await
+            .expect("status");
+        let running = store
+            .create_mission(Some("Running"), None, None, None, None, None, None)
+            .await
+            .expect("mission");
+
+        let conn = store.conn.lock().await;
+        let rows = [
+            (done.id, 1i64, "user_message", "2026-01-01T00:00:00+00:00"),
+            (done.id, 2, "tool_call", "2026-01-01T00:00:01+00:00"),
+            (done.id, 3, "tool_result", "2026-03-01T00:00:00+00:00"),
+            (running.id, 1, "tool_call", "2026-01-01T00:00:00+00:00"),
+        ];
+        for (mission_id, sequence, event_type, timestamp) in rows {
+            conn.execute(
+                "INSERT INTO mission_events (mission_id, sequence, event_type, timestamp)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![mission_id.to_string(), sequence, event_type, timestamp],
+            )
+            .expect("insert event");
+        }
+        drop(conn);
+
+        let deleted = store
+            .prune_events_before("2026-02-01T00:00:00+00:00")
+            .await
+            .expect("prune");
+        assert_eq!(deleted, 1);
+        let remaining: Vec<String> = store
+            .get_events(done.id, None, None, None)
+            .await
+            .expect("events")
+            .into_iter()
+            .map(|e| e.event_type)
+            .collect();
+        assert_eq!(remaining, vec!["user_message", "tool_result"]);
+        assert_eq!(
+            store
+                .get_events(running.id, None, None, None)
+                .await
+                .expect("events")
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn mission_costs_are_grouped_by_mission_within_range() {
+        let temp_dir = tempfile::tempdir().expect("temp dir");
+        let store = SqliteMissionStore::new(temp_dir.path().to_path_buf(), "test-user")
+            .await
+            .expect("sqlite store");
+        let tagged = store
+            .create_mission(Some("Tagged"), None, None, None, None, None, None)
+            .await
+            .expect("mission");
+        store
+            .update_mission_tags(tagged.id, &["team-a".to_string()])
+            .await
+            .expect("tags");
+        let other = store
+            .create_mission(Some("Other"), None, None, None, None, None, None)
+            .await
+            .expect("mission");
+
+        let conn = store.conn.lock().await;
+        let rows = [
+            (
+                tagged.id,
+                1i64,
+                r#"{"cost":{"amount_cents":40}}"#,
+                "2026-10-02T00:00:00+00:00",
+            ),
+            (
+                tagged.id,
+                2,
+                r#"{"cost_cents":2,"usage":{"input_tokens":10,"output_tokens":5}}"#,
+                "2026-10-03T00:00:00+00:00",
+            ),
+            (
+                tagged.id,
+                3,
+                r#"{"cost_cents":500}"#,
+                "2026-09-30T23:59:59+00:00",
+            ),
+            (
+                other.id,
+                1,
+                r#"{"cost_cents":0}"#,
+                "2026-10-02T00:00:00+00:00",
+            ),
+        ];
+        for (mission_id, sequence, metadata, timestamp) in rows {
+            conn.execute(
+                "INSERT INTO mission_events (mission_id, sequence, event_type, timestamp, metadata)
+                 VALUES (?1, ?2, 'assistant_message', ?3, ?4)",
+                params![mission_id.to_string(), sequence, timestamp, metadata],
+            )
+            .expect("insert event");
+        }
+        drop(conn);
+
+        let costs = store
+            .get_mission_costs_between("2026-10-01T00:00:00+00:00", "2026-11-01T00:00:00+00:00")
+            .await
+            .expect("costs");
+        assert_eq!(costs.len(), 1);
+        assert_eq!(costs[0].mission_id, tagged.id);
+        assert_eq!(costs[0].tags, vec!["team-a".to_string()]);
+        assert_eq!(costs[0].cost_cents, 42);
+        assert_eq!(
+            store
+                .get_token_usage_between("2026-10-01T00:00:00+00:00", "2026-11-01T00:00:00+00:00")
+                .await
+                .expect("tokens"),
+            15
+        );
+    }
 }
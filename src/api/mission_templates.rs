@@ -0,0 +1,264 @@
+//! Mission template instantiation.
+//!
+//! Templates are stored in the library (`mission-template/*.json`, CRUD under
+//! `/api/library/mission-template`). `POST /api/control/mission-templates/:name/instantiate`
+//! renders the template prompt with the supplied params, creates a mission with
+//! the template's agent/backend/model settings and starts it. The template's
+//! `budget_cents` becomes the mission's `step_budget.max_cost_cents`, enforced
+//! by the step budget supervisor (`api::step_budget`).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::auth::AuthUser;
+use super::control::{control_for_user, prepare_mission, CreateMissionRequest};
+use super::mission_batch::dispatch_missions;
+use super::mission_store::Mission;
+use super::routes::AppState;
+use super::step_budget::StepBudget;
+use crate::library::MissionTemplate;
+use crate::util::not_found_or_internal;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstantiateTemplateRequest {
+    /// Values for the template's `<param/>` placeholders.
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    /// Workspace to run in; defaults to a workspace built from the template's
+    /// `workspace_template`, or the host workspace when none is set.
+    pub workspace_id: Option<Uuid>,
+    pub title: Option<String>,
+    /// Start the mission immediately (default: true).
+    pub start: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstantiateTemplateResponse {
+    pub mission: Mission,
+    pub prompt: String,
+    pub started: bool,
+}
+
+/// Pick the workspace for a template mission.
+fn resolve_workspace(
+    template: &MissionTemplate,
+    requested: Option<Uuid>,
+    workspaces: &[crate::workspace::Workspace],
+) -> Result<Option<Uuid>, String> {
+    if requested.is_some() {
+        return Ok(requested);
+    }
+    let Some(wanted) = template.workspace_template.as_deref() else {
+        return Ok(None);
+    };
+    let matching: Vec<_> = workspaces
+        .iter()
+        .filter(|w| w.template.as_deref() == Some(wanted))
+        .collect();
+    match matching[..] {
+        [workspace] => Ok(Some(workspace.id)),
+        [] => Err(format!(
+            "No workspace created from template '{}'; create one or pass workspace_id",
+            wanted
+        )),
+        _ => Err(format!(
+            "{} workspaces were created from template '{}' ({}); pass workspace_id",
+            matching.len(),
+            wanted,
+            matching
+                .iter()
+                .map(|w| w.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Instantiate a mission template.
+pub async fn instantiate_mission_template(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(name): Path<String>,
+    body: Option<Json<InstantiateTemplateRequest>>,
+) -> Result<Json<InstantiateTemplateResponse>, (StatusCode, String)> {
+    let req = body.map(|Json(b)| b).unwrap_or_default();
+
+    let library = state.library.read().await.clone().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Library not initialized".to_string(),
+        )
+    })?;
+    let template = library
+        .get_mission_template(&name)
+        .await
+        .map_err(not_found_or_internal)?;
+
+    let prompt = template
+        .render_prompt(&req.params)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let workspaces = state.workspaces.list().await;
+    let workspace_id = resolve_workspace(&template, req.workspace_id, &workspaces)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let request = CreateMissionRequest {
+        title: req
+            .title
+            .clone()
+            .or_else(|| template.description.clone())
+            .or_else(|| Some(template.name.clone())),
+        workspace_id,
+        agent: template.agent.clone(),
+        model_override: template.model_override.clone(),
+        model_effort: template.model_effort.clone(),
+        reasoning: None,
+        config_profile: template.config_profile.clone(),
+        backend: template.backend.clone(),
+        locale: None,
+        tags: Vec::new(),
+        sla: None,
+        feature_flags: Default::default(),
+        step_budget: template.budget_cents.map(|budget_cents| StepBudget {
+            max_cost_cents: Some(budget_cents),
+            ..Default::default()
+        }),
+        priority: None,
+        mode: None,
+    };
+    let prepared = prepare_mission(&state, Some(&request)).await?;
+
+    let control = control_for_user(&state, &user).await;
+    super::budget::check_cap(
+        &control.mission_store,
+        prepared.workspace_id,
+        &prepared.tags,
+    )
+    .await?;
+    let mut mission = control
+        .mission_store
+        .create_mission(
+            prepared.title.as_deref(),
+            prepared.workspace_id,
+            prepared.agent.as_deref(),
+            prepared.model_override.as_deref(),
+            prepared.model_effort.as_deref(),
+            prepared.backend.as_deref(),
+            prepared.config_profile.as_deref(),
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if let Some(budget) = prepared.step_budget {
+        control
+            .mission_store
+            .update_mission_step_budget(mission.id, Some(budget))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        mission.step_budget = Some(budget);
+    }
+
+    tracing::info!(
+        template = %name,
+        mission_id = %mission.id,
+        budget_cents = ?template.budget_cents,
+        "Instantiated mission template"
+    );
+
+    let started = req.start.unwrap_or(true);
+    if started {
+        tokio::spawn(dispatch_missions(
+            control,
+            format!("template {}", name),
+            vec![(mission.id, prompt.clone())],
+        ));
+    }
+
+    Ok(Json(InstantiateTemplateResponse {
+        mission,
+        prompt,
+        started,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::MissionTemplateParam;
+    use crate::workspace::Workspace;
+    use std::path::PathBuf;
+
+    fn template() -> MissionTemplate {
+        serde_json::from_value(serde_json::json!({
+            "name": "bump-dep",
+            "prompt": "Bump <crate/> to <version/> in <repo/>.",
+            "params": [
+                {"name": "crate", "required": true},
+                {"name": "version", "default": "latest"},
+                {"name": "repo"}
+            ],
+            "workspace_template": "rust-dev",
+            "verification": ["cargo test passes"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_prompt_applies_defaults_and_checklist() {
+        let params = HashMap::from([("crate".to_string(), "serde".to_string())]);
+        let prompt = template().render_prompt(&params).unwrap();
+        assert!(prompt.starts_with("Bump serde to latest in ."));
+        assert!(prompt.contains("- [ ] cargo test passes"));
+
+        let err = template().render_prompt(&HashMap::new()).unwrap_err();
+        assert!(err.contains("crate"));
+    }
+
+    #[test]
+    fn test_validate_rejects_duplicate_params() {
+        let mut t = template();
+        t.params.push(MissionTemplateParam {
+            name: "crate".to_string(),
+            description: None,
+            default: None,
+            required: false,
+        });
+        assert!(t.validate().is_err());
+        assert!(template().validate().is_ok());
+
+        let mut t = template();
+        t.budget_cents = Some(0);
+        assert!(t.validate().is_err());
+    }
+
+    #[test]
+    fn test_resolve_workspace() {
+        let t = template();
+        let explicit = Uuid::new_v4();
+        assert_eq!(
+            resolve_workspace(&t, Some(explicit), &[]).unwrap(),
+            Some(explicit)
+        );
+        assert!(resolve_workspace(&t, None, &[]).is_err());
+
+        let workspace = |name: &str| {
+            let mut w = Workspace::new_container(name.to_string(), PathBuf::from("/tmp"));
+            w.template = Some("rust-dev".to_string());
+            w
+        };
+        let one = workspace("one");
+        assert_eq!(
+            resolve_workspace(&t, None, std::slice::from_ref(&one)).unwrap(),
+            Some(one.id)
+        );
+        let err = resolve_workspace(&t, None, &[one, workspace("two")]).unwrap_err();
+        assert!(err.contains("one, two"), "{}", err);
+    }
+}
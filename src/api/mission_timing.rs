@@ -0,0 +1,359 @@
+//! Per-turn timing breakdown of a mission.
+//!
+//! Derived from the mission's event log: a turn runs from the un-queued
+//! `user_message` to the next `assistant_message`. Tool time is the wall-clock
+//! time covered by `tool_call` → `tool_result` pairs (overlapping calls count
+//! once); the rest of the turn is attributed to the model (LLM latency plus
+//! harness overhead). Queue wait is the time a message spent queued behind
+//! another turn. Exposed as `timing` on `GET /api/control/missions/:id`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::control::MissionStatus;
+use super::mission_store::{Mission, MissionStore, StoredEvent};
+
+/// Event types the breakdown is computed from.
+const TIMING_EVENT_TYPES: &[&str] = &[
+    "user_message",
+    "assistant_message",
+    "tool_call",
+    "tool_result",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolTiming {
+    pub calls: u32,
+    pub total_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnTiming {
+    /// ID of the user message that started the turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    pub started_at: String,
+    /// Unset while the turn is running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<String>,
+    pub queue_wait_ms: u64,
+    pub total_ms: u64,
+    pub llm_ms: u64,
+    pub tool_ms: u64,
+    pub tools: BTreeMap<String, ToolTiming>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MissionTiming {
+    pub total_ms: u64,
+    pub queue_wait_ms: u64,
+    pub llm_ms: u64,
+    pub tool_ms: u64,
+    /// Time per tool, summed over all turns.
+    pub tools: BTreeMap<String, ToolTiming>,
+    pub turns: Vec<TurnTiming>,
+}
+
+fn parse_ts(ts: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(ts)
+        .ok()
+        .map(|t| t.timestamp_millis())
+}
+
+fn elapsed(from: i64, to: i64) -> u64 {
+    u64::try_from(to - from).unwrap_or(0)
+}
+
+/// Milliseconds covered by the union of `intervals`.
+fn covered_ms(mut intervals: Vec<(i64, i64)>) -> u64 {
+    intervals.sort_unstable();
+    let mut total = 0;
+    let mut current: Option<(i64, i64)> = None;
+    for (start, end) in intervals {
+        current = match current {
+            Some((s, e)) if start <= e => Some((s, e.max(end))),
+            Some((s, e)) => {
+                total += elapsed(s, e);
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+    if let Some((s, e)) = current {
+        total += elapsed(s, e);
+    }
+    total
+}
+
+struct OpenTurn {
+    message_id: Option<String>,
+    started_at: String,
+    start: i64,
+    last: i64,
+    queue_wait_ms: u64,
+    intervals: Vec<(i64, i64)>,
+    open_tools: HashMap<String, (String, i64)>,
+    tools: BTreeMap<String, ToolTiming>,
+}
+
+impl OpenTurn {
+    /// Finish the turn at `end`; tools still running are cut off there.
+    fn close(mut self, end: i64, ended_at: Option<String>) -> TurnTiming {
+        for (name, start) in std::mem::take(&mut self.open_tools).into_values() {
+            self.record_tool(name, start, end);
+        }
+        let total_ms = elapsed(self.start, end);
+        let tool_ms = covered_ms(self.intervals).min(total_ms);
+        TurnTiming {
+            message_id: self.message_id,
+            started_at: self.started_at,
+            ended_at,
+            queue_wait_ms: self.queue_wait_ms,
+            total_ms,
+            llm_ms: total_ms - tool_ms,
+            tool_ms,
+            tools: self.tools,
+        }
+    }
+
+    fn record_tool(&mut self, name: String, start: i64, end: i64) {
+        let entry = self.tools.entry(name).or_default();
+        entry.calls += 1;
+        entry.total_ms += elapsed(start, end);
+        self.intervals.push((start, end.max(start)));
+    }
+}
+
+/// Timing breakdown of the turns in `events`. A turn still open at the end is
+/// measured up to `now` when the mission is running, otherwise up to its last
+/// event.
+pub fn compute(events: &[StoredEvent], running: bool, now: DateTime<Utc>) -> MissionTiming {
+    let mut ordered: Vec<(i64, &StoredEvent)> = events
+        .iter()
+        .filter_map(|e| parse_ts(&e.timestamp).map(|ts| (ts, e)))
+        .collect();
+    // Queued messages keep their original sequence but carry the dequeue time,
+    // so order by time; at equal times, let the previous turn end first.
+    ordered.sort_by_key(|(ts, e)| (*ts, e.event_type != "assistant_message", e.sequence));
+
+    let mut turns = Vec::new();
+    let mut open: Option<OpenTurn> = None;
+    for (ts, event) in ordered {
+        match event.event_type.as_str() {
+            "user_message" => {
+                if event.metadata.get("queued").and_then(|v| v.as_bool()) == Some(true) {
+                    continue;
+                }
+                if let Some(turn) = open.take() {
+                    let last = turn.last;
+                    turns.push(turn.close(last, None));
+                }
+                let queue_wait_ms = event
+                    .metadata
+                    .get("queued_at")
+                    .and_then(|v| v.as_str())
+                    .and_then(parse_ts)
+                    .map(|queued| elapsed(queued, ts))
+                    .unwrap_or(0);
+                open = Some(OpenTurn {
+                    message_id: event.event_id.clone(),
+                    started_at: event.timestamp.clone(),
+                    start: ts,
+                    last: ts,
+                    queue_wait_ms,
+                    intervals: Vec::new(),
+                    open_tools: HashMap::new(),
+                    tools: BTreeMap::new(),
+                });
+            }
+            "tool_call" => {
+                if let (Some(turn), Some(id)) = (open.as_mut(), event.tool_call_id.as_ref()) {
+                    let name = event.tool_name.clone().unwrap_or_default();
+                    turn.open_tools.insert(id.clone(), (name, ts));
+                    turn.last = ts;
+                }
+            }
+            "tool_result" => {
+                if let Some(turn) = open.as_mut() {
+                    let started = event
+                        .tool_call_id
+                        .as_ref()
+                        .and_then(|id| turn.open_tools.remove(id));
+                    if let Some((name, start)) = started {
+                        turn.record_tool(name, start, ts);
+                    }
+                    turn.last = ts;
+                }
+            }
+            "assistant_message" => {
+                if let Some(turn) = open.take() {
+                    turns.push(turn.close(ts, Some(event.timestamp.clone())));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(turn) = open {
+        let end = if running {
+            now.timestamp_millis()
+        } else {
+            turn.last
+        };
+        turns.push(turn.close(end, None));
+    }
+
+    let mut timing = MissionTiming::default();
+    for turn in &turns {
+        timing.total_ms += turn.total_ms;
+        timing.queue_wait_ms += turn.queue_wait_ms;
+        timing.llm_ms += turn.llm_ms;
+        timing.tool_ms += turn.tool_ms;
+        for (name, tool) in &turn.tools {
+            let entry = timing.tools.entry(name.clone()).or_default();
+            entry.calls += tool.calls;
+            entry.total_ms += tool.total_ms;
+        }
+    }
+    timing.turns = turns;
+    timing
+}
+
+/// Timing breakdown of a stored mission, if it has any turns.
+pub async fn mission_timing(
+    store: &Arc<dyn MissionStore>,
+    mission: &Mission,
+) -> Option<MissionTiming> {
+    let events = match store
+        .get_events(mission.id, Some(TIMING_EVENT_TYPES), None, None)
+        .await
+    {
+        Ok(events) => events,
+        Err(e) => {
+            tracing::warn!(mission_id = %mission.id, error = %e, "Failed to load events for timing");
+            return None;
+        }
+    };
+    let timing = compute(&events, mission.status == MissionStatus::Active, Utc::now());
+    (!timing.turns.is_empty()).then_some(timing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn event(
+        sequence: i64,
+        event_type: &str,
+        secs: i64,
+        tool: Option<(&str, &str)>,
+        metadata: serde_json::Value,
+    ) -> StoredEvent {
+        StoredEvent {
+            id: sequence,
+            mission_id: Uuid::nil(),
+            sequence,
+            event_type: event_type.to_string(),
+            timestamp: DateTime::from_timestamp(1_700_000_000 + secs, 0)
+                .unwrap()
+                .to_rfc3339(),
+            event_id: Some(format!("e{}", sequence)),
+            tool_call_id: tool.map(|(id, _)| id.to_string()),
+            tool_name: tool.map(|(_, name)| name.to_string()),
+            content: String::new(),
+            metadata,
+        }
+    }
+
+    #[test]
+    fn splits_turn_into_tool_and_model_time() {
+        let events = vec![
+            event(
+                1,
+                "user_message",
+                0,
+                None,
+                serde_json::json!({ "queued": false }),
+            ),
+            event(
+                2,
+                "tool_call",
+                2,
+                Some(("a", "bash")),
+                serde_json::json!({}),
+            ),
+            event(
+                3,
+                "tool_call",
+                3,
+                Some(("b", "read")),
+                serde_json::json!({}),
+            ),
+            event(
+                4,
+                "tool_result",
+                5,
+                Some(("b", "read")),
+                serde_json::json!({}),
+            ),
+            event(
+                5,
+                "tool_result",
+                6,
+                Some(("a", "bash")),
+                serde_json::json!({}),
+            ),
+            event(6, "assistant_message", 10, None, serde_json::json!({})),
+        ];
+        let timing = compute(&events, false, Utc::now());
+        assert_eq!(timing.turns.len(), 1);
+        let turn = &timing.turns[0];
+        assert_eq!(turn.total_ms, 10_000);
+        // bash 2s–6s and read 3s–5s overlap: 4s of tool time.
+        assert_eq!(turn.tool_ms, 4_000);
+        assert_eq!(turn.llm_ms, 6_000);
+        assert_eq!(
+            turn.tools["bash"],
+            ToolTiming {
+                calls: 1,
+                total_ms: 4_000
+            }
+        );
+        assert_eq!(timing.tools["read"].total_ms, 2_000);
+    }
+
+    #[test]
+    fn measures_queue_wait_of_dequeued_message() {
+        let queued_at = DateTime::from_timestamp(1_700_000_005, 0)
+            .unwrap()
+            .to_rfc3339();
+        let events = vec![
+            event(
+                1,
+                "user_message",
+                0,
+                None,
+                serde_json::json!({ "queued": false }),
+            ),
+            // Queued at 5s, re-emitted (and re-timestamped) when it started at 20s.
+            event(
+                2,
+                "user_message",
+                20,
+                None,
+                serde_json::json!({ "queued": false, "queued_at": queued_at }),
+            ),
+            event(3, "assistant_message", 20, None, serde_json::json!({})),
+            event(4, "assistant_message", 30, None, serde_json::json!({})),
+        ];
+        let timing = compute(&events, false, Utc::now());
+        assert_eq!(timing.turns.len(), 2);
+        assert_eq!(timing.turns[0].total_ms, 20_000);
+        assert_eq!(timing.turns[1].queue_wait_ms, 15_000);
+        assert_eq!(timing.turns[1].total_ms, 10_000);
+        assert_eq!(timing.queue_wait_ms, 15_000);
+    }
+}
@@ -0,0 +1,336 @@
+//! Automatic mission titles.
+//!
+//! A background task per control session watches mission events. When an
+//! untitled mission starts its first turn, a cheap model writes a concise
+//! title from the prompt. When the mission finishes, the title is rewritten
+//! from the conversation and tagged with the outcome, e.g.
+//! "Fix flaky auth tests (completed)". Titles set through the API are never
+//! touched. If the model is unavailable the history-based title written after
+//! the first turn is kept, and only the outcome tag is added.
+//!
+//! Disable with `SANDBOXED_SH_AUTO_TITLE=false`; the model defaults to
+//! `builtin/cheap` and can be changed with `SANDBOXED_SH_TITLE_MODEL`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::control::{AgentEvent, MissionStatus};
+use super::mission_store::MissionStore;
+use super::skill_test::config_proxy_completion;
+use crate::config::Config;
+use crate::tools::safe_truncate_index;
+
+/// Model (or chain) that writes titles unless configured otherwise.
+const DEFAULT_TITLE_MODEL: &str = "builtin/cheap";
+/// Longest title kept from a model reply.
+const MAX_TITLE_CHARS: usize = 80;
+/// Characters of each message shown to the model.
+const MAX_EXCERPT_CHARS: usize = 2_000;
+
+const TITLE_INSTRUCTIONS: &str = "You name tasks given to a coding agent. Reply with a concise title \
+of at most 8 words in the imperative mood (e.g. \"Fix flaky auth tests\"). No quotes, no trailing period.";
+
+const RETITLE_INSTRUCTIONS: &str =
+    "You name finished tasks of a coding agent. From the request and the \
+agent's final answer, reply with a concise title of at most 8 words describing what was done \
+(e.g. \"Fix flaky auth tests\"). No quotes, no trailing period, no status.";
+
+pub fn auto_title_enabled() -> bool {
+    crate::util::env_var_bool("SANDBOXED_SH_AUTO_TITLE", true)
+}
+
+fn title_model() -> String {
+    std::env::var("SANDBOXED_SH_TITLE_MODEL")
+        .ok()
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TITLE_MODEL.to_string())
+}
+
+/// Outcome tag appended to the title of a finished mission.
+fn outcome_label(status: MissionStatus) -> Option<&'static str> {
+    match status {
+        MissionStatus::Completed => Some("completed"),
+        MissionStatus::Failed => Some("failed"),
+        MissionStatus::Blocked => Some("blocked"),
+        MissionStatus::NotFeasible => Some("not feasible"),
+        MissionStatus::Pending | MissionStatus::Active | MissionStatus::Interrupted => None,
+    }
+}
+
+/// `title` without an outcome tag added by a previous retitle.
+fn strip_outcome(title: &str) -> &str {
+    let trimmed = title.trim_end();
+    for label in ["completed", "failed", "blocked", "not feasible"] {
+        if let Some(base) = trimmed
+            .strip_suffix(')')
+            .and_then(|t| t.strip_suffix(label))
+            .and_then(|t| t.strip_suffix(" ("))
+        {
+            return base.trim_end();
+        }
+    }
+    trimmed
+}
+
+fn with_outcome(title: &str, label: &str) -> String {
+    format!("{} ({})", strip_outcome(title), label)
+}
+
+/// First line of a model reply, without quotes or a trailing period.
+fn clean_title(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .trim_start_matches(['#', '*', ' '])
+        .trim_start_matches("Title:")
+        .trim()
+        .trim_matches(['"', '\'', '`', '*'])
+        .trim_end_matches('.')
+        .trim();
+    if line.is_empty() {
+        return None;
+    }
+    let title = match line.char_indices().nth(MAX_TITLE_CHARS) {
+        Some((idx, _)) => format!("{}…", line[..idx].trim_end()),
+        None => line.to_string(),
+    };
+    Some(strip_outcome(&title).to_string())
+}
+
+fn excerpt(text: &str) -> &str {
+    &text[..safe_truncate_index(text, MAX_EXCERPT_CHARS)]
+}
+
+/// Which missions the worker may title.
+#[derive(Default)]
+struct Titles {
+    /// Missions that started untitled, with the last title written here.
+    auto: HashMap<Uuid, Option<String>>,
+    /// Missions already checked that keep their own title.
+    skip: HashSet<Uuid>,
+}
+
+#[derive(Clone)]
+struct TitleWorker {
+    config: Config,
+    store: Arc<dyn MissionStore>,
+    events_tx: broadcast::Sender<AgentEvent>,
+    titles: Arc<Mutex<Titles>>,
+}
+
+/// Start the title worker of a control session.
+pub fn spawn_title_worker(
+    config: Config,
+    store: Arc<dyn MissionStore>,
+    events_tx: broadcast::Sender<AgentEvent>,
+) {
+    let worker = TitleWorker {
+        config,
+        store,
+        events_tx: events_tx.clone(),
+        titles: Arc::new(Mutex::new(Titles::default())),
+    };
+    let mut rx = events_tx.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => worker.observe(&event),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Mission title worker lagged by {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+impl TitleWorker {
+    fn titles(&self) -> std::sync::MutexGuard<'_, Titles> {
+        self.titles.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn observe(&self, event: &AgentEvent) {
+        match event {
+            AgentEvent::UserMessage {
+                content,
+                queued: false,
+                mission_id: Some(mid),
+                ..
+            } => {
+                let mid = *mid;
+                {
+                    let mut titles = self.titles();
+                    if titles.auto.contains_key(&mid) || !titles.skip.insert(mid) {
+                        return;
+                    }
+                }
+                let worker = self.clone();
+                let prompt = content.clone();
+                tokio::spawn(async move { worker.initial_title(mid, prompt).await });
+            }
+            AgentEvent::MissionTitleChanged { mission_id, title } => {
+                let mut titles = self.titles();
+                let ours = matches!(
+                    titles.auto.get(mission_id),
+                    Some(Some(written)) if written == title
+                );
+                if !ours && titles.auto.remove(mission_id).is_some() {
+                    titles.skip.insert(*mission_id);
+                }
+            }
+            AgentEvent::MissionStatusChanged {
+                mission_id, status, ..
+            } => {
+                let Some(label) = outcome_label(*status) else {
+                    return;
+                };
+                if !self.titles().auto.contains_key(mission_id) {
+                    return;
+                }
+                let worker = self.clone();
+                let mid = *mission_id;
+                tokio::spawn(async move { worker.retitle(mid, label).await });
+            }
+            _ => {}
+        }
+    }
+
+    async fn complete(&self, system: &str, prompt: &str) -> Option<String> {
+        let secret = std::env::var("SANDBOXED_PROXY_SECRET").unwrap_or_default();
+        match config_proxy_completion(
+            &self.config,
+            &secret,
+            &title_model(),
+            system,
+            prompt,
+            Duration::from_secs(60),
+        )
+        .await
+        {
+            Ok(reply) => clean_title(&reply),
+            Err(e) => {
+                tracing::debug!(error = %e, "Mission title generation failed");
+                None
+            }
+        }
+    }
+
+    async fn current_title(&self, mid: Uuid) -> Option<Option<String>> {
+        let mission = self.store.get_mission(mid).await.ok().flatten()?;
+        Some(mission.title.filter(|t| !t.trim().is_empty()))
+    }
+
+    /// Whether the worker may still overwrite the mission's current title.
+    fn owns(&self, mid: Uuid, current: &Option<String>) -> bool {
+        match self.titles().auto.get(&mid) {
+            Some(None) => true,
+            Some(written) => written == current,
+            None => false,
+        }
+    }
+
+    async fn write(&self, mid: Uuid, title: String) {
+        if let Err(e) = self.store.update_mission_title(mid, &title).await {
+            tracing::warn!(mission_id = %mid, error = %e, "Failed to update mission title");
+            return;
+        }
+        self.titles().auto.insert(mid, Some(title.clone()));
+        let _ = self.events_tx.send(AgentEvent::MissionTitleChanged {
+            mission_id: mid,
+            title,
+        });
+    }
+
+    async fn initial_title(&self, mid: Uuid, prompt: String) {
+        if !matches!(self.current_title(mid).await, Some(None)) {
+            return;
+        }
+        {
+            let mut titles = self.titles();
+            titles.skip.remove(&mid);
+            titles.auto.insert(mid, None);
+        }
+        let Some(title) = self.complete(TITLE_INSTRUCTIONS, excerpt(&prompt)).await else {
+            return;
+        };
+        // A title set through the API while the model ran wins; the
+        // history-based title written after the first turn does not.
+        let Some(current) = self.current_title(mid).await else {
+            return;
+        };
+        if !self.owns(mid, &current) {
+            return;
+        }
+        tracing::info!(mission_id = %mid, title = %title, "Generated mission title");
+        self.write(mid, title).await;
+    }
+
+    async fn retitle(&self, mid: Uuid, label: &str) {
+        let Ok(Some(mission)) = self.store.get_mission(mid).await else {
+            return;
+        };
+        let current = mission.title.clone().filter(|t| !t.trim().is_empty());
+        let Some(current_title) = current.clone() else {
+            return;
+        };
+        if !self.owns(mid, &current) {
+            return;
+        }
+        let request = mission.history.iter().find(|e| e.role == "user");
+        let answer = mission.history.iter().rev().find(|e| e.role == "assistant");
+        let base = match (request, answer) {
+            (Some(request), Some(answer)) => {
+                let prompt = format!(
+                    "Request:\n{}\n\nFinal answer:\n{}",
+                    excerpt(&request.content),
+                    excerpt(&answer.content)
+                );
+                self.complete(RETITLE_INSTRUCTIONS, &prompt).await
+            }
+            _ => None,
+        }
+        .unwrap_or(current_title);
+        let title = with_outcome(&base, label);
+        if current.as_deref() == Some(title.as_str()) {
+            return;
+        }
+        tracing::info!(mission_id = %mid, title = %title, "Retitled finished mission");
+        self.write(mid, title).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcome_tag_replaces_previous_tag() {
+        assert_eq!(
+            with_outcome("Fix flaky auth tests", "completed"),
+            "Fix flaky auth tests (completed)"
+        );
+        assert_eq!(
+            with_outcome("Fix flaky auth tests (failed)", "completed"),
+            "Fix flaky auth tests (completed)"
+        );
+        assert_eq!(strip_outcome("Deploy (staging)"), "Deploy (staging)");
+    }
+
+    #[test]
+    fn cleans_model_reply() {
+        assert_eq!(
+            clean_title("\n\"Fix flaky auth tests.\"\nExtra").as_deref(),
+            Some("Fix flaky auth tests")
+        );
+        assert_eq!(
+            clean_title("Title: Add dark mode (completed)").as_deref(),
+            Some("Add dark mode")
+        );
+        assert!(clean_title("  \n ").is_none());
+        let long = "word ".repeat(40);
+        assert!(clean_title(&long).unwrap().chars().count() <= MAX_TITLE_CHARS + 1);
+    }
+}
@@ -17,22 +17,50 @@
 
 pub mod ai_providers;
 pub mod ampcode;
+pub mod approvals;
 mod auth;
 pub mod automation_variables;
 pub mod backends;
+pub mod budget;
 pub mod claudecode;
 mod console;
+mod context_recovery;
 pub mod control;
 pub mod deferred_proxy;
 pub mod desktop;
 mod desktop_stream;
+pub mod event_export;
+pub mod event_schema;
+pub mod fair_share;
 mod fs;
+pub mod host_exec;
 pub mod library;
+pub mod llm_recorder;
+pub mod maintenance;
 pub mod mcp;
+pub mod mission_audit;
+pub mod mission_batch;
+pub mod mission_cache;
+pub mod mission_compact;
+pub mod mission_compare;
+pub mod mission_credentials;
+pub mod mission_daemon;
+pub mod mission_draft;
+pub mod mission_guard;
+pub mod mission_messages;
+pub mod mission_postprocess;
+pub mod mission_priority;
+pub mod mission_queue;
 pub mod mission_runner;
+pub mod mission_sla;
 pub mod mission_store;
+pub mod mission_templates;
+pub mod mission_timing;
+pub mod mission_titles;
 mod model_routing;
 mod monitoring;
+pub mod notifier;
+mod oidc;
 pub mod opencode;
 mod providers;
 mod proxy;
@@ -40,9 +68,17 @@ mod proxy_keys;
 mod routes;
 pub mod secrets;
 pub mod settings;
+mod skill_test;
+pub mod spending_alerts;
+pub mod step_budget;
 pub mod system;
+pub mod terminal_stream;
+mod tool_call_repair;
+pub mod tool_loops;
 pub mod types;
+pub mod workspace_terminal;
+mod workspace_vm;
 pub mod workspaces;
 
-pub use routes::serve;
+pub use routes::{serve, Server};
 pub use types::*;
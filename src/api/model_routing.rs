@@ -6,6 +6,8 @@
 //! - Resolve a chain into ordered entries (for debugging)
 //! - Clear cooldowns
 //! - RTK token savings stats
+//! - Tool-call argument repair counts
+//! - Streaming health per provider/model, also as Prometheus metrics
 
 use std::sync::Arc;
 
@@ -38,6 +40,7 @@ pub fn routes() -> Router<Arc<super::routes::AppState>> {
         .route("/events", get(list_fallback_events))
         // RTK stats
         .route("/rtk-stats", get(get_rtk_stats))
+        .route("/tool-call-repairs", get(get_tool_call_repairs))
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -399,3 +402,26 @@ async fn get_rtk_stats() -> Json<RtkStatsResponse> {
         savings_percent,
     })
 }
+
+/// GET /api/model-routing/tool-call-repairs - Tool-call argument repair
+/// counts per model since startup.
+async fn get_tool_call_repairs(
+) -> Json<std::collections::BTreeMap<String, super::tool_call_repair::RepairStats>> {
+    Json(super::tool_call_repair::stats())
+}
+
+/// GET /api/models/health - Streaming health per provider/model since startup.
+pub async fn get_models_health() -> Json<Vec<crate::stream_metrics::ModelHealth>> {
+    Json(crate::stream_metrics::health())
+}
+
+/// GET /metrics - Streaming health in the Prometheus text format.
+pub async fn get_metrics() -> impl axum::response::IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        crate::stream_metrics::prometheus_text(),
+    )
+}
@@ -23,6 +23,7 @@ use tokio::sync::{broadcast, RwLock};
 
 use super::auth;
 use super::routes::AppState;
+use crate::config::Role;
 
 /// How many historical samples to keep (at 1 sample/sec = 60 seconds of history)
 const HISTORY_SIZE: usize = 60;
@@ -253,7 +254,7 @@ pub async fn monitoring_ws(
             Some(t) => t,
             None => return (StatusCode::UNAUTHORIZED, "Missing websocket JWT").into_response(),
         };
-        if !auth::verify_token_for_config(&token, &state.config) {
+        if !auth::verify_token_for_config(&token, &state.config, Role::Viewer) {
             return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response();
         }
     }
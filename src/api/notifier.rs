@@ -0,0 +1,129 @@
+//! Outgoing webhook notifications.
+//!
+//! A [`Notification`] is posted as JSON to a webhook URL. Slack incoming
+//! webhooks (`https://hooks.slack.com/...`) get a Slack message (`text` with
+//! mrkdwn links); any other URL gets the notification itself:
+//!
+//! ```json
+//! {"kind": "daily_spend", "title": "...", "text": "...",
+//!  "links": [{"label": "...", "url": "..."}], "data": {...}}
+//! ```
+
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotificationLink {
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    /// Machine-readable type (e.g. `daily_spend`).
+    pub kind: String,
+    pub title: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<NotificationLink>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+}
+
+/// Link to a mission: the dashboard page when `dashboard_url` is set,
+/// otherwise the API endpoint.
+pub fn mission_link(dashboard_url: Option<&str>, mission_id: Uuid) -> String {
+    match dashboard_url {
+        Some(base) => format!(
+            "{}/control?mission={}",
+            base.trim_end_matches('/'),
+            mission_id
+        ),
+        None => format!("/api/control/missions/{}", mission_id),
+    }
+}
+
+fn is_slack(url: &str) -> bool {
+    url.starts_with("https://hooks.slack.com/")
+}
+
+/// Request body for `url`.
+fn payload(url: &str, notification: &Notification) -> Value {
+    if !is_slack(url) {
+        return serde_json::to_value(notification).unwrap_or(Value::Null);
+    }
+    let mut text = format!("*{}*\n{}", notification.title, notification.text);
+    for link in &notification.links {
+        // Relative API paths are not clickable in Slack; show them as code.
+        if link.url.starts_with("http") {
+            text.push_str(&format!("\n• <{}|{}>", link.url, link.label));
+        } else {
+            text.push_str(&format!("\n• {}: `{}`", link.label, link.url));
+        }
+    }
+    json!({ "text": text })
+}
+
+/// Post `notification` to `url`.
+pub async fn send(url: &str, notification: &Notification) -> anyhow::Result<()> {
+    crate::offline::check_url(url).map_err(anyhow::Error::msg)?;
+    let client = reqwest::Client::builder().timeout(SEND_TIMEOUT).build()?;
+    let response = client
+        .post(url)
+        .json(&payload(url, notification))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        anyhow::bail!("webhook returned {}", response.status());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification() -> Notification {
+        let id = Uuid::nil();
+        Notification {
+            kind: "mission_cost".to_string(),
+            title: "Expensive mission".to_string(),
+            text: "Cost $12.00".to_string(),
+            links: vec![
+                NotificationLink {
+                    label: "Mission".to_string(),
+                    url: mission_link(Some("https://dash.example.com/"), id),
+                },
+                NotificationLink {
+                    label: "API".to_string(),
+                    url: mission_link(None, id),
+                },
+            ],
+            data: json!({ "cost_cents": 1200 }),
+        }
+    }
+
+    #[test]
+    fn slack_payload_uses_mrkdwn_links() {
+        let body = payload("https://hooks.slack.com/services/x", &notification());
+        let text = body["text"].as_str().unwrap();
+        assert!(text.starts_with("*Expensive mission*\nCost $12.00"));
+        assert!(text.contains(
+            "<https://dash.example.com/control?mission=00000000-0000-0000-0000-000000000000|Mission>"
+        ));
+        assert!(text.contains("API: `/api/control/missions/00000000"));
+    }
+
+    #[test]
+    fn generic_payload_is_the_notification() {
+        let body = payload("https://example.com/hook", &notification());
+        assert_eq!(body["kind"], "mission_cost");
+        assert_eq!(body["links"][0]["label"], "Mission");
+        assert_eq!(body["data"]["cost_cents"], 1200);
+    }
+}
@@ -0,0 +1,917 @@
+//! OpenID Connect login for the dashboard (Authorization Code + PKCE).
+//!
+//! - `GET /api/auth/oidc/login` redirects the browser to the identity provider and
+//!   binds the login's `state` to the browser with a short-lived HttpOnly cookie
+//! - `GET /api/auth/oidc/callback` checks the `state` against that cookie, exchanges
+//!   the code, verifies the ID token, maps the user's groups to a [`Role`] and
+//!   redirects back to the dashboard with a one-time `#code=<opaque>` in the URL
+//!   fragment
+//! - `POST /api/auth/oidc/exchange` trades that code (once, within a minute) for
+//!   the session token, refresh token and role, so no token appears in a URL
+//! - `POST /api/auth/refresh` trades a refresh token for a new session token
+//!   (re-checking the user with the identity provider when it issued a refresh token)
+//! - `POST /api/auth/logout` revokes a refresh token
+//!
+//! Session tokens are ordinary JWTs signed with `JWT_SECRET`, so the existing auth
+//! middleware accepts them; they are short-lived (`OIDC_SESSION_TTL_MINUTES`).
+//! Refresh tokens rotate on every use and are kept in memory only, so users sign in
+//! again after a server restart.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use super::auth::{self, AuthUser};
+use super::routes::AppState;
+use super::types::LoginResponse;
+use crate::config::{AuthMode, OidcConfig, Role};
+
+/// How long a login may take between redirect and callback.
+const PENDING_LOGIN_TTL: StdDuration = StdDuration::from_secs(600);
+/// Logins in flight at once; further attempts are refused until some finish
+/// or expire.
+const MAX_PENDING_LOGINS: usize = 1_000;
+/// Cookie binding a login's `state` to the browser that started it.
+const STATE_COOKIE: &str = "sandboxed_oidc_state";
+/// How long the dashboard has to redeem the code it was redirected with.
+const LOGIN_CODE_TTL: StdDuration = StdDuration::from_secs(60);
+/// How long discovery metadata and signing keys are cached.
+const METADATA_CACHE_TTL: StdDuration = StdDuration::from_secs(3600);
+
+/// Subset of the provider's discovery document we rely on.
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    #[serde(default)]
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    access_token: Option<String>,
+    #[serde(default)]
+    id_token: Option<String>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+struct PendingLogin {
+    verifier: String,
+    nonce: String,
+    created_at: Instant,
+}
+
+/// A finished login waiting for the dashboard to redeem its code.
+struct IssuedLogin {
+    response: SsoLoginResponse,
+    created_at: Instant,
+}
+
+struct OidcSession {
+    id: String,
+    user: AuthUser,
+    /// Refresh token issued by the identity provider, if any.
+    idp_refresh_token: Option<String>,
+    expires_at: DateTime<Utc>,
+}
+
+/// In-memory OIDC state: cached provider metadata, in-flight logins and sessions.
+#[derive(Default)]
+pub struct OidcState {
+    metadata: RwLock<Option<(Instant, ProviderMetadata)>>,
+    jwks: RwLock<Option<(Instant, JwkSet)>>,
+    /// Keyed by the `state` parameter sent to the provider.
+    pending: RwLock<HashMap<String, PendingLogin>>,
+    /// Keyed by the SHA-256 of the one-time code.
+    issued: RwLock<HashMap<String, IssuedLogin>>,
+    /// Keyed by the SHA-256 of the refresh token.
+    sessions: RwLock<HashMap<String, OidcSession>>,
+}
+
+impl OidcState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn metadata(
+        &self,
+        http: &reqwest::Client,
+        config: &OidcConfig,
+    ) -> anyhow::Result<ProviderMetadata> {
+        if let Some((fetched_at, metadata)) = self.metadata.read().await.as_ref() {
+            if fetched_at.elapsed() < METADATA_CACHE_TTL {
+                return Ok(metadata.clone());
+            }
+        }
+        let url = format!("{}/.well-known/openid-configuration", config.issuer_url);
+        let metadata: ProviderMetadata = http
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        *self.metadata.write().await = Some((Instant::now(), metadata.clone()));
+        Ok(metadata)
+    }
+
+    async fn jwks(
+        &self,
+        http: &reqwest::Client,
+        metadata: &ProviderMetadata,
+        force_refresh: bool,
+    ) -> anyhow::Result<JwkSet> {
+        if !force_refresh {
+            if let Some((fetched_at, jwks)) = self.jwks.read().await.as_ref() {
+                if fetched_at.elapsed() < METADATA_CACHE_TTL {
+                    return Ok(jwks.clone());
+                }
+            }
+        }
+        let jwks: JwkSet = http
+            .get(&metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        *self.jwks.write().await = Some((Instant::now(), jwks.clone()));
+        Ok(jwks)
+    }
+
+    /// Verify an ID token's signature, issuer, audience and (optionally) nonce.
+    async fn verify_id_token(
+        &self,
+        http: &reqwest::Client,
+        config: &OidcConfig,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: Option<&str>,
+    ) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+        let header = jsonwebtoken::decode_header(id_token)?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            anyhow::bail!("ID token uses unsupported algorithm {:?}", header.alg);
+        }
+
+        let mut jwks = self.jwks(http, metadata, false).await?;
+        let mut key = find_jwk(&jwks, header.kid.as_deref()).cloned();
+        if key.is_none() {
+            // The provider may have rotated its keys since we cached them.
+            jwks = self.jwks(http, metadata, true).await?;
+            key = find_jwk(&jwks, header.kid.as_deref()).cloned();
+        }
+        let key = key.ok_or_else(|| anyhow::anyhow!("No matching signing key for ID token"))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&config.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+        let claims = jsonwebtoken::decode::<serde_json::Map<String, serde_json::Value>>(
+            id_token,
+            &DecodingKey::from_jwk(&key)?,
+            &validation,
+        )?
+        .claims;
+
+        if let Some(expected) = nonce {
+            let actual = claims.get("nonce").and_then(|v| v.as_str()).unwrap_or("");
+            if !auth::constant_time_eq(actual, expected) {
+                anyhow::bail!("ID token nonce mismatch");
+            }
+        }
+        Ok(claims)
+    }
+
+    /// Build the user for verified ID token claims, falling back to the userinfo
+    /// endpoint when the token does not carry the groups claim.
+    async fn user_for_claims(
+        &self,
+        state: &AppState,
+        config: &OidcConfig,
+        metadata: &ProviderMetadata,
+        claims: &serde_json::Map<String, serde_json::Value>,
+        access_token: Option<&str>,
+    ) -> Result<AuthUser, String> {
+        let sub = claims
+            .get("sub")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "ID token has no subject".to_string())?;
+
+        let mut groups = claims.get(&config.groups_claim).map(claim_strings);
+        if groups.is_none() {
+            if let (Some(endpoint), Some(token)) = (&metadata.userinfo_endpoint, access_token) {
+                match fetch_userinfo(&state.http_client, endpoint, token).await {
+                    Ok(info) => groups = info.get(&config.groups_claim).map(claim_strings),
+                    Err(e) => tracing::warn!("OIDC userinfo request failed: {}", e),
+                }
+            }
+        }
+        let groups = groups.unwrap_or_default();
+
+        let role = config.role_for_groups(&groups).ok_or_else(|| {
+            tracing::info!(sub = %sub, groups = ?groups, "OIDC login denied: no role for groups");
+            "Your account is not a member of any group allowed to use this server".to_string()
+        })?;
+
+        let username = ["preferred_username", "email", "name"]
+            .iter()
+            .find_map(|k| claims.get(*k).and_then(|v| v.as_str()))
+            .unwrap_or(sub)
+            .to_string();
+
+        // Single-tenant servers share one mission store between all logins;
+        // multi-user servers give each SSO user their own.
+        let id = match state.config.auth.auth_mode(state.config.dev_mode) {
+            AuthMode::MultiUser => format!("oidc:{}", sub),
+            AuthMode::SingleTenant | AuthMode::Disabled => "default".to_string(),
+        };
+
+        Ok(AuthUser { id, username, role })
+    }
+
+    /// Create a session and issue its first session token.
+    async fn start_session(
+        &self,
+        secret: &str,
+        config: &OidcConfig,
+        user: AuthUser,
+        idp_refresh_token: Option<String>,
+    ) -> anyhow::Result<LoginResponse> {
+        let session = OidcSession {
+            id: random_token(16),
+            user,
+            idp_refresh_token,
+            expires_at: Utc::now() + Duration::hours(config.refresh_ttl_hours),
+        };
+        self.issue(secret, config, session).await
+    }
+
+    /// Store `session` under a fresh refresh token and issue a session token for it.
+    async fn issue(
+        &self,
+        secret: &str,
+        config: &OidcConfig,
+        session: OidcSession,
+    ) -> anyhow::Result<LoginResponse> {
+        let (token, exp) = auth::issue_session_jwt(
+            secret,
+            Duration::minutes(config.session_ttl_minutes),
+            &session.user,
+            &session.id,
+        )?;
+        let refresh_token = random_token(32);
+
+        let mut sessions = self.sessions.write().await;
+        let now = Utc::now();
+        sessions.retain(|_, s| s.expires_at > now);
+        sessions.insert(hash_token(&refresh_token), session);
+
+        Ok(LoginResponse {
+            token,
+            exp,
+            refresh_token: Some(refresh_token),
+        })
+    }
+
+    /// Record a login started with `csrf_state`; refused when too many are in flight.
+    async fn begin_login(&self, csrf_state: String, login: PendingLogin) -> bool {
+        let mut pending = self.pending.write().await;
+        pending.retain(|_, p| p.created_at.elapsed() < PENDING_LOGIN_TTL);
+        if pending.len() >= MAX_PENDING_LOGINS {
+            return false;
+        }
+        pending.insert(csrf_state, login);
+        true
+    }
+
+    /// The login started with `csrf_state`, if the browser that started it
+    /// presents the matching cookie.
+    async fn finish_login(&self, csrf_state: &str, cookie: Option<&str>) -> Option<PendingLogin> {
+        if !cookie.is_some_and(|c| auth::constant_time_eq(c, csrf_state)) {
+            return None;
+        }
+        let login = self.pending.write().await.remove(csrf_state)?;
+        (login.created_at.elapsed() < PENDING_LOGIN_TTL).then_some(login)
+    }
+
+    /// Hold a finished login for the dashboard; returns the one-time code.
+    async fn stash_login(&self, response: SsoLoginResponse) -> String {
+        let code = random_token(32);
+        let mut issued = self.issued.write().await;
+        issued.retain(|_, l| l.created_at.elapsed() < LOGIN_CODE_TTL);
+        issued.insert(
+            hash_token(&code),
+            IssuedLogin {
+                response,
+                created_at: Instant::now(),
+            },
+        );
+        code
+    }
+
+    async fn redeem_login(&self, code: &str) -> Option<SsoLoginResponse> {
+        let login = self.issued.write().await.remove(&hash_token(code))?;
+        (login.created_at.elapsed() < LOGIN_CODE_TTL).then_some(login.response)
+    }
+
+    async fn take_session(&self, refresh_token: &str) -> Option<OidcSession> {
+        let session = self
+            .sessions
+            .write()
+            .await
+            .remove(&hash_token(refresh_token))?;
+        (session.expires_at > Utc::now()).then_some(session)
+    }
+}
+
+fn find_jwk<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Option<&'a jsonwebtoken::jwk::Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+}
+
+/// Group claims are usually arrays but some providers send a single string.
+fn claim_strings(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        serde_json::Value::String(s) => vec![s.clone()],
+        _ => Vec::new(),
+    }
+}
+
+async fn fetch_userinfo(
+    http: &reqwest::Client,
+    endpoint: &str,
+    access_token: &str,
+) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    Ok(http
+        .get(endpoint)
+        .bearer_auth(access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+async fn request_tokens(
+    http: &reqwest::Client,
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    params: &[(&str, &str)],
+) -> anyhow::Result<TokenResponse> {
+    let mut form: Vec<(&str, &str)> = params.to_vec();
+    form.push(("client_id", &config.client_id));
+    if let Some(secret) = config.client_secret.as_deref() {
+        form.push(("client_secret", secret));
+    }
+    let response = http
+        .post(&metadata.token_endpoint)
+        .form(&form)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Token endpoint returned {}: {}", status, body);
+    }
+    Ok(response.json().await?)
+}
+
+fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    URL_SAFE_NO_PAD.encode(buf)
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+type ApiError = (StatusCode, String);
+
+fn oidc_config(state: &AppState) -> Result<(&OidcConfig, &str), ApiError> {
+    let config = state
+        .config
+        .auth
+        .oidc
+        .as_ref()
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "SSO is not configured".to_string()))?;
+    let secret = state.config.auth.jwt_secret.as_deref().ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "JWT_SECRET not configured".to_string(),
+        )
+    })?;
+    Ok((config, secret))
+}
+
+fn bad_gateway(e: impl std::fmt::Display) -> ApiError {
+    tracing::warn!("OIDC provider error: {}", e);
+    (
+        StatusCode::BAD_GATEWAY,
+        format!("Identity provider error: {}", e),
+    )
+}
+
+/// Redirect back to the dashboard with `params` in the URL fragment, clearing
+/// the state cookie.
+fn dashboard_redirect(config: &OidcConfig, params: &[(&str, &str)]) -> Response {
+    let fragment = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    let mut response =
+        Redirect::to(&format!("{}#{}", config.post_login_redirect, fragment)).into_response();
+    if let Ok(cookie) = HeaderValue::from_str(&state_cookie(config, "", 0)) {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    response
+}
+
+/// `Set-Cookie` value for the state cookie, scoped to the callback.
+fn state_cookie(config: &OidcConfig, value: &str, max_age_secs: u64) -> String {
+    let secure = if config.redirect_url.starts_with("https://") {
+        "; Secure"
+    } else {
+        ""
+    };
+    // Lax: the provider sends the browser back with a top-level GET
+    format!(
+        "{}={}; Path=/api/auth/oidc; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        STATE_COOKIE, value, max_age_secs, secure
+    )
+}
+
+/// The state cookie sent with a request, if any.
+fn cookie_state(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == STATE_COOKIE)
+        .map(|(_, value)| value.to_string())
+}
+
+/// `GET /api/auth/oidc/login` - start an SSO login.
+pub async fn login(State(state): State<Arc<AppState>>) -> Result<Response, ApiError> {
+    let (config, _) = oidc_config(&state)?;
+    let metadata = state
+        .oidc
+        .metadata(&state.http_client, config)
+        .await
+        .map_err(bad_gateway)?;
+
+    let csrf_state = random_token(24);
+    let nonce = random_token(24);
+    let verifier = random_token(48);
+
+    let mut url = url::Url::parse(&metadata.authorization_endpoint).map_err(bad_gateway)?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &config.redirect_url)
+        .append_pair("scope", &config.scopes.join(" "))
+        .append_pair("state", &csrf_state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &pkce_challenge(&verifier))
+        .append_pair("code_challenge_method", "S256");
+
+    let cookie = state_cookie(config, &csrf_state, PENDING_LOGIN_TTL.as_secs());
+    let started = state
+        .oidc
+        .begin_login(
+            csrf_state,
+            PendingLogin {
+                verifier,
+                nonce,
+                created_at: Instant::now(),
+            },
+        )
+        .await;
+    if !started {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many logins in progress, please try again shortly".to_string(),
+        ));
+    }
+
+    let mut response = Redirect::to(url.as_str()).into_response();
+    response.headers_mut().insert(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&cookie).map_err(bad_gateway)?,
+    );
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// `GET /api/auth/oidc/callback` - finish an SSO login and return to the dashboard.
+pub async fn callback(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<CallbackQuery>,
+) -> Result<Response, ApiError> {
+    let (config, secret) = oidc_config(&state)?;
+    let fail = |message: &str| Ok(dashboard_redirect(config, &[("error", message)]));
+
+    if let Some(error) = query.error {
+        let message = query.error_description.unwrap_or(error);
+        return fail(&message);
+    }
+    let (Some(code), Some(csrf_state)) = (query.code, query.state) else {
+        return fail("Missing code or state");
+    };
+    let cookie = cookie_state(&headers);
+    let Some(pending) = state
+        .oidc
+        .finish_login(&csrf_state, cookie.as_deref())
+        .await
+    else {
+        return fail("Login expired, please try again");
+    };
+
+    let result: Result<(LoginResponse, Role), String> = async {
+        let metadata = state
+            .oidc
+            .metadata(&state.http_client, config)
+            .await
+            .map_err(|e| e.to_string())?;
+        let tokens = request_tokens(
+            &state.http_client,
+            config,
+            &metadata,
+            &[
+                ("grant_type", "authorization_code"),
+                ("code", &code),
+                ("redirect_uri", &config.redirect_url),
+                ("code_verifier", &pending.verifier),
+            ],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        let id_token = tokens
+            .id_token
+            .as_deref()
+            .ok_or_else(|| "Provider did not return an ID token".to_string())?;
+        let claims = state
+            .oidc
+            .verify_id_token(
+                &state.http_client,
+                config,
+                &metadata,
+                id_token,
+                Some(&pending.nonce),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        let user = state
+            .oidc
+            .user_for_claims(
+                &state,
+                config,
+                &metadata,
+                &claims,
+                tokens.access_token.as_deref(),
+            )
+            .await?;
+        tracing::info!(username = %user.username, role = user.role.as_str(), "OIDC login");
+        let role = user.role;
+        let login = state
+            .oidc
+            .start_session(secret, config, user, tokens.refresh_token)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok((login, role))
+    }
+    .await;
+
+    match result {
+        Ok((login, role)) => {
+            let code = state
+                .oidc
+                .stash_login(SsoLoginResponse { login, role })
+                .await;
+            Ok(dashboard_redirect(config, &[("code", code.as_str())]))
+        }
+        Err(message) => {
+            tracing::warn!("OIDC login failed: {}", message);
+            fail(&message)
+        }
+    }
+}
+
+/// A finished SSO login: the session, plus the role it grants.
+#[derive(Debug, Clone, Serialize)]
+pub struct SsoLoginResponse {
+    #[serde(flatten)]
+    pub login: LoginResponse,
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExchangeRequest {
+    pub code: String,
+}
+
+/// `POST /api/auth/oidc/exchange` - redeem the one-time code from the callback.
+pub async fn exchange(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ExchangeRequest>,
+) -> Result<Json<SsoLoginResponse>, ApiError> {
+    oidc_config(&state)?;
+    state
+        .oidc
+        .redeem_login(req.code.trim())
+        .await
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired login code".to_string(),
+            )
+        })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// `POST /api/auth/refresh` - rotate a refresh token and issue a new session token.
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let (config, secret) = oidc_config(&state)?;
+    let unauthorized = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired refresh token".to_string(),
+        )
+    };
+
+    let mut session = state
+        .oidc
+        .take_session(req.refresh_token.trim())
+        .await
+        .ok_or_else(unauthorized)?;
+
+    // Re-check the user with the provider so disabled accounts and group
+    // changes take effect without waiting for our refresh token to expire.
+    if let Some(idp_refresh_token) = session.idp_refresh_token.clone() {
+        let metadata = state
+            .oidc
+            .metadata(&state.http_client, config)
+            .await
+            .map_err(bad_gateway)?;
+        let tokens = request_tokens(
+            &state.http_client,
+            config,
+            &metadata,
+            &[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", &idp_refresh_token),
+            ],
+        )
+        .await
+        .map_err(|e| {
+            tracing::info!(username = %session.user.username, "OIDC refresh rejected: {}", e);
+            unauthorized()
+        })?;
+
+        if let Some(id_token) = tokens.id_token.as_deref() {
+            let claims = state
+                .oidc
+                .verify_id_token(&state.http_client, config, &metadata, id_token, None)
+                .await
+                .map_err(|e| {
+                    tracing::warn!("OIDC refresh returned an invalid ID token: {}", e);
+                    unauthorized()
+                })?;
+            session.user = state
+                .oidc
+                .user_for_claims(
+                    &state,
+                    config,
+                    &metadata,
+                    &claims,
+                    tokens.access_token.as_deref(),
+                )
+                .await
+                .map_err(|message| (StatusCode::FORBIDDEN, message))?;
+        }
+        if tokens.refresh_token.is_some() {
+            session.idp_refresh_token = tokens.refresh_token;
+        }
+    }
+
+    let response = state
+        .oidc
+        .issue(secret, config, session)
+        .await
+        .map_err(crate::util::internal_error)?;
+    Ok(Json(response))
+}
+
+/// `POST /api/auth/logout` - revoke a refresh token.
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<StatusCode, ApiError> {
+    oidc_config(&state)?;
+    if let Some(session) = state.oidc.take_session(req.refresh_token.trim()).await {
+        tracing::info!(username = %session.user.username, "OIDC logout");
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(mapping: &[(&str, Role)], default_role: Option<Role>) -> OidcConfig {
+        OidcConfig {
+            issuer_url: "https://idp.example.com".to_string(),
+            client_id: "sandboxed".to_string(),
+            client_secret: None,
+            redirect_url: "https://agent.example.com/api/auth/oidc/callback".to_string(),
+            post_login_redirect: "/".to_string(),
+            scopes: vec!["openid".to_string()],
+            groups_claim: "groups".to_string(),
+            role_mapping: mapping.iter().map(|(g, r)| (g.to_string(), *r)).collect(),
+            default_role,
+            session_ttl_minutes: 15,
+            refresh_ttl_hours: 168,
+        }
+    }
+
+    #[test]
+    fn test_role_for_groups_picks_highest() {
+        let config = config(&[("eng", Role::Operator), ("platform", Role::Admin)], None);
+        let groups = vec!["eng".to_string(), "platform".to_string()];
+        assert_eq!(config.role_for_groups(&groups), Some(Role::Admin));
+        assert_eq!(
+            config.role_for_groups(&["eng".to_string()]),
+            Some(Role::Operator)
+        );
+        assert_eq!(config.role_for_groups(&["sales".to_string()]), None);
+    }
+
+    #[test]
+    fn test_role_for_groups_default_role() {
+        let config = config(&[("platform", Role::Admin)], Some(Role::Viewer));
+        assert_eq!(config.role_for_groups(&[]), Some(Role::Viewer));
+    }
+
+    #[test]
+    fn test_claim_strings() {
+        assert_eq!(
+            claim_strings(&serde_json::json!(["a", "b", 3])),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        assert_eq!(
+            claim_strings(&serde_json::json!("a")),
+            vec!["a".to_string()]
+        );
+        assert!(claim_strings(&serde_json::json!(null)).is_empty());
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // RFC 7636 appendix B.
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    fn pending_login() -> PendingLogin {
+        PendingLogin {
+            verifier: "v".to_string(),
+            nonce: "n".to_string(),
+            created_at: Instant::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_callback_needs_the_browser_that_started_the_login() {
+        let state = OidcState::new();
+        assert!(state.begin_login("s1".to_string(), pending_login()).await);
+        // Another browser (login CSRF) has no cookie or a different one
+        assert!(state.finish_login("s1", None).await.is_none());
+        assert!(state.finish_login("s1", Some("s2")).await.is_none());
+        assert!(state.finish_login("s1", Some("s1")).await.is_some());
+        assert!(state.finish_login("s1", Some("s1")).await.is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; sandboxed_oidc_state=abc"),
+        );
+        assert_eq!(cookie_state(&headers).as_deref(), Some("abc"));
+        let cookie = state_cookie(&config(&[], None), "abc", 600);
+        assert!(cookie.contains("HttpOnly") && cookie.contains("SameSite=Lax"));
+        assert!(cookie.contains("Secure"));
+    }
+
+    #[tokio::test]
+    async fn test_pending_logins_are_capped() {
+        let state = OidcState::new();
+        for i in 0..MAX_PENDING_LOGINS {
+            assert!(state.begin_login(i.to_string(), pending_login()).await);
+        }
+        assert!(
+            !state
+                .begin_login("one-more".to_string(), pending_login())
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_login_codes_are_single_use() {
+        let state = OidcState::new();
+        let code = state
+            .stash_login(SsoLoginResponse {
+                login: LoginResponse {
+                    token: "jwt".to_string(),
+                    exp: 0,
+                    refresh_token: Some("refresh".to_string()),
+                },
+                role: Role::Viewer,
+            })
+            .await;
+        let login = state.redeem_login(&code).await.unwrap();
+        assert_eq!(login.login.token, "jwt");
+        assert_eq!(login.role, Role::Viewer);
+        assert!(state.redeem_login(&code).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_tokens_rotate() {
+        let state = OidcState::new();
+        let config = config(&[], Some(Role::Operator));
+        let user = AuthUser {
+            id: "default".to_string(),
+            username: "alice".to_string(),
+            role: Role::Operator,
+        };
+        let first = state
+            .start_session("secret", &config, user, None)
+            .await
+            .unwrap();
+        let first_refresh = first.refresh_token.unwrap();
+
+        let session = state.take_session(&first_refresh).await.unwrap();
+        assert_eq!(session.user.username, "alice");
+        let second = state.issue("secret", &config, session).await.unwrap();
+
+        assert!(state.take_session(&first_refresh).await.is_none());
+        assert!(state
+            .take_session(&second.refresh_token.unwrap())
+            .await
+            .is_some());
+    }
+}
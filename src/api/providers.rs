@@ -851,9 +851,13 @@ pub async fn fetch_model_catalog(
     ];
 
     // Resolve API keys for all targets + Anthropic
-    let anthropic_key = get_api_key_for_provider(ProviderType::Anthropic, &providers_list);
+    // (in offline mode only local providers are queried)
+    let offline = crate::offline::offline_mode();
+    let anthropic_key =
+        get_api_key_for_provider(ProviderType::Anthropic, &providers_list).filter(|_| !offline);
     let target_keys: Vec<(FetchTarget, Option<String>)> = targets
         .into_iter()
+        .filter(|_| !offline)
         .map(|t| {
             let key = get_api_key_for_provider(t.provider_type, &providers_list);
             (t, key)
@@ -918,11 +922,49 @@ pub async fn fetch_model_catalog(
         }));
     }
 
-    // Collect results
+    // Fetch models pulled on local Ollama servers (no credentials needed)
+    for provider in providers_list
+        .iter()
+        .filter(|p| p.provider_type == ProviderType::Ollama && p.enabled)
+    {
+        let base_url = provider.base_url.clone();
+        handles.push(tokio::spawn(async move {
+            let client = crate::ollama::OllamaClient::new(base_url.as_deref());
+            if let Err(e) = crate::offline::check_url(&client.openai_base_url()) {
+                tracing::debug!("Skipping Ollama model fetch: {}", e);
+                return None;
+            }
+            match client.list_models().await {
+                Ok(models) => {
+                    tracing::info!("Fetched {} models from Ollama", models.len());
+                    let models = models
+                        .into_iter()
+                        .map(|m| ProviderModel {
+                            id: m.name.clone(),
+                            description: m.description(),
+                            name: m.name,
+                        })
+                        .collect();
+                    Some(("ollama".to_string(), models))
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch Ollama models: {}", e);
+                    None
+                }
+            }
+        }));
+    }
+
+    // Collect results (several Ollama servers share the "ollama" entry)
     for handle in handles {
         if let Ok(Some((provider_id, models))) = handle.await {
             if !models.is_empty() {
-                result.insert(provider_id, models);
+                let entry: &mut Vec<ProviderModel> = result.entry(provider_id).or_default();
+                for model in models {
+                    if !entry.iter().any(|m| m.id == model.id) {
+                        entry.push(model);
+                    }
+                }
             }
         }
     }
@@ -1222,8 +1264,16 @@ pub async fn validate_model_override(
     backend: &str,
     model_override: &str,
 ) -> Result<(), String> {
-    // Amp ignores model overrides, so no validation needed
-    if backend == "amp" {
+    // Amp ignores model overrides and custom backends check their own,
+    // so no validation needed
+    if backend == "amp"
+        || state
+            .backend_registry
+            .read()
+            .await
+            .plugin(backend)
+            .is_some()
+    {
         return Ok(());
     }
 
@@ -21,6 +21,8 @@ use axum::{
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 
+use super::context_recovery;
+use super::tool_call_repair;
 use crate::ai_providers::ProviderType;
 use crate::provider_health::CooldownReason;
 
@@ -137,6 +139,7 @@ fn default_base_url(provider_type: ProviderType) -> Option<&'static str> {
         ProviderType::TogetherAI => Some("https://api.together.xyz/v1"),
         ProviderType::Perplexity => Some("https://api.perplexity.ai"),
         ProviderType::Custom => None, // uses account's base_url
+        ProviderType::Ollama => None, // see completions_url
         // Non-OpenAI-compatible providers
         ProviderType::Anthropic => None,
         ProviderType::Google => None,
@@ -150,6 +153,11 @@ fn default_base_url(provider_type: ProviderType) -> Option<&'static str> {
 
 /// Get the chat completions URL for a resolved entry.
 fn completions_url(provider_type: ProviderType, account_base_url: Option<&str>) -> Option<String> {
+    // Ollama serves its OpenAI-compatible API under /v1 of the server root
+    if provider_type == ProviderType::Ollama {
+        let base = crate::ollama::openai_base_url(account_base_url);
+        return Some(format!("{}/chat/completions", base));
+    }
     // Account-level override takes precedence
     let base = account_base_url.or_else(|| default_base_url(provider_type))?;
     let base = base.trim_end_matches('/');
@@ -162,7 +170,7 @@ fn has_routable_proxy_credentials(
     has_oauth: bool,
 ) -> bool {
     match provider_type {
-        ProviderType::Custom => true,
+        ProviderType::Custom | ProviderType::Ollama => true,
         ProviderType::Google => has_api_key || has_oauth,
         _ => has_api_key,
     }
@@ -336,6 +344,212 @@ async fn chat_completions(
     State(state): State<Arc<super::routes::AppState>>,
     headers: HeaderMap,
     body: bytes::Bytes,
+) -> Response {
+    // Record the call for the mission's debug bundle (opt-in, authorized requests only)
+    let recording =
+        match super::llm_recorder::Recording::start(&state.config.working_dir, &headers, &body) {
+            Some(recording) if verify_proxy_auth(&headers, &state).await.is_ok() => Some(recording),
+            _ => None,
+        };
+    let response = route_with_tool_call_repair(state, headers, body).await;
+    match recording {
+        Some(recording) => recording.finish(response),
+        None => response,
+    }
+}
+
+/// Route a request and repair malformed tool-call arguments in the
+/// non-streaming response, re-asking the model for calls that cannot be
+/// repaired (see [`tool_call_repair`]).
+async fn route_with_tool_call_repair(
+    state: Arc<super::routes::AppState>,
+    headers: HeaderMap,
+    body: bytes::Bytes,
+) -> Response {
+    let request = serde_json::from_slice::<serde_json::Value>(&body)
+        .ok()
+        .filter(|r| !r.get("stream").and_then(|s| s.as_bool()).unwrap_or(false));
+    let mut response = route_with_context_recovery(state.clone(), headers.clone(), body).await;
+    let Some(mut request) = request else {
+        return response;
+    };
+
+    for attempt in 0..=tool_call_repair::MAX_REASKS {
+        if response.status() != StatusCode::OK {
+            return response;
+        }
+        let (mut parts, resp_body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(resp_body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return error_response(
+                    StatusCode::BAD_GATEWAY,
+                    format!("Failed to read upstream response: {}", e),
+                    "upstream_error",
+                )
+            }
+        };
+        let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+            return Response::from_parts(parts, Body::from(bytes));
+        };
+        let outcome = tool_call_repair::repair_response(&mut value);
+        if outcome.invalid.is_empty() || attempt == tool_call_repair::MAX_REASKS {
+            if outcome.repaired == 0 {
+                return Response::from_parts(parts, Body::from(bytes));
+            }
+            parts.headers.remove(header::CONTENT_LENGTH);
+            let body = serde_json::to_vec(&value).unwrap_or_else(|_| bytes.to_vec());
+            return Response::from_parts(parts, Body::from(body));
+        }
+
+        let model = tool_call_repair::response_model(&value);
+        tracing::info!(
+            model = %model,
+            invalid = outcome.invalid.len(),
+            attempt = attempt + 1,
+            "Re-asking model after unrepairable tool-call arguments"
+        );
+        tool_call_repair::record_reask(model);
+        let reask = tool_call_repair::reask_messages(&value, &outcome.invalid);
+        let messages = request
+            .get_mut("messages")
+            .and_then(|m| m.as_array_mut())
+            .filter(|_| !reask.is_empty());
+        let Some(messages) = messages else {
+            return Response::from_parts(parts, Body::from(bytes));
+        };
+        messages.extend(reask);
+        let Ok(reask_body) = serde_json::to_vec(&request) else {
+            return Response::from_parts(parts, Body::from(bytes));
+        };
+        response =
+            route_with_context_recovery(state.clone(), headers.clone(), reask_body.into()).await;
+    }
+    response
+}
+
+/// Route a request, recovering from context-length-exceeded errors by trimming
+/// older tool results and then switching to the long-context fallback chain
+/// (see [`context_recovery`]).
+async fn route_with_context_recovery(
+    state: Arc<super::routes::AppState>,
+    headers: HeaderMap,
+    body: bytes::Bytes,
+) -> Response {
+    let mut response =
+        route_chat_completion(State(state.clone()), headers.clone(), body.clone()).await;
+    if !context_recovery::is_overflow_response(&response) {
+        return response;
+    }
+    let Ok(mut request) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return response;
+    };
+    let mission_id = headers
+        .get(crate::web_proxy::MISSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| uuid::Uuid::parse_str(v.trim()).ok());
+
+    for (keep_recent, excerpt_chars) in context_recovery::TRIM_STAGES {
+        let report = context_recovery::trim_tool_results(&mut request, keep_recent, excerpt_chars);
+        if report.trimmed == 0 {
+            continue;
+        }
+        let Ok(trimmed_body) = serde_json::to_vec(&request) else {
+            return response;
+        };
+        response =
+            route_chat_completion(State(state.clone()), headers.clone(), trimmed_body.into()).await;
+        let recovered = !context_recovery::is_overflow_response(&response);
+        tracing::info!(
+            trimmed = report.trimmed,
+            removed_chars = report.removed_chars,
+            recovered,
+            "Retried request with trimmed tool results after context overflow"
+        );
+        if let Some(mission_id) = mission_id {
+            context_recovery::emit(
+                &state.control,
+                mission_id,
+                "trimmed_tool_results",
+                format!(
+                    "Context window exceeded; {} {} older tool result(s) ({} characters removed){}",
+                    if excerpt_chars == 0 {
+                        "dropped"
+                    } else {
+                        "shortened"
+                    },
+                    report.trimmed,
+                    report.removed_chars,
+                    if recovered { "" } else { ", still too long" }
+                ),
+                serde_json::json!({
+                    "trimmed": report.trimmed,
+                    "removed_chars": report.removed_chars,
+                    "excerpt_chars": excerpt_chars,
+                    "recovered": recovered,
+                }),
+            )
+            .await;
+        }
+        if recovered {
+            return response;
+        }
+    }
+
+    // Still too long: retry the original request on the long-context chain.
+    let Some(fallback) = crate::settings::long_context_model_cached() else {
+        return response;
+    };
+    let Ok(mut request) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return response;
+    };
+    let from_model = request
+        .get("model")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default()
+        .to_string();
+    if from_model == fallback {
+        return response;
+    }
+    request["model"] = serde_json::Value::String(fallback.clone());
+    let Ok(fallback_body) = serde_json::to_vec(&request) else {
+        return response;
+    };
+    let response = route_chat_completion(State(state.clone()), headers, fallback_body.into()).await;
+    let recovered =
+        !context_recovery::is_overflow_response(&response) && response.status().is_success();
+    tracing::info!(
+        from_model = %from_model,
+        model = %fallback,
+        recovered,
+        "Retried request on long-context chain after context overflow"
+    );
+    if let Some(mission_id) = mission_id {
+        context_recovery::emit(
+            &state.control,
+            mission_id,
+            "long_context_fallback",
+            format!(
+                "Context window exceeded; switched from '{}' to long-context model '{}'{}",
+                from_model,
+                fallback,
+                if recovered { "" } else { ", which also failed" }
+            ),
+            serde_json::json!({
+                "from_model": from_model,
+                "model": fallback,
+                "recovered": recovered,
+            }),
+        )
+        .await;
+    }
+    response
+}
+
+async fn route_chat_completion(
+    State(state): State<Arc<super::routes::AppState>>,
+    headers: HeaderMap,
+    body: bytes::Bytes,
 ) -> Response {
     // 0. Verify proxy authorization
     if let Err(resp) = verify_proxy_auth(&headers, &state).await {
@@ -420,10 +634,40 @@ async fn chat_completions(
         );
     }
 
-    // 4. Try each entry in order (waterfall)
+    // 4. Drop entries the organization's model policy refuses, whatever the
+    //    chain or mission asked for.
+    let policy = crate::settings::model_policy_cached();
+    let mut policy_refusals = Vec::new();
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| match policy.check(&entry.provider_id, &entry.model_id) {
+            Ok(()) => true,
+            Err(reason) => {
+                tracing::debug!(provider = %entry.provider_id, "Skipping chain entry: {}", reason);
+                policy_refusals.push(reason);
+                false
+            }
+        })
+        .collect();
+    if entries.is_empty() {
+        policy_refusals.dedup();
+        return error_response(
+            StatusCode::FORBIDDEN,
+            format!(
+                "No model in chain '{}' is allowed by the model policy: {}",
+                chain_id,
+                policy_refusals.join("; ")
+            ),
+            "model_policy_violation",
+        );
+    }
+
+    // 5. Try each entry in order (waterfall)
     let mut rate_limit_count: u32 = 0;
     let mut client_error_count: u32 = 0;
     let mut server_error_count: u32 = 0;
+    // Client errors that were context-length overflows
+    let mut context_overflow_count: u32 = 0;
     let mut pending_fallback_events: Vec<crate::provider_health::FallbackEvent> = Vec::new();
 
     let chain_length = entries.len() as u32;
@@ -441,6 +685,10 @@ async fn chat_completions(
         }
 
         let use_google_oauth_adapter = provider_type == ProviderType::Google && entry.has_oauth;
+        // The Google OAuth adapter always talks to Google.
+        if use_google_oauth_adapter && crate::offline::offline_mode() {
+            continue;
+        }
         let (url, upstream_body, extra_headers) = if use_google_oauth_adapter {
             let access_token = match get_google_access_token().await {
                 Ok(token) => token,
@@ -491,8 +739,12 @@ async fn chat_completions(
                 );
                 continue;
             };
+            if let Err(reason) = crate::offline::check_url(&url) {
+                tracing::debug!(provider = %entry.provider_id, "Skipping chain entry: {}", reason);
+                continue;
+            }
             // Build the upstream request body: replace model with the real model ID
-            let upstream_body = match rewrite_model(&body, &entry.model_id) {
+            let upstream_body = match rewrite_model(&body, &entry.model_id, provider_type) {
                 Ok(b) => b,
                 Err(e) => {
                     tracing::error!("Failed to rewrite model in request body: {}", e);
@@ -556,6 +808,9 @@ async fn chat_completions(
                     latency_ms = elapsed_ms,
                     "Upstream request failed (network error)"
                 );
+                if is_stream {
+                    record_stream_error(entry, elapsed_ms);
+                }
                 let reason = if e.is_timeout() {
                     CooldownReason::Timeout
                 } else {
@@ -611,8 +866,14 @@ async fn chat_completions(
                     .await;
                 let account_id = entry.account_id;
                 let health_tracker = state.health_tracker.clone();
-                let tracked_stream =
-                    track_stream_health(response_stream, health_tracker, account_id, None);
+                let tracked_stream = track_stream_health(
+                    response_stream,
+                    health_tracker,
+                    account_id,
+                    None,
+                    entry,
+                    request_start,
+                );
 
                 let success_provider = entry.provider_id.clone();
                 for evt in &mut pending_fallback_events {
@@ -909,13 +1170,21 @@ async fn chat_completions(
         // failure, and don't return the upstream error to avoid leaking
         // internal provider details.
         if status.is_client_error() {
+            let context_overflow = upstream_resp
+                .bytes()
+                .await
+                .is_ok_and(|b| context_recovery::is_context_overflow(&b));
             tracing::warn!(
                 provider = %entry.provider_id,
                 account_id = %entry.account_id,
                 model = %entry.model_id,
                 status = %status,
+                context_overflow,
                 "Upstream client error (possibly wrong model), trying next entry"
             );
+            if context_overflow {
+                context_overflow_count += 1;
+            }
             client_error_count += 1;
             continue;
         }
@@ -982,6 +1251,7 @@ async fn chat_completions(
 
             if peek_failed {
                 let elapsed_ms = request_start.elapsed().as_millis() as u64;
+                record_stream_error(entry, elapsed_ms);
                 let cooldown = state
                     .health_tracker
                     .record_failure(entry.account_id, CooldownReason::ServerError, None)
@@ -1005,6 +1275,7 @@ async fn chat_completions(
 
             if is_stream_error {
                 let elapsed_ms = request_start.elapsed().as_millis() as u64;
+                record_stream_error(entry, elapsed_ms);
                 // Parse the peeked data to classify the error type.
                 let reason = std::str::from_utf8(&peek_buf)
                     .ok()
@@ -1087,8 +1358,14 @@ async fn chat_completions(
             let byte_stream = normalize_sse_stream(combined);
 
             // Wrap the stream to record success/failure on completion.
-            let tracked_stream =
-                track_stream_health(byte_stream, health_tracker, account_id, rate_limit_snapshot);
+            let tracked_stream = track_stream_health(
+                byte_stream,
+                health_tracker,
+                account_id,
+                rate_limit_snapshot,
+                entry,
+                request_start,
+            );
 
             return (status, response_headers, Body::from_stream(tracked_stream)).into_response();
         }
@@ -1257,12 +1534,31 @@ async fn chat_completions(
         error_response(
             StatusCode::BAD_GATEWAY,
             format!(
-                "All {} providers in chain '{}' were skipped (missing credentials or incompatible)",
+                "All {} providers in chain '{}' were skipped ({})",
                 entries.len(),
-                chain_id
+                chain_id,
+                if crate::offline::offline_mode() {
+                    "missing credentials, incompatible, or not local in offline mode"
+                } else {
+                    "missing credentials or incompatible"
+                }
             ),
             "provider_configuration_error",
         )
+    } else if context_overflow_count > 0 && rate_limit_count == 0 && server_error_count == 0 {
+        // The prompt does not fit: let the caller trim it or switch models.
+        let mut response = error_response(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "The request exceeds the context window of the providers in chain '{}'",
+                chain_id
+            ),
+            "context_length_exceeded",
+        );
+        response
+            .extensions_mut()
+            .insert(context_recovery::ContextOverflow);
+        response
     } else if client_error_count > 0 && rate_limit_count == 0 && server_error_count == 0 {
         // All failures were client errors (4xx / auth) — likely a configuration
         // or credentials issue, not a transient rate limit.
@@ -1349,10 +1645,28 @@ async fn enqueue_deferred_request(
 }
 
 /// Rewrite the `model` field in the JSON request body.
-fn rewrite_model(body: &[u8], new_model: &str) -> Result<bytes::Bytes, String> {
+///
+/// For OpenRouter, an OpenAI-style `reasoning_effort` is translated into
+/// OpenRouter's `reasoning` object (unless the client already sent one).
+fn rewrite_model(
+    body: &[u8],
+    new_model: &str,
+    provider_type: ProviderType,
+) -> Result<bytes::Bytes, String> {
     let mut value: serde_json::Value =
         serde_json::from_slice(body).map_err(|e| format!("Invalid JSON: {}", e))?;
     value["model"] = serde_json::Value::String(new_model.to_string());
+    if provider_type == ProviderType::OpenRouter && value.get("reasoning").is_none() {
+        let reasoning = value
+            .get("reasoning_effort")
+            .and_then(|v| v.as_str())
+            .and_then(crate::chat_options::normalize_reasoning_effort)
+            .and_then(|effort| crate::chat_options::openrouter_reasoning(&effort));
+        if let (Some(reasoning), Some(obj)) = (reasoning, value.as_object_mut()) {
+            obj.remove("reasoning_effort");
+            obj.insert("reasoning".to_string(), reasoning);
+        }
+    }
     serde_json::to_vec(&value)
         .map(bytes::Bytes::from)
         .map_err(|e| format!("Failed to serialize: {}", e))
@@ -1945,24 +2259,44 @@ fn normalize_sse_line(line: &[u8]) -> Vec<u8> {
     out
 }
 
+/// Record a streaming attempt that failed before its first token.
+fn record_stream_error(entry: &crate::provider_health::ResolvedEntry, elapsed_ms: u64) {
+    crate::stream_metrics::record(
+        &entry.provider_id,
+        &entry.model_id,
+        crate::stream_metrics::StreamSample {
+            duration_ms: elapsed_ms,
+            errored: true,
+            ..Default::default()
+        },
+    );
+}
+
 /// Wrap a streaming response to defer health tracking until the stream finishes.
 ///
 /// Records `record_success` when the stream ends cleanly, or `record_failure`
-/// if the stream terminates with an I/O error mid-flight.
+/// if the stream terminates with an I/O error mid-flight. The stream's time to
+/// first token, output tokens and outcome also go to [`crate::stream_metrics`].
 fn track_stream_health(
     inner: impl futures::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send + 'static,
     health_tracker: crate::provider_health::SharedProviderHealthTracker,
     account_id: uuid::Uuid,
     rate_limit_snapshot: Option<crate::provider_health::RateLimitSnapshot>,
+    entry: &crate::provider_health::ResolvedEntry,
+    request_start: std::time::Instant,
 ) -> impl futures::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send + 'static {
+    let provider = entry.provider_id.clone();
+    let model = entry.model_id.clone();
     async_stream::stream! {
         let mut stream = std::pin::pin!(inner);
         let mut errored = false;
         let mut received_any = false;
+        let mut ttft_ms: Option<u64> = None;
         let mut input_tokens: u64 = 0;
         let mut output_tokens: u64 = 0;
         while let Some(item) = stream.next().await {
             received_any = true;
+            ttft_ms.get_or_insert_with(|| request_start.elapsed().as_millis() as u64);
             match &item {
                 Ok(chunk) => {
                     // Scan SSE data lines for usage in the final chunk.
@@ -1989,6 +2323,16 @@ fn track_stream_health(
             }
             yield item;
         }
+        crate::stream_metrics::record(
+            &provider,
+            &model,
+            crate::stream_metrics::StreamSample {
+                ttft_ms,
+                output_tokens,
+                duration_ms: request_start.elapsed().as_millis() as u64,
+                errored: errored || !received_any,
+            },
+        );
         if errored || !received_any {
             health_tracker
                 .record_failure(account_id, CooldownReason::ServerError, None)
@@ -3026,6 +3370,34 @@ mod tests {
         assert!(matches!(reason, CooldownReason::Overloaded));
     }
 
+    #[test]
+    fn rewrite_model_translates_reasoning_effort_for_openrouter() {
+        let body = serde_json::json!({"model": "smart", "reasoning_effort": "high"});
+        let bytes = serde_json::to_vec(&body).unwrap();
+
+        let rewritten: serde_json::Value = serde_json::from_slice(
+            &rewrite_model(
+                &bytes,
+                "anthropic/claude-sonnet-4",
+                ProviderType::OpenRouter,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(rewritten["model"], "anthropic/claude-sonnet-4");
+        assert_eq!(
+            rewritten["reasoning"],
+            serde_json::json!({"effort": "high"})
+        );
+        assert!(rewritten.get("reasoning_effort").is_none());
+
+        let untouched: serde_json::Value =
+            serde_json::from_slice(&rewrite_model(&bytes, "gpt-5", ProviderType::OpenAI).unwrap())
+                .unwrap();
+        assert_eq!(untouched["reasoning_effort"], "high");
+        assert!(untouched.get("reasoning").is_none());
+    }
+
     #[test]
     fn build_google_request_tool_message_uses_only_function_response_part() {
         let body = serde_json::json!({
@@ -3128,5 +3500,22 @@ mod tests {
             false,
             false
         ));
+        assert!(has_routable_proxy_credentials(
+            ProviderType::Ollama,
+            false,
+            false
+        ));
+    }
+
+    #[test]
+    fn ollama_completions_url_uses_openai_compatible_api() {
+        assert_eq!(
+            completions_url(ProviderType::Ollama, None).as_deref(),
+            Some("http://127.0.0.1:11434/v1/chat/completions")
+        );
+        assert_eq!(
+            completions_url(ProviderType::Ollama, Some("http://gpu-box:11434")).as_deref(),
+            Some("http://gpu-box:11434/v1/chat/completions")
+        );
     }
 }
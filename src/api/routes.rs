@@ -23,7 +23,7 @@ use tower_http::trace::TraceLayer;
 use uuid::Uuid;
 
 use crate::agents::{AgentContext, AgentRef, OpenCodeAgent};
-use crate::backend::registry::BackendRegistry;
+use crate::backend::{Backend, BackendRegistry};
 use crate::backend_config::BackendConfigEntry;
 use crate::config::{AuthMode, Config};
 use crate::mcp::McpRegistry;
@@ -45,26 +45,47 @@ use super::providers::ModelCatalog;
 
 use super::ai_providers as ai_providers_api;
 use super::ampcode as ampcode_api;
+use super::approvals;
 use super::auth::{self, AuthUser};
 use super::backends as backends_api;
+use super::budget;
 use super::claudecode as claudecode_api;
 use super::console;
 use super::control;
 use super::deferred_proxy as deferred_proxy_api;
 use super::desktop;
 use super::desktop_stream;
+use super::event_export;
+use super::event_schema;
+use super::fair_share;
 use super::fs;
+use super::host_exec;
 use super::library as library_api;
+use super::llm_recorder;
+use super::maintenance;
 use super::mcp as mcp_api;
+use super::mission_audit;
+use super::mission_batch;
+use super::mission_compact;
+use super::mission_compare;
+use super::mission_daemon;
+use super::mission_draft;
+use super::mission_guard;
+use super::mission_messages;
+use super::mission_priority;
+use super::mission_templates;
 use super::model_routing as model_routing_api;
 use super::monitoring;
+use super::oidc;
 use super::opencode as opencode_api;
 use super::proxy as proxy_api;
 use super::proxy_keys as proxy_keys_api;
 use super::secrets as secrets_api;
 use super::settings as settings_api;
 use super::system as system_api;
+use super::terminal_stream;
 use super::types::*;
+use super::workspace_terminal;
 use super::workspaces as workspaces_api;
 
 /// Shared application state.
@@ -114,10 +135,52 @@ pub struct AppState {
     pub proxy_api_keys: super::proxy_keys::SharedProxyApiKeyStore,
     /// Deferred queue for proxy requests that opt into async-on-rate-limit mode
     pub deferred_requests: Arc<deferred_proxy_api::DeferredRequestStore>,
+    /// OIDC discovery cache, pending SSO logins and refresh sessions
+    pub oidc: Arc<oidc::OidcState>,
 }
 
 /// Start the HTTP server.
 pub async fn serve(config: Config) -> anyhow::Result<()> {
+    Server::new(config).serve().await
+}
+
+/// HTTP server with custom backends plugged in next to the built-in ones.
+///
+/// ```no_run
+/// # async fn run(config: sandboxed_sh::config::Config, harness: std::sync::Arc<dyn sandboxed_sh::backend::Backend>) -> anyhow::Result<()> {
+/// sandboxed_sh::api::Server::new(config)
+///     .with_backend(harness)
+///     .serve()
+///     .await
+/// # }
+/// ```
+pub struct Server {
+    config: Config,
+    backends: Vec<Arc<dyn Backend>>,
+}
+
+impl Server {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            backends: Vec::new(),
+        }
+    }
+
+    /// Register a custom backend. It is listed by `GET /api/backends` and
+    /// missions can select it by ID; its ID must not be a built-in one.
+    pub fn with_backend(mut self, backend: Arc<dyn Backend>) -> Self {
+        self.backends.push(backend);
+        self
+    }
+
+    /// Start the HTTP server.
+    pub async fn serve(self) -> anyhow::Result<()> {
+        run(self.config, self.backends).await
+    }
+}
+
+async fn run(config: Config, plugins: Vec<Arc<dyn Backend>>) -> anyhow::Result<()> {
     let mut config = config;
     // Start monitoring background collector early so clients get history immediately
     monitoring::init_monitoring();
@@ -127,11 +190,12 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
     if let Err(e) = crate::opencode_config::ensure_global_config(&mcp).await {
         tracing::warn!("Failed to ensure OpenCode global config: {}", e);
     }
-    // Refresh all MCPs in background
+    // Connect MCPs in background (lazily started ones on first use), then keep them healthy
     {
         let mcp_clone = Arc::clone(&mcp);
         tokio::spawn(async move {
-            mcp_clone.refresh_all().await;
+            mcp_clone.start_all().await;
+            mcp_clone.start_health_checks();
         });
     }
 
@@ -317,8 +381,18 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
     backend_registry.register(crate::backend::claudecode::registry_entry());
     backend_registry.register(crate::backend::amp::registry_entry());
     backend_registry.register(crate::backend::codex::registry_entry());
+    for plugin in plugins {
+        let id = plugin.id().to_string();
+        backend_registry
+            .register_plugin(plugin)
+            .map_err(anyhow::Error::msg)?;
+        tracing::info!("Registered custom backend {}", id);
+    }
+    tracing::info!(
+        "Backend registry initialized with {} backends",
+        backend_registry.len()
+    );
     let backend_registry = Arc::new(RwLock::new(backend_registry));
-    tracing::info!("Backend registry initialized with {} backends", 4);
 
     // Note: No central OpenCode server cleanup needed - missions use per-workspace CLI execution
 
@@ -341,7 +415,9 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
                         }
                     }
                     tracing::info!("Configuration library initialized from {}", library_remote);
-                    *library_clone.write().await = Some(Arc::new(store));
+                    let store = Arc::new(store);
+                    store.start_background_fetch();
+                    *library_clone.write().await = Some(store);
 
                     let workspaces = workspaces_clone.list().await;
                     if let Some(library) = library_clone.read().await.as_ref() {
@@ -379,6 +455,7 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
         Arc::clone(&workspaces),
         Arc::clone(&library),
         secrets.clone(),
+        Arc::clone(&backend_registry),
     );
 
     let state = Arc::new(AppState {
@@ -420,6 +497,7 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
             }),
         proxy_api_keys,
         deferred_requests,
+        oidc: Arc::new(oidc::OidcState::new()),
     });
 
     // Start background desktop session cleanup task
@@ -441,6 +519,19 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
     // Start deferred proxy queue worker.
     deferred_proxy_api::start_worker(Arc::clone(&state));
 
+    // Consume mission requests from NATS/Redis when configured.
+    super::mission_queue::start(Arc::clone(&state));
+    crate::host::log_capabilities();
+
+    if crate::offline::offline_mode() {
+        tracing::info!("Offline mode enabled: only local model providers and hosts are reachable");
+    }
+
+    // Start the outbound web proxy (fetch_url and workspace HTTP traffic) when enabled.
+    if let Err(e) = crate::web_proxy::start(&config.working_dir).await {
+        tracing::warn!("Failed to start outbound web proxy: {}", e);
+    }
+
     // Fetch model catalog from provider APIs in background
     {
         let catalog = Arc::clone(&state.model_catalog);
@@ -461,12 +552,23 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
 
     let public_routes = Router::new()
         .route("/api/health", get(health))
+        .route("/api/schema/events", get(event_schema::get_event_schemas))
         .route("/api/auth/login", post(auth::login))
+        // SSO login flow (the session tokens it issues are checked by require_auth)
+        .route("/api/auth/oidc/login", get(oidc::login))
+        .route("/api/auth/oidc/callback", get(oidc::callback))
+        .route("/api/auth/oidc/exchange", post(oidc::exchange))
+        .route("/api/auth/refresh", post(oidc::refresh))
+        .route("/api/auth/logout", post(oidc::logout))
         // Webhook receiver endpoint (no auth required - uses webhook secret validation)
         .route(
             "/api/webhooks/:mission_id/:webhook_id",
             post(control::webhook_receiver),
         )
+        .route(
+            "/api/daemon-webhooks/:mission_id/:webhook_id",
+            post(mission_daemon::daemon_webhook),
+        )
         // WebSocket console uses subprotocol-based auth (browser can't set Authorization header)
         .route("/api/console/ws", get(console::console_ws))
         // WebSocket workspace shell uses subprotocol-based auth
@@ -474,6 +576,10 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
             "/api/workspaces/:id/shell",
             get(console::workspace_shell_ws),
         )
+        .route(
+            "/api/workspaces/:id/terminal/:session_id/ws",
+            get(workspace_terminal::attach_ws),
+        )
         // WebSocket desktop stream uses subprotocol-based auth
         .route(
             "/api/desktop/stream",
@@ -481,6 +587,37 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
         )
         // WebSocket system monitoring uses subprotocol-based auth
         .route("/api/monitoring/ws", get(monitoring::monitoring_ws))
+        // Live command output: tools post with the proxy secret, the dashboard
+        // reads over a WebSocket with subprotocol-based auth
+        .route(
+            "/api/terminal-stream/:mission_id",
+            post(terminal_stream::post_frames),
+        )
+        // Host exec calls from workspace tools (proxy secret); answered once
+        // an admin decided and the command ran
+        .route(
+            "/api/host-exec/:mission_id",
+            post(host_exec::post_host_exec),
+        )
+        // Guardrails for workspace tools (proxy secret): policies are served
+        // from the server, guarded calls wait for an admin's approval
+        .route(
+            "/api/mission-guard/:mission_id/policies",
+            get(mission_guard::get_policies),
+        )
+        .route(
+            "/api/mission-guard/:mission_id/hooks",
+            get(mission_guard::get_hooks),
+        )
+        .route(
+            "/api/mission-guard/:mission_id/settings",
+            get(mission_guard::get_settings),
+        )
+        .route("/api/approvals/:mission_id", post(approvals::post_approval))
+        .route(
+            "/api/control/missions/:id/terminal/ws",
+            get(terminal_stream::terminal_ws),
+        )
         // OpenAI-compatible proxy endpoint (bearer token auth via SANDBOXED_PROXY_SECRET).
         // LLM payloads with tool outputs and long contexts can exceed the default 2MB
         // body limit, so set a generous 50MB limit for proxy routes.
@@ -528,6 +665,59 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
         // Mission management endpoints
         .route("/api/control/missions", get(control::list_missions))
         .route("/api/control/missions", post(control::create_mission))
+        .route(
+            "/api/control/maintenance/run",
+            post(maintenance::run_maintenance_now),
+        )
+        .route(
+            "/api/control/events/exports",
+            get(event_export::get_event_exports),
+        )
+        .route(
+            "/api/control/budget/buckets",
+            get(budget::get_budget_status),
+        )
+        .route(
+            "/api/control/missions/draft",
+            post(mission_draft::draft_mission),
+        )
+        .route(
+            "/api/control/missions/batch",
+            post(mission_batch::create_mission_batch),
+        )
+        .route(
+            "/api/control/missions/batch/:id",
+            get(mission_batch::get_mission_batch),
+        )
+        .route(
+            "/api/control/missions/compare",
+            get(mission_compare::compare_missions),
+        )
+        .route(
+            "/api/control/missions/:id/compact",
+            post(mission_compact::compact_mission),
+        )
+        .route(
+            "/api/control/missions/:id/messages",
+            post(mission_messages::post_mission_message),
+        )
+        .route(
+            "/api/control/missions/:id/daemon",
+            get(mission_daemon::get_daemon).put(mission_daemon::put_daemon),
+        )
+        .route(
+            "/api/control/missions/:id/daemon/queue/:name",
+            post(mission_daemon::post_queue_message),
+        )
+        .route(
+            "/api/control/missions/:id/daemon/:action",
+            post(mission_daemon::control_daemon),
+        )
+        .route("/api/control/daemons", get(mission_daemon::list_daemons))
+        .route(
+            "/api/control/mission-templates/:name/instantiate",
+            post(mission_templates::instantiate_mission_template),
+        )
         .route(
             "/api/control/missions/current",
             get(control::get_current_mission),
@@ -541,6 +731,34 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
             "/api/control/missions/:id/events",
             get(control::get_mission_events),
         )
+        .route(
+            "/api/control/missions/:id/diff",
+            get(control::get_mission_diff),
+        )
+        .route(
+            "/api/control/missions/:id/plan",
+            get(control::get_mission_plan),
+        )
+        .route(
+            "/api/control/missions/:id/checkpoints",
+            get(control::get_mission_checkpoints),
+        )
+        .route(
+            "/api/control/missions/:id/files-at",
+            get(control::get_mission_file_at),
+        )
+        .route(
+            "/api/control/missions/:id/web-access",
+            get(control::get_mission_web_access),
+        )
+        .route(
+            "/api/control/missions/:id/tool-usage",
+            get(control::get_mission_tool_usage),
+        )
+        .route(
+            "/api/control/missions/:id/debug-bundle",
+            get(llm_recorder::download_debug_bundle),
+        )
         .route(
             "/api/control/missions/:id/load",
             post(control::load_mission),
@@ -553,6 +771,14 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
             "/api/control/missions/:id/title",
             post(control::set_mission_title),
         )
+        .route(
+            "/api/control/missions/:id/locale",
+            post(control::set_mission_locale),
+        )
+        .route(
+            "/api/control/missions/:id/tags",
+            post(control::set_mission_tags),
+        )
         .route(
             "/api/control/missions/:id/cancel",
             post(control::cancel_mission),
@@ -565,6 +791,18 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
             "/api/control/missions/:id/parallel",
             post(control::start_mission_parallel),
         )
+        .route(
+            "/api/control/missions/:id/priority",
+            post(mission_priority::set_mission_priority),
+        )
+        .route(
+            "/api/control/missions/:id/preempt",
+            post(mission_priority::preempt_mission),
+        )
+        .route(
+            "/api/control/missions/:id/audit-report",
+            get(mission_audit::get_audit_report),
+        )
         .route(
             "/api/control/missions/:id",
             axum::routing::delete(control::delete_mission),
@@ -610,6 +848,48 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
             "/api/control/parallel/config",
             get(control::get_parallel_config),
         )
+        .route("/api/control/parallel/shares", get(fair_share::get_shares))
+        .route(
+            "/api/control/parallel/waiting",
+            get(mission_priority::get_waiting),
+        )
+        .route(
+            "/api/control/host-exec/missions/:id",
+            get(host_exec::get_mission_host_exec).put(host_exec::set_mission_host_exec),
+        )
+        .route(
+            "/api/control/host-exec/requests",
+            get(host_exec::list_pending),
+        )
+        .route(
+            "/api/control/host-exec/requests/:id/approve",
+            post(host_exec::approve_request),
+        )
+        .route(
+            "/api/control/host-exec/requests/:id/deny",
+            post(host_exec::deny_request),
+        )
+        .route("/api/control/approvals", get(approvals::list_pending))
+        .route(
+            "/api/control/approvals/:id/approve",
+            post(approvals::approve_request),
+        )
+        .route(
+            "/api/control/approvals/:id/deny",
+            post(approvals::deny_request),
+        )
+        .route(
+            "/api/control/tool-pruning/stats",
+            get(control::get_tool_pruning_stats),
+        )
+        .route(
+            "/api/control/feature-flags",
+            get(control::get_feature_flags),
+        )
+        .route(
+            "/api/control/commits/:hash",
+            get(control::get_commit_provenance),
+        )
         // Memory endpoints
         .route("/api/runs", get(list_runs))
         .route("/api/runs/:id", get(get_run))
@@ -689,6 +969,12 @@ pub async fn serve(config: Config) -> anyhow::Result<()> {
         .nest("/api/ai/providers", ai_providers_api::routes())
         // Model routing (chains + health)
         .nest("/api/model-routing", model_routing_api::routes())
+        // Streaming health per provider/model
+        .route(
+            "/api/models/health",
+            get(model_routing_api::get_models_health),
+        )
+        .route("/metrics", get(model_routing_api::get_metrics))
         // Proxy API key management
         .nest("/api/proxy-keys", proxy_keys_api::routes())
         // Secrets management endpoints
@@ -828,7 +1114,9 @@ async fn health(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
         dev_mode: state.config.dev_mode,
         auth_required: state.config.auth.auth_required(state.config.dev_mode),
         auth_mode: auth_mode.to_string(),
+        oidc_enabled: state.config.auth.oidc.is_some(),
         max_iterations: state.config.max_iterations,
+        offline_mode: crate::offline::offline_mode(),
         library_remote,
     })
 }
@@ -1,5 +1,6 @@
 //! API endpoints for global settings management.
 
+use std::collections::BTreeMap;
 use std::io::{Read as IoRead, Write as IoWrite};
 use std::sync::Arc;
 
@@ -13,11 +14,21 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::settings::Settings;
+use crate::locale::LocaleSettings;
+use crate::model_policy::ModelPolicy;
+use crate::schedule_windows::{MaintenanceWindow, QuietHours};
+use crate::settings::{
+    BudgetBucket, FairShareSettings, MaintenanceSettings, Settings, SpendingAlertSettings,
+};
+use crate::tool_quotas::ToolQuotas;
 use crate::util::internal_error;
 use crate::workspace;
 
+use super::mission_credentials::CredentialIssuer;
+use super::mission_priority::PreemptionSettings;
 use super::routes::AppState;
+use super::step_budget::StepBudget;
+use super::tool_loops::ToolLoopSettings;
 
 /// Create the settings API routes.
 pub fn routes() -> Router<Arc<AppState>> {
@@ -36,6 +47,22 @@ pub struct SettingsResponse {
     pub sandboxed_repo_path: Option<String>,
     pub rtk_enabled: Option<bool>,
     pub max_parallel_missions: Option<usize>,
+    pub quiet_hours: Vec<QuietHours>,
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+    pub maintenance: MaintenanceSettings,
+    pub locale: LocaleSettings,
+    pub budget_buckets: Vec<BudgetBucket>,
+    pub spending_alerts: SpendingAlertSettings,
+    pub llm_recording: bool,
+    pub long_context_model: Option<String>,
+    pub model_policy: ModelPolicy,
+    pub fair_share: FairShareSettings,
+    pub feature_flags: BTreeMap<String, u8>,
+    pub step_budget: StepBudget,
+    pub tool_loops: ToolLoopSettings,
+    pub tool_quotas: ToolQuotas,
+    pub preemption: PreemptionSettings,
+    pub mission_credentials: Vec<CredentialIssuer>,
 }
 
 impl From<Settings> for SettingsResponse {
@@ -45,6 +72,22 @@ impl From<Settings> for SettingsResponse {
             sandboxed_repo_path: settings.sandboxed_repo_path,
             rtk_enabled: settings.rtk_enabled,
             max_parallel_missions: settings.max_parallel_missions,
+            quiet_hours: settings.quiet_hours,
+            maintenance_windows: settings.maintenance_windows,
+            maintenance: settings.maintenance.unwrap_or_default(),
+            locale: settings.locale.unwrap_or_default(),
+            budget_buckets: settings.budget_buckets,
+            spending_alerts: settings.spending_alerts.unwrap_or_default(),
+            llm_recording: settings.llm_recording.unwrap_or(false),
+            long_context_model: settings.long_context_model,
+            model_policy: settings.model_policy.unwrap_or_default(),
+            fair_share: settings.fair_share.unwrap_or_default(),
+            feature_flags: settings.feature_flags,
+            step_budget: settings.step_budget.unwrap_or_default(),
+            tool_loops: settings.tool_loops.unwrap_or_default(),
+            tool_quotas: settings.tool_quotas.unwrap_or_default(),
+            preemption: settings.preemption.unwrap_or_default(),
+            mission_credentials: settings.mission_credentials,
         }
     }
 }
@@ -60,6 +103,46 @@ pub struct UpdateSettingsRequest {
     pub rtk_enabled: Option<bool>,
     #[serde(default)]
     pub max_parallel_missions: Option<usize>,
+    #[serde(default)]
+    pub quiet_hours: Option<Vec<QuietHours>>,
+    #[serde(default)]
+    pub maintenance_windows: Option<Vec<MaintenanceWindow>>,
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceSettings>,
+    /// Default locale and time zone. Send `{}` to clear.
+    #[serde(default)]
+    pub locale: Option<LocaleSettings>,
+    #[serde(default)]
+    pub budget_buckets: Option<Vec<BudgetBucket>>,
+    #[serde(default)]
+    pub spending_alerts: Option<SpendingAlertSettings>,
+    #[serde(default)]
+    pub llm_recording: Option<bool>,
+    /// Long-context fallback chain. Set to null or empty string to clear.
+    #[serde(default)]
+    pub long_context_model: Option<Option<String>>,
+    /// Model allow/deny list and hosting regions. Send `{}` to clear.
+    #[serde(default)]
+    pub model_policy: Option<ModelPolicy>,
+    #[serde(default)]
+    pub fair_share: Option<FairShareSettings>,
+    /// Rollout percentage per feature flag. Send `{}` to clear.
+    #[serde(default)]
+    pub feature_flags: Option<BTreeMap<String, u8>>,
+    /// Default turn and tool call limits of missions. Send `{}` to clear.
+    #[serde(default)]
+    pub step_budget: Option<StepBudget>,
+    #[serde(default)]
+    pub tool_loops: Option<ToolLoopSettings>,
+    /// Per-mission quotas for expensive tools. Send `{}` to clear.
+    #[serde(default)]
+    pub tool_quotas: Option<ToolQuotas>,
+    /// Preemption of running missions by urgent ones.
+    #[serde(default)]
+    pub preemption: Option<PreemptionSettings>,
+    /// Issuers of short-lived mission credentials. Send `[]` to clear.
+    #[serde(default)]
+    pub mission_credentials: Option<Vec<CredentialIssuer>>,
 }
 
 /// Request to update library remote specifically.
@@ -115,6 +198,126 @@ async fn update_settings(
         new_settings.max_parallel_missions = Some(value);
         crate::settings::set_max_parallel_missions_cached(value);
     }
+    if let Some(windows) = req.quiet_hours {
+        for window in &windows {
+            window
+                .validate()
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("quiet_hours: {}", e)))?;
+        }
+        new_settings.quiet_hours = windows;
+    }
+    if let Some(windows) = req.maintenance_windows {
+        for window in &windows {
+            window.validate().map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("maintenance_windows: {}", e),
+                )
+            })?;
+        }
+        new_settings.maintenance_windows = windows;
+    }
+    if let Some(maintenance) = req.maintenance {
+        maintenance
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("maintenance: {}", e)))?;
+        new_settings.maintenance = Some(maintenance);
+        crate::settings::set_maintenance_settings_cached(new_settings.maintenance.clone());
+    }
+    if let Some(locale) = req.locale {
+        locale
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("locale: {}", e)))?;
+        new_settings.locale = Some(locale).filter(|l| !l.is_empty());
+        crate::settings::set_locale_settings_cached(new_settings.locale.clone());
+    }
+    if let Some(mut buckets) = req.budget_buckets {
+        for bucket in &mut buckets {
+            bucket.name = bucket.name.trim().to_string();
+            bucket.tags = super::budget::normalize_tags(&bucket.tags)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("budget_buckets: {}", e)))?;
+        }
+        BudgetBucket::validate_all(&buckets)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("budget_buckets: {}", e)))?;
+        new_settings.budget_buckets = buckets;
+        crate::settings::set_budget_buckets_cached(new_settings.budget_buckets.clone());
+    }
+    if let Some(alerts) = req.spending_alerts {
+        alerts
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("spending_alerts: {}", e)))?;
+        new_settings.spending_alerts = Some(alerts);
+        crate::settings::set_spending_alert_settings_cached(new_settings.spending_alerts.clone());
+    }
+    if let Some(value) = req.llm_recording {
+        new_settings.llm_recording = Some(value);
+        crate::settings::set_llm_recording_cached(value);
+    }
+    if let Some(value) = req.long_context_model {
+        new_settings.long_context_model = value
+            .map(|model| model.trim().to_string())
+            .filter(|model| !model.is_empty());
+        crate::settings::set_long_context_model_cached(new_settings.long_context_model.clone());
+    }
+    if let Some(policy) = req.model_policy {
+        policy
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("model_policy: {}", e)))?;
+        new_settings.model_policy = Some(policy).filter(|p| !p.is_empty());
+        crate::settings::set_model_policy_cached(new_settings.model_policy.clone());
+    }
+    if let Some(fair_share) = req.fair_share {
+        fair_share
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("fair_share: {}", e)))?;
+        new_settings.fair_share = Some(fair_share);
+        crate::settings::set_fair_share_settings_cached(new_settings.fair_share.clone());
+    }
+    if let Some(rollouts) = req.feature_flags {
+        crate::feature_flags::validate_rollouts(&rollouts)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("feature_flags: {}", e)))?;
+        new_settings.feature_flags = rollouts;
+        crate::settings::set_feature_flag_rollouts_cached(new_settings.feature_flags.clone());
+    }
+    if let Some(budget) = req.step_budget {
+        budget
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("step_budget: {}", e)))?;
+        new_settings.step_budget = Some(budget).filter(|b| !b.is_empty());
+        crate::settings::set_step_budget_cached(new_settings.step_budget);
+    }
+    if let Some(tool_loops) = req.tool_loops {
+        tool_loops
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("tool_loops: {}", e)))?;
+        new_settings.tool_loops = Some(tool_loops);
+        crate::settings::set_tool_loop_settings_cached(new_settings.tool_loops);
+    }
+    if let Some(quotas) = req.tool_quotas {
+        new_settings.tool_quotas = Some(quotas).filter(|q| !q.is_empty());
+        crate::settings::set_tool_quotas_cached(new_settings.tool_quotas);
+    }
+    if let Some(preemption) = req.preemption {
+        preemption
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("preemption: {}", e)))?;
+        new_settings.preemption = Some(preemption);
+        crate::settings::set_preemption_settings_cached(new_settings.preemption);
+    }
+    if let Some(issuers) = req.mission_credentials {
+        super::mission_credentials::validate_issuers(&issuers).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("mission_credentials: {}", e),
+            )
+        })?;
+        new_settings.mission_credentials = issuers;
+        crate::settings::set_mission_credentials_cached(new_settings.mission_credentials.clone());
+    }
+    crate::settings::set_schedule_windows_cached(
+        new_settings.quiet_hours.clone(),
+        new_settings.maintenance_windows.clone(),
+    );
 
     state
         .settings
@@ -220,6 +423,7 @@ async fn reinitialize_library(state: &Arc<AppState>, remote: &str) -> Result<(),
 
             tracing::info!("Configuration library reinitialized from {}", remote);
             let library = Arc::new(store);
+            library.start_background_fetch();
             *state.library.write().await = Some(Arc::clone(&library));
 
             // Sync skills/tools to all workspaces
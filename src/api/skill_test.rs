@@ -0,0 +1,604 @@
+//! Skill testing harness.
+//!
+//! `POST /api/library/skills/:name/test` dry-runs a skill before it is used by
+//! real missions: the skill's `setup_commands` run in a throwaway container
+//! (an ephemeral snapshot of an existing workspace, or a scratch container
+//! built from a workspace template), and an optional smoke-test prompt is sent
+//! to a cheap model with the skill loaded as its instructions.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::routes::AppState;
+use crate::config::Config;
+use crate::library::{LibraryStore, Skill};
+use crate::nspawn::{self, NspawnConfig, NspawnDistro};
+use crate::tools::safe_truncate_index;
+use crate::workspace::{self, Workspace, WorkspaceStatus};
+
+/// Default limit for running all setup commands.
+const DEFAULT_TIMEOUT_SECS: u64 = 600;
+/// Model (or chain) used for the smoke-test prompt unless the request names one.
+const DEFAULT_SMOKE_MODEL: &str = "builtin/cheap";
+/// Where the generated setup script is bind-mounted inside the container.
+const SCRIPT_MOUNT: &str = "/sandboxed-skill-test.sh";
+/// Marker lines the setup script prints around each command.
+const STEP_MARKER: &str = "@@sandboxed-skill-step";
+/// Per-step output and smoke-test response are truncated to this many bytes.
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SkillTestRequest {
+    /// Workspace template to build a scratch container from.
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Existing container workspace to run against (as an ephemeral snapshot).
+    #[serde(default)]
+    pub workspace_id: Option<Uuid>,
+    /// Optional smoke-test prompt sent with the skill as system instructions.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Model or chain for the smoke test (default: `builtin/cheap`).
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Substring (case-insensitive) the smoke-test response must contain.
+    #[serde(default)]
+    pub expect: Option<String>,
+    /// Limit for all setup commands together (default: 600).
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Result of one setup command.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SetupStepResult {
+    pub command: String,
+    /// Exit code, or `None` if the command did not finish or never ran.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// True if an earlier command failed so this one never ran.
+    pub skipped: bool,
+    /// Combined stdout/stderr
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmokeTestResult {
+    pub model: String,
+    pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillTestReport {
+    pub skill: String,
+    /// Where setup ran: `workspace:<name>` or `template:<name>`; `None` if
+    /// the skill has no setup commands.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub passed: bool,
+    pub setup: Vec<SetupStepResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smoke_test: Option<SmokeTestResult>,
+    /// Failures outside individual steps (build errors, timeouts, ...)
+    pub errors: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// Container the setup commands run in.
+struct Target {
+    workspace: Workspace,
+    label: String,
+    /// Scratch containers are destroyed afterwards; existing workspaces are
+    /// run with `--ephemeral` so they are never modified.
+    scratch: bool,
+}
+
+/// Run the skill test described by `req`.
+pub async fn run(
+    state: &Arc<AppState>,
+    library: &LibraryStore,
+    name: &str,
+    req: SkillTestRequest,
+) -> Result<SkillTestReport, (StatusCode, String)> {
+    let started = Instant::now();
+    let skill = library
+        .get_skill(name)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+
+    let mut errors = Vec::new();
+    let mut setup = Vec::new();
+    let mut target_label = None;
+
+    if !skill.setup_commands.is_empty() {
+        let target = resolve_target(state, library, &skill, &req).await?;
+        target_label = Some(target.label.clone());
+
+        let timeout = Duration::from_secs(req.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        match run_setup(&target, &skill.setup_commands, timeout).await {
+            Ok(steps) => setup = steps,
+            Err(e) => {
+                errors.push(e);
+                setup = parse_setup_output(&skill.setup_commands, "", None);
+            }
+        }
+
+        if target.scratch {
+            if let Err(e) = workspace::destroy_container_workspace(&target.workspace).await {
+                tracing::warn!(
+                    skill = %skill.name,
+                    error = %e,
+                    "Failed to remove scratch skill-test container"
+                );
+            }
+        }
+    }
+
+    let smoke_test = match req.prompt.as_deref().map(str::trim) {
+        Some(prompt) if !prompt.is_empty() => Some(
+            run_smoke_test(
+                state,
+                &skill,
+                prompt,
+                req.model.as_deref().unwrap_or(DEFAULT_SMOKE_MODEL),
+                req.expect.as_deref(),
+            )
+            .await,
+        ),
+        _ => None,
+    };
+
+    let passed = errors.is_empty()
+        && setup.iter().all(|s| s.exit_code == Some(0))
+        && !matches!(&smoke_test, Some(s) if !s.passed);
+
+    Ok(SkillTestReport {
+        skill: skill.name,
+        target: target_label,
+        passed,
+        setup,
+        smoke_test,
+        errors,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+async fn resolve_target(
+    state: &Arc<AppState>,
+    library: &LibraryStore,
+    skill: &Skill,
+    req: &SkillTestRequest,
+) -> Result<Target, (StatusCode, String)> {
+    if crate::bwrap::container_backend().is_none() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Skill tests need systemd-nspawn or bubblewrap to run setup commands in isolation"
+                .to_string(),
+        ));
+    }
+
+    let runnable =
+        |ws: &Workspace| ws.status == WorkspaceStatus::Ready && workspace::use_container_rootfs(ws);
+
+    if let Some(id) = req.workspace_id {
+        let ws = state
+            .workspaces
+            .get(id)
+            .await
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Workspace {} not found", id)))?;
+        if !runnable(&ws) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Workspace '{}' is not a ready container workspace", ws.name),
+            ));
+        }
+        return Ok(Target {
+            label: format!("workspace:{}", ws.name),
+            workspace: ws,
+            scratch: false,
+        });
+    }
+
+    let Some(template_name) = req.template.as_deref() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Specify a workspace template or workspace_id to run setup commands in".to_string(),
+        ));
+    };
+
+    // Reuse a ready workspace built from the template when there is one.
+    if let Some(ws) = state
+        .workspaces
+        .list()
+        .await
+        .into_iter()
+        .find(|ws| ws.template.as_deref() == Some(template_name) && runnable(ws))
+    {
+        return Ok(Target {
+            label: format!("workspace:{}", ws.name),
+            workspace: ws,
+            scratch: false,
+        });
+    }
+
+    let template = library
+        .get_workspace_template(template_name)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    let distro = match template.distro.as_deref() {
+        Some(d) => Some(NspawnDistro::parse(d).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Template '{}' has unknown distro '{}'", template_name, d),
+            )
+        })?),
+        None => None,
+    };
+
+    let scratch_name = format!("skill-test-{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let mut ws = Workspace::new_container(
+        scratch_name.clone(),
+        state
+            .config
+            .working_dir
+            .join(".sandboxed-sh/containers")
+            .join(&scratch_name),
+    );
+    ws.template = Some(template_name.to_string());
+    ws.env_vars = template.env_vars;
+    ws.init_scripts = template.init_scripts;
+    ws.init_script = Some(template.init_script).filter(|s| !s.trim().is_empty());
+    // The skill under test runs its own setup separately so failures are
+    // attributed to the right command.
+    ws.skills = template
+        .skills
+        .into_iter()
+        .filter(|s| s != &skill.name)
+        .collect();
+
+    tracing::info!(
+        skill = %skill.name,
+        template = %template_name,
+        path = %ws.path.display(),
+        "Building scratch container for skill test"
+    );
+    if let Err(e) = workspace::build_container_workspace(
+        &mut ws,
+        distro,
+        true,
+        &state.config.working_dir,
+        Some(library),
+    )
+    .await
+    {
+        let _ = workspace::destroy_container_workspace(&ws).await;
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to build scratch container from template: {}", e),
+        ));
+    }
+
+    Ok(Target {
+        label: format!("template:{}", template_name),
+        workspace: ws,
+        scratch: true,
+    })
+}
+
+async fn run_setup(
+    target: &Target,
+    commands: &[String],
+    timeout: Duration,
+) -> Result<Vec<SetupStepResult>, String> {
+    let script_path =
+        std::env::temp_dir().join(format!("sandboxed-skill-test-{}.sh", Uuid::new_v4()));
+    tokio::fs::write(&script_path, build_setup_script(commands))
+        .await
+        .map_err(|e| format!("Failed to write setup script: {}", e))?;
+
+    let ws = &target.workspace;
+    let config = NspawnConfig {
+        ephemeral: !target.scratch,
+        env: ws.env_vars.clone(),
+        binds: vec![format!("{}:{}", script_path.display(), SCRIPT_MOUNT)],
+        ..Default::default()
+    };
+    let command = vec![shell_for(&ws.path).to_string(), SCRIPT_MOUNT.to_string()];
+
+    let result = tokio::time::timeout(
+        timeout,
+        nspawn::execute_in_container(&ws.path, &command, &config),
+    )
+    .await;
+    let _ = tokio::fs::remove_file(&script_path).await;
+
+    match result {
+        Ok(Ok(output)) => {
+            let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+            combined.push_str(&String::from_utf8_lossy(&output.stderr));
+            Ok(parse_setup_output(
+                commands,
+                &combined,
+                output.status.code(),
+            ))
+        }
+        Ok(Err(e)) => Err(format!("Failed to run setup commands: {}", e)),
+        Err(_) => Err(format!(
+            "Setup commands timed out after {}s",
+            timeout.as_secs()
+        )),
+    }
+}
+
+fn shell_for(root: &Path) -> &'static str {
+    if root.join("bin/bash").exists() {
+        "/bin/bash"
+    } else {
+        "/bin/sh"
+    }
+}
+
+/// Script running each command in turn, framed by marker lines so output and
+/// exit codes can be attributed per command. Stops at the first failure, like
+/// a workspace init script would leave later commands without their inputs.
+fn build_setup_script(commands: &[String]) -> String {
+    let mut script = String::from("#!/bin/sh\n# Auto-generated skill test script\nexec 2>&1\n");
+    for (i, cmd) in commands.iter().enumerate() {
+        let cmd = LibraryStore::substitute_npm_with_bun(cmd);
+        script.push_str(&format!(
+            "\necho '{marker} {i} start'\n{{\n{cmd}\n}}\nrc=$?\necho \"{marker} {i} exit $rc\"\n[ \"$rc\" -eq 0 ] || exit \"$rc\"\n",
+            marker = STEP_MARKER,
+        ));
+    }
+    script
+}
+
+/// `status` is the script's exit code: a command that ends the script itself
+/// (e.g. `exit 3`) never prints its exit marker, so it inherits that code.
+fn parse_setup_output(
+    commands: &[String],
+    output: &str,
+    status: Option<i32>,
+) -> Vec<SetupStepResult> {
+    let mut steps: Vec<SetupStepResult> = commands
+        .iter()
+        .map(|command| SetupStepResult {
+            command: command.clone(),
+            exit_code: None,
+            skipped: true,
+            output: String::new(),
+        })
+        .collect();
+
+    let mut current: Option<usize> = None;
+    for line in output.lines() {
+        let marker = line
+            .strip_prefix(STEP_MARKER)
+            .map(|rest| rest.split_whitespace().collect::<Vec<_>>());
+        match marker.as_deref() {
+            Some([idx, "start"]) => {
+                current = idx.parse().ok().filter(|i| *i < steps.len());
+                if let Some(i) = current {
+                    steps[i].skipped = false;
+                }
+            }
+            Some([idx, "exit", code]) => {
+                if let Some(step) = idx.parse::<usize>().ok().and_then(|i| steps.get_mut(i)) {
+                    step.exit_code = code.parse().ok();
+                }
+                current = None;
+            }
+            _ => {
+                if let Some(i) = current {
+                    let step = &mut steps[i];
+                    if step.output.len() < MAX_OUTPUT_BYTES {
+                        step.output.push_str(line);
+                        step.output.push('\n');
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(i) = current {
+        steps[i].exit_code = status;
+    }
+    for step in &mut steps {
+        let end = safe_truncate_index(&step.output, MAX_OUTPUT_BYTES);
+        step.output.truncate(end);
+    }
+    steps
+}
+
+async fn run_smoke_test(
+    state: &Arc<AppState>,
+    skill: &Skill,
+    prompt: &str,
+    model: &str,
+    expect: Option<&str>,
+) -> SmokeTestResult {
+    let mut result = SmokeTestResult {
+        model: model.to_string(),
+        prompt: prompt.to_string(),
+        response: None,
+        passed: false,
+        error: None,
+    };
+
+    match complete(state, skill, prompt, model).await {
+        Ok(response) => {
+            let end = safe_truncate_index(&response, MAX_OUTPUT_BYTES);
+            result.passed = smoke_passed(&response, expect);
+            if !result.passed {
+                result.error = Some(match expect {
+                    Some(expect) => format!("Response does not contain '{}'", expect),
+                    None => "Model returned an empty response".to_string(),
+                });
+            }
+            result.response = Some(response[..end].to_string());
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    result
+}
+
+fn smoke_passed(response: &str, expect: Option<&str>) -> bool {
+    match expect.map(str::trim).filter(|e| !e.is_empty()) {
+        Some(expect) => response.to_lowercase().contains(&expect.to_lowercase()),
+        None => !response.trim().is_empty(),
+    }
+}
+
+/// Send the prompt with the skill loaded as system instructions.
+async fn complete(
+    state: &Arc<AppState>,
+    skill: &Skill,
+    prompt: &str,
+    model: &str,
+) -> anyhow::Result<String> {
+    let system = format!(
+        "You are smoke-testing the skill `{}`. Follow its instructions when answering.\n\n{}",
+        skill.name, skill.content
+    );
+    proxy_completion(state, model, &system, prompt, Duration::from_secs(120)).await
+}
+
+/// Send a prompt through the local OpenAI-compatible proxy so model chains
+/// and provider credentials resolve exactly as they do for missions.
+pub(super) async fn proxy_completion(
+    state: &Arc<AppState>,
+    model: &str,
+    system: &str,
+    prompt: &str,
+    timeout: Duration,
+) -> anyhow::Result<String> {
+    config_proxy_completion(
+        &state.config,
+        &state.proxy_secret,
+        model,
+        system,
+        prompt,
+        timeout,
+    )
+    .await
+}
+
+/// [`proxy_completion`] for callers without the app state (e.g. background
+/// tasks of a control session).
+pub(super) async fn config_proxy_completion(
+    config: &Config,
+    proxy_secret: &str,
+    model: &str,
+    system: &str,
+    prompt: &str,
+    timeout: Duration,
+) -> anyhow::Result<String> {
+    let local_host = match config.host.as_str() {
+        "0.0.0.0" | "::" => "127.0.0.1",
+        host => host,
+    };
+
+    let client = reqwest::Client::builder().timeout(timeout).build()?;
+    let response = client
+        .post(format!(
+            "http://{}:{}/v1/chat/completions",
+            local_host, config.port
+        ))
+        .bearer_auth(proxy_secret)
+        .json(&json!({
+            "model": model,
+            "stream": false,
+            "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": prompt }
+            ]
+        }))
+        .send()
+        .await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        anyhow::bail!("Completion request failed ({}): {}", status, body);
+    }
+    Ok(body["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands() -> Vec<String> {
+        vec![
+            "apt-get install -y jq".to_string(),
+            "false".to_string(),
+            "echo never".to_string(),
+        ]
+    }
+
+    #[test]
+    fn setup_script_reports_each_command_and_stops_on_failure() {
+        let commands = vec![
+            "echo hello; echo oops >&2".to_string(),
+            "exit 3".to_string(),
+            "echo never".to_string(),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("setup.sh");
+        std::fs::write(&script, build_setup_script(&commands)).unwrap();
+        let output = std::process::Command::new("sh")
+            .arg(&script)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(3));
+
+        let steps = parse_setup_output(
+            &commands,
+            &String::from_utf8_lossy(&output.stdout),
+            output.status.code(),
+        );
+        assert_eq!(steps[0].exit_code, Some(0));
+        assert_eq!(steps[0].output, "hello\noops\n");
+        assert_eq!(steps[1].exit_code, Some(3));
+        assert!(steps[2].skipped);
+    }
+
+    #[test]
+    fn parses_step_output_and_skips_after_failure() {
+        let output = "\
+@@sandboxed-skill-step 0 start
+Reading package lists...
+@@sandboxed-skill-step 0 exit 0
+@@sandboxed-skill-step 1 start
+@@sandboxed-skill-step 1 exit 1
+";
+        let steps = parse_setup_output(&commands(), output, Some(1));
+        assert_eq!(steps[0].exit_code, Some(0));
+        assert_eq!(steps[0].output, "Reading package lists...\n");
+        assert_eq!(steps[1].exit_code, Some(1));
+        assert!(!steps[1].skipped);
+        assert!(steps[2].skipped);
+        assert_eq!(steps[2].exit_code, None);
+    }
+
+    #[test]
+    fn smoke_test_expectation_is_case_insensitive() {
+        assert!(smoke_passed("Use `JQ` to filter", Some("jq")));
+        assert!(!smoke_passed("Use sed", Some("jq")));
+        assert!(smoke_passed("anything", None));
+        assert!(!smoke_passed("  ", Some(" ")));
+    }
+}
@@ -0,0 +1,295 @@
+//! Spending alerts and anomaly detection.
+//!
+//! A background task checks spend every few minutes against the thresholds in
+//! the `spending_alerts` settings and posts alerts through the [`notifier`]:
+//! - **Daily spend**: spend since midnight (UTC) exceeds `daily_spend_cents`;
+//!   sent at most once a day, with links to the most expensive missions.
+//! - **Expensive mission**: a mission that spent in the last 24 hours has cost
+//!   more than `mission_cost_multiplier` × the median mission cost of the last
+//!   30 days; sent once per mission.
+//! - **Token spike**: tokens used in the last hour exceed
+//!   `token_spike_multiplier` × the hourly average of the 24 hours before;
+//!   sent at most once an hour, with links to the missions active in that hour.
+//!
+//! [`notifier`]: super::notifier
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::mission_store::{MissionCost, MissionStore};
+use super::notifier::{self, mission_link, Notification, NotificationLink};
+use crate::settings::SpendingAlertSettings;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+/// Window the median mission cost is computed over.
+const MEDIAN_WINDOW_DAYS: i64 = 30;
+/// Fewer missions than this in the window make the median meaningless.
+const MIN_MEDIAN_SAMPLES: usize = 5;
+/// Missions linked from a single alert.
+const MAX_LINKS: usize = 5;
+
+fn format_cents(cents: u64) -> String {
+    format!("${}.{:02}", cents / 100, cents % 100)
+}
+
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    })
+}
+
+/// Missions in `recent` whose cost in `history` exceeds `multiplier` × the
+/// median of `history`, with that median.
+fn expensive_missions<'a>(
+    history: &'a [MissionCost],
+    recent: &HashSet<Uuid>,
+    multiplier: f64,
+    min_cost_cents: u64,
+) -> Vec<(&'a MissionCost, u64)> {
+    if history.len() < MIN_MEDIAN_SAMPLES {
+        return Vec::new();
+    }
+    let Some(median) = median(history.iter().map(|c| c.cost_cents).collect()) else {
+        return Vec::new();
+    };
+    let threshold = (median as f64 * multiplier).max(min_cost_cents as f64);
+    history
+        .iter()
+        .filter(|c| recent.contains(&c.mission_id) && c.cost_cents as f64 > threshold)
+        .map(|c| (c, median))
+        .collect()
+}
+
+fn is_token_spike(last_hour: u64, previous_day: u64, multiplier: f64, min_hourly: u64) -> bool {
+    last_hour >= min_hourly && last_hour as f64 > multiplier * (previous_day as f64 / 24.0)
+}
+
+fn mission_links(settings: &SpendingAlertSettings, costs: &[MissionCost]) -> Vec<NotificationLink> {
+    let mut costs: Vec<&MissionCost> = costs.iter().collect();
+    costs.sort_by_key(|c| std::cmp::Reverse(c.cost_cents));
+    costs
+        .into_iter()
+        .take(MAX_LINKS)
+        .map(|c| NotificationLink {
+            label: format!("Mission {} ({})", c.mission_id, format_cents(c.cost_cents)),
+            url: mission_link(settings.dashboard_url.as_deref(), c.mission_id),
+        })
+        .collect()
+}
+
+/// What has already been alerted on, so alerts are not repeated.
+#[derive(Debug, Default)]
+struct AlertState {
+    daily_alerted: Option<NaiveDate>,
+    missions_alerted: HashSet<Uuid>,
+    spike_alerted_at: Option<DateTime<Utc>>,
+}
+
+/// Run every enabled check and return the alerts to send.
+async fn check(
+    store: &Arc<dyn MissionStore>,
+    settings: &SpendingAlertSettings,
+    state: &mut AlertState,
+    now: DateTime<Utc>,
+) -> Result<Vec<Notification>, String> {
+    let mut alerts = Vec::new();
+    let ts = |t: DateTime<Utc>| t.to_rfc3339();
+    let now_ts = ts(now);
+
+    if let Some(limit) = settings.daily_spend_cents {
+        let today = now.date_naive();
+        if state.daily_alerted != Some(today) {
+            let midnight = ts(today.and_hms_opt(0, 0, 0).unwrap().and_utc());
+            let spent = store.get_total_cost_cents_since(&midnight).await?;
+            if spent > limit {
+                let costs = store.get_mission_costs_between(&midnight, &now_ts).await?;
+                alerts.push(Notification {
+                    kind: "daily_spend".to_string(),
+                    title: "Daily spend threshold exceeded".to_string(),
+                    text: format!(
+                        "Spent {} today (UTC), above the {} threshold, across {} missions.",
+                        format_cents(spent),
+                        format_cents(limit),
+                        costs.len()
+                    ),
+                    links: mission_links(settings, &costs),
+                    data: json!({ "spent_cents": spent, "threshold_cents": limit }),
+                });
+                state.daily_alerted = Some(today);
+            }
+        }
+    }
+
+    if let Some(multiplier) = settings.mission_cost_multiplier {
+        let since = ts(now - chrono::Duration::days(MEDIAN_WINDOW_DAYS));
+        let history = store.get_mission_costs_between(&since, &now_ts).await?;
+        let recent: HashSet<Uuid> = store
+            .get_mission_costs_between(&ts(now - chrono::Duration::hours(24)), &now_ts)
+            .await?
+            .into_iter()
+            .map(|c| c.mission_id)
+            .collect();
+        for (cost, median) in expensive_missions(
+            &history,
+            &recent,
+            multiplier,
+            settings.min_mission_cost_cents,
+        ) {
+            if !state.missions_alerted.insert(cost.mission_id) {
+                continue;
+            }
+            alerts.push(Notification {
+                kind: "mission_cost".to_string(),
+                title: "Unusually expensive mission".to_string(),
+                text: format!(
+                    "Mission {} has cost {}, {:.1}× the median mission cost of {} over the last {} days.",
+                    cost.mission_id,
+                    format_cents(cost.cost_cents),
+                    cost.cost_cents as f64 / median.max(1) as f64,
+                    format_cents(median),
+                    MEDIAN_WINDOW_DAYS
+                ),
+                links: mission_links(settings, std::slice::from_ref(cost)),
+                data: json!({
+                    "mission_id": cost.mission_id,
+                    "cost_cents": cost.cost_cents,
+                    "median_cents": median,
+                }),
+            });
+        }
+        // Forget missions that dropped out of the window.
+        let in_window: HashSet<Uuid> = history.iter().map(|c| c.mission_id).collect();
+        state.missions_alerted.retain(|id| in_window.contains(id));
+    }
+
+    if let Some(multiplier) = settings.token_spike_multiplier {
+        let recently_alerted = state
+            .spike_alerted_at
+            .is_some_and(|at| now - at < chrono::Duration::hours(1));
+        if !recently_alerted {
+            let hour_ago = ts(now - chrono::Duration::hours(1));
+            let day_before = ts(now - chrono::Duration::hours(25));
+            let last_hour = store.get_token_usage_between(&hour_ago, &now_ts).await?;
+            let previous = store
+                .get_token_usage_between(&day_before, &hour_ago)
+                .await?;
+            if is_token_spike(last_hour, previous, multiplier, settings.min_hourly_tokens) {
+                let costs = store.get_mission_costs_between(&hour_ago, &now_ts).await?;
+                alerts.push(Notification {
+                    kind: "token_spike".to_string(),
+                    title: "Token usage spike".to_string(),
+                    text: format!(
+                        "{} tokens used in the last hour, against an hourly average of {} over the previous 24 hours.",
+                        last_hour,
+                        previous / 24
+                    ),
+                    links: mission_links(settings, &costs),
+                    data: json!({
+                        "last_hour_tokens": last_hour,
+                        "previous_day_tokens": previous,
+                    }),
+                });
+                state.spike_alerted_at = Some(now);
+            }
+        }
+    }
+
+    Ok(alerts)
+}
+
+/// Background task that checks spend while `spending_alerts.enabled` is set.
+pub async fn spending_alert_loop(store: Arc<dyn MissionStore>) {
+    let mut state = AlertState::default();
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        let settings = crate::settings::spending_alert_settings_cached();
+        let Some(url) = settings.webhook_url.clone().filter(|_| settings.enabled) else {
+            continue;
+        };
+        let alerts = match check(&store, &settings, &mut state, Utc::now()).await {
+            Ok(alerts) => alerts,
+            Err(e) => {
+                tracing::warn!(error = %e, "Spending alert check failed");
+                continue;
+            }
+        };
+        for alert in alerts {
+            tracing::info!(kind = %alert.kind, "Sending spending alert");
+            if let Err(e) = notifier::send(&url, &alert).await {
+                tracing::warn!(kind = %alert.kind, error = %e, "Failed to send spending alert");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(cost_cents: u64) -> MissionCost {
+        MissionCost {
+            mission_id: Uuid::new_v4(),
+            workspace_id: Uuid::nil(),
+            tags: Vec::new(),
+            cost_cents,
+        }
+    }
+
+    #[test]
+    fn flags_recent_missions_far_above_median() {
+        let history: Vec<MissionCost> = [100, 120, 90, 110, 105, 2000, 300]
+            .into_iter()
+            .map(cost)
+            .collect();
+        let recent: HashSet<Uuid> = history[4..].iter().map(|c| c.mission_id).collect();
+        let flagged = expensive_missions(&history, &recent, 5.0, 100);
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].0.cost_cents, 2000);
+        assert_eq!(flagged[0].1, 110);
+
+        // The minimum cost applies even when the median is tiny.
+        assert!(expensive_missions(&history, &recent, 5.0, 5000).is_empty());
+        assert!(expensive_missions(&history[..3], &recent, 5.0, 0).is_empty());
+    }
+
+    #[test]
+    fn detects_token_spikes() {
+        assert!(is_token_spike(500_000, 24 * 50_000, 3.0, 100_000));
+        assert!(!is_token_spike(120_000, 24 * 50_000, 3.0, 100_000));
+        assert!(!is_token_spike(50_000, 0, 3.0, 100_000));
+        assert_eq!(median(vec![4, 1, 3, 2]), Some(2));
+        assert_eq!(format_cents(1205), "$12.05");
+    }
+
+    #[tokio::test]
+    async fn no_alerts_without_spend() {
+        // The in-memory store reports no spend, so nothing fires.
+        let store: Arc<dyn MissionStore> =
+            Arc::new(super::super::mission_store::InMemoryMissionStore::new());
+        let settings = SpendingAlertSettings {
+            daily_spend_cents: Some(0),
+            mission_cost_multiplier: Some(3.0),
+            token_spike_multiplier: Some(3.0),
+            ..Default::default()
+        };
+        let mut state = AlertState::default();
+        let alerts = check(&store, &settings, &mut state, Utc::now())
+            .await
+            .unwrap();
+        assert!(alerts.is_empty());
+        assert!(state.daily_alerted.is_none());
+        assert!(state.spike_alerted_at.is_none());
+    }
+}
@@ -0,0 +1,577 @@
+//! Step budgets: limits on the turns, tool calls and cost of a mission.
+//!
+//! The settings give a default budget and a mission can override any limit
+//! when it is created:
+//!
+//! ```json
+//! "step_budget": { "max_turns": 40, "max_tool_calls": 500, "max_cost_cents": 200 }
+//! ```
+//!
+//! A background task counts each mission's turns (assistant messages) and tool
+//! calls from the session's events. When a mission has used all its turns, or
+//! goes over its tool calls in the middle of a turn (which is then cancelled),
+//! the agent is asked for a wrap-up without tools: what was done, what is left
+//! and how to continue. Once the wrap-up answers, the mission is completed with
+//! terminal reason `step_budget_exhausted`. A wrap-up that keeps calling tools
+//! is cancelled after [`WRAP_UP_TOOL_CALLS`] calls and the mission completed
+//! without it.
+//!
+//! A mission whose cost goes over `max_cost_cents` is cancelled after the turn
+//! that crossed it and ends `interrupted` with terminal reason
+//! `budget_exceeded`. The budget is stored with the mission and its cost
+//! recounted from the stored events, so the limit still holds after a server
+//! restart or when the mission is resumed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+use super::control::{AgentEvent, ControlCommand, MissionStatus};
+use super::mission_batch::{event_cost_cents, is_terminal};
+use super::mission_store::MissionStore;
+
+/// Terminal reason of missions stopped by their step budget.
+pub const EXHAUSTED_REASON: &str = "step_budget_exhausted";
+
+/// Terminal reason of missions stopped by their cost limit.
+pub const COST_EXCEEDED_REASON: &str = "budget_exceeded";
+
+/// Tool calls the wrap-up turn may make before it is cancelled.
+pub const WRAP_UP_TOOL_CALLS: u64 = 10;
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Turn, tool call and cost limits; unset limits are unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepBudget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_calls: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost_cents: Option<u64>,
+}
+
+impl StepBudget {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_turns == Some(0)
+            || self.max_tool_calls == Some(0)
+            || self.max_cost_cents == Some(0)
+        {
+            return Err("step budget limits must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_turns.is_none() && self.max_tool_calls.is_none() && self.max_cost_cents.is_none()
+    }
+
+    /// This budget with its unset limits taken from `defaults`.
+    pub fn or(self, defaults: StepBudget) -> StepBudget {
+        StepBudget {
+            max_turns: self.max_turns.or(defaults.max_turns),
+            max_tool_calls: self.max_tool_calls.or(defaults.max_tool_calls),
+            max_cost_cents: self.max_cost_cents.or(defaults.max_cost_cents),
+        }
+    }
+}
+
+/// The budget of a mission under the current settings.
+pub fn effective(mission_budget: Option<StepBudget>) -> StepBudget {
+    mission_budget
+        .unwrap_or_default()
+        .or(crate::settings::step_budget_cached().unwrap_or_default())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Running,
+    /// The wrap-up message was sent and, once `started`, is being answered
+    WrapUp {
+        message_id: Uuid,
+        started: bool,
+        tool_calls: u64,
+    },
+    Done,
+}
+
+/// What the supervisor does after an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    None,
+    /// Ask for a wrap-up, cancelling the running turn first
+    WrapUp {
+        cancel: bool,
+    },
+    /// Complete the mission, cancelling the running turn first
+    Finish {
+        cancel: bool,
+    },
+}
+
+/// Steps a mission has used.
+#[derive(Debug, Clone)]
+struct Usage {
+    budget: Option<StepBudget>,
+    turns: u64,
+    tool_calls: u64,
+    cost_cents: u64,
+    phase: Phase,
+}
+
+impl Usage {
+    /// Add a turn's cost; whether the mission is now over its cost limit.
+    fn on_cost(&mut self, cost_cents: u64, budget: StepBudget) -> bool {
+        self.cost_cents += cost_cents;
+        budget
+            .max_cost_cents
+            .is_some_and(|max| self.cost_cents > max)
+    }
+
+    fn on_tool_call(&mut self, budget: StepBudget) -> Action {
+        self.tool_calls += 1;
+        match &mut self.phase {
+            Phase::Running
+                if budget
+                    .max_tool_calls
+                    .is_some_and(|max| self.tool_calls > max) =>
+            {
+                Action::WrapUp { cancel: true }
+            }
+            Phase::WrapUp {
+                started: true,
+                tool_calls,
+                ..
+            } => {
+                *tool_calls += 1;
+                if *tool_calls > WRAP_UP_TOOL_CALLS {
+                    Action::Finish { cancel: true }
+                } else {
+                    Action::None
+                }
+            }
+            _ => Action::None,
+        }
+    }
+
+    fn on_turn_started(&mut self, id: Uuid) {
+        if let Phase::WrapUp {
+            message_id,
+            started,
+            ..
+        } = &mut self.phase
+        {
+            *started |= *message_id == id;
+        }
+    }
+
+    fn on_turn_finished(&mut self, budget: StepBudget) -> Action {
+        self.turns += 1;
+        match self.phase {
+            Phase::Running if budget.max_turns.is_some_and(|max| self.turns >= max) => {
+                Action::WrapUp { cancel: false }
+            }
+            Phase::WrapUp { started: true, .. } => Action::Finish { cancel: false },
+            _ => Action::None,
+        }
+    }
+
+    /// Which limit ran out, for the status summary.
+    fn exhausted(&self, budget: StepBudget) -> String {
+        match (budget.max_turns, budget.max_tool_calls) {
+            (_, Some(max)) if self.tool_calls > max => {
+                format!("tool call budget exhausted ({} allowed)", max)
+            }
+            (Some(max), _) => format!("turn budget exhausted ({} turns used)", max),
+            _ => "step budget exhausted".to_string(),
+        }
+    }
+}
+
+fn wrap_up_prompt(reason: &str) -> String {
+    format!(
+        "The mission's {}. Do not call any more tools. Reply with a short wrap-up: \
+         what was done, what is left, and how to continue the work.",
+        reason
+    )
+}
+
+struct Supervisor {
+    store: Arc<dyn MissionStore>,
+    cmd_tx: mpsc::Sender<ControlCommand>,
+    events_tx: broadcast::Sender<AgentEvent>,
+    missions: HashMap<Uuid, Usage>,
+}
+
+impl Supervisor {
+    /// Usage of a mission, counted from its stored events the first time it is
+    /// seen.
+    async fn usage(&mut self, mission_id: Uuid) -> Option<&mut Usage> {
+        if !self.missions.contains_key(&mission_id) {
+            let mission = self.store.get_mission(mission_id).await.ok()??;
+            let events = self
+                .store
+                .get_events(
+                    mission_id,
+                    Some(&["assistant_message", "tool_call"]),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap_or_default();
+            let count = |event_type: &str| {
+                events.iter().filter(|e| e.event_type == event_type).count() as u64
+            };
+            let done = mission.terminal_reason.as_deref() == Some(EXHAUSTED_REASON);
+            self.missions.insert(
+                mission_id,
+                Usage {
+                    budget: mission.step_budget,
+                    turns: count("assistant_message"),
+                    tool_calls: count("tool_call"),
+                    cost_cents: events
+                        .iter()
+                        .filter(|e| e.event_type == "assistant_message")
+                        .map(event_cost_cents)
+                        .sum(),
+                    phase: if done { Phase::Done } else { Phase::Running },
+                },
+            );
+        }
+        self.missions.get_mut(&mission_id)
+    }
+
+    async fn observe(&mut self, event: &AgentEvent) {
+        let Some(mission_id) = event.mission_id() else {
+            return;
+        };
+        if let AgentEvent::UserMessage {
+            id, queued: false, ..
+        } = event
+        {
+            if let Some(usage) = self.missions.get_mut(&mission_id) {
+                usage.on_turn_started(*id);
+            }
+            return;
+        }
+        if !matches!(
+            event,
+            AgentEvent::ToolCall { .. } | AgentEvent::AssistantMessage { .. }
+        ) {
+            return;
+        }
+        let Some(usage) = self.usage(mission_id).await else {
+            return;
+        };
+        let budget = effective(usage.budget);
+        if let AgentEvent::AssistantMessage { cost_cents, .. } = event {
+            if usage.on_cost(*cost_cents, budget) {
+                let spent = usage.cost_cents;
+                self.stop_over_cost(mission_id, spent, budget).await;
+                return;
+            }
+        }
+        let action = match event {
+            AgentEvent::ToolCall { .. } => usage.on_tool_call(budget),
+            _ => usage.on_turn_finished(budget),
+        };
+        let reason = usage.exhausted(budget);
+        match action {
+            Action::None => {}
+            Action::WrapUp { cancel } => self.wrap_up(mission_id, cancel, &reason).await,
+            Action::Finish { cancel } => self.finish(mission_id, cancel, &reason).await,
+        }
+    }
+
+    async fn cancel(&self, mission_id: Uuid) {
+        let (tx, rx) = oneshot::channel();
+        let command = ControlCommand::CancelMission {
+            mission_id,
+            respond: tx,
+        };
+        if let Some(Err(e)) = send_command(&self.cmd_tx, command, rx).await {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to cancel mission over its step budget");
+        }
+    }
+
+    /// Cancel a mission that went over its cost limit.
+    async fn stop_over_cost(&self, mission_id: Uuid, spent: u64, budget: StepBudget) {
+        let running = self
+            .store
+            .get_mission(mission_id)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|m| !is_terminal(m.status));
+        if !running {
+            return;
+        }
+        let max = budget.max_cost_cents.unwrap_or_default();
+        tracing::warn!(
+            mission_id = %mission_id,
+            cost_cents = spent,
+            max_cost_cents = max,
+            "Mission exceeded its cost budget; cancelling"
+        );
+        // Stops a queued turn that already started; an idle mission has none
+        let (tx, rx) = oneshot::channel();
+        let command = ControlCommand::CancelMission {
+            mission_id,
+            respond: tx,
+        };
+        let _ = send_command(&self.cmd_tx, command, rx).await;
+        if let Err(e) = self
+            .store
+            .update_mission_status_with_reason(
+                mission_id,
+                MissionStatus::Interrupted,
+                Some(COST_EXCEEDED_REASON),
+            )
+            .await
+        {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to interrupt mission over its cost budget");
+            return;
+        }
+        let _ = self.events_tx.send(AgentEvent::MissionStatusChanged {
+            mission_id,
+            status: MissionStatus::Interrupted,
+            summary: Some(format!(
+                "Stopped: cost budget exceeded ({} of {} cents spent)",
+                spent, max
+            )),
+        });
+    }
+
+    async fn wrap_up(&mut self, mission_id: Uuid, cancel: bool, reason: &str) {
+        if cancel {
+            self.cancel(mission_id).await;
+        } else {
+            // The turn limit is checked after each turn: missions that ended
+            // on their own have nothing to wrap up
+            let active = self
+                .store
+                .get_mission(mission_id)
+                .await
+                .ok()
+                .flatten()
+                .is_some_and(|m| m.status == MissionStatus::Active);
+            if !active {
+                return;
+            }
+        }
+        tracing::info!(mission_id = %mission_id, "Mission {}; asking for a wrap-up", reason);
+        let message_id = Uuid::new_v4();
+        if let Some(usage) = self.missions.get_mut(&mission_id) {
+            usage.phase = Phase::WrapUp {
+                message_id,
+                started: false,
+                tool_calls: 0,
+            };
+        }
+        let (tx, rx) = oneshot::channel();
+        let command = ControlCommand::UserMessage {
+            id: message_id,
+            content: wrap_up_prompt(reason),
+            agent: None,
+            target_mission_id: Some(mission_id),
+            respond: tx,
+        };
+        if send_command(&self.cmd_tx, command, rx).await.is_none() {
+            self.finish(mission_id, false, reason).await;
+        }
+    }
+
+    async fn finish(&mut self, mission_id: Uuid, cancel: bool, reason: &str) {
+        if let Some(usage) = self.missions.get_mut(&mission_id) {
+            usage.phase = Phase::Done;
+        }
+        if cancel {
+            self.cancel(mission_id).await;
+        }
+        if let Err(e) = self
+            .store
+            .update_mission_status_with_reason(
+                mission_id,
+                MissionStatus::Completed,
+                Some(EXHAUSTED_REASON),
+            )
+            .await
+        {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to complete mission over its step budget");
+            return;
+        }
+        let _ = self.events_tx.send(AgentEvent::MissionStatusChanged {
+            mission_id,
+            status: MissionStatus::Completed,
+            summary: Some(format!("Stopped: {}", reason)),
+        });
+    }
+}
+
+async fn send_command<T>(
+    cmd_tx: &mpsc::Sender<ControlCommand>,
+    command: ControlCommand,
+    rx: oneshot::Receiver<T>,
+) -> Option<T> {
+    cmd_tx.send(command).await.ok()?;
+    tokio::time::timeout(COMMAND_TIMEOUT, rx).await.ok()?.ok()
+}
+
+/// Background task that enforces the step budgets of one control session's
+/// missions.
+pub async fn step_budget_loop(
+    store: Arc<dyn MissionStore>,
+    cmd_tx: mpsc::Sender<ControlCommand>,
+    events_tx: broadcast::Sender<AgentEvent>,
+) {
+    let mut events = events_tx.subscribe();
+    let mut supervisor = Supervisor {
+        store,
+        cmd_tx,
+        events_tx,
+        missions: HashMap::new(),
+    };
+    loop {
+        match events.recv().await {
+            Ok(event) => supervisor.observe(&event).await,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                // Counts missed events: recount from the store
+                tracing::debug!(skipped, "Step budget supervisor lagged behind events");
+                supervisor
+                    .missions
+                    .retain(|_, usage| usage.phase != Phase::Running);
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage() -> Usage {
+        Usage {
+            budget: None,
+            turns: 0,
+            tool_calls: 0,
+            cost_cents: 0,
+            phase: Phase::Running,
+        }
+    }
+
+    #[test]
+    fn mission_budget_overrides_defaults() {
+        let defaults = StepBudget {
+            max_turns: Some(50),
+            max_tool_calls: Some(1000),
+            max_cost_cents: None,
+        };
+        let mission = StepBudget {
+            max_turns: Some(5),
+            max_tool_calls: None,
+            max_cost_cents: Some(200),
+        };
+        assert_eq!(
+            mission.or(defaults),
+            StepBudget {
+                max_turns: Some(5),
+                max_tool_calls: Some(1000),
+                max_cost_cents: Some(200),
+            }
+        );
+        assert!(StepBudget::default().is_empty());
+        assert!(StepBudget {
+            max_turns: Some(0),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        assert!(StepBudget {
+            max_cost_cents: Some(0),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn cost_limit_counts_cost_already_spent() {
+        let budget = StepBudget {
+            max_cost_cents: Some(100),
+            ..Default::default()
+        };
+        // Usage recounted from stored events after a restart or resume
+        let mut usage = Usage {
+            cost_cents: 90,
+            ..usage()
+        };
+        assert!(!usage.on_cost(10, budget));
+        assert!(usage.on_cost(1, budget));
+        assert!(!usage.on_cost(1_000, StepBudget::default()));
+    }
+
+    #[test]
+    fn turn_limit_asks_for_wrap_up_then_finishes() {
+        let budget = StepBudget {
+            max_turns: Some(2),
+            max_tool_calls: None,
+            ..Default::default()
+        };
+        let mut usage = usage();
+        assert_eq!(usage.on_turn_finished(budget), Action::None);
+        assert_eq!(
+            usage.on_turn_finished(budget),
+            Action::WrapUp { cancel: false }
+        );
+        let message_id = Uuid::new_v4();
+        usage.phase = Phase::WrapUp {
+            message_id,
+            started: false,
+            tool_calls: 0,
+        };
+        // Another message's turn finishing does not end the wrap-up
+        usage.on_turn_started(Uuid::new_v4());
+        assert_eq!(usage.on_turn_finished(budget), Action::None);
+        usage.on_turn_started(message_id);
+        assert_eq!(
+            usage.on_turn_finished(budget),
+            Action::Finish { cancel: false }
+        );
+        assert!(usage.exhausted(budget).contains("turn budget"));
+    }
+
+    #[test]
+    fn tool_call_limit_cancels_and_bounds_wrap_up() {
+        let budget = StepBudget {
+            max_turns: None,
+            max_tool_calls: Some(3),
+            ..Default::default()
+        };
+        let mut usage = usage();
+        for _ in 0..3 {
+            assert_eq!(usage.on_tool_call(budget), Action::None);
+        }
+        assert_eq!(usage.on_tool_call(budget), Action::WrapUp { cancel: true });
+        assert!(usage.exhausted(budget).contains("tool call budget"));
+
+        let message_id = Uuid::new_v4();
+        usage.phase = Phase::WrapUp {
+            message_id,
+            started: false,
+            tool_calls: 0,
+        };
+        // Calls of the cancelled turn still arriving are not the wrap-up's
+        assert_eq!(usage.on_tool_call(budget), Action::None);
+        usage.on_turn_started(message_id);
+        for _ in 0..WRAP_UP_TOOL_CALLS {
+            assert_eq!(usage.on_tool_call(budget), Action::None);
+        }
+        assert_eq!(usage.on_tool_call(budget), Action::Finish { cancel: true });
+    }
+}
@@ -1,7 +1,7 @@
 //! System component management API.
 //!
 //! Provides endpoints to query and update system components like OpenCode
-//! and oh-my-opencode.
+//! and oh-my-opencode, and to inspect or purge the shared package caches.
 
 use std::pin::Pin;
 use std::sync::Arc;
@@ -13,7 +13,7 @@ use axum::{
         sse::{Event, Sse},
         Json,
     },
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use futures::stream::Stream;
@@ -21,6 +21,7 @@ use serde::Serialize;
 use tokio::process::Command;
 
 use super::routes::AppState;
+use crate::package_cache::{self, PackageCacheKind, PackageCacheStats, PurgeResult};
 use crate::util::home_dir;
 
 /// Git remote used for sandboxed.sh self-updates
@@ -266,6 +267,14 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/components", get(get_components))
         .route("/components/:name/update", post(update_component))
         .route("/components/:name/uninstall", post(uninstall_component))
+        .route("/package-caches", get(get_package_caches))
+        .route("/package-caches/:kind", delete(purge_package_cache))
+        .route("/capabilities", get(get_capabilities))
+}
+
+/// Report the host platform and which sandbox features are degraded on it.
+async fn get_capabilities() -> Json<crate::host::HostCapabilities> {
+    Json(crate::host::capabilities())
 }
 
 /// Get information about all system components.
@@ -464,15 +473,10 @@ async fn get_codex_info() -> ComponentInfo {
 }
 
 /// Find the path to a CLI binary.
-/// Checks `which` first (respects the user's PATH), then explicit fallback paths.
+/// Checks the user's PATH first, then explicit fallback paths.
 async fn which_binary(name: &str, fallback_paths: &[&str]) -> Option<String> {
-    if let Ok(output) = Command::new("which").arg(name).output().await {
-        if output.status.success() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                return Some(path);
-            }
-        }
+    if let Some(path) = crate::host::find_command(name) {
+        return Some(path.to_string_lossy().into_owned());
     }
     for path in fallback_paths {
         if std::path::Path::new(path).exists() {
@@ -552,6 +556,9 @@ async fn which_amp() -> Option<String> {
 
 /// Fetch the latest version string for an npm package from the registry.
 async fn fetch_npm_latest_version(package: &str) -> Option<String> {
+    if crate::offline::offline_mode() {
+        return None;
+    }
     let url = format!("https://registry.npmjs.org/{package}/latest");
     let resp = reqwest::Client::new()
         .get(&url)
@@ -592,6 +599,9 @@ async fn check_codex_update(current_version: Option<&str>) -> Option<String> {
 /// Check if there's a newer version of OpenCode available.
 async fn check_opencode_update(current_version: Option<&str>) -> Option<String> {
     let current = current_version?;
+    if crate::offline::offline_mode() {
+        return None;
+    }
 
     // Fetch latest release from opencode.ai or GitHub
     let client = reqwest::Client::new();
@@ -629,14 +639,18 @@ async fn check_sandboxed_update(
 ) -> Option<String> {
     let current = current_version?;
 
-    // First, try GitHub releases API
+    // First, try GitHub releases API (not in offline mode)
     let client = reqwest::Client::new();
-    let resp = client
-        .get("https://api.github.com/repos/Th0rgal/sandboxed.sh/releases/latest")
-        .header("User-Agent", "open-agent")
-        .send()
-        .await
-        .ok();
+    let resp = if crate::offline::offline_mode() {
+        None
+    } else {
+        client
+            .get("https://api.github.com/repos/Th0rgal/sandboxed.sh/releases/latest")
+            .header("User-Agent", "open-agent")
+            .send()
+            .await
+            .ok()
+    };
 
     if let Some(resp) = resp {
         if resp.status().is_success() {
@@ -851,6 +865,40 @@ async fn update_component(
     }
 }
 
+/// Disk usage of the shared package manager caches.
+async fn get_package_caches() -> Result<Json<Vec<PackageCacheStats>>, (StatusCode, String)> {
+    tokio::task::spawn_blocking(package_cache::stats)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Purge one shared package cache (`cargo`, `npm`, `pip`) or `all` of them.
+async fn purge_package_cache(
+    Path(kind): Path<String>,
+) -> Result<Json<Vec<PurgeResult>>, (StatusCode, String)> {
+    let kinds = if kind == "all" {
+        PackageCacheKind::ALL.to_vec()
+    } else {
+        vec![PackageCacheKind::parse(&kind).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Unknown package cache: {}", kind),
+            )
+        })?]
+    };
+    tokio::task::spawn_blocking(move || {
+        kinds
+            .into_iter()
+            .map(package_cache::purge)
+            .collect::<anyhow::Result<Vec<_>>>()
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map(Json)
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
 /// Uninstall a system component.
 async fn uninstall_component(
     State(_state): State<Arc<AppState>>,
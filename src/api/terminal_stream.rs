@@ -0,0 +1,255 @@
+//! Live terminal view of mission commands.
+//!
+//! Workspace tools post `run_command` output frames (see
+//! `tools::terminal_stream`) to `POST /api/terminal-stream/:mission_id`,
+//! authenticated with the internal proxy secret. They are fanned out to
+//! dashboard clients on `GET /api/control/missions/:id/terminal/ws`, a
+//! read-only WebSocket where every command is a channel of `open`, `data` and
+//! `exit` frames. Clients can follow one command with `?channel=`.
+//!
+//! Recent frames are kept per mission so a client that connects mid-command
+//! sees its output so far. Nothing is persisted: the final result still
+//! arrives as the tool result in the mission history.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::auth;
+use super::routes::AppState;
+use crate::config::Role;
+use crate::tools::terminal_stream::TerminalFrame;
+
+/// Frames kept for late joiners, per mission.
+const REPLAY_BYTES: usize = 256 * 1024;
+/// Missions with terminal state; the least recently active is dropped first.
+const MAX_MISSIONS: usize = 64;
+
+struct MissionTerminal {
+    tx: broadcast::Sender<TerminalFrame>,
+    replay: VecDeque<TerminalFrame>,
+    replay_bytes: usize,
+    updated: Instant,
+}
+
+impl MissionTerminal {
+    fn new() -> Self {
+        Self {
+            tx: broadcast::channel(1024).0,
+            replay: VecDeque::new(),
+            replay_bytes: 0,
+            updated: Instant::now(),
+        }
+    }
+}
+
+static HUB: Mutex<BTreeMap<Uuid, MissionTerminal>> = Mutex::new(BTreeMap::new());
+
+fn with_terminal<T>(mission_id: Uuid, f: impl FnOnce(&mut MissionTerminal) -> T) -> T {
+    let mut hub = HUB.lock().unwrap_or_else(|e| e.into_inner());
+    if !hub.contains_key(&mission_id) && hub.len() >= MAX_MISSIONS {
+        if let Some(oldest) = hub
+            .iter()
+            .filter(|(_, t)| t.tx.receiver_count() == 0)
+            .min_by_key(|(_, t)| t.updated)
+            .map(|(id, _)| *id)
+        {
+            hub.remove(&oldest);
+        }
+    }
+    f(hub.entry(mission_id).or_insert_with(MissionTerminal::new))
+}
+
+/// Record frames for a mission and send them to connected clients.
+pub fn publish(mission_id: Uuid, frames: Vec<TerminalFrame>) {
+    with_terminal(mission_id, |terminal| {
+        terminal.updated = Instant::now();
+        for frame in frames {
+            terminal.replay_bytes += frame.size();
+            terminal.replay.push_back(frame.clone());
+            let _ = terminal.tx.send(frame);
+        }
+        while terminal.replay_bytes > REPLAY_BYTES {
+            match terminal.replay.pop_front() {
+                Some(old) => terminal.replay_bytes -= old.size(),
+                None => break,
+            }
+        }
+    });
+}
+
+/// Buffered frames plus a receiver for new ones.
+fn subscribe(mission_id: Uuid) -> (Vec<TerminalFrame>, broadcast::Receiver<TerminalFrame>) {
+    with_terminal(mission_id, |terminal| {
+        (
+            terminal.replay.iter().cloned().collect(),
+            terminal.tx.subscribe(),
+        )
+    })
+}
+
+/// POST /api/terminal-stream/:mission_id - output frames from workspace tools.
+pub async fn post_frames(
+    State(state): State<Arc<AppState>>,
+    Path(mission_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(frames): Json<Vec<TerminalFrame>>,
+) -> impl IntoResponse {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let authorized = !state.proxy_secret.is_empty()
+        && token.is_some_and(|t| auth::constant_time_eq(t, &state.proxy_secret));
+    if !authorized {
+        return StatusCode::UNAUTHORIZED;
+    }
+    publish(mission_id, frames);
+    StatusCode::NO_CONTENT
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TerminalParams {
+    /// Only stream this command's channel
+    pub channel: Option<String>,
+}
+
+/// Extract JWT from WebSocket subprotocol header
+fn extract_jwt_from_protocols(headers: &HeaderMap) -> Option<String> {
+    let raw = headers
+        .get("sec-websocket-protocol")
+        .and_then(|v| v.to_str().ok())?;
+    for part in raw.split(',').map(|s| s.trim()) {
+        if let Some(rest) = part.strip_prefix("jwt.") {
+            if !rest.is_empty() {
+                return Some(rest.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// GET /api/control/missions/:id/terminal/ws - live command output.
+pub async fn terminal_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Path(mission_id): Path<Uuid>,
+    Query(params): Query<TerminalParams>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if state.config.auth.auth_required(state.config.dev_mode) {
+        let token = match extract_jwt_from_protocols(&headers) {
+            Some(t) => t,
+            None => return (StatusCode::UNAUTHORIZED, "Missing websocket JWT").into_response(),
+        };
+        if !auth::verify_token_for_config(&token, &state.config, Role::Viewer) {
+            return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response();
+        }
+    }
+
+    ws.protocols(["sandboxed"])
+        .on_upgrade(move |socket| handle_terminal_stream(socket, mission_id, params.channel))
+}
+
+async fn handle_terminal_stream(socket: WebSocket, mission_id: Uuid, channel: Option<String>) {
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+    let wanted = |frame: &TerminalFrame| channel.as_deref().is_none_or(|c| frame.channel() == c);
+
+    let (replay, mut rx) = subscribe(mission_id);
+    for frame in replay.iter().filter(|f| wanted(f)) {
+        let Ok(json) = serde_json::to_string(frame) else {
+            continue;
+        };
+        if ws_sender.send(Message::Text(json)).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            frame = rx.recv() => {
+                let frame = match frame {
+                    Ok(frame) => frame,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        let notice = serde_json::json!({ "type": "lagged", "skipped": skipped });
+                        if ws_sender.send(Message::Text(notice.to_string())).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !wanted(&frame) {
+                    continue;
+                }
+                let Ok(json) = serde_json::to_string(&frame) else {
+                    continue;
+                };
+                if ws_sender.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+            // Read-only stream: client messages are ignored until it closes.
+            msg = ws_receiver.next() => {
+                match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::terminal_stream::OutputStream;
+
+    fn data(channel: &str, text: &str) -> TerminalFrame {
+        TerminalFrame::Data {
+            channel: channel.to_string(),
+            stream: OutputStream::Stdout,
+            data: text.to_string(),
+            ts: "2026-01-01T00:00:00.000Z".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn late_subscribers_get_replay_then_live_frames() {
+        let mission_id = Uuid::new_v4();
+        publish(mission_id, vec![data("a", "one\n"), data("b", "two\n")]);
+
+        let (replay, mut rx) = subscribe(mission_id);
+        assert_eq!(replay, vec![data("a", "one\n"), data("b", "two\n")]);
+
+        publish(mission_id, vec![data("a", "three\n")]);
+        assert_eq!(rx.recv().await.unwrap(), data("a", "three\n"));
+    }
+
+    #[test]
+    fn replay_is_bounded() {
+        let mission_id = Uuid::new_v4();
+        let chunk = "x".repeat(64 * 1024);
+        for i in 0..8 {
+            publish(mission_id, vec![data(&i.to_string(), &chunk)]);
+        }
+        let (replay, _rx) = subscribe(mission_id);
+        let total: usize = replay.iter().map(TerminalFrame::size).sum();
+        assert!(total <= REPLAY_BYTES);
+        assert_eq!(replay.last().unwrap().channel(), "7");
+    }
+}
@@ -0,0 +1,219 @@
+//! Repair of malformed tool-call arguments in proxied chat completions.
+//!
+//! Non-streaming `/v1/chat/completions` responses are checked for tool calls
+//! whose `function.arguments` is not valid JSON. Arguments that
+//! [`crate::json_repair`] can fix are rewritten in place. If a call cannot be
+//! repaired, the request is re-sent (at most [`MAX_REASKS`] times) with the
+//! model's tool calls answered by a structured `invalid_tool_arguments`
+//! error, so the model can issue them again. Counts per model are exposed by
+//! `GET /api/model-routing/tool-call-repairs`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::json_repair;
+
+/// Maximum number of times one request is re-sent for invalid arguments.
+pub const MAX_REASKS: usize = 2;
+
+/// Tool-call argument repair counts for one model, since startup.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RepairStats {
+    pub tool_calls: u64,
+    pub repaired: u64,
+    pub unrepairable: u64,
+    pub reasks: u64,
+    /// Repaired calls per kind of fix
+    pub fixes: BTreeMap<&'static str, u64>,
+}
+
+static STATS: Mutex<BTreeMap<String, RepairStats>> = Mutex::new(BTreeMap::new());
+
+fn record(model: &str, update: impl FnOnce(&mut RepairStats)) {
+    let mut stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    update(stats.entry(model.to_string()).or_default());
+}
+
+/// Repair counts per model.
+pub fn stats() -> BTreeMap<String, RepairStats> {
+    STATS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Count a re-ask for `model`.
+pub fn record_reask(model: &str) {
+    record(model, |s| s.reasks += 1);
+}
+
+/// A tool call whose arguments could not be repaired.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidCall {
+    pub id: String,
+    pub name: String,
+    pub error: String,
+}
+
+/// Result of [`repair_response`].
+#[derive(Debug, Default)]
+pub struct RepairOutcome {
+    /// Calls whose arguments were rewritten
+    pub repaired: usize,
+    pub invalid: Vec<InvalidCall>,
+}
+
+/// Model a chat completion response reports, for stats.
+pub fn response_model(response: &Value) -> &str {
+    response
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown")
+}
+
+/// Repair the tool-call arguments of a chat completion response in place.
+pub fn repair_response(response: &mut Value) -> RepairOutcome {
+    let model = response_model(response).to_string();
+    let mut outcome = RepairOutcome::default();
+    let Some(choices) = response.get_mut("choices").and_then(Value::as_array_mut) else {
+        return outcome;
+    };
+    let calls = choices
+        .iter_mut()
+        .filter_map(|choice| choice.pointer_mut("/message/tool_calls"))
+        .filter_map(Value::as_array_mut)
+        .flatten();
+    for call in calls {
+        let id = call.get("id").and_then(Value::as_str).unwrap_or_default();
+        let id = id.to_string();
+        let Some(function) = call.get_mut("function") else {
+            continue;
+        };
+        let name = function
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string();
+        let Some(arguments) = function.get("arguments").and_then(Value::as_str) else {
+            continue;
+        };
+        record(&model, |s| s.tool_calls += 1);
+        if arguments.trim().is_empty() {
+            continue;
+        }
+        match json_repair::parse(arguments) {
+            Ok(repaired) if repaired.fixes.is_empty() => {}
+            Ok(repaired) => {
+                tracing::debug!(model = %model, tool = %name, fixes = ?repaired.fixes, "Repaired tool-call arguments");
+                function["arguments"] = Value::String(repaired.value.to_string());
+                outcome.repaired += 1;
+                record(&model, |s| {
+                    s.repaired += 1;
+                    for fix in &repaired.fixes {
+                        *s.fixes.entry(fix.as_str()).or_default() += 1;
+                    }
+                });
+            }
+            Err(error) => {
+                record(&model, |s| s.unrepairable += 1);
+                outcome.invalid.push(InvalidCall { id, name, error });
+            }
+        }
+    }
+    outcome
+}
+
+/// Messages to append to the request before re-asking: the assistant's
+/// message, then a tool result for each of its calls, with a validation error
+/// for the invalid ones.
+pub fn reask_messages(response: &Value, invalid: &[InvalidCall]) -> Vec<Value> {
+    let Some(message) = response.pointer("/choices/0/message") else {
+        return Vec::new();
+    };
+    let mut messages = vec![message.clone()];
+    let calls = message
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten();
+    for call in calls {
+        let id = call.get("id").and_then(Value::as_str).unwrap_or_default();
+        let content = match invalid.iter().find(|c| c.id == id) {
+            Some(invalid) => json!({
+                "error": "invalid_tool_arguments",
+                "tool": invalid.name,
+                "message": format!(
+                    "The arguments are not valid JSON ({}). Call the tool again with a valid JSON object.",
+                    invalid.error
+                ),
+            }),
+            None => json!({
+                "error": "not_executed",
+                "message": "Not executed because another tool call in the same message had invalid arguments. Call it again if still needed.",
+            }),
+        };
+        messages.push(json!({
+            "role": "tool",
+            "tool_call_id": id,
+            "content": content.to_string(),
+        }));
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(calls: Value) -> Value {
+        json!({
+            "model": "test-model",
+            "choices": [{"message": {"role": "assistant", "content": null, "tool_calls": calls}}]
+        })
+    }
+
+    #[test]
+    fn repairs_arguments_in_place() {
+        let mut value = response(json!([
+            {"id": "a", "type": "function", "function": {"name": "read_file", "arguments": "{\"path\": \"x\",}"}},
+            {"id": "b", "type": "function", "function": {"name": "ls", "arguments": "{}"}},
+            {"id": "c", "type": "function", "function": {"name": "grep", "arguments": "{\"q\" 1}"}},
+        ]));
+        let outcome = repair_response(&mut value);
+        assert_eq!(outcome.repaired, 1);
+        assert_eq!(outcome.invalid.len(), 1);
+        assert_eq!(outcome.invalid[0].name, "grep");
+        assert_eq!(
+            value["choices"][0]["message"]["tool_calls"][0]["function"]["arguments"],
+            "{\"path\":\"x\"}"
+        );
+        let stats = stats()["test-model"].clone();
+        assert!(stats.tool_calls >= 3);
+        assert!(stats.fixes["trailing_comma"] >= 1);
+    }
+
+    #[test]
+    fn reask_answers_every_call() {
+        let value = response(json!([
+            {"id": "a", "type": "function", "function": {"name": "read_file", "arguments": "{}"}},
+            {"id": "c", "type": "function", "function": {"name": "grep", "arguments": "{\"q\" 1}"}},
+        ]));
+        let invalid = [InvalidCall {
+            id: "c".to_string(),
+            name: "grep".to_string(),
+            error: "expected `:`".to_string(),
+        }];
+        let messages = reask_messages(&value, &invalid);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["role"], "assistant");
+        assert!(messages[1]["content"]
+            .as_str()
+            .unwrap()
+            .contains("not_executed"));
+        assert_eq!(messages[2]["tool_call_id"], "c");
+        assert!(messages[2]["content"]
+            .as_str()
+            .unwrap()
+            .contains("invalid_tool_arguments"));
+    }
+}
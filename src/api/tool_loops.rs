@@ -0,0 +1,441 @@
+//! Detection of repeated failing tool calls.
+//!
+//! Models sometimes retry the same failing tool call over and over. A
+//! background task follows each mission's tool calls and results and counts,
+//! per call, how often it failed among the mission's recent calls. Calls count
+//! as the same when their tool and arguments match after whitespace is
+//! collapsed and free-text fields such as `description` are dropped.
+//!
+//! After `advise_after` failures the running turn is cancelled and the agent
+//! gets an advisory message quoting the last error and asking it to change
+//! approach. If the call keeps failing, after `abort_after` failures the
+//! mission is stopped as failed with terminal reason `infinite_loop`. The
+//! thresholds are in the global settings:
+//!
+//! ```json
+//! "tool_loops": { "enabled": true, "advise_after": 3, "abort_after": 6 }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use uuid::Uuid;
+
+use super::control::{AgentEvent, ControlCommand, MissionStatus};
+use super::mission_store::MissionStore;
+use crate::tools::safe_truncate_index;
+
+/// Terminal reason of missions stopped for repeating a failing call.
+pub const LOOP_REASON: &str = "infinite_loop";
+
+/// Calls after which an earlier failing call is forgotten.
+const RECENT_CALLS: u64 = 20;
+/// Arguments that do not change what a call does.
+const IGNORED_ARGS: &[&str] = &["description", "explanation", "timeout"];
+/// Error text quoted in the advisory.
+const MAX_ERROR_CHARS: usize = 500;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Repeated failing tool call handling (global settings).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolLoopSettings {
+    pub enabled: bool,
+    /// Failures of the same call before the agent is advised to change approach
+    pub advise_after: u32,
+    /// Failures of the same call before the mission is stopped
+    pub abort_after: u32,
+}
+
+impl Default for ToolLoopSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            advise_after: 3,
+            abort_after: 6,
+        }
+    }
+}
+
+impl ToolLoopSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.advise_after < 2 {
+            return Err("advise_after must be at least 2".to_string());
+        }
+        if self.abort_after <= self.advise_after {
+            return Err("abort_after must be greater than advise_after".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Arguments with whitespace collapsed and free-text fields dropped, so
+/// near-identical calls compare equal.
+fn normalize(args: &Value) -> Value {
+    match args {
+        Value::String(s) => Value::String(s.split_whitespace().collect::<Vec<_>>().join(" ")),
+        Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| !IGNORED_ARGS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), normalize(value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Key identifying a call for repeat counting.
+fn fingerprint(name: &str, args: &Value) -> String {
+    // serde_json maps are sorted, so equal arguments serialize the same way
+    format!("{}:{}", name, normalize(args))
+}
+
+/// The error of a failed tool result, as the harnesses report it.
+fn failure(result: &Value) -> Option<String> {
+    match result {
+        Value::String(text) => {
+            let trimmed = text.trim_start();
+            let failed = trimmed.starts_with("Error")
+                || trimmed.starts_with("Tool error")
+                || trimmed.contains("<tool_use_error>");
+            failed.then(|| text.clone())
+        }
+        Value::Object(map) => {
+            if map.get("is_error").and_then(Value::as_bool) == Some(true) {
+                let text = ["stderr", "content", "error"]
+                    .iter()
+                    .filter_map(|key| map.get(*key).and_then(Value::as_str))
+                    .find(|text| !text.trim().is_empty())
+                    .unwrap_or("(no output)");
+                return Some(text.to_string());
+            }
+            match map.get("error") {
+                Some(Value::String(error)) if !error.is_empty() => Some(error.clone()),
+                Some(error @ Value::Object(_)) => Some(error.to_string()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn truncated(text: &str, max: usize) -> String {
+    let end = safe_truncate_index(text, max);
+    if end < text.len() {
+        format!("{}…", &text[..end])
+    } else {
+        text.to_string()
+    }
+}
+
+/// Failures of one call.
+#[derive(Debug, Clone)]
+struct Repeat {
+    tool: String,
+    failures: u32,
+    last_call: u64,
+    advised: bool,
+}
+
+/// What to do after a tool result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Verdict {
+    None,
+    Advise {
+        tool: String,
+        failures: u32,
+        error: String,
+    },
+    Abort {
+        tool: String,
+        failures: u32,
+    },
+}
+
+/// Tool calls of one mission.
+#[derive(Debug, Default)]
+struct Tracker {
+    calls: u64,
+    /// Fingerprints of calls waiting for their result, by tool call ID
+    pending: HashMap<String, String>,
+    repeats: HashMap<String, Repeat>,
+}
+
+impl Tracker {
+    fn on_call(&mut self, id: &str, name: &str, args: &Value) {
+        self.calls += 1;
+        let calls = self.calls;
+        self.repeats
+            .retain(|_, repeat| repeat.last_call + RECENT_CALLS >= calls);
+        self.pending.insert(id.to_string(), fingerprint(name, args));
+    }
+
+    fn on_result(
+        &mut self,
+        id: &str,
+        name: &str,
+        result: &Value,
+        settings: ToolLoopSettings,
+    ) -> Verdict {
+        let Some(key) = self.pending.remove(id) else {
+            return Verdict::None;
+        };
+        let Some(error) = failure(result) else {
+            self.repeats.remove(&key);
+            return Verdict::None;
+        };
+        let repeat = self.repeats.entry(key).or_insert_with(|| Repeat {
+            tool: name.to_string(),
+            failures: 0,
+            last_call: 0,
+            advised: false,
+        });
+        repeat.failures += 1;
+        repeat.last_call = self.calls;
+        if repeat.failures >= settings.abort_after {
+            Verdict::Abort {
+                tool: repeat.tool.clone(),
+                failures: repeat.failures,
+            }
+        } else if repeat.failures >= settings.advise_after && !repeat.advised {
+            repeat.advised = true;
+            Verdict::Advise {
+                tool: repeat.tool.clone(),
+                failures: repeat.failures,
+                error: truncated(&error, MAX_ERROR_CHARS),
+            }
+        } else {
+            Verdict::None
+        }
+    }
+}
+
+fn advisory(tool: &str, failures: u32, error: &str) -> String {
+    format!(
+        "You have called `{}` with the same arguments {} times and it failed every time. \
+         The last error was:\n\n{}\n\nDo not repeat this call. Read the error, check your \
+         assumptions and try a different approach, or explain what is blocking you.",
+        tool, failures, error
+    )
+}
+
+async fn send_command<T>(
+    cmd_tx: &mpsc::Sender<ControlCommand>,
+    command: ControlCommand,
+    rx: oneshot::Receiver<T>,
+) -> Option<T> {
+    cmd_tx.send(command).await.ok()?;
+    tokio::time::timeout(COMMAND_TIMEOUT, rx).await.ok()?.ok()
+}
+
+struct Supervisor {
+    store: Arc<dyn MissionStore>,
+    cmd_tx: mpsc::Sender<ControlCommand>,
+    events_tx: broadcast::Sender<AgentEvent>,
+    missions: HashMap<Uuid, Tracker>,
+}
+
+impl Supervisor {
+    async fn observe(&mut self, event: &AgentEvent) {
+        let settings = crate::settings::tool_loop_settings_cached();
+        match event {
+            AgentEvent::ToolCall {
+                tool_call_id,
+                name,
+                args,
+                mission_id: Some(mission_id),
+            } if settings.enabled => {
+                self.missions
+                    .entry(*mission_id)
+                    .or_default()
+                    .on_call(tool_call_id, name, args);
+            }
+            AgentEvent::ToolResult {
+                tool_call_id,
+                name,
+                result,
+                mission_id: Some(mission_id),
+            } if settings.enabled => {
+                let Some(tracker) = self.missions.get_mut(mission_id) else {
+                    return;
+                };
+                match tracker.on_result(tool_call_id, name, result, settings) {
+                    Verdict::None => {}
+                    Verdict::Advise {
+                        tool,
+                        failures,
+                        error,
+                    } => self.advise(*mission_id, &tool, failures, &error).await,
+                    Verdict::Abort { tool, failures } => {
+                        self.missions.remove(mission_id);
+                        self.abort(*mission_id, &tool, failures).await;
+                    }
+                }
+            }
+            AgentEvent::MissionStatusChanged {
+                mission_id, status, ..
+            } if !matches!(status, MissionStatus::Active | MissionStatus::Interrupted) => {
+                self.missions.remove(mission_id);
+            }
+            _ => {}
+        }
+    }
+
+    async fn cancel(&self, mission_id: Uuid) {
+        let (tx, rx) = oneshot::channel();
+        let command = ControlCommand::CancelMission {
+            mission_id,
+            respond: tx,
+        };
+        if let Some(Err(e)) = send_command(&self.cmd_tx, command, rx).await {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to cancel mission repeating a tool call");
+        }
+    }
+
+    /// Stop the turn and tell the agent to change approach.
+    async fn advise(&self, mission_id: Uuid, tool: &str, failures: u32, error: &str) {
+        tracing::info!(mission_id = %mission_id, tool, failures, "Mission repeats a failing tool call; advising");
+        self.cancel(mission_id).await;
+        let (tx, rx) = oneshot::channel();
+        let command = ControlCommand::UserMessage {
+            id: Uuid::new_v4(),
+            content: advisory(tool, failures, error),
+            agent: None,
+            target_mission_id: Some(mission_id),
+            respond: tx,
+        };
+        if send_command(&self.cmd_tx, command, rx).await.is_none() {
+            tracing::warn!(mission_id = %mission_id, "Failed to send tool loop advisory");
+        }
+    }
+
+    async fn abort(&self, mission_id: Uuid, tool: &str, failures: u32) {
+        tracing::warn!(mission_id = %mission_id, tool, failures, "Stopping mission repeating a failing tool call");
+        self.cancel(mission_id).await;
+        if let Err(e) = self
+            .store
+            .update_mission_status_with_reason(mission_id, MissionStatus::Failed, Some(LOOP_REASON))
+            .await
+        {
+            tracing::warn!(mission_id = %mission_id, error = %e, "Failed to stop mission repeating a tool call");
+            return;
+        }
+        let _ = self.events_tx.send(AgentEvent::MissionStatusChanged {
+            mission_id,
+            status: MissionStatus::Failed,
+            summary: Some(format!(
+                "Stopped: `{}` failed {} times with the same arguments",
+                tool, failures
+            )),
+        });
+    }
+}
+
+/// Background task that breaks repeated failing tool calls in one control
+/// session's missions.
+pub async fn repeat_monitor_loop(
+    store: Arc<dyn MissionStore>,
+    cmd_tx: mpsc::Sender<ControlCommand>,
+    events_tx: broadcast::Sender<AgentEvent>,
+) {
+    let mut events = events_tx.subscribe();
+    let mut supervisor = Supervisor {
+        store,
+        cmd_tx,
+        events_tx,
+        missions: HashMap::new(),
+    };
+    loop {
+        match events.recv().await {
+            Ok(event) => supervisor.observe(&event).await,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!(skipped, "Tool loop monitor lagged behind events");
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn near_identical_calls_share_a_fingerprint() {
+        let a = json!({ "command": "cargo  build\n", "description": "Build the crate" });
+        let b = json!({ "command": "cargo build", "description": "Try building again" });
+        assert_eq!(fingerprint("Bash", &a), fingerprint("Bash", &b));
+        assert_ne!(
+            fingerprint("Bash", &a),
+            fingerprint("Bash", &json!({ "command": "cargo test" }))
+        );
+        assert_ne!(fingerprint("Bash", &a), fingerprint("run_command", &a));
+    }
+
+    #[test]
+    fn recognizes_failed_results() {
+        assert!(failure(&json!("Error: file not found")).is_some());
+        assert!(failure(&json!(
+            "<tool_use_error>File does not exist</tool_use_error>"
+        ))
+        .is_some());
+        assert!(failure(&json!("Build finished")).is_none());
+        assert_eq!(
+            failure(&json!({ "content": "", "stderr": "permission denied", "is_error": true }))
+                .as_deref(),
+            Some("permission denied")
+        );
+        assert!(failure(&json!({ "content": "ok", "is_error": false })).is_none());
+        assert!(failure(&json!({ "error": "timeout" })).is_some());
+    }
+
+    #[test]
+    fn advises_then_aborts_on_repeated_failures() {
+        let settings = ToolLoopSettings::default();
+        let mut tracker = Tracker::default();
+        let args = json!({ "path": "/tmp/missing" });
+        let mut verdicts = Vec::new();
+        for i in 0..6 {
+            let id = format!("call-{}", i);
+            tracker.on_call(&id, "read_file", &args);
+            verdicts.push(tracker.on_result(
+                &id,
+                "read_file",
+                &json!("Error: not found"),
+                settings,
+            ));
+        }
+        assert_eq!(verdicts[1], Verdict::None);
+        assert!(matches!(verdicts[2], Verdict::Advise { failures: 3, .. }));
+        // Advised once, then left alone until the abort threshold
+        assert_eq!(verdicts[3], Verdict::None);
+        assert!(matches!(verdicts[5], Verdict::Abort { failures: 6, .. }));
+
+        // A success resets the count
+        let mut tracker = Tracker::default();
+        for (i, result) in ["Error: busy", "Error: busy", "done", "Error: busy"]
+            .iter()
+            .enumerate()
+        {
+            let id = i.to_string();
+            tracker.on_call(&id, "run_command", &args);
+            assert_eq!(
+                tracker.on_result(&id, "run_command", &json!(result), settings),
+                Verdict::None
+            );
+        }
+        assert!(ToolLoopSettings {
+            advise_after: 4,
+            abort_after: 4,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
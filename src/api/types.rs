@@ -137,7 +137,7 @@ pub struct TaskEvent {
 }
 
 /// Health check response.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthResponse {
     /// Service status
     pub status: String,
@@ -154,16 +154,24 @@ pub struct HealthResponse {
     /// Authentication mode ("disabled", "single_tenant", "multi_user")
     pub auth_mode: String,
 
+    /// Whether SSO login via `/api/auth/oidc/login` is available
+    #[serde(default)]
+    pub oidc_enabled: bool,
+
     /// Maximum iterations per agent (from MAX_ITERATIONS env var)
     pub max_iterations: usize,
 
+    /// Whether offline (air-gapped) mode is enabled (SANDBOXED_SH_OFFLINE)
+    #[serde(default)]
+    pub offline_mode: bool,
+
     /// Configured library remote URL (from LIBRARY_REMOTE env var)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub library_remote: Option<String>,
 }
 
 /// Login request for dashboard auth.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginRequest {
     #[serde(default)]
     pub username: Option<String>,
@@ -171,9 +179,12 @@ pub struct LoginRequest {
 }
 
 /// Login response containing a JWT for API authentication.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
     /// Expiration as unix seconds.
     pub exp: i64,
+    /// Refresh token for `/api/auth/refresh` (SSO sessions only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
 }
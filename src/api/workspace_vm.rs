@@ -0,0 +1,130 @@
+//! microVM control for workspaces whose template sets `microvm`.
+//!
+//! - `GET /api/workspaces/:id/vm` - VM status
+//! - `POST /api/workspaces/:id/vm/start` / `stop` - boot or shut down the VM
+//!   (commands boot it on demand, so `start` is only needed to warm it up)
+//! - `GET`/`PUT /api/workspaces/:id/vm/file?path=` - read or write a file in
+//!   the guest through the agent, e.g. on Firecracker where the VM disk is not
+//!   visible from the host
+
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Path as AxumPath, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::routes::AppState;
+use super::workspaces::require_workspace;
+use crate::microvm::{self, VmStatus};
+use crate::workspace::Workspace;
+
+/// Largest file accepted by `PUT /vm/file`.
+const MAX_FILE_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct VmFileQuery {
+    /// Absolute path inside the guest
+    pub path: String,
+}
+
+async fn require_microvm(state: &AppState, id: Uuid) -> Result<Workspace, (StatusCode, String)> {
+    let workspace = require_workspace(&state.workspaces, id).await?;
+    if !microvm::is_microvm_root(&workspace.path) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "Workspace {} is not a built microVM workspace",
+                workspace.name
+            ),
+        ));
+    }
+    Ok(workspace)
+}
+
+fn guest_path(path: &str) -> Result<&str, (StatusCode, String)> {
+    if !path.starts_with('/') {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "path must be absolute inside the VM".to_string(),
+        ));
+    }
+    Ok(path)
+}
+
+/// GET /api/workspaces/:id/vm
+pub async fn get_vm(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+) -> Result<Json<VmStatus>, (StatusCode, String)> {
+    let workspace = require_microvm(&state, id).await?;
+    Ok(Json(microvm::status(&workspace.path)))
+}
+
+/// POST /api/workspaces/:id/vm/start
+pub async fn start_vm(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+) -> Result<Json<VmStatus>, (StatusCode, String)> {
+    let workspace = require_microvm(&state, id).await?;
+    microvm::ensure_running(&workspace.path)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(microvm::status(&workspace.path)))
+}
+
+/// POST /api/workspaces/:id/vm/stop
+pub async fn stop_vm(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+) -> Result<Json<VmStatus>, (StatusCode, String)> {
+    let workspace = require_microvm(&state, id).await?;
+    microvm::stop(&workspace.path).await;
+    Ok(Json(microvm::status(&workspace.path)))
+}
+
+/// GET /api/workspaces/:id/vm/file?path=
+pub async fn read_vm_file(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+    Query(query): Query<VmFileQuery>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let workspace = require_microvm(&state, id).await?;
+    let path = guest_path(&query.path)?;
+    microvm::ensure_running(&workspace.path)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let data = microvm::read_file(&workspace.path, path)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], data))
+}
+
+/// PUT /api/workspaces/:id/vm/file?path=
+pub async fn write_vm_file(
+    State(state): State<Arc<AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+    Query(query): Query<VmFileQuery>,
+    body: Bytes,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let workspace = require_microvm(&state, id).await?;
+    let path = guest_path(&query.path)?;
+    if body.len() > MAX_FILE_BYTES {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("File exceeds {} bytes", MAX_FILE_BYTES),
+        ));
+    }
+    microvm::ensure_running(&workspace.path)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    microvm::write_file(&workspace.path, path, &body)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -4,13 +4,14 @@
 //! - List workspaces
 //! - Create workspace
 //! - Get workspace details
+//! - Clone workspace
 //! - Delete workspace
 
 use axum::{
     extract::{Path as AxumPath, State},
     http::StatusCode,
     routing::{delete, get, post, put},
-    Json, Router,
+    Extension, Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -18,8 +19,18 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::library::WorkspaceTemplate;
+use super::auth::AuthUser;
+use crate::chat_options::ChatOptions;
+use crate::config::Role;
+use crate::egress::EgressPolicy;
+use crate::gpu::GpuRequest;
+use crate::init_report::{FragmentResult, FragmentStatus, InitScriptReport};
+use crate::library::{env_schema, WorkspaceTemplate};
+use crate::mcp::{McpStatus, McpTransport};
+use crate::microvm::MicroVmSpec;
 use crate::nspawn::NspawnDistro;
+use crate::schedule_windows::QuietHours;
+use crate::template_drift::{self, PlanStep, TemplateDrift, TemplateFingerprint};
 use crate::util::sanitize_skill_list;
 use crate::workspace::{self, TailscaleMode, Workspace, WorkspaceStatus, WorkspaceType};
 
@@ -32,12 +43,37 @@ pub fn routes() -> Router<Arc<super::routes::AppState>> {
         .route("/:id", put(update_workspace))
         .route("/:id", delete(delete_workspace))
         .route("/:id/build", post(build_workspace))
+        .route("/:id/clone", post(clone_workspace))
         .route("/:id/sync", post(sync_workspace))
         .route("/:id/exec", post(exec_workspace_command))
+        // Attachable terminals for manual intervention
+        .route(
+            "/:id/terminal",
+            post(super::workspace_terminal::create_attach),
+        )
+        .route(
+            "/:id/terminals",
+            get(super::workspace_terminal::list_attach),
+        )
+        .route(
+            "/:id/terminal/:session_id",
+            delete(super::workspace_terminal::end_attach),
+        )
+        // microVM workspaces
+        .route("/:id/vm", get(super::workspace_vm::get_vm))
+        .route("/:id/vm/start", post(super::workspace_vm::start_vm))
+        .route("/:id/vm/stop", post(super::workspace_vm::stop_vm))
+        .route(
+            "/:id/vm/file",
+            get(super::workspace_vm::read_vm_file).put(super::workspace_vm::write_vm_file),
+        )
         // Debug endpoints for template development
         .route("/:id/debug", get(get_workspace_debug))
         .route("/:id/rerun-init", post(rerun_init_script))
         .route("/:id/init-log", get(get_init_log))
+        .route("/:id/build-log", get(get_build_log))
+        .route("/:id/template-drift", get(get_template_drift))
+        .route("/:id/mcp-status", get(get_mcp_status))
         // Memory monitoring
         .route("/:id/memory", get(get_workspace_memory))
         .route("/memory/all", get(get_all_workspaces_memory))
@@ -80,12 +116,20 @@ pub struct CreateWorkspaceRequest {
     pub shared_network: Option<bool>,
     /// Tailscale networking mode when shared_network is false.
     pub tailscale_mode: Option<TailscaleMode>,
+    /// Outbound network policy (overrides the template's).
+    pub egress_policy: Option<EgressPolicy>,
+    /// Run this container workspace in a microVM (overrides the template's).
+    pub microvm: Option<MicroVmSpec>,
+    /// GPUs to pass through (overrides the template's).
+    pub gpu: Option<GpuRequest>,
     /// MCP server names to enable for this workspace.
     /// Empty = use default MCPs (those with `default_enabled = true`).
     #[serde(default)]
     pub mcps: Vec<String>,
     /// Optional config profile to apply to this workspace.
     pub config_profile: Option<String>,
+    /// Default chat options for missions in this workspace.
+    pub chat_options: Option<ChatOptions>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -111,13 +155,19 @@ pub struct UpdateWorkspaceRequest {
     pub shared_network: Option<bool>,
     /// Tailscale networking mode when shared_network is false.
     pub tailscale_mode: Option<TailscaleMode>,
+    /// Outbound network policy (overrides the template's).
+    pub egress_policy: Option<EgressPolicy>,
     /// MCP server names to enable for this workspace.
     pub mcps: Option<Vec<String>>,
     /// Optional config profile to apply to this workspace.
     pub config_profile: Option<String>,
+    /// Quiet hours during which automations for this workspace are deferred.
+    pub quiet_hours: Option<Vec<QuietHours>>,
+    /// Default chat options for missions in this workspace (empty clears them).
+    pub chat_options: Option<ChatOptions>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct WorkspaceResponse {
     pub id: Uuid,
     pub name: String,
@@ -135,12 +185,65 @@ pub struct WorkspaceResponse {
     pub init_script: Option<String>,
     pub shared_network: Option<bool>,
     pub tailscale_mode: Option<TailscaleMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_policy: Option<EgressPolicy>,
     pub mcps: Vec<String>,
     pub config_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quiet_hours: Vec<QuietHours>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub microvm: Option<MicroVmSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<GpuRequest>,
+    #[serde(default, skip_serializing_if = "ChatOptions::is_empty")]
+    pub chat_options: ChatOptions,
+}
+
+/// Shown instead of env var values to users below admin.
+pub(crate) const MASKED_ENV_VALUE: &str = "********";
+
+/// Hide env var values (tokens, kubeconfigs, ...) from users below admin;
+/// the keys stay visible.
+pub(crate) fn mask_env_values(env_vars: &mut HashMap<String, String>, role: Role) {
+    if role < Role::Admin {
+        for value in env_vars.values_mut() {
+            *value = MASKED_ENV_VALUE.to_string();
+        }
+    }
+}
+
+/// Env vars sent back with [`MASKED_ENV_VALUE`] keep their `stored` value,
+/// so saving a masked form does not overwrite the secrets.
+pub(crate) fn restore_masked_env_values(
+    env_vars: HashMap<String, String>,
+    stored: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    env_vars
+        .into_iter()
+        .filter_map(|(key, value)| {
+            if value == MASKED_ENV_VALUE {
+                stored.get(&key).map(|stored| (key, stored.clone()))
+            } else {
+                Some((key, value))
+            }
+        })
+        .collect()
+}
+
+impl WorkspaceResponse {
+    /// The response as `user` may see it.
+    fn for_user(mut self, user: &AuthUser) -> Self {
+        mask_env_values(&mut self.env_vars, user.role);
+        self
+    }
 }
 
 impl From<Workspace> for WorkspaceResponse {
     fn from(w: Workspace) -> Self {
+        let quiet_hours = workspace::workspace_quiet_hours(&w);
+        let microvm = workspace::workspace_microvm(&w);
+        let gpu = workspace::workspace_gpu(&w);
+        let chat_options = workspace::workspace_chat_options(&w);
         Self {
             id: w.id,
             name: w.name,
@@ -158,8 +261,13 @@ impl From<Workspace> for WorkspaceResponse {
             init_script: w.init_script,
             shared_network: w.shared_network,
             tailscale_mode: w.tailscale_mode,
+            egress_policy: w.egress_policy,
             mcps: w.mcps,
             config_profile: w.config_profile,
+            quiet_hours,
+            microvm,
+            gpu,
+            chat_options,
         }
     }
 }
@@ -180,6 +288,8 @@ async fn merge_build_result(store: &workspace::WorkspaceStore, built: Workspace)
         latest.status = built.status;
         latest.error_message = built.error_message;
         latest.distro = built.distro;
+        latest.init_report = built.init_report;
+        latest.built_from = built.built_from;
         store.update(latest).await;
     } else {
         store.update(built).await;
@@ -187,7 +297,7 @@ async fn merge_build_result(store: &workspace::WorkspaceStore, built: Workspace)
 }
 
 /// Look up a workspace by ID, returning 404 if it does not exist.
-async fn require_workspace(
+pub(super) async fn require_workspace(
     store: &workspace::WorkspaceStore,
     id: Uuid,
 ) -> Result<Workspace, (StatusCode, String)> {
@@ -204,9 +314,13 @@ async fn require_workspace(
 /// GET /api/workspaces - List all workspaces.
 async fn list_workspaces(
     State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
 ) -> Result<Json<Vec<WorkspaceResponse>>, (StatusCode, String)> {
     let workspaces = state.workspaces.list().await;
-    let responses: Vec<WorkspaceResponse> = workspaces.into_iter().map(Into::into).collect();
+    let responses: Vec<WorkspaceResponse> = workspaces
+        .into_iter()
+        .map(|w| WorkspaceResponse::from(w).for_user(&user))
+        .collect();
     Ok(Json(responses))
 }
 
@@ -294,6 +408,7 @@ fn path_within(base: &Path, target: &Path) -> bool {
 /// POST /api/workspaces - Create a new workspace.
 async fn create_workspace(
     State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
     Json(req): Json<CreateWorkspaceRequest>,
 ) -> Result<Json<WorkspaceResponse>, (StatusCode, String)> {
     // Validate workspace name for path traversal
@@ -357,10 +472,28 @@ async fn create_workspace(
         .map(|t| t.env_vars.clone())
         .unwrap_or_default();
     if let Some(custom_env) = req.env_vars.clone() {
+        let custom_env = restore_masked_env_values(custom_env, &env_vars);
         env_vars.extend(custom_env);
     }
     env_vars = sanitize_env_vars(env_vars);
 
+    // Fail fast on missing/malformed variables the template declares, rather
+    // than letting a mission hit an opaque auth error later.
+    if let Some(template) = template_data.as_ref() {
+        env_schema::apply_defaults(&template.env_schema, &mut env_vars);
+        let issues = env_schema::check(&template.env_schema, &env_vars);
+        if !issues.is_empty() {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "Template '{}' environment variables need values: {}",
+                    template.name,
+                    env_schema::describe(&issues)
+                ),
+            ));
+        }
+    }
+
     let mut skills = template_data
         .as_ref()
         .map(|t| t.skills.clone())
@@ -401,6 +534,61 @@ async fn create_workspace(
         .tailscale_mode
         .or_else(|| template_data.as_ref().and_then(|t| t.tailscale_mode));
 
+    // egress_policy: request overrides template
+    let egress_policy = req
+        .egress_policy
+        .clone()
+        .or_else(|| template_data.as_ref().and_then(|t| t.egress_policy.clone()));
+    if let Some(policy) = egress_policy.as_ref() {
+        policy
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    // microvm: request overrides template
+    let microvm = req
+        .microvm
+        .clone()
+        .or_else(|| template_data.as_ref().and_then(|t| t.microvm.clone()));
+    if let Some(spec) = microvm.as_ref() {
+        if workspace_type != WorkspaceType::Container {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "microvm is only supported for container workspaces".to_string(),
+            ));
+        }
+        spec.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    // gpu: request overrides template
+    let gpu = req
+        .gpu
+        .clone()
+        .or_else(|| template_data.as_ref().and_then(|t| t.gpu.clone()));
+    if let Some(request) = gpu.as_ref() {
+        if workspace_type != WorkspaceType::Container {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "gpu is only supported for container workspaces".to_string(),
+            ));
+        }
+        if microvm.is_some() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "gpu cannot be combined with microvm".to_string(),
+            ));
+        }
+        request
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    if let Some(options) = req.chat_options.as_ref() {
+        options
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("chat_options: {}", e)))?;
+    }
+
     // MCPs: request overrides template
     let mcps = if !req.mcps.is_empty() {
         req.mcps.clone()
@@ -432,6 +620,8 @@ async fn create_workspace(
             path,
             status: WorkspaceStatus::Ready,
             error_message: None,
+            init_report: None,
+            built_from: None,
             config: serde_json::json!({}),
             template: req.template.clone(),
             distro,
@@ -443,6 +633,7 @@ async fn create_workspace(
             plugins: req.plugins,
             shared_network,
             tailscale_mode,
+            egress_policy,
             mcps: mcps.clone(),
             config_profile: config_profile.clone(),
         },
@@ -457,11 +648,23 @@ async fn create_workspace(
             ws.init_script = init_script;
             ws.shared_network = shared_network;
             ws.tailscale_mode = tailscale_mode;
+            ws.egress_policy = egress_policy;
             ws.mcps = mcps;
             ws.config_profile = config_profile;
+            let mut config = serde_json::Map::new();
+            if let Some(spec) = microvm.as_ref() {
+                config.insert("microvm".to_string(), serde_json::json!(spec));
+            }
+            if let Some(request) = gpu.as_ref() {
+                config.insert("gpu".to_string(), serde_json::json!(request));
+            }
+            ws.config = serde_json::Value::Object(config);
             ws
         }
     };
+    if let Some(options) = req.chat_options.as_ref().filter(|o| !o.is_empty()) {
+        set_workspace_config(&mut workspace, "chat_options", Some(options))?;
+    }
 
     let id = state.workspaces.add(workspace.clone()).await;
 
@@ -537,7 +740,7 @@ async fn create_workspace(
         tracing::info!("Created workspace: {} ({})", workspace.name, id);
     }
 
-    let response: WorkspaceResponse = workspace.into();
+    let response = WorkspaceResponse::from(workspace).for_user(&user);
 
     Ok(Json(response))
 }
@@ -545,16 +748,18 @@ async fn create_workspace(
 /// GET /api/workspaces/:id - Get workspace details.
 async fn get_workspace(
     State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
     AxumPath(id): AxumPath<Uuid>,
 ) -> Result<Json<WorkspaceResponse>, (StatusCode, String)> {
     require_workspace(&state.workspaces, id)
         .await
-        .map(|w| Json(w.into()))
+        .map(|w| Json(WorkspaceResponse::from(w).for_user(&user)))
 }
 
 /// PUT /api/workspaces/:id - Update a workspace.
 async fn update_workspace(
     State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
     AxumPath(id): AxumPath<Uuid>,
     Json(req): Json<UpdateWorkspaceRequest>,
 ) -> Result<Json<WorkspaceResponse>, (StatusCode, String)> {
@@ -598,6 +803,7 @@ async fn update_workspace(
     }
 
     if let Some(env_vars) = req.env_vars {
+        let env_vars = restore_masked_env_values(env_vars, &workspace.env_vars);
         workspace.env_vars = sanitize_env_vars(env_vars);
     }
 
@@ -624,6 +830,14 @@ async fn update_workspace(
         workspace.tailscale_mode = req.tailscale_mode;
     }
 
+    // Update egress_policy if explicitly set in the request
+    if let Some(policy) = req.egress_policy {
+        policy
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        workspace.egress_policy = Some(policy);
+    }
+
     // Update MCPs if provided
     if let Some(mcps) = req.mcps {
         workspace.mcps = mcps;
@@ -638,6 +852,30 @@ async fn update_workspace(
         }
     }
 
+    if let Some(quiet_hours) = req.quiet_hours {
+        for window in &quiet_hours {
+            window
+                .validate()
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("quiet_hours: {}", e)))?;
+        }
+        set_workspace_config(
+            &mut workspace,
+            "quiet_hours",
+            Some(&quiet_hours).filter(|q| !q.is_empty()),
+        )?;
+    }
+
+    if let Some(options) = req.chat_options {
+        options
+            .validate()
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("chat_options: {}", e)))?;
+        set_workspace_config(
+            &mut workspace,
+            "chat_options",
+            Some(&options).filter(|o| !o.is_empty()),
+        )?;
+    }
+
     // Save the updated workspace
     state.workspaces.update(workspace.clone()).await;
 
@@ -662,12 +900,36 @@ async fn update_workspace(
 
     tracing::info!("Updated workspace: {} ({})", workspace.name, id);
 
-    Ok(Json(workspace.into()))
+    Ok(Json(WorkspaceResponse::from(workspace).for_user(&user)))
+}
+
+/// Store `value` under `config.<key>`, or remove the key when `value` is `None`.
+fn set_workspace_config<T: Serialize>(
+    workspace: &mut Workspace,
+    key: &str,
+    value: Option<&T>,
+) -> Result<(), (StatusCode, String)> {
+    let mut obj = workspace.config.as_object().cloned().unwrap_or_default();
+    match value {
+        Some(value) => {
+            obj.insert(
+                key.to_string(),
+                serde_json::to_value(value)
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+            );
+        }
+        None => {
+            obj.remove(key);
+        }
+    }
+    workspace.config = serde_json::Value::Object(obj);
+    Ok(())
 }
 
 /// POST /api/workspaces/:id/sync - Manually sync skills and tools to workspace.
 async fn sync_workspace(
     State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
     AxumPath(id): AxumPath<Uuid>,
 ) -> Result<Json<WorkspaceResponse>, (StatusCode, String)> {
     let workspace = require_workspace(&state.workspaces, id).await?;
@@ -693,7 +955,7 @@ async fn sync_workspace(
 
     tracing::info!("Synced skills to workspace: {} ({})", workspace.name, id);
 
-    Ok(Json(workspace.into()))
+    Ok(Json(WorkspaceResponse::from(workspace).for_user(&user)))
 }
 
 /// DELETE /api/workspaces/:id - Delete a workspace.
@@ -795,6 +1057,7 @@ fn shell_escape(s: &str) -> String {
 /// POST /api/workspaces/:id/build - Build a container workspace.
 async fn build_workspace(
     State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
     AxumPath(id): AxumPath<Uuid>,
     body: Option<Json<BuildWorkspaceRequest>>,
 ) -> Result<Json<WorkspaceResponse>, (StatusCode, String)> {
@@ -868,7 +1131,111 @@ async fn build_workspace(
         merge_build_result(&workspaces_store, workspace_for_build).await;
     });
 
-    Ok(Json(workspace.into()))
+    Ok(Json(WorkspaceResponse::from(workspace).for_user(&user)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloneWorkspaceRequest {
+    /// Name of the new workspace
+    pub name: String,
+    /// Where to put the copy (required for host workspaces; container clones
+    /// default to the containers directory)
+    pub path: Option<PathBuf>,
+}
+
+/// POST /api/workspaces/:id/clone - Duplicate a ready workspace.
+///
+/// The clone gets the source's configuration and a copy of its files (the
+/// container filesystem, or the host directory, with the repos in it), so it
+/// can be used without rebuilding from the template or re-running init
+/// scripts. The copy runs in the background: the clone is `building` until it
+/// finishes, then `ready` (or `error`).
+async fn clone_workspace(
+    State(state): State<Arc<super::routes::AppState>>,
+    Extension(user): Extension<AuthUser>,
+    AxumPath(id): AxumPath<Uuid>,
+    Json(req): Json<CloneWorkspaceRequest>,
+) -> Result<Json<WorkspaceResponse>, (StatusCode, String)> {
+    let source = require_workspace(&state.workspaces, id).await?;
+    validate_workspace_name(&req.name)?;
+    if source.status != WorkspaceStatus::Ready {
+        return Err((
+            StatusCode::CONFLICT,
+            "Only ready workspaces can be cloned".to_string(),
+        ));
+    }
+
+    let path = match (&req.path, source.workspace_type) {
+        (Some(custom_path), _) => resolve_custom_path(&state.config.working_dir, custom_path)?,
+        (None, WorkspaceType::Container) => state
+            .config
+            .working_dir
+            .join(".sandboxed-sh/containers")
+            .join(&req.name),
+        (None, WorkspaceType::Host) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Cloning a host workspace requires a path for the copy".to_string(),
+            ));
+        }
+    };
+    if path.starts_with(&source.path) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "The copy cannot be inside the workspace it copies".to_string(),
+        ));
+    }
+    if path.exists() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("{} already exists", path.display()),
+        ));
+    }
+
+    let clone = Workspace {
+        id: Uuid::new_v4(),
+        name: req.name,
+        path,
+        status: WorkspaceStatus::Building,
+        error_message: None,
+        created_at: chrono::Utc::now(),
+        ..source.clone()
+    };
+    state.workspaces.add(clone.clone()).await;
+
+    let workspaces_store = Arc::clone(&state.workspaces);
+    let (clone_id, clone_path) = (clone.id, clone.path.clone());
+    tokio::spawn(async move {
+        let result = workspace::clone_workspace_files(&source, &clone_path).await;
+        let Some(mut latest) = workspaces_store.get(clone_id).await else {
+            // Deleted while copying
+            let _ = tokio::fs::remove_dir_all(&clone_path).await;
+            return;
+        };
+        match result {
+            Ok(()) => {
+                latest.status = WorkspaceStatus::Ready;
+                tracing::info!(
+                    "Cloned workspace {} into {} ({})",
+                    source.name,
+                    latest.name,
+                    clone_id
+                );
+            }
+            Err(e) => {
+                tracing::error!(
+                    workspace = %latest.name,
+                    error = %e,
+                    "Failed to clone workspace"
+                );
+                latest.status = WorkspaceStatus::Error;
+                latest.error_message = Some(format!("Clone failed: {}", e));
+            }
+        }
+        workspaces_store.update(latest).await;
+    });
+
+    Ok(Json(WorkspaceResponse::from(clone).for_user(&user)))
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -955,6 +1322,56 @@ pub struct InitLogResponse {
     pub log_path: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BuildLogResponse {
+    pub status: WorkspaceStatus,
+    pub error_message: Option<String>,
+    /// Per-fragment outcome of the last init script run, if any
+    pub init_report: Option<InitScriptReport>,
+    /// Fragments that failed or were interrupted, in script order
+    pub failed_fragments: Vec<FragmentResult>,
+    /// One-line summary of the failed fragments
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplateDriftResponse {
+    pub template: String,
+    /// When the workspace was last built from the template. None for
+    /// workspaces built before builds were fingerprinted; they are compared
+    /// using their own settings instead.
+    pub built_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub drift: TemplateDrift,
+    /// Steps `POST /api/workspaces/:id/build` with `rebuild: true` would run
+    pub rebuild_plan: Vec<PlanStep>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceMcpStatus {
+    pub id: Uuid,
+    pub name: String,
+    /// `stdio` or `http`
+    pub transport: &'static str,
+    pub status: McpStatus,
+    pub error: Option<String>,
+    pub version: Option<String>,
+    pub tool_count: usize,
+    /// Waiting for the first tool call to start (lazy start)
+    pub pending_start: bool,
+    pub restarts: u32,
+    pub consecutive_failures: u32,
+    pub last_connected_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_health_check: Option<chrono::DateTime<chrono::Utc>>,
+    pub next_retry_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct McpStatusResponse {
+    pub servers: Vec<WorkspaceMcpStatus>,
+    /// MCP names the workspace lists that aren't registered
+    pub missing: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RerunInitResponse {
     /// Whether the rerun was successful
@@ -1367,6 +1784,132 @@ async fn get_init_log(
     }))
 }
 
+/// GET /api/workspaces/:id/build-log - Get the per-fragment init script report
+/// from the last build, with a summary of failed fragments.
+async fn get_build_log(
+    State(state): State<Arc<super::routes::AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+) -> Result<Json<BuildLogResponse>, (StatusCode, String)> {
+    let workspace = require_workspace(&state.workspaces, id).await?;
+
+    let failed_fragments: Vec<FragmentResult> = workspace
+        .init_report
+        .iter()
+        .flat_map(|r| r.fragments.iter())
+        .filter(|f| {
+            matches!(
+                f.status,
+                FragmentStatus::Failed | FragmentStatus::Interrupted
+            )
+        })
+        .cloned()
+        .collect();
+    let summary = workspace
+        .init_report
+        .as_ref()
+        .map(|r| r.failed_fragments())
+        .filter(|failed| !failed.is_empty())
+        .map(|failed| format!("{} fragment(s) failed: {}", failed.len(), failed.join(", ")));
+
+    Ok(Json(BuildLogResponse {
+        status: workspace.status,
+        error_message: workspace.error_message,
+        init_report: workspace.init_report,
+        failed_fragments,
+        summary,
+    }))
+}
+
+/// GET /api/workspaces/:id/template-drift - Compare the template a workspace
+/// was built from with the template now, and plan a rebuild without running it.
+async fn get_template_drift(
+    State(state): State<Arc<super::routes::AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+) -> Result<Json<TemplateDriftResponse>, (StatusCode, String)> {
+    let workspace = require_workspace(&state.workspaces, id).await?;
+    let Some(template_name) = workspace.template.clone() else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Workspace was not created from a template".to_string(),
+        ));
+    };
+    let library = clone_library(&state.library).await.ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Library not initialized".to_string(),
+        )
+    })?;
+    let template = library
+        .get_workspace_template(&template_name)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+
+    let current = TemplateFingerprint::of_template(&library, &template).await;
+    let built_at = workspace.built_from.as_ref().map(|b| b.captured_at);
+    let built = workspace
+        .built_from
+        .clone()
+        .unwrap_or_else(|| TemplateFingerprint::of_workspace(&workspace));
+    let rebuild_plan = template_drift::rebuild_plan(&workspace, Some(&library), &built).await;
+
+    Ok(Json(TemplateDriftResponse {
+        template: template_name,
+        built_at,
+        drift: template_drift::diff(&built, &current),
+        rebuild_plan,
+    }))
+}
+
+/// GET /api/workspaces/:id/mcp-status - Report the state of each MCP server the
+/// workspace uses (its `mcps` allowlist, or the default-enabled servers).
+async fn get_mcp_status(
+    State(state): State<Arc<super::routes::AppState>>,
+    AxumPath(id): AxumPath<Uuid>,
+) -> Result<Json<McpStatusResponse>, (StatusCode, String)> {
+    let workspace = require_workspace(&state.workspaces, id).await?;
+    let mut servers: Vec<WorkspaceMcpStatus> = state
+        .mcp
+        .list()
+        .await
+        .into_iter()
+        .filter(|s| {
+            if workspace.mcps.is_empty() {
+                s.config.default_enabled
+            } else {
+                workspace.mcps.contains(&s.config.name)
+            }
+        })
+        .map(|s| WorkspaceMcpStatus {
+            id: s.config.id,
+            transport: match s.config.transport {
+                McpTransport::Stdio { .. } => "stdio",
+                McpTransport::Http { .. } => "http",
+            },
+            status: s.status,
+            error: s.error,
+            version: s.config.version,
+            tool_count: s.config.tool_descriptors.len(),
+            pending_start: s.pending_start,
+            restarts: s.restarts,
+            consecutive_failures: s.consecutive_failures,
+            last_connected_at: s.config.last_connected_at,
+            last_health_check: s.last_health_check,
+            next_retry_at: s.next_retry_at,
+            name: s.config.name,
+        })
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let missing = workspace
+        .mcps
+        .iter()
+        .filter(|name| !servers.iter().any(|s| &s.name == *name))
+        .cloned()
+        .collect();
+
+    Ok(Json(McpStatusResponse { servers, missing }))
+}
+
 /// POST /api/workspaces/:id/rerun-init - Re-run the init script without rebuilding the container.
 ///
 /// This allows template developers to iterate on their init script without
@@ -1443,6 +1986,7 @@ async fn rerun_init_script(
 
     let config = crate::nspawn::NspawnConfig {
         env: workspace.env_vars.clone(),
+        gpu: workspace::workspace_gpu_passthrough(&workspace),
         ..Default::default()
     };
 
@@ -1721,10 +2265,12 @@ async fn check_backend_preflight(
         None
     };
 
+    let backends = state.backend_registry.read().await;
     let result = super::mission_runner::check_backend_prerequisites(
         &workspace,
         &backend_id,
         cli_path.as_deref(),
+        &backends,
     )
     .await;
 
@@ -1821,4 +2367,72 @@ mod tests {
     fn test_validate_workspace_name_rejects_empty() {
         assert!(validate_workspace_name("").is_err());
     }
+
+    fn user(role: Role) -> AuthUser {
+        AuthUser {
+            id: "user".to_string(),
+            username: "user".to_string(),
+            role,
+        }
+    }
+
+    #[test]
+    fn test_env_values_redacted_below_admin() {
+        let mut ws = Workspace::default_host(std::env::temp_dir());
+        ws.env_vars
+            .insert("GH_TOKEN".to_string(), "ghp_secret".to_string());
+
+        for role in [Role::Viewer, Role::Operator] {
+            let response = WorkspaceResponse::from(ws.clone()).for_user(&user(role));
+            assert_eq!(response.env_vars["GH_TOKEN"], MASKED_ENV_VALUE);
+        }
+        let response = WorkspaceResponse::from(ws).for_user(&user(Role::Admin));
+        assert_eq!(response.env_vars["GH_TOKEN"], "ghp_secret");
+    }
+
+    #[test]
+    fn test_masked_env_values_keep_stored_value() {
+        let stored = HashMap::from([("GH_TOKEN".to_string(), "ghp_secret".to_string())]);
+        let sent = HashMap::from([
+            ("GH_TOKEN".to_string(), MASKED_ENV_VALUE.to_string()),
+            ("NEW_KEY".to_string(), MASKED_ENV_VALUE.to_string()),
+            ("PLAIN".to_string(), "value".to_string()),
+        ]);
+        let restored = restore_masked_env_values(sent, &stored);
+        assert_eq!(restored["GH_TOKEN"], "ghp_secret");
+        assert_eq!(restored["PLAIN"], "value");
+        assert!(!restored.contains_key("NEW_KEY"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_clone_workspace_files_copies_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let repo = source_path.join("workspaces/mission-1/repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        std::fs::write(repo.join("main.rs"), "fn main() {}").unwrap();
+        std::os::unix::fs::symlink("main.rs", repo.join("link.rs")).unwrap();
+
+        let mut source = Workspace::new_container("source".to_string(), source_path);
+        source.workspace_type = WorkspaceType::Host;
+        let target = temp_dir.path().join("copies/clone");
+        workspace::clone_workspace_files(&source, &target)
+            .await
+            .unwrap();
+
+        let copied = target.join("workspaces/mission-1/repo");
+        assert_eq!(
+            std::fs::read_to_string(copied.join("main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert_eq!(
+            std::fs::read_link(copied.join("link.rs")).unwrap(),
+            Path::new("main.rs")
+        );
+        // The target must be new
+        assert!(workspace::clone_workspace_files(&source, &target)
+            .await
+            .is_err());
+    }
 }
@@ -93,6 +93,7 @@ impl AmpClient {
             "Starting Amp CLI process"
         );
 
+        crate::process_group::isolate(&mut cmd);
         let mut child = cmd.spawn().map_err(|e| {
             anyhow!(
                 "Failed to spawn Amp CLI at '{}': {}. Is Amp installed?",
@@ -209,6 +210,7 @@ impl AmpClient {
             "Continuing Amp thread"
         );
 
+        crate::process_group::isolate(&mut cmd);
         let mut child = cmd
             .spawn()
             .map_err(|e| anyhow!("Failed to spawn Amp CLI: {}", e))?;
@@ -6,11 +6,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
 use crate::backend::events::ExecutionEvent;
 use crate::backend::shared::convert_cli_event;
-use crate::backend::{AgentInfo, Backend, Session, SessionConfig};
+use crate::backend::{AgentInfo, Backend, BackendCapabilities, Session, SessionConfig};
 
 use client::{AmpClient, AmpConfig};
 
@@ -66,6 +67,14 @@ impl Backend for AmpBackend {
         &self.name
     }
 
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            agents: true,
+            session_resume: true,
+            read_only: false,
+        }
+    }
+
     async fn list_agents(&self) -> Result<Vec<AgentInfo>, Error> {
         // Amp has built-in modes rather than agents
         Ok(vec![
@@ -94,6 +103,7 @@ impl Backend for AmpBackend {
         &self,
         session: &Session,
         message: &str,
+        cancel: CancellationToken,
     ) -> Result<(mpsc::Receiver<ExecutionEvent>, JoinHandle<()>), Error> {
         let config = self.config.read().await.clone();
         let client = AmpClient::with_config(config);
@@ -115,7 +125,18 @@ impl Backend for AmpBackend {
         let handle = tokio::spawn(async move {
             let mut pending_tools: HashMap<String, String> = HashMap::new();
 
-            while let Some(event) = amp_rx.recv().await {
+            loop {
+                let event = tokio::select! {
+                    _ = cancel.cancelled() => {
+                        let report = amp_handle.kill().await;
+                        let _ = tx.send(ExecutionEvent::KilledProcesses { report }).await;
+                        return;
+                    }
+                    event = amp_rx.recv() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                };
                 let exec_events = convert_cli_event(event, &mut pending_tools);
 
                 for exec_event in exec_events {
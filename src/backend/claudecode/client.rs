@@ -113,6 +113,7 @@ impl ClaudeCodeClient {
             directory, effective_model, session_id, agent
         );
 
+        crate::process_group::isolate(&mut cmd);
         let mut child = cmd.spawn().map_err(|e| {
             error!("Failed to spawn Claude CLI: {}", e);
             anyhow!(
@@ -6,11 +6,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
 
 use crate::backend::events::ExecutionEvent;
 use crate::backend::shared::convert_cli_event;
-use crate::backend::{AgentInfo, Backend, Session, SessionConfig};
+use crate::backend::{AgentInfo, Backend, BackendCapabilities, Session, SessionConfig};
 
 use client::{ClaudeCodeClient, ClaudeCodeConfig};
 
@@ -66,6 +67,14 @@ impl Backend for ClaudeCodeBackend {
         &self.name
     }
 
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            agents: true,
+            session_resume: true,
+            read_only: true,
+        }
+    }
+
     async fn list_agents(&self) -> Result<Vec<AgentInfo>, Error> {
         // Claude Code has built-in agents
         Ok(vec![
@@ -102,6 +111,7 @@ impl Backend for ClaudeCodeBackend {
         &self,
         session: &Session,
         message: &str,
+        cancel: CancellationToken,
     ) -> Result<(mpsc::Receiver<ExecutionEvent>, JoinHandle<()>), Error> {
         let config = self.config.read().await.clone();
         let client = ClaudeCodeClient::with_config(config);
@@ -124,7 +134,18 @@ impl Backend for ClaudeCodeBackend {
             // Track pending tool calls for name lookup AND completion tracking
             let mut pending_tools: HashMap<String, String> = HashMap::new();
 
-            while let Some(event) = claude_rx.recv().await {
+            loop {
+                let event = tokio::select! {
+                    _ = cancel.cancelled() => {
+                        let report = claude_handle.kill().await;
+                        let _ = tx.send(ExecutionEvent::KilledProcesses { report }).await;
+                        return;
+                    }
+                    event = claude_rx.recv() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                };
                 let exec_events = convert_cli_event(event, &mut pending_tools);
 
                 for exec_event in exec_events {
@@ -21,6 +21,8 @@ pub struct CodexConfig {
     pub oauth_token: Option<String>,
     pub default_model: Option<String>,
     pub model_effort: Option<String>,
+    /// Run in Codex's read-only sandbox instead of without one (audit missions)
+    pub read_only: bool,
 }
 
 impl Default for CodexConfig {
@@ -30,6 +32,7 @@ impl Default for CodexConfig {
             oauth_token: std::env::var("OPENAI_OAUTH_TOKEN").ok(),
             default_model: None,
             model_effort: None,
+            read_only: false,
         }
     }
 }
@@ -55,14 +58,16 @@ impl CodexClient {
     }
 
     /// Execute a message and return a stream of events.
+    /// With `thread_id`, the message continues that Codex thread (`exec resume`)
+    /// instead of starting a new one.
     /// Returns a tuple of (event receiver, process handle).
     pub async fn execute_message(
         &self,
         directory: &str,
         message: &str,
         model: Option<&str>,
-        _session_id: Option<&str>, // Codex doesn't support session IDs like Claude
-        _agent: Option<&str>,      // Codex doesn't have agent types like Claude
+        thread_id: Option<&str>,
+        _agent: Option<&str>, // Codex doesn't have agent types like Claude
         workspace_exec: Option<&WorkspaceExec>,
     ) -> Result<(mpsc::Receiver<CodexEvent>, ProcessHandle)> {
         let (tx, rx) = mpsc::channel(256);
@@ -71,8 +76,12 @@ impl CodexClient {
             "exec".to_string(),
             "--json".to_string(),
             "--skip-git-repo-check".to_string(),
-            "--dangerously-bypass-approvals-and-sandbox".to_string(),
         ];
+        if self.config.read_only {
+            args.extend(["--sandbox".to_string(), "read-only".to_string()]);
+        } else {
+            args.push("--dangerously-bypass-approvals-and-sandbox".to_string());
+        }
 
         let mut env: HashMap<String, String> = HashMap::new();
         // Set OAuth token if configured
@@ -92,13 +101,18 @@ impl CodexClient {
             args.push(format!("reasoning.effort=\"{}\"", effort));
         }
 
+        if let Some(thread_id) = thread_id {
+            args.push("resume".to_string());
+            args.push(thread_id.to_string());
+        }
+
         // Add the message as a positional arg (guard prompts starting with '-')
         args.push("--".to_string());
         args.push(message.to_string());
 
         info!(
-            "Spawning Codex CLI: directory={}, model={:?}, effort={:?}",
-            directory, effective_model, self.config.model_effort
+            "Spawning Codex CLI: directory={}, model={:?}, effort={:?}, thread={:?}",
+            directory, effective_model, self.config.model_effort, thread_id
         );
 
         let (program, full_args) = if self.config.cli_path.contains(' ') {
@@ -132,6 +146,7 @@ impl CodexClient {
             if !env.is_empty() {
                 cmd.envs(env);
             }
+            crate::process_group::isolate(&mut cmd);
             cmd.spawn().map_err(|e| {
                 error!("Failed to spawn Codex CLI: {}", e);
                 anyhow!(
@@ -2,13 +2,15 @@ pub mod client;
 
 use anyhow::Error;
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
 use crate::backend::events::ExecutionEvent;
-use crate::backend::{AgentInfo, Backend, Session, SessionConfig};
+use crate::backend::{AgentInfo, Backend, BackendCapabilities, Session, SessionConfig};
 
 use client::{CodexClient, CodexConfig, CodexEvent};
 
@@ -18,6 +20,9 @@ pub struct CodexBackend {
     name: String,
     config: Arc<RwLock<CodexConfig>>,
     workspace_exec: Option<crate::workspace_exec::WorkspaceExec>,
+    /// Codex thread of each session, once the CLI has reported one.
+    /// Messages to a session with a thread continue that thread.
+    threads: Arc<RwLock<HashMap<String, String>>>,
 }
 
 impl CodexBackend {
@@ -27,6 +32,7 @@ impl CodexBackend {
             name: "Codex".to_string(),
             config: Arc::new(RwLock::new(CodexConfig::default())),
             workspace_exec: None,
+            threads: Arc::default(),
         }
     }
 
@@ -36,6 +42,7 @@ impl CodexBackend {
             name: "Codex".to_string(),
             config: Arc::new(RwLock::new(config)),
             workspace_exec: None,
+            threads: Arc::default(),
         }
     }
 
@@ -48,6 +55,7 @@ impl CodexBackend {
             name: "Codex".to_string(),
             config: Arc::new(RwLock::new(config)),
             workspace_exec: Some(workspace_exec),
+            threads: Arc::default(),
         }
     }
 
@@ -61,6 +69,27 @@ impl CodexBackend {
     pub async fn get_config(&self) -> CodexConfig {
         self.config.read().await.clone()
     }
+
+    /// Create a session that continues an existing Codex thread, e.g. one
+    /// started by an earlier (possibly interrupted) turn of the same mission.
+    pub async fn resume_session(&self, config: SessionConfig, thread_id: &str) -> Session {
+        let session = Session {
+            id: CodexClient::new().create_session_id(),
+            directory: config.directory,
+            model: config.model,
+            agent: config.agent,
+        };
+        self.threads
+            .write()
+            .await
+            .insert(session.id.clone(), thread_id.to_string());
+        session
+    }
+
+    /// Codex thread of `session_id`, if the CLI has started (or resumed) one.
+    pub async fn thread_id(&self, session_id: &str) -> Option<String> {
+        self.threads.read().await.get(session_id).cloned()
+    }
 }
 
 impl Default for CodexBackend {
@@ -79,6 +108,14 @@ impl Backend for CodexBackend {
         &self.name
     }
 
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            agents: true,
+            session_resume: true,
+            read_only: true,
+        }
+    }
+
     async fn list_agents(&self) -> Result<Vec<AgentInfo>, Error> {
         // Codex doesn't have separate agent types like Claude Code
         // Return a single general-purpose agent
@@ -102,17 +139,19 @@ impl Backend for CodexBackend {
         &self,
         session: &Session,
         message: &str,
+        cancel: CancellationToken,
     ) -> Result<(mpsc::Receiver<ExecutionEvent>, JoinHandle<()>), Error> {
         let config = self.config.read().await.clone();
         let client = CodexClient::with_config(config);
         let workspace_exec = self.workspace_exec.as_ref();
+        let thread_id = self.thread_id(&session.id).await;
 
         let (mut codex_rx, codex_handle) = client
             .execute_message(
                 &session.directory,
                 message,
                 session.model.as_deref(),
-                Some(&session.id),
+                thread_id.as_deref(),
                 session.agent.as_deref(),
                 workspace_exec,
             )
@@ -120,6 +159,7 @@ impl Backend for CodexBackend {
 
         let (tx, rx) = mpsc::channel(256);
         let session_id = session.id.clone();
+        let threads = Arc::clone(&self.threads);
 
         // Spawn event conversion task
         let handle = tokio::spawn(async move {
@@ -127,7 +167,24 @@ impl Backend for CodexBackend {
             let mut item_content_cache: std::collections::HashMap<String, String> =
                 std::collections::HashMap::new();
 
-            'outer: while let Some(event) = codex_rx.recv().await {
+            'outer: loop {
+                let event = tokio::select! {
+                    _ = cancel.cancelled() => {
+                        let report = codex_handle.kill().await;
+                        let _ = tx.send(ExecutionEvent::KilledProcesses { report }).await;
+                        return;
+                    }
+                    event = codex_rx.recv() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                };
+                if let CodexEvent::ThreadStarted { thread_id } = &event {
+                    threads
+                        .write()
+                        .await
+                        .insert(session_id.clone(), thread_id.clone());
+                }
                 let exec_events = convert_codex_event(event, &mut item_content_cache);
 
                 for exec_event in exec_events {
@@ -629,6 +686,27 @@ mod tests {
             .unwrap();
         assert!(!session.id.is_empty());
         assert_eq!(session.directory, "/tmp");
+        assert_eq!(backend.thread_id(&session.id).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_resume_session_continues_thread() {
+        let backend = CodexBackend::new();
+        let session = backend
+            .resume_session(
+                SessionConfig {
+                    directory: "/tmp".to_string(),
+                    title: None,
+                    model: None,
+                    agent: None,
+                },
+                "019c21ae-c46c-7a40-a5f5-36ab53521a27",
+            )
+            .await;
+        assert_eq!(
+            backend.thread_id(&session.id).await.as_deref(),
+            Some("019c21ae-c46c-7a40-a5f5-36ab53521a27")
+        );
     }
 
     // ---------------------------------------------------------------
@@ -1,7 +1,13 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// Backend-agnostic execution events.
-#[derive(Debug, Clone)]
+///
+/// Evolves additively within an [`EVENT_SCHEMA_VERSION`](crate::api::event_schema::EVENT_SCHEMA_VERSION):
+/// fields added later must be optional or carry `#[serde(default)]`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum ExecutionEvent {
     /// Agent is thinking/reasoning.
     Thinking { content: String },
@@ -30,4 +36,8 @@ pub enum ExecutionEvent {
     MessageComplete { session_id: String },
     /// Error occurred.
     Error { message: String },
+    /// Backend processes were terminated after cancellation.
+    KilledProcesses {
+        report: crate::process_group::KillReport,
+    },
 }
@@ -8,10 +8,13 @@ pub mod shared;
 
 use anyhow::Error;
 use async_trait::async_trait;
+use serde::Serialize;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 use events::ExecutionEvent;
+pub use registry::{BackendInfo, BackendRegistry};
 
 #[derive(Debug, Clone)]
 pub struct AgentInfo {
@@ -35,15 +38,36 @@ pub struct Session {
     pub agent: Option<String>,
 }
 
+/// What a backend supports, reported by `GET /api/backends`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BackendCapabilities {
+    /// Lists selectable agents (or modes) for missions
+    pub agents: bool,
+    /// Continues its own session across mission turns instead of
+    /// replaying the conversation. Backends plugged in at startup get the
+    /// ID of the session they created for the mission's first turn.
+    pub session_resume: bool,
+    /// Can run read-only audit missions
+    pub read_only: bool,
+}
+
 #[async_trait]
 pub trait Backend: Send + Sync {
     fn id(&self) -> &str;
     fn name(&self) -> &str;
+    /// Optional features this backend supports; none by default.
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities::default()
+    }
     async fn list_agents(&self) -> Result<Vec<AgentInfo>, Error>;
     async fn create_session(&self, config: SessionConfig) -> Result<Session, Error>;
+    /// Stream the response to `message`. When `cancel` fires the backend
+    /// tears down its subprocesses, reports them with
+    /// [`ExecutionEvent::KilledProcesses`] and closes the stream.
     async fn send_message_streaming(
         &self,
         session: &Session,
         message: &str,
+        cancel: CancellationToken,
     ) -> Result<(mpsc::Receiver<ExecutionEvent>, JoinHandle<()>), Error>;
 }
@@ -6,9 +6,10 @@ use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 use crate::backend::events::ExecutionEvent;
-use crate::backend::{AgentInfo, Backend, Session, SessionConfig};
+use crate::backend::{AgentInfo, Backend, BackendCapabilities, Session, SessionConfig};
 use client::OpenCodeClient;
 
 pub struct OpenCodeBackend {
@@ -95,6 +96,14 @@ impl Backend for OpenCodeBackend {
         &self.name
     }
 
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            agents: true,
+            session_resume: true,
+            read_only: false,
+        }
+    }
+
     async fn list_agents(&self) -> Result<Vec<AgentInfo>, Error> {
         match self.fetch_agents().await {
             Ok(payload) => Ok(Self::parse_agents(payload)),
@@ -125,8 +134,9 @@ impl Backend for OpenCodeBackend {
         &self,
         session: &Session,
         message: &str,
+        cancel: CancellationToken,
     ) -> Result<(mpsc::Receiver<ExecutionEvent>, JoinHandle<()>), Error> {
-        let (rx, handle) = self
+        let (rx, mut handle) = self
             .client
             .send_message_streaming(
                 &session.id,
@@ -136,8 +146,13 @@ impl Backend for OpenCodeBackend {
                 session.agent.as_deref(),
             )
             .await?;
+        // The OpenCode server owns its processes; cancelling only stops the stream.
         let join_handle = tokio::spawn(async move {
-            let _ = handle.await;
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = &mut handle => {}
+            }
+            handle.abort();
         });
         Ok((rx, join_handle))
     }
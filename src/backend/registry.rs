@@ -1,12 +1,18 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use super::Backend;
+use super::{Backend, BackendCapabilities};
+
+/// Backends shipped with the server; missions on them run through dedicated
+/// turn runners.
+pub const BUILTIN_BACKENDS: &[&str] = &["claudecode", "opencode", "codex", "amp"];
 
 #[derive(Debug, Clone)]
 pub struct BackendInfo {
     pub id: String,
     pub name: String,
+    pub builtin: bool,
+    pub capabilities: BackendCapabilities,
 }
 
 pub struct BackendRegistry {
@@ -26,23 +32,66 @@ impl BackendRegistry {
         self.backends.insert(backend.id().to_string(), backend);
     }
 
+    /// Register a custom backend next to the built-in ones and make it
+    /// available to missions. Built-in and already registered IDs are refused.
+    pub fn register_plugin(&mut self, backend: Arc<dyn Backend>) -> Result<(), String> {
+        let id = backend.id().to_string();
+        if id.trim().is_empty() {
+            return Err("Backend ID must not be empty".to_string());
+        }
+        if BUILTIN_BACKENDS.contains(&id.as_str()) || self.backends.contains_key(&id) {
+            return Err(format!("Backend '{}' is already registered", id));
+        }
+        self.register(backend);
+        Ok(())
+    }
+
     pub fn list(&self) -> Vec<BackendInfo> {
         let mut list: Vec<_> = self
             .backends
             .values()
-            .map(|backend| BackendInfo {
-                id: backend.id().to_string(),
-                name: backend.name().to_string(),
-            })
+            .map(|b| Self::info(b.as_ref()))
             .collect();
         list.sort_by(|a, b| a.name.cmp(&b.name));
         list
     }
 
+    pub fn info(backend: &dyn Backend) -> BackendInfo {
+        let builtin = BUILTIN_BACKENDS.contains(&backend.id());
+        let mut capabilities = backend.capabilities();
+        // Audit missions rely on tool restrictions only the built-in turn
+        // runners apply.
+        capabilities.read_only &= builtin;
+        BackendInfo {
+            id: backend.id().to_string(),
+            name: backend.name().to_string(),
+            builtin,
+            capabilities,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
     pub fn get(&self, id: &str) -> Option<Arc<dyn Backend>> {
         self.backends.get(id).cloned()
     }
 
+    /// Backend plugged in at startup (see [`crate::api::Server::with_backend`])
+    /// with this ID, if any. Missions on it run through the generic
+    /// [`Backend`] turn runner.
+    pub fn plugin(&self, id: &str) -> Option<Arc<dyn Backend>> {
+        if BUILTIN_BACKENDS.contains(&id) {
+            return None;
+        }
+        self.get(id)
+    }
+
     pub fn default_backend(&self) -> Option<Arc<dyn Backend>> {
         self.get(&self.default_backend)
             .or_else(|| self.backends.values().next().cloned())
@@ -52,3 +101,90 @@ impl BackendRegistry {
         &self.default_backend
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::events::ExecutionEvent;
+    use crate::backend::{AgentInfo, Session, SessionConfig};
+    use async_trait::async_trait;
+    use tokio::sync::mpsc;
+    use tokio::task::JoinHandle;
+    use tokio_util::sync::CancellationToken;
+
+    struct InHouse(&'static str);
+
+    #[async_trait]
+    impl Backend for InHouse {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn name(&self) -> &str {
+            "In-house harness"
+        }
+
+        fn capabilities(&self) -> BackendCapabilities {
+            BackendCapabilities {
+                session_resume: true,
+                read_only: true,
+                ..Default::default()
+            }
+        }
+
+        async fn list_agents(&self) -> anyhow::Result<Vec<AgentInfo>> {
+            Ok(vec![])
+        }
+
+        async fn create_session(&self, config: SessionConfig) -> anyhow::Result<Session> {
+            Ok(Session {
+                id: "s1".to_string(),
+                directory: config.directory,
+                model: config.model,
+                agent: config.agent,
+            })
+        }
+
+        async fn send_message_streaming(
+            &self,
+            _session: &Session,
+            _message: &str,
+            _cancel: CancellationToken,
+        ) -> anyhow::Result<(mpsc::Receiver<ExecutionEvent>, JoinHandle<()>)> {
+            let (_tx, rx) = mpsc::channel(1);
+            Ok((rx, tokio::spawn(async {})))
+        }
+    }
+
+    #[test]
+    fn plugins_are_listed_with_capabilities_and_cannot_shadow_builtins() {
+        let mut registry = BackendRegistry::new("claudecode");
+        registry.register(crate::backend::claudecode::registry_entry());
+        registry
+            .register_plugin(Arc::new(InHouse("registry-test-harness")))
+            .unwrap();
+
+        assert!(registry
+            .register_plugin(Arc::new(InHouse("codex")))
+            .is_err());
+        assert!(registry
+            .register_plugin(Arc::new(InHouse("registry-test-harness")))
+            .is_err());
+        assert_eq!(registry.len(), 2);
+
+        let list = registry.list();
+        let plugin_info = list
+            .iter()
+            .find(|b| b.id == "registry-test-harness")
+            .unwrap();
+        assert!(!plugin_info.builtin);
+        assert!(plugin_info.capabilities.session_resume);
+        assert!(!plugin_info.capabilities.read_only);
+        let claude = list.iter().find(|b| b.id == "claudecode").unwrap();
+        assert!(claude.builtin && claude.capabilities.read_only);
+
+        assert!(registry.plugin("registry-test-harness").is_some());
+        assert!(registry.plugin("claudecode").is_none());
+        assert!(registry.plugin("codex").is_none());
+    }
+}
@@ -15,11 +15,12 @@ use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 use super::events::ExecutionEvent;
+use crate::process_group::{self, KillReport};
 
 // ── Process handle ────────────────────────────────────────────────
 
-/// Handle to a running CLI process (Claude Code or Amp).
-/// Call `kill()` to terminate the process when cancelling a mission.
+/// Handle to a running CLI process (Claude Code, Amp or Codex).
+/// Call `kill()` to terminate the process tree when cancelling a mission.
 pub struct ProcessHandle {
     child: Arc<Mutex<Option<Child>>>,
     _task_handle: JoinHandle<()>,
@@ -48,15 +49,25 @@ impl ProcessHandle {
         }
     }
 
-    /// Kill the underlying CLI process.
-    pub async fn kill(&self) {
-        if let Some(mut child) = self.child.lock().await.take() {
-            if let Err(e) = child.kill().await {
-                warn!("Failed to kill CLI process: {}", e);
-            } else {
-                info!("CLI process killed");
-            }
+    /// Kill the underlying CLI process and everything it spawned.
+    pub async fn kill(&self) -> KillReport {
+        let Some(mut child) = self.child.lock().await.take() else {
+            return KillReport::default();
+        };
+        let report = match child.id() {
+            Some(pid) => process_group::terminate(pid).await,
+            None => KillReport::default(),
+        };
+        if let Err(e) = child.kill().await {
+            warn!("Failed to kill CLI process: {}", e);
+        } else {
+            info!(
+                killed = report.killed.len(),
+                survivors = report.survivors.len(),
+                "CLI process tree killed"
+            );
         }
+        report
     }
 }
 
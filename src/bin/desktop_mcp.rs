@@ -13,6 +13,7 @@ use std::sync::atomic::{AtomicU32, Ordering};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use sandboxed_sh::tool_quotas::{self, Quota};
 use sandboxed_sh::tools::desktop::find_browser_command;
 
 /// Global counter for display numbers to avoid conflicts
@@ -452,6 +453,8 @@ fn tool_screenshot(args: &Value) -> Result<(String, Option<String>), String> {
         .get("display")
         .and_then(|v| v.as_str())
         .ok_or("Missing 'display' argument")?;
+    let working_dir = get_working_dir();
+    tool_quotas::check(&working_dir, Quota::Screenshot)?;
 
     // Wait before taking screenshot if specified
     let wait_seconds = args
@@ -470,7 +473,6 @@ fn tool_screenshot(args: &Value) -> Result<(String, Option<String>), String> {
         .map(|s| s.to_string())
         .unwrap_or_else(|| format!("screenshot_{}.png", timestamp));
 
-    let working_dir = get_working_dir();
     let screenshots_dir = working_dir.join("screenshots");
     std::fs::create_dir_all(&screenshots_dir)
         .map_err(|e| format!("Failed to create screenshots dir: {}", e))?;
@@ -536,6 +538,7 @@ fn tool_screenshot(args: &Value) -> Result<(String, Option<String>), String> {
         filepath.display(),
         metadata.len()
     );
+    tool_quotas::record(&working_dir, Quota::Screenshot, 1);
 
     Ok((result, image_data))
 }
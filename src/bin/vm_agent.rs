@@ -0,0 +1,439 @@
+//! Guest agent and host bridge for microVM workspaces.
+//!
+//! In the guest this binary is the init process (`init=` on the kernel command
+//! line): it mounts the pseudo filesystems, then serves command execution and
+//! file transfer on vsock port 1024, one JSON message per line (see
+//! `sandboxed_sh::microvm`).
+//!
+//! On the host, `sandboxed-vm-agent exec --socket <vsock.sock> [--cwd DIR]
+//! [--env K=V]... -- <argv>` runs a command in the VM with this process's
+//! stdin/stdout/stderr bridged to it, and exits with the command's status.
+
+use std::collections::BTreeMap;
+use std::io::{BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+
+use sandboxed_sh::microvm::{
+    self, decode, encode, read_message, write_message, AgentReply, AgentRequest,
+};
+
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: sandboxed-vm-agent exec --socket PATH [--cwd DIR] [--env K=V]... -- COMMAND [ARGS]...\n       sandboxed-vm-agent serve"
+    );
+    std::process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if std::process::id() == 1 {
+        init();
+    }
+    match args.first().map(String::as_str) {
+        Some("exec") => std::process::exit(exec(&args[1..])),
+        Some("serve") => serve(),
+        _ => usage(),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Host side
+// ─────────────────────────────────────────────────────────────────────────────
+
+fn exec(args: &[String]) -> i32 {
+    let mut socket = None;
+    let mut cwd = "/".to_string();
+    let mut env = BTreeMap::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--socket" => socket = iter.next().map(PathBuf::from),
+            "--cwd" => cwd = iter.next().cloned().unwrap_or_else(|| usage()),
+            "--env" => {
+                let pair = iter.next().unwrap_or_else(|| usage());
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                env.insert(key.to_string(), value.to_string());
+            }
+            "--" => break,
+            _ => usage(),
+        }
+    }
+    let argv: Vec<String> = iter.cloned().collect();
+    let Some(socket) = socket else { usage() };
+    if argv.is_empty() {
+        usage();
+    }
+
+    let stream = match microvm::connect(&socket) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!(
+                "sandboxed-vm-agent: cannot reach VM at {}: {}",
+                socket.display(),
+                e
+            );
+            return 255;
+        }
+    };
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("sandboxed-vm-agent: {}", e);
+            return 255;
+        }
+    };
+    if let Err(e) = write_message(&mut writer, &AgentRequest::Exec { argv, cwd, env }) {
+        eprintln!("sandboxed-vm-agent: {}", e);
+        return 255;
+    }
+
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin().lock();
+        let mut buf = [0u8; 8192];
+        loop {
+            let message = match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => AgentRequest::StdinEof,
+                Ok(n) => AgentRequest::Stdin {
+                    data: encode(&buf[..n]),
+                },
+            };
+            let eof = message == AgentRequest::StdinEof;
+            if write_message(&mut writer, &message).is_err() || eof {
+                break;
+            }
+        }
+    });
+
+    let mut reader = BufReader::new(stream);
+    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
+    loop {
+        match read_message::<AgentReply>(&mut reader) {
+            Ok(Some(AgentReply::Stdout { data })) => {
+                if let Ok(bytes) = decode(&data) {
+                    let _ = stdout.write_all(&bytes);
+                    let _ = stdout.flush();
+                }
+            }
+            Ok(Some(AgentReply::Stderr { data })) => {
+                if let Ok(bytes) = decode(&data) {
+                    let _ = stderr.write_all(&bytes);
+                    let _ = stderr.flush();
+                }
+            }
+            Ok(Some(AgentReply::Exit { code })) => return code,
+            Ok(Some(AgentReply::Error { message })) => {
+                eprintln!("sandboxed-vm-agent: {}", message);
+                return 127;
+            }
+            Ok(Some(_)) => {}
+            Ok(None) | Err(_) => {
+                eprintln!("sandboxed-vm-agent: connection to the VM was lost");
+                return 255;
+            }
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Guest side
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PID 1: mount the basics, keep `serve` running and reap orphans.
+#[cfg(target_os = "linux")]
+fn init() -> ! {
+    use std::ffi::CString;
+
+    let mounts = [
+        ("proc", "/proc", "proc"),
+        ("sysfs", "/sys", "sysfs"),
+        ("devtmpfs", "/dev", "devtmpfs"),
+        ("devpts", "/dev/pts", "devpts"),
+        ("tmpfs", "/tmp", "tmpfs"),
+        ("tmpfs", "/run", "tmpfs"),
+    ];
+    for (source, target, fstype) in mounts {
+        let _ = std::fs::create_dir_all(target);
+        let (Ok(source), Ok(target_c), Ok(fstype)) = (
+            CString::new(source),
+            CString::new(target),
+            CString::new(fstype),
+        ) else {
+            continue;
+        };
+        // SAFETY: all pointers are valid NUL-terminated strings.
+        let rc = unsafe {
+            libc::mount(
+                source.as_ptr(),
+                target_c.as_ptr(),
+                fstype.as_ptr(),
+                0,
+                std::ptr::null(),
+            )
+        };
+        if rc != 0 {
+            eprintln!(
+                "sandboxed-vm-agent: mount {} failed: {}",
+                target,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+    let _ = std::process::Command::new("ip")
+        .args(["link", "set", "lo", "up"])
+        .status();
+
+    let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("/proc/self/exe"));
+    loop {
+        let server = match std::process::Command::new(&exe).arg("serve").spawn() {
+            Ok(child) => child.id() as i32,
+            Err(e) => {
+                eprintln!("sandboxed-vm-agent: cannot start server: {}", e);
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                continue;
+            }
+        };
+        loop {
+            let mut status = 0;
+            // SAFETY: waitpid with a valid status pointer.
+            let pid = unsafe { libc::waitpid(-1, &mut status, 0) };
+            if pid == server || pid < 0 {
+                break;
+            }
+        }
+        eprintln!("sandboxed-vm-agent: server exited, restarting");
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn init() -> ! {
+    eprintln!("sandboxed-vm-agent: guest mode is only supported on Linux");
+    std::process::exit(1);
+}
+
+#[cfg(target_os = "linux")]
+fn serve() -> ! {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: plain socket syscalls; the address is zero-initialized and filled
+    // in before use, and accepted descriptors are owned by `File`.
+    unsafe {
+        let fd = libc::socket(libc::AF_VSOCK, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0);
+        if fd < 0 {
+            eprintln!(
+                "sandboxed-vm-agent: vsock socket: {}",
+                std::io::Error::last_os_error()
+            );
+            std::process::exit(1);
+        }
+        let mut addr: libc::sockaddr_vm = std::mem::zeroed();
+        addr.svm_family = libc::AF_VSOCK as libc::sa_family_t;
+        addr.svm_port = microvm::AGENT_PORT;
+        addr.svm_cid = libc::VMADDR_CID_ANY;
+        let len = std::mem::size_of::<libc::sockaddr_vm>() as libc::socklen_t;
+        if libc::bind(fd, &addr as *const _ as *const libc::sockaddr, len) != 0
+            || libc::listen(fd, 16) != 0
+        {
+            eprintln!(
+                "sandboxed-vm-agent: vsock bind: {}",
+                std::io::Error::last_os_error()
+            );
+            std::process::exit(1);
+        }
+        loop {
+            let conn = libc::accept4(
+                fd,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                libc::SOCK_CLOEXEC,
+            );
+            if conn < 0 {
+                continue;
+            }
+            let stream = std::fs::File::from_raw_fd(conn);
+            std::thread::spawn(move || handle_connection(stream));
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn serve() -> ! {
+    eprintln!("sandboxed-vm-agent: guest mode is only supported on Linux");
+    std::process::exit(1);
+}
+
+fn reply(writer: &Mutex<std::fs::File>, message: &AgentReply) -> std::io::Result<()> {
+    let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+    write_message(&mut *writer, message)
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn handle_connection(stream: std::fs::File) {
+    let Ok(write_half) = stream.try_clone() else {
+        return;
+    };
+    let writer = Arc::new(Mutex::new(write_half));
+    let mut reader = BufReader::new(stream);
+    let request = match read_message::<AgentRequest>(&mut reader) {
+        Ok(Some(request)) => request,
+        _ => return,
+    };
+    let result = match request {
+        AgentRequest::Ping => reply(&writer, &AgentReply::Pong),
+        AgentRequest::ReadFile { path } => match std::fs::read(&path) {
+            Ok(data) => reply(
+                &writer,
+                &AgentReply::File {
+                    data: encode(&data),
+                },
+            ),
+            Err(e) => reply(
+                &writer,
+                &AgentReply::Error {
+                    message: format!("{}: {}", path, e),
+                },
+            ),
+        },
+        AgentRequest::WriteFile { path, data } => {
+            let written = decode(&data).and_then(|bytes| {
+                if let Some(parent) = std::path::Path::new(&path).parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, bytes)
+            });
+            match written {
+                Ok(()) => reply(&writer, &AgentReply::Ok),
+                Err(e) => reply(
+                    &writer,
+                    &AgentReply::Error {
+                        message: format!("{}: {}", path, e),
+                    },
+                ),
+            }
+        }
+        AgentRequest::Exec { argv, cwd, env } => {
+            run_command(argv, cwd, env, reader, writer.clone())
+        }
+        other => reply(
+            &writer,
+            &AgentReply::Error {
+                message: format!("unexpected request {:?}", other),
+            },
+        ),
+    };
+    if let Err(e) = result {
+        eprintln!("sandboxed-vm-agent: connection error: {}", e);
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn run_command(
+    argv: Vec<String>,
+    cwd: String,
+    env: BTreeMap<String, String>,
+    mut reader: BufReader<std::fs::File>,
+    writer: Arc<Mutex<std::fs::File>>,
+) -> std::io::Result<()> {
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+    let Some((program, args)) = argv.split_first() else {
+        return reply(
+            &writer,
+            &AgentReply::Error {
+                message: "empty command".to_string(),
+            },
+        );
+    };
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args)
+        .current_dir(&cwd)
+        .env_clear()
+        .envs(&env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0);
+    if !env.contains_key("PATH") {
+        cmd.env("PATH", DEFAULT_PATH);
+    }
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            return reply(
+                &writer,
+                &AgentReply::Error {
+                    message: format!("{}: {}", program, e),
+                },
+            )
+        }
+    };
+    let pgid = child.id() as i32;
+
+    let mut pumps = Vec::new();
+    if let Some(out) = child.stdout.take() {
+        pumps.push(pump(out, writer.clone(), false));
+    }
+    if let Some(err) = child.stderr.take() {
+        pumps.push(pump(err, writer.clone(), true));
+    }
+    let mut stdin = child.stdin.take();
+    std::thread::spawn(move || loop {
+        match read_message::<AgentRequest>(&mut reader) {
+            Ok(Some(AgentRequest::Stdin { data })) => {
+                if let (Some(pipe), Ok(bytes)) = (stdin.as_mut(), decode(&data)) {
+                    if pipe.write_all(&bytes).is_err() {
+                        stdin = None;
+                    }
+                }
+            }
+            Ok(Some(AgentRequest::StdinEof)) => stdin = None,
+            Ok(Some(_)) => {}
+            Ok(None) | Err(_) => {
+                // The host side went away: don't leave the command running.
+                // SAFETY: signalling the process group we created.
+                unsafe {
+                    libc::kill(-pgid, libc::SIGKILL);
+                }
+                break;
+            }
+        }
+    });
+
+    let status = child.wait()?;
+    for pump in pumps {
+        let _ = pump.join();
+    }
+    let code = status
+        .code()
+        .unwrap_or_else(|| 128 + status.signal().unwrap_or(0));
+    reply(&writer, &AgentReply::Exit { code })
+}
+
+fn pump(
+    mut source: impl Read + Send + 'static,
+    writer: Arc<Mutex<std::fs::File>>,
+    stderr: bool,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = match source.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            let data = encode(&buf[..n]);
+            let message = if stderr {
+                AgentReply::Stderr { data }
+            } else {
+                AgentReply::Stdout { data }
+            };
+            if reply(&writer, &message).is_err() {
+                break;
+            }
+        }
+    })
+}
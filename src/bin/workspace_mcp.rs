@@ -3,7 +3,7 @@
 //! Exposes a minimal set of Open Agent tools to OpenCode via MCP.
 //! Communicates over stdio using JSON-RPC 2.0.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -13,6 +13,12 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use sandboxed_sh::hooks::{self, HookEvent};
+use sandboxed_sh::offline;
+use sandboxed_sh::pii;
+use sandboxed_sh::policy;
+use sandboxed_sh::tool_pruning;
+use sandboxed_sh::tool_quotas::{self, Quota};
 use sandboxed_sh::tools;
 use sandboxed_sh::tools::Tool;
 
@@ -584,12 +590,84 @@ fn tool_set() -> HashMap<String, Arc<dyn Tool>> {
     let mut tools: HashMap<String, Arc<dyn Tool>> = HashMap::new();
 
     tools.insert("read_file".to_string(), Arc::new(tools::ReadFile));
+    tools.insert("read_file_at".to_string(), Arc::new(tools::ReadFileAt));
     tools.insert("write_file".to_string(), Arc::new(tools::WriteFile));
     tools.insert("delete_file".to_string(), Arc::new(tools::DeleteFile));
     tools.insert("list_directory".to_string(), Arc::new(tools::ListDirectory));
     tools.insert("search_files".to_string(), Arc::new(tools::SearchFiles));
     tools.insert("grep_search".to_string(), Arc::new(tools::GrepSearch));
+    tools.insert("watch_path".to_string(), Arc::new(tools::WatchPath));
+    tools.insert("set_plan".to_string(), Arc::new(tools::SetPlan));
+    tools.insert("update_plan".to_string(), Arc::new(tools::UpdatePlan));
     tools.insert("fetch_url".to_string(), Arc::new(tools::FetchUrl));
+    tools.insert("lookup_docs".to_string(), Arc::new(tools::LookupDocs));
+    tools.insert("package_info".to_string(), Arc::new(tools::PackageInfo));
+    tools.insert(
+        "detect_environment".to_string(),
+        Arc::new(tools::DetectEnvironment),
+    );
+    tools.insert("analyze_logs".to_string(), Arc::new(tools::AnalyzeLogs));
+    tools.insert("reproduce_bug".to_string(), Arc::new(tools::ReproduceBug));
+    tools.insert(
+        "query_structured".to_string(),
+        Arc::new(tools::QueryStructured),
+    );
+    tools.insert(
+        "patch_structured".to_string(),
+        Arc::new(tools::PatchStructured),
+    );
+    tools.insert("inspect_data".to_string(), Arc::new(tools::InspectData));
+    tools.insert("read_notebook".to_string(), Arc::new(tools::ReadNotebook));
+    tools.insert(
+        "edit_notebook_cell".to_string(),
+        Arc::new(tools::EditNotebookCell),
+    );
+    tools.insert("compare_images".to_string(), Arc::new(tools::CompareImages));
+    tools.insert("ocr_image".to_string(), Arc::new(tools::OcrImage));
+    tools.insert("docker_build".to_string(), Arc::new(tools::DockerBuild));
+    tools.insert("docker_run".to_string(), Arc::new(tools::DockerRun));
+    tools.insert("docker_push".to_string(), Arc::new(tools::DockerPush));
+    tools.insert("k8s_get".to_string(), Arc::new(tools::K8sGet));
+    tools.insert("k8s_describe".to_string(), Arc::new(tools::K8sDescribe));
+    tools.insert("k8s_logs".to_string(), Arc::new(tools::K8sLogs));
+    tools.insert("k8s_events".to_string(), Arc::new(tools::K8sEvents));
+    tools.insert("terraform_plan".to_string(), Arc::new(tools::TerraformPlan));
+    tools.insert("git_commit".to_string(), Arc::new(tools::GitCommit));
+    tools.insert("git_push".to_string(), Arc::new(tools::GitPush));
+    tools.insert(
+        "git_create_branch".to_string(),
+        Arc::new(tools::GitCreateBranch),
+    );
+    tools.insert("git_rebase".to_string(), Arc::new(tools::GitRebase));
+    tools.insert(
+        "checkout_reference_repo".to_string(),
+        Arc::new(tools::CheckoutReferenceRepo),
+    );
+    tools.insert("gh_pr_diff".to_string(), Arc::new(tools::GhPrDiff));
+    tools.insert("gh_pr_comment".to_string(), Arc::new(tools::GhPrComment));
+    tools.insert(
+        "gh_pr_review_threads".to_string(),
+        Arc::new(tools::GhPrReviewThreads),
+    );
+    tools.insert("gh_pr_reply".to_string(), Arc::new(tools::GhPrReply));
+    tools.insert("gh_pr_review".to_string(), Arc::new(tools::GhPrReview));
+    tools.insert("gh_pr_open".to_string(), Arc::new(tools::GhPrOpen));
+    tools.insert(
+        "tracker_get_issue".to_string(),
+        Arc::new(tools::TrackerGetIssue),
+    );
+    tools.insert(
+        "tracker_add_comment".to_string(),
+        Arc::new(tools::TrackerAddComment),
+    );
+    tools.insert(
+        "tracker_transition".to_string(),
+        Arc::new(tools::TrackerTransition),
+    );
+    tools.insert(
+        "tracker_create_subtask".to_string(),
+        Arc::new(tools::TrackerCreateSubtask),
+    );
     tools.insert("update_skill".to_string(), Arc::new(UpdateSkillTool));
     tools.insert(
         "update_init_script".to_string(),
@@ -612,6 +690,82 @@ fn tool_definitions(tools: &HashMap<String, Arc<dyn Tool>>) -> Vec<ToolDefinitio
     defs
 }
 
+/// Load the mission's library tools (see `tools::library_tool`) into `tools`,
+/// returning their names. Built-in tools win on name clashes.
+fn load_library_tools(
+    runtime: &tokio::runtime::Runtime,
+    tools: &mut HashMap<String, Arc<dyn Tool>>,
+    working_dir: &Path,
+) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for tool in runtime.block_on(tools::library_tool::load_library_tools(working_dir)) {
+        let name = tool.name().to_string();
+        if tools.contains_key(&name) {
+            eprintln!(
+                "[workspace-mcp] Library tool '{}' shadows a built-in tool; skipping",
+                name
+            );
+            continue;
+        }
+        tools.insert(name.clone(), tool);
+        names.insert(name);
+    }
+    debug_log("library_tools", &json!({ "loaded": names }));
+    names
+}
+
+/// Register the agent's tool aliases for this turn (see `tools::alias`).
+fn register_agent_aliases(
+    tools: &mut HashMap<String, Arc<dyn Tool>>,
+    working_dir: &Path,
+) -> HashSet<String> {
+    let aliases = tools::alias::read_agent_aliases(working_dir);
+    let mut names = HashSet::new();
+    for result in tools::alias::register_aliases(tools, &aliases) {
+        match result {
+            Ok(name) => {
+                names.insert(name);
+            }
+            Err(e) => eprintln!("[workspace-mcp] Skipping tool alias: {}", e),
+        }
+    }
+    debug_log("tool_aliases", &json!({ "registered": names }));
+    names
+}
+
+/// Prune tool definitions for this turn (see `tool_pruning`), recording the
+/// prompt-size savings for the mission runner. Library tools are always kept:
+/// they were picked for this mission explicitly.
+fn pruned_tool_definitions(
+    defs: Vec<ToolDefinition>,
+    working_dir: &Path,
+    library_tools: &HashSet<String>,
+) -> Vec<ToolDefinition> {
+    let Some(hints) = tool_pruning::read_turn_hints(working_dir) else {
+        return defs;
+    };
+    let recent = tool_pruning::recent_tool_use(working_dir);
+    let selected =
+        tool_pruning::select_tools(defs.iter().map(|d| d.name.as_str()), &hints, &recent);
+    let schema_bytes = |d: &ToolDefinition| serde_json::to_string(d).map(|s| s.len()).unwrap_or(0);
+
+    let total_tools = defs.len();
+    let total_schema_bytes = defs.iter().map(schema_bytes).sum();
+    let pruned: Vec<ToolDefinition> = defs
+        .into_iter()
+        .filter(|d| selected.contains(&d.name) || library_tools.contains(&d.name))
+        .collect();
+    let stats = tool_pruning::PruningStats {
+        total_tools,
+        selected_tools: pruned.len(),
+        total_schema_bytes,
+        selected_schema_bytes: pruned.iter().map(schema_bytes).sum(),
+    };
+    debug_log("tools/list pruned", &json!(stats));
+    let _ = tool_pruning::write_stats(working_dir, &stats);
+    pruned
+}
+
 fn execute_tool(
     runtime: &tokio::runtime::Runtime,
     tools: &HashMap<String, Arc<dyn Tool>>,
@@ -628,25 +782,145 @@ fn execute_tool(
         };
     };
 
-    let result = runtime.block_on(tool.execute(args.clone(), working_dir));
-    match result {
-        Ok(text) => ToolResult {
-            content: vec![ToolContent::Text { text }],
-            is_error: false,
-        },
-        Err(e) => ToolResult {
+    // Alias calls are validated, checked and hooked as the call that runs
+    let (tool, name, mut args) = match tool.alias_target(args) {
+        Some((target, expanded)) => {
+            let target_name = target.name().to_string();
+            (target, target_name, expanded)
+        }
+        None => (Arc::clone(tool), name.to_string(), args.clone()),
+    };
+    let name = name.as_str();
+    if let Err(e) = tools::args::validate(&tool.parameters_schema(), &mut args) {
+        return ToolResult {
             content: vec![ToolContent::Text {
-                text: format!("Tool error: {}", e),
+                text: e.message(name),
             }],
             is_error: true,
-        },
+        };
+    }
+    let args = &args;
+
+    if let Some(reason) = runtime.block_on(tools::host_access::path_refusal(
+        tool.as_ref(),
+        args,
+        working_dir,
+    )) {
+        return ToolResult {
+            content: vec![ToolContent::Text { text: reason }],
+            is_error: true,
+        };
+    }
+
+    let policy = runtime.block_on(policy::evaluate(working_dir, name, tool.path_args(), args));
+    if let Some(reason) = policy.refusal() {
+        return ToolResult {
+            content: vec![ToolContent::Text { text: reason }],
+            is_error: true,
+        };
+    }
+    if let Some(reason) = policy.approval_reason() {
+        if let Err(reason) = runtime.block_on(tools::guard::request_approval(name, args, &reason)) {
+            return ToolResult {
+                content: vec![ToolContent::Text { text: reason }],
+                is_error: true,
+            };
+        }
+    }
+
+    let quota = Quota::for_tool(name);
+    if let Some(quota) = quota {
+        if let Err(reason) = tool_quotas::check(working_dir, quota) {
+            return ToolResult {
+                content: vec![ToolContent::Text { text: reason }],
+                is_error: true,
+            };
+        }
+    }
+
+    let hook_set = match runtime.block_on(hooks::fetch()) {
+        Ok(hook_set) => hook_set,
+        Err(e) => {
+            return ToolResult {
+                content: vec![ToolContent::Text {
+                    text: format!("Tool call blocked: hooks could not be loaded ({})", e),
+                }],
+                is_error: true,
+            };
+        }
+    };
+    let mission_id = std::env::var("SANDBOXED_SH_MISSION_ID").ok();
+    let pre = runtime.block_on(hooks::run_hooks(
+        &hook_set,
+        working_dir,
+        HookEvent::PreTool,
+        Some(name),
+        json!({ "mission_id": mission_id, "tool": name, "args": args }),
+    ));
+    if let Some(reason) = pre.blocked {
+        return ToolResult {
+            content: vec![ToolContent::Text {
+                text: format!("Tool call blocked by hook: {}", reason),
+            }],
+            is_error: true,
+        };
+    }
+
+    let result = runtime.block_on(tool.execute(args.clone(), working_dir));
+    let (mut text, is_error) = match result {
+        Ok(text) => (text, false),
+        Err(e) => (format!("Tool error: {}", e), true),
+    };
+    if let Some(quota) = quota.filter(|_| !is_error) {
+        tool_quotas::record(working_dir, quota, quota.amount(&text));
+    }
+
+    let post = runtime.block_on(hooks::run_hooks(
+        &hook_set,
+        working_dir,
+        HookEvent::PostTool,
+        Some(name),
+        json!({
+            "mission_id": mission_id,
+            "tool": name,
+            "args": args,
+            "result": text,
+            "is_error": is_error,
+        }),
+    ));
+    for warning in policy.warnings() {
+        text.push_str(&format!(
+            "\n\n[policy warning {}] {}",
+            warning.rule, warning.message
+        ));
+    }
+    for annotation in pre.annotations.iter().chain(&post.annotations) {
+        text.push_str(&format!(
+            "\n\n[hook {}] {}",
+            annotation.script, annotation.text
+        ));
+    }
+    let sensitivity = pii::Sensitivity::from_workspace();
+    if sensitivity != pii::Sensitivity::Off {
+        let (masked, spans) = pii::mask(&text, sensitivity);
+        if !spans.is_empty() {
+            text = masked;
+            if let Err(e) = pii::record(working_dir, name, spans) {
+                debug_log("pii report failed", &json!({ "error": e.to_string() }));
+            }
+        }
+    }
+    ToolResult {
+        content: vec![ToolContent::Text { text }],
+        is_error,
     }
 }
 
 fn handle_request(
     request: &JsonRpcRequest,
     runtime: &tokio::runtime::Runtime,
-    tools: &HashMap<String, Arc<dyn Tool>>,
+    tools: &mut HashMap<String, Arc<dyn Tool>>,
+    library_tools: &mut HashSet<String>,
     working_dir: &Arc<RwLock<PathBuf>>,
 ) -> Option<JsonRpcResponse> {
     match request.method.as_str() {
@@ -659,6 +933,55 @@ fn handle_request(
                 }
             }
             apply_runtime_workspace(working_dir);
+            let cwd = working_dir
+                .read()
+                .map(|guard| guard.clone())
+                .unwrap_or_else(|_| PathBuf::from("."));
+            // Compose the mission's tool set: built-in bundles, then library tools
+            let selection = tools::bundles::BundleSelection::for_workspace(&cwd);
+            debug_log("tool_bundles", &json!(selection));
+            *tools = tool_set();
+            tools.retain(|name, _| selection.allows(name));
+            // Offline mode (as the server reports it): drop tools that need
+            // internet access. Unreadable settings count as offline.
+            let offline = match runtime.block_on(tools::guard::setting(offline::OFFLINE_SETTING)) {
+                Ok(value) => value.is_some_and(|v| offline::enabled(&v)),
+                Err(e) => {
+                    debug_log("offline_setting", &json!({ "error": e.to_string() }));
+                    true
+                }
+            };
+            if offline {
+                for name in offline::NETWORK_TOOLS {
+                    tools.remove(*name);
+                }
+            }
+            // Audit missions get read-only tools only: no library tools,
+            // aliases or host exec
+            let audit = tools::audit::audit_mode(&cwd);
+            if audit {
+                tools.retain(|name, _| tools::audit::is_read_only(name));
+                tools.insert(
+                    "run_audit_check".to_string(),
+                    Arc::new(tools::RunAuditCheck),
+                );
+                tools.insert(
+                    "submit_audit_report".to_string(),
+                    Arc::new(tools::SubmitAuditReport),
+                );
+                library_tools.clear();
+            } else {
+                *library_tools = load_library_tools(runtime, tools, &cwd);
+                library_tools.extend(register_agent_aliases(tools, &cwd));
+            }
+            // Offered only while an admin has host exec enabled for the mission
+            if !audit && tools::host_access::host_exec_enabled(&cwd) {
+                tools.insert(
+                    "host_exec".to_string(),
+                    Arc::new(tools::host_access::HostExec),
+                );
+                library_tools.insert("host_exec".to_string());
+            }
             Some(JsonRpcResponse::success(
                 request.id.clone(),
                 json!({
@@ -677,7 +1000,15 @@ fn handle_request(
         }
         "notifications/initialized" | "initialized" => None,
         "tools/list" => {
-            let defs = tool_definitions(tools);
+            let mut defs = tool_definitions(tools);
+            apply_runtime_workspace(working_dir);
+            let cwd = working_dir
+                .read()
+                .map(|guard| guard.clone())
+                .unwrap_or_else(|_| PathBuf::from("."));
+            if tool_pruning::pruning_active(&cwd) {
+                defs = pruned_tool_definitions(defs, &cwd, library_tools);
+            }
             Some(JsonRpcResponse::success(
                 request.id.clone(),
                 json!({ "tools": defs }),
@@ -700,6 +1031,9 @@ fn handle_request(
                 .read()
                 .map(|guard| guard.clone())
                 .unwrap_or_else(|_| PathBuf::from("."));
+            if tool_pruning::pruning_active(&cwd) && tools.contains_key(name) {
+                tool_pruning::record_tool_use(&cwd, name);
+            }
             let result = execute_tool(runtime, tools, name, &args, &cwd);
             Some(JsonRpcResponse::success(request.id.clone(), json!(result)))
         }
@@ -719,7 +1053,8 @@ fn main() {
         .build()
         .expect("Failed to start tokio runtime");
 
-    let tools = tool_set();
+    let mut tools = tool_set();
+    let mut library_tools = HashSet::new();
     let workspace = Arc::new(RwLock::new(hydrate_workspace_env(None)));
 
     let stdin = std::io::stdin();
@@ -748,7 +1083,13 @@ fn main() {
             }
         };
 
-        if let Some(response) = handle_request(&request, &runtime, &tools, &workspace) {
+        if let Some(response) = handle_request(
+            &request,
+            &runtime,
+            &mut tools,
+            &mut library_tools,
+            &workspace,
+        ) {
             if let Ok(resp) = serde_json::to_string(&response) {
                 let _ = writeln!(stdout, "{}", resp);
                 let _ = stdout.flush();
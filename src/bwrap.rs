@@ -0,0 +1,254 @@
+//! bubblewrap (bwrap) isolation for container workspaces.
+//!
+//! systemd-nspawn is the preferred container backend, but it is missing in
+//! many environments (inside Docker, minimal distros, hosts without systemd).
+//! When it is absent and `bwrap` is installed, container workspaces run in
+//! their root filesystem under bubblewrap instead of silently falling back to
+//! the host: the same rootfs, bind mounts and environment, with private PID,
+//! IPC and UTS namespaces.
+//!
+//! Networking is mapped from the same workspace fields: a shared network uses
+//! the host stack, an isolated one gets its own network namespace with only
+//! loopback. bwrap cannot create veth links, so Tailscale and egress policies
+//! still need systemd-nspawn.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Once;
+
+use crate::nspawn;
+
+/// How a container workspace is isolated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerBackend {
+    Nspawn,
+    Bwrap,
+    /// Per-workspace microVM, chosen by the template (see `crate::microvm`)
+    MicroVm,
+}
+
+impl ContainerBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Nspawn => "systemd-nspawn",
+            Self::Bwrap => "bwrap",
+            Self::MicroVm => "microvm",
+        }
+    }
+}
+
+/// Returns true if bubblewrap is available on this host.
+pub fn bwrap_available() -> bool {
+    cfg!(target_os = "linux") && crate::host::command_on_path("bwrap")
+}
+
+fn select_backend(requested: &str, nspawn: bool, bwrap: bool) -> Option<ContainerBackend> {
+    match requested.trim().to_ascii_lowercase().as_str() {
+        "nspawn" | "systemd-nspawn" => nspawn.then_some(ContainerBackend::Nspawn),
+        "bwrap" | "bubblewrap" => bwrap.then_some(ContainerBackend::Bwrap),
+        _ if nspawn => Some(ContainerBackend::Nspawn),
+        _ if bwrap => Some(ContainerBackend::Bwrap),
+        _ => None,
+    }
+}
+
+/// Backend used for container workspaces, or `None` when neither is
+/// available (workspaces then fall back to the host if allowed).
+///
+/// `SANDBOXED_SH_CONTAINER_BACKEND` (`auto`, `nspawn` or `bwrap`) pins the
+/// choice; `auto` prefers systemd-nspawn. The selection is logged once.
+pub fn container_backend() -> Option<ContainerBackend> {
+    static LOGGED: Once = Once::new();
+    let requested = std::env::var("SANDBOXED_SH_CONTAINER_BACKEND").unwrap_or_default();
+    let nspawn = nspawn::nspawn_available();
+    let bwrap = bwrap_available();
+    let backend = select_backend(&requested, nspawn, bwrap);
+    LOGGED.call_once(|| match backend {
+        Some(backend) => tracing::info!(
+            backend = backend.as_str(),
+            requested = %requested,
+            nspawn_available = nspawn,
+            bwrap_available = bwrap,
+            "Container isolation backend selected"
+        ),
+        None => tracing::warn!(
+            requested = %requested,
+            nspawn_available = nspawn,
+            bwrap_available = bwrap,
+            "No container isolation backend available; install systemd-container or bubblewrap"
+        ),
+    });
+    backend
+}
+
+/// Network namespace for a bwrap sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BwrapNetwork {
+    /// Share the host network.
+    Shared,
+    /// Private namespace with only loopback.
+    Private,
+}
+
+#[derive(Debug, Clone)]
+pub struct BwrapOptions {
+    /// Working directory inside the sandbox
+    pub chdir: String,
+    pub hostname: Option<String>,
+    pub network: BwrapNetwork,
+    /// Bind mounts in systemd-nspawn syntax (`--bind=SRC[:DST]`,
+    /// `--bind-ro=SRC[:DST]`) so the existing bind helpers can be reused.
+    pub binds: Vec<String>,
+    pub capabilities: Vec<String>,
+    pub env: HashMap<String, String>,
+}
+
+impl Default for BwrapOptions {
+    fn default() -> Self {
+        Self {
+            chdir: "/".to_string(),
+            hostname: None,
+            network: BwrapNetwork::Shared,
+            binds: Vec::new(),
+            capabilities: Vec::new(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Translate a systemd-nspawn bind argument into bwrap arguments. Missing
+/// sources are skipped rather than failing the whole command. Writable binds
+/// of `/dev` nodes (GPUs) keep device access.
+fn translate_bind(arg: &str) -> Option<[String; 3]> {
+    let (flag, spec) = if let Some(spec) = arg.strip_prefix("--bind-ro=") {
+        ("--ro-bind-try", spec)
+    } else if let Some(spec) = arg.strip_prefix("--bind=") {
+        if spec.starts_with("/dev/") {
+            ("--dev-bind-try", spec)
+        } else {
+            ("--bind-try", spec)
+        }
+    } else {
+        return None;
+    };
+    let mut parts = spec.splitn(3, ':');
+    let src = parts.next().filter(|s| !s.is_empty())?;
+    let dst = parts.next().filter(|s| !s.is_empty()).unwrap_or(src);
+    Some([flag.to_string(), src.to_string(), dst.to_string()])
+}
+
+/// Arguments for `bwrap` running `command` in the rootfs at `root`.
+pub fn bwrap_args(root: &Path, opts: &BwrapOptions, command: &[String]) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "--bind".to_string(),
+        root.to_string_lossy().into_owned(),
+        "/".to_string(),
+        "--dev".to_string(),
+        "/dev".to_string(),
+        "--proc".to_string(),
+        "/proc".to_string(),
+        "--tmpfs".to_string(),
+        "/tmp".to_string(),
+        "--unshare-pid".to_string(),
+        "--unshare-ipc".to_string(),
+        "--unshare-uts".to_string(),
+        "--die-with-parent".to_string(),
+    ];
+    if opts.network == BwrapNetwork::Private {
+        args.push("--unshare-net".to_string());
+    }
+    if let Some(hostname) = opts.hostname.as_deref().filter(|h| !h.trim().is_empty()) {
+        args.push("--hostname".to_string());
+        args.push(hostname.to_string());
+    }
+    for bind in &opts.binds {
+        match translate_bind(bind) {
+            Some(bind) => args.extend(bind),
+            None => tracing::debug!(bind = %bind, "Ignoring unsupported bind for bwrap"),
+        }
+    }
+    for capability in &opts.capabilities {
+        if capability.trim().is_empty() {
+            continue;
+        }
+        args.push("--cap-add".to_string());
+        args.push(capability.to_string());
+    }
+    args.push("--chdir".to_string());
+    args.push(opts.chdir.clone());
+
+    // Like systemd-nspawn, start from a clean environment instead of
+    // inheriting the server's.
+    args.push("--clearenv".to_string());
+    let mut env: Vec<_> = opts
+        .env
+        .iter()
+        .filter(|(k, _)| !k.trim().is_empty())
+        .collect();
+    env.sort();
+    if !opts.env.contains_key("PATH") {
+        args.extend(["--setenv", "PATH", DEFAULT_PATH].map(String::from));
+    }
+    for (key, value) in env {
+        args.extend(["--setenv".to_string(), key.clone(), value.clone()]);
+    }
+
+    args.push("--".to_string());
+    args.extend(command.iter().cloned());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_backend_by_preference_and_availability() {
+        use ContainerBackend::*;
+        assert_eq!(select_backend("", true, true), Some(Nspawn));
+        assert_eq!(select_backend("auto", false, true), Some(Bwrap));
+        assert_eq!(select_backend("bwrap", true, true), Some(Bwrap));
+        assert_eq!(select_backend("nspawn", false, true), None);
+        assert_eq!(select_backend("", false, false), None);
+    }
+
+    #[test]
+    fn translates_nspawn_binds() {
+        assert_eq!(
+            translate_bind("--bind-ro=/var/cache/repos:/opt/repos").unwrap(),
+            ["--ro-bind-try", "/var/cache/repos", "/opt/repos"]
+        );
+        assert_eq!(
+            translate_bind("--bind=/tmp/.X11-unix").unwrap(),
+            ["--bind-try", "/tmp/.X11-unix", "/tmp/.X11-unix"]
+        );
+        assert_eq!(
+            translate_bind("--bind=/dev/nvidia0").unwrap(),
+            ["--dev-bind-try", "/dev/nvidia0", "/dev/nvidia0"]
+        );
+        assert!(translate_bind("--network-veth").is_none());
+    }
+
+    #[test]
+    fn builds_isolated_command() {
+        let opts = BwrapOptions {
+            chdir: "/root/work".to_string(),
+            hostname: Some("ws1".to_string()),
+            network: BwrapNetwork::Private,
+            binds: vec!["--bind-ro=/etc/resolv.conf".to_string()],
+            env: HashMap::from([("HOME".to_string(), "/root".to_string())]),
+            ..Default::default()
+        };
+        let args = bwrap_args(Path::new("/containers/ws1"), &opts, &["ls".to_string()]);
+        let joined = args.join(" ");
+        assert!(joined.starts_with("--bind /containers/ws1 / "));
+        assert!(joined.contains("--unshare-net"));
+        assert!(joined.contains("--hostname ws1"));
+        assert!(joined.contains("--ro-bind-try /etc/resolv.conf /etc/resolv.conf"));
+        assert!(joined.contains("--chdir /root/work --clearenv"));
+        assert!(joined.contains("--setenv HOME /root"));
+        assert!(joined.ends_with("-- ls"));
+    }
+}
@@ -0,0 +1,355 @@
+//! Default sampling options for agent turns.
+//!
+//! `ChatOptions` can be declared in the `.sandboxed-sh/config.json` of a
+//! config profile, on a workspace (`config.chat_options`), and in library agent
+//! frontmatter, each overriding the previous. The effective options are
+//! applied to the harness for each turn and recorded on the turn's
+//! `assistant_message` event alongside token usage.
+//!
+//! Not every harness can apply every option (see [`ChatOptions::retain_applicable`]).
+//! Options a backend would ignore are dropped from the recorded set and
+//! reported as a `config_warning` when the mission starts.
+//!
+//! The reasoning setting is a level (`off`, `low`, `medium`, `high`) or an
+//! explicit thinking token budget, mapped per provider: Anthropic extended
+//! thinking budgets, Codex `reasoning.effort`, and OpenRouter's `reasoning`
+//! request object.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Reasoning effort levels accepted across harnesses.
+pub const REASONING_EFFORT_LEVELS: &[&str] = &["off", "low", "medium", "high"];
+
+/// Accepted range for an explicit thinking token budget.
+pub const MIN_THINKING_BUDGET: u64 = 1_024;
+pub const MAX_THINKING_BUDGET: u64 = 128_000;
+
+/// Sampling and generation options for a chat completion.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ChatOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// "off", "low", "medium", "high", or a thinking token budget ("16000").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+}
+
+impl ChatOptions {
+    pub fn is_empty(&self) -> bool {
+        self.temperature.is_none()
+            && self.top_p.is_none()
+            && self.max_tokens.is_none()
+            && self.reasoning_effort.is_none()
+    }
+
+    /// Parse options from agent YAML frontmatter.
+    ///
+    /// Accepts both snake_case and the camelCase spellings OpenCode uses
+    /// (`topP`, `maxTokens`, `reasoningEffort`), plus `reasoning` as a
+    /// shorthand. Invalid values are ignored.
+    pub fn from_frontmatter(frontmatter: &Option<serde_yaml::Value>) -> Self {
+        let Some(fm) = frontmatter.as_ref() else {
+            return Self::default();
+        };
+        let get = |keys: &[&str]| keys.iter().find_map(|k| fm.get(*k));
+        let options = Self {
+            temperature: get(&["temperature"]).and_then(|v| v.as_f64()),
+            top_p: get(&["top_p", "topP"]).and_then(|v| v.as_f64()),
+            max_tokens: get(&["max_tokens", "maxTokens"]).and_then(|v| v.as_u64()),
+            reasoning_effort: get(&["reasoning_effort", "reasoningEffort", "reasoning"])
+                .and_then(|v| match v {
+                    serde_yaml::Value::String(s) => Some(s.clone()),
+                    serde_yaml::Value::Number(n) => Some(n.to_string()),
+                    serde_yaml::Value::Bool(false) => Some("off".to_string()),
+                    _ => None,
+                })
+                .and_then(|v| normalize_reasoning_effort(&v)),
+        };
+        options.sanitized()
+    }
+
+    /// Check that every set value is within range.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(t) = self.temperature {
+            if !(0.0..=2.0).contains(&t) {
+                return Err(format!("temperature must be between 0 and 2 (got {})", t));
+            }
+        }
+        if let Some(p) = self.top_p {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(format!("top_p must be between 0 and 1 (got {})", p));
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return Err("max_tokens must be at least 1".to_string());
+        }
+        if let Some(effort) = self.reasoning_effort.as_deref() {
+            if normalize_reasoning_effort(effort).is_none() {
+                return Err(format!(
+                    "reasoning_effort must be one of: {}, or a token budget between {} and {}",
+                    REASONING_EFFORT_LEVELS.join(", "),
+                    MIN_THINKING_BUDGET,
+                    MAX_THINKING_BUDGET
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop out-of-range values instead of failing.
+    fn sanitized(mut self) -> Self {
+        if self.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+            self.temperature = None;
+        }
+        if self.top_p.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+            self.top_p = None;
+        }
+        if self.max_tokens == Some(0) {
+            self.max_tokens = None;
+        }
+        self
+    }
+
+    /// Layer `overrides` on top of `self`; set fields in `overrides` win.
+    pub fn merged_with(&self, overrides: &ChatOptions) -> ChatOptions {
+        ChatOptions {
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            reasoning_effort: overrides
+                .reasoning_effort
+                .clone()
+                .or_else(|| self.reasoning_effort.clone()),
+        }
+    }
+
+    /// Drop the options `backend` cannot apply and return their names.
+    ///
+    /// Claude Code takes the output and thinking budgets, Codex the reasoning
+    /// effort. OpenCode reads sampling options from the agent's own
+    /// frontmatter, so there they only apply when they come from `agent`.
+    pub fn retain_applicable(&mut self, backend: &str, agent: &ChatOptions) -> Vec<&'static str> {
+        let applies = |field: &str, from_agent: bool| match backend {
+            "claudecode" => matches!(field, "max_tokens" | "reasoning_effort"),
+            "codex" => field == "reasoning_effort",
+            "opencode" => {
+                from_agent && matches!(field, "temperature" | "top_p" | "reasoning_effort")
+            }
+            _ => false,
+        };
+        let mut dropped = Vec::new();
+        let mut check = |field: &'static str, set: bool, from_agent: bool| {
+            let keep = !set || applies(field, from_agent);
+            if !keep {
+                dropped.push(field);
+            }
+            keep
+        };
+        if !check(
+            "temperature",
+            self.temperature.is_some(),
+            agent.temperature == self.temperature,
+        ) {
+            self.temperature = None;
+        }
+        if !check("top_p", self.top_p.is_some(), agent.top_p == self.top_p) {
+            self.top_p = None;
+        }
+        if !check(
+            "max_tokens",
+            self.max_tokens.is_some(),
+            agent.max_tokens == self.max_tokens,
+        ) {
+            self.max_tokens = None;
+        }
+        if !check(
+            "reasoning_effort",
+            self.reasoning_effort.is_some(),
+            agent.reasoning_effort == self.reasoning_effort,
+        ) {
+            self.reasoning_effort = None;
+        }
+        dropped
+    }
+
+    /// Environment variables understood by the Claude Code CLI.
+    ///
+    /// Claude Code has no temperature/top_p flags; output length and the
+    /// extended-thinking budget are controlled through its environment.
+    pub fn claudecode_env(&self) -> HashMap<String, String> {
+        let mut env = HashMap::new();
+        if let Some(max) = self.max_tokens {
+            env.insert("CLAUDE_CODE_MAX_OUTPUT_TOKENS".to_string(), max.to_string());
+        }
+        if let Some(budget) = self
+            .reasoning_effort
+            .as_deref()
+            .and_then(thinking_budget_for_effort)
+        {
+            env.insert("MAX_THINKING_TOKENS".to_string(), budget.to_string());
+        }
+        env
+    }
+}
+
+/// Normalize a reasoning setting ("Medium " -> "medium", "none" -> "off",
+/// " 16000" -> "16000"). Budgets outside the accepted range are rejected.
+pub fn normalize_reasoning_effort(raw: &str) -> Option<String> {
+    let normalized = raw.trim().to_ascii_lowercase();
+    match normalized.as_str() {
+        "none" | "disabled" | "false" => return Some("off".to_string()),
+        level if REASONING_EFFORT_LEVELS.contains(&level) => return Some(normalized),
+        _ => {}
+    }
+    let budget: u64 = normalized.parse().ok()?;
+    (MIN_THINKING_BUDGET..=MAX_THINKING_BUDGET)
+        .contains(&budget)
+        .then(|| budget.to_string())
+}
+
+/// Extended-thinking token budget for a normalized reasoning setting.
+/// `off` maps to a zero budget, which disables thinking.
+pub fn thinking_budget_for_effort(effort: &str) -> Option<u64> {
+    match effort {
+        "off" => Some(0),
+        "low" => Some(4_000),
+        "medium" => Some(10_000),
+        "high" => Some(32_000),
+        budget => budget.parse().ok(),
+    }
+}
+
+/// Codex `reasoning.effort` value for a normalized reasoning setting.
+///
+/// Codex has no "off" level, so it maps to `minimal`; explicit budgets are
+/// bucketed to the nearest level.
+pub fn codex_reasoning_effort(effort: &str) -> Option<&'static str> {
+    match effort {
+        "off" => Some("minimal"),
+        "low" => Some("low"),
+        "medium" => Some("medium"),
+        "high" => Some("high"),
+        budget => match budget.parse::<u64>().ok()? {
+            0..=4_000 => Some("low"),
+            4_001..=16_000 => Some("medium"),
+            _ => Some("high"),
+        },
+    }
+}
+
+/// OpenRouter `reasoning` request object for a normalized reasoning setting.
+pub fn openrouter_reasoning(effort: &str) -> Option<serde_json::Value> {
+    match effort {
+        "off" => Some(serde_json::json!({ "enabled": false })),
+        "low" | "medium" | "high" => Some(serde_json::json!({ "effort": effort })),
+        budget => budget
+            .parse::<u64>()
+            .ok()
+            .map(|max_tokens| serde_json::json!({ "max_tokens": max_tokens })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_frontmatter_accepts_both_spellings() {
+        let fm: serde_yaml::Value = serde_yaml::from_str(
+            "temperature: 0.2\ntopP: 0.9\nmax_tokens: 4096\nreasoningEffort: High\n",
+        )
+        .unwrap();
+        let options = ChatOptions::from_frontmatter(&Some(fm));
+        assert_eq!(options.temperature, Some(0.2));
+        assert_eq!(options.top_p, Some(0.9));
+        assert_eq!(options.max_tokens, Some(4096));
+        assert_eq!(options.reasoning_effort.as_deref(), Some("high"));
+
+        let fm: serde_yaml::Value = serde_yaml::from_str("temperature: 5\n").unwrap();
+        assert!(ChatOptions::from_frontmatter(&Some(fm)).is_empty());
+    }
+
+    #[test]
+    fn test_reasoning_setting_mappings() {
+        assert_eq!(normalize_reasoning_effort("None").as_deref(), Some("off"));
+        assert_eq!(
+            normalize_reasoning_effort(" 16000 ").as_deref(),
+            Some("16000")
+        );
+        assert_eq!(normalize_reasoning_effort("100"), None);
+        assert_eq!(normalize_reasoning_effort("turbo"), None);
+
+        assert_eq!(thinking_budget_for_effort("off"), Some(0));
+        assert_eq!(thinking_budget_for_effort("16000"), Some(16_000));
+        assert_eq!(codex_reasoning_effort("off"), Some("minimal"));
+        assert_eq!(codex_reasoning_effort("20000"), Some("high"));
+        assert_eq!(
+            openrouter_reasoning("2048"),
+            Some(serde_json::json!({ "max_tokens": 2048 }))
+        );
+        assert_eq!(
+            openrouter_reasoning("low"),
+            Some(serde_json::json!({ "effort": "low" }))
+        );
+    }
+
+    #[test]
+    fn test_merge_prefers_overrides() {
+        let profile = ChatOptions {
+            temperature: Some(0.7),
+            max_tokens: Some(8000),
+            ..Default::default()
+        };
+        let agent = ChatOptions {
+            temperature: Some(0.1),
+            ..Default::default()
+        };
+        let merged = profile.merged_with(&agent);
+        assert_eq!(merged.temperature, Some(0.1));
+        assert_eq!(merged.max_tokens, Some(8000));
+    }
+
+    #[test]
+    fn test_retain_applicable_reports_dropped_options() {
+        let workspace = ChatOptions {
+            temperature: Some(0.7),
+            top_p: Some(0.9),
+            max_tokens: Some(8000),
+            reasoning_effort: Some("high".to_string()),
+        };
+        let agent = ChatOptions {
+            temperature: Some(0.7),
+            ..Default::default()
+        };
+
+        let mut claudecode = workspace.clone();
+        let dropped = claudecode.retain_applicable("claudecode", &agent);
+        assert_eq!(dropped, vec!["temperature", "top_p"]);
+        assert_eq!(claudecode.max_tokens, Some(8000));
+        assert!(claudecode.temperature.is_none());
+
+        let mut codex = workspace.clone();
+        assert_eq!(
+            codex.retain_applicable("codex", &agent),
+            vec!["temperature", "top_p", "max_tokens"]
+        );
+        assert_eq!(codex.reasoning_effort.as_deref(), Some("high"));
+
+        let mut opencode = workspace.clone();
+        assert_eq!(
+            opencode.retain_applicable("opencode", &agent),
+            vec!["top_p", "max_tokens", "reasoning_effort"]
+        );
+        assert_eq!(opencode.temperature, Some(0.7));
+
+        let mut amp = workspace;
+        assert_eq!(amp.retain_applicable("amp", &agent).len(), 4);
+        assert!(amp.is_empty());
+    }
+}
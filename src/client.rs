@@ -0,0 +1,1061 @@
+//! Typed async client for the sandboxed.sh HTTP API.
+//!
+//! Request and response bodies reuse the server's own types, so a client
+//! built from the same crate version always matches the server's JSON.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), sandboxed_sh::client::ClientError> {
+//! use futures::StreamExt;
+//! use sandboxed_sh::client::Client;
+//!
+//! let client = Client::new("http://localhost:3000")?
+//!     .login(None, "password")
+//!     .await?;
+//! let mission = client.create_mission(&Default::default()).await?;
+//! client.start_mission(mission.id, "Fix the failing tests").await?;
+//!
+//! let mut events = client.mission_events_stream(mission.id).await?;
+//! while let Some(event) = events.next().await {
+//!     let event = event?;
+//!     println!("{}: {}", event.event, event.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Endpoints without a dedicated method can be reached with [`Client::get`],
+//! [`Client::post`], [`Client::put`] and [`Client::delete`].
+
+use std::collections::HashMap;
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use reqwest::{Method, StatusCode};
+use reqwest_eventsource::{Event as SseEvent, RequestBuilderExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::control::{
+    ControlMessageRequest, ControlMessageResponse, CreateAutomationRequest, CreateMissionRequest,
+    DeleteMissionResponse, MissionStatus, ResumeMissionRequest, SetMissionStatusRequest,
+    StartParallelRequest,
+};
+use crate::api::mission_batch::{BatchStatusResponse, CreateBatchRequest, CreateBatchResponse};
+use crate::api::mission_runner::RunningMissionInfo;
+use crate::api::mission_store::{Automation, AutomationExecution, Mission, StoredEvent};
+use crate::api::mission_templates::{InstantiateTemplateRequest, InstantiateTemplateResponse};
+use crate::api::types::{HealthResponse, LoginRequest, LoginResponse};
+use crate::api::workspaces::WorkspaceResponse;
+use crate::library::{MissionTemplate, MissionTemplateSummary};
+use crate::workspace_snapshot::MissionDiff;
+
+/// Errors returned by [`Client`].
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Invalid base URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered with a non-success status; `message` is the body.
+    #[error("API error ({status}): {message}")]
+    Api { status: StatusCode, message: String },
+
+    #[error("Failed to decode response: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    #[error("Event stream error: {0}")]
+    Stream(String),
+}
+
+impl ClientError {
+    /// HTTP status for API errors.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// One event from the control SSE stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlEvent {
+    /// SSE event name (e.g. "assistant_message", "tool_call", "status").
+    pub event: String,
+    /// Mission the event belongs to, when the server tags it.
+    pub mission_id: Option<Uuid>,
+    /// Event payload as sent by the server.
+    pub data: serde_json::Value,
+}
+
+impl ControlEvent {
+    fn parse(event: String, data: &str) -> Result<Self> {
+        let data: serde_json::Value = serde_json::from_str(data)?;
+        let mission_id = data
+            .get("mission_id")
+            .and_then(|v| v.as_str())
+            .and_then(|s| Uuid::parse_str(s).ok());
+        Ok(Self {
+            event,
+            mission_id,
+            data,
+        })
+    }
+
+    /// Whether this event reports a mission reaching a terminal status.
+    pub fn is_terminal_status(&self) -> bool {
+        self.event == "mission_status_changed"
+            && self
+                .data
+                .get("status")
+                .and_then(|s| s.as_str())
+                .is_some_and(|s| !matches!(s, "pending" | "active"))
+    }
+}
+
+/// Stream of control events.
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<ControlEvent>> + Send>>;
+
+/// Filters for [`Client::mission_events`].
+#[derive(Debug, Clone, Default)]
+pub struct EventsQuery {
+    /// Event types to include (e.g. `["tool_call", "tool_result"]`).
+    pub types: Vec<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Async client for a sandboxed.sh server.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl Client {
+    /// Create a client for `base_url` (e.g. `http://localhost:3000`).
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Create a client that reuses an existing `reqwest::Client`.
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Result<Self> {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        url::Url::parse(&base_url).map_err(|e| ClientError::InvalidUrl(e.to_string()))?;
+        Ok(Self {
+            http,
+            base_url,
+            token: None,
+        })
+    }
+
+    /// Use an existing JWT for authentication.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Log in and return a client authenticated with the issued JWT.
+    pub async fn login(self, username: Option<&str>, password: &str) -> Result<Self> {
+        let response: LoginResponse = self
+            .post(
+                "/api/auth/login",
+                &LoginRequest {
+                    username: username.map(str::to_string),
+                    password: password.to_string(),
+                },
+            )
+            .await?;
+        Ok(self.with_token(response.token))
+    }
+
+    /// Exchange an SSO refresh token for a new session token. Returns the
+    /// authenticated client and the rotated refresh token.
+    pub async fn refresh_session(self, refresh_token: &str) -> Result<(Self, String)> {
+        let response: LoginResponse = self
+            .post(
+                "/api/auth/refresh",
+                &serde_json::json!({ "refresh_token": refresh_token }),
+            )
+            .await?;
+        let refresh_token = response.refresh_token.unwrap_or_default();
+        Ok((self.with_token(response.token), refresh_token))
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, builder: reqwest::RequestBuilder) -> Result<T> {
+        let response = builder.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(ClientError::Api {
+                status,
+                message: body,
+            });
+        }
+        // Some endpoints answer with a plain-text confirmation.
+        let body = if body.is_empty() { "null" } else { &body };
+        match serde_json::from_str(body) {
+            Ok(value) => Ok(value),
+            Err(e) => serde_json::from_value(serde_json::Value::String(body.to_string()))
+                .map_err(|_| ClientError::Decode(e)),
+        }
+    }
+
+    /// `GET path` and decode the JSON response.
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(self.request(Method::GET, path)).await
+    }
+
+    /// `POST path` with a JSON body.
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(self.request(Method::POST, path).json(body)).await
+    }
+
+    /// `PUT path` with a JSON body.
+    pub async fn put<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        self.send(self.request(Method::PUT, path).json(body)).await
+    }
+
+    /// `DELETE path`.
+    pub async fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(self.request(Method::DELETE, path)).await
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // System
+    // ─────────────────────────────────────────────────────────────────────────
+
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.get("/api/health").await
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Missions
+    // ─────────────────────────────────────────────────────────────────────────
+
+    pub async fn list_missions(&self) -> Result<Vec<Mission>> {
+        self.get("/api/control/missions").await
+    }
+
+    pub async fn get_mission(&self, id: Uuid) -> Result<Mission> {
+        self.get(&format!("/api/control/missions/{}", id)).await
+    }
+
+    pub async fn current_mission(&self) -> Result<Option<Mission>> {
+        self.get("/api/control/missions/current").await
+    }
+
+    /// Create a mission and make it the current mission.
+    pub async fn create_mission(&self, request: &CreateMissionRequest) -> Result<Mission> {
+        self.post("/api/control/missions", request).await
+    }
+
+    pub async fn load_mission(&self, id: Uuid) -> Result<Mission> {
+        self.post(
+            &format!("/api/control/missions/{}/load", id),
+            &serde_json::json!({}),
+        )
+        .await
+    }
+
+    pub async fn delete_mission(&self, id: Uuid) -> Result<DeleteMissionResponse> {
+        self.delete(&format!("/api/control/missions/{}", id)).await
+    }
+
+    pub async fn set_mission_status(&self, id: Uuid, status: MissionStatus) -> Result<()> {
+        let _: serde_json::Value = self
+            .post(
+                &format!("/api/control/missions/{}/status", id),
+                &SetMissionStatusRequest { status },
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_mission_title(&self, id: Uuid, title: &str) -> Result<()> {
+        let _: serde_json::Value = self
+            .post(
+                &format!("/api/control/missions/{}/title", id),
+                &serde_json::json!({ "title": title }),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Send a message to the control session (optionally targeting a mission).
+    pub async fn send_message(
+        &self,
+        request: &ControlMessageRequest,
+    ) -> Result<ControlMessageResponse> {
+        self.post("/api/control/message", request).await
+    }
+
+    /// Start a mission as a parallel mission with an initial message.
+    pub async fn start_mission(&self, id: Uuid, content: &str) -> Result<()> {
+        let _: serde_json::Value = self
+            .post(
+                &format!("/api/control/missions/{}/parallel", id),
+                &StartParallelRequest {
+                    content: content.to_string(),
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn cancel_mission(&self, id: Uuid) -> Result<()> {
+        let _: serde_json::Value = self
+            .post(
+                &format!("/api/control/missions/{}/cancel", id),
+                &serde_json::json!({}),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn resume_mission(
+        &self,
+        id: Uuid,
+        request: &ResumeMissionRequest,
+    ) -> Result<Mission> {
+        self.post(&format!("/api/control/missions/{}/resume", id), request)
+            .await
+    }
+
+    pub async fn running_missions(&self) -> Result<Vec<RunningMissionInfo>> {
+        self.get("/api/control/running").await
+    }
+
+    /// Stored events of a mission (history/replay).
+    pub async fn mission_events(&self, id: Uuid, query: &EventsQuery) -> Result<Vec<StoredEvent>> {
+        let mut params = Vec::new();
+        if !query.types.is_empty() {
+            params.push(format!(
+                "types={}",
+                urlencoding::encode(&query.types.join(","))
+            ));
+        }
+        if let Some(limit) = query.limit {
+            params.push(format!("limit={}", limit));
+        }
+        if let Some(offset) = query.offset {
+            params.push(format!("offset={}", offset));
+        }
+        let mut path = format!("/api/control/missions/{}/events", id);
+        if !params.is_empty() {
+            path = format!("{}?{}", path, params.join("&"));
+        }
+        self.get(&path).await
+    }
+
+    /// Mission diff summary (`include_patch` adds the unified patch).
+    pub async fn mission_diff(&self, id: Uuid, include_patch: bool) -> Result<MissionDiff> {
+        self.get(&format!(
+            "/api/control/missions/{}/diff?include_patch={}",
+            id, include_patch
+        ))
+        .await
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Batches and templates
+    // ─────────────────────────────────────────────────────────────────────────
+
+    pub async fn create_mission_batch(
+        &self,
+        request: &CreateBatchRequest,
+    ) -> Result<CreateBatchResponse> {
+        self.post("/api/control/missions/batch", request).await
+    }
+
+    pub async fn mission_batch(&self, id: Uuid) -> Result<BatchStatusResponse> {
+        self.get(&format!("/api/control/missions/batch/{}", id))
+            .await
+    }
+
+    pub async fn list_mission_templates(&self) -> Result<Vec<MissionTemplateSummary>> {
+        self.get("/api/library/mission-template").await
+    }
+
+    pub async fn get_mission_template(&self, name: &str) -> Result<MissionTemplate> {
+        self.get(&format!(
+            "/api/library/mission-template/{}",
+            urlencoding::encode(name)
+        ))
+        .await
+    }
+
+    pub async fn save_mission_template(
+        &self,
+        name: &str,
+        template: &MissionTemplate,
+    ) -> Result<()> {
+        let _: String = self
+            .put(
+                &format!(
+                    "/api/library/mission-template/{}",
+                    urlencoding::encode(name)
+                ),
+                template,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn instantiate_mission_template(
+        &self,
+        name: &str,
+        request: &InstantiateTemplateRequest,
+    ) -> Result<InstantiateTemplateResponse> {
+        self.post(
+            &format!(
+                "/api/control/mission-templates/{}/instantiate",
+                urlencoding::encode(name)
+            ),
+            request,
+        )
+        .await
+    }
+
+    /// Instantiate a template with only params.
+    pub async fn run_mission_template(
+        &self,
+        name: &str,
+        params: HashMap<String, String>,
+    ) -> Result<InstantiateTemplateResponse> {
+        self.instantiate_mission_template(
+            name,
+            &InstantiateTemplateRequest {
+                params,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Automations
+    // ─────────────────────────────────────────────────────────────────────────
+
+    pub async fn list_mission_automations(&self, mission_id: Uuid) -> Result<Vec<Automation>> {
+        self.get(&format!("/api/control/missions/{}/automations", mission_id))
+            .await
+    }
+
+    pub async fn create_automation(
+        &self,
+        mission_id: Uuid,
+        request: &CreateAutomationRequest,
+    ) -> Result<Automation> {
+        self.post(
+            &format!("/api/control/missions/{}/automations", mission_id),
+            request,
+        )
+        .await
+    }
+
+    pub async fn delete_automation(&self, id: Uuid) -> Result<()> {
+        self.delete(&format!("/api/control/automations/{}", id))
+            .await
+    }
+
+    pub async fn automation_executions(&self, id: Uuid) -> Result<Vec<AutomationExecution>> {
+        self.get(&format!("/api/control/automations/{}/executions", id))
+            .await
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Workspaces
+    // ─────────────────────────────────────────────────────────────────────────
+
+    pub async fn list_workspaces(&self) -> Result<Vec<WorkspaceResponse>> {
+        self.get("/api/workspaces").await
+    }
+
+    pub async fn get_workspace(&self, id: Uuid) -> Result<WorkspaceResponse> {
+        self.get(&format!("/api/workspaces/{}", id)).await
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Event streaming
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Stream all control session events. The stream ends when the server
+    /// closes the connection; it does not reconnect.
+    pub async fn events_stream(&self) -> Result<EventStream> {
+        let mut source = self
+            .request(Method::GET, "/api/control/stream")
+            .eventsource()
+            .map_err(|e| ClientError::Stream(e.to_string()))?;
+
+        let stream = async_stream::stream! {
+            while let Some(item) = source.next().await {
+                match item {
+                    Ok(SseEvent::Open) => {}
+                    Ok(SseEvent::Message(message)) => {
+                        yield ControlEvent::parse(message.event, &message.data);
+                    }
+                    Err(reqwest_eventsource::Error::StreamEnded) => break,
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(status, response)) => {
+                        let message = response.text().await.unwrap_or_default();
+                        yield Err(ClientError::Api { status, message });
+                        break;
+                    }
+                    Err(e) => {
+                        yield Err(ClientError::Stream(e.to_string()));
+                        break;
+                    }
+                }
+            }
+            source.close();
+        };
+        Ok(Box::pin(stream))
+    }
+
+    /// Stream events for one mission, ending after it reaches a terminal status.
+    pub async fn mission_events_stream(&self, mission_id: Uuid) -> Result<EventStream> {
+        let mut events = self.events_stream().await?;
+        let stream = async_stream::stream! {
+            while let Some(item) = events.next().await {
+                match item {
+                    Ok(event) if event.mission_id != Some(mission_id) => {}
+                    Ok(event) => {
+                        let done = event.is_terminal_status();
+                        yield Ok(event);
+                        if done {
+                            break;
+                        }
+                    }
+                    Err(e) => yield Err(e),
+                }
+            }
+        };
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use axum::extract::{Query, State};
+    use axum::http::HeaderMap;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+
+    use crate::api::mission_batch::BatchMissionStatus;
+    use crate::api::mission_runner::MissionHealth;
+    use crate::api::mission_store::{
+        CommandSource, ExecutionStatus, FreshSession, InMemoryMissionStore, MissionBatch,
+        MissionStore, RetryConfig, StopPolicy, TriggerType,
+    };
+    use crate::workspace_snapshot::{FileChange, FileChangeStatus};
+
+    /// Server-side values the test server answers with.
+    struct Fixtures {
+        mission: Mission,
+        automation: Automation,
+    }
+
+    type Shared = State<Arc<Fixtures>>;
+
+    fn ok() -> Json<serde_json::Value> {
+        Json(serde_json::json!({ "ok": true }))
+    }
+
+    fn workspace() -> WorkspaceResponse {
+        crate::workspace::Workspace::default_host(std::path::PathBuf::from("/tmp")).into()
+    }
+
+    fn template() -> MissionTemplate {
+        MissionTemplate {
+            name: "bump-deps".to_string(),
+            description: None,
+            path: "mission-template/bump-deps.json".to_string(),
+            prompt: "Bump <crate/>".to_string(),
+            params: Vec::new(),
+            workspace_template: None,
+            agent: None,
+            backend: None,
+            model_override: None,
+            model_effort: None,
+            config_profile: None,
+            budget_cents: None,
+            verification: Vec::new(),
+        }
+    }
+
+    fn batch_status(f: &Fixtures) -> BatchStatusResponse {
+        let status = BatchMissionStatus {
+            mission_id: f.mission.id,
+            title: f.mission.title.clone(),
+            status: "pending".to_string(),
+            terminal_reason: None,
+            cost_cents: 0,
+        };
+        BatchStatusResponse {
+            id: Uuid::nil(),
+            title: None,
+            created_at: f.mission.created_at.clone(),
+            total: 1,
+            finished: 0,
+            by_status: BTreeMap::from([("pending".to_string(), 1)]),
+            failures: Vec::new(),
+            total_cost_cents: 0,
+            missions: vec![status],
+        }
+    }
+
+    /// A local server answering every endpoint the client has a method for,
+    /// with bodies serialized from the server's own types.
+    async fn test_server() -> (String, Arc<Fixtures>) {
+        let mission = InMemoryMissionStore::new()
+            .create_mission(Some("Fix the tests"), None, None, None, None, None, None)
+            .await
+            .unwrap();
+        let automation = Automation {
+            id: Uuid::new_v4(),
+            mission_id: mission.id,
+            command_source: CommandSource::Inline {
+                content: "run the tests".to_string(),
+            },
+            trigger: TriggerType::Interval { seconds: 3600 },
+            variables: HashMap::new(),
+            active: true,
+            created_at: mission.created_at.clone(),
+            last_triggered_at: None,
+            retry_config: RetryConfig::default(),
+            stop_policy: StopPolicy::Never,
+            fresh_session: FreshSession::Keep,
+            consecutive_failures: 0,
+        };
+        let fixtures = Arc::new(Fixtures {
+            mission,
+            automation,
+        });
+
+        let app = Router::new()
+            .route(
+                "/api/health",
+                get(|| async {
+                    Json(HealthResponse {
+                        status: "ok".to_string(),
+                        version: "test".to_string(),
+                        dev_mode: false,
+                        auth_required: true,
+                        auth_mode: "single_tenant".to_string(),
+                        oidc_enabled: false,
+                        max_iterations: 50,
+                        offline_mode: false,
+                        library_remote: None,
+                    })
+                }),
+            )
+            .route(
+                "/api/auth/login",
+                post(|Json(req): Json<LoginRequest>| async move {
+                    assert_eq!(req.password, "secret");
+                    Json(LoginResponse {
+                        token: "test-token".to_string(),
+                        exp: 0,
+                        refresh_token: None,
+                    })
+                }),
+            )
+            .route(
+                "/api/auth/refresh",
+                post(|| async {
+                    Json(LoginResponse {
+                        token: "refreshed".to_string(),
+                        exp: 0,
+                        refresh_token: Some("next".to_string()),
+                    })
+                }),
+            )
+            .route(
+                "/api/control/missions",
+                get(|State(f): Shared, headers: HeaderMap| async move {
+                    assert_eq!(headers.get("authorization").unwrap(), "Bearer test-token");
+                    Json(vec![f.mission.clone()])
+                })
+                .post(|State(f): Shared| async move { Json(f.mission.clone()) }),
+            )
+            .route(
+                "/api/control/missions/current",
+                get(|State(f): Shared| async move { Json(Some(f.mission.clone())) }),
+            )
+            .route(
+                "/api/control/missions/batch",
+                post(|State(f): Shared| async move {
+                    Json(CreateBatchResponse {
+                        batch: MissionBatch {
+                            id: Uuid::nil(),
+                            title: None,
+                            mission_ids: vec![f.mission.id],
+                            created_at: f.mission.created_at.clone(),
+                        },
+                        missions: vec![f.mission.clone()],
+                    })
+                }),
+            )
+            .route(
+                "/api/control/missions/batch/:id",
+                get(|State(f): Shared| async move { Json(batch_status(&f)) }),
+            )
+            .route(
+                "/api/control/missions/:id",
+                get(|State(f): Shared| async move { Json(f.mission.clone()) }).delete(
+                    |State(f): Shared| async move {
+                        Json(DeleteMissionResponse {
+                            ok: true,
+                            deleted: f.mission.id,
+                        })
+                    },
+                ),
+            )
+            .route(
+                "/api/control/missions/:id/load",
+                post(|State(f): Shared| async move { Json(f.mission.clone()) }),
+            )
+            .route(
+                "/api/control/missions/:id/resume",
+                post(|State(f): Shared| async move { Json(f.mission.clone()) }),
+            )
+            .route("/api/control/missions/:id/status", post(|| async { ok() }))
+            .route("/api/control/missions/:id/title", post(|| async { ok() }))
+            .route(
+                "/api/control/missions/:id/parallel",
+                post(|| async { ok() }),
+            )
+            .route("/api/control/missions/:id/cancel", post(|| async { ok() }))
+            .route(
+                "/api/control/message",
+                post(|| async {
+                    Json(ControlMessageResponse {
+                        id: Uuid::nil(),
+                        queued: true,
+                    })
+                }),
+            )
+            .route(
+                "/api/control/running",
+                get(|State(f): Shared| async move {
+                    Json(vec![RunningMissionInfo {
+                        mission_id: f.mission.id,
+                        state: "running".to_string(),
+                        queue_len: 0,
+                        history_len: 2,
+                        seconds_since_activity: 1,
+                        health: MissionHealth::Healthy,
+                        expected_deliverables: 0,
+                        current_activity: None,
+                        subtask_total: 0,
+                        subtask_completed: 0,
+                    }])
+                }),
+            )
+            .route(
+                "/api/control/missions/:id/events",
+                get(
+                    |State(f): Shared, Query(q): Query<HashMap<String, String>>| async move {
+                        assert_eq!(
+                            q.get("types").map(String::as_str),
+                            Some("tool_call,tool_result")
+                        );
+                        assert_eq!(q.get("limit").map(String::as_str), Some("10"));
+                        Json(vec![StoredEvent {
+                            id: 1,
+                            mission_id: f.mission.id,
+                            sequence: 1,
+                            event_type: "tool_call".to_string(),
+                            timestamp: f.mission.created_at.clone(),
+                            event_id: None,
+                            tool_call_id: Some("call-1".to_string()),
+                            tool_name: Some("read_file".to_string()),
+                            content: String::new(),
+                            metadata: serde_json::json!({}),
+                        }])
+                    },
+                ),
+            )
+            .route(
+                "/api/control/missions/:id/diff",
+                get(|Query(q): Query<HashMap<String, String>>| async move {
+                    Json(MissionDiff {
+                        has_baseline: true,
+                        files: vec![FileChange {
+                            path: "src/lib.rs".to_string(),
+                            status: FileChangeStatus::Modified,
+                            additions: Some(3),
+                            deletions: Some(1),
+                            binary: false,
+                        }],
+                        total_additions: 3,
+                        total_deletions: 1,
+                        patch: (q.get("include_patch").map(String::as_str) == Some("true"))
+                            .then(|| "--- a/src/lib.rs".to_string()),
+                    })
+                }),
+            )
+            .route(
+                "/api/library/mission-template",
+                get(|| async {
+                    Json(vec![MissionTemplateSummary {
+                        name: "bump-deps".to_string(),
+                        description: None,
+                        path: "mission-template/bump-deps.json".to_string(),
+                        params: vec!["crate".to_string()],
+                    }])
+                }),
+            )
+            .route(
+                "/api/library/mission-template/:name",
+                get(|| async { Json(template()) })
+                    .put(|| async { (StatusCode::OK, "Mission template saved".to_string()) }),
+            )
+            .route(
+                "/api/control/mission-templates/:name/instantiate",
+                post(
+                    |State(f): Shared, Json(req): Json<InstantiateTemplateRequest>| async move {
+                        Json(InstantiateTemplateResponse {
+                            mission: f.mission.clone(),
+                            prompt: format!("Bump {}", req.params["crate"]),
+                            started: true,
+                        })
+                    },
+                ),
+            )
+            .route(
+                "/api/control/missions/:id/automations",
+                get(|State(f): Shared| async move { Json(vec![f.automation.clone()]) })
+                    .post(|State(f): Shared| async move { Json(f.automation.clone()) }),
+            )
+            .route(
+                "/api/control/automations/:id",
+                axum::routing::delete(|| async { StatusCode::NO_CONTENT }),
+            )
+            .route(
+                "/api/control/automations/:id/executions",
+                get(|State(f): Shared| async move {
+                    Json(vec![AutomationExecution {
+                        id: Uuid::new_v4(),
+                        automation_id: f.automation.id,
+                        mission_id: f.mission.id,
+                        triggered_at: f.mission.created_at.clone(),
+                        trigger_source: "interval".to_string(),
+                        status: ExecutionStatus::Success,
+                        webhook_payload: None,
+                        variables_used: HashMap::new(),
+                        completed_at: None,
+                        error: None,
+                        retry_count: 0,
+                    }])
+                }),
+            )
+            .route("/api/workspaces", get(|| async { Json(vec![workspace()]) }))
+            .route("/api/workspaces/:id", get(|| async { Json(workspace()) }))
+            .with_state(fixtures.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, fixtures)
+    }
+
+    #[tokio::test]
+    async fn methods_parse_server_responses() {
+        let (url, f) = test_server().await;
+        let id = f.mission.id;
+        let client = Client::new(&url).unwrap();
+
+        assert_eq!(client.health().await.unwrap().status, "ok");
+        let (refreshed, next) = client.clone().refresh_session("old").await.unwrap();
+        assert_eq!(
+            (refreshed.token(), next.as_str()),
+            (Some("refreshed"), "next")
+        );
+        let client = client.login(None, "secret").await.unwrap();
+        assert_eq!(client.token(), Some("test-token"));
+
+        assert_eq!(client.list_missions().await.unwrap()[0].id, id);
+        assert_eq!(client.get_mission(id).await.unwrap().id, id);
+        assert_eq!(client.current_mission().await.unwrap().unwrap().id, id);
+        let created = client.create_mission(&Default::default()).await.unwrap();
+        assert_eq!(created.title.as_deref(), Some("Fix the tests"));
+        assert_eq!(client.load_mission(id).await.unwrap().id, id);
+        assert_eq!(client.delete_mission(id).await.unwrap().deleted, id);
+        client
+            .set_mission_status(id, MissionStatus::Completed)
+            .await
+            .unwrap();
+        client.set_mission_title(id, "Renamed").await.unwrap();
+        let sent = client
+            .send_message(&ControlMessageRequest {
+                content: "hi".to_string(),
+                agent: None,
+                mission_id: Some(id),
+            })
+            .await
+            .unwrap();
+        assert!(sent.queued);
+        client.start_mission(id, "Go").await.unwrap();
+        client.cancel_mission(id).await.unwrap();
+        let resumed = client
+            .resume_mission(id, &ResumeMissionRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(resumed.id, id);
+        assert_eq!(client.running_missions().await.unwrap()[0].mission_id, id);
+
+        let events = client
+            .mission_events(
+                id,
+                &EventsQuery {
+                    types: vec!["tool_call".to_string(), "tool_result".to_string()],
+                    limit: Some(10),
+                    offset: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(events[0].tool_name.as_deref(), Some("read_file"));
+
+        let diff = client.mission_diff(id, false).await.unwrap();
+        assert_eq!(diff.files[0].status, FileChangeStatus::Modified);
+        assert_eq!(diff.total_additions, 3);
+        assert!(diff.patch.is_none());
+        assert!(client.mission_diff(id, true).await.unwrap().patch.is_some());
+
+        let batch = client
+            .create_mission_batch(&CreateBatchRequest {
+                title: None,
+                missions: Vec::new(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(batch.batch.mission_ids, vec![id]);
+        assert_eq!(client.mission_batch(Uuid::nil()).await.unwrap().total, 1);
+
+        assert_eq!(
+            client.list_mission_templates().await.unwrap()[0].params,
+            vec!["crate"]
+        );
+        let template = client.get_mission_template("bump-deps").await.unwrap();
+        assert_eq!(template.prompt, "Bump <crate/>");
+        client
+            .save_mission_template("bump-deps", &template)
+            .await
+            .unwrap();
+        let run = client
+            .run_mission_template(
+                "bump-deps",
+                HashMap::from([("crate".to_string(), "serde".to_string())]),
+            )
+            .await
+            .unwrap();
+        assert_eq!((run.prompt.as_str(), run.started), ("Bump serde", true));
+
+        let automation_id = f.automation.id;
+        assert_eq!(
+            client.list_mission_automations(id).await.unwrap()[0].id,
+            automation_id
+        );
+        let automation = client
+            .create_automation(
+                id,
+                &CreateAutomationRequest {
+                    command_source: f.automation.command_source.clone(),
+                    trigger: f.automation.trigger.clone(),
+                    variables: HashMap::new(),
+                    retry_config: None,
+                    stop_policy: None,
+                    fresh_session: None,
+                    start_immediately: false,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(automation.id, automation_id);
+        client.delete_automation(automation_id).await.unwrap();
+        let executions = client.automation_executions(automation_id).await.unwrap();
+        assert_eq!(executions[0].status, ExecutionStatus::Success);
+
+        let workspaces = client.list_workspaces().await.unwrap();
+        assert_eq!(workspaces[0].id, crate::workspace::DEFAULT_WORKSPACE_ID);
+        let workspace = client.get_workspace(workspaces[0].id).await.unwrap();
+        assert_eq!(workspace.name, workspaces[0].name);
+    }
+
+    #[tokio::test]
+    async fn api_errors_keep_status_and_body() {
+        let (url, _) = test_server().await;
+        let client = Client::new(url).unwrap();
+        let err = client
+            .get::<serde_json::Value>("/api/nothing-here")
+            .await
+            .unwrap_err();
+        assert!(err.is_not_found());
+    }
+
+    #[test]
+    fn test_control_event_parse() {
+        let id = Uuid::new_v4();
+        let event = ControlEvent::parse(
+            "mission_status_changed".to_string(),
+            &serde_json::json!({"type": "mission_status_changed", "mission_id": id, "status": "completed"})
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(event.mission_id, Some(id));
+        assert!(event.is_terminal_status());
+
+        let event = ControlEvent::parse("status".to_string(), r#"{"state":"idle"}"#).unwrap();
+        assert_eq!(event.mission_id, None);
+        assert!(!event.is_terminal_status());
+    }
+
+    #[test]
+    fn test_base_url_validation() {
+        let client = Client::new("http://localhost:3000/").unwrap();
+        assert_eq!(client.base_url, "http://localhost:3000");
+        assert!(matches!(
+            Client::new("not a url"),
+            Err(ClientError::InvalidUrl(_))
+        ));
+    }
+}
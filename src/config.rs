@@ -11,6 +11,8 @@
 //! - `OPENCODE_AGENT` - Optional. Default OpenCode agent name (e.g., `Sisyphus`, `oracle`).
 //! - `OPENCODE_PERMISSIVE` - Optional. If true, auto-allows all permissions for OpenCode sessions (default: true).
 //! - `SANDBOXED_USERS` or `SANDBOXED_SH_USERS` (legacy) - Optional. JSON array of user accounts for multi-user auth.
+//! - `OIDC_ISSUER_URL`, `OIDC_CLIENT_ID`, `OIDC_REDIRECT_URL` - Optional. Enable dashboard SSO via OpenID Connect
+//!   (Authorization Code + PKCE). See [`OidcConfig`] for the remaining `OIDC_*` variables.
 //! - `LIBRARY_GIT_SSH_KEY` - Optional. SSH key path for library git operations. If set to a path, uses that key.
 //!   If set to empty string, ignores ~/.ssh/config (useful when the config specifies a non-existent key).
 //!   If unset, uses default SSH behavior.
@@ -203,6 +205,9 @@ pub struct Config {
     /// Hours of inactivity after which an active mission is auto-closed (0 = disabled)
     pub stale_mission_hours: u64,
 
+    /// Seconds mission details are cached for status polling (0 = disabled)
+    pub mission_cache_ttl_secs: u64,
+
     /// Maximum number of missions that can run in parallel (1 = sequential only)
     pub max_parallel_missions: usize,
 
@@ -233,6 +238,9 @@ pub struct Config {
 
     /// Whether mission automations are enabled
     pub automations_enabled: bool,
+
+    /// Mission requests consumed from NATS or Redis (if set)
+    pub mission_queue: Option<MissionQueueConfig>,
 }
 
 /// API auth configuration.
@@ -249,6 +257,9 @@ pub struct AuthConfig {
 
     /// Multi-user accounts (if set, overrides dashboard_password auth).
     pub users: Vec<UserAccount>,
+
+    /// OpenID Connect SSO for the dashboard (if set, enables `/api/auth/oidc/*`).
+    pub oidc: Option<OidcConfig>,
 }
 
 impl Default for AuthConfig {
@@ -258,7 +269,214 @@ impl Default for AuthConfig {
             jwt_secret: None,
             jwt_ttl_days: 30,
             users: Vec::new(),
+            oidc: None,
+        }
+    }
+}
+
+/// Access level of an authenticated user.
+///
+/// Password and multi-user logins are always `Admin`; OIDC users get the role
+/// mapped from their identity provider groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Read-only access.
+    Viewer,
+    /// Can run and manage missions, workspaces and the library.
+    Operator,
+    /// Full access, including settings, secrets and system management.
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// OpenID Connect (Authorization Code + PKCE) configuration.
+///
+/// Environment variables:
+/// - `OIDC_ISSUER_URL` - Issuer URL; discovery is read from `/.well-known/openid-configuration`.
+/// - `OIDC_CLIENT_ID` - Client ID registered with the identity provider.
+/// - `OIDC_CLIENT_SECRET` - Optional. Omit for public clients.
+/// - `OIDC_REDIRECT_URL` - Callback URL registered with the provider
+///   (e.g. `https://agent.example.com/api/auth/oidc/callback`).
+/// - `OIDC_POST_LOGIN_REDIRECT` - Optional. Dashboard URL to return to after login. Defaults to `/`.
+/// - `OIDC_SCOPES` - Optional. Space-separated scopes. Defaults to `openid profile email`.
+/// - `OIDC_GROUPS_CLAIM` - Optional. Claim holding the user's groups. Defaults to `groups`.
+/// - `OIDC_ROLE_MAPPING` - Optional. JSON object mapping group names to roles
+///   (e.g. `{"platform-admins": "admin", "engineering": "operator"}`).
+/// - `OIDC_DEFAULT_ROLE` - Optional. Role for users matching no mapped group.
+///   If unset, such users are denied.
+/// - `OIDC_SESSION_TTL_MINUTES` - Optional. Session token lifetime. Defaults to `15`.
+/// - `OIDC_REFRESH_TTL_HOURS` - Optional. Refresh token lifetime. Defaults to `168` (7 days).
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub post_login_redirect: String,
+    pub scopes: Vec<String>,
+    pub groups_claim: String,
+    pub role_mapping: std::collections::HashMap<String, Role>,
+    pub default_role: Option<Role>,
+    pub session_ttl_minutes: i64,
+    pub refresh_ttl_hours: i64,
+}
+
+impl OidcConfig {
+    /// Highest role granted by any of `groups`, falling back to the default role.
+    pub fn role_for_groups(&self, groups: &[String]) -> Option<Role> {
+        groups
+            .iter()
+            .filter_map(|g| self.role_mapping.get(g).copied())
+            .max()
+            .or(self.default_role)
+    }
+
+    fn from_env() -> Result<Option<Self>, ConfigError> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let Some(issuer_url) = var("OIDC_ISSUER_URL") else {
+            return Ok(None);
+        };
+        let client_id = var("OIDC_CLIENT_ID")
+            .ok_or_else(|| ConfigError::MissingEnvVar("OIDC_CLIENT_ID".into()))?;
+        let redirect_url = var("OIDC_REDIRECT_URL")
+            .ok_or_else(|| ConfigError::MissingEnvVar("OIDC_REDIRECT_URL".into()))?;
+
+        let parse_role = |name: &str, raw: String| {
+            serde_json::from_value::<Role>(serde_json::Value::String(raw.to_lowercase())).map_err(
+                |_| {
+                    ConfigError::InvalidValue(
+                        name.to_string(),
+                        format!(
+                            "unknown role '{}' (expected viewer, operator or admin)",
+                            raw
+                        ),
+                    )
+                },
+            )
+        };
+
+        let role_mapping = var("OIDC_ROLE_MAPPING")
+            .map(|raw| {
+                serde_json::from_str::<std::collections::HashMap<String, String>>(&raw)
+                    .map_err(|e| {
+                        ConfigError::InvalidValue("OIDC_ROLE_MAPPING".to_string(), e.to_string())
+                    })?
+                    .into_iter()
+                    .map(|(group, role)| Ok((group, parse_role("OIDC_ROLE_MAPPING", role)?)))
+                    .collect::<Result<_, ConfigError>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let default_role = var("OIDC_DEFAULT_ROLE")
+            .map(|raw| parse_role("OIDC_DEFAULT_ROLE", raw))
+            .transpose()?;
+
+        let parse_i64 = |name: &str, default: i64| {
+            var(name)
+                .map(|v| {
+                    v.parse::<i64>()
+                        .map_err(|e| ConfigError::InvalidValue(name.to_string(), e.to_string()))
+                })
+                .transpose()
+                .map(|v| v.unwrap_or(default).max(1))
+        };
+
+        Ok(Some(Self {
+            issuer_url: issuer_url.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: var("OIDC_CLIENT_SECRET"),
+            redirect_url,
+            post_login_redirect: var("OIDC_POST_LOGIN_REDIRECT").unwrap_or_else(|| "/".to_string()),
+            scopes: var("OIDC_SCOPES")
+                .unwrap_or_else(|| "openid profile email".to_string())
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            groups_claim: var("OIDC_GROUPS_CLAIM").unwrap_or_else(|| "groups".to_string()),
+            role_mapping,
+            default_role,
+            session_ttl_minutes: parse_i64("OIDC_SESSION_TTL_MINUTES", 15)?,
+            refresh_ttl_hours: parse_i64("OIDC_REFRESH_TTL_HOURS", 168)?,
+        }))
+    }
+}
+
+/// Message queue ingestion of mission requests (see `api::mission_queue`).
+///
+/// Environment variables:
+/// - `MISSION_QUEUE_URL` - `nats://host:4222` or `redis://host:6379` (`rediss://` for TLS).
+///   Unset disables ingestion.
+/// - `MISSION_QUEUE_SUBJECT` - Optional. NATS subject or Redis stream carrying requests.
+///   Defaults to `sandboxed.missions`.
+/// - `MISSION_QUEUE_EVENTS_SUBJECT` - Optional. Subject or stream lifecycle events are
+///   published to. Defaults to `<subject>.events`.
+/// - `MISSION_QUEUE_GROUP` - Optional. NATS queue group or Redis consumer group, so several
+///   servers can share one queue. Defaults to `sandboxed-sh`.
+/// - `MISSION_QUEUE_USER` - Optional. User whose control session runs the missions.
+///   Defaults to `default` (`dev` in dev mode).
+#[derive(Debug, Clone)]
+pub struct MissionQueueConfig {
+    pub url: String,
+    pub subject: String,
+    pub events_subject: String,
+    pub group: String,
+    pub user_id: String,
+}
+
+impl MissionQueueConfig {
+    fn from_env(dev_mode: bool) -> Result<Option<Self>, ConfigError> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let Some(url) = var("MISSION_QUEUE_URL") else {
+            return Ok(None);
+        };
+        if !["nats://", "tls://", "redis://", "rediss://"]
+            .iter()
+            .any(|scheme| url.starts_with(scheme))
+        {
+            return Err(ConfigError::InvalidValue(
+                "MISSION_QUEUE_URL".to_string(),
+                "expected a nats://, tls://, redis:// or rediss:// URL".to_string(),
+            ));
         }
+        let subject =
+            var("MISSION_QUEUE_SUBJECT").unwrap_or_else(|| "sandboxed.missions".to_string());
+        Ok(Some(Self {
+            url,
+            events_subject: var("MISSION_QUEUE_EVENTS_SUBJECT")
+                .unwrap_or_else(|| format!("{}.events", subject)),
+            subject,
+            group: var("MISSION_QUEUE_GROUP").unwrap_or_else(|| "sandboxed-sh".to_string()),
+            user_id: var("MISSION_QUEUE_USER")
+                .unwrap_or_else(|| if dev_mode { "dev" } else { "default" }.to_string()),
+        }))
+    }
+
+    /// Whether the queue is a Redis stream (otherwise NATS).
+    pub fn is_redis(&self) -> bool {
+        self.url.starts_with("redis://") || self.url.starts_with("rediss://")
     }
 }
 
@@ -297,7 +515,7 @@ impl AuthConfig {
         if !self.users.is_empty() {
             return AuthMode::MultiUser;
         }
-        if self.dashboard_password.is_some() && self.jwt_secret.is_some() {
+        if (self.dashboard_password.is_some() || self.oidc.is_some()) && self.jwt_secret.is_some() {
             return AuthMode::SingleTenant;
         }
         AuthMode::Disabled
@@ -361,6 +579,14 @@ impl Config {
                 ConfigError::InvalidValue("STALE_MISSION_HOURS".to_string(), format!("{}", e))
             })?;
 
+        // Seconds `GET /missions/:id` responses are cached (default: 5, 0 = off).
+        let mission_cache_ttl_secs = std::env::var("MISSION_CACHE_TTL_SECS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .map_err(|e| {
+                ConfigError::InvalidValue("MISSION_CACHE_TTL_SECS".to_string(), format!("{}", e))
+            })?;
+
         // Maximum parallel missions (default: 1 = sequential)
         let max_parallel_missions = std::env::var("MAX_PARALLEL_MISSIONS")
             .unwrap_or_else(|_| "1".to_string())
@@ -415,6 +641,7 @@ impl Config {
                 .transpose()?
                 .unwrap_or(30),
             users,
+            oidc: OidcConfig::from_env()?,
         };
 
         // In non-dev mode, require auth secrets to be set.
@@ -441,7 +668,7 @@ impl Config {
                     }
                 }
                 AuthMode::SingleTenant => {
-                    if auth.dashboard_password.is_none() {
+                    if auth.dashboard_password.is_none() && auth.oidc.is_none() {
                         return Err(ConfigError::MissingEnvVar("DASHBOARD_PASSWORD".to_string()));
                     }
                     if auth.jwt_secret.is_none() {
@@ -450,7 +677,9 @@ impl Config {
                 }
                 AuthMode::Disabled => {
                     // Provide a more specific error message when partial config exists
-                    if auth.dashboard_password.is_some() && auth.jwt_secret.is_none() {
+                    if (auth.dashboard_password.is_some() || auth.oidc.is_some())
+                        && auth.jwt_secret.is_none()
+                    {
                         return Err(ConfigError::MissingEnvVar("JWT_SECRET".to_string()));
                     }
                     return Err(ConfigError::MissingEnvVar(
@@ -492,6 +721,8 @@ impl Config {
             .transpose()?
             .unwrap_or(true);
 
+        let mission_queue = MissionQueueConfig::from_env(dev_mode)?;
+
         Ok(Self {
             default_model,
             working_dir,
@@ -499,6 +730,7 @@ impl Config {
             port,
             max_iterations,
             stale_mission_hours,
+            mission_cache_ttl_secs,
             max_parallel_missions,
             dev_mode,
             auth,
@@ -509,6 +741,7 @@ impl Config {
             library_path,
             default_backend,
             automations_enabled,
+            mission_queue,
         })
     }
 
@@ -522,6 +755,7 @@ impl Config {
             port: 3000,
             max_iterations: 50,
             stale_mission_hours: 2,
+            mission_cache_ttl_secs: 5,
             max_parallel_missions: 1,
             dev_mode: true,
             auth: AuthConfig::default(),
@@ -532,6 +766,7 @@ impl Config {
             library_path,
             default_backend: None,
             automations_enabled: true,
+            mission_queue: None,
         }
     }
 }
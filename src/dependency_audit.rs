@@ -0,0 +1,681 @@
+//! License and provenance tracking for dependencies an agent adds.
+//!
+//! Before a mission's first turn the runner snapshots the dependencies declared
+//! in the mission directory's `Cargo.toml` and `package.json` files. After each
+//! turn (and when the agent calls `complete_mission`) the manifests are scanned
+//! again; dependencies that were not in the baseline are looked up on their
+//! registry (crates.io, npm), checked against the license allowlist and
+//! recorded in [`LEDGER_FILE`]. The runner emits a `dependency_added` event per
+//! new dependency, and `complete_mission` lists violations in its summary.
+//!
+//! Git dependencies have no registry metadata, so their license is unknown
+//! and they are reported as violations; path dependencies are local code and
+//! are recorded without a license check.
+//!
+//! Settings (workspace env vars or process env):
+//! - `SANDBOXED_SH_DEPENDENCY_AUDIT` - set to `off` to disable tracking
+//! - `SANDBOXED_SH_LICENSE_ALLOWLIST` - comma-separated SPDX ids (default:
+//!   common permissive licenses, see [`DEFAULT_LICENSE_ALLOWLIST`])
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use walkdir::WalkDir;
+
+/// Dependency ledger, relative to the mission directory.
+pub const LEDGER_FILE: &str = ".sandboxed-sh/dependencies.json";
+
+pub const AUDIT_SETTING: &str = "SANDBOXED_SH_DEPENDENCY_AUDIT";
+pub const ALLOWLIST_SETTING: &str = "SANDBOXED_SH_LICENSE_ALLOWLIST";
+
+pub const DEFAULT_LICENSE_ALLOWLIST: &str = "MIT,MIT-0,Apache-2.0,BSD-2-Clause,BSD-3-Clause,ISC,0BSD,Zlib,Unlicense,CC0-1.0,BSL-1.0,Unicode-3.0,Unicode-DFS-2016";
+
+/// Directories never searched for manifests.
+const SKIP_DIRS: &[&str] = &[
+    ".git",
+    ".sandboxed-sh",
+    "node_modules",
+    "target",
+    "vendor",
+    "dist",
+    "build",
+];
+
+const MAX_MANIFEST_DEPTH: usize = 5;
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+}
+
+impl Ecosystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Npm => "npm",
+        }
+    }
+}
+
+/// Where a dependency is fetched from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencySource {
+    Registry,
+    Git,
+    Path,
+}
+
+/// A dependency declared in a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Dependency {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    /// Version requirement or source spec as written in the manifest.
+    pub requirement: String,
+    pub source: DependencySource,
+    /// Manifest path relative to the mission directory.
+    pub manifest: String,
+}
+
+impl Dependency {
+    fn key(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.ecosystem.as_str(),
+            self.manifest,
+            self.name
+        )
+    }
+}
+
+/// A dependency added during the mission, with its license and provenance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyRecord {
+    #[serde(flatten)]
+    pub dependency: Dependency,
+    pub license: Option<String>,
+    /// Registry page the metadata came from.
+    pub registry_url: Option<String>,
+    /// Why the dependency violates the license policy, if it does.
+    pub violation: Option<String>,
+    pub recorded_at: String,
+    /// Whether the runner has emitted an event for it.
+    #[serde(default)]
+    pub reported: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Ledger {
+    baseline: BTreeSet<String>,
+    #[serde(default)]
+    records: Vec<DependencyRecord>,
+}
+
+fn is_disabled(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "0" | "false" | "no" | "off" | "disabled"
+    )
+}
+
+/// Whether tracking is enabled for the current workspace (MCP side).
+pub fn audit_enabled() -> bool {
+    !crate::tools::terminal::workspace_setting(AUDIT_SETTING).is_some_and(|v| is_disabled(&v))
+}
+
+/// Whether tracking is enabled for a workspace with these env vars (runner side).
+pub fn audit_enabled_for(env_vars: &HashMap<String, String>) -> bool {
+    !env_vars
+        .get(AUDIT_SETTING)
+        .cloned()
+        .or_else(|| std::env::var(AUDIT_SETTING).ok())
+        .is_some_and(|v| is_disabled(&v))
+}
+
+/// Allowed SPDX license ids (case-insensitive).
+#[derive(Debug, Clone)]
+pub struct LicenseAllowlist(Vec<String>);
+
+impl LicenseAllowlist {
+    pub fn parse(raw: &str) -> Self {
+        Self(
+            raw.split(',')
+                .map(|id| id.trim().to_ascii_lowercase())
+                .filter(|id| !id.is_empty())
+                .collect(),
+        )
+    }
+
+    /// Allowlist for the current workspace (MCP side).
+    pub fn from_setting() -> Self {
+        Self::parse(
+            &crate::tools::terminal::workspace_setting(ALLOWLIST_SETTING)
+                .unwrap_or_else(|| DEFAULT_LICENSE_ALLOWLIST.to_string()),
+        )
+    }
+
+    /// Allowlist for a workspace with these env vars (runner side).
+    pub fn from_env_vars(env_vars: &HashMap<String, String>) -> Self {
+        Self::parse(
+            &env_vars
+                .get(ALLOWLIST_SETTING)
+                .cloned()
+                .or_else(|| std::env::var(ALLOWLIST_SETTING).ok())
+                .unwrap_or_else(|| DEFAULT_LICENSE_ALLOWLIST.to_string()),
+        )
+    }
+
+    fn allows_id(&self, id: &str) -> bool {
+        // "GPL-2.0+" and "Apache-2.0 WITH LLVM-exception" reduce to their base id.
+        let id = id.split(" WITH ").next().unwrap_or(id).trim();
+        let id = id.trim_end_matches('+').to_ascii_lowercase();
+        self.0.contains(&id)
+    }
+
+    /// Whether an SPDX expression is satisfiable with allowed licenses:
+    /// any `OR` alternative whose `AND` terms are all allowed.
+    pub fn allows(&self, expression: &str) -> bool {
+        let normalized = expression.replace(['(', ')'], " ").replace('/', " OR ");
+        normalized.split(" OR ").any(|alternative| {
+            let terms: Vec<&str> = alternative
+                .split(" AND ")
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .collect();
+            !terms.is_empty() && terms.iter().all(|t| self.allows_id(t))
+        })
+    }
+
+    /// Policy violation for a dependency with this license, if any.
+    fn violation(&self, dependency: &Dependency, license: Option<&str>) -> Option<String> {
+        match (dependency.source, license) {
+            (DependencySource::Path, _) => None,
+            (_, None) => Some("license unknown".to_string()),
+            (_, Some(license)) if !self.allows(license) => {
+                Some(format!("license '{}' is not on the allowlist", license))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse the dependency tables of a `Cargo.toml`.
+///
+/// Handles `[dependencies]`-style tables (including dev, build, workspace and
+/// target-specific ones) and `[dependencies.<name>]` sub-tables.
+fn parse_cargo_toml(content: &str) -> Vec<(String, String, DependencySource)> {
+    fn is_dep_table(name: &str) -> bool {
+        let last = name.rsplit('.').next().unwrap_or(name);
+        matches!(
+            last,
+            "dependencies" | "dev-dependencies" | "build-dependencies"
+        )
+    }
+    fn unquote(s: &str) -> &str {
+        s.trim().trim_matches('"').trim_matches('\'')
+    }
+    fn inline_value<'a>(table: &'a str, key: &str) -> Option<&'a str> {
+        table
+            .trim()
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| k.trim() == key)
+            .map(|(_, v)| unquote(v))
+    }
+    fn classify(spec: &str) -> (String, DependencySource) {
+        if let Some(git) = inline_value(spec, "git") {
+            (git.to_string(), DependencySource::Git)
+        } else if let Some(path) = inline_value(spec, "path") {
+            (path.to_string(), DependencySource::Path)
+        } else if let Some(version) = inline_value(spec, "version") {
+            (version.to_string(), DependencySource::Registry)
+        } else if spec.trim_start().starts_with('{') {
+            // `{ workspace = true }` and friends
+            (spec.trim().to_string(), DependencySource::Path)
+        } else {
+            (unquote(spec).to_string(), DependencySource::Registry)
+        }
+    }
+
+    let mut deps = Vec::new();
+    let mut in_table = false;
+    // `[dependencies.foo]` sub-table: name and accumulated key/values.
+    let mut sub_table: Option<(String, Vec<String>)> = None;
+
+    let flush = |sub: &mut Option<(String, Vec<String>)>,
+                 deps: &mut Vec<(String, String, DependencySource)>| {
+        if let Some((name, pairs)) = sub.take() {
+            let (req, source) = classify(&format!("{{ {} }}", pairs.join(", ")));
+            deps.push((name, req, source));
+        }
+    };
+
+    for raw in content.lines() {
+        let line = raw.split(" #").next().unwrap_or(raw).trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            flush(&mut sub_table, &mut deps);
+            let header = line.trim_matches(|c| c == '[' || c == ']').trim();
+            in_table = is_dep_table(header);
+            if !in_table {
+                if let Some((table, name)) = header.rsplit_once('.') {
+                    if is_dep_table(table) {
+                        sub_table = Some((unquote(name).to_string(), Vec::new()));
+                    }
+                }
+            }
+            continue;
+        }
+        if let Some((_, pairs)) = sub_table.as_mut() {
+            pairs.push(line.to_string());
+            continue;
+        }
+        if in_table {
+            if let Some((name, spec)) = line.split_once('=') {
+                let (req, source) = classify(spec);
+                deps.push((unquote(name).to_string(), req, source));
+            }
+        }
+    }
+    flush(&mut sub_table, &mut deps);
+    deps
+}
+
+/// Parse the dependency maps of a `package.json`.
+fn parse_package_json(content: &str) -> Vec<(String, String, DependencySource)> {
+    let Ok(json) = serde_json::from_str::<Value>(content) else {
+        return Vec::new();
+    };
+    let mut deps = Vec::new();
+    for field in [
+        "dependencies",
+        "devDependencies",
+        "optionalDependencies",
+        "peerDependencies",
+    ] {
+        let Some(map) = json.get(field).and_then(|v| v.as_object()) else {
+            continue;
+        };
+        for (name, spec) in map {
+            let spec = spec.as_str().unwrap_or_default().to_string();
+            let source = if ["file:", "link:", "workspace:", "portal:"]
+                .iter()
+                .any(|p| spec.starts_with(p))
+            {
+                DependencySource::Path
+            } else if spec.starts_with("git")
+                || spec.starts_with("github:")
+                || spec.contains("://")
+                || (spec.contains('/') && !spec.starts_with('@') && !spec.starts_with("npm:"))
+            {
+                DependencySource::Git
+            } else {
+                DependencySource::Registry
+            };
+            deps.push((name.clone(), spec, source));
+        }
+    }
+    deps
+}
+
+/// Dependencies declared by the manifests under `working_dir`.
+pub fn scan_manifests(working_dir: &Path) -> Vec<Dependency> {
+    let mut deps = Vec::new();
+    let walker = WalkDir::new(working_dir)
+        .max_depth(MAX_MANIFEST_DEPTH)
+        .into_iter()
+        .filter_entry(|e| {
+            !(e.file_type().is_dir()
+                && SKIP_DIRS.contains(&e.file_name().to_string_lossy().as_ref()))
+        });
+    for entry in walker.filter_map(|e| e.ok()) {
+        let (ecosystem, parsed) = match entry.file_name().to_str() {
+            Some("Cargo.toml") => (
+                Ecosystem::Cargo,
+                std::fs::read_to_string(entry.path())
+                    .map(|c| parse_cargo_toml(&c))
+                    .unwrap_or_default(),
+            ),
+            Some("package.json") => (
+                Ecosystem::Npm,
+                std::fs::read_to_string(entry.path())
+                    .map(|c| parse_package_json(&c))
+                    .unwrap_or_default(),
+            ),
+            _ => continue,
+        };
+        let manifest = entry
+            .path()
+            .strip_prefix(working_dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .to_string();
+        deps.extend(
+            parsed
+                .into_iter()
+                .map(|(name, requirement, source)| Dependency {
+                    ecosystem,
+                    name,
+                    requirement,
+                    source,
+                    manifest: manifest.clone(),
+                }),
+        );
+    }
+    deps
+}
+
+fn load_ledger(working_dir: &Path) -> Option<Ledger> {
+    let raw = std::fs::read_to_string(working_dir.join(LEDGER_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn save_ledger(working_dir: &Path, ledger: &Ledger) -> std::io::Result<()> {
+    let path = working_dir.join(LEDGER_FILE);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(ledger).map_err(std::io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+/// Snapshot the current dependencies as the mission's baseline (first call only).
+pub fn record_baseline(working_dir: &Path) -> std::io::Result<()> {
+    if load_ledger(working_dir).is_some() {
+        return Ok(());
+    }
+    let ledger = Ledger {
+        baseline: scan_manifests(working_dir)
+            .iter()
+            .map(|d| d.key())
+            .collect(),
+        records: Vec::new(),
+    };
+    save_ledger(working_dir, &ledger)
+}
+
+/// Look up a registry dependency's license and registry page.
+async fn registry_license(
+    client: &reqwest::Client,
+    dependency: &Dependency,
+) -> (Option<String>, Option<String>) {
+    if dependency.source != DependencySource::Registry {
+        return (None, None);
+    }
+    let name = dependency.name.as_str();
+    let (api_url, page_url) = match dependency.ecosystem {
+        Ecosystem::Cargo => (
+            format!("https://crates.io/api/v1/crates/{}", name),
+            format!("https://crates.io/crates/{}", name),
+        ),
+        Ecosystem::Npm => (
+            format!(
+                "https://registry.npmjs.org/{}/latest",
+                name.replace('/', "%2F")
+            ),
+            format!("https://www.npmjs.com/package/{}", name),
+        ),
+    };
+    let response = client
+        .get(&api_url)
+        .timeout(REGISTRY_TIMEOUT)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    let json = match response {
+        Ok(r) => r.json::<Value>().await.ok(),
+        Err(e) => {
+            tracing::warn!(dependency = %name, error = %e, "Registry lookup failed");
+            None
+        }
+    };
+    let Some(json) = json else {
+        return (None, None);
+    };
+    let license = match dependency.ecosystem {
+        Ecosystem::Cargo => json["versions"][0]["license"].as_str().map(str::to_string),
+        Ecosystem::Npm => json["license"]
+            .as_str()
+            .or_else(|| json["license"]["type"].as_str())
+            .map(str::to_string),
+    };
+    (license, Some(page_url))
+}
+
+/// Record dependencies added since the baseline and return every record so far.
+///
+/// Without a baseline (the mission started before tracking), the current
+/// dependencies become the baseline and nothing is recorded.
+pub async fn audit(
+    working_dir: &Path,
+    allowlist: &LicenseAllowlist,
+) -> anyhow::Result<Vec<DependencyRecord>> {
+    let Some(mut ledger) = load_ledger(working_dir) else {
+        record_baseline(working_dir)?;
+        return Ok(Vec::new());
+    };
+
+    let known: BTreeSet<String> = ledger
+        .baseline
+        .iter()
+        .cloned()
+        .chain(ledger.records.iter().map(|r| r.dependency.key()))
+        .collect();
+    let added: Vec<Dependency> = scan_manifests(working_dir)
+        .into_iter()
+        .filter(|d| !known.contains(&d.key()))
+        .collect();
+    if added.is_empty() {
+        return Ok(ledger.records);
+    }
+
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("sandboxed-sh/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    for dependency in added {
+        let (license, registry_url) = registry_license(&client, &dependency).await;
+        let violation = allowlist.violation(&dependency, license.as_deref());
+        tracing::info!(
+            ecosystem = dependency.ecosystem.as_str(),
+            dependency = %dependency.name,
+            license = ?license,
+            violation = ?violation,
+            "Recorded agent-added dependency"
+        );
+        ledger.records.push(DependencyRecord {
+            dependency,
+            license,
+            registry_url,
+            violation,
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+            reported: false,
+        });
+    }
+    save_ledger(working_dir, &ledger)?;
+    Ok(ledger.records)
+}
+
+/// Records not yet reported as events; marks them reported.
+pub fn take_unreported(working_dir: &Path) -> Vec<DependencyRecord> {
+    let Some(mut ledger) = load_ledger(working_dir) else {
+        return Vec::new();
+    };
+    let unreported: Vec<DependencyRecord> = ledger
+        .records
+        .iter_mut()
+        .filter(|r| !r.reported)
+        .map(|r| {
+            r.reported = true;
+            r.clone()
+        })
+        .collect();
+    if !unreported.is_empty() {
+        if let Err(e) = save_ledger(working_dir, &ledger) {
+            tracing::warn!(error = %e, "Failed to update dependency ledger");
+        }
+    }
+    unreported
+}
+
+/// Human-readable list of license violations, if any.
+pub fn format_violations(records: &[DependencyRecord]) -> Option<String> {
+    let lines: Vec<String> = records
+        .iter()
+        .filter_map(|r| {
+            r.violation.as_ref().map(|v| {
+                format!(
+                    "- {} {} ({}, {}): {}",
+                    r.dependency.ecosystem.as_str(),
+                    r.dependency.name,
+                    r.dependency.requirement,
+                    r.dependency.manifest,
+                    v
+                )
+            })
+        })
+        .collect();
+    (!lines.is_empty()).then(|| {
+        format!(
+            "License violations in added dependencies:\n{}",
+            lines.join("\n")
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_toml() {
+        let deps = parse_cargo_toml(
+            r#"
+[package]
+name = "demo"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+anyhow = "1.0" # errors
+local = { path = "../local" }
+shared = { workspace = true }
+
+[dev-dependencies.mockito]
+git = "https://github.com/lipanski/mockito"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#,
+        );
+        assert_eq!(
+            deps,
+            vec![
+                ("serde".into(), "1".into(), DependencySource::Registry),
+                ("anyhow".into(), "1.0".into(), DependencySource::Registry),
+                ("local".into(), "../local".into(), DependencySource::Path),
+                (
+                    "shared".into(),
+                    "{ workspace = true }".into(),
+                    DependencySource::Path
+                ),
+                (
+                    "mockito".into(),
+                    "https://github.com/lipanski/mockito".into(),
+                    DependencySource::Git
+                ),
+                ("libc".into(), "0.2".into(), DependencySource::Registry),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_package_json() {
+        let mut deps = parse_package_json(
+            r#"{
+                "dependencies": { "react": "^18.2.0", "@scope/pkg": "1.0.0", "mine": "file:../mine" },
+                "devDependencies": { "fork": "github:user/fork", "gh": "user/repo" }
+            }"#,
+        );
+        deps.sort_by(|a, b| a.0.cmp(&b.0));
+        let sources: Vec<_> = deps.iter().map(|(n, _, s)| (n.as_str(), *s)).collect();
+        assert_eq!(
+            sources,
+            vec![
+                ("@scope/pkg", DependencySource::Registry),
+                ("fork", DependencySource::Git),
+                ("gh", DependencySource::Git),
+                ("mine", DependencySource::Path),
+                ("react", DependencySource::Registry),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_license_expressions() {
+        let allow = LicenseAllowlist::parse(DEFAULT_LICENSE_ALLOWLIST);
+        assert!(allow.allows("MIT"));
+        assert!(allow.allows("MIT OR Apache-2.0"));
+        assert!(allow.allows("MIT/Apache-2.0"));
+        assert!(allow.allows("Apache-2.0 WITH LLVM-exception"));
+        assert!(allow.allows("GPL-3.0 OR MIT"));
+        assert!(!allow.allows("GPL-3.0"));
+        assert!(!allow.allows("MIT AND GPL-3.0"));
+        assert!(!allow.allows(""));
+
+        let dep = |source| Dependency {
+            ecosystem: Ecosystem::Cargo,
+            name: "x".into(),
+            requirement: "1".into(),
+            source,
+            manifest: "Cargo.toml".into(),
+        };
+        assert!(allow
+            .violation(&dep(DependencySource::Registry), Some("MIT"))
+            .is_none());
+        assert!(allow
+            .violation(&dep(DependencySource::Registry), Some("AGPL-3.0"))
+            .is_some());
+        assert!(allow.violation(&dep(DependencySource::Git), None).is_some());
+        assert!(allow
+            .violation(&dep(DependencySource::Path), None)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_baseline_excludes_existing_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"left-pad": "1.3.0"}}"#,
+        )
+        .unwrap();
+        record_baseline(dir.path()).unwrap();
+
+        // A path dependency needs no registry lookup.
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"dependencies": {"left-pad": "1.3.0", "mine": "file:./mine"}}"#,
+        )
+        .unwrap();
+        let allow = LicenseAllowlist::parse(DEFAULT_LICENSE_ALLOWLIST);
+        let records = audit(dir.path(), &allow).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].dependency.name, "mine");
+        assert!(records[0].violation.is_none());
+
+        assert_eq!(take_unreported(dir.path()).len(), 1);
+        assert!(take_unreported(dir.path()).is_empty());
+        assert_eq!(audit(dir.path(), &allow).await.unwrap().len(), 1);
+    }
+}
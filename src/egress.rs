@@ -0,0 +1,567 @@
+//! Network egress policies for container workspaces.
+//!
+//! A workspace (usually via its template) can restrict outbound traffic to
+//! allowlisted domains and CIDRs, or deny everything except an HTTP(S) proxy.
+//! Enforcement happens inside the container's own network namespace: commands
+//! are moved onto a private veth network and an nftables table is installed
+//! before the program starts, so the host's rules are never touched.
+//!
+//! Domains are resolved on the host when the ruleset is built, since the
+//! kernel only sees addresses. The program itself runs without
+//! `CAP_NET_ADMIN` and `CAP_NET_RAW`, so it can't touch the table. Rejected
+//! connections are added to a dynamic nftables set, which the host reads
+//! from the command's network namespace (see [`watch`]); the mission runner
+//! reports new entries as security events.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::net::IpAddr;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// nftables table owned by the egress policy.
+const NFT_TABLE: &str = "sandboxed_egress";
+
+/// Egress enforcement mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EgressMode {
+    /// No restrictions (default)
+    #[default]
+    Open,
+    /// Only allowlisted domains and CIDRs (plus DNS and the proxy) are reachable
+    Allowlist,
+    /// Nothing is reachable except the proxy, if one is configured
+    DenyAll,
+}
+
+impl EgressMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Allowlist => "allowlist",
+            Self::DenyAll => "deny_all",
+        }
+    }
+}
+
+/// Outbound network policy for a workspace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EgressPolicy {
+    #[serde(default)]
+    pub mode: EgressMode,
+    /// Hostnames reachable in `allowlist` mode (resolved when commands start)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_domains: Vec<String>,
+    /// Address ranges reachable in `allowlist` mode (e.g. `10.0.0.0/8`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_cidrs: Vec<String>,
+    /// HTTP(S) proxy (`host:port`) that stays reachable in every enforced mode.
+    /// Exported to commands as `HTTP_PROXY`/`HTTPS_PROXY`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+}
+
+/// A connection rejected by the egress policy.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EgressViolation {
+    pub destination: String,
+    pub port: String,
+}
+
+/// Addresses the ruleset admits, after resolving domains and the proxy host.
+#[derive(Debug, Default)]
+struct ResolvedAllowlist {
+    v4: BTreeSet<String>,
+    v6: BTreeSet<String>,
+    proxy: BTreeSet<(IpAddr, u16)>,
+}
+
+impl EgressPolicy {
+    /// Whether the policy restricts anything.
+    pub fn is_enforced(&self) -> bool {
+        self.mode != EgressMode::Open
+    }
+
+    /// Check the policy for entries that cannot be enforced.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode == EgressMode::DenyAll
+            && (!self.allow_domains.is_empty() || !self.allow_cidrs.is_empty())
+        {
+            return Err(
+                "deny_all egress policies cannot allowlist domains or CIDRs; use mode 'allowlist'"
+                    .to_string(),
+            );
+        }
+        for domain in &self.allow_domains {
+            let valid = !domain.is_empty()
+                && domain
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+            if !valid {
+                return Err(format!(
+                    "Invalid egress domain '{}' (wildcards are not supported; route them through the proxy)",
+                    domain
+                ));
+            }
+        }
+        for cidr in &self.allow_cidrs {
+            parse_cidr(cidr).ok_or_else(|| format!("Invalid egress CIDR '{}'", cidr))?;
+        }
+        if let Some(proxy) = &self.proxy {
+            split_proxy(proxy)
+                .ok_or_else(|| format!("Invalid egress proxy '{}' (expected host:port)", proxy))?;
+        }
+        Ok(())
+    }
+
+    /// Proxy environment for commands, if a proxy is configured.
+    pub fn proxy_env(&self) -> Vec<(String, String)> {
+        let Some((host, port)) = self.proxy.as_deref().and_then(split_proxy) else {
+            return Vec::new();
+        };
+        let url = format!("http://{}:{}", host, port);
+        let mut env = Vec::new();
+        for key in ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"] {
+            env.push((key.to_string(), url.clone()));
+        }
+        for key in ["NO_PROXY", "no_proxy"] {
+            env.push((key.to_string(), "localhost,127.0.0.1,::1".to_string()));
+        }
+        env
+    }
+
+    /// Build the nftables ruleset, resolving domains and the proxy host on the host.
+    pub async fn ruleset(&self) -> String {
+        let mut resolved = ResolvedAllowlist::default();
+        if self.mode == EgressMode::Allowlist {
+            for cidr in &self.allow_cidrs {
+                if let Some((addr, prefix)) = parse_cidr(cidr) {
+                    resolved.insert(addr, Some(prefix));
+                }
+            }
+            for domain in &self.allow_domains {
+                for addr in lookup(domain, 443).await {
+                    resolved.insert(addr, None);
+                }
+            }
+        }
+        if let Some((host, port)) = self.proxy.as_deref().and_then(split_proxy) {
+            for addr in lookup(host, port).await {
+                resolved.proxy.insert((addr, port));
+            }
+        }
+        self.render(&resolved)
+    }
+
+    fn render(&self, resolved: &ResolvedAllowlist) -> String {
+        let elements = |set: &BTreeSet<String>| {
+            if set.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    " elements = {{ {} }};",
+                    set.iter().cloned().collect::<Vec<_>>().join(", ")
+                )
+            }
+        };
+        let mut rules = vec![
+            "oif \"lo\" accept".to_string(),
+            "ct state established,related accept".to_string(),
+            "udp dport 67 accept".to_string(),
+        ];
+        if self.mode == EgressMode::Allowlist {
+            rules.push("udp dport 53 accept".to_string());
+            rules.push("tcp dport 53 accept".to_string());
+            rules.push("ip daddr @allow4 accept".to_string());
+            rules.push("ip6 daddr @allow6 accept".to_string());
+        }
+        for (addr, port) in &resolved.proxy {
+            let family = if addr.is_ipv4() { "ip" } else { "ip6" };
+            rules.push(format!("{family} daddr {addr} tcp dport {port} accept"));
+        }
+        rules.push(
+            "meta nfproto ipv4 meta l4proto { tcp, udp } add @denied4 { ip daddr . th dport }"
+                .to_string(),
+        );
+        rules.push(
+            "meta nfproto ipv6 meta l4proto { tcp, udp } add @denied6 { ip6 daddr . th dport }"
+                .to_string(),
+        );
+        rules.push("reject with icmpx type admin-prohibited".to_string());
+
+        let mut out = String::new();
+        // Declaring then deleting the table makes the replacement atomic.
+        out.push_str(&format!("table inet {NFT_TABLE} {{}}\n"));
+        out.push_str(&format!("delete table inet {NFT_TABLE}\n"));
+        out.push_str(&format!("table inet {NFT_TABLE} {{\n"));
+        out.push_str(&format!(
+            "\tset allow4 {{ type ipv4_addr; flags interval; auto-merge;{} }}\n",
+            elements(&resolved.v4)
+        ));
+        out.push_str(&format!(
+            "\tset allow6 {{ type ipv6_addr; flags interval; auto-merge;{} }}\n",
+            elements(&resolved.v6)
+        ));
+        out.push_str(
+            "\tset denied4 { type ipv4_addr . inet_service; flags dynamic; size 4096; }\n",
+        );
+        out.push_str(
+            "\tset denied6 { type ipv6_addr . inet_service; flags dynamic; size 4096; }\n",
+        );
+        out.push_str("\tchain output {\n");
+        out.push_str("\t\ttype filter hook output priority 0; policy drop;\n");
+        for rule in rules {
+            out.push_str(&format!("\t\t{rule}\n"));
+        }
+        out.push_str("\t}\n}\n");
+        out
+    }
+}
+
+impl ResolvedAllowlist {
+    fn insert(&mut self, addr: IpAddr, prefix: Option<u8>) {
+        let entry = match prefix {
+            Some(prefix) => format!("{}/{}", addr, prefix),
+            None => addr.to_string(),
+        };
+        if addr.is_ipv4() {
+            self.v4.insert(entry);
+        } else {
+            self.v6.insert(entry);
+        }
+    }
+}
+
+/// Table the host adds to a command's network namespace once it holds it open.
+/// The wrapper waits for it before exiting with unread violations, so a short
+/// command's namespace doesn't vanish before the host can read it.
+const WATCHED_TABLE: &str = "sandboxed_egress_watched";
+
+/// Network namespaces of wrapped commands, held open per workspace so their
+/// rejected destinations can still be read after the command exits, plus the
+/// entries already drained from them.
+static WATCHED: LazyLock<Mutex<HashMap<PathBuf, Watched>>> = LazyLock::new(Default::default);
+
+#[derive(Default)]
+struct Watched {
+    namespaces: Vec<(u64, File)>,
+    pending: BTreeSet<EgressViolation>,
+}
+
+/// Shell snippet run by the wrapper once the program exits: if rejected
+/// destinations are waiting in the sets, give the host a moment to take hold
+/// of the namespace before it disappears.
+pub fn await_collection_script() -> String {
+    format!(
+        "if {{ nft -n list set inet {t} denied4; nft -n list set inet {t} denied6; }} 2>/dev/null | grep -q elements; then \
+         _sb_w=0; while [ $_sb_w -lt 50 ] && ! nft list table inet {w} >/dev/null 2>&1; do sleep 0.1; _sb_w=$((_sb_w+1)); done; \
+         fi; ",
+        t = NFT_TABLE,
+        w = WATCHED_TABLE,
+    )
+}
+
+/// Hold the network namespace of a wrapped command open for the workspace.
+///
+/// `launcher` is the host pid of nsenter or systemd-nspawn; the namespace is
+/// taken from the first of its descendants that left the host's. Violations
+/// are read from the host side later, so nothing the workload can write
+/// inside the container affects what gets reported.
+pub fn watch(workspace: &Path, launcher: u32) {
+    let workspace = workspace.to_path_buf();
+    std::thread::spawn(move || {
+        let Ok(host_ns) = netns_inode("self") else {
+            return;
+        };
+        for _ in 0..1500 {
+            if !process_alive(launcher) {
+                return;
+            }
+            if let Some(pid) = foreign_descendant(launcher, host_ns) {
+                pin(&workspace, &pid.to_string());
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        tracing::warn!(workspace = %workspace.display(), "Egress policy: command never left the host network namespace");
+    });
+}
+
+fn pin(workspace: &Path, pid: &str) {
+    let path = format!("/proc/{}/ns/net", pid);
+    let (Ok(file), Ok(inode)) = (File::open(&path), netns_inode(pid)) else {
+        return;
+    };
+    let ns = own_fd_path(&file);
+    if let Err(e) = nft_in(&ns, &["add", "table", "inet", WATCHED_TABLE]) {
+        tracing::warn!(workspace = %workspace.display(), error = %e, "Failed to mark egress namespace as watched");
+    }
+    let mut watched = WATCHED.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = watched.entry(workspace.to_path_buf()).or_default();
+    if !entry.namespaces.iter().any(|(i, _)| *i == inode) {
+        entry.namespaces.push((inode, file));
+    }
+}
+
+/// Drain the rejected destinations recorded in the workspace's namespaces.
+/// Called before a wrapped command replaces the ruleset, so nothing recorded
+/// by a previous command is lost. Namespaces no process uses anymore are
+/// released once read.
+pub fn collect(workspace: &Path) {
+    let mut watched = WATCHED.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = watched.get_mut(workspace) else {
+        return;
+    };
+    let in_use = namespaces_in_use();
+    let mut drained = Vec::new();
+    entry.namespaces.retain(|(inode, file)| {
+        let ns = own_fd_path(file);
+        let mut listing = String::new();
+        for set in ["denied4", "denied6"] {
+            if let Ok(out) = nft_in(&ns, &["-n", "list", "set", "inet", NFT_TABLE, set]) {
+                listing.push_str(&out);
+                let _ = nft_in(&ns, &["flush", "set", "inet", NFT_TABLE, set]);
+            }
+        }
+        drained.extend(parse_violations(&listing));
+        in_use.contains(inode)
+    });
+    entry.pending.extend(drained);
+}
+
+/// Take the workspace's rejected destinations, returning each once.
+pub fn take_violations(workspace: &Path) -> Vec<EgressViolation> {
+    collect(workspace);
+    let mut watched = WATCHED.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = watched.get_mut(workspace) else {
+        return Vec::new();
+    };
+    let violations = std::mem::take(&mut entry.pending).into_iter().collect();
+    if entry.namespaces.is_empty() {
+        watched.remove(workspace);
+    }
+    violations
+}
+
+/// Path through which child processes can open a namespace this process holds.
+fn own_fd_path(file: &File) -> String {
+    format!("/proc/{}/fd/{}", std::process::id(), file.as_raw_fd())
+}
+
+fn nft_in(netns: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = std::process::Command::new("nsenter")
+        .arg(format!("--net={}", netns))
+        .arg("nft")
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn netns_inode(pid: &str) -> std::io::Result<u64> {
+    Ok(std::fs::metadata(format!("/proc/{}/ns/net", pid))?.ino())
+}
+
+/// Parent pid and state from `/proc/<pid>/stat`.
+fn proc_stat(pid: &str) -> Option<(u32, char)> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace();
+    let state = fields.next()?.chars().next()?;
+    Some((fields.next()?.parse().ok()?, state))
+}
+
+fn process_alive(pid: u32) -> bool {
+    proc_stat(&pid.to_string()).is_some_and(|(_, state)| state != 'Z')
+}
+
+fn proc_pids() -> Vec<u32> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse().ok())
+        .collect()
+}
+
+/// A process under `root` (or `root` itself) in another network namespace.
+fn foreign_descendant(root: u32, host_ns: u64) -> Option<u32> {
+    let parents: HashMap<u32, u32> = proc_pids()
+        .into_iter()
+        .filter_map(|pid| Some((pid, proc_stat(&pid.to_string())?.0)))
+        .collect();
+    parents.keys().copied().find(|&pid| {
+        let mut cur = pid;
+        for _ in 0..64 {
+            if cur == root {
+                return netns_inode(&pid.to_string()).is_ok_and(|ns| ns != host_ns);
+            }
+            match parents.get(&cur) {
+                Some(&parent) if parent != 0 => cur = parent,
+                _ => return false,
+            }
+        }
+        false
+    })
+}
+
+fn namespaces_in_use() -> HashSet<u64> {
+    proc_pids()
+        .into_iter()
+        .filter_map(|pid| netns_inode(&pid.to_string()).ok())
+        .collect()
+}
+
+fn parse_violations(content: &str) -> Vec<EgressViolation> {
+    let mut seen = BTreeSet::new();
+    for (start, marker) in content.match_indices("elements = {") {
+        let rest = &content[start + marker.len()..];
+        let rest = rest.split('}').next().unwrap_or_default();
+        for entry in rest.split(',') {
+            let mut parts = entry.split(" . ");
+            let (Some(addr), Some(port)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let addr = addr.trim();
+            let port = port.trim();
+            if addr.parse::<IpAddr>().is_ok() && !port.is_empty() {
+                seen.insert(EgressViolation {
+                    destination: addr.to_string(),
+                    port: port.to_string(),
+                });
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+fn parse_cidr(cidr: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix) = match cidr.split_once('/') {
+        Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, prefix.parse::<u8>().ok()?),
+        None => {
+            let addr = cidr.parse::<IpAddr>().ok()?;
+            (addr, if addr.is_ipv4() { 32 } else { 128 })
+        }
+    };
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    (prefix <= max).then_some((addr, prefix))
+}
+
+fn split_proxy(proxy: &str) -> Option<(&str, u16)> {
+    let proxy = proxy
+        .trim()
+        .trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .trim_end_matches('/');
+    let (host, port) = proxy.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return None;
+    }
+    Some((host, port.parse().ok()?))
+}
+
+async fn lookup(host: &str, port: u16) -> Vec<IpAddr> {
+    if let Ok(addr) = host.parse::<IpAddr>() {
+        return vec![addr];
+    }
+    match tokio::net::lookup_host((host, port)).await {
+        Ok(addrs) => addrs.map(|a| a.ip()).collect(),
+        Err(e) => {
+            tracing::warn!(host = %host, error = %e, "Failed to resolve egress allowlist entry");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mode: EgressMode) -> EgressPolicy {
+        EgressPolicy {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unenforceable_entries() {
+        let mut p = policy(EgressMode::Allowlist);
+        p.allow_domains = vec!["*.github.com".to_string()];
+        assert!(p.validate().is_err());
+
+        let mut p = policy(EgressMode::Allowlist);
+        p.allow_cidrs = vec!["10.0.0.0/33".to_string()];
+        assert!(p.validate().is_err());
+
+        let mut p = policy(EgressMode::DenyAll);
+        p.allow_cidrs = vec!["10.0.0.0/8".to_string()];
+        assert!(p.validate().is_err());
+
+        let mut p = policy(EgressMode::DenyAll);
+        p.proxy = Some("http://proxy.internal:3128".to_string());
+        assert!(p.validate().is_ok());
+        assert!(p.proxy_env().contains(&(
+            "HTTPS_PROXY".to_string(),
+            "http://proxy.internal:3128".to_string()
+        )));
+    }
+
+    #[tokio::test]
+    async fn allowlist_ruleset_admits_cidrs_dns_and_proxy() {
+        let mut p = policy(EgressMode::Allowlist);
+        p.allow_cidrs = vec!["10.0.0.0/8".to_string(), "2001:db8::/32".to_string()];
+        p.allow_domains = vec!["192.0.2.7".to_string()];
+        p.proxy = Some("198.51.100.1:3128".to_string());
+        let rules = p.ruleset().await;
+        assert!(rules.contains("elements = { 10.0.0.0/8, 192.0.2.7 };"));
+        assert!(rules.contains("elements = { 2001:db8::/32 };"));
+        assert!(rules.contains("udp dport 53 accept"));
+        assert!(rules.contains("ip daddr 198.51.100.1 tcp dport 3128 accept"));
+        assert!(rules
+            .trim_end()
+            .ends_with("reject with icmpx type admin-prohibited\n\t}\n}"));
+    }
+
+    #[tokio::test]
+    async fn deny_all_ruleset_blocks_dns() {
+        let rules = policy(EgressMode::DenyAll).ruleset().await;
+        assert!(!rules.contains("dport 53"));
+        assert!(!rules.contains("@allow4 accept"));
+        assert!(rules.contains("add @denied4 { ip daddr . th dport }"));
+    }
+
+    #[test]
+    fn parses_and_dedupes_violations() {
+        let log = "1700000000 table inet sandboxed_egress { set denied4 { type ipv4_addr . inet_service size 4096 flags dynamic elements = { 203.0.113.5 . 443, 203.0.113.9 . 80 } } } \n\
+                   1700000100 table inet sandboxed_egress { set denied6 { elements = { 2001:db8::1 . 443 } } set denied4 { elements = { 203.0.113.5 . 443 } } }\n";
+        let violations = parse_violations(log);
+        assert_eq!(violations.len(), 3);
+        assert_eq!(violations[0].destination, "2001:db8::1");
+        assert!(violations.contains(&EgressViolation {
+            destination: "203.0.113.5".to_string(),
+            port: "443".to_string(),
+        }));
+    }
+
+    #[test]
+    fn finds_only_descendants_outside_the_host_namespace() {
+        let me = std::process::id();
+        let (parent, state) = proc_stat(&me.to_string()).expect("own stat");
+        assert_eq!(parent, std::os::unix::process::parent_id());
+        assert_ne!(state, 'Z');
+        // Everything under this test shares its network namespace.
+        let host_ns = netns_inode("self").expect("own namespace");
+        assert_eq!(foreign_descendant(me, host_ns), None);
+        assert!(namespaces_in_use().contains(&host_ns));
+    }
+}
@@ -0,0 +1,272 @@
+//! Feature flags for rolling out executor changes gradually.
+//!
+//! Each flag gates one executor behavior and has a built-in default. The
+//! global settings give flags a rollout percentage:
+//!
+//! ```json
+//! { "feature_flags": { "tool_pruning": 25, "session_rotation": 100 } }
+//! ```
+//!
+//! A mission falls in a stable bucket (0-99) per flag, derived from its ID and
+//! the flag name, and gets the flag when the bucket is below the percentage,
+//! so raising a rollout only adds missions. Flags without a percentage use
+//! their default. A mission can override flags when it is created.
+//!
+//! The resolved flag set is recorded on the mission when it is created, so
+//! later turns keep the same behavior while the rollout changes, and
+//! regressions can be correlated with the flags (`GET /api/control/feature-flags`
+//! lists each flag with its rollout and mission outcomes).
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+/// A flag gating an executor behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FeatureFlag {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Used when the settings give no rollout percentage
+    pub default: bool,
+}
+
+/// Claude Code sessions restart every 50 turns from a summary of the history,
+/// compressing the context.
+pub const SESSION_ROTATION: &str = "session_rotation";
+
+/// The workspace MCP lists only the tools relevant to each turn (as with the
+/// `SANDBOXED_SH_TOOL_PRUNING` workspace setting).
+pub const TOOL_PRUNING: &str = "tool_pruning";
+
+pub const FLAGS: &[FeatureFlag] = &[
+    FeatureFlag {
+        name: SESSION_ROTATION,
+        description: "Restart Claude Code sessions every 50 turns from a summary of the history",
+        default: true,
+    },
+    FeatureFlag {
+        name: TOOL_PRUNING,
+        description: "List only the tools relevant to each turn in the workspace MCP",
+        default: false,
+    },
+];
+
+/// Resolved flags of a mission (flag name → enabled).
+pub type FlagSet = BTreeMap<String, bool>;
+
+fn flag(name: &str) -> Option<&'static FeatureFlag> {
+    FLAGS.iter().find(|f| f.name == name)
+}
+
+fn check_names<'a>(names: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+    for name in names {
+        if flag(name).is_none() {
+            return Err(format!(
+                "unknown feature flag '{}' (known: {})",
+                name,
+                FLAGS.iter().map(|f| f.name).collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate the rollout percentages from the settings.
+pub fn validate_rollouts(rollouts: &BTreeMap<String, u8>) -> Result<(), String> {
+    check_names(rollouts.keys())?;
+    match rollouts.iter().find(|(_, percent)| **percent > 100) {
+        Some((name, _)) => Err(format!("{}: rollout must be 0-100", name)),
+        None => Ok(()),
+    }
+}
+
+/// Validate per-mission overrides.
+pub fn validate_overrides(overrides: &FlagSet) -> Result<(), String> {
+    check_names(overrides.keys())
+}
+
+/// Stable rollout bucket (0-99) of a mission for a flag (FNV-1a).
+fn bucket(mission_id: Uuid, flag: &str) -> u8 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in mission_id.as_bytes().iter().chain(flag.as_bytes()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    (hash % 100) as u8
+}
+
+/// Resolve every known flag for a mission: overrides, then rollouts, then
+/// defaults.
+pub fn resolve(mission_id: Uuid, rollouts: &BTreeMap<String, u8>, overrides: &FlagSet) -> FlagSet {
+    FLAGS
+        .iter()
+        .map(|f| {
+            let enabled = match (overrides.get(f.name), rollouts.get(f.name)) {
+                (Some(enabled), _) => *enabled,
+                (None, Some(percent)) => bucket(mission_id, f.name) < *percent,
+                (None, None) => f.default,
+            };
+            (f.name.to_string(), enabled)
+        })
+        .collect()
+}
+
+/// Flags of a new mission under the current rollouts (before overrides).
+pub fn for_new_mission(mission_id: Uuid) -> FlagSet {
+    resolve(
+        mission_id,
+        &crate::settings::feature_flag_rollouts_cached(),
+        &FlagSet::new(),
+    )
+}
+
+/// Whether a mission has a flag. Missions recorded before the flag existed
+/// keep the flag's default.
+pub fn is_enabled(flags: &FlagSet, name: &str) -> bool {
+    flags
+        .get(name)
+        .copied()
+        .unwrap_or_else(|| flag(name).is_some_and(|f| f.default))
+}
+
+/// Mission outcomes for one value of a flag.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Outcomes {
+    pub missions: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// A flag with its rollout and the outcomes of missions with and without it.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlagSummary {
+    #[serde(flatten)]
+    pub flag: FeatureFlag,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollout: Option<u8>,
+    pub enabled: Outcomes,
+    pub disabled: Outcomes,
+}
+
+/// Summarize every flag over missions given as (flags, status).
+pub fn summarize<'a>(
+    rollouts: &BTreeMap<String, u8>,
+    missions: impl IntoIterator<Item = (&'a FlagSet, &'a str)>,
+) -> Vec<FlagSummary> {
+    let mut summaries: Vec<FlagSummary> = FLAGS
+        .iter()
+        .map(|f| FlagSummary {
+            flag: *f,
+            rollout: rollouts.get(f.name).copied(),
+            enabled: Outcomes::default(),
+            disabled: Outcomes::default(),
+        })
+        .collect();
+    for (flags, status) in missions {
+        for summary in &mut summaries {
+            let outcomes = if is_enabled(flags, summary.flag.name) {
+                &mut summary.enabled
+            } else {
+                &mut summary.disabled
+            };
+            outcomes.missions += 1;
+            match status {
+                "completed" => outcomes.completed += 1,
+                "failed" => outcomes.failed += 1,
+                _ => {}
+            }
+        }
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_overrides_rollouts_and_defaults() {
+        let id = Uuid::new_v4();
+        let flags = resolve(id, &BTreeMap::new(), &FlagSet::new());
+        assert_eq!(flags.len(), FLAGS.len());
+        assert!(flags[SESSION_ROTATION]);
+        assert!(!flags[TOOL_PRUNING]);
+
+        let rollouts = BTreeMap::from([
+            (SESSION_ROTATION.to_string(), 0),
+            (TOOL_PRUNING.to_string(), 100),
+        ]);
+        let flags = resolve(id, &rollouts, &FlagSet::new());
+        assert!(!flags[SESSION_ROTATION]);
+        assert!(flags[TOOL_PRUNING]);
+
+        let overrides = FlagSet::from([(TOOL_PRUNING.to_string(), false)]);
+        assert!(!resolve(id, &rollouts, &overrides)[TOOL_PRUNING]);
+
+        // Flags recorded before a flag existed fall back to its default
+        assert!(is_enabled(&FlagSet::new(), SESSION_ROTATION));
+        assert!(!is_enabled(&FlagSet::new(), "unknown"));
+    }
+
+    #[test]
+    fn partial_rollout_is_stable_and_monotonic() {
+        let ids: Vec<Uuid> = (0..1000).map(|_| Uuid::new_v4()).collect();
+        let enabled_at = |percent: u8| -> Vec<bool> {
+            let rollouts = BTreeMap::from([(TOOL_PRUNING.to_string(), percent)]);
+            ids.iter()
+                .map(|id| resolve(*id, &rollouts, &FlagSet::new())[TOOL_PRUNING])
+                .collect()
+        };
+        let quarter = enabled_at(25);
+        assert_eq!(quarter, enabled_at(25));
+        let count = quarter.iter().filter(|e| **e).count();
+        assert!((150..350).contains(&count), "{}", count);
+        // Raising the rollout keeps every mission that already had the flag
+        let half = enabled_at(50);
+        assert!(quarter.iter().zip(&half).all(|(q, h)| !q || *h));
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_bad_percentages() {
+        let unknown = BTreeMap::from([("parallel_tools".to_string(), 10)]);
+        assert!(validate_rollouts(&unknown)
+            .unwrap_err()
+            .contains("unknown feature flag"));
+        let too_high = BTreeMap::from([(TOOL_PRUNING.to_string(), 101)]);
+        assert!(validate_rollouts(&too_high).is_err());
+        assert!(
+            validate_overrides(&FlagSet::from([(SESSION_ROTATION.to_string(), false)])).is_ok()
+        );
+    }
+
+    #[test]
+    fn summarizes_outcomes_by_flag_value() {
+        let pruned = FlagSet::from([(TOOL_PRUNING.to_string(), true)]);
+        let unpruned = FlagSet::from([(TOOL_PRUNING.to_string(), false)]);
+        let missions = [
+            (&pruned, "failed"),
+            (&pruned, "completed"),
+            (&unpruned, "completed"),
+            (&unpruned, "active"),
+        ];
+        let rollouts = BTreeMap::from([(TOOL_PRUNING.to_string(), 10)]);
+        let summary = summarize(&rollouts, missions);
+        let pruning = summary
+            .iter()
+            .find(|s| s.flag.name == TOOL_PRUNING)
+            .unwrap();
+        assert_eq!(pruning.rollout, Some(10));
+        assert_eq!((pruning.enabled.completed, pruning.enabled.failed), (1, 1));
+        assert_eq!(
+            (pruning.disabled.missions, pruning.disabled.completed),
+            (2, 1)
+        );
+        // Not recorded on these missions: all counted under the default
+        let rotation = summary
+            .iter()
+            .find(|s| s.flag.name == SESSION_ROTATION)
+            .unwrap();
+        assert_eq!(rotation.enabled.missions, 4);
+    }
+}
@@ -0,0 +1,490 @@
+//! NVIDIA GPU passthrough for container workspaces.
+//!
+//! A template requests GPUs with `gpu` (`{}` for all host GPUs, `{"count": 1}`
+//! or `{"devices": [0, 2]}`). Container commands then get the selected
+//! `/dev/nvidiaN` nodes plus the control devices, and the host driver's
+//! user-space files bind-mounted read-only: libraries under
+//! [`GUEST_LIB_DIR`] (added to `LD_LIBRARY_PATH`) and tools such as
+//! `nvidia-smi` under `/usr/local/bin`. The CUDA toolkit itself comes from the
+//! template (e.g. a `pip install torch` init script); only the driver has to
+//! match the host.
+//!
+//! The driver file list comes from nvidia-container-toolkit
+//! (`nvidia-container-cli list`) when installed, otherwise from scanning the
+//! host library directories.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+/// Where driver libraries are mounted inside the container.
+pub const GUEST_LIB_DIR: &str = "/usr/local/nvidia/lib64";
+const GUEST_BIN_DIR: &str = "/usr/local/bin";
+
+/// Device nodes every CUDA process needs besides the GPUs themselves.
+const CONTROL_DEVICES: &[&str] = &[
+    "/dev/nvidiactl",
+    "/dev/nvidia-uvm",
+    "/dev/nvidia-uvm-tools",
+    "/dev/nvidia-modeset",
+];
+
+/// Library name prefixes of the NVIDIA user-space driver.
+const DRIVER_LIBRARY_PREFIXES: &[&str] = &[
+    "libcuda.so",
+    "libcudadebugger.so",
+    "libnvidia-",
+    "libnvcuvid.so",
+    "libnvoptix.so",
+];
+
+const DRIVER_BINARIES: &[&str] = &[
+    "nvidia-smi",
+    "nvidia-debugdump",
+    "nvidia-cuda-mps-control",
+    "nvidia-cuda-mps-server",
+];
+
+const HOST_LIBRARY_DIRS: &[&str] = &[
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib/aarch64-linux-gnu",
+    "/usr/lib64",
+    "/usr/lib",
+];
+
+/// GPUs requested by a workspace template (`gpu`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuRequest {
+    /// Number of GPUs; all host GPUs when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    /// Specific GPUs by device index (`/dev/nvidiaN`); overrides `count`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<u32>,
+}
+
+impl GpuRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.count == Some(0) {
+            return Err("gpu.count must be at least 1".to_string());
+        }
+        let unique: BTreeSet<_> = self.devices.iter().collect();
+        if unique.len() != self.devices.len() {
+            return Err("gpu.devices contains duplicates".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Where the driver file list came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriverSource {
+    ContainerToolkit,
+    HostLibraries,
+}
+
+/// Resolved passthrough for one workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuPassthrough {
+    pub gpus: Vec<u32>,
+    pub devices: Vec<PathBuf>,
+    /// Read-only driver files as (host path, container path)
+    pub files: Vec<(PathBuf, String)>,
+    pub source: DriverSource,
+}
+
+fn gpu_index(file_name: &str) -> Option<u32> {
+    file_name.strip_prefix("nvidia")?.parse().ok()
+}
+
+/// GPU device indices present on the host.
+pub fn host_gpus() -> Vec<u32> {
+    let mut gpus: Vec<u32> = std::fs::read_dir("/dev")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| gpu_index(&entry.file_name().to_string_lossy()))
+        .collect();
+    gpus.sort_unstable();
+    gpus
+}
+
+fn select_gpus(available: &[u32], request: &GpuRequest) -> Result<Vec<u32>, String> {
+    if available.is_empty() {
+        return Err("no NVIDIA GPUs found on the host (/dev/nvidia*)".to_string());
+    }
+    if !request.devices.is_empty() {
+        let missing: Vec<String> = request
+            .devices
+            .iter()
+            .filter(|d| !available.contains(d))
+            .map(|d| d.to_string())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "GPU {} not present on the host (available: {:?})",
+                missing.join(", "),
+                available
+            ));
+        }
+        return Ok(request.devices.clone());
+    }
+    match request.count {
+        Some(count) if count as usize > available.len() => Err(format!(
+            "{} GPU(s) requested but the host has {}",
+            count,
+            available.len()
+        )),
+        Some(count) => Ok(available[..count as usize].to_vec()),
+        None => Ok(available.to_vec()),
+    }
+}
+
+fn is_driver_library(name: &str) -> bool {
+    DRIVER_LIBRARY_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        && name.contains(".so")
+}
+
+/// Container paths for driver files: libraries into [`GUEST_LIB_DIR`],
+/// executables into `/usr/local/bin`, keeping file names (including soname
+/// symlinks, which bind mounts resolve).
+fn driver_mounts(libraries: &[PathBuf], binaries: &[PathBuf]) -> Vec<(PathBuf, String)> {
+    let mut seen = BTreeSet::new();
+    let mut mounts = Vec::new();
+    for (paths, dir) in [(libraries, GUEST_LIB_DIR), (binaries, GUEST_BIN_DIR)] {
+        for path in paths {
+            let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+                continue;
+            };
+            let target = format!("{}/{}", dir, name);
+            if seen.insert(target.clone()) {
+                mounts.push((path.clone(), target));
+            }
+        }
+    }
+    mounts
+}
+
+/// `path` plus the symlinks next to it that point at it (`libcuda.so.1` ->
+/// `libcuda.so.535.104.05`), so sonames resolve in the container.
+fn with_symlinks(path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![path.to_path_buf()];
+    let (Some(dir), Ok(target)) = (path.parent(), path.canonicalize()) else {
+        return paths;
+    };
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let candidate = entry.path();
+        if candidate == path || !entry.file_type().is_ok_and(|t| t.is_symlink()) {
+            continue;
+        }
+        if candidate.canonicalize().is_ok_and(|c| c == target) {
+            paths.push(candidate);
+        }
+    }
+    paths
+}
+
+fn toolkit_files() -> Option<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let output = std::process::Command::new("nvidia-container-cli")
+        .args(["list", "--libraries", "--binaries"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut libraries = Vec::new();
+    let mut binaries = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let path = PathBuf::from(line.trim());
+        if !path.is_absolute() {
+            continue;
+        }
+        if path.to_string_lossy().contains(".so") {
+            libraries.extend(with_symlinks(&path));
+        } else {
+            binaries.push(path);
+        }
+    }
+    Some((libraries, binaries))
+}
+
+fn scanned_files() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut libraries = Vec::new();
+    for dir in HOST_LIBRARY_DIRS {
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            if is_driver_library(&entry.file_name().to_string_lossy()) {
+                libraries.push(entry.path());
+            }
+        }
+        if !libraries.is_empty() {
+            // Stop at the first directory holding the driver so 32-bit or
+            // stale copies elsewhere don't shadow it.
+            break;
+        }
+    }
+    libraries.sort();
+    let binaries = DRIVER_BINARIES
+        .iter()
+        .filter_map(|name| crate::host::find_command(name))
+        .collect();
+    (libraries, binaries)
+}
+
+/// Host driver files, looked up once per process.
+fn driver_files() -> &'static (Vec<(PathBuf, String)>, DriverSource) {
+    static FILES: OnceLock<(Vec<(PathBuf, String)>, DriverSource)> = OnceLock::new();
+    FILES.get_or_init(|| {
+        let (libraries, binaries, source) = match toolkit_files() {
+            Some((libraries, binaries)) => (libraries, binaries, DriverSource::ContainerToolkit),
+            None => {
+                let (libraries, binaries) = scanned_files();
+                (libraries, binaries, DriverSource::HostLibraries)
+            }
+        };
+        let mounts = driver_mounts(&libraries, &binaries);
+        tracing::info!(
+            files = mounts.len(),
+            source = ?source,
+            "Resolved NVIDIA driver files for GPU passthrough"
+        );
+        (mounts, source)
+    })
+}
+
+impl GpuPassthrough {
+    /// Resolve `request` against the host's GPUs and driver.
+    pub fn resolve(request: &GpuRequest) -> Result<Self, String> {
+        let gpus = select_gpus(&host_gpus(), request)?;
+        let (files, source) = driver_files();
+        if files.is_empty() {
+            return Err("NVIDIA driver libraries not found on the host".to_string());
+        }
+        let devices = gpus
+            .iter()
+            .map(|i| PathBuf::from(format!("/dev/nvidia{}", i)))
+            .chain(
+                CONTROL_DEVICES
+                    .iter()
+                    .map(PathBuf::from)
+                    .filter(|p| p.exists()),
+            )
+            .collect();
+        Ok(Self {
+            gpus,
+            devices,
+            files: files.clone(),
+            source: *source,
+        })
+    }
+
+    /// Mount arguments in systemd-nspawn syntax. Device nodes also need a
+    /// `DeviceAllow=` property for the container's cgroup.
+    pub fn nspawn_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for device in &self.devices {
+            args.push(format!("--bind={}", device.display()));
+            args.push(format!("--property=DeviceAllow={} rwm", device.display()));
+        }
+        for (host, container) in &self.files {
+            args.push(format!("--bind-ro={}:{}", host.display(), container));
+        }
+        args
+    }
+
+    /// Environment for processes using the GPUs.
+    pub fn apply_env(&self, env: &mut HashMap<String, String>) {
+        let lib_path = match env.get("LD_LIBRARY_PATH").filter(|v| !v.is_empty()) {
+            Some(existing) if existing.split(':').any(|p| p == GUEST_LIB_DIR) => existing.clone(),
+            Some(existing) => format!("{}:{}", GUEST_LIB_DIR, existing),
+            None => GUEST_LIB_DIR.to_string(),
+        };
+        env.insert("LD_LIBRARY_PATH".to_string(), lib_path);
+        env.entry("NVIDIA_VISIBLE_DEVICES".to_string())
+            .or_insert_with(|| {
+                self.gpus
+                    .iter()
+                    .map(|g| g.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            });
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Detection (detect_environment)
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GpuDevice {
+    pub index: u32,
+    pub name: String,
+    pub memory: String,
+}
+
+/// GPUs and CUDA versions visible to the current process.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GpuReport {
+    pub driver_version: Option<String>,
+    /// Highest CUDA version the driver supports
+    pub cuda_driver_version: Option<String>,
+    /// CUDA toolkit version (`nvcc`)
+    pub cuda_toolkit_version: Option<String>,
+    pub devices: Vec<GpuDevice>,
+}
+
+/// Parse `nvidia-smi --query-gpu=index,name,driver_version,memory.total
+/// --format=csv,noheader` output.
+fn parse_smi_query(output: &str) -> (Option<String>, Vec<GpuDevice>) {
+    let mut driver = None;
+    let mut devices = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [index, name, driver_version, memory] = fields[..] else {
+            continue;
+        };
+        let Ok(index) = index.parse() else {
+            continue;
+        };
+        driver.get_or_insert_with(|| driver_version.to_string());
+        devices.push(GpuDevice {
+            index,
+            name: name.to_string(),
+            memory: memory.to_string(),
+        });
+    }
+    (driver, devices)
+}
+
+/// Version following `marker` in `text` (e.g. `CUDA Version: 12.4`).
+fn version_after(text: &str, marker: &str) -> Option<String> {
+    let rest = &text[text.find(marker)? + marker.len()..];
+    let version: String = rest
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    (!version.is_empty()).then_some(version)
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Probe GPUs with `nvidia-smi` and the CUDA toolkit with `nvcc`.
+pub fn probe() -> GpuReport {
+    let mut report = GpuReport::default();
+    if let Some(output) = command_output(
+        "nvidia-smi",
+        &[
+            "--query-gpu=index,name,driver_version,memory.total",
+            "--format=csv,noheader",
+        ],
+    ) {
+        let (driver, devices) = parse_smi_query(&output);
+        report.driver_version = driver;
+        report.devices = devices;
+        report.cuda_driver_version = command_output("nvidia-smi", &[])
+            .and_then(|header| version_after(&header, "CUDA Version:"));
+    }
+    let nvcc = crate::host::find_command("nvcc")
+        .or_else(|| Some(PathBuf::from("/usr/local/cuda/bin/nvcc")).filter(|p| p.is_file()));
+    if let Some(nvcc) = nvcc {
+        report.cuda_toolkit_version = command_output(&nvcc.to_string_lossy(), &["--version"])
+            .and_then(|out| version_after(&out, "release"));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_requested_gpus() {
+        let available = [0, 1, 2, 3];
+        assert_eq!(
+            select_gpus(&available, &GpuRequest::default()).unwrap(),
+            vec![0, 1, 2, 3]
+        );
+        let two = GpuRequest {
+            count: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(select_gpus(&available, &two).unwrap(), vec![0, 1]);
+        let explicit = GpuRequest {
+            devices: vec![3, 1],
+            ..Default::default()
+        };
+        assert_eq!(select_gpus(&available, &explicit).unwrap(), vec![3, 1]);
+        let too_many = GpuRequest {
+            count: Some(8),
+            ..Default::default()
+        };
+        assert!(select_gpus(&available, &too_many).is_err());
+        assert!(select_gpus(&[], &GpuRequest::default()).is_err());
+        assert_eq!(gpu_index("nvidia0"), Some(0));
+        assert_eq!(gpu_index("nvidiactl"), None);
+    }
+
+    #[test]
+    fn builds_mounts_and_env() {
+        assert!(is_driver_library("libnvidia-ml.so.535.104.05"));
+        assert!(is_driver_library("libcuda.so.1"));
+        assert!(!is_driver_library("libcudart.so.12"));
+
+        let passthrough = GpuPassthrough {
+            gpus: vec![1],
+            devices: vec![PathBuf::from("/dev/nvidia1")],
+            files: driver_mounts(
+                &[PathBuf::from("/usr/lib/x86_64-linux-gnu/libcuda.so.1")],
+                &[PathBuf::from("/usr/bin/nvidia-smi")],
+            ),
+            source: DriverSource::HostLibraries,
+        };
+        assert_eq!(
+            passthrough.nspawn_args(),
+            vec![
+                "--bind=/dev/nvidia1",
+                "--property=DeviceAllow=/dev/nvidia1 rwm",
+                "--bind-ro=/usr/lib/x86_64-linux-gnu/libcuda.so.1:/usr/local/nvidia/lib64/libcuda.so.1",
+                "--bind-ro=/usr/bin/nvidia-smi:/usr/local/bin/nvidia-smi",
+            ]
+        );
+
+        let mut env = HashMap::from([("LD_LIBRARY_PATH".to_string(), "/opt/lib".to_string())]);
+        passthrough.apply_env(&mut env);
+        assert_eq!(env["LD_LIBRARY_PATH"], "/usr/local/nvidia/lib64:/opt/lib");
+        assert_eq!(env["NVIDIA_VISIBLE_DEVICES"], "1");
+    }
+
+    #[test]
+    fn parses_driver_and_cuda_versions() {
+        let (driver, devices) = parse_smi_query(
+            "0, NVIDIA A100-SXM4-40GB, 535.104.05, 40960 MiB\n1, NVIDIA A100-SXM4-40GB, 535.104.05, 40960 MiB\n",
+        );
+        assert_eq!(driver.as_deref(), Some("535.104.05"));
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[1].memory, "40960 MiB");
+
+        let header =
+            "| NVIDIA-SMI 535.104.05   Driver Version: 535.104.05   CUDA Version: 12.2     |";
+        assert_eq!(
+            version_after(header, "CUDA Version:").as_deref(),
+            Some("12.2")
+        );
+        let nvcc = "Cuda compilation tools, release 12.4, V12.4.131";
+        assert_eq!(version_after(nvcc, "release").as_deref(), Some("12.4"));
+    }
+}
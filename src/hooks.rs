@@ -0,0 +1,510 @@
+//! Lifecycle hooks around missions and tool calls.
+//!
+//! Hooks are scripts in the library `hook/` directory, configured by
+//! `hook/hooks.json`:
+//!
+//! ```json
+//! {
+//!   "hooks": [
+//!     { "event": "pre_tool", "script": "guard.sh", "tools": ["run_command", "git_*"],
+//!       "timeout_secs": 10, "on_failure": "block" },
+//!     { "event": "post_mission", "script": "notify.py" }
+//!   ]
+//! }
+//! ```
+//!
+//! The mission runner fires `pre_mission`/`post_mission` around each mission
+//! turn and workspace-mcp fires `pre_tool`/`post_tool` around each tool call,
+//! so hooks apply to every backend. Both run the library copy of the hooks
+//! (workspace-mcp fetches it from the server, see [`fetch`]), never the files
+//! synced into [`HOOKS_DIR`], which the agent could rewrite to drop its own
+//! vetoes.
+//!
+//! Each script runs in the mission directory with a JSON context on stdin
+//! (`event`, `mission_id`, plus `tool`/`args`/`result` or `user_message`/
+//! `output` depending on the event). It reports back through its exit code
+//! and stdout:
+//! - exit 0 with `{"decision": "block", "reason": "..."}` or exit 2 (reason on
+//!   stderr) vetoes the mission turn or tool call (`pre_*` events only)
+//! - exit 0 with `{"annotation": "..."}` or plain text attaches a note to the
+//!   event (shown in the mission timeline, or appended to the tool result)
+//! - any other exit code, a timeout or a missing script is a hook failure,
+//!   handled by the hook's `on_failure` policy: `ignore` (default) or `block`
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::library::types::LibraryHookFile;
+use crate::tool_pruning::pattern_matches;
+use crate::tools::terminal::run_workspace_shell_with_stdin;
+
+/// Where library hooks are synced for post-processors, relative to the
+/// mission directory.
+pub const HOOKS_DIR: &str = ".sandboxed-sh/hooks";
+
+/// Hook configuration file inside [`HOOKS_DIR`].
+pub const HOOKS_CONFIG_FILE: &str = "hooks.json";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Exit code a hook uses to veto the event.
+const BLOCK_EXIT_CODE: i32 = 2;
+
+/// Runs the script passed in `SANDBOXED_SH_HOOK_SCRIPT` from a private
+/// temporary file, so nothing in the mission directory is executed.
+const RUN_SCRIPT: &str = r#"f=$(mktemp) && printf '%s' "$SANDBOXED_SH_HOOK_SCRIPT" > "$f" && chmod 700 "$f" && "$f"; code=$?; rm -f "$f"; exit $code"#;
+
+/// Maximum length of a hook's reason or annotation.
+const MAX_MESSAGE_CHARS: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    PreMission,
+    PostMission,
+    PreTool,
+    PostTool,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::PreMission => "pre_mission",
+            HookEvent::PostMission => "post_mission",
+            HookEvent::PreTool => "pre_tool",
+            HookEvent::PostTool => "post_tool",
+        }
+    }
+
+    /// Whether hooks for this event can veto it.
+    pub fn can_block(&self) -> bool {
+        matches!(self, HookEvent::PreMission | HookEvent::PreTool)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FailurePolicy {
+    #[default]
+    Ignore,
+    Block,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HookSpec {
+    event: HookEvent,
+    script: String,
+    /// Tool name patterns (`*` suffix wildcard) for tool events; empty = all.
+    #[serde(default)]
+    tools: Vec<String>,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    #[serde(default)]
+    on_failure: FailurePolicy,
+}
+
+impl HookSpec {
+    fn applies_to(&self, event: HookEvent, tool: Option<&str>) -> bool {
+        if self.event != event {
+            return false;
+        }
+        match tool {
+            Some(name) if !self.tools.is_empty() => {
+                self.tools.iter().any(|p| pattern_matches(p, name))
+            }
+            _ => true,
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.timeout_secs
+                .filter(|secs| *secs > 0)
+                .unwrap_or(DEFAULT_TIMEOUT_SECS),
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HooksConfig {
+    #[serde(default)]
+    hooks: Vec<HookSpec>,
+}
+
+/// The library's hooks: `hooks.json` and the scripts it names.
+#[derive(Debug, Default)]
+pub struct Hooks {
+    config: HooksConfig,
+    scripts: HashMap<String, String>,
+}
+
+impl Hooks {
+    pub fn from_files(files: Vec<LibraryHookFile>) -> Self {
+        let mut hooks = Hooks::default();
+        for file in files {
+            if file.name == HOOKS_CONFIG_FILE {
+                hooks.config = serde_json::from_str(&file.content).unwrap_or_else(|e| {
+                    tracing::warn!(error = %e, "Invalid hooks config");
+                    HooksConfig::default()
+                });
+            } else {
+                hooks.scripts.insert(file.name, file.content);
+            }
+        }
+        hooks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.config.hooks.is_empty()
+    }
+}
+
+/// The library's hooks as served to workspace-mcp (`api::mission_guard`).
+/// Empty outside a mission; an error when the server cannot answer.
+pub async fn fetch() -> anyhow::Result<Hooks> {
+    let files = crate::tools::guard::fetch::<Vec<LibraryHookFile>>("hooks").await?;
+    Ok(Hooks::from_files(files.unwrap_or_default()))
+}
+
+/// A note a hook attached to an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookAnnotation {
+    pub script: String,
+    pub text: String,
+}
+
+/// Combined result of the hooks fired for one event.
+#[derive(Debug, Default)]
+pub struct HookOutcome {
+    /// Reason the event was vetoed, if a hook blocked it.
+    pub blocked: Option<String>,
+    pub annotations: Vec<HookAnnotation>,
+}
+
+/// Structured stdout of a hook.
+#[derive(Debug, Deserialize)]
+struct HookResponse {
+    #[serde(default)]
+    decision: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    annotation: Option<String>,
+}
+
+enum HookVerdict {
+    Allow(Option<String>),
+    Block(String),
+    Failed(String),
+}
+
+fn truncate(s: &str) -> String {
+    let s = s.trim();
+    let idx = crate::tools::safe_truncate_index(s, MAX_MESSAGE_CHARS);
+    s[..idx].to_string()
+}
+
+/// Interpret a hook's exit code and output.
+fn verdict(code: Option<i32>, stdout: &str, stderr: &str) -> HookVerdict {
+    match code {
+        Some(0) => {
+            let stdout = stdout.trim();
+            match serde_json::from_str::<HookResponse>(stdout) {
+                Ok(response) if response.decision.as_deref() == Some("block") => {
+                    HookVerdict::Block(truncate(
+                        response.reason.as_deref().unwrap_or("Blocked by hook"),
+                    ))
+                }
+                Ok(response) => HookVerdict::Allow(
+                    response
+                        .annotation
+                        .map(|a| truncate(&a))
+                        .filter(|a| !a.is_empty()),
+                ),
+                Err(_) if stdout.is_empty() => HookVerdict::Allow(None),
+                Err(_) => HookVerdict::Allow(Some(truncate(stdout))),
+            }
+        }
+        Some(BLOCK_EXIT_CODE) => {
+            let reason = truncate(stderr);
+            HookVerdict::Block(if reason.is_empty() {
+                "Blocked by hook".to_string()
+            } else {
+                reason
+            })
+        }
+        code => HookVerdict::Failed(format!(
+            "exited with {}: {}",
+            code.map(|c| c.to_string())
+                .unwrap_or_else(|| "signal".to_string()),
+            truncate(stderr)
+        )),
+    }
+}
+
+pub(crate) fn valid_script_name(script: &str) -> bool {
+    !script.is_empty()
+        && script != ".."
+        && script != "."
+        && !script.contains('/')
+        && !script.contains('\\')
+}
+
+async fn run_hook(
+    working_dir: &Path,
+    script: Option<&String>,
+    spec: &HookSpec,
+    input: &str,
+) -> HookVerdict {
+    if !valid_script_name(&spec.script) {
+        return HookVerdict::Failed(format!("invalid script name '{}'", spec.script));
+    }
+    let Some(script) = script else {
+        return HookVerdict::Failed(format!("script '{}' is not in the library", spec.script));
+    };
+    let mut env = HashMap::new();
+    env.insert(
+        "SANDBOXED_SH_HOOK_EVENT".to_string(),
+        spec.event.as_str().to_string(),
+    );
+    env.insert("SANDBOXED_SH_HOOK_SCRIPT".to_string(), script.clone());
+    match run_workspace_shell_with_stdin(
+        working_dir,
+        RUN_SCRIPT,
+        env,
+        Some(input.to_string()),
+        spec.timeout(),
+    )
+    .await
+    {
+        Ok(output) => verdict(
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+        ),
+        Err(e) => HookVerdict::Failed(e.to_string()),
+    }
+}
+
+/// Fire the `hooks` configured for `event` in `working_dir`.
+///
+/// `context` is merged into the JSON sent to each hook. Hooks run in config
+/// order; the first veto stops the remaining hooks.
+pub async fn run_hooks(
+    hooks: &Hooks,
+    working_dir: &Path,
+    event: HookEvent,
+    tool: Option<&str>,
+    context: Value,
+) -> HookOutcome {
+    let mut outcome = HookOutcome::default();
+    let specs: Vec<&HookSpec> = hooks
+        .config
+        .hooks
+        .iter()
+        .filter(|spec| spec.applies_to(event, tool))
+        .collect();
+    if specs.is_empty() {
+        return outcome;
+    }
+
+    let mut input = match context {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    input.insert("event".to_string(), Value::from(event.as_str()));
+    let input = Value::Object(input).to_string();
+
+    for spec in specs {
+        let script = hooks.scripts.get(&spec.script);
+        let (blocked, annotation) = match run_hook(working_dir, script, spec, &input).await {
+            HookVerdict::Allow(annotation) => (None, annotation),
+            HookVerdict::Block(reason) => (Some(reason), None),
+            HookVerdict::Failed(error) => {
+                tracing::warn!(
+                    event = event.as_str(),
+                    script = %spec.script,
+                    error = %error,
+                    "Hook failed"
+                );
+                match spec.on_failure {
+                    FailurePolicy::Ignore => (None, None),
+                    FailurePolicy::Block => (
+                        Some(format!("Hook {} failed: {}", spec.script, error)),
+                        None,
+                    ),
+                }
+            }
+        };
+
+        if let Some(reason) = blocked {
+            if event.can_block() {
+                tracing::info!(
+                    event = event.as_str(),
+                    script = %spec.script,
+                    tool = ?tool,
+                    reason = %reason,
+                    "Hook blocked event"
+                );
+                outcome.blocked = Some(reason);
+                return outcome;
+            }
+            // Post-event hooks cannot undo what happened; keep the reason as a note.
+            outcome.annotations.push(HookAnnotation {
+                script: spec.script.clone(),
+                text: reason,
+            });
+        }
+        if let Some(text) = annotation {
+            outcome.annotations.push(HookAnnotation {
+                script: spec.script.clone(),
+                text,
+            });
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verdict() {
+        assert!(matches!(verdict(Some(0), "", ""), HookVerdict::Allow(None)));
+        assert!(matches!(
+            verdict(Some(0), "looks fine\n", ""),
+            HookVerdict::Allow(Some(ref a)) if a == "looks fine"
+        ));
+        assert!(matches!(
+            verdict(Some(0), r#"{"decision":"block","reason":"no rm -rf"}"#, ""),
+            HookVerdict::Block(ref r) if r == "no rm -rf"
+        ));
+        assert!(matches!(
+            verdict(Some(0), r#"{"annotation":"touched 3 files"}"#, ""),
+            HookVerdict::Allow(Some(ref a)) if a == "touched 3 files"
+        ));
+        assert!(matches!(
+            verdict(Some(2), "", "denied\n"),
+            HookVerdict::Block(ref r) if r == "denied"
+        ));
+        assert!(matches!(
+            verdict(Some(1), "", "boom"),
+            HookVerdict::Failed(_)
+        ));
+        assert!(matches!(verdict(None, "", ""), HookVerdict::Failed(_)));
+    }
+
+    #[test]
+    fn test_config_matching() {
+        let config: HooksConfig = serde_json::from_str(
+            r#"{"hooks": [
+                {"event": "pre_tool", "script": "guard.sh", "tools": ["git_*", "run_command"]},
+                {"event": "post_tool", "script": "log.sh", "on_failure": "block"},
+                {"event": "pre_mission", "script": "start.sh", "timeout_secs": 5}
+            ]}"#,
+        )
+        .unwrap();
+        let guard = &config.hooks[0];
+        assert!(guard.applies_to(HookEvent::PreTool, Some("git_push")));
+        assert!(guard.applies_to(HookEvent::PreTool, Some("run_command")));
+        assert!(!guard.applies_to(HookEvent::PreTool, Some("read_file")));
+        assert!(!guard.applies_to(HookEvent::PostTool, Some("git_push")));
+        assert!(config.hooks[1].applies_to(HookEvent::PostTool, Some("read_file")));
+        assert_eq!(config.hooks[1].on_failure, FailurePolicy::Block);
+        assert_eq!(config.hooks[2].timeout(), Duration::from_secs(5));
+        assert_eq!(config.hooks[0].timeout(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_script_names() {
+        assert!(valid_script_name("guard.sh"));
+        assert!(!valid_script_name("../guard.sh"));
+        assert!(!valid_script_name(".."));
+        assert!(!valid_script_name(""));
+    }
+
+    #[tokio::test]
+    async fn test_run_hooks_block_and_annotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, content: &str| LibraryHookFile {
+            name: name.to_string(),
+            content: content.to_string(),
+        };
+        let hooks = Hooks::from_files(vec![
+            file(
+                HOOKS_CONFIG_FILE,
+                r#"{"hooks": [
+                    {"event": "pre_tool", "script": "note.sh"},
+                    {"event": "pre_tool", "script": "guard.sh", "tools": ["run_command"]},
+                    {"event": "post_tool", "script": "guard.sh"}
+                ]}"#,
+            ),
+            file(
+                "note.sh",
+                "#!/bin/sh\necho \"saw $SANDBOXED_SH_HOOK_EVENT\"\n",
+            ),
+            file(
+                "guard.sh",
+                "#!/bin/sh\ngrep -q 'rm -rf' && { echo 'destructive command' >&2; exit 2; }\nexit 0\n",
+            ),
+        ]);
+        // A copy rewritten in the mission directory is not what runs
+        let synced = dir.path().join(HOOKS_DIR);
+        std::fs::create_dir_all(&synced).unwrap();
+        std::fs::write(synced.join("guard.sh"), "#!/bin/sh\nexit 0\n").unwrap();
+
+        let ctx = serde_json::json!({ "tool": "run_command", "args": { "command": "rm -rf /" } });
+        let outcome = run_hooks(
+            &hooks,
+            dir.path(),
+            HookEvent::PreTool,
+            Some("run_command"),
+            ctx.clone(),
+        )
+        .await;
+        assert_eq!(outcome.blocked.as_deref(), Some("destructive command"));
+        assert_eq!(outcome.annotations[0].text, "saw pre_tool");
+
+        let outcome = run_hooks(
+            &hooks,
+            dir.path(),
+            HookEvent::PreTool,
+            Some("read_file"),
+            ctx.clone(),
+        )
+        .await;
+        assert!(outcome.blocked.is_none());
+
+        // Post-event vetoes become annotations.
+        let outcome = run_hooks(
+            &hooks,
+            dir.path(),
+            HookEvent::PostTool,
+            Some("run_command"),
+            ctx,
+        )
+        .await;
+        assert!(outcome.blocked.is_none());
+        assert_eq!(outcome.annotations[0].text, "destructive command");
+
+        // Scripts missing from the library are hook failures
+        let missing = Hooks::from_files(vec![file(
+            HOOKS_CONFIG_FILE,
+            r#"{"hooks": [{"event": "pre_tool", "script": "gone.sh", "on_failure": "block"}]}"#,
+        )]);
+        let outcome = run_hooks(
+            &missing,
+            dir.path(),
+            HookEvent::PreTool,
+            Some("read_file"),
+            serde_json::json!({}),
+        )
+        .await;
+        assert!(outcome.blocked.unwrap().contains("not in the library"));
+    }
+}
@@ -0,0 +1,325 @@
+//! Host platform abstraction.
+//!
+//! sandboxed.sh is developed on Linux, where container workspaces use
+//! systemd-nspawn (or bubblewrap) and the desktop tools drive Xvfb + i3. On
+//! macOS and Windows the server runs in "host mode": container workspaces fall
+//! back to host execution, desktop tools are unavailable and commands run
+//! through the platform shell. This module answers "what can this host do" in one place so
+//! tools degrade with a clear message and `GET /api/system/capabilities` can
+//! report it.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::bwrap::{self, ContainerBackend};
+use crate::microvm::{self, Hypervisor};
+use crate::nspawn;
+
+/// Operating system family of the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Linux,
+    Macos,
+    Windows,
+    Other,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "linux") {
+            Platform::Linux
+        } else if cfg!(target_os = "macos") {
+            Platform::Macos
+        } else if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else {
+            Platform::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Platform::Linux => "Linux",
+            Platform::Macos => "macOS",
+            Platform::Windows => "Windows",
+            Platform::Other => "this platform",
+        }
+    }
+}
+
+/// Executable name candidates for `cmd` (adds `PATHEXT` extensions on Windows).
+fn executable_names(cmd: &str) -> Vec<String> {
+    if !cfg!(windows) || Path::new(cmd).extension().is_some() {
+        return vec![cmd.to_string()];
+    }
+    let exts = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string());
+    exts.split(';')
+        .filter(|e| !e.is_empty())
+        .map(|e| format!("{}{}", cmd, e.to_lowercase()))
+        .collect()
+}
+
+/// Full path of `cmd` if it is an executable on `PATH` (or a path to a file).
+pub fn find_command(cmd: &str) -> Option<PathBuf> {
+    if cmd.contains('/') || cmd.contains(std::path::MAIN_SEPARATOR) {
+        let path = PathBuf::from(cmd);
+        return path.is_file().then_some(path);
+    }
+    let path_var = std::env::var_os("PATH")?;
+    let names = executable_names(cmd);
+    std::env::split_paths(&path_var)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether `cmd` is available on `PATH`.
+pub fn command_on_path(cmd: &str) -> bool {
+    find_command(cmd).is_some()
+}
+
+/// Program and argument used to run a command string through a shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCommand {
+    pub program: String,
+    /// Flag that introduces the command string (`-c`, `/C`, `-Command`)
+    pub arg: &'static str,
+}
+
+/// Shell for running command strings on Windows. A requested shell is
+/// honored when it is one we know how to invoke; otherwise `cmd` is used.
+pub fn windows_shell(requested: Option<&str>) -> ShellCommand {
+    let requested = requested.map(str::trim).filter(|s| !s.is_empty());
+    if let Some(shell) = requested {
+        // Split on both separators so Windows paths parse on any host.
+        let file = shell.rsplit(['/', '\\']).next().unwrap_or(shell);
+        let name = file
+            .rsplit_once('.')
+            .map_or(file, |(stem, _)| stem)
+            .to_lowercase();
+        let arg = match name.as_str() {
+            "pwsh" | "powershell" => Some("-Command"),
+            "bash" | "sh" | "zsh" => Some("-c"),
+            "cmd" => Some("/C"),
+            _ => None,
+        };
+        if let Some(arg) = arg {
+            return ShellCommand {
+                program: shell.to_string(),
+                arg,
+            };
+        }
+    }
+    ShellCommand {
+        program: "cmd".to_string(),
+        arg: "/C",
+    }
+}
+
+/// A feature that is unavailable or reduced on this host.
+#[derive(Debug, Clone, Serialize)]
+pub struct Degraded {
+    pub feature: &'static str,
+    pub reason: String,
+}
+
+/// What this host supports, as reported by `GET /api/system/capabilities`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HostCapabilities {
+    pub platform: Platform,
+    pub arch: &'static str,
+    /// Container workspaces run isolated (systemd-nspawn or bubblewrap)
+    pub container_isolation: bool,
+    /// Isolation backend in use (`systemd-nspawn` or `bwrap`)
+    pub container_backend: Option<&'static str>,
+    /// Container workspaces run on the host instead when isolation is unavailable
+    pub container_fallback: bool,
+    /// Hypervisors available for microVM workspace templates
+    pub microvm_hypervisors: Vec<Hypervisor>,
+    /// NVIDIA GPU indices available for passthrough
+    pub gpus: Vec<u32>,
+    /// Mission subprocesses can be killed as a process group
+    pub process_groups: bool,
+    /// Desktop tools (Xvfb + i3) can run
+    pub desktop: bool,
+    /// Shell used for `run_command` without an explicit `shell`
+    pub shell: String,
+    /// External programs used by the tool layer and whether they were found
+    pub commands: BTreeMap<&'static str, bool>,
+    pub degraded: Vec<Degraded>,
+}
+
+/// External programs the tools use, with what breaks without them.
+const TOOL_COMMANDS: &[(&str, &str)] = &[
+    (
+        "git",
+        "git tools and workspace repositories are unavailable",
+    ),
+    ("rg", "grep_search falls back to grep or a built-in search"),
+    ("grep", "grep_search uses ripgrep or a built-in search"),
+    (
+        "curl",
+        "init scripts and downloads that shell out to curl fail",
+    ),
+];
+
+/// Probe the host.
+pub fn capabilities() -> HostCapabilities {
+    let platform = Platform::current();
+    let backend = bwrap::container_backend();
+    let container_isolation = backend.is_some();
+    let container_fallback = nspawn::allow_container_fallback();
+    let process_groups = cfg!(unix);
+    let desktop = platform == Platform::Linux && command_on_path("Xvfb") && command_on_path("i3");
+    let shell = if platform == Platform::Windows {
+        windows_shell(None).program
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    };
+
+    let mut degraded = Vec::new();
+    if backend == Some(ContainerBackend::Bwrap) {
+        degraded.push(Degraded {
+            feature: "container_networking",
+            reason: "containers run under bubblewrap: isolated networks are loopback-only, \
+                     Tailscale and egress policies need systemd-nspawn"
+                .to_string(),
+        });
+    }
+    if !container_isolation {
+        let reason = if platform != Platform::Linux {
+            format!(
+                "systemd-nspawn and bubblewrap are Linux-only; not available on {}",
+                platform.label()
+            )
+        } else {
+            "neither systemd-nspawn nor bubblewrap is installed".to_string()
+        };
+        degraded.push(Degraded {
+            feature: "container_workspaces",
+            reason: if container_fallback {
+                format!(
+                    "{}; container workspaces run on the host without isolation",
+                    reason
+                )
+            } else {
+                format!("{}; container workspaces cannot run", reason)
+            },
+        });
+    }
+    if !process_groups {
+        degraded.push(Degraded {
+            feature: "process_groups",
+            reason: "cancelling a mission only stops the backend process, not its children"
+                .to_string(),
+        });
+    }
+    if !desktop {
+        degraded.push(Degraded {
+            feature: "desktop",
+            reason: if platform == Platform::Linux {
+                "Xvfb or i3 is not installed".to_string()
+            } else {
+                format!(
+                    "desktop tools need X11 (Xvfb + i3), which {} does not provide",
+                    platform.label()
+                )
+            },
+        });
+    }
+    let mut commands = BTreeMap::new();
+    for (cmd, impact) in TOOL_COMMANDS {
+        let found = command_on_path(cmd);
+        if !found {
+            degraded.push(Degraded {
+                feature: cmd,
+                reason: format!("{} not found: {}", cmd, impact),
+            });
+        }
+        commands.insert(*cmd, found);
+    }
+
+    HostCapabilities {
+        platform,
+        arch: std::env::consts::ARCH,
+        container_isolation,
+        container_backend: backend.map(|b| b.as_str()),
+        container_fallback,
+        microvm_hypervisors: microvm::available_hypervisors(),
+        gpus: crate::gpu::host_gpus(),
+        process_groups,
+        desktop,
+        shell,
+        commands,
+        degraded,
+    }
+}
+
+/// Log reduced features once at startup.
+pub fn log_capabilities() {
+    let caps = capabilities();
+    for item in &caps.degraded {
+        tracing::info!(
+            platform = caps.platform.label(),
+            feature = item.feature,
+            "Host feature degraded: {}",
+            item.reason
+        );
+    }
+}
+
+/// Error for tools that cannot work on this host.
+pub fn unsupported(feature: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} is not supported on {} (see GET /api/system/capabilities)",
+        feature,
+        Platform::current().label()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_commands_on_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = if cfg!(windows) { "probe.exe" } else { "probe" };
+        std::fs::write(dir.path().join(name), "").unwrap();
+        let found = find_command(&dir.path().join(name).to_string_lossy());
+        assert_eq!(found, Some(dir.path().join(name)));
+        assert!(find_command("definitely-not-a-real-command-4712").is_none());
+    }
+
+    #[test]
+    fn windows_shell_selection() {
+        assert_eq!(windows_shell(None).program, "cmd");
+        assert_eq!(windows_shell(Some("pwsh")).arg, "-Command");
+        assert_eq!(
+            windows_shell(Some(r"C:\Program Files\Git\bin\bash.exe")).arg,
+            "-c"
+        );
+        // Unknown shells fall back to cmd rather than guessing their syntax.
+        assert_eq!(windows_shell(Some("fish")).program, "cmd");
+    }
+
+    #[test]
+    fn reports_degraded_features() {
+        let caps = capabilities();
+        assert_eq!(caps.platform, Platform::current());
+        assert_eq!(
+            caps.container_isolation,
+            bwrap::container_backend().is_some()
+        );
+        if !caps.container_isolation {
+            assert!(caps
+                .degraded
+                .iter()
+                .any(|d| d.feature == "container_workspaces"));
+        }
+        assert_eq!(caps.commands.len(), TOOL_COMMANDS.len());
+    }
+}
@@ -0,0 +1,393 @@
+//! Per-fragment status reporting for workspace init scripts.
+//!
+//! [`crate::library::LibraryStore::assemble_init_script`] frames every section
+//! of the assembled script (library fragments, skill setup commands, the
+//! custom script) with marker lines. While the script runs, its output is fed
+//! through a [`FragmentTracker`], which attributes output, duration and exit
+//! status to each section. The resulting [`InitScriptReport`] is stored on the
+//! workspace and served by `GET /api/workspaces/:id/build-log`.
+
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the marker lines printed around each section.
+pub const MARKER: &str = "@@sandboxed-init";
+
+/// Bytes of output kept per fragment. The tail is kept since errors are
+/// usually reported last.
+const MAX_FRAGMENT_OUTPUT: usize = 16 * 1024;
+
+/// Directory inside the container holding stamps of applied non-idempotent
+/// fragments.
+pub const STAMP_DIR: &str = "/var/lib/sandboxed-init";
+
+/// Section kinds used in markers.
+pub const KIND_FRAGMENT: &str = "fragment";
+pub const KIND_SKILL_SETUP: &str = "skill-setup";
+pub const KIND_CUSTOM: &str = "custom";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FragmentStatus {
+    Succeeded,
+    Failed,
+    /// The script stopped while this section was running (e.g. `exit` or a
+    /// killed container); the script's exit code is attributed to it.
+    Interrupted,
+    /// The fragment was missing from the library and left out of the script.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FragmentResult {
+    /// `fragment`, `skill-setup` or `custom`
+    pub kind: String,
+    pub name: String,
+    pub status: FragmentStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Combined stdout/stderr (tail)
+    #[serde(default)]
+    pub output: String,
+}
+
+/// Outcome of one init script run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitScriptReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub fragments: Vec<FragmentResult>,
+}
+
+impl InitScriptReport {
+    /// Sections that failed or were interrupted, as `kind:name`.
+    pub fn failed_fragments(&self) -> Vec<String> {
+        self.fragments
+            .iter()
+            .filter(|f| {
+                matches!(
+                    f.status,
+                    FragmentStatus::Failed | FragmentStatus::Interrupted
+                )
+            })
+            .map(|f| format!("{}:{}", f.kind, f.name))
+            .collect()
+    }
+}
+
+/// Quote a string for a POSIX shell single-quoted context.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Shell preamble for an assembled script: merge stderr into stdout so the
+/// output of each section stays in order.
+pub fn script_preamble() -> &'static str {
+    "exec 2>&1\n__sandboxed_rc=0\n"
+}
+
+/// Shell epilogue: exit with the status of the last section, as the script
+/// did before sections were framed.
+pub fn script_epilogue() -> &'static str {
+    "\nexit $__sandboxed_rc\n"
+}
+
+/// Frame `body` as one section of the init script. The leading `:` keeps the
+/// group valid when the body has no commands.
+pub fn section(kind: &str, name: &str, body: &str) -> String {
+    let label = shell_quote(&format!("{} {}", kind, name));
+    format!(
+        "echo {marker}' start '{label}\n{{\n:\n{body}\n}}\n__sandboxed_rc=$?\necho {marker}\" end $__sandboxed_rc \"{label}\n",
+        marker = shell_quote(MARKER),
+    )
+}
+
+/// Guard a non-idempotent fragment body with a stamp file under
+/// [`STAMP_DIR`]: the body runs until it succeeds once, then is skipped.
+pub fn run_once(name: &str, body: &str) -> String {
+    let stamp = shell_quote(&format!("{}/{}.done", STAMP_DIR, name));
+    format!(
+        "if [ -e {stamp} ]; then\necho {skip}\nelse\n{{\n:\n{body}\n}} && mkdir -p {dir} && touch {stamp}\nfi",
+        skip = shell_quote(&format!("[sandboxed] {} already applied, skipping", name)),
+        dir = shell_quote(STAMP_DIR),
+    )
+}
+
+/// Run already-framed sections concurrently. Each section's output is
+/// buffered and printed in order once all have finished, so markers stay
+/// well-formed; reported durations of parallel sections are therefore not
+/// meaningful. The group's status is that of the last failing section.
+pub fn parallel_group(sections: &[String]) -> String {
+    let mut script = String::from("__sandboxed_par=$(mktemp -d /tmp/sandboxed-init.XXXXXX)\n");
+    for (i, section) in sections.iter().enumerate() {
+        script.push_str(&format!(
+            "(\n{section}exit $__sandboxed_rc\n) > \"$__sandboxed_par/{i}\" 2>&1 &\n__sandboxed_pid_{i}=$!\n"
+        ));
+    }
+    script.push_str("__sandboxed_rc=0\n");
+    for i in 0..sections.len() {
+        script.push_str(&format!(
+            "wait $__sandboxed_pid_{i} || __sandboxed_rc=$?\ncat \"$__sandboxed_par/{i}\"\n"
+        ));
+    }
+    script.push_str("rm -rf \"$__sandboxed_par\"\n");
+    script
+}
+
+/// Strip a leading shebang line; sections run inside the assembled script.
+pub fn strip_shebang(content: &str) -> &str {
+    if content.starts_with("#!") {
+        content.split_once('\n').map_or("", |(_, rest)| rest)
+    } else {
+        content
+    }
+}
+
+/// Init script consisting of just the workspace's custom script.
+pub fn custom_script(content: &str) -> String {
+    format!(
+        "{}{}{}",
+        script_preamble(),
+        section(KIND_CUSTOM, "custom", strip_shebang(content)),
+        script_epilogue()
+    )
+}
+
+/// Marker for a fragment that was left out of the script.
+pub fn skipped_section(kind: &str, name: &str) -> String {
+    format!(
+        "echo {}' skipped '{}\n",
+        shell_quote(MARKER),
+        shell_quote(&format!("{} {}", kind, name))
+    )
+}
+
+/// Builds an [`InitScriptReport`] from streamed script output.
+pub struct FragmentTracker {
+    started_at: DateTime<Utc>,
+    fragments: Vec<FragmentResult>,
+    current: Option<(usize, Instant)>,
+}
+
+impl Default for FragmentTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FragmentTracker {
+    pub fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            fragments: Vec::new(),
+            current: None,
+        }
+    }
+
+    /// Feed one output line, received at `at`.
+    pub fn observe(&mut self, line: &str, at: Instant) {
+        let Some(rest) = line.strip_prefix(MARKER).and_then(|r| r.strip_prefix(' ')) else {
+            if let Some((idx, _)) = self.current {
+                append_tail(&mut self.fragments[idx].output, line);
+            }
+            return;
+        };
+
+        let (event, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        match event {
+            "start" => {
+                self.close_current(at, None);
+                let (kind, name) = split_label(rest);
+                self.fragments.push(FragmentResult {
+                    kind,
+                    name,
+                    status: FragmentStatus::Interrupted,
+                    exit_code: None,
+                    duration_ms: 0,
+                    output: String::new(),
+                });
+                self.current = Some((self.fragments.len() - 1, at));
+            }
+            "end" => {
+                let (code, _) = rest.split_once(' ').unwrap_or((rest, ""));
+                self.close_current(at, code.parse().ok());
+            }
+            "skipped" => {
+                let (kind, name) = split_label(rest);
+                self.fragments.push(FragmentResult {
+                    kind,
+                    name,
+                    status: FragmentStatus::Skipped,
+                    exit_code: None,
+                    duration_ms: 0,
+                    output: String::new(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Finish with the script's exit code. A section still running (no end
+    /// marker) is marked interrupted with that code.
+    pub fn finish(mut self, exit_code: Option<i32>) -> InitScriptReport {
+        if let Some((idx, started)) = self.current.take() {
+            let fragment = &mut self.fragments[idx];
+            fragment.exit_code = exit_code;
+            fragment.duration_ms = started.elapsed().as_millis() as u64;
+        }
+        InitScriptReport {
+            started_at: self.started_at,
+            finished_at: Utc::now(),
+            exit_code,
+            fragments: self.fragments,
+        }
+    }
+
+    fn close_current(&mut self, at: Instant, code: Option<i32>) {
+        let Some((idx, started)) = self.current.take() else {
+            return;
+        };
+        let fragment = &mut self.fragments[idx];
+        fragment.duration_ms = at.saturating_duration_since(started).as_millis() as u64;
+        if code.is_some() {
+            fragment.exit_code = code;
+            fragment.status = if code == Some(0) {
+                FragmentStatus::Succeeded
+            } else {
+                FragmentStatus::Failed
+            };
+        }
+    }
+}
+
+fn split_label(label: &str) -> (String, String) {
+    let (kind, name) = label.split_once(' ').unwrap_or((label, ""));
+    (kind.to_string(), name.to_string())
+}
+
+fn append_tail(output: &mut String, line: &str) {
+    output.push_str(line);
+    output.push('\n');
+    if output.len() > MAX_FRAGMENT_OUTPUT {
+        let mut cut = output.len() - MAX_FRAGMENT_OUTPUT;
+        while !output.is_char_boundary(cut) {
+            cut += 1;
+        }
+        output.drain(..cut);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_script(script: &str) -> (String, Option<i32>) {
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .output()
+            .expect("run sh");
+        (
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            output.status.code(),
+        )
+    }
+
+    fn track(output: &str, code: Option<i32>) -> InitScriptReport {
+        let mut tracker = FragmentTracker::new();
+        for line in output.lines() {
+            tracker.observe(line, Instant::now());
+        }
+        tracker.finish(code)
+    }
+
+    #[test]
+    fn attributes_output_and_status_per_section() {
+        let mut script = String::from(script_preamble());
+        script.push_str(&section(
+            KIND_FRAGMENT,
+            "base",
+            "echo installing; echo warn >&2",
+        ));
+        script.push_str(&skipped_section(KIND_FRAGMENT, "missing"));
+        script.push_str(&section(KIND_FRAGMENT, "it's broken", "false"));
+        script.push_str(&section(KIND_CUSTOM, "custom", "echo done"));
+        script.push_str(script_epilogue());
+
+        let (output, code) = run_script(&script);
+        assert_eq!(code, Some(0), "exit status follows the last section");
+        let report = track(&output, code);
+
+        let statuses: Vec<_> = report.fragments.iter().map(|f| f.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                FragmentStatus::Succeeded,
+                FragmentStatus::Skipped,
+                FragmentStatus::Failed,
+                FragmentStatus::Succeeded
+            ]
+        );
+        assert_eq!(report.fragments[0].output, "installing\nwarn\n");
+        assert_eq!(report.fragments[2].name, "it's broken");
+        assert_eq!(report.fragments[2].exit_code, Some(1));
+        assert_eq!(report.failed_fragments(), ["fragment:it's broken"]);
+    }
+
+    #[test]
+    fn section_that_exits_the_script_is_interrupted() {
+        let mut script = String::from(script_preamble());
+        script.push_str(&section(KIND_FRAGMENT, "first", "echo ok"));
+        script.push_str(&section(KIND_SKILL_SETUP, "skills", "echo boom; exit 7"));
+        script.push_str(&section(KIND_CUSTOM, "custom", "echo never"));
+        script.push_str(script_epilogue());
+
+        let (output, code) = run_script(&script);
+        assert_eq!(code, Some(7));
+        let report = track(&output, code);
+        assert_eq!(report.fragments.len(), 2);
+        assert_eq!(report.fragments[1].status, FragmentStatus::Interrupted);
+        assert_eq!(report.fragments[1].exit_code, Some(7));
+        assert_eq!(report.fragments[1].output, "boom\n");
+        assert_eq!(report.failed_fragments(), ["skill-setup:skills"]);
+    }
+
+    #[test]
+    fn parallel_group_reports_each_section() {
+        let mut script = String::from(script_preamble());
+        script.push_str(&parallel_group(&[
+            section(KIND_FRAGMENT, "slow", "sleep 0.2; echo slow"),
+            section(KIND_FRAGMENT, "fails", "echo failing; exit 3"),
+            section(KIND_FRAGMENT, "empty", "# nothing to do"),
+        ]));
+        script.push_str(script_epilogue());
+
+        let (output, code) = run_script(&script);
+        assert_eq!(code, Some(3));
+        let report = track(&output, code);
+        let names: Vec<_> = report.fragments.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["slow", "fails", "empty"]);
+        assert_eq!(report.fragments[0].status, FragmentStatus::Succeeded);
+        assert_eq!(report.fragments[0].output, "slow\n");
+        assert_eq!(report.fragments[2].status, FragmentStatus::Succeeded);
+        assert_eq!(report.failed_fragments(), ["fragment:fails"]);
+    }
+
+    #[test]
+    fn keeps_the_tail_of_long_output() {
+        let mut output = String::new();
+        for i in 0..2000 {
+            append_tail(
+                &mut output,
+                &format!("line {:04} with some padding text", i),
+            );
+        }
+        assert!(output.len() <= MAX_FRAGMENT_OUTPUT);
+        assert!(output.ends_with("line 1999 with some padding text\n"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Lenient parsing of almost-JSON emitted by models.
+//!
+//! Models regularly produce tool-call arguments that are nearly valid JSON:
+//! trailing commas, raw newlines inside strings, single-quoted strings,
+//! Markdown code fences, or an object cut off by the output token limit.
+//! [`parse`] accepts valid JSON unchanged and otherwise rewrites the text to
+//! fix those mistakes before parsing it again, reporting which fixes applied.
+
+use serde_json::Value;
+
+/// A kind of mistake [`parse`] corrected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fix {
+    /// The JSON was wrapped in a Markdown code fence
+    CodeFence,
+    /// A `,` directly before `}` or `]`
+    TrailingComma,
+    /// A raw newline, tab or other control character inside a string
+    ControlCharacter,
+    /// A string delimited by `'` instead of `"`
+    SingleQuotes,
+    /// Unclosed strings, objects or arrays at the end of the input
+    Truncated,
+}
+
+impl Fix {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fix::CodeFence => "code_fence",
+            Fix::TrailingComma => "trailing_comma",
+            Fix::ControlCharacter => "control_character",
+            Fix::SingleQuotes => "single_quotes",
+            Fix::Truncated => "truncated",
+        }
+    }
+}
+
+/// Successfully parsed value and the fixes needed to parse it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repaired {
+    pub value: Value,
+    /// Empty when the input was valid JSON
+    pub fixes: Vec<Fix>,
+}
+
+/// Parse `raw` as JSON, repairing common mistakes. On failure, returns the
+/// parse error of the original input.
+pub fn parse(raw: &str) -> Result<Repaired, String> {
+    let error = match serde_json::from_str(raw) {
+        Ok(value) => {
+            return Ok(Repaired {
+                value,
+                fixes: Vec::new(),
+            })
+        }
+        Err(e) => e.to_string(),
+    };
+
+    let mut fixes = Vec::new();
+    let mut text = raw.trim();
+    if let Some(inner) = strip_code_fence(text) {
+        text = inner;
+        fixes.push(Fix::CodeFence);
+    }
+    let rewritten = rewrite(text, &mut fixes);
+    serde_json::from_str(&rewritten)
+        .map(|value| Repaired { value, fixes })
+        .map_err(|_| error)
+}
+
+fn strip_code_fence(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("```")?;
+    let rest = rest.strip_prefix("json").unwrap_or(rest);
+    Some(rest.strip_suffix("```").unwrap_or(rest).trim())
+}
+
+fn note(fixes: &mut Vec<Fix>, fix: Fix) {
+    if !fixes.contains(&fix) {
+        fixes.push(fix);
+    }
+}
+
+/// Remove a `,` (and the whitespace after it) at the end of `out`.
+fn strip_trailing_comma(out: &mut String) -> bool {
+    let trimmed = out.trim_end();
+    if trimmed.ends_with(',') {
+        out.truncate(trimmed.len() - 1);
+        true
+    } else {
+        false
+    }
+}
+
+fn rewrite(text: &str, fixes: &mut Vec<Fix>) -> String {
+    let mut out = String::with_capacity(text.len() + 8);
+    // Closing brackets of the open objects and arrays
+    let mut stack: Vec<char> = Vec::new();
+    // Delimiter of the string being read, if any
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            match c {
+                '\\' => match chars.next() {
+                    // `\'` is not a JSON escape
+                    Some('\'') => out.push('\''),
+                    Some(next) => {
+                        out.push('\\');
+                        out.push(next);
+                    }
+                    None => {}
+                },
+                c if c == q => {
+                    out.push('"');
+                    quote = None;
+                }
+                '"' => out.push_str("\\\""),
+                '\n' | '\r' | '\t' => {
+                    note(fixes, Fix::ControlCharacter);
+                    out.push_str(match c {
+                        '\n' => "\\n",
+                        '\r' => "\\r",
+                        _ => "\\t",
+                    });
+                }
+                c if (c as u32) < 0x20 => {
+                    note(fixes, Fix::ControlCharacter);
+                    out.push_str(&format!("\\u{:04x}", c as u32));
+                }
+                c => out.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                quote = Some('"');
+                out.push('"');
+            }
+            '\'' => {
+                note(fixes, Fix::SingleQuotes);
+                quote = Some('\'');
+                out.push('"');
+            }
+            '{' => {
+                stack.push('}');
+                out.push(c);
+            }
+            '[' => {
+                stack.push(']');
+                out.push(c);
+            }
+            '}' | ']' => {
+                if strip_trailing_comma(&mut out) {
+                    note(fixes, Fix::TrailingComma);
+                }
+                if stack.last() == Some(&c) {
+                    stack.pop();
+                }
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+
+    if quote.is_some() || !stack.is_empty() {
+        note(fixes, Fix::Truncated);
+        if quote.is_some() {
+            out.push('"');
+        }
+        strip_trailing_comma(&mut out);
+        if out.trim_end().ends_with(':') {
+            out.push_str("null");
+        }
+        while let Some(close) = stack.pop() {
+            out.push(close);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn valid_json_is_unchanged() {
+        let repaired = parse(r#"{"path": "src/main.rs"}"#).unwrap();
+        assert_eq!(repaired.value, json!({"path": "src/main.rs"}));
+        assert!(repaired.fixes.is_empty());
+    }
+
+    #[test]
+    fn repairs_common_mistakes() {
+        let repaired = parse("{'path': 'it\\'s \"here\"', 'lines': [1, 2,],}").unwrap();
+        assert_eq!(
+            repaired.value,
+            json!({"path": "it's \"here\"", "lines": [1, 2]})
+        );
+        assert_eq!(repaired.fixes, vec![Fix::SingleQuotes, Fix::TrailingComma]);
+
+        let repaired = parse("```json\n{\"content\": \"line 1\nline 2\"}\n```").unwrap();
+        assert_eq!(repaired.value, json!({"content": "line 1\nline 2"}));
+        assert_eq!(repaired.fixes, vec![Fix::CodeFence, Fix::ControlCharacter]);
+
+        let repaired =
+            parse(r#"{"edits": [{"old": "a", "new": "b"}, {"old": "c", "new": "d"#).unwrap();
+        assert_eq!(
+            repaired.value,
+            json!({"edits": [{"old": "a", "new": "b"}, {"old": "c", "new": "d"}]})
+        );
+        assert_eq!(repaired.fixes, vec![Fix::Truncated]);
+        assert_eq!(
+            parse(r#"{"path": "a", "mode":"#).unwrap().value,
+            json!({"path": "a", "mode": null})
+        );
+    }
+
+    #[test]
+    fn reports_original_error_when_unrepairable() {
+        let err = parse(r#"{"path" "a"}"#).unwrap_err();
+        assert!(err.contains("expected `:`"), "{err}");
+    }
+}
@@ -38,23 +38,53 @@ pub mod ai_providers;
 pub mod api;
 pub mod backend;
 pub mod backend_config;
+pub mod bwrap;
+pub mod chat_options;
+pub mod client;
 pub mod config;
 pub mod cost;
+pub mod dependency_audit;
+pub mod egress;
+pub mod feature_flags;
+pub mod gpu;
+pub mod hooks;
+pub mod host;
+pub mod init_report;
+pub mod json_repair;
 pub mod library;
+pub mod locale;
 pub mod mcp;
+pub mod microvm;
+pub mod model_policy;
 pub mod nspawn;
+pub mod object_storage;
+pub mod offline;
+pub mod ollama;
 pub mod opencode;
 pub mod opencode_config;
+pub mod package_cache;
+pub mod pii;
 pub mod pkg_manager;
+pub mod policy;
+pub mod process_group;
+pub mod provenance;
 pub mod provider_health;
+pub mod reference_repos;
+pub mod schedule_windows;
 pub mod secrets;
 pub mod settings;
 pub mod skills_registry;
+pub mod stream_metrics;
 pub mod task;
+pub mod template_drift;
+pub mod tool_pruning;
+pub mod tool_quotas;
 pub mod tools;
 pub mod util;
+pub mod web_proxy;
 pub mod workspace;
 pub mod workspace_exec;
+pub mod workspace_snapshot;
 
 pub use ai_providers::{AIProvider, AIProviderStore, ProviderType};
 pub use config::Config;
@@ -0,0 +1,180 @@
+//! Environment variable schemas for workspace templates.
+//!
+//! Templates can declare the variables they expect (see [`TemplateEnvVar`]).
+//! Workspace creation applies declared defaults and rejects missing or
+//! malformed values up front, instead of letting a mission fail later with an
+//! unrelated-looking auth error.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use regex::Regex;
+
+use super::types::{EnvVarIssue, EnvVarIssueKind, TemplateEnvVar};
+
+/// Prefix of template values whose decryption failed; they count as missing.
+const DECRYPTION_FAILED_PREFIX: &str = "[DECRYPTION_FAILED]";
+
+fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+/// Check that a schema is well-formed: valid, unique names and compilable patterns.
+pub fn validate_schema(schema: &[TemplateEnvVar]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for var in schema {
+        let name = var.name.trim();
+        if name.is_empty() {
+            bail!("Environment variable name cannot be empty");
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            || name.starts_with(|c: char| c.is_ascii_digit())
+        {
+            bail!("Invalid environment variable name '{}'", name);
+        }
+        if !seen.insert(name) {
+            bail!("Environment variable '{}' is declared more than once", name);
+        }
+        if let Some(pattern) = &var.pattern {
+            if let Err(e) = compile(pattern) {
+                bail!("Invalid pattern for '{}': {}", name, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fill in declared defaults for variables without a value.
+pub fn apply_defaults(schema: &[TemplateEnvVar], env: &mut HashMap<String, String>) {
+    for var in schema {
+        let Some(default) = &var.default else {
+            continue;
+        };
+        if !matches!(env.get(&var.name), Some(v) if !v.is_empty()) {
+            env.insert(var.name.clone(), default.clone());
+        }
+    }
+}
+
+/// Check `env` against the schema, returning the problems found.
+pub fn check(schema: &[TemplateEnvVar], env: &HashMap<String, String>) -> Vec<EnvVarIssue> {
+    let mut issues = Vec::new();
+    for var in schema {
+        let value = env
+            .get(&var.name)
+            .filter(|v| !v.is_empty() && !v.starts_with(DECRYPTION_FAILED_PREFIX));
+        let issue = |kind, message: String| EnvVarIssue {
+            name: var.name.clone(),
+            kind,
+            description: var.description.clone(),
+            message,
+        };
+        match value {
+            None if var.required && var.default.is_none() => issues.push(issue(
+                EnvVarIssueKind::Missing,
+                format!("{} is required", var.name),
+            )),
+            None => {}
+            Some(value) => {
+                let Some(pattern) = &var.pattern else {
+                    continue;
+                };
+                // Patterns are checked when the template is saved; treat a
+                // hand-edited invalid one as not matching.
+                if !compile(pattern).is_ok_and(|re| re.is_match(value)) {
+                    issues.push(issue(
+                        EnvVarIssueKind::Invalid,
+                        format!("{} does not match pattern {}", var.name, pattern),
+                    ));
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// One-line summary of issues for error responses.
+pub fn describe(issues: &[EnvVarIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| match &issue.description {
+            Some(description) => format!("{} ({})", issue.message, description),
+            None => issue.message.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(
+        name: &str,
+        required: bool,
+        default: Option<&str>,
+        pattern: Option<&str>,
+    ) -> TemplateEnvVar {
+        TemplateEnvVar {
+            name: name.to_string(),
+            description: Some(format!("{} description", name)),
+            required,
+            default: default.map(str::to_string),
+            pattern: pattern.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn reports_missing_and_invalid_values() {
+        let schema = vec![
+            var("GH_TOKEN", true, None, Some("gh[pousr]_[A-Za-z0-9]+")),
+            var("API_KEY", true, None, None),
+            var("REGION", true, Some("eu"), None),
+            var("OPTIONAL", false, None, Some("[0-9]+")),
+        ];
+        let env = HashMap::from([
+            ("GH_TOKEN".to_string(), "not-a-token".to_string()),
+            ("API_KEY".to_string(), "[DECRYPTION_FAILED]abc".to_string()),
+        ]);
+
+        let issues = check(&schema, &env);
+        let summary: Vec<_> = issues.iter().map(|i| (i.name.as_str(), i.kind)).collect();
+        assert_eq!(
+            summary,
+            [
+                ("GH_TOKEN", EnvVarIssueKind::Invalid),
+                ("API_KEY", EnvVarIssueKind::Missing)
+            ]
+        );
+        assert_eq!(
+            describe(&issues[1..]),
+            "API_KEY is required (API_KEY description)"
+        );
+    }
+
+    #[test]
+    fn applies_defaults_only_when_unset() {
+        let schema = vec![
+            var("REGION", true, Some("eu"), None),
+            var("TIER", false, Some("free"), None),
+        ];
+        let mut env = HashMap::from([
+            ("TIER".to_string(), "pro".to_string()),
+            ("REGION".to_string(), String::new()),
+        ]);
+        apply_defaults(&schema, &mut env);
+        assert_eq!(env["REGION"], "eu");
+        assert_eq!(env["TIER"], "pro");
+        assert!(check(&schema, &env).is_empty());
+    }
+
+    #[test]
+    fn validates_schema() {
+        assert!(validate_schema(&[var("GOOD_NAME", true, None, Some("a|b"))]).is_ok());
+        assert!(validate_schema(&[var("1BAD", false, None, None)]).is_err());
+        assert!(validate_schema(&[var("X", false, None, Some("("))]).is_err());
+        assert!(
+            validate_schema(&[var("X", false, None, None), var("X", true, None, None)]).is_err()
+        );
+    }
+}
@@ -1,10 +1,93 @@
 //! Git operations for the configuration library.
 
 use anyhow::{Context, Result};
+use regex::Regex;
 use std::path::Path;
+use std::process::Stdio;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
 use tokio::process::Command;
 
-use super::types::LibraryStatus;
+use super::types::{CloneProgress, HistoryEntry, LibraryStatus};
+
+/// History depth of new library clones. Override with `LIBRARY_GIT_CLONE_DEPTH`
+/// (`0` clones the full history).
+const DEFAULT_CLONE_DEPTH: u32 = 1;
+
+/// Interval between background fetches. Override with
+/// `LIBRARY_GIT_FETCH_INTERVAL_SECS` (`0` disables background fetching).
+const DEFAULT_FETCH_INTERVAL_SECS: u64 = 300;
+
+/// Top-level directories checked out when sparse checkout is enabled
+/// (`LIBRARY_GIT_SPARSE`, default on). Cone mode always includes files at the
+/// repository root such as `plugins.json`.
+const SPARSE_DIRS: &[&str] = &[
+    "agent",
+    "command",
+    "configs",
+    "hook",
+    "init-script",
+    "mcp",
+    "mission-template",
+    "policy",
+    "sandboxed",
+    "skill",
+    "tool",
+    "workspace-template",
+];
+
+/// Progress of the most recent clone, exposed via `GET /api/library/clone-progress`.
+static CLONE_PROGRESS: Mutex<Option<CloneProgress>> = Mutex::new(None);
+
+static PROGRESS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:remote:\s*)?([A-Za-z][A-Za-z ]*?):\s+(\d+)%\s+\((\d+)/(\d+)\)").unwrap()
+});
+
+fn clone_depth() -> u32 {
+    std::env::var("LIBRARY_GIT_CLONE_DEPTH")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CLONE_DEPTH)
+}
+
+fn sparse_enabled() -> bool {
+    crate::util::env_var_bool("LIBRARY_GIT_SPARSE", true)
+}
+
+/// Interval between background fetches, or `None` if disabled.
+pub fn fetch_interval() -> Option<Duration> {
+    let secs = std::env::var("LIBRARY_GIT_FETCH_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_FETCH_INTERVAL_SECS);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Progress of the most recent library clone, if one has run in this process.
+pub fn clone_progress() -> Option<CloneProgress> {
+    CLONE_PROGRESS.lock().ok().and_then(|p| p.clone())
+}
+
+fn update_clone_progress(f: impl FnOnce(&mut CloneProgress)) {
+    if let Ok(mut guard) = CLONE_PROGRESS.lock() {
+        if let Some(progress) = guard.as_mut() {
+            f(progress);
+            progress.updated_at = chrono::Utc::now().to_rfc3339();
+        }
+    }
+}
+
+/// One parsed `git --progress` line: phase, percent, current, total.
+fn parse_progress_line(line: &str) -> Option<(String, u8, u64, u64)> {
+    let caps = PROGRESS_RE.captures(line.trim())?;
+    Some((
+        caps[1].trim().to_string(),
+        caps[2].parse::<u8>().ok()?.min(100),
+        caps[3].parse().ok()?,
+        caps[4].parse().ok()?,
+    ))
+}
 
 /// Get the GIT_SSH_COMMAND value for git operations.
 ///
@@ -37,30 +120,134 @@ fn apply_ssh_config(cmd: &mut Command) {
 }
 
 /// Clone a git repository if it doesn't exist.
+///
+/// New clones are shallow (`LIBRARY_GIT_CLONE_DEPTH`), blobless and, unless
+/// `LIBRARY_GIT_SPARSE=false`, limited to the library directories. Missing
+/// history is fetched on demand with [`deepen`].
 pub async fn clone_if_needed(path: &Path, remote: &str) -> Result<bool> {
     if path.exists() && path.join(".git").exists() {
         tracing::debug!(path = %path.display(), "Library repo already exists");
         return Ok(false);
     }
 
-    tracing::info!(remote = %remote, path = %path.display(), "Cloning library repository");
+    let depth = clone_depth();
+    let sparse = sparse_enabled();
+    tracing::info!(
+        remote = %remote,
+        path = %path.display(),
+        depth,
+        sparse,
+        "Cloning library repository"
+    );
 
     // Create parent directory if needed
     if let Some(parent) = path.parent() {
         tokio::fs::create_dir_all(parent).await?;
     }
 
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Ok(mut guard) = CLONE_PROGRESS.lock() {
+        *guard = Some(CloneProgress {
+            remote: remote.to_string(),
+            phase: "Starting".to_string(),
+            percent: None,
+            current: None,
+            total: None,
+            done: false,
+            error: None,
+            started_at: now.clone(),
+            updated_at: now,
+        });
+    }
+
+    let result = clone_with_progress(path, remote, depth, sparse).await;
+    update_clone_progress(|p| {
+        p.done = true;
+        match &result {
+            Ok(()) => p.phase = "Done".to_string(),
+            Err(e) => p.error = Some(e.to_string()),
+        }
+    });
+    result?;
+
+    Ok(true)
+}
+
+async fn clone_with_progress(path: &Path, remote: &str, depth: u32, sparse: bool) -> Result<()> {
     let mut cmd = Command::new("git");
-    cmd.args(["clone", remote, &path.to_string_lossy()]);
+    cmd.args(["clone", "--progress", "--filter=blob:none"]);
+    if depth > 0 {
+        cmd.args(["--depth", &depth.to_string(), "--no-single-branch"]);
+    }
+    if sparse {
+        cmd.arg("--sparse");
+    }
+    cmd.args([remote, &path.to_string_lossy()]);
+    cmd.stdout(Stdio::null()).stderr(Stdio::piped());
     apply_ssh_config(&mut cmd);
-    let output = cmd.output().await.context("Failed to execute git clone")?;
+    let mut child = cmd.spawn().context("Failed to execute git clone")?;
+
+    // git rewrites progress lines in place with `\r`; anything that is not a
+    // progress line is kept for the error message.
+    let mut stderr = child
+        .stderr
+        .take()
+        .context("git clone stderr unavailable")?;
+    let mut output = String::new();
+    let mut pending = String::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stderr.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        pending.push_str(&String::from_utf8_lossy(&buf[..n]));
+        while let Some(idx) = pending.find(['\r', '\n']) {
+            let line: String = pending.drain(..=idx).collect();
+            let line = line.trim_end();
+            if let Some((phase, percent, current, total)) = parse_progress_line(line) {
+                update_clone_progress(|p| {
+                    p.phase = phase;
+                    p.percent = Some(percent);
+                    p.current = Some(current);
+                    p.total = Some(total);
+                });
+            } else if !line.is_empty() {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+    output.push_str(&pending);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git clone failed: {}", stderr);
+    let status = child.wait().await.context("Failed to wait for git clone")?;
+    if !status.success() {
+        anyhow::bail!("git clone failed: {}", output.trim());
     }
 
-    Ok(true)
+    if sparse {
+        update_clone_progress(|p| {
+            p.phase = "Checking out library directories".to_string();
+            p.percent = None;
+            p.current = None;
+            p.total = None;
+        });
+        let mut cmd = Command::new("git");
+        cmd.current_dir(path)
+            .args(["sparse-checkout", "set", "--cone"])
+            .args(SPARSE_DIRS);
+        apply_ssh_config(&mut cmd);
+        let output = cmd
+            .output()
+            .await
+            .context("Failed to execute git sparse-checkout")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("git sparse-checkout failed: {}", stderr);
+        }
+    }
+
+    Ok(())
 }
 
 /// Ensure the repository has the expected remote configured.
@@ -107,10 +294,13 @@ pub async fn ensure_remote(path: &Path, remote: &str) -> Result<()> {
         }
     }
 
-    // Fetch from the new remote
+    // Fetch from the new remote, keeping a shallow clone shallow
     tracing::info!("Fetching from new remote");
     let mut cmd = Command::new("git");
     cmd.current_dir(path).args(["fetch", "origin"]);
+    if is_shallow(path).await {
+        cmd.args(["--depth", &clone_depth().max(1).to_string()]);
+    }
     apply_ssh_config(&mut cmd);
     let output = cmd.output().await.context("Failed to execute git fetch")?;
 
@@ -194,9 +384,79 @@ pub async fn status(path: &Path) -> Result<LibraryStatus> {
         ahead,
         behind,
         modified_files,
+        shallow: is_shallow(path).await,
+        sparse: is_sparse(path).await,
     })
 }
 
+/// Fetch from origin without touching the working tree or the current branch.
+pub async fn fetch(path: &Path) -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(path).args(["fetch", "--quiet", "origin"]);
+    apply_ssh_config(&mut cmd);
+    let output = cmd.output().await.context("Failed to execute git fetch")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git fetch failed: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Fetch more history for a shallow clone: `commits` more commits, or the full
+/// history when `None`. Returns `false` if the clone already has full history.
+pub async fn deepen(path: &Path, commits: Option<u32>) -> Result<bool> {
+    if !is_shallow(path).await {
+        return Ok(false);
+    }
+
+    tracing::info!(path = %path.display(), commits = ?commits, "Deepening library history");
+
+    let arg = match commits {
+        Some(n) => format!("--deepen={}", n.max(1)),
+        None => "--unshallow".to_string(),
+    };
+    let mut cmd = Command::new("git");
+    cmd.current_dir(path).args(["fetch", &arg, "origin"]);
+    apply_ssh_config(&mut cmd);
+    let output = cmd.output().await.context("Failed to execute git fetch")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git fetch {} failed: {}", arg, stderr);
+    }
+
+    Ok(true)
+}
+
+/// True if the repository has truncated history.
+pub async fn is_shallow(path: &Path) -> bool {
+    git_stdout(path, &["rev-parse", "--is-shallow-repository"])
+        .await
+        .is_some_and(|out| out == "true")
+}
+
+/// True if the working tree is limited by a sparse-checkout definition.
+pub async fn is_sparse(path: &Path) -> bool {
+    git_stdout(path, &["config", "--bool", "core.sparseCheckout"])
+        .await
+        .is_some_and(|out| out == "true")
+}
+
+async fn git_stdout(path: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(path)
+        .args(args)
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 /// Error type for git pull operations.
 #[derive(Debug)]
 pub enum PullError {
@@ -338,13 +598,13 @@ impl GitAuthor {
 pub async fn commit(path: &Path, message: &str, author: Option<&GitAuthor>) -> Result<()> {
     tracing::info!(path = %path.display(), message = %message, "Committing library changes");
 
-    // Stage all changes
-    let output = Command::new("git")
-        .current_dir(path)
-        .args(["add", "-A"])
-        .output()
-        .await
-        .context("Failed to execute git add")?;
+    // Stage all changes, including new paths outside a sparse-checkout cone
+    let mut cmd = Command::new("git");
+    cmd.current_dir(path).args(["add", "-A"]);
+    if is_sparse(path).await {
+        cmd.arg("--sparse");
+    }
+    let output = cmd.output().await.context("Failed to execute git add")?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -508,6 +768,175 @@ pub async fn sparse_clone(path: &Path, remote: &str, subpath: &str) -> Result<()
     Ok(())
 }
 
+/// Whether `rev` is safe to hand to git as a revision (no options, no pathspecs).
+pub fn valid_revision(rev: &str) -> bool {
+    !rev.is_empty()
+        && rev.len() <= 100
+        && !rev.starts_with('-')
+        && rev
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "~^._/-".contains(c))
+}
+
+/// Resolve a revision to a full commit hash.
+pub async fn resolve_commit(path: &Path, rev: &str) -> Result<String> {
+    if !valid_revision(rev) {
+        anyhow::bail!("Invalid revision '{}'", rev);
+    }
+    git_stdout(
+        path,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )
+    .await
+    .ok_or_else(|| anyhow::anyhow!("Revision '{}' not found", rev))
+}
+
+/// Commits touching `rel_path`, newest first.
+pub async fn log_path(path: &Path, rel_path: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+    let output = Command::new("git")
+        .current_dir(path)
+        .args([
+            "log",
+            &format!("--max-count={}", limit),
+            "--format=%H%x1f%h%x1f%an%x1f%ae%x1f%aI%x1f%s",
+            "--",
+            rel_path,
+        ])
+        .output()
+        .await
+        .context("Failed to execute git log")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git log failed: {}", stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\x1f');
+            Some(HistoryEntry {
+                commit: fields.next()?.to_string(),
+                short_commit: fields.next()?.to_string(),
+                author_name: fields.next()?.to_string(),
+                author_email: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                message: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Unified diff of `rel_path` between two commits.
+pub async fn diff_path(path: &Path, rel_path: &str, from: &str, to: &str) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(path)
+        .args(["diff", "--no-color", from, to, "--", rel_path])
+        .output()
+        .await
+        .context("Failed to execute git diff")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git diff failed: {}", stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// True if `rel_path` exists in `commit`.
+pub async fn path_exists_at(path: &Path, rel_path: &str, commit: &str) -> bool {
+    git_stdout(
+        path,
+        &["cat-file", "-t", &format!("{}:{}", commit, rel_path)],
+    )
+    .await
+    .is_some()
+}
+
+/// Replace `rel_path` with its content at `commit` and commit only that path.
+/// Other uncommitted changes in the library are left untouched. Returns the
+/// new commit hash, or `None` if the path already matched `commit`.
+pub async fn restore_path(
+    path: &Path,
+    rel_path: &str,
+    commit: &str,
+    message: &str,
+    author: Option<&GitAuthor>,
+) -> Result<Option<String>> {
+    tracing::info!(path = %path.display(), rel_path = %rel_path, commit = %commit, "Restoring library item");
+
+    // Remove first so files added after `commit` do not survive the restore.
+    let output = Command::new("git")
+        .current_dir(path)
+        .args([
+            "rm",
+            "-r",
+            "-q",
+            "-f",
+            "--ignore-unmatch",
+            "--cached",
+            "--",
+            rel_path,
+        ])
+        .output()
+        .await
+        .context("Failed to execute git rm")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git rm failed: {}", stderr);
+    }
+    let full_path = path.join(rel_path);
+    if full_path.is_dir() {
+        tokio::fs::remove_dir_all(&full_path).await?;
+    } else if full_path.exists() {
+        tokio::fs::remove_file(&full_path).await?;
+    }
+
+    let output = Command::new("git")
+        .current_dir(path)
+        .args(["checkout", commit, "--", rel_path])
+        .output()
+        .await
+        .context("Failed to execute git checkout")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git checkout failed: {}", stderr);
+    }
+
+    let unchanged = Command::new("git")
+        .current_dir(path)
+        .args(["diff", "--cached", "--quiet", "HEAD", "--", rel_path])
+        .status()
+        .await
+        .context("Failed to execute git diff")?
+        .success();
+    if unchanged {
+        return Ok(None);
+    }
+
+    let mut cmd = Command::new("git");
+    cmd.current_dir(path).args(["commit", "-q", "-m", message]);
+    if let Some(author) = author {
+        if let (Some(name), Some(email)) = (&author.name, &author.email) {
+            cmd.args(["--author", &format!("{} <{}>", name, email)]);
+        }
+    }
+    cmd.args(["--", rel_path]);
+    let output = cmd.output().await.context("Failed to execute git commit")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git commit failed: {}", stderr);
+    }
+
+    resolve_commit(path, "HEAD").await.map(Some)
+}
+
 // Helper functions
 
 async fn get_branch(path: &Path) -> Result<String> {
@@ -589,3 +1018,88 @@ async fn get_ahead_behind(path: &Path) -> Result<(u32, u32)> {
         Ok((0, 0))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .current_dir(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .expect("run git")
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn parses_git_progress_lines() {
+        assert_eq!(
+            parse_progress_line("Receiving objects:  45% (450/1000), 1.20 MiB | 2.00 MiB/s"),
+            Some(("Receiving objects".to_string(), 45, 450, 1000))
+        );
+        assert_eq!(
+            parse_progress_line("remote: Counting objects: 100% (12/12), done."),
+            Some(("Counting objects".to_string(), 100, 12, 12))
+        );
+        assert_eq!(parse_progress_line("Cloning into 'library'..."), None);
+    }
+
+    #[tokio::test]
+    async fn clone_is_shallow_and_sparse_until_deepened() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let origin = temp.path().join("origin");
+        std::fs::create_dir_all(origin.join("skill/demo")).unwrap();
+        std::fs::create_dir_all(origin.join("assets")).unwrap();
+        git(&origin, &["init", "-q", "-b", "main"]);
+        std::fs::write(origin.join("plugins.json"), "{}").unwrap();
+        std::fs::write(origin.join("skill/demo/SKILL.md"), "# demo").unwrap();
+        std::fs::write(origin.join("assets/big.bin"), "blob").unwrap();
+        git(&origin, &["add", "-A"]);
+        git(&origin, &["commit", "-q", "-m", "first"]);
+        std::fs::write(origin.join("plugins.json"), "{\"a\":1}").unwrap();
+        git(&origin, &["commit", "-q", "-am", "second"]);
+
+        // file:// so git honours --depth for a local remote
+        let remote = format!("file://{}", origin.display());
+        let library = temp.path().join("library");
+        assert!(clone_if_needed(&library, &remote).await.unwrap());
+
+        assert!(library.join("plugins.json").exists());
+        assert!(library.join("skill/demo/SKILL.md").exists());
+        assert!(!library.join("assets").exists());
+        assert!(is_shallow(&library).await);
+        assert!(is_sparse(&library).await);
+        assert!(clone_progress().is_some());
+
+        assert!(deepen(&library, None).await.unwrap());
+        assert!(!is_shallow(&library).await);
+        assert!(!deepen(&library, Some(5)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn commit_stages_new_directories_outside_sparse_cone() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let origin = temp.path().join("origin");
+        std::fs::create_dir_all(origin.join("skill")).unwrap();
+        git(&origin, &["init", "-q", "-b", "main"]);
+        std::fs::write(origin.join("skill/README.md"), "skills").unwrap();
+        git(&origin, &["add", "-A"]);
+        git(&origin, &["commit", "-q", "-m", "init"]);
+
+        let remote = format!("file://{}", origin.display());
+        let library = temp.path().join("library");
+        clone_if_needed(&library, &remote).await.unwrap();
+        git(&library, &["config", "user.name", "test"]);
+        git(&library, &["config", "user.email", "test@example.com"]);
+
+        std::fs::create_dir_all(library.join("new-dir")).unwrap();
+        std::fs::write(library.join("new-dir/file.md"), "x").unwrap();
+        commit(&library, "add new dir", None).await.unwrap();
+
+        let (clean, _) = get_status(&library).await.unwrap();
+        assert!(clean);
+    }
+}
@@ -0,0 +1,222 @@
+//! Per-item git history, diffs and rollback for the configuration library.
+
+use anyhow::Result;
+
+use super::git::{self, GitAuthor};
+use super::rename::ItemType;
+use super::types::{HistoryEntry, ItemDiff, RestoreResult};
+use super::LibraryStore;
+
+/// Default number of commits returned by [`LibraryStore::item_history`].
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// Path of an item relative to the library root.
+fn item_rel_path(item_type: ItemType, name: &str) -> String {
+    match item_type {
+        ItemType::Skill => format!("skill/{}", name),
+        ItemType::Command => format!("command/{}.md", name),
+        ItemType::Agent => format!("agent/{}.md", name),
+        ItemType::Tool => format!("tool/{}.ts", name),
+        ItemType::WorkspaceTemplate => format!("workspace-template/{}.json", name),
+    }
+}
+
+impl LibraryStore {
+    /// Commits that touched an item, newest first.
+    ///
+    /// Shallow library clones are deepened to full history first so the list
+    /// is not cut off at the clone boundary.
+    pub async fn item_history(
+        &self,
+        item_type: ItemType,
+        name: &str,
+        limit: usize,
+    ) -> Result<Vec<HistoryEntry>> {
+        Self::validate_name(name)?;
+        git::deepen(&self.path, None).await?;
+        git::log_path(&self.path, &item_rel_path(item_type, name), limit.max(1)).await
+    }
+
+    /// Diff an item between `from` and `to` (defaults to `HEAD`).
+    pub async fn item_diff(
+        &self,
+        item_type: ItemType,
+        name: &str,
+        from: &str,
+        to: Option<&str>,
+    ) -> Result<ItemDiff> {
+        Self::validate_name(name)?;
+        let rel_path = item_rel_path(item_type, name);
+        let from = self.resolve_revision(from).await?;
+        let to = self.resolve_revision(to.unwrap_or("HEAD")).await?;
+        let diff = git::diff_path(&self.path, &rel_path, &from, &to).await?;
+        Ok(ItemDiff {
+            path: rel_path,
+            from,
+            to,
+            diff,
+        })
+    }
+
+    /// Restore an item to its content at `revision`, recorded as a new commit.
+    ///
+    /// Only the item's own path is committed; unrelated uncommitted edits in
+    /// the library stay as they are.
+    pub async fn restore_item(
+        &self,
+        item_type: ItemType,
+        name: &str,
+        revision: &str,
+        author: Option<&GitAuthor>,
+    ) -> Result<RestoreResult> {
+        Self::validate_name(name)?;
+        let rel_path = item_rel_path(item_type, name);
+        let revision = self.resolve_revision(revision).await?;
+        if !git::path_exists_at(&self.path, &rel_path, &revision).await {
+            anyhow::bail!(
+                "{} '{}' not found at revision {}",
+                item_type.as_str(),
+                name,
+                &revision[..revision.len().min(12)]
+            );
+        }
+
+        let message = format!(
+            "Restore {} to {}",
+            rel_path,
+            &revision[..revision.len().min(12)]
+        );
+        let commit = git::restore_path(&self.path, &rel_path, &revision, &message, author).await?;
+        Ok(RestoreResult {
+            path: rel_path,
+            revision,
+            commit,
+        })
+    }
+
+    /// Resolve a revision, fetching full history if it lies beyond a shallow clone.
+    async fn resolve_revision(&self, rev: &str) -> Result<String> {
+        match git::resolve_commit(&self.path, rev).await {
+            Ok(commit) => Ok(commit),
+            Err(e) if git::valid_revision(rev) && git::is_shallow(&self.path).await => {
+                git::deepen(&self.path, None).await?;
+                git::resolve_commit(&self.path, rev).await.map_err(|_| e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .current_dir(dir)
+            .args(args)
+            .output()
+            .expect("run git")
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    async fn test_library() -> (tempfile::TempDir, LibraryStore) {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("library");
+        std::fs::create_dir_all(path.join("command")).unwrap();
+        git(&path, &["init", "-q", "-b", "main"]);
+        git(&path, &["config", "user.name", "test"]);
+        git(&path, &["config", "user.email", "test@example.com"]);
+        let store = LibraryStore::with_test_store(path).await;
+        (temp, store)
+    }
+
+    #[tokio::test]
+    async fn history_and_diff_track_a_single_item() {
+        let (_temp, store) = test_library().await;
+        store.save_command("deploy", "v1\n").await.unwrap();
+        store.save_command("other", "x\n").await.unwrap();
+        store.commit("add commands", None).await.unwrap();
+        store.save_command("deploy", "v2\n").await.unwrap();
+        store.commit("edit deploy", None).await.unwrap();
+        store.save_command("other", "y\n").await.unwrap();
+        store.commit("edit other", None).await.unwrap();
+
+        let history = store
+            .item_history(ItemType::Command, "deploy", DEFAULT_HISTORY_LIMIT)
+            .await
+            .unwrap();
+        let messages: Vec<_> = history.iter().map(|h| h.message.as_str()).collect();
+        assert_eq!(messages, ["edit deploy", "add commands"]);
+
+        let diff = store
+            .item_diff(ItemType::Command, "deploy", &history[1].commit, None)
+            .await
+            .unwrap();
+        assert_eq!(diff.path, "command/deploy.md");
+        assert!(diff.diff.contains("-v1") && diff.diff.contains("+v2"));
+        assert!(!diff.diff.contains("other"));
+    }
+
+    #[tokio::test]
+    async fn restore_commits_only_the_item() {
+        let (_temp, store) = test_library().await;
+        std::fs::create_dir_all(store.path().join("skill/demo")).unwrap();
+        std::fs::write(store.path().join("skill/demo/SKILL.md"), "good").unwrap();
+        store.commit("add skill", None).await.unwrap();
+        let good = git::resolve_commit(store.path(), "HEAD").await.unwrap();
+
+        std::fs::write(store.path().join("skill/demo/SKILL.md"), "bad").unwrap();
+        std::fs::write(store.path().join("skill/demo/extra.md"), "extra").unwrap();
+        store.commit("break skill", None).await.unwrap();
+        store.save_command("wip", "unsaved").await.unwrap();
+
+        let result = store
+            .restore_item(ItemType::Skill, "demo", &good[..8], None)
+            .await
+            .unwrap();
+        assert_eq!(result.revision, good);
+        assert!(result.commit.is_some());
+        let skill_dir = store.path().join("skill/demo");
+        assert_eq!(
+            std::fs::read_to_string(skill_dir.join("SKILL.md")).unwrap(),
+            "good"
+        );
+        assert!(!skill_dir.join("extra.md").exists());
+
+        let status = store.status().await.unwrap();
+        assert_eq!(status.modified_files, ["?? command/"]);
+
+        // Restoring again is a no-op.
+        let again = store
+            .restore_item(ItemType::Skill, "demo", &good, None)
+            .await
+            .unwrap();
+        assert!(again.commit.is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_and_unsafe_revisions() {
+        let (_temp, store) = test_library().await;
+        store.save_command("deploy", "v1").await.unwrap();
+        store.commit("init", None).await.unwrap();
+
+        let err = store
+            .restore_item(ItemType::Command, "deploy", "--output=/tmp/x", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid revision"));
+        let err = store
+            .item_diff(ItemType::Command, "deploy", "deadbeef", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        let err = store
+            .restore_item(ItemType::Command, "missing", "HEAD", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found at revision"));
+    }
+}
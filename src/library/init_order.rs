@@ -0,0 +1,276 @@
+//! Init script fragment ordering.
+//!
+//! Fragments may declare ordering metadata in their header comments (see
+//! [`InitScriptMeta`]). The planner sorts the selected fragments so every
+//! fragment runs after the fragments it depends on, keeping the listed order
+//! wherever dependencies allow, and groups adjacent independent
+//! `parallel: true` fragments into steps that run concurrently.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::types::{InitScript, InitScriptMeta, InitScriptPlan, InitScriptStep};
+use super::LibraryStore;
+
+/// Parse ordering metadata from the header comment block of a fragment
+/// (comment lines before the first command).
+pub fn parse_meta(content: &str) -> InitScriptMeta {
+    let mut meta = InitScriptMeta::default();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with("#!") {
+            continue;
+        }
+        let Some(comment) = trimmed.strip_prefix('#') else {
+            break;
+        };
+        let Some((key, value)) = comment.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "after" => meta.after.extend(split_list(value)),
+            "provides" => meta.provides.extend(split_list(value)),
+            "idempotent" => {
+                if let Some(flag) = parse_flag(value) {
+                    meta.idempotent = flag;
+                }
+            }
+            "parallel" | "parallel-safe" => {
+                if let Some(flag) = parse_flag(value) {
+                    meta.parallel = flag;
+                }
+            }
+            _ => {}
+        }
+    }
+    meta
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Plan the execution order of `fragments` (name and metadata, in listed
+/// order). A dependency cycle falls back to the listed order, run
+/// sequentially.
+pub fn plan(fragments: &[(String, InitScriptMeta)]) -> InitScriptPlan {
+    let mut warnings = Vec::new();
+
+    // Drop duplicates, keeping the first occurrence.
+    let mut seen = HashSet::new();
+    let fragments: Vec<&(String, InitScriptMeta)> = fragments
+        .iter()
+        .filter(|(name, _)| {
+            let first = seen.insert(name.as_str());
+            if !first {
+                warnings.push(format!(
+                    "'{}' is listed more than once; running it once",
+                    name
+                ));
+            }
+            first
+        })
+        .collect();
+
+    let mut providers: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, (name, meta)) in fragments.iter().enumerate() {
+        providers.entry(name.as_str()).or_default().push(i);
+        for token in &meta.provides {
+            providers.entry(token.as_str()).or_default().push(i);
+        }
+    }
+
+    let mut deps: Vec<HashSet<usize>> = vec![HashSet::new(); fragments.len()];
+    for (i, (name, meta)) in fragments.iter().enumerate() {
+        for target in &meta.after {
+            match providers.get(target.as_str()) {
+                Some(indices) => deps[i].extend(indices.iter().copied().filter(|&j| j != i)),
+                None => warnings.push(format!(
+                    "'{}' runs after '{}', which no selected fragment provides",
+                    name, target
+                )),
+            }
+        }
+    }
+
+    // Kahn's algorithm, always taking the earliest listed ready fragment.
+    let mut remaining: Vec<usize> = deps.iter().map(HashSet::len).collect();
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); fragments.len()];
+    for (i, d) in deps.iter().enumerate() {
+        for &j in d {
+            dependents[j].push(i);
+        }
+    }
+    let mut ready: BTreeSet<usize> = (0..fragments.len())
+        .filter(|&i| remaining[i] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(fragments.len());
+    while let Some(i) = ready.pop_first() {
+        order.push(i);
+        for &d in &dependents[i] {
+            remaining[d] -= 1;
+            if remaining[d] == 0 {
+                ready.insert(d);
+            }
+        }
+    }
+
+    if order.len() < fragments.len() {
+        let cyclic: Vec<&str> = (0..fragments.len())
+            .filter(|i| remaining[*i] > 0)
+            .map(|i| fragments[i].0.as_str())
+            .collect();
+        warnings.push(format!(
+            "Dependency cycle between {}; using listed order",
+            cyclic.join(", ")
+        ));
+        let steps = fragments
+            .iter()
+            .map(|(name, _)| InitScriptStep {
+                fragments: vec![name.clone()],
+                parallel: false,
+            })
+            .collect();
+        return InitScriptPlan { steps, warnings };
+    }
+
+    // Group adjacent parallel-safe fragments that don't depend on each other.
+    let mut steps = Vec::new();
+    let mut group: Vec<usize> = Vec::new();
+    let flush = |group: &mut Vec<usize>, steps: &mut Vec<InitScriptStep>| {
+        if !group.is_empty() {
+            steps.push(InitScriptStep {
+                fragments: group.iter().map(|&i| fragments[i].0.clone()).collect(),
+                parallel: group.len() > 1,
+            });
+            group.clear();
+        }
+    };
+    for i in order {
+        if fragments[i].1.parallel {
+            if group.iter().any(|g| deps[i].contains(g)) {
+                flush(&mut group, &mut steps);
+            }
+            group.push(i);
+        } else {
+            flush(&mut group, &mut steps);
+            group.push(i);
+            flush(&mut group, &mut steps);
+        }
+    }
+    flush(&mut group, &mut steps);
+
+    InitScriptPlan { steps, warnings }
+}
+
+impl LibraryStore {
+    /// Load the named init script fragments, returning the found ones in
+    /// listed order and the names that could not be loaded.
+    pub(crate) async fn load_init_fragments(
+        &self,
+        names: &[String],
+    ) -> (Vec<InitScript>, Vec<(String, anyhow::Error)>) {
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        for name in names {
+            match self.get_init_script(name).await {
+                Ok(script) => found.push(script),
+                Err(e) => missing.push((name.clone(), e)),
+            }
+        }
+        (found, missing)
+    }
+
+    /// Plan the execution order of the named init script fragments.
+    pub async fn plan_init_scripts(&self, names: &[String]) -> InitScriptPlan {
+        let (found, missing) = self.load_init_fragments(names).await;
+        let metas: Vec<_> = found.into_iter().map(|s| (s.name, s.meta)).collect();
+        let mut plan = plan(&metas);
+        for (name, _) in missing {
+            plan.warnings
+                .push(format!("'{}' not found in library; skipped", name));
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(after: &[&str], provides: &[&str], parallel: bool) -> InitScriptMeta {
+        InitScriptMeta {
+            after: after.iter().map(|s| s.to_string()).collect(),
+            provides: provides.iter().map(|s| s.to_string()).collect(),
+            parallel,
+            ..Default::default()
+        }
+    }
+
+    fn names(plan: &InitScriptPlan) -> Vec<Vec<&str>> {
+        plan.steps
+            .iter()
+            .map(|s| s.fragments.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    #[test]
+    fn parses_header_metadata() {
+        let content = "#!/usr/bin/env bash\n# Install Node.js\n# after: base, apt\n# Provides: node\n# idempotent: no\n# parallel: true\n\napt-get install -y nodejs\n# after: ignored\n";
+        let meta = parse_meta(content);
+        assert_eq!(meta.after, ["base", "apt"]);
+        assert_eq!(meta.provides, ["node"]);
+        assert!(!meta.idempotent);
+        assert!(meta.parallel);
+
+        assert_eq!(parse_meta("echo hi\n"), InitScriptMeta::default());
+    }
+
+    #[test]
+    fn sorts_by_dependencies_and_groups_parallel_fragments() {
+        let fragments = vec![
+            ("node-tools".to_string(), meta(&["node"], &[], true)),
+            ("base".to_string(), meta(&[], &[], false)),
+            ("node".to_string(), meta(&["base"], &["nodejs"], true)),
+            ("rust".to_string(), meta(&["base"], &[], true)),
+            ("ssh".to_string(), meta(&["missing"], &[], false)),
+        ];
+        let plan = plan(&fragments);
+        assert_eq!(
+            names(&plan),
+            vec![
+                vec!["base"],
+                vec!["node"],
+                vec!["node-tools", "rust"],
+                vec!["ssh"]
+            ]
+        );
+        assert!(!plan.steps[1].parallel);
+        assert!(plan.steps[2].parallel);
+        assert_eq!(plan.warnings.len(), 1);
+        assert!(plan.warnings[0].contains("missing"));
+    }
+
+    #[test]
+    fn cycle_falls_back_to_listed_order() {
+        let fragments = vec![
+            ("a".to_string(), meta(&["b"], &[], true)),
+            ("b".to_string(), meta(&["a"], &[], true)),
+            ("c".to_string(), meta(&[], &[], true)),
+        ];
+        let plan = plan(&fragments);
+        assert_eq!(names(&plan), vec![vec!["a"], vec!["b"], vec!["c"]]);
+        assert!(plan.warnings[0].contains("cycle between a, b"));
+    }
+}
@@ -7,6 +7,7 @@
 //! - Plugins registry (`plugins.json`)
 //! - Library agents (`agent/*.md`)
 //! - Library tools (`tool/*.ts`)
+//! - Mission templates (`mission-template/*.json`)
 //! - Config profiles (`configs/<profile>/`) with harness-specific settings:
 //!   - `.opencode/` - OpenCode settings (settings.json, oh-my-opencode.json)
 //!   - `.claudecode/` - Claude Code settings (settings.json)
@@ -14,7 +15,10 @@
 //!   - `.sandboxed-sh/` - Sandboxed config (config.json)
 
 pub mod env_crypto;
+pub mod env_schema;
 mod git;
+pub mod history;
+pub mod init_order;
 pub mod rename;
 pub mod types;
 
@@ -22,9 +26,12 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs;
 
-pub use git::GitAuthor;
+use crate::chat_options::ChatOptions;
+
+pub use git::{clone_progress, GitAuthor};
 pub use types::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,21 +61,37 @@ struct WorkspaceTemplateConfig {
     /// Tailscale networking mode (only relevant when shared_network is false).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     tailscale_mode: Option<crate::workspace::TailscaleMode>,
+    /// Outbound network policy for workspaces created from this template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    egress_policy: Option<crate::egress::EgressPolicy>,
+    /// microVM isolation and sizing for workspaces created from this template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    microvm: Option<crate::microvm::MicroVmSpec>,
+    /// GPUs to pass through to workspaces created from this template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gpu: Option<crate::gpu::GpuRequest>,
     /// MCP server names to enable for workspaces created from this template.
     #[serde(default)]
     mcps: Vec<String>,
     /// Config profile to use for workspaces created from this template.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     config_profile: Option<String>,
+    /// Declared environment variables (required/optional, defaults, validation).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    env_schema: Vec<TemplateEnvVar>,
 }
 
 // Directory constants (OpenCode-aligned structure)
 const SKILL_DIR: &str = "skill";
 const COMMAND_DIR: &str = "command";
 const AGENT_DIR: &str = "agent";
+const TOOL_DIR: &str = "tool";
+const HOOK_DIR: &str = "hook";
+const POLICY_DIR: &str = "policy";
 const INIT_SCRIPT_DIR: &str = "init-script";
 const PLUGINS_FILE: &str = "plugins.json";
 const WORKSPACE_TEMPLATE_DIR: &str = "workspace-template";
+const MISSION_TEMPLATE_DIR: &str = "mission-template";
 const CONFIGS_DIR: &str = "configs";
 const DEFAULT_PROFILE: &str = "default";
 
@@ -93,6 +116,27 @@ impl LibraryStore {
         })
     }
 
+    /// Periodically fetch from the remote so `status` reports accurate
+    /// ahead/behind counts without a round trip. The task stops once the
+    /// store is dropped (e.g. after the remote changes).
+    pub fn start_background_fetch(self: &Arc<Self>) {
+        let Some(interval) = git::fetch_interval() else {
+            return;
+        };
+        let store = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(store) = store.upgrade() else {
+                    break;
+                };
+                if let Err(e) = git::fetch(&store.path).await {
+                    tracing::debug!(error = %e, "Background library fetch failed");
+                }
+            }
+        });
+    }
+
     /// Get the library path.
     pub fn path(&self) -> &Path {
         &self.path
@@ -194,6 +238,12 @@ impl LibraryStore {
         git::push(&self.path).await
     }
 
+    /// Fetch history that a shallow clone is missing: `commits` more commits,
+    /// or everything when `None`. Returns `false` if history was already complete.
+    pub async fn deepen(&self, commits: Option<u32>) -> Result<bool> {
+        git::deepen(&self.path, commits).await
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // MCP Servers (mcp/servers.json)
     // ─────────────────────────────────────────────────────────────────────────
@@ -1065,7 +1115,10 @@ impl LibraryStore {
         let description = extract_description(&frontmatter);
         let model = extract_model(&frontmatter);
         let tools = extract_tools(&frontmatter);
+        let tool_bundles = extract_string_array(&frontmatter, "tool_bundles");
+        let tool_aliases = extract_tool_aliases(&frontmatter);
         let permissions = extract_permissions(&frontmatter);
+        let chat_options = ChatOptions::from_frontmatter(&frontmatter);
 
         Ok(LibraryAgent {
             name: name.to_string(),
@@ -1074,7 +1127,10 @@ impl LibraryStore {
             content,
             model,
             tools,
+            tool_bundles,
+            tool_aliases,
             permissions,
+            chat_options,
         })
     }
 
@@ -1108,6 +1164,165 @@ impl LibraryStore {
         Ok(())
     }
 
+    // ─────────────────────────────────────────────────────────────────────────
+    // Library Tools (tool/*.ts, tool/*.js)
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Load all library tool sources.
+    pub async fn list_library_tools(&self) -> Result<Vec<LibraryTool>> {
+        let tools_dir = self.path.join(TOOL_DIR);
+
+        if !tools_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut tools = Vec::new();
+        let mut entries = fs::read_dir(&tools_dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let entry_path = entry.path();
+
+            let Some(ext) = entry_path.extension().and_then(|e| e.to_str()) else {
+                continue;
+            };
+            if !matches!(ext, "ts" | "js") {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.trim_end_matches(&format!(".{}", ext)).to_string();
+            if Self::validate_name(&name).is_err() {
+                continue;
+            }
+
+            let content = fs::read_to_string(&entry_path)
+                .await
+                .with_context(|| format!("Failed to read tool file {}", file_name))?;
+
+            tools.push(LibraryTool {
+                name,
+                path: format!("{}/{}", TOOL_DIR, file_name),
+                content,
+            });
+        }
+
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tools)
+    }
+
+    /// List compiled WASM tool plugins with their capability manifests.
+    pub async fn list_wasm_tool_plugins(&self) -> Result<Vec<WasmToolPlugin>> {
+        let tools_dir = self.path.join(TOOL_DIR);
+
+        if !tools_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut plugins = Vec::new();
+        let mut entries = fs::read_dir(&tools_dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let entry_path = entry.path();
+            if entry_path.extension().and_then(|e| e.to_str()) != Some("wasm") {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.trim_end_matches(".wasm").to_string();
+            if Self::validate_name(&name).is_err() {
+                continue;
+            }
+
+            let manifest = fs::read_to_string(tools_dir.join(format!("{}.json", name)))
+                .await
+                .ok();
+
+            plugins.push(WasmToolPlugin {
+                name,
+                path: format!("{}/{}", TOOL_DIR, file_name),
+                manifest,
+            });
+        }
+
+        plugins.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(plugins)
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Lifecycle Hooks (hook/hooks.json, hook/*)
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Load the hook config and scripts from `hook/`.
+    pub async fn list_hook_files(&self) -> Result<Vec<LibraryHookFile>> {
+        let hooks_dir = self.path.join(HOOK_DIR);
+
+        if !hooks_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        let mut entries = fs::read_dir(&hooks_dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let content = fs::read_to_string(entry.path())
+                .await
+                .with_context(|| format!("Failed to read hook file {}", name))?;
+            files.push(LibraryHookFile { name, content });
+        }
+
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(files)
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Guardrail Policies (policy/*.json)
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Load all guardrail policy rule sets.
+    pub async fn list_policies(&self) -> Result<Vec<LibraryPolicy>> {
+        let policy_dir = self.path.join(POLICY_DIR);
+
+        if !policy_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut policies = Vec::new();
+        let mut entries = fs::read_dir(&policy_dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let entry_path = entry.path();
+            if entry_path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.trim_end_matches(".json").to_string();
+            if Self::validate_name(&name).is_err() {
+                continue;
+            }
+
+            let content = fs::read_to_string(&entry_path)
+                .await
+                .with_context(|| format!("Failed to read policy file {}", file_name))?;
+
+            policies.push(LibraryPolicy {
+                name,
+                path: format!("{}/{}", POLICY_DIR, file_name),
+                content,
+            });
+        }
+
+        policies.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(policies)
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Workspace Templates (workspace-template/*.json)
     // ─────────────────────────────────────────────────────────────────────────
@@ -1264,8 +1479,12 @@ impl LibraryStore {
             init_script: config.init_script,
             shared_network: config.shared_network,
             tailscale_mode: config.tailscale_mode,
+            egress_policy: config.egress_policy,
+            microvm: config.microvm,
+            gpu: config.gpu,
             mcps: config.mcps,
             config_profile: config.config_profile,
+            env_schema: config.env_schema,
         })
     }
 
@@ -1316,8 +1535,12 @@ impl LibraryStore {
             init_script: template.init_script.clone(),
             shared_network: template.shared_network,
             tailscale_mode: template.tailscale_mode,
+            egress_policy: template.egress_policy.clone(),
+            microvm: template.microvm.clone(),
+            gpu: template.gpu.clone(),
             mcps: template.mcps.clone(),
             config_profile: template.config_profile.clone(),
+            env_schema: template.env_schema.clone(),
         };
 
         let content = serde_json::to_string_pretty(&config)?;
@@ -1345,6 +1568,111 @@ impl LibraryStore {
         Ok(())
     }
 
+    // ─────────────────────────────────────────────────────────────────────────
+    // Mission Templates (mission-template/*.json)
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// List all mission templates with their summaries.
+    pub async fn list_mission_templates(&self) -> Result<Vec<MissionTemplateSummary>> {
+        let templates_dir = self.path.join(MISSION_TEMPLATE_DIR);
+
+        if !templates_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut templates = Vec::new();
+        let mut entries = fs::read_dir(&templates_dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let entry_path = entry.path();
+            if entry_path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.trim_end_matches(".json").to_string();
+            let template = fs::read_to_string(&entry_path)
+                .await
+                .ok()
+                .and_then(|c| serde_json::from_str::<MissionTemplate>(&c).ok());
+
+            templates.push(MissionTemplateSummary {
+                name,
+                description: template.as_ref().and_then(|t| t.description.clone()),
+                path: format!("{}/{}", MISSION_TEMPLATE_DIR, file_name),
+                params: template
+                    .map(|t| t.params.into_iter().map(|p| p.name).collect())
+                    .unwrap_or_default(),
+            });
+        }
+
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// Get a mission template by name.
+    pub async fn get_mission_template(&self, name: &str) -> Result<MissionTemplate> {
+        Self::validate_name(name)?;
+        let template_path = self
+            .path
+            .join(MISSION_TEMPLATE_DIR)
+            .join(format!("{}.json", name));
+
+        if !template_path.exists() {
+            anyhow::bail!("Mission template not found: {}", name);
+        }
+
+        let content = fs::read_to_string(&template_path)
+            .await
+            .context("Failed to read mission template file")?;
+        let mut template: MissionTemplate =
+            serde_json::from_str(&content).context("Failed to parse mission template file")?;
+        template.name = name.to_string();
+        template.path = format!("{}/{}.json", MISSION_TEMPLATE_DIR, name);
+        Ok(template)
+    }
+
+    /// Save a mission template.
+    pub async fn save_mission_template(
+        &self,
+        name: &str,
+        template: &MissionTemplate,
+    ) -> Result<()> {
+        Self::validate_name(name)?;
+        template.validate().map_err(anyhow::Error::msg)?;
+
+        let templates_dir = self.path.join(MISSION_TEMPLATE_DIR);
+        fs::create_dir_all(&templates_dir).await?;
+
+        let mut template = template.clone();
+        template.name = name.to_string();
+        template.path = format!("{}/{}.json", MISSION_TEMPLATE_DIR, name);
+
+        let content = serde_json::to_string_pretty(&template)?;
+        fs::write(templates_dir.join(format!("{}.json", name)), content)
+            .await
+            .context("Failed to write mission template file")?;
+
+        Ok(())
+    }
+
+    /// Delete a mission template.
+    pub async fn delete_mission_template(&self, name: &str) -> Result<()> {
+        Self::validate_name(name)?;
+        let template_path = self
+            .path
+            .join(MISSION_TEMPLATE_DIR)
+            .join(format!("{}.json", name));
+
+        if template_path.exists() {
+            fs::remove_file(&template_path)
+                .await
+                .context("Failed to delete mission template file")?;
+        }
+
+        Ok(())
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Init Script Fragments (init-script/*/SCRIPT.sh)
     // ─────────────────────────────────────────────────────────────────────────
@@ -1380,10 +1708,15 @@ impl LibraryStore {
             let description = content
                 .as_ref()
                 .and_then(|c| Self::extract_script_description(c));
+            let meta = content
+                .as_deref()
+                .map(init_order::parse_meta)
+                .unwrap_or_default();
 
             scripts.push(InitScriptSummary {
                 name,
                 description,
+                meta,
                 path: format!(
                     "{}/{}/SCRIPT.sh",
                     INIT_SCRIPT_DIR,
@@ -1413,11 +1746,13 @@ impl LibraryStore {
             .context("Failed to read SCRIPT.sh")?;
 
         let description = Self::extract_script_description(&content);
+        let meta = init_order::parse_meta(&content);
 
         Ok(InitScript {
             name: name.to_string(),
             description,
             path: format!("{}/{}/SCRIPT.sh", INIT_SCRIPT_DIR, name),
+            meta,
             content,
         })
     }
@@ -1455,56 +1790,81 @@ impl LibraryStore {
     }
 
     /// Assemble a combined init script from fragments, skill setup commands, and optional custom script.
-    /// Each fragment is prefixed with a header comment for debugging.
+    /// Fragments are ordered by their declared dependencies (see [`init_order`]); adjacent
+    /// independent parallel-safe fragments run concurrently. Each fragment is prefixed with a
+    /// header comment for debugging and framed with [`init_report`](crate::init_report) markers
+    /// so its output and exit status can be attributed when the script runs.
     pub async fn assemble_init_script(
         &self,
         fragment_names: &[String],
         custom_script: Option<&str>,
         skill_setup_commands: Option<&[(String, Vec<String>)]>,
     ) -> Result<String> {
+        use crate::init_report::{self, KIND_CUSTOM, KIND_FRAGMENT, KIND_SKILL_SETUP};
+
         let mut assembled = String::new();
 
         // Add shebang
         assembled.push_str("#!/usr/bin/env bash\n");
-        assembled.push_str("# Auto-assembled init script from fragments\n\n");
+        assembled.push_str("# Auto-assembled init script from fragments\n");
+        assembled.push_str(init_report::script_preamble());
 
-        // Add each fragment (skip missing ones with a warning)
-        for name in fragment_names {
-            let script = match self.get_init_script(name).await {
-                Ok(s) => s,
-                Err(e) => {
-                    tracing::warn!(
-                        fragment = %name,
-                        error = %e,
-                        "Init script fragment not found, skipping"
-                    );
-                    // Add a comment in the assembled script noting the skip
-                    assembled.push_str(&format!(
-                        "\n# === {} === (SKIPPED: not found in library)\n",
-                        name
-                    ));
-                    continue;
-                }
-            };
-
-            // Add header for this fragment
-            assembled.push_str(&format!("\n# === {} ===\n", name));
-
-            // Strip shebang from fragment content if present
-            let content = if script.content.starts_with("#!") {
-                // Skip the first line (shebang)
-                script
-                    .content
-                    .lines()
-                    .skip(1)
-                    .collect::<Vec<_>>()
-                    .join("\n")
+        // Skip missing fragments with a warning
+        let (scripts, missing) = self.load_init_fragments(fragment_names).await;
+        for (name, e) in missing {
+            tracing::warn!(
+                fragment = %name,
+                error = %e,
+                "Init script fragment not found, skipping"
+            );
+            // Add a comment in the assembled script noting the skip
+            assembled.push_str(&format!(
+                "\n# === {} === (SKIPPED: not found in library)\n",
+                name
+            ));
+            assembled.push_str(&init_report::skipped_section(KIND_FRAGMENT, &name));
+        }
+
+        let metas: Vec<_> = scripts
+            .iter()
+            .map(|s| (s.name.clone(), s.meta.clone()))
+            .collect();
+        let plan = init_order::plan(&metas);
+        for warning in &plan.warnings {
+            tracing::warn!(warning = %warning, "Init script fragment ordering");
+            assembled.push_str(&format!("# WARNING: {}\n", warning));
+        }
+
+        let by_name: HashMap<&str, &InitScript> =
+            scripts.iter().map(|s| (s.name.as_str(), s)).collect();
+        let fragment_section = |script: &InitScript| {
+            let body = init_report::strip_shebang(&script.content);
+            if script.meta.idempotent {
+                init_report::section(KIND_FRAGMENT, &script.name, body)
             } else {
-                script.content.clone()
-            };
+                init_report::section(
+                    KIND_FRAGMENT,
+                    &script.name,
+                    &init_report::run_once(&script.name, body),
+                )
+            }
+        };
 
-            assembled.push_str(&content);
-            assembled.push('\n');
+        for step in &plan.steps {
+            let sections: Vec<String> = step
+                .fragments
+                .iter()
+                .filter_map(|name| by_name.get(name.as_str()))
+                .map(|script| fragment_section(script))
+                .collect();
+
+            // Add header for this step
+            assembled.push_str(&format!("\n# === {} ===\n", step.fragments.join(", ")));
+            if step.parallel {
+                assembled.push_str(&init_report::parallel_group(&sections));
+            } else {
+                assembled.push_str(&sections.concat());
+            }
         }
 
         // Add skill setup commands if provided
@@ -1515,13 +1875,18 @@ impl LibraryStore {
                 assembled.push_str("# (npm commands auto-substituted to use bun if available)\n");
                 for (skill_name, commands) in skills {
                     if !commands.is_empty() {
-                        assembled.push_str(&format!("# Skill: {}\n", skill_name));
+                        let mut body = String::new();
                         for cmd in commands {
                             // Auto-substitute npm with bun for faster installs
-                            let cmd = Self::substitute_npm_with_bun(cmd);
-                            assembled.push_str(&cmd);
-                            assembled.push('\n');
+                            body.push_str(&Self::substitute_npm_with_bun(cmd));
+                            body.push('\n');
                         }
+                        assembled.push_str(&format!("# Skill: {}\n", skill_name));
+                        assembled.push_str(&init_report::section(
+                            KIND_SKILL_SETUP,
+                            skill_name,
+                            body.trim_end(),
+                        ));
                     }
                 }
             }
@@ -1532,19 +1897,15 @@ impl LibraryStore {
             let trimmed = custom.trim();
             if !trimmed.is_empty() {
                 assembled.push_str("\n# === Custom Script ===\n");
-
-                // Strip shebang from custom script if present
-                let content = if trimmed.starts_with("#!") {
-                    trimmed.lines().skip(1).collect::<Vec<_>>().join("\n")
-                } else {
-                    trimmed.to_string()
-                };
-
-                assembled.push_str(&content);
-                assembled.push('\n');
+                assembled.push_str(&init_report::section(
+                    KIND_CUSTOM,
+                    "custom",
+                    init_report::strip_shebang(trimmed),
+                ));
             }
         }
 
+        assembled.push_str(init_report::script_epilogue());
         Ok(assembled)
     }
 
@@ -1576,7 +1937,7 @@ impl LibraryStore {
 
     /// Substitute npm commands with bun equivalents for faster package installation.
     /// Generates a shell command that uses bun if available, falling back to npm.
-    fn substitute_npm_with_bun(cmd: &str) -> String {
+    pub(crate) fn substitute_npm_with_bun(cmd: &str) -> String {
         // Check if this is an npm install command
         let trimmed = cmd.trim();
         if trimmed.starts_with("npm install") || trimmed.starts_with("npm i ") {
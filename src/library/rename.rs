@@ -36,6 +36,18 @@ impl ItemType {
             Self::WorkspaceTemplate => "workspace-template",
         }
     }
+
+    /// Parse the URL form used by the API (`skill`, `workspace-template`, ...).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "skill" => Some(Self::Skill),
+            "command" => Some(Self::Command),
+            "agent" => Some(Self::Agent),
+            "tool" => Some(Self::Tool),
+            "workspace-template" => Some(Self::WorkspaceTemplate),
+            _ => None,
+        }
+    }
 }
 
 /// A single change that will be or was applied.
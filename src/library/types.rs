@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
+use crate::chat_options::ChatOptions;
+use crate::tools::alias::ToolAlias;
 use crate::workspace::TailscaleMode;
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -32,6 +34,9 @@ pub enum McpServer {
         url: String,
         #[serde(default)]
         headers: HashMap<String, String>,
+        /// Bearer or OAuth client-credentials auth; see [`crate::mcp::McpAuth`]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auth: Option<crate::mcp::McpAuth>,
         #[serde(default = "default_true")]
         enabled: bool,
     },
@@ -72,6 +77,53 @@ pub struct Plugin {
     pub ui: PluginUI,
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Library Tool Types (OpenCode-style custom tools)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Library tool file (`tool/<name>.ts` or `tool/<name>.js`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryTool {
+    /// Tool name (filename without extension)
+    pub name: String,
+    /// Path relative to library root
+    pub path: String,
+    /// Source code
+    pub content: String,
+}
+
+/// Guardrail policy rule set (`policy/<name>.json`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryPolicy {
+    /// Policy name (filename without .json)
+    pub name: String,
+    /// Path relative to library root
+    pub path: String,
+    /// Raw JSON rule set
+    pub content: String,
+}
+
+/// Lifecycle hook script or config file (`hook/*`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryHookFile {
+    /// File name (e.g. `hooks.json`, `guard.sh`)
+    pub name: String,
+    /// File content
+    pub content: String,
+}
+
+/// Compiled WASM tool plugin (`tool/<name>.wasm`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmToolPlugin {
+    /// Plugin name (filename without .wasm)
+    pub name: String,
+    /// Path relative to library root
+    pub path: String,
+    /// Capability manifest (`tool/<name>.json`), if present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Library Agent Types (OpenCode agent definitions)
 // ─────────────────────────────────────────────────────────────────────────────
@@ -107,9 +159,18 @@ pub struct LibraryAgent {
     /// Tool patterns: {"read": true, "write": false, "playwright_*": true}
     #[serde(default)]
     pub tools: HashMap<String, bool>,
+    /// Built-in tool bundles the agent uses (see `tools::bundles`); empty = all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_bundles: Vec<String>,
+    /// Tools exposed under agent-specific names (see `tools::alias`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_aliases: Vec<ToolAlias>,
     /// Permission levels: {"bash": "ask", "write": "allow"}
     #[serde(default)]
     pub permissions: HashMap<String, String>,
+    /// Default sampling options (temperature, top_p, max_tokens, reasoning_effort)
+    #[serde(default, skip_serializing_if = "ChatOptions::is_empty")]
+    pub chat_options: ChatOptions,
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -175,6 +236,15 @@ pub struct WorkspaceTemplate {
     /// - `tailnet_only`: Connect to tailnet but use host gateway for internet
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tailscale_mode: Option<TailscaleMode>,
+    /// Outbound network policy (allowlisted domains/CIDRs or deny-all with proxy).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_policy: Option<crate::egress::EgressPolicy>,
+    /// Run workspaces from this template in a microVM instead of a container.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub microvm: Option<crate::microvm::MicroVmSpec>,
+    /// GPUs to pass through to workspaces created from this template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<crate::gpu::GpuRequest>,
     /// MCP server names to enable for workspaces created from this template.
     /// Empty = use default MCPs (those with `default_enabled = true`).
     #[serde(default)]
@@ -183,6 +253,163 @@ pub struct WorkspaceTemplate {
     /// Defaults to "default" if not specified.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub config_profile: Option<String>,
+    /// Declared environment variables, checked when a workspace is created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_schema: Vec<TemplateEnvVar>,
+}
+
+/// An environment variable declared by a workspace template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateEnvVar {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Whether workspace creation fails without a value (ignored when a default is set)
+    #[serde(default)]
+    pub required: bool,
+    /// Value used when neither the template nor the request provides one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Regex the whole value must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvVarIssueKind {
+    Missing,
+    Invalid,
+}
+
+/// A declared environment variable that is missing or fails validation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvVarIssue {
+    pub name: String,
+    pub kind: EnvVarIssueKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub message: String,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Mission Template Types
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A parameter accepted by a mission template prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionTemplateParam {
+    /// Placeholder name, used as `<name/>` in the prompt
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value used when the caller omits the parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Whether instantiation fails without a value (ignored when a default is set)
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Mission template summary for listing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionTemplateSummary {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Path relative to library root (e.g., "mission-template/bump-deps.json")
+    pub path: String,
+    /// Parameter names accepted by the prompt
+    #[serde(default)]
+    pub params: Vec<String>,
+}
+
+/// A reusable mission spec: prompt template plus full mission configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionTemplate {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Path relative to library root
+    #[serde(default)]
+    pub path: String,
+    /// Prompt with `<param/>` placeholders
+    pub prompt: String,
+    #[serde(default)]
+    pub params: Vec<MissionTemplateParam>,
+    /// Workspace template the mission should run in (a workspace created from it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_override: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_effort: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_profile: Option<String>,
+    /// Cost cap in cents; the mission is cancelled once it is exceeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_cents: Option<u64>,
+    /// Criteria the agent must verify before reporting completion
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification: Vec<String>,
+}
+
+impl MissionTemplate {
+    /// Check that the prompt is set, the budget is positive and param names are
+    /// non-empty and unique.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.prompt.trim().is_empty() {
+            return Err("Mission template prompt cannot be empty".to_string());
+        }
+        if self.budget_cents == Some(0) {
+            return Err("Mission template budget_cents must be at least 1".to_string());
+        }
+        let mut seen = HashSet::new();
+        for param in &self.params {
+            if param.name.trim().is_empty() {
+                return Err("Mission template param names cannot be empty".to_string());
+            }
+            if !seen.insert(param.name.as_str()) {
+                return Err(format!("Duplicate mission template param: {}", param.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the prompt with `params`, falling back to parameter defaults.
+    /// Verification criteria are appended as a checklist.
+    pub fn render_prompt(&self, params: &HashMap<String, String>) -> Result<String, String> {
+        let mut values = HashMap::new();
+        let mut missing = Vec::new();
+        for param in &self.params {
+            match params.get(&param.name).or(param.default.as_ref()) {
+                Some(value) => {
+                    values.insert(param.name.clone(), value.clone());
+                }
+                None if param.required => missing.push(param.name.clone()),
+                None => {
+                    values.insert(param.name.clone(), String::new());
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(format!("Missing required params: {}", missing.join(", ")));
+        }
+
+        let mut prompt =
+            crate::api::automation_variables::substitute_custom_variables(&self.prompt, &values);
+        if !self.verification.is_empty() {
+            prompt.push_str("\n\n**Verification criteria** (confirm each before finishing):\n");
+            for criterion in &self.verification {
+                prompt.push_str(&format!("- [ ] {}\n", criterion));
+            }
+        }
+        Ok(prompt)
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -199,6 +426,66 @@ pub struct InitScriptSummary {
     pub description: Option<String>,
     /// Path relative to library root (e.g., "init-script/base/SCRIPT.sh")
     pub path: String,
+    /// Ordering metadata from header comments
+    #[serde(default)]
+    pub meta: InitScriptMeta,
+}
+
+/// Ordering metadata declared in an init script fragment's header comments:
+///
+/// ```sh
+/// #!/usr/bin/env bash
+/// # Install Node.js
+/// # after: base
+/// # provides: node
+/// # idempotent: false
+/// # parallel: true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InitScriptMeta {
+    /// Fragment names or `provides` capabilities that must run first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+    /// Capabilities other fragments can depend on via `after`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provides: Vec<String>,
+    /// Whether the fragment is safe to re-run. Non-idempotent fragments leave a
+    /// stamp in the container and are skipped once they have succeeded.
+    #[serde(default = "default_true")]
+    pub idempotent: bool,
+    /// Whether the fragment may run concurrently with other independent
+    /// parallel-safe fragments.
+    #[serde(default)]
+    pub parallel: bool,
+}
+
+impl Default for InitScriptMeta {
+    fn default() -> Self {
+        Self {
+            after: Vec::new(),
+            provides: Vec::new(),
+            idempotent: true,
+            parallel: false,
+        }
+    }
+}
+
+/// One step of an init script execution plan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InitScriptStep {
+    /// Fragments in this step; more than one only when `parallel` is set
+    pub fragments: Vec<String>,
+    /// Whether the fragments run concurrently
+    pub parallel: bool,
+}
+
+/// Execution order for a list of init script fragments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InitScriptPlan {
+    pub steps: Vec<InitScriptStep>,
+    /// Dependency problems (unknown references, cycles, missing fragments)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 /// Full init script fragment with content.
@@ -211,6 +498,9 @@ pub struct InitScript {
     pub description: Option<String>,
     /// Path relative to library root
     pub path: String,
+    /// Ordering metadata from header comments
+    #[serde(default)]
+    pub meta: InitScriptMeta,
     /// Full script content
     pub content: String,
 }
@@ -371,6 +661,79 @@ pub struct LibraryStatus {
     pub behind: u32,
     /// List of modified/untracked files
     pub modified_files: Vec<String>,
+    /// True if the clone has truncated history (see `POST /api/library/deepen`)
+    #[serde(default)]
+    pub shallow: bool,
+    /// True if only the library directories are checked out
+    #[serde(default)]
+    pub sparse: bool,
+}
+
+/// A commit touching a library item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Full commit hash
+    pub commit: String,
+    /// Abbreviated commit hash
+    pub short_commit: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date (ISO 8601)
+    pub date: String,
+    /// Commit subject line
+    pub message: String,
+}
+
+/// Unified diff of a library item between two revisions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemDiff {
+    /// Path relative to library root
+    pub path: String,
+    /// Resolved base commit
+    pub from: String,
+    /// Resolved target commit
+    pub to: String,
+    /// Unified diff (empty if the item is unchanged)
+    pub diff: String,
+}
+
+/// Result of restoring a library item to an earlier revision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreResult {
+    /// Path relative to library root
+    pub path: String,
+    /// Resolved revision the item was restored to
+    pub revision: String,
+    /// New commit recording the restore, or `None` if nothing changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+/// Progress of the most recent library clone, parsed from `git clone --progress`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloneProgress {
+    /// Remote being cloned
+    pub remote: String,
+    /// Current git phase (e.g. "Receiving objects", "Resolving deltas")
+    pub phase: String,
+    /// Completion of the current phase, 0-100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u8>,
+    /// Objects processed in the current phase
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<u64>,
+    /// Total objects in the current phase
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// True once the clone has finished (successfully or not)
+    pub done: bool,
+    /// Error message if the clone failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the clone started (RFC 3339)
+    pub started_at: String,
+    /// When progress was last reported (RFC 3339)
+    pub updated_at: String,
 }
 
 /// Migration report showing what changed during library structure migration.
@@ -446,6 +809,10 @@ pub struct SandboxedConfig {
     /// Desktop session lifecycle configuration.
     #[serde(default)]
     pub desktop: DesktopConfig,
+    /// Default sampling options for missions using this profile.
+    /// Library agent frontmatter overrides individual fields.
+    #[serde(default, skip_serializing_if = "ChatOptions::is_empty")]
+    pub chat_options: ChatOptions,
 }
 
 impl Default for SandboxedConfig {
@@ -465,6 +832,7 @@ impl Default for SandboxedConfig {
             ],
             default_agent: Some("Sisyphus".to_string()),
             desktop: DesktopConfig::default(),
+            chat_options: ChatOptions::default(),
         }
     }
 }
@@ -499,6 +867,29 @@ pub struct ClaudeCodeConfig {
     /// Set commit/pr to empty strings to disable co-author attribution.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub attribution: Option<ClaudeCodeAttribution>,
+    /// Permission settings passed to the Claude CLI on every mission turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<ClaudeCodePermissions>,
+}
+
+/// Claude Code permission settings, mirroring the `permissions` block of
+/// Claude's own `settings.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ClaudeCodePermissions {
+    /// Permission mode ("default", "acceptEdits", "plan", "dontAsk",
+    /// "bypassPermissions"). Unset runs with all permission checks skipped.
+    #[serde(
+        default,
+        alias = "defaultMode",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_mode: Option<String>,
+    /// Tools allowed without prompting, e.g. "Bash(git:*)" or "mcp__github__*".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// Tools the agent may never use.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
 }
 
 /// Amp Code configuration stored in the Library.
@@ -793,6 +1184,15 @@ pub fn extract_string_array(frontmatter: &Option<serde_yaml::Value>, field: &str
         .unwrap_or_default()
 }
 
+/// Extract `tool_aliases` from YAML frontmatter.
+pub fn extract_tool_aliases(frontmatter: &Option<serde_yaml::Value>) -> Vec<ToolAlias> {
+    frontmatter
+        .as_ref()
+        .and_then(|fm| fm.get("tool_aliases"))
+        .map(crate::tools::alias::parse_aliases)
+        .unwrap_or_default()
+}
+
 /// Extract command params from YAML frontmatter.
 /// Supports two formats:
 /// 1. Simple list: `params: [repo-path, pr-number]`
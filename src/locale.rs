@@ -0,0 +1,241 @@
+//! Locale and timezone context for missions.
+//!
+//! Agents otherwise answer in English and reason about time in UTC. A
+//! [`LocaleSettings`] can be set globally (in settings) and per mission; the
+//! mission value wins field by field. The resolved settings are:
+//! - injected into every mission turn as instructions (language, time zone and
+//!   the current local time), see [`LocaleSettings::prompt_instructions`]
+//! - used to format times in generated text such as mission summaries and
+//!   maintenance reports, see [`LocaleSettings::format_time`]
+//!
+//! Time zones are IANA names (`Europe/Berlin`), resolved against the system
+//! zoneinfo database so daylight saving time is applied, or fixed offsets
+//! (`+02:00`). Times are formatted as `YYYY-MM-DD HH:MM <zone>` whatever the
+//! locale; the locale only tells the agent which language to use.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Preferred language and time zone.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocaleSettings {
+    /// BCP 47 language tag (e.g. `de-DE`, `pt-BR`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// IANA time zone (e.g. `Europe/Berlin`) or fixed offset (e.g. `+02:00`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// A validated time zone.
+pub(crate) enum Zone {
+    Fixed(FixedOffset),
+    Named(tz::TimeZone),
+}
+
+impl Zone {
+    pub(crate) fn parse(raw: &str) -> Result<Self, String> {
+        let raw = raw.trim();
+        match raw {
+            "UTC" | "Etc/UTC" | "Z" => return Ok(Zone::Fixed(FixedOffset::east_opt(0).unwrap())),
+            _ => {}
+        }
+        if raw.starts_with('+') || raw.starts_with('-') {
+            return raw
+                .parse::<FixedOffset>()
+                .map(Zone::Fixed)
+                .map_err(|_| format!("Invalid UTC offset '{}' (expected e.g. +02:00)", raw));
+        }
+        // Only plain zone names: no paths outside the zoneinfo database.
+        let valid_name = !raw.is_empty()
+            && !raw.starts_with('/')
+            && !raw.contains("..")
+            && raw
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '+'));
+        if !valid_name {
+            return Err(format!("Invalid time zone '{}'", raw));
+        }
+        tz::TimeZone::from_posix_tz(raw)
+            .map(Zone::Named)
+            .map_err(|_| format!("Unknown time zone '{}'", raw))
+    }
+
+    /// UTC offset in effect at `time`.
+    pub(crate) fn offset_at(&self, time: DateTime<Utc>) -> FixedOffset {
+        FixedOffset::east_opt(self.at(time).0).unwrap_or(FixedOffset::east_opt(0).unwrap())
+    }
+
+    /// UTC offset in seconds and zone abbreviation at `time`.
+    fn at(&self, time: DateTime<Utc>) -> (i32, String) {
+        match self {
+            Zone::Fixed(offset) => {
+                let secs = offset.local_minus_utc();
+                let name = if secs == 0 {
+                    "UTC".to_string()
+                } else {
+                    format!("UTC{}", offset)
+                };
+                (secs, name)
+            }
+            Zone::Named(tz) => match tz.find_local_time_type(time.timestamp()) {
+                Ok(t) => (t.ut_offset(), t.time_zone_designation().to_string()),
+                Err(_) => (0, "UTC".to_string()),
+            },
+        }
+    }
+}
+
+impl LocaleSettings {
+    pub fn is_empty(&self) -> bool {
+        self.locale.is_none() && self.timezone.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(locale) = &self.locale {
+            let valid = !locale.is_empty()
+                && locale.len() <= 35
+                && locale
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(format!("Invalid locale '{}' (expected e.g. de-DE)", locale));
+            }
+        }
+        if let Some(timezone) = &self.timezone {
+            Zone::parse(timezone)?;
+        }
+        Ok(())
+    }
+
+    /// Fill unset fields from `fallback`.
+    pub fn or(&self, fallback: &LocaleSettings) -> LocaleSettings {
+        LocaleSettings {
+            locale: self.locale.clone().or_else(|| fallback.locale.clone()),
+            timezone: self.timezone.clone().or_else(|| fallback.timezone.clone()),
+        }
+    }
+
+    /// Settings for a mission: its own values, falling back to the global ones.
+    pub fn resolve(mission: Option<&LocaleSettings>) -> LocaleSettings {
+        let global = crate::settings::locale_settings_cached();
+        match mission {
+            Some(mission) => mission.or(&global),
+            None => global,
+        }
+    }
+
+    /// The time zone, or `None` for UTC (also when the stored value no longer
+    /// resolves, e.g. after the zoneinfo database changed).
+    fn zone(&self) -> Option<Zone> {
+        self.timezone
+            .as_deref()
+            .and_then(|raw| Zone::parse(raw).ok())
+    }
+
+    /// Format `time` in the configured time zone, e.g. `2026-10-16 05:00 CEST`.
+    pub fn format_time(&self, time: DateTime<Utc>) -> String {
+        match self.zone() {
+            Some(zone) => {
+                let (secs, name) = zone.at(time);
+                let offset =
+                    FixedOffset::east_opt(secs).unwrap_or(FixedOffset::east_opt(0).unwrap());
+                format!(
+                    "{} {}",
+                    time.with_timezone(&offset).format("%Y-%m-%d %H:%M"),
+                    name
+                )
+            }
+            None => format!("{} UTC", time.format("%Y-%m-%d %H:%M")),
+        }
+    }
+
+    /// Format an RFC 3339 timestamp (as stored on missions); unparseable
+    /// values are returned unchanged.
+    pub fn format_timestamp(&self, timestamp: &str) -> String {
+        DateTime::parse_from_rfc3339(timestamp)
+            .map(|t| self.format_time(t.with_timezone(&Utc)))
+            .unwrap_or_else(|_| timestamp.to_string())
+    }
+
+    /// Instructions appended to a mission turn, or `None` when nothing is set.
+    pub fn prompt_instructions(&self, now: DateTime<Utc>) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut lines = Vec::new();
+        if let Some(locale) = &self.locale {
+            lines.push(format!(
+                "- The user's locale is {}. Reply in that language and use its conventions for dates, numbers and units.",
+                locale
+            ));
+        }
+        if let Some(timezone) = &self.timezone {
+            lines.push(format!(
+                "- The user's time zone is {}; it is now {}. Give times in this zone unless asked otherwise.",
+                timezone.trim(),
+                self.format_time(now)
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn settings(locale: Option<&str>, timezone: Option<&str>) -> LocaleSettings {
+        LocaleSettings {
+            locale: locale.map(str::to_string),
+            timezone: timezone.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn validates_locale_and_timezone() {
+        assert!(settings(Some("de-DE"), Some("+02:00")).validate().is_ok());
+        assert!(settings(None, Some("UTC")).validate().is_ok());
+        assert!(settings(Some("de DE"), None).validate().is_err());
+        assert!(settings(None, Some("../../etc/passwd")).validate().is_err());
+        assert!(settings(None, Some("+25:00")).validate().is_err());
+    }
+
+    #[test]
+    fn formats_times_in_zone() {
+        let time = Utc.with_ymd_and_hms(2026, 10, 16, 3, 0, 0).unwrap();
+        assert_eq!(
+            settings(None, None).format_time(time),
+            "2026-10-16 03:00 UTC"
+        );
+        assert_eq!(
+            settings(None, Some("-05:30")).format_time(time),
+            "2026-10-15 21:30 UTC-05:30"
+        );
+        if std::path::Path::new("/usr/share/zoneinfo/Europe/Berlin").exists() {
+            let berlin = settings(None, Some("Europe/Berlin"));
+            assert_eq!(berlin.format_time(time), "2026-10-16 05:00 CEST");
+            let winter = Utc.with_ymd_and_hms(2026, 12, 1, 3, 0, 0).unwrap();
+            assert_eq!(berlin.format_time(winter), "2026-12-01 04:00 CET");
+        }
+    }
+
+    #[test]
+    fn mission_settings_override_global_per_field() {
+        let global = settings(Some("fr-FR"), Some("Europe/Paris"));
+        let mission = settings(Some("de-DE"), None);
+        assert_eq!(
+            mission.or(&global),
+            settings(Some("de-DE"), Some("Europe/Paris"))
+        );
+
+        let time = Utc.with_ymd_and_hms(2026, 10, 16, 3, 0, 0).unwrap();
+        assert!(settings(None, None).prompt_instructions(time).is_none());
+        let prompt = settings(Some("de-DE"), Some("+02:00"))
+            .prompt_instructions(time)
+            .unwrap();
+        assert!(prompt.contains("locale is de-DE"));
+        assert!(prompt.contains("2026-10-16 05:00 UTC+02:00"));
+    }
+}
@@ -0,0 +1,330 @@
+//! Authentication for HTTP MCP servers.
+//!
+//! Header values and credentials may reference environment variables as
+//! `${VAR}` and may be encrypted with the library key (`<encrypted v="1">`).
+//! OAuth client-credential tokens are cached per token endpoint and client,
+//! and refreshed shortly before they expire or after the server rejects them.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use super::types::McpAuth;
+use crate::library::env_crypto;
+
+/// Refresh tokens this long before they expire.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+static PLACEHOLDER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+static TOKEN_CACHE: LazyLock<Mutex<HashMap<String, CachedToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn is_fresh(&self, now: Instant) -> bool {
+        match self.expires_at {
+            Some(expires_at) => now + REFRESH_MARGIN < expires_at,
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// Resolve `${VAR}` placeholders (from `env`, then the process environment)
+/// and decrypt an encrypted value.
+pub fn resolve_value(value: &str, env: &HashMap<String, String>) -> anyhow::Result<String> {
+    let mut missing = Vec::new();
+    let substituted = PLACEHOLDER_RE.replace_all(value, |caps: &regex::Captures| {
+        let name = &caps[1];
+        match env.get(name).cloned().or_else(|| std::env::var(name).ok()) {
+            Some(v) => v,
+            None => {
+                missing.push(name.to_string());
+                String::new()
+            }
+        }
+    });
+    if !missing.is_empty() {
+        anyhow::bail!("Environment variable {} is not set", missing.join(", "));
+    }
+
+    let trimmed = substituted.trim();
+    if env_crypto::is_encrypted(trimmed) {
+        let key = env_crypto::load_private_key_from_env()?
+            .ok_or_else(|| anyhow::anyhow!("Encrypted value but PRIVATE_KEY is not set"))?;
+        return env_crypto::decrypt_value(&key, trimmed);
+    }
+    Ok(substituted.into_owned())
+}
+
+fn cache_key(auth: &McpAuth) -> Option<String> {
+    match auth {
+        McpAuth::Bearer { .. } => None,
+        McpAuth::OauthClientCredentials {
+            token_url,
+            client_id,
+            scopes,
+            audience,
+            ..
+        } => Some(format!(
+            "{}|{}|{}|{}",
+            token_url,
+            client_id,
+            scopes.join(" "),
+            audience.as_deref().unwrap_or_default()
+        )),
+    }
+}
+
+fn cached_token(key: &str, now: Instant) -> Option<String> {
+    let cache = TOKEN_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get(key)
+        .filter(|t| t.is_fresh(now))
+        .map(|t| t.access_token.clone())
+}
+
+fn store_token(key: String, token: CachedToken) {
+    TOKEN_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, token);
+}
+
+/// Drop the cached token for `auth`, e.g. after the server answered 401.
+pub fn invalidate(auth: &McpAuth) {
+    if let Some(key) = cache_key(auth) {
+        TOKEN_CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&key);
+    }
+}
+
+/// Request a token with the OAuth client-credentials grant.
+async fn fetch_token(
+    client: &reqwest::Client,
+    auth: &McpAuth,
+    env: &HashMap<String, String>,
+) -> anyhow::Result<CachedToken> {
+    let McpAuth::OauthClientCredentials {
+        token_url,
+        client_id,
+        client_secret,
+        scopes,
+        audience,
+    } = auth
+    else {
+        anyhow::bail!("Not an OAuth configuration");
+    };
+
+    let mut form = vec![
+        ("grant_type", "client_credentials".to_string()),
+        ("client_id", resolve_value(client_id, env)?),
+        ("client_secret", resolve_value(client_secret, env)?),
+    ];
+    if !scopes.is_empty() {
+        form.push(("scope", scopes.join(" ")));
+    }
+    if let Some(audience) = audience {
+        form.push(("audience", audience.clone()));
+    }
+
+    let requested_at = Instant::now();
+    let response = client
+        .post(resolve_value(token_url, env)?)
+        .header("Accept", "application/json")
+        .form(&form)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!(
+            "Token request failed: HTTP {}: {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        );
+    }
+    let token: TokenResponse = response.json().await?;
+    Ok(CachedToken {
+        access_token: token.access_token,
+        expires_at: token
+            .expires_in
+            .map(|secs| requested_at + Duration::from_secs(secs)),
+    })
+}
+
+/// The `Authorization` header value for `auth`, fetching an OAuth token when
+/// none is cached (or `refresh` is set).
+pub async fn authorization(
+    client: &reqwest::Client,
+    auth: &McpAuth,
+    env: &HashMap<String, String>,
+    refresh: bool,
+) -> anyhow::Result<String> {
+    if let McpAuth::Bearer { token } = auth {
+        return Ok(format!("Bearer {}", resolve_value(token, env)?));
+    }
+    let key = cache_key(auth).unwrap_or_default();
+    if !refresh {
+        if let Some(token) = cached_token(&key, Instant::now()) {
+            return Ok(format!("Bearer {}", token));
+        }
+    }
+    let token = fetch_token(client, auth, env).await?;
+    let header = format!("Bearer {}", token.access_token);
+    store_token(key, token);
+    Ok(header)
+}
+
+/// Headers for a request to an HTTP MCP server, with values resolved and the
+/// `Authorization` header set from `auth`.
+pub async fn request_headers(
+    client: &reqwest::Client,
+    headers: &HashMap<String, String>,
+    auth: Option<&McpAuth>,
+    refresh: bool,
+) -> anyhow::Result<HashMap<String, String>> {
+    let env = HashMap::new();
+    let mut resolved = HashMap::with_capacity(headers.len() + 1);
+    for (name, value) in headers {
+        resolved.insert(name.clone(), resolve_value(value, &env)?);
+    }
+    if let Some(auth) = auth {
+        resolved.retain(|name, _| !name.eq_ignore_ascii_case("authorization"));
+        resolved.insert(
+            "Authorization".to_string(),
+            authorization(client, auth, &env, refresh).await?,
+        );
+    }
+    Ok(resolved)
+}
+
+/// Headers to write into a harness MCP config. Values that can't be resolved
+/// are kept as written; OAuth tokens are included when one is cached.
+pub fn config_headers(
+    headers: &HashMap<String, String>,
+    auth: Option<&McpAuth>,
+    env: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut resolved: HashMap<String, String> = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = resolve_value(value, env).unwrap_or_else(|e| {
+                tracing::warn!(header = %name, error = %e, "Could not resolve MCP header");
+                value.clone()
+            });
+            (name.clone(), value)
+        })
+        .collect();
+    let authorization = match auth {
+        Some(McpAuth::Bearer { token }) => resolve_value(token, env)
+            .map_err(|e| tracing::warn!(error = %e, "Could not resolve MCP bearer token"))
+            .ok()
+            .map(|token| format!("Bearer {}", token)),
+        Some(auth) => cache_key(auth)
+            .and_then(|key| cached_token(&key, Instant::now()))
+            .map(|token| format!("Bearer {}", token)),
+        None => None,
+    };
+    if let Some(authorization) = authorization {
+        resolved.retain(|name, _| !name.eq_ignore_ascii_case("authorization"));
+        resolved.insert("Authorization".to_string(), authorization);
+    }
+    resolved
+}
+
+/// Extract JSON-RPC messages from a `text/event-stream` response body.
+pub fn parse_sse_messages(body: &str) -> Vec<serde_json::Value> {
+    let mut messages = Vec::new();
+    let mut data = String::new();
+    for line in body.lines().chain(std::iter::once("")) {
+        if line.is_empty() {
+            if !data.is_empty() {
+                if let Ok(value) = serde_json::from_str(&data) {
+                    messages.push(value);
+                }
+                data.clear();
+            }
+        } else if let Some(rest) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(rest.strip_prefix(' ').unwrap_or(rest));
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_placeholders_from_env() {
+        let env = HashMap::from([("GH_TOKEN".to_string(), "ghp_abc".to_string())]);
+        assert_eq!(
+            resolve_value("Bearer ${GH_TOKEN}", &env).unwrap(),
+            "Bearer ghp_abc"
+        );
+        assert_eq!(resolve_value("plain", &env).unwrap(), "plain");
+        assert!(resolve_value("${SANDBOXED_SH_TEST_UNSET_VAR}", &env).is_err());
+    }
+
+    #[test]
+    fn config_headers_prefer_auth_over_static_header() {
+        let env = HashMap::from([("TOKEN".to_string(), "secret".to_string())]);
+        let headers = HashMap::from([
+            ("authorization".to_string(), "Bearer stale".to_string()),
+            ("X-Org".to_string(), "${TOKEN}-org".to_string()),
+        ]);
+        let auth = McpAuth::Bearer {
+            token: "${TOKEN}".to_string(),
+        };
+        let resolved = config_headers(&headers, Some(&auth), &env);
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved["Authorization"], "Bearer secret");
+        assert_eq!(resolved["X-Org"], "secret-org");
+    }
+
+    #[test]
+    fn tokens_are_refreshed_before_expiry() {
+        let now = Instant::now();
+        let token = |secs| CachedToken {
+            access_token: "t".to_string(),
+            expires_at: Some(now + Duration::from_secs(secs)),
+        };
+        assert!(token(3600).is_fresh(now));
+        assert!(!token(30).is_fresh(now));
+        assert!(CachedToken {
+            access_token: "t".to_string(),
+            expires_at: None
+        }
+        .is_fresh(now));
+    }
+
+    #[test]
+    fn parses_sse_messages() {
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\nid: 2\ndata: {\"jsonrpc\":\"2.0\",\ndata: \"id\":7,\"result\":{}}\n";
+        let messages = parse_sse_messages(body);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1]["id"], 7);
+    }
+}
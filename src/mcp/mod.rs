@@ -3,6 +3,7 @@
 //! Allows dynamic addition/removal of MCP servers and their tools without restarting.
 //! Configurations are persisted to `{working_dir}/.sandboxed-sh/mcp/config.json`.
 
+pub mod auth;
 mod config;
 mod registry;
 mod types;
@@ -86,10 +86,40 @@ pub struct McpRegistry {
     disabled_tools: RwLock<std::collections::HashSet<String>>,
     /// Request ID counter for JSON-RPC
     request_id: AtomicU64,
+    /// `Mcp-Session-Id` assigned by streamable HTTP servers (keyed by endpoint)
+    http_sessions: RwLock<HashMap<String, String>>,
 }
 
 const MCP_REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
 const MCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Timeout for an HTTP health probe
+const MCP_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+/// Default interval between health checks (`SANDBOXED_SH_MCP_HEALTH_INTERVAL_SECS`, 0 disables)
+const DEFAULT_HEALTH_INTERVAL_SECS: u64 = 30;
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(5);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+/// Delay before the next restart attempt after `failures` consecutive failures.
+fn restart_backoff(failures: u32) -> Duration {
+    let exp = failures.saturating_sub(1).min(16);
+    RESTART_BACKOFF_BASE
+        .saturating_mul(1u32 << exp)
+        .min(RESTART_BACKOFF_MAX)
+}
+
+/// Whether stdio servers with known tools are started on first tool use
+/// instead of at startup (`SANDBOXED_SH_MCP_LAZY_START`, default on).
+fn lazy_start_enabled() -> bool {
+    crate::util::env_var_bool("SANDBOXED_SH_MCP_LAZY_START", true)
+}
+
+fn health_check_interval() -> Option<Duration> {
+    let secs = std::env::var("SANDBOXED_SH_MCP_HEALTH_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_HEALTH_INTERVAL_SECS);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
 
 impl McpRegistry {
     /// Create a new MCP registry.
@@ -118,6 +148,7 @@ impl McpRegistry {
             stdio_processes: RwLock::new(HashMap::new()),
             disabled_tools: RwLock::new(std::collections::HashSet::new()),
             request_id: AtomicU64::new(1),
+            http_sessions: RwLock::new(HashMap::new()),
         }
     }
 
@@ -377,33 +408,97 @@ impl McpRegistry {
         self.request_id.fetch_add(1, Ordering::SeqCst)
     }
 
-    /// Send a JSON-RPC request via HTTP
+    /// POST a JSON-RPC message to an HTTP MCP server, retrying once with a
+    /// fresh token if an OAuth-authenticated request is rejected.
+    async fn post_http(
+        &self,
+        endpoint: &str,
+        headers: &HashMap<String, String>,
+        auth: Option<&McpAuth>,
+        body: &serde_json::Value,
+    ) -> anyhow::Result<reqwest::Response> {
+        let session_id = self.http_sessions.read().await.get(endpoint).cloned();
+        let mut refresh = false;
+        loop {
+            let resolved =
+                super::auth::request_headers(&self.http_client, headers, auth, refresh).await?;
+            let mut req_builder = self
+                .http_client
+                .post(endpoint)
+                .header("Content-Type", "application/json")
+                .header("Accept", "application/json, text/event-stream");
+            if let Some(session_id) = &session_id {
+                req_builder = req_builder.header("Mcp-Session-Id", session_id.as_str());
+            }
+
+            // Add custom headers
+            for (key, value) in &resolved {
+                req_builder = req_builder.header(key.as_str(), value.as_str());
+            }
+
+            let response = req_builder.json(body).send().await?;
+            let can_refresh = matches!(auth, Some(McpAuth::OauthClientCredentials { .. }));
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED && can_refresh && !refresh {
+                if let Some(auth) = auth {
+                    super::auth::invalidate(auth);
+                }
+                refresh = true;
+                continue;
+            }
+            if let Some(session_id) = response
+                .headers()
+                .get("mcp-session-id")
+                .and_then(|v| v.to_str().ok())
+            {
+                self.http_sessions
+                    .write()
+                    .await
+                    .insert(endpoint.to_string(), session_id.to_string());
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Send a JSON-RPC request via HTTP. Servers may answer with plain JSON
+    /// or a `text/event-stream` carrying the response.
     async fn send_jsonrpc_http(
         &self,
         endpoint: &str,
         method: &str,
         params: Option<serde_json::Value>,
         headers: &HashMap<String, String>,
+        auth: Option<&McpAuth>,
     ) -> anyhow::Result<serde_json::Value> {
-        let request = JsonRpcRequest::new(self.next_request_id(), method, params);
+        let id = self.next_request_id();
+        let request = JsonRpcRequest::new(id, method, params);
 
-        let mut req_builder = self
-            .http_client
-            .post(endpoint)
-            .header("Content-Type", "application/json");
+        let response = self
+            .post_http(endpoint, headers, auth, &serde_json::to_value(&request)?)
+            .await?;
 
-        // Add custom headers
-        for (key, value) in headers {
-            req_builder = req_builder.header(key.as_str(), value.as_str());
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            // The server dropped our session; the next initialize starts a new one.
+            self.http_sessions.write().await.remove(endpoint);
         }
-
-        let response = req_builder.json(&request).send().await?;
-
         if !response.status().is_success() {
             anyhow::bail!("HTTP {}", response.status());
         }
 
-        let json_response: JsonRpcResponse = response.json().await?;
+        let is_event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let json_response: JsonRpcResponse = if is_event_stream {
+            let body = response.text().await?;
+            super::auth::parse_sse_messages(&body)
+                .into_iter()
+                .filter_map(|m| serde_json::from_value::<JsonRpcResponse>(m).ok())
+                .find(|r| r.id == Some(id))
+                .ok_or_else(|| anyhow::anyhow!("No response in event stream"))?
+        } else {
+            response.json().await?
+        };
 
         if let Some(error) = json_response.error {
             anyhow::bail!("JSON-RPC error {}: {}", error.code, error.message);
@@ -500,6 +595,7 @@ impl McpRegistry {
         &self,
         endpoint: &str,
         headers: &HashMap<String, String>,
+        auth: Option<&McpAuth>,
     ) -> anyhow::Result<InitializeResult> {
         let params = InitializeParams {
             protocol_version: MCP_PROTOCOL_VERSION.to_string(),
@@ -510,33 +606,32 @@ impl McpRegistry {
             },
         };
 
+        // A new initialize starts a new session
+        self.http_sessions.write().await.remove(endpoint);
+
         let result = self
             .send_jsonrpc_http(
                 endpoint,
                 "initialize",
                 Some(serde_json::to_value(params)?),
                 headers,
+                auth,
             )
             .await?;
 
         let init_result: InitializeResult = serde_json::from_value(result)?;
 
         // Send initialized notification (no response expected, but some servers require it)
-        let mut req_builder = self
-            .http_client
-            .post(endpoint)
-            .header("Content-Type", "application/json");
-
-        for (key, value) in headers {
-            req_builder = req_builder.header(key.as_str(), value.as_str());
-        }
-
-        let _ = req_builder
-            .json(&serde_json::json!({
-                "jsonrpc": "2.0",
-                "method": "notifications/initialized"
-            }))
-            .send()
+        let _ = self
+            .post_http(
+                endpoint,
+                headers,
+                auth,
+                &serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/initialized"
+                }),
+            )
             .await;
 
         Ok(init_result)
@@ -767,6 +862,12 @@ impl McpRegistry {
                 if let Some(state) = states.get_mut(&id) {
                     state.status = McpStatus::Error;
                     state.error = Some(error_msg);
+                    state.pending_start = false;
+                    state.consecutive_failures += 1;
+                    let backoff = restart_backoff(state.consecutive_failures);
+                    state.next_retry_at = chrono::Duration::from_std(backoff)
+                        .ok()
+                        .map(|d| chrono::Utc::now() + d);
                 }
                 return;
             }
@@ -798,6 +899,9 @@ impl McpRegistry {
                     state.config.last_connected_at = Some(chrono::Utc::now());
                     state.status = McpStatus::Connected;
                     state.error = None;
+                    state.pending_start = false;
+                    state.consecutive_failures = 0;
+                    state.next_retry_at = None;
                 }
                 return;
             }
@@ -822,8 +926,12 @@ impl McpRegistry {
         }
 
         match &state.config.transport {
-            McpTransport::Http { endpoint, headers } => {
-                self.refresh_http(id, endpoint.clone(), headers.clone())
+            McpTransport::Http {
+                endpoint,
+                headers,
+                auth,
+            } => {
+                self.refresh_http(id, endpoint.clone(), headers.clone(), auth.clone())
                     .await
             }
             McpTransport::Stdio { command, args, env } => {
@@ -839,11 +947,15 @@ impl McpRegistry {
         id: Uuid,
         endpoint: String,
         headers: HashMap<String, String>,
+        auth: Option<McpAuth>,
     ) -> anyhow::Result<McpServerState> {
         let endpoint = endpoint.trim_end_matches('/').to_string();
 
         // Step 1: Initialize the MCP connection with JSON-RPC
-        let init_result = match self.initialize_mcp_http(&endpoint, &headers).await {
+        let init_result = match self
+            .initialize_mcp_http(&endpoint, &headers, auth.as_ref())
+            .await
+        {
             Ok(result) => result,
             Err(e) => {
                 self.update_state_error(id, format!("Initialize failed: {}", e))
@@ -863,7 +975,7 @@ impl McpRegistry {
 
         // Step 2: List tools using JSON-RPC
         match self
-            .send_jsonrpc_http(&endpoint, "tools/list", None, &headers)
+            .send_jsonrpc_http(&endpoint, "tools/list", None, &headers, auth.as_ref())
             .await
         {
             Ok(result) => {
@@ -1037,9 +1149,7 @@ impl McpRegistry {
             anyhow::bail!("MCP {} is disabled", state.config.name);
         }
 
-        if state.status != McpStatus::Connected {
-            anyhow::bail!("MCP {} is not connected", state.config.name);
-        }
+        let state = self.ensure_connected(state).await?;
 
         let params = serde_json::json!({
             "name": tool_name,
@@ -1047,9 +1157,13 @@ impl McpRegistry {
         });
 
         let result = match &state.config.transport {
-            McpTransport::Http { endpoint, headers } => {
+            McpTransport::Http {
+                endpoint,
+                headers,
+                auth,
+            } => {
                 let endpoint = endpoint.trim_end_matches('/');
-                self.send_jsonrpc_http(endpoint, "tools/call", Some(params), headers)
+                self.send_jsonrpc_http(endpoint, "tools/call", Some(params), headers, auth.as_ref())
                     .await
             }
             McpTransport::Stdio { .. } => {
@@ -1100,15 +1214,190 @@ impl McpRegistry {
             }
             Err(e) => {
                 // Increment error counter
-                let mut states = self.states.write().await;
-                if let Some(state) = states.get_mut(&mcp_id) {
-                    state.tool_errors += 1;
+                {
+                    let mut states = self.states.write().await;
+                    if let Some(state) = states.get_mut(&mcp_id) {
+                        state.tool_errors += 1;
+                    }
+                }
+                // A dead stdio process is restarted by the next call or health check.
+                if !self.stdio_process_alive(mcp_id).await.unwrap_or(true) {
+                    self.update_state_error(mcp_id, "Process exited".to_string())
+                        .await;
                 }
                 anyhow::bail!("Tool call failed: {}", e);
             }
         }
     }
 
+    /// Connect all enabled servers at startup. With lazy start, stdio servers
+    /// whose tools are known from a previous connection are deferred until
+    /// their first tool call.
+    pub async fn start_all(&self) {
+        let lazy = lazy_start_enabled();
+        let mut ids = Vec::new();
+        {
+            let mut states = self.states.write().await;
+            for state in states.values_mut() {
+                let deferrable = matches!(state.config.transport, McpTransport::Stdio { .. })
+                    && !state.config.tool_descriptors.is_empty();
+                if lazy && state.config.enabled && deferrable {
+                    state.pending_start = true;
+                } else {
+                    ids.push(state.config.id);
+                }
+            }
+        }
+        let futures: Vec<_> = ids.iter().map(|id| self.refresh(*id)).collect();
+        futures::future::join_all(futures).await;
+    }
+
+    /// Connect a server that is not connected yet: lazily started servers are
+    /// started, failed ones restarted once their backoff has elapsed.
+    async fn ensure_connected(&self, state: McpServerState) -> anyhow::Result<McpServerState> {
+        if state.status == McpStatus::Connected {
+            return Ok(state);
+        }
+        if !state.pending_start {
+            if let Some(retry_at) = state.next_retry_at {
+                let wait = retry_at - chrono::Utc::now();
+                if wait > chrono::Duration::zero() {
+                    anyhow::bail!(
+                        "MCP {} is unavailable ({}); next restart attempt in {}s",
+                        state.config.name,
+                        state.error.as_deref().unwrap_or("not connected"),
+                        wait.num_seconds().max(1)
+                    );
+                }
+            }
+        }
+
+        let id = state.config.id;
+        let state = if state.pending_start {
+            tracing::info!(mcp = %state.config.name, "Starting MCP on first tool use");
+            self.refresh(id).await?
+        } else {
+            self.restart(id).await?
+        };
+        if state.status != McpStatus::Connected {
+            anyhow::bail!(
+                "MCP {} is not connected: {}",
+                state.config.name,
+                state.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        Ok(state)
+    }
+
+    /// Reconnect a failed server, counting it as a restart.
+    async fn restart(&self, id: Uuid) -> anyhow::Result<McpServerState> {
+        {
+            let mut states = self.states.write().await;
+            if let Some(state) = states.get_mut(&id) {
+                state.restarts += 1;
+                tracing::info!(
+                    mcp = %state.config.name,
+                    attempt = state.consecutive_failures + 1,
+                    "Restarting MCP"
+                );
+            }
+        }
+        self.refresh(id).await
+    }
+
+    /// Whether the stdio process of a server is still running. `None` when
+    /// there is no process, or it is busy serving a request.
+    async fn stdio_process_alive(&self, id: Uuid) -> Option<bool> {
+        let process = self.stdio_processes.read().await.get(&id).cloned()?;
+        let mut proc = process.try_lock().ok()?;
+        Some(matches!(proc.child.try_wait(), Ok(None)))
+    }
+
+    /// Probe a connected server. Stdio servers are checked for a live process
+    /// (a JSON-RPC probe would queue behind long-running tool calls); HTTP
+    /// servers must answer `tools/list`.
+    async fn probe(&self, state: &McpServerState) -> anyhow::Result<()> {
+        match &state.config.transport {
+            McpTransport::Stdio { .. } => {
+                let has_process = self
+                    .stdio_processes
+                    .read()
+                    .await
+                    .contains_key(&state.config.id);
+                if !has_process || self.stdio_process_alive(state.config.id).await == Some(false) {
+                    anyhow::bail!("Process exited");
+                }
+                Ok(())
+            }
+            McpTransport::Http {
+                endpoint,
+                headers,
+                auth,
+            } => {
+                let endpoint = endpoint.trim_end_matches('/');
+                tokio::time::timeout(
+                    MCP_HEALTH_CHECK_TIMEOUT,
+                    self.send_jsonrpc_http(endpoint, "tools/list", None, headers, auth.as_ref()),
+                )
+                .await
+                .map_err(|_| anyhow::anyhow!("Timed out"))??;
+                Ok(())
+            }
+        }
+    }
+
+    /// Run one round of health checks: probe connected servers and restart
+    /// failed ones whose backoff has elapsed. Lazily started servers that
+    /// haven't been used yet are left alone.
+    pub async fn health_check_all(&self) {
+        for state in self.list().await {
+            if !state.config.enabled || state.pending_start {
+                continue;
+            }
+            let id = state.config.id;
+            let mut failed = state.status != McpStatus::Connected;
+            if !failed {
+                let result = self.probe(&state).await;
+                if let Some(s) = self.states.write().await.get_mut(&id) {
+                    s.last_health_check = Some(chrono::Utc::now());
+                }
+                if let Err(e) = result {
+                    tracing::warn!(mcp = %state.config.name, error = %e, "MCP health check failed");
+                    self.update_state_error(id, format!("Health check failed: {}", e))
+                        .await;
+                    failed = true;
+                }
+            }
+            if !failed {
+                continue;
+            }
+            let due = self
+                .get(id)
+                .await
+                .is_some_and(|s| !matches!(s.next_retry_at, Some(t) if t > chrono::Utc::now()));
+            if due {
+                let _ = self.restart(id).await;
+            }
+        }
+    }
+
+    /// Run [`Self::health_check_all`] periodically while the registry is alive.
+    pub fn start_health_checks(self: &Arc<Self>) {
+        let Some(interval) = health_check_interval() else {
+            return;
+        };
+        let registry = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(registry) = registry.upgrade() else {
+                    break;
+                };
+                registry.health_check_all().await;
+            }
+        });
+    }
+
     /// List all tools from all connected MCPs.
     ///
     /// Tool names are prefixed with the MCP server name to avoid conflicts
@@ -1119,7 +1408,7 @@ impl McpRegistry {
 
         let mut tools = Vec::new();
         for state in states.values() {
-            if state.config.enabled && state.status == McpStatus::Connected {
+            if state.tools_available() {
                 // Derive prefix from MCP server name (sanitized for function names)
                 let prefix = sanitize_mcp_prefix(&state.config.name);
 
@@ -1166,7 +1455,7 @@ impl McpRegistry {
         let disabled = self.disabled_tools.read().await;
 
         for state in states.values() {
-            if state.config.enabled && state.status == McpStatus::Connected {
+            if state.tools_available() {
                 let prefix = sanitize_mcp_prefix(&state.config.name);
 
                 for descriptor in &state.config.tool_descriptors {
@@ -1205,3 +1494,42 @@ impl McpRegistry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_backoff_doubles_up_to_the_cap() {
+        assert_eq!(restart_backoff(1), Duration::from_secs(5));
+        assert_eq!(restart_backoff(2), Duration::from_secs(10));
+        assert_eq!(restart_backoff(4), Duration::from_secs(40));
+        assert_eq!(restart_backoff(7), RESTART_BACKOFF_MAX);
+        assert_eq!(restart_backoff(u32::MAX), RESTART_BACKOFF_MAX);
+    }
+
+    #[test]
+    fn lazily_started_servers_offer_cached_tools() {
+        let mut config = McpServerConfig::new_stdio(
+            "fs".to_string(),
+            "fs-mcp".to_string(),
+            vec![],
+            HashMap::new(),
+        );
+        let mut state = McpServerState::from_config(config.clone());
+        state.pending_start = true;
+        assert!(!state.tools_available(), "no tools known yet");
+
+        config.tool_descriptors.push(McpToolDescriptor {
+            name: "read".to_string(),
+            description: String::new(),
+            input_schema: serde_json::json!({}),
+        });
+        state.config = config;
+        assert!(state.tools_available());
+
+        state.pending_start = false;
+        state.status = McpStatus::Error;
+        assert!(!state.tools_available());
+    }
+}
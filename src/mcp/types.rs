@@ -12,6 +12,9 @@ pub enum McpTransport {
         endpoint: String,
         #[serde(default)]
         headers: std::collections::HashMap<String, String>,
+        /// Authentication applied on top of `headers`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auth: Option<McpAuth>,
     },
     /// Stdio transport (spawn process, communicate via stdin/stdout)
     Stdio {
@@ -28,10 +31,32 @@ impl Default for McpTransport {
         McpTransport::Http {
             endpoint: "http://127.0.0.1:3000".to_string(),
             headers: std::collections::HashMap::new(),
+            auth: None,
         }
     }
 }
 
+/// Authentication for HTTP MCP servers.
+///
+/// Secret values may reference environment variables as `${VAR}` or be
+/// encrypted with the library key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum McpAuth {
+    /// Static bearer token
+    Bearer { token: String },
+    /// OAuth 2.0 client-credentials grant; tokens are cached and refreshed
+    OauthClientCredentials {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default)]
+        scopes: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        audience: Option<String>,
+    },
+}
+
 /// Status of an MCP server connection.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -204,6 +229,7 @@ impl McpServerConfig {
             transport: McpTransport::Http {
                 endpoint,
                 headers: std::collections::HashMap::new(),
+                auth: None,
             },
             scope: McpScope::Global,
             description: None,
@@ -255,6 +281,15 @@ pub struct McpServerState {
     pub tool_calls: u64,
     /// Number of failed tool calls
     pub tool_errors: u64,
+    /// Start deferred until the first tool call (tools come from the last connection)
+    pub pending_start: bool,
+    /// Automatic restarts after a failed health check or call
+    pub restarts: u32,
+    /// Failed (re)connect attempts since the last successful one
+    pub consecutive_failures: u32,
+    pub last_health_check: Option<chrono::DateTime<chrono::Utc>>,
+    /// Earliest time of the next automatic restart attempt
+    pub next_retry_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl McpServerState {
@@ -270,8 +305,21 @@ impl McpServerState {
             error: None,
             tool_calls: 0,
             tool_errors: 0,
+            pending_start: false,
+            restarts: 0,
+            consecutive_failures: 0,
+            last_health_check: None,
+            next_retry_at: None,
         }
     }
+
+    /// Whether tools of this server can be offered: connected, or waiting
+    /// for a lazy start with tools known from a previous connection.
+    pub fn tools_available(&self) -> bool {
+        self.config.enabled
+            && (self.status == McpStatus::Connected
+                || (self.pending_start && !self.config.tool_descriptors.is_empty()))
+    }
 }
 
 /// A tool exposed by an MCP server.
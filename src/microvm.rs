@@ -0,0 +1,995 @@
+//! microVM workspaces (Cloud Hypervisor or Firecracker).
+//!
+//! A container workspace whose template sets `microvm` runs in its own
+//! virtual machine instead of a namespace sandbox, for untrusted code that
+//! should not share the host kernel. The root filesystem is built exactly like
+//! a container's (debootstrap + template init scripts); the VM boots from it:
+//!
+//! - Cloud Hypervisor shares the rootfs directory with the guest over
+//!   virtio-fs, so host-side file staging keeps working as with containers.
+//! - Firecracker has no shared filesystem: on first boot the rootfs directory
+//!   (or a prebuilt `rootfs_image`) becomes a per-workspace ext4 disk, which is
+//!   the source of truth from then on. Host-side files are reached through the
+//!   agent's file API.
+//!
+//! The guest runs `sandboxed-vm-agent` as PID 1. Commands and file transfers go
+//! over vsock using the hypervisor's hybrid vsock socket (`CONNECT <port>`),
+//! one JSON message per line. On the host, `sandboxed-vm-agent exec` bridges a
+//! process's stdio to a guest command, so `WorkspaceExec` can treat a VM like
+//! any other isolation backend.
+//!
+//! VM state lives next to the rootfs in `<workspace path>.vm/`.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::Ipv4Addr;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// vsock port the guest agent listens on.
+pub const AGENT_PORT: u32 = 1024;
+/// Agent binary name, on the host and in the guest.
+pub const AGENT_BIN: &str = "sandboxed-vm-agent";
+const GUEST_AGENT_PATH: &str = "/usr/local/bin/sandboxed-vm-agent";
+const GUEST_CID: u32 = 3;
+const BOOT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Serializes boots so concurrent commands don't start a VM twice.
+static BOOT_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hypervisor {
+    CloudHypervisor,
+    Firecracker,
+}
+
+impl Hypervisor {
+    pub fn binary(self) -> &'static str {
+        match self {
+            Self::CloudHypervisor => "cloud-hypervisor",
+            Self::Firecracker => "firecracker",
+        }
+    }
+
+    /// Whether the hypervisor (and, for Cloud Hypervisor, virtiofsd) is installed.
+    pub fn available(self) -> bool {
+        if !cfg!(target_os = "linux") || !crate::host::command_on_path(self.binary()) {
+            return false;
+        }
+        match self {
+            Self::CloudHypervisor => virtiofsd_binary().is_some(),
+            Self::Firecracker => true,
+        }
+    }
+}
+
+/// Hypervisors usable on this host, in order of preference.
+pub fn available_hypervisors() -> Vec<Hypervisor> {
+    [Hypervisor::CloudHypervisor, Hypervisor::Firecracker]
+        .into_iter()
+        .filter(|h| h.available())
+        .collect()
+}
+
+fn virtiofsd_binary() -> Option<PathBuf> {
+    crate::host::find_command("virtiofsd").or_else(|| {
+        ["/usr/libexec/virtiofsd", "/usr/lib/qemu/virtiofsd"]
+            .iter()
+            .map(PathBuf::from)
+            .find(|p| p.is_file())
+    })
+}
+
+fn default_vcpus() -> u32 {
+    2
+}
+
+fn default_memory_mib() -> u32 {
+    2048
+}
+
+fn default_disk_mib() -> u32 {
+    8192
+}
+
+/// microVM settings from a workspace template (`microvm`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MicroVmSpec {
+    /// Hypervisor to use; defaults to the first available one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hypervisor: Option<Hypervisor>,
+    #[serde(default = "default_vcpus")]
+    pub vcpus: u32,
+    #[serde(default = "default_memory_mib")]
+    pub memory_mib: u32,
+    /// Root disk size (Firecracker only)
+    #[serde(default = "default_disk_mib")]
+    pub disk_mib: u32,
+    /// Uncompressed guest kernel; defaults to `SANDBOXED_SH_MICROVM_KERNEL`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel: Option<String>,
+    /// Prebuilt ext4 root image (Firecracker only); built from the workspace
+    /// rootfs when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rootfs_image: Option<String>,
+}
+
+impl Default for MicroVmSpec {
+    fn default() -> Self {
+        Self {
+            hypervisor: None,
+            vcpus: default_vcpus(),
+            memory_mib: default_memory_mib(),
+            disk_mib: default_disk_mib(),
+            kernel: None,
+            rootfs_image: None,
+        }
+    }
+}
+
+impl MicroVmSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=64).contains(&self.vcpus) {
+            return Err("microvm.vcpus must be between 1 and 64".to_string());
+        }
+        if !(128..=262_144).contains(&self.memory_mib) {
+            return Err("microvm.memory_mib must be between 128 and 262144".to_string());
+        }
+        if self.disk_mib < 256 {
+            return Err("microvm.disk_mib must be at least 256".to_string());
+        }
+        if self.rootfs_image.is_some() && self.hypervisor == Some(Hypervisor::CloudHypervisor) {
+            return Err("microvm.rootfs_image is only supported with Firecracker".to_string());
+        }
+        Ok(())
+    }
+
+    /// The hypervisor this spec runs on, or an error naming what is missing.
+    pub fn resolve_hypervisor(&self) -> anyhow::Result<Hypervisor> {
+        match self.hypervisor {
+            Some(h) if h.available() => Ok(h),
+            Some(h) => Err(anyhow::anyhow!(
+                "{} is not available on this host{}",
+                h.binary(),
+                if h == Hypervisor::CloudHypervisor {
+                    " (cloud-hypervisor and virtiofsd are required)"
+                } else {
+                    ""
+                }
+            )),
+            None => available_hypervisors().into_iter().next().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "microVM workspaces need cloud-hypervisor (with virtiofsd) or firecracker on the host"
+                )
+            }),
+        }
+    }
+
+    fn kernel_path(&self) -> anyhow::Result<PathBuf> {
+        let kernel = self
+            .kernel
+            .clone()
+            .filter(|k| !k.trim().is_empty())
+            .or_else(|| std::env::var("SANDBOXED_SH_MICROVM_KERNEL").ok())
+            .filter(|k| !k.trim().is_empty())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No guest kernel configured; set microvm.kernel or SANDBOXED_SH_MICROVM_KERNEL"
+                )
+            })?;
+        let path = PathBuf::from(kernel);
+        if !path.is_file() {
+            return Err(anyhow::anyhow!(
+                "Guest kernel {} does not exist",
+                path.display()
+            ));
+        }
+        Ok(path)
+    }
+}
+
+/// Files of one workspace VM.
+#[derive(Debug, Clone)]
+pub struct VmPaths {
+    pub dir: PathBuf,
+    config: PathBuf,
+    api_socket: PathBuf,
+    pub vsock_socket: PathBuf,
+    fs_socket: PathBuf,
+    disk: PathBuf,
+    pub console_log: PathBuf,
+    pid: PathBuf,
+    fs_pid: PathBuf,
+}
+
+impl VmPaths {
+    pub fn for_root(root: &Path) -> Self {
+        let mut dir = root.to_path_buf().into_os_string();
+        dir.push(".vm");
+        let dir = PathBuf::from(dir);
+        Self {
+            config: dir.join("vm.json"),
+            api_socket: dir.join("api.sock"),
+            vsock_socket: dir.join("vsock.sock"),
+            fs_socket: dir.join("virtiofs.sock"),
+            disk: dir.join("rootfs.ext4"),
+            console_log: dir.join("console.log"),
+            pid: dir.join("vm.pid"),
+            fs_pid: dir.join("virtiofsd.pid"),
+            dir,
+        }
+    }
+}
+
+/// What `prepare` records for later boots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VmConfig {
+    spec: MicroVmSpec,
+    /// Give the guest NAT networking through a tap device
+    network: bool,
+}
+
+/// Whether the rootfs at `root` belongs to a microVM workspace.
+pub fn is_microvm_root(root: &Path) -> bool {
+    VmPaths::for_root(root).config.is_file()
+}
+
+fn read_config(paths: &VmPaths) -> anyhow::Result<VmConfig> {
+    let content = std::fs::read_to_string(&paths.config)
+        .map_err(|e| anyhow::anyhow!("microVM is not prepared: {}", e))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Record the VM settings and install the guest agent into the rootfs.
+pub async fn prepare(root: &Path, spec: &MicroVmSpec, network: bool) -> anyhow::Result<()> {
+    spec.validate().map_err(|e| anyhow::anyhow!(e))?;
+    let paths = VmPaths::for_root(root);
+    tokio::fs::create_dir_all(&paths.dir).await?;
+
+    let agent = host_agent_binary()
+        .ok_or_else(|| anyhow::anyhow!("{} binary not found next to sandboxed-sh", AGENT_BIN))?;
+    let guest_agent = root.join(GUEST_AGENT_PATH.trim_start_matches('/'));
+    if let Some(parent) = guest_agent.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::copy(&agent, &guest_agent).await?;
+    if network {
+        // The kernel configures eth0 from the `ip=` parameter but not DNS.
+        let _ = tokio::fs::write(
+            root.join("etc/resolv.conf"),
+            "nameserver 1.1.1.1\nnameserver 8.8.8.8\n",
+        )
+        .await;
+    }
+
+    let config = VmConfig {
+        spec: spec.clone(),
+        network,
+    };
+    tokio::fs::write(&paths.config, serde_json::to_vec_pretty(&config)?).await?;
+    Ok(())
+}
+
+fn read_pid(path: &Path) -> Option<i32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn pid_alive(pid: i32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists.
+    pid > 0 && unsafe { libc::kill(pid, 0) } == 0
+}
+
+/// Whether the workspace VM is running.
+pub fn is_running(root: &Path) -> bool {
+    read_pid(&VmPaths::for_root(root).pid).is_some_and(pid_alive)
+}
+
+/// Host-side tap device for a VM with networking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TapNet {
+    name: String,
+    host_ip: Ipv4Addr,
+    guest_ip: Ipv4Addr,
+    mac: String,
+}
+
+/// A /30 in 172.30.0.0/16 and a tap name derived from the rootfs path, so a
+/// workspace keeps its addresses across reboots.
+fn tap_for(root: &Path) -> TapNet {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    root.hash(&mut hasher);
+    let hash = hasher.finish();
+    let block = (hash & 0x3fff) as u32; // 16384 /30 blocks
+    let base = u32::from(Ipv4Addr::new(172, 30, 0, 0)) + block * 4;
+    TapNet {
+        name: format!("sbx{:08x}", (hash >> 16) as u32),
+        host_ip: Ipv4Addr::from(base + 1),
+        guest_ip: Ipv4Addr::from(base + 2),
+        mac: format!(
+            "06:00:{:02x}:{:02x}:{:02x}:{:02x}",
+            (base >> 24) as u8,
+            (base >> 16) as u8,
+            (base >> 8) as u8,
+            (base + 2) as u8
+        ),
+    }
+}
+
+fn kernel_cmdline(hypervisor: Hypervisor, net: Option<&TapNet>) -> String {
+    let mut cmdline = String::from("console=ttyS0 reboot=k panic=1 rw");
+    match hypervisor {
+        Hypervisor::CloudHypervisor => cmdline.push_str(" rootfstype=virtiofs root=rootfs"),
+        Hypervisor::Firecracker => cmdline.push_str(" pci=off root=/dev/vda"),
+    }
+    cmdline.push_str(" init=");
+    cmdline.push_str(GUEST_AGENT_PATH);
+    if let Some(net) = net {
+        cmdline.push_str(&format!(
+            " ip={}::{}:255.255.255.252::eth0:off",
+            net.guest_ip, net.host_ip
+        ));
+    }
+    cmdline
+}
+
+fn firecracker_config(
+    spec: &MicroVmSpec,
+    paths: &VmPaths,
+    kernel: &Path,
+    net: Option<&TapNet>,
+) -> serde_json::Value {
+    let mut config = serde_json::json!({
+        "boot-source": {
+            "kernel_image_path": kernel,
+            "boot_args": kernel_cmdline(Hypervisor::Firecracker, net),
+        },
+        "drives": [{
+            "drive_id": "rootfs",
+            "path_on_host": paths.disk,
+            "is_root_device": true,
+            "is_read_only": false,
+        }],
+        "machine-config": {
+            "vcpu_count": spec.vcpus,
+            "mem_size_mib": spec.memory_mib,
+        },
+        "vsock": {
+            "guest_cid": GUEST_CID,
+            "uds_path": paths.vsock_socket,
+        },
+    });
+    if let Some(net) = net {
+        config["network-interfaces"] = serde_json::json!([{
+            "iface_id": "eth0",
+            "host_dev_name": net.name,
+            "guest_mac": net.mac,
+        }]);
+    }
+    config
+}
+
+fn cloud_hypervisor_args(
+    spec: &MicroVmSpec,
+    paths: &VmPaths,
+    kernel: &Path,
+    net: Option<&TapNet>,
+) -> Vec<String> {
+    let mut args = vec![
+        "--api-socket".to_string(),
+        format!("path={}", paths.api_socket.display()),
+        "--kernel".to_string(),
+        kernel.display().to_string(),
+        "--cmdline".to_string(),
+        kernel_cmdline(Hypervisor::CloudHypervisor, net),
+        "--cpus".to_string(),
+        format!("boot={}", spec.vcpus),
+        // virtio-fs needs guest memory shared with virtiofsd.
+        "--memory".to_string(),
+        format!("size={}M,shared=on", spec.memory_mib),
+        "--fs".to_string(),
+        format!(
+            "tag=rootfs,socket={},num_queues=1,queue_size=1024",
+            paths.fs_socket.display()
+        ),
+        "--vsock".to_string(),
+        format!("cid={},socket={}", GUEST_CID, paths.vsock_socket.display()),
+        "--serial".to_string(),
+        format!("file={}", paths.console_log.display()),
+        "--console".to_string(),
+        "off".to_string(),
+    ];
+    if let Some(net) = net {
+        args.push("--net".to_string());
+        args.push(format!("tap={},mac={}", net.name, net.mac));
+    }
+    args
+}
+
+async fn run(program: &str, args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new(program).args(args).output().await?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+async fn setup_tap(net: &TapNet) -> anyhow::Result<()> {
+    let _ = run("ip", &["link", "del", &net.name]).await;
+    run("ip", &["tuntap", "add", "dev", &net.name, "mode", "tap"]).await?;
+    run(
+        "ip",
+        &[
+            "addr",
+            "add",
+            &format!("{}/30", net.host_ip),
+            "dev",
+            &net.name,
+        ],
+    )
+    .await?;
+    run("ip", &["link", "set", &net.name, "up"]).await?;
+    let _ = tokio::fs::write("/proc/sys/net/ipv4/ip_forward", "1").await;
+    let subnet = format!("{}/30", Ipv4Addr::from(u32::from(net.host_ip) - 1));
+    let rule = [
+        "-t",
+        "nat",
+        "POSTROUTING",
+        "-s",
+        &subnet,
+        "-j",
+        "MASQUERADE",
+    ];
+    let check: Vec<&str> = [&rule[..2], &["-C"], &rule[2..]].concat();
+    if run("iptables", &check).await.is_err() {
+        let add: Vec<&str> = [&rule[..2], &["-A"], &rule[2..]].concat();
+        run("iptables", &add).await?;
+    }
+    Ok(())
+}
+
+async fn teardown_tap(net: &TapNet) {
+    let _ = run("ip", &["link", "del", &net.name]).await;
+    let subnet = format!("{}/30", Ipv4Addr::from(u32::from(net.host_ip) - 1));
+    let _ = run(
+        "iptables",
+        &[
+            "-t",
+            "nat",
+            "-D",
+            "POSTROUTING",
+            "-s",
+            &subnet,
+            "-j",
+            "MASQUERADE",
+        ],
+    )
+    .await;
+}
+
+/// Spawn a long-lived process with output appended to the console log,
+/// reaping it in the background.
+fn spawn_detached(mut cmd: Command, paths: &VmPaths, pid_file: &Path) -> anyhow::Result<()> {
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&paths.console_log)?;
+    cmd.stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .kill_on_drop(false);
+    let mut child = cmd.spawn()?;
+    if let Some(pid) = child.id() {
+        std::fs::write(pid_file, pid.to_string())?;
+    }
+    tokio::spawn(async move {
+        let _ = child.wait().await;
+    });
+    Ok(())
+}
+
+fn console_tail(paths: &VmPaths) -> String {
+    let content = std::fs::read_to_string(&paths.console_log).unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    lines[lines.len().saturating_sub(20)..].join("\n")
+}
+
+/// Boot the workspace VM unless it is already running, and wait for the
+/// guest agent.
+pub async fn ensure_running(root: &Path) -> anyhow::Result<()> {
+    if is_running(root) {
+        return Ok(());
+    }
+    let _guard = BOOT_LOCK.lock().await;
+    if is_running(root) {
+        return Ok(());
+    }
+
+    let paths = VmPaths::for_root(root);
+    let config = read_config(&paths)?;
+    let spec = &config.spec;
+    let hypervisor = spec.resolve_hypervisor()?;
+    let kernel = spec.kernel_path()?;
+    for socket in [&paths.api_socket, &paths.vsock_socket, &paths.fs_socket] {
+        let _ = std::fs::remove_file(socket);
+    }
+    let _ = std::fs::write(&paths.console_log, "");
+
+    let net = config.network.then(|| tap_for(root));
+    if let Some(net) = net.as_ref() {
+        setup_tap(net).await?;
+    }
+
+    tracing::info!(
+        root = %root.display(),
+        hypervisor = hypervisor.binary(),
+        vcpus = spec.vcpus,
+        memory_mib = spec.memory_mib,
+        network = config.network,
+        "Booting workspace microVM"
+    );
+
+    match hypervisor {
+        Hypervisor::Firecracker => {
+            if !paths.disk.exists() {
+                build_disk(root, spec, &paths).await?;
+            }
+            let config_file = paths.dir.join("firecracker.json");
+            let fc_config = firecracker_config(spec, &paths, &kernel, net.as_ref());
+            std::fs::write(&config_file, serde_json::to_vec_pretty(&fc_config)?)?;
+            let mut cmd = Command::new(hypervisor.binary());
+            cmd.arg("--api-sock")
+                .arg(&paths.api_socket)
+                .arg("--config-file")
+                .arg(&config_file);
+            spawn_detached(cmd, &paths, &paths.pid)?;
+        }
+        Hypervisor::CloudHypervisor => {
+            let virtiofsd =
+                virtiofsd_binary().ok_or_else(|| anyhow::anyhow!("virtiofsd is not installed"))?;
+            let mut cmd = Command::new(virtiofsd);
+            cmd.arg(format!("--socket-path={}", paths.fs_socket.display()))
+                .arg(format!("--shared-dir={}", root.display()))
+                .arg("--cache=auto")
+                .arg("--sandbox=none");
+            spawn_detached(cmd, &paths, &paths.fs_pid)?;
+            wait_for(|| paths.fs_socket.exists(), Duration::from_secs(5)).await;
+
+            let mut cmd = Command::new(hypervisor.binary());
+            cmd.args(cloud_hypervisor_args(spec, &paths, &kernel, net.as_ref()));
+            spawn_detached(cmd, &paths, &paths.pid)?;
+        }
+    }
+
+    let vsock = paths.vsock_socket.clone();
+    let ready = wait_for(|| ping(&vsock).is_ok(), BOOT_TIMEOUT).await;
+    if !ready {
+        let tail = console_tail(&paths);
+        stop(root).await;
+        return Err(anyhow::anyhow!(
+            "microVM guest agent did not respond within {}s. Console:\n{}",
+            BOOT_TIMEOUT.as_secs(),
+            tail
+        ));
+    }
+    Ok(())
+}
+
+async fn wait_for(mut ready: impl FnMut() -> bool, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tokio::time::Instant::now() < deadline {
+        if ready() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    ready()
+}
+
+/// Turn the rootfs directory (or the spec's prebuilt image) into the
+/// Firecracker root disk.
+async fn build_disk(root: &Path, spec: &MicroVmSpec, paths: &VmPaths) -> anyhow::Result<()> {
+    if let Some(image) = spec.rootfs_image.as_deref() {
+        tokio::fs::copy(image, &paths.disk).await?;
+        let file = std::fs::OpenOptions::new().write(true).open(&paths.disk)?;
+        let size = u64::from(spec.disk_mib) * 1024 * 1024;
+        if file.metadata()?.len() < size {
+            file.set_len(size)?;
+        }
+        return Ok(());
+    }
+    run(
+        "mke2fs",
+        &[
+            "-q",
+            "-t",
+            "ext4",
+            "-d",
+            &root.to_string_lossy(),
+            &paths.disk.to_string_lossy(),
+            &format!("{}M", spec.disk_mib),
+        ],
+    )
+    .await
+}
+
+/// Stop the workspace VM (and virtiofsd) and remove its network.
+pub async fn stop(root: &Path) {
+    let paths = VmPaths::for_root(root);
+    for pid_file in [&paths.pid, &paths.fs_pid] {
+        if let Some(pid) = read_pid(pid_file).filter(|pid| pid_alive(*pid)) {
+            // SAFETY: the pid was recorded when we spawned the process.
+            unsafe {
+                libc::kill(pid, libc::SIGTERM);
+            }
+            let _ = wait_for(|| !pid_alive(pid), Duration::from_secs(5)).await;
+            if pid_alive(pid) {
+                // SAFETY: as above.
+                unsafe {
+                    libc::kill(pid, libc::SIGKILL);
+                }
+            }
+        }
+        let _ = std::fs::remove_file(pid_file);
+    }
+    if read_config(&paths).is_ok_and(|c| c.network) {
+        teardown_tap(&tap_for(root)).await;
+    }
+}
+
+/// Copy the VM settings and Firecracker disk of a stopped VM to a cloned
+/// rootfs at `to`. Sockets, pids and logs are left behind.
+pub async fn clone_state(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if status(from).running {
+        anyhow::bail!("stop the workspace VM before cloning it");
+    }
+    let (src, dst) = (VmPaths::for_root(from), VmPaths::for_root(to));
+    tokio::fs::create_dir_all(&dst.dir).await?;
+    tokio::fs::copy(&src.config, &dst.config).await?;
+    if src.disk.is_file() {
+        run(
+            "cp",
+            &[
+                "--sparse=always",
+                "--reflink=auto",
+                &src.disk.to_string_lossy(),
+                &dst.disk.to_string_lossy(),
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Stop the VM and delete its state (including a Firecracker disk).
+pub async fn destroy(root: &Path) {
+    stop(root).await;
+    let _ = tokio::fs::remove_dir_all(VmPaths::for_root(root).dir).await;
+}
+
+/// VM state reported by the workspace API.
+#[derive(Debug, Clone, Serialize)]
+pub struct VmStatus {
+    pub running: bool,
+    pub hypervisor: Option<Hypervisor>,
+    pub spec: Option<MicroVmSpec>,
+    pub network: bool,
+    pub pid: Option<i32>,
+}
+
+pub fn status(root: &Path) -> VmStatus {
+    let paths = VmPaths::for_root(root);
+    let config = read_config(&paths).ok();
+    let pid = read_pid(&paths.pid).filter(|pid| pid_alive(*pid));
+    VmStatus {
+        running: pid.is_some(),
+        hypervisor: config
+            .as_ref()
+            .and_then(|c| c.spec.resolve_hypervisor().ok()),
+        network: config.as_ref().is_some_and(|c| c.network),
+        spec: config.map(|c| c.spec),
+        pid,
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Guest agent protocol
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Message to the guest agent. Binary payloads are base64.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AgentRequest {
+    Ping,
+    /// Start a command; stdin follows as `stdin` messages
+    Exec {
+        argv: Vec<String>,
+        cwd: String,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+    },
+    Stdin {
+        data: String,
+    },
+    StdinEof,
+    ReadFile {
+        path: String,
+    },
+    WriteFile {
+        path: String,
+        data: String,
+    },
+}
+
+/// Message from the guest agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentReply {
+    Pong,
+    Stdout { data: String },
+    Stderr { data: String },
+    Exit { code: i32 },
+    File { data: String },
+    Ok,
+    Error { message: String },
+}
+
+pub fn encode(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+pub fn decode(data: &str) -> std::io::Result<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Write one protocol message as a JSON line.
+pub fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// Read one protocol message; `None` at end of stream.
+pub fn read_message<T: for<'de> Deserialize<'de>>(
+    reader: &mut impl BufRead,
+) -> std::io::Result<Option<T>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(line.trim_end())
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Connect to the guest agent through the hypervisor's vsock socket.
+pub fn connect(vsock_socket: &Path) -> std::io::Result<UnixStream> {
+    let mut stream = UnixStream::connect(vsock_socket)?;
+    stream.write_all(format!("CONNECT {}\n", AGENT_PORT).as_bytes())?;
+    // Read the acknowledgement byte by byte so no protocol data is buffered away.
+    let mut ack = Vec::new();
+    let mut byte = [0u8; 1];
+    while ack.len() < 64 {
+        if stream.read(&mut byte)? == 0 {
+            break;
+        }
+        if byte[0] == b'\n' {
+            break;
+        }
+        ack.push(byte[0]);
+    }
+    if !ack.starts_with(b"OK") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::ConnectionRefused,
+            format!(
+                "vsock connect refused: {}",
+                String::from_utf8_lossy(&ack).trim()
+            ),
+        ));
+    }
+    Ok(stream)
+}
+
+fn request(vsock_socket: &Path, message: &AgentRequest) -> std::io::Result<AgentReply> {
+    let stream = connect(vsock_socket)?;
+    stream.set_read_timeout(Some(Duration::from_secs(60)))?;
+    let mut writer = stream.try_clone()?;
+    write_message(&mut writer, message)?;
+    let mut reader = BufReader::new(stream);
+    read_message(&mut reader)?.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "guest agent closed the connection",
+        )
+    })
+}
+
+fn ping(vsock_socket: &Path) -> std::io::Result<()> {
+    match request(vsock_socket, &AgentRequest::Ping)? {
+        AgentReply::Pong => Ok(()),
+        other => Err(std::io::Error::other(format!(
+            "unexpected reply {:?}",
+            other
+        ))),
+    }
+}
+
+/// Read a file from the running VM.
+pub async fn read_file(root: &Path, path: &str) -> anyhow::Result<Vec<u8>> {
+    let vsock = VmPaths::for_root(root).vsock_socket;
+    let message = AgentRequest::ReadFile {
+        path: path.to_string(),
+    };
+    let reply = tokio::task::spawn_blocking(move || request(&vsock, &message)).await??;
+    match reply {
+        AgentReply::File { data } => Ok(decode(&data)?),
+        AgentReply::Error { message } => Err(anyhow::anyhow!(message)),
+        other => Err(anyhow::anyhow!("Unexpected agent reply: {:?}", other)),
+    }
+}
+
+/// Write a file inside the running VM, creating parent directories.
+pub async fn write_file(root: &Path, path: &str, data: &[u8]) -> anyhow::Result<()> {
+    let vsock = VmPaths::for_root(root).vsock_socket;
+    let message = AgentRequest::WriteFile {
+        path: path.to_string(),
+        data: encode(data),
+    };
+    let reply = tokio::task::spawn_blocking(move || request(&vsock, &message)).await??;
+    match reply {
+        AgentReply::Ok => Ok(()),
+        AgentReply::Error { message } => Err(anyhow::anyhow!(message)),
+        other => Err(anyhow::anyhow!("Unexpected agent reply: {:?}", other)),
+    }
+}
+
+/// The agent binary shipped next to the server executable.
+fn host_agent_binary() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(AGENT_BIN)))
+        .filter(|p| p.is_file())
+        .or_else(|| crate::host::find_command(AGENT_BIN))
+}
+
+/// Host command line running `program` in the VM with stdio bridged over
+/// vsock (`sandboxed-vm-agent exec`).
+pub fn exec_argv(
+    root: &Path,
+    cwd: &str,
+    env: &std::collections::HashMap<String, String>,
+    program: &str,
+    args: &[String],
+) -> Vec<String> {
+    let agent = host_agent_binary()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|| AGENT_BIN.to_string());
+    let mut argv = vec![
+        agent,
+        "exec".to_string(),
+        "--socket".to_string(),
+        VmPaths::for_root(root)
+            .vsock_socket
+            .to_string_lossy()
+            .into_owned(),
+        "--cwd".to_string(),
+        cwd.to_string(),
+    ];
+    let mut env: Vec<_> = env.iter().filter(|(k, _)| !k.trim().is_empty()).collect();
+    env.sort();
+    for (key, value) in env {
+        argv.push("--env".to_string());
+        argv.push(format!("{}={}", key, value));
+    }
+    argv.push("--".to_string());
+    argv.push(program.to_string());
+    argv.extend(args.iter().cloned());
+    argv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_defaults_and_validation() {
+        let spec: MicroVmSpec = serde_json::from_str(r#"{"vcpus": 4}"#).unwrap();
+        assert_eq!(spec.vcpus, 4);
+        assert_eq!(spec.memory_mib, 2048);
+        assert!(spec.validate().is_ok());
+
+        let bad = MicroVmSpec {
+            memory_mib: 64,
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+        let image_on_ch = MicroVmSpec {
+            hypervisor: Some(Hypervisor::CloudHypervisor),
+            rootfs_image: Some("/images/base.ext4".to_string()),
+            ..Default::default()
+        };
+        assert!(image_on_ch.validate().is_err());
+    }
+
+    #[test]
+    fn builds_hypervisor_configs() {
+        let root = Path::new("/containers/ws1");
+        let paths = VmPaths::for_root(root);
+        assert_eq!(paths.dir, Path::new("/containers/ws1.vm"));
+        let spec = MicroVmSpec::default();
+        let net = tap_for(root);
+        assert_eq!(net, tap_for(root));
+        assert!(net.name.len() <= 15);
+
+        let fc = firecracker_config(&spec, &paths, Path::new("/k/vmlinux"), Some(&net));
+        assert_eq!(fc["machine-config"]["vcpu_count"], 2);
+        assert_eq!(fc["vsock"]["uds_path"], "/containers/ws1.vm/vsock.sock");
+        let boot_args = fc["boot-source"]["boot_args"].as_str().unwrap();
+        assert!(boot_args.contains("root=/dev/vda"));
+        assert!(boot_args.contains(&format!("ip={}::{}", net.guest_ip, net.host_ip)));
+        assert_eq!(fc["network-interfaces"][0]["host_dev_name"], net.name);
+
+        let ch = cloud_hypervisor_args(&spec, &paths, Path::new("/k/vmlinux"), None);
+        let joined = ch.join(" ");
+        assert!(joined.contains("--memory size=2048M,shared=on"));
+        assert!(joined.contains("rootfstype=virtiofs root=rootfs"));
+        assert!(!joined.contains("--net"));
+    }
+
+    #[test]
+    fn protocol_round_trips_as_json_lines() {
+        let mut buf = Vec::new();
+        let exec = AgentRequest::Exec {
+            argv: vec!["ls".to_string(), "-la".to_string()],
+            cwd: "/root".to_string(),
+            env: BTreeMap::from([("A".to_string(), "1".to_string())]),
+        };
+        write_message(&mut buf, &exec).unwrap();
+        write_message(
+            &mut buf,
+            &AgentRequest::Stdin {
+                data: encode(b"hi\n"),
+            },
+        )
+        .unwrap();
+        let mut reader = std::io::Cursor::new(buf);
+        assert_eq!(
+            read_message::<AgentRequest>(&mut reader).unwrap(),
+            Some(exec)
+        );
+        match read_message::<AgentRequest>(&mut reader).unwrap() {
+            Some(AgentRequest::Stdin { data }) => assert_eq!(decode(&data).unwrap(), b"hi\n"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(read_message::<AgentRequest>(&mut reader).unwrap(), None);
+
+        let argv = exec_argv(
+            Path::new("/c/ws"),
+            "/root",
+            &std::collections::HashMap::from([("K".to_string(), "v".to_string())]),
+            "sh",
+            &["-c".to_string(), "true".to_string()],
+        );
+        let joined = argv[1..].join(" ");
+        assert_eq!(
+            joined,
+            "exec --socket /c/ws.vm/vsock.sock --cwd /root --env K=v -- sh -c true"
+        );
+    }
+}
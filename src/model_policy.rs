@@ -0,0 +1,197 @@
+//! Organization-wide model allow/deny list and data-residency constraints.
+//!
+//! A [`ModelPolicy`] in the global settings restricts which provider/model
+//! pairs can ever be used, whatever a mission or chain asks for:
+//!
+//! ```json
+//! {
+//!   "allow": ["anthropic/*", "mistral/*", "openrouter/*"],
+//!   "deny": ["openrouter/*:free"],
+//!   "allowed_regions": ["eu"],
+//!   "regions": { "mistral": "eu", "anthropic": "us", "openrouter/mistralai/*": "eu" }
+//! }
+//! ```
+//!
+//! Models are named `provider/model` and matched against `*` globs; a pattern
+//! without `/` matches every model of that provider. A model is allowed when
+//! `allow` is empty or matches it, `deny` does not match it, and, if
+//! `allowed_regions` is set, its hosting region (the longest matching
+//! `regions` pattern) is one of them. Models without a known region are
+//! refused once `allowed_regions` is set.
+//!
+//! The policy is enforced in the model-routing proxy, which skips chain
+//! entries it refuses, and at mission creation, which rejects model overrides
+//! (and chains) the policy leaves nothing of.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::glob_match;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPolicy {
+    /// `provider/model` patterns that may be used. Empty = all.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// `provider/model` patterns that may never be used.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// Hosting regions models must run in (e.g. `eu`). Empty = any.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_regions: Vec<String>,
+    /// Hosting region per provider or `provider/model` pattern.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub regions: BTreeMap<String, String>,
+}
+
+fn matches(pattern: &str, provider: &str, model: &str) -> bool {
+    let pattern = pattern.trim();
+    if pattern.contains('/') {
+        glob_match(pattern, &format!("{}/{}", provider, model))
+    } else {
+        glob_match(pattern, provider)
+    }
+}
+
+impl ModelPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty()
+            && self.deny.is_empty()
+            && self.allowed_regions.is_empty()
+            && self.regions.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let patterns = self
+            .allow
+            .iter()
+            .chain(&self.deny)
+            .chain(self.regions.keys());
+        for pattern in patterns {
+            if pattern.trim().is_empty() {
+                return Err("patterns must not be empty".to_string());
+            }
+        }
+        if self
+            .allowed_regions
+            .iter()
+            .chain(self.regions.values())
+            .any(|r| r.trim().is_empty())
+        {
+            return Err("regions must not be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Hosting region of a model: the longest matching `regions` pattern.
+    pub fn region_of(&self, provider: &str, model: &str) -> Option<&str> {
+        self.regions
+            .iter()
+            .filter(|(pattern, _)| matches(pattern, provider, model))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, region)| region.trim())
+    }
+
+    /// Check a provider/model pair; the error explains the refusal.
+    pub fn check(&self, provider: &str, model: &str) -> Result<(), String> {
+        let name = format!("{}/{}", provider, model);
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| matches(p, provider, model)) {
+            return Err(format!(
+                "model '{}' is not in the organization's model allow list",
+                name
+            ));
+        }
+        if let Some(pattern) = self.deny.iter().find(|p| matches(p, provider, model)) {
+            return Err(format!(
+                "model '{}' is denied by the organization's model policy ({})",
+                name,
+                pattern.trim()
+            ));
+        }
+        if !self.allowed_regions.is_empty() {
+            let allowed = self.allowed_regions.join(", ");
+            match self.region_of(provider, model) {
+                None => {
+                    return Err(format!(
+                    "model '{}' has no known hosting region; only models hosted in {} may be used",
+                    name, allowed
+                ))
+                }
+                Some(region)
+                    if !self
+                        .allowed_regions
+                        .iter()
+                        .any(|r| r.trim().eq_ignore_ascii_case(region)) =>
+                {
+                    return Err(format!(
+                        "model '{}' is hosted in {}; only models hosted in {} may be used",
+                        name, region, allowed
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The provider a backend's bare model IDs belong to, for backends that talk
+/// to a single provider directly.
+pub fn backend_provider(backend: &str) -> Option<&'static str> {
+    match backend {
+        "claudecode" => Some("anthropic"),
+        "codex" => Some("openai"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(value: serde_json::Value) -> ModelPolicy {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn allow_and_deny_lists() {
+        let policy = policy(serde_json::json!({
+            "allow": ["anthropic/*", "openrouter"],
+            "deny": ["openrouter/*:free", "anthropic/claude-3-haiku*"]
+        }));
+        assert!(policy.check("anthropic", "claude-sonnet-4").is_ok());
+        assert!(policy.check("openrouter", "qwen/qwen3-coder").is_ok());
+        let err = policy
+            .check("openrouter", "qwen/qwen3-coder:free")
+            .unwrap_err();
+        assert!(err.contains("denied"), "{}", err);
+        assert!(policy
+            .check("anthropic", "claude-3-haiku-20240307")
+            .is_err());
+        let err = policy.check("openai", "gpt-5").unwrap_err();
+        assert!(err.contains("allow list"), "{}", err);
+        assert!(ModelPolicy::default().check("openai", "gpt-5").is_ok());
+    }
+
+    #[test]
+    fn data_residency_uses_most_specific_region() {
+        let policy = policy(serde_json::json!({
+            "allowed_regions": ["eu"],
+            "regions": {
+                "mistral": "eu",
+                "openrouter": "us",
+                "openrouter/mistralai/*": "EU"
+            }
+        }));
+        assert!(policy.check("mistral", "mistral-large").is_ok());
+        assert!(policy
+            .check("openrouter", "mistralai/mistral-large")
+            .is_ok());
+        let err = policy.check("openrouter", "openai/gpt-5").unwrap_err();
+        assert!(err.contains("hosted in us"), "{}", err);
+        let err = policy.check("zai", "glm-5").unwrap_err();
+        assert!(err.contains("no known hosting region"), "{}", err);
+    }
+}
@@ -1,7 +1,8 @@
 //! systemd-nspawn container workspace creation and management.
 //!
 //! This module provides functionality to create isolated container environments
-//! for workspace execution using debootstrap/pacstrap and systemd-nspawn.
+//! for workspace execution using debootstrap/pacstrap and systemd-nspawn (or
+//! bubblewrap, see `crate::bwrap`).
 
 use std::collections::HashMap;
 use std::path::Path;
@@ -39,24 +40,6 @@ pub type NspawnResult<T> = Result<T, NspawnError>;
 
 use crate::util::env_var_bool;
 
-fn command_on_path(cmd: &str) -> bool {
-    if cmd.contains('/') {
-        return Path::new(cmd).is_file();
-    }
-    if let Ok(path_var) = std::env::var("PATH") {
-        for dir in path_var.split(':') {
-            if dir.trim().is_empty() {
-                continue;
-            }
-            let candidate = PathBuf::from(dir).join(cmd);
-            if candidate.is_file() {
-                return true;
-            }
-        }
-    }
-    false
-}
-
 /// Returns true if systemd-nspawn is available on this host.
 pub fn nspawn_available() -> bool {
     if !cfg!(target_os = "linux") {
@@ -65,7 +48,7 @@ pub fn nspawn_available() -> bool {
     if Path::new("/usr/bin/systemd-nspawn").is_file() {
         return true;
     }
-    command_on_path("systemd-nspawn")
+    crate::host::command_on_path("systemd-nspawn")
 }
 
 /// Whether we should allow container workspaces to fall back to host execution.
@@ -163,6 +146,8 @@ pub struct NspawnConfig {
     pub env: std::collections::HashMap<String, String>,
     pub binds: Vec<String>,
     pub capabilities: Vec<String>,
+    /// GPUs passed through to the container
+    pub gpu: Option<crate::gpu::GpuPassthrough>,
 }
 
 impl Default for NspawnConfig {
@@ -175,6 +160,7 @@ impl Default for NspawnConfig {
             env: std::collections::HashMap::new(),
             binds: Vec::new(),
             capabilities: Vec::new(),
+            gpu: None,
         }
     }
 }
@@ -540,17 +526,81 @@ async fn unmount_if_present(root: &Path, target: &str) -> NspawnResult<()> {
     Ok(())
 }
 
-/// Execute a command inside a container using systemd-nspawn.
-pub async fn execute_in_container(
-    path: &Path,
-    command: &[String],
-    config: &NspawnConfig,
-) -> NspawnResult<std::process::Output> {
-    if command.is_empty() {
-        return Err(NspawnError::NspawnExecution("Empty command".to_string()));
+/// Bind mounts shared by every one-shot container command, in systemd-nspawn
+/// syntax.
+fn container_binds(config: &NspawnConfig) -> Vec<String> {
+    let mut binds = Vec::new();
+    let tailscale_active = tailscale_enabled(&config.env);
+    let should_bind_dns = matches!(config.network_mode, NetworkMode::Host)
+        || (matches!(config.network_mode, NetworkMode::Private) && !tailscale_active);
+    if should_bind_dns && Path::new("/etc/resolv.conf").exists() {
+        binds.push("--bind-ro=/etc/resolv.conf".to_string());
+    }
+
+    for bind in &config.binds {
+        if bind.trim().is_empty() {
+            continue;
+        }
+        let has_dest = bind.contains(':');
+        if has_dest || Path::new(bind).exists() {
+            binds.push(format!("--bind={}", bind));
+        }
+    }
+
+    if let Some(bind) = crate::reference_repos::nspawn_bind_arg() {
+        binds.push(bind);
     }
+    binds.extend(crate::package_cache::nspawn_bind_args(&config.env));
 
-    let mut cmd = tokio::process::Command::new("systemd-nspawn");
+    if config.bind_x11 && Path::new("/tmp/.X11-unix").exists() {
+        binds.push("--bind=/tmp/.X11-unix".to_string());
+    }
+    binds
+}
+
+/// Command running `command` inside the container at `path` with the
+/// selected isolation backend (systemd-nspawn, or bubblewrap when nspawn is
+/// unavailable). Rootfs directories of microVM workspaces run the command in
+/// the VM, which must already be running.
+fn container_command(path: &Path, command: &[String], config: &NspawnConfig) -> Command {
+    let mut env = config.env.clone();
+    if let Some(display) = config.display.as_ref() {
+        env.insert("DISPLAY".to_string(), display.clone());
+    }
+    if let Some(gpu) = config.gpu.as_ref() {
+        gpu.apply_env(&mut env);
+    }
+
+    if crate::microvm::is_microvm_root(path) {
+        // Bind mounts and capabilities don't apply to a VM.
+        let argv = crate::microvm::exec_argv(path, "/", &env, &command[0], &command[1..]);
+        let mut cmd = Command::new(&argv[0]);
+        cmd.args(&argv[1..]);
+        return cmd;
+    }
+
+    if crate::bwrap::container_backend() == Some(crate::bwrap::ContainerBackend::Bwrap) {
+        // bwrap has no veth support: any non-host network mode gets a private
+        // namespace with loopback only. Ephemeral snapshots are not supported.
+        let opts = crate::bwrap::BwrapOptions {
+            network: match config.network_mode {
+                NetworkMode::Host => crate::bwrap::BwrapNetwork::Shared,
+                NetworkMode::Private | NetworkMode::None => crate::bwrap::BwrapNetwork::Private,
+            },
+            binds: container_binds(config)
+                .into_iter()
+                .chain(config.gpu.iter().flat_map(|gpu| gpu.nspawn_args()))
+                .collect(),
+            capabilities: config.capabilities.clone(),
+            env,
+            ..Default::default()
+        };
+        let mut cmd = Command::new("bwrap");
+        cmd.args(crate::bwrap::bwrap_args(path, &opts, command));
+        return cmd;
+    }
+
+    let mut cmd = Command::new("systemd-nspawn");
     cmd.arg("-D").arg(path);
     cmd.arg("--quiet");
     // Disable timezone bind-mount (minbase containers lack /usr/share/zoneinfo)
@@ -566,13 +616,6 @@ pub async fn execute_in_container(
         }
     }
 
-    let tailscale_active = tailscale_enabled(&config.env);
-    let should_bind_dns = matches!(config.network_mode, NetworkMode::Host)
-        || (matches!(config.network_mode, NetworkMode::Private) && !tailscale_active);
-    if should_bind_dns && Path::new("/etc/resolv.conf").exists() {
-        cmd.arg("--bind-ro=/etc/resolv.conf");
-    }
-
     if config.ephemeral {
         cmd.arg("--ephemeral");
     }
@@ -584,39 +627,46 @@ pub async fn execute_in_container(
         cmd.arg(format!("--capability={}", capability));
     }
 
-    for bind in &config.binds {
-        if bind.trim().is_empty() {
+    cmd.args(container_binds(config));
+    if let Some(gpu) = config.gpu.as_ref() {
+        cmd.args(gpu.nspawn_args());
+    }
+
+    for (key, value) in &env {
+        if key.trim().is_empty() {
             continue;
         }
-        let has_dest = bind.contains(':');
-        if has_dest || Path::new(bind).exists() {
-            cmd.arg(format!("--bind={}", bind));
-        }
+        cmd.arg(format!("--setenv={}={}", key, value));
     }
 
-    if config.bind_x11 && Path::new("/tmp/.X11-unix").exists() {
-        cmd.arg("--bind=/tmp/.X11-unix");
-    }
+    cmd.args(command);
+    cmd
+}
 
-    if let Some(display) = config.display.as_ref() {
-        cmd.arg(format!("--setenv=DISPLAY={}", display));
+/// Execute a command inside a container (systemd-nspawn or bubblewrap).
+pub async fn execute_in_container(
+    path: &Path,
+    command: &[String],
+    config: &NspawnConfig,
+) -> NspawnResult<std::process::Output> {
+    if command.is_empty() {
+        return Err(NspawnError::NspawnExecution("Empty command".to_string()));
     }
 
-    if !config.env.is_empty() {
-        for (key, value) in &config.env {
-            if key.trim().is_empty() {
-                continue;
-            }
-            cmd.arg(format!("--setenv={}={}", key, value));
-        }
+    if crate::microvm::is_microvm_root(path) {
+        crate::microvm::ensure_running(path)
+            .await
+            .map_err(|e| NspawnError::NspawnExecution(e.to_string()))?;
     }
-
-    cmd.args(command);
+    let mut cmd = container_command(path, command, config);
+    // Callers may bound the run with a timeout; don't leave the container behind.
+    cmd.kill_on_drop(true);
 
     let output = cmd.output().await.map_err(|e| {
         if e.kind() == std::io::ErrorKind::NotFound {
             NspawnError::NspawnExecution(
-                "systemd-nspawn not found. Install systemd-container on the host.".to_string(),
+                "systemd-nspawn not found. Install systemd-container (or bubblewrap) on the host."
+                    .to_string(),
             )
         } else {
             NspawnError::NspawnExecution(e.to_string())
@@ -633,6 +683,18 @@ pub async fn execute_in_container_streaming(
     command: &[String],
     config: &NspawnConfig,
     log_file: &Path,
+) -> NspawnResult<std::process::ExitStatus> {
+    execute_in_container_streaming_with(path, command, config, log_file, None).await
+}
+
+/// Like [`execute_in_container_streaming`], additionally forwarding each stdout
+/// line to `line_tx` as it is read.
+pub async fn execute_in_container_streaming_with(
+    path: &Path,
+    command: &[String],
+    config: &NspawnConfig,
+    log_file: &Path,
+    line_tx: Option<tokio::sync::mpsc::UnboundedSender<String>>,
 ) -> NspawnResult<std::process::ExitStatus> {
     use std::io::Write;
 
@@ -640,74 +702,20 @@ pub async fn execute_in_container_streaming(
         return Err(NspawnError::NspawnExecution("Empty command".to_string()));
     }
 
-    let mut cmd = Command::new("systemd-nspawn");
-    cmd.arg("-D").arg(path);
-    cmd.arg("--quiet");
-    cmd.arg("--timezone=off");
-
-    match config.network_mode {
-        NetworkMode::Host => {}
-        NetworkMode::Private => {
-            cmd.arg("--network-veth");
-        }
-        NetworkMode::None => {
-            cmd.arg("--private-network");
-        }
-    }
-
-    let tailscale_active = tailscale_enabled(&config.env);
-    let should_bind_dns = matches!(config.network_mode, NetworkMode::Host)
-        || (matches!(config.network_mode, NetworkMode::Private) && !tailscale_active);
-    if should_bind_dns && Path::new("/etc/resolv.conf").exists() {
-        cmd.arg("--bind-ro=/etc/resolv.conf");
-    }
-
-    if config.ephemeral {
-        cmd.arg("--ephemeral");
-    }
-
-    for capability in &config.capabilities {
-        if capability.trim().is_empty() {
-            continue;
-        }
-        cmd.arg(format!("--capability={}", capability));
+    if crate::microvm::is_microvm_root(path) {
+        crate::microvm::ensure_running(path)
+            .await
+            .map_err(|e| NspawnError::NspawnExecution(e.to_string()))?;
     }
-
-    for bind in &config.binds {
-        if bind.trim().is_empty() {
-            continue;
-        }
-        let has_dest = bind.contains(':');
-        if has_dest || Path::new(bind).exists() {
-            cmd.arg(format!("--bind={}", bind));
-        }
-    }
-
-    if config.bind_x11 && Path::new("/tmp/.X11-unix").exists() {
-        cmd.arg("--bind=/tmp/.X11-unix");
-    }
-
-    if let Some(display) = config.display.as_ref() {
-        cmd.arg(format!("--setenv=DISPLAY={}", display));
-    }
-
-    if !config.env.is_empty() {
-        for (key, value) in &config.env {
-            if key.trim().is_empty() {
-                continue;
-            }
-            cmd.arg(format!("--setenv={}={}", key, value));
-        }
-    }
-
-    cmd.args(command);
+    let mut cmd = container_command(path, command, config);
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
     let mut child = cmd.spawn().map_err(|e| {
         if e.kind() == std::io::ErrorKind::NotFound {
             NspawnError::NspawnExecution(
-                "systemd-nspawn not found. Install systemd-container on the host.".to_string(),
+                "systemd-nspawn not found. Install systemd-container (or bubblewrap) on the host."
+                    .to_string(),
             )
         } else {
             NspawnError::NspawnExecution(e.to_string())
@@ -732,6 +740,9 @@ pub async fn execute_in_container_streaming(
                 {
                     let _ = writeln!(f, "{}", line);
                 }
+                if let Some(tx) = &line_tx {
+                    let _ = tx.send(line);
+                }
             }
         }
     });
@@ -0,0 +1,210 @@
+//! Offline (air-gapped) mode.
+//!
+//! When `SANDBOXED_SH_OFFLINE` is enabled the server makes no calls outside
+//! the local network, so it can run on local models (Ollama, llama.cpp or any
+//! OpenAI-compatible server on the LAN) for sensitive codebases:
+//! - the model-routing proxy skips chain entries whose endpoint is not local
+//! - the tools in [`NETWORK_TOOLS`] (web lookups, pushes and fetches, image
+//!   builds, cluster and cloud access) are not registered, and the outbound
+//!   web proxy refuses non-local hosts
+//! - GitHub App tokens are only minted against a local API
+//! - the model catalog only queries local providers
+//! - update checks and webhook notifications to remote hosts are skipped
+//!
+//! "Local" means loopback, private (RFC 1918 / unique local) and link-local
+//! addresses, `localhost`, `.local`/`.internal`/`.lan` names and single-label
+//! host names such as Docker service names (`ollama`).
+
+use std::net::IpAddr;
+
+use crate::util::env_var_bool;
+
+/// Env var that enables offline mode. Missions get it from the server along
+/// with the other server-side settings.
+pub const OFFLINE_SETTING: &str = "SANDBOXED_SH_OFFLINE";
+
+/// Tools that need internet access; not registered in offline mode.
+pub const NETWORK_TOOLS: &[&str] = &[
+    "fetch_url",
+    "lookup_docs",
+    "package_info",
+    "checkout_reference_repo",
+    "git_push",
+    "git_rebase",
+    "gh_pr_diff",
+    "gh_pr_comment",
+    "gh_pr_review_threads",
+    "gh_pr_reply",
+    "gh_pr_review",
+    "gh_pr_open",
+    "tracker_get_issue",
+    "tracker_add_comment",
+    "tracker_transition",
+    "tracker_create_subtask",
+    "docker_build",
+    "docker_push",
+    "k8s_get",
+    "k8s_describe",
+    "k8s_logs",
+    "k8s_events",
+    "terraform_plan",
+];
+
+/// Tools that work without a network; every built-in tool is in exactly one
+/// of these two lists.
+pub const LOCAL_TOOLS: &[&str] = &[
+    "read_file",
+    "read_file_at",
+    "write_file",
+    "delete_file",
+    "list_directory",
+    "search_files",
+    "index_files",
+    "search_file_index",
+    "run_command",
+    "grep_search",
+    "watch_path",
+    "git_commit",
+    "git_create_branch",
+    "ui_optionList",
+    "ui_dataTable",
+    "analyze_codebase",
+    "deep_search",
+    "prepare_project",
+    "debug_error",
+    "reproduce_bug",
+    "detect_environment",
+    "analyze_logs",
+    "query_structured",
+    "patch_structured",
+    "inspect_data",
+    "read_notebook",
+    "edit_notebook_cell",
+    "compare_images",
+    "ocr_image",
+    "docker_run",
+    "desktop_start_session",
+    "desktop_stop_session",
+    "desktop_screenshot",
+    "desktop_type",
+    "desktop_click",
+    "desktop_get_text",
+    "desktop_mouse_move",
+    "desktop_scroll",
+    "desktop_i3_command",
+    "complete_mission",
+    "set_plan",
+    "update_plan",
+];
+
+/// Whether offline mode is enabled.
+pub fn offline_mode() -> bool {
+    env_var_bool(OFFLINE_SETTING, false)
+}
+
+/// Whether a value of [`OFFLINE_SETTING`] turns offline mode on.
+pub fn enabled(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "y" | "on"
+    )
+}
+
+/// Whether `host` (a name or IP address, without port) is on the local network.
+pub fn is_local_host(host: &str) -> bool {
+    let host = host
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_lowercase();
+    if host.is_empty() {
+        return false;
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return match ip {
+            IpAddr::V4(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local(),
+            IpAddr::V6(v6) => {
+                let first = v6.segments()[0];
+                v6.is_loopback()
+                    || (first & 0xfe00) == 0xfc00 // unique local
+                    || (first & 0xffc0) == 0xfe80 // link-local
+                    || v6.to_ipv4_mapped().is_some_and(|v4| {
+                        v4.is_loopback() || v4.is_private() || v4.is_link_local()
+                    })
+            }
+        };
+    }
+    !host.contains('.')
+        || host.ends_with(".localhost")
+        || host.ends_with(".local")
+        || host.ends_with(".internal")
+        || host.ends_with(".lan")
+}
+
+/// Whether `url` points at a local host. Unparseable URLs are not local.
+pub fn is_local_url(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(is_local_host))
+        .unwrap_or(false)
+}
+
+/// Refuse requests to non-local `url`s while offline mode is enabled.
+pub fn check_url(url: &str) -> Result<(), String> {
+    if offline_mode() && !is_local_url(url) {
+        return Err(format!("Offline mode: {} is not a local address", url));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_local_hosts() {
+        for host in [
+            "localhost",
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.1.20",
+            "172.16.0.5",
+            "::1",
+            "[fd00::1]",
+            "ollama",
+            "gpu-box.local",
+            "llm.internal",
+        ] {
+            assert!(is_local_host(host), "{} should be local", host);
+        }
+        for host in ["openrouter.ai", "8.8.8.8", "172.32.0.1", "2001:db8::1", ""] {
+            assert!(!is_local_host(host), "{} should not be local", host);
+        }
+    }
+
+    #[test]
+    fn classifies_local_urls() {
+        assert!(is_local_url("http://127.0.0.1:11434/v1"));
+        assert!(is_local_url("http://[::1]:8080/v1/chat/completions"));
+        assert!(!is_local_url("https://api.openai.com/v1"));
+        assert!(!is_local_url("not a url"));
+    }
+
+    #[test]
+    fn every_registered_tool_is_classified() {
+        let registry = crate::tools::ToolRegistry::new();
+        for tool in registry.list_tools() {
+            let network = NETWORK_TOOLS.contains(&tool.name.as_str());
+            let local = LOCAL_TOOLS.contains(&tool.name.as_str());
+            assert!(
+                network != local,
+                "{} must be in exactly one of NETWORK_TOOLS and LOCAL_TOOLS",
+                tool.name
+            );
+        }
+        for name in NETWORK_TOOLS {
+            assert!(!LOCAL_TOOLS.contains(name), "{} is in both lists", name);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Client for local Ollama servers.
+//!
+//! Ollama serves an OpenAI-compatible API under `/v1`, which the model-routing
+//! proxy uses for chat completions; the native API is only used to discover
+//! the models that have been pulled (`GET /api/tags`). Other local servers
+//! such as llama.cpp's `llama-server` are configured as custom providers with
+//! their OpenAI-compatible base URL.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// Where Ollama listens by default.
+pub const DEFAULT_BASE_URL: &str = "http://127.0.0.1:11434";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Ollama server root for a configured base URL, which may include `/v1`.
+fn server_root(base_url: Option<&str>) -> String {
+    let base = base_url
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .unwrap_or(DEFAULT_BASE_URL)
+        .trim_end_matches('/');
+    base.strip_suffix("/v1").unwrap_or(base).to_string()
+}
+
+/// OpenAI-compatible base URL (`.../v1`) for a configured base URL.
+pub fn openai_base_url(base_url: Option<&str>) -> String {
+    format!("{}/v1", server_root(base_url))
+}
+
+/// A model pulled on the Ollama server.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OllamaModel {
+    /// Model name with tag (e.g. `qwen2.5-coder:32b`)
+    pub name: String,
+    /// Size on disk in bytes
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub details: OllamaModelDetails,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub parameter_size: Option<String>,
+    #[serde(default)]
+    pub quantization_level: Option<String>,
+}
+
+impl OllamaModel {
+    /// Short description such as `qwen2 32.8B Q4_K_M`.
+    pub fn description(&self) -> Option<String> {
+        let parts: Vec<&str> = [
+            self.details.family.as_deref(),
+            self.details.parameter_size.as_deref(),
+            self.details.quantization_level.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+pub struct OllamaClient {
+    root: String,
+    http: reqwest::Client,
+}
+
+impl OllamaClient {
+    /// Client for the server at `base_url` (default [`DEFAULT_BASE_URL`]).
+    pub fn new(base_url: Option<&str>) -> Self {
+        Self {
+            root: server_root(base_url),
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn openai_base_url(&self) -> String {
+        format!("{}/v1", self.root)
+    }
+
+    /// Whether the server answers.
+    pub async fn is_available(&self) -> bool {
+        self.http
+            .get(format!("{}/api/version", self.root))
+            .send()
+            .await
+            .is_ok_and(|r| r.status().is_success())
+    }
+
+    /// Models pulled on the server.
+    pub async fn list_models(&self) -> anyhow::Result<Vec<OllamaModel>> {
+        let response = self
+            .http
+            .get(format!("{}/api/tags", self.root))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            anyhow::bail!("Ollama returned {}", response.status());
+        }
+        Ok(response.json::<TagsResponse>().await?.models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_base_urls() {
+        assert_eq!(openai_base_url(None), "http://127.0.0.1:11434/v1");
+        assert_eq!(
+            openai_base_url(Some("http://gpu-box:11434/")),
+            "http://gpu-box:11434/v1"
+        );
+        assert_eq!(
+            OllamaClient::new(Some("http://gpu-box:11434/v1")).openai_base_url(),
+            "http://gpu-box:11434/v1"
+        );
+    }
+
+    #[test]
+    fn parses_tags_response() {
+        let body = r#"{"models": [{
+            "name": "qwen2.5-coder:32b",
+            "modified_at": "2026-09-01T10:00:00Z",
+            "size": 19851349856,
+            "details": {"family": "qwen2", "parameter_size": "32.8B", "quantization_level": "Q4_K_M"}
+        }, {"name": "llama3.2"}]}"#;
+        let models = serde_json::from_str::<TagsResponse>(body).unwrap().models;
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].name, "qwen2.5-coder:32b");
+        assert_eq!(
+            models[0].description().as_deref(),
+            Some("qwen2 32.8B Q4_K_M")
+        );
+        assert!(models[1].description().is_none());
+    }
+}
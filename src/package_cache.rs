@@ -0,0 +1,371 @@
+//! Shared package manager download caches for container workspaces.
+//!
+//! Every container workspace has its own root filesystem, so parallel missions
+//! would each download the same crates, npm tarballs and wheels. The caches
+//! below live once on the host and are bind-mounted read-write into every
+//! `systemd-nspawn` run:
+//!
+//! | kind  | host (under the cache root) | container                     |
+//! |-------|-----------------------------|-------------------------------|
+//! | cargo | `cargo/registry`, `cargo/git` | `/root/.cargo/registry`, `/root/.cargo/git` |
+//! | npm   | `npm`                       | `/root/.npm/_cacache`         |
+//! | pip   | `pip`                       | `/root/.cache/pip`            |
+//!
+//! Concurrency: npm's cacache and pip's cache write entries atomically
+//! (temp file + rename), so sharing them is safe. Cargo serializes access
+//! with lock files in `CARGO_HOME`, so those lock files are bind-mounted from
+//! the shared cache as well; every container then takes the same `flock`.
+//! Purging takes the cargo lock too.
+//!
+//! Settings (workspace env vars or process env):
+//! - `SANDBOXED_SH_PACKAGE_CACHES` - comma-separated kinds to share (default
+//!   `cargo,npm,pip`), or `off`
+//! - `SANDBOXED_SH_PACKAGE_CACHE_DIR` - cache root (process env only; default
+//!   `{WORKING_DIR}/.sandboxed-sh/cache/packages`)
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+pub const CACHES_SETTING: &str = "SANDBOXED_SH_PACKAGE_CACHES";
+
+/// Cargo's lock files in `CARGO_HOME` (`.package-cache` guards downloads,
+/// `.package-cache-mutate` guards extraction on newer toolchains).
+const CARGO_LOCK_FILES: &[&str] = &[".package-cache", ".package-cache-mutate"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageCacheKind {
+    Cargo,
+    Npm,
+    Pip,
+}
+
+impl PackageCacheKind {
+    pub const ALL: [PackageCacheKind; 3] = [Self::Cargo, Self::Npm, Self::Pip];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Pip => "pip",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cargo" | "rust" | "crates" => Some(Self::Cargo),
+            "npm" | "node" => Some(Self::Npm),
+            "pip" | "python" | "pypi" => Some(Self::Pip),
+            _ => None,
+        }
+    }
+
+    /// `(host dir relative to the cache root, container path)` pairs.
+    fn mounts(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Cargo => &[
+                ("cargo/registry", "/root/.cargo/registry"),
+                ("cargo/git", "/root/.cargo/git"),
+            ],
+            Self::Npm => &[("npm", "/root/.npm/_cacache")],
+            Self::Pip => &[("pip", "/root/.cache/pip")],
+        }
+    }
+}
+
+/// Size of one cache on disk.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageCacheStats {
+    pub kind: PackageCacheKind,
+    pub path: String,
+    pub enabled: bool,
+    pub size_bytes: u64,
+    pub files: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Result of [`purge`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PurgeResult {
+    pub kind: PackageCacheKind,
+    pub freed_bytes: u64,
+}
+
+pub fn cache_root() -> PathBuf {
+    if let Some(dir) = std::env::var("SANDBOXED_SH_PACKAGE_CACHE_DIR")
+        .ok()
+        .filter(|d| !d.trim().is_empty())
+    {
+        return PathBuf::from(dir.trim());
+    }
+    let working_dir = std::env::var("WORKING_DIR").unwrap_or_else(|_| "/root".to_string());
+    PathBuf::from(working_dir)
+        .join(".sandboxed-sh")
+        .join("cache")
+        .join("packages")
+}
+
+/// Kinds enabled by `SANDBOXED_SH_PACKAGE_CACHES` (workspace env first).
+pub fn enabled_kinds(workspace_env: &HashMap<String, String>) -> Vec<PackageCacheKind> {
+    let raw = workspace_env
+        .get(CACHES_SETTING)
+        .cloned()
+        .or_else(|| std::env::var(CACHES_SETTING).ok())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty());
+    match raw.as_deref() {
+        None | Some("on" | "true" | "1" | "all") => PackageCacheKind::ALL.to_vec(),
+        Some("off" | "false" | "0" | "none") => Vec::new(),
+        Some(list) => {
+            let mut kinds: Vec<PackageCacheKind> = list
+                .split(',')
+                .filter_map(PackageCacheKind::parse)
+                .collect();
+            kinds.dedup();
+            kinds
+        }
+    }
+}
+
+/// `systemd-nspawn` bind arguments for the enabled caches, creating the host
+/// directories and cargo lock files on first use.
+pub fn nspawn_bind_args(workspace_env: &HashMap<String, String>) -> Vec<String> {
+    bind_args_in(&cache_root(), &enabled_kinds(workspace_env))
+}
+
+fn bind_args_in(root: &Path, kinds: &[PackageCacheKind]) -> Vec<String> {
+    let mut args = Vec::new();
+    for kind in kinds {
+        let mut binds = Vec::new();
+        for (host, container) in kind.mounts() {
+            binds.push((root.join(host), container.to_string()));
+        }
+        if *kind == PackageCacheKind::Cargo {
+            for lock in CARGO_LOCK_FILES {
+                binds.push((
+                    root.join("cargo").join(lock),
+                    format!("/root/.cargo/{}", lock),
+                ));
+            }
+        }
+        let ready = binds.iter().all(|(host, _)| ensure_exists(host));
+        if !ready {
+            tracing::warn!(
+                kind = kind.as_str(),
+                "Package cache directories unavailable; not sharing this cache"
+            );
+            continue;
+        }
+        args.extend(
+            binds
+                .into_iter()
+                .map(|(host, container)| format!("--bind={}:{}", host.display(), container)),
+        );
+    }
+    args
+}
+
+/// Create a cache directory, or an empty lock file for names starting with `.`.
+fn ensure_exists(path: &Path) -> bool {
+    let is_lock = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.'));
+    if is_lock {
+        if path.is_file() {
+            return true;
+        }
+        return path
+            .parent()
+            .map(|p| std::fs::create_dir_all(p).is_ok())
+            .unwrap_or(false)
+            && std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .is_ok();
+    }
+    path.is_dir() || std::fs::create_dir_all(path).is_ok()
+}
+
+fn kind_dirs(root: &Path, kind: PackageCacheKind) -> Vec<PathBuf> {
+    kind.mounts()
+        .iter()
+        .map(|(host, _)| root.join(host))
+        .collect()
+}
+
+fn usage(dirs: &[PathBuf]) -> (u64, u64, Option<DateTime<Utc>>) {
+    let mut size = 0;
+    let mut files = 0;
+    let mut last_modified: Option<DateTime<Utc>> = None;
+    for dir in dirs {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            size += meta.len();
+            files += 1;
+            if let Ok(modified) = meta.modified() {
+                let modified = DateTime::<Utc>::from(modified);
+                last_modified = Some(last_modified.map_or(modified, |m| m.max(modified)));
+            }
+        }
+    }
+    (size, files, last_modified)
+}
+
+/// Disk usage of every cache (walks the trees; run off the async runtime).
+pub fn stats() -> Vec<PackageCacheStats> {
+    stats_in(&cache_root(), &enabled_kinds(&HashMap::new()))
+}
+
+fn stats_in(root: &Path, enabled: &[PackageCacheKind]) -> Vec<PackageCacheStats> {
+    PackageCacheKind::ALL
+        .iter()
+        .map(|kind| {
+            let (size_bytes, files, last_modified) = usage(&kind_dirs(root, *kind));
+            PackageCacheStats {
+                kind: *kind,
+                path: root.join(kind.as_str()).display().to_string(),
+                enabled: enabled.contains(kind),
+                size_bytes,
+                files,
+                last_modified,
+            }
+        })
+        .collect()
+}
+
+/// Delete the contents of a cache. The directories themselves stay, since
+/// running containers have them bind-mounted.
+pub fn purge(kind: PackageCacheKind) -> anyhow::Result<PurgeResult> {
+    purge_in(&cache_root(), kind)
+}
+
+fn purge_in(root: &Path, kind: PackageCacheKind) -> anyhow::Result<PurgeResult> {
+    // Hold cargo's own lock so no build is mid-download while we delete.
+    let _lock = if kind == PackageCacheKind::Cargo {
+        let lock_path = root.join("cargo").join(CARGO_LOCK_FILES[0]);
+        if ensure_exists(&lock_path) {
+            let file = File::open(&lock_path)?;
+            file.lock_exclusive()?;
+            Some(file)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let dirs = kind_dirs(root, kind);
+    let (freed_bytes, _, _) = usage(&dirs);
+    for dir in &dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let result = if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+            if let Err(e) = result {
+                tracing::warn!("Failed to purge {}: {}", path.display(), e);
+            }
+        }
+    }
+    Ok(PurgeResult { kind, freed_bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_enabled_kinds() {
+        let env = |v: &str| HashMap::from([(CACHES_SETTING.to_string(), v.to_string())]);
+        assert_eq!(enabled_kinds(&env("off")), Vec::new());
+        assert_eq!(
+            enabled_kinds(&env("pip, cargo")),
+            vec![PackageCacheKind::Pip, PackageCacheKind::Cargo]
+        );
+        assert_eq!(enabled_kinds(&env("all")), PackageCacheKind::ALL.to_vec());
+    }
+
+    #[test]
+    fn bind_args_create_dirs_and_cargo_locks() {
+        let dir = tempfile::tempdir().unwrap();
+        let args = bind_args_in(
+            dir.path(),
+            &[PackageCacheKind::Cargo, PackageCacheKind::Npm],
+        );
+        let root = dir.path().display();
+        assert_eq!(
+            args,
+            vec![
+                format!("--bind={}/cargo/registry:/root/.cargo/registry", root),
+                format!("--bind={}/cargo/git:/root/.cargo/git", root),
+                format!(
+                    "--bind={}/cargo/.package-cache:/root/.cargo/.package-cache",
+                    root
+                ),
+                format!(
+                    "--bind={}/cargo/.package-cache-mutate:/root/.cargo/.package-cache-mutate",
+                    root
+                ),
+                format!("--bind={}/npm:/root/.npm/_cacache", root),
+            ]
+        );
+        assert!(dir.path().join("cargo/registry").is_dir());
+        assert!(dir.path().join("cargo/.package-cache").is_file());
+    }
+
+    #[test]
+    fn stats_report_usage_per_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("pip/http")).unwrap();
+        std::fs::write(dir.path().join("pip/http/a"), vec![0u8; 10]).unwrap();
+        std::fs::write(dir.path().join("pip/b"), vec![0u8; 5]).unwrap();
+
+        let stats = stats_in(dir.path(), &[PackageCacheKind::Pip]);
+        let pip = stats
+            .iter()
+            .find(|s| s.kind == PackageCacheKind::Pip)
+            .unwrap();
+        assert!(pip.enabled);
+        assert_eq!((pip.size_bytes, pip.files), (15, 2));
+        assert!(pip.last_modified.is_some());
+        let npm = stats
+            .iter()
+            .find(|s| s.kind == PackageCacheKind::Npm)
+            .unwrap();
+        assert!(!npm.enabled);
+        assert_eq!(npm.size_bytes, 0);
+    }
+
+    #[test]
+    fn purge_empties_but_keeps_mounted_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        bind_args_in(dir.path(), &[PackageCacheKind::Cargo]);
+        let crate_dir = dir.path().join("cargo/registry/cache/index/serde-1.0.0");
+        std::fs::create_dir_all(&crate_dir).unwrap();
+        std::fs::write(crate_dir.join("serde.crate"), vec![0u8; 42]).unwrap();
+
+        let result = purge_in(dir.path(), PackageCacheKind::Cargo).unwrap();
+        assert_eq!(result.freed_bytes, 42);
+        assert!(dir.path().join("cargo/registry").is_dir());
+        assert!(!dir.path().join("cargo/registry/cache").exists());
+        assert!(dir.path().join("cargo/.package-cache").is_file());
+    }
+}
@@ -0,0 +1,563 @@
+//! Policy-as-code guardrails for tool calls.
+//!
+//! Policies are JSON rule sets in the library `policy/` directory. They are
+//! served by the server (`api::mission_guard`) rather than synced into the
+//! mission directory the agent can write, and evaluated by workspace-mcp
+//! before every tool call:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "id": "prod-infra", "effect": "deny", "paths": ["/infra/prod/**"],
+//!       "message": "Production infrastructure is managed by the platform team" },
+//!     { "id": "lockfiles", "effect": "require_approval", "tools": ["git_commit"],
+//!       "paths": ["**/*.lock"], "unless": "cargo test --quiet" },
+//!     { "id": "curl-pipe", "effect": "warn", "tools": ["run_command"],
+//!       "args": { "command": "curl .*\\|\\s*(ba)?sh" } }
+//!   ]
+//! }
+//! ```
+//!
+//! A rule matches when every condition it sets holds:
+//! - `tools` - tool name patterns (`*` suffix wildcard)
+//! - `args` - regexes matched against the named arguments
+//! - `paths` - globs (`*`, `**`, `?`) matched against the files the call
+//!   touches: the path arguments the tool declares (`Tool::path_args`), plus
+//!   the staged diff for `git_commit`.
+//!   Patterns starting with `/` are anchored at the mission directory.
+//! - `unless` - shell command; the rule is waived when it exits 0
+//!
+//! Effects: `deny` refuses the call, `require_approval` holds it until an
+//! admin approves it in the approval queue (`api::approvals`), and `warn`
+//! lets it run with the warning appended to the result.
+//!
+//! Calls that name anything under `.sandboxed-sh/` are always refused: that
+//! directory holds the server's mission state.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::tool_pruning::pattern_matches;
+use crate::tools::terminal::{run_workspace_shell_with_stdin, shell_quote};
+use crate::tools::{path_arg_values, PATH_ARGS};
+
+/// Mission state kept by the server; no tool call may name it.
+const STATE_DIR: &str = ".sandboxed-sh";
+
+/// Timeout for `unless` commands and staged-diff lookups.
+const CONDITION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Tools whose `path` argument is a repository directory rather than a file.
+const REPO_PATH_TOOLS: &[&str] = &[
+    "git_commit",
+    "git_push",
+    "git_create_branch",
+    "git_rebase",
+    "gh_pr_open",
+];
+
+/// A library policy file as served to workspace-mcp.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyFile {
+    pub name: String,
+    /// Raw JSON rule set
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyEffect {
+    Deny,
+    RequireApproval,
+    Warn,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PolicyRule {
+    id: String,
+    effect: PolicyEffect,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    tools: Vec<String>,
+    #[serde(default)]
+    args: HashMap<String, String>,
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    unless: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PolicySet {
+    #[serde(default)]
+    rules: Vec<PolicyRule>,
+}
+
+/// A rule that matched a tool call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: String,
+    pub effect: PolicyEffect,
+    pub message: String,
+}
+
+/// Outcome of evaluating the policies for one tool call.
+#[derive(Debug, Default)]
+pub struct PolicyDecision {
+    pub violations: Vec<Violation>,
+}
+
+impl PolicyDecision {
+    /// Why the call must not run, if any rule refuses it.
+    pub fn refusal(&self) -> Option<String> {
+        self.first(PolicyEffect::Deny)
+            .map(|v| format!("Blocked by policy '{}': {}", v.rule, v.message))
+    }
+
+    /// Why the call needs an admin's approval before it runs, if it does.
+    pub fn approval_reason(&self) -> Option<String> {
+        self.first(PolicyEffect::RequireApproval)
+            .map(|v| format!("policy '{}': {}", v.rule, v.message))
+    }
+
+    /// Warnings to attach to the result of a call that ran.
+    pub fn warnings(&self) -> impl Iterator<Item = &Violation> {
+        self.violations
+            .iter()
+            .filter(|v| v.effect == PolicyEffect::Warn)
+    }
+
+    fn first(&self, effect: PolicyEffect) -> Option<&Violation> {
+        self.violations.iter().find(|v| v.effect == effect)
+    }
+}
+
+/// Convert a path glob to an anchored regex (`**` spans directories).
+fn glob_regex(glob: &str) -> Option<Regex> {
+    let mut re = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).ok()
+}
+
+/// Whether `glob` matches a touched path (relative to the mission dir when inside it).
+fn path_matches(glob: &str, path: &str) -> bool {
+    let Some(re) = glob_regex(glob) else {
+        return false;
+    };
+    if re.is_match(path) {
+        return true;
+    }
+    // Root-anchored globs ("/infra/**") refer to the mission directory.
+    !path.starts_with('/') && re.is_match(&format!("/{}", path))
+}
+
+fn arg_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Normalize a path argument relative to the mission directory.
+fn normalize_path(raw: &str, working_dir: &Path) -> String {
+    let path = Path::new(raw);
+    let rel = if path.is_absolute() {
+        path.strip_prefix(working_dir).unwrap_or(path)
+    } else {
+        path
+    };
+    let rel = rel.to_string_lossy();
+    rel.trim_start_matches("./").to_string()
+}
+
+/// Files named by the call's path arguments.
+fn argument_paths(tool: &str, path_args: &[&str], args: &Value, working_dir: &Path) -> Vec<String> {
+    let keys: Vec<&str> = path_args
+        .iter()
+        .copied()
+        .filter(|key| *key != "path" || !REPO_PATH_TOOLS.contains(&tool))
+        .collect();
+    path_arg_values(args, &keys)
+        .into_iter()
+        .map(|s| normalize_path(s, working_dir))
+        .collect()
+}
+
+/// Files a `git_commit` or `gh_pr_open` call would commit (staged, plus what
+/// it stages itself; `gh_pr_open` stages everything unless given files).
+async fn commit_paths(tool: &str, args: &Value, working_dir: &Path) -> Vec<String> {
+    let repo = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .map(|p| working_dir.join(p))
+        .unwrap_or_else(|| working_dir.to_path_buf());
+    let mut command = "git diff --cached --name-only".to_string();
+    let stages_all = args.get("all").and_then(|v| v.as_bool()) == Some(true)
+        || (tool == "gh_pr_open" && args.get("files").is_none());
+    if stages_all {
+        command.push_str(" && git diff --name-only && git ls-files --others --exclude-standard");
+    }
+    let Ok(output) =
+        run_workspace_shell_with_stdin(&repo, &command, HashMap::new(), None, CONDITION_TIMEOUT)
+            .await
+    else {
+        return Vec::new();
+    };
+    let prefix = repo
+        .strip_prefix(working_dir)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            if prefix.is_empty() {
+                l.to_string()
+            } else {
+                format!("{}/{}", prefix.trim_end_matches('/'), l)
+            }
+        })
+        .collect()
+}
+
+fn load_rules(mut files: Vec<PolicyFile>) -> Vec<PolicyRule> {
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut rules = Vec::new();
+    for file in files {
+        match serde_json::from_str::<PolicySet>(&file.content) {
+            Ok(set) => rules.extend(set.rules),
+            Err(e) => {
+                tracing::warn!(policy = %file.name, error = %e, "Invalid policy file");
+            }
+        }
+    }
+    rules
+}
+
+/// Whether the call names the server's mission state directory: in a path
+/// argument (the tool's own or a common one, including repository paths) or
+/// its shell command.
+fn touches_state_dir(path_args: &[&str], args: &Value, working_dir: &Path) -> bool {
+    let named = path_arg_values(args, path_args)
+        .into_iter()
+        .chain(path_arg_values(args, PATH_ARGS))
+        .map(|p| normalize_path(p, working_dir))
+        .any(|p| p.split('/').any(|part| part == STATE_DIR));
+    named
+        || args
+            .get("command")
+            .and_then(|v| v.as_str())
+            .is_some_and(|c| c.contains(STATE_DIR))
+}
+
+/// Check the rule's static conditions (tool, args, paths) against the call.
+fn rule_matches(rule: &PolicyRule, tool: &str, args: &Value, paths: &[String]) -> bool {
+    if !rule.tools.is_empty() && !rule.tools.iter().any(|p| pattern_matches(p, tool)) {
+        return false;
+    }
+    for (key, pattern) in &rule.args {
+        let Some(value) = args.get(key) else {
+            return false;
+        };
+        match Regex::new(pattern) {
+            Ok(re) if re.is_match(&arg_text(value)) => {}
+            Ok(_) => return false,
+            Err(e) => {
+                tracing::warn!(rule = %rule.id, error = %e, "Invalid policy argument regex");
+                return false;
+            }
+        }
+    }
+    if !rule.paths.is_empty()
+        && !paths
+            .iter()
+            .any(|path| rule.paths.iter().any(|glob| path_matches(glob, path)))
+    {
+        return false;
+    }
+    true
+}
+
+/// Evaluate the library policies against a proposed tool call. When the
+/// server cannot serve them the call is refused.
+pub async fn evaluate(
+    working_dir: &Path,
+    tool: &str,
+    path_args: &[&str],
+    args: &Value,
+) -> PolicyDecision {
+    match crate::tools::guard::fetch::<Vec<PolicyFile>>("policies").await {
+        Ok(files) => {
+            evaluate_rules(
+                load_rules(files.unwrap_or_default()),
+                working_dir,
+                tool,
+                path_args,
+                args,
+            )
+            .await
+        }
+        Err(e) => {
+            tracing::warn!(tool = %tool, error = %e, "Failed to load policies");
+            PolicyDecision {
+                violations: vec![Violation {
+                    rule: "policies".to_string(),
+                    effect: PolicyEffect::Deny,
+                    message: format!("policies could not be loaded ({})", e),
+                }],
+            }
+        }
+    }
+}
+
+async fn evaluate_rules(
+    rules: Vec<PolicyRule>,
+    working_dir: &Path,
+    tool: &str,
+    path_args: &[&str],
+    args: &Value,
+) -> PolicyDecision {
+    let mut decision = PolicyDecision::default();
+    if touches_state_dir(path_args, args, working_dir) {
+        decision.violations.push(Violation {
+            rule: "mission-state".to_string(),
+            effect: PolicyEffect::Deny,
+            message: format!("{} is managed by the server", STATE_DIR),
+        });
+        return decision;
+    }
+    if rules.is_empty() {
+        return decision;
+    }
+
+    let mut paths = argument_paths(tool, path_args, args, working_dir);
+    if matches!(tool, "git_commit" | "gh_pr_open") && rules.iter().any(|r| !r.paths.is_empty()) {
+        paths.extend(commit_paths(tool, args, working_dir).await);
+    }
+
+    for rule in rules {
+        if !rule_matches(&rule, tool, args, &paths) {
+            continue;
+        }
+        if let Some(command) = rule.unless.as_deref().filter(|c| !c.trim().is_empty()) {
+            let waived = run_workspace_shell_with_stdin(
+                working_dir,
+                &format!("sh -c {}", shell_quote(command)),
+                HashMap::new(),
+                None,
+                CONDITION_TIMEOUT,
+            )
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+            if waived {
+                continue;
+            }
+        }
+        tracing::info!(rule = %rule.id, effect = ?rule.effect, tool = %tool, "Policy rule matched");
+        decision.violations.push(Violation {
+            message: rule
+                .message
+                .clone()
+                .unwrap_or_else(|| format!("matched policy rule '{}'", rule.id)),
+            rule: rule.id,
+            effect: rule.effect,
+        });
+    }
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_glob_matching() {
+        assert!(path_matches("/infra/prod/**", "infra/prod/main.tf"));
+        assert!(path_matches("/infra/prod/**", "infra/prod/a/b.tf"));
+        assert!(!path_matches("/infra/prod/**", "src/infra/prod/main.tf"));
+        assert!(path_matches("**/*.lock", "Cargo.lock"));
+        assert!(path_matches("**/*.lock", "web/yarn.lock"));
+        assert!(!path_matches("*.lock", "web/yarn.lock"));
+        assert!(path_matches("src/?.rs", "src/a.rs"));
+        assert!(path_matches("/etc/**", "/etc/hosts"));
+    }
+
+    #[test]
+    fn test_argument_paths() {
+        let dir = Path::new("/work/mission");
+        assert_eq!(
+            argument_paths(
+                "write_file",
+                PATH_ARGS,
+                &json!({ "path": "/work/mission/./infra/x.tf" }),
+                dir
+            ),
+            vec!["infra/x.tf"]
+        );
+        assert_eq!(
+            argument_paths(
+                "git_commit",
+                PATH_ARGS,
+                &json!({ "path": "repo", "files": ["a.lock"] }),
+                dir
+            ),
+            vec!["a.lock"]
+        );
+        assert_eq!(
+            argument_paths(
+                "compare_images",
+                &["baseline", "current", "diff_output"],
+                &json!({ "baseline": "shots/a.png", "path": "ignored" }),
+                dir
+            ),
+            vec!["shots/a.png"]
+        );
+    }
+
+    #[test]
+    fn test_rule_matching_and_decision() {
+        let set: PolicySet = serde_json::from_str(
+            r#"{"rules": [
+                {"id": "prod", "effect": "deny", "paths": ["/infra/prod/**"]},
+                {"id": "rm", "effect": "require_approval", "tools": ["run_command"],
+                 "args": {"command": "rm\\s+-rf"}},
+                {"id": "curl", "effect": "warn", "tools": ["run_*"], "args": {"command": "curl"}}
+            ]}"#,
+        )
+        .unwrap();
+        let [prod, rm, curl] = &set.rules[..] else {
+            panic!("expected three rules");
+        };
+        let paths = vec!["infra/prod/main.tf".to_string()];
+        assert!(rule_matches(prod, "write_file", &json!({}), &paths));
+        assert!(!rule_matches(prod, "write_file", &json!({}), &[]));
+
+        let args = json!({ "command": "curl x && rm -rf build" });
+        assert!(rule_matches(rm, "run_command", &args, &[]));
+        assert!(!rule_matches(rm, "read_file", &args, &[]));
+        assert!(rule_matches(curl, "run_command", &args, &[]));
+
+        let decision = PolicyDecision {
+            violations: vec![
+                Violation {
+                    rule: "rm".to_string(),
+                    effect: PolicyEffect::RequireApproval,
+                    message: "destructive".to_string(),
+                },
+                Violation {
+                    rule: "curl".to_string(),
+                    effect: PolicyEffect::Warn,
+                    message: "network".to_string(),
+                },
+            ],
+        };
+        assert!(decision.refusal().is_none());
+        assert_eq!(
+            decision.approval_reason().as_deref(),
+            Some("policy 'rm': destructive")
+        );
+        assert_eq!(decision.warnings().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_unless_waives_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = load_rules(vec![PolicyFile {
+            name: "base".to_string(),
+            content: r#"{"rules": [
+                {"id": "waived", "effect": "deny", "tools": ["write_file"], "unless": "true"},
+                {"id": "kept", "effect": "deny", "tools": ["write_file"], "unless": "false",
+                 "message": "no writes"}
+            ]}"#
+            .to_string(),
+        }]);
+
+        let decision = evaluate_rules(
+            rules,
+            dir.path(),
+            "write_file",
+            PATH_ARGS,
+            &json!({ "path": "a.txt" }),
+        )
+        .await;
+        assert_eq!(decision.violations.len(), 1);
+        assert_eq!(
+            decision.refusal().as_deref(),
+            Some("Blocked by policy 'kept': no writes")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mission_state_is_off_limits() {
+        let dir = Path::new("/work/mission");
+        for (tool, args) in [
+            (
+                "write_file",
+                json!({ "path": "/work/mission/.sandboxed-sh/policies/a.json" }),
+            ),
+            ("git_commit", json!({ "path": ".sandboxed-sh" })),
+            (
+                "run_command",
+                json!({ "command": "rm -rf .sandboxed-sh/hooks" }),
+            ),
+            (
+                "run_command",
+                json!({ "command": "ls", "cwd": "./.sandboxed-sh" }),
+            ),
+        ] {
+            let decision = evaluate_rules(Vec::new(), dir, tool, PATH_ARGS, &args).await;
+            assert!(
+                decision
+                    .refusal()
+                    .is_some_and(|r| r.contains("mission-state")),
+                "{} {} was allowed",
+                tool,
+                args
+            );
+        }
+        let decision = evaluate_rules(
+            Vec::new(),
+            dir,
+            "compare_images",
+            &["baseline", "current", "diff_output"],
+            &json!({ "diff_output": ".sandboxed-sh/diff.png" }),
+        )
+        .await;
+        assert!(decision.refusal().is_some());
+        let decision = evaluate_rules(
+            Vec::new(),
+            dir,
+            "write_file",
+            PATH_ARGS,
+            &json!({ "path": "src/a.rs" }),
+        )
+        .await;
+        assert!(decision.violations.is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+//! Process-group management for backend CLI subprocesses.
+//!
+//! Backend CLIs (codex, claude, amp, opencode and the curl SSE readers) spawn
+//! their own children in the workspace: shells, language servers, dev
+//! servers. Killing only the direct child leaves those running after a
+//! mission is aborted. [`isolate`] makes a spawned command lead its own
+//! process group, and [`terminate`] takes down the whole tree: it signals the
+//! group and every descendant found under `/proc` (including ones that moved
+//! to a new session), escalates from SIGTERM to SIGKILL after a grace period,
+//! then re-checks which processes are still alive.
+//!
+//! On non-Linux hosts there is no `/proc` to walk, so only the group itself
+//! is signalled and the report is empty.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How long processes get to exit after SIGTERM before they are SIGKILLed.
+const TERM_GRACE: Duration = Duration::from_secs(3);
+/// How long to wait for SIGKILLed processes to disappear.
+const KILL_GRACE: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A process that was signalled while tearing down a backend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct KilledProcess {
+    pub pid: u32,
+    /// Executable name as reported by the kernel (`/proc/<pid>/comm`)
+    pub command: String,
+}
+
+/// Outcome of [`terminate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct KillReport {
+    /// Processes that exited after being signalled
+    pub killed: Vec<KilledProcess>,
+    /// Processes still alive after SIGKILL (e.g. stuck in uninterruptible IO)
+    #[serde(default)]
+    pub survivors: Vec<KilledProcess>,
+}
+
+impl KillReport {
+    pub fn is_empty(&self) -> bool {
+        self.killed.is_empty() && self.survivors.is_empty()
+    }
+}
+
+/// Make `cmd` start in a new process group led by the child, so the child
+/// and everything it spawns can be signalled together.
+pub fn isolate(cmd: &mut tokio::process::Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+#[derive(Debug, Clone)]
+struct ProcEntry {
+    pid: u32,
+    ppid: u32,
+    pgid: u32,
+    comm: String,
+}
+
+/// Parse `/proc/<pid>/stat`. The command name is parenthesized and may itself
+/// contain spaces or parentheses, so fields are read after the last `)`.
+fn parse_stat(line: &str) -> Option<(ProcEntry, char)> {
+    let open = line.find('(')?;
+    let close = line.rfind(')')?;
+    let pid = line[..open].trim().parse().ok()?;
+    let comm = line.get(open + 1..close)?.to_string();
+    let mut fields = line[close + 1..].split_whitespace();
+    let state = fields.next()?.chars().next()?;
+    let ppid = fields.next()?.parse().ok()?;
+    let pgid = fields.next()?.parse().ok()?;
+    Some((
+        ProcEntry {
+            pid,
+            ppid,
+            pgid,
+            comm,
+        },
+        state,
+    ))
+}
+
+/// Live (non-zombie) processes on the host.
+fn snapshot() -> Vec<ProcEntry> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        })
+        .filter_map(|e| std::fs::read_to_string(e.path().join("stat")).ok())
+        .filter_map(|line| parse_stat(&line))
+        .filter(|(_, state)| *state != 'Z' && *state != 'X')
+        .map(|(entry, _)| entry)
+        .collect()
+}
+
+/// `root`, its descendants, and any other member of the process group it leads.
+fn process_tree(root: u32, procs: &[ProcEntry]) -> Vec<ProcEntry> {
+    let mut children: HashMap<u32, Vec<&ProcEntry>> = HashMap::new();
+    for p in procs {
+        children.entry(p.ppid).or_default().push(p);
+    }
+    let mut seen: HashSet<u32> = HashSet::new();
+    let mut tree = Vec::new();
+    let mut stack: Vec<&ProcEntry> = procs
+        .iter()
+        .filter(|p| p.pid == root || p.pgid == root)
+        .collect();
+    while let Some(p) = stack.pop() {
+        if !seen.insert(p.pid) {
+            continue;
+        }
+        tree.push(p.clone());
+        if let Some(kids) = children.get(&p.pid) {
+            stack.extend(kids.iter().copied());
+        }
+    }
+    tree
+}
+
+fn signal(root: u32, pids: &[u32], sig: i32) {
+    #[cfg(unix)]
+    // SAFETY: kill/killpg only send signals; invalid or exited pids return ESRCH.
+    unsafe {
+        libc::killpg(root as libc::pid_t, sig);
+        for pid in pids {
+            libc::kill(*pid as libc::pid_t, sig);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (root, pids, sig);
+}
+
+fn alive(targets: &[ProcEntry]) -> Vec<ProcEntry> {
+    let live: HashMap<u32, String> = snapshot().into_iter().map(|p| (p.pid, p.comm)).collect();
+    targets
+        .iter()
+        .filter(|t| live.get(&t.pid) == Some(&t.comm))
+        .cloned()
+        .collect()
+}
+
+async fn wait_for_exit(targets: &[ProcEntry], grace: Duration) -> Vec<ProcEntry> {
+    let deadline = tokio::time::Instant::now() + grace;
+    loop {
+        let remaining = alive(targets);
+        if remaining.is_empty() || tokio::time::Instant::now() >= deadline {
+            return remaining;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Terminate the process tree rooted at `root` (normally a child spawned
+/// with [`isolate`]) and report what was killed and what survived.
+pub async fn terminate(root: u32) -> KillReport {
+    #[cfg(unix)]
+    {
+        let targets = process_tree(root, &snapshot());
+        let pids: Vec<u32> = targets.iter().map(|p| p.pid).collect();
+        signal(root, &pids, libc::SIGTERM);
+        let mut remaining = wait_for_exit(&targets, TERM_GRACE).await;
+        if !remaining.is_empty() {
+            let pids: Vec<u32> = remaining.iter().map(|p| p.pid).collect();
+            tracing::debug!(root, ?pids, "Processes ignored SIGTERM; sending SIGKILL");
+            signal(root, &pids, libc::SIGKILL);
+            remaining = wait_for_exit(&remaining, KILL_GRACE).await;
+        }
+        let survivors: HashSet<u32> = remaining.iter().map(|p| p.pid).collect();
+        let to_killed = |p: &ProcEntry| KilledProcess {
+            pid: p.pid,
+            command: p.comm.clone(),
+        };
+        KillReport {
+            killed: targets
+                .iter()
+                .filter(|p| !survivors.contains(&p.pid))
+                .map(to_killed)
+                .collect(),
+            survivors: remaining.iter().map(to_killed).collect(),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = root;
+        KillReport::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stat_with_awkward_command_names() {
+        let (entry, state) =
+            parse_stat("4242 (my (weird) cmd) S 17 4242 4242 0 -1 4194560 102 0").unwrap();
+        assert_eq!(entry.pid, 4242);
+        assert_eq!(entry.comm, "my (weird) cmd");
+        assert_eq!(state, 'S');
+        assert_eq!(entry.ppid, 17);
+        assert_eq!(entry.pgid, 4242);
+        assert!(parse_stat("garbage").is_none());
+    }
+
+    #[test]
+    fn tree_follows_parents_and_group_members() {
+        let entry = |pid, ppid, pgid| ProcEntry {
+            pid,
+            ppid,
+            pgid,
+            comm: format!("p{pid}"),
+        };
+        let procs = vec![
+            entry(10, 1, 10),
+            entry(11, 10, 10),
+            // Moved to its own session but still a descendant.
+            entry(12, 11, 12),
+            // Reparented to init but still in the group.
+            entry(13, 1, 10),
+            entry(20, 1, 20),
+        ];
+        let mut pids: Vec<u32> = process_tree(10, &procs).iter().map(|p| p.pid).collect();
+        pids.sort();
+        assert_eq!(pids, vec![10, 11, 12, 13]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn terminates_whole_tree() {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg("sleep 30 & sleep 30; wait");
+        isolate(&mut cmd);
+        let mut child = cmd.spawn().unwrap();
+        let pid = child.id().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let report = terminate(pid).await;
+        let _ = child.wait().await;
+
+        assert!(report.survivors.is_empty());
+        assert!(report.killed.iter().any(|p| p.pid == pid));
+        assert!(
+            report
+                .killed
+                .iter()
+                .filter(|p| p.command == "sleep")
+                .count()
+                >= 2
+        );
+    }
+}
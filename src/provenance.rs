@@ -0,0 +1,322 @@
+//! Provenance metadata for commits made by agents.
+//!
+//! Before each turn the mission runner writes [`CONTEXT_FILE`] (mission ID,
+//! backend and model) into the mission directory. `git_commit` reads it and,
+//! depending on `SANDBOXED_SH_GIT_PROVENANCE`, adds commit trailers:
+//!
+//! ```text
+//! Sandboxed-Mission: 6f1c1a52-0c9e-4a57-b0c4-2f0d8f3b2f7e
+//! Sandboxed-Model: claude-sonnet-4
+//! Sandboxed-Timestamp: 2026-01-15T10:42:03Z
+//! ```
+//!
+//! and/or a one-line header comment at the top of files the commit adds. Every
+//! commit is also appended to [`PENDING_FILE`]; after the turn the runner moves
+//! the records into the global [`INDEX_FILE`], which backs the commit lookup
+//! API (`GET /api/control/commits/:hash`).
+//!
+//! `SANDBOXED_SH_GIT_PROVENANCE` (workspace env vars or process env) is a
+//! comma-separated list of `trailers` and `headers`, or `off`. Default:
+//! `trailers`. Commits are recorded in the index even when it is `off`.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Turn context, relative to the mission directory.
+pub const CONTEXT_FILE: &str = ".sandboxed-sh/provenance.json";
+/// Commits made during the turn, relative to the mission directory.
+pub const PENDING_FILE: &str = ".sandboxed-sh/commits.jsonl";
+/// Commit index, relative to the server working directory.
+pub const INDEX_FILE: &str = ".sandboxed-sh/commit_provenance.jsonl";
+
+pub const PROVENANCE_SETTING: &str = "SANDBOXED_SH_GIT_PROVENANCE";
+
+/// Marker identifying file headers written by [`file_header`].
+const HEADER_MARKER: &str = "sandboxed.sh mission";
+
+/// Minimum hash prefix length accepted by [`lookup`].
+pub const MIN_HASH_PREFIX: usize = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mode {
+    pub trailers: bool,
+    pub headers: bool,
+}
+
+impl Mode {
+    pub fn parse(value: Option<&str>) -> Self {
+        let Some(value) = value else {
+            return Self {
+                trailers: true,
+                headers: false,
+            };
+        };
+        let items: HashSet<String> = value
+            .split(',')
+            .map(|s| s.trim().to_ascii_lowercase())
+            .collect();
+        let all = items.contains("all") || items.contains("on");
+        Self {
+            trailers: all || items.contains("trailers"),
+            headers: all || items.contains("headers"),
+        }
+    }
+
+    pub fn from_workspace() -> Self {
+        Self::parse(crate::tools::terminal::workspace_setting(PROVENANCE_SETTING).as_deref())
+    }
+}
+
+/// Who is committing: written by the runner before each turn.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnContext {
+    pub mission_id: String,
+    pub backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl TurnContext {
+    pub fn write(&self, work_dir: &Path) -> std::io::Result<()> {
+        let path = work_dir.join(CONTEXT_FILE);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// The context of the current turn, falling back to the mission ID the
+    /// MCP was started with.
+    pub fn load(work_dir: &Path) -> Option<Self> {
+        std::fs::read_to_string(work_dir.join(CONTEXT_FILE))
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .or_else(|| {
+                let mission_id = std::env::var("SANDBOXED_SH_MISSION_ID").ok()?;
+                Some(Self {
+                    mission_id,
+                    ..Self::default()
+                })
+            })
+    }
+
+    fn model_label(&self) -> &str {
+        self.model.as_deref().unwrap_or("unknown")
+    }
+
+    /// Commit trailers recording this context.
+    pub fn trailers(&self, timestamp: &str) -> Vec<String> {
+        vec![
+            format!("Sandboxed-Mission: {}", self.mission_id),
+            format!("Sandboxed-Model: {}", self.model_label()),
+            format!("Sandboxed-Timestamp: {}", timestamp),
+        ]
+    }
+
+    /// A header comment for a new file, or `None` when the file type has no
+    /// known line-comment syntax.
+    pub fn file_header(&self, path: &Path, timestamp: &str) -> Option<String> {
+        let name = path.file_name()?.to_str()?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let (open, close) = match ext {
+            "rs" | "go" | "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "kt" | "swift" | "js"
+            | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "cs" | "scala" | "dart" | "php" => ("//", ""),
+            "py" | "sh" | "bash" | "zsh" | "rb" | "pl" | "r" | "toml" | "yaml" | "yml" | "tf"
+            | "ex" | "exs" | "nix" => ("#", ""),
+            "sql" | "lua" | "hs" => ("--", ""),
+            "css" | "scss" => ("/*", " */"),
+            "html" | "xml" | "vue" | "svelte" | "md" => ("<!--", " -->"),
+            _ if name == "Dockerfile" || name == "Makefile" => ("#", ""),
+            _ => return None,
+        };
+        Some(format!(
+            "{} Generated by {} {} (model {}, {}){}",
+            open,
+            HEADER_MARKER,
+            self.mission_id,
+            self.model_label(),
+            timestamp,
+            close
+        ))
+    }
+}
+
+/// Append trailers to a commit message, joining an existing trailer block.
+pub fn append_trailers(message: &str, trailers: &[String]) -> String {
+    let message = message.trim_end();
+    let last_paragraph = message.rsplit("\n\n").next().unwrap_or("");
+    let has_trailer_block = message.contains("\n\n")
+        && last_paragraph.lines().all(|line| {
+            line.split_once(": ").is_some_and(|(key, _)| {
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+        });
+    let separator = if has_trailer_block { "\n" } else { "\n\n" };
+    format!("{}{}{}", message, separator, trailers.join("\n"))
+}
+
+/// Insert a header line at the top of `content`, after a shebang or XML
+/// declaration. Returns `None` when the file already carries one.
+pub fn insert_header(content: &str, header: &str) -> Option<String> {
+    if content.lines().take(3).any(|l| l.contains(HEADER_MARKER)) {
+        return None;
+    }
+    let split = if content.starts_with("#!") || content.starts_with("<?xml") {
+        content.find('\n').map(|i| i + 1).unwrap_or(content.len())
+    } else {
+        0
+    };
+    let (head, rest) = content.split_at(split);
+    let head = if !head.is_empty() && !head.ends_with('\n') {
+        format!("{}\n", head)
+    } else {
+        head.to_string()
+    };
+    Some(format!("{}{}\n{}", head, header, rest))
+}
+
+/// A commit an agent made.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitRecord {
+    pub commit: String,
+    pub repo: String,
+    pub subject: String,
+    pub mission_id: String,
+    #[serde(default)]
+    pub backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub timestamp: String,
+}
+
+fn append_jsonl(path: &Path, records: &[CommitRecord]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    for record in records {
+        let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn read_jsonl(path: &Path) -> Vec<CommitRecord> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Remember a commit made in the mission directory.
+pub fn record_commit(work_dir: &Path, record: CommitRecord) -> std::io::Result<()> {
+    append_jsonl(&work_dir.join(PENDING_FILE), &[record])
+}
+
+/// Drain the commits recorded in a mission directory.
+pub fn take_pending(work_dir: &Path) -> Vec<CommitRecord> {
+    let path = work_dir.join(PENDING_FILE);
+    let records = read_jsonl(&path);
+    if !records.is_empty() {
+        if let Err(e) = std::fs::write(&path, "") {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to truncate commit log");
+        }
+    }
+    records
+}
+
+/// Add commits to the server-wide index.
+pub fn index_commits(root: &Path, records: &[CommitRecord]) -> std::io::Result<()> {
+    append_jsonl(&root.join(INDEX_FILE), records)
+}
+
+/// Commits in the index whose hash starts with `hash` (case-insensitive).
+pub fn lookup(root: &Path, hash: &str) -> Vec<CommitRecord> {
+    let hash = hash.trim().to_ascii_lowercase();
+    if hash.len() < MIN_HASH_PREFIX {
+        return Vec::new();
+    }
+    read_jsonl(&root.join(INDEX_FILE))
+        .into_iter()
+        .filter(|r| r.commit.starts_with(&hash))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TurnContext {
+        TurnContext {
+            mission_id: "m-1".to_string(),
+            backend: "claudecode".to_string(),
+            model: Some("claude-sonnet-4".to_string()),
+        }
+    }
+
+    #[test]
+    fn trailers_join_existing_block() {
+        let trailers = context().trailers("2026-01-15T10:00:00Z");
+        let plain = append_trailers("fix: parser\n\nHandle empty input.\n", &trailers);
+        assert!(plain.ends_with(
+            "input.\n\nSandboxed-Mission: m-1\nSandboxed-Model: claude-sonnet-4\nSandboxed-Timestamp: 2026-01-15T10:00:00Z"
+        ));
+        let signed = append_trailers("fix: parser\n\nSigned-off-by: A <a@b.c>", &trailers);
+        assert!(signed.contains("Signed-off-by: A <a@b.c>\nSandboxed-Mission: m-1"));
+        assert_eq!(
+            Mode::parse(None),
+            Mode {
+                trailers: true,
+                headers: false
+            }
+        );
+        assert!(!Mode::parse(Some("off")).trailers);
+        assert!(Mode::parse(Some("trailers, headers")).headers);
+    }
+
+    #[test]
+    fn headers_respect_shebang_and_are_added_once() {
+        let ctx = context();
+        let header = ctx.file_header(Path::new("bin/run.sh"), "t").unwrap();
+        assert_eq!(
+            header,
+            "# Generated by sandboxed.sh mission m-1 (model claude-sonnet-4, t)"
+        );
+        let content = insert_header("#!/bin/sh\necho hi\n", &header).unwrap();
+        assert!(content.starts_with("#!/bin/sh\n# Generated by"));
+        assert!(insert_header(&content, &header).is_none());
+        assert!(ctx.file_header(Path::new("data.json"), "t").is_none());
+    }
+
+    #[test]
+    fn commits_are_indexed_and_found_by_prefix() {
+        let mission = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let record = CommitRecord {
+            commit: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            repo: "/work/repo".to_string(),
+            subject: "fix: parser".to_string(),
+            mission_id: "m-1".to_string(),
+            backend: "claudecode".to_string(),
+            model: None,
+            timestamp: "t".to_string(),
+        };
+        record_commit(mission.path(), record.clone()).unwrap();
+        let pending = take_pending(mission.path());
+        assert_eq!(pending, vec![record.clone()]);
+        assert!(take_pending(mission.path()).is_empty());
+
+        index_commits(root.path(), &pending).unwrap();
+        assert_eq!(lookup(root.path(), "0123456ABC"), Vec::new());
+        assert_eq!(lookup(root.path(), "0123456789AB"), vec![record]);
+        assert!(lookup(root.path(), "0123").is_empty());
+    }
+}
@@ -783,7 +783,9 @@ impl ModelChainStore {
     /// 1. `AIProviderStore` — custom providers and future multi-account standard providers
     /// 2. `standard_accounts` — standard providers from OpenCode's config files
     ///
-    /// Returns entries in priority order, ready for waterfall routing.
+    /// Returns entries in priority order, ready for waterfall routing. Models
+    /// whose recent streams mostly failed or were much slower to the first
+    /// token are moved behind the others (see [`crate::stream_metrics`]).
     pub async fn resolve_chain(
         &self,
         chain_id: &str,
@@ -874,7 +876,7 @@ impl ModelChainStore {
             }
         }
 
-        resolved
+        crate::stream_metrics::rank_by_health(resolved, |e| (&e.provider_id, &e.model_id))
     }
 }
 
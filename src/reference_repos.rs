@@ -0,0 +1,498 @@
+//! Shared, read-only cache of reference repositories.
+//!
+//! Agents regularly need to read a dependency's source. `checkout` shallow
+//! clones `url` at `git_ref` into the cache directory (or reuses an existing
+//! checkout) and records it in an index. Container workspaces get the cache
+//! bind-mounted read-only at [`CONTAINER_PATH`]; host workspaces read the
+//! cache directory directly.
+//!
+//! The cache is bounded: after each checkout the least recently used repos
+//! are evicted until the total size fits `SANDBOXED_SH_REFERENCE_REPOS_MAX_BYTES`.
+//! Checkouts from several processes are serialized with a lock file.
+//!
+//! Settings (process env):
+//! - `SANDBOXED_SH_REFERENCE_REPOS_DIR` - cache directory (default:
+//!   `{WORKING_DIR}/.sandboxed-sh/cache/reference-repos`)
+//! - `SANDBOXED_SH_REFERENCE_REPOS_MAX_BYTES` - size bound (default 5 GiB)
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::process::Command;
+use walkdir::WalkDir;
+
+/// Mount point of the cache inside container workspaces.
+pub const CONTAINER_PATH: &str = "/opt/reference-repos";
+
+const DEFAULT_MAX_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+const INDEX_FILE: &str = ".index.json";
+const LOCK_FILE: &str = ".lock";
+const GIT_TIMEOUT: Duration = Duration::from_secs(600);
+/// Branch checkouts older than this are re-fetched; commit SHAs never are.
+const REFRESH_SECS: i64 = 3600;
+
+/// One cached checkout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferenceRepo {
+    /// Directory name below the cache root.
+    pub key: String,
+    pub url: String,
+    pub git_ref: Option<String>,
+    pub commit: String,
+    pub size_bytes: u64,
+    pub fetched_at: DateTime<Utc>,
+    pub last_used: DateTime<Utc>,
+}
+
+/// Result of [`checkout`].
+#[derive(Debug, Clone)]
+pub struct Checkout {
+    pub repo: ReferenceRepo,
+    /// Host path of the checkout.
+    pub path: PathBuf,
+    /// True when an existing checkout was reused without fetching.
+    pub reused: bool,
+    /// Keys evicted to stay within the size bound.
+    pub evicted: Vec<String>,
+}
+
+pub fn cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var("SANDBOXED_SH_REFERENCE_REPOS_DIR")
+        .ok()
+        .filter(|d| !d.trim().is_empty())
+    {
+        return PathBuf::from(dir.trim());
+    }
+    let working_dir = std::env::var("WORKING_DIR").unwrap_or_else(|_| "/root".to_string());
+    PathBuf::from(working_dir)
+        .join(".sandboxed-sh")
+        .join("cache")
+        .join("reference-repos")
+}
+
+fn max_bytes() -> u64 {
+    std::env::var("SANDBOXED_SH_REFERENCE_REPOS_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+/// `systemd-nspawn` argument mounting the cache read-only, when it exists.
+pub fn nspawn_bind_arg() -> Option<String> {
+    let dir = cache_dir();
+    dir.is_dir()
+        .then(|| format!("--bind-ro={}:{}", dir.display(), CONTAINER_PATH))
+}
+
+/// Only remote URLs are accepted: local paths and `file://` would expose host
+/// files, and `ext::` transports run arbitrary commands.
+pub fn validate_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    if url.starts_with('-') {
+        return Err("URL must not start with '-'".to_string());
+    }
+    if let Some(rest) = url.strip_prefix("git@") {
+        return match rest.split_once(':') {
+            Some((host, path)) if !host.is_empty() && !path.is_empty() => Ok(()),
+            _ => Err(format!("Invalid SSH URL '{}'", url)),
+        };
+    }
+    let parsed = url::Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "https" | "http" | "ssh" | "git") {
+        return Err(format!(
+            "Unsupported URL scheme '{}': use https, ssh or git",
+            parsed.scheme()
+        ));
+    }
+    if parsed.host_str().unwrap_or_default().is_empty() {
+        return Err(format!("URL '{}' has no host", url));
+    }
+    Ok(())
+}
+
+/// Refs are passed to `git fetch`; reject anything that could be an option
+/// or a refspec with a destination.
+pub fn validate_ref(git_ref: &str) -> Result<(), String> {
+    let valid = !git_ref.is_empty()
+        && !git_ref.starts_with('-')
+        && !git_ref.contains("..")
+        && git_ref
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-/+".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid git ref '{}'", git_ref))
+    }
+}
+
+fn is_commit_sha(git_ref: &str) -> bool {
+    git_ref.len() == 40 && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Readable, collision-free directory name, e.g. `github.com_tokio-rs_tokio@v1.40.0-1a2b3c4d`.
+fn cache_key(url: &str, git_ref: Option<&str>) -> String {
+    let trimmed = url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let without_scheme = trimmed
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(trimmed);
+    let without_user = without_scheme
+        .split_once('@')
+        .map(|(_, rest)| rest)
+        .unwrap_or(without_scheme);
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    let digest = Sha256::digest(format!("{}\n{}", url.trim(), git_ref.unwrap_or("")).as_bytes());
+    format!(
+        "{}@{}-{}",
+        sanitize(without_user),
+        sanitize(git_ref.unwrap_or("HEAD")),
+        hex::encode(&digest[..4])
+    )
+}
+
+fn load_index(dir: &Path) -> HashMap<String, ReferenceRepo> {
+    std::fs::read_to_string(dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Vec<ReferenceRepo>>(&raw).ok())
+        .map(|repos| repos.into_iter().map(|r| (r.key.clone(), r)).collect())
+        .unwrap_or_default()
+}
+
+fn save_index(dir: &Path, index: &HashMap<String, ReferenceRepo>) -> anyhow::Result<()> {
+    let mut repos: Vec<&ReferenceRepo> = index.values().collect();
+    repos.sort_by(|a, b| a.key.cmp(&b.key));
+    let tmp = dir.join(format!("{}.tmp", INDEX_FILE));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&repos)?)?;
+    std::fs::rename(tmp, dir.join(INDEX_FILE))?;
+    Ok(())
+}
+
+fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Drop entries whose directory vanished, then evict least recently used
+/// repos (never `keep`) until the total fits `max_bytes`.
+fn evict(
+    dir: &Path,
+    index: &mut HashMap<String, ReferenceRepo>,
+    keep: &str,
+    max_bytes: u64,
+) -> Vec<String> {
+    index.retain(|key, _| dir.join(key).is_dir());
+    let mut total: u64 = index.values().map(|r| r.size_bytes).sum();
+    let mut candidates: Vec<(DateTime<Utc>, String)> = index
+        .values()
+        .filter(|r| r.key != keep)
+        .map(|r| (r.last_used, r.key.clone()))
+        .collect();
+    candidates.sort();
+    let mut evicted = Vec::new();
+    for (_, key) in candidates {
+        if total <= max_bytes {
+            break;
+        }
+        if let Err(e) = std::fs::remove_dir_all(dir.join(&key)) {
+            tracing::warn!("Failed to evict reference repo {}: {}", key, e);
+            continue;
+        }
+        if let Some(repo) = index.remove(&key) {
+            total = total.saturating_sub(repo.size_bytes);
+        }
+        evicted.push(key);
+    }
+    evicted
+}
+
+async fn git(cwd: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(cwd)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ASKPASS", "true")
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(GIT_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| anyhow::anyhow!("git {} timed out", args.first().unwrap_or(&"")))??;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Shallow-fetch `git_ref` (or the default branch) into `path` and check it out.
+async fn fetch_into(path: &Path, url: &str, git_ref: Option<&str>) -> anyhow::Result<String> {
+    if !path.join(".git").is_dir() {
+        std::fs::create_dir_all(path)?;
+        git(path, &["init", "--quiet"]).await?;
+        git(path, &["remote", "add", "origin", url]).await?;
+    }
+    git(
+        path,
+        &[
+            "fetch",
+            "--quiet",
+            "--depth",
+            "1",
+            "--no-tags",
+            "origin",
+            git_ref.unwrap_or("HEAD"),
+        ],
+    )
+    .await?;
+    git(
+        path,
+        &["checkout", "--quiet", "--force", "--detach", "FETCH_HEAD"],
+    )
+    .await?;
+    git(path, &["clean", "--quiet", "-ffdx"]).await?;
+    git(path, &["rev-parse", "HEAD"]).await
+}
+
+/// Clone (or reuse) `url` at `git_ref` in the shared cache.
+pub async fn checkout(url: &str, git_ref: Option<&str>) -> anyhow::Result<Checkout> {
+    validate_url(url).map_err(|e| anyhow::anyhow!(e))?;
+    if let Some(git_ref) = git_ref {
+        validate_ref(git_ref).map_err(|e| anyhow::anyhow!(e))?;
+    }
+    checkout_in(&cache_dir(), url.trim(), git_ref, max_bytes()).await
+}
+
+async fn checkout_in(
+    dir: &Path,
+    url: &str,
+    git_ref: Option<&str>,
+    max_bytes: u64,
+) -> anyhow::Result<Checkout> {
+    std::fs::create_dir_all(dir)?;
+    let lock = File::create(dir.join(LOCK_FILE))?;
+    let lock = tokio::task::spawn_blocking(move || lock.lock_exclusive().map(|_| lock)).await??;
+
+    let key = cache_key(url, git_ref);
+    let path = dir.join(&key);
+    let mut index = load_index(dir);
+    let now = Utc::now();
+
+    let existing = index
+        .get(&key)
+        .filter(|_| path.join(".git").is_dir())
+        .cloned();
+    let fresh = existing.as_ref().is_some_and(|repo| {
+        git_ref.is_some_and(is_commit_sha) || (now - repo.fetched_at).num_seconds() < REFRESH_SECS
+    });
+
+    let (repo, reused) = match existing {
+        Some(repo) if fresh => (
+            ReferenceRepo {
+                last_used: now,
+                ..repo
+            },
+            true,
+        ),
+        _ => {
+            let result = fetch_into(&path, url, git_ref).await;
+            let commit = match result {
+                Ok(commit) => commit,
+                Err(e) => {
+                    // Keep a previous checkout; drop a half-created one.
+                    if index.contains_key(&key) {
+                        tracing::warn!("Refreshing reference repo {} failed: {}", key, e);
+                    } else {
+                        let _ = std::fs::remove_dir_all(&path);
+                    }
+                    return Err(e);
+                }
+            };
+            (
+                ReferenceRepo {
+                    key: key.clone(),
+                    url: url.to_string(),
+                    git_ref: git_ref.map(str::to_string),
+                    commit,
+                    size_bytes: dir_size(&path),
+                    fetched_at: now,
+                    last_used: now,
+                },
+                false,
+            )
+        }
+    };
+    index.insert(key.clone(), repo.clone());
+    let evicted = evict(dir, &mut index, &key, max_bytes);
+    save_index(dir, &index)?;
+    drop(lock);
+
+    Ok(Checkout {
+        repo,
+        path,
+        reused,
+        evicted,
+    })
+}
+
+/// Mount the cache into a running container that was started before it
+/// existed (one-shot `systemd-nspawn` runs get [`nspawn_bind_arg`] instead).
+pub async fn bind_into_running_container(machine: &str, leader: &str) -> anyhow::Result<()> {
+    let mountinfo = std::fs::read_to_string(format!("/proc/{}/mountinfo", leader))?;
+    if mountinfo
+        .lines()
+        .any(|line| line.split(' ').nth(4) == Some(CONTAINER_PATH))
+    {
+        return Ok(());
+    }
+    let machinectl = if Path::new("/usr/bin/machinectl").exists() {
+        "/usr/bin/machinectl"
+    } else {
+        "machinectl"
+    };
+    let dir = cache_dir();
+    let output = Command::new(machinectl)
+        .args(["bind", "--read-only", "--mkdir", machine])
+        .arg(&dir)
+        .arg(CONTAINER_PATH)
+        .output()
+        .await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "machinectl bind failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_urls_and_refs() {
+        assert!(validate_url("https://github.com/tokio-rs/tokio").is_ok());
+        assert!(validate_url("git@github.com:tokio-rs/tokio.git").is_ok());
+        assert!(validate_url("file:///etc").is_err());
+        assert!(validate_url("/root/secrets").is_err());
+        assert!(validate_url("ext::sh -c touch% /tmp/pwned").is_err());
+        assert!(validate_url("--upload-pack=evil").is_err());
+
+        assert!(validate_ref("v1.40.0").is_ok());
+        assert!(validate_ref("feature/x").is_ok());
+        assert!(validate_ref("--output=/tmp/x").is_err());
+        assert!(validate_ref("main:refs/heads/x").is_err());
+    }
+
+    #[test]
+    fn cache_keys_are_readable_and_distinct() {
+        let key = cache_key("https://github.com/tokio-rs/tokio.git", Some("v1.40.0"));
+        assert!(key.starts_with("github.com_tokio-rs_tokio@v1.40.0-"));
+        assert_ne!(
+            cache_key("https://github.com/a/b", Some("feature/x")),
+            cache_key("https://github.com/a/b", Some("feature_x"))
+        );
+        assert!(cache_key("git@github.com:a/b.git", None).starts_with("github.com_a_b@HEAD-"));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_repos() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = HashMap::new();
+        for (idx, key) in ["old", "mid", "new"].iter().enumerate() {
+            std::fs::create_dir_all(dir.path().join(key)).unwrap();
+            index.insert(
+                key.to_string(),
+                ReferenceRepo {
+                    key: key.to_string(),
+                    url: format!("https://example.com/{}", key),
+                    git_ref: None,
+                    commit: "abc".to_string(),
+                    size_bytes: 100,
+                    fetched_at: Utc::now(),
+                    last_used: Utc::now() + chrono::Duration::seconds(idx as i64),
+                },
+            );
+        }
+        index.insert(
+            "gone".to_string(),
+            ReferenceRepo {
+                key: "gone".to_string(),
+                ..index["old"].clone()
+            },
+        );
+
+        // "old" is the least recently used but is being checked out right now.
+        let evicted = evict(dir.path(), &mut index, "old", 150);
+        assert_eq!(evicted, vec!["mid".to_string(), "new".to_string()]);
+        assert!(index.contains_key("old"));
+        assert!(!index.contains_key("gone"));
+        assert!(!dir.path().join("mid").exists());
+    }
+
+    #[tokio::test]
+    async fn checks_out_and_reuses_repos() {
+        let upstream = tempfile::tempdir().unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        let run = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(upstream.path())
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        run(&["init", "--quiet", "-b", "main"]);
+        std::fs::write(upstream.path().join("lib.rs"), "pub fn hello() {}\n").unwrap();
+        run(&["add", "."]);
+        run(&[
+            "-c",
+            "user.name=t",
+            "-c",
+            "user.email=t@t",
+            "commit",
+            "--quiet",
+            "-m",
+            "init",
+        ]);
+
+        let url = upstream.path().to_string_lossy().to_string();
+        let first = checkout_in(cache.path(), &url, Some("main"), u64::MAX)
+            .await
+            .unwrap();
+        assert!(!first.reused);
+        assert_eq!(first.repo.commit.len(), 40);
+        assert!(first.path.join("lib.rs").exists());
+
+        let second = checkout_in(cache.path(), &url, Some("main"), u64::MAX)
+            .await
+            .unwrap();
+        assert!(second.reused);
+        assert_eq!(second.repo.commit, first.repo.commit);
+        assert_eq!(load_index(cache.path()).len(), 1);
+    }
+}
@@ -0,0 +1,238 @@
+//! Quiet hours and maintenance windows for automated mission triggers.
+//!
+//! - **Quiet hours** are recurring weekly windows (globally in settings, or per
+//!   workspace) during which interval and webhook automations are held back.
+//!   Interval automations fire once the window ends; webhook payloads are stored
+//!   as pending executions and dispatched afterwards.
+//! - **Maintenance windows** are one-off calendar ranges during which the
+//!   automation scheduler pauses entirely. Missions already running are left to
+//!   finish; nothing new is started until the window closes.
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Zone;
+
+/// A recurring weekly quiet-hours window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Days the window starts on (e.g. `["mon", "fri"]`). Empty means every day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    /// Local start time (`HH:MM`).
+    pub start: String,
+    /// Local end time (`HH:MM`). An end before the start spans midnight.
+    pub end: String,
+    /// Fixed UTC offset for `start`/`end` (e.g. `+02:00`). Defaults to UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset: Option<String>,
+    /// IANA time zone for `start`/`end` (e.g. `Europe/Berlin`), following
+    /// daylight saving time. Not combined with `utc_offset`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+impl QuietHours {
+    fn parse_time(value: &str) -> Result<NaiveTime, String> {
+        NaiveTime::parse_from_str(value.trim(), "%H:%M")
+            .map_err(|_| format!("Invalid time '{}' (expected HH:MM)", value))
+    }
+
+    /// UTC offset of the window's local time at `now`.
+    fn offset(&self, now: DateTime<Utc>) -> Result<FixedOffset, String> {
+        let utc_offset = self
+            .utc_offset
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let time_zone = self
+            .time_zone
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        match (utc_offset, time_zone) {
+            (Some(_), Some(_)) => Err("Set either utc_offset or time_zone, not both".to_string()),
+            (None, Some(name)) => Ok(Zone::parse(name)?.offset_at(now)),
+            (None | Some("Z") | Some("UTC"), None) => Ok(FixedOffset::east_opt(0).unwrap()),
+            (Some(raw), None) => raw
+                .parse::<FixedOffset>()
+                .map_err(|_| format!("Invalid utc_offset '{}' (expected e.g. +02:00)", raw)),
+        }
+    }
+
+    /// Check that times and the offset or time zone parse.
+    pub fn validate(&self) -> Result<(), String> {
+        Self::parse_time(&self.start)?;
+        Self::parse_time(&self.end)?;
+        self.offset(Utc::now())?;
+        Ok(())
+    }
+
+    fn applies_to(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Whether `now` falls inside this window. Invalid windows never match.
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let (Ok(start), Ok(end), Ok(offset)) = (
+            Self::parse_time(&self.start),
+            Self::parse_time(&self.end),
+            self.offset(now),
+        ) else {
+            return false;
+        };
+        let local = now.with_timezone(&offset);
+        let time = local.time();
+        let day = local.weekday();
+
+        if start == end {
+            self.applies_to(day)
+        } else if start < end {
+            self.applies_to(day) && time >= start && time < end
+        } else if time >= start {
+            self.applies_to(day)
+        } else {
+            // Early-morning tail of a window that started the previous evening.
+            time < end && self.applies_to(day.pred())
+        }
+    }
+}
+
+/// A one-off maintenance window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl MaintenanceWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ends_at <= self.starts_at {
+            return Err("Maintenance window must end after it starts".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        now >= self.starts_at && now < self.ends_at
+    }
+}
+
+/// Whether any of the quiet-hours windows covers `now`.
+pub fn in_quiet_hours(windows: &[QuietHours], now: DateTime<Utc>) -> bool {
+    windows.iter().any(|w| w.contains(now))
+}
+
+/// The maintenance window covering `now`, if any.
+pub fn active_maintenance(
+    windows: &[MaintenanceWindow],
+    now: DateTime<Utc>,
+) -> Option<&MaintenanceWindow> {
+    windows.iter().find(|w| w.contains(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn window(days: &[Weekday], start: &str, end: &str, offset: Option<&str>) -> QuietHours {
+        QuietHours {
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+            utc_offset: offset.map(str::to_string),
+            time_zone: None,
+        }
+    }
+
+    #[test]
+    fn test_same_day_window() {
+        let w = window(&[], "09:00", "17:00", None);
+        // 2026-10-14 is a Wednesday.
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 14, 9, 0, 0).unwrap()));
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 14, 16, 59, 0).unwrap()));
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 14, 17, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_overnight_window_uses_start_day() {
+        let w = window(&[Weekday::Fri], "22:00", "06:00", None);
+        // Friday night and early Saturday morning are quiet...
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 16, 23, 0, 0).unwrap()));
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 17, 5, 0, 0).unwrap()));
+        // ...but not early Friday morning or Saturday night.
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 16, 5, 0, 0).unwrap()));
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 17, 23, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_utc_offset() {
+        let w = window(&[], "09:00", "10:00", Some("+02:00"));
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 14, 7, 30, 0).unwrap()));
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 14, 9, 30, 0).unwrap()));
+        assert!(window(&[], "25:00", "10:00", None).validate().is_err());
+        assert!(window(&[], "09:00", "10:00", Some("CEST"))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_time_zone_follows_dst() {
+        if !std::path::Path::new("/usr/share/zoneinfo/Europe/Berlin").exists() {
+            return;
+        }
+        let w = QuietHours {
+            time_zone: Some("Europe/Berlin".to_string()),
+            ..window(&[Weekday::Sat], "22:00", "06:00", None)
+        };
+        assert!(w.validate().is_ok());
+        // Berlin leaves summer time at 03:00 on Sunday 2026-10-25, inside
+        // the window: it starts at 22:00 CEST (20:00 UTC)...
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 24, 19, 30, 0).unwrap()));
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 24, 20, 0, 0).unwrap()));
+        // ...and ends at 06:00 CET (05:00 UTC), an hour later in UTC than
+        // a fixed +02:00 offset would put it.
+        assert!(w.contains(Utc.with_ymd_and_hms(2026, 10, 25, 4, 30, 0).unwrap()));
+        assert!(!w.contains(Utc.with_ymd_and_hms(2026, 10, 25, 5, 0, 0).unwrap()));
+
+        let both = QuietHours {
+            utc_offset: Some("+01:00".to_string()),
+            ..w.clone()
+        };
+        assert!(both.validate().is_err());
+        let unknown = QuietHours {
+            time_zone: Some("Mars/Olympus".to_string()),
+            ..w
+        };
+        assert!(unknown.validate().is_err());
+    }
+
+    #[test]
+    fn test_maintenance_window() {
+        let m = MaintenanceWindow {
+            starts_at: Utc.with_ymd_and_hms(2026, 10, 14, 2, 0, 0).unwrap(),
+            ends_at: Utc.with_ymd_and_hms(2026, 10, 14, 4, 0, 0).unwrap(),
+            reason: None,
+        };
+        let windows = [m.clone()];
+        assert!(active_maintenance(
+            &windows,
+            Utc.with_ymd_and_hms(2026, 10, 14, 3, 0, 0).unwrap()
+        )
+        .is_some());
+        assert!(active_maintenance(
+            &windows,
+            Utc.with_ymd_and_hms(2026, 10, 14, 4, 0, 0).unwrap()
+        )
+        .is_none());
+        let backwards = MaintenanceWindow {
+            starts_at: m.ends_at,
+            ends_at: m.starts_at,
+            reason: None,
+        };
+        assert!(backwards.validate().is_err());
+    }
+}
@@ -4,17 +4,66 @@
 //! Environment variables are used as initial defaults when no settings file exists.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::api::mission_credentials::CredentialIssuer;
+use crate::api::mission_priority::PreemptionSettings;
+use crate::api::step_budget::StepBudget;
+use crate::api::tool_loops::ToolLoopSettings;
+use crate::locale::LocaleSettings;
+use crate::model_policy::ModelPolicy;
+use crate::schedule_windows::{MaintenanceWindow, QuietHours};
+use crate::tool_quotas::ToolQuotas;
+
 /// Global cached RTK enabled state, updated when settings change.
 /// This allows synchronous checks from non-async contexts.
 static RTK_ENABLED_CACHED: AtomicBool = AtomicBool::new(false);
+/// Global cached LLM call recording state, checked by the model-routing proxy.
+static LLM_RECORDING_CACHED: AtomicBool = AtomicBool::new(false);
+/// Global cached long-context fallback chain, used by the model-routing proxy.
+static LONG_CONTEXT_MODEL_CACHED: std::sync::RwLock<Option<String>> = std::sync::RwLock::new(None);
 /// Global cached max parallel missions value.
 /// A value of 0 means "unset" and callers should fall back to their default.
 static MAX_PARALLEL_MISSIONS_CACHED: AtomicUsize = AtomicUsize::new(0);
+/// Global cached quiet hours and maintenance windows, read by the automation scheduler.
+static SCHEDULE_WINDOWS_CACHED: std::sync::RwLock<(Vec<QuietHours>, Vec<MaintenanceWindow>)> =
+    std::sync::RwLock::new((Vec::new(), Vec::new()));
+/// Global cached maintenance settings, read by the maintenance scheduler.
+static MAINTENANCE_CACHED: std::sync::RwLock<Option<MaintenanceSettings>> =
+    std::sync::RwLock::new(None);
+/// Global cached locale and time zone, the fallback for per-mission settings.
+static LOCALE_CACHED: std::sync::RwLock<Option<LocaleSettings>> = std::sync::RwLock::new(None);
+/// Global cached spending alert settings, read by the alert checker.
+static SPENDING_ALERTS_CACHED: std::sync::RwLock<Option<SpendingAlertSettings>> =
+    std::sync::RwLock::new(None);
+/// Global cached budget buckets, read when attributing mission cost.
+static BUDGET_BUCKETS_CACHED: std::sync::RwLock<Vec<BudgetBucket>> =
+    std::sync::RwLock::new(Vec::new());
+/// Global cached model policy, enforced by the model-routing proxy.
+static MODEL_POLICY_CACHED: std::sync::RwLock<Option<ModelPolicy>> = std::sync::RwLock::new(None);
+/// Global cached fair-share settings, read when parallel missions start.
+static FAIR_SHARE_CACHED: std::sync::RwLock<Option<FairShareSettings>> =
+    std::sync::RwLock::new(None);
+/// Global cached feature flag rollouts, resolved for each new mission.
+static FEATURE_FLAGS_CACHED: std::sync::RwLock<BTreeMap<String, u8>> =
+    std::sync::RwLock::new(BTreeMap::new());
+/// Global cached default step budget, enforced for missions without their own.
+static STEP_BUDGET_CACHED: std::sync::RwLock<Option<StepBudget>> = std::sync::RwLock::new(None);
+/// Global cached repeated tool call handling, read by the tool loop monitor.
+static TOOL_LOOPS_CACHED: std::sync::RwLock<Option<ToolLoopSettings>> =
+    std::sync::RwLock::new(None);
+/// Global cached quotas for expensive tools, written to each mission's directory.
+static TOOL_QUOTAS_CACHED: std::sync::RwLock<Option<ToolQuotas>> = std::sync::RwLock::new(None);
+/// Global cached preemption policy, read when a waiting mission finds no free slot.
+static PREEMPTION_CACHED: std::sync::RwLock<Option<PreemptionSettings>> =
+    std::sync::RwLock::new(None);
+/// Global cached issuers of short-lived mission credentials.
+static MISSION_CREDENTIALS_CACHED: std::sync::RwLock<Vec<CredentialIssuer>> =
+    std::sync::RwLock::new(Vec::new());
 
 /// Default repo path for sandboxed.sh source (used for self-updates).
 pub const DEFAULT_SANDBOXED_REPO_PATH: &str = "/opt/sandboxed-sh/vaduz-v1";
@@ -30,6 +79,338 @@ pub struct AuthSettings {
     pub password_changed_at: Option<String>,
 }
 
+/// Nightly maintenance job (see `api::maintenance`). Every step that deletes
+/// data is off unless enabled here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceSettings {
+    /// Run the job once a day from the scheduler.
+    pub enabled: bool,
+    /// UTC hour (0-23) after which the nightly run starts.
+    pub run_at_hour: u8,
+    /// Pull the configuration library from its remote.
+    pub sync_library: bool,
+    /// Delete OpenCode sessions not updated for `opencode_session_max_age_hours`.
+    pub cleanup_opencode_sessions: bool,
+    pub opencode_session_max_age_hours: u64,
+    /// Upload events older than `event_export.export_after_days` to object
+    /// storage. While enabled, events are only pruned after a successful
+    /// export.
+    pub export_events: bool,
+    pub event_export: EventExportSettings,
+    /// Delete tool/streaming events of finished missions older than
+    /// `event_retention_days` (user and assistant messages are kept).
+    pub prune_events: bool,
+    pub event_retention_days: u64,
+    /// Delete the directories of finished missions not updated for
+    /// `workspace_retention_days`.
+    pub gc_workspaces: bool,
+    pub workspace_retention_days: u64,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            run_at_hour: 3,
+            sync_library: true,
+            cleanup_opencode_sessions: false,
+            opencode_session_max_age_hours: 168,
+            export_events: false,
+            event_export: EventExportSettings::default(),
+            prune_events: false,
+            event_retention_days: 30,
+            gc_workspaces: false,
+            workspace_retention_days: 14,
+        }
+    }
+}
+
+impl MaintenanceSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.run_at_hour > 23 {
+            return Err("run_at_hour must be between 0 and 23".to_string());
+        }
+        if self.opencode_session_max_age_hours == 0
+            || self.event_retention_days == 0
+            || self.workspace_retention_days == 0
+        {
+            return Err("retention periods must be at least 1".to_string());
+        }
+        if self.export_events {
+            self.event_export.validate()?;
+            if self.prune_events && self.event_retention_days < self.event_export.export_after_days
+            {
+                return Err(
+                    "event_retention_days must be at least event_export.export_after_days"
+                        .to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Object storage destination of the event export (see `api::event_export`).
+/// Credentials come from `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventExportSettings {
+    /// S3-compatible endpoint, e.g. `https://s3.eu-west-1.amazonaws.com` or
+    /// `https://storage.googleapis.com`.
+    pub endpoint: Option<String>,
+    pub bucket: Option<String>,
+    pub region: String,
+    /// Key prefix objects are written under.
+    pub prefix: String,
+    pub format: EventExportFormat,
+    /// Events older than this many days are exported.
+    pub export_after_days: u64,
+}
+
+impl Default for EventExportSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            bucket: None,
+            region: "us-east-1".to_string(),
+            prefix: "sandboxed-sh".to_string(),
+            format: EventExportFormat::default(),
+            export_after_days: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventExportFormat {
+    #[default]
+    Jsonl,
+    Parquet,
+}
+
+impl EventExportSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.endpoint.as_deref() {
+            Some(url) if url.starts_with("https://") || url.starts_with("http://") => {}
+            Some(_) => return Err("event_export.endpoint must be an http(s) URL".to_string()),
+            None => return Err("event_export.endpoint is required".to_string()),
+        }
+        if self.bucket.as_deref().is_none_or(|b| b.trim().is_empty()) {
+            return Err("event_export.bucket is required".to_string());
+        }
+        if self.region.trim().is_empty() {
+            return Err("event_export.region must not be empty".to_string());
+        }
+        if self.export_after_days == 0 {
+            return Err("event_export.export_after_days must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Spending alerts (see `api::spending_alerts`). Each check is off until its
+/// threshold is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpendingAlertSettings {
+    pub enabled: bool,
+    /// Webhook alerts are posted to. Slack incoming webhooks
+    /// (`https://hooks.slack.com/...`) get Slack-formatted messages.
+    pub webhook_url: Option<String>,
+    /// Dashboard base URL used for links to the offending missions.
+    pub dashboard_url: Option<String>,
+    /// Alert once a day when spend since midnight (UTC) exceeds this.
+    pub daily_spend_cents: Option<u64>,
+    /// Alert when a mission costs more than this many times the median
+    /// mission cost of the last 30 days.
+    pub mission_cost_multiplier: Option<f64>,
+    /// Missions cheaper than this never trigger the median check.
+    pub min_mission_cost_cents: u64,
+    /// Alert when tokens used in the last hour exceed this many times the
+    /// hourly average of the 24 hours before.
+    pub token_spike_multiplier: Option<f64>,
+    /// Hours with fewer tokens than this never trigger the spike check.
+    pub min_hourly_tokens: u64,
+}
+
+impl Default for SpendingAlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            webhook_url: None,
+            dashboard_url: None,
+            daily_spend_cents: None,
+            mission_cost_multiplier: None,
+            min_mission_cost_cents: 100,
+            token_spike_multiplier: None,
+            min_hourly_tokens: 100_000,
+        }
+    }
+}
+
+impl SpendingAlertSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, url) in [
+            ("webhook_url", &self.webhook_url),
+            ("dashboard_url", &self.dashboard_url),
+        ] {
+            if let Some(url) = url {
+                if !url.starts_with("https://") && !url.starts_with("http://") {
+                    return Err(format!("{} must be an http(s) URL", name));
+                }
+            }
+        }
+        for (name, value) in [
+            ("mission_cost_multiplier", self.mission_cost_multiplier),
+            ("token_spike_multiplier", self.token_spike_multiplier),
+        ] {
+            if value.is_some_and(|v| !v.is_finite() || v <= 1.0) {
+                return Err(format!("{} must be greater than 1", name));
+            }
+        }
+        if self.enabled && self.webhook_url.is_none() {
+            return Err("webhook_url is required when alerts are enabled".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A budget bucket (e.g. a team or repo) that mission cost is attributed to,
+/// see `api::budget`. A mission belongs to the first bucket listing one of its
+/// tags, otherwise to the first bucket listing its workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetBucket {
+    pub name: String,
+    /// Monthly spending cap in cents. Once reached, new missions attributed to
+    /// the bucket are refused until the next month. None = uncapped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_cap_cents: Option<u64>,
+    /// Mission tags attributed to this bucket.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Workspaces whose missions are attributed to this bucket.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<uuid::Uuid>,
+}
+
+impl BudgetBucket {
+    /// Validate a list of buckets: names must be non-empty and unique.
+    pub fn validate_all(buckets: &[BudgetBucket]) -> Result<(), String> {
+        let mut names = std::collections::HashSet::new();
+        for bucket in buckets {
+            let name = bucket.name.trim();
+            if name.is_empty() {
+                return Err("bucket name must not be empty".to_string());
+            }
+            if name == crate::api::budget::UNATTRIBUTED {
+                return Err(format!("'{}' is a reserved bucket name", name));
+            }
+            if !names.insert(name) {
+                return Err(format!("duplicate bucket name '{}'", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fair-share scheduling of parallel missions across users (see
+/// `api::fair_share`). Off unless enabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FairShareSettings {
+    pub enabled: bool,
+    /// Parallel missions across all users. None = no shared cap, only the
+    /// per-user ceilings apply.
+    pub max_workers: Option<usize>,
+    /// Half-life of the recent usage that orders waiting users, in minutes.
+    pub usage_half_life_minutes: u64,
+    /// Limits for users without an entry in `tenants`.
+    pub default_limits: TenantLimits,
+    /// Limits per user ID.
+    pub tenants: BTreeMap<String, TenantLimits>,
+}
+
+impl Default for FairShareSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_workers: None,
+            usage_half_life_minutes: 60,
+            default_limits: TenantLimits::default(),
+            tenants: BTreeMap::new(),
+        }
+    }
+}
+
+/// Share weight and concurrency floor/ceiling of one user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantLimits {
+    /// Relative share of the workers; usage is divided by it.
+    pub weight: f64,
+    /// Missions the user can always run while waiting for a worker, ahead of
+    /// users above their floor.
+    pub min_concurrency: usize,
+    /// Missions the user may run at once. None = no ceiling.
+    pub max_concurrency: Option<usize>,
+}
+
+impl Default for TenantLimits {
+    fn default() -> Self {
+        Self {
+            weight: 1.0,
+            min_concurrency: 0,
+            max_concurrency: None,
+        }
+    }
+}
+
+impl FairShareSettings {
+    /// Limits for a user.
+    pub fn limits(&self, tenant: &str) -> &TenantLimits {
+        self.tenants.get(tenant).unwrap_or(&self.default_limits)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_workers == Some(0) {
+            return Err("max_workers must be at least 1".to_string());
+        }
+        if self.usage_half_life_minutes == 0 {
+            return Err("usage_half_life_minutes must be at least 1".to_string());
+        }
+        let named = self.tenants.iter().map(|(k, v)| (k.as_str(), v));
+        for (name, limits) in std::iter::once(("default_limits", &self.default_limits)).chain(named)
+        {
+            if name.trim().is_empty() {
+                return Err("tenant IDs must not be empty".to_string());
+            }
+            if !limits.weight.is_finite() || limits.weight <= 0.0 {
+                return Err(format!("{}: weight must be greater than 0", name));
+            }
+            if limits
+                .max_concurrency
+                .is_some_and(|max| max == 0 || max < limits.min_concurrency)
+            {
+                return Err(format!(
+                    "{}: max_concurrency must be at least 1 and at least min_concurrency",
+                    name
+                ));
+            }
+        }
+        if let Some(max_workers) = self.max_workers {
+            let floors: usize = self.tenants.values().map(|l| l.min_concurrency).sum();
+            if floors > max_workers {
+                return Err(format!(
+                    "tenant floors ({}) exceed max_workers ({})",
+                    floors, max_workers
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Global application settings.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Settings {
@@ -50,6 +431,56 @@ pub struct Settings {
     /// When None, falls back to the MAX_PARALLEL_MISSIONS env var (default: 1).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_parallel_missions: Option<usize>,
+    /// Global quiet hours during which scheduled and webhook automations are deferred.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quiet_hours: Vec<QuietHours>,
+    /// Maintenance windows during which the automation scheduler is paused.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+    /// Nightly maintenance job configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<MaintenanceSettings>,
+    /// Default locale and time zone for missions (overridable per mission).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<LocaleSettings>,
+    /// Budget buckets for cost attribution and monthly caps.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub budget_buckets: Vec<BudgetBucket>,
+    /// Spending alert thresholds and delivery.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spending_alerts: Option<SpendingAlertSettings>,
+    /// Record raw LLM requests/responses per mission for debug bundles
+    /// (see `api::llm_recorder`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_recording: Option<bool>,
+    /// Model chain retried when a request still exceeds the context window
+    /// after older tool results were trimmed (e.g. "builtin/long-context").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_context_model: Option<String>,
+    /// Organization-wide model allow/deny list and hosting regions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_policy: Option<ModelPolicy>,
+    /// Fair-share scheduling of parallel missions across users.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fair_share: Option<FairShareSettings>,
+    /// Rollout percentage per executor feature flag (see `feature_flags`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub feature_flags: BTreeMap<String, u8>,
+    /// Default turn and tool call limits of missions (see `api::step_budget`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_budget: Option<StepBudget>,
+    /// Handling of repeated failing tool calls (see `api::tool_loops`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_loops: Option<ToolLoopSettings>,
+    /// Per-mission quotas for expensive tools (see `tool_quotas`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_quotas: Option<ToolQuotas>,
+    /// Preemption of running missions by urgent ones (see `api::mission_priority`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preemption: Option<PreemptionSettings>,
+    /// Issuers of short-lived mission credentials (see `api::mission_credentials`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mission_credentials: Vec<CredentialIssuer>,
 }
 
 /// In-memory store for global settings with disk persistence.
@@ -123,6 +554,22 @@ impl SettingsStore {
             auth: None,
             rtk_enabled,
             max_parallel_missions,
+            quiet_hours: Vec::new(),
+            maintenance_windows: Vec::new(),
+            maintenance: None,
+            locale: None,
+            budget_buckets: Vec::new(),
+            spending_alerts: None,
+            llm_recording: None,
+            long_context_model: None,
+            model_policy: None,
+            fair_share: None,
+            feature_flags: BTreeMap::new(),
+            step_budget: None,
+            tool_loops: None,
+            tool_quotas: None,
+            preemption: None,
+            mission_credentials: Vec::new(),
         }
     }
 
@@ -290,6 +737,24 @@ impl SettingsStore {
             if let Some(limit) = settings.max_parallel_missions {
                 set_max_parallel_missions_cached(limit);
             }
+            set_schedule_windows_cached(
+                settings.quiet_hours.clone(),
+                settings.maintenance_windows.clone(),
+            );
+            set_maintenance_settings_cached(settings.maintenance.clone());
+            set_locale_settings_cached(settings.locale.clone());
+            set_budget_buckets_cached(settings.budget_buckets.clone());
+            set_spending_alert_settings_cached(settings.spending_alerts.clone());
+            set_llm_recording_cached(settings.llm_recording.unwrap_or(false));
+            set_long_context_model_cached(settings.long_context_model.clone());
+            set_model_policy_cached(settings.model_policy.clone());
+            set_fair_share_settings_cached(settings.fair_share.clone());
+            set_feature_flag_rollouts_cached(settings.feature_flags.clone());
+            set_step_budget_cached(settings.step_budget);
+            set_tool_loop_settings_cached(settings.tool_loops);
+            set_tool_quotas_cached(settings.tool_quotas);
+            set_preemption_settings_cached(settings.preemption);
+            set_mission_credentials_cached(settings.mission_credentials.clone());
         }
     }
 }
@@ -309,6 +774,31 @@ pub fn set_rtk_enabled_cached(enabled: bool) {
     RTK_ENABLED_CACHED.store(enabled, Ordering::Relaxed);
 }
 
+/// Whether LLM calls are recorded for debug bundles.
+pub fn llm_recording_cached() -> bool {
+    LLM_RECORDING_CACHED.load(Ordering::Relaxed)
+}
+
+/// Update the cached LLM call recording state.
+pub fn set_llm_recording_cached(enabled: bool) {
+    LLM_RECORDING_CACHED.store(enabled, Ordering::Relaxed);
+}
+
+/// Model chain to fall back to on context overflow, if configured.
+pub fn long_context_model_cached() -> Option<String> {
+    LONG_CONTEXT_MODEL_CACHED
+        .read()
+        .ok()
+        .and_then(|model| model.clone())
+}
+
+/// Update the cached long-context fallback chain.
+pub fn set_long_context_model_cached(model: Option<String>) {
+    if let Ok(mut cached) = LONG_CONTEXT_MODEL_CACHED.write() {
+        *cached = model;
+    }
+}
+
 /// Get the effective max parallel missions limit from cache, with a fallback default.
 pub fn max_parallel_missions_cached_or(default: usize) -> usize {
     let cached = MAX_PARALLEL_MISSIONS_CACHED.load(Ordering::Relaxed);
@@ -326,3 +816,215 @@ pub fn max_parallel_missions_cached_or(default: usize) -> usize {
 pub fn set_max_parallel_missions_cached(max_parallel_missions: usize) {
     MAX_PARALLEL_MISSIONS_CACHED.store(max_parallel_missions.max(1), Ordering::Relaxed);
 }
+
+/// Get the cached global quiet hours and maintenance windows.
+pub fn schedule_windows_cached() -> (Vec<QuietHours>, Vec<MaintenanceWindow>) {
+    SCHEDULE_WINDOWS_CACHED
+        .read()
+        .map(|windows| windows.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached quiet hours and maintenance windows.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_schedule_windows_cached(
+    quiet_hours: Vec<QuietHours>,
+    maintenance_windows: Vec<MaintenanceWindow>,
+) {
+    if let Ok(mut windows) = SCHEDULE_WINDOWS_CACHED.write() {
+        *windows = (quiet_hours, maintenance_windows);
+    }
+}
+
+/// Get the cached maintenance settings (defaults when unset).
+pub fn maintenance_settings_cached() -> MaintenanceSettings {
+    MAINTENANCE_CACHED
+        .read()
+        .ok()
+        .and_then(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached maintenance settings.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_maintenance_settings_cached(settings: Option<MaintenanceSettings>) {
+    if let Ok(mut cached) = MAINTENANCE_CACHED.write() {
+        *cached = settings;
+    }
+}
+
+/// Get the cached global locale settings (empty when unset).
+pub fn locale_settings_cached() -> LocaleSettings {
+    LOCALE_CACHED
+        .read()
+        .ok()
+        .and_then(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached global locale settings.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_locale_settings_cached(settings: Option<LocaleSettings>) {
+    if let Ok(mut cached) = LOCALE_CACHED.write() {
+        *cached = settings;
+    }
+}
+
+/// Get the cached budget buckets.
+pub fn budget_buckets_cached() -> Vec<BudgetBucket> {
+    BUDGET_BUCKETS_CACHED
+        .read()
+        .map(|buckets| buckets.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached budget buckets.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_budget_buckets_cached(buckets: Vec<BudgetBucket>) {
+    if let Ok(mut cached) = BUDGET_BUCKETS_CACHED.write() {
+        *cached = buckets;
+    }
+}
+
+/// Get the cached spending alert settings (defaults when unset).
+pub fn spending_alert_settings_cached() -> SpendingAlertSettings {
+    SPENDING_ALERTS_CACHED
+        .read()
+        .ok()
+        .and_then(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached spending alert settings.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_spending_alert_settings_cached(settings: Option<SpendingAlertSettings>) {
+    if let Ok(mut cached) = SPENDING_ALERTS_CACHED.write() {
+        *cached = settings;
+    }
+}
+
+/// Get the cached model policy (empty when unset).
+pub fn model_policy_cached() -> ModelPolicy {
+    MODEL_POLICY_CACHED
+        .read()
+        .ok()
+        .and_then(|policy| policy.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached model policy.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_model_policy_cached(policy: Option<ModelPolicy>) {
+    if let Ok(mut cached) = MODEL_POLICY_CACHED.write() {
+        *cached = policy;
+    }
+}
+
+/// Get the cached fair-share settings (disabled when unset).
+pub fn fair_share_settings_cached() -> FairShareSettings {
+    FAIR_SHARE_CACHED
+        .read()
+        .ok()
+        .and_then(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached fair-share settings.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_fair_share_settings_cached(settings: Option<FairShareSettings>) {
+    if let Ok(mut cached) = FAIR_SHARE_CACHED.write() {
+        *cached = settings;
+    }
+}
+
+/// Get the cached feature flag rollout percentages.
+pub fn feature_flag_rollouts_cached() -> BTreeMap<String, u8> {
+    FEATURE_FLAGS_CACHED
+        .read()
+        .map(|rollouts| rollouts.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached feature flag rollout percentages.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_feature_flag_rollouts_cached(rollouts: BTreeMap<String, u8>) {
+    if let Ok(mut cached) = FEATURE_FLAGS_CACHED.write() {
+        *cached = rollouts;
+    }
+}
+
+/// Get the cached default step budget.
+pub fn step_budget_cached() -> Option<StepBudget> {
+    STEP_BUDGET_CACHED.read().ok().and_then(|budget| *budget)
+}
+
+/// Update the cached default step budget.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_step_budget_cached(budget: Option<StepBudget>) {
+    if let Ok(mut cached) = STEP_BUDGET_CACHED.write() {
+        *cached = budget;
+    }
+}
+
+/// Get the cached repeated tool call handling (defaults when unset).
+pub fn tool_loop_settings_cached() -> ToolLoopSettings {
+    TOOL_LOOPS_CACHED
+        .read()
+        .ok()
+        .and_then(|settings| *settings)
+        .unwrap_or_default()
+}
+
+/// Update the cached repeated tool call handling.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_tool_loop_settings_cached(settings: Option<ToolLoopSettings>) {
+    if let Ok(mut cached) = TOOL_LOOPS_CACHED.write() {
+        *cached = settings;
+    }
+}
+
+/// Get the cached tool quotas.
+pub fn tool_quotas_cached() -> Option<ToolQuotas> {
+    TOOL_QUOTAS_CACHED.read().ok().and_then(|quotas| *quotas)
+}
+
+/// Update the cached tool quotas.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_tool_quotas_cached(quotas: Option<ToolQuotas>) {
+    if let Ok(mut cached) = TOOL_QUOTAS_CACHED.write() {
+        *cached = quotas;
+    }
+}
+
+/// Get the cached preemption policy (disabled when unset).
+pub fn preemption_settings_cached() -> PreemptionSettings {
+    PREEMPTION_CACHED
+        .read()
+        .ok()
+        .and_then(|settings| *settings)
+        .unwrap_or_default()
+}
+
+/// Update the cached preemption policy.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_preemption_settings_cached(settings: Option<PreemptionSettings>) {
+    if let Ok(mut cached) = PREEMPTION_CACHED.write() {
+        *cached = settings;
+    }
+}
+
+/// Get the cached issuers of mission credentials.
+pub fn mission_credentials_cached() -> Vec<CredentialIssuer> {
+    MISSION_CREDENTIALS_CACHED
+        .read()
+        .map(|issuers| issuers.clone())
+        .unwrap_or_default()
+}
+
+/// Update the cached issuers of mission credentials.
+/// Called during startup and when the settings are changed via the API.
+pub fn set_mission_credentials_cached(issuers: Vec<CredentialIssuer>) {
+    if let Ok(mut cached) = MISSION_CREDENTIALS_CACHED.write() {
+        *cached = issuers;
+    }
+}
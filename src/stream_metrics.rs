@@ -0,0 +1,307 @@
+//! Streaming health per provider and model.
+//!
+//! Every streamed completion, whether it went through the `/v1` proxy or was
+//! run by a backend harness, is recorded as a [`StreamSample`]: time to the
+//! first token, output tokens, duration and whether the stream failed. The
+//! totals are exposed as Prometheus text on `GET /metrics` and as JSON on
+//! `GET /api/models/health`.
+//!
+//! Recent behaviour is also kept as moving averages, which
+//! [`ModelChainStore::resolve_chain`](crate::provider_health::ModelChainStore::resolve_chain)
+//! uses to move failing or slow models behind healthy ones in a failover
+//! chain. Chain order is kept otherwise.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+/// Weight of the newest sample in the moving averages.
+const EWMA_ALPHA: f64 = 0.2;
+
+/// Streams needed before a model's recent behaviour affects failover order.
+pub const MIN_STREAMS_FOR_RANKING: u64 = 5;
+
+/// Recent error rate from which a model is moved behind healthy ones.
+pub const FAILING_ERROR_RATE: f64 = 0.5;
+
+/// A model whose recent time to first token is this many times the fastest
+/// model's in the same chain is moved behind faster ones.
+pub const SLOW_TTFT_FACTOR: f64 = 3.0;
+
+/// One streamed completion.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StreamSample {
+    /// Time until the first token arrived, if one did
+    pub ttft_ms: Option<u64>,
+    pub output_tokens: u64,
+    /// Time from the request to the end of the stream
+    pub duration_ms: u64,
+    pub errored: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct StreamStats {
+    streams: u64,
+    errors: u64,
+    ttft_ms_sum: u64,
+    ttft_samples: u64,
+    output_tokens: u64,
+    /// Time spent generating after the first token, for tokens/sec
+    generation_ms: u64,
+    recent_ttft_ms: Option<f64>,
+    recent_error_rate: f64,
+}
+
+impl StreamStats {
+    fn add(&mut self, sample: StreamSample) {
+        self.streams += 1;
+        let error = if sample.errored { 1.0 } else { 0.0 };
+        self.recent_error_rate = if self.streams == 1 {
+            error
+        } else {
+            EWMA_ALPHA * error + (1.0 - EWMA_ALPHA) * self.recent_error_rate
+        };
+        if sample.errored {
+            self.errors += 1;
+        }
+        if let Some(ttft) = sample.ttft_ms {
+            self.ttft_ms_sum += ttft;
+            self.ttft_samples += 1;
+            self.recent_ttft_ms = Some(match self.recent_ttft_ms {
+                Some(recent) => EWMA_ALPHA * ttft as f64 + (1.0 - EWMA_ALPHA) * recent,
+                None => ttft as f64,
+            });
+            if !sample.errored && sample.output_tokens > 0 {
+                self.output_tokens += sample.output_tokens;
+                self.generation_ms += sample.duration_ms.saturating_sub(ttft).max(1);
+            }
+        }
+    }
+}
+
+/// Streaming health of one provider/model, since startup.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelHealth {
+    pub provider: String,
+    pub model: String,
+    pub streams: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    /// Moving average, weighted towards the latest streams
+    pub recent_error_rate: f64,
+    pub avg_ttft_ms: Option<f64>,
+    pub recent_ttft_ms: Option<f64>,
+    /// Output tokens per second after the first token
+    pub tokens_per_second: Option<f64>,
+}
+
+impl ModelHealth {
+    fn from_stats(provider: &str, model: &str, stats: &StreamStats) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            streams: stats.streams,
+            errors: stats.errors,
+            error_rate: if stats.streams == 0 {
+                0.0
+            } else {
+                stats.errors as f64 / stats.streams as f64
+            },
+            recent_error_rate: stats.recent_error_rate,
+            avg_ttft_ms: (stats.ttft_samples > 0)
+                .then(|| stats.ttft_ms_sum as f64 / stats.ttft_samples as f64),
+            recent_ttft_ms: stats.recent_ttft_ms,
+            tokens_per_second: (stats.generation_ms > 0)
+                .then(|| stats.output_tokens as f64 * 1000.0 / stats.generation_ms as f64),
+        }
+    }
+}
+
+static STATS: Mutex<BTreeMap<(String, String), StreamStats>> = Mutex::new(BTreeMap::new());
+
+/// Record a streamed completion of `model` from `provider`.
+pub fn record(provider: &str, model: &str, sample: StreamSample) {
+    let mut stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    stats
+        .entry((provider.to_string(), model.to_string()))
+        .or_default()
+        .add(sample);
+}
+
+/// Streaming health of every provider/model seen since startup.
+pub fn health() -> Vec<ModelHealth> {
+    let stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    stats
+        .iter()
+        .map(|((provider, model), s)| ModelHealth::from_stats(provider, model, s))
+        .collect()
+}
+
+fn health_of(provider: &str, model: &str) -> Option<ModelHealth> {
+    let stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    stats
+        .get(&(provider.to_string(), model.to_string()))
+        .map(|s| ModelHealth::from_stats(provider, model, s))
+}
+
+/// Order `entries` of a failover chain by recent streaming health: models
+/// that mostly fail go last, then models much slower to the first token than
+/// the fastest one. Models with too few streams count as healthy, and the
+/// sort is stable so chain order decides otherwise.
+pub fn rank_by_health<T>(entries: Vec<T>, key: impl Fn(&T) -> (&str, &str)) -> Vec<T> {
+    let health: Vec<Option<ModelHealth>> = entries
+        .iter()
+        .map(|entry| {
+            let (provider, model) = key(entry);
+            health_of(provider, model).filter(|h| h.streams >= MIN_STREAMS_FOR_RANKING)
+        })
+        .collect();
+    let fastest = health
+        .iter()
+        .flatten()
+        .filter(|h| h.recent_error_rate < FAILING_ERROR_RATE)
+        .filter_map(|h| h.recent_ttft_ms)
+        .fold(None, |min: Option<f64>, t| {
+            Some(min.map_or(t, |m| m.min(t)))
+        });
+    let tiers: Vec<u8> = health
+        .iter()
+        .map(|h| match h {
+            Some(h) if h.recent_error_rate >= FAILING_ERROR_RATE => 2,
+            Some(ModelHealth {
+                recent_ttft_ms: Some(ttft),
+                ..
+            }) if fastest.is_some_and(|f| *ttft > f * SLOW_TTFT_FACTOR) => 1,
+            _ => 0,
+        })
+        .collect();
+    let mut ranked: Vec<(u8, T)> = tiers.into_iter().zip(entries).collect();
+    ranked.sort_by_key(|(tier, _)| *tier);
+    ranked.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render the stream stats in the Prometheus text exposition format.
+pub fn prometheus_text() -> String {
+    let stats = STATS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: &dyn Fn(&StreamStats) -> f64| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for ((provider, model), s) in &stats {
+            let _ = writeln!(
+                out,
+                "{}{{provider=\"{}\",model=\"{}\"}} {}",
+                name,
+                label(provider),
+                label(model),
+                value(s)
+            );
+        }
+    };
+    metric(
+        "sandboxed_stream_requests_total",
+        "counter",
+        "Streamed completions.",
+        &|s| s.streams as f64,
+    );
+    metric(
+        "sandboxed_stream_errors_total",
+        "counter",
+        "Streamed completions that failed.",
+        &|s| s.errors as f64,
+    );
+    metric(
+        "sandboxed_stream_ttft_seconds_total",
+        "counter",
+        "Total time to first token.",
+        &|s| s.ttft_ms_sum as f64 / 1000.0,
+    );
+    metric(
+        "sandboxed_stream_first_tokens_total",
+        "counter",
+        "Streams with a first token.",
+        &|s| s.ttft_samples as f64,
+    );
+    metric(
+        "sandboxed_stream_output_tokens_total",
+        "counter",
+        "Output tokens of successful streams.",
+        &|s| s.output_tokens as f64,
+    );
+    metric(
+        "sandboxed_stream_generation_seconds_total",
+        "counter",
+        "Time spent generating after the first token.",
+        &|s| s.generation_ms as f64 / 1000.0,
+    );
+    metric(
+        "sandboxed_stream_recent_error_rate",
+        "gauge",
+        "Moving average of the stream error rate.",
+        &|s| s.recent_error_rate,
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(ttft_ms: u64, errored: bool) -> StreamSample {
+        StreamSample {
+            ttft_ms: (!errored).then_some(ttft_ms),
+            output_tokens: if errored { 0 } else { 100 },
+            duration_ms: ttft_ms + 2000,
+            errored,
+        }
+    }
+
+    #[test]
+    fn test_health_and_prometheus_text() {
+        for _ in 0..3 {
+            record("test-health", "m\"1", sample(400, false));
+        }
+        record("test-health", "m\"1", sample(0, true));
+        let health = health()
+            .into_iter()
+            .find(|h| h.provider == "test-health")
+            .unwrap();
+        assert_eq!(health.streams, 4);
+        assert_eq!(health.errors, 1);
+        assert_eq!(health.avg_ttft_ms, Some(400.0));
+        assert_eq!(health.tokens_per_second, Some(50.0));
+
+        let text = prometheus_text();
+        assert!(text.contains("# TYPE sandboxed_stream_errors_total counter"));
+        assert!(text.contains(
+            "sandboxed_stream_requests_total{provider=\"test-health\",model=\"m\\\"1\"} 4"
+        ));
+    }
+
+    #[test]
+    fn test_rank_by_health_demotes_failing_and_slow_models() {
+        for _ in 0..MIN_STREAMS_FOR_RANKING {
+            record("test-rank", "failing", sample(0, true));
+            record("test-rank", "slow", sample(9000, false));
+            record("test-rank", "fast", sample(500, false));
+        }
+        // Too few streams to judge: keeps its place.
+        record("test-rank", "new", sample(0, true));
+
+        let entries = vec!["failing", "slow", "new", "fast", "unknown"];
+        let entries = rank_by_health(entries, |m| ("test-rank", m));
+        assert_eq!(entries, vec!["new", "fast", "unknown", "slow", "failing"]);
+
+        let healthy = rank_by_health(vec!["fast", "new"], |m| ("test-rank", m));
+        assert_eq!(healthy, vec!["fast", "new"]);
+    }
+}
@@ -0,0 +1,427 @@
+//! Drift between a workspace and the template it was built from.
+//!
+//! When a container workspace built from a template finishes building, a
+//! [`TemplateFingerprint`] of the template is stored on the workspace: its
+//! skills, env var keys, MCPs and distro, and a hash of every init script
+//! fragment and of the custom script. `GET /api/workspaces/:id/template-drift`
+//! fingerprints the template as it is now, reports what changed, and lists the
+//! steps a rebuild (`POST /api/workspaces/:id/build` with `rebuild: true`)
+//! would run without running them.
+//!
+//! A rebuild runs the workspace's own settings, which were copied from the
+//! template when the workspace was created, with the current content of the
+//! fragments in the library. Changes to the template's lists only apply once
+//! the workspace is updated to match.
+
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::library::{LibraryStore, WorkspaceTemplate};
+use crate::nspawn::{self, NspawnDistro};
+use crate::workspace::{self, Workspace};
+
+/// An init script fragment and a hash of its content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentHash {
+    pub name: String,
+    /// None when the fragment is missing from the library
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl FragmentHash {
+    fn unknown(names: &[String]) -> Vec<FragmentHash> {
+        names
+            .iter()
+            .map(|name| FragmentHash {
+                name: name.clone(),
+                hash: None,
+            })
+            .collect()
+    }
+}
+
+/// What a workspace template looked like, as far as builds are concerned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateFingerprint {
+    pub template: String,
+    pub captured_at: DateTime<Utc>,
+    #[serde(default)]
+    pub skills: Vec<String>,
+    /// Init script fragments, in the template's order
+    #[serde(default)]
+    pub init_scripts: Vec<FragmentHash>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_script_hash: Option<String>,
+    /// Env var keys only; values may be secrets
+    #[serde(default)]
+    pub env_keys: Vec<String>,
+    #[serde(default)]
+    pub mcps: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distro: Option<String>,
+}
+
+fn hash(content: &str) -> String {
+    let digest = Sha256::digest(content.as_bytes());
+    digest
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn script_hash(script: &str) -> Option<String> {
+    let script = script.trim();
+    (!script.is_empty()).then(|| hash(script))
+}
+
+async fn fragment_hashes(library: &LibraryStore, names: &[String]) -> Vec<FragmentHash> {
+    let mut fragments = Vec::with_capacity(names.len());
+    for name in names {
+        let hash = library
+            .get_init_script(name)
+            .await
+            .ok()
+            .map(|script| hash(&script.content));
+        fragments.push(FragmentHash {
+            name: name.clone(),
+            hash,
+        });
+    }
+    fragments
+}
+
+impl TemplateFingerprint {
+    /// Fingerprint `template` with the current fragments in `library`.
+    pub async fn of_template(library: &LibraryStore, template: &WorkspaceTemplate) -> Self {
+        let mut env_keys: Vec<String> = template.env_vars.keys().cloned().collect();
+        env_keys.sort();
+        Self {
+            template: template.name.clone(),
+            captured_at: Utc::now(),
+            skills: template.skills.clone(),
+            init_scripts: fragment_hashes(library, &template.init_scripts).await,
+            init_script_hash: script_hash(&template.init_script),
+            env_keys,
+            mcps: template.mcps.clone(),
+            distro: template.distro.clone(),
+        }
+    }
+
+    /// Best guess for workspaces built before fingerprints were recorded:
+    /// the settings the workspace was created with, without fragment hashes.
+    pub fn of_workspace(workspace: &Workspace) -> Self {
+        let mut env_keys: Vec<String> = workspace.env_vars.keys().cloned().collect();
+        env_keys.sort();
+        Self {
+            template: workspace.template.clone().unwrap_or_default(),
+            captured_at: workspace.created_at,
+            skills: workspace.skills.clone(),
+            init_scripts: FragmentHash::unknown(&workspace.init_scripts),
+            init_script_hash: workspace.init_script.as_deref().and_then(script_hash),
+            env_keys,
+            mcps: workspace.mcps.clone(),
+            distro: workspace.distro.clone(),
+        }
+    }
+
+    fn fragment_hash(&self, name: &str) -> Option<&Option<String>> {
+        self.init_scripts
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| &f.hash)
+    }
+}
+
+/// Entries added and removed between two lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ListDrift {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ListDrift {
+    fn between(before: &[String], after: &[String]) -> Self {
+        let before_set: BTreeSet<&String> = before.iter().collect();
+        let after_set: BTreeSet<&String> = after.iter().collect();
+        Self {
+            added: after
+                .iter()
+                .filter(|s| !before_set.contains(s))
+                .cloned()
+                .collect(),
+            removed: before
+                .iter()
+                .filter(|s| !after_set.contains(s))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FragmentDrift {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Fragments in both whose content changed
+    pub changed: Vec<String>,
+    /// Fragments in both that now run in a different order
+    pub reordered: bool,
+}
+
+/// What changed in a template since a workspace was built from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TemplateDrift {
+    pub drifted: bool,
+    pub skills: ListDrift,
+    pub init_scripts: FragmentDrift,
+    pub init_script_changed: bool,
+    pub env_keys: ListDrift,
+    pub mcps: ListDrift,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distro: Option<DistroChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DistroChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Compare the fingerprint a workspace was built from with the current one.
+/// Fragment content is compared only where both hashes are known.
+pub fn diff(built: &TemplateFingerprint, current: &TemplateFingerprint) -> TemplateDrift {
+    let built_names: Vec<String> = built.init_scripts.iter().map(|f| f.name.clone()).collect();
+    let current_names: Vec<String> = current
+        .init_scripts
+        .iter()
+        .map(|f| f.name.clone())
+        .collect();
+    let fragment_lists = ListDrift::between(&built_names, &current_names);
+    let changed = current
+        .init_scripts
+        .iter()
+        .filter(|f| {
+            matches!(
+                (built.fragment_hash(&f.name), &f.hash),
+                (Some(Some(before)), Some(after)) if before != after
+            )
+        })
+        .map(|f| f.name.clone())
+        .collect();
+    let kept_built: Vec<&String> = built_names
+        .iter()
+        .filter(|n| current_names.contains(n))
+        .collect();
+    let kept_current: Vec<&String> = current_names
+        .iter()
+        .filter(|n| built_names.contains(n))
+        .collect();
+    let init_scripts = FragmentDrift {
+        added: fragment_lists.added,
+        removed: fragment_lists.removed,
+        changed,
+        reordered: kept_built != kept_current,
+    };
+    let skills = ListDrift::between(&built.skills, &current.skills);
+    let env_keys = ListDrift::between(&built.env_keys, &current.env_keys);
+    let mcps = ListDrift::between(&built.mcps, &current.mcps);
+    let init_script_changed = built.init_script_hash != current.init_script_hash;
+    let distro = (built.distro != current.distro).then(|| DistroChange {
+        from: built.distro.clone(),
+        to: current.distro.clone(),
+    });
+    let drifted = !skills.is_empty()
+        || !env_keys.is_empty()
+        || !mcps.is_empty()
+        || init_script_changed
+        || distro.is_some()
+        || init_scripts != FragmentDrift::default();
+    TemplateDrift {
+        drifted,
+        skills,
+        init_scripts,
+        init_script_changed,
+        env_keys,
+        mcps,
+        distro,
+    }
+}
+
+/// One step of a rebuild.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlanStep {
+    pub step: &'static str,
+    pub detail: String,
+    /// Runs something that differs from the last build
+    pub changed: bool,
+}
+
+fn step(step: &'static str, detail: impl Into<String>, changed: bool) -> PlanStep {
+    PlanStep {
+        step,
+        detail: detail.into(),
+        changed,
+    }
+}
+
+/// The steps a forced rebuild of `workspace` would run, in order, marking
+/// those that differ from the build recorded in `built`. Fragments are listed
+/// in the workspace's order; the build may run independent ones together.
+pub async fn rebuild_plan(
+    workspace: &Workspace,
+    library: Option<&LibraryStore>,
+    built: &TemplateFingerprint,
+) -> Vec<PlanStep> {
+    let mut plan = Vec::new();
+    if nspawn::is_container_ready(&workspace.path) {
+        plan.push(step(
+            "destroy_container",
+            format!("Remove the container at {}", workspace.path.display()),
+            false,
+        ));
+    }
+    let distro = workspace
+        .distro
+        .as_deref()
+        .and_then(NspawnDistro::parse)
+        .unwrap_or_default();
+    plan.push(step(
+        "create_container",
+        format!("Install the {} base system", distro.api_value()),
+        workspace.distro != built.distro,
+    ));
+    plan.push(step(
+        "sync_mcp_binaries",
+        "Copy the MCP server binaries",
+        false,
+    ));
+    if workspace::workspace_microvm(workspace).is_some() {
+        plan.push(step("prepare_microvm", "Build a fresh microVM disk", false));
+    }
+
+    let fragments = match library {
+        Some(library) => fragment_hashes(library, &workspace.init_scripts).await,
+        None => FragmentHash::unknown(&workspace.init_scripts),
+    };
+    for fragment in &fragments {
+        let (detail, changed) = match (built.fragment_hash(&fragment.name), &fragment.hash) {
+            (_, None) if library.is_some() => (
+                format!("{} (missing from the library)", fragment.name),
+                true,
+            ),
+            (None, _) => (format!("{} (new)", fragment.name), true),
+            (Some(Some(before)), Some(after)) if before != after => {
+                (format!("{} (changed)", fragment.name), true)
+            }
+            _ => (fragment.name.clone(), false),
+        };
+        plan.push(step("init_fragment", detail, changed));
+    }
+    if let Some(library) = library {
+        for (skill, commands) in library
+            .collect_skill_setup_commands(&workspace.skills)
+            .await
+        {
+            let changed = !built.skills.contains(&skill);
+            plan.push(step(
+                "skill_setup",
+                format!("{} ({} setup commands)", skill, commands.len()),
+                changed,
+            ));
+        }
+    }
+    if let Some(script_hash) = workspace.init_script.as_deref().and_then(script_hash) {
+        plan.push(step(
+            "custom_init_script",
+            "Run the workspace's init script",
+            built.init_script_hash.as_deref() != Some(script_hash.as_str()),
+        ));
+    }
+    plan.push(step(
+        "install_harnesses",
+        "Install missing agent harnesses",
+        false,
+    ));
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(fragments: &[(&str, Option<&str>)]) -> TemplateFingerprint {
+        TemplateFingerprint {
+            template: "rust".to_string(),
+            captured_at: Utc::now(),
+            skills: vec!["cargo".to_string(), "git".to_string()],
+            init_scripts: fragments
+                .iter()
+                .map(|(name, hash)| FragmentHash {
+                    name: name.to_string(),
+                    hash: hash.map(str::to_string),
+                })
+                .collect(),
+            init_script_hash: script_hash("echo hi"),
+            env_keys: vec!["RUST_LOG".to_string()],
+            mcps: Vec::new(),
+            distro: Some("ubuntu-noble".to_string()),
+        }
+    }
+
+    #[test]
+    fn diff_reports_list_and_content_changes() {
+        let built = fingerprint(&[("base", Some("a")), ("rust", Some("b")), ("node", None)]);
+        assert!(!diff(&built, &built).drifted);
+
+        let mut current = fingerprint(&[("rust", Some("c")), ("base", Some("a")), ("py", None)]);
+        current.skills = vec!["git".to_string(), "clippy".to_string()];
+        current.env_keys.push("CARGO_HOME".to_string());
+        current.init_script_hash = None;
+        let drift = diff(&built, &current);
+        assert!(drift.drifted);
+        assert_eq!(drift.skills.added, vec!["clippy"]);
+        assert_eq!(drift.skills.removed, vec!["cargo"]);
+        assert_eq!(drift.env_keys.added, vec!["CARGO_HOME"]);
+        assert_eq!(drift.init_scripts.added, vec!["py"]);
+        assert_eq!(drift.init_scripts.removed, vec!["node"]);
+        assert_eq!(drift.init_scripts.changed, vec!["rust"]);
+        assert!(drift.init_scripts.reordered);
+        assert!(drift.init_script_changed);
+        assert!(drift.distro.is_none());
+    }
+
+    #[tokio::test]
+    async fn rebuild_plan_marks_new_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut workspace = Workspace::new_container("ws".to_string(), dir.path().join("ws"));
+        workspace.template = Some("rust".to_string());
+        workspace.distro = Some("ubuntu-noble".to_string());
+        workspace.init_scripts = vec!["base".to_string(), "extra".to_string()];
+        workspace.init_script = Some("echo changed".to_string());
+        let built = fingerprint(&[("base", None)]);
+
+        let plan = rebuild_plan(&workspace, None, &built).await;
+        let steps: Vec<(&str, bool)> = plan.iter().map(|s| (s.step, s.changed)).collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("create_container", false),
+                ("sync_mcp_binaries", false),
+                ("init_fragment", false),
+                ("init_fragment", true),
+                ("custom_init_script", true),
+                ("install_harnesses", false),
+            ]
+        );
+        assert_eq!(plan[3].detail, "extra (new)");
+    }
+}
@@ -0,0 +1,490 @@
+//! Per-turn tool schema pruning for the workspace MCP server.
+//!
+//! Sending every tool schema on each turn costs prompt tokens the model rarely
+//! needs. When [`PRUNING_SETTING`] is enabled for a workspace (or the mission
+//! has the `tool_pruning` feature flag), the workspace MCP answers `tools/list`
+//! with a relevant subset:
+//!
+//! - core filesystem/search tools are always kept,
+//! - tools the agent's `tools` frontmatter disables are dropped (even core),
+//! - tools the agent explicitly enables, or that were used recently, are kept,
+//! - remaining tools are kept when the turn's prompt mentions related keywords.
+//!
+//! The mission runner writes the turn's prompt and agent tool permissions to
+//! [`TURN_HINTS_FILE`] in the mission directory before spawning the harness.
+//! The MCP records tool calls in [`TOOL_USAGE_FILE`] and the size of each
+//! pruned listing in [`PRUNING_STATS_FILE`], which the runner folds into
+//! process-wide totals after the turn. Pruned tools remain callable by name.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// Workspace env var that turns pruning on.
+pub const PRUNING_SETTING: &str = "SANDBOXED_SH_TOOL_PRUNING";
+
+pub const TURN_HINTS_FILE: &str = ".sandboxed-sh_turn.json";
+pub const TOOL_USAGE_FILE: &str = ".sandboxed-sh_tool_usage.json";
+pub const PRUNING_STATS_FILE: &str = ".sandboxed-sh_tool_pruning.json";
+
+/// Tools listed on every turn regardless of the prompt.
+pub const CORE_TOOLS: &[&str] = &[
+    "read_file",
+    "write_file",
+    "delete_file",
+    "list_directory",
+    "search_files",
+    "grep_search",
+    "set_plan",
+    "update_plan",
+];
+
+/// Prompt keywords that make a tool (or tool-name prefix) relevant.
+const TOOL_KEYWORDS: &[(&str, &[&str])] = &[
+    (
+        "git_",
+        &[
+            "git", "commit", "branch", "push", "rebase", "merge", "checkout",
+        ],
+    ),
+    (
+        "gh_pr_",
+        &["pr", "pull request", "github", "review", "reviewer", "diff"],
+    ),
+    (
+        "tracker_",
+        &["jira", "linear", "issue", "ticket", "subtask", "transition"],
+    ),
+    (
+        "docker_",
+        &[
+            "docker",
+            "dockerfile",
+            "container",
+            "image",
+            "podman",
+            "containerize",
+        ],
+    ),
+    (
+        "k8s_",
+        &[
+            "kubernetes",
+            "k8s",
+            "kubectl",
+            "pod",
+            "deployment",
+            "crashloop",
+            "namespace",
+        ],
+    ),
+    (
+        "query_structured",
+        &["json", "yaml", "yml", "toml", "config", "manifest"],
+    ),
+    (
+        "patch_structured",
+        &["json", "yaml", "yml", "toml", "config", "manifest"],
+    ),
+    (
+        "inspect_data",
+        &["csv", "tsv", "parquet", "dataset", "sql", "columns"],
+    ),
+    (
+        "read_file_at",
+        &[
+            "before",
+            "previous",
+            "original",
+            "checkpoint",
+            "earlier",
+            "regression",
+        ],
+    ),
+    (
+        "read_notebook",
+        &["notebook", "notebooks", "jupyter", "ipynb", "cell"],
+    ),
+    (
+        "edit_notebook_cell",
+        &["notebook", "notebooks", "jupyter", "ipynb", "cell"],
+    ),
+    (
+        "compare_images",
+        &[
+            "screenshot",
+            "screenshots",
+            "visual",
+            "regression",
+            "pixel",
+            "css",
+            "layout",
+        ],
+    ),
+    (
+        "ocr_image",
+        &[
+            "ocr",
+            "screenshot",
+            "image",
+            "dialog",
+            "scanned",
+            "tesseract",
+        ],
+    ),
+    (
+        "analyze_logs",
+        &["log", "logs", "logfile", "stacktrace", "errors"],
+    ),
+    (
+        "reproduce_bug",
+        &[
+            "bug",
+            "reproduce",
+            "repro",
+            "stacktrace",
+            "traceback",
+            "panic",
+            "crash",
+            "failing",
+        ],
+    ),
+    (
+        "terraform_",
+        &[
+            "terraform",
+            "tofu",
+            "opentofu",
+            "iac",
+            "infrastructure",
+            "hcl",
+        ],
+    ),
+    (
+        "fetch_url",
+        &[
+            "http", "https", "url", "fetch", "download", "website", "docs",
+        ],
+    ),
+    (
+        "checkout_reference_repo",
+        &[
+            "source code",
+            "upstream",
+            "dependency",
+            "library",
+            "repository",
+            "clone",
+        ],
+    ),
+    (
+        "lookup_docs",
+        &[
+            "docs",
+            "documentation",
+            "api",
+            "crate",
+            "module",
+            "mdn",
+            "reference",
+        ],
+    ),
+    (
+        "package_info",
+        &[
+            "dependency",
+            "dependencies",
+            "version",
+            "package",
+            "crate",
+            "npm",
+            "pypi",
+            "upgrade",
+            "cargo.toml",
+            "package.json",
+            "requirements",
+        ],
+    ),
+    (
+        "watch_path",
+        &["watch", "wait until", "wait for", "appears", "reactive"],
+    ),
+    ("update_skill", &["skill", "skills"]),
+    (
+        "update_init_script",
+        &["init script", "init_script", "bootstrap"],
+    ),
+];
+
+/// Number of recently used tools remembered across turns.
+const RECENT_USAGE_LIMIT: usize = 20;
+/// Prompt characters kept in the hints file.
+const MAX_PROMPT_CHARS: usize = 8_000;
+
+/// Per-turn inputs for tool selection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TurnHints {
+    #[serde(default)]
+    pub prompt: String,
+    /// Agent tool patterns from frontmatter: `{"git_*": false, "fetch_url": true}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub agent_tools: HashMap<String, bool>,
+}
+
+impl TurnHints {
+    pub fn new(prompt: &str, agent_tools: HashMap<String, bool>) -> Self {
+        Self {
+            prompt: prompt.chars().take(MAX_PROMPT_CHARS).collect(),
+            agent_tools,
+        }
+    }
+}
+
+/// Size of one `tools/list` response before and after pruning.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruningStats {
+    pub total_tools: usize,
+    pub selected_tools: usize,
+    pub total_schema_bytes: usize,
+    pub selected_schema_bytes: usize,
+}
+
+impl PruningStats {
+    pub fn saved_bytes(&self) -> usize {
+        self.total_schema_bytes
+            .saturating_sub(self.selected_schema_bytes)
+    }
+}
+
+/// Process-wide pruning totals, exposed by the control API.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruningTotals {
+    pub turns: u64,
+    pub tools_listed: u64,
+    pub tools_pruned: u64,
+    pub total_schema_bytes: u64,
+    pub selected_schema_bytes: u64,
+    pub saved_bytes: u64,
+    /// Rough token estimate (~4 bytes per token).
+    pub estimated_tokens_saved: u64,
+}
+
+static TOTALS: Mutex<PruningTotals> = Mutex::new(PruningTotals {
+    turns: 0,
+    tools_listed: 0,
+    tools_pruned: 0,
+    total_schema_bytes: 0,
+    selected_schema_bytes: 0,
+    saved_bytes: 0,
+    estimated_tokens_saved: 0,
+});
+
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// Whether pruning is enabled for the current workspace (MCP side).
+pub fn pruning_enabled() -> bool {
+    crate::tools::terminal::workspace_setting(PRUNING_SETTING).is_some_and(|v| is_truthy(&v))
+}
+
+/// Whether the runner enabled pruning for this turn (MCP side): the workspace
+/// setting is on or the runner wrote turn hints for a flagged mission.
+pub fn pruning_active(work_dir: &Path) -> bool {
+    pruning_enabled() || work_dir.join(TURN_HINTS_FILE).exists()
+}
+
+/// Whether pruning is enabled for a workspace with these env vars (runner side).
+pub fn pruning_enabled_for(env_vars: &HashMap<String, String>) -> bool {
+    env_vars
+        .get(PRUNING_SETTING)
+        .cloned()
+        .or_else(|| std::env::var(PRUNING_SETTING).ok())
+        .is_some_and(|v| is_truthy(&v))
+}
+
+/// Match a frontmatter tool pattern (`*` suffix wildcard) against a tool name.
+pub(crate) fn pattern_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
+/// The agent's setting for `name`: exact entries win over wildcard ones,
+/// and the longest wildcard wins among those.
+fn agent_setting(agent_tools: &HashMap<String, bool>, name: &str) -> Option<(bool, bool)> {
+    if let Some(enabled) = agent_tools.get(name) {
+        return Some((*enabled, true));
+    }
+    agent_tools
+        .iter()
+        .filter(|(pattern, _)| pattern.ends_with('*') && pattern_matches(pattern, name))
+        .max_by_key(|(pattern, _)| pattern.len())
+        .map(|(_, enabled)| (*enabled, false))
+}
+
+fn prompt_mentions(prompt_lower: &str, words: &HashSet<&str>, keyword: &str) -> bool {
+    if keyword.contains(' ') || keyword.contains('_') {
+        prompt_lower.contains(keyword)
+    } else {
+        words.contains(keyword)
+    }
+}
+
+/// Select the tools to list for a turn.
+pub fn select_tools<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    hints: &TurnHints,
+    recent: &[String],
+) -> HashSet<String> {
+    let prompt_lower = hints.prompt.to_lowercase();
+    let words: HashSet<&str> = prompt_lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    names
+        .into_iter()
+        .filter(|name| {
+            let setting = agent_setting(&hints.agent_tools, name);
+            if matches!(setting, Some((false, _))) {
+                return false;
+            }
+            if CORE_TOOLS.contains(name)
+                || matches!(setting, Some((true, true)))
+                || recent.iter().any(|r| r == name)
+            {
+                return true;
+            }
+            TOOL_KEYWORDS
+                .iter()
+                .filter(|(key, _)| name.starts_with(key))
+                .any(|(_, keywords)| {
+                    keywords
+                        .iter()
+                        .any(|k| prompt_mentions(&prompt_lower, &words, k))
+                })
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    let contents = serde_json::to_string(value).map_err(std::io::Error::other)?;
+    std::fs::write(path, contents)
+}
+
+pub fn write_turn_hints(work_dir: &Path, hints: &TurnHints) -> std::io::Result<()> {
+    write_json(&work_dir.join(TURN_HINTS_FILE), hints)
+}
+
+/// Remove stale hints so the MCP does not prune for a mission without pruning.
+pub fn clear_turn_hints(work_dir: &Path) {
+    let _ = std::fs::remove_file(work_dir.join(TURN_HINTS_FILE));
+}
+
+pub fn read_turn_hints(work_dir: &Path) -> Option<TurnHints> {
+    read_json(&work_dir.join(TURN_HINTS_FILE))
+}
+
+/// Tools used recently in this mission directory, most recent last.
+pub fn recent_tool_use(work_dir: &Path) -> Vec<String> {
+    read_json(&work_dir.join(TOOL_USAGE_FILE)).unwrap_or_default()
+}
+
+/// Remember that `name` was called so it stays listed on later turns.
+pub fn record_tool_use(work_dir: &Path, name: &str) {
+    let mut recent = recent_tool_use(work_dir);
+    recent.retain(|n| n != name);
+    recent.push(name.to_string());
+    let overflow = recent.len().saturating_sub(RECENT_USAGE_LIMIT);
+    recent.drain(..overflow);
+    let _ = write_json(&work_dir.join(TOOL_USAGE_FILE), &recent);
+}
+
+pub fn write_stats(work_dir: &Path, stats: &PruningStats) -> std::io::Result<()> {
+    write_json(&work_dir.join(PRUNING_STATS_FILE), stats)
+}
+
+/// Read and remove the stats left by the MCP for the last turn.
+pub fn take_stats(work_dir: &Path) -> Option<PruningStats> {
+    let path = work_dir.join(PRUNING_STATS_FILE);
+    let stats = read_json(&path);
+    let _ = std::fs::remove_file(&path);
+    stats
+}
+
+/// Add one turn's stats to the process-wide totals.
+pub fn record_turn_stats(stats: &PruningStats) {
+    let mut totals = TOTALS.lock().unwrap_or_else(|e| e.into_inner());
+    totals.turns += 1;
+    totals.tools_listed += stats.selected_tools as u64;
+    totals.tools_pruned += stats.total_tools.saturating_sub(stats.selected_tools) as u64;
+    totals.total_schema_bytes += stats.total_schema_bytes as u64;
+    totals.selected_schema_bytes += stats.selected_schema_bytes as u64;
+    totals.saved_bytes += stats.saved_bytes() as u64;
+    totals.estimated_tokens_saved = totals.saved_bytes / 4;
+}
+
+pub fn totals() -> PruningTotals {
+    TOTALS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: &[&str] = &[
+        "read_file",
+        "grep_search",
+        "git_commit",
+        "git_push",
+        "gh_pr_diff",
+        "tracker_get_issue",
+        "fetch_url",
+        "update_skill",
+    ];
+
+    #[test]
+    fn test_keywords_and_core_tools() {
+        let hints = TurnHints::new("Commit the fix and open a PR", HashMap::new());
+        let selected = select_tools(ALL.iter().copied(), &hints, &[]);
+        assert!(selected.contains("read_file"));
+        assert!(selected.contains("git_commit"));
+        assert!(selected.contains("gh_pr_diff"));
+        assert!(!selected.contains("tracker_get_issue"));
+        assert!(!selected.contains("fetch_url"));
+        // "prompt" must not match the "pr" keyword.
+        let hints = TurnHints::new("Improve the prompt", HashMap::new());
+        assert!(!select_tools(ALL.iter().copied(), &hints, &[]).contains("gh_pr_diff"));
+    }
+
+    #[test]
+    fn test_agent_permissions_and_recent_usage() {
+        let agent_tools = HashMap::from([
+            ("git_*".to_string(), false),
+            ("grep_search".to_string(), false),
+            ("fetch_url".to_string(), true),
+        ]);
+        let hints = TurnHints::new("commit and push", agent_tools);
+        let recent = vec!["tracker_get_issue".to_string()];
+        let selected = select_tools(ALL.iter().copied(), &hints, &recent);
+        assert!(!selected.contains("git_commit"));
+        assert!(!selected.contains("grep_search"));
+        assert!(selected.contains("fetch_url"));
+        assert!(selected.contains("tracker_get_issue"));
+        assert!(!selected.contains("update_skill"));
+    }
+}
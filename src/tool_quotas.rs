@@ -0,0 +1,281 @@
+//! Whole-mission quotas for expensive tools.
+//!
+//! Some tools cost money or bandwidth on every call: web searches, page
+//! fetches, desktop screenshots and the composite tools that run whole
+//! analysis pipelines. The `tool_quotas` setting caps how much of each a
+//! single mission may use:
+//!
+//! ```json
+//! "tool_quotas": { "web_search_calls": 20, "fetch_url_bytes": 5000000 }
+//! ```
+//!
+//! The mission runner writes the limits to [`LIMITS_FILE`] in the mission
+//! directory before each turn. The MCP servers check them before running a
+//! metered tool and record what it used in [`USAGE_FILE`]; a call over quota
+//! is refused with a message telling the model to continue without the tool.
+//! Built-in harness web search cannot be refused mid-call, so it is counted
+//! from the harness events and denied on later turns once exhausted.
+
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+
+pub const LIMITS_FILE: &str = ".sandboxed-sh_tool_quotas.json";
+pub const USAGE_FILE: &str = ".sandboxed-sh_quota_usage.json";
+
+/// Composite tools metered by [`Quota::Composite`].
+pub const COMPOSITE_TOOLS: &[&str] = &[
+    "analyze_codebase",
+    "deep_search",
+    "prepare_project",
+    "debug_error",
+    "reproduce_bug",
+    "gh_pr_open",
+];
+
+/// Per-mission limits; unset limits are unlimited and zero disables the tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolQuotas {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_search_calls: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_url_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desktop_screenshots: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub composite_calls: Option<u64>,
+}
+
+impl ToolQuotas {
+    pub fn is_empty(&self) -> bool {
+        *self == ToolQuotas::default()
+    }
+}
+
+/// What a mission has used so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaUsage {
+    pub web_search_calls: u64,
+    pub fetch_url_bytes: u64,
+    pub desktop_screenshots: u64,
+    pub composite_calls: u64,
+    /// Calls refused because their quota was used up.
+    pub refused_calls: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quota {
+    WebSearch,
+    FetchUrl,
+    Screenshot,
+    Composite,
+}
+
+impl Quota {
+    /// The quota a tool is metered by, if any. MCP prefixes such as
+    /// `mcp__workspace__` are ignored.
+    pub fn for_tool(name: &str) -> Option<Quota> {
+        let name = name.rsplit("__").next().unwrap_or(name);
+        match name {
+            "web_search" | "WebSearch" | "websearch" => Some(Quota::WebSearch),
+            "fetch_url" => Some(Quota::FetchUrl),
+            "desktop_screenshot" => Some(Quota::Screenshot),
+            _ if COMPOSITE_TOOLS.contains(&name) => Some(Quota::Composite),
+            _ => None,
+        }
+    }
+
+    /// How much one call with this `output` uses.
+    pub fn amount(self, output: &str) -> u64 {
+        match self {
+            Quota::FetchUrl => output.len() as u64,
+            _ => 1,
+        }
+    }
+
+    fn limit(self, quotas: &ToolQuotas) -> Option<u64> {
+        match self {
+            Quota::WebSearch => quotas.web_search_calls,
+            Quota::FetchUrl => quotas.fetch_url_bytes,
+            Quota::Screenshot => quotas.desktop_screenshots,
+            Quota::Composite => quotas.composite_calls,
+        }
+    }
+
+    fn used(self, usage: &mut QuotaUsage) -> &mut u64 {
+        match self {
+            Quota::WebSearch => &mut usage.web_search_calls,
+            Quota::FetchUrl => &mut usage.fetch_url_bytes,
+            Quota::Screenshot => &mut usage.desktop_screenshots,
+            Quota::Composite => &mut usage.composite_calls,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Quota::WebSearch => "web search calls",
+            Quota::FetchUrl => "bytes of fetched pages",
+            Quota::Screenshot => "desktop screenshots",
+            Quota::Composite => "composite tool calls",
+        }
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+}
+
+pub fn write_limits(work_dir: &Path, quotas: &ToolQuotas) -> std::io::Result<()> {
+    let contents = serde_json::to_string(quotas).map_err(std::io::Error::other)?;
+    std::fs::write(work_dir.join(LIMITS_FILE), contents)
+}
+
+/// Remove stale limits so a mission without quotas is not metered.
+pub fn clear_limits(work_dir: &Path) {
+    let _ = std::fs::remove_file(work_dir.join(LIMITS_FILE));
+}
+
+pub fn read_limits(work_dir: &Path) -> Option<ToolQuotas> {
+    read_json(&work_dir.join(LIMITS_FILE))
+}
+
+pub fn read_usage(work_dir: &Path) -> QuotaUsage {
+    read_json(&work_dir.join(USAGE_FILE)).unwrap_or_default()
+}
+
+/// Apply `f` to the usage file while holding an exclusive lock on it, since
+/// several MCP servers of the same mission may update it at once.
+fn update_usage<R>(work_dir: &Path, f: impl FnOnce(&mut QuotaUsage) -> R) -> std::io::Result<R> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(work_dir.join(USAGE_FILE))?;
+    file.lock_exclusive()?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let mut usage: QuotaUsage = serde_json::from_str(&contents).unwrap_or_default();
+    let result = f(&mut usage);
+    let contents = serde_json::to_string(&usage).map_err(std::io::Error::other)?;
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(contents.as_bytes())?;
+    Ok(result)
+}
+
+/// Check a call against its quota. A refused call is counted and the error is
+/// the message to return to the model. A fetch may go over the byte quota,
+/// since its size is only known afterwards; the next one is then refused.
+pub fn check(work_dir: &Path, quota: Quota) -> Result<(), String> {
+    let Some(limit) = read_limits(work_dir).and_then(|q| quota.limit(&q)) else {
+        return Ok(());
+    };
+    let refused = update_usage(work_dir, |usage| {
+        if *quota.used(usage) >= limit {
+            usage.refused_calls += 1;
+            true
+        } else {
+            false
+        }
+    })
+    .unwrap_or(false);
+    if refused {
+        return Err(format!(
+            "Quota exceeded: this mission may use {} {}, and they are used up. \
+             Continue without this tool.",
+            limit,
+            quota.label()
+        ));
+    }
+    Ok(())
+}
+
+/// Add what a call used to the mission's usage.
+pub fn record(work_dir: &Path, quota: Quota, amount: u64) {
+    if let Err(e) = update_usage(work_dir, |usage| *quota.used(usage) += amount) {
+        tracing::warn!(error = %e, "Failed to record tool quota usage");
+    }
+}
+
+/// Whether the quota is used up, without counting a refusal.
+pub fn exhausted(work_dir: &Path, quota: Quota) -> bool {
+    let Some(limit) = read_limits(work_dir).and_then(|q| quota.limit(&q)) else {
+        return false;
+    };
+    let mut usage = read_usage(work_dir);
+    *quota.used(&mut usage) >= limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_names() {
+        assert_eq!(Quota::for_tool("WebSearch"), Some(Quota::WebSearch));
+        assert_eq!(
+            Quota::for_tool("mcp__workspace__fetch_url"),
+            Some(Quota::FetchUrl)
+        );
+        assert_eq!(Quota::for_tool("deep_search"), Some(Quota::Composite));
+        assert_eq!(Quota::for_tool("read_file"), None);
+        assert_eq!(Quota::FetchUrl.amount("hello"), 5);
+        assert_eq!(Quota::Screenshot.amount("saved"), 1);
+    }
+
+    #[test]
+    fn test_check_refuses_over_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        // Without limits, calls are allowed but still recorded.
+        assert!(check(dir.path(), Quota::Screenshot).is_ok());
+        record(dir.path(), Quota::Screenshot, 1);
+        write_limits(
+            dir.path(),
+            &ToolQuotas {
+                desktop_screenshots: Some(2),
+                composite_calls: Some(0),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(check(dir.path(), Quota::Screenshot).is_ok());
+        record(dir.path(), Quota::Screenshot, 1);
+        assert!(exhausted(dir.path(), Quota::Screenshot));
+        let err = check(dir.path(), Quota::Screenshot).unwrap_err();
+        assert!(err.contains("2 desktop screenshots"));
+        assert!(check(dir.path(), Quota::Composite).is_err());
+        assert!(check(dir.path(), Quota::FetchUrl).is_ok());
+
+        let usage = read_usage(dir.path());
+        assert_eq!(usage.desktop_screenshots, 2);
+        assert_eq!(usage.refused_calls, 2);
+        clear_limits(dir.path());
+        assert!(!exhausted(dir.path(), Quota::Screenshot));
+    }
+
+    #[test]
+    fn test_fetch_bytes_may_overshoot_once() {
+        let dir = tempfile::tempdir().unwrap();
+        write_limits(
+            dir.path(),
+            &ToolQuotas {
+                fetch_url_bytes: Some(100),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(check(dir.path(), Quota::FetchUrl).is_ok());
+        record(dir.path(), Quota::FetchUrl, 60);
+        assert!(check(dir.path(), Quota::FetchUrl).is_ok());
+        record(dir.path(), Quota::FetchUrl, 60);
+        assert!(check(dir.path(), Quota::FetchUrl).is_err());
+        assert_eq!(read_usage(dir.path()).fetch_url_bytes, 120);
+    }
+}
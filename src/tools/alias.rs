@@ -0,0 +1,365 @@
+//! Agent-defined tool aliases.
+//!
+//! A library agent can expose an existing tool under a domain-specific name,
+//! with some arguments fixed or pre-filled, in its frontmatter:
+//!
+//! ```yaml
+//! tool_aliases:
+//!   deploy_preview:
+//!     tool: run_command
+//!     description: Deploy the current branch to the preview environment.
+//!     args:
+//!       command: ./scripts/deploy-preview.sh
+//!     defaults:
+//!       timeout_secs: 600
+//! ```
+//!
+//! `args` are fixed: they are dropped from the alias's schema and always win
+//! over what the model sends. `defaults` stay in the schema but become
+//! optional. With `replace: true` the target tool is hidden from the mission,
+//! so the alias effectively renames it.
+//!
+//! The mission runner writes the agent's aliases to [`AGENT_ALIASES_FILE`] in
+//! the mission directory before each turn, and the workspace MCP registers
+//! them after bundle filtering, so an alias of a tool outside the mission's
+//! bundles is skipped. Policies and hooks see the expanded call to the target
+//! tool.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::Tool;
+
+/// Agent aliases for the current turn, written by the mission runner.
+pub const AGENT_ALIASES_FILE: &str = ".sandboxed-sh_tool_aliases.json";
+
+/// An alias of an existing tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolAlias {
+    /// Name the model sees
+    pub name: String,
+    /// Tool the alias calls
+    pub tool: String,
+    /// Defaults to the target's description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Arguments the model cannot set or override
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub args: Map<String, Value>,
+    /// Arguments used when the model leaves them out
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub defaults: Map<String, Value>,
+    /// Hide the target tool
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub replace: bool,
+}
+
+/// Whether `name` is usable as a tool name (what model APIs accept).
+pub fn valid_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Parse the `tool_aliases` frontmatter mapping (alias name → definition).
+/// Invalid entries are skipped with a warning.
+pub fn parse_aliases(value: &serde_yaml::Value) -> Vec<ToolAlias> {
+    let Some(mapping) = value.as_mapping() else {
+        return Vec::new();
+    };
+    let mut aliases = Vec::new();
+    for (name, spec) in mapping {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        let parsed = serde_yaml::from_value::<Value>(spec.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|mut spec| {
+                let object = spec.as_object_mut().ok_or("expected a mapping")?;
+                object.insert("name".to_string(), Value::String(name.to_string()));
+                serde_json::from_value::<ToolAlias>(spec).map_err(|e| e.to_string())
+            });
+        match parsed {
+            Ok(_) if !valid_alias_name(name) => {
+                tracing::warn!(alias = %name, "Invalid tool alias name; use letters, digits, _ and -");
+            }
+            Ok(alias) => aliases.push(alias),
+            Err(e) => tracing::warn!(alias = %name, error = %e, "Invalid tool alias"),
+        }
+    }
+    aliases.sort_by(|a, b| a.name.cmp(&b.name));
+    aliases
+}
+
+/// A tool registered under an alias.
+pub struct AliasTool {
+    alias: ToolAlias,
+    target: Arc<dyn Tool>,
+    description: String,
+    schema: Value,
+}
+
+impl AliasTool {
+    pub fn new(alias: ToolAlias, target: Arc<dyn Tool>) -> Self {
+        let description = alias
+            .description
+            .clone()
+            .unwrap_or_else(|| target.description().to_string());
+        let schema = alias_schema(target.parameters_schema(), &alias);
+        Self {
+            alias,
+            target,
+            description,
+            schema,
+        }
+    }
+
+    /// The target's arguments: defaults, then the model's, then fixed ones.
+    fn expand(&self, args: &Value) -> Value {
+        let mut merged = self.alias.defaults.clone();
+        if let Some(args) = args.as_object() {
+            merged.extend(args.clone());
+        }
+        merged.extend(self.alias.args.clone());
+        Value::Object(merged)
+    }
+}
+
+/// The target's schema without the fixed arguments, with defaulted ones made
+/// optional.
+fn alias_schema(mut schema: Value, alias: &ToolAlias) -> Value {
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        for name in alias.args.keys() {
+            properties.remove(name);
+        }
+        for (name, default) in &alias.defaults {
+            if let Some(property) = properties.get_mut(name).and_then(Value::as_object_mut) {
+                property.insert("default".to_string(), default.clone());
+            }
+        }
+    }
+    if let Some(required) = schema.get_mut("required").and_then(Value::as_array_mut) {
+        required.retain(|name| {
+            name.as_str().is_some_and(|name| {
+                !alias.args.contains_key(name) && !alias.defaults.contains_key(name)
+            })
+        });
+    }
+    schema
+}
+
+#[async_trait]
+impl Tool for AliasTool {
+    fn name(&self) -> &str {
+        &self.alias.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let mut args = self.expand(&args);
+        super::args::validate(&self.target.parameters_schema(), &mut args)
+            .map_err(|e| anyhow::anyhow!(e.message(&self.alias.name)))?;
+        self.target.execute(args, working_dir).await
+    }
+
+    fn alias_target(&self, args: &Value) -> Option<(Arc<dyn Tool>, Value)> {
+        Some((Arc::clone(&self.target), self.expand(args)))
+    }
+}
+
+/// Register `aliases` over `tools`, returning each alias's name or the reason
+/// it was skipped. Targets of `replace` aliases are removed afterwards.
+pub fn register_aliases(
+    tools: &mut HashMap<String, Arc<dyn Tool>>,
+    aliases: &[ToolAlias],
+) -> Vec<Result<String, String>> {
+    let mut replaced = Vec::new();
+    let results = aliases
+        .iter()
+        .map(|alias| {
+            if tools.contains_key(&alias.name) {
+                return Err(format!(
+                    "alias '{}' clashes with an existing tool",
+                    alias.name
+                ));
+            }
+            let target = tools.get(&alias.tool).ok_or_else(|| {
+                format!(
+                    "alias '{}' targets unavailable tool '{}'",
+                    alias.name, alias.tool
+                )
+            })?;
+            let tool = AliasTool::new(alias.clone(), Arc::clone(target));
+            tools.insert(alias.name.clone(), Arc::new(tool));
+            if alias.replace {
+                replaced.push(alias.tool.clone());
+            }
+            Ok(alias.name.clone())
+        })
+        .collect();
+    for name in replaced {
+        tools.remove(&name);
+    }
+    results
+}
+
+/// Record the agent's aliases for the turn (empty removes the file).
+pub fn write_agent_aliases(work_dir: &Path, aliases: &[ToolAlias]) -> std::io::Result<()> {
+    let path = work_dir.join(AGENT_ALIASES_FILE);
+    if aliases.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    std::fs::write(path, serde_json::to_vec(aliases)?)
+}
+
+pub fn read_agent_aliases(work_dir: &Path) -> Vec<ToolAlias> {
+    std::fs::read(work_dir.join(AGENT_ALIASES_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Echoes its arguments.
+    struct Echo;
+
+    #[async_trait]
+    impl Tool for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Echo the arguments"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string" },
+                    "timeout_secs": { "type": "integer" },
+                    "cwd": { "type": "string" }
+                },
+                "required": ["command", "timeout_secs"]
+            })
+        }
+
+        async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+            Ok(args.to_string())
+        }
+    }
+
+    fn aliases() -> Vec<ToolAlias> {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+deploy_preview:
+  tool: echo
+  description: Deploy a preview.
+  args:
+    command: ./deploy.sh
+  defaults:
+    timeout_secs: 600
+shout:
+  tool: echo
+  replace: true
+"bad name":
+  tool: echo
+broken: 3
+"#,
+        )
+        .unwrap();
+        parse_aliases(&yaml)
+    }
+
+    #[test]
+    fn parses_frontmatter_and_skips_invalid_entries() {
+        let aliases = aliases();
+        let names: Vec<&str> = aliases.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["deploy_preview", "shout"]);
+        assert_eq!(aliases[0].args["command"], "./deploy.sh");
+        assert!(aliases[1].replace);
+
+        let dir = tempfile::tempdir().unwrap();
+        write_agent_aliases(dir.path(), &aliases).unwrap();
+        assert_eq!(read_agent_aliases(dir.path()), aliases);
+        write_agent_aliases(dir.path(), &[]).unwrap();
+        assert!(read_agent_aliases(dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn alias_fixes_and_defaults_arguments() {
+        let mut tools: HashMap<String, Arc<dyn Tool>> = HashMap::new();
+        tools.insert("echo".to_string(), Arc::new(Echo));
+        let results = register_aliases(&mut tools, &aliases());
+        assert_eq!(
+            results,
+            vec![Ok("deploy_preview".to_string()), Ok("shout".to_string())]
+        );
+        assert!(!tools.contains_key("echo"));
+
+        let alias = &tools["deploy_preview"];
+        assert_eq!(alias.description(), "Deploy a preview.");
+        let schema = alias.parameters_schema();
+        assert!(schema["properties"].get("command").is_none());
+        assert_eq!(schema["properties"]["timeout_secs"]["default"], 600);
+        assert_eq!(schema["required"], json!([]));
+
+        let dir = std::env::temp_dir();
+        let out = alias
+            .execute(json!({ "command": "rm -rf /", "cwd": "app" }), &dir)
+            .await
+            .unwrap();
+        let out: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(
+            out,
+            json!({ "command": "./deploy.sh", "timeout_secs": 600, "cwd": "app" })
+        );
+        let (target, expanded) = alias.alias_target(&json!({})).unwrap();
+        assert_eq!(target.name(), "echo");
+        assert_eq!(expanded["command"], "./deploy.sh");
+    }
+
+    #[test]
+    fn skips_clashes_and_missing_targets() {
+        let mut tools: HashMap<String, Arc<dyn Tool>> = HashMap::new();
+        tools.insert("echo".to_string(), Arc::new(Echo));
+        let clash = ToolAlias {
+            name: "echo".to_string(),
+            tool: "echo".to_string(),
+            description: None,
+            args: Map::new(),
+            defaults: Map::new(),
+            replace: false,
+        };
+        let missing = ToolAlias {
+            name: "fetch".to_string(),
+            tool: "fetch_url".to_string(),
+            ..clash.clone()
+        };
+        let results = register_aliases(&mut tools, &[clash, missing]);
+        assert!(results.iter().all(|r| r.is_err()));
+        assert_eq!(tools.len(), 1);
+    }
+}
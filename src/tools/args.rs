@@ -0,0 +1,370 @@
+//! Tool argument validation.
+//!
+//! [`ToolRegistry::execute`](super::ToolRegistry::execute) checks arguments
+//! against the tool's `parameters_schema()` before running it, so every tool
+//! gets the same model-friendly error listing each missing or invalid field
+//! instead of failing on the first one it happens to read. Numbers and
+//! booleans sent as strings (`"5"`, `"true"`) are coerced to the schema type.
+//!
+//! The validator covers the JSON Schema subset tool schemas use: `type`,
+//! `enum`, `const`, `required`, `properties`, `additionalProperties`, `items`,
+//! `minimum`/`maximum` and `anyOf`/`oneOf`.
+//!
+//! Tools can declare a typed argument struct deriving `Deserialize` and
+//! `JsonSchema` and use [`ToolArgs::schema`] and [`ToolArgs::parse`] instead of
+//! writing the schema and reading fields by hand.
+
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// Every problem found in a tool call's arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgsError {
+    pub problems: Vec<String>,
+}
+
+impl ArgsError {
+    /// Error message for the model.
+    pub fn message(&self, tool: &str) -> String {
+        let mut message = format!("Invalid arguments for tool '{}':", tool);
+        for problem in &self.problems {
+            message.push_str("\n- ");
+            message.push_str(problem);
+        }
+        message.push_str("\nFix the arguments and call the tool again.");
+        message
+    }
+}
+
+/// Validate `args` against a tool's parameter schema, coercing scalar
+/// strings to the expected type in place.
+pub fn validate(schema: &Value, args: &mut Value) -> Result<(), ArgsError> {
+    if args.is_null() {
+        *args = Value::Object(Map::new());
+    }
+    let mut problems = Vec::new();
+    check(schema, args, "", &mut problems);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ArgsError { problems })
+    }
+}
+
+fn field(path: &str) -> String {
+    if path.is_empty() {
+        "arguments".to_string()
+    } else {
+        format!("`{}`", path)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        other => type_name(value) == other,
+    }
+}
+
+/// `value` converted to `expected`, if it is an unambiguous spelling of it.
+fn coerce(value: &Value, expected: &str) -> Option<Value> {
+    match (value, expected) {
+        (Value::String(s), "integer") => s.trim().parse::<i64>().ok().map(Value::from),
+        (Value::String(s), "number") => s
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+        (Value::String(s), "boolean") => match s.trim() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        (Value::Number(n), "integer") => n
+            .as_f64()
+            .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+            .map(|f| Value::from(f as i64)),
+        _ => None,
+    }
+}
+
+fn allowed_types(schema: &Map<String, Value>) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn check(schema: &Value, value: &mut Value, path: &str, problems: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    let branches = schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(Value::as_array);
+    if let Some(branches) = branches {
+        let matched = branches.iter().find_map(|branch| {
+            let mut candidate = value.clone();
+            let mut branch_problems = Vec::new();
+            check(branch, &mut candidate, path, &mut branch_problems);
+            branch_problems.is_empty().then_some(candidate)
+        });
+        match matched {
+            Some(candidate) => *value = candidate,
+            None => {
+                problems.push(format!(
+                    "{} does not match any of the allowed forms",
+                    field(path)
+                ));
+                return;
+            }
+        }
+    }
+
+    let types = allowed_types(schema);
+    if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+        match types.iter().find_map(|t| coerce(value, t)) {
+            Some(coerced) => *value = coerced,
+            None => {
+                problems.push(format!(
+                    "{} must be {}, got {}",
+                    field(path),
+                    types.join(" or "),
+                    type_name(value)
+                ));
+                return;
+            }
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let options: Vec<String> = options.iter().map(Value::to_string).collect();
+            problems.push(format!(
+                "{} must be one of {}, got {}",
+                field(path),
+                options.join(", "),
+                value
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            problems.push(format!("{} must be {}", field(path), expected));
+        }
+    }
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min {
+                problems.push(format!("{} must be at least {}", field(path), min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max {
+                problems.push(format!("{} must be at most {}", field(path), max));
+            }
+        }
+    }
+
+    let child = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", path, name)
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            let empty = Map::new();
+            let properties = schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .unwrap_or(&empty);
+            let required: Vec<&str> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|r| r.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            for name in &required {
+                if !map.contains_key(*name) {
+                    let expected = properties
+                        .get(*name)
+                        .and_then(Value::as_object)
+                        .map(allowed_types)
+                        .filter(|t| !t.is_empty())
+                        .map(|t| format!(" ({})", t.join(" or ")))
+                        .unwrap_or_default();
+                    problems.push(format!(
+                        "missing required field {}{}",
+                        field(&child(name)),
+                        expected
+                    ));
+                }
+            }
+            for (name, item) in map.iter_mut() {
+                match properties.get(name) {
+                    // Optional fields sent as null are treated as omitted
+                    Some(_) if item.is_null() && !required.contains(&name.as_str()) => {}
+                    Some(sub) => check(sub, item, &child(name), problems),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            let mut known: Vec<&str> =
+                                properties.keys().map(String::as_str).collect();
+                            known.sort_unstable();
+                            problems.push(format!(
+                                "unknown field {} (expected one of: {})",
+                                field(&child(name)),
+                                known.join(", ")
+                            ));
+                        }
+                        Some(sub @ Value::Object(_)) => check(sub, item, &child(name), problems),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (idx, item) in items.iter_mut().enumerate() {
+                    check(item_schema, item, &format!("{}[{}]", path, idx), problems);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Typed tool arguments: derive `Deserialize` and `JsonSchema` on a struct
+/// and use it for both `parameters_schema()` and `execute()`. Doc comments on
+/// the fields become parameter descriptions.
+pub trait ToolArgs: DeserializeOwned + JsonSchema {
+    /// JSON schema for `parameters_schema()`.
+    fn schema() -> Value {
+        let generator = schemars::gen::SchemaSettings::draft07()
+            .with(|s| {
+                s.inline_subschemas = true;
+                s.meta_schema = None;
+            })
+            .into_generator();
+        let mut schema =
+            serde_json::to_value(generator.into_root_schema_for::<Self>()).unwrap_or_default();
+        if let Some(schema) = schema.as_object_mut() {
+            schema.remove("title");
+        }
+        schema
+    }
+
+    /// Parse validated arguments.
+    fn parse(args: Value) -> anyhow::Result<Self> {
+        serde_json::from_value(args).map_err(|e| anyhow::anyhow!("Invalid arguments: {}", e))
+    }
+}
+
+impl<T: DeserializeOwned + JsonSchema> ToolArgs for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[test]
+    fn reports_every_problem() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "mode": {"type": "string", "enum": ["read", "write"]},
+                "edits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"line": {"type": "integer", "minimum": 1}},
+                        "required": ["line"]
+                    }
+                }
+            },
+            "required": ["path"]
+        });
+        let mut args = json!({"mode": "append", "edits": [{"line": 0}, {}]});
+        let err = validate(&schema, &mut args).unwrap_err();
+        assert_eq!(
+            err.problems,
+            vec![
+                "missing required field `path` (string)",
+                "`edits[0].line` must be at least 1",
+                "missing required field `edits[1].line` (integer)",
+                "`mode` must be one of \"read\", \"write\", got \"append\"",
+            ]
+        );
+        assert!(err
+            .message("read_file")
+            .starts_with("Invalid arguments for tool 'read_file':\n- missing"));
+    }
+
+    #[test]
+    fn coerces_scalar_strings() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "number": {"type": "integer"},
+                "draft": {"type": "boolean"},
+                "repo": {"type": "string"}
+            },
+            "required": ["number"]
+        });
+        let mut args = json!({"number": "42", "draft": "true", "repo": null});
+        validate(&schema, &mut args).unwrap();
+        assert_eq!(args, json!({"number": 42, "draft": true, "repo": null}));
+
+        let mut args = Value::Null;
+        let err = validate(&schema, &mut args).unwrap_err();
+        assert_eq!(
+            err.problems,
+            vec!["missing required field `number` (integer)"]
+        );
+    }
+
+    #[test]
+    fn typed_args_round_trip() {
+        /// Arguments for a test tool.
+        #[derive(Debug, Deserialize, JsonSchema)]
+        struct Args {
+            /// File to read
+            path: String,
+            #[serde(default)]
+            limit: Option<u32>,
+        }
+
+        let schema = Args::schema();
+        assert_eq!(schema["required"], json!(["path"]));
+        assert_eq!(schema["properties"]["path"]["description"], "File to read");
+
+        let mut args = json!({"path": "a.txt", "limit": "10"});
+        validate(&schema, &mut args).unwrap();
+        let args = Args::parse(args).unwrap();
+        assert_eq!((args.path.as_str(), args.limit), ("a.txt", Some(10)));
+
+        let mut args = json!({"limit": -1});
+        let err = validate(&schema, &mut args).unwrap_err();
+        assert_eq!(err.problems.len(), 2, "{:?}", err.problems);
+    }
+}
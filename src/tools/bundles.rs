@@ -0,0 +1,181 @@
+//! Named tool bundles and per-mission tool set composition.
+//!
+//! Built-in tools are grouped into [`BUNDLES`]. A workspace (usually through
+//! its template's env vars) can restrict the bundles its missions get with
+//! [`BUNDLES_SETTING`], e.g. `SANDBOXED_SH_TOOL_BUNDLES=core,git`, and a
+//! library agent can narrow that further with `tool_bundles` in its
+//! frontmatter. The mission runner writes the agent's bundles to
+//! [`AGENT_BUNDLES_FILE`] in the mission directory before each turn, and the
+//! workspace MCP composes its tool set from both when a harness connects.
+//!
+//! `core` is always included, and tools outside every bundle (library tools,
+//! tools registered by plugins) are never removed.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::tool_pruning::pattern_matches;
+
+/// Workspace env var listing the bundles missions may use.
+pub const BUNDLES_SETTING: &str = "SANDBOXED_SH_TOOL_BUNDLES";
+
+/// Agent bundles for the current turn, written by the mission runner.
+pub const AGENT_BUNDLES_FILE: &str = ".sandboxed-sh_tool_bundles.json";
+
+/// Bundle names and the tools (or `*` name patterns) they contain.
+pub const BUNDLES: &[(&str, &[&str])] = &[
+    (
+        "core",
+        &[
+            "read_file",
+            "read_file_at",
+            "write_file",
+            "delete_file",
+            "list_directory",
+            "search_files",
+            "grep_search",
+            "watch_path",
+            "index_files",
+            "search_file_index",
+            "run_command",
+            "complete_mission",
+            "set_plan",
+            "update_plan",
+            "ui_*",
+            "analyze_codebase",
+            "deep_search",
+            "prepare_project",
+            "debug_error",
+            "reproduce_bug",
+            "detect_environment",
+            "analyze_logs",
+            "query_structured",
+            "patch_structured",
+            "inspect_data",
+            "read_notebook",
+            "edit_notebook_cell",
+            "compare_images",
+            "ocr_image",
+        ],
+    ),
+    ("git", &["git_*", "gh_pr_*", "checkout_reference_repo"]),
+    ("web", &["fetch_url", "lookup_docs", "package_info"]),
+    ("tracker", &["tracker_*"]),
+    ("desktop", &["desktop_*"]),
+    ("containers", &["docker_*"]),
+    ("kubernetes", &["k8s_*"]),
+    ("infra", &["terraform_*"]),
+    // Tools that persist what the agent learned into the library
+    ("memory", &["update_skill", "update_init_script"]),
+];
+
+/// The bundle a built-in tool belongs to.
+pub fn bundle_of(tool: &str) -> Option<&'static str> {
+    BUNDLES
+        .iter()
+        .find(|(_, tools)| tools.iter().any(|pattern| pattern_matches(pattern, tool)))
+        .map(|(name, _)| *name)
+}
+
+/// Parse a comma- or space-separated bundle list.
+pub fn parse_bundles(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Names in `bundles` that are not known bundles.
+pub fn unknown_bundles(bundles: &[String]) -> Vec<String> {
+    bundles
+        .iter()
+        .filter(|name| !BUNDLES.iter().any(|(known, _)| known == name))
+        .cloned()
+        .collect()
+}
+
+/// Bundles a mission may use. `None` leaves that side unrestricted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleSelection {
+    /// From the workspace's [`BUNDLES_SETTING`]
+    pub workspace: Option<Vec<String>>,
+    /// From the agent's `tool_bundles` frontmatter
+    pub agent: Option<Vec<String>>,
+}
+
+impl BundleSelection {
+    /// Whether the mission gets `tool`.
+    pub fn allows(&self, tool: &str) -> bool {
+        let Some(bundle) = bundle_of(tool) else {
+            return true;
+        };
+        bundle == "core"
+            || [&self.workspace, &self.agent]
+                .into_iter()
+                .flatten()
+                .all(|bundles| bundles.iter().any(|b| b == bundle))
+    }
+
+    /// Selection for the current workspace (MCP side).
+    pub fn for_workspace(work_dir: &Path) -> Self {
+        Self {
+            workspace: super::terminal::workspace_setting(BUNDLES_SETTING)
+                .map(|value| parse_bundles(&value)),
+            agent: read_agent_bundles(work_dir),
+        }
+    }
+}
+
+/// Record the agent's bundles for the turn (`None` removes the restriction).
+pub fn write_agent_bundles(work_dir: &Path, bundles: Option<&[String]>) -> std::io::Result<()> {
+    let path = work_dir.join(AGENT_BUNDLES_FILE);
+    match bundles {
+        Some(bundles) => std::fs::write(path, serde_json::to_vec(bundles)?),
+        None => match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+pub fn read_agent_bundles(work_dir: &Path) -> Option<Vec<String>> {
+    let content = std::fs::read(work_dir.join(AGENT_BUNDLES_FILE)).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_intersects_workspace_and_agent() {
+        let selection = BundleSelection {
+            workspace: Some(parse_bundles("core, git web")),
+            agent: Some(vec!["git".to_string(), "desktop".to_string()]),
+        };
+        assert!(selection.allows("read_file"));
+        assert!(selection.allows("gh_pr_diff"));
+        assert!(!selection.allows("fetch_url"));
+        assert!(!selection.allows("desktop_click"));
+        // Library and plugin tools are outside every bundle
+        assert!(selection.allows("my_library_tool"));
+        assert!(BundleSelection::default().allows("desktop_click"));
+        assert_eq!(
+            unknown_bundles(&parse_bundles("git,browser")),
+            vec!["browser"]
+        );
+    }
+
+    #[test]
+    fn agent_bundles_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundles = vec!["web".to_string()];
+        write_agent_bundles(dir.path(), Some(&bundles)).unwrap();
+        assert_eq!(read_agent_bundles(dir.path()), Some(bundles));
+        write_agent_bundles(dir.path(), None).unwrap();
+        write_agent_bundles(dir.path(), None).unwrap();
+        assert_eq!(read_agent_bundles(dir.path()), None);
+    }
+}
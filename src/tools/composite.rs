@@ -431,17 +431,10 @@ impl Tool for PrepareProject {
         ];
 
         for tool in tools_to_check {
-            let output = tokio::process::Command::new("which")
-                .arg(tool)
-                .output()
-                .await;
-
-            if let Ok(out) = output {
-                if out.status.success() {
-                    result.push_str(&format!("- ✓ `{}` is installed\n", tool));
-                } else {
-                    result.push_str(&format!("- ✗ `{}` not found\n", tool));
-                }
+            if crate::host::command_on_path(tool) {
+                result.push_str(&format!("- ✓ `{}` is installed\n", tool));
+            } else {
+                result.push_str(&format!("- ✗ `{}` not found\n", tool));
             }
         }
 
@@ -600,3 +593,731 @@ impl Tool for DebugError {
         Ok(result)
     }
 }
+
+/// Time allowed for the test command of `gh_pr_open`.
+const PR_TEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1800);
+
+/// Output of a failed test command kept in the report.
+const PR_TEST_OUTPUT_CHARS: usize = 4_000;
+
+/// Diff size sent to the LLM for the pull request description.
+const PR_DIFF_CHARS: usize = 24_000;
+
+/// Branch name for a pull request without one, from its title when given.
+fn pr_branch_name(title: Option<&str>) -> String {
+    let mut slug = String::new();
+    for c in title.unwrap_or("").to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= 48 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        format!(
+            "sandboxed/pr-{}",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        )
+    } else {
+        format!("sandboxed/{}", slug)
+    }
+}
+
+/// The last `max` bytes of `text`, on a char boundary.
+pub(super) fn output_tail(text: &str, max: usize) -> &str {
+    let mut start = text.len().saturating_sub(max);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+/// Steps of `gh_pr_open` done so far, reported on success and failure.
+struct PrReport {
+    steps: Vec<String>,
+}
+
+impl PrReport {
+    fn fail(&self, step: &str, error: impl std::fmt::Display) -> anyhow::Error {
+        let mut message = format!("Failed to {}: {}", step, error);
+        if !self.steps.is_empty() {
+            message.push_str("\n\nCompleted steps:\n");
+            for done in &self.steps {
+                message.push_str(&format!("- {}\n", done));
+            }
+        }
+        message
+            .push_str("\nCompleted steps are kept; call gh_pr_open again to continue from here.");
+        anyhow::anyhow!(message)
+    }
+}
+
+/// Open a pull request from the working tree changes: branch, test, commit,
+/// push and `gh pr create`, reusing the git and GitHub tools.
+pub struct GhPrOpen;
+
+#[async_trait]
+impl Tool for GhPrOpen {
+    fn name(&self) -> &str {
+        "gh_pr_open"
+    }
+
+    fn description(&self) -> &str {
+        "Open a GitHub pull request from the current changes in one call: moves to a feature branch if on a protected/base branch, runs the test command, stages and commits (generating the message if none is given), pushes with the workspace credentials and push policy, and creates the PR with a title and description generated from the diff. Stops at the first failing step and reports what was done; calling it again continues from there."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory (default: workspace)"
+                },
+                "branch": {
+                    "type": "string",
+                    "description": "Branch to open the PR from (default: current branch, or a new branch named after the title when on a protected or base branch)"
+                },
+                "base": {
+                    "type": "string",
+                    "description": "Branch to merge into (default: the remote's default branch)"
+                },
+                "remote": {
+                    "type": "string",
+                    "description": "Remote to push to (default: origin)"
+                },
+                "test_command": {
+                    "type": "string",
+                    "description": "Shell command that must pass before committing, e.g. 'cargo test'"
+                },
+                "commit_message": {
+                    "type": "string",
+                    "description": "Commit message (default: generated from the staged diff)"
+                },
+                "files": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Files to stage (default: all changes)"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "PR title (default: the commit subject)"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "PR description (default: generated from the diff)"
+                },
+                "draft": {
+                    "type": "boolean",
+                    "description": "Open the PR as a draft"
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        use super::git::{git_output, is_protected_branch, protected_patterns};
+
+        let repo = args["path"]
+            .as_str()
+            .map(|p| super::resolve_path_simple(p, working_dir))
+            .unwrap_or_else(|| working_dir.to_path_buf());
+        let repo_arg = repo.display().to_string();
+        let arg = |key: &str| {
+            args[key]
+                .as_str()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let remote = arg("remote").unwrap_or_else(|| "origin".to_string());
+        let title = arg("title");
+        let mut report = PrReport { steps: Vec::new() };
+
+        // 1. Branch: never open a PR from the base or a protected branch
+        let base = match arg("base") {
+            Some(base) => Some(base),
+            None => git_output(
+                &repo,
+                &[
+                    "symbolic-ref",
+                    "--short",
+                    &format!("refs/remotes/{}/HEAD", remote),
+                ],
+            )
+            .await
+            .ok()
+            .and_then(|r| {
+                r.trim()
+                    .strip_prefix(&format!("{}/", remote))
+                    .map(str::to_string)
+            }),
+        };
+        let current = git_output(&repo, &["rev-parse", "--abbrev-ref", "HEAD"])
+            .await
+            .map_err(|e| report.fail("read the current branch", e))?;
+        let current = current.trim().to_string();
+        let patterns = protected_patterns()
+            .await
+            .map_err(|e| report.fail("read the protected branches", e))?;
+        let on_base = current == "HEAD"
+            || base.as_deref() == Some(current.as_str())
+            || is_protected_branch(&current, &patterns);
+        let branch = match arg("branch") {
+            Some(branch) => branch,
+            None if on_base => pr_branch_name(title.as_deref()),
+            None => current.clone(),
+        };
+        if branch != current {
+            let exists = git_output(
+                &repo,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/heads/{}", branch),
+                ],
+            )
+            .await
+            .is_ok();
+            let switched = if exists {
+                git_output(&repo, &["checkout", &branch]).await.map(|_| ())
+            } else {
+                super::GitCreateBranch
+                    .execute(json!({ "path": repo_arg, "name": branch }), working_dir)
+                    .await
+                    .map(|_| ())
+            };
+            switched.map_err(|e| report.fail(&format!("switch to branch '{}'", branch), e))?;
+            report
+                .steps
+                .push(format!("Switched to branch `{}`", branch));
+        } else {
+            report.steps.push(format!("Using branch `{}`", branch));
+        }
+
+        // 2. Tests, before anything is committed
+        let mut tests = None;
+        if let Some(command) = arg("test_command") {
+            let output = super::terminal::run_workspace_shell(
+                &repo,
+                &command,
+                std::collections::HashMap::new(),
+                PR_TEST_TIMEOUT,
+            )
+            .await
+            .map_err(|e| report.fail("run the tests", e))?;
+            if !output.status.success() {
+                let combined = format!(
+                    "{}{}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                return Err(report.fail(
+                    "pass the tests",
+                    format!(
+                        "`{}` exited with {}\n```\n{}\n```",
+                        command,
+                        output.status.code().unwrap_or(-1),
+                        output_tail(combined.trim_end(), PR_TEST_OUTPUT_CHARS)
+                    ),
+                ));
+            }
+            report.steps.push(format!("Tests passed: `{}`", command));
+            tests = Some(command);
+        }
+
+        // 3. Commit what is staged (all changes unless files are given)
+        let mut commit_args = json!({ "path": repo_arg });
+        match args["files"].as_array().filter(|f| !f.is_empty()) {
+            Some(files) => commit_args["files"] = Value::Array(files.clone()),
+            None => commit_args["all"] = json!(true),
+        }
+        match arg("commit_message") {
+            Some(message) => commit_args["message"] = json!(message),
+            None => commit_args["generate_message"] = json!(true),
+        }
+        let committed = super::GitCommit
+            .execute(commit_args, working_dir)
+            .await
+            .map_err(|e| report.fail("commit", e))?;
+        report
+            .steps
+            .push(committed.lines().next().unwrap_or("").to_string());
+
+        let base_ref = base.as_deref().map(|base| format!("{}/{}", remote, base));
+        if let Some(base_ref) = base_ref.as_deref() {
+            let ahead = git_output(
+                &repo,
+                &["rev-list", "--count", &format!("{}..HEAD", base_ref)],
+            )
+            .await
+            .unwrap_or_default();
+            if ahead.trim() == "0" {
+                return Err(report.fail(
+                    "open a pull request",
+                    format!(
+                        "branch '{}' has no commits that are not on {}",
+                        branch, base_ref
+                    ),
+                ));
+            }
+        }
+
+        // 4. Push
+        super::GitPush
+            .execute(
+                json!({
+                    "path": repo_arg,
+                    "remote": remote,
+                    "branch": branch,
+                }),
+                working_dir,
+            )
+            .await
+            .map_err(|e| report.fail("push", e))?;
+        report
+            .steps
+            .push(format!("Pushed `{}` to {}", branch, remote));
+
+        // 5. Pull request (pushing already updated an existing one)
+        let branch_ref: &str = &branch;
+        if let Ok(url) = super::github::gh_output(
+            &repo,
+            &["pr", "view", branch_ref, "--json", "url", "-q", ".url"],
+        )
+        .await
+        {
+            if !url.trim().is_empty() {
+                report
+                    .steps
+                    .push("Updated the existing pull request".to_string());
+                return Ok(format!(
+                    "Pull request: {}\n\n{}",
+                    url.trim(),
+                    report.steps.join("\n")
+                ));
+            }
+        }
+
+        let title = match title {
+            Some(title) => title,
+            None => git_output(&repo, &["log", "-1", "--format=%s"])
+                .await
+                .map(|s| s.trim().to_string())
+                .map_err(|e| report.fail("read the commit subject", e))?,
+        };
+        let body = match arg("body") {
+            Some(body) => body,
+            None => {
+                let diff = match base_ref.as_deref() {
+                    Some(base_ref) => {
+                        git_output(&repo, &["diff", &format!("{}...HEAD", base_ref)]).await
+                    }
+                    None => git_output(&repo, &["show", "--format=%B", "HEAD"]).await,
+                }
+                .unwrap_or_default();
+                pr_description(&title, &diff, tests.as_deref()).await
+            }
+        };
+
+        let mut create = vec![
+            "pr", "create", "--head", branch_ref, "--title", &title, "--body", &body,
+        ];
+        if let Some(base) = base.as_deref() {
+            create.extend(["--base", base]);
+        }
+        if args["draft"].as_bool().unwrap_or(false) {
+            create.push("--draft");
+        }
+        let url = super::github::gh_output(&repo, &create)
+            .await
+            .map_err(|e| report.fail("create the pull request", e))?;
+        Ok(format!(
+            "Opened pull request: {}\n\n{}",
+            url.trim(),
+            report.steps.join("\n")
+        ))
+    }
+}
+
+/// Generate a PR description from the diff, falling back to a plain one when
+/// the LLM is unavailable so a PR is still opened.
+async fn pr_description(title: &str, diff: &str, tests: Option<&str>) -> String {
+    let tested = match tests {
+        Some(command) => format!("Tests: `{}` passed.", command),
+        None => "No tests were run.".to_string(),
+    };
+    let end = super::safe_truncate_index(diff, PR_DIFF_CHARS);
+    let instructions = format!(
+        "Write a GitHub pull request description for the diff below, titled \"{}\". \
+         Start with one or two sentences on what the change does and why, then a short \
+         bullet list of the main changes, then a Testing section stating: {} \
+         Reply with the description in Markdown only.",
+        title, tested
+    );
+    match super::git::generate_text(&instructions, &diff[..end]).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::debug!(error = %e, "PR description generation failed");
+            format!("{}\n\n{}", title, tested)
+        }
+    }
+}
+
+/// Time allowed for the reproduction command when no timeout is given.
+const REPRO_TIMEOUT_SECS: u64 = 300;
+
+/// Stack frames shown with their source.
+const REPRO_MAX_FRAMES: usize = 5;
+
+/// Output of the reproduction command kept in the report.
+const REPRO_OUTPUT_CHARS: usize = 3_000;
+
+/// Dependency directories whose frames are not the project's code.
+const VENDORED_DIRS: &[&str] = &["node_modules", "site-packages", ".cargo", "vendor"];
+
+/// A `file:line` location from a stack trace.
+#[derive(Debug, PartialEq)]
+struct Frame {
+    file: String,
+    line: usize,
+}
+
+/// Source locations in a stack trace, in order and without duplicates.
+fn stack_frames(error: &str) -> Vec<Frame> {
+    let re = regex::Regex::new(r#"File "([^"]+)", line (\d+)|([\w./\\-]+\.[A-Za-z]{1,5}):(\d+)"#)
+        .expect("valid frame pattern");
+    let mut frames = Vec::new();
+    for cap in re.captures_iter(error) {
+        let (file, line) = match (cap.get(1), cap.get(3)) {
+            (Some(file), _) => (file.as_str(), &cap[2]),
+            (None, Some(file)) => (file.as_str(), &cap[4]),
+            _ => continue,
+        };
+        let Ok(line) = line.parse() else {
+            continue;
+        };
+        let frame = Frame {
+            file: file.to_string(),
+            line,
+        };
+        if !frames.contains(&frame) {
+            frames.push(frame);
+        }
+    }
+    frames
+}
+
+/// The project file a frame refers to, relative to `repo`: the path as given
+/// or its longest suffix that exists, since traces often carry absolute paths
+/// from another machine.
+fn resolve_frame(repo: &Path, file: &str) -> Option<String> {
+    let parts: Vec<&str> = file
+        .split(['/', '\\'])
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+    if parts.iter().any(|p| VENDORED_DIRS.contains(p)) {
+        return None;
+    }
+    (0..parts.len())
+        .map(|i| parts[i..].join("/"))
+        .find(|relative| repo.join(relative).is_file())
+}
+
+/// Names of the failing tests mentioned in test runner output.
+fn failing_tests(error: &str) -> Vec<String> {
+    let patterns = [
+        r"thread '([^']+)' panicked",
+        r"(?m)^test (\S+) \.\.\. FAILED",
+        r"(?m)^FAILED (\S+\.py::\S+)",
+        r"--- FAIL: (\S+)",
+    ];
+    let mut tests = Vec::new();
+    for pattern in patterns {
+        let re = regex::Regex::new(pattern).expect("valid test pattern");
+        for cap in re.captures_iter(error) {
+            let name = cap[1].to_string();
+            if name != "main" && !tests.contains(&name) {
+                tests.push(name);
+            }
+        }
+    }
+    tests
+}
+
+/// A command that should reproduce the failure: the first failing test, or
+/// the test file a frame points into.
+fn repro_command(repo: &Path, tests: &[String], files: &[String]) -> Option<String> {
+    use super::terminal::shell_quote;
+
+    let has = |marker: &str| repo.join(marker).exists();
+    if let Some(test) = tests.first() {
+        if test.contains(".py::") {
+            return Some(format!("python -m pytest {}", shell_quote(test)));
+        }
+        if has("Cargo.toml") {
+            return Some(format!("cargo test {}", shell_quote(test)));
+        }
+        if has("go.mod") {
+            return Some(format!(
+                "go test ./... -run {}",
+                shell_quote(&format!("^{}$", test))
+            ));
+        }
+    }
+    files.iter().find_map(|file| {
+        let name = file.rsplit('/').next().unwrap_or(file);
+        if name.ends_with(".py") && (name.starts_with("test_") || name.ends_with("_test.py")) {
+            Some(format!("python -m pytest {}", shell_quote(file)))
+        } else if name.ends_with("_test.go") {
+            let dir = file.rsplit_once('/').map(|(dir, _)| dir).unwrap_or(".");
+            Some(format!("go test ./{}", dir.trim_start_matches("./")))
+        } else if [".test.", ".spec."].iter().any(|m| name.contains(m)) && has("package.json") {
+            Some(format!("npm test -- {}", shell_quote(file)))
+        } else {
+            None
+        }
+    })
+}
+
+/// The line of an error report that identifies the failure, without thread
+/// names and source locations, so it can be looked for in other output.
+fn error_signature(error: &str) -> Option<String> {
+    let lines: Vec<&str> = error
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("File \"") && !l.starts_with("at "))
+        .collect();
+    let is_key = |line: &&str| {
+        let lower = line.to_lowercase();
+        ["error", "exception", "panicked", "assert", "fail"]
+            .iter()
+            .any(|k| lower.contains(k))
+    };
+    // Python prints the exception after the traceback, others before it.
+    let index = if error.contains("Traceback (most recent call last)") {
+        lines.iter().rposition(is_key)?
+    } else {
+        lines.iter().position(is_key)?
+    };
+    let noise = regex::Regex::new(r"thread '[^']*' |[\w./\\-]+\.[A-Za-z]{1,5}:\d+(:\d+)?")
+        .expect("valid noise pattern");
+    let mut signature = noise.replace_all(lines[index], "").trim().to_string();
+    // Rust prints the panic message on the line after the location.
+    if signature.ends_with(':') {
+        if let Some(next) = lines.get(index + 1) {
+            signature = next.to_string();
+        }
+    }
+    let signature = signature.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!signature.is_empty()).then_some(signature)
+}
+
+/// Reproduce a reported failure: locate its frames, derive and run a
+/// reproduction command, and compare the result with the report.
+pub struct ReproduceBug;
+
+#[async_trait]
+impl Tool for ReproduceBug {
+    fn name(&self) -> &str {
+        "reproduce_bug"
+    }
+
+    fn description(&self) -> &str {
+        "Reproduce a bug from its error message or stack trace: finds the project files in the trace and shows the code at each frame, derives a reproduction command (the failing test, or the test file in the trace) unless one is given, runs it, and reports whether the same failure reproduces. Use it as the first step of fixing a reported failure."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "error_message": {
+                    "type": "string",
+                    "description": "The error message, stack trace or failing test output"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Command that should reproduce the failure (default: derived from the trace)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Project directory (default: current directory)"
+                },
+                "timeout_seconds": {
+                    "type": "integer",
+                    "description": "Time allowed for the command (default: 300)"
+                }
+            },
+            "required": ["error_message"]
+        })
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let error_message = args
+            .get("error_message")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("error_message is required"))?;
+        let repo = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .map(|p| super::resolve_path_simple(p, working_dir))
+            .unwrap_or_else(|| working_dir.to_path_buf());
+
+        let mut result = String::from("# Bug Reproduction\n\n");
+
+        // 1. Frames in the project, with their code
+        let mut files: Vec<String> = Vec::new();
+        result.push_str("## Stack Frames\n");
+        let mut shown = 0;
+        for frame in stack_frames(error_message) {
+            let Some(file) = resolve_frame(&repo, &frame.file) else {
+                continue;
+            };
+            if shown < REPRO_MAX_FRAMES {
+                result.push_str(&format!("\n### `{}:{}`\n", file, frame.line));
+                if let Ok(content) = tokio::fs::read_to_string(repo.join(&file)).await {
+                    result.push_str("```\n");
+                    let first = frame.line.saturating_sub(3);
+                    for (i, line) in content.lines().enumerate().skip(first).take(5) {
+                        let marker = if i + 1 == frame.line { ">" } else { " " };
+                        result.push_str(&format!("{}{:5} | {}\n", marker, i + 1, line));
+                    }
+                    result.push_str("```\n");
+                }
+                shown += 1;
+            }
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+        if shown == 0 {
+            result.push_str("- No frames point into this project\n");
+        }
+
+        // 2. Reproduction command
+        let tests = failing_tests(error_message);
+        let command = match args.get("command").and_then(|v| v.as_str()) {
+            Some(command) if !command.trim().is_empty() => command.trim().to_string(),
+            _ => match repro_command(&repo, &tests, &files) {
+                Some(command) => command,
+                None => {
+                    result.push_str(
+                        "\n## Reproduction Command\nCould not derive one from the trace. \
+                         Write a minimal failing test or script for the frames above and \
+                         call reproduce_bug again with `command`.\n",
+                    );
+                    return Ok(result);
+                }
+            },
+        };
+        result.push_str(&format!("\n## Reproduction Command\n`{}`\n", command));
+
+        // 3. Run it and compare with the report
+        let timeout = args
+            .get("timeout_seconds")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(REPRO_TIMEOUT_SECS);
+        result.push_str("\n## Result\n");
+        let output = match super::terminal::run_workspace_shell(
+            &repo,
+            &command,
+            std::collections::HashMap::new(),
+            std::time::Duration::from_secs(timeout),
+        )
+        .await
+        {
+            Ok(output) => output,
+            Err(e) => {
+                result.push_str(&format!("**Not run**: {}\n", e));
+                return Ok(result);
+            }
+        };
+        let combined = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let signature = error_signature(error_message);
+        let verdict = if output.status.success() {
+            "**Not reproduced**: the command passed.".to_string()
+        } else {
+            let normalized = combined.split_whitespace().collect::<Vec<_>>().join(" ");
+            match signature.as_deref() {
+                Some(signature) if !normalized.contains(signature) => format!(
+                    "**Failed differently**: exit code {}, but the output does not contain `{}`.",
+                    output.status.code().unwrap_or(-1),
+                    signature
+                ),
+                _ => format!(
+                    "**Reproduced**: exit code {}.",
+                    output.status.code().unwrap_or(-1)
+                ),
+            }
+        };
+        result.push_str(&verdict);
+        result.push_str(&format!(
+            "\n\n```\n{}\n```\n",
+            output_tail(combined.trim_end(), REPRO_OUTPUT_CHARS)
+        ));
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pr_branch_name_and_output_tail() {
+        assert_eq!(
+            pr_branch_name(Some("Fix: handle  empty config (#12)")),
+            "sandboxed/fix-handle-empty-config-12"
+        );
+        assert!(pr_branch_name(None).starts_with("sandboxed/pr-"));
+        assert!(pr_branch_name(Some("!!!")).starts_with("sandboxed/pr-"));
+        assert_eq!(output_tail("héllo", 4), "llo");
+        assert_eq!(output_tail("ok", 10), "ok");
+    }
+
+    #[test]
+    fn test_reproduce_bug_parsing() {
+        let rust = "thread 'parser::tests::empty' panicked at src/parser.rs:42:9:\n\
+                    called `Option::unwrap()` on a `None` value\n\
+                    test parser::tests::empty ... FAILED";
+        assert_eq!(
+            stack_frames(rust),
+            vec![Frame {
+                file: "src/parser.rs".to_string(),
+                line: 42
+            }]
+        );
+        assert_eq!(failing_tests(rust), vec!["parser::tests::empty"]);
+        assert_eq!(
+            error_signature(rust).as_deref(),
+            Some("called `Option::unwrap()` on a `None` value")
+        );
+
+        let python = "Traceback (most recent call last):\n  \
+                      File \"/ci/app/tests/test_cfg.py\", line 7, in test_load\n    \
+                      load({})\nKeyError: 'name'";
+        assert_eq!(stack_frames(python)[0].file, "/ci/app/tests/test_cfg.py");
+        assert_eq!(error_signature(python).as_deref(), Some("KeyError: 'name'"));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("tests")).unwrap();
+        std::fs::write(dir.path().join("tests/test_cfg.py"), "").unwrap();
+        let file = resolve_frame(dir.path(), "/ci/app/tests/test_cfg.py").unwrap();
+        assert_eq!(file, "tests/test_cfg.py");
+        assert_eq!(
+            repro_command(dir.path(), &[], &[file]).as_deref(),
+            Some("python -m pytest 'tests/test_cfg.py'")
+        );
+        assert!(resolve_frame(dir.path(), "/usr/lib/python3/site-packages/x.py").is_none());
+    }
+}
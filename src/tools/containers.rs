@@ -0,0 +1,638 @@
+//! Container image tools: `docker_build`, `docker_run` and `docker_push`.
+//!
+//! They let a mission that containerizes a service check that the image
+//! builds and starts, without handing the agent the full engine CLI. Commands
+//! run in the workspace execution context with Docker, or Podman when Docker
+//! is not installed (`SANDBOXED_SH_CONTAINER_ENGINE` picks one explicitly).
+//!
+//! Guardrails:
+//! - containers never run privileged, on the host network, or with extra
+//!   capabilities or host mounts (only the working directory, read-only),
+//! - memory, CPU and process counts are capped, and every call has a timeout,
+//! - built images larger than `SANDBOXED_SH_IMAGE_MAX_SIZE_MB` are removed,
+//! - pushes go only to registries listed in `SANDBOXED_SH_IMAGE_PUSH_ALLOWLIST`.
+//!
+//! The size limit and the push allowlist are read from the server (see
+//! `guard::SERVER_SETTINGS`), not from the env the mission can rewrite.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::terminal::{run_workspace_shell, shell_quote, workspace_setting};
+use super::{resolve_path_simple as resolve_path, safe_truncate_index, Tool, ToolArgs};
+
+/// Workspace setting forcing the engine (`docker` or `podman`).
+pub const ENGINE_SETTING: &str = "SANDBOXED_SH_CONTAINER_ENGINE";
+/// Server-side setting for the largest image `docker_build` keeps, in MiB.
+pub const MAX_IMAGE_SIZE_SETTING: &str = "SANDBOXED_SH_IMAGE_MAX_SIZE_MB";
+/// Server-side setting listing registry prefixes `docker_push` may push to.
+pub const PUSH_ALLOWLIST_SETTING: &str = "SANDBOXED_SH_IMAGE_PUSH_ALLOWLIST";
+
+const DEFAULT_MAX_IMAGE_SIZE_MB: u64 = 4096;
+const DEFAULT_BUILD_TIMEOUT_SECS: u64 = 900;
+const MAX_BUILD_TIMEOUT_SECS: u64 = 3600;
+const DEFAULT_RUN_TIMEOUT_SECS: u64 = 120;
+const MAX_RUN_TIMEOUT_SECS: u64 = 900;
+const MAX_MEMORY_MB: u64 = 4096;
+const MAX_CPUS: f64 = 4.0;
+const PIDS_LIMIT: u32 = 512;
+/// Build and container output returned to the agent (the tail is kept).
+const MAX_OUTPUT_CHARS: usize = 20_000;
+
+/// Name prefix of containers started by `docker_run`.
+const CONTAINER_PREFIX: &str = "sandboxed-sh-run-";
+
+/// Whether `reference` is a plausible image reference (`[registry/]name[:tag][@digest]`).
+fn valid_reference(reference: &str) -> bool {
+    !reference.is_empty()
+        && reference.len() <= 255
+        && !reference.starts_with('-')
+        && reference
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/' | ':' | '@'))
+}
+
+fn valid_env_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn check_reference(reference: &str) -> anyhow::Result<()> {
+    if !valid_reference(reference) {
+        anyhow::bail!("Invalid image reference '{}'", reference);
+    }
+    Ok(())
+}
+
+fn check_env(env: &HashMap<String, String>, what: &str) -> anyhow::Result<()> {
+    if let Some(key) = env.keys().find(|key| !valid_env_key(key)) {
+        anyhow::bail!("Invalid {} name '{}'", what, key);
+    }
+    Ok(())
+}
+
+/// Whether `image` may be pushed under the comma-separated registry prefixes.
+///
+/// A prefix matches at a path boundary: `ghcr.io/acme` allows
+/// `ghcr.io/acme/api:1` but not `ghcr.io/acme-evil/api:1`. Images without a
+/// registry host are never allowed, since they resolve to Docker Hub.
+fn push_allowed(image: &str, allowlist: &str) -> bool {
+    let has_registry = image
+        .split_once('/')
+        .is_some_and(|(host, _)| host.contains('.') || host.contains(':') || host == "localhost");
+    has_registry
+        && allowlist
+            .split(',')
+            .map(|prefix| prefix.trim().trim_end_matches('/'))
+            .filter(|prefix| !prefix.is_empty())
+            .any(|prefix| {
+                image.strip_prefix(prefix).is_some_and(|rest| {
+                    rest.starts_with('/') || rest.starts_with(':') || rest.starts_with('@')
+                })
+            })
+}
+
+/// Keep the last `max` bytes of `text`, which is where build errors are.
+fn tail(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_string();
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[... {} bytes omitted ...]\n{}", start, &text[start..])
+}
+
+fn command_line(engine: &str, args: &[String]) -> String {
+    std::iter::once(engine.to_string())
+        .chain(args.iter().map(|a| shell_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Run `<engine> <args>` in the workspace, returning (success, stdout, stderr).
+async fn engine_output(
+    cwd: &Path,
+    engine: &str,
+    args: &[String],
+    timeout: Duration,
+) -> anyhow::Result<(bool, String, String)> {
+    let output =
+        run_workspace_shell(cwd, &command_line(engine, args), HashMap::new(), timeout).await?;
+    Ok((
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    ))
+}
+
+/// The container engine available in the workspace.
+async fn detect_engine(cwd: &Path) -> anyhow::Result<&'static str> {
+    if let Some(engine) = workspace_setting(ENGINE_SETTING) {
+        return match engine.as_str() {
+            "docker" => Ok("docker"),
+            "podman" => Ok("podman"),
+            other => anyhow::bail!(
+                "{} must be docker or podman, got '{}'",
+                ENGINE_SETTING,
+                other
+            ),
+        };
+    }
+    let probe = "command -v docker >/dev/null 2>&1 && echo docker || { command -v podman >/dev/null 2>&1 && echo podman; }";
+    let output = run_workspace_shell(cwd, probe, HashMap::new(), Duration::from_secs(10)).await?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "docker" => Ok("docker"),
+        "podman" => Ok("podman"),
+        _ => anyhow::bail!(
+            "Neither docker nor podman is installed in this workspace. Install one (e.g. via the workspace template's init script) to build images."
+        ),
+    }
+}
+
+async fn max_image_bytes() -> anyhow::Result<u64> {
+    Ok(super::guard::setting(MAX_IMAGE_SIZE_SETTING)
+        .await?
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_IMAGE_SIZE_MB)
+        * 1024
+        * 1024)
+}
+
+/// Build an image from a Dockerfile.
+pub struct DockerBuild;
+
+#[derive(Deserialize, JsonSchema)]
+struct BuildArgs {
+    /// Tag for the built image, e.g. `myservice:dev`
+    tag: String,
+    /// Build context directory (default: working directory)
+    #[serde(default)]
+    context: Option<String>,
+    /// Dockerfile path relative to the context (default: Dockerfile)
+    #[serde(default)]
+    dockerfile: Option<String>,
+    /// Build arguments (`--build-arg NAME=value`)
+    #[serde(default)]
+    build_args: HashMap<String, String>,
+    /// Target stage of a multi-stage build
+    #[serde(default)]
+    target: Option<String>,
+    /// Build without the layer cache
+    #[serde(default)]
+    no_cache: bool,
+    /// Timeout in seconds (default 900, max 3600)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+fn build_argv(args: &BuildArgs, context: &Path) -> anyhow::Result<Vec<String>> {
+    check_reference(&args.tag)?;
+    check_env(&args.build_args, "build argument")?;
+    let mut argv = vec!["build".to_string(), "--tag".to_string(), args.tag.clone()];
+    if let Some(dockerfile) = args.dockerfile.as_deref() {
+        argv.push("--file".to_string());
+        argv.push(context.join(dockerfile).to_string_lossy().to_string());
+    }
+    let mut build_args: Vec<_> = args.build_args.iter().collect();
+    build_args.sort();
+    for (name, value) in build_args {
+        argv.push("--build-arg".to_string());
+        argv.push(format!("{}={}", name, value));
+    }
+    if let Some(target) = args.target.as_deref() {
+        if !valid_reference(target) {
+            anyhow::bail!("Invalid target stage '{}'", target);
+        }
+        argv.push("--target".to_string());
+        argv.push(target.to_string());
+    }
+    if args.no_cache {
+        argv.push("--no-cache".to_string());
+    }
+    argv.push(context.to_string_lossy().to_string());
+    Ok(argv)
+}
+
+/// Build context and Dockerfile.
+const BUILD_PATH_ARGS: &[&str] = &["context", "dockerfile"];
+
+#[async_trait]
+impl Tool for DockerBuild {
+    fn name(&self) -> &str {
+        "docker_build"
+    }
+
+    fn description(&self) -> &str {
+        "Build a container image from a Dockerfile (docker or podman) and report its size. Images over the workspace size limit are removed. Use docker_run afterwards to check that the image starts."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        BuildArgs::schema()
+    }
+
+    fn path_args(&self) -> &[&'static str] {
+        BUILD_PATH_ARGS
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = BuildArgs::parse(args)?;
+        let context = args
+            .context
+            .as_deref()
+            .map(|c| resolve_path(c, working_dir))
+            .unwrap_or_else(|| working_dir.to_path_buf());
+        let argv = build_argv(&args, &context)?;
+        let engine = detect_engine(working_dir).await?;
+        let timeout = Duration::from_secs(
+            args.timeout_secs
+                .unwrap_or(DEFAULT_BUILD_TIMEOUT_SECS)
+                .clamp(1, MAX_BUILD_TIMEOUT_SECS),
+        );
+
+        let (ok, stdout, stderr) = engine_output(working_dir, engine, &argv, timeout).await?;
+        // BuildKit writes its progress to stderr.
+        let log = tail(&format!("{}{}", stdout, stderr), MAX_OUTPUT_CHARS);
+        if !ok {
+            anyhow::bail!("{} build failed:\n{}", engine, log);
+        }
+
+        let inspect = vec![
+            "image".to_string(),
+            "inspect".to_string(),
+            "--format".to_string(),
+            "{{.Size}}".to_string(),
+            args.tag.clone(),
+        ];
+        let (_, size, _) =
+            engine_output(working_dir, engine, &inspect, Duration::from_secs(30)).await?;
+        let size: u64 = size.trim().parse().unwrap_or(0);
+        let limit = max_image_bytes().await?;
+        if size > limit {
+            let rmi = vec!["rmi".to_string(), "--force".to_string(), args.tag.clone()];
+            let _ = engine_output(working_dir, engine, &rmi, Duration::from_secs(60)).await;
+            anyhow::bail!(
+                "Image {} is {} MiB, over the {} MiB limit ({}); it was removed. Use a smaller base image or a multi-stage build.",
+                args.tag,
+                size / (1024 * 1024),
+                limit / (1024 * 1024),
+                MAX_IMAGE_SIZE_SETTING
+            );
+        }
+
+        Ok(format!(
+            "Built {} with {} ({} MiB).\n\n{}",
+            args.tag,
+            engine,
+            size / (1024 * 1024),
+            log
+        ))
+    }
+}
+
+/// Run a container from an image with resource caps.
+pub struct DockerRun;
+
+#[derive(Deserialize, JsonSchema)]
+struct RunArgs {
+    /// Image to run
+    image: String,
+    /// Command and arguments (default: the image's entrypoint/cmd)
+    #[serde(default)]
+    command: Vec<String>,
+    /// Environment variables for the container
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// Container ports to publish on 127.0.0.1, as `container` or `host:container`
+    #[serde(default)]
+    ports: Vec<String>,
+    /// Mount the working directory read-only at /workspace
+    #[serde(default)]
+    mount_workspace: bool,
+    /// Start in the background (for services): wait `wait_secs`, then report
+    /// whether it is still running along with its logs
+    #[serde(default)]
+    detach: bool,
+    /// Seconds to wait before checking a detached container (default 5)
+    #[serde(default)]
+    wait_secs: Option<u64>,
+    /// Leave a detached container running after the check (default: stop and remove it)
+    #[serde(default)]
+    keep_running: bool,
+    /// Memory limit in MiB (default and max 4096)
+    #[serde(default)]
+    memory_mb: Option<u64>,
+    /// CPU limit (default and max 4)
+    #[serde(default)]
+    cpus: Option<f64>,
+    /// Timeout in seconds for foreground runs (default 120, max 900)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+/// Parse a `ports` entry into (host port, container port); host 0 means any.
+fn parse_port(spec: &str) -> anyhow::Result<(u16, u16)> {
+    let parse = |p: &str| {
+        p.trim()
+            .parse::<u16>()
+            .map_err(|_| anyhow::anyhow!("Invalid port mapping '{}'", spec))
+    };
+    match spec.split_once(':') {
+        Some((host, container)) => Ok((parse(host)?, parse(container)?)),
+        None => Ok((0, parse(spec)?)),
+    }
+}
+
+fn run_argv(args: &RunArgs, name: &str, working_dir: &Path) -> anyhow::Result<Vec<String>> {
+    check_reference(&args.image)?;
+    check_env(&args.env, "environment variable")?;
+    let memory = args
+        .memory_mb
+        .unwrap_or(MAX_MEMORY_MB)
+        .clamp(16, MAX_MEMORY_MB);
+    let cpus = args.cpus.unwrap_or(MAX_CPUS);
+    if cpus.is_nan() || cpus <= 0.0 {
+        anyhow::bail!("cpus must be positive");
+    }
+    let cpus = cpus.min(MAX_CPUS);
+
+    let mut argv: Vec<String> = vec!["run".to_string(), "--name".to_string(), name.to_string()];
+    if args.detach {
+        argv.push("--detach".to_string());
+    } else {
+        argv.push("--rm".to_string());
+    }
+    argv.extend([
+        "--network=bridge".to_string(),
+        "--security-opt=no-new-privileges".to_string(),
+        format!("--memory={}m", memory),
+        format!("--memory-swap={}m", memory),
+        format!("--cpus={}", cpus),
+        format!("--pids-limit={}", PIDS_LIMIT),
+    ]);
+    for spec in &args.ports {
+        let (host, container) = parse_port(spec)?;
+        argv.push("--publish".to_string());
+        argv.push(if host == 0 {
+            format!("127.0.0.1::{}", container)
+        } else {
+            format!("127.0.0.1:{}:{}", host, container)
+        });
+    }
+    if args.mount_workspace {
+        argv.push("--volume".to_string());
+        argv.push(format!("{}:/workspace:ro", working_dir.display()));
+    }
+    let mut env: Vec<_> = args.env.iter().collect();
+    env.sort();
+    for (key, value) in env {
+        argv.push("--env".to_string());
+        argv.push(format!("{}={}", key, value));
+    }
+    argv.push(args.image.clone());
+    argv.extend(args.command.iter().cloned());
+    Ok(argv)
+}
+
+async fn remove_container(cwd: &Path, engine: &str, name: &str) {
+    let rm = vec!["rm".to_string(), "--force".to_string(), name.to_string()];
+    let _ = engine_output(cwd, engine, &rm, Duration::from_secs(30)).await;
+}
+
+#[async_trait]
+impl Tool for DockerRun {
+    fn name(&self) -> &str {
+        "docker_run"
+    }
+
+    fn description(&self) -> &str {
+        "Run a container from an image with capped memory, CPU and processes, no privileges and no host network. Foreground runs return the exit code and output. With detach=true the container runs as a service: after wait_secs the tool reports whether it is still running, its published ports and logs, then removes it unless keep_running is set."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        RunArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = RunArgs::parse(args)?;
+        let name = format!(
+            "{}{}",
+            CONTAINER_PREFIX,
+            &uuid::Uuid::new_v4().simple().to_string()[..12]
+        );
+        let argv = run_argv(&args, &name, working_dir)?;
+        let engine = detect_engine(working_dir).await?;
+
+        if !args.detach {
+            let timeout = Duration::from_secs(
+                args.timeout_secs
+                    .unwrap_or(DEFAULT_RUN_TIMEOUT_SECS)
+                    .clamp(1, MAX_RUN_TIMEOUT_SECS),
+            );
+            let result = run_workspace_shell(
+                working_dir,
+                &command_line(engine, &argv),
+                HashMap::new(),
+                timeout,
+            )
+            .await;
+            let output = match result {
+                Ok(output) => output,
+                Err(e) => {
+                    remove_container(working_dir, engine, &name).await;
+                    return Err(e);
+                }
+            };
+            let text = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return Ok(format!(
+                "Container exited with code {}.\n\n{}",
+                output.status.code().unwrap_or(-1),
+                tail(&text, MAX_OUTPUT_CHARS)
+            ));
+        }
+
+        let (ok, _, stderr) =
+            engine_output(working_dir, engine, &argv, Duration::from_secs(120)).await?;
+        if !ok {
+            remove_container(working_dir, engine, &name).await;
+            anyhow::bail!("{} run failed: {}", engine, stderr.trim());
+        }
+        tokio::time::sleep(Duration::from_secs(args.wait_secs.unwrap_or(5).min(300))).await;
+
+        let inspect = vec![
+            "inspect".to_string(),
+            "--format".to_string(),
+            "{{.State.Status}} {{.State.ExitCode}}".to_string(),
+            name.clone(),
+        ];
+        let (_, state, _) =
+            engine_output(working_dir, engine, &inspect, Duration::from_secs(30)).await?;
+        let ports = vec!["port".to_string(), name.clone()];
+        let (_, ports, _) =
+            engine_output(working_dir, engine, &ports, Duration::from_secs(30)).await?;
+        let logs = vec![
+            "logs".to_string(),
+            "--tail".to_string(),
+            "200".to_string(),
+            name.clone(),
+        ];
+        let (_, out, err) =
+            engine_output(working_dir, engine, &logs, Duration::from_secs(30)).await?;
+
+        let mut report = format!("Container {}: {}\n", name, state.trim());
+        if !ports.trim().is_empty() {
+            report.push_str(&format!("Published ports:\n{}\n", ports.trim()));
+        }
+        if args.keep_running && state.trim().starts_with("running") {
+            report.push_str(&format!(
+                "Left running; stop it with `{} rm --force {}`.\n",
+                engine, name
+            ));
+        } else {
+            remove_container(working_dir, engine, &name).await;
+            report.push_str("Container removed.\n");
+        }
+        let logs = format!("{}{}", out, err);
+        let end = safe_truncate_index(&logs, MAX_OUTPUT_CHARS);
+        report.push_str(&format!("\nLogs:\n{}", &logs[..end]));
+        Ok(report)
+    }
+}
+
+/// Push a built image to an allowlisted registry.
+pub struct DockerPush;
+
+#[derive(Deserialize, JsonSchema)]
+struct PushArgs {
+    /// Image to push, including the registry, e.g. `ghcr.io/acme/api:1.2`
+    image: String,
+}
+
+#[async_trait]
+impl Tool for DockerPush {
+    fn name(&self) -> &str {
+        "docker_push"
+    }
+
+    fn description(&self) -> &str {
+        "Push a local image to a registry. Only registries in the workspace's push allowlist are accepted; the engine's existing login is used."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        PushArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = PushArgs::parse(args)?;
+        check_reference(&args.image)?;
+        let allowlist = super::guard::setting(PUSH_ALLOWLIST_SETTING)
+            .await?
+            .unwrap_or_default();
+        if !push_allowed(&args.image, &allowlist) {
+            anyhow::bail!(
+                "Pushing {} is not allowed. Set {} in the workspace env vars to the registries missions may push to.",
+                args.image,
+                PUSH_ALLOWLIST_SETTING
+            );
+        }
+        let engine = detect_engine(working_dir).await?;
+        let argv = vec!["push".to_string(), args.image.clone()];
+        let (ok, stdout, stderr) =
+            engine_output(working_dir, engine, &argv, Duration::from_secs(900)).await?;
+        let log = tail(&format!("{}{}", stdout, stderr), MAX_OUTPUT_CHARS);
+        if !ok {
+            anyhow::bail!("{} push failed:\n{}", engine, log);
+        }
+        Ok(format!("Pushed {}.\n\n{}", args.image, log))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_allowlist_matches_at_path_boundary() {
+        let allowlist = "ghcr.io/acme, registry.internal:5000/";
+        assert!(push_allowed("ghcr.io/acme/api:1", allowlist));
+        assert!(push_allowed("registry.internal:5000/team/app", allowlist));
+        assert!(!push_allowed("ghcr.io/acme-evil/api:1", allowlist));
+        assert!(!push_allowed("ghcr.io/other/api", allowlist));
+        // Hub images have no registry host and are never pushable.
+        assert!(!push_allowed("acme/api", "acme"));
+        assert!(!push_allowed("ghcr.io/acme/api", ""));
+    }
+
+    #[test]
+    fn run_argv_applies_guardrails() {
+        let args = RunArgs::parse(serde_json::json!({
+            "image": "myservice:dev",
+            "command": ["serve", "--port", "8080"],
+            "env": {"MODE": "test"},
+            "ports": ["8080", "9000:9090"],
+            "memory_mb": 100000,
+            "cpus": 16,
+        }))
+        .unwrap();
+        let argv = run_argv(&args, "sandboxed-sh-run-x", Path::new("/work")).unwrap();
+        assert!(argv.contains(&"--rm".to_string()));
+        assert!(argv.contains(&"--memory=4096m".to_string()));
+        assert!(argv.contains(&"--cpus=4".to_string()));
+        assert!(argv.contains(&"--security-opt=no-new-privileges".to_string()));
+        assert!(argv.contains(&"127.0.0.1::8080".to_string()));
+        assert!(argv.contains(&"127.0.0.1:9000:9090".to_string()));
+        assert!(!argv
+            .iter()
+            .any(|a| a.contains("privileged") || a == "--volume"));
+        assert_eq!(
+            &argv[argv.len() - 4..],
+            ["myservice:dev", "serve", "--port", "8080"]
+        );
+
+        let bad = RunArgs::parse(serde_json::json!({"image": "--privileged"})).unwrap();
+        assert!(run_argv(&bad, "x", Path::new("/work")).is_err());
+        let bad = RunArgs::parse(serde_json::json!({"image": "a", "env": {"A B": "1"}})).unwrap();
+        assert!(run_argv(&bad, "x", Path::new("/work")).is_err());
+    }
+
+    #[test]
+    fn build_argv_and_tail() {
+        let args = BuildArgs::parse(serde_json::json!({
+            "tag": "svc:dev",
+            "dockerfile": "docker/Dockerfile",
+            "build_args": {"VERSION": "1.2"},
+            "no_cache": true,
+        }))
+        .unwrap();
+        let argv = build_argv(&args, Path::new("/work/svc")).unwrap();
+        assert_eq!(
+            argv,
+            [
+                "build",
+                "--tag",
+                "svc:dev",
+                "--file",
+                "/work/svc/docker/Dockerfile",
+                "--build-arg",
+                "VERSION=1.2",
+                "--no-cache",
+                "/work/svc"
+            ]
+        );
+        let log = format!("{}ERROR: step failed", "x".repeat(100));
+        assert!(tail(&log, 30).ends_with("ERROR: step failed"));
+        assert_eq!(tail("short", 30), "short");
+    }
+}
@@ -0,0 +1,749 @@
+//! `inspect_data`: profile CSV and Parquet files, or query them with SQL.
+//!
+//! The profile lists each column's type, null count and ratio and numeric
+//! range, the row count and a few sample rows, so a data-engineering mission
+//! can understand a file without reading it into context. CSV files are
+//! streamed and types inferred from the values; Parquet files report their
+//! schema, with row counts and null counts taken from the footer metadata.
+//!
+//! With `sql`, the file (up to [`MAX_SQL_ROWS`] rows) is loaded into an
+//! in-memory SQLite table named `data` and the query's result is returned as
+//! a table. Only read-only statements run, and the connection cannot attach
+//! other databases, so a query sees nothing but the file.
+
+use std::fs::File;
+use std::path::Path;
+
+use async_trait::async_trait;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use rusqlite::types::Value as SqlValue;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{resolve_path_simple as resolve_path, Tool, ToolArgs};
+
+const DEFAULT_SAMPLE_ROWS: usize = 5;
+const MAX_SAMPLE_ROWS: usize = 50;
+const DEFAULT_RESULT_ROWS: usize = 50;
+const MAX_RESULT_ROWS: usize = 500;
+/// Rows loaded into SQLite for a query.
+const MAX_SQL_ROWS: usize = 100_000;
+const MAX_CELL_CHARS: usize = 80;
+
+/// CSV values treated as missing.
+const NULL_MARKERS: &[&str] = &["", "null", "NULL", "NA", "N/A", "NaN", "None"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataFormat {
+    Csv(u8),
+    Parquet,
+}
+
+impl DataFormat {
+    fn detect(path: &Path, explicit: Option<&str>) -> anyhow::Result<Self> {
+        let name = explicit
+            .map(str::to_ascii_lowercase)
+            .or_else(|| {
+                path.extension()
+                    .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            })
+            .unwrap_or_default();
+        match name.as_str() {
+            "csv" => Ok(Self::Csv(b',')),
+            "tsv" | "tab" => Ok(Self::Csv(b'\t')),
+            "parquet" | "pq" => Ok(Self::Parquet),
+            _ => anyhow::bail!(
+                "Cannot tell the format of {}; pass format: csv, tsv or parquet",
+                path.display()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum InferredType {
+    Empty,
+    Integer,
+    Float,
+    Boolean,
+    String,
+}
+
+impl InferredType {
+    fn of(value: &str) -> Self {
+        if value.parse::<i64>().is_ok() {
+            Self::Integer
+        } else if value.parse::<f64>().is_ok() {
+            Self::Float
+        } else if matches!(
+            value,
+            "true" | "false" | "TRUE" | "FALSE" | "True" | "False"
+        ) {
+            Self::Boolean
+        } else {
+            Self::String
+        }
+    }
+
+    /// The narrowest type holding both.
+    fn widen(self, other: Self) -> Self {
+        use InferredType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Empty, t) | (t, Empty) => t,
+            (Integer, Float) | (Float, Integer) => Float,
+            _ => String,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ColumnProfile {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+    /// None when the file does not record it (Parquet without statistics).
+    nulls: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Profile {
+    format: &'static str,
+    rows: u64,
+    columns: Vec<ColumnProfile>,
+    sample: Vec<Vec<String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<String>,
+}
+
+fn csv_reader(path: &Path, delimiter: u8) -> anyhow::Result<csv::Reader<File>> {
+    Ok(csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)?)
+}
+
+fn profile_csv(path: &Path, delimiter: u8, samples: usize) -> anyhow::Result<Profile> {
+    let mut reader = csv_reader(path, delimiter)?;
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    let mut types = vec![InferredType::Empty; headers.len()];
+    let mut nulls = vec![0u64; headers.len()];
+    let mut ranges: Vec<Option<(f64, f64)>> = vec![None; headers.len()];
+    let mut sample = Vec::new();
+    let mut rows = 0u64;
+    let mut ragged = 0u64;
+
+    for record in reader.records() {
+        let record = record?;
+        rows += 1;
+        if record.len() != headers.len() {
+            ragged += 1;
+        }
+        if sample.len() < samples {
+            sample.push(record.iter().map(str::to_string).collect());
+        }
+        for (i, value) in record.iter().take(headers.len()).enumerate() {
+            let value = value.trim();
+            if NULL_MARKERS.contains(&value) {
+                nulls[i] += 1;
+                continue;
+            }
+            let kind = InferredType::of(value);
+            types[i] = types[i].widen(kind);
+            if matches!(kind, InferredType::Integer | InferredType::Float) {
+                let n: f64 = value.parse().unwrap_or_default();
+                ranges[i] = Some(match ranges[i] {
+                    Some((lo, hi)) => (lo.min(n), hi.max(n)),
+                    None => (n, n),
+                });
+            }
+        }
+        // Short rows are missing their trailing values.
+        for null in nulls.iter_mut().skip(record.len()) {
+            *null += 1;
+        }
+    }
+
+    let columns = headers
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let numeric = matches!(types[i], InferredType::Integer | InferredType::Float);
+            ColumnProfile {
+                name,
+                data_type: serde_json::to_value(types[i])
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                nulls: Some(nulls[i]),
+                min: ranges[i].filter(|_| numeric).map(|r| r.0),
+                max: ranges[i].filter(|_| numeric).map(|r| r.1),
+            }
+        })
+        .collect();
+    let mut notes = Vec::new();
+    if ragged > 0 {
+        notes.push(format!(
+            "{} rows have a different number of fields than the header",
+            ragged
+        ));
+    }
+    Ok(Profile {
+        format: if delimiter == b'\t' { "tsv" } else { "csv" },
+        rows,
+        columns,
+        sample,
+        notes,
+    })
+}
+
+/// A Parquet value as text for display.
+fn field_text(field: &Field) -> String {
+    match field {
+        Field::Null => String::new(),
+        Field::Str(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A Parquet value for SQLite.
+fn field_sql(field: &Field) -> SqlValue {
+    match field {
+        Field::Null => SqlValue::Null,
+        Field::Bool(b) => SqlValue::Integer(*b as i64),
+        Field::Byte(v) => SqlValue::Integer(*v as i64),
+        Field::Short(v) => SqlValue::Integer(*v as i64),
+        Field::Int(v) => SqlValue::Integer(*v as i64),
+        Field::Long(v) => SqlValue::Integer(*v),
+        Field::UByte(v) => SqlValue::Integer(*v as i64),
+        Field::UShort(v) => SqlValue::Integer(*v as i64),
+        Field::UInt(v) => SqlValue::Integer(*v as i64),
+        Field::ULong(v) => SqlValue::Integer(*v as i64),
+        Field::Float(v) => SqlValue::Real(*v as f64),
+        Field::Double(v) => SqlValue::Real(*v),
+        other => SqlValue::Text(field_text(other)),
+    }
+}
+
+fn profile_parquet(path: &Path, samples: usize) -> anyhow::Result<Profile> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let metadata = reader.metadata();
+    let schema = metadata.file_metadata().schema_descr();
+
+    let columns = schema
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let nulls = metadata
+                .row_groups()
+                .iter()
+                .map(|rg| rg.column(i).statistics().and_then(|s| s.null_count_opt()))
+                .sum::<Option<u64>>();
+            let data_type = match column.logical_type() {
+                Some(logical) => format!("{} ({:?})", column.physical_type(), logical),
+                None => column.physical_type().to_string(),
+            };
+            ColumnProfile {
+                name: column.path().string(),
+                data_type,
+                nulls,
+                min: None,
+                max: None,
+            }
+        })
+        .collect();
+
+    let mut sample = Vec::new();
+    for row in reader.get_row_iter(None)?.take(samples) {
+        sample.push(
+            row?.get_column_iter()
+                .map(|(_, field)| field_text(field))
+                .collect(),
+        );
+    }
+    let mut notes = vec![format!(
+        "{} row groups{}",
+        metadata.num_row_groups(),
+        metadata
+            .file_metadata()
+            .created_by()
+            .map(|by| format!(", written by {}", by))
+            .unwrap_or_default()
+    )];
+    if schema.num_columns() != schema.root_schema().get_fields().len() {
+        notes.push("Nested schema: columns are listed by leaf path".to_string());
+    }
+    Ok(Profile {
+        format: "parquet",
+        rows: metadata.file_metadata().num_rows().max(0) as u64,
+        columns,
+        sample,
+        notes,
+    })
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A CSV value for SQLite: integers and reals where they parse, NULL for markers.
+fn csv_sql(value: &str) -> SqlValue {
+    let value = value.trim();
+    if NULL_MARKERS.contains(&value) {
+        SqlValue::Null
+    } else if let Ok(i) = value.parse::<i64>() {
+        SqlValue::Integer(i)
+    } else if let Ok(f) = value.parse::<f64>() {
+        SqlValue::Real(f)
+    } else {
+        SqlValue::Text(value.to_string())
+    }
+}
+
+/// Column names usable in `CREATE TABLE`: empty headers become `col_<n>`
+/// and repeats (compared case-insensitively, like SQLite) get a `_<n>` suffix.
+fn column_names(headers: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut names = Vec::new();
+    for (i, header) in headers.into_iter().enumerate() {
+        let header = header.trim();
+        let base = if header.is_empty() {
+            format!("col_{}", i + 1)
+        } else {
+            header.to_string()
+        };
+        let mut name = base.clone();
+        let mut n = 1;
+        while !seen.insert(name.to_lowercase()) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Load the file into an in-memory `data` table; returns the connection and
+/// whether the row cap cut the load short.
+fn load_sqlite(path: &Path, format: DataFormat) -> anyhow::Result<(rusqlite::Connection, bool)> {
+    let mut conn = rusqlite::Connection::open_in_memory()?;
+    // SQLite counts ATTACH as read-only; without this a query could read
+    // (or create) any database file the server can reach.
+    conn.set_limit(rusqlite::limits::Limit::SQLITE_LIMIT_ATTACHED, 0);
+    let mut truncated = false;
+    let mut insert_rows = |columns: &[String],
+                           rows: &mut dyn Iterator<Item = anyhow::Result<Vec<SqlValue>>>|
+     -> anyhow::Result<()> {
+        let defs = columns
+            .iter()
+            .map(|c| quote_ident(c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        conn.execute(&format!("CREATE TABLE data ({})", defs), [])?;
+        let tx = conn.transaction()?;
+        {
+            let placeholders = vec!["?"; columns.len()].join(", ");
+            let mut stmt = tx.prepare(&format!("INSERT INTO data VALUES ({})", placeholders))?;
+            for (n, row) in rows.enumerate() {
+                if n >= MAX_SQL_ROWS {
+                    truncated = true;
+                    break;
+                }
+                let mut row = row?;
+                row.resize(columns.len(), SqlValue::Null);
+                stmt.execute(rusqlite::params_from_iter(row))?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    };
+
+    match format {
+        DataFormat::Csv(delimiter) => {
+            let mut reader = csv_reader(path, delimiter)?;
+            let columns = column_names(reader.headers()?.iter().map(str::to_string));
+            let width = columns.len();
+            let mut rows = reader
+                .into_records()
+                .map(|record| Ok(record?.iter().take(width).map(csv_sql).collect::<Vec<_>>()));
+            insert_rows(&columns, &mut rows)?;
+        }
+        DataFormat::Parquet => {
+            let reader = SerializedFileReader::new(File::open(path)?)?;
+            let columns = column_names(
+                reader
+                    .metadata()
+                    .file_metadata()
+                    .schema_descr()
+                    .root_schema()
+                    .get_fields()
+                    .iter()
+                    .map(|f| f.name().to_string()),
+            );
+            let mut rows = reader.get_row_iter(None)?.map(|row| {
+                Ok(row?
+                    .get_column_iter()
+                    .map(|(_, field)| field_sql(field))
+                    .collect::<Vec<_>>())
+            });
+            insert_rows(&columns, &mut rows)?;
+        }
+    }
+    Ok((conn, truncated))
+}
+
+struct QueryResult {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    more: bool,
+}
+
+fn run_sql(conn: &rusqlite::Connection, sql: &str, limit: usize) -> anyhow::Result<QueryResult> {
+    let mut stmt = conn.prepare(sql)?;
+    if !stmt.readonly() {
+        anyhow::bail!("Only read-only queries (SELECT, WITH ...) are allowed");
+    }
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let mut result = stmt.query([])?;
+    let mut rows = Vec::new();
+    let mut more = false;
+    while let Some(row) = result.next()? {
+        if rows.len() == limit {
+            more = true;
+            break;
+        }
+        let mut values = Vec::with_capacity(columns.len());
+        for i in 0..columns.len() {
+            values.push(match row.get::<_, SqlValue>(i)? {
+                SqlValue::Null => String::new(),
+                SqlValue::Integer(v) => v.to_string(),
+                SqlValue::Real(v) => v.to_string(),
+                SqlValue::Text(v) => v,
+                SqlValue::Blob(v) => format!("<{} bytes>", v.len()),
+            });
+        }
+        rows.push(values);
+    }
+    Ok(QueryResult {
+        columns,
+        rows,
+        more,
+    })
+}
+
+fn cell(value: &str) -> String {
+    let value = value.replace('|', "\\|").replace(['\n', '\r'], " ");
+    if value.chars().count() > MAX_CELL_CHARS {
+        let cut: String = value.chars().take(MAX_CELL_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        value
+    }
+}
+
+fn markdown_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut out = format!(
+        "| {} |\n|{}\n",
+        headers
+            .iter()
+            .map(|h| cell(h))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        " --- |".repeat(headers.len())
+    );
+    for row in rows {
+        let cells: Vec<String> = (0..headers.len())
+            .map(|i| row.get(i).map(|v| cell(v)).unwrap_or_default())
+            .collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+fn render_profile(name: &str, profile: &Profile) -> String {
+    let mut out = format!(
+        "# {} ({})\n\n{} rows, {} columns\n",
+        name,
+        profile.format,
+        profile.rows,
+        profile.columns.len()
+    );
+    for note in &profile.notes {
+        out.push_str(&format!("- {}\n", note));
+    }
+    out.push_str("\n## Columns\n\n| column | type | nulls | null % | min | max |\n| --- | --- | --- | --- | --- | --- |\n");
+    for column in &profile.columns {
+        let (nulls, ratio) = match column.nulls {
+            Some(n) if profile.rows > 0 => (
+                n.to_string(),
+                format!("{:.1}", n as f64 * 100.0 / profile.rows as f64),
+            ),
+            Some(n) => (n.to_string(), "-".to_string()),
+            None => ("?".to_string(), "?".to_string()),
+        };
+        let num = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            cell(&column.name),
+            column.data_type,
+            nulls,
+            ratio,
+            num(column.min),
+            num(column.max)
+        ));
+    }
+    if !profile.sample.is_empty() {
+        let headers: Vec<String> = if profile.format == "parquet" {
+            // Sample rows hold top-level fields, which differ from leaf paths
+            // for nested schemas.
+            let width = profile.sample[0].len();
+            if width == profile.columns.len() {
+                profile.columns.iter().map(|c| c.name.clone()).collect()
+            } else {
+                (1..=width).map(|i| format!("field {}", i)).collect()
+            }
+        } else {
+            profile.columns.iter().map(|c| c.name.clone()).collect()
+        };
+        out.push_str(&format!(
+            "\n## Sample ({} rows)\n\n{}",
+            profile.sample.len(),
+            markdown_table(&headers, &profile.sample)
+        ));
+    }
+    out
+}
+
+/// Profile or query a CSV/TSV/Parquet file.
+pub struct InspectData;
+
+#[derive(Deserialize, JsonSchema)]
+struct InspectArgs {
+    /// CSV, TSV or Parquet file
+    path: String,
+    /// csv, tsv or parquet (default: from the file extension)
+    #[serde(default)]
+    format: Option<String>,
+    /// Sample rows to show (default 5, max 50)
+    #[serde(default)]
+    sample_rows: Option<usize>,
+    /// SQLite SQL over the file, loaded as table `data`, e.g.
+    /// `SELECT status, count(*) FROM data GROUP BY 1`
+    #[serde(default)]
+    sql: Option<String>,
+    /// Rows of query output to return (default 50, max 500)
+    #[serde(default)]
+    max_rows: Option<usize>,
+    /// Return the profile as JSON
+    #[serde(default)]
+    json: bool,
+}
+
+#[async_trait]
+impl Tool for InspectData {
+    fn name(&self) -> &str {
+        "inspect_data"
+    }
+
+    fn description(&self) -> &str {
+        "Profile a CSV, TSV or Parquet file without reading it into context: row count, column types, null counts and ratios, numeric min/max and sample rows. With `sql`, runs a read-only SQLite query over the file (table name `data`) and returns the result as a table."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        InspectArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = InspectArgs::parse(args)?;
+        let path = resolve_path(&args.path, working_dir);
+        let format = DataFormat::detect(&path, args.format.as_deref())?;
+        let name = args.path.clone();
+
+        tokio::task::spawn_blocking(move || -> anyhow::Result<String> {
+            if let Some(sql) = args.sql.as_deref() {
+                let (conn, truncated) = load_sqlite(&path, format)?;
+                let limit = args
+                    .max_rows
+                    .unwrap_or(DEFAULT_RESULT_ROWS)
+                    .clamp(1, MAX_RESULT_ROWS);
+                let result = run_sql(&conn, sql, limit)?;
+                let mut out = markdown_table(&result.columns, &result.rows);
+                if result.more {
+                    out.push_str(&format!("\n(first {} rows shown)\n", limit));
+                }
+                if truncated {
+                    out.push_str(&format!(
+                        "\nNote: only the first {} rows of the file were loaded.\n",
+                        MAX_SQL_ROWS
+                    ));
+                }
+                return Ok(out);
+            }
+
+            let samples = args
+                .sample_rows
+                .unwrap_or(DEFAULT_SAMPLE_ROWS)
+                .min(MAX_SAMPLE_ROWS);
+            let profile = match format {
+                DataFormat::Csv(delimiter) => profile_csv(&path, delimiter, samples)?,
+                DataFormat::Parquet => profile_parquet(&path, samples)?,
+            };
+            if args.json {
+                Ok(serde_json::to_string_pretty(&profile)?)
+            } else {
+                Ok(render_profile(&name, &profile))
+            }
+        })
+        .await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn write(dir: &Path, name: &str, content: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn profiles_csv_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "orders.csv",
+            "id,amount,status,note\n1,9.5,paid,\n2,12,refunded,late\n3,,paid,NA\n",
+        );
+        let profile = profile_csv(&path, b',', 2).unwrap();
+        assert_eq!(profile.rows, 3);
+        let types: Vec<&str> = profile
+            .columns
+            .iter()
+            .map(|c| c.data_type.as_str())
+            .collect();
+        assert_eq!(types, ["integer", "float", "string", "string"]);
+        assert_eq!(profile.columns[1].nulls, Some(1));
+        assert_eq!(profile.columns[1].max, Some(12.0));
+        assert_eq!(profile.columns[3].nulls, Some(2));
+        assert_eq!(profile.sample.len(), 2);
+
+        let text = render_profile("orders.csv", &profile);
+        assert!(text.contains("| amount | float | 1 | 33.3 | 9.5 | 12 |"));
+    }
+
+    #[test]
+    fn sql_over_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "orders.csv",
+            "id,amount,status\n1,9.5,paid\n2,12,refunded\n3,4,paid\n",
+        );
+        let (conn, truncated) = load_sqlite(&path, DataFormat::Csv(b',')).unwrap();
+        assert!(!truncated);
+        let result = run_sql(
+            &conn,
+            "SELECT status, count(*) AS n, sum(amount) AS total FROM data GROUP BY status ORDER BY status",
+            10,
+        )
+        .unwrap();
+        assert_eq!(result.columns, ["status", "n", "total"]);
+        assert_eq!(
+            result.rows,
+            [["paid", "2", "13.5"], ["refunded", "1", "12"]]
+        );
+        assert!(run_sql(&conn, "DELETE FROM data", 10).is_err());
+    }
+
+    #[test]
+    fn sql_cannot_attach_other_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "orders.csv", "id\n1\n");
+        let other = dir.path().join("other.db");
+        rusqlite::Connection::open(&other)
+            .unwrap()
+            .execute_batch("CREATE TABLE secrets (token TEXT); INSERT INTO secrets VALUES ('x');")
+            .unwrap();
+
+        let (conn, _) = load_sqlite(&path, DataFormat::Csv(b',')).unwrap();
+        let attach = format!("ATTACH '{}' AS other", other.display());
+        assert!(run_sql(&conn, &attach, 10).is_err());
+        assert!(run_sql(&conn, "SELECT * FROM other.secrets", 10).is_err());
+
+        let missing = dir.path().join("created.db");
+        let attach = format!("ATTACH '{}' AS created", missing.display());
+        assert!(run_sql(&conn, &attach, 10).is_err());
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn sql_over_csv_with_bad_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "wide.csv", "id,,ID,name,name\n1,2,3,a,b\n");
+        let (conn, _) = load_sqlite(&path, DataFormat::Csv(b',')).unwrap();
+        let result = run_sql(&conn, "SELECT * FROM data", 10).unwrap();
+        assert_eq!(result.columns, ["id", "col_2", "ID_2", "name", "name_2"]);
+        assert_eq!(result.rows, [["1", "2", "3", "a", "b"]]);
+    }
+
+    #[test]
+    fn profiles_parquet_from_metadata() {
+        use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.parquet");
+        let schema = Arc::new(
+            parse_message_type(
+                "message schema { REQUIRED INT64 id; OPTIONAL BYTE_ARRAY name (UTF8); }",
+            )
+            .unwrap(),
+        );
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(File::create(&path).unwrap(), schema, props).unwrap();
+        let mut group = writer.next_row_group().unwrap();
+        let mut column = group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2, 3], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(
+                &[ByteArray::from("ada"), ByteArray::from("grace")],
+                Some(&[1, 0, 1]),
+                None,
+            )
+            .unwrap();
+        column.close().unwrap();
+        group.close().unwrap();
+        writer.close().unwrap();
+
+        let profile = profile_parquet(&path, 5).unwrap();
+        assert_eq!(profile.rows, 3);
+        assert_eq!(profile.columns[0].name, "id");
+        assert_eq!(profile.columns[1].nulls, Some(1));
+        assert_eq!(profile.sample[1], ["2", ""]);
+        assert_eq!(profile.sample[2], ["3", "grace"]);
+
+        let (conn, _) = load_sqlite(&path, DataFormat::Parquet).unwrap();
+        let result = run_sql(&conn, "SELECT count(name) FROM data", 5).unwrap();
+        assert_eq!(result.rows, [["2"]]);
+    }
+}
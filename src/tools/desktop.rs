@@ -36,6 +36,7 @@ fn kill_pid(pid: u32) {
     }
     // SAFETY: Sending SIGTERM to a valid PID. The pid == 0 guard above
     // prevents accidentally signalling the caller's process group.
+    #[cfg(unix)]
     unsafe {
         libc::kill(pid as i32, libc::SIGTERM);
     }
@@ -195,6 +196,9 @@ impl Tool for StartSession {
                 "Desktop tools are disabled. Set DESKTOP_ENABLED=true to enable."
             ));
         }
+        if crate::host::Platform::current() != crate::host::Platform::Linux {
+            return Err(crate::host::unsupported("Desktop tools (Xvfb + i3)"));
+        }
 
         // Get next display number
         let display_num = DISPLAY_COUNTER.fetch_add(1, Ordering::SeqCst);
@@ -390,13 +394,8 @@ impl Tool for StopSession {
                     // Kill processes by PID
                     for pid_key in ["xvfb_pid", "i3_pid", "browser_pid"] {
                         if let Some(pid) = session_info[pid_key].as_u64() {
-                            let pid = pid as i32;
-                            // SAFETY: PIDs are read from a session file we wrote;
-                            // SIGTERM is a safe signal to send to any process.
-                            unsafe {
-                                libc::kill(pid, libc::SIGTERM);
-                            }
-                            killed_pids.push(pid);
+                            kill_pid(pid as u32);
+                            killed_pids.push(pid as i32);
                         }
                     }
                 }
@@ -479,6 +478,11 @@ Set return_image=true to SEE the screenshot yourself (vision). This lets you ver
         })
     }
 
+    fn path_args(&self) -> &[&'static str] {
+        // Joined to `screenshots/`, so an absolute name lands anywhere.
+        &["filename"]
+    }
+
     async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
         let display_id = args["display"]
             .as_str()
@@ -187,7 +187,7 @@ impl Tool for SearchFiles {
 }
 
 /// Simple glob pattern matching.
-fn glob_match(pattern: &str, text: &str) -> bool {
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
     let parts: Vec<&str> = pattern.split('*').collect();
 
     if parts.len() == 1 {
@@ -0,0 +1,736 @@
+//! Documentation lookup: resolve an API symbol to its official docs and return
+//! just the relevant section instead of the whole page.
+//!
+//! Resolvers exist for Rust (doc.rust-lang.org for `std`/`core`/`alloc`,
+//! docs.rs for crates), Python (docs.python.org) and JavaScript/Web APIs (MDN).
+//! Fetched pages are cached under `{WORKING_DIR}/.sandboxed-sh/cache/docs` for
+//! a week; the cache is also used when the network is unavailable or when the
+//! lookup runs offline (`offline: true` or `SANDBOXED_SH_DOCS_OFFLINE=true`).
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::html_markdown::{self, Element};
+use super::terminal::workspace_setting;
+use super::web::{read_capped, send_get};
+use super::{safe_truncate_index, Tool};
+use crate::web_proxy;
+
+const CACHE_TTL_SECS: i64 = 7 * 24 * 3600;
+const MAX_PAGE_BYTES: usize = 16 * 1024 * 1024;
+const DEFAULT_MAX_CHARS: usize = 8000;
+
+/// Crates documented on doc.rust-lang.org rather than docs.rs.
+const RUST_STD_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+/// rustdoc anchor prefixes for members of an item, most common first.
+const RUSTDOC_MEMBER_PREFIXES: &[&str] = &[
+    "method.",
+    "tymethod.",
+    "structfield.",
+    "variant.",
+    "associatedtype.",
+    "associatedconstant.",
+];
+
+/// rustdoc item kinds as they appear in page file names (`struct.Mutex.html`).
+const RUSTDOC_ITEM_KINDS: &[&str] = &[
+    "struct",
+    "enum",
+    "fn",
+    "trait",
+    "macro",
+    "type",
+    "constant",
+    "static",
+    "union",
+    "attr",
+    "derive",
+    "primitive",
+    "traitalias",
+    "keyword",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl Language {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "python" | "py" => Some(Self::Python),
+            "javascript" | "js" | "typescript" | "ts" | "mdn" | "web" | "html" | "css" => {
+                Some(Self::JavaScript)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A fetched page as stored in the cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPage {
+    url: String,
+    final_url: String,
+    fetched_at: DateTime<Utc>,
+    body: String,
+    /// Served from the cache after the network fetch failed (or offline).
+    #[serde(skip)]
+    stale: bool,
+}
+
+impl CachedPage {
+    fn is_fresh(&self) -> bool {
+        (Utc::now() - self.fetched_at).num_seconds() < CACHE_TTL_SECS
+    }
+
+    fn base(&self) -> Option<url::Url> {
+        url::Url::parse(&self.final_url).ok()
+    }
+}
+
+/// On-disk page cache keyed by URL.
+struct DocsCache {
+    dir: PathBuf,
+}
+
+impl DocsCache {
+    fn default_dir() -> PathBuf {
+        let working_dir = std::env::var("WORKING_DIR").unwrap_or_else(|_| "/root".to_string());
+        PathBuf::from(working_dir)
+            .join(".sandboxed-sh")
+            .join("cache")
+            .join("docs")
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let digest = Sha256::digest(url.as_bytes());
+        self.dir
+            .join(format!("{}.json", hex::encode(&digest[..16])))
+    }
+
+    fn load(&self, url: &str) -> Option<CachedPage> {
+        let raw = std::fs::read_to_string(self.path(url)).ok()?;
+        serde_json::from_str::<CachedPage>(&raw)
+            .ok()
+            .filter(|page| page.url == url)
+    }
+
+    fn store(&self, page: &CachedPage) {
+        if let Err(e) = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(self.path(&page.url), serde_json::to_vec(page)?))
+        {
+            tracing::debug!("Failed to cache docs page {}: {}", page.url, e);
+        }
+    }
+}
+
+/// Fetches pages through the cache.
+struct Fetcher {
+    client: reqwest::Client,
+    cache: DocsCache,
+    offline: bool,
+}
+
+impl Fetcher {
+    /// The page at `url`, or None when it does not exist (or is not cached
+    /// while offline).
+    async fn page(&self, url: &str) -> anyhow::Result<Option<CachedPage>> {
+        let cached = self.cache.load(url);
+        if self.offline {
+            return Ok(cached.map(|page| CachedPage {
+                stale: !page.is_fresh(),
+                ..page
+            }));
+        }
+        if let Some(page) = cached.as_ref().filter(|p| p.is_fresh()) {
+            return Ok(Some(page.clone()));
+        }
+        match self.fetch(url).await {
+            Ok(Some(page)) => {
+                self.cache.store(&page);
+                Ok(Some(page))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => match cached {
+                Some(page) => {
+                    tracing::debug!("Serving cached docs for {} after error: {}", url, e);
+                    Ok(Some(CachedPage {
+                        stale: true,
+                        ..page
+                    }))
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> anyhow::Result<Option<CachedPage>> {
+        let response = send_get(&self.client, url).await?;
+        let status = response.status();
+        if let Some(reason) = response
+            .headers()
+            .get(web_proxy::BLOCKED_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            anyhow::bail!("Blocked by web proxy: {}", reason);
+        }
+        if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            anyhow::bail!("HTTP error {} fetching {}", status, url);
+        }
+        let final_url = response
+            .headers()
+            .get(web_proxy::FINAL_URL_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .unwrap_or_else(|| response.url().to_string());
+        let (body, _) = read_capped(response, MAX_PAGE_BYTES).await?;
+        Ok(Some(CachedPage {
+            url: url.to_string(),
+            final_url,
+            fetched_at: Utc::now(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+            stale: false,
+        }))
+    }
+}
+
+/// The focused documentation for a query.
+struct DocResult {
+    url: String,
+    title: Option<String>,
+    markdown: String,
+    stale: bool,
+}
+
+impl DocResult {
+    fn from_page(
+        page: &CachedPage,
+        (root, title): &(Element, Option<String>),
+        focused: Option<Element>,
+    ) -> Self {
+        let base = page.base();
+        let markdown = match focused {
+            Some(el) => html_markdown::render(&el, base.as_ref(), false),
+            None => {
+                html_markdown::render(html_markdown::readable_content(root), base.as_ref(), false)
+            }
+        };
+        Self {
+            url: page.final_url.clone(),
+            title: title.clone(),
+            markdown,
+            stale: page.stale,
+        }
+    }
+}
+
+/// Parse a page and its title, dropping navigation and other boilerplate.
+fn parse_page(page: &CachedPage) -> (Element, Option<String>) {
+    let mut root = html_markdown::parse(&page.body);
+    let title = html_markdown::title(&root);
+    html_markdown::strip_boilerplate(&mut root);
+    (root, title)
+}
+
+/// Look up documentation for `query` in `language`.
+///
+/// Resolves the docs page for the symbol and returns the section for it.
+pub struct LookupDocs;
+
+#[async_trait]
+impl Tool for LookupDocs {
+    fn name(&self) -> &str {
+        "lookup_docs"
+    }
+
+    fn description(&self) -> &str {
+        "Look up official documentation for an API symbol and return only the relevant section as Markdown. Supports Rust (std and docs.rs crates, e.g. 'tokio::sync::Mutex::lock'), Python (docs.python.org, e.g. 'os.path.join') and JavaScript/Web APIs (MDN, e.g. 'Array.prototype.map'). Pages are cached locally and served from the cache when offline."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "language": {
+                    "type": "string",
+                    "enum": ["rust", "python", "javascript"],
+                    "description": "Documentation source: rust (docs.rs / doc.rust-lang.org), python (docs.python.org) or javascript (MDN, also for Web APIs, HTML and CSS)"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "Symbol to look up, e.g. 'serde_json::Value', 'std::vec::Vec::push', 'json.dumps', 'fetch' or 'Array.prototype.map'"
+                },
+                "version": {
+                    "type": "string",
+                    "description": "Optional version: crate version for Rust (default: latest; 'stable'/'nightly' for std), Python version (default: 3)"
+                },
+                "section": {
+                    "type": "string",
+                    "description": "Optional heading to narrow the result to, e.g. 'Examples' or 'Syntax'"
+                },
+                "max_chars": {
+                    "type": "integer",
+                    "description": "Maximum characters to return (default: 8000)"
+                },
+                "offline": {
+                    "type": "boolean",
+                    "description": "Only use the local docs cache, never the network (default: false)"
+                }
+            },
+            "required": ["language", "query"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let language = args["language"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'language' argument"))?;
+        let language = Language::parse(language).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unsupported language '{}': expected rust, python or javascript",
+                language
+            )
+        })?;
+        let query = args["query"]
+            .as_str()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'query' argument"))?;
+        let version = args["version"]
+            .as_str()
+            .map(str::trim)
+            .filter(|v| !v.is_empty() && !v.contains(['/', '?', '#']));
+        let max_chars = args["max_chars"]
+            .as_u64()
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_CHARS)
+            .max(200);
+        let offline = args["offline"].as_bool().unwrap_or(false)
+            || workspace_setting("SANDBOXED_SH_DOCS_OFFLINE")
+                .is_some_and(|v| matches!(v.trim(), "1" | "true" | "yes" | "on"));
+
+        let fetcher = Fetcher {
+            client: reqwest::Client::builder()
+                .user_agent("Mozilla/5.0 (compatible; Sandboxed/1.0)")
+                .redirect(reqwest::redirect::Policy::limited(
+                    web_proxy::FETCH_MAX_REDIRECTS,
+                ))
+                .timeout(Duration::from_secs(60))
+                .build()?,
+            cache: DocsCache {
+                dir: DocsCache::default_dir(),
+            },
+            offline,
+        };
+
+        let mut result = match language {
+            Language::Rust => lookup_rust(&fetcher, query, version).await,
+            Language::Python => lookup_python(&fetcher, query, version).await,
+            Language::JavaScript => lookup_mdn(&fetcher, query).await,
+        }
+        .map_err(|e| {
+            if offline {
+                anyhow::anyhow!("{} (offline: only cached pages are available)", e)
+            } else {
+                e
+            }
+        })?;
+
+        if let Some(heading) = args["section"].as_str().filter(|s| !s.trim().is_empty()) {
+            // Narrow on the rendered Markdown so every resolver gets it for free.
+            let root = html_markdown::parse(&markdown_sections_html(&result.markdown));
+            if let Some(section) = html_markdown::section_by_heading(&root, heading) {
+                result.markdown = html_markdown::render(&section, None, false);
+            }
+        }
+
+        Ok(format_result(&result, max_chars))
+    }
+}
+
+/// Turn rendered Markdown back into headings and `<pre>` blocks so
+/// `section_by_heading` can narrow it.
+fn markdown_sections_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut in_fence = false;
+    let mut block = Vec::new();
+    let flush = |block: &mut Vec<&str>, html: &mut String| {
+        if !block.is_empty() {
+            html.push_str("<pre>");
+            html.push_str(&escape_html(&block.join("\n")));
+            html.push_str("</pre>");
+            block.clear();
+        }
+    };
+    for line in markdown.lines() {
+        if line.starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if !in_fence && (1..=6).contains(&level) && line[level..].starts_with(' ') {
+            flush(&mut block, &mut html);
+            html.push_str(&format!(
+                "<h{l}>{}</h{l}>",
+                escape_html(line[level..].trim()),
+                l = level
+            ));
+        } else {
+            block.push(line);
+        }
+    }
+    flush(&mut block, &mut html);
+    html
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;")
+}
+
+fn format_result(result: &DocResult, max_chars: usize) -> String {
+    let mut out = String::new();
+    if let Some(title) = &result.title {
+        out.push_str(&format!("# {}\n", title));
+    }
+    out.push_str(&format!("Source: {}", result.url));
+    if result.stale {
+        out.push_str(" (from local cache)");
+    }
+    out.push_str("\n\n");
+    let end = safe_truncate_index(&result.markdown, max_chars);
+    out.push_str(&result.markdown[..end]);
+    if end < result.markdown.len() {
+        out.push_str(&format!(
+            "\n\n[Truncated at {} chars; use fetch_url on the source for the full page]",
+            end
+        ));
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Rust (rustdoc)
+// ---------------------------------------------------------------------------
+
+async fn lookup_rust(
+    fetcher: &Fetcher,
+    query: &str,
+    version: Option<&str>,
+) -> anyhow::Result<DocResult> {
+    let segments: Vec<&str> = query
+        .split("::")
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+    let Some(crate_name) = segments.first().copied() else {
+        anyhow::bail!("Empty Rust path '{}'", query);
+    };
+    let crate_dir = crate_name.replace('-', "_");
+    let base = if RUST_STD_CRATES.contains(&crate_dir.as_str()) {
+        format!(
+            "https://doc.rust-lang.org/{}/{}/",
+            version.unwrap_or("stable"),
+            crate_dir
+        )
+    } else {
+        format!(
+            "https://docs.rs/{}/{}/{}/",
+            crate_name,
+            version.unwrap_or("latest"),
+            crate_dir
+        )
+    };
+    let rest = &segments[1..];
+
+    if rest.is_empty() {
+        return rustdoc_page(fetcher, &format!("{}index.html", base), None).await;
+    }
+
+    let all = fetcher
+        .page(&format!("{}all.html", base))
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("No documentation found for crate '{}'", crate_name))?;
+    let items = rustdoc_items(&html_markdown::parse(&all.body));
+    let item_base = all
+        .base()
+        .ok_or_else(|| anyhow::anyhow!("Invalid docs URL {}", all.final_url))?;
+
+    match resolve_rust_item(&items, rest) {
+        Some((href, member)) => {
+            let url = item_base.join(href)?;
+            rustdoc_page(fetcher, url.as_str(), member).await
+        }
+        None => {
+            // Modules are not listed in all.html.
+            let url = item_base.join(&format!("{}/index.html", rest.join("/")))?;
+            match rustdoc_page(fetcher, url.as_str(), None).await {
+                Ok(result) => Ok(result),
+                Err(_) => anyhow::bail!(
+                    "'{}' not found in the documentation of '{}'",
+                    rest.join("::"),
+                    crate_name
+                ),
+            }
+        }
+    }
+}
+
+async fn rustdoc_page(
+    fetcher: &Fetcher,
+    url: &str,
+    member: Option<&str>,
+) -> anyhow::Result<DocResult> {
+    let page = fetcher
+        .page(url)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Documentation page not found: {}", url))?;
+    let parsed = parse_page(&page);
+    let root = &parsed.0;
+    let focused = match member {
+        Some(member) => Some(
+            RUSTDOC_MEMBER_PREFIXES
+                .iter()
+                .find_map(|prefix| {
+                    html_markdown::section_by_id(root, &format!("{}{}", prefix, member))
+                })
+                .ok_or_else(|| anyhow::anyhow!("No member '{}' on {}", member, url))?,
+        ),
+        None => html_markdown::section_by_id(root, "main-content"),
+    };
+    Ok(DocResult::from_page(&page, &parsed, focused))
+}
+
+/// `(path, href)` pairs from a rustdoc `all.html`, e.g. `("sync::Mutex", "sync/struct.Mutex.html")`.
+fn rustdoc_items(root: &Element) -> Vec<(String, String)> {
+    html_markdown::links(root)
+        .into_iter()
+        .filter(|(href, text)| {
+            let file = href.rsplit('/').next().unwrap_or_default();
+            !text.is_empty()
+                && !text.contains(' ')
+                && file
+                    .split_once('.')
+                    .is_some_and(|(kind, _)| RUSTDOC_ITEM_KINDS.contains(&kind))
+        })
+        .map(|(href, text)| (text, href))
+        .collect()
+}
+
+/// Find the item page for a path below the crate, plus a trailing member name.
+///
+/// Exact paths win; otherwise the item name may be given without its module
+/// (crate-root re-exports such as `tokio::spawn` → `task::spawn`).
+fn resolve_rust_item<'a>(
+    items: &'a [(String, String)],
+    rest: &[&'a str],
+) -> Option<(&'a str, Option<&'a str>)> {
+    for split in (1..=rest.len()).rev() {
+        if rest.len() - split > 1 {
+            break;
+        }
+        let path = rest[..split].join("::");
+        let member = rest.get(split).copied();
+        if let Some((_, href)) = items.iter().find(|(p, _)| *p == path) {
+            return Some((href.as_str(), member));
+        }
+        let suffix = format!("::{}", path);
+        if let Some((_, href)) = items
+            .iter()
+            .filter(|(p, _)| p.ends_with(&suffix))
+            .min_by_key(|(p, _)| p.len())
+        {
+            return Some((href.as_str(), member));
+        }
+    }
+    None
+}
+
+// ---------------------------------------------------------------------------
+// Python (Sphinx)
+// ---------------------------------------------------------------------------
+
+async fn lookup_python(
+    fetcher: &Fetcher,
+    query: &str,
+    version: Option<&str>,
+) -> anyhow::Result<DocResult> {
+    let version = version.unwrap_or("3");
+    for module in python_module_candidates(query) {
+        let url = format!(
+            "https://docs.python.org/{}/library/{}.html",
+            version, module
+        );
+        let Some(page) = fetcher.page(&url).await? else {
+            continue;
+        };
+        let parsed = parse_page(&page);
+        let focused = if module == query {
+            html_markdown::section_by_id(&parsed.0, &format!("module-{}", module))
+        } else {
+            html_markdown::section_by_id(&parsed.0, query)
+        };
+        return Ok(DocResult::from_page(&page, &parsed, focused));
+    }
+    anyhow::bail!("No Python documentation found for '{}'", query)
+}
+
+/// Module pages that may document `query`, longest prefix first.
+fn python_module_candidates(query: &str) -> Vec<String> {
+    let parts: Vec<&str> = query.split('.').filter(|p| !p.is_empty()).collect();
+    (1..=parts.len())
+        .rev()
+        .map(|n| parts[..n].join("."))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// JavaScript / Web APIs (MDN)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+struct MdnSearch {
+    #[serde(default)]
+    documents: Vec<MdnDocument>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MdnDocument {
+    mdn_url: String,
+}
+
+async fn lookup_mdn(fetcher: &Fetcher, query: &str) -> anyhow::Result<DocResult> {
+    let search_url = format!(
+        "https://developer.mozilla.org/api/v1/search?q={}&locale=en-US",
+        urlencoding::encode(query)
+    );
+    let search = fetcher
+        .page(&search_url)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("MDN search failed for '{}'", query))?;
+    let results: MdnSearch = serde_json::from_str(&search.body)?;
+    let Some(document) = results.documents.first() else {
+        anyhow::bail!("No MDN documentation found for '{}'", query);
+    };
+    let url = format!("https://developer.mozilla.org{}", document.mdn_url);
+    let page = fetcher
+        .page(&url)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("MDN page not found: {}", url))?;
+    Ok(DocResult::from_page(&page, &parse_page(&page), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_rustdoc_items_and_members() {
+        let all = html_markdown::parse(
+            r#"<h3 id="structs">Structs</h3><ul class="all-items">
+            <li><a href="sync/struct.Mutex.html">sync::Mutex</a></li>
+            <li><a href="sync/mpsc/struct.Sender.html">sync::mpsc::Sender</a></li>
+            <li><a href="task/fn.spawn.html">task::spawn</a></li>
+            <li><a href="../help.html">Help</a></li></ul>"#,
+        );
+        let items = rustdoc_items(&all);
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            resolve_rust_item(&items, &["sync", "Mutex", "lock"]),
+            Some(("sync/struct.Mutex.html", Some("lock")))
+        );
+        assert_eq!(
+            resolve_rust_item(&items, &["sync", "mpsc", "Sender"]),
+            Some(("sync/mpsc/struct.Sender.html", None))
+        );
+        assert_eq!(
+            resolve_rust_item(&items, &["spawn"]),
+            Some(("task/fn.spawn.html", None))
+        );
+        assert_eq!(resolve_rust_item(&items, &["sync"]), None);
+    }
+
+    #[test]
+    fn focuses_documentation_entries() {
+        let sphinx = html_markdown::parse(
+            r#"<section id="module-os.path"><h1>os.path</h1><p>Intro</p>
+            <dl class="py function"><dt id="os.path.join">os.path.join(path, *paths)</dt>
+            <dd><p>Join one or more path segments.</p></dd></dl>
+            <dl class="py function"><dt id="os.path.split">os.path.split(path)</dt><dd>Split.</dd></dl></section>"#,
+        );
+        let entry = html_markdown::section_by_id(&sphinx, "os.path.join").unwrap();
+        let md = html_markdown::render(&entry, None, false);
+        assert!(md.contains("os.path.join(path, *paths)"));
+        assert!(md.contains("Join one or more path segments."));
+        assert!(!md.contains("Split."));
+
+        let rustdoc = html_markdown::parse(
+            r#"<details class="toggle method-toggle" open><summary><section id="method.lock" class="method">
+            <h4 class="code-header">pub async fn lock(&self) -> MutexGuard</h4></section></summary>
+            <div class="docblock"><p>Locks this mutex.</p></div></details>
+            <details><summary><section id="method.try_lock"><h4>pub fn try_lock(&self)</h4></section></summary></details>"#,
+        );
+        let member = html_markdown::section_by_id(&rustdoc, "method.lock").unwrap();
+        let md = html_markdown::render(&member, None, false);
+        assert!(md.contains("pub async fn lock(&self) -> MutexGuard"));
+        assert!(md.contains("Locks this mutex."));
+        assert!(!md.contains("try_lock"));
+    }
+
+    #[test]
+    fn narrows_rendered_markdown_by_heading() {
+        let markdown = "## Syntax\n\n```js\nmap(fn)\n```\n\n## Examples\n\nUse it.\n\n### More\n\nNested.\n\n## See also\n\nOther.";
+        let root = html_markdown::parse(&markdown_sections_html(markdown));
+        let section = html_markdown::section_by_heading(&root, "examples").unwrap();
+        let md = html_markdown::render(&section, None, false);
+        assert!(md.contains("Use it."));
+        assert!(md.contains("Nested."));
+        assert!(!md.contains("Other."));
+        assert!(!md.contains("map(fn)"));
+        assert_eq!(
+            python_module_candidates("os.path.join"),
+            vec!["os.path.join", "os.path", "os"]
+        );
+    }
+
+    #[tokio::test]
+    async fn offline_lookups_use_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let fetcher = Fetcher {
+            client: reqwest::Client::new(),
+            cache: DocsCache {
+                dir: dir.path().to_path_buf(),
+            },
+            offline: true,
+        };
+        let url = "https://docs.python.org/3/library/json.html";
+        assert!(fetcher.page(url).await.unwrap().is_none());
+
+        fetcher.cache.store(&CachedPage {
+            url: url.to_string(),
+            final_url: url.to_string(),
+            fetched_at: Utc::now() - chrono::Duration::days(30),
+            body: r#"<section id="module-json"><h1>json</h1><dl><dt id="json.dumps">json.dumps(obj)</dt><dd>Serialize obj.</dd></dl></section>"#.to_string(),
+            stale: false,
+        });
+        let result = lookup_python(&fetcher, "json.dumps", None).await.unwrap();
+        assert!(result.stale);
+        let out = format_result(&result, 1000);
+        assert!(
+            out.contains("Source: https://docs.python.org/3/library/json.html (from local cache)")
+        );
+        assert!(out.contains("Serialize obj."));
+    }
+}
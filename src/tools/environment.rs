@@ -0,0 +1,213 @@
+//! `detect_environment`: what the agent's machine (host or container) offers.
+//!
+//! Reports the OS, CPU and memory, the language toolchains on `PATH`, and the
+//! GPUs with driver and CUDA versions, so a mission can tell up front whether
+//! a training smoke test will run on the GPU or whether it needs to install a
+//! toolkit first.
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Tool, ToolArgs};
+use crate::gpu::{self, GpuReport};
+
+/// Toolchains probed with `<command> --version` (first output line).
+const TOOLCHAINS: &[&str] = &[
+    "python3", "pip3", "node", "bun", "cargo", "rustc", "go", "java", "gcc", "cmake", "docker",
+];
+
+/// Report the runtime environment.
+pub struct DetectEnvironment;
+
+#[derive(Deserialize, JsonSchema)]
+struct DetectArgs {
+    /// Return JSON instead of a text summary
+    #[serde(default)]
+    json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Environment {
+    os: Option<String>,
+    kernel: Option<String>,
+    arch: &'static str,
+    cpus: usize,
+    memory_mib: Option<u64>,
+    toolchains: Vec<(String, String)>,
+    gpu: GpuReport,
+}
+
+/// `PRETTY_NAME` from os-release content.
+fn os_name(os_release: &str) -> Option<String> {
+    os_release.lines().find_map(|line| {
+        line.strip_prefix("PRETTY_NAME=")
+            .map(|v| v.trim_matches('"').to_string())
+    })
+}
+
+/// `MemTotal` from /proc/meminfo content, in MiB.
+fn mem_total_mib(meminfo: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let kib: u64 = line
+            .strip_prefix("MemTotal:")?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .ok()?;
+        Some(kib / 1024)
+    })
+}
+
+fn first_line_of(program: &str) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .arg("--version")
+        .output()
+        .ok()?;
+    // Some tools (java, older pythons) print their version on stderr.
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    String::from_utf8_lossy(&text)
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+}
+
+fn detect() -> Environment {
+    let read = |path: &str| std::fs::read_to_string(path).ok();
+    Environment {
+        os: read("/etc/os-release").and_then(|c| os_name(&c)),
+        kernel: read("/proc/sys/kernel/osrelease").map(|k| k.trim().to_string()),
+        arch: std::env::consts::ARCH,
+        cpus: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        memory_mib: read("/proc/meminfo").and_then(|c| mem_total_mib(&c)),
+        toolchains: TOOLCHAINS
+            .iter()
+            .filter(|name| crate::host::command_on_path(name))
+            .map(|name| {
+                (
+                    name.to_string(),
+                    first_line_of(name).unwrap_or_else(|| "installed".to_string()),
+                )
+            })
+            .collect(),
+        gpu: gpu::probe(),
+    }
+}
+
+fn render(env: &Environment) -> String {
+    let mut out = String::from("# Environment\n\n");
+    out.push_str(&format!(
+        "- OS: {}\n- Kernel: {}\n- Arch: {}\n- CPUs: {}\n",
+        env.os.as_deref().unwrap_or("unknown"),
+        env.kernel.as_deref().unwrap_or("unknown"),
+        env.arch,
+        env.cpus
+    ));
+    if let Some(mib) = env.memory_mib {
+        out.push_str(&format!("- Memory: {} MiB\n", mib));
+    }
+
+    out.push_str("\n## GPU\n\n");
+    if env.gpu.devices.is_empty() {
+        out.push_str("No NVIDIA GPU visible (nvidia-smi not found or no devices).\n");
+    } else {
+        out.push_str(&format!(
+            "- Driver: {}\n- CUDA (driver): {}\n",
+            env.gpu.driver_version.as_deref().unwrap_or("unknown"),
+            env.gpu.cuda_driver_version.as_deref().unwrap_or("unknown")
+        ));
+        for device in &env.gpu.devices {
+            out.push_str(&format!(
+                "- GPU {}: {} ({})\n",
+                device.index, device.name, device.memory
+            ));
+        }
+    }
+    match env.gpu.cuda_toolkit_version.as_deref() {
+        Some(version) => out.push_str(&format!("- CUDA toolkit (nvcc): {}\n", version)),
+        None => out.push_str("- CUDA toolkit (nvcc): not installed\n"),
+    }
+
+    out.push_str("\n## Toolchains\n\n");
+    if env.toolchains.is_empty() {
+        out.push_str("None of the common toolchains were found on PATH.\n");
+    }
+    for (name, version) in &env.toolchains {
+        out.push_str(&format!("- `{}`: {}\n", name, version));
+    }
+    out
+}
+
+#[async_trait]
+impl Tool for DetectEnvironment {
+    fn name(&self) -> &str {
+        "detect_environment"
+    }
+
+    fn description(&self) -> &str {
+        "Report the environment commands run in: OS, kernel, CPU count, memory, installed toolchains with versions, and NVIDIA GPUs with driver, CUDA driver and CUDA toolkit versions. Use it before GPU work (training, CUDA builds) to check what is available."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        DetectArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let args = DetectArgs::parse(args)?;
+        let env = tokio::task::spawn_blocking(detect).await?;
+        if args.json {
+            Ok(serde_json::to_string_pretty(&env)?)
+        } else {
+            Ok(render(&env))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_os_release_and_meminfo() {
+        let os_release = "NAME=\"Ubuntu\"\nPRETTY_NAME=\"Ubuntu 24.04 LTS\"\nID=ubuntu\n";
+        assert_eq!(os_name(os_release).as_deref(), Some("Ubuntu 24.04 LTS"));
+        let meminfo = "MemTotal:       16318048 kB\nMemFree:         1000 kB\n";
+        assert_eq!(mem_total_mib(meminfo), Some(15935));
+    }
+
+    #[test]
+    fn renders_gpu_section() {
+        let env = Environment {
+            os: Some("Ubuntu 24.04 LTS".to_string()),
+            kernel: None,
+            arch: "x86_64",
+            cpus: 8,
+            memory_mib: None,
+            toolchains: vec![("python3".to_string(), "Python 3.12.3".to_string())],
+            gpu: GpuReport {
+                driver_version: Some("535.104.05".to_string()),
+                cuda_driver_version: Some("12.2".to_string()),
+                cuda_toolkit_version: None,
+                devices: vec![gpu::GpuDevice {
+                    index: 0,
+                    name: "NVIDIA L4".to_string(),
+                    memory: "23034 MiB".to_string(),
+                }],
+            },
+        };
+        let text = render(&env);
+        assert!(text.contains("- Driver: 535.104.05"));
+        assert!(text.contains("- GPU 0: NVIDIA L4 (23034 MiB)"));
+        assert!(text.contains("CUDA toolkit (nvcc): not installed"));
+        assert!(text.contains("- `python3`: Python 3.12.3"));
+    }
+}
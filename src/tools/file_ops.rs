@@ -81,48 +81,122 @@ impl Tool for ReadFile {
             }
         };
 
-        // Handle optional line range
         let start_line = args["start_line"].as_u64().map(|n| n as usize);
         let end_line = args["end_line"].as_u64().map(|n| n as usize);
+        Ok(numbered_lines(&content, start_line, end_line))
+    }
+}
 
-        if start_line.is_some() || end_line.is_some() {
-            let lines: Vec<&str> = content.lines().collect();
-            let total_lines = lines.len();
-            let start = start_line.unwrap_or(1).saturating_sub(1).min(total_lines);
-            let end = end_line.unwrap_or(total_lines).min(total_lines);
-
-            // Ensure start <= end
-            let (start, end) = if start > end {
-                (end, start)
-            } else {
-                (start, end)
-            };
-
-            if start >= total_lines {
-                return Ok(format!(
-                    "File has {} lines, requested start line {} is beyond end of file",
-                    total_lines,
-                    start + 1
-                ));
-            }
-
-            let selected: Vec<String> = lines[start..end]
-                .iter()
-                .enumerate()
-                .map(|(i, line)| format!("{:4}| {}", start + i + 1, line))
-                .collect();
+/// Number the lines of `content`, optionally limited to a 1-indexed range.
+fn numbered_lines(content: &str, start_line: Option<usize>, end_line: Option<usize>) -> String {
+    if start_line.is_some() || end_line.is_some() {
+        let lines: Vec<&str> = content.lines().collect();
+        let total_lines = lines.len();
+        let start = start_line.unwrap_or(1).saturating_sub(1).min(total_lines);
+        let end = end_line.unwrap_or(total_lines).min(total_lines);
+
+        // Ensure start <= end
+        let (start, end) = if start > end {
+            (end, start)
+        } else {
+            (start, end)
+        };
 
-            return Ok(selected.join("\n"));
+        if start >= total_lines {
+            return format!(
+                "File has {} lines, requested start line {} is beyond end of file",
+                total_lines,
+                start + 1
+            );
         }
 
-        // Return with line numbers for context
-        let numbered: Vec<String> = content
-            .lines()
+        let selected: Vec<String> = lines[start..end]
+            .iter()
             .enumerate()
-            .map(|(i, line)| format!("{:4}| {}", i + 1, line))
+            .map(|(i, line)| format!("{:4}| {}", start + i + 1, line))
             .collect();
 
-        Ok(numbered.join("\n"))
+        return selected.join("\n");
+    }
+
+    // Return with line numbers for context
+    let numbered: Vec<String> = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:4}| {}", i + 1, line))
+        .collect();
+
+    numbered.join("\n")
+}
+
+/// Read a file as it was at a mission checkpoint.
+pub struct ReadFileAt;
+
+#[async_trait]
+impl Tool for ReadFileAt {
+    fn name(&self) -> &str {
+        "read_file_at"
+    }
+
+    fn description(&self) -> &str {
+        "Read a workspace file as it was at an earlier point of the mission, without changing the workspace. Checkpoint 'base' is the workspace when the mission started; '1', '2', ... are the starts of later turns that changed files. Use it to see what a file looked like before your changes."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File path relative to the workspace (e.g., 'src/main.rs')"
+                },
+                "checkpoint": {
+                    "type": "string",
+                    "description": "Checkpoint ID: 'base' (default) or a turn checkpoint like '2'. An unknown ID lists the available ones.",
+                    "default": "base"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "Optional: start reading from this line number (1-indexed)"
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "Optional: stop reading at this line number (inclusive)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let path = args["path"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'path' argument"))?;
+        let checkpoint = args["checkpoint"]
+            .as_str()
+            .unwrap_or(crate::workspace_snapshot::BASE_CHECKPOINT);
+        let relative = Path::new(path)
+            .strip_prefix(working_dir)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string());
+
+        let Some(bytes) =
+            crate::workspace_snapshot::read_file_at(working_dir, &relative, checkpoint).await?
+        else {
+            return Ok(format!(
+                "{} did not exist at checkpoint {}",
+                relative, checkpoint
+            ));
+        };
+        let Ok(content) = String::from_utf8(bytes) else {
+            return Ok(format!(
+                "Binary file at checkpoint {}: {}",
+                checkpoint, relative
+            ));
+        };
+        let start_line = args["start_line"].as_u64().map(|n| n as usize);
+        let end_line = args["end_line"].as_u64().map(|n| n as usize);
+        Ok(numbered_lines(&content, start_line, end_line))
     }
 }
 
@@ -0,0 +1,955 @@
+//! Git tools for committing, branching, rebasing, and pushing work in the workspace.
+//!
+//! Git commands run in the workspace execution context (inside the container for
+//! container workspaces), so commits use the repository state the agent sees.
+//!
+//! ## Configuration
+//!
+//! Settings are read from the workspace env vars first, then the process env.
+//! The push policy, protected branches and token guard what the agent may do,
+//! so the server keeps them out of the mission directory and serves them
+//! instead (see `guard::setting`):
+//! - `SANDBOXED_SH_GIT_COMMIT_PATTERN`: regex commit subjects must match
+//! - `SANDBOXED_SH_GIT_CONVENTIONAL_COMMITS`: enforce conventional commits (default pattern)
+//! - `SANDBOXED_SH_GIT_SIGNING_KEY`: GPG key ID or SSH key path used when `sign` is set
+//! - `SANDBOXED_SH_GIT_SIGNING_FORMAT`: `gpg` (default) or `ssh`
+//! - `SANDBOXED_SH_COMMIT_MODEL`: model used to generate messages (default `builtin/smart`)
+//! - `SANDBOXED_SH_GIT_PROTECTED_BRANCHES`: comma-separated branch globs that are never
+//!   force-pushed or rebased (default `main,master,release/*,production`)
+//! - `SANDBOXED_SH_GIT_PUSH_POLICY`: `allow` (default), `confirm`, `confirm_protected`, or `deny`
+//! - `SANDBOXED_SH_GIT_TOKEN`: HTTPS token for pushes/fetches (sent as a basic-auth header)
+//! - `SANDBOXED_SH_GIT_SSH_KEY`: SSH private key path for pushes/fetches
+//! - `SANDBOXED_SH_GIT_PROVENANCE`: `trailers` (default), `headers`, both, or `off`; see
+//!   `crate::provenance`
+//!
+//! Commits are refused when the staged diff contains credentials (see `secret_scan`).
+//!
+//! Pushes and fetches only go to remotes configured in the repository, and the
+//! token is only sent to the URL of that remote.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::{json, Value};
+
+use super::guard;
+use super::secret_scan;
+use super::terminal::{run_workspace_shell, shell_quote, workspace_setting};
+use super::{resolve_path_simple as resolve_path, safe_truncate_index, Tool};
+
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Branch patterns protected when `SANDBOXED_SH_GIT_PROTECTED_BRANCHES` is unset.
+const DEFAULT_PROTECTED_BRANCHES: &str = "main,master,release/*,production";
+
+/// Default conventional-commit subject pattern: `type(scope)!: description`.
+pub const CONVENTIONAL_COMMIT_PATTERN: &str =
+    r"^(feat|fix|docs|style|refactor|perf|test|build|ci|chore|revert)(\([\w./-]+\))?!?: \S.{0,99}$";
+
+/// Maximum staged diff size sent to the LLM for message generation.
+const MAX_DIFF_CHARS_FOR_MESSAGE: usize = 24_000;
+
+/// Run `git <args>` in `cwd` and return stdout, failing on non-zero exit.
+pub(crate) async fn git_output(cwd: &Path, args: &[&str]) -> anyhow::Result<String> {
+    git_output_with_env(cwd, args, HashMap::new()).await
+}
+
+/// Run `git <args>` with extra environment variables.
+pub(crate) async fn git_output_with_env(
+    cwd: &Path,
+    args: &[&str],
+    env: HashMap<String, String>,
+) -> anyhow::Result<String> {
+    let command = std::iter::once("git".to_string())
+        .chain(args.iter().map(|a| shell_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let output = run_workspace_shell(cwd, &command, env, GIT_TIMEOUT).await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let detail = if stderr.trim().is_empty() {
+            stdout.trim().to_string()
+        } else {
+            stderr.trim().to_string()
+        };
+        anyhow::bail!("git {} failed: {}", args.first().unwrap_or(&""), detail);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Resolve the commit-subject pattern to enforce, if any.
+fn commit_pattern() -> anyhow::Result<Option<Regex>> {
+    let pattern = match workspace_setting("SANDBOXED_SH_GIT_COMMIT_PATTERN") {
+        Some(p) => p,
+        None if workspace_setting("SANDBOXED_SH_GIT_CONVENTIONAL_COMMITS")
+            .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+            .unwrap_or(false) =>
+        {
+            CONVENTIONAL_COMMIT_PATTERN.to_string()
+        }
+        None => return Ok(None),
+    };
+    Regex::new(&pattern)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("Invalid commit message pattern '{}': {}", pattern, e))
+}
+
+/// Validate the subject line of a commit message against a pattern.
+pub fn validate_commit_message(message: &str, pattern: &Regex) -> Result<(), String> {
+    let subject = message.lines().next().unwrap_or("").trim();
+    if subject.is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+    if !pattern.is_match(subject) {
+        return Err(format!(
+            "Commit subject '{}' does not match required pattern: {}",
+            subject,
+            pattern.as_str()
+        ));
+    }
+    Ok(())
+}
+
+/// Strip code fences and surrounding quotes that models like to add.
+fn clean_generated_message(raw: &str) -> String {
+    let trimmed = raw.trim();
+    let without_fence = trimmed
+        .strip_prefix("```")
+        .map(|rest| {
+            let rest = rest.split_once('\n').map(|(_, body)| body).unwrap_or("");
+            rest.strip_suffix("```").unwrap_or(rest)
+        })
+        .unwrap_or(trimmed);
+    without_fence
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+        .trim()
+        .to_string()
+}
+
+/// Ask the LLM (via the internal OpenAI-compatible proxy) for a commit message.
+async fn generate_commit_message(diff: &str, pattern: Option<&Regex>) -> anyhow::Result<String> {
+    let end = safe_truncate_index(diff, MAX_DIFF_CHARS_FOR_MESSAGE);
+    let mut instructions = String::from(
+        "Write a git commit message for the staged diff below. Use an imperative subject \
+         line of at most 72 characters, then a blank line and a short body only if the change \
+         needs explanation. Reply with the commit message only.",
+    );
+    if let Some(pattern) = pattern {
+        instructions.push_str(&format!(
+            "\nThe subject line MUST match this regex: {}",
+            pattern.as_str()
+        ));
+    }
+    generate_text(&instructions, &diff[..end])
+        .await
+        .map_err(|e| anyhow::anyhow!("Commit message generation failed: {}", e))
+}
+
+/// Ask the LLM for text following `instructions` about `input`, with the
+/// workspace commit model. Code fences and quotes around the reply are removed.
+pub(crate) async fn generate_text(instructions: &str, input: &str) -> anyhow::Result<String> {
+    let api_base = std::env::var("SANDBOXED_SH_API_URL").unwrap_or_else(|_| {
+        let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
+        format!("http://127.0.0.1:{}", port)
+    });
+    let model =
+        workspace_setting("SANDBOXED_SH_COMMIT_MODEL").unwrap_or_else(|| "builtin/smart".into());
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()?;
+    let mut request = client
+        .post(format!(
+            "{}/v1/chat/completions",
+            api_base.trim_end_matches('/')
+        ))
+        .json(&json!({
+            "model": model,
+            "stream": false,
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": input }
+            ]
+        }));
+    if let Ok(secret) = std::env::var("SANDBOXED_PROXY_SECRET") {
+        request = request.bearer_auth(secret);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        anyhow::bail!("{}: {}", status, body);
+    }
+    let content = body["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("no content returned"))?;
+    let text = clean_generated_message(content);
+    if text.is_empty() {
+        anyhow::bail!("empty reply");
+    }
+    Ok(text)
+}
+
+/// Build git `-c`/flag arguments for commit signing from workspace configuration.
+fn signing_args() -> anyhow::Result<Vec<String>> {
+    let key = workspace_setting("SANDBOXED_SH_GIT_SIGNING_KEY");
+    let format = workspace_setting("SANDBOXED_SH_GIT_SIGNING_FORMAT")
+        .unwrap_or_else(|| "gpg".to_string())
+        .to_lowercase();
+    match (format.as_str(), key) {
+        ("ssh", Some(key)) => Ok(vec![
+            "-c".to_string(),
+            "gpg.format=ssh".to_string(),
+            "-c".to_string(),
+            format!("user.signingkey={}", key),
+            "commit".to_string(),
+            "--gpg-sign".to_string(),
+        ]),
+        ("ssh", None) => anyhow::bail!(
+            "SSH signing requested but SANDBOXED_SH_GIT_SIGNING_KEY is not configured for this workspace"
+        ),
+        ("gpg", Some(key)) => Ok(vec!["commit".to_string(), format!("--gpg-sign={}", key)]),
+        // Fall back to the repository/user default GPG key.
+        ("gpg", None) => Ok(vec!["commit".to_string(), "--gpg-sign".to_string()]),
+        (other, _) => anyhow::bail!("Unsupported signing format '{}' (use gpg or ssh)", other),
+    }
+}
+
+/// Match a branch name against a simple glob (`*` matches any run of characters).
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    let mut rest = name;
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(idx) => rest = &rest[idx + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+/// Whether a branch matches one of the comma-separated protected patterns.
+pub fn is_protected_branch(branch: &str, patterns: &str) -> bool {
+    patterns
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .any(|p| glob_match(p, branch))
+}
+
+pub(crate) async fn protected_patterns() -> anyhow::Result<String> {
+    Ok(guard::setting("SANDBOXED_SH_GIT_PROTECTED_BRANCHES")
+        .await?
+        .unwrap_or_else(|| DEFAULT_PROTECTED_BRANCHES.to_string()))
+}
+
+/// Policy applied before any push.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushPolicy {
+    /// Push without confirmation.
+    Allow,
+    /// Every push needs an admin's approval.
+    Confirm,
+    /// Pushes to protected branches need an admin's approval.
+    ConfirmProtected,
+    /// Pushing is disabled for this workspace.
+    Deny,
+}
+
+impl PushPolicy {
+    fn from_setting(raw: Option<&str>) -> Self {
+        match raw.map(|s| s.to_lowercase()).as_deref() {
+            Some("confirm") | Some("always_confirm") => Self::Confirm,
+            Some("confirm_protected") => Self::ConfirmProtected,
+            Some("deny") | Some("disabled") => Self::Deny,
+            _ => Self::Allow,
+        }
+    }
+}
+
+/// Why `branch` cannot be pushed as a plain branch name. Refspecs
+/// (`HEAD:main`, `+feat:main`) and options would let the push land on a
+/// branch other than the one checked against the protection rules.
+fn push_branch_refusal(branch: &str) -> Option<String> {
+    if branch.contains(':') || branch.starts_with('+') || branch.starts_with('-') {
+        return Some(format!(
+            "'{}' is not a branch name; git_push takes a branch, not a refspec",
+            branch
+        ));
+    }
+    None
+}
+
+/// Check a push against the protection rules and push policy.
+///
+/// Returns whether the push needs an admin's approval, or an error message
+/// describing why it is refused.
+fn check_push_allowed(
+    branch: &str,
+    force: bool,
+    patterns: &str,
+    policy: PushPolicy,
+) -> Result<bool, String> {
+    if let Some(reason) = push_branch_refusal(branch) {
+        return Err(reason);
+    }
+    let protected = is_protected_branch(branch, patterns);
+    if force && protected {
+        return Err(format!(
+            "Refusing to force-push to protected branch '{}' (protected patterns: {})",
+            branch, patterns
+        ));
+    }
+    match policy {
+        PushPolicy::Allow => Ok(false),
+        PushPolicy::Confirm => Ok(true),
+        PushPolicy::ConfirmProtected => Ok(protected),
+        PushPolicy::Deny => Err("Pushing is disabled by the workspace push policy".to_string()),
+    }
+}
+
+/// Why `remote` cannot be used as a remote name. Options (`--all`,
+/// `--mirror`) would push more than the checked branch, and URLs would send
+/// the workspace credentials to a host nobody configured.
+fn remote_refusal(remote: &str) -> Option<String> {
+    if remote.is_empty() || remote.starts_with('-') || remote.contains("://") {
+        return Some(format!(
+            "'{}' is not a remote name; pass the name of a remote configured in the repository",
+            remote
+        ));
+    }
+    None
+}
+
+/// URL git uses for the configured `remote` (its push URL when `push`).
+async fn remote_url(repo: &Path, remote: &str, push: bool) -> anyhow::Result<String> {
+    if let Some(reason) = remote_refusal(remote) {
+        anyhow::bail!(reason);
+    }
+    let mut args = vec!["remote", "get-url"];
+    if push {
+        args.push("--push");
+    }
+    args.push(remote);
+    let url = git_output(repo, &args)
+        .await
+        .map_err(|_| anyhow::anyhow!("No remote named '{}' is configured", remote))?;
+    Ok(url.trim().to_string())
+}
+
+/// Git config args and env that authenticate against `url` using workspace
+/// credentials. The token is scoped to `url` and only sent over HTTPS.
+async fn remote_auth(url: &str) -> anyhow::Result<(Vec<String>, HashMap<String, String>)> {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    let mut config = Vec::new();
+    let mut env = HashMap::new();
+    if let Some(token) = guard::setting("SANDBOXED_SH_GIT_TOKEN").await? {
+        if url.starts_with("https://") {
+            let basic = BASE64.encode(format!("x-access-token:{}", token));
+            config.push("-c".to_string());
+            config.push(format!(
+                "http.{}.extraHeader=Authorization: Basic {}",
+                url, basic
+            ));
+        }
+    }
+    if let Some(key) = workspace_setting("SANDBOXED_SH_GIT_SSH_KEY") {
+        env.insert(
+            "GIT_SSH_COMMAND".to_string(),
+            format!(
+                "ssh -i {} -o IdentitiesOnly=yes -o StrictHostKeyChecking=accept-new",
+                shell_quote(&key)
+            ),
+        );
+    }
+    // Never block waiting for an interactive credential prompt.
+    env.insert("GIT_TERMINAL_PROMPT".to_string(), "0".to_string());
+    Ok((config, env))
+}
+
+/// Run an authenticated git command against the remote at `url` (push/fetch).
+async fn git_remote_output(cwd: &Path, url: &str, args: &[&str]) -> anyhow::Result<String> {
+    let (mut full_args, env) = remote_auth(url).await?;
+    full_args.extend(args.iter().map(|a| a.to_string()));
+    let full_args: Vec<&str> = full_args.iter().map(String::as_str).collect();
+    git_output_with_env(cwd, &full_args, env).await
+}
+
+/// Name of the currently checked-out branch.
+async fn current_branch(repo: &Path) -> anyhow::Result<String> {
+    let branch = git_output(repo, &["rev-parse", "--abbrev-ref", "HEAD"]).await?;
+    let branch = branch.trim().to_string();
+    if branch == "HEAD" {
+        anyhow::bail!("HEAD is detached; check out a branch first");
+    }
+    Ok(branch)
+}
+
+fn repo_dir(args: &Value, working_dir: &Path) -> std::path::PathBuf {
+    args["path"]
+        .as_str()
+        .map(|p| resolve_path(p, working_dir))
+        .unwrap_or_else(|| working_dir.to_path_buf())
+}
+
+/// Commit staged changes, optionally generating and validating the message.
+pub struct GitCommit;
+
+#[async_trait]
+impl Tool for GitCommit {
+    fn name(&self) -> &str {
+        "git_commit"
+    }
+
+    fn description(&self) -> &str {
+        "Commit changes in a git repository. Can stage files first, auto-generate the commit message from the staged diff, and sign the commit with the workspace-configured key. Enforces the workspace commit-message pattern (e.g. conventional commits) when configured."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory (default: workspace)"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "Commit message. Omit and set generate_message to have one written from the staged diff."
+                },
+                "generate_message": {
+                    "type": "boolean",
+                    "description": "Generate the commit message from the staged diff when no message is given (default: false)"
+                },
+                "files": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Files to stage before committing"
+                },
+                "all": {
+                    "type": "boolean",
+                    "description": "Stage all changes (including untracked files) before committing"
+                },
+                "sign": {
+                    "type": "boolean",
+                    "description": "Sign the commit using the workspace signing key (GPG or SSH)"
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let repo = repo_dir(&args, working_dir);
+
+        if args["all"].as_bool().unwrap_or(false) {
+            git_output(&repo, &["add", "--all"]).await?;
+        }
+        if let Some(files) = args["files"].as_array() {
+            let files: Vec<&str> = files.iter().filter_map(|f| f.as_str()).collect();
+            if !files.is_empty() {
+                let mut add_args = vec!["add", "--"];
+                add_args.extend(files);
+                git_output(&repo, &add_args).await?;
+            }
+        }
+
+        let staged = git_output(&repo, &["diff", "--cached", "--stat"]).await?;
+        if staged.trim().is_empty() {
+            return Ok("Nothing to commit: no staged changes.".to_string());
+        }
+
+        if secret_scan::scanning_enabled() {
+            let findings = secret_scan::scan_staged(&repo).await?;
+            if !findings.is_empty() {
+                anyhow::bail!("{}", secret_scan::format_findings(&findings, "this commit"));
+            }
+        }
+
+        let pattern = commit_pattern()?;
+        let message = match args["message"].as_str().map(str::trim) {
+            Some(m) if !m.is_empty() => m.to_string(),
+            _ if args["generate_message"].as_bool().unwrap_or(false) => {
+                let diff = git_output(&repo, &["diff", "--cached"]).await?;
+                generate_commit_message(&diff, pattern.as_ref()).await?
+            }
+            _ => anyhow::bail!("Missing 'message' (or set generate_message: true)"),
+        };
+
+        if let Some(pattern) = pattern.as_ref() {
+            validate_commit_message(&message, pattern).map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        let provenance = crate::provenance::TurnContext::load(working_dir);
+        let mode = crate::provenance::Mode::from_workspace();
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let mut full_message = message.clone();
+        if let Some(context) = provenance.as_ref() {
+            if mode.headers {
+                annotate_added_files(&repo, context, &timestamp).await?;
+            }
+            if mode.trailers {
+                full_message =
+                    crate::provenance::append_trailers(&message, &context.trailers(&timestamp));
+            }
+        }
+
+        let mut commit_args: Vec<String> = if args["sign"].as_bool().unwrap_or(false) {
+            signing_args()?
+        } else {
+            vec!["commit".to_string()]
+        };
+        commit_args.push("-m".to_string());
+        commit_args.push(full_message);
+        let commit_args: Vec<&str> = commit_args.iter().map(String::as_str).collect();
+        git_output(&repo, &commit_args).await?;
+
+        if let Some(context) = provenance {
+            let commit = git_output(&repo, &["rev-parse", "HEAD"]).await?;
+            let record = crate::provenance::CommitRecord {
+                commit: commit.trim().to_string(),
+                repo: repo.display().to_string(),
+                subject: message.lines().next().unwrap_or("").to_string(),
+                mission_id: context.mission_id,
+                backend: context.backend,
+                model: context.model,
+                timestamp,
+            };
+            if let Err(e) = crate::provenance::record_commit(working_dir, record) {
+                tracing::warn!(error = %e, "Failed to record commit provenance");
+            }
+        }
+
+        let hash = git_output(&repo, &["rev-parse", "--short", "HEAD"]).await?;
+        Ok(format!(
+            "Committed {}\n\n{}\n\n{}",
+            hash.trim(),
+            message,
+            staged.trim_end()
+        ))
+    }
+}
+
+/// Add provenance header comments to the files the staged changes add.
+async fn annotate_added_files(
+    repo: &Path,
+    context: &crate::provenance::TurnContext,
+    timestamp: &str,
+) -> anyhow::Result<()> {
+    let added = git_output(
+        repo,
+        &["diff", "--cached", "--name-only", "--diff-filter=A"],
+    )
+    .await?;
+    let mut annotated = Vec::new();
+    for file in added.lines().map(str::trim).filter(|f| !f.is_empty()) {
+        let path = repo.join(file);
+        let Some(header) = context.file_header(&path, timestamp) else {
+            continue;
+        };
+        let Ok(content) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+        if let Some(updated) = crate::provenance::insert_header(&content, &header) {
+            tokio::fs::write(&path, updated).await?;
+            annotated.push(file);
+        }
+    }
+    if !annotated.is_empty() {
+        let mut add_args = vec!["add", "--"];
+        add_args.extend(annotated);
+        git_output(repo, &add_args).await?;
+    }
+    Ok(())
+}
+
+/// Push a branch with protected-branch and push-policy checks.
+pub struct GitPush;
+
+#[async_trait]
+impl Tool for GitPush {
+    fn name(&self) -> &str {
+        "git_push"
+    }
+
+    fn description(&self) -> &str {
+        "Push a branch to a remote using the workspace git credentials. Force pushes use --force-with-lease and are always refused for protected branches; the workspace push policy may hold the push until an administrator approves it."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory (default: workspace)"
+                },
+                "remote": {
+                    "type": "string",
+                    "description": "Remote name (default: origin)"
+                },
+                "branch": {
+                    "type": "string",
+                    "description": "Branch to push (default: current branch)"
+                },
+                "force": {
+                    "type": "boolean",
+                    "description": "Force push with lease (never allowed for protected branches)"
+                },
+                "set_upstream": {
+                    "type": "boolean",
+                    "description": "Set the pushed branch as upstream (default: true)"
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let repo = repo_dir(&args, working_dir);
+        let remote = args["remote"].as_str().unwrap_or("origin");
+        let branch = match args["branch"].as_str() {
+            Some(b) if !b.trim().is_empty() => b.trim().to_string(),
+            _ => current_branch(&repo).await?,
+        };
+        let force = args["force"].as_bool().unwrap_or(false);
+
+        let policy = PushPolicy::from_setting(
+            guard::setting("SANDBOXED_SH_GIT_PUSH_POLICY")
+                .await?
+                .as_deref(),
+        );
+        let needs_approval =
+            check_push_allowed(&branch, force, &protected_patterns().await?, policy)
+                .map_err(|e| anyhow::anyhow!(e))?;
+        let url = remote_url(&repo, remote, true).await?;
+        git_output(&repo, &["check-ref-format", "--branch", &branch])
+            .await
+            .map_err(|_| anyhow::anyhow!("Invalid branch name: {}", branch))?;
+        if needs_approval {
+            super::guard::request_approval(
+                "git_push",
+                &json!({
+                    "path": repo.display().to_string(),
+                    "remote": remote,
+                    "branch": branch,
+                    "force": force,
+                }),
+                &format!("push to '{}' under the workspace push policy", branch),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        let mut push_args = vec!["push"];
+        if force {
+            push_args.push("--force-with-lease");
+        }
+        if args["set_upstream"].as_bool().unwrap_or(true) {
+            push_args.push("--set-upstream");
+        }
+        push_args.push(remote);
+        push_args.push(&branch);
+
+        let output = git_remote_output(&repo, &url, &push_args).await?;
+        Ok(format!(
+            "Pushed '{}' to {}{}{}",
+            branch,
+            remote,
+            if force { " (force-with-lease)" } else { "" },
+            if output.trim().is_empty() {
+                String::new()
+            } else {
+                format!("\n\n{}", output.trim_end())
+            }
+        ))
+    }
+}
+
+/// Create (and optionally check out) a new branch.
+pub struct GitCreateBranch;
+
+#[async_trait]
+impl Tool for GitCreateBranch {
+    fn name(&self) -> &str {
+        "git_create_branch"
+    }
+
+    fn description(&self) -> &str {
+        "Create a new git branch from the current HEAD or a given start point, and check it out by default."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory (default: workspace)"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "New branch name"
+                },
+                "start_point": {
+                    "type": "string",
+                    "description": "Commit, tag, or branch to start from (default: HEAD)"
+                },
+                "checkout": {
+                    "type": "boolean",
+                    "description": "Check out the new branch (default: true)"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let repo = repo_dir(&args, working_dir);
+        let name = args["name"]
+            .as_str()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'name' argument"))?;
+        git_output(&repo, &["check-ref-format", "--branch", name])
+            .await
+            .map_err(|_| anyhow::anyhow!("Invalid branch name: {}", name))?;
+
+        let start_point = args["start_point"].as_str().unwrap_or("HEAD");
+        if args["checkout"].as_bool().unwrap_or(true) {
+            git_output(&repo, &["checkout", "-b", name, start_point]).await?;
+            Ok(format!(
+                "Created and checked out '{}' from {}",
+                name, start_point
+            ))
+        } else {
+            git_output(&repo, &["branch", name, start_point]).await?;
+            Ok(format!("Created '{}' from {}", name, start_point))
+        }
+    }
+}
+
+/// Rebase the current branch, refusing to rewrite protected branches.
+pub struct GitRebase;
+
+#[async_trait]
+impl Tool for GitRebase {
+    fn name(&self) -> &str {
+        "git_rebase"
+    }
+
+    fn description(&self) -> &str {
+        "Rebase the current branch onto another ref (optionally fetching first), or continue/abort an in-progress rebase. Protected branches are never rebased. On conflicts, lists the conflicted files."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory (default: workspace)"
+                },
+                "onto": {
+                    "type": "string",
+                    "description": "Ref to rebase onto (e.g. origin/main)"
+                },
+                "fetch": {
+                    "type": "boolean",
+                    "description": "Fetch the remote of 'onto' before rebasing (default: false)"
+                },
+                "action": {
+                    "type": "string",
+                    "enum": ["start", "continue", "abort"],
+                    "description": "start a rebase (default), or continue/abort one in progress"
+                }
+            },
+            "required": []
+        })
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let repo = repo_dir(&args, working_dir);
+        match args["action"].as_str().unwrap_or("start") {
+            "abort" => {
+                git_output(&repo, &["rebase", "--abort"]).await?;
+                return Ok("Rebase aborted.".to_string());
+            }
+            "continue" => {
+                // Avoid opening an editor for the commit message.
+                let mut env = HashMap::new();
+                env.insert("GIT_EDITOR".to_string(), "true".to_string());
+                return match git_output_with_env(&repo, &["rebase", "--continue"], env).await {
+                    Ok(_) => Ok("Rebase continued successfully.".to_string()),
+                    Err(e) => Ok(rebase_conflict_report(&repo, &e.to_string()).await),
+                };
+            }
+            "start" => {}
+            other => anyhow::bail!("Unknown action '{}'", other),
+        }
+
+        let onto = args["onto"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'onto' argument"))?;
+        let branch = current_branch(&repo).await?;
+        let patterns = protected_patterns().await?;
+        if is_protected_branch(&branch, &patterns) {
+            anyhow::bail!(
+                "Refusing to rebase protected branch '{}' (protected patterns: {})",
+                branch,
+                patterns
+            );
+        }
+
+        if args["fetch"].as_bool().unwrap_or(false) {
+            let remote = onto.split_once('/').map(|(r, _)| r).unwrap_or("origin");
+            let url = remote_url(&repo, remote, false).await?;
+            git_remote_output(&repo, &url, &["fetch", remote]).await?;
+        }
+
+        match git_output(&repo, &["rebase", onto]).await {
+            Ok(_) => Ok(format!("Rebased '{}' onto {}", branch, onto)),
+            Err(e) => Ok(rebase_conflict_report(&repo, &e.to_string()).await),
+        }
+    }
+}
+
+/// Describe a stopped rebase, listing conflicted files.
+async fn rebase_conflict_report(repo: &Path, error: &str) -> String {
+    let conflicts = git_output(repo, &["diff", "--name-only", "--diff-filter=U"])
+        .await
+        .unwrap_or_default();
+    if conflicts.trim().is_empty() {
+        return format!("Rebase failed: {}", error);
+    }
+    format!(
+        "Rebase stopped with conflicts in:\n{}\n\nResolve them, stage the files, then call git_rebase with action \"continue\" (or \"abort\").",
+        conflicts.trim_end()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conventional_commit_pattern() {
+        let re = Regex::new(CONVENTIONAL_COMMIT_PATTERN).unwrap();
+        assert!(validate_commit_message("feat(api): add mission diff", &re).is_ok());
+        assert!(validate_commit_message("fix!: drop legacy flag\n\nbody", &re).is_ok());
+        assert!(validate_commit_message("Add mission diff", &re).is_err());
+        assert!(validate_commit_message("feat: ", &re).is_err());
+        assert!(validate_commit_message("", &re).is_err());
+    }
+
+    #[test]
+    fn test_clean_generated_message() {
+        assert_eq!(
+            clean_generated_message("```\nfix: handle empty diff\n```"),
+            "fix: handle empty diff"
+        );
+        assert_eq!(
+            clean_generated_message("\"docs: update readme\""),
+            "docs: update readme"
+        );
+    }
+
+    #[test]
+    fn test_protected_branch_globs() {
+        let patterns = "main, release/*, hotfix-*-prod";
+        assert!(is_protected_branch("main", patterns));
+        assert!(is_protected_branch("release/1.2", patterns));
+        assert!(is_protected_branch("hotfix-42-prod", patterns));
+        assert!(!is_protected_branch("mainline", patterns));
+        assert!(!is_protected_branch("feature/release", patterns));
+    }
+
+    #[test]
+    fn test_push_protection_rules() {
+        let patterns = DEFAULT_PROTECTED_BRANCHES;
+        assert!(check_push_allowed("main", true, patterns, PushPolicy::Allow).is_err());
+        assert_eq!(
+            check_push_allowed("feature", true, patterns, PushPolicy::Allow),
+            Ok(false)
+        );
+        assert_eq!(
+            check_push_allowed("main", false, patterns, PushPolicy::Allow),
+            Ok(false)
+        );
+        assert_eq!(
+            check_push_allowed("main", false, patterns, PushPolicy::ConfirmProtected),
+            Ok(true)
+        );
+        assert_eq!(
+            check_push_allowed("feature", false, patterns, PushPolicy::ConfirmProtected),
+            Ok(false)
+        );
+        assert_eq!(
+            check_push_allowed("feature", false, patterns, PushPolicy::Confirm),
+            Ok(true)
+        );
+        assert!(check_push_allowed("feature", false, patterns, PushPolicy::Deny).is_err());
+    }
+
+    #[test]
+    fn test_push_refuses_refspecs() {
+        let patterns = DEFAULT_PROTECTED_BRANCHES;
+        // Both would update main while only "HEAD" or "+feat" was checked
+        assert!(check_push_allowed("HEAD:main", true, patterns, PushPolicy::Allow).is_err());
+        assert!(check_push_allowed("+feat:main", false, patterns, PushPolicy::Allow).is_err());
+        assert!(check_push_allowed("+feat", false, patterns, PushPolicy::Allow).is_err());
+        assert!(push_branch_refusal("feature/x").is_none());
+    }
+
+    #[test]
+    fn test_push_refuses_remote_options_and_urls() {
+        for remote in [
+            "--all",
+            "--mirror",
+            "-f",
+            "https://example.com/repo.git",
+            "",
+        ] {
+            assert!(remote_refusal(remote).is_some(), "{} was accepted", remote);
+        }
+        assert!(remote_refusal("origin").is_none());
+        assert!(remote_refusal("upstream").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_push_needs_a_configured_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        git_output(dir.path(), &["init", "--quiet"]).await.unwrap();
+        git_output(
+            dir.path(),
+            &["remote", "add", "origin", "https://example.com/repo.git"],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            remote_url(dir.path(), "origin", true).await.unwrap(),
+            "https://example.com/repo.git"
+        );
+        assert!(remote_url(dir.path(), "upstream", true).await.is_err());
+        assert!(remote_url(dir.path(), "--mirror", true).await.is_err());
+    }
+}
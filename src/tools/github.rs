@@ -0,0 +1,518 @@
+//! GitHub pull request review tools (via the `gh` CLI).
+//!
+//! These let a review agent read a PR, leave inline comments on specific lines,
+//! reply to existing review threads, and submit an approve/request-changes review
+//! instead of posting a single blob comment.
+//!
+//! Commands run in the workspace execution context. Authentication uses
+//! `GH_TOKEN` (or `SANDBOXED_SH_GITHUB_TOKEN`) from the workspace env vars,
+//! falling back to whatever `gh auth` state exists in the workspace.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::terminal::{run_workspace_shell, shell_quote, workspace_setting};
+use super::{resolve_path_simple as resolve_path, safe_truncate_index, Tool};
+
+const GH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Maximum diff size returned to the agent.
+const MAX_DIFF_CHARS: usize = 60_000;
+
+/// Run `gh <args>` and return stdout, failing on non-zero exit.
+pub(crate) async fn gh_output(cwd: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let mut env = HashMap::new();
+    if let Some(token) =
+        workspace_setting("GH_TOKEN").or_else(|| workspace_setting("SANDBOXED_SH_GITHUB_TOKEN"))
+    {
+        env.insert("GH_TOKEN".to_string(), token);
+    }
+    env.insert("GH_PROMPT_DISABLED".to_string(), "1".to_string());
+    env.insert("NO_COLOR".to_string(), "1".to_string());
+
+    let command = std::iter::once("gh".to_string())
+        .chain(args.iter().map(|a| shell_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let output = run_workspace_shell(cwd, &command, env, GH_TIMEOUT).await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "gh {} failed: {}",
+            args.iter().take(2).cloned().collect::<Vec<_>>().join(" "),
+            stderr.trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Common arguments shared by all PR tools.
+struct PrTarget {
+    cwd: std::path::PathBuf,
+    number: u64,
+    /// Explicit `owner/repo`, or None to use the repository in `cwd`.
+    repo: Option<String>,
+}
+
+impl PrTarget {
+    fn from_args(args: &Value, working_dir: &Path) -> anyhow::Result<Self> {
+        let number = args["number"]
+            .as_u64()
+            .or_else(|| args["number"].as_str().and_then(|s| s.parse().ok()))
+            .ok_or_else(|| anyhow::anyhow!("Missing 'number' argument"))?;
+        let repo = args["repo"]
+            .as_str()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(str::to_string);
+        if let Some(repo) = repo.as_deref() {
+            if repo.split('/').count() != 2 || repo.contains("..") {
+                anyhow::bail!("Invalid repo '{}': expected owner/name", repo);
+            }
+        }
+        let cwd = args["path"]
+            .as_str()
+            .map(|p| resolve_path(p, working_dir))
+            .unwrap_or_else(|| working_dir.to_path_buf());
+        Ok(Self { cwd, number, repo })
+    }
+
+    /// `--repo owner/name` flags for `gh pr` subcommands.
+    fn repo_flag(&self) -> Vec<&str> {
+        match self.repo.as_deref() {
+            Some(repo) => vec!["--repo", repo],
+            None => Vec::new(),
+        }
+    }
+
+    /// REST path prefix for `gh api` (gh expands `{owner}/{repo}` from the cwd).
+    fn api_prefix(&self) -> String {
+        format!(
+            "repos/{}/pulls/{}",
+            self.repo.as_deref().unwrap_or("{owner}/{repo}"),
+            self.number
+        )
+    }
+
+    async fn pr(&self, args: &[&str]) -> anyhow::Result<String> {
+        let number = self.number.to_string();
+        let mut full = vec!["pr"];
+        full.extend_from_slice(&args[..1]);
+        full.push(&number);
+        full.extend_from_slice(&args[1..]);
+        full.extend(self.repo_flag());
+        gh_output(&self.cwd, &full).await
+    }
+}
+
+fn common_properties() -> serde_json::Map<String, Value> {
+    let props = json!({
+        "number": {
+            "type": "integer",
+            "description": "Pull request number"
+        },
+        "repo": {
+            "type": "string",
+            "description": "Repository as owner/name (default: repository in path)"
+        },
+        "path": {
+            "type": "string",
+            "description": "Local checkout to run gh from (default: workspace)"
+        }
+    });
+    props.as_object().cloned().unwrap_or_default()
+}
+
+fn schema_with(extra: Value, required: &[&str]) -> Value {
+    let mut props = common_properties();
+    if let Some(extra) = extra.as_object() {
+        props.extend(extra.clone());
+    }
+    let mut required: Vec<&str> = required.to_vec();
+    required.insert(0, "number");
+    json!({
+        "type": "object",
+        "properties": props,
+        "required": required
+    })
+}
+
+/// Read a PR's metadata and unified diff.
+pub struct GhPrDiff;
+
+#[async_trait]
+impl Tool for GhPrDiff {
+    fn name(&self) -> &str {
+        "gh_pr_diff"
+    }
+
+    fn description(&self) -> &str {
+        "Read a GitHub pull request: title, description, branches, changed files, and the unified diff. Use before leaving inline review comments."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        schema_with(
+            json!({
+                "name_only": {
+                    "type": "boolean",
+                    "description": "Only list changed files, without the diff"
+                }
+            }),
+            &[],
+        )
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let target = PrTarget::from_args(&args, working_dir)?;
+        let meta = target
+            .pr(&[
+                "view",
+                "--json",
+                "title,body,author,baseRefName,headRefName,headRefOid,files",
+            ])
+            .await?;
+        let meta: Value = serde_json::from_str(&meta).unwrap_or(Value::Null);
+
+        let mut out = format!(
+            "# PR #{}: {}\nAuthor: {}\nBranches: {} <- {} ({})\n\n{}\n\n## Files\n",
+            target.number,
+            meta["title"].as_str().unwrap_or(""),
+            meta["author"]["login"].as_str().unwrap_or("unknown"),
+            meta["baseRefName"].as_str().unwrap_or("?"),
+            meta["headRefName"].as_str().unwrap_or("?"),
+            meta["headRefOid"].as_str().unwrap_or("?"),
+            meta["body"].as_str().unwrap_or("").trim()
+        );
+        for file in meta["files"].as_array().into_iter().flatten() {
+            out.push_str(&format!(
+                "- {} (+{} -{})\n",
+                file["path"].as_str().unwrap_or("?"),
+                file["additions"].as_u64().unwrap_or(0),
+                file["deletions"].as_u64().unwrap_or(0)
+            ));
+        }
+
+        if !args["name_only"].as_bool().unwrap_or(false) {
+            let diff = target.pr(&["diff"]).await?;
+            let end = safe_truncate_index(&diff, MAX_DIFF_CHARS);
+            out.push_str("\n## Diff\n```diff\n");
+            out.push_str(&diff[..end]);
+            if end < diff.len() {
+                out.push_str("\n... [diff truncated]");
+            }
+            out.push_str("\n```\n");
+        }
+        Ok(out)
+    }
+}
+
+/// Post an inline review comment on a specific line of a PR.
+pub struct GhPrComment;
+
+#[async_trait]
+impl Tool for GhPrComment {
+    fn name(&self) -> &str {
+        "gh_pr_comment"
+    }
+
+    fn description(&self) -> &str {
+        "Post an inline review comment on a specific line (or line range) of a file in a GitHub pull request."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        schema_with(
+            json!({
+                "file": {
+                    "type": "string",
+                    "description": "File path in the PR (as shown in the diff)"
+                },
+                "line": {
+                    "type": "integer",
+                    "description": "Line number in the file to comment on (last line for ranges)"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "First line for a multi-line comment"
+                },
+                "side": {
+                    "type": "string",
+                    "enum": ["RIGHT", "LEFT"],
+                    "description": "RIGHT for new code (default), LEFT for removed code"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Comment text (markdown; ```suggestion blocks supported)"
+                }
+            }),
+            &["file", "line", "body"],
+        )
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let target = PrTarget::from_args(&args, working_dir)?;
+        let file = args["file"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'file' argument"))?;
+        let line = args["line"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'line' argument"))?;
+        let body = args["body"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'body' argument"))?;
+        let side = match args["side"].as_str().unwrap_or("RIGHT") {
+            "LEFT" => "LEFT",
+            _ => "RIGHT",
+        };
+
+        let head = target.pr(&["view", "--json", "headRefOid"]).await?;
+        let head: Value = serde_json::from_str(&head)?;
+        let commit = head["headRefOid"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Could not resolve PR head commit"))?
+            .to_string();
+
+        let endpoint = format!("{}/comments", target.api_prefix());
+        let fields = [
+            format!("body={}", body),
+            format!("commit_id={}", commit),
+            format!("path={}", file),
+            format!("side={}", side),
+        ];
+        let typed = [format!("line={}", line)];
+        let mut api_args: Vec<&str> = vec!["api", "--method", "POST", &endpoint];
+        for f in &fields {
+            api_args.push("-f");
+            api_args.push(f);
+        }
+        for f in &typed {
+            api_args.push("-F");
+            api_args.push(f);
+        }
+        let start = args["start_line"]
+            .as_u64()
+            .map(|s| format!("start_line={}", s));
+        if let Some(start) = start.as_deref() {
+            api_args.extend(["-F", start, "-f"]);
+            api_args.push(if side == "LEFT" {
+                "start_side=LEFT"
+            } else {
+                "start_side=RIGHT"
+            });
+        }
+
+        let response = gh_output(&target.cwd, &api_args).await?;
+        let response: Value = serde_json::from_str(&response).unwrap_or(Value::Null);
+        Ok(format!(
+            "Posted comment {} on {}:{}\n{}",
+            response["id"].as_u64().unwrap_or(0),
+            file,
+            line,
+            response["html_url"].as_str().unwrap_or("")
+        ))
+    }
+}
+
+/// List existing inline review comments (threads) on a PR.
+pub struct GhPrReviewThreads;
+
+#[async_trait]
+impl Tool for GhPrReviewThreads {
+    fn name(&self) -> &str {
+        "gh_pr_review_threads"
+    }
+
+    fn description(&self) -> &str {
+        "List inline review comments on a GitHub pull request grouped by thread, with comment IDs for replying."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        schema_with(json!({}), &[])
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let target = PrTarget::from_args(&args, working_dir)?;
+        let endpoint = format!("{}/comments", target.api_prefix());
+        let raw = gh_output(&target.cwd, &["api", "--paginate", &endpoint]).await?;
+        let comments: Vec<Value> = serde_json::from_str(&raw).unwrap_or_default();
+        Ok(format_review_threads(&comments))
+    }
+}
+
+/// Group review comments into threads keyed by their root comment.
+fn format_review_threads(comments: &[Value]) -> String {
+    if comments.is_empty() {
+        return "No inline review comments.".to_string();
+    }
+    let mut roots: Vec<&Value> = Vec::new();
+    let mut replies: HashMap<u64, Vec<&Value>> = HashMap::new();
+    for c in comments {
+        match c["in_reply_to_id"].as_u64() {
+            Some(parent) => replies.entry(parent).or_default().push(c),
+            None => roots.push(c),
+        }
+    }
+
+    let mut out = String::new();
+    for root in roots {
+        let id = root["id"].as_u64().unwrap_or(0);
+        out.push_str(&format!(
+            "## Thread {} — {}:{}\n- [{}] {}: {}\n",
+            id,
+            root["path"].as_str().unwrap_or("?"),
+            root["line"]
+                .as_u64()
+                .or_else(|| root["original_line"].as_u64())
+                .unwrap_or(0),
+            id,
+            root["user"]["login"].as_str().unwrap_or("?"),
+            root["body"].as_str().unwrap_or("").trim()
+        ));
+        for reply in replies.get(&id).into_iter().flatten() {
+            out.push_str(&format!(
+                "  - [{}] {}: {}\n",
+                reply["id"].as_u64().unwrap_or(0),
+                reply["user"]["login"].as_str().unwrap_or("?"),
+                reply["body"].as_str().unwrap_or("").trim()
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Reply to an existing review comment thread.
+pub struct GhPrReply;
+
+#[async_trait]
+impl Tool for GhPrReply {
+    fn name(&self) -> &str {
+        "gh_pr_reply"
+    }
+
+    fn description(&self) -> &str {
+        "Reply to an inline review comment thread on a GitHub pull request (use gh_pr_review_threads to find comment IDs)."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        schema_with(
+            json!({
+                "comment_id": {
+                    "type": "integer",
+                    "description": "ID of the comment to reply to"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Reply text (markdown)"
+                }
+            }),
+            &["comment_id", "body"],
+        )
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let target = PrTarget::from_args(&args, working_dir)?;
+        let comment_id = args["comment_id"]
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'comment_id' argument"))?;
+        let body = args["body"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'body' argument"))?;
+        let endpoint = format!("{}/comments/{}/replies", target.api_prefix(), comment_id);
+        let field = format!("body={}", body);
+        gh_output(
+            &target.cwd,
+            &["api", "--method", "POST", &endpoint, "-f", &field],
+        )
+        .await?;
+        Ok(format!("Replied to comment {}", comment_id))
+    }
+}
+
+/// Submit a review verdict (approve, request changes, or comment).
+pub struct GhPrReview;
+
+#[async_trait]
+impl Tool for GhPrReview {
+    fn name(&self) -> &str {
+        "gh_pr_review"
+    }
+
+    fn description(&self) -> &str {
+        "Submit a review on a GitHub pull request: approve, request changes, or leave a summary comment. Post inline comments first with gh_pr_comment."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        schema_with(
+            json!({
+                "event": {
+                    "type": "string",
+                    "enum": ["approve", "request_changes", "comment"],
+                    "description": "Review verdict"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Review summary (required for request_changes and comment)"
+                }
+            }),
+            &["event"],
+        )
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let target = PrTarget::from_args(&args, working_dir)?;
+        let flag = match args["event"].as_str().unwrap_or("") {
+            "approve" => "--approve",
+            "request_changes" => "--request-changes",
+            "comment" => "--comment",
+            other => anyhow::bail!("Unknown review event '{}'", other),
+        };
+        let body = args["body"].as_str().unwrap_or("").trim();
+        if body.is_empty() && flag != "--approve" {
+            anyhow::bail!("'body' is required for {} reviews", &flag[2..]);
+        }
+
+        let mut review_args = vec!["review", flag];
+        if !body.is_empty() {
+            review_args.push("--body");
+            review_args.push(body);
+        }
+        target.pr(&review_args).await?;
+        Ok(format!(
+            "Submitted {} review on PR #{}",
+            &flag[2..],
+            target.number
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_review_threads_groups_replies() {
+        let comments = vec![
+            json!({"id": 1, "path": "src/lib.rs", "line": 10, "user": {"login": "alice"}, "body": "Why?"}),
+            json!({"id": 2, "in_reply_to_id": 1, "user": {"login": "bot"}, "body": "Because."}),
+            json!({"id": 3, "path": "README.md", "original_line": 3, "user": {"login": "bob"}, "body": "Typo"}),
+        ];
+        let out = format_review_threads(&comments);
+        assert!(out.contains("## Thread 1 — src/lib.rs:10"));
+        assert!(out.contains("  - [2] bot: Because."));
+        assert!(out.contains("## Thread 3 — README.md:3"));
+    }
+
+    #[test]
+    fn test_pr_target_validates_repo() {
+        let dir = Path::new("/tmp");
+        assert!(PrTarget::from_args(&json!({"number": 5, "repo": "owner/name"}), dir).is_ok());
+        assert!(PrTarget::from_args(&json!({"number": 5, "repo": "bad"}), dir).is_err());
+        assert!(PrTarget::from_args(&json!({"repo": "owner/name"}), dir).is_err());
+        let target = PrTarget::from_args(&json!({"number": "7"}), dir).unwrap();
+        assert_eq!(target.api_prefix(), "repos/{owner}/{repo}/pulls/7");
+    }
+}
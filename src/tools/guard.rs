@@ -0,0 +1,163 @@
+//! Client for the server-side mission guardrails (see `api::mission_guard`
+//! and `api::approvals`).
+//!
+//! Guardrail state must not live where the agent it checks can change it, so
+//! workspace-mcp fetches it from the server on every tool call, authenticated
+//! with the mission's token ([`MISSION_TOKEN_ENV`]). Outside a mission (no
+//! `SANDBOXED_SH_MISSION_ID`) there are no guardrails and nothing to approve.
+//!
+//! The same goes for [`SERVER_SETTINGS`]: the workspace env is written to the
+//! mission directory, so settings that steer guarded tools or hold their
+//! credentials are left out of it and read with [`setting`].
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Timeout for guardrail lookups.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Workspace env var holding the mission's token for the guard endpoints,
+/// approvals and `host_exec`.
+pub const MISSION_TOKEN_ENV: &str = "SANDBOXED_SH_MISSION_TOKEN";
+
+/// Workspace settings served by the server instead of the workspace env.
+pub const SERVER_SETTINGS: &[&str] = &[
+    "SANDBOXED_SH_GIT_PUSH_POLICY",
+    "SANDBOXED_SH_GIT_PROTECTED_BRANCHES",
+    "SANDBOXED_SH_GIT_TOKEN",
+    super::host_access::HOST_PATHS_SETTING,
+    super::host_access::TOOL_USER_SETTING,
+    crate::offline::OFFLINE_SETTING,
+    super::containers::MAX_IMAGE_SIZE_SETTING,
+    super::containers::PUSH_ALLOWLIST_SETTING,
+    super::kubernetes::NAMESPACE_SETTING,
+    super::kubernetes::CONTEXT_SETTING,
+    super::terraform::PROTECTED_SETTING,
+    super::terraform::APPLY_SETTING,
+    super::library_tool::FETCH_ALLOWLIST_SETTING,
+];
+
+/// Result of an approval request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ApprovalOutcome {
+    Approved {
+        actor: String,
+    },
+    Denied {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// Nobody decided in time
+    Expired,
+}
+
+pub(crate) struct Server {
+    pub mission_id: String,
+    pub api_base: String,
+    pub token: String,
+}
+
+/// The server the current mission's guard endpoints are on.
+pub(crate) fn server() -> Option<Server> {
+    let mission_id = std::env::var("SANDBOXED_SH_MISSION_ID")
+        .ok()
+        .filter(|id| !id.trim().is_empty())?;
+    let api_base = std::env::var("SANDBOXED_SH_API_URL").unwrap_or_else(|_| {
+        let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
+        format!("http://127.0.0.1:{}", port)
+    });
+    Some(Server {
+        mission_id,
+        api_base: api_base.trim_end_matches('/').to_string(),
+        token: super::terminal::workspace_setting(MISSION_TOKEN_ENV).unwrap_or_default(),
+    })
+}
+
+/// Fetch `/api/mission-guard/:mission_id/<path>`. `Ok(None)` outside a
+/// mission; an error when the server cannot answer, which callers treat as
+/// a refusal.
+pub async fn fetch<T: DeserializeOwned>(path: &str) -> anyhow::Result<Option<T>> {
+    let Some(server) = server() else {
+        return Ok(None);
+    };
+    let response = reqwest::Client::builder()
+        .timeout(LOOKUP_TIMEOUT)
+        .build()?
+        .get(format!(
+            "{}/api/mission-guard/{}/{}",
+            server.api_base, server.mission_id, path
+        ))
+        .bearer_auth(&server.token)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        anyhow::bail!("guardrail lookup failed ({}): {}", status, text);
+    }
+    Ok(Some(response.json().await?))
+}
+
+/// One of the [`SERVER_SETTINGS`] for the current mission; the process env
+/// outside a mission. Empty values are treated as unset. An error when the
+/// server cannot answer, which callers treat as a refusal.
+pub async fn setting(name: &str) -> anyhow::Result<Option<String>> {
+    debug_assert!(SERVER_SETTINGS.contains(&name), "{} is not served", name);
+    let value = match fetch::<HashMap<String, String>>("settings").await? {
+        Some(mut settings) => settings.remove(name),
+        None => std::env::var(name).ok(),
+    };
+    Ok(value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty()))
+}
+
+/// Ask an administrator to approve `tool` with `args`, waiting until they
+/// decide. `Err` explains why the call must not run.
+pub async fn request_approval(tool: &str, args: &Value, reason: &str) -> Result<String, String> {
+    let Some(server) = server() else {
+        return Err(format!(
+            "{} needs an administrator's approval, which is only available inside a mission.",
+            tool
+        ));
+    };
+    let outcome = async {
+        // The server holds the request until it is decided
+        let response = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60 * 60))
+            .build()?
+            .post(format!(
+                "{}/api/approvals/{}",
+                server.api_base, server.mission_id
+            ))
+            .bearer_auth(&server.token)
+            .json(&json!({ "tool": tool, "args": args, "reason": reason }))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("approval request failed ({}): {}", status, text);
+        }
+        Ok(response.json::<ApprovalOutcome>().await?)
+    }
+    .await
+    .map_err(|e: anyhow::Error| format!("{} was not approved: {}", tool, e))?;
+    match outcome {
+        ApprovalOutcome::Approved { actor } => Ok(actor),
+        ApprovalOutcome::Denied { reason } => Err(format!(
+            "The administrator denied this {} call{}. Do not retry it unchanged.",
+            tool,
+            reason.map(|r| format!(": {}", r)).unwrap_or_default()
+        )),
+        ApprovalOutcome::Expired => Err(format!(
+            "No administrator approved this {} call in time.",
+            tool
+        )),
+    }
+}
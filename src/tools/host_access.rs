@@ -0,0 +1,482 @@
+//! Guards on what workspace tools can reach on the host.
+//!
+//! Tools of host workspaces run directly on the server, so there:
+//! - Path arguments (those a tool declares in [`Tool::path_args`]) must
+//!   resolve inside the mission directory or `/tmp`; other calls are refused
+//!   before they run.
+//!   Set [`HOST_PATHS_SETTING`]`=allow` to restore the old behaviour.
+//! - When the server runs as root, shell commands of missions (`run_command`
+//!   and the tools built on it) run as the unprivileged [`DEFAULT_TOOL_USER`],
+//!   or the user named by [`TOOL_USER_SETTING`]. The server hands it the
+//!   mission directory before each turn ([`grant_mission_dir`]); its own
+//!   state in `.sandboxed-sh` stays with the server. Commands are refused
+//!   while the user does not exist. [`SERVER_USER`] keeps the server's user.
+//! - Host-level commands go through `host_exec`, which is only offered to
+//!   missions an admin enabled it for. Each call waits for an admin to
+//!   approve it and is recorded in the mission's audit log (see
+//!   `api::host_exec`). The server runs approved commands, so this also works
+//!   from container workspaces.
+//!
+//! Both settings are server settings (see `guard::SERVER_SETTINGS`), so the
+//! agent cannot lift its own confinement.
+//!
+//! The mission runner writes [`HOST_EXEC_FILE`] to the mission directory
+//! before each turn when host exec is enabled; the workspace MCP registers
+//! `host_exec` when it finds it.
+
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::guard;
+use super::{path_arg_values, Tool};
+
+/// Server setting; `allow` lets tools use paths outside the mission.
+pub const HOST_PATHS_SETTING: &str = "SANDBOXED_SH_HOST_PATHS";
+
+/// Server setting naming the user commands run as on host workspaces.
+pub const TOOL_USER_SETTING: &str = "SANDBOXED_SH_TOOL_USER";
+
+/// User commands of missions run as when [`TOOL_USER_SETTING`] is unset.
+pub const DEFAULT_TOOL_USER: &str = "sandboxed-tools";
+
+/// [`TOOL_USER_SETTING`] value that keeps the server's user.
+pub const SERVER_USER: &str = "server";
+
+/// Mission state kept by the server, never handed to the tool user.
+const STATE_DIR: &str = ".sandboxed-sh";
+
+/// Marker written by the mission runner when host exec is enabled.
+pub const HOST_EXEC_FILE: &str = ".sandboxed-sh_host_exec";
+
+/// Directories outside the mission that tools may always use.
+const SHARED_DIRS: &[&str] = &["/tmp"];
+
+/// Longest a `host_exec` command may run.
+pub const MAX_HOST_EXEC_TIMEOUT_SECS: u64 = 600;
+
+/// Result of a `host_exec` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum HostExecOutcome {
+    Completed {
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+    Failed {
+        error: String,
+    },
+    Denied {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// Nobody approved the call in time
+    Expired,
+    /// Host exec is not enabled for the mission
+    Disabled,
+}
+
+fn is_host_workspace() -> bool {
+    std::env::var("SANDBOXED_SH_WORKSPACE_TYPE")
+        .map(|t| t != "container")
+        .unwrap_or(true)
+}
+
+async fn paths_confined() -> anyhow::Result<bool> {
+    Ok(is_host_workspace()
+        && !guard::setting(HOST_PATHS_SETTING)
+            .await?
+            .is_some_and(|v| v.eq_ignore_ascii_case("allow")))
+}
+
+/// Resolve `raw` against `working_dir`, following `..` and existing symlinks.
+fn resolve(raw: &str, working_dir: &Path) -> PathBuf {
+    let joined = working_dir.join(raw);
+    let components: Vec<Component> = joined.components().collect();
+    // Canonicalize the longest existing prefix; the rest does not exist, so
+    // it holds no symlinks and can be applied lexically.
+    for split in (1..=components.len()).rev() {
+        let prefix: PathBuf = components[..split].iter().collect();
+        if let Ok(mut path) = prefix.canonicalize() {
+            for component in &components[split..] {
+                match component {
+                    Component::ParentDir => {
+                        path.pop();
+                    }
+                    Component::Normal(name) => path.push(name),
+                    _ => {}
+                }
+            }
+            return path;
+        }
+    }
+    PathBuf::from("/")
+}
+
+/// Path arguments (`path_args`) of a call that resolve outside `working_dir`
+/// and the shared directories.
+pub fn outside_paths(path_args: &[&str], args: &Value, working_dir: &Path) -> Vec<String> {
+    outside_paths_in(path_args, args, working_dir, SHARED_DIRS)
+}
+
+fn outside_paths_in(
+    path_args: &[&str],
+    args: &Value,
+    working_dir: &Path,
+    shared: &[&str],
+) -> Vec<String> {
+    let root = working_dir
+        .canonicalize()
+        .unwrap_or_else(|_| working_dir.to_path_buf());
+    let allowed = |path: &Path| {
+        path.starts_with(&root)
+            || shared
+                .iter()
+                .any(|dir| path.starts_with(resolve(dir, Path::new("/"))))
+    };
+    path_arg_values(args, path_args)
+        .into_iter()
+        .filter(|raw| !allowed(&resolve(raw, working_dir)))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Refusal for a call of `tool` that reaches outside the mission on a host
+/// workspace.
+pub async fn path_refusal(tool: &dyn Tool, args: &Value, working_dir: &Path) -> Option<String> {
+    let name = tool.name();
+    match paths_confined().await {
+        Ok(true) => {}
+        Ok(false) => return None,
+        Err(e) => {
+            return Some(format!(
+                "{} was refused: the host path settings could not be loaded ({})",
+                name, e
+            ))
+        }
+    }
+    let outside = outside_paths(tool.path_args(), args, working_dir);
+    if outside.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "{} was refused: {} outside the mission directory. Tools may only use paths inside \
+         the mission directory or /tmp. Use relative paths, or ask the user to enable \
+         host_exec for this mission if host access is really needed.",
+        name,
+        outside
+            .iter()
+            .map(|p| format!("`{}` is", p))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// User and group of the tool user named by `setting` (`default` when
+/// unset). `None` keeps the server's user: it is not root, so it cannot
+/// switch, or the setting is [`SERVER_USER`].
+#[cfg(unix)]
+fn lookup_tool_user(
+    setting: Option<&str>,
+    default: Option<&str>,
+) -> anyhow::Result<Option<(u32, u32)>> {
+    // SAFETY: geteuid() is a trivial syscall with no preconditions.
+    if unsafe { libc::geteuid() } != 0 {
+        return Ok(None);
+    }
+    let Some(name) = setting.or(default).filter(|name| *name != SERVER_USER) else {
+        return Ok(None);
+    };
+    let c_name = std::ffi::CString::new(name)?;
+    // SAFETY: getpwnam gets a valid NUL-terminated string; the returned
+    // record is read immediately, before any other passwd lookup.
+    let user = unsafe {
+        let entry = libc::getpwnam(c_name.as_ptr());
+        (!entry.is_null()).then(|| ((*entry).pw_uid, (*entry).pw_gid))
+    };
+    user.map(Some).ok_or_else(|| {
+        anyhow::anyhow!(
+            "the tool user '{}' does not exist; an administrator must create it, or set {}={} \
+             on the server to run commands as the server's user",
+            name,
+            TOOL_USER_SETTING,
+            SERVER_USER
+        )
+    })
+}
+
+#[cfg(not(unix))]
+fn lookup_tool_user(
+    _setting: Option<&str>,
+    _default: Option<&str>,
+) -> anyhow::Result<Option<(u32, u32)>> {
+    Ok(None)
+}
+
+/// User and group shell commands switch to on host workspaces. Outside a
+/// mission there is no agent to confine, so only an explicit setting applies.
+pub(crate) async fn tool_user() -> anyhow::Result<Option<(u32, u32)>> {
+    if !is_host_workspace() {
+        return Ok(None);
+    }
+    let setting = guard::setting(TOOL_USER_SETTING).await?;
+    let default = guard::server().is_some().then_some(DEFAULT_TOOL_USER);
+    lookup_tool_user(setting.as_deref(), default)
+}
+
+/// Hand a host workspace's mission directory to the tool user named by
+/// `setting`, so the commands it runs can write there. The server's state
+/// in `.sandboxed-sh` keeps its owner.
+#[cfg(unix)]
+pub fn grant_mission_dir(dir: &Path, setting: Option<&str>) -> anyhow::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let Some((uid, gid)) = lookup_tool_user(setting, Some(DEFAULT_TOOL_USER))? else {
+        return Ok(());
+    };
+    let entries = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| e.depth() != 1 || e.file_name() != STATE_DIR);
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.path().symlink_metadata()?;
+        if metadata.uid() != uid || metadata.gid() != gid {
+            std::os::unix::fs::lchown(entry.path(), Some(uid), Some(gid))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn grant_mission_dir(_dir: &Path, _setting: Option<&str>) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Record whether host exec is enabled for the mission's next turn.
+pub fn write_host_exec_enabled(work_dir: &Path, enabled: bool) -> std::io::Result<()> {
+    let path = work_dir.join(HOST_EXEC_FILE);
+    if enabled {
+        return std::fs::write(path, b"enabled\n");
+    }
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub fn host_exec_enabled(work_dir: &Path) -> bool {
+    work_dir.join(HOST_EXEC_FILE).exists()
+}
+
+/// Run a command on the host after an admin approves it.
+pub struct HostExec;
+
+#[async_trait]
+impl Tool for HostExec {
+    fn name(&self) -> &str {
+        "host_exec"
+    }
+
+    fn description(&self) -> &str {
+        "Run a shell command on the host machine, outside the workspace sandbox. Every call \
+         waits for an administrator to approve it and is audit-logged, so only use it for \
+         host-level operations that cannot be done in the workspace, and explain why in \
+         `reason`."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "Shell command to run on the host"
+                },
+                "reason": {
+                    "type": "string",
+                    "description": "Why the command is needed, shown to the approver"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_HOST_EXEC_TIMEOUT_SECS,
+                    "description": "Command timeout once approved (default 60)"
+                }
+            },
+            "required": ["command", "reason"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let server = guard::server()
+            .ok_or_else(|| anyhow::anyhow!("host_exec is only available inside a mission"))?;
+
+        // The server holds the request until it is approved and has run
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60 * 60))
+            .build()?;
+        let response = client
+            .post(format!(
+                "{}/api/host-exec/{}",
+                server.api_base, server.mission_id
+            ))
+            .bearer_auth(&server.token)
+            .json(&args)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("host_exec request failed ({}): {}", status, text);
+        }
+        Ok(match response.json::<HostExecOutcome>().await? {
+            HostExecOutcome::Completed {
+                exit_code,
+                stdout,
+                stderr,
+            } => {
+                let mut text = format!(
+                    "Exit code: {}\n",
+                    exit_code.map_or("none (killed)".to_string(), |c| c.to_string())
+                );
+                if !stdout.is_empty() {
+                    text.push_str(&format!("\n--- stdout ---\n{}", stdout));
+                }
+                if !stderr.is_empty() {
+                    text.push_str(&format!("\n--- stderr ---\n{}", stderr));
+                }
+                text
+            }
+            HostExecOutcome::Failed { error } => anyhow::bail!("host_exec failed: {}", error),
+            HostExecOutcome::Denied { reason } => anyhow::bail!(
+                "The administrator denied this host_exec call{}. Do not retry it unchanged.",
+                reason.map(|r| format!(": {}", r)).unwrap_or_default()
+            ),
+            HostExecOutcome::Expired => {
+                anyhow::bail!("No administrator approved this host_exec call in time.")
+            }
+            HostExecOutcome::Disabled => {
+                anyhow::bail!("host_exec is not enabled for this mission.")
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::PATH_ARGS;
+
+    #[test]
+    fn finds_paths_outside_the_mission() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let mission = dir.path().join("mission");
+        std::fs::create_dir(&mission).unwrap();
+        let args = json!({
+            "path": "src/../notes.md",
+            "files": ["a.rs", "/etc/passwd", "../src/lib.rs"],
+            "cwd": "/var/cache/build",
+            "content": "/etc/shadow"
+        });
+        // Temp dirs live under /tmp, so leave the shared dirs out here
+        assert_eq!(
+            outside_paths_in(PATH_ARGS, &args, &mission, &[]),
+            vec![
+                "/etc/passwd".to_string(),
+                "../src/lib.rs".to_string(),
+                "/var/cache/build".to_string()
+            ]
+        );
+        let args = json!({ "cwd": "/var/cache/build" });
+        assert!(outside_paths_in(PATH_ARGS, &args, &mission, &["/var/cache"]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn checks_the_path_args_a_tool_declares() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = crate::tools::image_diff::CompareImages;
+        let args = json!({
+            "baseline": "before.png",
+            "current": "after.png",
+            "diff_output": "/etc/cron.d/diff.png"
+        });
+        let refusal = path_refusal(&tool, &args, dir.path()).await.unwrap();
+        assert!(refusal.starts_with("compare_images was refused"));
+        assert!(refusal.contains("`/etc/cron.d/diff.png`"));
+
+        let args = json!({ "baseline": "a.png", "current": "b.png", "diff_output": "diff.png" });
+        assert!(path_refusal(&tool, &args, dir.path()).await.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_mission_are_outside() {
+        let dir = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink("/etc", dir.path().join("etc")).unwrap();
+        let args = json!({ "path": "etc/new-file" });
+        assert_eq!(
+            outside_paths_in(PATH_ARGS, &args, dir.path(), &[]),
+            vec!["etc/new-file"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tool_user_lookup() {
+        if unsafe { libc::geteuid() } != 0 {
+            // Without root the server's user is kept
+            assert_eq!(lookup_tool_user(Some("nobody"), None).unwrap(), None);
+            return;
+        }
+        assert!(lookup_tool_user(None, Some("nobody")).unwrap().is_some());
+        assert_eq!(lookup_tool_user(None, None).unwrap(), None);
+        assert_eq!(
+            lookup_tool_user(Some(SERVER_USER), Some("nobody")).unwrap(),
+            None
+        );
+        let missing = lookup_tool_user(Some("no-such-user-sandboxed"), None).unwrap_err();
+        assert!(missing.to_string().contains(SERVER_USER));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mission_dir_goes_to_the_tool_user_except_server_state() {
+        use std::os::unix::fs::MetadataExt;
+
+        if unsafe { libc::geteuid() } != 0 {
+            return;
+        }
+        let (uid, _) = lookup_tool_user(Some("nobody"), None).unwrap().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        std::fs::create_dir_all(dir.path().join(STATE_DIR)).unwrap();
+        std::fs::write(dir.path().join(STATE_DIR).join("workspace_env.json"), "{}").unwrap();
+
+        grant_mission_dir(dir.path(), Some("nobody")).unwrap();
+        let owner = |path: &str| std::fs::metadata(dir.path().join(path)).unwrap().uid();
+        assert_eq!(owner("src/main.rs"), uid);
+        assert_eq!(owner(STATE_DIR), 0);
+        assert_eq!(owner(".sandboxed-sh/workspace_env.json"), 0);
+    }
+
+    #[test]
+    fn host_exec_marker_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!host_exec_enabled(dir.path()));
+        write_host_exec_enabled(dir.path(), true).unwrap();
+        assert!(host_exec_enabled(dir.path()));
+        write_host_exec_enabled(dir.path(), false).unwrap();
+        write_host_exec_enabled(dir.path(), false).unwrap();
+        assert!(!host_exec_enabled(dir.path()));
+
+        let outcome: HostExecOutcome =
+            serde_json::from_value(json!({ "status": "denied" })).unwrap();
+        assert_eq!(outcome, HostExecOutcome::Denied { reason: None });
+    }
+}
@@ -0,0 +1,965 @@
+//! HTML parsing, readability extraction and Markdown rendering for `fetch_url`.
+//!
+//! The parser is a small, tolerant tree builder rather than a spec-compliant
+//! HTML5 parser: it handles the markup real pages use (unclosed `<p>`/`<li>`,
+//! void elements, raw-text `<script>`/`<style>`) well enough to find the main
+//! content and render it as compact Markdown.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Elements without content or closing tag.
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is raw text, not markup.
+const RAW_TEXT_TAGS: &[&str] = &["script", "style", "textarea", "title", "xmp"];
+
+/// Elements that never carry readable content.
+const BOILERPLATE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "math", "iframe", "canvas", "object", "nav",
+    "header", "footer", "aside", "form", "button", "select", "input", "dialog", "head",
+];
+
+/// Elements that implicitly close an open `<p>`.
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "div",
+    "dl",
+    "fieldset",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+static ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([^\s"'=<>/]+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#).unwrap()
+});
+
+static UNLIKELY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(nav|navbar|menu|footer|sidebar|side-bar|comment|comments|share|sharing|social|cookie|consent|banner|advert|ads?|promo|related|breadcrumbs?|popup|modal|subscribe|newsletter|skip-link|masthead|toolbar)\b",
+    )
+    .unwrap()
+});
+
+static LIKELY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(article|content|main|post|entry|story|text|body|blog|markdown|prose|docs?)\b",
+    )
+    .unwrap()
+});
+
+#[derive(Debug, Clone)]
+pub(super) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Element {
+    tag: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn new(tag: &str, attrs: Vec<(String, String)>) -> Self {
+        Self {
+            tag: tag.to_string(),
+            attrs,
+            children: Vec::new(),
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn class_and_id(&self) -> String {
+        format!(
+            "{} {}",
+            self.attr("class").unwrap_or_default(),
+            self.attr("id").unwrap_or_default()
+        )
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|c| match c {
+            Node::Element(e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// First descendant (or self) with the given tag.
+    fn find(&self, tag: &str) -> Option<&Element> {
+        if self.tag == tag {
+            return Some(self);
+        }
+        self.elements().find_map(|e| e.find(tag))
+    }
+
+    fn find_all<'a>(&'a self, pred: &dyn Fn(&Element) -> bool, out: &mut Vec<&'a Element>) {
+        if pred(self) {
+            out.push(self);
+        }
+        for child in self.elements() {
+            child.find_all(pred, out);
+        }
+    }
+
+    fn text_content(&self) -> String {
+        let mut out = String::new();
+        collect_text(self, &mut out);
+        out
+    }
+
+    fn text_len(&self) -> usize {
+        self.text_content()
+            .split_whitespace()
+            .map(|w| w.len() + 1)
+            .sum()
+    }
+
+    fn link_text_len(&self) -> usize {
+        let mut links = Vec::new();
+        self.find_all(&|e| e.tag == "a", &mut links);
+        links.iter().map(|a| a.text_len()).sum()
+    }
+}
+
+fn collect_text(el: &Element, out: &mut String) {
+    for child in &el.children {
+        match child {
+            Node::Text(t) => out.push_str(t),
+            Node::Element(e) => {
+                collect_text(e, out);
+                out.push(' ');
+            }
+        }
+    }
+}
+
+/// Parse HTML into a tree rooted at a synthetic `#root` element.
+pub(super) fn parse(html: &str) -> Element {
+    let lower = html.to_ascii_lowercase();
+    let mut stack = vec![Element::new("#root", Vec::new())];
+    let mut i = 0;
+    while i < html.len() {
+        let rest = &html[i..];
+        if rest.starts_with("<!--") {
+            i += rest.find("-->").map(|p| p + 3).unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            i += rest.find('>').map(|p| p + 1).unwrap_or(rest.len());
+            continue;
+        }
+        if let Some(close) = rest.strip_prefix("</") {
+            let end = close.find('>').unwrap_or(close.len());
+            let name = close[..end].trim().to_ascii_lowercase();
+            close_element(&mut stack, &name);
+            i += 2 + (end + 1).min(close.len());
+            continue;
+        }
+        let starts_tag = rest.starts_with('<')
+            && rest[1..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic());
+        if starts_tag {
+            if let Some(end) = tag_end(rest) {
+                let inner = &rest[1..end];
+                let self_closing = inner.ends_with('/');
+                let inner = inner.trim_end_matches('/');
+                let name_end = inner
+                    .find(|c: char| c.is_whitespace())
+                    .unwrap_or(inner.len());
+                let name = inner[..name_end].to_ascii_lowercase();
+                let attrs = parse_attrs(&inner[name_end..]);
+                i += end + 1;
+
+                auto_close(&mut stack, &name);
+                let mut element = Element::new(&name, attrs);
+                if RAW_TEXT_TAGS.contains(&name.as_str()) && !self_closing {
+                    let closing = format!("</{}", name);
+                    let content_end = lower[i..].find(&closing).map(|p| i + p);
+                    let content = &html[i..content_end.unwrap_or(html.len())];
+                    if !content.is_empty() {
+                        element.children.push(Node::Text(decode_entities(content)));
+                    }
+                    i = match content_end {
+                        Some(p) => p + html[p..].find('>').map(|q| q + 1).unwrap_or(html.len() - p),
+                        None => html.len(),
+                    };
+                    push_child(&mut stack, Node::Element(element));
+                } else if self_closing || VOID_TAGS.contains(&name.as_str()) {
+                    push_child(&mut stack, Node::Element(element));
+                } else {
+                    stack.push(element);
+                }
+                continue;
+            }
+        }
+        let first_len = rest.chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+        let next = rest[first_len..]
+            .find('<')
+            .map(|p| p + first_len)
+            .unwrap_or(rest.len());
+        push_child(&mut stack, Node::Text(decode_entities(&rest[..next])));
+        i += next;
+    }
+    while stack.len() > 1 {
+        let el = stack.pop().unwrap();
+        push_child(&mut stack, Node::Element(el));
+    }
+    stack.pop().unwrap()
+}
+
+/// Index of the `>` closing a start tag, skipping quoted attribute values.
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (idx, c) in rest.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '>') => return Some(idx),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attrs(raw: &str) -> Vec<(String, String)> {
+    ATTR_RE
+        .captures_iter(raw)
+        .map(|cap| {
+            let value = cap
+                .get(2)
+                .or_else(|| cap.get(3))
+                .or_else(|| cap.get(4))
+                .map(|m| decode_entities(m.as_str()))
+                .unwrap_or_default();
+            (cap[1].to_ascii_lowercase(), value)
+        })
+        .collect()
+}
+
+fn push_child(stack: &mut [Element], node: Node) {
+    if let Some(top) = stack.last_mut() {
+        top.children.push(node);
+    }
+}
+
+fn close_element(stack: &mut Vec<Element>, name: &str) {
+    let Some(pos) = stack.iter().rposition(|e| e.tag == name) else {
+        return;
+    };
+    if pos == 0 {
+        return;
+    }
+    while stack.len() > pos {
+        let el = stack.pop().unwrap();
+        push_child(stack, Node::Element(el));
+    }
+}
+
+/// Close elements a new start tag implicitly ends (`<p>`, `<li>`, table cells...).
+fn auto_close(stack: &mut Vec<Element>, name: &str) {
+    let (targets, boundaries): (&[&str], &[&str]) = match name {
+        "li" => (&["li"], &["ul", "ol", "menu"]),
+        "dt" | "dd" => (&["dt", "dd"], &["dl"]),
+        "tr" => (&["tr", "td", "th"], &["table", "tbody", "thead", "tfoot"]),
+        "td" | "th" => (&["td", "th"], &["tr", "table"]),
+        "option" => (&["option"], &["select", "datalist"]),
+        _ => (&[], &[]),
+    };
+    if !targets.is_empty() {
+        let found = stack
+            .iter()
+            .rev()
+            .take_while(|e| !boundaries.contains(&e.tag.as_str()))
+            .position(|e| targets.contains(&e.tag.as_str()));
+        if let Some(depth) = found {
+            let pos = stack.len() - 1 - depth;
+            let tag = stack[pos].tag.clone();
+            close_element(stack, &tag);
+        }
+    }
+    if BLOCK_TAGS.contains(&name) && stack.last().is_some_and(|e| e.tag == "p") {
+        close_element(stack, "p");
+    }
+}
+
+/// Decode character references.
+pub(super) fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let end = rest[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+            .map(|p| p + 1)
+            .unwrap_or(rest.len());
+        let name = &rest[1..end];
+        let decoded = if let Some(num) = name.strip_prefix('#') {
+            let code = match num.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => num.parse().ok(),
+            };
+            code.and_then(char::from_u32).map(|c| c.to_string())
+        } else {
+            named_entity(name).map(str::to_string)
+        };
+        match decoded {
+            Some(text) => {
+                out.push_str(&text);
+                rest = &rest[end..];
+                if rest.starts_with(';') {
+                    rest = &rest[1..];
+                }
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn named_entity(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" => " ",
+        "ndash" => "–",
+        "mdash" => "—",
+        "hellip" => "…",
+        "lsquo" => "‘",
+        "rsquo" => "’",
+        "ldquo" => "“",
+        "rdquo" => "”",
+        "laquo" => "«",
+        "raquo" => "»",
+        "bull" => "•",
+        "middot" => "·",
+        "copy" => "©",
+        "reg" => "®",
+        "trade" => "™",
+        "times" => "×",
+        "deg" => "°",
+        "euro" => "€",
+        "pound" => "£",
+        _ => return None,
+    })
+}
+
+/// Drop elements that never hold article content.
+pub(super) fn strip_boilerplate(el: &mut Element) {
+    el.children.retain(|child| match child {
+        Node::Element(e) => !is_boilerplate(e),
+        Node::Text(_) => true,
+    });
+    for child in &mut el.children {
+        if let Node::Element(e) = child {
+            strip_boilerplate(e);
+        }
+    }
+}
+
+fn is_boilerplate(el: &Element) -> bool {
+    if BOILERPLATE_TAGS.contains(&el.tag.as_str()) {
+        return true;
+    }
+    if el.attr("hidden").is_some()
+        || el.attr("aria-hidden") == Some("true")
+        || el
+            .attr("style")
+            .is_some_and(|s| s.replace(' ', "").contains("display:none"))
+        || matches!(
+            el.attr("role"),
+            Some("navigation" | "banner" | "contentinfo" | "complementary" | "dialog")
+        )
+    {
+        return true;
+    }
+    if matches!(el.tag.as_str(), "html" | "body" | "main" | "article") {
+        return false;
+    }
+    let names = el.class_and_id();
+    UNLIKELY_RE.is_match(&names) && !LIKELY_RE.is_match(&names)
+}
+
+/// Pick the element holding the main content (readability-style scoring).
+pub(super) fn readable_content(root: &Element) -> &Element {
+    const MIN_CONTENT_LEN: usize = 250;
+
+    let mut articles = Vec::new();
+    root.find_all(&|e| e.tag == "article", &mut articles);
+    if let Some(best) = articles.into_iter().max_by_key(|e| e.text_len()) {
+        if best.text_len() >= MIN_CONTENT_LEN {
+            return best;
+        }
+    }
+    let mut mains = Vec::new();
+    root.find_all(
+        &|e| e.tag == "main" || e.attr("role") == Some("main"),
+        &mut mains,
+    );
+    if let Some(main) = mains.into_iter().max_by_key(|e| e.text_len()) {
+        if main.text_len() >= MIN_CONTENT_LEN {
+            return main;
+        }
+    }
+
+    let body = root.find("body").unwrap_or(root);
+    let mut candidates = Vec::new();
+    body.find_all(
+        &|e| {
+            matches!(
+                e.tag.as_str(),
+                "div" | "section" | "td" | "blockquote" | "body"
+            )
+        },
+        &mut candidates,
+    );
+    let best = candidates
+        .into_iter()
+        .map(|e| (candidate_score(e), e))
+        .filter(|(score, _)| *score > 0.0)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, e)| e);
+    match best {
+        Some(e) if e.text_len() >= MIN_CONTENT_LEN => e,
+        _ => body,
+    }
+}
+
+fn paragraph_score(el: &Element) -> f64 {
+    if !matches!(el.tag.as_str(), "p" | "pre" | "td" | "blockquote" | "li") {
+        return 0.0;
+    }
+    let text = el.text_content();
+    let len = text.trim().len();
+    if len < 25 {
+        return 0.0;
+    }
+    1.0 + text.matches(',').count() as f64 + (len as f64 / 100.0).min(3.0)
+}
+
+fn candidate_score(el: &Element) -> f64 {
+    let mut score = 0.0;
+    for child in el.elements() {
+        score += paragraph_score(child);
+        for grandchild in child.elements() {
+            score += paragraph_score(grandchild) / 2.0;
+        }
+    }
+    let names = el.class_and_id();
+    if LIKELY_RE.is_match(&names) {
+        score += 25.0;
+    }
+    if UNLIKELY_RE.is_match(&names) {
+        score -= 25.0;
+    }
+    let text_len = el.text_len().max(1);
+    let link_density = el.link_text_len() as f64 / text_len as f64;
+    score * (1.0 - link_density.min(1.0))
+}
+
+/// Document title from `<title>` or the first `<h1>`.
+pub(super) fn title(root: &Element) -> Option<String> {
+    root.find("title")
+        .or_else(|| root.find("h1"))
+        .map(|e| collapse_whitespace(&e.text_content()))
+        .filter(|t| !t.is_empty())
+}
+
+/// All `(href, text)` pairs of links below `root`.
+pub(super) fn links(root: &Element) -> Vec<(String, String)> {
+    let mut anchors = Vec::new();
+    root.find_all(&|e| e.tag == "a" && e.attr("href").is_some(), &mut anchors);
+    anchors
+        .into_iter()
+        .map(|a| {
+            (
+                a.attr("href").unwrap_or_default().to_string(),
+                collapse_whitespace(&a.text_content()),
+            )
+        })
+        .collect()
+}
+
+/// The documentation entry anchored at `id`: a heading with the content up
+/// to the next heading of the same level, a Sphinx `<dl>` entry, or a
+/// rustdoc `<details>` toggle holding the member and its docblock.
+pub(super) fn section_by_id(root: &Element, id: &str) -> Option<Element> {
+    let path = find_path(root, &|e| e.attr("id") == Some(id))?;
+    focus(root, &path)
+}
+
+/// The section under the first heading whose text contains `heading`.
+pub(super) fn section_by_heading(root: &Element, heading: &str) -> Option<Element> {
+    let needle = heading.trim().to_lowercase();
+    let path = find_path(root, &|e| {
+        heading_level(e).is_some() && e.text_content().to_lowercase().contains(&needle)
+    })?;
+    focus(root, &path)
+}
+
+fn heading_level(el: &Element) -> Option<usize> {
+    match el.tag.as_str() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => el.tag[1..].parse().ok(),
+        _ => None,
+    }
+}
+
+/// Child indices leading from `root` to the first element matching `pred`.
+fn find_path(root: &Element, pred: &dyn Fn(&Element) -> bool) -> Option<Vec<usize>> {
+    for (idx, child) in root.children.iter().enumerate() {
+        if let Node::Element(e) = child {
+            if pred(e) {
+                return Some(vec![idx]);
+            }
+            if let Some(mut rest) = find_path(e, pred) {
+                rest.insert(0, idx);
+                return Some(rest);
+            }
+        }
+    }
+    None
+}
+
+fn at_path<'a>(root: &'a Element, path: &[usize]) -> &'a Element {
+    path.iter().fold(root, |el, idx| match &el.children[*idx] {
+        Node::Element(e) => e,
+        Node::Text(_) => el,
+    })
+}
+
+fn focus(root: &Element, path: &[usize]) -> Option<Element> {
+    let target = at_path(root, path);
+    let parent_path = &path[..path.len() - 1];
+    let parent = at_path(root, parent_path);
+
+    if let Some(level) = heading_level(target) {
+        let start = path[path.len() - 1];
+        let mut section = Element::new("div", Vec::new());
+        for (idx, child) in parent.children.iter().enumerate().skip(start) {
+            if let Node::Element(e) = child {
+                if idx > start && heading_level(e).is_some_and(|l| l <= level) {
+                    break;
+                }
+            }
+            section.children.push(child.clone());
+        }
+        return Some(section);
+    }
+    if target.tag == "dt" && parent.tag == "dl" {
+        return Some(parent.clone());
+    }
+    // rustdoc: <details><summary><section id="method.x">..</section></summary><div class="docblock">
+    for depth in 1..=2.min(parent_path.len()) {
+        let ancestor_path = &path[..path.len() - depth];
+        if at_path(root, ancestor_path).tag == "summary" && !ancestor_path.is_empty() {
+            let details = at_path(root, &ancestor_path[..ancestor_path.len() - 1]);
+            if details.tag == "details" {
+                return Some(details.clone());
+            }
+        }
+    }
+    Some(target.clone())
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Render an element as Markdown (or plain text when `plain` is set).
+pub(super) fn render(el: &Element, base: Option<&url::Url>, plain: bool) -> String {
+    let renderer = Renderer { base, plain };
+    let mut out = String::new();
+    renderer.children(el, &mut out, false);
+    tidy(&out)
+}
+
+struct Renderer<'a> {
+    base: Option<&'a url::Url>,
+    plain: bool,
+}
+
+impl Renderer<'_> {
+    fn children(&self, el: &Element, out: &mut String, pre: bool) {
+        for child in &el.children {
+            match child {
+                Node::Text(t) if pre => out.push_str(t),
+                Node::Text(t) => push_inline_text(out, t),
+                Node::Element(e) => self.element(e, out, pre),
+            }
+        }
+    }
+
+    fn inner(&self, el: &Element) -> String {
+        let mut s = String::new();
+        self.children(el, &mut s, false);
+        s.trim().to_string()
+    }
+
+    fn block(&self, out: &mut String, content: &str) {
+        if content.is_empty() {
+            return;
+        }
+        out.push_str("\n\n");
+        out.push_str(content);
+        out.push_str("\n\n");
+    }
+
+    fn wrap(&self, out: &mut String, el: &Element, marker: &str) {
+        let inner = self.inner(el);
+        if inner.is_empty() {
+            return;
+        }
+        if self.plain {
+            out.push_str(&inner);
+        } else {
+            out.push_str(marker);
+            out.push_str(&inner);
+            out.push_str(marker);
+        }
+    }
+
+    fn resolve(&self, href: &str) -> String {
+        match self.base.and_then(|b| b.join(href).ok()) {
+            Some(url) => url.to_string(),
+            None => href.to_string(),
+        }
+    }
+
+    fn element(&self, el: &Element, out: &mut String, pre: bool) {
+        match el.tag.as_str() {
+            "title" | "head" | "script" | "style" => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = el.tag[1..].parse::<usize>().unwrap_or(1);
+                let text = collapse_whitespace(&self.inner(el));
+                if !text.is_empty() {
+                    let heading = if self.plain {
+                        text
+                    } else {
+                        format!("{} {}", "#".repeat(level), text)
+                    };
+                    self.block(out, &heading);
+                }
+            }
+            "p" | "div" | "section" | "article" | "main" | "figure" | "figcaption" | "address"
+            | "details" | "summary" | "center" | "body" | "html" => {
+                let inner = self.inner(el);
+                self.block(out, &inner);
+            }
+            "br" => out.push('\n'),
+            "hr" => self.block(out, if self.plain { "" } else { "---" }),
+            "a" => {
+                let text = collapse_whitespace(&self.inner(el));
+                let href = el.attr("href").unwrap_or_default().trim();
+                if self.plain
+                    || text.is_empty()
+                    || href.is_empty()
+                    || href.starts_with('#')
+                    || href.starts_with("javascript:")
+                {
+                    out.push_str(&text);
+                } else {
+                    out.push_str(&format!("[{}]({})", text, self.resolve(href)));
+                }
+            }
+            "img" => {
+                let alt = collapse_whitespace(el.attr("alt").unwrap_or_default());
+                let src = el.attr("src").unwrap_or_default();
+                if !self.plain && !alt.is_empty() && !src.is_empty() {
+                    out.push_str(&format!("![{}]({})", alt, self.resolve(src)));
+                }
+            }
+            "strong" | "b" => self.wrap(out, el, "**"),
+            "em" | "i" => self.wrap(out, el, "*"),
+            "del" | "s" | "strike" => self.wrap(out, el, "~~"),
+            "code" | "kbd" | "samp" if !pre => self.wrap(out, el, "`"),
+            "pre" => {
+                let code = el.text_content();
+                let code = code.trim_matches('\n');
+                if code.trim().is_empty() {
+                    return;
+                }
+                if self.plain {
+                    self.block(out, code);
+                } else {
+                    let lang = el
+                        .find("code")
+                        .and_then(|c| c.attr("class"))
+                        .or(el.attr("class"))
+                        .and_then(|c| {
+                            c.split_whitespace()
+                                .find_map(|cls| cls.strip_prefix("language-"))
+                        })
+                        .unwrap_or_default();
+                    self.block(out, &format!("```{}\n{}\n```", lang, code));
+                }
+            }
+            "blockquote" => {
+                let inner = tidy(&{
+                    let mut s = String::new();
+                    self.children(el, &mut s, false);
+                    s
+                });
+                if self.plain {
+                    self.block(out, &inner);
+                } else {
+                    let quoted = inner
+                        .lines()
+                        .map(|l| {
+                            if l.is_empty() {
+                                ">".to_string()
+                            } else {
+                                format!("> {}", l)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    self.block(out, &quoted);
+                }
+            }
+            "ul" | "ol" | "menu" => {
+                let ordered = el.tag == "ol";
+                let mut items = Vec::new();
+                for (idx, li) in el.elements().filter(|e| e.tag == "li").enumerate() {
+                    let marker = if ordered {
+                        format!("{}. ", idx + 1)
+                    } else {
+                        "- ".to_string()
+                    };
+                    let content = tidy(&{
+                        let mut s = String::new();
+                        self.children(li, &mut s, false);
+                        s
+                    });
+                    if content.is_empty() {
+                        continue;
+                    }
+                    let indent = " ".repeat(marker.len());
+                    let mut lines = content.lines();
+                    let mut item = format!("{}{}", marker, lines.next().unwrap_or_default());
+                    for line in lines {
+                        item.push('\n');
+                        if !line.is_empty() {
+                            item.push_str(&indent);
+                            item.push_str(line);
+                        }
+                    }
+                    items.push(item.replace("\n\n", "\n"));
+                }
+                self.block(out, &items.join("\n"));
+            }
+            "dl" => {
+                let mut lines = Vec::new();
+                for item in el.elements() {
+                    let text = collapse_whitespace(&self.inner(item));
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match item.tag.as_str() {
+                        "dt" if !self.plain => lines.push(format!("**{}**", text)),
+                        "dd" if !self.plain => lines.push(format!(": {}", text)),
+                        _ => lines.push(text),
+                    }
+                }
+                self.block(out, &lines.join("\n"));
+            }
+            "table" => self.table(el, out),
+            _ => self.children(el, out, pre),
+        }
+    }
+
+    fn table(&self, el: &Element, out: &mut String) {
+        let mut rows = Vec::new();
+        collect_rows(el, &mut rows);
+        let cells: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                row.elements()
+                    .filter(|c| c.tag == "td" || c.tag == "th")
+                    .map(|c| collapse_whitespace(&self.inner(c)).replace('|', "\\|"))
+                    .collect()
+            })
+            .filter(|r: &Vec<String>| r.iter().any(|c| !c.is_empty()))
+            .collect();
+        let columns = cells.iter().map(|r| r.len()).max().unwrap_or(0);
+        // Layout tables (a single column) read better as plain blocks.
+        if columns < 2 || self.plain {
+            let mut s = String::new();
+            for row in &rows {
+                for cell in row.elements() {
+                    self.children(cell, &mut s, false);
+                    s.push_str("\n\n");
+                }
+            }
+            self.block(out, &tidy(&s));
+            return;
+        }
+        let mut lines = Vec::new();
+        for (idx, row) in cells.iter().enumerate() {
+            let mut row = row.clone();
+            row.resize(columns, String::new());
+            lines.push(format!("| {} |", row.join(" | ")));
+            if idx == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        self.block(out, &lines.join("\n"));
+    }
+}
+
+/// Rows of a table, without descending into nested tables.
+fn collect_rows<'a>(el: &'a Element, rows: &mut Vec<&'a Element>) {
+    for child in el.elements() {
+        match child.tag.as_str() {
+            "tr" => rows.push(child),
+            "table" => {}
+            _ => collect_rows(child, rows),
+        }
+    }
+}
+
+fn push_inline_text(out: &mut String, text: &str) {
+    let collapsed = collapse_whitespace(text);
+    let leading = text.starts_with(char::is_whitespace);
+    let trailing = text.ends_with(char::is_whitespace);
+    if leading && !out.ends_with([' ', '\n']) && !out.is_empty() {
+        out.push(' ');
+    }
+    out.push_str(&collapsed);
+    if trailing && !collapsed.is_empty() {
+        out.push(' ');
+    }
+}
+
+/// Trim trailing spaces and collapse runs of blank lines.
+fn tidy(s: &str) -> String {
+    let mut out = String::new();
+    let mut blank = 0;
+    for line in s.lines() {
+        let line = line.trim_end();
+        let line = if line.trim().is_empty() { "" } else { line };
+        if line.is_empty() {
+            blank += 1;
+            if blank > 1 {
+                continue;
+            }
+        } else {
+            blank = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sloppy_markup() {
+        let root =
+            parse("<ul><li>one<li>two &amp; <b>three</b></ul><p>a<p>b<script>if (a<b) {}</script>");
+        let ul = root.find("ul").unwrap();
+        assert_eq!(ul.elements().filter(|e| e.tag == "li").count(), 2);
+        assert_eq!(root.elements().filter(|e| e.tag == "p").count(), 2);
+        assert!(root.find("script").unwrap().text_content().contains("a<b"));
+        assert_eq!(
+            decode_entities("&lt;x&gt; &#39;&#x41;&unknown;"),
+            "<x> 'A&unknown;"
+        );
+    }
+
+    #[test]
+    fn readability_picks_article_over_boilerplate() {
+        let para =
+            "This sentence is part of the real article body, and it is long enough. ".repeat(2);
+        let html = format!(
+            r#"<html><head><title>Post</title></head><body>
+            <nav><a href="/">Home</a><a href="/about">About</a></nav>
+            <div class="sidebar"><p>{}</p></div>
+            <div class="post-content"><h1>Hello</h1><p>{}</p><p>{}</p><p>{}</p></div>
+            <footer>Copyright</footer></body></html>"#,
+            para, para, para, para
+        );
+        let mut root = parse(&html);
+        assert_eq!(title(&root).as_deref(), Some("Post"));
+        strip_boilerplate(&mut root);
+        let content = readable_content(&root);
+        assert_eq!(content.attr("class"), Some("post-content"));
+        let md = render(content, None, false);
+        assert!(md.starts_with("# Hello"));
+        assert!(!md.contains("Home"));
+        assert!(!md.contains("Copyright"));
+    }
+
+    #[test]
+    fn renders_markdown_constructs() {
+        let base = url::Url::parse("https://example.com/docs/page").unwrap();
+        let root = parse(
+            r#"<h2>Usage</h2><p>See <a href="../api">the API</a> and <code>run()</code>.</p>
+            <pre><code class="language-rust">fn main() {
+    println!("hi");
+}</code></pre>
+            <ol><li>First</li><li>Second<ul><li>Nested</li></ul></li></ol>
+            <table><tr><th>Name</th><th>Value</th></tr><tr><td>a</td><td>1</td></tr></table>
+            <blockquote><p>Quoted</p></blockquote>"#,
+        );
+        let md = render(&root, Some(&base), false);
+        assert!(md.contains("## Usage"));
+        assert!(md.contains("See [the API](https://example.com/api) and `run()`."));
+        assert!(md.contains("```rust\nfn main() {\n    println!(\"hi\");\n}\n```"));
+        assert!(md.contains("1. First\n2. Second\n   - Nested"));
+        assert!(md.contains("| Name | Value |\n| --- | --- |\n| a | 1 |"));
+        assert!(md.contains("> Quoted"));
+
+        let text = render(&root, Some(&base), true);
+        assert!(text.contains("See the API and run()."));
+        assert!(!text.contains("```"));
+    }
+}
@@ -0,0 +1,450 @@
+//! `compare_images`: visual regression check between two screenshots.
+//!
+//! Pixels are compared in YIQ space (the metric pixelmatch uses), so small
+//! rendering noise stays under the threshold while visible changes do not.
+//! Changed pixels are grouped into bounding boxes on a coarse grid, and a
+//! windowed SSIM score gives a perceptual similarity for the whole image.
+//! Optionally a diff image is written with changes highlighted in red over a
+//! faded copy of the baseline.
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use image::{Rgba, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{resolve_path_simple as resolve_path, Tool, ToolArgs};
+
+/// Largest possible YIQ delta between two pixels.
+const MAX_YIQ_DELTA: f64 = 35215.0;
+const DEFAULT_THRESHOLD: f64 = 0.1;
+const DEFAULT_MAX_DIFF_RATIO: f64 = 0.001;
+/// Changed pixels within this many pixels of each other share a box.
+const BOX_GRID: u32 = 16;
+const MAX_BOXES: usize = 20;
+const SSIM_WINDOW: u32 = 8;
+
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema, Serialize)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Region {
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && x < self.x.saturating_add(self.width)
+            && y < self.y.saturating_add(self.height)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Comparison {
+    width: u32,
+    height: u32,
+    /// Set when the images differ in size; only the overlap is compared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size_mismatch: Option<((u32, u32), (u32, u32))>,
+    changed_pixels: u64,
+    changed_ratio: f64,
+    ssim: f64,
+    boxes: Vec<Region>,
+}
+
+/// Blend a pixel over white and convert to YIQ.
+fn yiq(pixel: &Rgba<u8>) -> (f64, f64, f64) {
+    let a = pixel[3] as f64 / 255.0;
+    let blend = |c: u8| 255.0 + (c as f64 - 255.0) * a;
+    let (r, g, b) = (blend(pixel[0]), blend(pixel[1]), blend(pixel[2]));
+    (
+        r * 0.298_895_31 + g * 0.586_622_47 + b * 0.114_482_23,
+        r * 0.595_977_99 - g * 0.274_176_10 - b * 0.321_801_89,
+        r * 0.211_470_17 - g * 0.522_617_11 + b * 0.311_146_94,
+    )
+}
+
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    let (y1, i1, q1) = yiq(a);
+    let (y2, i2, q2) = yiq(b);
+    0.5053 * (y1 - y2).powi(2) + 0.299 * (i1 - i2).powi(2) + 0.1957 * (q1 - q2).powi(2)
+}
+
+fn luma(pixel: &Rgba<u8>) -> f64 {
+    yiq(pixel).0
+}
+
+/// Mean SSIM over non-overlapping windows of the luma channel.
+fn ssim(a: &RgbaImage, b: &RgbaImage, width: u32, height: u32) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let mut total = 0.0;
+    let mut windows = 0u64;
+    for wy in (0..height).step_by(SSIM_WINDOW as usize) {
+        for wx in (0..width).step_by(SSIM_WINDOW as usize) {
+            let (w, h) = (SSIM_WINDOW.min(width - wx), SSIM_WINDOW.min(height - wy));
+            let n = (w * h) as f64;
+            let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for y in wy..wy + h {
+                for x in wx..wx + w {
+                    let la = luma(a.get_pixel(x, y));
+                    let lb = luma(b.get_pixel(x, y));
+                    sa += la;
+                    sb += lb;
+                    saa += la * la;
+                    sbb += lb * lb;
+                    sab += la * lb;
+                }
+            }
+            let (ma, mb) = (sa / n, sb / n);
+            let va = saa / n - ma * ma;
+            let vb = sbb / n - mb * mb;
+            let cov = sab / n - ma * mb;
+            total += ((2.0 * ma * mb + C1) * (2.0 * cov + C2))
+                / ((ma * ma + mb * mb + C1) * (va + vb + C2));
+            windows += 1;
+        }
+    }
+    if windows == 0 {
+        1.0
+    } else {
+        total / windows as f64
+    }
+}
+
+/// Group changed pixels into bounding boxes: grid cells holding changes are
+/// joined with their 8 neighbours, and each group reports the exact extent
+/// of its changed pixels. Largest boxes first.
+fn bounding_boxes(changed: &[(u32, u32)], width: u32, height: u32) -> Vec<Region> {
+    let cols = width.div_ceil(BOX_GRID) as usize;
+    let rows = height.div_ceil(BOX_GRID) as usize;
+    // Per cell: pixel extent (min x, min y, max x, max y).
+    let mut cells: Vec<Option<(u32, u32, u32, u32)>> = vec![None; cols * rows];
+    for &(x, y) in changed {
+        let i = (y / BOX_GRID) as usize * cols + (x / BOX_GRID) as usize;
+        cells[i] = Some(match cells[i] {
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+
+    let mut seen = vec![false; cells.len()];
+    let mut boxes = Vec::new();
+    for start in 0..cells.len() {
+        if seen[start] || cells[start].is_none() {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut extent = cells[start].unwrap();
+        while let Some(i) = stack.pop() {
+            let (cx, cy) = ((i % cols) as i64, (i / cols) as i64);
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (nx, ny) = (cx + dx, cy + dy);
+                    if nx < 0 || ny < 0 || nx >= cols as i64 || ny >= rows as i64 {
+                        continue;
+                    }
+                    let j = ny as usize * cols + nx as usize;
+                    if seen[j] {
+                        continue;
+                    }
+                    if let Some((x0, y0, x1, y1)) = cells[j] {
+                        seen[j] = true;
+                        extent = (
+                            extent.0.min(x0),
+                            extent.1.min(y0),
+                            extent.2.max(x1),
+                            extent.3.max(y1),
+                        );
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        boxes.push(Region {
+            x: extent.0,
+            y: extent.1,
+            width: extent.2 - extent.0 + 1,
+            height: extent.3 - extent.1 + 1,
+        });
+    }
+    boxes.sort_by_key(|b| std::cmp::Reverse(b.width as u64 * b.height as u64));
+    boxes
+}
+
+/// Compare two images; returns the result and, when requested, a diff image.
+fn compare(
+    baseline: &RgbaImage,
+    current: &RgbaImage,
+    threshold: f64,
+    ignore: &[Region],
+    want_diff: bool,
+) -> (Comparison, Option<RgbaImage>) {
+    let width = baseline.width().min(current.width());
+    let height = baseline.height().min(current.height());
+    let max_delta = MAX_YIQ_DELTA * threshold * threshold;
+
+    let mut diff = want_diff.then(|| RgbaImage::new(width, height));
+    let mut changed = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let (a, b) = (baseline.get_pixel(x, y), current.get_pixel(x, y));
+            let ignored = ignore.iter().any(|r| r.contains(x, y));
+            let is_changed = !ignored && color_delta(a, b) > max_delta;
+            if is_changed {
+                changed.push((x, y));
+            }
+            if let Some(diff) = diff.as_mut() {
+                let pixel = if is_changed {
+                    Rgba([255, 0, 0, 255])
+                } else {
+                    // Faded grayscale baseline for context.
+                    let l = (255.0 - (255.0 - luma(a)) * 0.1).clamp(0.0, 255.0) as u8;
+                    if ignored {
+                        Rgba([l, l, 255, 255])
+                    } else {
+                        Rgba([l, l, l, 255])
+                    }
+                };
+                diff.put_pixel(x, y, pixel);
+            }
+        }
+    }
+
+    let size_mismatch = (baseline.dimensions() != current.dimensions())
+        .then(|| (baseline.dimensions(), current.dimensions()));
+    let area = (width as u64 * height as u64).max(1);
+    let comparison = Comparison {
+        width,
+        height,
+        size_mismatch,
+        changed_pixels: changed.len() as u64,
+        changed_ratio: changed.len() as f64 / area as f64,
+        ssim: ssim(baseline, current, width, height),
+        boxes: bounding_boxes(&changed, width, height),
+    };
+    (comparison, diff)
+}
+
+fn load(path: &Path) -> anyhow::Result<RgbaImage> {
+    Ok(image::open(path)
+        .map_err(|e| anyhow::anyhow!("Cannot open image {}: {}", path.display(), e))?
+        .to_rgba8())
+}
+
+/// Pixel and perceptual diff between a baseline and a current screenshot.
+pub struct CompareImages;
+
+#[derive(Deserialize, JsonSchema)]
+struct CompareArgs {
+    /// Baseline image (PNG, JPEG, WebP or GIF)
+    baseline: String,
+    /// Image to check against the baseline
+    current: String,
+    /// Per-pixel color difference (0-1) below which pixels count as equal
+    /// (default 0.1; lower is stricter)
+    #[serde(default)]
+    threshold: Option<f64>,
+    /// Fraction of changed pixels allowed before the check fails (default 0.001)
+    #[serde(default)]
+    max_diff_ratio: Option<f64>,
+    /// Regions to ignore, e.g. timestamps or animations
+    #[serde(default)]
+    ignore_regions: Vec<Region>,
+    /// Write a PNG highlighting changed pixels in red to this path
+    #[serde(default)]
+    diff_output: Option<String>,
+    /// Include the diff image in your context (requires diff_output)
+    #[serde(default)]
+    return_image: bool,
+    /// Return the result as JSON
+    #[serde(default)]
+    json: bool,
+}
+
+/// Images read and the diff image written.
+const COMPARE_PATH_ARGS: &[&str] = &["baseline", "current", "diff_output"];
+
+#[async_trait]
+impl Tool for CompareImages {
+    fn name(&self) -> &str {
+        "compare_images"
+    }
+
+    fn description(&self) -> &str {
+        "Compare two screenshots for visual regressions: counts changed pixels (with an anti-noise color threshold), reports a perceptual SSIM score and bounding boxes of changed areas, and passes or fails against max_diff_ratio. Can write a diff image with changes in red. Use with desktop_screenshot or browser screenshots before and after a UI change."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        CompareArgs::schema()
+    }
+
+    fn path_args(&self) -> &[&'static str] {
+        COMPARE_PATH_ARGS
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = CompareArgs::parse(args)?;
+        let threshold = args.threshold.unwrap_or(DEFAULT_THRESHOLD);
+        if !(0.0..=1.0).contains(&threshold) {
+            anyhow::bail!("threshold must be between 0 and 1");
+        }
+        let max_ratio = args.max_diff_ratio.unwrap_or(DEFAULT_MAX_DIFF_RATIO);
+        let baseline = resolve_path(&args.baseline, working_dir);
+        let current = resolve_path(&args.current, working_dir);
+        let diff_path: Option<PathBuf> = args
+            .diff_output
+            .as_deref()
+            .map(|p| resolve_path(p, working_dir));
+
+        let (result, diff_path) = tokio::task::spawn_blocking(move || {
+            let (baseline, current) = (load(&baseline)?, load(&current)?);
+            let (result, diff) = compare(
+                &baseline,
+                &current,
+                threshold,
+                &args.ignore_regions,
+                diff_path.is_some(),
+            );
+            if let (Some(path), Some(diff)) = (diff_path.as_ref(), diff) {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                diff.save_with_format(path, image::ImageFormat::Png)?;
+            }
+            anyhow::Ok((result, diff_path))
+        })
+        .await??;
+
+        let passed = result.size_mismatch.is_none() && result.changed_ratio <= max_ratio;
+        let vision = match (&diff_path, args.return_image) {
+            (Some(path), true) => format!("\n\n[VISION_IMAGE:file://{}]", path.display()),
+            _ => String::new(),
+        };
+        if args.json {
+            let mut value = serde_json::to_value(&result)?;
+            value["passed"] = Value::Bool(passed);
+            if let Some(path) = &diff_path {
+                value["diff_output"] = Value::String(path.display().to_string());
+            }
+            return Ok(format!(
+                "{}{}",
+                serde_json::to_string_pretty(&value)?,
+                vision
+            ));
+        }
+
+        let mut out = format!(
+            "{}: {} of {} pixels changed ({:.3}%, limit {:.3}%), SSIM {:.4}\n",
+            if passed { "PASS" } else { "FAIL" },
+            result.changed_pixels,
+            result.width as u64 * result.height as u64,
+            result.changed_ratio * 100.0,
+            max_ratio * 100.0,
+            result.ssim
+        );
+        if let Some(((bw, bh), (cw, ch))) = result.size_mismatch {
+            out.push_str(&format!(
+                "Size mismatch: baseline {}x{}, current {}x{}; compared the {}x{} overlap\n",
+                bw, bh, cw, ch, result.width, result.height
+            ));
+        }
+        if !result.boxes.is_empty() {
+            out.push_str(&format!("\nChanged regions ({}):\n", result.boxes.len()));
+            for b in result.boxes.iter().take(MAX_BOXES) {
+                out.push_str(&format!("- x={} y={} {}x{}\n", b.x, b.y, b.width, b.height));
+            }
+            if result.boxes.len() > MAX_BOXES {
+                out.push_str(&format!(
+                    "- … {} smaller regions\n",
+                    result.boxes.len() - MAX_BOXES
+                ));
+            }
+        }
+        if let Some(path) = &diff_path {
+            out.push_str(&format!("\nDiff image: {}\n", path.display()));
+        }
+        out.push_str(&vision);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page() -> RgbaImage {
+        RgbaImage::from_pixel(100, 80, Rgba([240, 240, 240, 255]))
+    }
+
+    fn paint(img: &mut RgbaImage, region: Region, color: Rgba<u8>) {
+        for y in region.y..region.y + region.height {
+            for x in region.x..region.x + region.width {
+                img.put_pixel(x, y, color);
+            }
+        }
+    }
+
+    #[test]
+    fn identical_and_noise_only_images_match() {
+        let baseline = page();
+        let mut current = page();
+        // Antialiasing-level noise stays under the default threshold.
+        current.put_pixel(10, 10, Rgba([236, 238, 240, 255]));
+        let (result, diff) = compare(&baseline, &current, DEFAULT_THRESHOLD, &[], false);
+        assert_eq!(result.changed_pixels, 0);
+        assert!(result.boxes.is_empty());
+        assert!(result.ssim > 0.999);
+        assert!(diff.is_none());
+    }
+
+    #[test]
+    fn reports_bounding_boxes_of_changes() {
+        let baseline = page();
+        let mut current = page();
+        let button = Region {
+            x: 20,
+            y: 30,
+            width: 12,
+            height: 6,
+        };
+        paint(&mut current, button, Rgba([30, 90, 200, 255]));
+        current.put_pixel(90, 5, Rgba([0, 0, 0, 255]));
+
+        let (result, diff) = compare(&baseline, &current, DEFAULT_THRESHOLD, &[], true);
+        assert_eq!(result.changed_pixels, 12 * 6 + 1);
+        assert_eq!(result.boxes.len(), 2);
+        let b = result.boxes[0];
+        assert_eq!((b.x, b.y, b.width, b.height), (20, 30, 12, 6));
+        assert!(result.ssim < 0.99);
+        assert_eq!(diff.unwrap().get_pixel(25, 32), &Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn ignore_regions_and_size_mismatch() {
+        let baseline = page();
+        let mut current = page();
+        let clock = Region {
+            x: 70,
+            y: 0,
+            width: 30,
+            height: 10,
+        };
+        paint(&mut current, clock, Rgba([0, 0, 0, 255]));
+        let (result, _) = compare(&baseline, &current, DEFAULT_THRESHOLD, &[clock], false);
+        assert_eq!(result.changed_pixels, 0);
+
+        let taller = RgbaImage::from_pixel(100, 90, Rgba([240, 240, 240, 255]));
+        let (result, _) = compare(&baseline, &taller, DEFAULT_THRESHOLD, &[], false);
+        assert_eq!(result.size_mismatch, Some(((100, 80), (100, 90))));
+        assert_eq!((result.width, result.height), (100, 80));
+    }
+}
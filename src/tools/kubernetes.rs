@@ -0,0 +1,533 @@
+//! Read-only Kubernetes inspection tools backed by `kubectl`.
+//!
+//! `k8s_get`, `k8s_describe`, `k8s_logs` and `k8s_events` let an SRE-style
+//! mission work out why a deployment is failing without write access to the
+//! cluster. Everything is scoped to the namespaces in the workspace's
+//! `SANDBOXED_SH_K8S_NAMESPACE` setting (comma-separated; the first is the
+//! default). Credentials come from `SANDBOXED_SH_KUBECONFIG`, a kubeconfig
+//! (plain or base64) kept in the template's encrypted env vars. It should
+//! belong to a service account with read-only RBAC: these tools only issue
+//! read verbs, but the kubeconfig decides what the cluster allows.
+//!
+//! The namespaces and the context are read from the server (see
+//! `guard::SERVER_SETTINGS`), so a mission can't widen them by rewriting its
+//! env. Secrets are never listed, and the kubeconfig is written to a temporary
+//! file for the duration of one command, outside the working directory.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::terminal::{run_workspace_shell, shell_quote, workspace_setting};
+use super::{safe_truncate_index, Tool, ToolArgs};
+
+/// Server-side setting with the allowed namespaces (first is the default).
+pub const NAMESPACE_SETTING: &str = "SANDBOXED_SH_K8S_NAMESPACE";
+/// Workspace setting with the kubeconfig contents (plain or base64).
+pub const KUBECONFIG_SETTING: &str = "SANDBOXED_SH_KUBECONFIG";
+/// Server-side setting selecting a kubeconfig context.
+pub const CONTEXT_SETTING: &str = "SANDBOXED_SH_K8S_CONTEXT";
+
+const KUBECTL_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_OUTPUT_CHARS: usize = 40_000;
+const DEFAULT_LOG_LINES: u32 = 200;
+const MAX_LOG_LINES: u32 = 2000;
+
+/// Namespaced resource kinds the tools may read.
+const READABLE_KINDS: &[&str] = &[
+    "pods",
+    "deployments",
+    "replicasets",
+    "statefulsets",
+    "daemonsets",
+    "jobs",
+    "cronjobs",
+    "services",
+    "endpoints",
+    "ingresses",
+    "configmaps",
+    "persistentvolumeclaims",
+    "horizontalpodautoscalers",
+    "poddisruptionbudgets",
+    "networkpolicies",
+    "serviceaccounts",
+    "events",
+];
+
+/// Short names and singulars accepted for [`READABLE_KINDS`].
+const KIND_ALIASES: &[(&str, &str)] = &[
+    ("po", "pods"),
+    ("deploy", "deployments"),
+    ("rs", "replicasets"),
+    ("sts", "statefulsets"),
+    ("ds", "daemonsets"),
+    ("cj", "cronjobs"),
+    ("svc", "services"),
+    ("ep", "endpoints"),
+    ("ing", "ingresses"),
+    ("ingress", "ingresses"),
+    ("cm", "configmaps"),
+    ("pvc", "persistentvolumeclaims"),
+    ("hpa", "horizontalpodautoscalers"),
+    ("pdb", "poddisruptionbudgets"),
+    ("netpol", "networkpolicies"),
+    ("networkpolicy", "networkpolicies"),
+    ("sa", "serviceaccounts"),
+    ("ev", "events"),
+];
+
+/// Canonical kind for `kind`, or an error for kinds that are not readable.
+fn resolve_kind(kind: &str) -> anyhow::Result<&'static str> {
+    let kind = kind.trim().to_ascii_lowercase();
+    // Drop an API group suffix such as `deployments.apps`.
+    let kind = kind.split('.').next().unwrap_or_default();
+    if let Some((_, canonical)) = KIND_ALIASES.iter().find(|(alias, _)| *alias == kind) {
+        return Ok(canonical);
+    }
+    READABLE_KINDS
+        .iter()
+        .find(|known| **known == kind || known.strip_suffix('s') == Some(kind))
+        .copied()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Kind '{}' is not readable. Allowed kinds: {}",
+                kind,
+                READABLE_KINDS.join(", ")
+            )
+        })
+}
+
+/// Whether `name` is a valid object name (DNS-1123 subdomain).
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 253
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && !name.starts_with('-')
+}
+
+fn check_name(name: &str) -> anyhow::Result<()> {
+    if !valid_name(name) {
+        anyhow::bail!("Invalid object name '{}'", name);
+    }
+    Ok(())
+}
+
+/// Namespaces the workspace allows, in configured order.
+async fn allowed_namespaces() -> anyhow::Result<Vec<String>> {
+    Ok(super::guard::setting(NAMESPACE_SETTING)
+        .await?
+        .unwrap_or_default()
+        .split(',')
+        .map(|ns| ns.trim().to_string())
+        .filter(|ns| !ns.is_empty())
+        .collect())
+}
+
+/// The namespace to use: `requested` if allowed, otherwise the default.
+fn pick_namespace(requested: Option<&str>, allowed: &[String]) -> anyhow::Result<String> {
+    let Some(default) = allowed.first() else {
+        anyhow::bail!(
+            "No Kubernetes namespace configured. Set {} (and {}) in the workspace env vars.",
+            NAMESPACE_SETTING,
+            KUBECONFIG_SETTING
+        );
+    };
+    match requested.map(str::trim).filter(|ns| !ns.is_empty()) {
+        None => Ok(default.clone()),
+        Some(ns) if allowed.iter().any(|a| a == ns) => Ok(ns.to_string()),
+        Some(ns) => anyhow::bail!(
+            "Namespace '{}' is not allowed for this workspace (allowed: {})",
+            ns,
+            allowed.join(", ")
+        ),
+    }
+}
+
+/// Kubeconfig YAML from the setting value, decoding base64 when needed.
+fn decode_kubeconfig(value: &str) -> String {
+    let compact: String = value.split_whitespace().collect();
+    match BASE64.decode(compact.as_bytes()) {
+        Ok(bytes) => match String::from_utf8(bytes) {
+            Ok(text) if text.contains("apiVersion") => text,
+            _ => value.to_string(),
+        },
+        Err(_) => value.to_string(),
+    }
+}
+
+/// Shell command running `kubectl` against the workspace cluster.
+///
+/// The kubeconfig is passed through the environment and written to a private
+/// temp file that is removed when kubectl exits.
+fn kubectl_command(namespace: &str, context: Option<&str>, args: &[String]) -> String {
+    let mut kubectl = vec![
+        "kubectl".to_string(),
+        format!("--namespace={}", namespace),
+        "--request-timeout=30s".to_string(),
+    ];
+    if let Some(context) = context {
+        kubectl.push(format!("--context={}", context));
+    }
+    kubectl.extend(args.iter().cloned());
+    let kubectl = kubectl
+        .iter()
+        .map(|a| shell_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "if [ -n \"${{SANDBOXED_SH_KUBECONFIG_DATA:-}}\" ]; then \
+         kc=$(mktemp) && chmod 600 \"$kc\" && printf '%s' \"$SANDBOXED_SH_KUBECONFIG_DATA\" > \"$kc\" \
+         && unset SANDBOXED_SH_KUBECONFIG_DATA && KUBECONFIG=\"$kc\" {kubectl}; rc=$?; rm -f \"$kc\"; exit $rc; \
+         else {kubectl}; fi"
+    )
+}
+
+/// Run a read-only kubectl command in the workspace and return its output.
+async fn kubectl(cwd: &Path, namespace: Option<&str>, args: Vec<String>) -> anyhow::Result<String> {
+    let namespace = pick_namespace(namespace, &allowed_namespaces().await?)?;
+    let context = super::guard::setting(CONTEXT_SETTING).await?;
+    let mut env = HashMap::new();
+    if let Some(config) = workspace_setting(KUBECONFIG_SETTING) {
+        env.insert(
+            "SANDBOXED_SH_KUBECONFIG_DATA".to_string(),
+            decode_kubeconfig(&config),
+        );
+    }
+    let command = kubectl_command(&namespace, context.as_deref(), &args);
+    let output = run_workspace_shell(cwd, &command, env, KUBECTL_TIMEOUT).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output.status.code() == Some(127) {
+            anyhow::bail!("kubectl is not installed in this workspace");
+        }
+        anyhow::bail!("kubectl {} failed: {}", args[0], stderr.trim());
+    }
+    let mut text = stdout.to_string();
+    if text.len() > MAX_OUTPUT_CHARS {
+        let end = safe_truncate_index(&text, MAX_OUTPUT_CHARS);
+        text.truncate(end);
+        text.push_str("\n[... output truncated; narrow the query with a name or selector ...]");
+    }
+    if text.trim().is_empty() {
+        text = format!("No resources found in namespace {}.", namespace);
+    }
+    Ok(text)
+}
+
+fn check_selector(selector: &str) -> anyhow::Result<()> {
+    let ok = !selector.starts_with('-')
+        && selector.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '.' | '-' | '_' | '/' | '=' | '!' | ',' | ' ' | '(' | ')')
+        });
+    if !ok {
+        anyhow::bail!("Invalid label selector '{}'", selector);
+    }
+    Ok(())
+}
+
+/// List or show resources (`kubectl get`).
+pub struct K8sGet;
+
+#[derive(Deserialize, JsonSchema)]
+struct GetArgs {
+    /// Resource kind, e.g. pods, deployments, svc
+    kind: String,
+    /// Object name (default: all objects of the kind)
+    #[serde(default)]
+    name: Option<String>,
+    /// Label selector, e.g. `app=api`
+    #[serde(default)]
+    selector: Option<String>,
+    /// Output format: wide (default), yaml or json
+    #[serde(default)]
+    output: Option<String>,
+    /// Namespace (must be one the workspace allows; default: the first)
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+fn get_argv(args: &GetArgs) -> anyhow::Result<Vec<String>> {
+    let kind = resolve_kind(&args.kind)?;
+    let mut argv = vec!["get".to_string(), kind.to_string()];
+    if let Some(name) = args.name.as_deref() {
+        check_name(name)?;
+        argv.push(name.to_string());
+    }
+    if let Some(selector) = args.selector.as_deref() {
+        check_selector(selector)?;
+        argv.push(format!("--selector={}", selector));
+    }
+    let output = args.output.as_deref().unwrap_or("wide");
+    if !matches!(output, "wide" | "yaml" | "json") {
+        anyhow::bail!("output must be wide, yaml or json");
+    }
+    argv.push(format!("--output={}", output));
+    Ok(argv)
+}
+
+#[async_trait]
+impl Tool for K8sGet {
+    fn name(&self) -> &str {
+        "k8s_get"
+    }
+
+    fn description(&self) -> &str {
+        "Read-only `kubectl get` in the workspace's Kubernetes namespace: list pods, deployments, services, etc. with status, or show one object as YAML/JSON. Secrets are not readable."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        GetArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = GetArgs::parse(args)?;
+        let argv = get_argv(&args)?;
+        kubectl(working_dir, args.namespace.as_deref(), argv).await
+    }
+}
+
+/// Describe an object (`kubectl describe`).
+pub struct K8sDescribe;
+
+#[derive(Deserialize, JsonSchema)]
+struct DescribeArgs {
+    /// Resource kind, e.g. pod, deployment
+    kind: String,
+    /// Object name
+    name: String,
+    /// Namespace (must be one the workspace allows; default: the first)
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+#[async_trait]
+impl Tool for K8sDescribe {
+    fn name(&self) -> &str {
+        "k8s_describe"
+    }
+
+    fn description(&self) -> &str {
+        "Read-only `kubectl describe` of one object in the workspace's Kubernetes namespace: conditions, container states, restart counts, probes and recent events. The first stop for a crashlooping pod."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        DescribeArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = DescribeArgs::parse(args)?;
+        let kind = resolve_kind(&args.kind)?;
+        check_name(&args.name)?;
+        let argv = vec!["describe".to_string(), kind.to_string(), args.name];
+        kubectl(working_dir, args.namespace.as_deref(), argv).await
+    }
+}
+
+/// Container logs (`kubectl logs`).
+pub struct K8sLogs;
+
+#[derive(Deserialize, JsonSchema)]
+struct LogsArgs {
+    /// Pod name, or `deployment/<name>` for one of its pods
+    pod: String,
+    /// Container name (for multi-container pods)
+    #[serde(default)]
+    container: Option<String>,
+    /// Logs of the previous (crashed) container instance
+    #[serde(default)]
+    previous: bool,
+    /// Number of most recent lines (default 200, max 2000)
+    #[serde(default)]
+    tail: Option<u32>,
+    /// Only logs newer than a duration, e.g. `10m`
+    #[serde(default)]
+    since: Option<String>,
+    /// Namespace (must be one the workspace allows; default: the first)
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+fn logs_argv(args: &LogsArgs) -> anyhow::Result<Vec<String>> {
+    let target = match args.pod.split_once('/') {
+        Some((kind, name)) => {
+            let kind = resolve_kind(kind)?;
+            if !matches!(
+                kind,
+                "pods" | "deployments" | "statefulsets" | "daemonsets" | "jobs" | "replicasets"
+            ) {
+                anyhow::bail!("Logs are available for pods and workloads, not {}", kind);
+            }
+            check_name(name)?;
+            format!("{}/{}", kind, name)
+        }
+        None => {
+            check_name(&args.pod)?;
+            args.pod.clone()
+        }
+    };
+    let tail = args
+        .tail
+        .unwrap_or(DEFAULT_LOG_LINES)
+        .clamp(1, MAX_LOG_LINES);
+    let mut argv = vec!["logs".to_string(), target, format!("--tail={}", tail)];
+    if let Some(container) = args.container.as_deref() {
+        check_name(container)?;
+        argv.push(format!("--container={}", container));
+    }
+    if args.previous {
+        argv.push("--previous".to_string());
+    }
+    if let Some(since) = args.since.as_deref() {
+        if since.is_empty() || !since.chars().all(|c| c.is_ascii_alphanumeric()) {
+            anyhow::bail!("Invalid since duration '{}'", since);
+        }
+        argv.push(format!("--since={}", since));
+    }
+    Ok(argv)
+}
+
+#[async_trait]
+impl Tool for K8sLogs {
+    fn name(&self) -> &str {
+        "k8s_logs"
+    }
+
+    fn description(&self) -> &str {
+        "Read container logs from a pod in the workspace's Kubernetes namespace. Use previous=true to see why a crashlooping container exited."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        LogsArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = LogsArgs::parse(args)?;
+        let argv = logs_argv(&args)?;
+        kubectl(working_dir, args.namespace.as_deref(), argv).await
+    }
+}
+
+/// Namespace events (`kubectl get events`).
+pub struct K8sEvents;
+
+#[derive(Deserialize, JsonSchema)]
+struct EventsArgs {
+    /// Only events about this object name
+    #[serde(default)]
+    object: Option<String>,
+    /// Only warnings
+    #[serde(default)]
+    warnings_only: bool,
+    /// Namespace (must be one the workspace allows; default: the first)
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+#[async_trait]
+impl Tool for K8sEvents {
+    fn name(&self) -> &str {
+        "k8s_events"
+    }
+
+    fn description(&self) -> &str {
+        "List recent events in the workspace's Kubernetes namespace, oldest first: scheduling failures, image pull errors, OOM kills, failed probes and back-off restarts."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        EventsArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = EventsArgs::parse(args)?;
+        let mut fields = Vec::new();
+        if let Some(object) = args.object.as_deref() {
+            check_name(object)?;
+            fields.push(format!("involvedObject.name={}", object));
+        }
+        if args.warnings_only {
+            fields.push("type=Warning".to_string());
+        }
+        let mut argv = vec![
+            "get".to_string(),
+            "events".to_string(),
+            "--sort-by=.lastTimestamp".to_string(),
+        ];
+        if !fields.is_empty() {
+            argv.push(format!("--field-selector={}", fields.join(",")));
+        }
+        kubectl(working_dir, args.namespace.as_deref(), argv).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_and_namespaces_are_restricted() {
+        assert_eq!(resolve_kind("deploy").unwrap(), "deployments");
+        assert_eq!(resolve_kind("Pod").unwrap(), "pods");
+        assert_eq!(resolve_kind("deployments.apps").unwrap(), "deployments");
+        assert!(resolve_kind("secrets").is_err());
+        assert!(resolve_kind("clusterrolebindings").is_err());
+
+        let allowed = vec!["shop".to_string(), "shop-staging".to_string()];
+        assert_eq!(pick_namespace(None, &allowed).unwrap(), "shop");
+        assert_eq!(
+            pick_namespace(Some("shop-staging"), &allowed).unwrap(),
+            "shop-staging"
+        );
+        assert!(pick_namespace(Some("kube-system"), &allowed).is_err());
+        assert!(pick_namespace(None, &[]).is_err());
+    }
+
+    #[test]
+    fn builds_get_and_logs_arguments() {
+        let args = GetArgs::parse(serde_json::json!({
+            "kind": "po",
+            "selector": "app=api",
+        }))
+        .unwrap();
+        assert_eq!(
+            get_argv(&args).unwrap(),
+            ["get", "pods", "--selector=app=api", "--output=wide"]
+        );
+
+        let args = LogsArgs::parse(serde_json::json!({
+            "pod": "deploy/api",
+            "previous": true,
+            "tail": 99999,
+        }))
+        .unwrap();
+        assert_eq!(
+            logs_argv(&args).unwrap(),
+            ["logs", "deployments/api", "--tail=2000", "--previous"]
+        );
+        let bad = LogsArgs::parse(serde_json::json!({"pod": "--all-namespaces"})).unwrap();
+        assert!(logs_argv(&bad).is_err());
+    }
+
+    #[test]
+    fn kubeconfig_is_decoded_and_kept_out_of_the_command() {
+        let yaml = "apiVersion: v1\nkind: Config\n";
+        assert_eq!(decode_kubeconfig(yaml), yaml);
+        assert_eq!(decode_kubeconfig(&BASE64.encode(yaml)), yaml);
+
+        let command = kubectl_command("shop", None, &["get".to_string(), "pods".to_string()]);
+        assert!(
+            command.contains("'kubectl' '--namespace=shop' '--request-timeout=30s' 'get' 'pods'")
+        );
+        assert!(command.contains("rm -f \"$kc\""));
+        assert!(!command.contains("apiVersion"));
+    }
+}
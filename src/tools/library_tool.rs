@@ -0,0 +1,601 @@
+//! Sandboxed runtime for library tools (`tool/*.ts`).
+//!
+//! Library tools are OpenCode-style modules:
+//!
+//! ```ts
+//! import { tool } from "@opencode-ai/plugin";
+//!
+//! export default tool({
+//!   description: "Count lines in a file",
+//!   args: { path: tool.schema.string().describe("File to count") },
+//!   async execute(args, context) {
+//!     const text = await context.fs.read(args.path);
+//!     return `${text.split("\n").length} lines`;
+//!   },
+//! });
+//! ```
+//!
+//! Mission preparation syncs them into `<mission dir>/.sandboxed-sh/tools/`, and
+//! workspace-mcp loads them from there. Each call runs in a fresh `deno` process
+//! with only these permissions:
+//! - read/write within the mission directory, except writes under
+//!   `.sandboxed-sh/` (the synced tools and the runner itself)
+//! - network access to the hosts in `SANDBOXED_SH_TOOL_FETCH_ALLOWLIST` (none by default)
+//! - no environment, subprocess, FFI or remote-import access
+//!
+//! `@opencode-ai/plugin` and `zod` resolve to a small shim that records argument
+//! schemas, which is how each tool's JSON schema is derived. Named exports become
+//! `<file>_<export>` tools, the default export is named after the file.
+//!
+//! Settings (workspace env vars or process env):
+//! - `SANDBOXED_SH_TOOL_RUNTIME` - Deno binary (default: `deno`)
+//! - `SANDBOXED_SH_TOOL_FETCH_ALLOWLIST` - Comma-separated hosts tools may
+//!   fetch; read from the server (see `guard::SERVER_SETTINGS`) so a mission
+//!   can't widen it
+//! - `SANDBOXED_SH_TOOL_TIMEOUT_SECS` - Per-call timeout (default: 60)
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::terminal::{run_workspace_shell_with_stdin, shell_quote, workspace_setting};
+use super::{safe_truncate_index, Tool};
+
+/// Where library tools are synced, relative to the mission directory.
+pub const LIBRARY_TOOLS_DIR: &str = ".sandboxed-sh/tools";
+
+/// Host scripts, relative to [`LIBRARY_TOOLS_DIR`].
+const RUNTIME_DIR: &str = ".runtime";
+
+/// Prefix of the line the runner prints its result on.
+const RESULT_MARKER: &str = "__SANDBOXED_TOOL_RESULT__";
+
+const DESCRIBE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_CALL_TIMEOUT_SECS: u64 = 60;
+
+/// Maximum tool log output (stderr) included in error messages.
+const MAX_LOG_CHARS: usize = 4_000;
+
+const RUNNER_JS: &str = r#"// sandboxed.sh library tool host: runner.js <describe|execute> <tool file>
+import { toJsonSchema } from "./plugin.js";
+
+const MARKER = "__SANDBOXED_TOOL_RESULT__";
+const encoder = new TextEncoder();
+const emit = (value) => {
+  Deno.stdout.writeSync(encoder.encode(`\n${MARKER}${JSON.stringify(value)}\n`));
+  Deno.exit(0);
+};
+
+// stdout is reserved for the result; tool logging goes to stderr.
+console.log = console.info = console.debug = (...args) => console.error(...args);
+
+const [mode, file] = Deno.args;
+const directory = Deno.cwd();
+const resolve = (p) => (p.startsWith("/") ? p : `${directory}/${p}`);
+
+try {
+  const input = mode === "execute"
+    ? JSON.parse(await new Response(Deno.stdin.readable).text())
+    : {};
+  const mod = await import(new URL(file, `file://${directory}/`).href);
+  const tools = Object.entries(mod).filter(
+    ([, value]) => value && typeof value.execute === "function",
+  );
+
+  if (mode === "describe") {
+    emit(tools.map(([name, t]) => ({
+      export: name,
+      description: typeof t.description === "string" ? t.description : "",
+      parameters: t.parameters ?? toJsonSchema(t.args ?? {}),
+    })));
+  }
+
+  const entry = tools.find(([name]) => name === input.export);
+  if (!entry) throw new Error(`Export '${input.export}' is not a tool`);
+  const context = {
+    directory,
+    worktree: directory,
+    missionID: input.mission_id ?? null,
+    sessionID: input.mission_id ?? null,
+    agent: "sandboxed",
+    abort: new AbortController().signal,
+    fs: {
+      read: (p) => Deno.readTextFile(resolve(p)),
+      write: (p, data) => Deno.writeTextFile(resolve(p), data),
+      list: async (p = ".") => {
+        const names = [];
+        for await (const e of Deno.readDir(resolve(p))) {
+          names.push(e.isDirectory ? `${e.name}/` : e.name);
+        }
+        return names.sort();
+      },
+    },
+    fetch: (...args) => fetch(...args),
+  };
+  const result = await entry[1].execute(input.args ?? {}, context);
+  emit({
+    ok: true,
+    output: typeof result === "string" ? result : JSON.stringify(result ?? null, null, 2),
+  });
+} catch (e) {
+  emit({ ok: false, error: String(e?.stack ?? e) });
+}
+"#;
+
+const PLUGIN_JS: &str = r#"// Stand-in for @opencode-ai/plugin and zod: records argument schemas as JSON Schema.
+class Schema {
+  constructor(json, optional = false) {
+    this.json = json;
+    this.isOptional = optional;
+  }
+  with(patch, optional = this.isOptional) {
+    return new Schema({ ...this.json, ...patch }, optional);
+  }
+  describe(description) { return this.with({ description }); }
+  optional() { return this.with({}, true); }
+  nullish() { return this.with({}, true); }
+  nullable() { return this.with({ nullable: true }); }
+  default(value) { return this.with({ default: value }, true); }
+  int() { return this.with({ type: "integer" }); }
+  min(n) { return this.with(bound("min", this.json.type, n)); }
+  max(n) { return this.with(bound("max", this.json.type, n)); }
+}
+
+function bound(kind, type, n) {
+  if (type === "string") return { [`${kind}Length`]: n };
+  if (type === "array") return { [`${kind}Items`]: n };
+  return { [kind === "min" ? "minimum" : "maximum"]: n };
+}
+
+// Other zod refinements (email(), url(), positive(), ...) are accepted and ignored.
+function wrap(schema) {
+  return new Proxy(schema, {
+    get(target, prop, receiver) {
+      if (prop in target) {
+        const value = Reflect.get(target, prop);
+        if (typeof value !== "function") return value;
+        return (...args) => {
+          const out = value.apply(target, args);
+          return out instanceof Schema ? wrap(out) : out;
+        };
+      }
+      if (typeof prop === "symbol" || prop === "then" || prop === "toJSON") return undefined;
+      return () => receiver;
+    },
+  });
+}
+
+const json = (s) => (s instanceof Schema ? s.json : {});
+const make = (schema) => wrap(new Schema(schema));
+
+export function toJsonSchema(args) {
+  if (args instanceof Schema) return json(args);
+  const properties = {};
+  const required = [];
+  for (const [key, value] of Object.entries(args)) {
+    properties[key] = json(value);
+    if (!(value instanceof Schema && value.isOptional)) required.push(key);
+  }
+  return { type: "object", properties, required };
+}
+
+export const z = {
+  string: () => make({ type: "string" }),
+  number: () => make({ type: "number" }),
+  boolean: () => make({ type: "boolean" }),
+  any: () => make({}),
+  unknown: () => make({}),
+  literal: (value) => make({ const: value }),
+  enum: (values) => make({ type: "string", enum: [...values] }),
+  array: (item) => make({ type: "array", items: json(item) }),
+  object: (shape) => make(toJsonSchema(shape)),
+  union: (options) => make({ anyOf: options.map(json) }),
+  record: (key, value) => make({ type: "object", additionalProperties: json(value ?? key) }),
+};
+z.coerce = { string: z.string, number: z.number, boolean: z.boolean };
+
+export function tool(definition) {
+  return definition;
+}
+tool.schema = z;
+"#;
+
+const ZOD_JS: &str = r#"import { z } from "./plugin.js";
+export { z };
+export default z;
+"#;
+
+const IMPORT_MAP: &str = r#"{
+  "imports": {
+    "@opencode-ai/plugin": "./plugin.js",
+    "zod": "./zod.js"
+  }
+}
+"#;
+
+/// Server-side setting with the hosts tools may fetch.
+pub const FETCH_ALLOWLIST_SETTING: &str = "SANDBOXED_SH_TOOL_FETCH_ALLOWLIST";
+
+/// Runtime settings resolved from workspace env vars and, for the fetch
+/// allowlist, the server.
+struct RuntimeSettings {
+    deno: String,
+    fetch_allowlist: Vec<String>,
+    timeout: Duration,
+}
+
+impl RuntimeSettings {
+    async fn load() -> anyhow::Result<Self> {
+        let deno = workspace_setting("SANDBOXED_SH_TOOL_RUNTIME")
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| "deno".to_string());
+        let fetch_allowlist = super::guard::setting(FETCH_ALLOWLIST_SETTING)
+            .await?
+            .map(|v| parse_allowlist(&v))
+            .unwrap_or_default();
+        let timeout = workspace_setting("SANDBOXED_SH_TOOL_TIMEOUT_SECS")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_CALL_TIMEOUT_SECS);
+        Ok(Self {
+            deno,
+            fetch_allowlist,
+            timeout: Duration::from_secs(timeout),
+        })
+    }
+
+    /// Shell command running the host script for `file` (relative to the mission dir).
+    fn command(&self, mode: &str, file: &str) -> String {
+        let runtime = format!("{}/{}", LIBRARY_TOOLS_DIR, RUNTIME_DIR);
+        let mut parts = vec![
+            shell_quote(&self.deno),
+            "run".to_string(),
+            "--quiet".to_string(),
+            "--no-prompt".to_string(),
+            "--no-config".to_string(),
+            "--no-lock".to_string(),
+            "--no-remote".to_string(),
+            format!("--import-map={}/import_map.json", runtime),
+            "--allow-read=.".to_string(),
+            "--allow-write=.".to_string(),
+            "--deny-write=.sandboxed-sh".to_string(),
+        ];
+        if !self.fetch_allowlist.is_empty() {
+            parts.push(shell_quote(&format!(
+                "--allow-net={}",
+                self.fetch_allowlist.join(",")
+            )));
+        }
+        parts.push(format!("{}/runner.js", runtime));
+        parts.push(mode.to_string());
+        parts.push(shell_quote(file));
+        parts.join(" ")
+    }
+}
+
+/// Parse a comma-separated host allowlist, dropping entries Deno would reject.
+fn parse_allowlist(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|h| {
+            h.trim()
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+        })
+        .map(|h| h.trim_end_matches('/'))
+        .filter(|h| {
+            !h.is_empty()
+                && h.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+async fn write_runtime_files(working_dir: &Path) -> anyhow::Result<()> {
+    let dir = working_dir.join(LIBRARY_TOOLS_DIR).join(RUNTIME_DIR);
+    tokio::fs::create_dir_all(&dir).await?;
+    for (name, content) in [
+        ("runner.js", RUNNER_JS),
+        ("plugin.js", PLUGIN_JS),
+        ("zod.js", ZOD_JS),
+        ("import_map.json", IMPORT_MAP),
+    ] {
+        tokio::fs::write(dir.join(name), content).await?;
+    }
+    Ok(())
+}
+
+/// Run the host script and return its result payload.
+async fn run_host(
+    working_dir: &Path,
+    settings: &RuntimeSettings,
+    mode: &str,
+    file: &str,
+    input: Option<Value>,
+    timeout: Duration,
+) -> anyhow::Result<Value> {
+    let mut env = HashMap::new();
+    env.insert("NO_COLOR".to_string(), "1".to_string());
+    let output = run_workspace_shell_with_stdin(
+        working_dir,
+        &settings.command(mode, file),
+        env,
+        input.map(|v| v.to_string()),
+        timeout,
+    )
+    .await?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let result = stdout
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(RESULT_MARKER))
+        .map(serde_json::from_str::<Value>)
+        .transpose()?;
+    match result {
+        Some(value) => Ok(value),
+        None => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "Tool runtime exited without a result ({}): {}",
+                output.status,
+                tail(&stderr)
+            )
+        }
+    }
+}
+
+/// Last `MAX_LOG_CHARS` bytes of `s`.
+fn tail(s: &str) -> &str {
+    let s = s.trim();
+    if s.len() <= MAX_LOG_CHARS {
+        return s;
+    }
+    let mut start = s.len() - MAX_LOG_CHARS;
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+    &s[start..]
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolDescription {
+    export: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    parameters: Value,
+}
+
+/// Tool name for an export of `stem` (OpenCode naming: default export = file name).
+fn tool_name(stem: &str, export: &str) -> Option<String> {
+    let name = if export == "default" {
+        stem.to_string()
+    } else {
+        format!("{}_{}", stem, export)
+    };
+    name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        .then_some(name)
+}
+
+/// A library tool executed by the sandboxed script runtime.
+pub struct LibraryTool {
+    name: String,
+    description: String,
+    schema: Value,
+    /// Source file relative to the mission directory.
+    file: String,
+    export: String,
+}
+
+#[async_trait]
+impl Tool for LibraryTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let settings = RuntimeSettings::load().await?;
+        write_runtime_files(working_dir).await?;
+        let input = json!({
+            "export": self.export,
+            "args": args,
+            "mission_id": std::env::var("SANDBOXED_SH_MISSION_ID").ok(),
+        });
+        let result = run_host(
+            working_dir,
+            &settings,
+            "execute",
+            &self.file,
+            Some(input),
+            settings.timeout,
+        )
+        .await?;
+
+        if result["ok"].as_bool() == Some(true) {
+            let output = result["output"].as_str().unwrap_or("").to_string();
+            let idx = safe_truncate_index(&output, 50_000);
+            Ok(output[..idx].to_string())
+        } else {
+            anyhow::bail!(
+                "{}",
+                result["error"].as_str().unwrap_or("Library tool failed")
+            )
+        }
+    }
+}
+
+/// Library tool sources synced into `working_dir`, sorted by file name.
+fn library_tool_files(working_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(working_dir.join(LIBRARY_TOOLS_DIR)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("ts" | "js")))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Load the library tools synced into `working_dir` (see [`LIBRARY_TOOLS_DIR`]):
+/// script tools followed by WASM plugins (see [`super::wasm_tool`]).
+pub async fn load_library_tools(working_dir: &Path) -> Vec<Arc<dyn Tool>> {
+    let mut tools = load_script_tools(working_dir).await;
+    tools.extend(super::wasm_tool::load_wasm_tools(working_dir).await);
+    tools
+}
+
+/// Load the script tools synced into `working_dir`.
+///
+/// Tools that fail to load are logged and skipped; if the Deno runtime is not
+/// installed, no script tools are loaded.
+async fn load_script_tools(working_dir: &Path) -> Vec<Arc<dyn Tool>> {
+    let files = library_tool_files(working_dir);
+    if files.is_empty() {
+        return Vec::new();
+    }
+
+    let settings = match RuntimeSettings::load().await {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load library tool settings; skipping script tools");
+            return Vec::new();
+        }
+    };
+    let probe = format!("command -v {} >/dev/null", shell_quote(&settings.deno));
+    let available =
+        run_workspace_shell_with_stdin(working_dir, &probe, HashMap::new(), None, DESCRIBE_TIMEOUT)
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+    if !available {
+        tracing::warn!(
+            runtime = %settings.deno,
+            count = files.len(),
+            "Library tools found but the Deno runtime is not installed; skipping them"
+        );
+        return Vec::new();
+    }
+    if let Err(e) = write_runtime_files(working_dir).await {
+        tracing::warn!(error = %e, "Failed to write library tool runtime files");
+        return Vec::new();
+    }
+
+    let mut tools: Vec<Arc<dyn Tool>> = Vec::new();
+    for path in files {
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let file = format!("{}/{}", LIBRARY_TOOLS_DIR, file_name);
+
+        let described = run_host(
+            working_dir,
+            &settings,
+            "describe",
+            &file,
+            None,
+            DESCRIBE_TIMEOUT,
+        )
+        .await
+        .and_then(|v| {
+            if v.get("ok").and_then(|ok| ok.as_bool()) == Some(false) {
+                anyhow::bail!("{}", v["error"].as_str().unwrap_or("describe failed"));
+            }
+            Ok(serde_json::from_value::<Vec<ToolDescription>>(v)?)
+        });
+        let descriptions = match described {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::warn!(file = %file, error = %e, "Failed to load library tool");
+                continue;
+            }
+        };
+
+        for desc in descriptions {
+            let Some(name) = tool_name(stem, &desc.export) else {
+                tracing::warn!(file = %file, export = %desc.export, "Invalid library tool name");
+                continue;
+            };
+            let schema = if desc.parameters.is_object() {
+                desc.parameters
+            } else {
+                json!({ "type": "object", "properties": {} })
+            };
+            tools.push(Arc::new(LibraryTool {
+                name,
+                description: desc.description,
+                schema,
+                file: file.clone(),
+                export: desc.export,
+            }));
+        }
+    }
+
+    tracing::info!(
+        count = tools.len(),
+        tools = ?tools.iter().map(|t| t.name().to_string()).collect::<Vec<_>>(),
+        "Loaded library tools"
+    );
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_name() {
+        assert_eq!(tool_name("count", "default").as_deref(), Some("count"));
+        assert_eq!(tool_name("db", "query").as_deref(), Some("db_query"));
+        assert_eq!(tool_name("bad name", "default"), None);
+    }
+
+    #[test]
+    fn test_parse_allowlist() {
+        assert_eq!(
+            parse_allowlist("api.github.com, https://example.com/ ,,bad host,localhost:8080"),
+            vec!["api.github.com", "example.com", "localhost:8080"]
+        );
+    }
+
+    #[test]
+    fn test_command_permissions() {
+        let settings = RuntimeSettings {
+            deno: "deno".to_string(),
+            fetch_allowlist: Vec::new(),
+            timeout: Duration::from_secs(1),
+        };
+        let cmd = settings.command("describe", ".sandboxed-sh/tools/count.ts");
+        assert!(cmd.contains("--allow-read=. --allow-write=. --deny-write=.sandboxed-sh"));
+        assert!(cmd.contains("--no-remote"));
+        assert!(!cmd.contains("--allow-net"));
+        assert!(!cmd.contains("--allow-env") && !cmd.contains("--allow-run"));
+
+        let settings = RuntimeSettings {
+            fetch_allowlist: vec!["api.github.com".to_string()],
+            ..settings
+        };
+        assert!(settings
+            .command("execute", "x.ts")
+            .contains("--allow-net=api.github.com"));
+    }
+}
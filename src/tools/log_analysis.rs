@@ -0,0 +1,480 @@
+//! `analyze_logs`: a digest of a large log file.
+//!
+//! The file is streamed line by line, so multi-hundred-MB logs never sit in
+//! memory. Lines are grouped into templates in the style of the Drain log
+//! parser: timestamps are stripped, tokens containing digits become `<*>`,
+//! and a line joins the most similar template with the same token count and
+//! first token, widening it where they differ. The digest lists the largest
+//! clusters with counts, first/last timestamps and a sample line, plus a time
+//! histogram of matching lines.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use async_trait::async_trait;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{resolve_path_simple as resolve_path, Tool, ToolArgs};
+
+/// Lines longer than this are cut before mining.
+const MAX_LINE_CHARS: usize = 2000;
+/// Templates kept in memory; later unmatched lines are counted as "other".
+const MAX_CLUSTERS: usize = 5000;
+/// Share of equal tokens for a line to join a template.
+const SIMILARITY: f64 = 0.5;
+const DEFAULT_TOP: usize = 20;
+const MAX_TOP: usize = 200;
+/// Histogram buckets before rolling up to a coarser granularity.
+const MAX_BUCKETS: usize = 60;
+const WILDCARD: &str = "<*>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+fn level_of(line: &str) -> Level {
+    let lower = line.to_ascii_lowercase();
+    if [
+        "error",
+        "fatal",
+        "panic",
+        "exception",
+        "traceback",
+        "critical",
+    ]
+    .iter()
+    .any(|k| lower.contains(k))
+    {
+        Level::Error
+    } else if lower.contains("warn") {
+        Level::Warn
+    } else {
+        Level::Info
+    }
+}
+
+fn timestamp_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^\[?(?:(\d{4}-\d{2}-\d{2})[T ](\d{2}:\d{2})(?::\d{2}(?:[.,]\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?|([A-Z][a-z]{2} [ \d]\d) (\d{2}:\d{2}):\d{2})\]?\s*",
+        )
+        .expect("valid timestamp regex")
+    })
+}
+
+/// Split a leading ISO-8601 or syslog timestamp off `line`.
+///
+/// Returns the minute the line was logged (`YYYY-MM-DD HH:MM` or
+/// `Mon DD HH:MM`) and the rest of the line.
+fn split_timestamp(line: &str) -> (Option<String>, &str) {
+    match timestamp_regex().captures(line) {
+        Some(caps) => {
+            let minute = match (caps.get(1), caps.get(2), caps.get(3), caps.get(4)) {
+                (Some(date), Some(time), _, _) | (_, _, Some(date), Some(time)) => {
+                    format!("{} {}", date.as_str(), time.as_str())
+                }
+                _ => return (None, line),
+            };
+            (Some(minute), &line[caps.get(0).map_or(0, |m| m.end())..])
+        }
+        None => (None, line),
+    }
+}
+
+/// Template tokens for a message: tokens containing digits become `<*>`.
+fn tokenize(message: &str) -> Vec<String> {
+    message
+        .split_whitespace()
+        .map(|token| {
+            if token.chars().any(|c| c.is_ascii_digit()) {
+                WILDCARD.to_string()
+            } else {
+                token.to_string()
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct Cluster {
+    template: String,
+    count: u64,
+    level: Level,
+    sample: String,
+    first_seen: Option<String>,
+    last_seen: Option<String>,
+    #[serde(skip)]
+    tokens: Vec<String>,
+}
+
+#[derive(Default)]
+struct Miner {
+    clusters: Vec<Cluster>,
+    /// (token count, first token) -> cluster indexes.
+    groups: HashMap<(usize, String), Vec<usize>>,
+    /// Lines that matched no template once [`MAX_CLUSTERS`] was reached.
+    other: u64,
+}
+
+impl Miner {
+    fn add(&mut self, tokens: Vec<String>, line: &str, minute: Option<&str>, level: Level) {
+        let key = (tokens.len(), tokens.first().cloned().unwrap_or_default());
+        let candidates = self.groups.entry(key).or_default();
+        let best = candidates
+            .iter()
+            .map(|&idx| {
+                let template = &self.clusters[idx].tokens;
+                let equal = template
+                    .iter()
+                    .zip(&tokens)
+                    .filter(|(a, b)| *a == WILDCARD || a == b)
+                    .count();
+                (idx, equal as f64 / tokens.len().max(1) as f64)
+            })
+            .filter(|(_, score)| *score >= SIMILARITY)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        let idx = match best {
+            Some((idx, _)) => idx,
+            None if self.clusters.len() < MAX_CLUSTERS => {
+                candidates.push(self.clusters.len());
+                self.clusters.push(Cluster {
+                    template: String::new(),
+                    count: 0,
+                    level,
+                    sample: line.to_string(),
+                    first_seen: minute.map(str::to_string),
+                    last_seen: None,
+                    tokens: tokens.clone(),
+                });
+                self.clusters.len() - 1
+            }
+            None => {
+                self.other += 1;
+                return;
+            }
+        };
+        let cluster = &mut self.clusters[idx];
+        for (slot, token) in cluster.tokens.iter_mut().zip(&tokens) {
+            if slot != token {
+                *slot = WILDCARD.to_string();
+            }
+        }
+        cluster.count += 1;
+        cluster.level = cluster.level.max(level);
+        if minute.is_some() {
+            if cluster.first_seen.is_none() {
+                cluster.first_seen = minute.map(str::to_string);
+            }
+            cluster.last_seen = minute.map(str::to_string);
+        }
+    }
+
+    /// Clusters sorted by level then count, with templates rendered.
+    fn finish(mut self) -> (Vec<Cluster>, u64) {
+        for cluster in &mut self.clusters {
+            cluster.template = cluster.tokens.join(" ");
+        }
+        self.clusters
+            .sort_by(|a, b| b.level.cmp(&a.level).then(b.count.cmp(&a.count)));
+        (self.clusters, self.other)
+    }
+}
+
+/// Roll minute buckets up to hours, then days, until few enough remain.
+fn roll_up(minutes: BTreeMap<String, u64>) -> (&'static str, BTreeMap<String, u64>) {
+    if minutes.len() <= MAX_BUCKETS {
+        return ("minute", minutes);
+    }
+    // "YYYY-MM-DD HH:MM" / "Mon DD HH:MM": drop ":MM", then " HH".
+    for (name, cut) in [("hour", 3), ("day", 6)] {
+        let mut rolled = BTreeMap::new();
+        for (key, count) in &minutes {
+            let prefix = &key[..key.len().saturating_sub(cut)];
+            *rolled.entry(prefix.to_string()).or_insert(0) += count;
+        }
+        if rolled.len() <= MAX_BUCKETS || name == "day" {
+            return (name, rolled);
+        }
+    }
+    unreachable!("the day roll-up always returns")
+}
+
+#[derive(Debug, Serialize)]
+struct Digest {
+    bytes_read: u64,
+    lines: u64,
+    matched: u64,
+    errors: u64,
+    warnings: u64,
+    clusters: Vec<Cluster>,
+    unclustered: u64,
+    bucket: &'static str,
+    histogram: BTreeMap<String, u64>,
+}
+
+/// Analyze log lines from `reader`.
+fn analyze<R: BufRead>(
+    mut reader: R,
+    min_level: Level,
+    filter: Option<&Regex>,
+) -> std::io::Result<Digest> {
+    let mut miner = Miner::default();
+    let mut minutes = BTreeMap::new();
+    let (mut bytes_read, mut lines, mut matched, mut errors, mut warnings) = (0, 0, 0, 0, 0);
+    let mut buf = Vec::new();
+    let mut last_minute: Option<String> = None;
+
+    loop {
+        buf.clear();
+        let n = reader.read_until(b'\n', &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        bytes_read += n as u64;
+        lines += 1;
+        let text = String::from_utf8_lossy(&buf);
+        let mut line = text.trim_end();
+        if line.len() > MAX_LINE_CHARS {
+            line = &line[..super::safe_truncate_index(line, MAX_LINE_CHARS)];
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let (minute, message) = split_timestamp(line);
+        // Continuation lines (stack frames) inherit the previous timestamp.
+        if minute.is_some() {
+            last_minute = minute;
+        }
+        let level = level_of(line);
+        match level {
+            Level::Error => errors += 1,
+            Level::Warn => warnings += 1,
+            Level::Info => {}
+        }
+        if level < min_level || filter.is_some_and(|re| !re.is_match(line)) {
+            continue;
+        }
+        matched += 1;
+        if let Some(minute) = &last_minute {
+            *minutes.entry(minute.clone()).or_insert(0) += 1;
+        }
+        miner.add(tokenize(message), line, last_minute.as_deref(), level);
+    }
+
+    let (clusters, unclustered) = miner.finish();
+    let (bucket, histogram) = roll_up(minutes);
+    Ok(Digest {
+        bytes_read,
+        lines,
+        matched,
+        errors,
+        warnings,
+        clusters,
+        unclustered,
+        bucket,
+        histogram,
+    })
+}
+
+fn render(path: &str, digest: &Digest, top: usize) -> String {
+    let mut out = format!(
+        "# Log digest: {}\n\n{} lines ({} MiB), {} errors, {} warnings, {} lines analyzed in {} clusters",
+        path,
+        digest.lines,
+        digest.bytes_read / (1024 * 1024),
+        digest.errors,
+        digest.warnings,
+        digest.matched,
+        digest.clusters.len()
+    );
+    if digest.unclustered > 0 {
+        out.push_str(&format!(" (+{} unclustered)", digest.unclustered));
+    }
+    out.push_str(".\n\n## Top clusters\n\n");
+    for cluster in digest.clusters.iter().take(top) {
+        out.push_str(&format!(
+            "- **{}x** [{:?}] `{}`\n",
+            cluster.count, cluster.level, cluster.template
+        ));
+        if let (Some(first), Some(last)) = (&cluster.first_seen, &cluster.last_seen) {
+            out.push_str(&format!("  - seen {} .. {}\n", first, last));
+        }
+        out.push_str(&format!("  - e.g. {}\n", cluster.sample));
+    }
+    if digest.clusters.len() > top {
+        out.push_str(&format!(
+            "\n({} more clusters; raise `top` to see them)\n",
+            digest.clusters.len() - top
+        ));
+    }
+
+    if !digest.histogram.is_empty() {
+        out.push_str(&format!("\n## Per {}\n\n```\n", digest.bucket));
+        let max = digest.histogram.values().copied().max().unwrap_or(1).max(1);
+        for (bucket, count) in &digest.histogram {
+            let bar = "#".repeat(((count * 40).div_ceil(max)) as usize);
+            out.push_str(&format!("{:<16} {:>8} {}\n", bucket, count, bar));
+        }
+        out.push_str("```\n");
+    }
+    out
+}
+
+/// Summarize a large log file into error clusters and a time histogram.
+pub struct AnalyzeLogs;
+
+#[derive(Deserialize, JsonSchema)]
+struct AnalyzeArgs {
+    /// Log file path
+    path: String,
+    /// Minimum level to cluster: error, warn (default) or all
+    #[serde(default)]
+    level: Option<String>,
+    /// Only analyze lines matching this regex
+    #[serde(default)]
+    filter: Option<String>,
+    /// Clusters to list (default 20, max 200)
+    #[serde(default)]
+    top: Option<usize>,
+    /// Only analyze the last N bytes of the file
+    #[serde(default)]
+    tail_bytes: Option<u64>,
+    /// Return the digest as JSON
+    #[serde(default)]
+    json: bool,
+}
+
+#[async_trait]
+impl Tool for AnalyzeLogs {
+    fn name(&self) -> &str {
+        "analyze_logs"
+    }
+
+    fn description(&self) -> &str {
+        "Digest a log file of any size: groups similar lines into templates (numbers and IDs become <*>) with counts, first/last timestamps and a sample line, and shows a time histogram. Use it instead of read_file for large logs, then grep_search for the interesting templates."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        AnalyzeArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = AnalyzeArgs::parse(args)?;
+        let path = resolve_path(&args.path, working_dir);
+        let min_level = match args.level.as_deref().unwrap_or("warn") {
+            "error" => Level::Error,
+            "warn" | "warning" => Level::Warn,
+            "all" | "info" => Level::Info,
+            other => anyhow::bail!("level must be error, warn or all, got '{}'", other),
+        };
+        let filter = args
+            .filter
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid filter regex: {}", e))?;
+        let top = args.top.unwrap_or(DEFAULT_TOP).clamp(1, MAX_TOP);
+        let tail_bytes = args.tail_bytes;
+
+        let digest = tokio::task::spawn_blocking(move || -> anyhow::Result<Digest> {
+            let mut file = std::fs::File::open(&path)
+                .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", path.display(), e))?;
+            let len = file.metadata()?.len();
+            let mut skip_partial = false;
+            if let Some(tail) = tail_bytes.filter(|&t| t < len) {
+                file.seek(SeekFrom::Start(len - tail))?;
+                skip_partial = true;
+            }
+            let mut reader = BufReader::with_capacity(1 << 20, file);
+            if skip_partial {
+                // Drop the partial first line after seeking.
+                let mut discard = Vec::new();
+                reader.by_ref().read_until(b'\n', &mut discard)?;
+            }
+            Ok(analyze(reader, min_level, filter.as_ref())?)
+        })
+        .await??;
+
+        if args.json {
+            Ok(serde_json::to_string_pretty(&digest)?)
+        } else {
+            Ok(render(&args.path, &digest, top))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+2024-05-01T10:00:01Z INFO server started on port 8080
+2024-05-01T10:00:05Z ERROR request 4411 failed: connection refused to 10.0.0.7:5432
+2024-05-01T10:01:10Z ERROR request 4412 failed: connection refused to 10.0.0.7:5432
+    at db.connect (pool.js:41)
+2024-05-01T10:02:00.123+02:00 WARN slow query took 1532ms
+2024-05-01T10:02:30Z ERROR request 4413 failed: connection refused to 10.0.0.8:5432
+";
+
+    #[test]
+    fn strips_timestamps_and_masks_numbers() {
+        let (minute, rest) = split_timestamp("2024-05-01T10:02:00.123+02:00 WARN slow query");
+        assert_eq!(minute.as_deref(), Some("2024-05-01 10:02"));
+        assert_eq!(rest, "WARN slow query");
+        let (minute, rest) = split_timestamp("[2024-05-01 10:02:00,5] boom");
+        assert_eq!(minute.as_deref(), Some("2024-05-01 10:02"));
+        assert_eq!(rest, "boom");
+        let (minute, _) = split_timestamp("Mar  4 09:15:02 host sshd[12]: ok");
+        assert_eq!(minute.as_deref(), Some("Mar  4 09:15"));
+        assert_eq!(split_timestamp("no time here").0, None);
+        assert_eq!(tokenize("took 1532ms id=a1"), ["took", "<*>", "<*>"]);
+    }
+
+    #[test]
+    fn clusters_errors_with_counts() {
+        let digest = analyze(LOG.as_bytes(), Level::Warn, None).unwrap();
+        assert_eq!(digest.lines, 6);
+        assert_eq!(digest.errors, 3);
+        let top = &digest.clusters[0];
+        assert_eq!(top.count, 3);
+        assert_eq!(top.level, Level::Error);
+        assert_eq!(
+            top.template,
+            "ERROR request <*> failed: connection refused to <*>"
+        );
+        assert_eq!(top.first_seen.as_deref(), Some("2024-05-01 10:00"));
+        assert_eq!(top.last_seen.as_deref(), Some("2024-05-01 10:02"));
+        // The WARN line and the stack frame are their own clusters.
+        assert!(digest
+            .clusters
+            .iter()
+            .any(|c| c.template == "WARN slow query took <*>"));
+        assert_eq!(digest.histogram["2024-05-01 10:02"], 2);
+
+        let text = render("app.log", &digest, 5);
+        assert!(text.contains("**3x** [Error]"));
+        assert!(text.contains("## Per minute"));
+    }
+
+    #[test]
+    fn histogram_rolls_up_to_hours() {
+        let minutes: BTreeMap<String, u64> = (0..120)
+            .map(|m| (format!("2024-05-01 {:02}:{:02}", 10 + m / 60, m % 60), 1))
+            .collect();
+        let (bucket, rolled) = roll_up(minutes);
+        assert_eq!(bucket, "hour");
+        assert_eq!(rolled.len(), 2);
+        assert_eq!(rolled["2024-05-01 10"], 60);
+    }
+}
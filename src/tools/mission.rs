@@ -205,6 +205,37 @@ IMPORTANT: Use 'blocked' or 'not_feasible' instead of producing fake/placeholder
             }
         }
 
+        // Don't deliver credentials: scan uncommitted changes and deliverables.
+        if status == MissionStatusValue::Completed && super::secret_scan::scanning_enabled() {
+            let findings = super::secret_scan::scan_mission_changes(working_dir).await;
+            if !findings.is_empty() {
+                tracing::warn!(
+                    count = findings.len(),
+                    "complete_mission blocked by secrets scan"
+                );
+                return Ok(format!(
+                    "⚠️ {}\n\nThe mission status was not changed. Call complete_mission again once the secrets are removed.",
+                    super::secret_scan::format_findings(&findings, "mission completion")
+                ));
+            }
+        }
+
+        // Surface license violations in dependencies the agent added.
+        let license_report = if status == MissionStatusValue::Completed
+            && crate::dependency_audit::audit_enabled()
+        {
+            let allowlist = crate::dependency_audit::LicenseAllowlist::from_setting();
+            match crate::dependency_audit::audit(working_dir, &allowlist).await {
+                Ok(records) => crate::dependency_audit::format_violations(&records),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Dependency audit failed");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         // Build enhanced summary for blocked/not_feasible
         let enhanced_summary = if matches!(
             status,
@@ -224,7 +255,11 @@ IMPORTANT: Use 'blocked' or 'not_feasible' instead of producing fake/placeholder
             }
             Some(parts.join("\n"))
         } else {
-            args.summary.clone()
+            match (args.summary.clone(), license_report.as_deref()) {
+                (Some(summary), Some(report)) => Some(format!("{}\n\n{}", summary, report)),
+                (None, Some(report)) => Some(report.to_string()),
+                (summary, None) => summary,
+            }
         };
 
         // Log blocked/not_feasible status clearly
@@ -12,23 +12,78 @@
 //! This encourages agents to stay within their assigned workspace while preserving
 //! flexibility for tasks that require broader access.
 
+pub mod alias;
+pub mod args;
+pub mod audit;
+pub mod bundles;
 mod composite;
+mod containers;
+mod data;
 pub mod desktop;
 mod directory;
+mod docs;
+mod environment;
 mod file_ops;
+pub mod git;
+mod github;
+pub mod guard;
+pub mod host_access;
+mod html_markdown;
+mod image_diff;
 mod index;
+mod kubernetes;
+pub mod library_tool;
+mod log_analysis;
 pub mod mission;
+mod notebook;
+mod ocr;
+mod packages;
+pub mod plan;
+mod reference_repo;
 mod search;
+mod secret_scan;
+mod structured;
 pub mod terminal;
+pub(crate) mod terminal_stream;
+mod terraform;
+mod tracker;
 mod ui;
+mod wasm_tool;
+mod watch;
 mod web;
 
+pub use args::{ArgsError, ToolArgs};
+pub use audit::{RunAuditCheck, SubmitAuditReport};
+pub use composite::{GhPrOpen, ReproduceBug};
+pub use containers::{DockerBuild, DockerPush, DockerRun};
+pub use data::InspectData;
 pub use directory::{ListDirectory, SearchFiles};
-pub use file_ops::{DeleteFile, ReadFile, WriteFile};
+pub use docs::LookupDocs;
+pub use environment::DetectEnvironment;
+pub use file_ops::{DeleteFile, ReadFile, ReadFileAt, WriteFile};
+pub use git::{GitCommit, GitCreateBranch, GitPush, GitRebase};
+pub use github::{GhPrComment, GhPrDiff, GhPrReply, GhPrReview, GhPrReviewThreads};
+pub use image_diff::CompareImages;
+pub use kubernetes::{K8sDescribe, K8sEvents, K8sGet, K8sLogs};
+pub use log_analysis::AnalyzeLogs;
+pub use notebook::{EditNotebookCell, ReadNotebook};
+pub use ocr::OcrImage;
+pub use packages::PackageInfo;
+pub use plan::{SetPlan, UpdatePlan};
+pub use reference_repo::CheckoutReferenceRepo;
 pub use search::GrepSearch;
+pub use structured::{PatchStructured, QueryStructured};
 pub use terminal::RunCommand;
+pub use terraform::TerraformPlan;
+pub use tracker::{TrackerAddComment, TrackerCreateSubtask, TrackerGetIssue, TrackerTransition};
+pub use watch::WatchPath;
 pub use web::FetchUrl;
 
+pub(crate) use directory::glob_match;
+pub(crate) use secret_scan::{redact_secrets, secret_spans};
+pub(crate) use wasm_tool::run_wasm_module;
+pub(crate) use watch::change_label;
+
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -126,6 +181,33 @@ pub fn safe_truncate_index(s: &str, max: usize) -> usize {
 use async_trait::async_trait;
 use serde_json::Value;
 
+/// Arguments that name files or directories, unless a tool declares its own
+/// (see [`Tool::path_args`]).
+pub const PATH_ARGS: &[&str] = &[
+    "path",
+    "paths",
+    "file",
+    "files",
+    "cwd",
+    "source",
+    "destination",
+    "output_path",
+    "index_path",
+];
+
+/// Values of the `keys` arguments of a call, from strings and string arrays.
+pub fn path_arg_values<'a>(args: &'a Value, keys: &[&str]) -> Vec<&'a str> {
+    let mut values = Vec::new();
+    for key in keys {
+        match args.get(*key) {
+            Some(Value::String(s)) => values.push(s.as_str()),
+            Some(Value::Array(items)) => values.extend(items.iter().filter_map(|v| v.as_str())),
+            _ => {}
+        }
+    }
+    values
+}
+
 /// Information about a tool for display purposes.
 #[derive(Debug, Clone)]
 pub struct ToolInfo {
@@ -147,9 +229,24 @@ pub trait Tool: Send + Sync {
 
     /// Execute the tool with the given arguments.
     ///
+    /// Callers validate `args` against [`Tool::parameters_schema`] first (see
+    /// [`args::validate`]).
     /// The `working_dir` is the default directory for relative paths.
     /// Tools can accept absolute paths to operate anywhere on the system.
     async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String>;
+
+    /// Arguments naming files or directories the tool reads or writes. The
+    /// host path guard and path policies check these before the call runs.
+    fn path_args(&self) -> &[&'static str] {
+        PATH_ARGS
+    }
+
+    /// For agent-defined aliases (see [`alias`]): the tool that actually runs
+    /// and the expanded arguments, so policies and hooks can check the real
+    /// call.
+    fn alias_target(&self, _args: &Value) -> Option<(Arc<dyn Tool>, Value)> {
+        None
+    }
 }
 
 /// Registry of available tools.
@@ -178,6 +275,7 @@ impl ToolRegistry {
 
         // File operations
         tools.insert("read_file".to_string(), Arc::new(file_ops::ReadFile));
+        tools.insert("read_file_at".to_string(), Arc::new(file_ops::ReadFileAt));
         tools.insert("write_file".to_string(), Arc::new(file_ops::WriteFile));
         tools.insert("delete_file".to_string(), Arc::new(file_ops::DeleteFile));
 
@@ -200,9 +298,54 @@ impl ToolRegistry {
 
         // Search
         tools.insert("grep_search".to_string(), Arc::new(search::GrepSearch));
+        tools.insert("watch_path".to_string(), Arc::new(watch::WatchPath));
+
+        // Git
+        tools.insert("git_commit".to_string(), Arc::new(git::GitCommit));
+        tools.insert("git_push".to_string(), Arc::new(git::GitPush));
+        tools.insert(
+            "git_create_branch".to_string(),
+            Arc::new(git::GitCreateBranch),
+        );
+        tools.insert("git_rebase".to_string(), Arc::new(git::GitRebase));
+        tools.insert(
+            "checkout_reference_repo".to_string(),
+            Arc::new(reference_repo::CheckoutReferenceRepo),
+        );
+
+        // GitHub PR review (via gh CLI)
+        tools.insert("gh_pr_diff".to_string(), Arc::new(github::GhPrDiff));
+        tools.insert("gh_pr_comment".to_string(), Arc::new(github::GhPrComment));
+        tools.insert(
+            "gh_pr_review_threads".to_string(),
+            Arc::new(github::GhPrReviewThreads),
+        );
+        tools.insert("gh_pr_reply".to_string(), Arc::new(github::GhPrReply));
+        tools.insert("gh_pr_review".to_string(), Arc::new(github::GhPrReview));
+        tools.insert("gh_pr_open".to_string(), Arc::new(composite::GhPrOpen));
 
-        // Web (fetch only; web search removed in favor of OMO/Exa)
+        // Issue trackers (Jira / Linear)
+        tools.insert(
+            "tracker_get_issue".to_string(),
+            Arc::new(tracker::TrackerGetIssue),
+        );
+        tools.insert(
+            "tracker_add_comment".to_string(),
+            Arc::new(tracker::TrackerAddComment),
+        );
+        tools.insert(
+            "tracker_transition".to_string(),
+            Arc::new(tracker::TrackerTransition),
+        );
+        tools.insert(
+            "tracker_create_subtask".to_string(),
+            Arc::new(tracker::TrackerCreateSubtask),
+        );
+
+        // Web (fetch, docs and package lookup; web search removed in favor of OMO/Exa)
         tools.insert("fetch_url".to_string(), Arc::new(web::FetchUrl));
+        tools.insert("lookup_docs".to_string(), Arc::new(docs::LookupDocs));
+        tools.insert("package_info".to_string(), Arc::new(packages::PackageInfo));
 
         // Frontend Tool UI (schemas for rich rendering in the dashboard)
         tools.insert("ui_optionList".to_string(), Arc::new(ui::UiOptionList));
@@ -219,6 +362,63 @@ impl ToolRegistry {
             Arc::new(composite::PrepareProject),
         );
         tools.insert("debug_error".to_string(), Arc::new(composite::DebugError));
+        tools.insert(
+            "reproduce_bug".to_string(),
+            Arc::new(composite::ReproduceBug),
+        );
+        tools.insert(
+            "detect_environment".to_string(),
+            Arc::new(environment::DetectEnvironment),
+        );
+        tools.insert(
+            "analyze_logs".to_string(),
+            Arc::new(log_analysis::AnalyzeLogs),
+        );
+        tools.insert(
+            "query_structured".to_string(),
+            Arc::new(structured::QueryStructured),
+        );
+        tools.insert(
+            "patch_structured".to_string(),
+            Arc::new(structured::PatchStructured),
+        );
+        tools.insert("inspect_data".to_string(), Arc::new(data::InspectData));
+        tools.insert(
+            "read_notebook".to_string(),
+            Arc::new(notebook::ReadNotebook),
+        );
+        tools.insert(
+            "edit_notebook_cell".to_string(),
+            Arc::new(notebook::EditNotebookCell),
+        );
+        tools.insert(
+            "compare_images".to_string(),
+            Arc::new(image_diff::CompareImages),
+        );
+        tools.insert("ocr_image".to_string(), Arc::new(ocr::OcrImage));
+
+        // Container image build/run (docker or podman in the workspace)
+        tools.insert(
+            "docker_build".to_string(),
+            Arc::new(containers::DockerBuild),
+        );
+        tools.insert("docker_run".to_string(), Arc::new(containers::DockerRun));
+        tools.insert("docker_push".to_string(), Arc::new(containers::DockerPush));
+
+        // Read-only Kubernetes inspection (namespace and kubeconfig from workspace env)
+        tools.insert("k8s_get".to_string(), Arc::new(kubernetes::K8sGet));
+        tools.insert(
+            "k8s_describe".to_string(),
+            Arc::new(kubernetes::K8sDescribe),
+        );
+        tools.insert("k8s_logs".to_string(), Arc::new(kubernetes::K8sLogs));
+        tools.insert("k8s_events".to_string(), Arc::new(kubernetes::K8sEvents));
+
+        // Infrastructure-as-code planning with policy checks
+        tools.insert(
+            "terraform_plan".to_string(),
+            Arc::new(terraform::TerraformPlan),
+        );
 
         // Desktop automation (conditional on DESKTOP_ENABLED)
         if desktop::desktop_enabled() {
@@ -255,6 +455,17 @@ impl ToolRegistry {
         };
         tools.insert("complete_mission".to_string(), mission_tool);
 
+        // Step-level plan shown to the user
+        tools.insert("set_plan".to_string(), Arc::new(plan::SetPlan));
+        tools.insert("update_plan".to_string(), Arc::new(plan::UpdatePlan));
+
+        // Offline mode: drop tools that need internet access
+        if crate::offline::offline_mode() {
+            for name in crate::offline::NETWORK_TOOLS {
+                tools.remove(*name);
+            }
+        }
+
         tracing::info!(
             "Registry {} complete with {} total tools",
             registry_id,
@@ -263,6 +474,50 @@ impl ToolRegistry {
         Self { tools }
     }
 
+    /// Register a tool, replacing any existing tool with the same name.
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.insert(tool.name().to_string(), tool);
+    }
+
+    /// Remove a tool, returning it if it was registered.
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.remove(name)
+    }
+
+    /// Keep only the tools a mission's bundle selection allows, returning the
+    /// names of the removed tools.
+    pub fn retain_bundles(&mut self, selection: &bundles::BundleSelection) -> Vec<String> {
+        let removed: Vec<String> = self
+            .tools
+            .keys()
+            .filter(|name| !selection.allows(name))
+            .cloned()
+            .collect();
+        for name in &removed {
+            self.tools.remove(name);
+        }
+        removed
+    }
+
+    /// Register the library tools synced into a mission directory.
+    ///
+    /// Built-in tools win on name clashes. Returns the number of tools added.
+    pub async fn register_library_tools(&mut self, working_dir: &Path) -> usize {
+        let mut added = 0;
+        for tool in library_tool::load_library_tools(working_dir).await {
+            if self.has_tool(tool.name()) {
+                tracing::warn!(
+                    tool = %tool.name(),
+                    "Library tool shadows a built-in tool; keeping the built-in"
+                );
+                continue;
+            }
+            self.register(tool);
+            added += 1;
+        }
+        added
+    }
+
     /// List all available tools.
     pub fn list_tools(&self) -> Vec<ToolInfo> {
         self.tools
@@ -294,6 +549,9 @@ impl ToolRegistry {
             .get(name)
             .ok_or_else(|| anyhow::anyhow!("Unknown tool: {}", name))?;
 
+        let mut args = args;
+        args::validate(&tool.parameters_schema(), &mut args)
+            .map_err(|e| anyhow::anyhow!(e.message(name)))?;
         tool.execute(args, working_dir).await
     }
 }
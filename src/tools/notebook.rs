@@ -0,0 +1,641 @@
+//! Jupyter notebook tools: `read_notebook` and `edit_notebook_cell`.
+//!
+//! Notebooks are JSON documents whose cell sources are arrays of lines, which
+//! agents tend to break when editing them as text. These tools present each
+//! cell as a numbered unit and edit one cell at a time, leaving every other
+//! cell, its outputs and all metadata untouched. The file is written back the
+//! way nbformat writes it (one-space indent, sorted keys, trailing newline) so
+//! diffs stay limited to the edited cell.
+//!
+//! `edit_notebook_cell` can run the notebook up to the edited cell through
+//! `jupyter nbconvert --execute` in the workspace and store the cell's new
+//! outputs, so the agent can verify the change.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use super::terminal::{run_workspace_shell, shell_quote};
+use super::{resolve_path_simple as resolve_path, safe_truncate_index, Tool, ToolArgs};
+
+const MAX_OUTPUT_CHARS: usize = 2_000;
+const DEFAULT_EXEC_TIMEOUT_SECS: u64 = 120;
+const MAX_EXEC_TIMEOUT_SECS: u64 = 900;
+
+fn ansi_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap())
+}
+
+/// Text stored either as a string or as an array of lines.
+fn multiline_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Source in nbformat's line-array form.
+fn source_lines(source: &str) -> Value {
+    Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+fn truncated(text: &str, max: usize) -> String {
+    let text = text.trim_end();
+    if text.len() <= max {
+        text.to_string()
+    } else {
+        format!(
+            "{}\n… ({} more chars)",
+            &text[..safe_truncate_index(text, max)],
+            text.len() - max
+        )
+    }
+}
+
+fn load_notebook(path: &Path) -> anyhow::Result<Value> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?;
+    let notebook: Value = serde_json::from_str(&text)
+        .map_err(|e| anyhow::anyhow!("{} is not valid notebook JSON: {}", path.display(), e))?;
+    if !notebook.get("cells").is_some_and(Value::is_array) {
+        anyhow::bail!(
+            "{} has no cells array (only nbformat 4 notebooks are supported)",
+            path.display()
+        );
+    }
+    Ok(notebook)
+}
+
+fn cells(notebook: &Value) -> &[Value] {
+    notebook["cells"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn cells_mut(notebook: &mut Value) -> &mut Vec<Value> {
+    notebook["cells"]
+        .as_array_mut()
+        .expect("load_notebook checks for a cells array")
+}
+
+/// Serialize the way nbformat does: one-space indent, sorted keys, trailing newline.
+fn notebook_json(notebook: &Value) -> anyhow::Result<String> {
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    serde::Serialize::serialize(notebook, &mut serializer)?;
+    buf.push(b'\n');
+    Ok(String::from_utf8(buf)?)
+}
+
+fn language(notebook: &Value) -> &str {
+    notebook["metadata"]["language_info"]["name"]
+        .as_str()
+        .or_else(|| notebook["metadata"]["kernelspec"]["language"].as_str())
+        .unwrap_or("")
+}
+
+/// Render one output as text; rich outputs are listed by MIME type.
+fn render_output(output: &Value) -> String {
+    match output["output_type"].as_str().unwrap_or("") {
+        "stream" => {
+            let name = output["name"].as_str().unwrap_or("stdout");
+            format!(
+                "[{}]\n{}",
+                name,
+                truncated(&multiline_text(&output["text"]), MAX_OUTPUT_CHARS)
+            )
+        }
+        "error" => {
+            let traceback: Vec<String> = output["traceback"]
+                .as_array()
+                .map(|lines| {
+                    lines
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(|l| ansi_regex().replace_all(l, "").to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            format!(
+                "[error] {}: {}\n{}",
+                output["ename"].as_str().unwrap_or("Error"),
+                output["evalue"].as_str().unwrap_or(""),
+                truncated(&traceback.join("\n"), MAX_OUTPUT_CHARS)
+            )
+        }
+        kind @ ("execute_result" | "display_data") => {
+            let data = output["data"].as_object();
+            let mut out = format!("[{}]", kind);
+            let rich: Vec<&str> = data
+                .map(|d| {
+                    d.keys()
+                        .map(String::as_str)
+                        .filter(|k| *k != "text/plain")
+                        .collect()
+                })
+                .unwrap_or_default();
+            if !rich.is_empty() {
+                out.push_str(&format!(" ({})", rich.join(", ")));
+            }
+            if let Some(text) = data.and_then(|d| d.get("text/plain")) {
+                out.push('\n');
+                out.push_str(&truncated(&multiline_text(text), MAX_OUTPUT_CHARS));
+            }
+            out
+        }
+        other => format!("[{}]", other),
+    }
+}
+
+fn render_cell(index: usize, cell: &Value, lang: &str, outputs: bool) -> String {
+    let kind = cell["cell_type"].as_str().unwrap_or("code");
+    let mut out = format!("## Cell {} ({}", index, kind);
+    if let Some(count) = cell["execution_count"].as_u64() {
+        out.push_str(&format!(", In [{}]", count));
+    }
+    out.push_str(")\n\n");
+    let source = multiline_text(&cell["source"]);
+    if kind == "code" {
+        out.push_str(&format!("```{}\n{}\n```\n", lang, source.trim_end()));
+    } else {
+        out.push_str(source.trim_end());
+        out.push('\n');
+    }
+    if outputs {
+        for output in cell["outputs"].as_array().into_iter().flatten() {
+            out.push_str(&format!("\n{}\n", render_output(output)));
+        }
+    }
+    out
+}
+
+/// Present a notebook's cells as numbered source units.
+pub struct ReadNotebook;
+
+#[derive(Deserialize, JsonSchema)]
+struct ReadNotebookArgs {
+    /// Path to the .ipynb file
+    path: String,
+    /// First cell to show (0-based, default 0)
+    #[serde(default)]
+    start: Option<usize>,
+    /// Last cell to show, inclusive (default: the last cell)
+    #[serde(default)]
+    end: Option<usize>,
+    /// Include cell outputs (default true)
+    #[serde(default)]
+    outputs: Option<bool>,
+}
+
+#[async_trait]
+impl Tool for ReadNotebook {
+    fn name(&self) -> &str {
+        "read_notebook"
+    }
+
+    fn description(&self) -> &str {
+        "Read a Jupyter notebook as numbered cells (0-based) with their type, source and outputs. Rich outputs such as images are listed by MIME type. Use the cell numbers with edit_notebook_cell instead of editing the .ipynb JSON directly."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        ReadNotebookArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = ReadNotebookArgs::parse(args)?;
+        let notebook = load_notebook(&resolve_path(&args.path, working_dir))?;
+        let cells = cells(&notebook);
+        if cells.is_empty() {
+            return Ok(format!("{} has no cells", args.path));
+        }
+        let start = args.start.unwrap_or(0);
+        let end = args.end.unwrap_or(cells.len() - 1).min(cells.len() - 1);
+        if start > end {
+            anyhow::bail!(
+                "Cell range {}..={} is empty; the notebook has {} cells",
+                start,
+                end,
+                cells.len()
+            );
+        }
+        let lang = language(&notebook);
+        let mut out = format!(
+            "# {} ({} cells{})\n\n",
+            args.path,
+            cells.len(),
+            if lang.is_empty() {
+                String::new()
+            } else {
+                format!(", {}", lang)
+            }
+        );
+        for (i, cell) in cells.iter().enumerate().take(end + 1).skip(start) {
+            out.push_str(&render_cell(i, cell, lang, args.outputs.unwrap_or(true)));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum CellAction {
+    Replace,
+    InsertBefore,
+    InsertAfter,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum CellType {
+    Code,
+    Markdown,
+    Raw,
+}
+
+impl CellType {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Code => "code",
+            Self::Markdown => "markdown",
+            Self::Raw => "raw",
+        }
+    }
+}
+
+/// Edit, insert or delete a single notebook cell.
+pub struct EditNotebookCell;
+
+#[derive(Deserialize, JsonSchema)]
+struct EditCellArgs {
+    /// Path to the .ipynb file
+    path: String,
+    /// Cell number (0-based) as shown by read_notebook. For insert_before,
+    /// the cell count appends at the end.
+    cell: usize,
+    /// replace (default), insert_before, insert_after or delete
+    #[serde(default)]
+    action: Option<CellAction>,
+    /// New cell source (required except for delete)
+    #[serde(default)]
+    source: Option<String>,
+    /// Cell type for inserted cells (default code); on replace, converts the cell
+    #[serde(default)]
+    cell_type: Option<CellType>,
+    /// Drop the edited cell's outputs and execution count
+    #[serde(default)]
+    clear_outputs: bool,
+    /// Run the notebook up to this cell with `jupyter nbconvert --execute`
+    /// and store the cell's new outputs
+    #[serde(default)]
+    execute: bool,
+    /// Execution timeout in seconds (default 120, max 900)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+fn new_cell(notebook: &Value, cell_type: CellType, source: &str) -> Value {
+    let mut cell = Map::new();
+    cell.insert("cell_type".into(), json!(cell_type.as_str()));
+    cell.insert("metadata".into(), json!({}));
+    cell.insert("source".into(), source_lines(source));
+    if cell_type == CellType::Code {
+        cell.insert("execution_count".into(), Value::Null);
+        cell.insert("outputs".into(), json!([]));
+    }
+    // Cell ids are required from nbformat 4.5.
+    let minor = notebook["nbformat_minor"].as_u64().unwrap_or(0);
+    if notebook["nbformat"].as_u64().unwrap_or(4) > 4 || minor >= 5 {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        cell.insert("id".into(), json!(&id[..8]));
+    }
+    Value::Object(cell)
+}
+
+/// Replace a cell's source, keeping its id, metadata and (unless converting
+/// to another type) its outputs.
+fn replace_cell(cell: &mut Value, source: &str, cell_type: Option<CellType>) {
+    let obj = cell.as_object_mut().expect("cells are objects");
+    let source = match obj.get("source") {
+        // Keep a single-string source as a string.
+        Some(Value::String(_)) => Value::String(source.to_string()),
+        _ => source_lines(source),
+    };
+    obj.insert("source".into(), source);
+    if let Some(kind) = cell_type {
+        if obj.get("cell_type").and_then(Value::as_str) != Some(kind.as_str()) {
+            obj.insert("cell_type".into(), json!(kind.as_str()));
+            if kind == CellType::Code {
+                obj.insert("execution_count".into(), Value::Null);
+                obj.insert("outputs".into(), json!([]));
+            } else {
+                obj.remove("execution_count");
+                obj.remove("outputs");
+            }
+        }
+    }
+}
+
+/// Apply one edit; returns the index of the resulting cell (None for delete).
+fn apply_edit(notebook: &mut Value, args: &EditCellArgs) -> anyhow::Result<Option<usize>> {
+    let action = args.action.unwrap_or(CellAction::Replace);
+    let count = cells(notebook).len();
+    let source = || {
+        args.source
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("source is required for {:?}", action))
+    };
+    let in_range = |limit: usize| {
+        if args.cell < limit {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Cell {} does not exist; the notebook has {} cells",
+                args.cell,
+                count
+            ))
+        }
+    };
+    let index = match action {
+        CellAction::Replace => {
+            in_range(count)?;
+            replace_cell(
+                &mut cells_mut(notebook)[args.cell],
+                source()?,
+                args.cell_type,
+            );
+            Some(args.cell)
+        }
+        CellAction::InsertBefore | CellAction::InsertAfter => {
+            let at = if action == CellAction::InsertBefore {
+                in_range(count + 1)?;
+                args.cell
+            } else {
+                in_range(count)?;
+                args.cell + 1
+            };
+            let cell = new_cell(
+                notebook,
+                args.cell_type.unwrap_or(CellType::Code),
+                source()?,
+            );
+            cells_mut(notebook).insert(at, cell);
+            Some(at)
+        }
+        CellAction::Delete => {
+            in_range(count)?;
+            cells_mut(notebook).remove(args.cell);
+            None
+        }
+    };
+    if let (Some(i), true) = (index, args.clear_outputs) {
+        let cell = &mut cells_mut(notebook)[i];
+        if cell["cell_type"] == "code" {
+            cell["outputs"] = json!([]);
+            cell["execution_count"] = Value::Null;
+        }
+    }
+    Ok(index)
+}
+
+/// Run cells `0..=index` through nbconvert and return the last cell's
+/// outputs and execution count.
+async fn execute_through(
+    notebook: &Value,
+    index: usize,
+    path: &Path,
+    timeout_secs: u64,
+) -> anyhow::Result<(Value, Value)> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "notebook".to_string());
+    // Written next to the notebook so relative paths in cells still resolve.
+    let input = format!(".{}.sandboxed-exec.ipynb", stem);
+    let output = format!(".{}.sandboxed-exec.out.ipynb", stem);
+
+    let mut partial = notebook.clone();
+    cells_mut(&mut partial).truncate(index + 1);
+    std::fs::write(dir.join(&input), notebook_json(&partial)?)?;
+
+    let command = format!(
+        "jupyter nbconvert --to notebook --execute --allow-errors --ExecutePreprocessor.timeout={} --output-dir . --output {} {}",
+        timeout_secs,
+        shell_quote(&output),
+        shell_quote(&input)
+    );
+    let result = run_workspace_shell(
+        dir,
+        &command,
+        HashMap::new(),
+        Duration::from_secs(timeout_secs + 60),
+    )
+    .await;
+    let _ = std::fs::remove_file(dir.join(&input));
+    let executed = std::fs::read_to_string(dir.join(&output));
+    let _ = std::fs::remove_file(dir.join(&output));
+
+    let result = result?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        if stderr.contains("not found") || stderr.contains("No module named") {
+            anyhow::bail!(
+                "Jupyter is not installed in this workspace (install `nbconvert` and a kernel such as `ipykernel`)"
+            );
+        }
+        anyhow::bail!(
+            "Notebook execution failed:\n{}",
+            truncated(&stderr, MAX_OUTPUT_CHARS)
+        );
+    }
+    let executed: Value = serde_json::from_str(&executed?)?;
+    let cell = cells(&executed)
+        .get(index)
+        .ok_or_else(|| anyhow::anyhow!("Executed notebook is missing cell {}", index))?;
+    Ok((cell["outputs"].clone(), cell["execution_count"].clone()))
+}
+
+#[async_trait]
+impl Tool for EditNotebookCell {
+    fn name(&self) -> &str {
+        "edit_notebook_cell"
+    }
+
+    fn description(&self) -> &str {
+        "Edit one cell of a Jupyter notebook by number (from read_notebook): replace its source, insert a new cell before/after it, or delete it. Other cells, outputs and metadata are preserved. With `execute: true`, runs the notebook up to the edited code cell via jupyter nbconvert and stores and returns the cell's new outputs."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        EditCellArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = EditCellArgs::parse(args)?;
+        let path = resolve_path(&args.path, working_dir);
+        let mut notebook = load_notebook(&path)?;
+        let index = apply_edit(&mut notebook, &args)?;
+
+        let mut report = match (index, args.action.unwrap_or(CellAction::Replace)) {
+            (None, _) => format!("Deleted cell {} from {}", args.cell, args.path),
+            (Some(i), CellAction::Replace) => format!("Replaced cell {} in {}", i, args.path),
+            (Some(i), _) => format!("Inserted cell {} in {}", i, args.path),
+        };
+
+        if args.execute {
+            match index {
+                Some(i) if cells(&notebook)[i]["cell_type"] == "code" => {
+                    let timeout = args
+                        .timeout_secs
+                        .unwrap_or(DEFAULT_EXEC_TIMEOUT_SECS)
+                        .clamp(1, MAX_EXEC_TIMEOUT_SECS);
+                    // Save the edit first so a failed run does not lose it.
+                    std::fs::write(&path, notebook_json(&notebook)?)?;
+                    let (outputs, count) = execute_through(&notebook, i, &path, timeout)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{}, but execution failed: {}", report, e))?;
+                    let cell = &mut cells_mut(&mut notebook)[i];
+                    cell["outputs"] = outputs;
+                    cell["execution_count"] = count;
+                    report.push_str(&format!(
+                        " and executed cells 0-{}.\n\n{}",
+                        i,
+                        render_cell(i, &cells(&notebook)[i], language(&notebook), true)
+                    ));
+                }
+                _ => report.push_str(" (nothing to execute: not a code cell)"),
+            }
+        }
+
+        std::fs::write(&path, notebook_json(&notebook)?)?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "id": "a1",
+   "metadata": {},
+   "source": ["# Title\n", "Intro"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 3,
+   "id": "b2",
+   "metadata": {"tags": ["keep"]},
+   "outputs": [
+    {"name": "stdout", "output_type": "stream", "text": ["4\n"]},
+    {"ename": "ValueError", "evalue": "bad", "output_type": "error", "traceback": ["\u001b[0;31mValueError\u001b[0m: bad"]}
+   ],
+   "source": ["x = 2\n", "print(x * 2)"]
+  }
+ ],
+ "metadata": {"language_info": {"name": "python"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+    fn args(value: Value) -> EditCellArgs {
+        EditCellArgs::parse(value).unwrap()
+    }
+
+    #[test]
+    fn renders_cells_with_outputs() {
+        let notebook: Value = serde_json::from_str(NOTEBOOK).unwrap();
+        let text = render_cell(1, &cells(&notebook)[1], "python", true);
+        assert!(text.starts_with("## Cell 1 (code, In [3])"));
+        assert!(text.contains("```python\nx = 2\nprint(x * 2)\n```"));
+        assert!(text.contains("[stdout]\n4"));
+        assert!(text.contains("[error] ValueError: bad\nValueError: bad"));
+    }
+
+    #[test]
+    fn replace_keeps_outputs_and_metadata() {
+        let mut notebook: Value = serde_json::from_str(NOTEBOOK).unwrap();
+        let index = apply_edit(
+            &mut notebook,
+            &args(json!({"path": "n.ipynb", "cell": 1, "source": "x = 3\nprint(x)"})),
+        )
+        .unwrap();
+        assert_eq!(index, Some(1));
+        let cell = &cells(&notebook)[1];
+        assert_eq!(cell["source"], json!(["x = 3\n", "print(x)"]));
+        assert_eq!(cell["metadata"]["tags"], json!(["keep"]));
+        assert_eq!(cell["id"], "b2");
+        assert_eq!(cell["outputs"].as_array().unwrap().len(), 2);
+
+        // Untouched cells serialize exactly as nbformat wrote them.
+        let written = notebook_json(&notebook).unwrap();
+        assert!(written.contains(
+            "  {\n   \"cell_type\": \"markdown\",\n   \"id\": \"a1\",\n   \"metadata\": {},\n   \"source\": [\n    \"# Title\\n\",\n    \"Intro\"\n   ]\n  },"
+        ));
+        assert!(written.ends_with("}\n"));
+    }
+
+    #[test]
+    fn insert_and_delete_cells() {
+        let mut notebook: Value = serde_json::from_str(NOTEBOOK).unwrap();
+        let index = apply_edit(
+            &mut notebook,
+            &args(json!({
+                "path": "n.ipynb",
+                "cell": 0,
+                "action": "insert_after",
+                "cell_type": "markdown",
+                "source": "## Setup"
+            })),
+        )
+        .unwrap();
+        assert_eq!(index, Some(1));
+        let cell = &cells(&notebook)[1];
+        assert_eq!(cell["cell_type"], "markdown");
+        assert_eq!(cell["id"].as_str().unwrap().len(), 8);
+        assert!(cell.get("outputs").is_none());
+
+        let appended = apply_edit(
+            &mut notebook,
+            &args(
+                json!({"path": "n.ipynb", "cell": 3, "action": "insert_before", "source": "y = 1"}),
+            ),
+        )
+        .unwrap();
+        assert_eq!(appended, Some(3));
+        assert_eq!(cells(&notebook)[3]["outputs"], json!([]));
+
+        apply_edit(
+            &mut notebook,
+            &args(json!({"path": "n.ipynb", "cell": 0, "action": "delete"})),
+        )
+        .unwrap();
+        assert_eq!(cells(&notebook).len(), 3);
+        assert!(apply_edit(
+            &mut notebook,
+            &args(json!({"path": "n.ipynb", "cell": 5, "source": "z"})),
+        )
+        .is_err());
+    }
+}
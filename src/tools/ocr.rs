@@ -0,0 +1,386 @@
+//! `ocr_image`: extract text with bounding boxes from an image.
+//!
+//! Tesseract runs in the workspace and its TSV output is grouped into lines
+//! with pixel boxes and confidences. When tesseract is not installed (or
+//! `engine: "vision"` is passed), the image is sent to a vision-capable model
+//! through the internal OpenAI-compatible proxy instead; its boxes are the
+//! model's estimates. This lets missions on non-vision models read error
+//! dialogs in desktop screenshots or user-attached images.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::Engine;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::terminal::{run_workspace_shell, shell_quote, workspace_setting};
+use super::{resolve_path_simple as resolve_path, Tool, ToolArgs};
+
+/// Workspace setting naming the model used for the vision fallback.
+pub const OCR_MODEL_SETTING: &str = "SANDBOXED_SH_OCR_MODEL";
+
+const TESSERACT_TIMEOUT: Duration = Duration::from_secs(120);
+/// Images larger than this are not sent to the vision model.
+const MAX_VISION_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct TextLine {
+    text: String,
+    /// [x, y, width, height] in pixels.
+    #[serde(rename = "box")]
+    bbox: [u32; 4],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct OcrResult {
+    engine: &'static str,
+    text: String,
+    lines: Vec<TextLine>,
+}
+
+/// Group tesseract TSV word rows into lines, dropping words below
+/// `min_confidence`.
+fn parse_tesseract_tsv(tsv: &str, min_confidence: f32) -> Vec<TextLine> {
+    struct Acc {
+        words: Vec<String>,
+        bbox: (u32, u32, u32, u32),
+        confidences: Vec<f32>,
+    }
+    let mut order = Vec::new();
+    let mut lines: HashMap<(u32, u32, u32, u32), Acc> = HashMap::new();
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let text = cols[11].trim();
+        let conf: f32 = cols[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || conf < min_confidence {
+            continue;
+        }
+        let n = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let key = (n(1), n(2), n(3), n(4));
+        let (x0, y0) = (n(6), n(7));
+        let (x1, y1) = (x0 + n(8), y0 + n(9));
+        let acc = lines.entry(key).or_insert_with(|| {
+            order.push(key);
+            Acc {
+                words: Vec::new(),
+                bbox: (x0, y0, x1, y1),
+                confidences: Vec::new(),
+            }
+        });
+        acc.words.push(text.to_string());
+        acc.bbox = (
+            acc.bbox.0.min(x0),
+            acc.bbox.1.min(y0),
+            acc.bbox.2.max(x1),
+            acc.bbox.3.max(y1),
+        );
+        acc.confidences.push(conf);
+    }
+    order
+        .into_iter()
+        .filter_map(|key| lines.remove(&key))
+        .map(|acc| {
+            let (x0, y0, x1, y1) = acc.bbox;
+            TextLine {
+                text: acc.words.join(" "),
+                bbox: [x0, y0, x1 - x0, y1 - y0],
+                confidence: Some(
+                    (acc.confidences.iter().sum::<f32>() / acc.confidences.len() as f32).round(),
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Run tesseract in the workspace; `Ok(None)` when it is not installed.
+async fn run_tesseract(
+    path: &Path,
+    lang: &str,
+    min_confidence: f32,
+) -> anyhow::Result<Option<Vec<TextLine>>> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let file = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let command = format!(
+        "command -v tesseract >/dev/null 2>&1 || exit 127; tesseract {} stdout -l {} tsv",
+        shell_quote(&file),
+        shell_quote(lang)
+    );
+    let output = run_workspace_shell(dir, &command, HashMap::new(), TESSERACT_TIMEOUT).await?;
+    if output.status.code() == Some(127) {
+        return Ok(None);
+    }
+    if !output.status.success() {
+        anyhow::bail!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(Some(parse_tesseract_tsv(
+        &String::from_utf8_lossy(&output.stdout),
+        min_confidence,
+    )))
+}
+
+/// Parse the vision model's reply: a JSON object with `lines`, possibly
+/// fenced. Falls back to one line per row of plain text (without boxes).
+fn parse_vision_reply(content: &str) -> Vec<TextLine> {
+    let trimmed = content.trim();
+    let body = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .map(|rest| rest.trim_end().trim_end_matches("```"))
+        .unwrap_or(trimmed);
+    #[derive(Deserialize)]
+    struct Reply {
+        lines: Vec<TextLine>,
+    }
+    if let Ok(reply) = serde_json::from_str::<Reply>(body) {
+        return reply.lines;
+    }
+    trimmed
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|text| TextLine {
+            text: text.to_string(),
+            bbox: [0, 0, 0, 0],
+            confidence: None,
+        })
+        .collect()
+}
+
+/// Ask a vision model (via the internal OpenAI-compatible proxy) to read the image.
+async fn run_vision(path: &Path) -> anyhow::Result<Vec<TextLine>> {
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_VISION_BYTES {
+        anyhow::bail!(
+            "Image is too large for the vision fallback ({} MB)",
+            size / 1024 / 1024
+        );
+    }
+    let bytes = std::fs::read(path)?;
+    let format = image::guess_format(&bytes)
+        .map_err(|e| anyhow::anyhow!("Unsupported image {}: {}", path.display(), e))?;
+    let (width, height) =
+        image::ImageReader::with_format(std::io::Cursor::new(&bytes), format).into_dimensions()?;
+    let data_url = format!(
+        "data:{};base64,{}",
+        format.to_mime_type(),
+        base64::engine::general_purpose::STANDARD.encode(&bytes)
+    );
+
+    let api_base = std::env::var("SANDBOXED_SH_API_URL").unwrap_or_else(|_| {
+        let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
+        format!("http://127.0.0.1:{}", port)
+    });
+    let model = workspace_setting(OCR_MODEL_SETTING).unwrap_or_else(|| "builtin/smart".into());
+    let instructions = format!(
+        "Transcribe all text visible in this {}x{} pixel image, top to bottom. Reply with JSON \
+         only: {{\"lines\": [{{\"text\": \"...\", \"box\": [x, y, width, height]}}]}}, one entry \
+         per line of text, boxes in pixels.",
+        width, height
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(120))
+        .build()?;
+    let mut request = client
+        .post(format!(
+            "{}/v1/chat/completions",
+            api_base.trim_end_matches('/')
+        ))
+        .json(&json!({
+            "model": model,
+            "stream": false,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": instructions },
+                    { "type": "image_url", "image_url": { "url": data_url } }
+                ]
+            }]
+        }));
+    if let Ok(secret) = std::env::var("SANDBOXED_PROXY_SECRET") {
+        request = request.bearer_auth(secret);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        anyhow::bail!("Vision OCR with {} failed ({}): {}", model, status, body);
+    }
+    let content = body["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Vision OCR returned no content"))?;
+    Ok(parse_vision_reply(content))
+}
+
+/// Extract text with bounding boxes from an image file.
+pub struct OcrImage;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum OcrEngine {
+    /// Tesseract, falling back to the vision model when it is not installed
+    #[default]
+    Auto,
+    Tesseract,
+    Vision,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct OcrArgs {
+    /// Image file (PNG, JPEG, WebP, GIF), e.g. a desktop_screenshot path
+    path: String,
+    /// auto (default), tesseract or vision
+    #[serde(default)]
+    engine: OcrEngine,
+    /// Tesseract language codes, e.g. `eng` or `eng+deu` (default eng)
+    #[serde(default)]
+    lang: Option<String>,
+    /// Drop tesseract words below this confidence, 0-100 (default 30)
+    #[serde(default)]
+    min_confidence: Option<f32>,
+    /// Return the result as JSON
+    #[serde(default)]
+    json: bool,
+}
+
+#[async_trait]
+impl Tool for OcrImage {
+    fn name(&self) -> &str {
+        "ocr_image"
+    }
+
+    fn description(&self) -> &str {
+        "Extract text from an image or screenshot, with a bounding box [x, y, width, height] and confidence per line. Uses tesseract in the workspace, falling back to a vision model when tesseract is not installed. Use it to read error dialogs or UI text when you cannot view images yourself."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        OcrArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = OcrArgs::parse(args)?;
+        let path = resolve_path(&args.path, working_dir);
+        if !path.is_file() {
+            anyhow::bail!("Image not found: {}", path.display());
+        }
+        let lang = args.lang.as_deref().unwrap_or("eng");
+        if !lang
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '_')
+        {
+            anyhow::bail!("Invalid tesseract language: {}", lang);
+        }
+        let min_confidence = args.min_confidence.unwrap_or(30.0);
+
+        let tesseract = match args.engine {
+            OcrEngine::Vision => None,
+            _ => run_tesseract(&path, lang, min_confidence).await?,
+        };
+        let result = match (tesseract, args.engine) {
+            (Some(lines), _) => OcrResult {
+                engine: "tesseract",
+                text: join_lines(&lines),
+                lines,
+            },
+            (None, OcrEngine::Tesseract) => {
+                anyhow::bail!("tesseract is not installed in this workspace")
+            }
+            (None, _) => {
+                let lines = run_vision(&path).await?;
+                OcrResult {
+                    engine: "vision",
+                    text: join_lines(&lines),
+                    lines,
+                }
+            }
+        };
+
+        if args.json {
+            return Ok(serde_json::to_string_pretty(&result)?);
+        }
+        if result.lines.is_empty() {
+            return Ok(format!("No text found ({})", result.engine));
+        }
+        let mut out = format!("{} lines ({})", result.lines.len(), result.engine);
+        if result.engine == "vision" {
+            out.push_str(", boxes are approximate");
+        }
+        out.push_str(":\n");
+        for line in &result.lines {
+            let [x, y, w, h] = line.bbox;
+            out.push_str(&format!("[{},{} {}x{}", x, y, w, h));
+            if let Some(conf) = line.confidence {
+                out.push_str(&format!(" {:.0}%", conf));
+            }
+            out.push_str(&format!("] {}\n", line.text));
+        }
+        Ok(out)
+    }
+}
+
+fn join_lines(lines: &[TextLine]) -> String {
+    lines
+        .iter()
+        .map(|l| l.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t
+4\t1\t1\t1\t1\t0\t40\t20\t200\t18\t-1\t
+5\t1\t1\t1\t1\t1\t40\t20\t80\t18\t95.5\tConnection
+5\t1\t1\t1\t1\t2\t126\t22\t114\t16\t91.2\trefused
+5\t1\t1\t1\t2\t1\t40\t50\t60\t14\t12.0\t~~
+5\t1\t2\t1\t1\t1\t300\t400\t30\t20\t88\tOK
+";
+
+    #[test]
+    fn groups_tesseract_words_into_lines() {
+        let lines = parse_tesseract_tsv(TSV, 30.0);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text, "Connection refused");
+        assert_eq!(lines[0].bbox, [40, 20, 200, 18]);
+        assert_eq!(lines[0].confidence, Some(93.0));
+        assert_eq!(lines[1].text, "OK");
+
+        // The low-confidence noise line is kept when the floor is lowered.
+        assert_eq!(parse_tesseract_tsv(TSV, 0.0).len(), 3);
+    }
+
+    #[test]
+    fn parses_vision_json_and_plain_replies() {
+        let reply =
+            "```json\n{\"lines\": [{\"text\": \"Error 502\", \"box\": [10, 20, 90, 15]}]}\n```";
+        let lines = parse_vision_reply(reply);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].bbox, [10, 20, 90, 15]);
+        assert_eq!(lines[0].confidence, None);
+
+        let lines = parse_vision_reply("Save changes?\n\n  Cancel  ");
+        assert_eq!(
+            lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>(),
+            ["Save changes?", "Cancel"]
+        );
+    }
+}
@@ -0,0 +1,696 @@
+//! Package registry metadata: latest versions, deprecation status, download
+//! stats and changelog links from crates.io, npm and PyPI.
+//!
+//! Agents editing manifests should check versions here instead of guessing.
+//! Requests go through `web::send_get`, so the web proxy logs and filters them.
+
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::web::{read_capped, send_get};
+use super::Tool;
+use crate::web_proxy;
+
+const MAX_METADATA_BYTES: usize = 32 * 1024 * 1024;
+/// Versions listed in the "recent versions" line.
+const RECENT_VERSIONS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Registry {
+    CratesIo,
+    Npm,
+    PyPi,
+}
+
+impl Registry {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "crates" | "crates.io" | "cargo" | "rust" => Some(Self::CratesIo),
+            "npm" | "node" | "javascript" | "js" | "typescript" | "ts" => Some(Self::Npm),
+            "pypi" | "pip" | "python" | "py" => Some(Self::PyPi),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::CratesIo => "crates.io",
+            Self::Npm => "npm",
+            Self::PyPi => "PyPI",
+        }
+    }
+}
+
+/// Status of one specific version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct VersionStatus {
+    version: String,
+    exists: bool,
+    /// Yank/deprecation reason, if any.
+    withdrawn: Option<String>,
+    published: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PackageMetadata {
+    registry: Option<Registry>,
+    name: String,
+    latest: Option<String>,
+    /// Newest pre-release when newer than `latest`.
+    latest_prerelease: Option<String>,
+    published: Option<String>,
+    description: Option<String>,
+    deprecated: Option<String>,
+    downloads: Vec<(&'static str, u64)>,
+    homepage: Option<String>,
+    repository: Option<String>,
+    changelog: Option<String>,
+    recent_versions: Vec<String>,
+    requested: Option<VersionStatus>,
+}
+
+impl PackageMetadata {
+    fn format(&self) -> String {
+        let registry = self.registry.map(|r| r.as_str()).unwrap_or_default();
+        let mut lines = vec![format!("{} ({})", self.name, registry)];
+        if let Some(description) = &self.description {
+            lines.push(description.trim().to_string());
+        }
+        lines.push(String::new());
+        match &self.latest {
+            Some(latest) => lines.push(format!(
+                "Latest version: {}{}",
+                latest,
+                self.published
+                    .as_deref()
+                    .map(|p| format!(" (published {})", p))
+                    .unwrap_or_default()
+            )),
+            None => lines.push("Latest version: none published".to_string()),
+        }
+        if let Some(pre) = &self.latest_prerelease {
+            lines.push(format!("Latest pre-release: {}", pre));
+        }
+        if let Some(reason) = &self.deprecated {
+            lines.push(format!("DEPRECATED: {}", reason));
+        }
+        if let Some(requested) = &self.requested {
+            let status = if !requested.exists {
+                "does not exist".to_string()
+            } else if let Some(reason) = &requested.withdrawn {
+                format!("exists but is withdrawn: {}", reason)
+            } else {
+                format!(
+                    "exists{}",
+                    requested
+                        .published
+                        .as_deref()
+                        .map(|p| format!(" (published {})", p))
+                        .unwrap_or_default()
+                )
+            };
+            lines.push(format!("Version {}: {}", requested.version, status));
+        }
+        for (label, count) in &self.downloads {
+            lines.push(format!("Downloads ({}): {}", label, count));
+        }
+        if !self.recent_versions.is_empty() {
+            lines.push(format!(
+                "Recent versions: {}",
+                self.recent_versions.join(", ")
+            ));
+        }
+        for (label, value) in [
+            ("Homepage", &self.homepage),
+            ("Repository", &self.repository),
+            ("Changelog", &self.changelog),
+        ] {
+            if let Some(value) = value {
+                lines.push(format!("{}: {}", label, value));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Look up package metadata on crates.io, npm or PyPI.
+pub struct PackageInfo;
+
+#[async_trait]
+impl Tool for PackageInfo {
+    fn name(&self) -> &str {
+        "package_info"
+    }
+
+    fn description(&self) -> &str {
+        "Get registry metadata for a package on crates.io, npm or PyPI: latest (and pre-release) version, deprecation/yank status, download stats, recent versions, and homepage/repository/changelog links. Optionally checks whether a specific version exists. Use this before adding or bumping a dependency instead of guessing versions."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "registry": {
+                    "type": "string",
+                    "enum": ["crates.io", "npm", "pypi"],
+                    "description": "Package registry"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Package name, e.g. 'serde', '@types/node' or 'requests'"
+                },
+                "version": {
+                    "type": "string",
+                    "description": "Optional version to check (exists, yanked or deprecated)"
+                }
+            },
+            "required": ["registry", "name"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let registry = args["registry"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Missing 'registry' argument"))?;
+        let registry = Registry::parse(registry).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unsupported registry '{}': expected crates.io, npm or pypi",
+                registry
+            )
+        })?;
+        let name = args["name"]
+            .as_str()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'name' argument"))?;
+        if !valid_package_name(name) {
+            anyhow::bail!("Invalid package name '{}'", name);
+        }
+        let version = args["version"]
+            .as_str()
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+
+        let client = reqwest::Client::builder()
+            // crates.io rejects requests without an identifying user agent.
+            .user_agent("sandboxed.sh package_info (https://github.com/writepavel/sandboxed.sh)")
+            .redirect(reqwest::redirect::Policy::limited(
+                web_proxy::FETCH_MAX_REDIRECTS,
+            ))
+            .timeout(Duration::from_secs(30))
+            .build()?;
+
+        let info = match registry {
+            Registry::CratesIo => {
+                let url = format!("https://crates.io/api/v1/crates/{}", name);
+                let metadata = get_json(&client, &url)
+                    .await?
+                    .ok_or_else(|| not_found(registry, name))?;
+                parse_crates_io(&metadata, version)
+            }
+            Registry::Npm => {
+                // Scoped names keep the `@` but escape the slash.
+                let encoded = name.replace('/', "%2F");
+                let url = format!("https://registry.npmjs.org/{}", encoded);
+                let metadata = get_json(&client, &url)
+                    .await?
+                    .ok_or_else(|| not_found(registry, name))?;
+                let downloads_url =
+                    format!("https://api.npmjs.org/downloads/point/last-week/{}", name);
+                // Download stats are best effort.
+                let downloads = get_json(&client, &downloads_url).await.ok().flatten();
+                parse_npm(&metadata, downloads.as_ref(), version)
+            }
+            Registry::PyPi => {
+                let url = format!("https://pypi.org/pypi/{}/json", name);
+                let metadata = get_json(&client, &url)
+                    .await?
+                    .ok_or_else(|| not_found(registry, name))?;
+                let stats_url = format!(
+                    "https://pypistats.org/api/packages/{}/recent",
+                    name.to_ascii_lowercase()
+                );
+                let stats = get_json(&client, &stats_url).await.ok().flatten();
+                parse_pypi(&metadata, stats.as_ref(), version)
+            }
+        };
+        Ok(PackageMetadata {
+            registry: Some(registry),
+            ..info
+        }
+        .format())
+    }
+}
+
+fn not_found(registry: Registry, name: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Package '{}' not found on {} (check the spelling; do not guess a name)",
+        name,
+        registry.as_str()
+    )
+}
+
+/// Registry names: letters, digits, `-_.` and an npm `@scope/` prefix.
+fn valid_package_name(name: &str) -> bool {
+    let bare = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, pkg)) if !scope.is_empty() => {
+                if !scope
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+                {
+                    return false;
+                }
+                pkg
+            }
+            _ => return false,
+        },
+        None => name,
+    };
+    !bare.is_empty()
+        && bare.len() <= 214
+        && bare
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
+
+/// GET a JSON document; None on 404.
+async fn get_json(client: &reqwest::Client, url: &str) -> anyhow::Result<Option<Value>> {
+    let response = send_get(client, url).await?;
+    if let Some(reason) = response
+        .headers()
+        .get(web_proxy::BLOCKED_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        anyhow::bail!("Blocked by web proxy: {}", reason);
+    }
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        anyhow::bail!("HTTP error {} from {}", status, url);
+    }
+    let (body, truncated) = read_capped(response, MAX_METADATA_BYTES).await?;
+    if truncated {
+        anyhow::bail!("Registry response from {} is too large", url);
+    }
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value[key]
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Date part of an RFC 3339 timestamp.
+fn date(timestamp: &str) -> String {
+    timestamp.split('T').next().unwrap_or(timestamp).to_string()
+}
+
+/// Normalize `git+https://…/repo.git` style URLs to a browsable URL.
+fn browsable_repo(url: &str) -> String {
+    let url = url.trim();
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    let url = url
+        .strip_prefix("git://")
+        .map(|rest| format!("https://{}", rest))
+        .unwrap_or_else(|| url.to_string());
+    let url = url
+        .strip_prefix("git@github.com:")
+        .map(|rest| format!("https://github.com/{}", rest))
+        .unwrap_or(url);
+    url.trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_string()
+}
+
+/// Releases page for GitHub-hosted repositories.
+fn releases_link(repository: Option<&str>) -> Option<String> {
+    repository
+        .filter(|r| r.starts_with("https://github.com/"))
+        .map(|r| format!("{}/releases", r))
+}
+
+fn is_prerelease(version: &str) -> bool {
+    let lower = version.to_ascii_lowercase();
+    version.contains('-')
+        || ["a", "b", "rc", "dev", "alpha", "beta", "pre"]
+            .iter()
+            .any(|tag| {
+                lower
+                    .split(|c: char| c.is_ascii_digit() || c == '.')
+                    .any(|part| part == *tag)
+            })
+}
+
+fn parse_crates_io(metadata: &Value, requested: Option<&str>) -> PackageMetadata {
+    let krate = &metadata["crate"];
+    let versions = metadata["versions"].as_array().cloned().unwrap_or_default();
+    let latest = str_field(krate, "max_stable_version").or_else(|| str_field(krate, "max_version"));
+    let newest = str_field(krate, "max_version");
+    let version_entry = |num: &str| versions.iter().find(|v| v["num"].as_str() == Some(num));
+    let repository = str_field(krate, "repository").map(|r| browsable_repo(&r));
+
+    let mut downloads = Vec::new();
+    if let Some(n) = krate["downloads"].as_u64() {
+        downloads.push(("all time", n));
+    }
+    if let Some(n) = krate["recent_downloads"].as_u64() {
+        downloads.push(("last 90 days", n));
+    }
+    let deprecated = latest
+        .as_deref()
+        .and_then(version_entry)
+        .filter(|v| v["yanked"].as_bool() == Some(true))
+        .map(|_| "the latest version is yanked".to_string());
+
+    PackageMetadata {
+        name: str_field(krate, "name").unwrap_or_default(),
+        published: latest
+            .as_deref()
+            .and_then(version_entry)
+            .and_then(|v| v["created_at"].as_str())
+            .map(date),
+        latest_prerelease: newest.filter(|n| Some(n) != latest.as_ref()),
+        latest,
+        description: str_field(krate, "description"),
+        deprecated,
+        downloads,
+        homepage: str_field(krate, "homepage"),
+        changelog: releases_link(repository.as_deref()),
+        repository,
+        recent_versions: versions
+            .iter()
+            .filter(|v| v["yanked"].as_bool() != Some(true))
+            .filter_map(|v| v["num"].as_str().map(str::to_string))
+            .take(RECENT_VERSIONS)
+            .collect(),
+        requested: requested.map(|num| match version_entry(num) {
+            Some(v) => VersionStatus {
+                version: num.to_string(),
+                exists: true,
+                withdrawn: (v["yanked"].as_bool() == Some(true)).then(|| "yanked".to_string()),
+                published: v["created_at"].as_str().map(date),
+            },
+            None => VersionStatus {
+                version: num.to_string(),
+                ..Default::default()
+            },
+        }),
+        ..Default::default()
+    }
+}
+
+fn parse_npm(
+    metadata: &Value,
+    downloads: Option<&Value>,
+    requested: Option<&str>,
+) -> PackageMetadata {
+    let latest = str_field(&metadata["dist-tags"], "latest");
+    let next = str_field(&metadata["dist-tags"], "next");
+    let versions = &metadata["versions"];
+    let time = &metadata["time"];
+    let latest_manifest = latest.as_deref().map(|v| &versions[v]);
+    let repository = match &metadata["repository"] {
+        Value::String(url) => Some(browsable_repo(url)),
+        repo => str_field(repo, "url").map(|url| browsable_repo(&url)),
+    };
+
+    let mut recent: Vec<(String, String)> = time
+        .as_object()
+        .map(|t| {
+            t.iter()
+                .filter(|(k, _)| versions.get(k.as_str()).is_some())
+                .filter_map(|(k, v)| v.as_str().map(|ts| (ts.to_string(), k.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+    recent.sort_by(|a, b| b.0.cmp(&a.0));
+
+    PackageMetadata {
+        name: str_field(metadata, "name").unwrap_or_default(),
+        published: latest.as_deref().and_then(|v| time[v].as_str()).map(date),
+        latest_prerelease: next.filter(|n| Some(n) != latest.as_ref()),
+        description: str_field(metadata, "description"),
+        deprecated: latest_manifest.and_then(|m| str_field(m, "deprecated")),
+        downloads: downloads
+            .and_then(|d| d["downloads"].as_u64())
+            .map(|n| vec![("last week", n)])
+            .unwrap_or_default(),
+        homepage: str_field(metadata, "homepage"),
+        changelog: releases_link(repository.as_deref()),
+        repository,
+        recent_versions: recent
+            .into_iter()
+            .map(|(_, v)| v)
+            .take(RECENT_VERSIONS)
+            .collect(),
+        requested: requested.map(|num| {
+            let manifest = &versions[num];
+            VersionStatus {
+                version: num.to_string(),
+                exists: manifest.is_object(),
+                withdrawn: str_field(manifest, "deprecated"),
+                published: time[num].as_str().map(date),
+            }
+        }),
+        latest,
+        ..Default::default()
+    }
+}
+
+fn parse_pypi(metadata: &Value, stats: Option<&Value>, requested: Option<&str>) -> PackageMetadata {
+    let info = &metadata["info"];
+    let releases = metadata["releases"]
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+    let latest = str_field(info, "version");
+    let files = |v: &str| releases.get(v).and_then(|f| f.as_array()).cloned();
+    let upload_date = |v: &str| {
+        files(v).and_then(|f| f.first().cloned()).and_then(|f| {
+            f["upload_time_iso_8601"]
+                .as_str()
+                .or(f["upload_time"].as_str())
+                .map(date)
+        })
+    };
+    let yank_reason = |v: &str| {
+        let files = files(v)?;
+        if !files.is_empty() && files.iter().all(|f| f["yanked"].as_bool() == Some(true)) {
+            Some(str_field(&files[0], "yanked_reason").unwrap_or_else(|| "yanked".to_string()))
+        } else {
+            None
+        }
+    };
+
+    let project_urls = info["project_urls"]
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+    let project_url = |keys: &[&str]| {
+        project_urls
+            .iter()
+            .find(|(k, _)| {
+                let k = k.to_ascii_lowercase();
+                keys.iter().any(|key| k.contains(key))
+            })
+            .and_then(|(_, v)| v.as_str().map(str::to_string))
+    };
+    let repository =
+        project_url(&["source", "repository", "github", "code"]).map(|r| browsable_repo(&r));
+    let inactive = info["classifiers"].as_array().is_some_and(|c| {
+        c.iter()
+            .any(|c| c.as_str() == Some("Development Status :: 7 - Inactive"))
+    });
+    let deprecated = latest
+        .as_deref()
+        .and_then(yank_reason)
+        .map(|reason| format!("the latest version is yanked ({})", reason))
+        .or_else(|| inactive.then(|| "marked 'Development Status :: 7 - Inactive'".to_string()));
+
+    let mut recent: Vec<(String, String)> = releases
+        .keys()
+        .filter(|v| yank_reason(v).is_none())
+        .filter_map(|v| upload_date(v).map(|d| (d, v.clone())))
+        .collect();
+    recent.sort_by(|a, b| b.0.cmp(&a.0));
+    let latest_prerelease = recent
+        .iter()
+        .map(|(_, v)| v)
+        .take_while(|v| Some(*v) != latest.as_ref())
+        .find(|v| is_prerelease(v))
+        .cloned();
+
+    let mut downloads = Vec::new();
+    for (key, label) in [
+        ("last_day", "last day"),
+        ("last_week", "last week"),
+        ("last_month", "last month"),
+    ] {
+        if let Some(n) = stats.and_then(|s| s["data"][key].as_u64()) {
+            downloads.push((label, n));
+        }
+    }
+
+    PackageMetadata {
+        name: str_field(info, "name").unwrap_or_default(),
+        published: latest.as_deref().and_then(upload_date),
+        latest_prerelease,
+        description: str_field(info, "summary"),
+        deprecated,
+        downloads,
+        homepage: project_url(&["homepage", "home"]).or_else(|| str_field(info, "home_page")),
+        changelog: project_url(&["changelog", "changes", "release notes", "history", "news"])
+            .or_else(|| releases_link(repository.as_deref())),
+        repository,
+        recent_versions: recent
+            .into_iter()
+            .map(|(_, v)| v)
+            .take(RECENT_VERSIONS)
+            .collect(),
+        requested: requested.map(|num| VersionStatus {
+            version: num.to_string(),
+            exists: releases.contains_key(num),
+            withdrawn: yank_reason(num),
+            published: upload_date(num),
+        }),
+        latest,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_crates_io_metadata() {
+        let metadata = json!({
+            "crate": {
+                "name": "tokio",
+                "description": "An event-driven, non-blocking I/O platform.",
+                "max_version": "2.0.0-alpha.1",
+                "max_stable_version": "1.40.0",
+                "downloads": 1000,
+                "recent_downloads": 100,
+                "repository": "https://github.com/tokio-rs/tokio"
+            },
+            "versions": [
+                {"num": "2.0.0-alpha.1", "yanked": false, "created_at": "2024-09-01T00:00:00Z"},
+                {"num": "1.40.0", "yanked": false, "created_at": "2024-08-30T12:00:00Z"},
+                {"num": "1.39.0", "yanked": true, "created_at": "2024-07-01T00:00:00Z"}
+            ]
+        });
+        let info = parse_crates_io(&metadata, Some("1.39.0"));
+        assert_eq!(info.latest.as_deref(), Some("1.40.0"));
+        assert_eq!(info.latest_prerelease.as_deref(), Some("2.0.0-alpha.1"));
+        assert_eq!(info.published.as_deref(), Some("2024-08-30"));
+        assert_eq!(info.recent_versions, vec!["2.0.0-alpha.1", "1.40.0"]);
+        assert_eq!(
+            info.changelog.as_deref(),
+            Some("https://github.com/tokio-rs/tokio/releases")
+        );
+        let requested = info.requested.unwrap();
+        assert!(requested.exists);
+        assert_eq!(requested.withdrawn.as_deref(), Some("yanked"));
+    }
+
+    #[test]
+    fn parses_npm_metadata_with_deprecation() {
+        let metadata = json!({
+            "name": "request",
+            "description": "Simplified HTTP request client.",
+            "dist-tags": {"latest": "2.88.2"},
+            "versions": {
+                "2.88.0": {},
+                "2.88.2": {"deprecated": "request has been deprecated"}
+            },
+            "time": {
+                "created": "2011-01-01T00:00:00.000Z",
+                "modified": "2024-01-01T00:00:00.000Z",
+                "2.88.0": "2018-08-10T00:00:00.000Z",
+                "2.88.2": "2020-02-11T00:00:00.000Z"
+            },
+            "repository": {"type": "git", "url": "git+https://github.com/request/request.git"}
+        });
+        let info = parse_npm(&metadata, Some(&json!({"downloads": 42})), Some("3.0.0"));
+        assert_eq!(info.latest.as_deref(), Some("2.88.2"));
+        assert_eq!(
+            info.deprecated.as_deref(),
+            Some("request has been deprecated")
+        );
+        assert_eq!(info.downloads, vec![("last week", 42)]);
+        assert_eq!(info.recent_versions, vec!["2.88.2", "2.88.0"]);
+        assert_eq!(
+            info.repository.as_deref(),
+            Some("https://github.com/request/request")
+        );
+        assert!(!info.requested.unwrap().exists);
+    }
+
+    #[test]
+    fn parses_pypi_metadata() {
+        let metadata = json!({
+            "info": {
+                "name": "requests",
+                "summary": "Python HTTP for Humans.",
+                "version": "2.32.3",
+                "project_urls": {
+                    "Changelog": "https://github.com/psf/requests/blob/main/HISTORY.md",
+                    "Source": "https://github.com/psf/requests"
+                },
+                "classifiers": []
+            },
+            "releases": {
+                "2.32.3": [{"upload_time_iso_8601": "2024-05-29T15:37:47Z", "yanked": false}],
+                "2.32.0": [{"upload_time_iso_8601": "2024-05-20T00:00:00Z", "yanked": true, "yanked_reason": "Broken"}],
+                "2.33.0rc1": [{"upload_time_iso_8601": "2024-06-01T00:00:00Z", "yanked": false}]
+            }
+        });
+        let stats = json!({"data": {"last_day": 1, "last_week": 7, "last_month": 30}});
+        let info = parse_pypi(&metadata, Some(&stats), Some("2.32.0"));
+        assert_eq!(info.latest.as_deref(), Some("2.32.3"));
+        assert_eq!(info.latest_prerelease.as_deref(), Some("2.33.0rc1"));
+        assert_eq!(info.published.as_deref(), Some("2024-05-29"));
+        assert_eq!(
+            info.changelog.as_deref(),
+            Some("https://github.com/psf/requests/blob/main/HISTORY.md")
+        );
+        assert_eq!(info.downloads.len(), 3);
+        assert_eq!(info.recent_versions, vec!["2.33.0rc1", "2.32.3"]);
+        assert_eq!(info.requested.unwrap().withdrawn.as_deref(), Some("Broken"));
+    }
+
+    #[test]
+    fn validates_names_and_formats_summary() {
+        assert!(valid_package_name("serde_json"));
+        assert!(valid_package_name("@types/node"));
+        assert!(!valid_package_name("../etc/passwd"));
+        assert!(!valid_package_name("@/x"));
+
+        let info = PackageMetadata {
+            registry: Some(Registry::Npm),
+            name: "left-pad".to_string(),
+            latest: Some("1.3.0".to_string()),
+            deprecated: Some("use String.prototype.padStart()".to_string()),
+            requested: Some(VersionStatus {
+                version: "9.9.9".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let text = info.format();
+        assert!(text.starts_with("left-pad (npm)"));
+        assert!(text.contains("Latest version: 1.3.0"));
+        assert!(text.contains("DEPRECATED: use String.prototype.padStart()"));
+        assert!(text.contains("Version 9.9.9: does not exist"));
+    }
+}
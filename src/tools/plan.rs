@@ -0,0 +1,291 @@
+//! Step-level mission plans.
+//!
+//! `set_plan` records the agent's plan as an ordered list of steps and
+//! `update_plan` changes the status of one step as work progresses. The
+//! workspace MCP keeps the plan in [`PLAN_FILE`] in the mission directory to
+//! validate updates and echo the plan back to the agent. The control session
+//! applies the same calls, seen as tool call events, to the plan stored on the
+//! mission and emits a `plan_updated` event, so users follow step-level
+//! progress instead of individual tool calls.
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Tool, ToolArgs};
+
+/// The plan as the workspace MCP last wrote it.
+pub const PLAN_FILE: &str = ".sandboxed-sh_plan.json";
+
+/// Most steps in a plan.
+pub const MAX_STEPS: usize = 50;
+
+const MAX_TITLE_CHARS: usize = 200;
+const MAX_NOTE_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Completed,
+    Skipped,
+    Failed,
+}
+
+impl StepStatus {
+    fn marker(self) -> &'static str {
+        match self {
+            Self::Pending => "[ ]",
+            Self::InProgress => "[~]",
+            Self::Completed => "[x]",
+            Self::Skipped => "[-]",
+            Self::Failed => "[!]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PlanStep {
+    /// Short description of the step
+    pub title: String,
+    /// Defaults to 'pending'
+    #[serde(default)]
+    pub status: StepStatus,
+    /// Outcome or reason, e.g. why the step was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A mission's plan.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MissionPlan {
+    pub steps: Vec<PlanStep>,
+}
+
+impl MissionPlan {
+    /// Steps that are completed or skipped.
+    pub fn done(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|s| matches!(s.status, StepStatus::Completed | StepStatus::Skipped))
+            .count()
+    }
+
+    /// The plan as a checklist for tool output.
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!("Plan ({}/{} done):", self.done(), self.steps.len())];
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut line = format!("{} {}. {}", step.status.marker(), i + 1, step.title);
+            if let Some(ref note) = step.note {
+                line.push_str(&format!(" ({})", note));
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct SetPlanArgs {
+    /// Steps in the order they will be done (replaces the current plan)
+    #[schemars(length(min = 1, max = 50))]
+    steps: Vec<PlanStep>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct UpdatePlanArgs {
+    /// Step number (1-based)
+    #[schemars(range(min = 1))]
+    step: usize,
+    status: StepStatus,
+    /// Outcome or reason, replaces the step's note
+    #[serde(default)]
+    note: Option<String>,
+}
+
+fn clip(text: &str, max_chars: usize) -> String {
+    text.trim().chars().take(max_chars).collect()
+}
+
+/// Which plan tool a harness tool name refers to. Harnesses prefix MCP tools
+/// with the server name (`mcp__workspace__set_plan`, `workspace_set_plan`).
+pub fn plan_tool(name: &str) -> Option<&'static str> {
+    ["set_plan", "update_plan"]
+        .into_iter()
+        .find(|tool| name == *tool || name.ends_with(&format!("_{}", tool)))
+}
+
+/// Apply a `set_plan` or `update_plan` call to a plan.
+pub fn apply(plan: Option<&MissionPlan>, tool: &str, args: &Value) -> Result<MissionPlan, String> {
+    match tool {
+        "set_plan" => {
+            let args: SetPlanArgs =
+                serde_json::from_value(args.clone()).map_err(|e| e.to_string())?;
+            if args.steps.is_empty() || args.steps.len() > MAX_STEPS {
+                return Err(format!("A plan has 1 to {} steps", MAX_STEPS));
+            }
+            let steps = args
+                .steps
+                .into_iter()
+                .map(|step| PlanStep {
+                    title: clip(&step.title, MAX_TITLE_CHARS),
+                    status: step.status,
+                    note: step.note.map(|n| clip(&n, MAX_NOTE_CHARS)),
+                })
+                .collect::<Vec<_>>();
+            if steps.iter().any(|s| s.title.is_empty()) {
+                return Err("Every step needs a title".to_string());
+            }
+            Ok(MissionPlan { steps })
+        }
+        "update_plan" => {
+            let args: UpdatePlanArgs =
+                serde_json::from_value(args.clone()).map_err(|e| e.to_string())?;
+            let mut plan = plan.cloned().ok_or("No plan yet: call set_plan first")?;
+            let count = plan.steps.len();
+            let step = args
+                .step
+                .checked_sub(1)
+                .and_then(|i| plan.steps.get_mut(i))
+                .ok_or_else(|| format!("Step {} does not exist (plan has {})", args.step, count))?;
+            step.status = args.status;
+            if let Some(note) = args.note {
+                step.note = Some(clip(&note, MAX_NOTE_CHARS)).filter(|n| !n.is_empty());
+            }
+            Ok(plan)
+        }
+        other => Err(format!("Unknown plan tool: {}", other)),
+    }
+}
+
+/// Read the plan the workspace MCP last wrote in a mission directory.
+pub fn read_plan(work_dir: &Path) -> Option<MissionPlan> {
+    let contents = std::fs::read_to_string(work_dir.join(PLAN_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn run(tool: &str, args: &Value, working_dir: &Path) -> anyhow::Result<String> {
+    let plan = apply(read_plan(working_dir).as_ref(), tool, args).map_err(anyhow::Error::msg)?;
+    std::fs::write(working_dir.join(PLAN_FILE), serde_json::to_string(&plan)?)?;
+    Ok(plan.render())
+}
+
+/// Record the mission plan.
+pub struct SetPlan;
+
+#[async_trait]
+impl Tool for SetPlan {
+    fn name(&self) -> &str {
+        "set_plan"
+    }
+
+    fn description(&self) -> &str {
+        "Record your plan for the task as an ordered list of steps before starting multi-step work, and again if the plan changes. The user sees the plan and each step's status. Mark progress with update_plan."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        SetPlanArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        run("set_plan", &args, working_dir)
+    }
+}
+
+/// Update the status of one plan step.
+pub struct UpdatePlan;
+
+#[async_trait]
+impl Tool for UpdatePlan {
+    fn name(&self) -> &str {
+        "update_plan"
+    }
+
+    fn description(&self) -> &str {
+        "Update the status of a step in your plan (in_progress when you start it, then completed, skipped or failed), optionally with a short note on the outcome."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        UpdatePlanArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        run("update_plan", &args, working_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn set_and_update_plan() {
+        let plan = apply(
+            None,
+            "set_plan",
+            &json!({ "steps": [{ "title": "Reproduce" }, { "title": "Fix", "status": "in_progress" }] }),
+        )
+        .unwrap();
+        assert_eq!(plan.steps[0].status, StepStatus::Pending);
+        assert_eq!(plan.steps[1].status, StepStatus::InProgress);
+
+        let plan = apply(
+            Some(&plan),
+            "update_plan",
+            &json!({ "step": 1, "status": "completed", "note": "fails on empty input" }),
+        )
+        .unwrap();
+        assert_eq!(plan.done(), 1);
+        assert_eq!(
+            plan.render(),
+            "Plan (1/2 done):\n[x] 1. Reproduce (fails on empty input)\n[~] 2. Fix"
+        );
+
+        assert!(apply(
+            Some(&plan),
+            "update_plan",
+            &json!({ "step": 3, "status": "failed" })
+        )
+        .unwrap_err()
+        .contains("does not exist"));
+        assert!(apply(
+            None,
+            "update_plan",
+            &json!({ "step": 1, "status": "failed" })
+        )
+        .is_err());
+        assert!(apply(None, "set_plan", &json!({ "steps": [{ "title": " " }] })).is_err());
+    }
+
+    #[test]
+    fn recognizes_prefixed_tool_names() {
+        assert_eq!(plan_tool("set_plan"), Some("set_plan"));
+        assert_eq!(
+            plan_tool("mcp__workspace__update_plan"),
+            Some("update_plan")
+        );
+        assert_eq!(plan_tool("workspace_set_plan"), Some("set_plan"));
+        assert_eq!(plan_tool("terraform_plan"), None);
+    }
+
+    #[tokio::test]
+    async fn tools_share_plan_file() {
+        let dir = tempfile::tempdir().unwrap();
+        SetPlan
+            .execute(json!({ "steps": [{ "title": "Build" }] }), dir.path())
+            .await
+            .unwrap();
+        let output = UpdatePlan
+            .execute(json!({ "step": 1, "status": "completed" }), dir.path())
+            .await
+            .unwrap();
+        assert!(output.contains("[x] 1. Build"));
+        assert_eq!(read_plan(dir.path()).unwrap().done(), 1);
+    }
+}
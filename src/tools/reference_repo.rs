@@ -0,0 +1,94 @@
+//! Read-only checkouts of other repositories for reference.
+//!
+//! Wraps [`crate::reference_repos`]: the checkout lands in the shared cache,
+//! which container workspaces see read-only at
+//! [`reference_repos::CONTAINER_PATH`]. Containers that were already running
+//! get the cache bound in with `machinectl bind`.
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::terminal::{container_root_from_env, running_workspace_container};
+use super::Tool;
+use crate::reference_repos;
+
+/// Clone (or reuse) a repository into the shared reference cache.
+pub struct CheckoutReferenceRepo;
+
+#[async_trait]
+impl Tool for CheckoutReferenceRepo {
+    fn name(&self) -> &str {
+        "checkout_reference_repo"
+    }
+
+    fn description(&self) -> &str {
+        "Check out a repository (e.g. a dependency's source) into a shared read-only cache and return its path. Reuses an existing checkout when possible. Use this to read library source code instead of cloning into the workspace; the checkout cannot be modified."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "Remote git URL (https, ssh or git@host:path)"
+                },
+                "ref": {
+                    "type": "string",
+                    "description": "Branch, tag or full commit SHA (default: the remote's default branch)"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let url = args["url"]
+            .as_str()
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'url' argument"))?;
+        let git_ref = args["ref"]
+            .as_str()
+            .map(str::trim)
+            .filter(|r| !r.is_empty());
+
+        let checkout = reference_repos::checkout(url, git_ref).await?;
+
+        let path = if container_root_from_env().is_some() {
+            if let Some((machine, leader)) = running_workspace_container().await {
+                if let Err(e) =
+                    reference_repos::bind_into_running_container(&machine, &leader).await
+                {
+                    tracing::warn!("Failed to bind reference repos into {}: {}", machine, e);
+                }
+            }
+            format!("{}/{}", reference_repos::CONTAINER_PATH, checkout.repo.key)
+        } else {
+            checkout.path.display().to_string()
+        };
+
+        let mut out = format!(
+            "{} {} @ {} (commit {}) at {}\nRead-only reference checkout, {:.1} MB.",
+            if checkout.reused {
+                "Reused"
+            } else {
+                "Checked out"
+            },
+            checkout.repo.url,
+            git_ref.unwrap_or("default branch"),
+            &checkout.repo.commit[..checkout.repo.commit.len().min(12)],
+            path,
+            checkout.repo.size_bytes as f64 / (1024.0 * 1024.0)
+        );
+        if !checkout.evicted.is_empty() {
+            out.push_str(&format!(
+                "\nEvicted from cache: {}",
+                checkout.evicted.join(", ")
+            ));
+        }
+        Ok(out)
+    }
+}
@@ -10,14 +10,37 @@ use std::path::Path;
 use std::process::Stdio;
 
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
 use tokio::process::Command;
 
-use super::{resolve_path, Tool};
+use super::{glob_match, resolve_path, Tool, ToolArgs};
+use crate::host::command_on_path;
+
+/// Most matches returned.
+const MAX_MATCHES: usize = 100;
+/// Files larger than this are skipped by the built-in search.
+const MAX_BUILTIN_FILE_BYTES: u64 = 10 * 1024 * 1024;
 
 /// Search file contents with regex/grep.
 pub struct GrepSearch;
 
+#[derive(Deserialize, JsonSchema)]
+struct GrepArgs {
+    /// Regex pattern to search for
+    pattern: String,
+    /// Directory to search. Defaults to workspace ('.'). Use relative paths for subdirectories or absolute for system search.
+    #[serde(default)]
+    path: Option<String>,
+    /// Optional: only search files matching this glob (e.g., '*.rs', '*.py', '*.log')
+    #[serde(default)]
+    file_pattern: Option<String>,
+    /// Whether search is case-sensitive (default: false)
+    #[serde(default)]
+    case_sensitive: bool,
+}
+
 #[async_trait]
 impl Tool for GrepSearch {
     fn name(&self) -> &str {
@@ -29,43 +52,37 @@ impl Tool for GrepSearch {
     }
 
     fn parameters_schema(&self) -> Value {
-        json!({
-            "type": "object",
-            "properties": {
-                "pattern": {
-                    "type": "string",
-                    "description": "Regex pattern to search for"
-                },
-                "path": {
-                    "type": "string",
-                    "description": "Directory to search. Defaults to workspace ('.'). Use relative paths for subdirectories or absolute for system search."
-                },
-                "file_pattern": {
-                    "type": "string",
-                    "description": "Optional: only search files matching this glob (e.g., '*.rs', '*.py', '*.log')"
-                },
-                "case_sensitive": {
-                    "type": "boolean",
-                    "description": "Whether search is case-sensitive (default: false)"
-                }
-            },
-            "required": ["pattern"]
-        })
+        GrepArgs::schema()
     }
 
     async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
-        let pattern = args["pattern"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Missing 'pattern' argument"))?;
-        let path = args["path"].as_str().unwrap_or(".");
-        let file_pattern = args["file_pattern"].as_str();
-        let case_sensitive = args["case_sensitive"].as_bool().unwrap_or(false);
+        let args = GrepArgs::parse(args)?;
+        let pattern = args.pattern.as_str();
+        let path = args.path.as_deref().unwrap_or(".");
+        let file_pattern = args.file_pattern.as_deref();
+        let case_sensitive = args.case_sensitive;
 
         let resolution = resolve_path(path, working_dir);
         let search_path = resolution.resolved;
 
-        // Try to use ripgrep (rg) if available, fall back to grep
-        let mut cmd = if which_exists("rg") {
+        // Try to use ripgrep (rg) if available, fall back to grep, then to a
+        // built-in search on hosts without either (e.g. Windows).
+        let have_rg = command_on_path("rg");
+        if !have_rg && !command_on_path("grep") {
+            let pattern = pattern.to_string();
+            let file_pattern = file_pattern.map(str::to_lowercase);
+            let matches = tokio::task::spawn_blocking(move || {
+                builtin_search(
+                    &pattern,
+                    &search_path,
+                    file_pattern.as_deref(),
+                    case_sensitive,
+                )
+            })
+            .await??;
+            return Ok(format_matches(args.pattern.as_str(), matches));
+        }
+        let mut cmd = if have_rg {
             let mut c = Command::new("rg");
             c.arg("--line-number");
             c.arg("--no-heading");
@@ -112,31 +129,93 @@ impl Tool for GrepSearch {
             return Err(anyhow::anyhow!("Search error: {}", stderr));
         }
 
-        if stdout.is_empty() {
-            return Ok(format!("No matches found for pattern: {}", pattern));
-        }
+        Ok(format_matches(
+            pattern,
+            stdout
+                .lines()
+                .take(MAX_MATCHES)
+                .map(str::to_string)
+                .collect(),
+        ))
+    }
+}
 
-        // Show results with full paths for system-wide clarity
-        let result: String = stdout
-            .lines()
-            .take(100) // Limit results
-            .collect::<Vec<_>>()
-            .join("\n");
+fn format_matches(pattern: &str, matches: Vec<String>) -> String {
+    if matches.is_empty() {
+        return format!("No matches found for pattern: {}", pattern);
+    }
+    // Show results with full paths for system-wide clarity
+    let result = matches.join("\n");
+    if matches.len() >= MAX_MATCHES {
+        format!("{}\n\n... (showing first {} matches)", result, MAX_MATCHES)
+    } else {
+        result
+    }
+}
 
-        let line_count = result.lines().count();
-        if line_count >= 100 {
-            Ok(format!("{}\n\n... (showing first 100 matches)", result))
-        } else {
-            Ok(result)
+/// In-process search producing `path:line:text` like grep, used when neither
+/// ripgrep nor grep is installed. Skips hidden directories, large files and
+/// files that are not UTF-8.
+fn builtin_search(
+    pattern: &str,
+    root: &Path,
+    file_pattern: Option<&str>,
+    case_sensitive: bool,
+) -> anyhow::Result<Vec<String>> {
+    let re = regex::RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Search error: invalid pattern: {}", e))?;
+    let mut matches = Vec::new();
+    let entries = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file());
+    for entry in entries {
+        if let Some(file_pattern) = file_pattern {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            if !glob_match(file_pattern, &name) {
+                continue;
+            }
+        }
+        if entry.metadata().map(|m| m.len()).unwrap_or(0) > MAX_BUILTIN_FILE_BYTES {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        for (idx, line) in content.lines().enumerate() {
+            if re.is_match(line) {
+                matches.push(format!("{}:{}:{}", entry.path().display(), idx + 1, line));
+                if matches.len() >= MAX_MATCHES {
+                    return Ok(matches);
+                }
+            }
         }
     }
+    Ok(matches)
 }
 
-/// Check if a command exists in PATH.
-fn which_exists(cmd: &str) -> bool {
-    std::process::Command::new("which")
-        .arg(cmd)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_search_matches_like_grep() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn main() {}\nlet Needle = 1;\n").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "needle\n").unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".git/config"), "needle\n").unwrap();
+
+        let mut all = builtin_search("needle", dir.path(), None, false).unwrap();
+        all.sort();
+        assert_eq!(all.len(), 2);
+        assert!(all[0].ends_with("a.rs:2:let Needle = 1;"));
+
+        let rs_only = builtin_search("needle", dir.path(), Some("*.rs"), true).unwrap();
+        assert!(rs_only.is_empty());
+        assert!(builtin_search("(", dir.path(), None, true).is_err());
+    }
 }
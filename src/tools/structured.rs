@@ -0,0 +1,1079 @@
+//! Structured data tools: `query_structured` and `patch_structured`.
+//!
+//! They read and edit JSON, YAML and TOML files by path (`.server.port`,
+//! `.services[0].env`, `.dependencies["serde"]`) so config-editing missions
+//! change one value instead of rewriting the whole file.
+//!
+//! Edits keep the file's formatting as far as each format allows:
+//! - TOML is edited with `toml_edit`, which keeps comments, ordering and
+//!   whitespace; replaced values keep their trailing comments.
+//! - YAML scalar updates and deletions are made in place on the text, so
+//!   comments and layout survive. Other YAML edits (new keys, non-scalar
+//!   values) re-serialize the document, which drops comments; the result
+//!   says when that happened.
+//! - JSON keeps key order and indentation.
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value as Json;
+use serde_yaml::{Mapping, Value as Tree};
+use toml_edit::DocumentMut;
+
+use super::{resolve_path_simple as resolve_path, Tool, ToolArgs};
+
+/// Largest result `query_structured` returns.
+const MAX_OUTPUT_CHARS: usize = 40_000;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl Segment {
+    fn display(path: &[Segment]) -> String {
+        let mut out = String::new();
+        for segment in path {
+            match segment {
+                Segment::Key(key) => {
+                    out.push('.');
+                    out.push_str(key);
+                }
+                Segment::Index(idx) => out.push_str(&format!("[{}]", idx)),
+                Segment::Wildcard => out.push_str("[*]"),
+            }
+        }
+        if out.is_empty() {
+            ".".to_string()
+        } else {
+            out
+        }
+    }
+}
+
+/// Parse a jq/yq-style path: `.a.b[0]`, `a.b.0`, `.a["x.y"]`, `.items[*].name`.
+fn parse_path(path: &str) -> anyhow::Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut chars = path.trim().chars().peekable();
+    let mut key = String::new();
+    let push_key = |key: &mut String, segments: &mut Vec<Segment>| {
+        if !key.is_empty() {
+            segments.push(match key.parse::<usize>() {
+                Ok(idx) => Segment::Index(idx),
+                Err(_) => Segment::Key(std::mem::take(key)),
+            });
+            key.clear();
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => push_key(&mut key, &mut segments),
+            '[' => {
+                push_key(&mut key, &mut segments);
+                let mut inner = String::new();
+                let mut closed = false;
+                let quote = chars.peek().copied().filter(|q| *q == '"' || *q == '\'');
+                if let Some(q) = quote {
+                    chars.next();
+                    for c in chars.by_ref() {
+                        if c == q {
+                            break;
+                        }
+                        inner.push(c);
+                    }
+                    if chars.next() == Some(']') {
+                        closed = true;
+                    }
+                    segments.push(Segment::Key(inner));
+                } else {
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            closed = true;
+                            break;
+                        }
+                        inner.push(c);
+                    }
+                    let inner = inner.trim();
+                    segments.push(match inner {
+                        "" | "*" => Segment::Wildcard,
+                        _ => Segment::Index(inner.parse().map_err(|_| {
+                            anyhow::anyhow!("Invalid index '[{}]' in path '{}'", inner, path)
+                        })?),
+                    });
+                }
+                if !closed {
+                    anyhow::bail!("Unclosed '[' in path '{}'", path);
+                }
+            }
+            '*' if key.is_empty() => segments.push(Segment::Wildcard),
+            _ => key.push(c),
+        }
+    }
+    push_key(&mut key, &mut segments);
+    Ok(segments)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    fn detect(path: &Path, explicit: Option<&str>) -> anyhow::Result<Self> {
+        let name = explicit
+            .map(str::to_string)
+            .or_else(|| {
+                path.extension()
+                    .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            })
+            .unwrap_or_default();
+        match name.as_str() {
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            _ => anyhow::bail!(
+                "Cannot tell the format of {}; pass format: json, yaml or toml",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Parse `text` into an order-preserving tree (YAML values cover JSON and TOML).
+fn parse_tree(text: &str, format: Format) -> anyhow::Result<Tree> {
+    match format {
+        Format::Json => Ok(serde_json::from_str(text)?),
+        Format::Yaml => {
+            let mut documents = Vec::new();
+            for document in serde_yaml::Deserializer::from_str(text) {
+                documents.push(Tree::deserialize(document)?);
+            }
+            match documents.len() {
+                0 => Ok(Tree::Null),
+                1 => Ok(documents.remove(0)),
+                // Multi-document files query as a list of documents.
+                _ => Ok(Tree::Sequence(documents)),
+            }
+        }
+        Format::Toml => Ok(toml_to_tree(text.parse::<DocumentMut>()?.as_item())),
+    }
+}
+
+fn toml_value_to_tree(value: &toml_edit::Value) -> Tree {
+    use toml_edit::Value as V;
+    match value {
+        V::String(s) => Tree::String(s.value().clone()),
+        V::Integer(i) => Tree::Number((*i.value()).into()),
+        V::Float(f) => Tree::Number((*f.value()).into()),
+        V::Boolean(b) => Tree::Bool(*b.value()),
+        V::Datetime(d) => Tree::String(d.value().to_string()),
+        V::Array(array) => Tree::Sequence(array.iter().map(toml_value_to_tree).collect()),
+        V::InlineTable(table) => Tree::Mapping(
+            table
+                .iter()
+                .map(|(k, v)| (Tree::String(k.to_string()), toml_value_to_tree(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn toml_to_tree(item: &toml_edit::Item) -> Tree {
+    match item {
+        toml_edit::Item::None => Tree::Null,
+        toml_edit::Item::Value(value) => toml_value_to_tree(value),
+        toml_edit::Item::Table(table) => Tree::Mapping(
+            table
+                .iter()
+                .map(|(k, v)| (Tree::String(k.to_string()), toml_to_tree(v)))
+                .collect(),
+        ),
+        toml_edit::Item::ArrayOfTables(tables) => Tree::Sequence(
+            tables
+                .iter()
+                .map(|t| toml_to_tree(&toml_edit::Item::Table(t.clone())))
+                .collect(),
+        ),
+    }
+}
+
+fn child<'a>(node: &'a Tree, segment: &Segment) -> Option<&'a Tree> {
+    match (node, segment) {
+        (Tree::Mapping(map), Segment::Key(key)) => map.get(key.as_str()),
+        (Tree::Mapping(map), Segment::Index(idx)) => map.get(idx.to_string().as_str()),
+        (Tree::Sequence(seq), Segment::Index(idx)) => seq.get(*idx),
+        _ => None,
+    }
+}
+
+/// All nodes matching `path`; wildcards fan out over sequences and mappings.
+fn select<'a>(node: &'a Tree, path: &[Segment]) -> Vec<&'a Tree> {
+    let Some((first, rest)) = path.split_first() else {
+        return vec![node];
+    };
+    match first {
+        Segment::Wildcard => match node {
+            Tree::Sequence(seq) => seq.iter().flat_map(|n| select(n, rest)).collect(),
+            Tree::Mapping(map) => map.values().flat_map(|n| select(n, rest)).collect(),
+            _ => Vec::new(),
+        },
+        segment => child(node, segment)
+            .map(|n| select(n, rest))
+            .unwrap_or_default(),
+    }
+}
+
+/// Read values from a JSON, YAML or TOML file by path.
+pub struct QueryStructured;
+
+#[derive(Deserialize, JsonSchema)]
+struct QueryArgs {
+    /// File to read
+    path: String,
+    /// Path to select, e.g. `.server.port`, `.services[*].image` (default: whole document)
+    #[serde(default)]
+    query: Option<String>,
+    /// json, yaml or toml (default: from the file extension)
+    #[serde(default)]
+    format: Option<String>,
+    /// Return only the keys of the selected mapping(s)
+    #[serde(default)]
+    keys: bool,
+}
+
+fn run_query(text: &str, format: Format, query: &str, keys: bool) -> anyhow::Result<String> {
+    let tree = parse_tree(text, format)?;
+    let path = parse_path(query)?;
+    let matches = select(&tree, &path);
+    if matches.is_empty() {
+        anyhow::bail!("Nothing matches '{}'", Segment::display(&path));
+    }
+    let results: Vec<Tree> = matches
+        .into_iter()
+        .map(|node| match (keys, node) {
+            (true, Tree::Mapping(map)) => Tree::Sequence(map.keys().cloned().collect()),
+            _ => node.clone(),
+        })
+        .collect();
+    let wildcard = path.contains(&Segment::Wildcard);
+    let out = if wildcard {
+        serde_json::to_string_pretty(&results)?
+    } else {
+        serde_json::to_string_pretty(&results[0])?
+    };
+    if out.len() > MAX_OUTPUT_CHARS {
+        let end = super::safe_truncate_index(&out, MAX_OUTPUT_CHARS);
+        return Ok(format!(
+            "{}\n[... truncated; query a narrower path or use keys: true ...]",
+            &out[..end]
+        ));
+    }
+    Ok(out)
+}
+
+#[async_trait]
+impl Tool for QueryStructured {
+    fn name(&self) -> &str {
+        "query_structured"
+    }
+
+    fn description(&self) -> &str {
+        "Read values from a JSON, YAML or TOML file by path (jq/yq style): `.server.port`, `.services[0].env`, `.items[*].name`, `.deps[\"serde\"]`. Returns JSON. Use keys: true to list a mapping's keys."
+    }
+
+    fn parameters_schema(&self) -> Json {
+        QueryArgs::schema()
+    }
+
+    async fn execute(&self, args: Json, working_dir: &Path) -> anyhow::Result<String> {
+        let args = QueryArgs::parse(args)?;
+        let path = resolve_path(&args.path, working_dir);
+        let format = Format::detect(&path, args.format.as_deref())?;
+        let text = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?;
+        run_query(
+            &text,
+            format,
+            args.query.as_deref().unwrap_or("."),
+            args.keys,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum PatchOp {
+    /// Set the value at `path`, creating missing parent mappings
+    Set,
+    /// Deep-merge a mapping into the mapping at `path`
+    Merge,
+    /// Remove the key or list item at `path`
+    Delete,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+struct Patch {
+    op: PatchOp,
+    /// Target path, e.g. `.server.port` or `.services[1]`
+    path: String,
+    /// New value (for set and merge)
+    #[serde(default)]
+    value: Option<Json>,
+}
+
+fn patch_value(patch: &Patch) -> anyhow::Result<&Json> {
+    patch
+        .value
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("'{}' on {} needs a value", op_name(&patch.op), patch.path))
+}
+
+fn op_name(op: &PatchOp) -> &'static str {
+    match op {
+        PatchOp::Set => "set",
+        PatchOp::Merge => "merge",
+        PatchOp::Delete => "delete",
+    }
+}
+
+fn plain_path(path: &str) -> anyhow::Result<Vec<Segment>> {
+    let segments = parse_path(path)?;
+    if segments.contains(&Segment::Wildcard) {
+        anyhow::bail!("Wildcards are not allowed in patch paths ('{}')", path);
+    }
+    if segments.is_empty() {
+        anyhow::bail!("Patch paths must name a key or index below the document root");
+    }
+    Ok(segments)
+}
+
+// --- Tree (JSON and YAML fallback) editing ---
+
+fn tree_set(node: &mut Tree, path: &[Segment], value: Tree) -> anyhow::Result<()> {
+    let (first, rest) = path.split_first().expect("non-empty path");
+    if node.is_null() {
+        *node = Tree::Mapping(Mapping::new());
+    }
+    let slot = match (node, first) {
+        (Tree::Mapping(map), Segment::Key(_) | Segment::Index(_)) => {
+            let key = match first {
+                Segment::Key(key) => key.clone(),
+                Segment::Index(idx) => idx.to_string(),
+                Segment::Wildcard => unreachable!(),
+            };
+            if rest.is_empty() {
+                map.insert(Tree::String(key), value);
+                return Ok(());
+            }
+            map.entry(Tree::String(key)).or_insert(Tree::Null)
+        }
+        (Tree::Sequence(seq), Segment::Index(idx)) => {
+            if *idx > seq.len() {
+                anyhow::bail!("Index {} is past the end of a list of {}", idx, seq.len());
+            }
+            if *idx == seq.len() {
+                seq.push(Tree::Null);
+            }
+            if rest.is_empty() {
+                seq[*idx] = value;
+                return Ok(());
+            }
+            &mut seq[*idx]
+        }
+        (other, segment) => anyhow::bail!(
+            "Cannot descend into {} with {}",
+            tree_kind(other),
+            Segment::display(std::slice::from_ref(segment))
+        ),
+    };
+    tree_set(slot, rest, value)
+}
+
+fn tree_delete(node: &mut Tree, path: &[Segment]) -> anyhow::Result<()> {
+    let (last, parents) = path.split_last().expect("non-empty path");
+    let mut node = node;
+    for segment in parents {
+        node = match (node, segment) {
+            (Tree::Mapping(map), Segment::Key(key)) => map.get_mut(key.as_str()),
+            (Tree::Sequence(seq), Segment::Index(idx)) => seq.get_mut(*idx),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow::anyhow!("Nothing at {}", Segment::display(path)))?;
+    }
+    let removed = match (node, last) {
+        (Tree::Mapping(map), Segment::Key(key)) => map.shift_remove(key.as_str()).is_some(),
+        (Tree::Mapping(map), Segment::Index(idx)) => {
+            map.shift_remove(idx.to_string().as_str()).is_some()
+        }
+        (Tree::Sequence(seq), Segment::Index(idx)) if *idx < seq.len() => {
+            seq.remove(*idx);
+            true
+        }
+        _ => false,
+    };
+    if !removed {
+        anyhow::bail!("Nothing at {}", Segment::display(path));
+    }
+    Ok(())
+}
+
+fn tree_kind(node: &Tree) -> &'static str {
+    match node {
+        Tree::Mapping(_) => "a mapping",
+        Tree::Sequence(_) => "a list",
+        _ => "a scalar",
+    }
+}
+
+/// JSON text in the original indentation, with a trailing newline if it had one.
+fn write_json(tree: &Tree, original: &str) -> anyhow::Result<String> {
+    let indent = original
+        .lines()
+        .skip(1)
+        .find(|l| !l.trim().is_empty())
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .filter(|i| !i.is_empty())
+        .unwrap_or("  ");
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    serde::Serialize::serialize(tree, &mut serializer)?;
+    let mut text = String::from_utf8(buf)?;
+    if original.ends_with('\n') {
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+// --- In-place YAML text editing ---
+
+struct YamlLine<'a> {
+    no: usize,
+    /// Column where `text` starts in the source line.
+    indent: usize,
+    text: &'a str,
+}
+
+/// Where a path's entry sits in YAML text.
+struct YamlEntry {
+    line: usize,
+    /// Column where the inline value starts.
+    value_col: usize,
+    /// Last line of the entry, including nested children.
+    last_line: usize,
+}
+
+fn yaml_key(text: &str) -> Option<(&str, usize)> {
+    let (key, rest_at) =
+        if let Some(quoted) = text.strip_prefix('"').or_else(|| text.strip_prefix('\'')) {
+            let end = quoted.find(['"', '\''])?;
+            (&quoted[..end], end + 2)
+        } else {
+            let end = text.find(':')?;
+            (&text[..end], end)
+        };
+    let after = &text[rest_at..];
+    let after = after.strip_prefix(':')?;
+    if !(after.is_empty() || after.starts_with(' ')) || key.starts_with("- ") {
+        return None;
+    }
+    let value_offset = rest_at + 1 + (after.len() - after.trim_start().len());
+    Some((key.trim(), value_offset))
+}
+
+fn locate_yaml(source: &str, path: &[Segment]) -> Option<YamlEntry> {
+    let all: Vec<YamlLine> = source
+        .lines()
+        .enumerate()
+        .filter_map(|(no, line)| {
+            let text = line.trim_start();
+            if text.is_empty() || text.starts_with('#') || text == "---" {
+                return None;
+            }
+            Some(YamlLine {
+                no,
+                indent: line.len() - text.len(),
+                text,
+            })
+        })
+        .collect();
+    let mut scope: Vec<YamlLine> = all;
+    for (i, segment) in path.iter().enumerate() {
+        let last = i + 1 == path.len();
+        let indent = scope.first()?.indent;
+        let siblings = scope.iter().enumerate().filter(|(_, l)| l.indent == indent);
+        let (pos, value_offset) = match segment {
+            Segment::Key(key) => siblings
+                .filter_map(|(pos, l)| {
+                    yaml_key(l.text)
+                        .filter(|(k, _)| k == key)
+                        .map(|(_, off)| (pos, off))
+                })
+                .next()?,
+            Segment::Index(idx) => {
+                let (pos, l) = siblings
+                    .filter(|(_, l)| l.text == "-" || l.text.starts_with("- "))
+                    .nth(*idx)?;
+                (
+                    pos,
+                    1 + (l.text[1..].len() - l.text[1..].trim_start().len()),
+                )
+            }
+            Segment::Wildcard => return None,
+        };
+        let (entry_no, entry_indent, entry_text) =
+            (scope[pos].no, scope[pos].indent, scope[pos].text);
+        let children: Vec<usize> = scope[pos + 1..]
+            .iter()
+            .take_while(|l| l.indent > entry_indent)
+            .map(|l| l.no)
+            .collect();
+        let last_line = children.last().copied().unwrap_or(entry_no);
+        if last {
+            return Some(YamlEntry {
+                line: entry_no,
+                value_col: entry_indent + value_offset,
+                last_line,
+            });
+        }
+        let inline = &entry_text[value_offset..];
+        let mut next = Vec::new();
+        if matches!(segment, Segment::Index(_)) && !inline.is_empty() {
+            // `- key: value` starts a mapping on the item's own line.
+            next.push(YamlLine {
+                no: entry_no,
+                indent: entry_indent + value_offset,
+                text: inline,
+            });
+        } else if !inline.is_empty() && !inline.starts_with('#') {
+            return None;
+        }
+        let child_lines: Vec<YamlLine> = scope
+            .drain(pos + 1..)
+            .take_while(|l| l.indent > entry_indent)
+            .collect();
+        next.extend(child_lines);
+        scope = next;
+    }
+    None
+}
+
+/// End of an inline scalar value (before any trailing comment).
+fn scalar_end(value: &str) -> Option<usize> {
+    let first = value.chars().next()?;
+    if matches!(first, '|' | '>' | '{' | '[' | '&' | '*' | '!' | '#') {
+        return None;
+    }
+    if first == '"' || first == '\'' {
+        let close = value[1..].find(first)? + 2;
+        return Some(close);
+    }
+    Some(
+        value
+            .find(" #")
+            .unwrap_or(value.len())
+            .min(value.trim_end().len()),
+    )
+}
+
+/// Render a scalar as a one-line YAML value.
+fn yaml_scalar(value: &Json) -> Option<String> {
+    if value.is_object() || value.is_array() {
+        return None;
+    }
+    let text = serde_yaml::to_string(value).ok()?;
+    let text = text.trim_end();
+    (!text.contains('\n')).then(|| text.to_string())
+}
+
+/// Apply a scalar set or a delete directly to YAML text, if the layout allows.
+fn yaml_in_place(
+    source: &str,
+    op: &PatchOp,
+    path: &[Segment],
+    value: Option<&Json>,
+) -> Option<String> {
+    let entry = locate_yaml(source, path)?;
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    match op {
+        PatchOp::Set => {
+            let rendered = yaml_scalar(value?)?;
+            if entry.last_line != entry.line {
+                return None;
+            }
+            let line = &lines[entry.line];
+            let inline = line.get(entry.value_col..)?;
+            let end = scalar_end(inline)?;
+            lines[entry.line] =
+                format!("{}{}{}", &line[..entry.value_col], rendered, &inline[end..]);
+        }
+        PatchOp::Delete => {
+            lines.drain(entry.line..=entry.last_line);
+        }
+        PatchOp::Merge => return None,
+    }
+    let mut text = lines.join("\n");
+    if source.ends_with('\n') {
+        text.push('\n');
+    }
+    Some(text)
+}
+
+// --- TOML editing ---
+
+fn json_to_toml(value: &Json) -> anyhow::Result<toml_edit::Value> {
+    Ok(match value {
+        Json::Null => anyhow::bail!("TOML has no null; use op: delete instead"),
+        Json::Bool(b) => (*b).into(),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or_default().into(),
+        },
+        Json::String(s) => s.as_str().into(),
+        Json::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(json_to_toml(item)?);
+            }
+            toml_edit::Value::Array(array)
+        }
+        Json::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (k, v) in map {
+                table.insert(k, json_to_toml(v)?);
+            }
+            toml_edit::Value::InlineTable(table)
+        }
+    })
+}
+
+/// `[[table]]` entry for a mapping value.
+fn json_to_toml_table(value: &Json) -> anyhow::Result<toml_edit::Table> {
+    let mut table = toml_edit::Item::Value(json_to_toml(value)?)
+        .into_table()
+        .map_err(|_| anyhow::anyhow!("Items of a table array must be mappings"))?;
+    table.fmt();
+    Ok(table)
+}
+
+fn toml_child<'a>(
+    item: &'a mut toml_edit::Item,
+    segment: &Segment,
+) -> Option<&'a mut toml_edit::Item> {
+    match segment {
+        Segment::Key(key) => item.as_table_like_mut()?.get_mut(key),
+        Segment::Index(idx) => match item {
+            toml_edit::Item::ArrayOfTables(_)
+            | toml_edit::Item::Value(toml_edit::Value::Array(_)) => item.get_mut(*idx),
+            _ => None,
+        },
+        Segment::Wildcard => None,
+    }
+}
+
+fn toml_set(doc: &mut DocumentMut, path: &[Segment], value: &Json) -> anyhow::Result<()> {
+    let (last, parents) = path.split_last().expect("non-empty path");
+    let mut node = doc.as_item_mut();
+    for segment in parents {
+        let exists = toml_child(node, segment).is_some();
+        if !exists {
+            let Segment::Key(key) = segment else {
+                anyhow::bail!("Nothing at {}", Segment::display(path));
+            };
+            let parent = node
+                .as_table_like_mut()
+                .ok_or_else(|| anyhow::anyhow!("Cannot add '{}' under a non-table", key))?;
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            parent.insert(key, toml_edit::Item::Table(table));
+        }
+        node = toml_child(node, segment).expect("just inserted");
+    }
+    match last {
+        Segment::Key(key) => {
+            let parent = node
+                .as_table_like_mut()
+                .ok_or_else(|| anyhow::anyhow!("{} is not a table", Segment::display(parents)))?;
+            // Mappings become inline tables (`dep = { version = "1" }`).
+            let mut item = toml_edit::Item::Value(json_to_toml(value)?);
+            // Keep the old value's surrounding whitespace and trailing comment.
+            if let (Some(old), Some(new)) = (
+                parent.get(key).and_then(|i| i.as_value()),
+                item.as_value_mut(),
+            ) {
+                *new.decor_mut() = old.decor().clone();
+            }
+            parent.insert(key, item);
+        }
+        Segment::Index(idx) => match node {
+            toml_edit::Item::Value(toml_edit::Value::Array(array)) => {
+                let mut new = json_to_toml(value)?;
+                if *idx < array.len() {
+                    *new.decor_mut() = array.get(*idx).expect("in bounds").decor().clone();
+                    array.replace_formatted(*idx, new);
+                } else if *idx == array.len() {
+                    array.push(new);
+                } else {
+                    anyhow::bail!("Index {} is past the end of a list of {}", idx, array.len());
+                }
+            }
+            toml_edit::Item::ArrayOfTables(tables) => {
+                let table = json_to_toml_table(value)?;
+                if *idx < tables.len() {
+                    *tables.get_mut(*idx).expect("in bounds") = table;
+                } else if *idx == tables.len() {
+                    tables.push(table);
+                } else {
+                    anyhow::bail!(
+                        "Index {} is past the end of a list of {}",
+                        idx,
+                        tables.len()
+                    );
+                }
+            }
+            _ => anyhow::bail!("{} is not a list", Segment::display(parents)),
+        },
+        Segment::Wildcard => unreachable!("rejected by plain_path"),
+    }
+    Ok(())
+}
+
+fn toml_delete(doc: &mut DocumentMut, path: &[Segment]) -> anyhow::Result<()> {
+    let (last, parents) = path.split_last().expect("non-empty path");
+    let mut node = doc.as_item_mut();
+    for segment in parents {
+        node = toml_child(node, segment)
+            .ok_or_else(|| anyhow::anyhow!("Nothing at {}", Segment::display(path)))?;
+    }
+    let removed = match (node, last) {
+        (node, Segment::Key(key)) => node
+            .as_table_like_mut()
+            .and_then(|t| t.remove(key))
+            .is_some(),
+        (toml_edit::Item::Value(toml_edit::Value::Array(array)), Segment::Index(idx))
+            if *idx < array.len() =>
+        {
+            array.remove(*idx);
+            true
+        }
+        (toml_edit::Item::ArrayOfTables(tables), Segment::Index(idx)) if *idx < tables.len() => {
+            tables.remove(*idx);
+            true
+        }
+        _ => false,
+    };
+    if !removed {
+        anyhow::bail!("Nothing at {}", Segment::display(path));
+    }
+    Ok(())
+}
+
+// --- Patching ---
+
+/// A file being patched.
+enum Document {
+    Json { tree: Tree, original: String },
+    Yaml { text: String, lossy: bool },
+    Toml(DocumentMut),
+}
+
+impl Document {
+    fn load(text: &str, format: Format) -> anyhow::Result<Self> {
+        Ok(match format {
+            Format::Json => Self::Json {
+                tree: parse_tree(text, format)?,
+                original: text.to_string(),
+            },
+            Format::Yaml => {
+                if serde_yaml::Deserializer::from_str(text).count() > 1 {
+                    anyhow::bail!("patch_structured does not edit multi-document YAML; split the file or edit it with write_file");
+                }
+                parse_tree(text, format)?;
+                Self::Yaml {
+                    text: text.to_string(),
+                    lossy: false,
+                }
+            }
+            Format::Toml => Self::Toml(text.parse()?),
+        })
+    }
+
+    fn get(&self, path: &[Segment]) -> anyhow::Result<Option<Tree>> {
+        let tree = match self {
+            Self::Json { tree, .. } => return Ok(select(tree, path).first().map(|n| (*n).clone())),
+            Self::Yaml { text, .. } => parse_tree(text, Format::Yaml)?,
+            Self::Toml(doc) => toml_to_tree(doc.as_item()),
+        };
+        Ok(select(&tree, path).first().map(|n| (*n).clone()))
+    }
+
+    fn set(&mut self, path: &[Segment], value: &Json) -> anyhow::Result<()> {
+        match self {
+            Self::Json { tree, .. } => tree_set(tree, path, serde_yaml::to_value(value)?),
+            Self::Toml(doc) => toml_set(doc, path, value),
+            Self::Yaml { .. } => self.yaml_edit(&PatchOp::Set, path, Some(value)),
+        }
+    }
+
+    fn delete(&mut self, path: &[Segment]) -> anyhow::Result<()> {
+        match self {
+            Self::Json { tree, .. } => tree_delete(tree, path),
+            Self::Toml(doc) => toml_delete(doc, path),
+            Self::Yaml { .. } => self.yaml_edit(&PatchOp::Delete, path, None),
+        }
+    }
+
+    fn yaml_edit(
+        &mut self,
+        op: &PatchOp,
+        path: &[Segment],
+        value: Option<&Json>,
+    ) -> anyhow::Result<()> {
+        let Self::Yaml { text, lossy } = self else {
+            unreachable!("yaml_edit on a non-YAML document");
+        };
+        let mut expected = parse_tree(text, Format::Yaml)?;
+        match op {
+            PatchOp::Set => tree_set(&mut expected, path, serde_yaml::to_value(value)?)?,
+            _ => tree_delete(&mut expected, path)?,
+        }
+        // Use the in-place edit only if it parses to exactly the expected tree.
+        if let Some(edited) = yaml_in_place(text, op, path, value) {
+            if parse_tree(&edited, Format::Yaml).ok().as_ref() == Some(&expected) {
+                *text = edited;
+                return Ok(());
+            }
+        }
+        *text = serde_yaml::to_string(&expected)?;
+        *lossy = true;
+        Ok(())
+    }
+
+    /// Deep-merge `value` into the mapping at `path`.
+    fn merge(&mut self, path: &[Segment], value: &Json) -> anyhow::Result<()> {
+        match (value, self.get(path)?) {
+            (Json::Object(map), Some(Tree::Mapping(_))) => {
+                for (key, value) in map {
+                    let mut child = path.to_vec();
+                    child.push(Segment::Key(key.clone()));
+                    self.merge(&child, value)?;
+                }
+                Ok(())
+            }
+            _ => self.set(path, value),
+        }
+    }
+
+    fn apply(&mut self, patch: &Patch) -> anyhow::Result<()> {
+        let path = plain_path(&patch.path)?;
+        match patch.op {
+            PatchOp::Set => self.set(&path, patch_value(patch)?),
+            PatchOp::Merge => self.merge(&path, patch_value(patch)?),
+            PatchOp::Delete => self.delete(&path),
+        }
+    }
+
+    fn render(&self) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Json { tree, original } => write_json(tree, original)?,
+            Self::Yaml { text, .. } => text.clone(),
+            Self::Toml(doc) => doc.to_string(),
+        })
+    }
+}
+
+/// Apply `patches` to `text`; returns the new text and whether YAML
+/// comments/layout had to be dropped.
+fn run_patches(text: &str, format: Format, patches: &[Patch]) -> anyhow::Result<(String, bool)> {
+    let mut document = Document::load(text, format)?;
+    for (i, patch) in patches.iter().enumerate() {
+        document.apply(patch).map_err(|e| {
+            anyhow::anyhow!(
+                "Patch {} ({} {}): {}",
+                i + 1,
+                op_name(&patch.op),
+                patch.path,
+                e
+            )
+        })?;
+    }
+    let lossy = matches!(document, Document::Yaml { lossy: true, .. });
+    Ok((document.render()?, lossy))
+}
+
+/// Edit values in a JSON, YAML or TOML file by path.
+pub struct PatchStructured;
+
+#[derive(Deserialize, JsonSchema)]
+struct PatchArgs {
+    /// File to edit
+    path: String,
+    /// Edits applied in order; nothing is written if any fails
+    patches: Vec<Patch>,
+    /// json, yaml or toml (default: from the file extension)
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[async_trait]
+impl Tool for PatchStructured {
+    fn name(&self) -> &str {
+        "patch_structured"
+    }
+
+    fn description(&self) -> &str {
+        "Edit a JSON, YAML or TOML file by path instead of rewriting it: set a value, deep-merge a mapping, or delete a key or list item. Keeps comments and formatting (TOML fully; YAML for scalar updates and deletions; JSON keeps key order and indentation). All patches apply or none do."
+    }
+
+    fn parameters_schema(&self) -> Json {
+        PatchArgs::schema()
+    }
+
+    async fn execute(&self, args: Json, working_dir: &Path) -> anyhow::Result<String> {
+        let args = PatchArgs::parse(args)?;
+        if args.patches.is_empty() {
+            anyhow::bail!("No patches given");
+        }
+        let path = resolve_path(&args.path, working_dir);
+        let format = Format::detect(&path, args.format.as_deref())?;
+        let text = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?;
+        let (new_text, lossy) = run_patches(&text, format, &args.patches)?;
+        if new_text == text {
+            return Ok(format!(
+                "{} already up to date; nothing written.",
+                args.path
+            ));
+        }
+        tokio::fs::write(&path, &new_text).await?;
+        let mut out = format!("Applied {} patch(es) to {}.", args.patches.len(), args.path);
+        if lossy {
+            out.push_str(" Some edits needed the YAML to be re-serialized, so comments and custom formatting in this file were not preserved.");
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patches(value: Json) -> Vec<Patch> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parses_paths_and_queries_each_format() {
+        assert_eq!(
+            parse_path(".a.b[0][\"x.y\"].*").unwrap(),
+            vec![
+                Segment::Key("a".into()),
+                Segment::Key("b".into()),
+                Segment::Index(0),
+                Segment::Key("x.y".into()),
+                Segment::Wildcard,
+            ]
+        );
+        assert_eq!(parse_path("items.1").unwrap()[1], Segment::Index(1));
+
+        let json = r#"{"services": [{"name": "api", "port": 8080}, {"name": "db"}]}"#;
+        assert_eq!(
+            run_query(json, Format::Json, ".services[0].port", false).unwrap(),
+            "8080"
+        );
+        assert_eq!(
+            run_query(json, Format::Json, ".services[*].name", false).unwrap(),
+            "[\n  \"api\",\n  \"db\"\n]"
+        );
+        let yaml = "server:\n  port: 80\n  host: x\n";
+        assert_eq!(
+            run_query(yaml, Format::Yaml, ".server", true).unwrap(),
+            "[\n  \"port\",\n  \"host\"\n]"
+        );
+        let toml = "[package]\nname = \"demo\"\n[dependencies]\nserde = \"1\"\n";
+        assert_eq!(
+            run_query(toml, Format::Toml, ".dependencies.serde", false).unwrap(),
+            "\"1\""
+        );
+        assert!(run_query(toml, Format::Toml, ".missing", false).is_err());
+    }
+
+    #[test]
+    fn yaml_patches_keep_comments_when_possible() {
+        let yaml = "# app config\nserver:\n  port: 80 # public port\n  host: localhost\nitems:\n  - name: a # first\n    size: 1\n  - name: b\n";
+        let (out, lossy) = run_patches(
+            yaml,
+            Format::Yaml,
+            &patches(serde_json::json!([
+                {"op": "set", "path": ".server.port", "value": 8080},
+                {"op": "set", "path": ".items[0].size", "value": 2},
+                {"op": "delete", "path": ".items[1]"},
+            ])),
+        )
+        .unwrap();
+        assert!(!lossy);
+        assert_eq!(
+            out,
+            "# app config\nserver:\n  port: 8080 # public port\n  host: localhost\nitems:\n  - name: a # first\n    size: 2\n"
+        );
+
+        // Adding a new nested key falls back to re-serializing.
+        let (out, lossy) = run_patches(
+            yaml,
+            Format::Yaml,
+            &patches(serde_json::json!([
+                {"op": "merge", "path": ".server", "value": {"tls": {"enabled": true}}},
+            ])),
+        )
+        .unwrap();
+        assert!(lossy);
+        assert!(out.contains("tls:\n    enabled: true"));
+    }
+
+    #[test]
+    fn toml_and_json_patches_keep_formatting() {
+        let toml = "# Cargo manifest\n[package]\nname = \"demo\"  # crate name\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n";
+        let (out, _) = run_patches(
+            toml,
+            Format::Toml,
+            &patches(serde_json::json!([
+                {"op": "set", "path": ".package.name", "value": "renamed"},
+                {"op": "set", "path": ".dependencies.tokio", "value": {"version": "1", "features": ["full"]}},
+                {"op": "delete", "path": ".package.version"},
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "# Cargo manifest\n[package]\nname = \"renamed\"  # crate name\n\n[dependencies]\nserde = \"1\"\ntokio = { features = [\"full\"], version = \"1\" }\n"
+        );
+
+        let json = "{\n    \"zeta\": 1,\n    \"alpha\": {\"b\": 2}\n}\n";
+        let (out, _) = run_patches(
+            json,
+            Format::Json,
+            &patches(serde_json::json!([
+                {"op": "merge", "path": ".alpha", "value": {"c": 3}},
+                {"op": "set", "path": ".zeta", "value": 10},
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "{\n    \"zeta\": 10,\n    \"alpha\": {\n        \"b\": 2,\n        \"c\": 3\n    }\n}\n"
+        );
+        assert!(run_patches(
+            json,
+            Format::Json,
+            &patches(serde_json::json!([{"op": "set", "path": ".x[*]", "value": 1}]))
+        )
+        .is_err());
+    }
+}
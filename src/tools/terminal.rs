@@ -21,11 +21,14 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use serde_json::{json, Value};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::process::Command;
 
+use super::terminal_stream::{LiveTerminal, LiveWriter, OutputStream};
 use super::{resolve_path_simple as resolve_path, Tool};
 use crate::nspawn;
+use crate::package_cache;
+use crate::reference_repos;
 
 static RTK_COMMANDS_PROCESSED: AtomicU64 = AtomicU64::new(0);
 
@@ -770,7 +773,7 @@ fn allow_dangerous_commands() -> bool {
     )
 }
 
-fn container_root_from_env() -> Option<PathBuf> {
+pub(crate) fn container_root_from_env() -> Option<PathBuf> {
     let workspace_type = env::var("SANDBOXED_SH_WORKSPACE_TYPE").ok()?;
     if workspace_type != "container" {
         return None;
@@ -796,6 +799,10 @@ struct CommandOptions {
     shell: Option<String>,
     max_output_chars: usize,
     raw_output: bool,
+    /// Forward output to the dashboard as it is produced
+    live: Option<LiveTerminal>,
+    /// User and group to run as (see `host_access::tool_user`)
+    run_as: Option<(u32, u32)>,
 }
 
 const DEFAULT_MAX_OUTPUT_CHARS: usize = 10_000;
@@ -841,7 +848,8 @@ fn parse_env(args: &Value) -> HashMap<String, String> {
     envs
 }
 
-fn workspace_env_vars() -> HashMap<String, String> {
+/// Environment variables configured on the workspace (from template/workspace settings).
+pub(crate) fn workspace_env_vars() -> HashMap<String, String> {
     let mut envs = HashMap::new();
     if let Ok(raw_path) = env::var("SANDBOXED_SH_WORKSPACE_ENV_VARS_FILE") {
         let path = raw_path.trim();
@@ -889,6 +897,17 @@ fn workspace_env_vars() -> HashMap<String, String> {
     envs
 }
 
+/// Read a setting from workspace env vars, falling back to the process env.
+///
+/// Empty values are treated as unset.
+pub(crate) fn workspace_setting(name: &str) -> Option<String> {
+    workspace_env_vars()
+        .remove(name)
+        .or_else(|| env::var(name).ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
 fn parse_max_output_chars(args: &Value) -> usize {
     let max = args
         .get("max_output_chars")
@@ -916,6 +935,8 @@ fn parse_command_options(args: &Value) -> CommandOptions {
             .map(|s| s.to_string()),
         max_output_chars: parse_max_output_chars(args),
         raw_output: args.get("raw").and_then(|v| v.as_bool()).unwrap_or(false),
+        live: None,
+        run_as: None,
     }
 }
 
@@ -971,6 +992,14 @@ async fn run_shell_command(
     if !options.env.is_empty() {
         cmd.envs(&options.env);
     }
+    #[cfg(unix)]
+    if let Some((uid, gid)) = options.run_as {
+        // The tool user must not act for the mission on the server
+        cmd.uid(uid)
+            .gid(gid)
+            .env_remove("SANDBOXED_PROXY_SECRET")
+            .env_remove(super::guard::MISSION_TOKEN_ENV);
+    }
     cmd.stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -981,14 +1010,21 @@ async fn run_shell_command(
 
     if let Some(input) = options.stdin.as_deref() {
         if let Some(mut stdin) = child.stdin.take() {
-            stdin
-                .write_all(input.as_bytes())
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to write to stdin: {}", e))?;
+            // Commands that exit without reading their input close the pipe
+            if let Err(e) = stdin.write_all(input.as_bytes()).await {
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    anyhow::bail!("Failed to write to stdin: {}", e);
+                }
+            }
         }
     }
 
-    let output = tokio::time::timeout(options.timeout, child.wait_with_output()).await;
+    let output = match &options.live {
+        Some(live) => {
+            tokio::time::timeout(options.timeout, wait_with_live_output(child, live)).await
+        }
+        None => tokio::time::timeout(options.timeout, child.wait_with_output()).await,
+    };
 
     match output {
         Ok(Ok(output)) => Ok(output),
@@ -1000,13 +1036,51 @@ async fn run_shell_command(
     }
 }
 
+/// Read a pipe to the end, forwarding each chunk to the live terminal.
+async fn read_live<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    mut writer: LiveWriter,
+) -> std::io::Result<Vec<u8>> {
+    let mut collected = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = pipe.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            writer.write(&buf[..n]);
+            collected.extend_from_slice(&buf[..n]);
+        }
+    }
+    writer.flush();
+    Ok(collected)
+}
+
+/// Like `Child::wait_with_output`, but streams stdout/stderr while collecting them.
+async fn wait_with_live_output(
+    mut child: tokio::process::Child,
+    live: &LiveTerminal,
+) -> std::io::Result<Output> {
+    drop(child.stdin.take());
+    let stdout = read_live(child.stdout.take(), live.writer(OutputStream::Stdout));
+    let stderr = read_live(child.stderr.take(), live.writer(OutputStream::Stderr));
+    let (stdout, stderr, status) = tokio::try_join!(stdout, stderr, child.wait())?;
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
 async fn run_host_command(
     cwd: &Path,
     command: &str,
     options: &CommandOptions,
 ) -> anyhow::Result<Output> {
     let (shell, shell_arg) = if cfg!(target_os = "windows") {
-        ("cmd".to_string(), "/C".to_string())
+        let shell = crate::host::windows_shell(options.shell.as_deref());
+        (shell.program, shell.arg.to_string())
     } else {
         (
             resolve_shell(options.shell.as_deref(), None),
@@ -1014,7 +1088,66 @@ async fn run_host_command(
         )
     };
     let args = vec![shell_arg, command.to_string()];
-    run_shell_command(&shell, &args, Some(cwd), options).await
+    let options = CommandOptions {
+        run_as: super::host_access::tool_user().await?,
+        ..options.clone()
+    };
+    run_shell_command(&shell, &args, Some(cwd), &options).await
+}
+
+/// Quote a value for safe interpolation into a POSIX shell command.
+pub(crate) fn shell_quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('\'');
+    for ch in value.chars() {
+        if ch == '\'' {
+            out.push_str("'\\''");
+        } else {
+            out.push(ch);
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Run a shell command in the current workspace execution context.
+///
+/// Container workspaces execute inside the container; host workspaces execute
+/// directly. Used by higher-level tools that build their own command lines.
+pub(crate) async fn run_workspace_shell(
+    cwd: &Path,
+    command: &str,
+    env: HashMap<String, String>,
+    timeout: Duration,
+) -> anyhow::Result<Output> {
+    run_workspace_shell_with_stdin(cwd, command, env, None, timeout).await
+}
+
+/// Like [`run_workspace_shell`], optionally writing `stdin` to the command.
+pub(crate) async fn run_workspace_shell_with_stdin(
+    cwd: &Path,
+    command: &str,
+    env: HashMap<String, String>,
+    stdin: Option<String>,
+    timeout: Duration,
+) -> anyhow::Result<Output> {
+    let options = CommandOptions {
+        timeout,
+        env,
+        clear_env: false,
+        stdin,
+        shell: None,
+        max_output_chars: MAX_OUTPUT_CHARS_LIMIT,
+        raw_output: true,
+        live: None,
+        run_as: None,
+    };
+    match container_root_from_env() {
+        Some(container_root) => {
+            run_container_command(&container_root, cwd, command, &options).await
+        }
+        None => run_host_command(cwd, command, &options).await,
+    }
 }
 
 fn runtime_display_path() -> Option<PathBuf> {
@@ -1151,6 +1284,11 @@ async fn run_container_command(
     let mut merged_env = workspace_env_vars();
     merged_env.extend(options.env.clone());
 
+    if let Some(bind) = reference_repos::nspawn_bind_arg() {
+        args.push(bind);
+    }
+    args.extend(package_cache::nspawn_bind_args(&merged_env));
+
     for arg in nspawn::tailscale_nspawn_extra_args(&merged_env) {
         args.push(arg);
     }
@@ -1205,6 +1343,29 @@ async fn run_container_command(
     Ok(output)
 }
 
+/// Machine name and leader PID of the current workspace's running container.
+pub(crate) async fn running_workspace_container() -> Option<(String, String)> {
+    container_root_from_env()?;
+    let machine = env::var("SANDBOXED_SH_WORKSPACE_NAME").ok()?;
+    let machine = machine.trim();
+    if machine.is_empty() {
+        return None;
+    }
+    let options = CommandOptions {
+        timeout: Duration::from_secs(10),
+        env: HashMap::new(),
+        clear_env: false,
+        stdin: None,
+        shell: None,
+        max_output_chars: DEFAULT_MAX_OUTPUT_CHARS,
+        raw_output: true,
+        live: None,
+        run_as: None,
+    };
+    let leader = running_container_leader(machine, &options).await?;
+    Some((machine.to_string(), leader))
+}
+
 async fn running_container_leader(machine_name: &str, options: &CommandOptions) -> Option<String> {
     let machinectl = if Path::new("/usr/bin/machinectl").exists() {
         "/usr/bin/machinectl"
@@ -1372,7 +1533,8 @@ impl Tool for RunCommand {
             .as_str()
             .map(|p| resolve_path(p, working_dir))
             .unwrap_or_else(|| working_dir.to_path_buf());
-        let options = parse_command_options(&args);
+        let mut options = parse_command_options(&args);
+        options.live = LiveTerminal::start(command);
 
         let (final_command, rtk_used) = if rtk_enabled() {
             if let Some(rtk_path) = rtk_binary_path() {
@@ -1409,10 +1571,14 @@ impl Tool for RunCommand {
 
         let output = match container_root {
             Some(container_root) => {
-                run_container_command(&container_root, &cwd, &final_command, &options).await?
+                run_container_command(&container_root, &cwd, &final_command, &options).await
             }
-            None => run_host_command(&cwd, &final_command, &options).await?,
+            None => run_host_command(&cwd, &final_command, &options).await,
         };
+        if let Some(live) = &options.live {
+            live.finish(output.as_ref().ok().and_then(|o| o.status.code()));
+        }
+        let output = output?;
 
         let stdout = sanitize_output(&output.stdout);
         let stderr = sanitize_output(&output.stderr);
@@ -0,0 +1,302 @@
+//! Live output of `run_command` for the dashboard terminal view.
+//!
+//! While a mission runs a command, stdout/stderr chunks are forwarded to the
+//! server as they arrive instead of only returning the final result. Each
+//! command is its own channel: an `open` frame, timestamped `data` frames and
+//! an `exit` frame. Frames are batched and posted to
+//! `/api/terminal-stream/:mission_id` with the internal proxy secret; the
+//! server fans them out on the mission's terminal WebSocket.
+//!
+//! Streaming is best-effort: it is only active inside a mission, and a failed
+//! post disables it for the rest of the command without affecting the result.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// How long chunks are collected before they are posted.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(150);
+/// Largest `data` payload per frame; bigger reads are split.
+const MAX_FRAME_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// One frame of a command's terminal channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TerminalFrame {
+    Open {
+        channel: String,
+        command: String,
+        ts: String,
+    },
+    Data {
+        channel: String,
+        stream: OutputStream,
+        data: String,
+        ts: String,
+    },
+    /// `exit_code` is `None` when the command timed out or was killed.
+    Exit {
+        channel: String,
+        exit_code: Option<i32>,
+        ts: String,
+    },
+}
+
+impl TerminalFrame {
+    pub fn channel(&self) -> &str {
+        match self {
+            TerminalFrame::Open { channel, .. }
+            | TerminalFrame::Data { channel, .. }
+            | TerminalFrame::Exit { channel, .. } => channel,
+        }
+    }
+
+    /// Approximate size, used to bound replay buffers.
+    pub fn size(&self) -> usize {
+        match self {
+            TerminalFrame::Open { command, .. } => 64 + command.len(),
+            TerminalFrame::Data { data, .. } => 64 + data.len(),
+            TerminalFrame::Exit { .. } => 64,
+        }
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Decodes a byte stream into UTF-8 text without splitting characters that
+/// straddle two reads.
+#[derive(Debug, Default)]
+struct Utf8Chunker {
+    pending: Vec<u8>,
+}
+
+impl Utf8Chunker {
+    fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let keep = match std::str::from_utf8(&self.pending) {
+            Ok(_) => 0,
+            // An incomplete sequence at the end: wait for the next read.
+            Err(e) if e.error_len().is_none() => self.pending.len() - e.valid_up_to(),
+            Err(_) => 0,
+        };
+        let tail = self.pending.split_off(self.pending.len() - keep);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = tail;
+        text
+    }
+
+    fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+/// Split `text` into pieces of at most `max` bytes on character boundaries.
+fn split_text(text: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while rest.len() > max {
+        let end = super::safe_truncate_index(rest, max).max(1);
+        let end = (end..=rest.len())
+            .find(|&i| rest.is_char_boundary(i))
+            .unwrap_or(rest.len());
+        pieces.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+/// Live output channel for one command.
+#[derive(Debug, Clone)]
+pub(crate) struct LiveTerminal {
+    channel: String,
+    tx: mpsc::UnboundedSender<TerminalFrame>,
+}
+
+impl LiveTerminal {
+    /// Open a channel for `command` if running inside a mission.
+    pub(crate) fn start(command: &str) -> Option<Self> {
+        let mission_id = std::env::var("SANDBOXED_SH_MISSION_ID")
+            .ok()
+            .filter(|id| Uuid::parse_str(id).is_ok())?;
+        let secret = std::env::var("SANDBOXED_PROXY_SECRET").ok()?;
+        let api_base = std::env::var("SANDBOXED_SH_API_URL").unwrap_or_else(|_| {
+            let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
+            format!("http://127.0.0.1:{}", port)
+        });
+        let url = format!(
+            "{}/api/terminal-stream/{}",
+            api_base.trim_end_matches('/'),
+            mission_id
+        );
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(post_frames(url, secret, rx));
+        let live = Self {
+            channel: Uuid::new_v4().to_string(),
+            tx,
+        };
+        live.send(TerminalFrame::Open {
+            channel: live.channel.clone(),
+            command: command.to_string(),
+            ts: now(),
+        });
+        Some(live)
+    }
+
+    fn send(&self, frame: TerminalFrame) {
+        let _ = self.tx.send(frame);
+    }
+
+    /// Writer for one of the command's output pipes.
+    pub(crate) fn writer(&self, stream: OutputStream) -> LiveWriter {
+        LiveWriter {
+            live: self.clone(),
+            stream,
+            chunker: Utf8Chunker::default(),
+        }
+    }
+
+    /// Close the channel. The poster flushes what is left and exits once
+    /// every clone is dropped.
+    pub(crate) fn finish(&self, exit_code: Option<i32>) {
+        self.send(TerminalFrame::Exit {
+            channel: self.channel.clone(),
+            exit_code,
+            ts: now(),
+        });
+    }
+}
+
+/// Forwards chunks read from stdout or stderr.
+pub(crate) struct LiveWriter {
+    live: LiveTerminal,
+    stream: OutputStream,
+    chunker: Utf8Chunker,
+}
+
+impl LiveWriter {
+    fn send_text(&self, text: &str) {
+        for piece in split_text(text, MAX_FRAME_BYTES) {
+            self.live.send(TerminalFrame::Data {
+                channel: self.live.channel.clone(),
+                stream: self.stream,
+                data: piece.to_string(),
+                ts: now(),
+            });
+        }
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        let text = self.chunker.push(bytes);
+        self.send_text(&text);
+    }
+
+    /// Send any bytes held back at the end of the stream.
+    pub(crate) fn flush(mut self) {
+        let text = self.chunker.finish();
+        self.send_text(&text);
+    }
+}
+
+async fn post_frames(url: String, secret: String, mut rx: mpsc::UnboundedReceiver<TerminalFrame>) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+    {
+        Ok(client) => client,
+        Err(_) => return,
+    };
+    let mut batch = Vec::new();
+    let mut open = true;
+    while open {
+        match rx.recv().await {
+            Some(frame) => batch.push(frame),
+            None => break,
+        }
+        // Collect whatever else arrives within the flush interval.
+        let deadline = tokio::time::Instant::now() + FLUSH_INTERVAL;
+        loop {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(frame)) => batch.push(frame),
+                Ok(None) => {
+                    open = false;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        let result = client
+            .post(&url)
+            .bearer_auth(&secret)
+            .json(&batch)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        batch.clear();
+        if let Err(e) = result {
+            tracing::debug!("Live terminal output disabled: {}", e);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunker_keeps_split_characters_together() {
+        let bytes = "héllo ✓".as_bytes();
+        let mut chunker = Utf8Chunker::default();
+        let mut text = String::new();
+        for byte in bytes {
+            text.push_str(&chunker.push(std::slice::from_ref(byte)));
+        }
+        text.push_str(&chunker.finish());
+        assert_eq!(text, "héllo ✓");
+
+        let mut chunker = Utf8Chunker::default();
+        assert_eq!(chunker.push(b"ok\xff"), "ok\u{fffd}");
+    }
+
+    #[test]
+    fn splits_large_output_on_char_boundaries() {
+        let text = "é".repeat(10);
+        let pieces = split_text(&text, 5);
+        assert!(pieces.iter().all(|p| p.len() <= 5));
+        assert_eq!(pieces.concat(), text);
+        assert!(split_text("", 5).is_empty());
+    }
+
+    #[test]
+    fn frames_serialize_with_type_tag() {
+        let frame = TerminalFrame::Data {
+            channel: "c1".to_string(),
+            stream: OutputStream::Stderr,
+            data: "oops\n".to_string(),
+            ts: "2026-01-01T00:00:00.000Z".to_string(),
+        };
+        let value = serde_json::to_value(&frame).unwrap();
+        assert_eq!(value["type"], "data");
+        assert_eq!(value["stream"], "stderr");
+        assert_eq!(frame.channel(), "c1");
+        let back: TerminalFrame = serde_json::from_value(value).unwrap();
+        assert_eq!(back, frame);
+    }
+}
@@ -0,0 +1,460 @@
+//! `terraform_plan`: plan infrastructure changes, summarize them and check
+//! them against the workspace's protection rules.
+//!
+//! The tool runs `terraform plan` (or OpenTofu's `tofu plan`) into a saved
+//! plan file, reads it back with `show -json` and reports each resource
+//! change grouped by action. Deleting or replacing a resource whose address
+//! matches a pattern in `SANDBOXED_SH_TF_PROTECTED` is a policy violation.
+//!
+//! Nothing is applied unless the agent asks for it with `apply: true`, the
+//! plan has no violations, and an admin approved the summary in the approval
+//! queue (`api::approvals`); the call waits for the decision. The apply uses
+//! the saved plan, so exactly the reviewed changes are made.
+//! `SANDBOXED_SH_TF_APPLY=deny` turns applies off for a workspace. Both
+//! policy settings are read from the server (see `guard::SERVER_SETTINGS`),
+//! not from the env the mission can rewrite.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::terminal::{run_workspace_shell, shell_quote, workspace_setting};
+use super::{glob_match, resolve_path_simple as resolve_path, safe_truncate_index, Tool, ToolArgs};
+
+/// Server-side setting with address patterns that must not be deleted or replaced.
+pub const PROTECTED_SETTING: &str = "SANDBOXED_SH_TF_PROTECTED";
+/// Server-side setting that disables applies when set to `deny`.
+pub const APPLY_SETTING: &str = "SANDBOXED_SH_TF_APPLY";
+/// Workspace setting forcing the binary (`terraform` or `tofu`).
+pub const BINARY_SETTING: &str = "SANDBOXED_SH_TF_BINARY";
+
+/// Plan file written inside the configuration's `.terraform` directory.
+const PLAN_FILE: &str = ".terraform/sandboxed-sh.tfplan";
+
+const TF_TIMEOUT: Duration = Duration::from_secs(1200);
+const MAX_LOG_CHARS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ChangeAction {
+    Create,
+    Update,
+    Replace,
+    Delete,
+}
+
+impl ChangeAction {
+    /// Action for a plan's `change.actions` list; None for no-op and reads.
+    fn from_actions(actions: &[&str]) -> Option<Self> {
+        match actions {
+            ["create"] => Some(Self::Create),
+            ["update"] => Some(Self::Update),
+            ["delete"] => Some(Self::Delete),
+            ["delete", "create"] | ["create", "delete"] => Some(Self::Replace),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Replace => "replace",
+            Self::Delete => "delete",
+        }
+    }
+
+    fn destroys(self) -> bool {
+        matches!(self, Self::Replace | Self::Delete)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ResourceChange {
+    address: String,
+    resource_type: String,
+    action: ChangeAction,
+}
+
+#[derive(Debug, Serialize)]
+struct PlanSummary {
+    changes: Vec<ResourceChange>,
+    violations: Vec<String>,
+}
+
+/// Resource changes from `terraform show -json` output.
+fn parse_plan(plan: &Value) -> Vec<ResourceChange> {
+    plan["resource_changes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|rc| {
+            let actions: Vec<&str> = rc["change"]["actions"]
+                .as_array()?
+                .iter()
+                .filter_map(Value::as_str)
+                .collect();
+            Some(ResourceChange {
+                address: rc["address"].as_str()?.to_string(),
+                resource_type: rc["type"].as_str().unwrap_or_default().to_string(),
+                action: ChangeAction::from_actions(&actions)?,
+            })
+        })
+        .collect()
+}
+
+/// Policy violations: deletions or replacements of protected resources.
+///
+/// Patterns (comma-separated, `*` wildcards) match the resource address or
+/// its type, e.g. `aws_db_instance*` or `module.prod.*`.
+fn check_policy(changes: &[ResourceChange], protected: &str) -> Vec<String> {
+    let patterns: Vec<&str> = protected
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    changes
+        .iter()
+        .filter(|change| change.action.destroys())
+        .filter_map(|change| {
+            let pattern = patterns
+                .iter()
+                .find(|p| glob_match(p, &change.address) || glob_match(p, &change.resource_type))?;
+            Some(format!(
+                "Plan would {} protected resource {} (matches '{}')",
+                change.action.label(),
+                change.address,
+                pattern
+            ))
+        })
+        .collect()
+}
+
+fn render(summary: &PlanSummary) -> String {
+    let count = |action| {
+        summary
+            .changes
+            .iter()
+            .filter(|c| c.action == action)
+            .count()
+    };
+    let mut out = format!(
+        "Plan: {} to create, {} to update, {} to replace, {} to delete.\n",
+        count(ChangeAction::Create),
+        count(ChangeAction::Update),
+        count(ChangeAction::Replace),
+        count(ChangeAction::Delete)
+    );
+    for action in [
+        ChangeAction::Delete,
+        ChangeAction::Replace,
+        ChangeAction::Update,
+        ChangeAction::Create,
+    ] {
+        let addresses: Vec<&str> = summary
+            .changes
+            .iter()
+            .filter(|c| c.action == action)
+            .map(|c| c.address.as_str())
+            .collect();
+        if addresses.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {}\n\n", action.label()));
+        for address in addresses {
+            out.push_str(&format!("- {}\n", address));
+        }
+    }
+    if summary.violations.is_empty() {
+        out.push_str("\nPolicy checks passed.\n");
+    } else {
+        out.push_str("\n## Policy violations\n\n");
+        for violation in &summary.violations {
+            out.push_str(&format!("- {}\n", violation));
+        }
+    }
+    out
+}
+
+/// Why an apply must not run, or None when it may be put up for approval.
+fn apply_refusal(summary: &PlanSummary, setting: Option<&str>) -> Option<String> {
+    if setting == Some("deny") {
+        return Some(format!(
+            "Applying is disabled for this workspace ({}=deny).",
+            APPLY_SETTING
+        ));
+    }
+    if !summary.violations.is_empty() {
+        return Some("Not applying: the plan violates the workspace policy.".to_string());
+    }
+    None
+}
+
+/// Ask an admin to approve applying the plan; `Err` says why it must not run.
+async fn approve_apply(dir: &Path, summary: &PlanSummary) -> Result<String, String> {
+    let plan = render(summary);
+    let reason = plan.lines().next().unwrap_or_default().to_string();
+    super::guard::request_approval(
+        "terraform_plan",
+        &json!({ "path": dir.display().to_string(), "apply": true, "plan": plan }),
+        &reason,
+    )
+    .await
+}
+
+/// Plan (and optionally apply) Terraform changes.
+pub struct TerraformPlan;
+
+#[derive(Deserialize, JsonSchema)]
+struct PlanArgs {
+    /// Directory with the Terraform configuration (default: working directory)
+    #[serde(default)]
+    path: Option<String>,
+    /// Input variables (`-var name=value`)
+    #[serde(default)]
+    vars: HashMap<String, String>,
+    /// Variable files relative to the configuration directory
+    #[serde(default)]
+    var_files: Vec<String>,
+    /// Limit the plan to these resource addresses
+    #[serde(default)]
+    targets: Vec<String>,
+    /// Apply the plan after the checks pass and an admin approved it
+    #[serde(default)]
+    apply: bool,
+    /// Return the summary as JSON
+    #[serde(default)]
+    json: bool,
+}
+
+fn check_arg(value: &str, what: &str) -> anyhow::Result<()> {
+    if value.is_empty() || value.starts_with('-') {
+        anyhow::bail!("Invalid {} '{}'", what, value);
+    }
+    Ok(())
+}
+
+fn plan_argv(args: &PlanArgs) -> anyhow::Result<Vec<String>> {
+    let mut argv = vec![
+        "plan".to_string(),
+        "-input=false".to_string(),
+        "-no-color".to_string(),
+        format!("-out={}", PLAN_FILE),
+    ];
+    let mut vars: Vec<_> = args.vars.iter().collect();
+    vars.sort();
+    for (name, value) in vars {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("Invalid variable name '{}'", name);
+        }
+        argv.push(format!("-var={}={}", name, value));
+    }
+    for file in &args.var_files {
+        check_arg(file, "var file")?;
+        argv.push(format!("-var-file={}", file));
+    }
+    for target in &args.targets {
+        check_arg(target, "target")?;
+        argv.push(format!("-target={}", target));
+    }
+    Ok(argv)
+}
+
+/// Run `<binary> <args>` in `dir`, failing with the log tail on error.
+async fn tf(dir: &Path, binary: &str, args: &[String]) -> anyhow::Result<String> {
+    let command = std::iter::once(binary.to_string())
+        .chain(args.iter().map(|a| shell_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let env = HashMap::from([
+        ("TF_IN_AUTOMATION".to_string(), "1".to_string()),
+        ("TF_INPUT".to_string(), "0".to_string()),
+    ]);
+    let output = run_workspace_shell(dir, &command, env, TF_TIMEOUT).await?;
+    if !output.status.success() {
+        let mut log = String::from_utf8_lossy(&output.stderr).to_string();
+        if log.trim().is_empty() {
+            log = String::from_utf8_lossy(&output.stdout).to_string();
+        }
+        // Errors are at the end of the log.
+        let mut start = log.len().saturating_sub(MAX_LOG_CHARS);
+        while !log.is_char_boundary(start) {
+            start += 1;
+        }
+        anyhow::bail!("{} {} failed:\n{}", binary, args[0], log[start..].trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+async fn detect_binary(dir: &Path) -> anyhow::Result<String> {
+    if let Some(binary) = workspace_setting(BINARY_SETTING) {
+        return match binary.as_str() {
+            "terraform" | "tofu" => Ok(binary),
+            other => anyhow::bail!(
+                "{} must be terraform or tofu, got '{}'",
+                BINARY_SETTING,
+                other
+            ),
+        };
+    }
+    let probe = "command -v terraform >/dev/null 2>&1 && echo terraform || { command -v tofu >/dev/null 2>&1 && echo tofu; }";
+    let output = run_workspace_shell(dir, probe, HashMap::new(), Duration::from_secs(10)).await?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "" => anyhow::bail!("Neither terraform nor tofu is installed in this workspace"),
+        binary => Ok(binary.to_string()),
+    }
+}
+
+/// Configuration directory and variable files.
+const PLAN_PATH_ARGS: &[&str] = &["path", "var_files"];
+
+#[async_trait]
+impl Tool for TerraformPlan {
+    fn name(&self) -> &str {
+        "terraform_plan"
+    }
+
+    fn description(&self) -> &str {
+        "Run `terraform plan` (or `tofu plan`) and return a summary of resource changes grouped by action, with policy checks (deleting or replacing protected resources is refused). Never applies by default: apply: true waits for an administrator to approve the summary, then applies exactly the reviewed plan."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        PlanArgs::schema()
+    }
+
+    fn path_args(&self) -> &[&'static str] {
+        PLAN_PATH_ARGS
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = PlanArgs::parse(args)?;
+        let dir = args
+            .path
+            .as_deref()
+            .map(|p| resolve_path(p, working_dir))
+            .unwrap_or_else(|| working_dir.to_path_buf());
+        let argv = plan_argv(&args)?;
+        let protected = super::guard::setting(PROTECTED_SETTING)
+            .await?
+            .unwrap_or_default();
+        let binary = detect_binary(&dir).await?;
+
+        let init = ["init", "-input=false", "-no-color"].map(String::from);
+        tf(&dir, &binary, &init).await?;
+        tf(&dir, &binary, &argv).await?;
+        let show = ["show", "-json", PLAN_FILE].map(String::from);
+        let plan: Value = serde_json::from_str(&tf(&dir, &binary, &show).await?)
+            .map_err(|e| anyhow::anyhow!("Could not parse the plan JSON: {}", e))?;
+
+        let changes = parse_plan(&plan);
+        let violations = check_policy(&changes, &protected);
+        let summary = PlanSummary {
+            changes,
+            violations,
+        };
+        let mut out = if args.json {
+            serde_json::to_string_pretty(&summary)?
+        } else {
+            render(&summary)
+        };
+
+        if args.apply {
+            let setting = super::guard::setting(APPLY_SETTING).await?;
+            if let Some(reason) = apply_refusal(&summary, setting.as_deref()) {
+                out.push_str(&format!("\n{}\n", reason));
+                return Ok(out);
+            }
+            match approve_apply(&dir, &summary).await {
+                Ok(actor) => out.push_str(&format!("\nApply approved by {}.\n", actor)),
+                Err(reason) => {
+                    out.push_str(&format!("\nNot applied: {}\n", reason));
+                    return Ok(out);
+                }
+            }
+            let apply = ["apply", "-input=false", "-no-color", PLAN_FILE].map(String::from);
+            let log = tf(&dir, &binary, &apply).await?;
+            let end = safe_truncate_index(&log, MAX_LOG_CHARS);
+            out.push_str(&format!("\n## Apply\n\n{}\n", log[..end].trim()));
+        } else if !summary.changes.is_empty() {
+            out.push_str("\nNot applied.\n");
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_plan() -> Value {
+        serde_json::json!({
+            "resource_changes": [
+                {"address": "aws_s3_bucket.logs", "type": "aws_s3_bucket",
+                 "change": {"actions": ["create"]}},
+                {"address": "aws_db_instance.main", "type": "aws_db_instance",
+                 "change": {"actions": ["delete", "create"]}},
+                {"address": "module.app.aws_instance.web", "type": "aws_instance",
+                 "change": {"actions": ["update"]}},
+                {"address": "aws_iam_role.old", "type": "aws_iam_role",
+                 "change": {"actions": ["delete"]}},
+                {"address": "data.aws_ami.ubuntu", "type": "aws_ami",
+                 "change": {"actions": ["read"]}},
+                {"address": "aws_vpc.main", "type": "aws_vpc",
+                 "change": {"actions": ["no-op"]}}
+            ]
+        })
+    }
+
+    #[test]
+    fn parses_and_renders_plan() {
+        let changes = parse_plan(&sample_plan());
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[1].action, ChangeAction::Replace);
+        let text = render(&PlanSummary {
+            changes,
+            violations: Vec::new(),
+        });
+        assert!(text.starts_with("Plan: 1 to create, 1 to update, 1 to replace, 1 to delete."));
+        assert!(text.contains("## replace\n\n- aws_db_instance.main"));
+        assert!(text.contains("Policy checks passed."));
+    }
+
+    #[test]
+    fn protected_resources_block_destroys() {
+        let changes = parse_plan(&sample_plan());
+        let violations = check_policy(&changes, "aws_db_instance, module.app.*");
+        // The database is replaced; the module resource is only updated.
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("replace protected resource aws_db_instance.main"));
+        assert!(check_policy(&changes, "").is_empty());
+    }
+
+    #[tokio::test]
+    async fn apply_needs_approval_and_clean_plan() {
+        let clean = PlanSummary {
+            changes: parse_plan(&sample_plan()),
+            violations: Vec::new(),
+        };
+        assert!(apply_refusal(&clean, None).is_none());
+        assert!(apply_refusal(&clean, Some("deny")).is_some());
+        let dirty = PlanSummary {
+            changes: Vec::new(),
+            violations: vec!["x".to_string()],
+        };
+        assert!(apply_refusal(&dirty, None).is_some());
+
+        // A flag set by the model is not an approval: without a server to
+        // approve it (no mission), the apply is refused.
+        let args = PlanArgs::parse(json!({"apply": true, "user_confirmed": true})).unwrap();
+        assert!(args.apply);
+        assert!(approve_apply(Path::new("infra"), &clean).await.is_err());
+
+        let args = PlanArgs::parse(json!({"targets": ["-destroy"]})).unwrap();
+        assert!(plan_argv(&args).is_err());
+    }
+}
@@ -0,0 +1,825 @@
+//! Issue tracker tools (Jira and Linear).
+//!
+//! Missions triggered from tickets use these to read acceptance criteria and
+//! report progress back. Both trackers sit behind the [`Tracker`] trait so the
+//! tools are identical regardless of which one a workspace uses.
+//!
+//! Credentials come from the workspace env vars (stored encrypted in templates):
+//! - Jira: `JIRA_BASE_URL`, `JIRA_EMAIL`, `JIRA_API_TOKEN`
+//! - Linear: `LINEAR_API_KEY`
+
+use std::path::Path;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::terminal::workspace_setting;
+use super::Tool;
+
+const TRACKER_TIMEOUT: Duration = Duration::from_secs(30);
+const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+/// A tracker-agnostic view of an issue.
+#[derive(Debug, Clone, Default)]
+pub struct IssueDetails {
+    pub key: String,
+    pub title: String,
+    pub status: String,
+    pub url: Option<String>,
+    pub assignee: Option<String>,
+    pub priority: Option<String>,
+    pub labels: Vec<String>,
+    pub description: String,
+    /// (key, title, status) of child issues.
+    pub subtasks: Vec<(String, String, String)>,
+    /// (author, body) of the most recent comments.
+    pub comments: Vec<(String, String)>,
+}
+
+impl IssueDetails {
+    fn to_markdown(&self) -> String {
+        let mut out = format!("# {}: {}\n", self.key, self.title);
+        out.push_str(&format!("Status: {}\n", self.status));
+        if let Some(url) = &self.url {
+            out.push_str(&format!("URL: {}\n", url));
+        }
+        if let Some(assignee) = &self.assignee {
+            out.push_str(&format!("Assignee: {}\n", assignee));
+        }
+        if let Some(priority) = &self.priority {
+            out.push_str(&format!("Priority: {}\n", priority));
+        }
+        if !self.labels.is_empty() {
+            out.push_str(&format!("Labels: {}\n", self.labels.join(", ")));
+        }
+        out.push_str("\n## Description\n");
+        out.push_str(if self.description.trim().is_empty() {
+            "(no description)"
+        } else {
+            self.description.trim()
+        });
+        out.push('\n');
+        if !self.subtasks.is_empty() {
+            out.push_str("\n## Sub-tasks\n");
+            for (key, title, status) in &self.subtasks {
+                out.push_str(&format!("- {} [{}] {}\n", key, status, title));
+            }
+        }
+        if !self.comments.is_empty() {
+            out.push_str("\n## Recent comments\n");
+            for (author, body) in &self.comments {
+                out.push_str(&format!("- {}: {}\n", author, body.trim()));
+            }
+        }
+        out
+    }
+}
+
+/// Operations supported by every issue tracker backend.
+#[async_trait]
+pub trait Tracker: Send + Sync {
+    /// Tracker name for display ("jira", "linear").
+    fn name(&self) -> &'static str;
+
+    async fn fetch_issue(&self, key: &str) -> anyhow::Result<IssueDetails>;
+
+    /// Add a comment and return a link or identifier for it.
+    async fn add_comment(&self, key: &str, body: &str) -> anyhow::Result<String>;
+
+    /// Move the issue to the named status/transition and return the new status.
+    async fn transition(&self, key: &str, status: &str) -> anyhow::Result<String>;
+
+    /// Create a sub-task under `parent` and return its key.
+    async fn create_subtask(
+        &self,
+        parent: &str,
+        title: &str,
+        description: Option<&str>,
+    ) -> anyhow::Result<String>;
+}
+
+fn http_client() -> anyhow::Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(TRACKER_TIMEOUT)
+        .build()?)
+}
+
+/// Pick the best case-insensitive match for a requested status name.
+///
+/// Each candidate is `(id, names...)`; exact matches win over prefix matches.
+fn match_status<'a>(requested: &str, candidates: &'a [(String, Vec<String>)]) -> Option<&'a str> {
+    let wanted = requested.trim().to_lowercase();
+    let exact = candidates
+        .iter()
+        .find(|(_, names)| names.iter().any(|n| n.to_lowercase() == wanted));
+    let prefix = || {
+        candidates
+            .iter()
+            .find(|(_, names)| names.iter().any(|n| n.to_lowercase().starts_with(&wanted)))
+    };
+    exact.or_else(prefix).map(|(id, _)| id.as_str())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Jira
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Jira Cloud (REST API v3) tracker.
+pub struct JiraTracker {
+    base_url: String,
+    email: String,
+    token: String,
+}
+
+impl JiraTracker {
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            base_url: workspace_setting("JIRA_BASE_URL")?
+                .trim_end_matches('/')
+                .to_string(),
+            email: workspace_setting("JIRA_EMAIL")?,
+            token: workspace_setting("JIRA_API_TOKEN")?,
+        })
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<Value> {
+        let mut req = http_client()?
+            .request(method, format!("{}/rest/api/3/{}", self.base_url, path))
+            .basic_auth(&self.email, Some(&self.token))
+            .header("Accept", "application/json");
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let resp = req.send().await?;
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            anyhow::bail!("Jira API {} failed ({}): {}", path, status, text);
+        }
+        Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+    }
+}
+
+/// Convert plain text to an Atlassian Document Format document (one paragraph per block).
+fn text_to_adf(text: &str) -> Value {
+    let paragraphs: Vec<Value> = text
+        .split("\n\n")
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| {
+            json!({
+                "type": "paragraph",
+                "content": [{ "type": "text", "text": p.trim() }]
+            })
+        })
+        .collect();
+    json!({ "type": "doc", "version": 1, "content": paragraphs })
+}
+
+/// Flatten an Atlassian Document Format node into plain text.
+fn adf_to_text(node: &Value) -> String {
+    if let Some(s) = node.as_str() {
+        return s.to_string();
+    }
+    let mut out = String::new();
+    match node["type"].as_str() {
+        Some("text") => out.push_str(node["text"].as_str().unwrap_or("")),
+        Some("hardBreak") => out.push('\n'),
+        _ => {
+            for child in node["content"].as_array().into_iter().flatten() {
+                out.push_str(&adf_to_text(child));
+            }
+        }
+    }
+    match node["type"].as_str() {
+        Some("paragraph") | Some("heading") | Some("codeBlock") => out.push_str("\n\n"),
+        Some("listItem") => {
+            out = format!("- {}\n", out.trim());
+        }
+        _ => {}
+    }
+    out
+}
+
+#[async_trait]
+impl Tracker for JiraTracker {
+    fn name(&self) -> &'static str {
+        "jira"
+    }
+
+    async fn fetch_issue(&self, key: &str) -> anyhow::Result<IssueDetails> {
+        let issue = self
+            .request(
+                reqwest::Method::GET,
+                &format!(
+                    "issue/{}?fields=summary,description,status,assignee,priority,labels,subtasks,comment",
+                    urlencoding::encode(key)
+                ),
+                None,
+            )
+            .await?;
+        let fields = &issue["fields"];
+        let comments = fields["comment"]["comments"]
+            .as_array()
+            .map(|c| {
+                c.iter()
+                    .rev()
+                    .take(5)
+                    .rev()
+                    .map(|c| {
+                        (
+                            c["author"]["displayName"]
+                                .as_str()
+                                .unwrap_or("?")
+                                .to_string(),
+                            adf_to_text(&c["body"]),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(IssueDetails {
+            key: issue["key"].as_str().unwrap_or(key).to_string(),
+            title: fields["summary"].as_str().unwrap_or("").to_string(),
+            status: fields["status"]["name"].as_str().unwrap_or("?").to_string(),
+            url: Some(format!("{}/browse/{}", self.base_url, key)),
+            assignee: fields["assignee"]["displayName"]
+                .as_str()
+                .map(str::to_string),
+            priority: fields["priority"]["name"].as_str().map(str::to_string),
+            labels: fields["labels"]
+                .as_array()
+                .map(|l| {
+                    l.iter()
+                        .filter_map(|v| v.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            description: adf_to_text(&fields["description"]),
+            subtasks: fields["subtasks"]
+                .as_array()
+                .map(|s| {
+                    s.iter()
+                        .map(|t| {
+                            (
+                                t["key"].as_str().unwrap_or("?").to_string(),
+                                t["fields"]["summary"].as_str().unwrap_or("").to_string(),
+                                t["fields"]["status"]["name"]
+                                    .as_str()
+                                    .unwrap_or("?")
+                                    .to_string(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            comments,
+        })
+    }
+
+    async fn add_comment(&self, key: &str, body: &str) -> anyhow::Result<String> {
+        let resp = self
+            .request(
+                reqwest::Method::POST,
+                &format!("issue/{}/comment", urlencoding::encode(key)),
+                Some(json!({ "body": text_to_adf(body) })),
+            )
+            .await?;
+        Ok(format!(
+            "{}/browse/{}?focusedCommentId={}",
+            self.base_url,
+            key,
+            resp["id"].as_str().unwrap_or("")
+        ))
+    }
+
+    async fn transition(&self, key: &str, status: &str) -> anyhow::Result<String> {
+        let path = format!("issue/{}/transitions", urlencoding::encode(key));
+        let resp = self.request(reqwest::Method::GET, &path, None).await?;
+        let candidates: Vec<(String, Vec<String>)> = resp["transitions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|t| {
+                (
+                    t["id"].as_str().unwrap_or("").to_string(),
+                    vec![
+                        t["name"].as_str().unwrap_or("").to_string(),
+                        t["to"]["name"].as_str().unwrap_or("").to_string(),
+                    ],
+                )
+            })
+            .collect();
+        let Some(id) = match_status(status, &candidates) else {
+            let available: Vec<&str> = candidates.iter().map(|(_, n)| n[1].as_str()).collect();
+            anyhow::bail!(
+                "No transition to '{}' available for {} (available: {})",
+                status,
+                key,
+                available.join(", ")
+            );
+        };
+        self.request(
+            reqwest::Method::POST,
+            &path,
+            Some(json!({ "transition": { "id": id } })),
+        )
+        .await?;
+        let target = candidates
+            .iter()
+            .find(|(cid, _)| cid == id)
+            .map(|(_, names)| names[1].clone())
+            .unwrap_or_else(|| status.to_string());
+        Ok(target)
+    }
+
+    async fn create_subtask(
+        &self,
+        parent: &str,
+        title: &str,
+        description: Option<&str>,
+    ) -> anyhow::Result<String> {
+        let project = parent
+            .split_once('-')
+            .map(|(p, _)| p)
+            .ok_or_else(|| anyhow::anyhow!("Invalid Jira issue key: {}", parent))?;
+
+        // Sub-task issue type names differ between classic and team-managed projects.
+        let issue_type = match self
+            .request(
+                reqwest::Method::GET,
+                &format!("issue/createmeta/{}/issuetypes", project),
+                None,
+            )
+            .await
+        {
+            Ok(meta) => meta["issueTypes"]
+                .as_array()
+                .and_then(|types| types.iter().find(|t| t["subtask"].as_bool() == Some(true)))
+                .map(|t| json!({ "id": t["id"] }))
+                .unwrap_or_else(|| json!({ "name": "Sub-task" })),
+            Err(_) => json!({ "name": "Sub-task" }),
+        };
+
+        let mut fields = json!({
+            "project": { "key": project },
+            "parent": { "key": parent },
+            "summary": title,
+            "issuetype": issue_type,
+        });
+        if let Some(desc) = description {
+            fields["description"] = text_to_adf(desc);
+        }
+        let resp = self
+            .request(
+                reqwest::Method::POST,
+                "issue",
+                Some(json!({ "fields": fields })),
+            )
+            .await?;
+        Ok(resp["key"].as_str().unwrap_or("?").to_string())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Linear
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Linear (GraphQL API) tracker.
+pub struct LinearTracker {
+    api_key: String,
+}
+
+impl LinearTracker {
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            api_key: workspace_setting("LINEAR_API_KEY")?,
+        })
+    }
+
+    async fn graphql(&self, query: &str, variables: Value) -> anyhow::Result<Value> {
+        let resp = http_client()?
+            .post(LINEAR_API_URL)
+            .header("Authorization", &self.api_key)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await?;
+        let status = resp.status();
+        let body: Value = resp.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            anyhow::bail!("Linear API failed ({}): {}", status, body);
+        }
+        if let Some(errors) = body["errors"].as_array().filter(|e| !e.is_empty()) {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|e| e["message"].as_str())
+                .collect();
+            anyhow::bail!("Linear API error: {}", messages.join("; "));
+        }
+        Ok(body["data"].clone())
+    }
+}
+
+#[async_trait]
+impl Tracker for LinearTracker {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    async fn fetch_issue(&self, key: &str) -> anyhow::Result<IssueDetails> {
+        let data = self
+            .graphql(
+                "query($id: String!) { issue(id: $id) { identifier title description url \
+                 priorityLabel state { name } assignee { name } labels { nodes { name } } \
+                 children { nodes { identifier title state { name } } } \
+                 comments(last: 5) { nodes { body user { name } } } } }",
+                json!({ "id": key }),
+            )
+            .await?;
+        let issue = &data["issue"];
+        if issue.is_null() {
+            anyhow::bail!("Linear issue {} not found", key);
+        }
+        let names = |v: &Value| -> Vec<String> {
+            v["nodes"]
+                .as_array()
+                .map(|n| {
+                    n.iter()
+                        .filter_map(|x| x["name"].as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Ok(IssueDetails {
+            key: issue["identifier"].as_str().unwrap_or(key).to_string(),
+            title: issue["title"].as_str().unwrap_or("").to_string(),
+            status: issue["state"]["name"].as_str().unwrap_or("?").to_string(),
+            url: issue["url"].as_str().map(str::to_string),
+            assignee: issue["assignee"]["name"].as_str().map(str::to_string),
+            priority: issue["priorityLabel"].as_str().map(str::to_string),
+            labels: names(&issue["labels"]),
+            description: issue["description"].as_str().unwrap_or("").to_string(),
+            subtasks: issue["children"]["nodes"]
+                .as_array()
+                .map(|c| {
+                    c.iter()
+                        .map(|t| {
+                            (
+                                t["identifier"].as_str().unwrap_or("?").to_string(),
+                                t["title"].as_str().unwrap_or("").to_string(),
+                                t["state"]["name"].as_str().unwrap_or("?").to_string(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            comments: issue["comments"]["nodes"]
+                .as_array()
+                .map(|c| {
+                    c.iter()
+                        .map(|x| {
+                            (
+                                x["user"]["name"].as_str().unwrap_or("?").to_string(),
+                                x["body"].as_str().unwrap_or("").to_string(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    async fn add_comment(&self, key: &str, body: &str) -> anyhow::Result<String> {
+        let data = self
+            .graphql(
+                "mutation($issueId: String!, $body: String!) { commentCreate(input: \
+                 { issueId: $issueId, body: $body }) { success comment { url } } }",
+                json!({ "issueId": key, "body": body }),
+            )
+            .await?;
+        Ok(data["commentCreate"]["comment"]["url"]
+            .as_str()
+            .unwrap_or("")
+            .to_string())
+    }
+
+    async fn transition(&self, key: &str, status: &str) -> anyhow::Result<String> {
+        let data = self
+            .graphql(
+                "query($id: String!) { issue(id: $id) { id team { states { nodes { id name } } } } }",
+                json!({ "id": key }),
+            )
+            .await?;
+        let issue_id = data["issue"]["id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Linear issue {} not found", key))?
+            .to_string();
+        let candidates: Vec<(String, Vec<String>)> = data["issue"]["team"]["states"]["nodes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|s| {
+                (
+                    s["id"].as_str().unwrap_or("").to_string(),
+                    vec![s["name"].as_str().unwrap_or("").to_string()],
+                )
+            })
+            .collect();
+        let Some(state_id) = match_status(status, &candidates) else {
+            let available: Vec<&str> = candidates.iter().map(|(_, n)| n[0].as_str()).collect();
+            anyhow::bail!(
+                "No workflow state '{}' for {} (available: {})",
+                status,
+                key,
+                available.join(", ")
+            );
+        };
+        let data = self
+            .graphql(
+                "mutation($id: String!, $stateId: String!) { issueUpdate(id: $id, input: \
+                 { stateId: $stateId }) { success issue { state { name } } } }",
+                json!({ "id": issue_id, "stateId": state_id }),
+            )
+            .await?;
+        Ok(data["issueUpdate"]["issue"]["state"]["name"]
+            .as_str()
+            .unwrap_or(status)
+            .to_string())
+    }
+
+    async fn create_subtask(
+        &self,
+        parent: &str,
+        title: &str,
+        description: Option<&str>,
+    ) -> anyhow::Result<String> {
+        let data = self
+            .graphql(
+                "query($id: String!) { issue(id: $id) { id team { id } } }",
+                json!({ "id": parent }),
+            )
+            .await?;
+        let parent_id = data["issue"]["id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Linear issue {} not found", parent))?;
+        let team_id = data["issue"]["team"]["id"].as_str().unwrap_or("");
+        let data = self
+            .graphql(
+                "mutation($input: IssueCreateInput!) { issueCreate(input: $input) \
+                 { success issue { identifier url } } }",
+                json!({ "input": {
+                    "teamId": team_id,
+                    "parentId": parent_id,
+                    "title": title,
+                    "description": description.unwrap_or(""),
+                } }),
+            )
+            .await?;
+        Ok(data["issueCreate"]["issue"]["identifier"]
+            .as_str()
+            .unwrap_or("?")
+            .to_string())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tools
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Resolve the tracker requested by the tool args (or the only configured one).
+fn resolve_tracker(args: &Value) -> anyhow::Result<Box<dyn Tracker>> {
+    let jira = || JiraTracker::from_env().map(|t| Box::new(t) as Box<dyn Tracker>);
+    let linear = || LinearTracker::from_env().map(|t| Box::new(t) as Box<dyn Tracker>);
+    match args["tracker"]
+        .as_str()
+        .map(|s| s.to_lowercase())
+        .as_deref()
+    {
+        Some("jira") => jira().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Jira is not configured (set JIRA_BASE_URL, JIRA_EMAIL, JIRA_API_TOKEN)"
+            )
+        }),
+        Some("linear") => {
+            linear().ok_or_else(|| anyhow::anyhow!("Linear is not configured (set LINEAR_API_KEY)"))
+        }
+        Some(other) => anyhow::bail!("Unknown tracker '{}' (use jira or linear)", other),
+        None => match (jira(), linear()) {
+            (Some(j), None) => Ok(j),
+            (None, Some(l)) => Ok(l),
+            (Some(_), Some(_)) => {
+                anyhow::bail!("Both Jira and Linear are configured; pass tracker: jira|linear")
+            }
+            (None, None) => anyhow::bail!(
+                "No issue tracker configured for this workspace (set Jira or Linear env vars)"
+            ),
+        },
+    }
+}
+
+fn tracker_schema(extra: Value, required: &[&str]) -> Value {
+    let mut props = json!({
+        "tracker": {
+            "type": "string",
+            "enum": ["jira", "linear"],
+            "description": "Tracker to use (default: the one configured for this workspace)"
+        },
+        "issue": {
+            "type": "string",
+            "description": "Issue key (e.g. PROJ-123 or ENG-42)"
+        }
+    });
+    if let (Some(props), Some(extra)) = (props.as_object_mut(), extra.as_object()) {
+        props.extend(extra.clone());
+    }
+    let mut required: Vec<&str> = required.to_vec();
+    required.insert(0, "issue");
+    json!({ "type": "object", "properties": props, "required": required })
+}
+
+fn required_str<'a>(args: &'a Value, name: &str) -> anyhow::Result<&'a str> {
+    args[name]
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Missing '{}' argument", name))
+}
+
+/// Fetch an issue with its description (acceptance criteria), sub-tasks, and comments.
+pub struct TrackerGetIssue;
+
+#[async_trait]
+impl Tool for TrackerGetIssue {
+    fn name(&self) -> &str {
+        "tracker_get_issue"
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a Jira or Linear issue: title, status, description/acceptance criteria, sub-tasks, and recent comments."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        tracker_schema(json!({}), &[])
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let tracker = resolve_tracker(&args)?;
+        let issue = tracker.fetch_issue(required_str(&args, "issue")?).await?;
+        Ok(issue.to_markdown())
+    }
+}
+
+/// Add a progress comment to an issue.
+pub struct TrackerAddComment;
+
+#[async_trait]
+impl Tool for TrackerAddComment {
+    fn name(&self) -> &str {
+        "tracker_add_comment"
+    }
+
+    fn description(&self) -> &str {
+        "Add a comment to a Jira or Linear issue (e.g. to report mission progress or results)."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        tracker_schema(
+            json!({ "body": { "type": "string", "description": "Comment text" } }),
+            &["body"],
+        )
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let tracker = resolve_tracker(&args)?;
+        let key = required_str(&args, "issue")?;
+        let link = tracker
+            .add_comment(key, required_str(&args, "body")?)
+            .await?;
+        Ok(format!(
+            "Added comment to {} ({}) {}",
+            key,
+            tracker.name(),
+            link
+        ))
+    }
+}
+
+/// Move an issue to another status.
+pub struct TrackerTransition;
+
+#[async_trait]
+impl Tool for TrackerTransition {
+    fn name(&self) -> &str {
+        "tracker_transition"
+    }
+
+    fn description(&self) -> &str {
+        "Move a Jira or Linear issue to another status (e.g. 'In Progress', 'In Review', 'Done')."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        tracker_schema(
+            json!({ "status": { "type": "string", "description": "Target status or transition name" } }),
+            &["status"],
+        )
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let tracker = resolve_tracker(&args)?;
+        let key = required_str(&args, "issue")?;
+        let status = tracker
+            .transition(key, required_str(&args, "status")?)
+            .await?;
+        Ok(format!("Moved {} to '{}'", key, status))
+    }
+}
+
+/// Create a sub-task under an issue.
+pub struct TrackerCreateSubtask;
+
+#[async_trait]
+impl Tool for TrackerCreateSubtask {
+    fn name(&self) -> &str {
+        "tracker_create_subtask"
+    }
+
+    fn description(&self) -> &str {
+        "Create a sub-task under a Jira or Linear issue."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        tracker_schema(
+            json!({
+                "title": { "type": "string", "description": "Sub-task title" },
+                "description": { "type": "string", "description": "Optional sub-task description" }
+            }),
+            &["title"],
+        )
+    }
+
+    async fn execute(&self, args: Value, _working_dir: &Path) -> anyhow::Result<String> {
+        let tracker = resolve_tracker(&args)?;
+        let parent = required_str(&args, "issue")?;
+        let key = tracker
+            .create_subtask(
+                parent,
+                required_str(&args, "title")?,
+                args["description"].as_str(),
+            )
+            .await?;
+        Ok(format!("Created sub-task {} under {}", key, parent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adf_round_trip_text() {
+        let adf = text_to_adf("First paragraph.\n\nSecond one.");
+        assert_eq!(adf["content"].as_array().unwrap().len(), 2);
+        let text = adf_to_text(&adf);
+        assert!(text.contains("First paragraph."));
+        assert!(text.contains("Second one."));
+    }
+
+    #[test]
+    fn test_adf_lists() {
+        let adf = json!({
+            "type": "doc",
+            "content": [{
+                "type": "bulletList",
+                "content": [
+                    { "type": "listItem", "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "AC one" }] }] },
+                    { "type": "listItem", "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "AC two" }] }] }
+                ]
+            }]
+        });
+        assert_eq!(adf_to_text(&adf), "- AC one\n- AC two\n");
+    }
+
+    #[test]
+    fn test_match_status_prefers_exact() {
+        let candidates = vec![
+            (
+                "1".to_string(),
+                vec!["Start Progress".to_string(), "In Progress".to_string()],
+            ),
+            (
+                "2".to_string(),
+                vec!["Done".to_string(), "Done".to_string()],
+            ),
+            ("3".to_string(), vec!["In Review".to_string()]),
+        ];
+        assert_eq!(match_status("in progress", &candidates), Some("1"));
+        assert_eq!(match_status("done", &candidates), Some("2"));
+        assert_eq!(match_status("In Rev", &candidates), Some("3"));
+        assert_eq!(match_status("Blocked", &candidates), None);
+    }
+}
@@ -0,0 +1,401 @@
+//! WASM plugin runtime for library tools (`tool/*.wasm`).
+//!
+//! A plugin is a WASI command module (`wasm32-wasip1`) synced next to the
+//! script tools in [`LIBRARY_TOOLS_DIR`] and named after its file. It is run
+//! by the `wasmtime` CLI, one process per call.
+//!
+//! There is no WIT world: the server does not embed wasmtime, so plugins are
+//! core modules rather than components, and the interface is argv and stdio.
+//! Any toolchain that targets WASI preview 1 can build one. The contract:
+//!
+//! - `<plugin> describe` (no input) prints one JSON object on stdout:
+//!   `{"description": "...", "parameters": {...}}`, where `parameters` is the
+//!   JSON Schema of the arguments. A missing or non-object schema becomes an
+//!   empty object schema. It must finish within 30 seconds.
+//! - `<plugin> execute` reads the arguments as one JSON object on stdin and
+//!   prints the tool output on stdout, which is returned as-is (truncated to
+//!   50,000 bytes). It must finish within the manifest's `timeout_secs`.
+//! - Exit status 0 means success. Any other status fails the call with the
+//!   last 4,000 characters of stderr; stdout is discarded.
+//!
+//! A plugin that fails `describe` is logged and not registered. Mission
+//! post-processors (`hook/processors.json`) use the same `execute` contract.
+//!
+//! Plugins get no capabilities by default: no filesystem, no environment and
+//! no network. An optional manifest `tool/<name>.json` grants them:
+//!
+//! ```json
+//! {
+//!   "capabilities": { "workspace": true, "env": ["GITHUB_TOKEN"], "max_memory_mb": 128 },
+//!   "timeout_secs": 30
+//! }
+//! ```
+//!
+//! - `workspace` - read/write access to the mission directory (mounted at `.`)
+//! - `env` - environment variables passed through (values from workspace env)
+//! - `max_memory_mb` - linear memory limit (default: 256)
+//!
+//! Settings (workspace env vars or process env):
+//! - `SANDBOXED_SH_WASM_RUNTIME` - wasmtime binary (default: `wasmtime`)
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::library_tool::LIBRARY_TOOLS_DIR;
+use super::terminal::{run_workspace_shell_with_stdin, shell_quote, workspace_setting};
+use super::{safe_truncate_index, Tool};
+
+/// Subcommand printing the plugin's description and argument schema.
+const DESCRIBE: &str = "describe";
+/// Subcommand running the tool with its arguments on stdin.
+const EXECUTE: &str = "execute";
+
+const DESCRIBE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_CALL_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MAX_MEMORY_MB: u64 = 256;
+
+/// Maximum plugin stderr included in error messages.
+const MAX_LOG_CHARS: usize = 4_000;
+
+/// Maximum plugin stdout returned from `execute`.
+const MAX_OUTPUT_BYTES: usize = 50_000;
+
+/// Capability manifest (`tool/<name>.json`).
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    capabilities: Capabilities,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Capabilities {
+    #[serde(default)]
+    workspace: bool,
+    #[serde(default)]
+    env: Vec<String>,
+    #[serde(default)]
+    max_memory_mb: Option<u64>,
+}
+
+impl Manifest {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(raw) => Ok(serde_json::from_str(&raw)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.timeout_secs
+                .filter(|secs| *secs > 0)
+                .unwrap_or(DEFAULT_CALL_TIMEOUT_SECS),
+        )
+    }
+
+    /// Environment variable names the plugin may read, dropping invalid ones.
+    fn env_names(&self) -> Vec<&str> {
+        self.capabilities
+            .env
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+            .collect()
+    }
+
+    /// Shell command running `file` (relative to the mission dir) under wasmtime.
+    fn command(&self, runtime: &str, mode: &str, file: &str) -> String {
+        let memory_mb = self
+            .capabilities
+            .max_memory_mb
+            .filter(|mb| *mb > 0)
+            .unwrap_or(DEFAULT_MAX_MEMORY_MB);
+        let mut parts = vec![
+            shell_quote(runtime),
+            "run".to_string(),
+            format!("-W max-memory-size={}", memory_mb * 1024 * 1024),
+        ];
+        if self.capabilities.workspace {
+            parts.push("--dir=.".to_string());
+        }
+        // Values are inherited from the process env so secrets stay off the command line.
+        for name in self.env_names() {
+            parts.push(format!("--env {}", name));
+        }
+        parts.push(shell_quote(file));
+        parts.push(mode.to_string());
+        parts.join(" ")
+    }
+
+    /// Values for the granted environment variables, resolved from workspace env.
+    fn env_values(&self) -> HashMap<String, String> {
+        self.env_names()
+            .into_iter()
+            .filter_map(|name| workspace_setting(name).map(|value| (name.to_string(), value)))
+            .collect()
+    }
+}
+
+fn wasm_runtime() -> String {
+    workspace_setting("SANDBOXED_SH_WASM_RUNTIME")
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| "wasmtime".to_string())
+}
+
+/// Last `MAX_LOG_CHARS` bytes of `s`.
+fn tail(s: &str) -> &str {
+    let s = s.trim();
+    if s.len() <= MAX_LOG_CHARS {
+        return s;
+    }
+    let mut start = s.len() - MAX_LOG_CHARS;
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+    &s[start..]
+}
+
+/// Run a plugin subcommand and return its stdout.
+async fn run_plugin(
+    working_dir: &Path,
+    runtime: &str,
+    manifest: &Manifest,
+    mode: &str,
+    file: &str,
+    input: Option<String>,
+    timeout: Duration,
+) -> anyhow::Result<String> {
+    let output = run_workspace_shell_with_stdin(
+        working_dir,
+        &manifest.command(runtime, mode, file),
+        manifest.env_values(),
+        input,
+        timeout,
+    )
+    .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("WASM plugin failed ({}): {}", output.status, tail(&stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run a standalone WASM module (`file`, relative to `working_dir`) in
+/// `execute` mode with `input` on stdin, granting the capabilities of the
+/// manifest next to it. Used by mission post-processors.
+pub(crate) async fn run_wasm_module(
+    working_dir: &Path,
+    file: &str,
+    input: String,
+) -> anyhow::Result<String> {
+    let manifest = Manifest::load(&working_dir.join(file).with_extension("json"))?;
+    run_plugin(
+        working_dir,
+        &wasm_runtime(),
+        &manifest,
+        EXECUTE,
+        file,
+        Some(input),
+        manifest.timeout(),
+    )
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginDescription {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    parameters: Value,
+}
+
+/// A library tool implemented as a WASM plugin.
+pub struct WasmTool {
+    name: String,
+    description: String,
+    schema: Value,
+    /// Module file relative to the mission directory.
+    file: String,
+    manifest: Manifest,
+}
+
+#[async_trait]
+impl Tool for WasmTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.schema.clone()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let output = run_plugin(
+            working_dir,
+            &wasm_runtime(),
+            &self.manifest,
+            EXECUTE,
+            &self.file,
+            Some(args.to_string()),
+            self.manifest.timeout(),
+        )
+        .await?;
+        let idx = safe_truncate_index(&output, MAX_OUTPUT_BYTES);
+        Ok(output[..idx].to_string())
+    }
+}
+
+/// WASM plugins synced into `working_dir`, sorted by file name.
+fn wasm_plugin_files(working_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(working_dir.join(LIBRARY_TOOLS_DIR)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("wasm"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Load the WASM plugins synced into `working_dir` (see [`LIBRARY_TOOLS_DIR`]).
+///
+/// Plugins that fail to load are logged and skipped; if wasmtime is not
+/// installed, no plugins are loaded.
+pub async fn load_wasm_tools(working_dir: &Path) -> Vec<Arc<dyn Tool>> {
+    let files = wasm_plugin_files(working_dir);
+    if files.is_empty() {
+        return Vec::new();
+    }
+
+    let runtime = wasm_runtime();
+    let probe = format!("command -v {} >/dev/null", shell_quote(&runtime));
+    let available =
+        run_workspace_shell_with_stdin(working_dir, &probe, HashMap::new(), None, DESCRIBE_TIMEOUT)
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+    if !available {
+        tracing::warn!(
+            runtime = %runtime,
+            count = files.len(),
+            "WASM plugins found but wasmtime is not installed; skipping them"
+        );
+        return Vec::new();
+    }
+
+    let mut tools: Vec<Arc<dyn Tool>> = Vec::new();
+    for path in files {
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            tracing::warn!(path = %path.display(), "Invalid WASM plugin name");
+            continue;
+        }
+        let file = format!("{}/{}.wasm", LIBRARY_TOOLS_DIR, name);
+
+        let manifest = match Manifest::load(&path.with_extension("json")) {
+            Ok(m) => m,
+            Err(e) => {
+                tracing::warn!(file = %file, error = %e, "Invalid WASM plugin manifest");
+                continue;
+            }
+        };
+        let described = run_plugin(
+            working_dir,
+            &runtime,
+            &manifest,
+            DESCRIBE,
+            &file,
+            None,
+            DESCRIBE_TIMEOUT,
+        )
+        .await
+        .and_then(|out| Ok(serde_json::from_str::<PluginDescription>(out.trim())?));
+        let desc = match described {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::warn!(file = %file, error = %e, "Failed to load WASM plugin");
+                continue;
+            }
+        };
+
+        let schema = if desc.parameters.is_object() {
+            desc.parameters
+        } else {
+            json!({ "type": "object", "properties": {} })
+        };
+        tools.push(Arc::new(WasmTool {
+            name: name.to_string(),
+            description: desc.description,
+            schema,
+            file,
+            manifest,
+        }));
+    }
+
+    tracing::info!(
+        count = tools.len(),
+        tools = ?tools.iter().map(|t| t.name().to_string()).collect::<Vec<_>>(),
+        "Loaded WASM plugins"
+    );
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_manifest_grants_nothing() {
+        let cmd = Manifest::default().command("wasmtime", "describe", "t/x.wasm");
+        assert_eq!(
+            cmd,
+            "'wasmtime' run -W max-memory-size=268435456 't/x.wasm' describe"
+        );
+    }
+
+    #[test]
+    fn test_manifest_capabilities() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{
+                "capabilities": {
+                    "workspace": true,
+                    "env": ["GITHUB_TOKEN", "BAD NAME;rm", ""],
+                    "max_memory_mb": 64
+                },
+                "timeout_secs": 5
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.env_names(), vec!["GITHUB_TOKEN"]);
+        assert_eq!(manifest.timeout(), Duration::from_secs(5));
+
+        let cmd = manifest.command("wasmtime", "execute", "t/x.wasm");
+        assert!(cmd.contains("-W max-memory-size=67108864"));
+        assert!(cmd.contains("--dir=."));
+        assert!(cmd.contains("--env GITHUB_TOKEN 't/x.wasm'"));
+        assert!(!cmd.contains("BAD"));
+    }
+}
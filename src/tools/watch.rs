@@ -0,0 +1,354 @@
+//! Filesystem watcher tool for reactive workflows.
+//!
+//! `watch_path` blocks until something happens on disk: a generated file
+//! appears, a build output is removed, or files under a directory change.
+//! Changes are collected with `notify` and batched: after the first matching
+//! event the tool keeps listening until `batch_ms` pass without another one,
+//! so a save that touches several files comes back as one result.
+//!
+//! Paths that do not exist yet are handled by watching their nearest existing
+//! ancestor and moving the watch down as intermediate directories appear.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecursiveMode, Watcher};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use super::directory::glob_match;
+use super::{resolve_path, Tool, ToolArgs};
+
+/// Most changed paths listed in one result.
+const MAX_LISTED: usize = 100;
+
+/// Wait for filesystem changes.
+pub struct WatchPath;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum Until {
+    /// Any change under the path
+    #[default]
+    Change,
+    /// The path exists
+    Exists,
+    /// The path no longer exists
+    Removed,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct WatchArgs {
+    /// File or directory to watch. It may not exist yet.
+    path: String,
+    /// What to wait for: 'change' (default), 'exists' or 'removed'
+    #[serde(default)]
+    until: Until,
+    /// Give up after this many seconds (default: 60)
+    #[serde(default)]
+    #[schemars(range(min = 1, max = 1800))]
+    timeout_secs: Option<u64>,
+    /// After the first change, keep collecting until no change happens for this many milliseconds (default: 500)
+    #[serde(default)]
+    #[schemars(range(max = 10000))]
+    batch_ms: Option<u64>,
+    /// Only report changed files whose name matches this glob (e.g. '*.rs')
+    #[serde(default)]
+    pattern: Option<String>,
+}
+
+/// Label for a change event, or `None` for access and metadata-only events.
+pub(crate) fn change_label(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(ModifyKind::Name(_)) => Some("renamed"),
+        EventKind::Modify(ModifyKind::Metadata(_)) => None,
+        EventKind::Modify(_) => Some("modified"),
+        EventKind::Remove(_) => Some("removed"),
+        _ => None,
+    }
+}
+
+/// The deepest existing directory on the way to `path`.
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|p| p.is_dir())
+        .map(Path::to_path_buf)
+}
+
+fn display_path(path: &Path, working_dir: &Path) -> String {
+    path.strip_prefix(working_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Changed paths and how they changed, merged across events.
+#[derive(Default)]
+struct Batch {
+    changes: BTreeMap<PathBuf, &'static str>,
+}
+
+impl Batch {
+    fn add(&mut self, path: PathBuf, label: &'static str) {
+        let entry = self.changes.entry(path).or_insert(label);
+        // Keep "created" for a file written right after creation, but let a
+        // removal override whatever came before it.
+        if label == "removed" || *entry == "removed" {
+            *entry = label;
+        }
+    }
+
+    fn render(&self, working_dir: &Path) -> String {
+        let mut lines: Vec<String> = self
+            .changes
+            .iter()
+            .take(MAX_LISTED)
+            .map(|(path, label)| format!("- {}: {}", label, display_path(path, working_dir)))
+            .collect();
+        if self.changes.len() > MAX_LISTED {
+            lines.push(format!("... ({} more)", self.changes.len() - MAX_LISTED));
+        }
+        lines.join("\n")
+    }
+}
+
+#[async_trait]
+impl Tool for WatchPath {
+    fn name(&self) -> &str {
+        "watch_path"
+    }
+
+    fn description(&self) -> &str {
+        "Wait for filesystem changes under a file or directory, e.g. until a generated file appears or until sources change. Returns the batched list of created, modified, renamed and removed paths, or reports that nothing happened before the timeout."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        WatchArgs::schema()
+    }
+
+    async fn execute(&self, args: Value, working_dir: &Path) -> anyhow::Result<String> {
+        let args = WatchArgs::parse(args)?;
+        let timeout = Duration::from_secs(args.timeout_secs.unwrap_or(60));
+        let batch_window = Duration::from_millis(args.batch_ms.unwrap_or(500));
+        let pattern = args.pattern.as_deref().map(str::to_lowercase);
+        let target = resolve_path(&args.path, working_dir).resolved;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |res| {
+            let _ = tx.send(res);
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to start file watcher: {}", e))?;
+
+        // Watch the target itself, or the closest directory that exists.
+        let mut watched = if target.exists() {
+            let mode = if target.is_dir() {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+            watcher.watch(&target, mode)?;
+            target.clone()
+        } else {
+            let ancestor = existing_ancestor(&target).ok_or_else(|| {
+                anyhow::anyhow!("No existing parent directory for {}", target.display())
+            })?;
+            watcher.watch(&ancestor, RecursiveMode::NonRecursive)?;
+            ancestor
+        };
+
+        // Checked after the watch is in place so nothing slips in between.
+        let satisfied = |until: Until| match until {
+            Until::Exists => target.exists(),
+            Until::Removed => !target.exists(),
+            Until::Change => false,
+        };
+        let shown = display_path(&target, working_dir);
+        if satisfied(args.until) {
+            return Ok(format!(
+                "{} already {}",
+                shown,
+                if args.until == Until::Exists {
+                    "exists"
+                } else {
+                    "does not exist"
+                }
+            ));
+        }
+
+        let started = Instant::now();
+        let deadline = started + timeout;
+        let mut batch = Batch::default();
+        let mut quiet_until: Option<Instant> = None;
+
+        loop {
+            let wake = quiet_until.map_or(deadline, |q| q.min(deadline));
+            let event = match tokio::time::timeout_at(wake.into(), rx.recv()).await {
+                Ok(Some(Ok(event))) => event,
+                Ok(Some(Err(e))) => {
+                    tracing::warn!(error = %e, "File watcher error");
+                    continue;
+                }
+                // Timed out, or the watcher went away
+                _ => break,
+            };
+
+            // Follow a missing target down as its parent directories appear.
+            if watched != target {
+                let next = if target.exists() {
+                    Some(target.clone())
+                } else {
+                    existing_ancestor(&target).filter(|a| a != &watched)
+                };
+                if let Some(next) = next {
+                    let _ = watcher.unwatch(&watched);
+                    let mode = if next == target && next.is_dir() {
+                        RecursiveMode::Recursive
+                    } else {
+                        RecursiveMode::NonRecursive
+                    };
+                    watcher.watch(&next, mode)?;
+                    watched = next;
+                }
+            }
+
+            if args.until != Until::Change {
+                if satisfied(args.until) {
+                    let verb = if args.until == Until::Exists {
+                        "appeared"
+                    } else {
+                        "was removed"
+                    };
+                    return Ok(format!(
+                        "{} {} after {:.1}s",
+                        shown,
+                        verb,
+                        started.elapsed().as_secs_f64()
+                    ));
+                }
+                continue;
+            }
+
+            let Some(label) = change_label(&event.kind) else {
+                continue;
+            };
+            for path in event.paths {
+                if !path.starts_with(&target) {
+                    continue;
+                }
+                if let Some(pattern) = &pattern {
+                    let name = path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_lowercase())
+                        .unwrap_or_default();
+                    if !glob_match(pattern, &name) {
+                        continue;
+                    }
+                }
+                batch.add(path, label);
+                quiet_until = Some(Instant::now() + batch_window);
+            }
+        }
+
+        if batch.changes.is_empty() {
+            let what = match args.until {
+                Until::Change => "No changes under",
+                Until::Exists => "Still waiting for",
+                Until::Removed => "Still present:",
+            };
+            return Ok(format!(
+                "{} {} after {}s (timed out)",
+                what,
+                shown,
+                timeout.as_secs()
+            ));
+        }
+        Ok(format!(
+            "{} change(s) under {} after {:.1}s:\n{}",
+            batch.changes.len(),
+            shown,
+            started.elapsed().as_secs_f64(),
+            batch.render(working_dir)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn waits_for_nested_file_to_appear() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            std::fs::create_dir_all(root.join("out/build")).unwrap();
+            std::fs::write(root.join("out/build/report.json"), "{}").unwrap();
+        });
+        let result = WatchPath
+            .execute(
+                json!({"path": "out/build/report.json", "until": "exists", "timeout_secs": 10}),
+                dir.path(),
+            )
+            .await
+            .unwrap();
+        writer.await.unwrap();
+        assert!(
+            result.starts_with("out/build/report.json appeared"),
+            "{result}"
+        );
+    }
+
+    #[tokio::test]
+    async fn batches_matching_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let root = dir.path().to_path_buf();
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            std::fs::write(root.join("src/lib.rs"), "fn a() {}").unwrap();
+            std::fs::write(root.join("src/notes.txt"), "x").unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        });
+        let result = WatchPath
+            .execute(
+                json!({"path": "src", "pattern": "*.rs", "batch_ms": 300, "timeout_secs": 10}),
+                dir.path(),
+            )
+            .await
+            .unwrap();
+        writer.await.unwrap();
+        assert!(result.starts_with("2 change(s) under src"), "{result}");
+        assert!(result.contains("- created: src/lib.rs"), "{result}");
+        assert!(result.contains("- created: src/main.rs"), "{result}");
+        assert!(!result.contains("notes.txt"), "{result}");
+    }
+
+    #[tokio::test]
+    async fn reports_timeout_and_existing_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = WatchPath
+            .execute(json!({"path": ".", "timeout_secs": 1}), dir.path())
+            .await
+            .unwrap();
+        assert!(result.contains("(timed out)"), "{result}");
+
+        let result = WatchPath
+            .execute(
+                json!({"path": "missing.txt", "until": "removed"}),
+                dir.path(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, "missing.txt already does not exist");
+    }
+}
@@ -1,18 +1,42 @@
 //! Web access tools: fetch URLs.
 //!
 //! Only the `fetch_url` tool remains; search is handled upstream by OpenCode/OMO agents.
+//! When the built-in web proxy runs, fetches go through it (see `crate::web_proxy`).
+//! HTML pages are reduced to their main content and rendered as Markdown by
+//! default (see `html_markdown`).
 
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
+use super::html_markdown;
+use super::terminal::workspace_setting;
 use super::Tool;
+use crate::util::env_var_bool;
+use crate::web_proxy;
+
+const USER_AGENT: &str = "Mozilla/5.0 (compatible; Sandboxed/1.0)";
+/// Product token matched against robots.txt `User-agent` lines.
+const ROBOTS_AGENT: &str = "sandboxed";
+const ROBOTS_CACHE_TTL: Duration = Duration::from_secs(3600);
+const DEFAULT_MAX_BYTES: usize = 5 * 1024 * 1024;
+const MAX_INLINE_SIZE: usize = 20000;
+
+/// robots.txt rules per origin, cached for an hour.
+static ROBOTS_CACHE: LazyLock<Mutex<HashMap<String, (Instant, RobotsRules)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 
 /// Fetch content from a URL.
 ///
-/// For large responses (>20KB), saves the full content to /tmp/ and returns
+/// HTML is converted to Markdown (optionally after readability extraction),
+/// robots.txt is honoured unless `SANDBOXED_SH_FETCH_RESPECT_ROBOTS=false`,
+/// and bodies are capped at `SANDBOXED_SH_FETCH_MAX_BYTES` (5 MiB by default).
+/// For large results (>20KB), saves the full content to /tmp/ and returns
 /// the file path along with a preview to avoid truncation.
 pub struct FetchUrl;
 
@@ -23,7 +47,7 @@ impl Tool for FetchUrl {
     }
 
     fn description(&self) -> &str {
-        "Fetch the content of a URL. For small responses (<20KB), returns the content directly. For large responses, saves the full content to /tmp/ and returns the file path with a preview. Useful for reading documentation, APIs, or downloading data."
+        "Fetch the content of a URL. HTML pages are returned as Markdown of the main article content by default (navigation, scripts and other boilerplate removed). For small results (<20KB), returns the content directly. For large results, saves the full content to /tmp/ and returns the file path with a preview. Respects robots.txt. Useful for reading documentation, APIs, or downloading data."
     }
 
     fn parameters_schema(&self) -> Value {
@@ -33,6 +57,15 @@ impl Tool for FetchUrl {
                 "url": {
                     "type": "string",
                     "description": "The URL to fetch"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["markdown", "text", "raw"],
+                    "description": "How to return HTML pages: 'markdown' (default), 'text' (plain text), or 'raw' (unmodified HTML). Other content types are always returned as-is."
+                },
+                "readability": {
+                    "type": "boolean",
+                    "description": "Extract only the main article content of HTML pages (default: true). Set to false to convert the whole page."
                 }
             },
             "required": ["url"]
@@ -43,15 +76,44 @@ impl Tool for FetchUrl {
         let url = args["url"]
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("Missing 'url' argument"))?;
+        let format = args["format"].as_str().unwrap_or("markdown");
+        if !matches!(format, "markdown" | "text" | "raw") {
+            return Err(anyhow::anyhow!(
+                "Invalid format '{}': expected markdown, text or raw",
+                format
+            ));
+        }
+        let readability = args["readability"].as_bool().unwrap_or(true);
+        let parsed = reqwest::Url::parse(url)?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(anyhow::anyhow!("Only http(s) URLs can be fetched"));
+        }
 
         let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (compatible; Sandboxed/1.0)")
-            .timeout(std::time::Duration::from_secs(60))
+            .user_agent(USER_AGENT)
+            .redirect(reqwest::redirect::Policy::limited(
+                web_proxy::FETCH_MAX_REDIRECTS,
+            ))
+            .timeout(Duration::from_secs(60))
             .build()?;
 
-        let response = client.get(url).send().await?;
+        if respect_robots() && !robots_allowed(&client, &parsed).await {
+            return Err(anyhow::anyhow!(
+                "Fetching {} is disallowed by the site's robots.txt",
+                url
+            ));
+        }
+
+        let response = send_get(&client, url).await?;
         let status = response.status();
 
+        if let Some(reason) = response
+            .headers()
+            .get(web_proxy::BLOCKED_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            return Err(anyhow::anyhow!("Blocked by web proxy: {}", reason));
+        }
         if !status.is_success() {
             return Err(anyhow::anyhow!("HTTP error: {}", status));
         }
@@ -62,14 +124,31 @@ impl Tool for FetchUrl {
             .and_then(|v| v.to_str().ok())
             .map(|s| s.to_string())
             .unwrap_or_default();
+        // Through the proxy the response URL is the proxy endpoint; it reports
+        // where redirects actually ended up.
+        let final_url = response
+            .headers()
+            .get(web_proxy::FINAL_URL_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| reqwest::Url::parse(v).ok())
+            .unwrap_or_else(|| response.url().clone());
 
-        let body = response.text().await?;
+        let max_bytes = max_bytes();
+        let (bytes, truncated) = read_capped(response, max_bytes).await?;
+        let body = String::from_utf8_lossy(&bytes).into_owned();
+        let is_html = content_type.contains("text/html")
+            || content_type.contains("application/xhtml")
+            || (content_type.is_empty() && looks_like_html(&body));
 
         // Determine file extension from content type
-        let extension = if content_type.contains("application/json") {
+        let extension = if is_html {
+            match format {
+                "markdown" => "md",
+                "text" => "txt",
+                _ => "html",
+            }
+        } else if content_type.contains("application/json") {
             "json"
-        } else if content_type.contains("text/html") {
-            "html"
         } else if content_type.contains("text/csv") {
             "csv"
         } else if content_type.contains("text/xml") || content_type.contains("application/xml") {
@@ -78,16 +157,20 @@ impl Tool for FetchUrl {
             "txt"
         };
 
-        // If HTML, try to extract text content for display
-        let display_content = if content_type.contains("text/html") {
-            extract_text_from_html(&body)
+        let mut content = if is_html && format != "raw" {
+            html_to_output(&body, &final_url, format == "text", readability)
         } else {
-            body.clone()
+            body
         };
+        if truncated {
+            content.push_str(&format!(
+                "\n\n[Content truncated: response exceeded {} bytes]",
+                max_bytes
+            ));
+        }
 
-        // For large responses, save to file and return path
-        const MAX_INLINE_SIZE: usize = 20000;
-        if body.len() > MAX_INLINE_SIZE {
+        // For large results, save to file and return path
+        if content.len() > MAX_INLINE_SIZE {
             let tmp_dir = Path::new("/tmp");
 
             // Generate unique filename
@@ -95,77 +178,292 @@ impl Tool for FetchUrl {
             let file_path = tmp_dir.join(&filename);
 
             // Save full content to file
-            std::fs::write(&file_path, &body)?;
+            std::fs::write(&file_path, &content)?;
 
             // Return path with preview (safe for UTF-8)
-            let preview_len = std::cmp::min(2000, display_content.len());
-            let safe_end = super::safe_truncate_index(&display_content, preview_len);
-            let preview = &display_content[..safe_end];
+            let preview_len = std::cmp::min(2000, content.len());
+            let safe_end = super::safe_truncate_index(&content, preview_len);
+            let preview = &content[..safe_end];
 
             Ok(format!(
                 "Response too large ({} bytes). Full content saved to: {}\n\nPreview (first {} chars):\n{}{}",
-                body.len(),
+                content.len(),
                 file_path.display(),
                 safe_end,
                 preview,
-                if display_content.len() > safe_end { "\n..." } else { "" }
+                if content.len() > safe_end { "\n..." } else { "" }
             ))
         } else {
-            Ok(display_content)
+            Ok(content)
         }
     }
 }
 
-/// Extract readable text from HTML (simple approach).
-fn extract_text_from_html(html: &str) -> String {
-    // Remove script and style tags
-    let mut text = html.to_string();
+/// GET a URL, going through the built-in proxy when it runs so the request is
+/// logged against the mission, filtered and cached.
+pub(super) async fn send_get(
+    client: &reqwest::Client,
+    url: &str,
+) -> anyhow::Result<reqwest::Response> {
+    let request = match web_proxy::proxy_url() {
+        Some(proxy) => {
+            let mut endpoint = reqwest::Url::parse(&format!("{}/fetch", proxy))?;
+            endpoint.query_pairs_mut().append_pair("url", url);
+            let mut request = client.get(endpoint);
+            if let Ok(mission_id) = std::env::var("SANDBOXED_SH_MISSION_ID") {
+                request = request.header(web_proxy::MISSION_HEADER, mission_id);
+            }
+            request
+        }
+        None => client.get(url),
+    };
+    Ok(request.send().await?)
+}
 
-    // Remove scripts
-    while let Some(start) = text.find("<script") {
-        if let Some(end) = text[start..].find("</script>") {
-            text = format!("{}{}", &text[..start], &text[start + end + 9..]);
-        } else {
-            break;
+fn respect_robots() -> bool {
+    match workspace_setting("SANDBOXED_SH_FETCH_RESPECT_ROBOTS") {
+        Some(value) => !matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "0" | "false" | "no" | "off"
+        ),
+        None => env_var_bool("SANDBOXED_SH_FETCH_RESPECT_ROBOTS", true),
+    }
+}
+
+fn max_bytes() -> usize {
+    workspace_setting("SANDBOXED_SH_FETCH_MAX_BYTES")
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|n: &usize| *n > 0)
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+/// Read the body up to `max_bytes`, reporting whether it was cut short.
+pub(super) async fn read_capped(
+    mut response: reqwest::Response,
+    max_bytes: usize,
+) -> anyhow::Result<(Vec<u8>, bool)> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let room = max_bytes - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            return Ok((body, true));
         }
+        body.extend_from_slice(&chunk);
     }
+    Ok((body, false))
+}
 
-    // Remove styles
-    while let Some(start) = text.find("<style") {
-        if let Some(end) = text[start..].find("</style>") {
-            text = format!("{}{}", &text[..start], &text[start + end + 8..]);
-        } else {
-            break;
+fn looks_like_html(body: &str) -> bool {
+    let head = body.trim_start();
+    let head = &head[..super::safe_truncate_index(head, 512)];
+    let head = head.to_ascii_lowercase();
+    head.starts_with("<!doctype html") || head.contains("<html")
+}
+
+/// Convert an HTML page to Markdown (or plain text), keeping only the main
+/// content when `readability` is set.
+fn html_to_output(html: &str, base: &reqwest::Url, plain: bool, readability: bool) -> String {
+    let mut root = html_markdown::parse(html);
+    let title = html_markdown::title(&root);
+    html_markdown::strip_boilerplate(&mut root);
+    let content = if readability {
+        html_markdown::readable_content(&root)
+    } else {
+        &root
+    };
+    let body = html_markdown::render(content, Some(base), plain);
+    match title {
+        Some(title) if !body.contains(&title) => {
+            if plain {
+                format!("{}\n\n{}", title, body)
+            } else {
+                format!("# {}\n\n{}", title, body)
+            }
+        }
+        _ => body,
+    }
+}
+
+async fn robots_allowed(client: &reqwest::Client, url: &reqwest::Url) -> bool {
+    let origin = url.origin().ascii_serialization();
+    let cached = ROBOTS_CACHE
+        .lock()
+        .unwrap()
+        .get(&origin)
+        .filter(|(fetched, _)| fetched.elapsed() < ROBOTS_CACHE_TTL)
+        .map(|(_, rules)| rules.clone());
+    let rules = match cached {
+        Some(rules) => rules,
+        None => {
+            let rules = fetch_robots(client, &origin).await;
+            ROBOTS_CACHE
+                .lock()
+                .unwrap()
+                .insert(origin, (Instant::now(), rules.clone()));
+            rules
         }
+    };
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    rules.allows(&path)
+}
+
+/// Fetch and parse robots.txt; a missing or unreadable file allows everything.
+async fn fetch_robots(client: &reqwest::Client, origin: &str) -> RobotsRules {
+    let url = format!("{}/robots.txt", origin);
+    let Ok(response) = send_get(client, &url).await else {
+        return RobotsRules::default();
+    };
+    if !response.status().is_success() {
+        return RobotsRules::default();
+    }
+    match read_capped(response, 512 * 1024).await {
+        Ok((bytes, _)) => RobotsRules::parse(&String::from_utf8_lossy(&bytes), ROBOTS_AGENT),
+        Err(_) => RobotsRules::default(),
     }
+}
+
+/// Allow/Disallow rules from the robots.txt group that applies to us.
+#[derive(Debug, Clone, Default)]
+struct RobotsRules {
+    /// `(allow, pattern)` pairs.
+    rules: Vec<(bool, String)>,
+}
 
-    // Remove all tags
-    let mut result = String::new();
-    let mut in_tag = false;
+impl RobotsRules {
+    /// Use the group naming `agent`, falling back to the `*` group.
+    fn parse(content: &str, agent: &str) -> Self {
+        let mut specific = Vec::new();
+        let mut wildcard = Vec::new();
+        let mut matched_specific = false;
+        let mut current_agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
 
-    for c in text.chars() {
-        if c == '<' {
-            in_tag = true;
-        } else if c == '>' {
-            in_tag = false;
-            result.push(' ');
-        } else if !in_tag {
-            result.push(c);
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        current_agents.clear();
+                        in_rules = false;
+                    }
+                    current_agents.push(value.to_ascii_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow allows everything; it adds no rule.
+                    if value.is_empty() {
+                        if current_agents.iter().any(|a| a == agent) {
+                            matched_specific = true;
+                        }
+                        continue;
+                    }
+                    let rule = (key == "allow", value.to_string());
+                    if current_agents.iter().any(|a| a != "*" && a == agent) {
+                        matched_specific = true;
+                        specific.push(rule.clone());
+                    }
+                    if current_agents.iter().any(|a| a == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self {
+            rules: if matched_specific { specific } else { wildcard },
         }
     }
 
-    // Clean up whitespace
-    let result: String = result.split_whitespace().collect::<Vec<_>>().join(" ");
+    /// The longest matching pattern wins; Allow wins ties.
+    fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_match(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .map(|(allow, _)| *allow)
+            .unwrap_or(true)
+    }
+}
 
-    html_decode(&result)
+/// Match a robots.txt path pattern supporting `*` and a trailing `$`.
+fn robots_match(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some(first) = parts.first() else {
+        return true;
+    };
+    if !path.starts_with(first) {
+        return false;
+    }
+    let mut pos = first.len();
+    for (idx, part) in parts.iter().enumerate().skip(1) {
+        let is_last = idx == parts.len() - 1;
+        if is_last && anchored {
+            return path.len() >= pos + part.len() && path.ends_with(part);
+        }
+        match path[pos..].find(part) {
+            Some(found) => pos += found + part.len(),
+            None => return false,
+        }
+    }
+    !anchored || pos == path.len()
 }
 
-/// Basic HTML entity decoding.
-fn html_decode(s: &str) -> String {
-    s.replace("&amp;", "&")
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&quot;", "\"")
-        .replace("&#39;", "'")
-        .replace("&nbsp;", " ")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn robots_prefers_specific_group_and_longest_match() {
+        let robots = "User-agent: *\nDisallow: /\n\nUser-agent: Sandboxed\nUser-agent: other\nDisallow: /private\nAllow: /private/public\n";
+        let rules = RobotsRules::parse(robots, ROBOTS_AGENT);
+        assert!(rules.allows("/docs"));
+        assert!(!rules.allows("/private/secret"));
+        assert!(rules.allows("/private/public/page"));
+
+        let rules = RobotsRules::parse("User-agent: *\nDisallow: /admin\n", ROBOTS_AGENT);
+        assert!(!rules.allows("/admin/x"));
+        assert!(rules.allows("/"));
+        assert!(RobotsRules::parse("User-agent: *\nDisallow:\n", ROBOTS_AGENT).allows("/a"));
+    }
+
+    #[test]
+    fn robots_patterns_support_wildcards() {
+        assert!(robots_match("/*.pdf$", "/files/a.pdf"));
+        assert!(!robots_match("/*.pdf$", "/files/a.pdf?x=1"));
+        assert!(robots_match("/search*q=", "/search?page=1&q=rust"));
+        assert!(robots_match("/exact$", "/exact"));
+        assert!(!robots_match("/exact$", "/exactly"));
+    }
+
+    #[test]
+    fn html_output_keeps_title_and_main_content() {
+        let base = reqwest::Url::parse("https://example.com/blog/post").unwrap();
+        let html = format!(
+            "<html><head><title>A Post</title><script>track()</script></head><body><nav><a href=\"/\">Home</a></nav><article><p>{}</p><p>More at <a href=\"next\">the next post</a>.</p></article></body></html>",
+            "Readable article text that should survive extraction. ".repeat(6)
+        );
+        let md = html_to_output(&html, &base, false, true);
+        assert!(md.starts_with("# A Post"));
+        assert!(md.contains("[the next post](https://example.com/blog/next)"));
+        assert!(!md.contains("track()"));
+        assert!(!md.contains("Home"));
+
+        let text = html_to_output(&html, &base, true, true);
+        assert!(text.starts_with("A Post\n\n"));
+        assert!(!text.contains("]("));
+    }
 }
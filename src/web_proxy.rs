@@ -0,0 +1,845 @@
+//! Built-in outbound HTTP proxy with per-mission access logs.
+//!
+//! When `SANDBOXED_SH_WEB_PROXY` is enabled the server listens on
+//! `SANDBOXED_SH_WEB_PROXY_ADDR` (default `127.0.0.1:8119`) and exports the
+//! proxy URL as [`PROXY_URL_ENV`]. Three kinds of traffic go through it:
+//!
+//! - `fetch_url` calls the proxy's `/fetch?url=...` endpoint, so the proxy
+//!   sees (and can cache) full HTTPS URLs.
+//! - Plain `http://` requests from workspace commands are forwarded and cached.
+//! - `CONNECT` tunnels (HTTPS from workspace commands) are logged by host and
+//!   size only, since the payload is encrypted.
+//!
+//! Requests are attributed to a mission through the proxy credentials
+//! (`http://<mission-id>@host:port`) or the [`MISSION_HEADER`] header, and
+//! every request is appended to `.sandboxed-sh/web-access/<mission-id>.jsonl`
+//! under the server working directory. `SANDBOXED_SH_WEB_ALLOW_DOMAINS` and
+//! `SANDBOXED_SH_WEB_DENY_DOMAINS` (comma-separated; an entry also matches its
+//! subdomains) restrict which hosts are reachable.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+use crate::util::env_var_bool;
+
+/// Environment variable carrying the proxy URL to child processes.
+pub const PROXY_URL_ENV: &str = "SANDBOXED_SH_WEB_PROXY_URL";
+/// Header attributing a direct `/fetch` request to a mission.
+pub const MISSION_HEADER: &str = "x-sandboxed-mission";
+/// Response header set when the domain lists refused a request.
+pub const BLOCKED_HEADER: &str = "x-sandboxed-blocked";
+/// Response header reporting whether the response came from the cache.
+pub const CACHE_HEADER: &str = "x-sandboxed-cache";
+/// Response header carrying the URL a `/fetch` request ended up at.
+pub const FINAL_URL_HEADER: &str = "x-sandboxed-final-url";
+/// Redirects followed when fetching a URL on behalf of `fetch_url`.
+pub const FETCH_MAX_REDIRECTS: usize = 5;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8119";
+const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+const MAX_CACHE_ENTRIES: usize = 256;
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+const LOG_DIR: &str = ".sandboxed-sh/web-access";
+
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authorization",
+    "proxy-authenticate",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+    MISSION_HEADER,
+];
+
+/// Whether the built-in proxy should be started.
+pub fn proxy_enabled() -> bool {
+    env_var_bool("SANDBOXED_SH_WEB_PROXY", false)
+}
+
+/// URL of the running proxy, if one was started for this process tree.
+pub fn proxy_url() -> Option<String> {
+    std::env::var(PROXY_URL_ENV)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+}
+
+/// `HTTP(S)_PROXY` environment attributing a command's traffic to a mission.
+pub fn proxy_env_for_mission(mission_id: Uuid) -> Vec<(String, String)> {
+    let Some(mut url) = proxy_url().and_then(|u| reqwest::Url::parse(&u).ok()) else {
+        return Vec::new();
+    };
+    if url.set_username(&mission_id.to_string()).is_err() {
+        return Vec::new();
+    }
+    let url = url.to_string().trim_end_matches('/').to_string();
+    let mut env = Vec::new();
+    for key in ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"] {
+        env.push((key.to_string(), url.clone()));
+    }
+    for key in ["NO_PROXY", "no_proxy"] {
+        env.push((key.to_string(), "localhost,127.0.0.1,::1".to_string()));
+    }
+    env
+}
+
+/// Host allow/deny lists. An entry matches the host itself and its subdomains.
+#[derive(Debug, Clone, Default)]
+pub struct DomainFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl DomainFilter {
+    pub fn new(allow: &str, deny: &str) -> Self {
+        let parse = |raw: &str| {
+            raw.split(',')
+                .map(|d| d.trim().trim_start_matches("*.").to_lowercase())
+                .filter(|d| !d.is_empty())
+                .collect()
+        };
+        Self {
+            allow: parse(allow),
+            deny: parse(deny),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            &std::env::var("SANDBOXED_SH_WEB_ALLOW_DOMAINS").unwrap_or_default(),
+            &std::env::var("SANDBOXED_SH_WEB_DENY_DOMAINS").unwrap_or_default(),
+        )
+    }
+
+    /// Refusal reason for `host`, if it isn't reachable.
+    pub fn check(&self, host: &str) -> Result<(), String> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let matches = |entry: &String| host == *entry || host.ends_with(&format!(".{}", entry));
+        if crate::offline::offline_mode() && !crate::offline::is_local_host(&host) {
+            return Err(format!("{} is not reachable in offline mode", host));
+        }
+        if self.deny.iter().any(matches) {
+            return Err(format!("{} is on the web deny list", host));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(matches) {
+            return Err(format!("{} is not on the web allow list", host));
+        }
+        Ok(())
+    }
+}
+
+/// One request observed by the proxy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebAccessRecord {
+    pub timestamp: String,
+    pub mission_id: Option<Uuid>,
+    pub method: String,
+    pub url: String,
+    pub status: Option<u16>,
+    /// Response body size (bytes received from the server for tunnels)
+    pub bytes: u64,
+    #[serde(default)]
+    pub cached: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Directory holding the per-mission access logs.
+pub fn log_dir(working_dir: &Path) -> PathBuf {
+    working_dir.join(LOG_DIR)
+}
+
+/// Read the access log recorded for a mission.
+pub fn read_mission_log(working_dir: &Path, mission_id: Uuid) -> Vec<WebAccessRecord> {
+    let path = log_dir(working_dir).join(format!("{}.jsonl", mission_id));
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[derive(Clone)]
+struct CachedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: bytes::Bytes,
+    stored: Instant,
+}
+
+/// Response returned to the client.
+struct ProxyResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: bytes::Bytes,
+}
+
+impl ProxyResponse {
+    fn text(status: u16, headers: Vec<(String, String)>, body: String) -> Self {
+        Self {
+            status,
+            headers,
+            body: bytes::Bytes::from(body),
+        }
+    }
+}
+
+/// Shared proxy state: logging, filtering and the response cache.
+pub struct ProxyState {
+    log_dir: PathBuf,
+    filter: DomainFilter,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<String, CachedResponse>>,
+    log_lock: Mutex<()>,
+    client: reqwest::Client,
+    fetch_client: reqwest::Client,
+}
+
+impl ProxyState {
+    pub fn new(
+        log_dir: PathBuf,
+        filter: DomainFilter,
+        cache_ttl: Duration,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_secs(120))
+            .build()?;
+        // `/fetch` follows redirects itself, but never onto a refused host.
+        let redirect_filter = filter.clone();
+        let fetch_client = reqwest::Client::builder()
+            .user_agent("Mozilla/5.0 (compatible; Sandboxed/1.0)")
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                let host = attempt.url().host_str().unwrap_or_default().to_string();
+                if attempt.previous().len() >= FETCH_MAX_REDIRECTS
+                    || redirect_filter.check(&host).is_err()
+                {
+                    attempt.stop()
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .timeout(Duration::from_secs(60))
+            .build()?;
+        Ok(Self {
+            log_dir,
+            filter,
+            cache_ttl,
+            cache: Mutex::new(HashMap::new()),
+            log_lock: Mutex::new(()),
+            client,
+            fetch_client,
+        })
+    }
+
+    fn record(&self, record: WebAccessRecord) {
+        let name = record
+            .mission_id
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| "unattributed".to_string());
+        let path = self.log_dir.join(format!("{}.jsonl", name));
+        let Ok(line) = serde_json::to_string(&record) else {
+            return;
+        };
+        let _guard = self.log_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let result = std::fs::create_dir_all(&self.log_dir).and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut f| writeln!(f, "{}", line))
+        });
+        if let Err(e) = result {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to write web access log");
+        }
+    }
+
+    fn cached(&self, url: &str) -> Option<CachedResponse> {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .get(url)
+            .filter(|entry| entry.stored.elapsed() < self.cache_ttl)
+            .cloned()
+    }
+
+    fn store(&self, url: &str, entry: CachedResponse) {
+        if self.cache_ttl.is_zero() {
+            return;
+        }
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, e| e.stored.elapsed() < self.cache_ttl);
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            if let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, e)| e.stored)
+                .map(|(k, _)| k.clone())
+            {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(url.to_string(), entry);
+    }
+
+    /// Forward a request (or serve it from the cache), recording the outcome.
+    async fn forward(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(String, String)],
+        body: Vec<u8>,
+        mission_id: Option<Uuid>,
+        follow_redirects: bool,
+    ) -> ProxyResponse {
+        let mut record = WebAccessRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            mission_id,
+            method: method.to_string(),
+            url: url.to_string(),
+            status: None,
+            bytes: 0,
+            cached: false,
+            blocked: None,
+            error: None,
+        };
+
+        let parsed = match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+            _ => {
+                record.error = Some("invalid URL".to_string());
+                self.record(record);
+                return ProxyResponse::text(400, Vec::new(), format!("Invalid URL: {}", url));
+            }
+        };
+        if let Err(reason) = self.filter.check(parsed.host_str().unwrap_or_default()) {
+            record.status = Some(403);
+            record.blocked = Some(reason.clone());
+            self.record(record);
+            return ProxyResponse::text(
+                403,
+                vec![(BLOCKED_HEADER.to_string(), reason.clone())],
+                format!("Blocked by web proxy: {}", reason),
+            );
+        }
+
+        let cacheable = method == "GET";
+        // Redirects are resolved for `/fetch` but passed through otherwise.
+        let cache_key = if follow_redirects {
+            format!("fetch {}", url)
+        } else {
+            url.to_string()
+        };
+        if cacheable {
+            if let Some(hit) = self.cached(&cache_key) {
+                record.status = Some(hit.status);
+                record.bytes = hit.body.len() as u64;
+                record.cached = true;
+                self.record(record);
+                let mut headers = hit.headers;
+                headers.push((CACHE_HEADER.to_string(), "hit".to_string()));
+                return ProxyResponse {
+                    status: hit.status,
+                    headers,
+                    body: hit.body,
+                };
+            }
+        }
+
+        let Ok(reqwest_method) = reqwest::Method::from_bytes(method.as_bytes()) else {
+            record.error = Some("unsupported method".to_string());
+            self.record(record);
+            return ProxyResponse::text(405, Vec::new(), format!("Unsupported method {}", method));
+        };
+        let client = if follow_redirects {
+            &self.fetch_client
+        } else {
+            &self.client
+        };
+        let mut request = client.request(reqwest_method, parsed);
+        for (name, value) in headers {
+            if !is_hop_by_hop(name) {
+                request = request.header(name.as_str(), value.as_str());
+            }
+        }
+        if !body.is_empty() {
+            request = request.body(body);
+        }
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                record.error = Some(e.to_string());
+                self.record(record);
+                return ProxyResponse::text(502, Vec::new(), format!("Upstream error: {}", e));
+            }
+        };
+        let status = response.status().as_u16();
+        let mut response_headers: Vec<(String, String)> = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| !is_hop_by_hop(name.as_str()))
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|v| (name.as_str().to_string(), v.to_string()))
+            })
+            .collect();
+        if follow_redirects {
+            response_headers.push((FINAL_URL_HEADER.to_string(), response.url().to_string()));
+        }
+        let no_store = response_headers.iter().any(|(name, value)| {
+            name == "cache-control" && (value.contains("no-store") || value.contains("private"))
+        });
+        let body = match read_limited(response).await {
+            Ok(body) => body,
+            Err(e) => {
+                record.status = Some(status);
+                record.error = Some(e.to_string());
+                self.record(record);
+                return ProxyResponse::text(502, Vec::new(), format!("Upstream error: {}", e));
+            }
+        };
+        record.status = Some(status);
+        record.bytes = body.len() as u64;
+        self.record(record);
+
+        if cacheable && status == 200 && !no_store {
+            self.store(
+                &cache_key,
+                CachedResponse {
+                    status,
+                    headers: response_headers.clone(),
+                    body: body.clone(),
+                    stored: Instant::now(),
+                },
+            );
+        }
+        response_headers.push((CACHE_HEADER.to_string(), "miss".to_string()));
+        ProxyResponse {
+            status,
+            headers: response_headers,
+            body,
+        }
+    }
+}
+
+fn is_hop_by_hop(name: &str) -> bool {
+    HOP_BY_HOP.iter().any(|h| name.eq_ignore_ascii_case(h))
+}
+
+async fn read_limited(response: reqwest::Response) -> anyhow::Result<bytes::Bytes> {
+    let mut response = response;
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() > MAX_BODY_BYTES {
+            anyhow::bail!("response exceeds {} bytes", MAX_BODY_BYTES);
+        }
+    }
+    Ok(bytes::Bytes::from(body))
+}
+
+/// Parsed request line and headers.
+#[derive(Debug)]
+struct RequestHead {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+}
+
+impl RequestHead {
+    fn parse(raw: &str) -> Option<Self> {
+        let mut lines = raw.split("\r\n");
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?.to_string();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+        Some(Self {
+            method,
+            target,
+            headers,
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Mission from the proxy credentials' username or the mission header.
+    fn mission_id(&self) -> Option<Uuid> {
+        if let Some(id) = self.header(MISSION_HEADER) {
+            return Uuid::parse_str(id.trim()).ok();
+        }
+        let encoded = self.header("proxy-authorization")?.strip_prefix("Basic ")?;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let user = decoded.split(':').next()?;
+        Uuid::parse_str(user).ok()
+    }
+
+    /// Target URL of a `/fetch?url=...` request.
+    fn fetch_url(&self) -> Option<String> {
+        if !self.target.starts_with("/fetch?") {
+            return None;
+        }
+        let url = reqwest::Url::parse(&format!("http://proxy{}", self.target)).ok()?;
+        let target = url
+            .query_pairs()
+            .find(|(k, _)| k == "url")
+            .map(|(_, v)| v.to_string());
+        target
+    }
+}
+
+/// Start the proxy when enabled, exporting its URL to child processes.
+pub async fn start(working_dir: &Path) -> anyhow::Result<Option<String>> {
+    if !proxy_enabled() {
+        return Ok(None);
+    }
+    let addr = std::env::var("SANDBOXED_SH_WEB_PROXY_ADDR")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let ttl = std::env::var("SANDBOXED_SH_WEB_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CACHE_TTL_SECS);
+    let listener = TcpListener::bind(&addr).await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let state = Arc::new(ProxyState::new(
+        log_dir(working_dir),
+        DomainFilter::from_env(),
+        Duration::from_secs(ttl),
+    )?);
+    std::env::set_var(PROXY_URL_ENV, &url);
+    tokio::spawn(serve(listener, state));
+    tracing::info!(url = %url, cache_ttl_secs = ttl, "Outbound web proxy listening");
+    Ok(Some(url))
+}
+
+/// Accept proxy connections until the listener fails.
+pub async fn serve(listener: TcpListener, state: Arc<ProxyState>) {
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!(error = %e, "Web proxy accept failed");
+                continue;
+            }
+        };
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(&state, stream).await {
+                tracing::debug!(error = %e, "Web proxy connection failed");
+            }
+        });
+    }
+}
+
+async fn handle_connection(state: &ProxyState, mut stream: TcpStream) -> anyhow::Result<()> {
+    let (head, mut body) = read_head(&mut stream).await?;
+    let Some(head) = RequestHead::parse(&head) else {
+        return write_response(
+            &mut stream,
+            ProxyResponse::text(400, Vec::new(), String::new()),
+        )
+        .await;
+    };
+    let mission_id = head.mission_id();
+
+    if head.method.eq_ignore_ascii_case("CONNECT") {
+        return tunnel(state, stream, &head.target, mission_id).await;
+    }
+
+    let (url, follow_redirects) = if head.target.starts_with("http://") {
+        (head.target.clone(), false)
+    } else if let Some(url) = head.fetch_url() {
+        (url, true)
+    } else {
+        let response = ProxyResponse::text(
+            400,
+            Vec::new(),
+            "Expected an absolute http:// URL, CONNECT, or /fetch?url=".to_string(),
+        );
+        return write_response(&mut stream, response).await;
+    };
+
+    if head.header("transfer-encoding").is_some() {
+        let response = ProxyResponse::text(411, Vec::new(), "Length required".to_string());
+        return write_response(&mut stream, response).await;
+    }
+    let length: usize = head
+        .header("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        let response = ProxyResponse::text(413, Vec::new(), "Request body too large".to_string());
+        return write_response(&mut stream, response).await;
+    }
+    while body.len() < length {
+        let mut buf = vec![0u8; (length - body.len()).min(64 * 1024)];
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+    body.truncate(length);
+
+    let response = state
+        .forward(
+            &head.method,
+            &url,
+            &head.headers,
+            body,
+            mission_id,
+            follow_redirects,
+        )
+        .await;
+    write_response(&mut stream, response).await
+}
+
+async fn tunnel(
+    state: &ProxyState,
+    mut client: TcpStream,
+    authority: &str,
+    mission_id: Option<Uuid>,
+) -> anyhow::Result<()> {
+    let mut record = WebAccessRecord {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        mission_id,
+        method: "CONNECT".to_string(),
+        url: authority.to_string(),
+        status: None,
+        bytes: 0,
+        cached: false,
+        blocked: None,
+        error: None,
+    };
+    let host = authority
+        .rsplit_once(':')
+        .map(|(host, _)| host)
+        .unwrap_or(authority)
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    if let Err(reason) = state.filter.check(host) {
+        record.status = Some(403);
+        record.blocked = Some(reason.clone());
+        state.record(record);
+        let response = ProxyResponse::text(
+            403,
+            vec![(BLOCKED_HEADER.to_string(), reason.clone())],
+            format!("Blocked by web proxy: {}", reason),
+        );
+        return write_response(&mut client, response).await;
+    }
+    let mut upstream = match TcpStream::connect(authority).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            record.status = Some(502);
+            record.error = Some(e.to_string());
+            state.record(record);
+            let response = ProxyResponse::text(502, Vec::new(), format!("Upstream error: {}", e));
+            return write_response(&mut client, response).await;
+        }
+    };
+    client
+        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+        .await?;
+    let result = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+    record.status = Some(200);
+    match result {
+        Ok((_, received)) => record.bytes = received,
+        Err(e) => record.error = Some(e.to_string()),
+    }
+    state.record(record);
+    Ok(())
+}
+
+/// Read up to the end of the request head, returning it and any body bytes
+/// that arrived with it.
+async fn read_head(stream: &mut TcpStream) -> anyhow::Result<(String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            anyhow::bail!("connection closed before request head");
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..end]).to_string();
+            return Ok((head, buf[end + 4..].to_vec()));
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            anyhow::bail!("request head too large");
+        }
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, response: ProxyResponse) -> anyhow::Result<()> {
+    let reason = reqwest::StatusCode::from_u16(response.status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("");
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_filter_matches_subdomains() {
+        let filter = DomainFilter::new("github.com, *.rust-lang.org", "gist.github.com");
+        assert!(filter.check("github.com").is_ok());
+        assert!(filter.check("api.github.com").is_ok());
+        assert!(filter.check("doc.rust-lang.org").is_ok());
+        assert!(filter.check("gist.github.com").is_err());
+        assert!(filter.check("notgithub.com").is_err());
+        assert!(DomainFilter::default().check("example.com").is_ok());
+    }
+
+    #[test]
+    fn request_head_attributes_missions() {
+        let mission = Uuid::new_v4();
+        let creds = base64::engine::general_purpose::STANDARD.encode(format!("{}:", mission));
+        let raw = format!(
+            "GET http://example.com/ HTTP/1.1\r\nHost: example.com\r\nProxy-Authorization: Basic {}",
+            creds
+        );
+        let head = RequestHead::parse(&raw).unwrap();
+        assert_eq!(head.mission_id(), Some(mission));
+
+        let raw = format!(
+            "GET /fetch?url=https%3A%2F%2Fexample.com%2Fa%3Fb%3D1 HTTP/1.1\r\nX-Sandboxed-Mission: {}",
+            mission
+        );
+        let head = RequestHead::parse(&raw).unwrap();
+        assert_eq!(head.mission_id(), Some(mission));
+        assert_eq!(
+            head.fetch_url().as_deref(),
+            Some("https://example.com/a?b=1")
+        );
+    }
+
+    #[tokio::test]
+    async fn blocked_requests_are_logged_and_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = ProxyState::new(
+            log_dir(dir.path()),
+            DomainFilter::new("", "example.com"),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let mission = Uuid::new_v4();
+        let response = state
+            .forward(
+                "GET",
+                "https://example.com/x",
+                &[],
+                Vec::new(),
+                Some(mission),
+                true,
+            )
+            .await;
+        assert_eq!(response.status, 403);
+
+        let records = read_mission_log(dir.path(), mission);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].status, Some(403));
+        assert!(records[0].blocked.as_ref().unwrap().contains("deny list"));
+    }
+
+    #[tokio::test]
+    async fn forwards_and_caches_get_requests() {
+        // Minimal origin that counts how often it is hit.
+        let origin = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let origin_addr = origin.local_addr().unwrap();
+        let hits = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let origin_hits = Arc::clone(&hits);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = origin.accept().await.unwrap();
+                origin_hits.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let _ = read_head(&mut stream).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello",
+                    )
+                    .await;
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = Arc::new(
+            ProxyState::new(
+                log_dir(dir.path()),
+                DomainFilter::default(),
+                Duration::from_secs(60),
+            )
+            .unwrap(),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, state));
+
+        let mission = Uuid::new_v4();
+        let client = reqwest::Client::builder()
+            .proxy(
+                reqwest::Proxy::http(format!("http://{}", proxy_addr))
+                    .unwrap()
+                    .basic_auth(&mission.to_string(), ""),
+            )
+            .build()
+            .unwrap();
+        let url = format!("http://{}/page", origin_addr);
+        for expected in ["miss", "hit"] {
+            let response = client.get(&url).send().await.unwrap();
+            assert_eq!(response.headers()[CACHE_HEADER], expected);
+            assert_eq!(response.text().await.unwrap(), "hello");
+        }
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let records = read_mission_log(dir.path(), mission);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].bytes, 5);
+        assert!(!records[0].cached && records[1].cached);
+    }
+}
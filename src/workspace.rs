@@ -22,11 +22,17 @@ use tracing::warn;
 use uuid::Uuid;
 
 use crate::ai_providers::{AIProvider, ProviderType};
+use crate::bwrap::{self, ContainerBackend};
 use crate::config::Config;
+use crate::egress::EgressPolicy;
+use crate::gpu::{GpuPassthrough, GpuRequest};
+use crate::init_report::{self, InitScriptReport};
 use crate::library::env_crypto::strip_encrypted_tags;
-use crate::library::LibraryStore;
+use crate::library::{ClaudeCodePermissions, LibraryStore};
 use crate::mcp::{McpRegistry, McpScope, McpServerConfig, McpTransport};
+use crate::microvm::{self, MicroVmSpec};
 use crate::nspawn::{self, NspawnDistro};
+use crate::template_drift::TemplateFingerprint;
 use crate::tools::terminal::{rtk_binary_path, rtk_enabled};
 use crate::util::{env_var_bool, home_dir, strip_jsonc_comments, AI_PROVIDERS_PATH};
 
@@ -66,14 +72,89 @@ pub fn is_container_fallback(workspace: &Workspace) -> bool {
         .unwrap_or(false)
 }
 
-pub fn use_nspawn_for_workspace(workspace: &Workspace) -> bool {
+/// Per-workspace quiet hours, stored under `config.quiet_hours`.
+pub fn workspace_quiet_hours(workspace: &Workspace) -> Vec<crate::schedule_windows::QuietHours> {
+    workspace
+        .config
+        .get("quiet_hours")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Default chat options for missions in this workspace, stored under
+/// `config.chat_options`.
+pub fn workspace_chat_options(workspace: &Workspace) -> crate::chat_options::ChatOptions {
+    workspace
+        .config
+        .get("chat_options")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// microVM settings for a container workspace, stored under `config.microvm`.
+pub fn workspace_microvm(workspace: &Workspace) -> Option<MicroVmSpec> {
+    workspace
+        .config
+        .get("microvm")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+}
+
+/// GPUs requested for a container workspace, stored under `config.gpu`.
+pub fn workspace_gpu(workspace: &Workspace) -> Option<GpuRequest> {
     if workspace.workspace_type != WorkspaceType::Container {
-        return false;
+        return None;
+    }
+    workspace
+        .config
+        .get("gpu")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+}
+
+/// Resolved GPU passthrough for a container workspace. A request the host
+/// can no longer satisfy is logged and the workspace runs without GPUs.
+pub fn workspace_gpu_passthrough(workspace: &Workspace) -> Option<GpuPassthrough> {
+    let request = workspace_gpu(workspace)?;
+    match GpuPassthrough::resolve(&request) {
+        Ok(passthrough) => Some(passthrough),
+        Err(e) => {
+            tracing::warn!(workspace = %workspace.name, error = %e, "GPU passthrough unavailable");
+            None
+        }
+    }
+}
+
+/// Backend for a container workspace: a microVM when its template asks for
+/// one and a hypervisor is installed, otherwise the host's container backend.
+fn isolation_backend(workspace: &Workspace) -> Option<ContainerBackend> {
+    match workspace_microvm(workspace) {
+        Some(spec) => spec
+            .resolve_hypervisor()
+            .is_ok()
+            .then_some(ContainerBackend::MicroVm),
+        None => bwrap::container_backend(),
+    }
+}
+
+/// Isolation backend a container workspace runs under, or `None` for host
+/// workspaces and container workspaces in host fallback mode.
+pub fn workspace_container_backend(workspace: &Workspace) -> Option<ContainerBackend> {
+    if workspace.workspace_type != WorkspaceType::Container {
+        return None;
     }
     if is_container_fallback(workspace) {
-        return false;
+        return None;
     }
-    nspawn::nspawn_available()
+    isolation_backend(workspace)
+}
+
+/// Whether commands run inside the workspace's own root filesystem
+/// (systemd-nspawn or bubblewrap), so container paths apply.
+pub fn use_container_rootfs(workspace: &Workspace) -> bool {
+    workspace_container_backend(workspace).is_some()
 }
 
 /// Status of a workspace.
@@ -129,6 +210,12 @@ pub struct Workspace {
     pub status: WorkspaceStatus,
     /// Error message if status is Error
     pub error_message: Option<String>,
+    /// Per-fragment outcome of the last init script run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_report: Option<InitScriptReport>,
+    /// The template as it was when the workspace was last built from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub built_from: Option<TemplateFingerprint>,
     /// Additional configuration
     #[serde(default)]
     pub config: serde_json::Value,
@@ -165,6 +252,10 @@ pub struct Workspace {
     /// - `tailnet_only`: Connect to tailnet but use host gateway for internet
     #[serde(default)]
     pub tailscale_mode: Option<TailscaleMode>,
+    /// Outbound network policy for container workspaces.
+    /// Enforced policies move commands onto a private network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_policy: Option<EgressPolicy>,
     /// MCP server names to enable for this workspace.
     /// Empty = use all MCPs with `default_enabled = true`.
     /// Non-empty = allowlist of MCP names.
@@ -186,6 +277,8 @@ impl Workspace {
             path: working_dir,
             status: WorkspaceStatus::Ready,
             error_message: None,
+            init_report: None,
+            built_from: None,
             config: serde_json::json!({}),
             template: None,
             distro: None,
@@ -197,6 +290,7 @@ impl Workspace {
             plugins: Vec::new(),
             shared_network: None,
             tailscale_mode: None,
+            egress_policy: None,
             mcps: Vec::new(),
             config_profile: None,
         }
@@ -211,6 +305,8 @@ impl Workspace {
             path,
             status: WorkspaceStatus::Pending,
             error_message: None,
+            init_report: None,
+            built_from: None,
             config: serde_json::json!({}),
             template: None,
             distro: None,
@@ -223,6 +319,7 @@ impl Workspace {
             plugins: Vec::new(),
             shared_network: None,
             tailscale_mode: None,
+            egress_policy: None,
             mcps: Vec::new(),
         }
     }
@@ -402,6 +499,8 @@ impl WorkspaceStore {
                     path,
                     status,
                     error_message: None,
+                    init_report: None,
+                    built_from: None,
                     config: serde_json::json!({}),
                     template: None,
                     distro: None,
@@ -413,6 +512,7 @@ impl WorkspaceStore {
                     plugins: Vec::new(),
                     shared_network: None, // Default to shared network
                     tailscale_mode: None,
+                    egress_policy: None,
                     mcps: Vec::new(),
                     config_profile: None,
                 };
@@ -657,7 +757,12 @@ fn opencode_entry_from_mcp(
     }
 
     match &config.transport {
-        McpTransport::Http { endpoint, headers } => {
+        McpTransport::Http {
+            endpoint,
+            headers,
+            auth,
+        } => {
+            let headers = crate::mcp::auth::config_headers(headers, auth.as_ref(), workspace_env);
             let mut entry = serde_json::Map::new();
             entry.insert("type".to_string(), json!("http"));
             entry.insert("endpoint".to_string(), json!(endpoint));
@@ -696,6 +801,15 @@ fn opencode_entry_from_mcp(
             merged_env
                 .entry("WORKING_DIR".to_string())
                 .or_insert_with(|| workspace_dir.to_string_lossy().to_string());
+            // The web proxy listens on loopback, so only hand it to MCPs that share
+            // the host network.
+            if workspace_type == WorkspaceType::Host || shared_network.unwrap_or(true) {
+                if let Some(url) = crate::web_proxy::proxy_url() {
+                    merged_env
+                        .entry(crate::web_proxy::PROXY_URL_ENV.to_string())
+                        .or_insert(url);
+                }
+            }
             if workspace_type == WorkspaceType::Container {
                 if let Some(name) = workspace_root.file_name().and_then(|n| n.to_str()) {
                     if !name.trim().is_empty() {
@@ -723,7 +837,8 @@ fn opencode_entry_from_mcp(
                     )
                 })
                 .unwrap_or(false)
-                || (workspace_type == WorkspaceType::Container && !nspawn::nspawn_available());
+                || (workspace_type == WorkspaceType::Container
+                    && bwrap::container_backend().is_none());
             let per_workspace_runner = env_var_bool("SANDBOXED_SH_PER_WORKSPACE_RUNNER", true);
             if container_fallback {
                 merged_env
@@ -873,7 +988,12 @@ fn claude_entry_from_mcp(
     shared_network: Option<bool>,
 ) -> serde_json::Value {
     match &config.transport {
-        McpTransport::Http { endpoint, headers } => {
+        McpTransport::Http {
+            endpoint,
+            headers,
+            auth,
+        } => {
+            let headers = crate::mcp::auth::config_headers(headers, auth.as_ref(), workspace_env);
             let mut entry = serde_json::Map::new();
             entry.insert("url".to_string(), json!(endpoint));
             if !headers.is_empty() {
@@ -1254,7 +1374,8 @@ async fn write_rtk_hook_if_enabled(
     }
 
     // For container workspaces, copy the RTK binary from host into the container
-    let is_container = workspace_type == WorkspaceType::Container && nspawn::nspawn_available();
+    let is_container =
+        workspace_type == WorkspaceType::Container && bwrap::container_backend().is_some();
     if is_container {
         if let Some(host_rtk) = rtk_binary_path() {
             let dest_dir = workspace_root.join("usr").join("local").join("bin");
@@ -1805,13 +1926,17 @@ fn codex_entry_from_mcp(
         sanitized
     };
     match &config.transport {
-        McpTransport::Http { endpoint, headers } => Some(CodexMcpEntry {
+        McpTransport::Http {
+            endpoint,
+            headers,
+            auth,
+        } => Some(CodexMcpEntry {
             name,
             command: None,
             args: Vec::new(),
             env: HashMap::new(),
             url: Some(endpoint.clone()),
-            headers: headers.clone(),
+            headers: crate::mcp::auth::config_headers(headers, auth.as_ref(), workspace_env),
         }),
         McpTransport::Stdio { .. } => {
             let opencode_entry = opencode_entry_from_mcp(
@@ -1996,15 +2121,20 @@ fn amp_entry_from_mcp(
     _workspace_dir: &Path,
     _workspace_root: &Path,
     _workspace_type: WorkspaceType,
-    _workspace_env: &HashMap<String, String>,
+    workspace_env: &HashMap<String, String>,
 ) -> serde_json::Value {
     use crate::mcp::McpTransport;
 
     let mut entry = serde_json::Map::new();
 
     match &config.transport {
-        McpTransport::Http { endpoint, headers } => {
+        McpTransport::Http {
+            endpoint,
+            headers,
+            auth,
+        } => {
             // HTTP/SSE-based MCP server
+            let headers = crate::mcp::auth::config_headers(headers, auth.as_ref(), workspace_env);
             entry.insert("url".to_string(), json!(endpoint));
             if !headers.is_empty() {
                 entry.insert("headers".to_string(), json!(headers));
@@ -2120,6 +2250,28 @@ fn ensure_amp_skill_frontmatter(
     )
 }
 
+/// Profile permissions for Claude Code missions, relative to the mission directory.
+pub const CLAUDECODE_PERMISSIONS_PATH: &str = ".claude/profile-permissions.json";
+
+/// Write (or clear) the profile's Claude Code permissions in a mission directory.
+async fn write_claudecode_permissions(
+    workspace_dir: &Path,
+    permissions: Option<&ClaudeCodePermissions>,
+) -> anyhow::Result<()> {
+    let path = workspace_dir.join(CLAUDECODE_PERMISSIONS_PATH);
+    match permissions {
+        Some(permissions) => {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&path, serde_json::to_string_pretty(permissions)?).await?;
+        }
+        None if path.exists() => tokio::fs::remove_file(&path).await?,
+        None => {}
+    }
+    Ok(())
+}
+
 /// Write backend-specific configuration to the workspace.
 /// This is the main entry point for config generation.
 #[allow(clippy::too_many_arguments)]
@@ -3003,6 +3155,105 @@ pub async fn sync_agents_to_dir(
     Ok(())
 }
 
+/// Sync library tools (`tool/*.ts` scripts and `tool/*.wasm` plugins) into a
+/// directory's `.sandboxed-sh/tools/` folder, where workspace-mcp loads them.
+pub async fn sync_library_tools_to_dir(
+    target_dir: &Path,
+    context_name: &str,
+    library: &LibraryStore,
+) -> anyhow::Result<()> {
+    let tools = library.list_library_tools().await?;
+    let plugins = library.list_wasm_tool_plugins().await?;
+    let tools_dir = target_dir.join(crate::tools::library_tool::LIBRARY_TOOLS_DIR);
+
+    // Drop tools that were removed from the library since the last sync.
+    if tools_dir.exists() {
+        let mut entries = tokio::fs::read_dir(&tools_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("ts" | "js" | "wasm" | "json")
+            ) {
+                tokio::fs::remove_file(&path).await?;
+            }
+        }
+    }
+    if tools.is_empty() && plugins.is_empty() {
+        return Ok(());
+    }
+
+    tokio::fs::create_dir_all(&tools_dir).await?;
+    for tool in &tools {
+        let file_name = Path::new(&tool.path)
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_else(|| format!("{}.ts", tool.name).into());
+        tokio::fs::write(tools_dir.join(file_name), &tool.content).await?;
+    }
+    for plugin in &plugins {
+        tokio::fs::copy(
+            library.path().join(&plugin.path),
+            tools_dir.join(format!("{}.wasm", plugin.name)),
+        )
+        .await?;
+        if let Some(manifest) = &plugin.manifest {
+            tokio::fs::write(tools_dir.join(format!("{}.json", plugin.name)), manifest).await?;
+        }
+    }
+
+    tracing::info!(
+        context = %context_name,
+        tools = ?tools.iter().map(|t| &t.name).collect::<Vec<_>>(),
+        wasm_plugins = ?plugins.iter().map(|p| &p.name).collect::<Vec<_>>(),
+        target = %tools_dir.display(),
+        "Synced library tools to directory"
+    );
+
+    Ok(())
+}
+
+/// Sync library hooks (`hook/`) into a directory's `.sandboxed-sh/hooks/`
+/// folder, where mission post-processors are read from. Lifecycle hooks run
+/// from the library copy instead (see `crate::hooks`).
+pub async fn sync_library_hooks_to_dir(
+    target_dir: &Path,
+    context_name: &str,
+    library: &LibraryStore,
+) -> anyhow::Result<()> {
+    let files = library.list_hook_files().await?;
+    let hooks_dir = target_dir.join(crate::hooks::HOOKS_DIR);
+
+    // Hooks removed from the library must stop firing.
+    if hooks_dir.exists() {
+        tokio::fs::remove_dir_all(&hooks_dir).await?;
+    }
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    tokio::fs::create_dir_all(&hooks_dir).await?;
+    for file in &files {
+        let dest = hooks_dir.join(&file.name);
+        tokio::fs::write(&dest, &file.content).await?;
+        #[cfg(unix)]
+        if file.name != crate::hooks::HOOKS_CONFIG_FILE {
+            use std::os::unix::fs::PermissionsExt;
+            let perms = std::fs::Permissions::from_mode(0o755);
+            tokio::fs::set_permissions(&dest, perms).await?;
+        }
+    }
+
+    tracing::info!(
+        context = %context_name,
+        files = ?files.iter().map(|f| &f.name).collect::<Vec<_>>(),
+        target = %hooks_dir.display(),
+        "Synced library hooks to directory"
+    );
+
+    Ok(())
+}
+
 async fn prepare_workspace_dir(path: &Path) -> anyhow::Result<PathBuf> {
     tokio::fs::create_dir_all(path.join("output")).await?;
     tokio::fs::create_dir_all(path.join("temp")).await?;
@@ -3270,6 +3521,33 @@ pub async fn prepare_mission_workspace_with_skills_backend(
     )
     .await?;
 
+    // Sync the profile's Claude Code permissions; the runner maps them to CLI flags.
+    if backend_id == "claudecode" {
+        if let Some(lib) = library {
+            let profile = config_profile.unwrap_or("default");
+            let permissions = match lib.get_claudecode_config_for_profile(profile).await {
+                Ok(config) => config.permissions,
+                Err(e) => {
+                    tracing::warn!(
+                        mission = %mission_id,
+                        profile = %profile,
+                        error = %e,
+                        "Failed to load Claude Code permissions from profile"
+                    );
+                    None
+                }
+            };
+            if let Err(e) = write_claudecode_permissions(&dir, permissions.as_ref()).await {
+                tracing::warn!(
+                    mission = %mission_id,
+                    workspace = %workspace.name,
+                    error = %e,
+                    "Failed to write Claude Code permissions"
+                );
+            }
+        }
+    }
+
     // Sync oh-my-opencode settings into the mission directory when using OpenCode.
     if backend_id == "opencode" {
         if let Some(lib) = library {
@@ -3402,6 +3680,45 @@ pub async fn prepare_mission_workspace_with_skills_backend(
                 );
             }
         }
+
+        // Sync library tools (run by workspace-mcp's sandboxed script runtime)
+        if let Err(e) = sync_library_tools_to_dir(&dir, &context, lib).await {
+            tracing::warn!(
+                mission = %mission_id,
+                workspace = %workspace.name,
+                error = %e,
+                "Failed to sync library tools to mission directory"
+            );
+        }
+
+        // Sync lifecycle hooks (fired by the mission runner and workspace-mcp)
+        if let Err(e) = sync_library_hooks_to_dir(&dir, &context, lib).await {
+            tracing::warn!(
+                mission = %mission_id,
+                workspace = %workspace.name,
+                error = %e,
+                "Failed to sync library hooks to mission directory"
+            );
+        }
+    }
+
+    // Record the baseline snapshot used by the mission diff endpoint (first turn only).
+    if let Err(e) = crate::workspace_snapshot::record_baseline(&dir).await {
+        tracing::warn!(
+            mission = %mission_id,
+            workspace = %workspace.name,
+            error = %e,
+            "Failed to record mission baseline snapshot"
+        );
+    }
+    // Later turns checkpoint the workspace for reads at a previous state.
+    if let Err(e) = crate::workspace_snapshot::record_checkpoint(&dir).await {
+        tracing::warn!(
+            mission = %mission_id,
+            workspace = %workspace.name,
+            error = %e,
+            "Failed to record mission checkpoint"
+        );
     }
 
     Ok(dir)
@@ -3766,7 +4083,7 @@ async fn build_container_fallback(workspace: &mut Workspace, reason: &str) -> an
     tracing::warn!(
         workspace = %workspace.name,
         reason = %reason,
-        "Container fallback enabled; workspace will run on host without systemd-nspawn"
+        "Container fallback enabled; workspace will run on host without isolation"
     );
 
     tokio::fs::create_dir_all(&workspace.path).await?;
@@ -3792,14 +4109,38 @@ pub async fn build_container_workspace(
         return Err(anyhow::anyhow!("Workspace is not a container type"));
     }
 
-    if !nspawn::nspawn_available() {
+    let microvm = workspace_microvm(workspace);
+    if let Some(request) = workspace_gpu(workspace) {
+        // Fail the build up front instead of producing a workspace whose ML
+        // smoke tests silently run on CPU.
+        let resolved = if microvm.is_some() {
+            Err("GPU passthrough is not supported for microVM workspaces".to_string())
+        } else {
+            GpuPassthrough::resolve(&request).map(|_| ())
+        };
+        if let Err(e) = resolved {
+            workspace.status = WorkspaceStatus::Error;
+            workspace.error_message = Some(format!("GPU passthrough: {}", e));
+            return Err(anyhow::anyhow!("GPU passthrough: {}", e));
+        }
+    }
+    let Some(backend) = isolation_backend(workspace) else {
+        // A microVM template asks for stronger isolation than the host can
+        // give; never downgrade it to a container or the host.
+        if let Some(spec) = microvm.as_ref() {
+            let err = spec.resolve_hypervisor().unwrap_err();
+            workspace.status = WorkspaceStatus::Error;
+            workspace.error_message = Some(err.to_string());
+            return Err(err);
+        }
         if nspawn::allow_container_fallback() {
-            return build_container_fallback(workspace, "systemd-nspawn not available").await;
+            return build_container_fallback(workspace, "no container isolation backend available")
+                .await;
         }
         return Err(anyhow::anyhow!(
-            "systemd-nspawn not available; install systemd-container or set SANDBOXED_SH_ALLOW_CONTAINER_FALLBACK=1"
+            "Neither systemd-nspawn nor bubblewrap is available; install systemd-container or bubblewrap, or set SANDBOXED_SH_ALLOW_CONTAINER_FALLBACK=1"
         ));
-    }
+    };
 
     // Update status to building
     workspace.status = WorkspaceStatus::Building;
@@ -3826,6 +4167,15 @@ pub async fn build_container_workspace(
                             Some(format!("Failed to sync MCP binaries: {}", e));
                         return Err(e);
                     }
+                    if let Some(spec) = microvm.as_ref() {
+                        let network = workspace.shared_network.unwrap_or(true);
+                        if let Err(e) = microvm::prepare(&workspace.path, spec, network).await {
+                            workspace.status = WorkspaceStatus::Error;
+                            workspace.error_message =
+                                Some(format!("Failed to prepare microVM: {}", e));
+                            return Err(e);
+                        }
+                    }
                     workspace.status = WorkspaceStatus::Ready;
                     workspace.error_message = None;
                     return Ok(());
@@ -3854,9 +4204,10 @@ pub async fn build_container_workspace(
     }
 
     tracing::info!(
-        "Building container workspace at {} with distro {}",
+        "Building container workspace at {} with distro {} ({})",
         workspace.path.display(),
-        distro.as_str()
+        distro.as_str(),
+        backend.as_str()
     );
 
     // Initialize the build log so the dashboard can show progress immediately.
@@ -3892,6 +4243,18 @@ pub async fn build_container_workspace(
                 return Err(e);
             }
 
+            if let Some(spec) = microvm.as_ref() {
+                // Start from a fresh VM disk built from the new rootfs.
+                microvm::destroy(&workspace.path).await;
+                let network = workspace.shared_network.unwrap_or(true);
+                if let Err(e) = microvm::prepare(&workspace.path, spec, network).await {
+                    workspace.status = WorkspaceStatus::Error;
+                    workspace.error_message = Some(format!("Failed to prepare microVM: {}", e));
+                    return Err(e);
+                }
+                append_to_init_log(&workspace.path, "[sandboxed] Booting microVM...\n");
+            }
+
             let has_init_scripts = !workspace.init_scripts.is_empty();
             let has_custom_script = workspace
                 .init_script
@@ -3918,6 +4281,17 @@ pub async fn build_container_workspace(
                     "Harness bootstrap failed; workspace will still be marked ready"
                 );
             }
+            if let (Some(name), Some(library)) = (workspace.template.as_deref(), library) {
+                match library.get_workspace_template(name).await {
+                    Ok(template) => {
+                        workspace.built_from =
+                            Some(TemplateFingerprint::of_template(library, &template).await);
+                    }
+                    Err(e) => {
+                        tracing::warn!(workspace = %workspace.name, error = %e, "Failed to fingerprint workspace template");
+                    }
+                }
+            }
             workspace.status = WorkspaceStatus::Ready;
             workspace.error_message = None;
             tracing::info!("Container workspace built successfully");
@@ -4015,8 +4389,7 @@ async fn copy_dir_recursive(src: &Path, dst: &Path) -> anyhow::Result<()> {
 }
 
 async fn bootstrap_workspace_harnesses(workspace: &Workspace) -> anyhow::Result<()> {
-    if workspace.workspace_type != WorkspaceType::Container || !use_nspawn_for_workspace(workspace)
-    {
+    if workspace.workspace_type != WorkspaceType::Container || !use_container_rootfs(workspace) {
         return Ok(());
     }
 
@@ -4113,6 +4486,7 @@ echo "[sandboxed] Harness bootstrap done"
 
     let config = nspawn::NspawnConfig {
         env: workspace.env_vars.clone(),
+        gpu: workspace_gpu_passthrough(workspace),
         ..Default::default()
     };
 
@@ -4144,9 +4518,10 @@ echo "[sandboxed] Harness bootstrap done"
 }
 
 async fn run_workspace_init_script(
-    workspace: &Workspace,
+    workspace: &mut Workspace,
     library: Option<&LibraryStore>,
 ) -> anyhow::Result<()> {
+    workspace.init_report = None;
     let has_fragments = !workspace.init_scripts.is_empty();
     let custom_script = workspace
         .init_script
@@ -4199,7 +4574,7 @@ async fn run_workspace_init_script(
                         error = %e,
                         "Failed to assemble init script fragments, falling back to custom script only"
                     );
-                    custom_script.to_string()
+                    init_report::custom_script(custom_script)
                 }
             }
         } else {
@@ -4208,16 +4583,14 @@ async fn run_workspace_init_script(
                 workspace = %workspace.name,
                 "Init script fragments specified but library not available"
             );
-            custom_script.to_string()
+            init_report::custom_script(custom_script)
         }
-    } else {
+    } else if !custom_script.is_empty() {
         // No fragments, just use custom script
-        custom_script.to_string()
-    };
-
-    if script.is_empty() {
+        init_report::custom_script(custom_script)
+    } else {
         return Ok(());
-    }
+    };
 
     let script_path = workspace.path.join("sandboxed-init.sh");
     tokio::fs::write(&script_path, &script).await?;
@@ -4237,6 +4610,7 @@ async fn run_workspace_init_script(
 
     let config = nspawn::NspawnConfig {
         env: workspace.env_vars.clone(),
+        gpu: workspace_gpu_passthrough(workspace),
         ..Default::default()
     };
 
@@ -4250,18 +4624,56 @@ async fn run_workspace_init_script(
         nspawn::build_log_path_for(&workspace.path)
     };
 
-    // Use streaming execution to show logs in real-time
-    let status =
-        nspawn::execute_in_container_streaming(&workspace.path, &command, &config, &log_file)
-            .await?;
+    // Stream output to the log in real-time and attribute it to fragments as it arrives.
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let tracker = tokio::spawn(async move {
+        let mut tracker = init_report::FragmentTracker::new();
+        while let Some(line) = rx.recv().await {
+            tracker.observe(&line, std::time::Instant::now());
+        }
+        tracker
+    });
+    let status = nspawn::execute_in_container_streaming_with(
+        &workspace.path,
+        &command,
+        &config,
+        &log_file,
+        Some(tx),
+    )
+    .await;
+    if let Ok(tracker) = tracker.await {
+        let report = tracker.finish(status.as_ref().ok().and_then(|s| s.code()));
+        let failed = report.failed_fragments();
+        if !failed.is_empty() {
+            tracing::warn!(
+                workspace = %workspace.name,
+                failed = ?failed,
+                "Init script fragments failed"
+            );
+        }
+        workspace.init_report = Some(report);
+    }
+    let status = status?;
 
     // Clean up the script file after execution.
     let _ = tokio::fs::remove_file(&script_path).await;
 
     if !status.success() {
+        let failed = workspace
+            .init_report
+            .as_ref()
+            .map(|r| r.failed_fragments())
+            .unwrap_or_default();
+        if failed.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Init script failed with exit code {}",
+                status.code().unwrap_or(-1)
+            ));
+        }
         return Err(anyhow::anyhow!(
-            "Init script failed with exit code {}",
-            status.code().unwrap_or(-1)
+            "Init script failed with exit code {} (failed: {})",
+            status.code().unwrap_or(-1),
+            failed.join(", ")
         ));
     }
 
@@ -4279,7 +4691,11 @@ pub async fn destroy_container_workspace(workspace: &Workspace) -> anyhow::Resul
         workspace.path.display()
     );
 
-    if !use_nspawn_for_workspace(workspace) {
+    if microvm::is_microvm_root(&workspace.path) {
+        microvm::destroy(&workspace.path).await;
+    }
+
+    if !use_container_rootfs(workspace) {
         // Fallback workspaces are plain directories on the host.
         let _ = tokio::fs::remove_dir_all(&workspace.path).await;
         return Ok(());
@@ -4290,6 +4706,50 @@ pub async fn destroy_container_workspace(workspace: &Workspace) -> anyhow::Resul
     Ok(())
 }
 
+/// Copy a workspace's files (the container rootfs or host directory, with the
+/// mission directories and repos in it) to `to`, which must not exist yet.
+/// Ownership, permissions and links are preserved; copy-on-write filesystems
+/// share the data blocks.
+pub async fn clone_workspace_files(workspace: &Workspace, to: &Path) -> anyhow::Result<()> {
+    if to.exists() {
+        return Err(anyhow::anyhow!("{} already exists", to.display()));
+    }
+    let microvm_root = microvm::is_microvm_root(&workspace.path);
+    if microvm_root && microvm::status(&workspace.path).running {
+        return Err(anyhow::anyhow!("Stop the workspace VM before cloning it"));
+    }
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let output = tokio::process::Command::new("cp")
+        .args(["-a", "--reflink=auto", "--"])
+        .arg(&workspace.path)
+        .arg(to)
+        .output()
+        .await?;
+    let result = if output.status.success() {
+        if microvm_root {
+            microvm::clone_state(&workspace.path, to).await
+        } else {
+            Ok(())
+        }
+    } else {
+        Err(anyhow::anyhow!(
+            "Copying {} failed: {}",
+            workspace.path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    };
+    if result.is_err() {
+        let _ = tokio::fs::remove_dir_all(to).await;
+        if microvm_root {
+            let _ = tokio::fs::remove_dir_all(microvm::VmPaths::for_root(to).dir).await;
+        }
+    }
+    result
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Config Sync (Library → System)
 // ─────────────────────────────────────────────────────────────────────────────
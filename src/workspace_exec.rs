@@ -2,7 +2,8 @@
 //!
 //! Spawns processes inside a workspace execution context so that:
 //! - Host workspaces execute directly on the host
-//! - Container workspaces execute via systemd-nspawn in the container filesystem
+//! - Container workspaces execute via systemd-nspawn in the container filesystem,
+//!   or via bubblewrap when systemd-nspawn is unavailable
 //!
 //! This is used for per-workspace Claude Code and OpenCode execution.
 
@@ -14,9 +15,42 @@ use std::process::Stdio;
 use anyhow::Context;
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use tokio::process::{Child, Command};
+use uuid::Uuid;
 
+use crate::bwrap::{self, BwrapNetwork, BwrapOptions, ContainerBackend};
+use crate::egress::{self, EgressPolicy};
+use crate::gpu::GpuPassthrough;
+use crate::microvm;
 use crate::nspawn;
-use crate::workspace::{use_nspawn_for_workspace, TailscaleMode, Workspace, WorkspaceType};
+use crate::package_cache;
+use crate::process_group;
+use crate::reference_repos;
+use crate::web_proxy;
+use crate::workspace::{
+    use_container_rootfs, workspace_container_backend, workspace_gpu_passthrough, TailscaleMode,
+    Workspace, WorkspaceType,
+};
+
+/// Default route via the host end of the veth pair and public DNS, for when
+/// DHCP didn't provide them.
+const FALLBACK_ROUTE_AND_DNS: &str = "if ! ip route show default 2>/dev/null | grep -q default; then \
+     _oa_ip=$(ip -4 addr show host0 2>/dev/null | sed -n 's/.*inet \\([0-9.]*\\).*/\\1/p' | head -1); \
+     _oa_gw=\"${_oa_ip%.*}.1\"; \
+     [ -n \"$_oa_ip\" ] && ip route add default via \"$_oa_gw\" 2>/dev/null || true; \
+     fi; \
+     if [ ! -s /etc/resolv.conf ]; then \
+     printf 'nameserver 8.8.8.8\\nnameserver 1.1.1.1\\n' > /etc/resolv.conf 2>/dev/null || true; \
+     fi; ";
+
+/// Private network for one-shot containers with an egress policy; the
+/// capability lets the wrapper install the nftables ruleset and is dropped
+/// before the program runs.
+const EGRESS_NSPAWN_ARGS: [&str; 2] = ["--network-veth", "--capability=CAP_NET_ADMIN"];
+
+/// Runs the egress-wrapped program without the capabilities that would let
+/// it rewrite or bypass the nftables table.
+const EGRESS_DROP_CAPS: &str =
+    "setpriv --inh-caps=-net_admin,-net_raw --bounding-set=-net_admin,-net_raw -- ";
 
 fn select_container_resolv_conf() -> Option<PathBuf> {
     let default_path = PathBuf::from("/etc/resolv.conf");
@@ -87,6 +121,8 @@ fn bind_resolv_conf_cmd_builder(cmd: &mut CommandBuilder) {
 #[derive(Debug, Clone)]
 pub struct WorkspaceExec {
     pub workspace: Workspace,
+    /// Mission the commands run for, if any
+    pub mission_id: Option<Uuid>,
 }
 
 /// Child process spawned inside a PTY.
@@ -113,6 +149,16 @@ enum PtyMasterHandle {
 }
 
 impl PtyChild {
+    /// OS pid of the child. PTY children run in their own session, so this
+    /// is also their process group id.
+    pub fn process_id(&self) -> Option<u32> {
+        match &self.child {
+            PtyChildProcess::PortablePty(c) => c.process_id(),
+            #[cfg(unix)]
+            PtyChildProcess::Std(c) => Some(c.id()),
+        }
+    }
+
     pub fn kill(&mut self) {
         match &mut self.child {
             PtyChildProcess::PortablePty(c) => {
@@ -192,7 +238,16 @@ impl Drop for PtyChild {
 
 impl WorkspaceExec {
     pub fn new(workspace: Workspace) -> Self {
-        Self { workspace }
+        Self {
+            workspace,
+            mission_id: None,
+        }
+    }
+
+    /// Attribute the commands' web proxy traffic to a mission.
+    pub fn with_mission(mut self, mission_id: Uuid) -> Self {
+        self.mission_id = Some(mission_id);
+        self
     }
 
     /// Translate a host path to a container-relative path.
@@ -208,7 +263,7 @@ impl WorkspaceExec {
         if self.workspace.workspace_type != WorkspaceType::Container {
             return path.to_string_lossy().to_string();
         }
-        if !use_nspawn_for_workspace(&self.workspace) {
+        if !use_container_rootfs(&self.workspace) {
             return path.to_string_lossy().to_string();
         }
         // Translate to container-relative path
@@ -255,9 +310,30 @@ impl WorkspaceExec {
             merged
                 .entry("XDG_CACHE_HOME".to_string())
                 .or_insert_with(|| "/root/.cache".to_string());
+            if let Some(gpu) = self.gpu() {
+                gpu.apply_env(&mut merged);
+            }
+        }
+        if let Some(policy) = self.egress_policy() {
+            for (k, v) in policy.proxy_env() {
+                merged.entry(k).or_insert(v);
+            }
+        }
+        // Route HTTP(S) through the built-in web proxy when commands can reach
+        // it on loopback: host workspaces and containers on the host network.
+        if let Some(mission_id) = self.mission_id {
+            if self.egress_policy().is_none() && !merged.contains_key("HTTPS_PROXY") {
+                let on_host_network = self.workspace.workspace_type == WorkspaceType::Host
+                    || self.workspace.shared_network.unwrap_or(true);
+                if on_host_network {
+                    for (k, v) in web_proxy::proxy_env_for_mission(mission_id) {
+                        merged.entry(k).or_insert(v);
+                    }
+                }
+            }
         }
         if self.workspace.workspace_type == WorkspaceType::Container
-            && !use_nspawn_for_workspace(&self.workspace)
+            && !use_container_rootfs(&self.workspace)
         {
             merged
                 .entry("SANDBOXED_SH_CONTAINER_FALLBACK".to_string())
@@ -386,16 +462,7 @@ impl WorkspaceExec {
         } else {
             // exit_node mode: Fallback route only if DHCP/Tailscale didn't set one.
             // All traffic should go through Tailscale's exit node when properly configured.
-            cmd.push_str(
-                "if ! ip route show default 2>/dev/null | grep -q default; then \
-                 _oa_ip=$(ip -4 addr show host0 2>/dev/null | sed -n 's/.*inet \\([0-9.]*\\).*/\\1/p' | head -1); \
-                 _oa_gw=\"${_oa_ip%.*}.1\"; \
-                 [ -n \"$_oa_ip\" ] && ip route add default via \"$_oa_gw\" 2>/dev/null || true; \
-                 fi; \
-                 if [ ! -s /etc/resolv.conf ]; then \
-                 printf 'nameserver 8.8.8.8\\nnameserver 1.1.1.1\\n' > /etc/resolv.conf 2>/dev/null || true; \
-                 fi; ",
-            );
+            cmd.push_str(FALLBACK_ROUTE_AND_DNS);
         }
 
         // Change to the working directory and exec the main program.
@@ -410,6 +477,96 @@ impl WorkspaceExec {
         cmd
     }
 
+    /// The workspace's egress policy, when one is enforced for nspawn execution.
+    fn egress_policy(&self) -> Option<&EgressPolicy> {
+        if workspace_container_backend(&self.workspace) != Some(ContainerBackend::Nspawn) {
+            return None;
+        }
+        self.workspace
+            .egress_policy
+            .as_ref()
+            .filter(|p| p.is_enforced())
+    }
+
+    /// Wrap a program in a shell that installs the egress ruleset first.
+    ///
+    /// The shell brings up the private veth network if nothing else did,
+    /// applies the nftables table (refusing to run the program if that fails),
+    /// and runs the program without `CAP_NET_ADMIN` and `CAP_NET_RAW` so it
+    /// can't lift the rules. Rejected connections are read by the host; what
+    /// earlier commands left behind is collected before the ruleset is
+    /// replaced.
+    async fn wrap_for_egress(
+        &self,
+        program: &str,
+        args: &[String],
+    ) -> Option<(String, Vec<String>)> {
+        let policy = self.egress_policy()?;
+        egress::collect(&self.workspace.path);
+        let mut script = String::new();
+        script.push_str(
+            "if ! ip route show default 2>/dev/null | grep -q default \
+             && [ -x /usr/local/bin/sandboxed-network-up ]; then \
+             /usr/local/bin/sandboxed-network-up >/dev/null 2>&1 || true; \
+             fi; ",
+        );
+        script.push_str(FALLBACK_ROUTE_AND_DNS);
+        script.push_str(
+            "command -v setpriv >/dev/null 2>&1 || { echo 'sandboxed.sh: setpriv is required to enforce the egress policy' >&2; exit 126; }; ",
+        );
+        script.push_str("printf '%s' ");
+        script.push_str(&Self::shell_escape(&policy.ruleset().await));
+        script.push_str(
+            " | nft -f - || { echo 'sandboxed.sh: failed to apply egress policy' >&2; exit 126; }; ",
+        );
+        script.push_str(EGRESS_DROP_CAPS);
+        script.push_str(&Self::shell_escape(program));
+        for arg in args {
+            script.push(' ');
+            script.push_str(&Self::shell_escape(arg));
+        }
+        script.push_str("; _sb_rc=$?; ");
+        script.push_str(&egress::await_collection_script());
+        script.push_str("exit $_sb_rc");
+        tracing::info!(
+            workspace = %self.workspace.name,
+            mode = %policy.mode.as_str(),
+            "WorkspaceExec: enforcing egress policy"
+        );
+        Some(("/bin/sh".to_string(), vec!["-c".to_string(), script]))
+    }
+
+    /// Have the host hold the network namespace of an egress-wrapped command
+    /// so its rejected connections can be read.
+    fn watch_egress(&self, wrapped: bool, pid: Option<u32>) {
+        if let (true, Some(pid)) = (wrapped, pid) {
+            egress::watch(&self.workspace.path, pid);
+        }
+    }
+
+    /// Leader of a running container to nsenter into. With an egress policy,
+    /// containers sharing the host network namespace are skipped so the
+    /// ruleset is never applied to the host.
+    async fn exec_leader(&self) -> Option<String> {
+        if workspace_container_backend(&self.workspace) != Some(ContainerBackend::Nspawn) {
+            return None;
+        }
+        let leader = self.running_container_leader().await?;
+        if self.egress_policy().is_none() {
+            return Some(leader);
+        }
+        let container_ns = fs::read_link(format!("/proc/{}/ns/net", leader)).ok()?;
+        let host_ns = fs::read_link("/proc/self/ns/net").ok()?;
+        if container_ns == host_ns {
+            tracing::info!(
+                workspace = %self.workspace.name,
+                "WorkspaceExec: running container shares the host network; using one-shot nspawn for egress policy"
+            );
+            return None;
+        }
+        Some(leader)
+    }
+
     fn machine_name(&self) -> Option<String> {
         self.workspace
             .path
@@ -442,6 +599,127 @@ impl WorkspaceExec {
         }
     }
 
+    /// Full `bwrap` command line running `program` in the workspace rootfs.
+    ///
+    /// Mirrors the one-shot systemd-nspawn setup: context, reference repo,
+    /// package cache, X11 and resolv.conf binds plus the environment. A shared
+    /// network uses the host stack; an isolated one (or an enforced egress
+    /// policy, which needs nspawn's veth to be applied) gets a loopback-only
+    /// namespace.
+    /// GPU passthrough for commands in the workspace rootfs (not microVMs).
+    fn gpu(&self) -> Option<GpuPassthrough> {
+        if !matches!(
+            workspace_container_backend(&self.workspace),
+            Some(ContainerBackend::Nspawn | ContainerBackend::Bwrap)
+        ) {
+            return None;
+        }
+        workspace_gpu_passthrough(&self.workspace)
+    }
+
+    /// Host command line running `program` in the workspace microVM, booting
+    /// the VM first if needed.
+    pub(crate) async fn microvm_argv(
+        &self,
+        cwd: &Path,
+        program: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+    ) -> anyhow::Result<Vec<String>> {
+        microvm::ensure_running(&self.workspace.path).await?;
+        let mut env = env.clone();
+        env.entry("HOME".to_string())
+            .or_insert_with(|| "/root".to_string());
+        Ok(microvm::exec_argv(
+            &self.workspace.path,
+            &self.rel_path_in_container(cwd),
+            &env,
+            program,
+            args,
+        ))
+    }
+
+    pub(crate) fn bwrap_argv(
+        &self,
+        cwd: &Path,
+        program: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+    ) -> Vec<String> {
+        let mut env = env.clone();
+        env.entry("HOME".to_string())
+            .or_insert_with(|| "/root".to_string());
+        let mut binds = Vec::new();
+
+        let context_dir_name = std::env::var("SANDBOXED_SH_CONTEXT_DIR_NAME")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| "context".to_string());
+        let global_context_root = std::env::var("SANDBOXED_SH_CONTEXT_ROOT")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/root").join(&context_dir_name));
+        if global_context_root.exists() {
+            binds.push(format!(
+                "--bind={}:/root/context",
+                global_context_root.display()
+            ));
+            env.insert(
+                "SANDBOXED_SH_CONTEXT_ROOT".to_string(),
+                "/root/context".to_string(),
+            );
+            env.insert(
+                "SANDBOXED_SH_CONTEXT_DIR_NAME".to_string(),
+                context_dir_name,
+            );
+        }
+        if let Some(bind) = reference_repos::nspawn_bind_arg() {
+            binds.push(bind);
+        }
+        binds.extend(package_cache::nspawn_bind_args(&env));
+        if let Some(gpu) = self.gpu() {
+            binds.extend(gpu.nspawn_args());
+        }
+        if Path::new("/tmp/.X11-unix").exists() {
+            binds.push("--bind=/tmp/.X11-unix".to_string());
+        }
+        if let Some(path) = select_container_resolv_conf() {
+            binds.push(format!("--bind-ro={}:/etc/resolv.conf", path.display()));
+        }
+
+        let egress_requested = self
+            .workspace
+            .egress_policy
+            .as_ref()
+            .is_some_and(|p| p.is_enforced());
+        let shared = self.workspace.shared_network.unwrap_or(true) && !egress_requested;
+        if !shared && (egress_requested || nspawn::tailscale_enabled(&env)) {
+            tracing::warn!(
+                workspace = %self.workspace.name,
+                "WorkspaceExec: bwrap cannot apply Tailscale or egress policies; network is loopback-only"
+            );
+        }
+
+        let opts = BwrapOptions {
+            chdir: self.rel_path_in_container(cwd),
+            hostname: self.machine_name(),
+            network: if shared {
+                BwrapNetwork::Shared
+            } else {
+                BwrapNetwork::Private
+            },
+            binds,
+            env,
+            ..Default::default()
+        };
+        let mut command = vec![program.to_string()];
+        command.extend(args.iter().cloned());
+        let mut argv = vec!["bwrap".to_string()];
+        argv.extend(bwrap::bwrap_args(&self.workspace.path, &opts, &command));
+        argv
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn build_nsenter_command(
         &self,
@@ -521,7 +799,7 @@ impl WorkspaceExec {
                 Ok(cmd)
             }
             WorkspaceType::Container => {
-                if !use_nspawn_for_workspace(&self.workspace) {
+                if !use_container_rootfs(&self.workspace) {
                     // Fallback: execute on host when systemd-nspawn isn't available.
                     let mut cmd = Command::new(program);
                     cmd.current_dir(cwd);
@@ -534,6 +812,21 @@ impl WorkspaceExec {
                     cmd.stdin(stdin).stdout(stdout).stderr(stderr);
                     return Ok(cmd);
                 }
+                let argv = match workspace_container_backend(&self.workspace) {
+                    Some(ContainerBackend::Bwrap) => {
+                        Some(self.bwrap_argv(cwd, program, args, &env))
+                    }
+                    Some(ContainerBackend::MicroVm) => {
+                        Some(self.microvm_argv(cwd, program, args, &env).await?)
+                    }
+                    _ => None,
+                };
+                if let Some(argv) = argv {
+                    let mut cmd = Command::new(&argv[0]);
+                    cmd.args(&argv[1..]);
+                    cmd.stdin(stdin).stdout(stdout).stderr(stderr);
+                    return Ok(cmd);
+                }
 
                 let mut env = env;
                 if !env.contains_key("HOME") {
@@ -573,7 +866,7 @@ impl WorkspaceExec {
                         .tailscale_mode
                         .unwrap_or(TailscaleMode::ExitNode)
                         == TailscaleMode::TailnetOnly;
-                if let Some(leader) = self.running_container_leader().await {
+                if let Some(leader) = self.exec_leader().await {
                     return self.build_nsenter_command(
                         &leader,
                         cwd,
@@ -622,6 +915,15 @@ impl WorkspaceExec {
                     ));
                 }
 
+                // Shared reference repo cache, read-only.
+                if let Some(bind) = reference_repos::nspawn_bind_arg() {
+                    cmd.arg(bind);
+                }
+                cmd.args(package_cache::nspawn_bind_args(&env));
+                if let Some(gpu) = self.gpu() {
+                    cmd.args(gpu.nspawn_args());
+                }
+
                 // Bind X11 socket for GUI applications (e.g., Minecraft) when available.
                 // The desktop MCP creates Xvfb displays on the host; containers need
                 // access to /tmp/.X11-unix to connect to these displays.
@@ -636,7 +938,9 @@ impl WorkspaceExec {
                 // - shared_network=false: Isolated network with optional Tailscale
                 //   - tailscale_mode=exit_node: All traffic via Tailscale exit node
                 //   - tailscale_mode=tailnet_only: Tailscale for tailnet, host gateway for internet
-                let use_shared_network = self.workspace.shared_network.unwrap_or(true);
+                let egress_enforced = self.egress_policy().is_some();
+                let use_shared_network =
+                    self.workspace.shared_network.unwrap_or(true) && !egress_enforced;
                 let tailscale_mode = self
                     .workspace
                     .tailscale_mode
@@ -667,6 +971,9 @@ impl WorkspaceExec {
                     if tailscale_args.is_empty() {
                         tracing::debug!("WorkspaceExec: no Tailscale args, binding resolv.conf");
                         bind_resolv_conf(&mut cmd);
+                        if egress_enforced {
+                            cmd.args(EGRESS_NSPAWN_ARGS);
+                        }
                         false
                     } else {
                         tracing::info!(
@@ -747,6 +1054,11 @@ impl WorkspaceExec {
         env: HashMap<String, String>,
     ) -> anyhow::Result<std::process::Output> {
         let env = self.build_env(env);
+        let wrapped = self.wrap_for_egress(program, args).await;
+        let (program, args) = match &wrapped {
+            Some((program, args)) => (program.as_str(), args.as_slice()),
+            None => (program, args),
+        };
         let mut cmd = self
             .build_command(
                 cwd,
@@ -759,8 +1071,10 @@ impl WorkspaceExec {
             )
             .await
             .context("Failed to build workspace command")?;
-        let output = cmd
-            .output()
+        let child = cmd.spawn().context("Failed to run workspace command")?;
+        self.watch_egress(wrapped.is_some(), child.id());
+        let output = child
+            .wait_with_output()
             .await
             .context("Failed to run workspace command")?;
         Ok(output)
@@ -774,6 +1088,11 @@ impl WorkspaceExec {
         env: HashMap<String, String>,
     ) -> anyhow::Result<Child> {
         let env = self.build_env(env);
+        let wrapped = self.wrap_for_egress(program, args).await;
+        let (program, args) = match &wrapped {
+            Some((program, args)) => (program.as_str(), args.as_slice()),
+            None => (program, args),
+        };
         let mut cmd = self
             .build_command(
                 cwd,
@@ -786,8 +1105,11 @@ impl WorkspaceExec {
             )
             .await
             .context("Failed to build workspace command")?;
+        // Lead a process group so cancellation can take down the whole tree.
+        process_group::isolate(&mut cmd);
 
         let child = cmd.spawn().context("Failed to spawn workspace command")?;
+        self.watch_egress(wrapped.is_some(), child.id());
         Ok(child)
     }
 
@@ -799,6 +1121,11 @@ impl WorkspaceExec {
         env: HashMap<String, String>,
     ) -> anyhow::Result<PtyChild> {
         let mut env = self.build_env(env);
+        let wrapped = self.wrap_for_egress(program, args).await;
+        let (program, args) = match &wrapped {
+            Some((program, args)) => (program.as_str(), args.as_slice()),
+            None => (program, args),
+        };
         // A number of CLIs (notably Claude Code) behave differently without TERM.
         env.entry("TERM".to_string())
             .or_insert_with(|| "xterm-256color".to_string());
@@ -840,7 +1167,7 @@ impl WorkspaceExec {
                 cmd
             }
             WorkspaceType::Container => {
-                if !use_nspawn_for_workspace(&self.workspace) {
+                if !use_container_rootfs(&self.workspace) {
                     let mut cmd = CommandBuilder::new(program);
                     cmd.cwd(cwd);
                     if !args.is_empty() {
@@ -853,6 +1180,20 @@ impl WorkspaceExec {
                         cmd.env(k, v);
                     }
                     cmd
+                } else if workspace_container_backend(&self.workspace)
+                    == Some(ContainerBackend::Bwrap)
+                {
+                    let argv = self.bwrap_argv(cwd, program, args, &env);
+                    let mut cmd = CommandBuilder::new(&argv[0]);
+                    cmd.args(&argv[1..]);
+                    cmd
+                } else if workspace_container_backend(&self.workspace)
+                    == Some(ContainerBackend::MicroVm)
+                {
+                    let argv = self.microvm_argv(cwd, program, args, &env).await?;
+                    let mut cmd = CommandBuilder::new(&argv[0]);
+                    cmd.args(&argv[1..]);
+                    cmd
                 } else {
                     if !env.contains_key("HOME") {
                         env.insert("HOME".to_string(), "/root".to_string());
@@ -870,7 +1211,7 @@ impl WorkspaceExec {
                             .unwrap_or(TailscaleMode::ExitNode)
                             == TailscaleMode::TailnetOnly;
 
-                    if let Some(leader) = self.running_container_leader().await {
+                    if let Some(leader) = self.exec_leader().await {
                         let nsenter = if Path::new("/usr/bin/nsenter").exists() {
                             "/usr/bin/nsenter"
                         } else {
@@ -933,6 +1274,14 @@ impl WorkspaceExec {
                             ));
                         }
 
+                        if let Some(bind) = reference_repos::nspawn_bind_arg() {
+                            cmd.arg(bind);
+                        }
+                        cmd.args(package_cache::nspawn_bind_args(&env));
+                        if let Some(gpu) = self.gpu() {
+                            cmd.args(gpu.nspawn_args());
+                        }
+
                         // Bind X11 socket for GUI applications when available.
                         let x11_socket_path = Path::new("/tmp/.X11-unix");
                         if x11_socket_path.exists() {
@@ -940,7 +1289,9 @@ impl WorkspaceExec {
                         }
 
                         // Network configuration (same behavior as spawn_streaming/output).
-                        let use_shared_network = self.workspace.shared_network.unwrap_or(true);
+                        let egress_enforced = self.egress_policy().is_some();
+                        let use_shared_network =
+                            self.workspace.shared_network.unwrap_or(true) && !egress_enforced;
                         let tailscale_mode = self
                             .workspace
                             .tailscale_mode
@@ -953,6 +1304,9 @@ impl WorkspaceExec {
                             let tailscale_args = nspawn::tailscale_nspawn_extra_args(&env);
                             if tailscale_args.is_empty() {
                                 bind_resolv_conf_cmd_builder(&mut cmd);
+                                if egress_enforced {
+                                    cmd.args(EGRESS_NSPAWN_ARGS);
+                                }
                                 false
                             } else {
                                 bind_resolv_conf_cmd_builder(&mut cmd);
@@ -1001,6 +1355,7 @@ impl WorkspaceExec {
             .slave
             .spawn_command(cmd)
             .context("Failed to spawn PTY command")?;
+        self.watch_egress(wrapped.is_some(), child.process_id());
         // Drop the slave so the child owns the TTY; we only keep the master side.
         drop(pair.slave);
 
@@ -0,0 +1,782 @@
+//! Mission workspace snapshots.
+//!
+//! When a mission first prepares its workspace directory we record a baseline
+//! snapshot of every file in it using shadow git repositories that live next to
+//! (not inside) the mission directory:
+//!
+//! ```text
+//! <workspace>/workspaces/mission-1a2b3c4d/          # mission directory
+//! <workspace>/workspaces/.snapshots/mission-1a2b3c4d/
+//!     root.git/                                     # snapshot of the mission dir
+//!     repo_foo.git/                                 # snapshot of nested repo `repo/foo`
+//! ```
+//!
+//! Nested git repositories are snapshotted separately because git refuses to
+//! descend into embedded repositories. The agent never sees the shadow repos,
+//! so the diff reflects the real filesystem state regardless of whether the
+//! agent committed, amended, or reset its own work.
+//!
+//! Later turns also record numbered checkpoints of the same worktrees when
+//! files changed since the previous one, so any file can be read as it was at
+//! the start of a turn ([`read_file_at`]) without rolling anything back.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use walkdir::WalkDir;
+
+/// Ref that stores the baseline snapshot commit in each shadow repository.
+const BASE_REF: &str = "refs/sandboxed/base";
+
+/// Prefix of the refs storing numbered checkpoint commits.
+const CHECKPOINT_REFS: &str = "refs/sandboxed/checkpoints/";
+
+/// Checkpoint ID of the baseline snapshot.
+pub const BASE_CHECKPOINT: &str = "base";
+
+/// Git's well-known empty tree object, used as the base for repos that
+/// appeared after the baseline was recorded.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Directories that are never interesting in a mission diff.
+const DEFAULT_EXCLUDES: &[&str] = &["node_modules/", ".venv/", "__pycache__/"];
+
+/// Maximum depth searched for nested git repositories.
+const MAX_REPO_DEPTH: usize = 6;
+
+/// How a single file changed since the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    TypeChanged,
+}
+
+impl FileChangeStatus {
+    fn from_git(code: &str) -> Option<Self> {
+        match code.chars().next()? {
+            'A' => Some(Self::Added),
+            'M' => Some(Self::Modified),
+            'D' => Some(Self::Deleted),
+            'T' => Some(Self::TypeChanged),
+            _ => None,
+        }
+    }
+}
+
+/// Per-file stats for a mission diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Path relative to the mission directory.
+    pub path: String,
+    pub status: FileChangeStatus,
+    /// Lines added (None for binary files).
+    pub additions: Option<u64>,
+    /// Lines deleted (None for binary files).
+    pub deletions: Option<u64>,
+    pub binary: bool,
+}
+
+/// Cumulative diff of a mission directory against its baseline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionDiff {
+    /// Whether a baseline was recorded when the mission started.
+    /// Without one, every file is reported as added.
+    pub has_baseline: bool,
+    pub files: Vec<FileChange>,
+    pub total_additions: u64,
+    pub total_deletions: u64,
+    /// Unified diff (only populated when requested).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+/// A point in the mission the workspace can be read at.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Checkpoint {
+    /// `base` for the baseline, then `1`, `2`, ... in order
+    pub id: String,
+    /// When the snapshot was taken (RFC 3339)
+    pub created_at: String,
+}
+
+/// Directory holding the shadow repositories for a mission directory.
+pub fn snapshot_dir(mission_dir: &Path) -> PathBuf {
+    let name = mission_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mission".to_string());
+    mission_dir
+        .parent()
+        .unwrap_or(mission_dir)
+        .join(".snapshots")
+        .join(name)
+}
+
+/// Shadow repository path for a worktree relative to the mission directory.
+fn shadow_repo(snapshots: &Path, rel: &str) -> PathBuf {
+    if rel.is_empty() {
+        snapshots.join("root.git")
+    } else {
+        snapshots.join(format!(
+            "repo_{}.git",
+            crate::api::mission_store::sanitize_filename(rel)
+        ))
+    }
+}
+
+/// Find git repositories nested under `root`, returned as relative paths.
+fn find_nested_repos(root: &Path) -> Vec<String> {
+    let mut repos = Vec::new();
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(MAX_REPO_DEPTH)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.file_type().is_dir() && name != ".git" && name != "node_modules"
+        });
+    for entry in walker.flatten() {
+        if entry.path().join(".git").exists() {
+            if let Ok(rel) = entry.path().strip_prefix(root) {
+                repos.push(rel.to_string_lossy().to_string());
+            }
+        }
+    }
+    repos
+}
+
+/// Run git against a shadow repository with the given work tree.
+async fn shadow_git(git_dir: &Path, work_tree: &Path, args: &[&str]) -> Result<String> {
+    let stdout = shadow_git_bytes(git_dir, work_tree, args).await?;
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like [`shadow_git`], keeping stdout as raw bytes.
+async fn shadow_git_bytes(git_dir: &Path, work_tree: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .env("GIT_DIR", git_dir)
+        .env("GIT_WORK_TREE", work_tree)
+        .env("GIT_INDEX_FILE", git_dir.join("snapshot.index"))
+        .env("GIT_AUTHOR_NAME", "sandboxed.sh")
+        .env("GIT_AUTHOR_EMAIL", "snapshots@sandboxed.sh")
+        .env("GIT_COMMITTER_NAME", "sandboxed.sh")
+        .env("GIT_COMMITTER_EMAIL", "snapshots@sandboxed.sh")
+        .current_dir(work_tree)
+        .output()
+        .await
+        .with_context(|| format!("Failed to execute git {}", args.join(" ")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(output.stdout)
+}
+
+/// Create the shadow repository (if needed) and write its exclude list.
+///
+/// `nested` contains the worktree-relative paths of repositories nested below
+/// this one; they are excluded because each gets its own shadow repository.
+async fn prepare_shadow(
+    git_dir: &Path,
+    work_tree: &Path,
+    rel: &str,
+    nested: &[String],
+) -> Result<()> {
+    if !git_dir.join("HEAD").exists() {
+        tokio::fs::create_dir_all(git_dir).await?;
+        shadow_git(git_dir, work_tree, &["init", "--quiet"]).await?;
+        shadow_git(git_dir, work_tree, &["config", "sandboxed.worktree", rel]).await?;
+    }
+
+    let mut exclude = String::new();
+    for pattern in DEFAULT_EXCLUDES {
+        exclude.push_str(pattern);
+        exclude.push('\n');
+    }
+    for path in nested {
+        exclude.push('/');
+        exclude.push_str(path);
+        exclude.push('\n');
+    }
+    let info_dir = git_dir.join("info");
+    tokio::fs::create_dir_all(&info_dir).await?;
+    tokio::fs::write(info_dir.join("exclude"), exclude).await?;
+    Ok(())
+}
+
+/// Stage the full work tree into the shadow index and return its tree hash.
+async fn write_tree(git_dir: &Path, work_tree: &Path) -> Result<String> {
+    shadow_git(git_dir, work_tree, &["add", "--all", "."]).await?;
+    Ok(shadow_git(git_dir, work_tree, &["write-tree"])
+        .await?
+        .trim()
+        .to_string())
+}
+
+/// Paths of nested repos that live strictly below `rel`, relative to `rel`.
+fn nested_below(all: &[String], rel: &str) -> Vec<String> {
+    all.iter()
+        .filter_map(|p| {
+            if rel.is_empty() {
+                Some(p.clone())
+            } else {
+                p.strip_prefix(rel)
+                    .and_then(|rest| rest.strip_prefix('/'))
+                    .map(str::to_string)
+            }
+        })
+        .collect()
+}
+
+/// Whether a baseline has already been recorded for this mission directory.
+pub fn has_baseline(mission_dir: &Path) -> bool {
+    shadow_repo(&snapshot_dir(mission_dir), "")
+        .join(BASE_REF)
+        .exists()
+}
+
+/// Record the baseline snapshot for a mission directory.
+///
+/// This is idempotent: once the root snapshot exists, later calls are no-ops so
+/// that repositories cloned during the mission are treated as new files.
+pub async fn record_baseline(mission_dir: &Path) -> Result<()> {
+    if has_baseline(mission_dir) {
+        return Ok(());
+    }
+
+    let snapshots = snapshot_dir(mission_dir);
+    let repos = find_nested_repos(mission_dir);
+    let mut worktrees = vec![String::new()];
+    worktrees.extend(repos.iter().cloned());
+
+    // Record the root last so a partial failure is retried on the next turn.
+    for rel in worktrees.iter().rev() {
+        let work_tree = if rel.is_empty() {
+            mission_dir.to_path_buf()
+        } else {
+            mission_dir.join(rel)
+        };
+        let git_dir = shadow_repo(&snapshots, rel);
+        prepare_shadow(&git_dir, &work_tree, rel, &nested_below(&repos, rel)).await?;
+        let tree = write_tree(&git_dir, &work_tree).await?;
+        let commit = shadow_git(
+            &git_dir,
+            &work_tree,
+            &["commit-tree", &tree, "-m", "baseline"],
+        )
+        .await?
+        .trim()
+        .to_string();
+        shadow_git(&git_dir, &work_tree, &["update-ref", BASE_REF, &commit]).await?;
+    }
+
+    tracing::debug!(
+        mission_dir = %mission_dir.display(),
+        nested_repos = repos.len(),
+        "Recorded mission baseline snapshot"
+    );
+    Ok(())
+}
+
+/// Worktrees (relative to the mission directory) that have a shadow repository.
+async fn snapshotted_worktrees(snapshots: &Path) -> Vec<String> {
+    let mut worktrees = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(snapshots).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let git_dir = entry.path();
+            if let Ok(rel) =
+                shadow_git(&git_dir, snapshots, &["config", "sandboxed.worktree"]).await
+            {
+                worktrees.push(rel.trim().to_string());
+            }
+        }
+    }
+    worktrees
+}
+
+/// Tree hash of `rev` in a shadow repo, if it exists.
+async fn tree_of(git_dir: &Path, work_tree: &Path, rev: &str) -> Option<String> {
+    let spec = format!("{}^{{tree}}", rev);
+    shadow_git(
+        git_dir,
+        work_tree,
+        &["rev-parse", "--verify", "--quiet", &spec],
+    )
+    .await
+    .ok()
+    .map(|out| out.trim().to_string())
+    .filter(|tree| !tree.is_empty())
+}
+
+/// Ref holding a checkpoint, or None for a malformed ID.
+fn checkpoint_ref(id: &str) -> Option<String> {
+    if id == BASE_CHECKPOINT {
+        Some(BASE_REF.to_string())
+    } else if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) {
+        Some(format!("{}{}", CHECKPOINT_REFS, id))
+    } else {
+        None
+    }
+}
+
+/// List the mission's checkpoints, oldest first.
+pub async fn list_checkpoints(mission_dir: &Path) -> Result<Vec<Checkpoint>> {
+    if !has_baseline(mission_dir) {
+        return Ok(Vec::new());
+    }
+    let snapshots = snapshot_dir(mission_dir);
+    let out = shadow_git(
+        &shadow_repo(&snapshots, ""),
+        &snapshots,
+        &[
+            "for-each-ref",
+            "--format=%(refname)\t%(creatordate:iso-strict)",
+            "refs/sandboxed/",
+        ],
+    )
+    .await?;
+    let mut checkpoints: Vec<(u64, Checkpoint)> = out
+        .lines()
+        .filter_map(|line| {
+            let (name, created_at) = line.split_once('\t')?;
+            let (order, id) = if name == BASE_REF {
+                (0, BASE_CHECKPOINT.to_string())
+            } else {
+                let id = name.strip_prefix(CHECKPOINT_REFS)?;
+                (id.parse().ok()?, id.to_string())
+            };
+            Some((
+                order,
+                Checkpoint {
+                    id,
+                    created_at: created_at.to_string(),
+                },
+            ))
+        })
+        .collect();
+    checkpoints.sort_by_key(|(order, _)| *order);
+    Ok(checkpoints.into_iter().map(|(_, c)| c).collect())
+}
+
+/// Record a checkpoint of the mission directory if any file changed since the
+/// latest one (or the baseline). Returns the new checkpoint's ID.
+pub async fn record_checkpoint(mission_dir: &Path) -> Result<Option<String>> {
+    let checkpoints = list_checkpoints(mission_dir).await?;
+    let Some(latest) = checkpoints.last() else {
+        // Without a baseline there is nothing to compare against.
+        return Ok(None);
+    };
+    let previous = checkpoint_ref(&latest.id).expect("listed checkpoints are well-formed");
+    let id = checkpoints.len().to_string();
+    let latest_ref = format!("{}{}", CHECKPOINT_REFS, id);
+
+    let snapshots = snapshot_dir(mission_dir);
+    let repos = find_nested_repos(mission_dir);
+    let mut worktrees = vec![String::new()];
+    worktrees.extend(repos.iter().cloned());
+
+    let mut trees = Vec::with_capacity(worktrees.len());
+    let mut changed = false;
+    for rel in &worktrees {
+        let work_tree = if rel.is_empty() {
+            mission_dir.to_path_buf()
+        } else {
+            mission_dir.join(rel)
+        };
+        let git_dir = shadow_repo(&snapshots, rel);
+        prepare_shadow(&git_dir, &work_tree, rel, &nested_below(&repos, rel)).await?;
+        let tree = write_tree(&git_dir, &work_tree).await?;
+        let before = tree_of(&git_dir, &work_tree, &previous).await;
+        changed |= before.as_deref().unwrap_or(EMPTY_TREE) != tree;
+        trees.push((git_dir, work_tree, tree));
+    }
+    // Nested repos deleted since the previous checkpoint
+    for rel in snapshotted_worktrees(&snapshots).await {
+        if !worktrees.contains(&rel)
+            && tree_of(&shadow_repo(&snapshots, &rel), &snapshots, &previous)
+                .await
+                .is_some()
+        {
+            changed = true;
+        }
+    }
+    if !changed {
+        return Ok(None);
+    }
+
+    // Record the root last: it is what lists the checkpoint.
+    for (git_dir, work_tree, tree) in trees.iter().rev() {
+        let message = format!("checkpoint {}", id);
+        let commit = shadow_git(git_dir, work_tree, &["commit-tree", tree, "-m", &message])
+            .await?
+            .trim()
+            .to_string();
+        shadow_git(git_dir, work_tree, &["update-ref", &latest_ref, &commit]).await?;
+    }
+    tracing::debug!(
+        mission_dir = %mission_dir.display(),
+        checkpoint = %id,
+        "Recorded mission checkpoint"
+    );
+    Ok(Some(id))
+}
+
+async fn unknown_checkpoint(mission_dir: &Path, checkpoint: &str) -> anyhow::Error {
+    let ids: Vec<String> = list_checkpoints(mission_dir)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    if ids.is_empty() {
+        anyhow::anyhow!("No checkpoints were recorded for this mission")
+    } else {
+        anyhow::anyhow!(
+            "Unknown checkpoint '{}'. Available: {}",
+            checkpoint,
+            ids.join(", ")
+        )
+    }
+}
+
+/// Read a file (relative to the mission directory) as it was at a checkpoint.
+/// Returns None when the file did not exist then.
+pub async fn read_file_at(
+    mission_dir: &Path,
+    path: &str,
+    checkpoint: &str,
+) -> Result<Option<Vec<u8>>> {
+    let rel_path = Path::new(path.trim_start_matches("./"));
+    if path.is_empty()
+        || !rel_path
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+    {
+        anyhow::bail!("path must be relative to the mission directory: {}", path);
+    }
+    let path = rel_path.to_string_lossy().to_string();
+    let snapshots = snapshot_dir(mission_dir);
+    let Some(reference) = checkpoint_ref(checkpoint) else {
+        return Err(unknown_checkpoint(mission_dir, checkpoint).await);
+    };
+    let root = shadow_repo(&snapshots, "");
+    if tree_of(&root, &snapshots, &reference).await.is_none() {
+        return Err(unknown_checkpoint(mission_dir, checkpoint).await);
+    }
+
+    // The file belongs to the innermost snapshotted repo containing it.
+    let rel = snapshotted_worktrees(&snapshots)
+        .await
+        .into_iter()
+        .filter(|rel| {
+            rel.is_empty()
+                || path
+                    .strip_prefix(rel.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|rel| rel.len())
+        .unwrap_or_default();
+    let inner = if rel.is_empty() {
+        path.as_str()
+    } else {
+        &path[rel.len() + 1..]
+    };
+    let git_dir = shadow_repo(&snapshots, &rel);
+    let spec = format!("{}:{}", reference, inner);
+    let Ok(kind) = shadow_git(&git_dir, &snapshots, &["cat-file", "-t", &spec]).await else {
+        return Ok(None);
+    };
+    if kind.trim() != "blob" {
+        anyhow::bail!("{} was a directory at checkpoint {}", path, checkpoint);
+    }
+    Ok(Some(
+        shadow_git_bytes(&git_dir, &snapshots, &["cat-file", "blob", &spec]).await?,
+    ))
+}
+
+/// Resolve the baseline commit of a shadow repo, falling back to the empty tree.
+async fn base_of(git_dir: &Path, work_tree: &Path) -> String {
+    match shadow_git(
+        git_dir,
+        work_tree,
+        &["rev-parse", "--verify", "--quiet", BASE_REF],
+    )
+    .await
+    {
+        Ok(out) if !out.trim().is_empty() => out.trim().to_string(),
+        _ => EMPTY_TREE.to_string(),
+    }
+}
+
+/// Parse `git diff --name-status -z` and `--numstat -z` output into file changes.
+fn parse_changes(prefix: &str, name_status: &str, numstat: &str) -> Vec<FileChange> {
+    let join = |path: &str| {
+        if prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", prefix, path)
+        }
+    };
+
+    let mut stats = std::collections::HashMap::new();
+    for record in numstat.split('\0').filter(|r| !r.is_empty()) {
+        let mut parts = record.splitn(3, '\t');
+        let (Some(add), Some(del), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        stats.insert(path.to_string(), (add.parse().ok(), del.parse().ok()));
+    }
+
+    let mut fields = name_status.split('\0').filter(|f| !f.is_empty());
+    let mut changes = Vec::new();
+    while let (Some(code), Some(path)) = (fields.next(), fields.next()) {
+        let Some(status) = FileChangeStatus::from_git(code) else {
+            continue;
+        };
+        let (additions, deletions) = stats.get(path).copied().unwrap_or((None, None));
+        changes.push(FileChange {
+            path: join(path),
+            status,
+            additions,
+            deletions,
+            binary: additions.is_none() && deletions.is_none(),
+        });
+    }
+    changes
+}
+
+/// Compute the cumulative diff of a mission directory against its baseline.
+pub async fn mission_diff(mission_dir: &Path, include_patch: bool) -> Result<MissionDiff> {
+    let snapshots = snapshot_dir(mission_dir);
+    let has_baseline = has_baseline(mission_dir);
+
+    // Current worktrees, plus any snapshotted repos that have since disappeared.
+    let repos = if mission_dir.exists() {
+        find_nested_repos(mission_dir)
+    } else {
+        Vec::new()
+    };
+    let mut worktrees = vec![String::new()];
+    worktrees.extend(repos.iter().cloned());
+    for rel in snapshotted_worktrees(&snapshots).await {
+        if !worktrees.contains(&rel) {
+            worktrees.push(rel);
+        }
+    }
+
+    let mut diff = MissionDiff {
+        has_baseline,
+        files: Vec::new(),
+        total_additions: 0,
+        total_deletions: 0,
+        patch: include_patch.then(String::new),
+    };
+
+    for rel in &worktrees {
+        let work_tree = if rel.is_empty() {
+            mission_dir.to_path_buf()
+        } else {
+            mission_dir.join(rel)
+        };
+        let git_dir = shadow_repo(&snapshots, rel);
+        let base = base_of(&git_dir, &snapshots).await;
+
+        let current = if work_tree.exists() {
+            prepare_shadow(&git_dir, &work_tree, rel, &nested_below(&repos, rel)).await?;
+            write_tree(&git_dir, &work_tree).await?
+        } else if git_dir.exists() {
+            // Repo directory was deleted during the mission.
+            EMPTY_TREE.to_string()
+        } else {
+            continue;
+        };
+        if base == current {
+            continue;
+        }
+
+        let range = [base.as_str(), current.as_str()];
+        let name_status = shadow_git(
+            &git_dir,
+            &work_tree_or(&work_tree, &snapshots),
+            &[&["diff", "--no-renames", "--name-status", "-z"], &range[..]].concat(),
+        )
+        .await?;
+        let numstat = shadow_git(
+            &git_dir,
+            &work_tree_or(&work_tree, &snapshots),
+            &[&["diff", "--no-renames", "--numstat", "-z"], &range[..]].concat(),
+        )
+        .await?;
+        let changes = parse_changes(rel, &name_status, &numstat);
+        diff.total_additions += changes.iter().filter_map(|c| c.additions).sum::<u64>();
+        diff.total_deletions += changes.iter().filter_map(|c| c.deletions).sum::<u64>();
+        diff.files.extend(changes);
+
+        if let Some(patch) = diff.patch.as_mut() {
+            let (src, dst) = if rel.is_empty() {
+                ("--src-prefix=a/".to_string(), "--dst-prefix=b/".to_string())
+            } else {
+                (
+                    format!("--src-prefix=a/{}/", rel),
+                    format!("--dst-prefix=b/{}/", rel),
+                )
+            };
+            let text = shadow_git(
+                &git_dir,
+                &work_tree_or(&work_tree, &snapshots),
+                &[
+                    &["diff", "--no-renames", "--binary", &src, &dst],
+                    &range[..],
+                ]
+                .concat(),
+            )
+            .await?;
+            patch.push_str(&text);
+        }
+    }
+
+    diff.files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(diff)
+}
+
+/// Git needs an existing work tree directory even for tree-to-tree diffs.
+fn work_tree_or(work_tree: &Path, fallback: &Path) -> PathBuf {
+    if work_tree.exists() {
+        work_tree.to_path_buf()
+    } else {
+        fallback.to_path_buf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_changes_joins_prefix_and_stats() {
+        let name_status = "M\0src/lib.rs\0A\0logo.png\0";
+        let numstat = "3\t1\tsrc/lib.rs\0-\t-\tlogo.png\0";
+        let changes = parse_changes("repo", name_status, numstat);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "repo/src/lib.rs");
+        assert_eq!(changes[0].status, FileChangeStatus::Modified);
+        assert_eq!(changes[0].additions, Some(3));
+        assert_eq!(changes[0].deletions, Some(1));
+        assert!(!changes[0].binary);
+        assert_eq!(changes[1].status, FileChangeStatus::Added);
+        assert!(changes[1].binary);
+    }
+
+    #[test]
+    fn test_nested_below() {
+        let all = vec!["a".to_string(), "a/b".to_string(), "c".to_string()];
+        assert_eq!(nested_below(&all, ""), all);
+        assert_eq!(nested_below(&all, "a"), vec!["b".to_string()]);
+        assert!(nested_below(&all, "c").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mission_diff_tracks_changes_since_baseline() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mission_dir = tmp.path().join("workspaces").join("mission-test");
+        tokio::fs::create_dir_all(&mission_dir).await.unwrap();
+        tokio::fs::write(mission_dir.join("keep.txt"), "one\n")
+            .await
+            .unwrap();
+        tokio::fs::write(mission_dir.join("gone.txt"), "bye\n")
+            .await
+            .unwrap();
+
+        record_baseline(&mission_dir).await.unwrap();
+        assert!(has_baseline(&mission_dir));
+
+        tokio::fs::write(mission_dir.join("keep.txt"), "one\ntwo\n")
+            .await
+            .unwrap();
+        tokio::fs::remove_file(mission_dir.join("gone.txt"))
+            .await
+            .unwrap();
+        tokio::fs::write(mission_dir.join("new.txt"), "hi\n")
+            .await
+            .unwrap();
+
+        let diff = mission_diff(&mission_dir, true).await.unwrap();
+        let summary: Vec<_> = diff
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("gone.txt", FileChangeStatus::Deleted),
+                ("keep.txt", FileChangeStatus::Modified),
+                ("new.txt", FileChangeStatus::Added),
+            ]
+        );
+        assert_eq!(diff.total_additions, 2);
+        assert_eq!(diff.total_deletions, 1);
+        assert!(diff.patch.unwrap().contains("+two"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_at_checkpoints() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mission_dir = tmp.path().join("workspaces").join("mission-test");
+        tokio::fs::create_dir_all(&mission_dir).await.unwrap();
+        tokio::fs::write(mission_dir.join("a.txt"), "v1\n")
+            .await
+            .unwrap();
+
+        assert_eq!(record_checkpoint(&mission_dir).await.unwrap(), None);
+        record_baseline(&mission_dir).await.unwrap();
+        // Nothing changed since the baseline
+        assert_eq!(record_checkpoint(&mission_dir).await.unwrap(), None);
+
+        tokio::fs::write(mission_dir.join("a.txt"), "v2\n")
+            .await
+            .unwrap();
+        tokio::fs::write(mission_dir.join("b.txt"), "new\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            record_checkpoint(&mission_dir).await.unwrap().as_deref(),
+            Some("1")
+        );
+        tokio::fs::write(mission_dir.join("a.txt"), "v3\n")
+            .await
+            .unwrap();
+
+        let ids: Vec<String> = list_checkpoints(&mission_dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec!["base", "1"]);
+        let read = |path: &'static str, checkpoint: &'static str| {
+            let dir = mission_dir.clone();
+            async move { read_file_at(&dir, path, checkpoint).await }
+        };
+        assert_eq!(read("a.txt", "base").await.unwrap().unwrap(), b"v1\n");
+        assert_eq!(read("./a.txt", "1").await.unwrap().unwrap(), b"v2\n");
+        assert_eq!(read("b.txt", "base").await.unwrap(), None);
+        assert!(read("../a.txt", "base").await.is_err());
+        let err = read("a.txt", "7").await.unwrap_err().to_string();
+        assert!(err.contains("Available: base, 1"), "{}", err);
+        // The workspace itself is untouched
+        assert_eq!(
+            tokio::fs::read_to_string(mission_dir.join("a.txt"))
+                .await
+                .unwrap(),
+            "v3\n"
+        );
+    }
+}